@@ -0,0 +1,73 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rusty_binaural_filter::BinauralStates;
+use rusty_equalizer_filter::{FilterConfig, eq::EqState};
+
+const SAMPLE_RATE: f64 = 48000.0;
+const CHUNK_SIZES: [usize; 5] = [256, 512, 1024, 2048, 4096];
+// 4096サンプルだと、hrtfクレートがFFTに渡す作業領域が足りずにパニックする
+const BINAURAL_CHUNK_SIZES: [usize; 4] = [256, 512, 1024, 2048];
+
+fn noise(len: usize) -> Vec<f32> {
+    let mut state = 0x1234_5678_u32;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 23) as f32 - 1.0
+        })
+        .collect()
+}
+
+fn bench_eq(c: &mut Criterion) {
+    let config = FilterConfig {
+        wet: 0.8,
+        bass_gain: 6.0,
+        mid_gain: -3.0,
+        treble_gain: 4.5,
+        hipass_enable: true,
+        lopass_enable: true,
+        ..Default::default()
+    };
+    let mut group = c.benchmark_group("eq");
+    for chunk_size in CHUNK_SIZES {
+        let input = noise(chunk_size);
+        let mut samples = input.clone();
+        let mut state = EqState::new(SAMPLE_RATE, &config);
+        group.throughput(Throughput::Elements(chunk_size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(chunk_size),
+            &input,
+            |b, input| {
+                b.iter(|| {
+                    samples.copy_from_slice(input);
+                    state.process(std::hint::black_box(&mut samples));
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_binaural(c: &mut Criterion) {
+    let mut group = c.benchmark_group("binaural");
+    for chunk_size in BINAURAL_CHUNK_SIZES {
+        let mut state = BinauralStates::new(chunk_size, SAMPLE_RATE).unwrap();
+        let input = noise(state.requested_sample_count());
+        group.throughput(Throughput::Elements(chunk_size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(chunk_size),
+            &input,
+            |b, input| {
+                b.iter(|| {
+                    let (left, right) = state
+                        .process(std::hint::black_box(input), 2.0, 30.0, 10.0)
+                        .unwrap();
+                    std::hint::black_box((left, right));
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_eq, bench_binaural);
+criterion_main!(benches);
@@ -0,0 +1,3 @@
+//! サンプルのフィルタプラグインの音声処理のベンチマーク。
+//!
+//! `cargo bench -p bench-filter-dsp`で実行します。
@@ -0,0 +1,229 @@
+use aviutl2::filter::{FramePool, RgbaPixel, ops};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rusty_bloom_filter::{FilterConfig, apply_bloom};
+
+const SIZES: [(u32, u32); 3] = [(320, 180), (1280, 720), (1920, 1080)];
+
+fn noise_image(width: u32, height: u32) -> Vec<[f32; 4]> {
+    let mut state = 0x1234_5678_u32;
+    let mut next = move || {
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (state >> 8) as f32 / (1 << 24) as f32
+    };
+    (0..width * height)
+        .map(|_| {
+            let alpha = next();
+            [next() * alpha, next() * alpha, next() * alpha, alpha]
+        })
+        .collect()
+}
+
+fn label(width: u32, height: u32) -> String {
+    format!("{width}x{height}")
+}
+
+/// 窓の中を毎回足し合わせるボックスブラー。
+fn naive_box_blur(image: &mut [[f32; 4]], width: usize, height: usize, radius: usize) {
+    let source = image.to_vec();
+    let count = ((2 * radius + 1) * (2 * radius + 1)) as f32;
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0f32; 4];
+            for sy in y.saturating_sub(radius)..(y + radius + 1).min(height) {
+                for sx in x.saturating_sub(radius)..(x + radius + 1).min(width) {
+                    for c in 0..4 {
+                        sum[c] += source[sy * width + sx][c];
+                    }
+                }
+            }
+            image[y * width + x] = sum.map(|value| value / count);
+        }
+    }
+}
+
+/// ピクセルごとに参照位置を計算し、チャンネルごとに補間するバイリニア補間。
+fn naive_resize(
+    src: &[[f32; 4]],
+    src_width: usize,
+    src_height: usize,
+    dst: &mut [[f32; 4]],
+    dst_width: usize,
+    dst_height: usize,
+) {
+    for y in 0..dst_height {
+        for x in 0..dst_width {
+            let sx = ((x as f64 + 0.5) * src_width as f64 / dst_width as f64 - 0.5)
+                .clamp(0.0, (src_width - 1) as f64);
+            let sy = ((y as f64 + 0.5) * src_height as f64 / dst_height as f64 - 0.5)
+                .clamp(0.0, (src_height - 1) as f64);
+            let (x0, y0) = (sx.floor() as usize, sy.floor() as usize);
+            let (x1, y1) = ((x0 + 1).min(src_width - 1), (y0 + 1).min(src_height - 1));
+            let (tx, ty) = ((sx - x0 as f64) as f32, (sy - y0 as f64) as f32);
+            for c in 0..4 {
+                let top =
+                    src[y0 * src_width + x0][c] * (1.0 - tx) + src[y0 * src_width + x1][c] * tx;
+                let bottom =
+                    src[y1 * src_width + x0][c] * (1.0 - tx) + src[y1 * src_width + x1][c] * tx;
+                dst[y * dst_width + x][c] = top * (1.0 - ty) + bottom * ty;
+            }
+        }
+    }
+}
+
+/// 添字でアクセスするover合成。
+fn naive_over(dst: &mut [[f32; 4]], src: &[[f32; 4]]) {
+    for i in 0..dst.len() {
+        let inverse_alpha = 1.0 - src[i][3];
+        for c in 0..4 {
+            dst[i][c] = src[i][c] + dst[i][c] * inverse_alpha;
+        }
+    }
+}
+
+fn bench_box_blur(c: &mut Criterion) {
+    let mut group = c.benchmark_group("box_blur");
+    group.sample_size(10);
+    for radius in [2, 8, 32] {
+        let (width, height) = (320, 180);
+        let input = noise_image(width, height);
+        let mut image = input.clone();
+        let mut scratch = input.clone();
+        group.throughput(Throughput::Elements((width * height) as u64));
+        group.bench_with_input(BenchmarkId::new("ops", radius), &input, |b, input| {
+            b.iter(|| {
+                image.copy_from_slice(input);
+                ops::box_blur(
+                    std::hint::black_box(&mut image),
+                    &mut scratch,
+                    width,
+                    height,
+                    radius,
+                );
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("naive", radius), &input, |b, input| {
+            b.iter(|| {
+                image.copy_from_slice(input);
+                naive_box_blur(
+                    std::hint::black_box(&mut image),
+                    width as usize,
+                    height as usize,
+                    radius as usize,
+                );
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_resize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resize_bilinear");
+    for (width, height) in SIZES {
+        let input = noise_image(width / 4, height / 4);
+        let mut output = vec![[0.0; 4]; (width * height) as usize];
+        group.throughput(Throughput::Elements((width * height) as u64));
+        group.bench_with_input(
+            BenchmarkId::new("ops", label(width, height)),
+            &input,
+            |b, input| {
+                b.iter(|| {
+                    ops::resize_bilinear(
+                        std::hint::black_box(input),
+                        width / 4,
+                        height / 4,
+                        &mut output,
+                        width,
+                        height,
+                    );
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("naive", label(width, height)),
+            &input,
+            |b, input| {
+                b.iter(|| {
+                    naive_resize(
+                        std::hint::black_box(input),
+                        (width / 4) as usize,
+                        (height / 4) as usize,
+                        &mut output,
+                        width as usize,
+                        height as usize,
+                    );
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_over(c: &mut Criterion) {
+    let mut group = c.benchmark_group("over");
+    for (width, height) in SIZES {
+        let src = noise_image(width, height);
+        let mut dst = noise_image(width, height);
+        group.throughput(Throughput::Elements((width * height) as u64));
+        group.bench_with_input(
+            BenchmarkId::new("ops", label(width, height)),
+            &src,
+            |b, src| b.iter(|| ops::over(&mut dst, std::hint::black_box(src))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("naive", label(width, height)),
+            &src,
+            |b, src| b.iter(|| naive_over(&mut dst, std::hint::black_box(src))),
+        );
+    }
+    group.finish();
+}
+
+fn bench_bloom(c: &mut Criterion) {
+    let config = FilterConfig {
+        threshold: 0.5,
+        radius: 24.0,
+        intensity: 1.0,
+        downscale: 2.0,
+    };
+    let mut group = c.benchmark_group("bloom");
+    for (width, height) in SIZES {
+        let mut source = vec![RgbaPixel::default(); (width * height) as usize];
+        ops::premultiplied_to_rgba8(&noise_image(width, height), &mut source);
+        let mut image = source.clone();
+        group.throughput(Throughput::Elements((width * height) as u64));
+
+        // プラグインと同じく、同じプールを使い回す場合
+        let pool = FramePool::new();
+        group.bench_with_input(
+            BenchmarkId::new("shared_pool", label(width, height)),
+            &source,
+            |b, source| {
+                b.iter(|| {
+                    image.copy_from_slice(source);
+                    apply_bloom(&config, &pool, &mut image, width, height);
+                })
+            },
+        );
+        // 毎フレーム中間バッファを確保し直す場合
+        group.bench_with_input(
+            BenchmarkId::new("fresh_pool", label(width, height)),
+            &source,
+            |b, source| {
+                b.iter(|| {
+                    image.copy_from_slice(source);
+                    apply_bloom(&config, &FramePool::new(), &mut image, width, height);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_box_blur,
+    bench_resize,
+    bench_over,
+    bench_bloom
+);
+criterion_main!(benches);
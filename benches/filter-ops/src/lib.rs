@@ -0,0 +1,3 @@
+//! `aviutl2::filter::ops`の画像処理と、素直に実装した場合の比較のベンチマーク。
+//!
+//! `cargo bench -p bench-filter-ops`で実行します。
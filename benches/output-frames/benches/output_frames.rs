@@ -0,0 +1,80 @@
+use aviutl2::output::OutputInfo;
+use aviutl2::output::video_frame::RgbVideoFrame;
+use aviutl2::sys::output2::OUTPUT_INFO;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::cell::RefCell;
+
+const WIDTH: i32 = 1920;
+const HEIGHT: i32 = 1080;
+const NUM_FRAMES: i32 = 240;
+const WINDOWS: [u32; 3] = [1, 8, 32];
+
+thread_local! {
+    static FRAME_BUFFER: RefCell<Vec<u8>> = RefCell::new(vec![0; (WIDTH * HEIGHT * 3) as usize]);
+}
+
+// ホスト側の処理はモックなので、ラッパー側のコストのみを比較する
+extern "C" fn get_video(frame: i32, _format: u32) -> *mut std::ffi::c_void {
+    FRAME_BUFFER.with_borrow_mut(|buffer| {
+        buffer.fill(frame as u8);
+        buffer.as_mut_ptr() as *mut std::ffi::c_void
+    })
+}
+
+extern "C" fn is_abort() -> bool {
+    false
+}
+
+fn output_info() -> OUTPUT_INFO {
+    OUTPUT_INFO {
+        flag: OUTPUT_INFO::FLAG_VIDEO,
+        w: WIDTH,
+        h: HEIGHT,
+        rate: 30,
+        scale: 1,
+        n: NUM_FRAMES,
+        audio_rate: 0,
+        audio_ch: 0,
+        audio_n: 0,
+        savefile: std::ptr::null(),
+        func_get_video: Some(get_video),
+        func_get_audio: None,
+        func_is_abort: Some(is_abort),
+        func_rest_time_disp: None,
+        func_set_buffer_size: None,
+    }
+}
+
+fn bench_get_video_frames(c: &mut Criterion) {
+    let mut raw = output_info();
+    let info = unsafe { OutputInfo::__from_raw(&mut raw) };
+    let mut group = c.benchmark_group("get_video_frames");
+    group.sample_size(20);
+    for window in WINDOWS {
+        // 逆順に要求して、まとめて取得する場合の並べ替えも含める
+        let indices: Vec<u32> = (0..window).rev().collect();
+        group.throughput(Throughput::Elements(window as u64));
+        group.bench_with_input(
+            BenchmarkId::new("sequential", window),
+            &indices,
+            |b, indices| {
+                b.iter(|| {
+                    std::hint::black_box(indices)
+                        .iter()
+                        .map(|&i| info.get_video_frame::<RgbVideoFrame>(i as i32).unwrap())
+                        .collect::<Vec<_>>()
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("batch", window), &indices, |b, indices| {
+            b.iter(|| {
+                info.get_video_frames::<RgbVideoFrame>(std::hint::black_box(indices))
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_get_video_frames);
+criterion_main!(benches);
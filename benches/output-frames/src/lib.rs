@@ -0,0 +1,3 @@
+//! `aviutl2::output::OutputInfo`で、動画のフレームを1枚ずつ取得した場合とまとめて取得した場合の比較のベンチマーク。
+//!
+//! `cargo bench -p bench-output-frames`で実行します。
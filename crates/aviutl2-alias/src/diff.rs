@@ -0,0 +1,1421 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::Table;
+
+/// [`TablePath`]の1階層。
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TablePathSegment {
+    /// 名前で識別されるサブテーブル（`project`、`scene`など）。
+    Key(String),
+    /// `0`、`1`、`2`...のキーを持つ、順番に意味のあるサブテーブル（オブジェクト、フィルタ効果など）。
+    ///
+    /// 番号は比較元のテーブルでの位置です。
+    /// 途中に挿入・削除されると、以降のサブテーブルのキーは振り直されます。
+    Index(usize),
+}
+
+/// 比較元のテーブルでのサブテーブルの位置。
+///
+/// 空の場合はルートのテーブルを表します。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TablePath(pub Vec<TablePathSegment>);
+
+impl TablePath {
+    /// ルートのテーブルを表すパスを作成します。
+    pub fn root() -> Self {
+        Self(Vec::new())
+    }
+
+    /// ルートのテーブルかどうかを返します。
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// `prefix`と同じか、`prefix`の子孫のパスかどうかを返します。
+    pub fn starts_with(&self, prefix: &TablePath) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+
+    /// パスが指すサブテーブルを取得します。
+    pub fn resolve<'a>(&self, table: &'a Table) -> Option<&'a Table> {
+        let mut current = table;
+        for segment in &self.0 {
+            current = match segment {
+                TablePathSegment::Key(key) => current.tables.get(key)?,
+                TablePathSegment::Index(index) => current.tables.get(&index.to_string())?,
+            };
+        }
+        Some(current)
+    }
+
+    fn child(&self, segment: TablePathSegment) -> Self {
+        let mut path = self.clone();
+        path.0.push(segment);
+        path
+    }
+
+    fn split_last(&self) -> Option<(TablePath, &TablePathSegment)> {
+        let (last, parent) = self.0.split_last()?;
+        Some((TablePath(parent.to_vec()), last))
+    }
+
+    fn strip_prefix(&self, prefix: &TablePath) -> Option<TablePath> {
+        self.0
+            .strip_prefix(prefix.0.as_slice())
+            .map(|rest| TablePath(rest.to_vec()))
+    }
+}
+
+/// セクション名と同じく、`.`区切りで書き出します。
+impl std::fmt::Display for TablePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            match segment {
+                TablePathSegment::Key(key) => write!(f, "{key}")?,
+                TablePathSegment::Index(index) => write!(f, "{index}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// テーブルの1つの変更。
+///
+/// 位置は全て比較元のテーブルでの位置です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableChange {
+    /// 値が追加された。
+    ValueAdded {
+        /// 値を持つテーブル。
+        path: TablePath,
+        /// 比較元の`index`番目の値の前に挿入されたことを表します。末尾の場合は値の数と同じになります。
+        index: usize,
+        key: String,
+        value: String,
+    },
+    /// 値が削除された。
+    ValueRemoved {
+        /// 値を持つテーブル。
+        path: TablePath,
+        /// 比較元での、重複したキーを含めた値の位置。
+        index: usize,
+        key: String,
+        value: String,
+    },
+    /// 値が変更された。
+    ValueChanged {
+        /// 値を持つテーブル。
+        path: TablePath,
+        /// 比較元での、重複したキーを含めた値の位置。
+        index: usize,
+        key: String,
+        old: String,
+        new: String,
+    },
+    /// サブテーブルが追加された。
+    TableAdded {
+        /// 追加されたサブテーブル。
+        ///
+        /// 最後が[`TablePathSegment::Index`]の場合は、比較元のその番号のサブテーブルの前に挿入されたことを表します。
+        path: TablePath,
+        /// 親テーブルのサブテーブルの並び（比較元）の中で、何番目の前に挿入されたか。
+        ///
+        /// 最後が[`TablePathSegment::Index`]の場合は、`0`、`1`、`2`...のサブテーブル全体の位置を表します。
+        position: usize,
+        table: Table,
+    },
+    /// サブテーブルが削除された。
+    TableRemoved { path: TablePath, table: Table },
+}
+
+impl TableChange {
+    /// 変更されたテーブルのパスを返します。
+    ///
+    /// 値の変更の場合は値を持つテーブル、サブテーブルの追加・削除の場合はそのサブテーブルのパスです。
+    pub fn path(&self) -> &TablePath {
+        match self {
+            TableChange::ValueAdded { path, .. }
+            | TableChange::ValueRemoved { path, .. }
+            | TableChange::ValueChanged { path, .. }
+            | TableChange::TableAdded { path, .. }
+            | TableChange::TableRemoved { path, .. } => path,
+        }
+    }
+
+    fn with_path(&self, path: TablePath) -> TableChange {
+        let mut change = self.clone();
+        match &mut change {
+            TableChange::ValueAdded { path: p, .. }
+            | TableChange::ValueRemoved { path: p, .. }
+            | TableChange::ValueChanged { path: p, .. }
+            | TableChange::TableAdded { path: p, .. }
+            | TableChange::TableRemoved { path: p, .. } => *p = path,
+        }
+        change
+    }
+}
+
+/// [`diff`]で作成される、2つのテーブルの差分。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableDiff {
+    changes: Vec<TableChange>,
+}
+
+impl TableDiff {
+    /// 変更の一覧から差分を作成します。
+    ///
+    /// [`diff`]の結果から一部の変更だけを取り出して[`apply`]する場合などに使います。
+    pub fn new(changes: Vec<TableChange>) -> Self {
+        Self { changes }
+    }
+
+    /// 変更の一覧を返します。
+    pub fn changes(&self) -> &[TableChange] {
+        &self.changes
+    }
+
+    /// 変更の一覧を取り出します。
+    pub fn into_changes(self) -> Vec<TableChange> {
+        self.changes
+    }
+
+    /// 変更が無いかどうかを返します。
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// [`merge`]で解決できなかった衝突。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// 衝突した値を持つテーブル、または衝突したサブテーブルのパス（比較元での位置）。
+    pub path: TablePath,
+    pub kind: ConflictKind,
+}
+
+/// 衝突の内容。`None`は存在しない（削除された）ことを表します。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// 同じ値が両方で違うように変更された。
+    Value {
+        key: String,
+        base: Option<String>,
+        ours: Option<String>,
+        theirs: Option<String>,
+    },
+    /// 片方で削除されたサブテーブルが、もう片方で変更された。
+    /// または、同じ名前のサブテーブルが両方で追加され、中身が衝突した。
+    Table {
+        base: Option<Table>,
+        ours: Option<Table>,
+        theirs: Option<Table>,
+    },
+}
+
+/// 2つのテーブルの差分を取ります。
+///
+/// 値は重複したキーを含めた並びとして最長共通部分列で対応を取り、残った同じキーの値を変更として扱います。
+/// `0`、`1`、`2`...のキーを持つサブテーブル（オブジェクトやフィルタ効果など）は並びとして扱い、
+/// 中身の最長共通部分列で対応を取ります。
+/// そのため、途中にオブジェクトを挿入しても、以降のオブジェクトが全て変更されたことにはなりません。
+///
+/// サブテーブルの順番だけが変わった場合は、差分として扱いません。
+///
+/// # Example
+///
+/// ```rust
+/// let a: aviutl2_alias::Table = "[0]\r\nlayer=1\r\n[1]\r\nlayer=2\r\n".parse().unwrap();
+/// let b: aviutl2_alias::Table = "[0]\r\nlayer=1\r\n[1]\r\nlayer=3\r\n[2]\r\nlayer=2\r\n"
+///     .parse()
+///     .unwrap();
+/// let diff = aviutl2_alias::diff(&a, &b);
+/// // `[1]`が挿入されたとみなされる
+/// assert_eq!(diff.changes().len(), 1);
+/// assert_eq!(aviutl2_alias::apply(&a, &diff), b);
+/// ```
+pub fn diff(a: &Table, b: &Table) -> TableDiff {
+    let mut changes = Vec::new();
+    diff_table(a, b, &TablePath::root(), &mut changes);
+    TableDiff { changes }
+}
+
+/// 差分をテーブルに適用します。
+///
+/// `diff(a, b)`を`a`に適用すると、`b`と同じ内容になります。
+/// 差分の比較元と異なるテーブルに適用した場合、存在しない位置への変更は無視されます。
+pub fn apply(base: &Table, diff: &TableDiff) -> Table {
+    let pending = PendingChanges::group(&diff.changes);
+    apply_table(base, &TablePath::root(), &pending)
+}
+
+/// 共通の祖先`base`から変更された2つのテーブルを3-wayマージします。
+///
+/// 片方だけが変更した部分はその変更を、両方が同じように変更した部分は1つにまとめて取り込みます。
+/// 同じ位置に両方でオブジェクトなどのサブテーブルが挿入された場合は、`ours`、`theirs`の順に両方を残します。
+///
+/// 以下の場合は衝突として扱い、全ての衝突を`Err`で返します。
+/// - 同じ値を両方が違う値に変更した、または片方が変更しもう片方が削除した
+/// - 同じ位置に同じキーで違う値を追加した
+/// - 片方が削除したサブテーブルを、もう片方が変更した
+///
+/// # Example
+///
+/// ```rust
+/// let base: aviutl2_alias::Table = "[0]\r\nlayer=1\r\nframe=0,10\r\n".parse().unwrap();
+/// let ours: aviutl2_alias::Table = "[0]\r\nlayer=2\r\nframe=0,10\r\n".parse().unwrap();
+/// let theirs: aviutl2_alias::Table = "[0]\r\nlayer=1\r\nframe=0,20\r\n".parse().unwrap();
+/// let merged = aviutl2_alias::merge(&base, &ours, &theirs).unwrap();
+/// assert_eq!(merged.to_string(), "[0]\r\nlayer=2\r\nframe=0,20\r\n");
+/// ```
+pub fn merge(base: &Table, ours: &Table, theirs: &Table) -> Result<Table, Vec<Conflict>> {
+    let ours_changes = diff(base, ours).changes;
+    let theirs_changes = diff(base, theirs).changes;
+    let mut conflicts = Vec::new();
+
+    // 片方が削除したサブテーブルの中を、もう片方が変更している場合は衝突
+    let ours_removed = removed_tables(&ours_changes);
+    let theirs_removed = removed_tables(&theirs_changes);
+    let mut conflicted_tables = Vec::<TablePath>::new();
+    for (removed, other, ours_side) in [
+        (&ours_removed, &theirs_changes, true),
+        (&theirs_removed, &ours_changes, false),
+    ] {
+        for path in removed {
+            if conflicted_tables.contains(path) {
+                continue;
+            }
+            if !other.iter().any(|change| is_inside(change, path)) {
+                continue;
+            }
+            let base_table = path.resolve(base).cloned();
+            let other_table = base_table
+                .as_ref()
+                .map(|table| apply(table, &rebase(other, path)));
+            let (ours, theirs) = if ours_side {
+                (None, other_table)
+            } else {
+                (other_table, None)
+            };
+            conflicts.push(Conflict {
+                path: path.clone(),
+                kind: ConflictKind::Table {
+                    base: base_table,
+                    ours,
+                    theirs,
+                },
+            });
+            conflicted_tables.push(path.clone());
+        }
+    }
+    let outside_conflicts =
+        |change: &&TableChange| !conflicted_tables.iter().any(|p| is_inside(change, p));
+
+    let mut combined = ours_changes
+        .iter()
+        .filter(outside_conflicts)
+        .cloned()
+        .collect::<Vec<_>>();
+    let ours_index = ours_changes
+        .iter()
+        .filter(outside_conflicts)
+        .collect::<Vec<_>>();
+    // 間の要素が全て削除されている挿入位置は、同じ位置として扱う
+    let mut removed_values = HashSet::new();
+    let mut removed_array_tables = HashSet::new();
+    for change in ours_changes.iter().chain(&theirs_changes) {
+        match change {
+            TableChange::ValueRemoved { path, index, .. } => {
+                removed_values.insert((path.clone(), *index));
+            }
+            TableChange::TableRemoved { path, .. } => {
+                if let Some((parent, TablePathSegment::Index(index))) = path.split_last() {
+                    removed_array_tables.insert((parent, *index));
+                }
+            }
+            _ => {}
+        }
+    }
+    let same_gap = |removed: &HashSet<(TablePath, usize)>, path: &TablePath, a: usize, b: usize| {
+        (a.min(b)..a.max(b)).all(|index| removed.contains(&(path.clone(), index)))
+    };
+
+    let mut ours_values = HashMap::new();
+    let mut ours_value_additions = HashMap::<&TablePath, Vec<(usize, &str, &str)>>::new();
+    let mut ours_table_additions = HashMap::<TablePath, Vec<(usize, &Table)>>::new();
+    let mut ours_keyed_tables = HashMap::new();
+    let mut ours_removed_tables = HashSet::new();
+    for change in &ours_index {
+        match change {
+            TableChange::ValueAdded {
+                path,
+                index,
+                key,
+                value,
+            } => ours_value_additions.entry(path).or_default().push((
+                *index,
+                key.as_str(),
+                value.as_str(),
+            )),
+            TableChange::ValueRemoved { path, index, .. }
+            | TableChange::ValueChanged { path, index, .. } => {
+                ours_values.insert((path, *index), *change);
+            }
+            TableChange::TableAdded { path, table, .. } => match path.split_last() {
+                Some((parent, TablePathSegment::Index(index))) => ours_table_additions
+                    .entry(parent)
+                    .or_default()
+                    .push((*index, table)),
+                _ => {
+                    ours_keyed_tables.insert(path, table);
+                }
+            },
+            TableChange::TableRemoved { path, .. } => {
+                ours_removed_tables.insert(path);
+            }
+        }
+    }
+
+    for change in theirs_changes.iter().filter(outside_conflicts) {
+        match change {
+            TableChange::ValueAdded {
+                path,
+                index,
+                key,
+                value,
+            } => {
+                let ours = ours_value_additions
+                    .get(path)
+                    .into_iter()
+                    .flatten()
+                    .filter(|(i, _, _)| same_gap(&removed_values, path, *i, *index))
+                    .map(|&(_, key, value)| (key, value))
+                    .collect::<Vec<_>>();
+                if ours.contains(&(key.as_str(), value.as_str())) {
+                    continue;
+                }
+                if let Some((_, ours_value)) = ours.iter().find(|(k, _)| k == key) {
+                    conflicts.push(Conflict {
+                        path: path.clone(),
+                        kind: ConflictKind::Value {
+                            key: key.clone(),
+                            base: None,
+                            ours: Some(ours_value.to_string()),
+                            theirs: Some(value.clone()),
+                        },
+                    });
+                    continue;
+                }
+                combined.push(change.clone());
+            }
+            TableChange::ValueRemoved {
+                path, index, key, ..
+            }
+            | TableChange::ValueChanged {
+                path, index, key, ..
+            } => match ours_values.get(&(path, *index)) {
+                Some(ours) if *ours == change => {}
+                Some(ours) => conflicts.push(Conflict {
+                    path: path.clone(),
+                    kind: ConflictKind::Value {
+                        key: key.clone(),
+                        base: Some(base_value(ours).to_string()),
+                        ours: new_value(ours).map(str::to_string),
+                        theirs: new_value(change).map(str::to_string),
+                    },
+                }),
+                None => combined.push(change.clone()),
+            },
+            TableChange::TableAdded { path, table, .. } => match path.split_last() {
+                Some((parent, TablePathSegment::Index(index))) => {
+                    let added_by_ours = ours_table_additions
+                        .get(&parent)
+                        .into_iter()
+                        .flatten()
+                        .any(|(i, ours)| {
+                            *ours == table && same_gap(&removed_array_tables, &parent, *i, *index)
+                        });
+                    if !added_by_ours {
+                        combined.push(change.clone());
+                    }
+                }
+                _ => match ours_keyed_tables.get(path) {
+                    None => combined.push(change.clone()),
+                    Some(ours) if *ours == table => {}
+                    Some(ours) => match merge(&Table::new(), ours, table) {
+                        Ok(merged) => {
+                            for combined_change in &mut combined {
+                                match combined_change {
+                                    TableChange::TableAdded { path: p, table, .. } if p == path => {
+                                        *table = merged.clone()
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        Err(nested) => {
+                            conflicts.extend(nested.into_iter().map(|conflict| Conflict {
+                                path: TablePath(
+                                    path.0.iter().chain(&conflict.path.0).cloned().collect(),
+                                ),
+                                kind: conflict.kind,
+                            }))
+                        }
+                    },
+                },
+            },
+            TableChange::TableRemoved { path, .. } => {
+                if !ours_removed_tables.contains(path) {
+                    combined.push(change.clone());
+                }
+            }
+        }
+    }
+
+    if conflicts.is_empty() {
+        Ok(apply(base, &TableDiff::new(combined)))
+    } else {
+        Err(conflicts)
+    }
+}
+
+fn base_value(change: &TableChange) -> &str {
+    match change {
+        TableChange::ValueRemoved { value, .. } => value,
+        TableChange::ValueChanged { old, .. } => old,
+        _ => unreachable!(),
+    }
+}
+
+fn new_value(change: &TableChange) -> Option<&str> {
+    match change {
+        TableChange::ValueChanged { new, .. } => Some(new),
+        _ => None,
+    }
+}
+
+fn removed_tables(changes: &[TableChange]) -> Vec<TablePath> {
+    changes
+        .iter()
+        .filter_map(|change| match change {
+            TableChange::TableRemoved { path, .. } => Some(path.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `prefix`以下の変更を、`prefix`をルートとした差分にする。
+fn rebase(changes: &[TableChange], prefix: &TablePath) -> TableDiff {
+    TableDiff::new(
+        changes
+            .iter()
+            .filter(|change| is_inside(change, prefix))
+            .map(|change| change.with_path(change.path().strip_prefix(prefix).unwrap()))
+            .collect(),
+    )
+}
+
+/// `table`のサブテーブル内の変更かどうか。
+///
+/// `table`自体の追加・削除と、`table`の前へのサブテーブルの挿入は親テーブルへの変更なので含めない。
+fn is_inside(change: &TableChange, table: &TablePath) -> bool {
+    match change {
+        TableChange::ValueAdded { path, .. }
+        | TableChange::ValueRemoved { path, .. }
+        | TableChange::ValueChanged { path, .. } => path.starts_with(table),
+        TableChange::TableAdded { path, .. } | TableChange::TableRemoved { path, .. } => {
+            path.starts_with(table) && path != table
+        }
+    }
+}
+
+fn diff_table(a: &Table, b: &Table, path: &TablePath, changes: &mut Vec<TableChange>) {
+    // 値：重複したキーも含めた並びで対応を取り、残りは同じキーのものを変更として扱う
+    let alignment = align(&a.entries, &b.entries, |(a_key, _), (b_key, _)| {
+        (a_key == b_key).then_some(1)
+    });
+    for index in 0..=a.entries.len() {
+        for &j in &alignment.inserted[index] {
+            let (key, value) = &b.entries[j];
+            changes.push(TableChange::ValueAdded {
+                path: path.clone(),
+                index,
+                key: key.clone(),
+                value: value.clone(),
+            });
+        }
+        let Some((key, old)) = a.entries.get(index) else {
+            continue;
+        };
+        match alignment.matches[index] {
+            Some(j) if b.entries[j].1 != *old => changes.push(TableChange::ValueChanged {
+                path: path.clone(),
+                index,
+                key: key.clone(),
+                old: old.clone(),
+                new: b.entries[j].1.clone(),
+            }),
+            Some(_) => {}
+            None => changes.push(TableChange::ValueRemoved {
+                path: path.clone(),
+                index,
+                key: key.clone(),
+                value: old.clone(),
+            }),
+        }
+    }
+
+    // 名前で識別されるサブテーブル
+    let a_array_len = array_len(a);
+    let b_array_len = array_len(b);
+    let b_keyed = |key: &str| {
+        b.tables
+            .get(key)
+            .filter(|_| !is_array_key(key, b_array_len))
+    };
+    for (key, a_table) in &a.tables {
+        if is_array_key(key, a_array_len) {
+            continue;
+        }
+        let child = path.child(TablePathSegment::Key(key.clone()));
+        match b_keyed(key) {
+            Some(b_table) if a_table != b_table => diff_table(a_table, b_table, &child, changes),
+            Some(_) => {}
+            None => changes.push(TableChange::TableRemoved {
+                path: child,
+                table: a_table.clone(),
+            }),
+        }
+    }
+    let mut last_common = None;
+    let mut array_position = a.tables.get_index_of("0").filter(|_| a_array_len > 0);
+    for (key, b_table) in &b.tables {
+        let a_position = a.tables.get_index_of(key.as_str());
+        let added = !is_array_key(key, b_array_len)
+            && a_position.is_none_or(|_| is_array_key(key, a_array_len));
+        if key == "0" && b_array_len > 0 && array_position.is_none() {
+            array_position = Some(last_common.map_or(0, |position| position + 1));
+        }
+        if added {
+            changes.push(TableChange::TableAdded {
+                path: path.child(TablePathSegment::Key(key.clone())),
+                position: last_common.map_or(0, |position| position + 1),
+                table: b_table.clone(),
+            });
+        } else if a_position.is_some() {
+            last_common = a_position;
+        }
+    }
+
+    // 順番のあるサブテーブル：中身で対応を取る
+    let a_array = a.iter_subtables_as_array().collect::<Vec<_>>();
+    let b_array = b.iter_subtables_as_array().collect::<Vec<_>>();
+    let a_fingerprints = a_array
+        .iter()
+        .map(|t| (fingerprint(t), *t))
+        .collect::<Vec<_>>();
+    let b_fingerprints = b_array
+        .iter()
+        .map(|t| (fingerprint(t), *t))
+        .collect::<Vec<_>>();
+    let alignment = align(&a_fingerprints, &b_fingerprints, |(_, a), (_, b)| {
+        similarity(a, b)
+    });
+    for index in 0..=a_array.len() {
+        for &j in &alignment.inserted[index] {
+            changes.push(TableChange::TableAdded {
+                path: path.child(TablePathSegment::Index(index)),
+                position: array_position.unwrap_or(a.tables.len()),
+                table: b_array[j].clone(),
+            });
+        }
+        let Some(a_table) = a_array.get(index) else {
+            continue;
+        };
+        let child = path.child(TablePathSegment::Index(index));
+        match alignment.matches[index] {
+            Some(j) if *a_table != b_array[j] => diff_table(a_table, b_array[j], &child, changes),
+            Some(_) => {}
+            None => changes.push(TableChange::TableRemoved {
+                path: child,
+                table: (*a_table).clone(),
+            }),
+        }
+    }
+}
+
+/// 1つのテーブルに対する変更。
+#[derive(Default)]
+struct TableChanges<'a> {
+    added_values: BTreeMap<usize, Vec<(&'a String, &'a String)>>,
+    removed_values: HashSet<usize>,
+    changed_values: HashMap<usize, &'a String>,
+    added_keyed_tables: BTreeMap<usize, Vec<(&'a String, &'a Table)>>,
+    added_array_tables: BTreeMap<usize, Vec<&'a Table>>,
+    /// 比較元に`0`、`1`、`2`...のサブテーブルが無い場合に、追加されたものを挿入する位置。
+    array_position: Option<usize>,
+    removed_tables: HashSet<&'a TablePathSegment>,
+}
+
+/// 差分をテーブルごとにまとめたもの。
+struct PendingChanges<'a> {
+    tables: HashMap<TablePath, TableChanges<'a>>,
+    /// 変更があるテーブルと、その祖先。
+    touched: HashSet<TablePath>,
+}
+
+impl<'a> PendingChanges<'a> {
+    fn group(changes: &'a [TableChange]) -> Self {
+        let mut tables = HashMap::<TablePath, TableChanges<'a>>::new();
+        for change in changes {
+            match change {
+                TableChange::ValueAdded {
+                    path,
+                    index,
+                    key,
+                    value,
+                } => tables
+                    .entry(path.clone())
+                    .or_default()
+                    .added_values
+                    .entry(*index)
+                    .or_default()
+                    .push((key, value)),
+                TableChange::ValueRemoved { path, index, .. } => {
+                    tables
+                        .entry(path.clone())
+                        .or_default()
+                        .removed_values
+                        .insert(*index);
+                }
+                TableChange::ValueChanged {
+                    path, index, new, ..
+                } => {
+                    tables
+                        .entry(path.clone())
+                        .or_default()
+                        .changed_values
+                        .insert(*index, new);
+                }
+                TableChange::TableAdded {
+                    path,
+                    position,
+                    table,
+                } => {
+                    let Some((parent, last)) = path.split_last() else {
+                        continue;
+                    };
+                    let pending = tables.entry(parent).or_default();
+                    match last {
+                        TablePathSegment::Key(key) => pending
+                            .added_keyed_tables
+                            .entry(*position)
+                            .or_default()
+                            .push((key, table)),
+                        TablePathSegment::Index(index) => {
+                            pending.array_position.get_or_insert(*position);
+                            pending
+                                .added_array_tables
+                                .entry(*index)
+                                .or_default()
+                                .push(table)
+                        }
+                    }
+                }
+                TableChange::TableRemoved { path, .. } => {
+                    let Some((parent, last)) = path.split_last() else {
+                        continue;
+                    };
+                    tables
+                        .entry(parent)
+                        .or_default()
+                        .removed_tables
+                        .insert(last);
+                }
+            }
+        }
+        let mut touched = HashSet::new();
+        for path in tables.keys() {
+            for len in 0..=path.0.len() {
+                touched.insert(TablePath(path.0[..len].to_vec()));
+            }
+        }
+        Self { tables, touched }
+    }
+}
+
+fn apply_table(base: &Table, path: &TablePath, pending: &PendingChanges) -> Table {
+    if !pending.touched.contains(path) {
+        return base.clone();
+    }
+    let empty = TableChanges::default();
+    let changes = pending.tables.get(path).unwrap_or(&empty);
+    let mut result = Table::new();
+
+    for index in 0..=base.entries.len() {
+        if let Some(added) = changes.added_values.get(&index) {
+            result.entries.extend(
+                added
+                    .iter()
+                    .map(|(key, value)| ((*key).clone(), (*value).clone())),
+            );
+        }
+        let Some((key, value)) = base.entries.get(index) else {
+            continue;
+        };
+        if changes.removed_values.contains(&index) {
+            continue;
+        }
+        let value = changes.changed_values.get(&index).copied().unwrap_or(value);
+        result.entries.push((key.clone(), value.clone()));
+    }
+
+    let array_len = array_len(base);
+    let array_position = if array_len > 0 {
+        base.tables.get_index_of("0")
+    } else {
+        changes.array_position
+    };
+    let insert_keyed = |result: &mut Table, position: usize| {
+        for (key, table) in changes
+            .added_keyed_tables
+            .get(&position)
+            .into_iter()
+            .flatten()
+        {
+            result.tables.insert((*key).clone(), (*table).clone());
+        }
+    };
+    for (position, (key, table)) in base.tables.iter().enumerate() {
+        insert_keyed(&mut result, position);
+        if array_position == Some(position) {
+            apply_array(base, array_len, path, changes, pending, &mut result);
+        }
+        if is_array_key(key, array_len) {
+            continue;
+        }
+        let segment = TablePathSegment::Key(key.clone());
+        if changes.removed_tables.contains(&segment) {
+            continue;
+        }
+        let child = apply_table(table, &path.child(segment), pending);
+        result.tables.insert(key.clone(), child);
+    }
+    insert_keyed(&mut result, base.tables.len());
+    if array_position.is_none_or(|position| position >= base.tables.len()) {
+        apply_array(base, array_len, path, changes, pending, &mut result);
+    }
+    result
+}
+
+fn apply_array(
+    base: &Table,
+    array_len: usize,
+    path: &TablePath,
+    changes: &TableChanges,
+    pending: &PendingChanges,
+    result: &mut Table,
+) {
+    let mut next_index = 0usize;
+    let mut push = |result: &mut Table, table: Table| {
+        result.tables.insert(next_index.to_string(), table);
+        next_index += 1;
+    };
+    for index in 0..=array_len {
+        for table in changes.added_array_tables.get(&index).into_iter().flatten() {
+            push(result, (*table).clone());
+        }
+        if index == array_len {
+            break;
+        }
+        let segment = TablePathSegment::Index(index);
+        if changes.removed_tables.contains(&segment) {
+            continue;
+        }
+        let table = &base.tables[&index.to_string()];
+        push(result, apply_table(table, &path.child(segment), pending));
+    }
+}
+
+fn array_len(table: &Table) -> usize {
+    table.iter_subtables_as_array().count()
+}
+
+fn is_array_key(key: &str, array_len: usize) -> bool {
+    key.parse::<usize>()
+        .is_ok_and(|index| index < array_len && index.to_string() == key)
+}
+
+/// 2つのサブテーブルがどれだけ似ているか。同じ値と同じ子テーブルが多いほど大きくなる。
+///
+/// 共通するキーが半分未満の場合は、別のサブテーブルとみなして`None`を返す。
+/// 似ていないものまで対応させると、削除と追加が変更として扱われ、マージの結果が不自然になる。
+fn similarity(a: &Table, b: &Table) -> Option<usize> {
+    let common_keys = a
+        .entries
+        .iter()
+        .filter(|(key, _)| b.entries.iter().any(|(b_key, _)| b_key == key))
+        .count();
+    if common_keys * 2 < a.entries.len().max(b.entries.len()) {
+        return None;
+    }
+    let common_entries = a
+        .entries
+        .iter()
+        .filter(|entry| b.entries.contains(entry))
+        .count();
+    let common_tables = a
+        .tables
+        .iter()
+        .filter(|(key, table)| b.tables.get(*key) == Some(table))
+        .count();
+    Some(1 + common_entries + common_tables)
+}
+
+/// 比較のためのサブテーブルの要約。一致した場合も、中身は改めて比較する。
+fn fingerprint(table: &Table) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    table.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 比較元と比較先の要素の対応。
+struct Alignment {
+    /// 比較元の各要素に対応する比較先の要素。削除された場合は`None`。
+    matches: Vec<Option<usize>>,
+    /// 比較元の`i`番目の要素の前に挿入された比較先の要素。最後は末尾に挿入された要素。
+    inserted: Vec<Vec<usize>>,
+}
+
+/// 最長共通部分列で要素の対応を取る。
+///
+/// 共通部分列の間で対応の取れなかった要素は、`score`の合計が最大になるように対応させ、
+/// 変更されたものとして扱う。`score`が`None`を返す組は対応させない。
+fn align<T: Eq>(base: &[T], other: &[T], score: impl Fn(&T, &T) -> Option<usize>) -> Alignment {
+    let mut matches = vec![None; base.len()];
+    let mut inserted = vec![Vec::new(); base.len() + 1];
+    let (mut base_start, mut other_start) = (0, 0);
+    let anchors = longest_common_subsequence(base, other);
+    for (base_anchor, other_anchor) in anchors.into_iter().chain([(base.len(), other.len())]) {
+        let pairs = pair_gap(
+            &base[base_start..base_anchor],
+            &other[other_start..other_anchor],
+            &score,
+        );
+        // 挿入位置は対応の取れた要素の直後に揃える（削除された要素の前後で差分が変わらないように）
+        let (mut next, mut insert_at) = (other_start, base_start);
+        for (i, j) in pairs {
+            let (i, j) = (base_start + i, other_start + j);
+            inserted[insert_at].extend(next..j);
+            matches[i] = Some(j);
+            (next, insert_at) = (j + 1, i + 1);
+        }
+        inserted[insert_at].extend(next..other_anchor);
+        if base_anchor < base.len() {
+            matches[base_anchor] = Some(other_anchor);
+        }
+        (base_start, other_start) = (base_anchor + 1, other_anchor + 1);
+    }
+    Alignment { matches, inserted }
+}
+
+/// 共通部分列の間の要素を、`score`の合計が最大になるように対応させる。
+fn pair_gap<T>(
+    base: &[T],
+    other: &[T],
+    score: impl Fn(&T, &T) -> Option<usize>,
+) -> Vec<(usize, usize)> {
+    let (n, m) = (base.len(), other.len());
+    if n == 0 || m == 0 {
+        return Vec::new();
+    }
+    let scores = base
+        .iter()
+        .flat_map(|a| other.iter().map(|b| score(a, b)))
+        .collect::<Vec<_>>();
+    // totals[i][j]は`base[i..]`と`other[j..]`の対応のスコアの合計の最大値
+    let mut totals = vec![0usize; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            let paired =
+                scores[i * m + j].map_or(0, |score| score + totals[(i + 1) * (m + 1) + j + 1]);
+            totals[i * (m + 1) + j] = paired
+                .max(totals[(i + 1) * (m + 1) + j])
+                .max(totals[i * (m + 1) + j + 1]);
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        let total = totals[i * (m + 1) + j];
+        match scores[i * m + j] {
+            Some(score) if total == score + totals[(i + 1) * (m + 1) + j + 1] => {
+                pairs.push((i, j));
+                i += 1;
+                j += 1;
+            }
+            _ if total == totals[(i + 1) * (m + 1) + j] => i += 1,
+            _ => j += 1,
+        }
+    }
+    pairs
+}
+
+/// 最長共通部分列を、対応する要素の位置の組として返す。
+fn longest_common_subsequence<T: Eq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    // 前後の共通部分は表を作らずに対応させる
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let middle_a = &a[prefix..a.len() - suffix];
+    let middle_b = &b[prefix..b.len() - suffix];
+
+    // lengths[i][j]は`middle_a[i..]`と`middle_b[j..]`の最長共通部分列の長さ
+    let (n, m) = (middle_a.len(), middle_b.len());
+    let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i * (m + 1) + j] = if middle_a[i] == middle_b[j] {
+                lengths[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                lengths[(i + 1) * (m + 1) + j].max(lengths[i * (m + 1) + j + 1])
+            };
+        }
+    }
+
+    let mut pairs = (0..prefix).map(|i| (i, i)).collect::<Vec<_>>();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if middle_a[i] == middle_b[j] {
+            pairs.push((prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * (m + 1) + j] >= lengths[i * (m + 1) + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs.extend((0..suffix).map(|k| (a.len() - suffix + k, b.len() - suffix + k)));
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn table(source: &str) -> Table {
+        source.replace('\n', "\r\n").parse().unwrap()
+    }
+
+    fn index(index: usize) -> TablePathSegment {
+        TablePathSegment::Index(index)
+    }
+
+    fn key(key: &str) -> TablePathSegment {
+        TablePathSegment::Key(key.to_string())
+    }
+
+    fn assert_roundtrip(a: &Table, b: &Table) {
+        let diff = diff(a, b);
+        let applied = apply(a, &diff);
+        assert_eq!(applied.to_string(), b.to_string(), "{diff:#?}");
+    }
+
+    /// テスト用の疑似乱数（xorshift）。
+    struct Rng(u64);
+    impl Rng {
+        fn next(&mut self, max: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % max as u64) as usize
+        }
+    }
+
+    /// 値とサブテーブルの追加・削除・変更をランダムに行う。
+    ///
+    /// `edit_keys`が`false`の場合、値は変更だけを行う。
+    fn mutate(table: &mut Table, rng: &mut Rng, depth: usize, edit_keys: bool) {
+        for _ in 0..rng.next(4) {
+            match rng.next(3) {
+                _ if !edit_keys && !table.entries.is_empty() => {
+                    let index = rng.next(table.entries.len());
+                    table.entries[index].1 = rng.next(100).to_string();
+                }
+                _ if !edit_keys => {}
+                0 => {
+                    let index = rng.next(table.entries.len() + 1);
+                    let key = format!("key{}", rng.next(4));
+                    table
+                        .entries
+                        .insert(index, (key, rng.next(100).to_string()));
+                }
+                1 if !table.entries.is_empty() => {
+                    table.entries.remove(rng.next(table.entries.len()));
+                }
+                _ if !table.entries.is_empty() => {
+                    let index = rng.next(table.entries.len());
+                    table.entries[index].1 = rng.next(100).to_string();
+                }
+                _ => {}
+            }
+        }
+        let mut array = table.iter_subtables_as_array().cloned().collect::<Vec<_>>();
+        // 順番だけの変更は差分に含まれないので、元の位置に戻す
+        let position = table.tables.get_index_of("0").unwrap_or(table.tables.len());
+        for i in 0..array.len() {
+            table.tables.shift_remove(&i.to_string());
+        }
+        match rng.next(4) {
+            0 => {
+                let mut new_table = Table::new();
+                new_table.insert_value("added", rng.next(100));
+                array.insert(rng.next(array.len() + 1), new_table);
+            }
+            1 if !array.is_empty() => {
+                array.remove(rng.next(array.len()));
+            }
+            _ => {}
+        }
+        for (i, sub_table) in array.into_iter().enumerate() {
+            let position = (position + i).min(table.tables.len());
+            table
+                .tables
+                .shift_insert(position, i.to_string(), sub_table);
+        }
+        if depth < 3 {
+            for (_, sub_table) in table.tables.iter_mut() {
+                if rng.next(2) == 0 {
+                    mutate(sub_table, rng, depth + 1, edit_keys);
+                }
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(include_str!("../test_assets/tracks.aup2"))]
+    #[case(include_str!("../test_assets/everything.aup2"))]
+    #[case(include_str!("../test_assets/unknown_fields.aup2"))]
+    #[case(include_str!("../test_assets/object.object"))]
+    #[case(include_str!("../test_assets/effect.effect"))]
+    fn test_diff_same_table_is_empty(#[case] input: &str) {
+        let table: Table = input.parse().unwrap();
+        assert!(diff(&table, &table).is_empty());
+        assert_eq!(apply(&table, &TableDiff::default()), table);
+    }
+
+    #[rstest]
+    #[case(include_str!("../test_assets/tracks.aup2"))]
+    #[case(include_str!("../test_assets/everything.aup2"))]
+    #[case(include_str!("../test_assets/unknown_fields.aup2"))]
+    #[case(include_str!("../test_assets/object.object"))]
+    fn test_apply_random_mutations(#[case] input: &str) {
+        let original: Table = input.parse().unwrap();
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..200 {
+            let mut mutated = original.clone();
+            mutate(&mut mutated, &mut rng, 0, true);
+            assert_roundtrip(&original, &mutated);
+            assert_roundtrip(&mutated, &original);
+        }
+    }
+
+    #[test]
+    fn test_diff_values() {
+        let a = table("a=1\nb=2\nc=3\n");
+        let b = table("a=1\nc=4\nd=5\n");
+        assert_eq!(
+            diff(&a, &b).into_changes(),
+            vec![
+                TableChange::ValueRemoved {
+                    path: TablePath::root(),
+                    index: 1,
+                    key: "b".to_string(),
+                    value: "2".to_string(),
+                },
+                TableChange::ValueChanged {
+                    path: TablePath::root(),
+                    index: 2,
+                    key: "c".to_string(),
+                    old: "3".to_string(),
+                    new: "4".to_string(),
+                },
+                TableChange::ValueAdded {
+                    path: TablePath::root(),
+                    index: 3,
+                    key: "d".to_string(),
+                    value: "5".to_string(),
+                },
+            ]
+        );
+        assert_roundtrip(&a, &b);
+    }
+
+    #[test]
+    fn test_diff_duplicate_keys() {
+        let a = table("[0]\neffect=1\nvalue=a\neffect=2\nvalue=b\n");
+        let b = table("[0]\neffect=1\nvalue=a\neffect=3\nvalue=c\neffect=2\nvalue=b\n");
+        let diff = diff(&a, &b);
+        // 重複したキーも並びとして扱うため、挿入だけになる
+        assert_eq!(
+            diff.changes()
+                .iter()
+                .map(|change| match change {
+                    TableChange::ValueAdded { index, value, .. } => (*index, value.as_str()),
+                    other => panic!("unexpected change: {other:?}"),
+                })
+                .collect::<Vec<_>>(),
+            vec![(2, "3"), (2, "c")]
+        );
+        assert_roundtrip(&a, &b);
+    }
+
+    #[test]
+    fn test_diff_array_insert_in_middle() {
+        let a = table("[0]\nlayer=1\n[1]\nlayer=2\n[2]\nlayer=3\n");
+        let b = table("[0]\nlayer=1\n[1]\nlayer=9\n[2]\nlayer=2\n[3]\nlayer=3\n");
+        assert_eq!(
+            diff(&a, &b).into_changes(),
+            vec![TableChange::TableAdded {
+                path: TablePath(vec![index(1)]),
+                position: 0,
+                table: table("layer=9\n"),
+            }]
+        );
+        assert_roundtrip(&a, &b);
+    }
+
+    #[test]
+    fn test_diff_array_remove_in_middle() {
+        let a = table("[0]\nlayer=1\n[1]\nlayer=2\n[2]\nlayer=3\n");
+        let b = table("[0]\nlayer=1\n[1]\nlayer=3\n");
+        assert_eq!(
+            diff(&a, &b).into_changes(),
+            vec![TableChange::TableRemoved {
+                path: TablePath(vec![index(1)]),
+                table: table("layer=2\n"),
+            }]
+        );
+        assert_roundtrip(&a, &b);
+    }
+
+    #[test]
+    fn test_diff_array_change_in_middle() {
+        let a = table("[0]\nlayer=1\n[1]\nlayer=2\n[1.0]\nx=0\n[2]\nlayer=3\n");
+        let b = table("[0]\nlayer=1\n[1]\nlayer=2\n[1.0]\nx=5\n[2]\nlayer=3\n");
+        assert_eq!(
+            diff(&a, &b).into_changes(),
+            vec![TableChange::ValueChanged {
+                path: TablePath(vec![index(1), index(0)]),
+                index: 0,
+                key: "x".to_string(),
+                old: "0".to_string(),
+                new: "5".to_string(),
+            }]
+        );
+        assert_roundtrip(&a, &b);
+    }
+
+    #[test]
+    fn test_diff_keyed_tables() {
+        let a = table("[project]\nversion=1\n[0]\nlayer=1\n");
+        let b = table("[project]\nversion=1\n[scene]\nname=Root\n[0]\nlayer=1\n");
+        assert_eq!(
+            diff(&a, &b).into_changes(),
+            vec![TableChange::TableAdded {
+                path: TablePath(vec![key("scene")]),
+                position: 1,
+                table: table("name=Root\n"),
+            }]
+        );
+        assert_roundtrip(&a, &b);
+        assert_roundtrip(&b, &a);
+    }
+
+    #[test]
+    fn test_path_display() {
+        let path = TablePath(vec![key("scene"), index(0), index(2)]);
+        assert_eq!(path.to_string(), "scene.0.2");
+        assert!(path.starts_with(&TablePath(vec![key("scene")])));
+        assert!(!path.starts_with(&TablePath(vec![index(0)])));
+    }
+
+    #[test]
+    fn test_apply_ignores_missing_paths() {
+        let a = table("[0]\nlayer=1\n[1]\nlayer=2\n");
+        let b = table("[0]\nlayer=1\n[1]\nlayer=3\n");
+        let diff = diff(&a, &b);
+        let other = table("[0]\nlayer=1\n");
+        assert_eq!(apply(&other, &diff), other);
+    }
+
+    #[rstest]
+    #[case(include_str!("../test_assets/tracks.aup2"))]
+    #[case(include_str!("../test_assets/everything.aup2"))]
+    #[case(include_str!("../test_assets/object.object"))]
+    fn test_merge_identities(#[case] input: &str) {
+        let base: Table = input.parse().unwrap();
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..100 {
+            let mut changed = base.clone();
+            mutate(&mut changed, &mut rng, 0, true);
+            assert_eq!(merge(&base, &changed, &changed).unwrap(), changed);
+            assert_eq!(merge(&base, &base, &changed).unwrap(), changed);
+            assert_eq!(merge(&base, &changed, &base).unwrap(), changed);
+        }
+    }
+
+    #[rstest]
+    #[case(include_str!("../test_assets/tracks.aup2"))]
+    #[case(include_str!("../test_assets/everything.aup2"))]
+    fn test_merge_is_idempotent(#[case] input: &str) {
+        let base: Table = input.parse().unwrap();
+        let mut rng = Rng(0x1234_5678_9abc_def1);
+        let mut merged_count = 0;
+        for _ in 0..100 {
+            let mut ours = base.clone();
+            mutate(&mut ours, &mut rng, 0, false);
+            let mut theirs = base.clone();
+            mutate(&mut theirs, &mut rng, 0, false);
+            let Ok(merged) = merge(&base, &ours, &theirs) else {
+                continue;
+            };
+            merged_count += 1;
+            // マージ結果に同じ変更をもう一度マージしても変わらない
+            assert_eq!(merge(&base, &merged, &theirs).unwrap(), merged);
+            assert_eq!(merge(&base, &ours, &merged).unwrap(), merged);
+        }
+        assert!(merged_count > 0);
+    }
+
+    #[test]
+    fn test_merge_independent_changes() {
+        let base = table("[0]\nlayer=1\nframe=0,10\n[1]\nlayer=2\n[2]\nlayer=3\n");
+        let ours = table("[0]\nlayer=4\nframe=0,10\n[1]\nlayer=2\n[2]\nlayer=3\n");
+        let theirs = table("[0]\nlayer=1\nframe=0,20\n[1]\nlayer=3\n");
+        assert_eq!(
+            merge(&base, &ours, &theirs).unwrap().to_string(),
+            table("[0]\nlayer=4\nframe=0,20\n[1]\nlayer=3\n").to_string()
+        );
+    }
+
+    #[test]
+    fn test_merge_both_add_objects() {
+        let base = table("[0]\nlayer=1\n[1]\nlayer=2\n");
+        let ours = table("[0]\nlayer=1\n[1]\nlayer=5\n[2]\nlayer=2\n");
+        let theirs = table("[0]\nlayer=1\n[1]\nlayer=6\n[2]\nlayer=2\n[3]\nlayer=7\n");
+        assert_eq!(
+            merge(&base, &ours, &theirs).unwrap().to_string(),
+            table("[0]\nlayer=1\n[1]\nlayer=5\n[2]\nlayer=6\n[3]\nlayer=2\n[4]\nlayer=7\n")
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_merge_both_add_same_object() {
+        let base = table("[0]\nlayer=1\n");
+        let ours = table("[0]\nlayer=1\n[1]\nlayer=2\n");
+        assert_eq!(merge(&base, &ours, &ours.clone()).unwrap(), ours);
+    }
+
+    #[test]
+    fn test_merge_both_add_keyed_table() {
+        let base = table("[project]\nversion=1\n");
+        let ours = table("[project]\nversion=1\n[scene]\nname=Root\nwidth=1920\n");
+        let theirs = table("[project]\nversion=1\n[scene]\nname=Root\nheight=1080\n");
+        assert_eq!(
+            merge(&base, &ours, &theirs).unwrap().to_string(),
+            table("[project]\nversion=1\n[scene]\nname=Root\nwidth=1920\nheight=1080\n")
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_merge_value_conflict() {
+        let base = table("[0]\nlayer=1\nframe=0,10\n");
+        let ours = table("[0]\nlayer=2\nframe=0,10\n");
+        let theirs = table("[0]\nlayer=3\n");
+        assert_eq!(
+            merge(&base, &ours, &theirs),
+            Err(vec![Conflict {
+                path: TablePath(vec![index(0)]),
+                kind: ConflictKind::Value {
+                    key: "layer".to_string(),
+                    base: Some("1".to_string()),
+                    ours: Some("2".to_string()),
+                    theirs: Some("3".to_string()),
+                },
+            }])
+        );
+
+        let theirs = table("[0]\nlayer=1\n");
+        let ours = table("[0]\nlayer=1\nframe=0,20\n");
+        assert_eq!(
+            merge(&base, &ours, &theirs),
+            Err(vec![Conflict {
+                path: TablePath(vec![index(0)]),
+                kind: ConflictKind::Value {
+                    key: "frame".to_string(),
+                    base: Some("0,10".to_string()),
+                    ours: Some("0,20".to_string()),
+                    theirs: None,
+                },
+            }])
+        );
+    }
+
+    #[test]
+    fn test_merge_added_value_conflict() {
+        let base = table("a=1\n");
+        let ours = table("a=1\nb=2\n");
+        let theirs = table("a=1\nb=3\n");
+        assert_eq!(
+            merge(&base, &ours, &theirs),
+            Err(vec![Conflict {
+                path: TablePath::root(),
+                kind: ConflictKind::Value {
+                    key: "b".to_string(),
+                    base: None,
+                    ours: Some("2".to_string()),
+                    theirs: Some("3".to_string()),
+                },
+            }])
+        );
+    }
+
+    #[test]
+    fn test_merge_remove_modify_conflict() {
+        let base = table("[0]\nlayer=1\n[0.0]\nx=0\n[1]\nlayer=2\n");
+        let ours = table("[0]\nlayer=2\n");
+        let theirs = table("[0]\nlayer=1\n[0.0]\nx=5\n[1]\nlayer=2\n");
+        assert_eq!(
+            merge(&base, &ours, &theirs),
+            Err(vec![Conflict {
+                path: TablePath(vec![index(0)]),
+                kind: ConflictKind::Table {
+                    base: Some(table("layer=1\n[0]\nx=0\n")),
+                    ours: None,
+                    theirs: Some(table("layer=1\n[0]\nx=5\n")),
+                },
+            }])
+        );
+    }
+
+    #[test]
+    fn test_merge_both_remove() {
+        let base = table("[0]\nlayer=1\n[1]\nlayer=2\n[2]\nlayer=3\n");
+        let ours = table("[0]\nlayer=1\n[1]\nlayer=3\n");
+        let theirs = table("[0]\nlayer=1\n");
+        assert_eq!(merge(&base, &ours, &theirs).unwrap(), theirs);
+    }
+}
@@ -2,8 +2,10 @@
 //!
 //! AviUtl2のプロジェクトファイル（`*.aup2`）とエイリアスファイル（`*.object`、`*.effect`）で使われている
 //! データ構造を読み書きするクレート。
+mod diff;
 mod table;
 mod value;
 
+pub use diff::*;
 pub use table::*;
 pub use value::*;
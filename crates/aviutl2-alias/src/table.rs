@@ -1,34 +1,33 @@
 use crate::FromTableValue;
 
 /// テーブル構造を定義します。
+///
+/// 値は読み込んだ順番のまま保持されます。
+/// そのため、このクレートが解釈しないキーや、重複したキーもそのまま書き出されます。
 #[derive(Clone, Default, PartialEq, Eq)]
 pub struct Table {
-    items: indexmap::IndexMap<String, TableItem>,
-}
-
-#[derive(Clone, PartialEq, Eq)]
-struct TableItem {
-    value: Option<String>,
-    table: Option<Table>,
+    pub(crate) entries: Vec<(String, String)>,
+    pub(crate) tables: indexmap::IndexMap<String, Table>,
 }
 
 impl Table {
     /// 空のテーブルを作成します。
     pub fn new() -> Self {
         Self {
-            items: indexmap::IndexMap::new(),
+            entries: Vec::new(),
+            tables: indexmap::IndexMap::new(),
         }
     }
 
     /// 指定したキーに値を挿入します。
+    ///
+    /// キーが既に存在する場合は、その位置のまま値を置き換えます。
+    /// キーが重複している場合は、最後の値を置き換えます。
     pub fn insert_value<T: std::fmt::Display>(&mut self, key: &str, value: T) {
-        self.items
-            .entry(key.to_string())
-            .or_insert_with(|| TableItem {
-                value: None,
-                table: None,
-            })
-            .value = Some(value.to_string());
+        match self.get_value_mut(key) {
+            Some(existing) => *existing = value.to_string(),
+            None => self.entries.push((key.to_string(), value.to_string())),
+        }
     }
     /// 指定したキーにサブテーブルを挿入します。
     ///
@@ -36,36 +35,20 @@ impl Table {
     pub fn insert_table(&mut self, key: &str, table: Table) {
         let mut segments = key.split('.').collect::<Vec<_>>();
         if segments.len() <= 1 {
-            self.items
-                .entry(key.to_string())
-                .or_insert_with(|| TableItem {
-                    value: None,
-                    table: None,
-                })
-                .table = Some(table);
+            self.tables.insert(key.to_string(), table);
             return;
         }
 
         let last = segments.pop().unwrap();
         let path = segments.into_iter().map(str::to_string).collect::<Vec<_>>();
         let target = ensure_path(self, &path);
-        target
-            .items
-            .entry(last.to_string())
-            .or_insert_with(|| TableItem {
-                value: None,
-                table: None,
-            })
-            .table = Some(table);
+        target.tables.insert(last.to_string(), table);
     }
     /// 指定したキーの値を削除します。
+    ///
+    /// キーが重複している場合は、すべての値を削除します。
     pub fn remove_value(&mut self, key: &str) {
-        if let Some(item) = self.items.get_mut(key) {
-            item.value = None;
-            if item.table.is_none() {
-                self.items.shift_remove(key);
-            }
-        }
+        self.entries.retain(|(entry_key, _)| entry_key != key);
     }
     /// 指定したキーのサブテーブルを削除します。
     ///
@@ -73,12 +56,7 @@ impl Table {
     pub fn remove_table(&mut self, key: &str) {
         let mut segments = key.split('.').collect::<Vec<_>>();
         if segments.len() <= 1 {
-            if let Some(item) = self.items.get_mut(key) {
-                item.table = None;
-                if item.value.is_none() {
-                    self.items.shift_remove(key);
-                }
-            }
+            self.tables.shift_remove(key);
             return;
         }
 
@@ -89,8 +67,14 @@ impl Table {
         }
     }
     /// 指定したキーの値を文字列として読み取ります。
+    ///
+    /// キーが重複している場合は、最後の値を返します。
     pub fn get_value(&self, key: &str) -> Option<&String> {
-        self.items.get(key).and_then(|item| item.value.as_ref())
+        self.entries
+            .iter()
+            .rev()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
     }
 
     /// 指定したキーの値をパースして読み取ります。
@@ -99,8 +83,14 @@ impl Table {
             .map(|value_str| T::from_table_value(value_str))
     }
     /// 指定したキーの値への可変参照を取得します。
+    ///
+    /// キーが重複している場合は、最後の値への参照を返します。
     pub fn get_value_mut(&mut self, key: &str) -> Option<&mut String> {
-        self.items.get_mut(key).and_then(|item| item.value.as_mut())
+        self.entries
+            .iter_mut()
+            .rev()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
     }
     /// 指定したキーのサブテーブルを取得します。
     ///
@@ -108,8 +98,7 @@ impl Table {
     pub fn get_table(&self, key: &str) -> Option<&Table> {
         let mut current = self;
         for segment in key.split('.') {
-            let item = current.items.get(segment)?;
-            current = item.table.as_ref()?;
+            current = current.tables.get(segment)?;
         }
         Some(current)
     }
@@ -119,48 +108,48 @@ impl Table {
     pub fn get_table_mut(&mut self, key: &str) -> Option<&mut Table> {
         let mut current = self;
         for segment in key.split('.') {
-            let next = current.items.get_mut(segment)?.table.as_mut()?;
-            current = next;
+            current = current.tables.get_mut(segment)?;
         }
         Some(current)
     }
 
     /// 別のテーブルをマージします。
     pub fn merge(&mut self, other: &Table) {
-        for (key, other_item) in &other.items {
-            match self.items.get_mut(key) {
-                Some(item) => {
-                    if let Some(other_value) = &other_item.value {
-                        item.value = Some(other_value.clone());
-                    }
-                    if let Some(other_table) = &other_item.table {
-                        if let Some(item_table) = &mut item.table {
-                            item_table.merge(other_table);
-                        } else {
-                            item.table = Some(other_table.clone());
-                        }
-                    }
-                }
+        for (key, value) in other.values() {
+            self.insert_value(key, value);
+        }
+        for (key, other_table) in &other.tables {
+            match self.tables.get_mut(key) {
+                Some(table) => table.merge(other_table),
                 None => {
-                    self.items.insert(key.clone(), other_item.clone());
+                    self.tables.insert(key.clone(), other_table.clone());
                 }
             }
         }
     }
 
     /// 値を列挙するイテレーターを返します。
+    ///
+    /// キーが重複している場合は、最後の値のみを列挙します。
     pub fn values<'a>(&'a self) -> TableValuesIterator<'a> {
         TableValuesIterator::new(self)
     }
 
     /// 可変参照で値を列挙します。
+    ///
+    /// キーが重複している場合は、最後の値のみを列挙します。
     pub fn values_mut<'a>(&'a mut self) -> TableValuesIteratorMut<'a> {
         TableValuesIteratorMut::new(self)
     }
 
+    /// 重複したキーを含めて、すべての値を読み込んだ順番で列挙するイテレーターを返します。
+    pub fn raw_entries<'a>(&'a self) -> RawEntriesIterator<'a> {
+        RawEntriesIterator::new(self)
+    }
+
     /// 値が空かどうかを返します。
     pub fn is_values_empty(&self) -> bool {
-        self.items.values().all(|item| item.value.is_none())
+        self.entries.is_empty()
     }
 
     /// 子テーブルを列挙するイテレーターを返します。
@@ -175,7 +164,7 @@ impl Table {
 
     /// 子テーブルが空かどうかを返します。
     pub fn is_subtables_empty(&self) -> bool {
-        self.items.values().all(|item| item.table.is_none())
+        self.tables.is_empty()
     }
 
     /// `0`、`1`、`2`...のキーを持つ子テーブルを配列として列挙するイテレーターを返します。
@@ -192,24 +181,34 @@ impl Table {
     ///
     /// `prefix`はサブテーブルの名前の接頭辞として使用されます。
     /// 具体的には、`${prefix}.${key}`の形式でサブテーブルの名前が生成されます。
+    ///
+    /// 値と子テーブルのどちらも持たないサブテーブルは、空のセクションとして書き出されます。
     pub fn write_table(
         &self,
         f: &mut impl std::fmt::Write,
         prefix: Option<&str>,
     ) -> std::fmt::Result {
-        for (key, item) in self.values() {
-            write!(f, "{}={}\r\n", key, item)?;
+        for (key, value) in self.raw_entries() {
+            write!(f, "{}={}\r\n", key, value)?;
         }
         let prefix = prefix.map_or("".to_string(), |p| format!("{}.", p));
         for (key, sub_table) in self.subtables() {
             let subtable_name = format!("{}{}", prefix, key);
-            if !sub_table.is_values_empty() {
+            if !sub_table.is_values_empty() || sub_table.is_subtables_empty() {
                 write!(f, "[{}]\r\n", subtable_name)?;
             }
             sub_table.write_table(f, Some(&subtable_name))?;
         }
         Ok(())
     }
+
+    /// `index`番目の値が、後ろにある同じキーの値で隠されているかどうかを返します。
+    fn is_shadowed(&self, index: usize) -> bool {
+        let key = &self.entries[index].0;
+        self.entries[index + 1..]
+            .iter()
+            .any(|(entry_key, _)| entry_key == key)
+    }
 }
 
 /// [`Table::values`]で使われるイテレーター。
@@ -227,30 +226,41 @@ impl<'a> Iterator for TableValuesIterator<'a> {
     type Item = (&'a String, &'a String);
 
     fn next(&mut self) -> Option<Self::Item> {
-        while self.index < self.table.items.len() {
-            let item = &self.table.items.get_index(self.index).unwrap();
+        while self.index < self.table.entries.len() {
+            let index = self.index;
             self.index += 1;
-            if let Some(value) = &item.1.value {
-                return Some((item.0, value));
+            if !self.table.is_shadowed(index) {
+                let (key, value) = &self.table.entries[index];
+                return Some((key, value));
             }
         }
         None
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = self.table.items.len().saturating_sub(self.index);
+        let remaining = self.table.entries.len().saturating_sub(self.index);
         (0, Some(remaining))
     }
 }
 
 /// [`Table::values_mut`]で使われるイテレーター。
 pub struct TableValuesIteratorMut<'a> {
-    inner: indexmap::map::IterMut<'a, String, TableItem>,
+    inner: std::vec::IntoIter<(&'a String, &'a mut String)>,
 }
 impl<'a> TableValuesIteratorMut<'a> {
     pub fn new(table: &'a mut Table) -> Self {
+        let shadowed = (0..table.entries.len())
+            .map(|index| table.is_shadowed(index))
+            .collect::<Vec<_>>();
+        let values = table
+            .entries
+            .iter_mut()
+            .zip(shadowed)
+            .filter(|(_, shadowed)| !shadowed)
+            .map(|((key, value), _)| (&*key, value))
+            .collect::<Vec<_>>();
         Self {
-            inner: table.items.iter_mut(),
+            inner: values.into_iter(),
         }
     }
 }
@@ -258,71 +268,75 @@ impl<'a> Iterator for TableValuesIteratorMut<'a> {
     type Item = (&'a String, &'a mut String);
 
     fn next(&mut self) -> Option<Self::Item> {
-        for (key, item) in self.inner.by_ref() {
-            if let Some(value) = item.value.as_mut() {
-                return Some((key, value));
-            }
+        self.inner.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// [`Table::raw_entries`]で使われるイテレーター。
+#[derive(Debug)]
+pub struct RawEntriesIterator<'a> {
+    inner: std::slice::Iter<'a, (String, String)>,
+}
+impl<'a> RawEntriesIterator<'a> {
+    pub fn new(table: &'a Table) -> Self {
+        Self {
+            inner: table.entries.iter(),
         }
-        None
+    }
+}
+impl<'a> Iterator for RawEntriesIterator<'a> {
+    type Item = (&'a String, &'a String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (key, value))
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = self.inner.len();
-        (0, Some(remaining))
+        self.inner.size_hint()
     }
 }
 
 /// [`Table::subtables`]で使われるイテレーター。
 pub struct SubTablesIterator<'a> {
-    table: &'a Table,
-    index: usize,
+    inner: indexmap::map::Iter<'a, String, Table>,
 }
 impl<'a> SubTablesIterator<'a> {
     pub fn new(table: &'a Table) -> Self {
-        Self { table, index: 0 }
+        Self {
+            inner: table.tables.iter(),
+        }
     }
 }
 impl<'a> Iterator for SubTablesIterator<'a> {
     type Item = (&'a String, &'a Table);
     fn next(&mut self) -> Option<Self::Item> {
-        while self.index < self.table.items.len() {
-            let item = &self.table.items.get_index(self.index).unwrap();
-            self.index += 1;
-            if let Some(sub_table) = &item.1.table {
-                return Some((item.0, sub_table));
-            }
-        }
-        None
+        self.inner.next()
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = self.table.items.len().saturating_sub(self.index);
-        (0, Some(remaining))
+        self.inner.size_hint()
     }
 }
 
 /// [`Table::subtables_mut`]で使われるイテレーター。
 pub struct SubTablesIteratorMut<'a> {
-    inner: indexmap::map::IterMut<'a, String, TableItem>,
+    inner: indexmap::map::IterMut<'a, String, Table>,
 }
 impl<'a> SubTablesIteratorMut<'a> {
     pub fn new(table: &'a mut Table) -> Self {
         Self {
-            inner: table.items.iter_mut(),
+            inner: table.tables.iter_mut(),
         }
     }
 }
 impl<'a> Iterator for SubTablesIteratorMut<'a> {
     type Item = (&'a String, &'a mut Table);
     fn next(&mut self) -> Option<Self::Item> {
-        for (key, item) in self.inner.by_ref() {
-            if let Some(sub_table) = item.table.as_mut() {
-                return Some((key, sub_table));
-            }
-        }
-        None
+        self.inner.next()
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = self.inner.len();
-        (0, Some(remaining))
+        self.inner.size_hint()
     }
 }
 
@@ -348,7 +362,7 @@ impl<'a> Iterator for ArraySubTablesIterator<'a> {
         }
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = self.table.items.len().saturating_sub(self.index);
+        let remaining = self.table.tables.len().saturating_sub(self.index);
         (0, Some(remaining))
     }
 }
@@ -360,12 +374,11 @@ pub struct ArraySubTablesIteratorMut<'a> {
 impl<'a> ArraySubTablesIteratorMut<'a> {
     pub fn new(table: &'a mut Table) -> Self {
         let mut indexed_tables = table
-            .items
+            .tables
             .iter_mut()
-            .filter_map(|(key, item)| {
+            .filter_map(|(key, table)| {
                 let index = key.parse::<usize>().ok()?;
-                (index.to_string() == *key)
-                    .then(|| item.table.as_mut().map(|table| (index, table)))?
+                (index.to_string() == *key).then_some((index, table))
             })
             .collect::<Vec<_>>();
         indexed_tables.sort_unstable_by_key(|(index, _)| *index);
@@ -401,15 +414,6 @@ pub enum TableParseError {
     InvalidLine(String),
 }
 
-impl std::fmt::Debug for TableItem {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("TableItem")
-            .field("value", &self.value)
-            .field("table", &self.table)
-            .finish()
-    }
-}
-
 impl std::str::FromStr for Table {
     type Err = TableParseError;
 
@@ -423,6 +427,7 @@ impl std::str::FromStr for Table {
                 section.push_str(line);
                 if section.ends_with(']') {
                     parse_section_line(&section, &mut current_path)?;
+                    ensure_path(&mut root, &current_path);
                 } else {
                     section.push_str(line_ending);
                     section_line = Some(section);
@@ -432,6 +437,7 @@ impl std::str::FromStr for Table {
             } else if line.starts_with('[') {
                 if line.ends_with(']') {
                     parse_section_line(line, &mut current_path)?;
+                    ensure_path(&mut root, &current_path);
                 } else {
                     let mut section = line.to_string();
                     section.push_str(line_ending);
@@ -439,7 +445,7 @@ impl std::str::FromStr for Table {
                 }
             } else if let Some((key, value)) = line.split_once('=') {
                 let target = ensure_path(&mut root, &current_path);
-                target.insert_value(key, value);
+                target.entries.push((key.to_string(), value.to_string()));
             } else {
                 return Err(TableParseError::InvalidLine(line.to_string()));
             }
@@ -502,14 +508,7 @@ impl<'a> Iterator for SourceLines<'a> {
 
 fn ensure_path<'a>(mut table: &'a mut Table, path: &[String]) -> &'a mut Table {
     for segment in path {
-        let entry = table
-            .items
-            .entry(segment.clone())
-            .or_insert_with(|| TableItem {
-                value: None,
-                table: Some(Table::new()),
-            });
-        table = entry.table.get_or_insert_with(Table::new);
+        table = table.tables.entry(segment.clone()).or_default();
     }
     table
 }
@@ -536,6 +535,7 @@ impl std::fmt::Display for Table {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
     #[test]
     fn test_table_insert_and_get() {
         let mut table = Table::new();
@@ -672,4 +672,97 @@ mod tests {
         );
         assert_eq!(table.get_table("3").unwrap().get_value("index"), None);
     }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let input = "[0]\r\na=1\r\nb=2\r\na=3\r\n";
+        let mut table: Table = input.parse().unwrap();
+        let sub = table.get_table("0").unwrap();
+
+        assert_eq!(sub.get_value("a"), Some(&"3".to_string()));
+        assert_eq!(
+            sub.values().collect::<Vec<_>>(),
+            vec![
+                (&"b".to_string(), &"2".to_string()),
+                (&"a".to_string(), &"3".to_string())
+            ]
+        );
+        assert_eq!(
+            sub.raw_entries()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("a", "1"), ("b", "2"), ("a", "3")]
+        );
+        assert_eq!(table.to_string(), input);
+
+        table.get_table_mut("0").unwrap().insert_value("a", 4);
+        assert_eq!(table.to_string(), "[0]\r\na=1\r\nb=2\r\na=4\r\n");
+
+        table.get_table_mut("0").unwrap().remove_value("a");
+        assert_eq!(table.to_string(), "[0]\r\nb=2\r\n");
+    }
+
+    #[test]
+    fn test_empty_section() {
+        let input = "[a]\r\n[b.0]\r\nkey=value\r\n[c]\r\n";
+        let table: Table = input.parse().unwrap();
+        assert!(table.get_table("a").unwrap().is_values_empty());
+        assert_eq!(table.to_string(), input);
+    }
+
+    #[rstest]
+    #[case::binary(include_str!("../test_assets/binary.aup2"))]
+    #[case::tracks(include_str!("../test_assets/tracks.aup2"))]
+    #[case::unknown_fields(include_str!("../test_assets/unknown_fields.aup2"))]
+    #[case::object(include_str!("../test_assets/object.object"))]
+    #[case::effect(include_str!("../test_assets/effect.effect"))]
+    fn test_round_trip(#[case] input: &str) {
+        let table: Table = input.parse().unwrap();
+        assert_eq!(table.to_string(), input);
+
+        let reparsed: Table = table.to_string().parse().unwrap();
+        assert_eq!(reparsed, table);
+    }
+
+    #[rstest]
+    #[case::binary(include_str!("../test_assets/binary.aup2"))]
+    #[case::tracks(include_str!("../test_assets/tracks.aup2"))]
+    #[case::unknown_fields(include_str!("../test_assets/unknown_fields.aup2"))]
+    #[case::object(include_str!("../test_assets/object.object"))]
+    #[case::effect(include_str!("../test_assets/effect.effect"))]
+    fn test_round_trip_with_modification(#[case] input: &str) {
+        fn last_value_table(table: &mut Table) -> Option<&mut Table> {
+            if table.subtables().any(|(_, sub)| has_values(sub)) {
+                let (_, sub) = table
+                    .subtables_mut()
+                    .filter(|(_, sub)| has_values(sub))
+                    .last()?;
+                return last_value_table(sub);
+            }
+            (!table.is_values_empty()).then_some(table)
+        }
+        fn has_values(table: &Table) -> bool {
+            !table.is_values_empty() || table.subtables().any(|(_, sub)| has_values(sub))
+        }
+
+        let table: Table = input.parse().unwrap();
+        let mut modified_table = table.clone();
+        // 最後のセクションの最初の値を1つだけ書き換える
+        let target = last_value_table(&mut modified_table).unwrap();
+        let (_, value) = target.values_mut().next().unwrap();
+        value.push_str("_modified");
+        let output = modified_table.to_string();
+
+        let input_lines = input.split("\r\n").collect::<Vec<_>>();
+        let output_lines = output.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(input_lines.len(), output_lines.len());
+        let changed_lines = input_lines
+            .iter()
+            .zip(&output_lines)
+            .filter(|(input, output)| input != output)
+            .collect::<Vec<_>>();
+        assert_eq!(changed_lines.len(), 1);
+        let (input, output) = changed_lines[0];
+        assert_eq!(format!("{input}_modified"), *output);
+    }
 }
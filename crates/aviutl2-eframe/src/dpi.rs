@@ -0,0 +1,93 @@
+use aviutl2::{AnyResult, tracing};
+use std::sync::{
+    Arc,
+    atomic::{AtomicU32, Ordering},
+};
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    UI::{
+        HiDpi::GetDpiForWindow,
+        Shell::{DefSubclassProc, RemoveWindowSubclass, SetWindowSubclass},
+        WindowsAndMessaging::{WM_DPICHANGED, WM_DPICHANGED_AFTERPARENT, WM_NCDESTROY},
+    },
+};
+
+/// 拡大率100%のときのDPI。
+const DEFAULT_DPI: u32 = 96;
+const SUBCLASS_ID: usize = 0x4155_4432; // "AUD2"
+
+/// ウィンドウのDPIを保持する構造体。
+///
+/// AviUtl2によって子ウィンドウにされたウィンドウには`WM_DPICHANGED`が届かず、
+/// winitがスケールの変更を検知できないため、サブクラス化して自前で追跡する。
+pub struct DpiState {
+    dpi: AtomicU32,
+    egui_ctx: eframe::egui::Context,
+}
+
+impl DpiState {
+    /// 現在のDPIを返す。
+    pub fn dpi(&self) -> u32 {
+        self.dpi.load(Ordering::Relaxed)
+    }
+
+    /// 現在のスケール（100%のときに1.0）を返す。
+    pub fn scale_factor(&self) -> f32 {
+        self.dpi() as f32 / DEFAULT_DPI as f32
+    }
+
+    fn update(&self, hwnd: HWND) {
+        let dpi = window_dpi(hwnd);
+        let previous = self.dpi.swap(dpi, Ordering::Relaxed);
+        if previous != dpi {
+            tracing::debug!("Egui window DPI changed: {} -> {}", previous, dpi);
+            self.egui_ctx.request_repaint();
+        }
+    }
+}
+
+fn window_dpi(hwnd: HWND) -> u32 {
+    match unsafe { GetDpiForWindow(hwnd) } {
+        0 => DEFAULT_DPI,
+        dpi => dpi,
+    }
+}
+
+/// ウィンドウをサブクラス化し、DPIの変更を追跡する。
+pub fn install(hwnd: HWND, egui_ctx: eframe::egui::Context) -> AnyResult<Arc<DpiState>> {
+    let state = Arc::new(DpiState {
+        dpi: AtomicU32::new(window_dpi(hwnd)),
+        egui_ctx,
+    });
+    let ref_data = Arc::into_raw(Arc::clone(&state));
+    let installed =
+        unsafe { SetWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID, ref_data as usize) };
+    if !installed.as_bool() {
+        // SAFETY: サブクラス化に失敗したので、渡した参照はどこからも使われない
+        drop(unsafe { Arc::from_raw(ref_data) });
+        anyhow::bail!("Failed to subclass window for DPI tracking");
+    }
+    Ok(state)
+}
+
+unsafe extern "system" fn subclass_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    id: usize,
+    ref_data: usize,
+) -> LRESULT {
+    let state = ref_data as *const DpiState;
+    match msg {
+        WM_DPICHANGED | WM_DPICHANGED_AFTERPARENT => {
+            unsafe { &*state }.update(hwnd);
+        }
+        WM_NCDESTROY => unsafe {
+            let _ = RemoveWindowSubclass(hwnd, Some(subclass_proc), id);
+            drop(Arc::from_raw(state));
+        },
+        _ => {}
+    }
+    unsafe { DefSubclassProc(hwnd, msg, wparam, lparam) }
+}
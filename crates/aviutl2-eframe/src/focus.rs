@@ -0,0 +1,115 @@
+use std::sync::{
+    Arc, RwLock,
+    atomic::{AtomicBool, Ordering},
+};
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    UI::{
+        Input::KeyboardAndMouse::SetFocus,
+        Shell::{DefSubclassProc, RemoveWindowSubclass, SetWindowSubclass},
+        WindowsAndMessaging::{
+            DLGC_WANTALLKEYS, DLGC_WANTARROWS, DLGC_WANTCHARS, DLGC_WANTTAB, WM_APP, WM_GETDLGCODE,
+            WM_MOUSEACTIVATE, WM_NCDESTROY,
+        },
+    },
+};
+
+const SUBCLASS_ID: usize = 0x4155_4B42; // "AUKB"
+
+/// ウィンドウにキーボードフォーカスを移すメッセージ。
+///
+/// `SetFocus`はウィンドウを作ったスレッドからしか呼べないので、メッセージを送ってウィンドウのスレッドで処理する。
+pub const WM_REQUEST_FOCUS: u32 = WM_APP + 0x4B;
+
+/// 仮想キーコード。
+///
+/// `VirtualKey(0x74)`（F5）のように、Windowsの仮想キーコードを指定します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VirtualKey(pub u16);
+
+impl From<windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY> for VirtualKey {
+    fn from(value: windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY) -> Self {
+        Self(value.0)
+    }
+}
+
+/// キーボードフォーカスの状態を保持する構造体。
+///
+/// eguiのウィジェットがキーボード入力を受け取っている間は、ダイアログの処理にキーを奪われないように
+/// `WM_GETDLGCODE`ですべてのキーを要求する。
+#[derive(Default)]
+pub struct FocusState {
+    wants_keyboard: AtomicBool,
+    passthrough: RwLock<Vec<VirtualKey>>,
+}
+
+impl FocusState {
+    /// eguiがキーボード入力を受け取っているかどうかを記録する。
+    pub fn set_wants_keyboard(&self, wants_keyboard: bool) {
+        self.wants_keyboard.store(wants_keyboard, Ordering::Relaxed);
+    }
+
+    /// eguiがキーボード入力を受け取っているかどうか。
+    pub fn wants_keyboard(&self) -> bool {
+        self.wants_keyboard.load(Ordering::Relaxed)
+    }
+
+    /// eguiがキーボード入力を受け取っている間もAviUtl2に送るキーを設定する。
+    pub fn set_passthrough(&self, keys: &[VirtualKey]) {
+        *self.passthrough.write().unwrap() = keys.to_vec();
+    }
+
+    /// キーイベントをAviUtl2に送るかどうか。
+    pub fn should_forward(&self, key: VirtualKey) -> bool {
+        !self.wants_keyboard() || self.passthrough.read().unwrap().contains(&key)
+    }
+}
+
+/// ウィンドウをサブクラス化し、キーボードフォーカスを扱う。
+pub fn install(hwnd: HWND, state: Arc<FocusState>) -> aviutl2::AnyResult<()> {
+    let ref_data = Arc::into_raw(state);
+    let installed =
+        unsafe { SetWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID, ref_data as usize) };
+    if !installed.as_bool() {
+        // SAFETY: サブクラス化に失敗したので、渡した参照はどこからも使われない
+        drop(unsafe { Arc::from_raw(ref_data) });
+        anyhow::bail!("Failed to subclass window for keyboard focus");
+    }
+    Ok(())
+}
+
+unsafe extern "system" fn subclass_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    id: usize,
+    ref_data: usize,
+) -> LRESULT {
+    let state = ref_data as *const FocusState;
+    match msg {
+        WM_GETDLGCODE if unsafe { &*state }.wants_keyboard() => {
+            return LRESULT(
+                (DLGC_WANTALLKEYS | DLGC_WANTARROWS | DLGC_WANTCHARS | DLGC_WANTTAB) as isize,
+            );
+        }
+        WM_REQUEST_FOCUS => {
+            if let Err(e) = unsafe { SetFocus(Some(hwnd)) } {
+                aviutl2::tracing::warn!("SetFocus failed: {:?}", e);
+            }
+            return LRESULT(0);
+        }
+        WM_MOUSEACTIVATE => {
+            // 子ウィンドウはクリックされてもフォーカスを受け取らないので、ここで受け取る
+            if let Err(e) = unsafe { SetFocus(Some(hwnd)) } {
+                aviutl2::tracing::warn!("SetFocus failed: {:?}", e);
+            }
+        }
+        WM_NCDESTROY => unsafe {
+            let _ = RemoveWindowSubclass(hwnd, Some(subclass_proc), id);
+            drop(Arc::from_raw(state));
+        },
+        _ => {}
+    }
+    unsafe { DefSubclassProc(hwnd, msg, wparam, lparam) }
+}
@@ -13,12 +13,16 @@
 //! aviutl2-rsをGitリポジトリで依存として指定する場合は`[patch]`セクションを使用してください。
 //! もし`aviutl2 = { git = "..." }`のように直接指定した場合、`aviutl2-eframe`クレートから
 //! 参照する`aviutl2`クレートと依存関係が分裂してしまい、特に[`aviutl2_visuals`]関数などで問題が発生します。
+mod dpi;
+mod focus;
 mod key;
+mod panic_guard;
+pub mod widgets;
 
 use anyhow::Context;
 use aviutl2::{AnyResult, raw_window_handle, tracing};
 use eframe::EframeWinitApplication;
-use std::{num::NonZeroIsize, sync::mpsc};
+use std::{num::NonZeroIsize, sync::mpsc, time::Duration};
 use windows::Win32::{
     Foundation::{HWND, SetLastError},
     UI::WindowsAndMessaging::{
@@ -29,29 +33,37 @@ use winit::{platform::windows::EventLoopBuilderExtWindows, raw_window_handle::Ha
 
 pub use eframe;
 pub use eframe::egui;
+pub use focus::VirtualKey;
 
 /// eframeのウィンドウを表す構造体。
 ///
 /// この構造体は、別スレッドで動作するegui/eframeウィンドウを管理します。
 /// ウィンドウのハンドル（HWND）やeguiのコンテキストへのアクセスを提供します。
+///
+/// # Note
+///
+/// winitの制約により、イベントループは1つのDLLにつき一度しか作成できません。
+/// `EframeWindow`を破棄しても再び作成することはできないため、プラグインの初期化時などに1つだけ作成し、
+/// 破棄せずに使い回してください。2つ目を作成した場合は、[`Self::handle`]などがエラーを返します。
+/// `eframe::run_native`も同じイベントループを使うため、`EframeWindow`と同じDLLでは使えません。
 pub struct EframeWindow {
     hwnd: std::sync::OnceLock<NonZeroIsize>,
     egui_ctx: std::sync::OnceLock<egui::Context>,
-    #[expect(clippy::type_complexity)]
-    init_rx: std::sync::Mutex<
-        Option<
-            mpsc::Receiver<
-                Result<(isize, egui::Context), Box<dyn std::error::Error + Send + Sync>>,
-            >,
-        >,
-    >,
+    dpi: std::sync::OnceLock<std::sync::Arc<dpi::DpiState>>,
+    focus: std::sync::Arc<focus::FocusState>,
+    init_rx: std::sync::Mutex<Option<mpsc::Receiver<InitResult>>>,
     thread: Option<std::thread::JoinHandle<()>>,
     thread_terminator: std::sync::Arc<std::sync::OnceLock<()>>,
     event_loop_proxy:
         std::sync::Arc<std::sync::OnceLock<winit::event_loop::EventLoopProxy<eframe::UserEvent>>>,
     panic_message: std::sync::Arc<std::sync::OnceLock<String>>,
+    init_error: std::sync::OnceLock<String>,
+    app_error: std::sync::Arc<std::sync::OnceLock<String>>,
 }
 
+/// ウィンドウの初期化を待つ最大時間。
+const INIT_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// EframeWindowのウィンドウハンドル。
 ///
 /// `EframeWindow::handle()` で取得できます。
@@ -72,16 +84,54 @@ impl raw_window_handle::HasWindowHandle for EframeWindowHandle {
     }
 }
 
+type InitResult = Result<
+    (isize, egui::Context, std::sync::Arc<dpi::DpiState>),
+    Box<dyn std::error::Error + Send + Sync>,
+>;
+
 struct WrappedApp {
     hwnd: NonZeroIsize,
     internal_app: Box<dyn eframe::App>,
+    dpi: std::sync::Arc<dpi::DpiState>,
+    focus: std::sync::Arc<focus::FocusState>,
+    /// 最後にスケールを適用したときの（DPI, winitが認識しているスケール）。
+    applied_scale: Option<(u32, Option<f32>)>,
+    guard: panic_guard::PanicGuard,
+    app_error: std::sync::Arc<std::sync::OnceLock<String>>,
+    /// 独立したウィンドウかどうか。
+    standalone: bool,
 }
 
 impl eframe::App for WrappedApp {
     fn ui(&mut self, ui: &mut egui::Ui, frame: &mut eframe::Frame) {
-        self.internal_app.ui(ui, frame);
+        let internal_app = &mut self.internal_app;
+        if self
+            .guard
+            .run("ui", || internal_app.ui(ui, frame))
+            .is_none()
+        {
+            // パニックしたアプリは呼び出さず、エラーを表示する
+            let message = self.guard.error().unwrap_or_default();
+            self.app_error.set(message.to_string()).ok();
+            ui.colored_label(ui.visuals().error_fg_color, "The plugin UI has crashed.");
+            ui.label(message);
+        }
+
+        if self.standalone {
+            // 閉じるとイベントループが終了し、二度と作成できなくなるので、非表示にするだけにする
+            if ui.ctx().input(|i| i.viewport().close_requested()) {
+                ui.ctx()
+                    .send_viewport_cmd(egui::ViewportCommand::CancelClose);
+                ui.ctx()
+                    .send_viewport_cmd(egui::ViewportCommand::Visible(false));
+            }
+            // 親ウィンドウが無いので、キーイベントは送らない
+            return;
+        }
 
-        if cfg!(feature = "transparent_keyboard_input") && !ui.egui_wants_keyboard_input() {
+        self.focus
+            .set_wants_keyboard(ui.egui_wants_keyboard_input());
+        if cfg!(feature = "transparent_keyboard_input") {
             ui.input(|i| {
                 let parent_window = unsafe {
                     windows::Win32::UI::WindowsAndMessaging::GetParent(HWND(
@@ -133,6 +183,13 @@ impl eframe::App for WrappedApp {
                     ) else {
                         continue;
                     };
+                    // eguiのウィジェットがキーボード入力を受け取っている間は、指定されたキーだけを送る
+                    if !self
+                        .focus
+                        .should_forward(VirtualKey(key_message.wparam as u16))
+                    {
+                        continue;
+                    }
                     tracing::trace!(
                         concat!(
                             "Forwarding key event to parent window: ",
@@ -161,15 +218,18 @@ impl eframe::App for WrappedApp {
     }
 
     fn logic(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        self.internal_app.logic(ctx, frame);
+        let internal_app = &mut self.internal_app;
+        self.guard.run("logic", || internal_app.logic(ctx, frame));
     }
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        self.internal_app.save(storage);
+        let internal_app = &mut self.internal_app;
+        self.guard.run("save", || internal_app.save(storage));
     }
 
     fn on_exit(&mut self, gl: Option<&eframe::glow::Context>) {
-        self.internal_app.on_exit(gl);
+        let internal_app = &mut self.internal_app;
+        self.guard.run("on_exit", || internal_app.on_exit(gl));
     }
 
     fn auto_save_interval(&self) -> std::time::Duration {
@@ -185,37 +245,36 @@ impl eframe::App for WrappedApp {
     }
 
     fn raw_input_hook(&mut self, ctx: &egui::Context, raw_input: &mut egui::RawInput) {
-        // なぜかフォーカスがされないので、クリックされたら強制的にフォーカスを当てる
-        // （こうしないとキーボードが使えない）
-        if !raw_input.focused {
-            let is_clicked = raw_input
-                .events
-                .iter()
-                .any(|event| matches!(event, egui::Event::PointerButton { .. }));
-            if is_clicked {
-                tracing::trace!("Egui window clicked while unfocused, forcing focus");
-                let focus_result = unsafe {
-                    windows::Win32::UI::Input::KeyboardAndMouse::SetFocus(Some(HWND(
-                        self.hwnd.get() as *mut std::ffi::c_void,
-                    )))
-                };
-                match focus_result {
-                    Ok(_) => {
-                        tracing::trace!("SetFocus succeeded");
-                    }
-                    Err(e) => {
-                        tracing::warn!("SetFocus failed: {:?}", e);
-                    }
-                }
-            }
+        // 子ウィンドウではwinitがDPIの変更を検知できないので、自前で追跡したDPIに合わせる。
+        // winit側のスケールが変わった場合もズーム率がずれるので、合わせ直す。
+        let native_pixels_per_point = raw_input
+            .viewports
+            .get(&raw_input.viewport_id)
+            .and_then(|viewport| viewport.native_pixels_per_point);
+        let scale = (self.dpi.dpi(), native_pixels_per_point);
+        if self.applied_scale != Some(scale) {
+            tracing::debug!(
+                "Applying scale factor {} (native: {:?})",
+                self.dpi.scale_factor(),
+                native_pixels_per_point
+            );
+            ctx.set_pixels_per_point(self.dpi.scale_factor());
+            self.applied_scale = Some(scale);
+        }
 
+        // クリック時のフォーカスはWM_MOUSEACTIVATEで受け取るが、
+        // 子ウィンドウのフォーカスはwinitに伝わらないことがあるので、実際のフォーカスを見る
+        if !raw_input.focused {
             let current_focus = unsafe { windows::Win32::UI::Input::KeyboardAndMouse::GetFocus() };
             if (current_focus.0 as usize) == self.hwnd.get() as usize {
                 tracing::trace!("Egui window is now focused");
                 raw_input.focused = true;
             }
         }
-        self.internal_app.raw_input_hook(ctx, raw_input);
+        let internal_app = &mut self.internal_app;
+        self.guard.run("raw_input_hook", || {
+            internal_app.raw_input_hook(ctx, raw_input)
+        });
     }
 }
 
@@ -308,6 +367,8 @@ impl EframeWindow {
     /// `app_creator`は`eframe::run_native`と同様のclosureです。
     /// この関数はすぐに返り、ウィンドウの初期化はバックグラウンドで行われます。
     /// ウィンドウハンドルが必要な場合は `handle()` を呼び出してください。
+    ///
+    /// `app_creator`が失敗したりパニックした場合は、`handle()`などがそのエラーを返します。
     pub fn new<F>(name: &str, app_creator: F) -> AnyResult<Self>
     where
         F: 'static
@@ -318,15 +379,82 @@ impl EframeWindow {
             )
                 -> Result<Box<dyn eframe::App>, Box<dyn std::error::Error + Send + Sync>>,
     {
-        let (tx, rx) = mpsc::channel::<
-            Result<(isize, egui::Context), Box<dyn std::error::Error + Send + Sync>>,
-        >();
+        Self::new_impl(name, None, app_creator)
+    }
+
+    /// ウィンドウクライアントとして登録しない、独立したウィンドウを作成する。
+    ///
+    /// 出力プラグインの進捗表示など、汎用プラグインの外でウィンドウを表示する場合に使います。
+    /// ウィンドウの大きさやタイトル、最初に表示するかどうかは`viewport`で指定します。
+    ///
+    /// ユーザーがウィンドウを閉じた場合は非表示になるだけで、スレッドは`EframeWindow`が破棄されるまで動作し続けます。
+    /// [`Self::set_visible`]で再び表示できます。
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use aviutl2_eframe::{EframeWindow, eframe, egui};
+    /// # struct ProgressApp;
+    /// # impl eframe::App for ProgressApp {
+    /// #     fn ui(&mut self, _ui: &mut egui::Ui, _frame: &mut eframe::Frame) {}
+    /// # }
+    /// # fn f() -> aviutl2::AnyResult<()> {
+    /// let window = EframeWindow::new_standalone(
+    ///     "MyProgress",
+    ///     egui::ViewportBuilder::default()
+    ///         .with_title("Progress")
+    ///         .with_inner_size([320.0, 240.0])
+    ///         .with_visible(false),
+    ///     |_cc, _handle| Ok(Box::new(ProgressApp)),
+    /// )?;
+    /// // 出力の開始時に表示する
+    /// window.set_visible(true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_standalone<F>(
+        name: &str,
+        viewport: egui::ViewportBuilder,
+        app_creator: F,
+    ) -> AnyResult<Self>
+    where
+        F: 'static
+            + Send
+            + FnOnce(
+                &eframe::CreationContext<'_>,
+                AviUtl2EframeHandle,
+            )
+                -> Result<Box<dyn eframe::App>, Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Self::new_impl(name, Some(viewport), app_creator)
+    }
+
+    /// `standalone`が`Some`の場合は、そのビューポートで独立したウィンドウを作成する。
+    fn new_impl<F>(
+        name: &str,
+        standalone: Option<egui::ViewportBuilder>,
+        app_creator: F,
+    ) -> AnyResult<Self>
+    where
+        F: 'static
+            + Send
+            + FnOnce(
+                &eframe::CreationContext<'_>,
+                AviUtl2EframeHandle,
+            )
+                -> Result<Box<dyn eframe::App>, Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let (tx, rx) = mpsc::channel::<InitResult>();
         let name = name.to_string();
         let thread_terminator = std::sync::Arc::new(std::sync::OnceLock::new());
+        let focus = std::sync::Arc::new(focus::FocusState::default());
         let event_loop_proxy = std::sync::Arc::new(std::sync::OnceLock::new());
         let panic_message = std::sync::Arc::new(std::sync::OnceLock::<String>::new());
+        let app_error = std::sync::Arc::new(std::sync::OnceLock::<String>::new());
         let thread = std::thread::spawn({
+            let app_error = app_error.clone();
             let thread_terminator = thread_terminator.clone();
+            let focus = focus.clone();
             let event_loop_proxy = event_loop_proxy.clone();
             let panic_message = panic_message.clone();
             move || {
@@ -348,23 +476,39 @@ impl EframeWindow {
                         .unwrap_or_else(|| "<unknown location>".to_string());
                     panic_message.set(format!("{msg} (at {location})")).ok();
                 }));
-                let native_options = eframe::NativeOptions {
-                    viewport: egui::ViewportBuilder::default()
-                        .with_visible(false)
-                        .with_decorations(false)
-                        .with_taskbar(false)
-                        .with_icon(egui::IconData::default()),
-                    window_builder: Some(Box::new(|wb| {
-                        wb.with_visible(false).with_decorations(false)
-                    })),
-                    ..Default::default()
+                let is_standalone = standalone.is_some();
+                let native_options = match standalone {
+                    Some(viewport) => eframe::NativeOptions {
+                        viewport,
+                        ..Default::default()
+                    },
+                    None => eframe::NativeOptions {
+                        viewport: egui::ViewportBuilder::default()
+                            .with_visible(false)
+                            .with_decorations(false)
+                            .with_taskbar(false)
+                            .with_icon(egui::IconData::default()),
+                        window_builder: Some(Box::new(|wb| {
+                            wb.with_visible(false).with_decorations(false)
+                        })),
+                        ..Default::default()
+                    },
                 };
 
+                // イベントループは1つのDLLにつき一度しか作成できないので、失敗した場合は呼び出し側に返す
                 let event_loop =
-                    winit::event_loop::EventLoop::<eframe::UserEvent>::with_user_event()
+                    match winit::event_loop::EventLoop::<eframe::UserEvent>::with_user_event()
                         .with_any_thread(true)
                         .build()
-                        .unwrap();
+                    {
+                        Ok(event_loop) => event_loop,
+                        Err(e) => {
+                            tracing::error!("Failed to create event loop: {}", e);
+                            tx.send(Err(format!("Failed to create event loop: {e}").into()))
+                                .ok();
+                            return;
+                        }
+                    };
                 event_loop_proxy
                     .set(event_loop.create_proxy())
                     .expect("event loop proxy should only be initialized once");
@@ -373,67 +517,103 @@ impl EframeWindow {
                     &name,
                     native_options,
                     Box::new(|cc| {
-                        use winit::platform::windows::WindowExtWindows as _;
-                        let window = cc.winit_window().expect("should not be headless");
-                        // NOTE: これがないと上に1pxの影ができる
-                        window.set_undecorated_shadow(false);
-
-                        let raw_window_handle::RawWindowHandle::Win32(hwnd) = cc
-                            .window_handle()
-                            .expect("Failed to get window handle")
-                            .as_raw()
-                        else {
-                            unreachable!("Not a Win32 window handle");
-                        };
-                        unsafe {
-                            // Set window styles
-                            let hwnd = hwnd.hwnd.get() as _;
-
-                            SetLastError(windows::Win32::Foundation::WIN32_ERROR(0));
-                            let _ = ShowWindow(
-                                HWND(hwnd),
-                                windows::Win32::UI::WindowsAndMessaging::SW_HIDE,
-                            );
-                            if windows::Win32::Foundation::GetLastError().0 != 0 {
-                                let err = windows::core::Error::from_thread();
-                                return Err(anyhow::anyhow!("Failed to hide window: {}", err)
-                                    .into_boxed_dyn_error());
-                            }
+                        // 作成関数がパニックしたり失敗した場合も、呼び出し側に結果を送る
+                        let init = panic_guard::call_app_creator(|| {
+                            use winit::platform::windows::WindowExtWindows as _;
+                            let window = cc.winit_window().expect("should not be headless");
+                            // NOTE: これがないと上に1pxの影ができる
+                            window.set_undecorated_shadow(false);
 
-                            SetLastError(windows::Win32::Foundation::WIN32_ERROR(0));
-                            let res_style = SetWindowLongPtrW(
-                                HWND(hwnd),
-                                GWL_STYLE,
-                                (WS_CLIPSIBLINGS.0 | WS_POPUP.0) as isize,
-                            );
-                            if res_style == 0 && windows::Win32::Foundation::GetLastError().0 != 0 {
-                                let err = windows::core::Error::from_thread();
-                                return Err(anyhow::anyhow!("Failed to set window style: {}", err)
-                                    .into_boxed_dyn_error());
-                            }
+                            let raw_window_handle::RawWindowHandle::Win32(hwnd) = cc
+                                .window_handle()
+                                .expect("Failed to get window handle")
+                                .as_raw()
+                            else {
+                                unreachable!("Not a Win32 window handle");
+                            };
+                            // 独立したウィンドウは、ウィンドウクライアント用のスタイルにしない
+                            if !is_standalone {
+                                unsafe {
+                                    // Set window styles
+                                    let hwnd = hwnd.hwnd.get() as _;
+
+                                    SetLastError(windows::Win32::Foundation::WIN32_ERROR(0));
+                                    let _ = ShowWindow(
+                                        HWND(hwnd),
+                                        windows::Win32::UI::WindowsAndMessaging::SW_HIDE,
+                                    );
+                                    if windows::Win32::Foundation::GetLastError().0 != 0 {
+                                        let err = windows::core::Error::from_thread();
+                                        return Err(anyhow::anyhow!(
+                                            "Failed to hide window: {}",
+                                            err
+                                        )
+                                        .into_boxed_dyn_error());
+                                    }
+
+                                    SetLastError(windows::Win32::Foundation::WIN32_ERROR(0));
+                                    let res_style = SetWindowLongPtrW(
+                                        HWND(hwnd),
+                                        GWL_STYLE,
+                                        (WS_CLIPSIBLINGS.0 | WS_POPUP.0) as isize,
+                                    );
+                                    if res_style == 0
+                                        && windows::Win32::Foundation::GetLastError().0 != 0
+                                    {
+                                        let err = windows::core::Error::from_thread();
+                                        return Err(anyhow::anyhow!(
+                                            "Failed to set window style: {}",
+                                            err
+                                        )
+                                        .into_boxed_dyn_error());
+                                    }
 
-                            SetLastError(windows::Win32::Foundation::WIN32_ERROR(0));
-                            let res_exstyle = SetWindowLongPtrW(HWND(hwnd), GWL_EXSTYLE, 0);
-                            if res_exstyle == 0 && windows::Win32::Foundation::GetLastError().0 != 0
-                            {
-                                let err = windows::core::Error::from_thread();
-                                return Err(anyhow::anyhow!(
-                                    "Failed to set window exstyle: {}",
-                                    err
-                                )
-                                .into_boxed_dyn_error());
+                                    SetLastError(windows::Win32::Foundation::WIN32_ERROR(0));
+                                    let res_exstyle = SetWindowLongPtrW(HWND(hwnd), GWL_EXSTYLE, 0);
+                                    if res_exstyle == 0
+                                        && windows::Win32::Foundation::GetLastError().0 != 0
+                                    {
+                                        let err = windows::core::Error::from_thread();
+                                        return Err(anyhow::anyhow!(
+                                            "Failed to set window exstyle: {}",
+                                            err
+                                        )
+                                        .into_boxed_dyn_error());
+                                    }
+                                }
+                            }
+                            let dpi = dpi::install(HWND(hwnd.hwnd.get() as _), cc.egui_ctx.clone())
+                                .map_err(|e| e.into_boxed_dyn_error())?;
+                            focus::install(HWND(hwnd.hwnd.get() as _), focus.clone())
+                                .map_err(|e| e.into_boxed_dyn_error())?;
+                            // 最初のフレームから正しい大きさで描画されるように、先にスケールを合わせておく
+                            cc.egui_ctx.set_pixels_per_point(dpi.scale_factor());
+                            let app_handle = AviUtl2EframeHandle {
+                                hwnd: NonZeroIsize::new(hwnd.hwnd.get()).context("HWND is null")?,
+                            };
+                            let app = app_creator(cc, app_handle)?;
+                            Ok((app, hwnd.hwnd, dpi))
+                        });
+                        let (app, hwnd, dpi) = match init {
+                            Ok(init) => init,
+                            Err(e) => {
+                                tracing::error!("Failed to create Egui app: {}", e);
+                                tx.send(Err(e.to_string().into())).ok();
+                                return Err(e);
                             }
-                        }
-                        let app_handle = AviUtl2EframeHandle {
-                            hwnd: NonZeroIsize::new(hwnd.hwnd.get()).context("HWND is null")?,
                         };
-                        let app = app_creator(cc, app_handle)?;
-                        tx.send(Ok((hwnd.hwnd.get(), cc.egui_ctx.clone())))
+                        tx.send(Ok((hwnd.get(), cc.egui_ctx.clone(), dpi.clone())))
                             .context("Failed to send HWND")?;
-                        tracing::debug!("Egui app created, with HWND: 0x{:016x}", hwnd.hwnd);
+                        tracing::debug!("Egui app created, with HWND: 0x{:016x}", hwnd);
                         Ok(Box::new(WrappedApp {
-                            hwnd: NonZeroIsize::new(hwnd.hwnd.get()).context("HWND is null")?,
+                            hwnd,
                             internal_app: app,
+                            dpi,
+                            focus,
+                            applied_scale: None,
+                            guard: panic_guard::PanicGuard::default(),
+                            app_error,
+                            standalone: is_standalone,
                         }) as Box<dyn eframe::App>)
                     }),
                     &event_loop,
@@ -450,11 +630,15 @@ impl EframeWindow {
         Ok(Self {
             hwnd: std::sync::OnceLock::new(),
             egui_ctx: std::sync::OnceLock::new(),
+            dpi: std::sync::OnceLock::new(),
+            focus,
             init_rx: std::sync::Mutex::new(Some(rx)),
             thread: Some(thread),
             thread_terminator,
             event_loop_proxy,
             panic_message,
+            init_error: std::sync::OnceLock::new(),
+            app_error,
         })
     }
 
@@ -462,29 +646,72 @@ impl EframeWindow {
         if self.hwnd.get().is_some() {
             return Ok(());
         }
-        let rx = self.init_rx.lock().unwrap().take();
-        let Some(rx) = rx else {
-            while self.hwnd.get().is_none() {
-                std::thread::yield_now();
-            }
+        // 初期化を待っている間はロックを持ち続け、他の呼び出し元はロックの解放を待つ
+        let mut init_rx = self.init_rx.lock().unwrap();
+        if self.hwnd.get().is_some() {
             return Ok(());
+        }
+        let Some(rx) = init_rx.as_ref() else {
+            let error = self
+                .init_error
+                .get()
+                .map_or("<unknown error>", String::as_str);
+            anyhow::bail!("Failed to create Egui app: {}", error);
         };
-        let (hwnd, egui_ctx) = match rx.recv() {
+        let (hwnd, egui_ctx, dpi) = match rx.recv_timeout(INIT_TIMEOUT) {
             Ok(Ok(v)) => v,
-            Ok(Err(e)) => return Err(anyhow::anyhow!("Failed to create Egui app: {}", e)),
-            Err(e) => {
-                return Err(anyhow::anyhow!(
-                    "Failed to receive init data from Egui thread: {}",
-                    e
-                ));
+            Ok(Err(e)) => {
+                self.init_error.set(e.to_string()).ok();
+                *init_rx = None;
+                anyhow::bail!("Failed to create Egui app: {}", e);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // まだ初期化中かもしれないので、受信側は残しておく
+                anyhow::bail!(
+                    "Timed out waiting for Egui window initialization ({:?})",
+                    INIT_TIMEOUT
+                );
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                let error = match self.panic_message.get() {
+                    Some(message) => format!("Egui thread panicked: {message}"),
+                    None => "Egui thread exited before initialization".to_string(),
+                };
+                self.init_error.set(error.clone()).ok();
+                *init_rx = None;
+                anyhow::bail!("Failed to create Egui app: {}", error);
             }
         };
+        *init_rx = None;
         let hwnd = NonZeroIsize::new(hwnd).context("Received null HWND from Egui thread")?;
         self.hwnd.set(hwnd).ok();
         self.egui_ctx.set(egui_ctx).ok();
+        self.dpi.set(dpi).ok();
         Ok(())
     }
 
+    /// ウィンドウのスレッドが動作しているかどうかを返す。
+    ///
+    /// アプリの作成に失敗した場合や、スレッドが終了した場合は`false`を返します。
+    /// アプリの`ui`などがパニックした場合はスレッドは動作し続けるので、[`Self::app_error`]を確認してください。
+    pub fn is_alive(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+
+    /// ウィンドウのスレッドのハンドルを取得する。
+    pub fn join_handle(&self) -> &std::thread::JoinHandle<()> {
+        self.thread.as_ref().expect("thread is only taken on drop")
+    }
+
+    /// アプリがパニックした場合、そのメッセージを返す。
+    ///
+    /// パニックしたアプリはそれ以降呼び出されず、ウィンドウにはエラーが表示されます。
+    pub fn app_error(&self) -> Option<String> {
+        self.app_error.get().cloned()
+    }
+
     /// ウィンドウハンドルを取得する。
     ///
     /// 初回呼び出し時にウィンドウの初期化が完了するまでブロックします。
@@ -505,6 +732,76 @@ impl EframeWindow {
             .expect("egui_ctx set after resolve_init")
             .clone())
     }
+
+    /// ウィンドウの現在のスケール（拡大率100%のときに1.0）を取得する。
+    ///
+    /// ウィンドウが別のモニターに移動した場合も追従します。
+    /// 初回呼び出し時にウィンドウの初期化が完了するまでブロックします。
+    pub fn current_scale_factor(&self) -> AnyResult<f32> {
+        self.resolve_init()?;
+        Ok(self
+            .dpi
+            .get()
+            .expect("dpi set after resolve_init")
+            .scale_factor())
+    }
+
+    /// ウィンドウの表示・非表示を切り替える。
+    ///
+    /// どのスレッドからも呼び出せます。主に[`Self::new_standalone`]で作成したウィンドウに使います。
+    /// 初回呼び出し時にウィンドウの初期化が完了するまでブロックします。
+    pub fn set_visible(&self, visible: bool) -> AnyResult<()> {
+        let ctx = self.egui_ctx()?;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Visible(visible));
+        if visible {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+        ctx.request_repaint();
+        Ok(())
+    }
+
+    /// ウィンドウにキーボードフォーカスを移す。
+    ///
+    /// どのスレッドからも呼び出せます。
+    /// 特定のウィジェットにフォーカスを移したい場合は、アプリ側で`egui::Response::request_focus`を呼んでください。
+    /// 初回呼び出し時にウィンドウの初期化が完了するまでブロックします。
+    pub fn focus(&self) -> AnyResult<()> {
+        self.resolve_init()?;
+        let hwnd = *self.hwnd.get().expect("hwnd set after resolve_init");
+        unsafe {
+            windows::Win32::UI::WindowsAndMessaging::PostMessageW(
+                Some(HWND(hwnd.get() as *mut std::ffi::c_void)),
+                focus::WM_REQUEST_FOCUS,
+                windows::Win32::Foundation::WPARAM(0),
+                windows::Win32::Foundation::LPARAM(0),
+            )?;
+        }
+        if let Some(ctx) = self.egui_ctx.get() {
+            ctx.request_repaint();
+        }
+        Ok(())
+    }
+
+    /// eguiのウィジェットがキーボード入力を受け取っている間も、AviUtl2に送るキーを設定する。
+    ///
+    /// eguiのウィジェット（テキストボックスなど）にフォーカスがある間は、
+    /// Tabや矢印キー、文字キーはeguiが受け取り、AviUtl2のショートカットは発火しません。
+    /// ここで指定したキーだけは、フォーカスがある間もAviUtl2に送られます。
+    /// フォーカスが外れている間は、`transparent_keyboard_input`フィーチャーが有効ならすべてのキーが送られます。
+    ///
+    /// デフォルトでは空です。
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn f(window: &aviutl2_eframe::EframeWindow) {
+    /// // F5はテキストを入力している間もAviUtl2に送る
+    /// window.set_shortcut_passthrough(&[aviutl2_eframe::VirtualKey(0x74)]);
+    /// # }
+    /// ```
+    pub fn set_shortcut_passthrough(&self, keys: &[VirtualKey]) {
+        self.focus.set_passthrough(keys);
+    }
 }
 
 /// aviutl2-eframeでウィンドウ内から呼び出される関数のハンドル。
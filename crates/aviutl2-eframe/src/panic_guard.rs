@@ -0,0 +1,96 @@
+use aviutl2::tracing;
+
+/// パニックのペイロードからメッセージを取り出す。
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "<unknown panic>".to_string()
+    }
+}
+
+/// `f`を呼び出し、パニックした場合はそのメッセージをエラーとして返す。
+pub(crate) fn catch_panic<R>(f: impl FnOnce() -> R) -> Result<R, String> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+        .map_err(|payload| panic_message(payload.as_ref()))
+}
+
+/// アプリの作成関数を呼び出す。
+///
+/// 作成関数がパニックした場合も、エラーとして返す。
+pub(crate) fn call_app_creator<T>(
+    creator: impl FnOnce() -> Result<T, Box<dyn std::error::Error + Send + Sync>>,
+) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+    catch_panic(creator).map_err(|message| format!("App creator panicked: {message}"))?
+}
+
+/// アプリの処理でパニックが起きたかを記録する。
+///
+/// 一度パニックが起きたアプリは状態が壊れている可能性があるので、以降は呼び出さない。
+#[derive(Debug, Default)]
+pub(crate) struct PanicGuard {
+    error: Option<String>,
+}
+
+impl PanicGuard {
+    /// パニックしたときのメッセージを返す。
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// `f`を呼び出す。
+    ///
+    /// `f`がパニックした場合や、以前にパニックしている場合は`None`を返す。
+    pub fn run<R>(&mut self, name: &str, f: impl FnOnce() -> R) -> Option<R> {
+        if self.error.is_some() {
+            return None;
+        }
+        match catch_panic(f) {
+            Ok(result) => Some(result),
+            Err(message) => {
+                tracing::error!("Egui app panicked in {name}: {message}");
+                self.error = Some(message);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panicking_creator_returns_error() {
+        let result = call_app_creator::<()>(|| panic!("creator failed"));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "App creator panicked: creator failed"
+        );
+
+        let result = call_app_creator::<()>(|| Err("not supported".into()));
+        assert_eq!(result.unwrap_err().to_string(), "not supported");
+
+        assert_eq!(call_app_creator(|| Ok(1)).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_panicking_update_stops_calling_app() {
+        let mut guard = PanicGuard::default();
+        let mut calls = 0;
+        assert_eq!(guard.run("ui", || calls += 1), Some(()));
+
+        let frame = 3;
+        assert_eq!(
+            guard.run::<()>("ui", || panic!("update failed at frame {frame}")),
+            None
+        );
+        assert_eq!(guard.error(), Some("update failed at frame 3"));
+
+        // 以降は呼び出さずにエラー表示にする
+        assert_eq!(guard.run("ui", || calls += 1), None);
+        assert_eq!(calls, 1);
+    }
+}
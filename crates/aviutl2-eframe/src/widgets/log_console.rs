@@ -0,0 +1,256 @@
+use aviutl2::logger::{DEFAULT_RECENT_CAPACITY, LogFilter, LogLevel, LogRecord};
+use eframe::egui;
+use std::collections::VecDeque;
+
+/// [`aviutl2::logger::recent`]に記録されたログを表示するウィジェット。
+///
+/// レベルでの絞り込み、文字列での検索、表示中のログのクリップボードへのコピーができます。
+/// 新しいログは表示するたびに取得するので、ウィンドウを開いたままにしておけばログコンソールとして使えます。
+///
+/// ```rust,no_run
+/// use aviutl2_eframe::{eframe, egui, widgets::LogConsole};
+///
+/// struct MyApp {
+///     log_console: LogConsole,
+/// }
+///
+/// impl eframe::App for MyApp {
+///     fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+///         egui::CentralPanel::default().show(ui, |ui| {
+///             self.log_console.ui(ui);
+///         });
+///     }
+/// }
+/// ```
+pub struct LogConsole {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+    next_seq: u64,
+    filter: LogFilter,
+    /// `filter`に一致する`records`の添字。`None`の場合は次の表示時に計算し直す。
+    visible: Option<Vec<usize>>,
+}
+
+impl Default for LogConsole {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogConsole {
+    /// 最大[`DEFAULT_RECENT_CAPACITY`]件のログを表示する`LogConsole`を作成します。
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_RECENT_CAPACITY)
+    }
+
+    /// 最大`capacity`件のログを表示する`LogConsole`を作成します。
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::new(),
+            capacity,
+            next_seq: 0,
+            filter: LogFilter::new(),
+            visible: None,
+        }
+    }
+
+    /// 現在の絞り込み条件を返します。
+    pub fn filter(&self) -> &LogFilter {
+        &self.filter
+    }
+
+    /// 絞り込み条件を変更します。
+    pub fn filter_mut(&mut self) -> &mut LogFilter {
+        self.visible = None;
+        &mut self.filter
+    }
+
+    /// 表示しているログを消去します。
+    ///
+    /// [`aviutl2::logger::recent`]に記録されたログは消えません。
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.visible = None;
+    }
+
+    /// ログコンソールを表示します。
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        self.poll();
+        self.render_toolbar(ui);
+        ui.separator();
+        self.render_records(ui);
+        // 新しいログが書き込まれたときに表示されるよう、定期的に再描画する
+        ui.request_repaint_after(std::time::Duration::from_millis(250));
+    }
+
+    fn poll(&mut self) {
+        let new_records = aviutl2::logger::recent_since(self.next_seq);
+        let Some(last) = new_records.last() else {
+            return;
+        };
+        self.next_seq = last.seq + 1;
+        self.records.extend(new_records);
+        while self.records.len() > self.capacity {
+            self.records.pop_front();
+        }
+        self.visible = None;
+    }
+
+    fn visible(&mut self) -> &[usize] {
+        let records = &self.records;
+        let filter = &self.filter;
+        self.visible.get_or_insert_with(|| {
+            records
+                .iter()
+                .enumerate()
+                .filter(|(_, record)| filter.matches(record))
+                .map(|(i, _)| i)
+                .collect()
+        })
+    }
+
+    fn visible_records(&mut self) -> impl Iterator<Item = &LogRecord> {
+        self.visible();
+        self.visible
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|&i| &self.records[i])
+    }
+
+    fn render_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            for level in LogLevel::ALL {
+                let mut shown = self.filter.is_level_shown(level);
+                let label = egui::RichText::new(level.as_str()).color(level_color(ui, level));
+                if ui.checkbox(&mut shown, label).changed() {
+                    self.filter_mut().set_level_shown(level, shown);
+                }
+            }
+            ui.separator();
+            let search = egui::TextEdit::singleline(&mut self.filter.query)
+                .hint_text("Search...")
+                .desired_width(160.0);
+            if ui.add(search).changed() {
+                self.visible = None;
+            }
+            if ui.button("Copy").clicked() {
+                let text = self
+                    .visible_records()
+                    .map(format_record)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ui.ctx().copy_text(text);
+            }
+            if ui.button("Clear").clicked() {
+                self.clear();
+            }
+            let dropped = aviutl2::logger::dropped_record_count();
+            if dropped > 0 {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("{dropped} records dropped"),
+                );
+            }
+        });
+    }
+
+    fn render_records(&mut self, ui: &mut egui::Ui) {
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let visible_count = self.visible().len();
+        egui::ScrollArea::vertical()
+            .auto_shrink(false)
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, visible_count, |ui, range| {
+                let visible = self.visible.as_deref().unwrap_or_default();
+                for &i in &visible[range] {
+                    let record = &self.records[i];
+                    let text = egui::RichText::new(format_record(record))
+                        .monospace()
+                        .color(level_color(ui, record.level));
+                    ui.add(egui::Label::new(text).truncate())
+                        .on_hover_text(&record.message);
+                }
+            });
+    }
+}
+
+fn level_color(ui: &egui::Ui, level: LogLevel) -> egui::Color32 {
+    let visuals = ui.visuals();
+    match level {
+        LogLevel::Error => visuals.error_fg_color,
+        LogLevel::Warn => visuals.warn_fg_color,
+        LogLevel::Info | LogLevel::Plugin => visuals.text_color(),
+        LogLevel::Verbose => visuals.weak_text_color(),
+    }
+}
+
+/// クリップボードにコピーするときの形式でログを文字列にする。
+fn format_record(record: &LogRecord) -> String {
+    let time = format_time(record.time);
+    if record.target.is_empty() {
+        format!("[{time}] [{}] {}", record.level, record.message)
+    } else {
+        format!(
+            "[{time}] [{}] [{}] {}",
+            record.level, record.target, record.message
+        )
+    }
+}
+
+/// 時刻をローカル時刻の`HH:MM:SS.mmm`の形式にする。
+fn format_time(time: std::time::SystemTime) -> String {
+    use windows::Win32::{
+        Foundation::{FILETIME, SYSTEMTIME},
+        System::Time::{FileTimeToSystemTime, SystemTimeToTzSpecificLocalTime},
+    };
+
+    // 1601-01-01から1970-01-01までの100ナノ秒単位の時間
+    const UNIX_EPOCH_IN_FILETIME: u64 = 116_444_736_000_000_000;
+    let since_epoch = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let ticks = UNIX_EPOCH_IN_FILETIME + (since_epoch.as_nanos() / 100) as u64;
+    let file_time = FILETIME {
+        dwLowDateTime: ticks as u32,
+        dwHighDateTime: (ticks >> 32) as u32,
+    };
+    let mut utc = SYSTEMTIME::default();
+    let mut local = SYSTEMTIME::default();
+    let converted = unsafe {
+        FileTimeToSystemTime(&file_time, &mut utc)
+            .and_then(|_| SystemTimeToTzSpecificLocalTime(None, &utc, &mut local))
+    };
+    if converted.is_err() {
+        return "--:--:--.---".to_string();
+    }
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        local.wHour, local.wMinute, local.wSecond, local.wMilliseconds
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_console_smoke() {
+        aviutl2::logger::write_error_log("log console smoke test").unwrap();
+        aviutl2::logger::write_info_log("log console smoke test (info)").unwrap();
+
+        let ctx = egui::Context::default();
+        let mut console = LogConsole::new();
+        console.filter_mut().query = "smoke test".to_string();
+        console.filter_mut().set_level_shown(LogLevel::Info, false);
+        for _ in 0..2 {
+            let _ = ctx.run_ui(egui::RawInput::default(), |ui| console.ui(ui));
+        }
+
+        let messages = console
+            .visible_records()
+            .map(|record| record.message.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["log console smoke test"]);
+    }
+}
@@ -0,0 +1,5 @@
+//! プラグインのUIでそのまま使えるウィジェット。
+
+mod log_console;
+
+pub use log_console::LogConsole;
@@ -0,0 +1,55 @@
+use crate::PluginDll;
+use std::path::PathBuf;
+
+/// ワークスペースのルート。
+fn workspace_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../..")
+        .canonicalize()
+        .expect("Failed to resolve the workspace root")
+}
+
+/// ビルド結果が置かれるディレクトリ。`CARGO_TARGET_DIR`が設定されている場合はそれを使う。
+fn target_dir() -> PathBuf {
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| workspace_root().join("target"))
+}
+
+/// ワークスペースのサンプルプラグインをデバッグビルドし、DLLのパスを返す。
+///
+/// # Arguments
+///
+/// - `package`：パッケージ名（例：`example-pixel-format-test-input`）。
+/// - `lib_name`：`[lib]`の`name`（例：`rusty_pixel_format_test_input`）。
+pub fn build_example(package: &str, lib_name: &str) -> anyhow::Result<PathBuf> {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let status = std::process::Command::new(cargo)
+        .args(["build", "--package", package])
+        .current_dir(workspace_root())
+        .status()?;
+    anyhow::ensure!(status.success(), "Failed to build {package}: {status}");
+
+    let path = target_dir().join("debug").join(format!("{lib_name}.dll"));
+    anyhow::ensure!(path.exists(), "{} was not found", path.display());
+    Ok(path)
+}
+
+/// サンプルプラグインをビルドして読み込む。
+///
+/// プラグインはプロセス内で一度しか初期化できないことが多いため、読み込んだDLLはプロセスの終了まで保持し、
+/// 同じパッケージに対しては同じものを返します。
+pub fn load_example(package: &str, lib_name: &str) -> anyhow::Result<&'static PluginDll> {
+    static LOADED: std::sync::Mutex<Vec<(String, &'static PluginDll)>> =
+        std::sync::Mutex::new(Vec::new());
+
+    let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, dll)) = loaded.iter().find(|(name, _)| name == package) {
+        return Ok(dll);
+    }
+    let dll: &'static PluginDll = Box::leak(Box::new(PluginDll::load(build_example(
+        package, lib_name,
+    )?)?));
+    loaded.push((package.to_string(), dll));
+    Ok(dll)
+}
@@ -0,0 +1,229 @@
+use crate::{PluginDll, load_wide};
+use aviutl2_sys::filter2::{
+    FILTER_PLUGIN_TABLE, FILTER_PROC_VIDEO, OBJECT_IMAGE_PARAM, OBJECT_INFO, PIXEL_RGBA, SCENE_INFO,
+};
+
+/// フィルタに渡すオブジェクトの画像。RGBA（8bit）で、左上から順に並びます。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageSim {
+    pub width: i32,
+    pub height: i32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+/// フィルタ処理中の画像。ホストの関数にはユーザーデータを渡せないため、グローバルに持つ。
+static FILTER_IMAGE: std::sync::Mutex<Option<ImageSim>> = std::sync::Mutex::new(None);
+/// フィルタ処理は同時に1つしか行えないため、処理中はこれをロックする。
+static FILTER_SESSION: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+unsafe extern "C" fn get_image_data(buffer: *mut PIXEL_RGBA) {
+    let image = FILTER_IMAGE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(image) = image.as_ref() else {
+        return;
+    };
+    unsafe {
+        std::ptr::copy_nonoverlapping(
+            image.pixels.as_ptr().cast::<PIXEL_RGBA>(),
+            buffer,
+            image.pixels.len(),
+        );
+    }
+}
+
+unsafe extern "C" fn set_image_data(buffer: *const PIXEL_RGBA, width: i32, height: i32) {
+    let len = (width.max(0) * height.max(0)) as usize;
+    let pixels = unsafe { std::slice::from_raw_parts(buffer.cast::<[u8; 4]>(), len) }.to_vec();
+    *FILTER_IMAGE.lock().unwrap_or_else(|e| e.into_inner()) = Some(ImageSim {
+        width,
+        height,
+        pixels,
+    });
+}
+
+/// フィルタプラグインを呼び出すシミュレーター。
+pub struct FilterPluginSim<'a> {
+    table: &'a FILTER_PLUGIN_TABLE,
+}
+
+impl std::fmt::Debug for FilterPluginSim<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilterPluginSim")
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> FilterPluginSim<'a> {
+    /// `GetFilterPluginTable`を呼び出す。
+    pub fn new(dll: &'a PluginDll) -> anyhow::Result<Self> {
+        let table = unsafe { dll.plugin_table::<FILTER_PLUGIN_TABLE>(b"GetFilterPluginTable\0")? };
+        Ok(Self {
+            table: unsafe { &*table },
+        })
+    }
+
+    /// 汎用プラグインが登録したプラグインの情報から作成する。
+    ///
+    /// # See Also
+    ///
+    /// - [`crate::GenericPluginSim`]
+    pub fn from_table(table: &'a FILTER_PLUGIN_TABLE) -> Self {
+        Self { table }
+    }
+
+    /// プラグインの情報を返す。
+    pub fn table(&self) -> &'a FILTER_PLUGIN_TABLE {
+        self.table
+    }
+
+    /// プラグインの名前を返す。
+    pub fn name(&self) -> Option<String> {
+        unsafe { load_wide(self.table.name) }
+    }
+
+    /// `func_proc_video`で画像を処理する。
+    ///
+    /// 設定項目は初期値のまま処理され、処理後の画像と戻り値を返します。
+    ///
+    /// # Note
+    ///
+    /// `get_image_data`と`set_image_data`以外のホストの関数は提供していません。
+    /// それ以外の関数を呼ぶフィルタは処理できません。
+    pub fn proc_video(&self, image: ImageSim) -> anyhow::Result<(bool, ImageSim)> {
+        let func_proc_video = self
+            .table
+            .func_proc_video
+            .ok_or_else(|| anyhow::anyhow!("func_proc_video is null"))?;
+        anyhow::ensure!(
+            image.pixels.len() == (image.width * image.height) as usize,
+            "the image has {} pixels, but the size is {}x{}",
+            image.pixels.len(),
+            image.width,
+            image.height
+        );
+        let _session = FILTER_SESSION.lock().unwrap_or_else(|e| e.into_inner());
+
+        let scene = SCENE_INFO {
+            width: 1920,
+            height: 1080,
+            rate: 30,
+            scale: 1,
+            sample_rate: 48000,
+        };
+        let object = OBJECT_INFO {
+            id: 1,
+            frame: 0,
+            frame_total: 1,
+            time: 0.0,
+            time_total: 1.0 / 30.0,
+            width: image.width,
+            height: image.height,
+            sample_index: 0,
+            sample_total: 0,
+            sample_num: 0,
+            channel_num: 0,
+            effect_id: 1,
+            flag: 0,
+            layer: 0,
+            index: 0,
+            num: 1,
+            frame_s: 0,
+            frame_e: 0,
+        };
+        let mut param = OBJECT_IMAGE_PARAM {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            rx: 0.0,
+            ry: 0.0,
+            rz: 0.0,
+            sx: 1.0,
+            sy: 1.0,
+            sz: 1.0,
+            cx: 0.0,
+            cy: 0.0,
+            cz: 0.0,
+            alpha: 1.0,
+        };
+        *FILTER_IMAGE.lock().unwrap_or_else(|e| e.into_inner()) = Some(image);
+
+        // 画像の入出力以外の関数はシミュレートしないので、ゼロのままにする
+        let mut video = std::mem::MaybeUninit::<FILTER_PROC_VIDEO>::zeroed();
+        let ptr = video.as_mut_ptr();
+        unsafe {
+            std::ptr::addr_of_mut!((*ptr).scene).write(&raw const scene);
+            std::ptr::addr_of_mut!((*ptr).object).write(&raw const object);
+            std::ptr::addr_of_mut!((*ptr).get_image_data).write(get_image_data);
+            std::ptr::addr_of_mut!((*ptr).set_image_data).write(set_image_data);
+            std::ptr::addr_of_mut!((*ptr).edit).write(std::ptr::null_mut());
+            std::ptr::addr_of_mut!((*ptr).param).write(&raw mut param);
+        }
+        let result = func_proc_video(ptr);
+        let image = FILTER_IMAGE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .expect("image was removed during func_proc_video");
+        Ok((result, image))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixelsort_filter() -> &'static PluginDll {
+        crate::load_example("example-pixelsort-filter", "rusty_pixelsort_filter").unwrap()
+    }
+
+    fn gray(value: u8) -> [u8; 4] {
+        [value, value, value, 255]
+    }
+
+    #[test]
+    fn test_pixelsort_filter_table() {
+        let plugin = FilterPluginSim::new(pixelsort_filter()).unwrap();
+        assert_eq!(plugin.name().as_deref(), Some("Rusty Pixel Sort Filter"));
+        assert_ne!(plugin.table().flag & FILTER_PLUGIN_TABLE::FLAG_VIDEO, 0);
+        assert_ne!(plugin.table().flag & FILTER_PLUGIN_TABLE::FLAG_FILTER, 0);
+    }
+
+    #[test]
+    fn test_pixelsort_filter_sorts_rows() {
+        let plugin = FilterPluginSim::new(pixelsort_filter()).unwrap();
+        // 初期設定はしきい値0.5以上を左右にソートする。100はしきい値未満なので動かない。
+        let image = ImageSim {
+            width: 4,
+            height: 2,
+            pixels: vec![
+                gray(250),
+                gray(200),
+                gray(150),
+                gray(100),
+                gray(100),
+                gray(220),
+                gray(180),
+                gray(140),
+            ],
+        };
+        let (result, image) = plugin.proc_video(image).unwrap();
+        assert!(result);
+        assert_eq!(
+            image,
+            ImageSim {
+                width: 4,
+                height: 2,
+                pixels: vec![
+                    gray(150),
+                    gray(200),
+                    gray(250),
+                    gray(100),
+                    gray(100),
+                    gray(140),
+                    gray(180),
+                    gray(220),
+                ],
+            }
+        );
+    }
+}
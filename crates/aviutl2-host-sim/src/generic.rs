@@ -0,0 +1,242 @@
+use crate::{FilterPluginSim, InputPluginSim, OutputPluginSim, PluginDll, load_wide};
+use aviutl2_sys::filter2::FILTER_PLUGIN_TABLE;
+use aviutl2_sys::input2::INPUT_PLUGIN_TABLE;
+use aviutl2_sys::module2::SCRIPT_MODULE_TABLE;
+use aviutl2_sys::output2::OUTPUT_PLUGIN_TABLE;
+use aviutl2_sys::plugin2::{
+    EDIT_SECTION, EVENT_TYPE, HINSTANCE, HOST_APP_TABLE, HWND, PROJECT_FILE,
+};
+
+/// `RegisterPlugin`で登録されたもの。
+#[derive(Default)]
+struct Registered {
+    input_plugins: Vec<*mut INPUT_PLUGIN_TABLE>,
+    output_plugins: Vec<*mut OUTPUT_PLUGIN_TABLE>,
+    filter_plugins: Vec<*mut FILTER_PLUGIN_TABLE>,
+    script_modules: Vec<(*mut SCRIPT_MODULE_TABLE, Option<String>)>,
+}
+
+thread_local! {
+    /// 登録中のもの。ホストの関数にはユーザーデータを渡せないため、スレッドごとに持つ。
+    static REGISTERED: std::cell::RefCell<Registered> = std::cell::RefCell::default();
+}
+
+unsafe extern "C" fn register_input_plugin(table: *mut INPUT_PLUGIN_TABLE) {
+    REGISTERED.with_borrow_mut(|registered| registered.input_plugins.push(table));
+}
+
+unsafe extern "C" fn register_output_plugin(table: *mut OUTPUT_PLUGIN_TABLE) {
+    REGISTERED.with_borrow_mut(|registered| registered.output_plugins.push(table));
+}
+
+unsafe extern "C" fn register_filter_plugin(table: *mut FILTER_PLUGIN_TABLE) {
+    REGISTERED.with_borrow_mut(|registered| registered.filter_plugins.push(table));
+}
+
+unsafe extern "C" fn register_script_module(table: *mut SCRIPT_MODULE_TABLE) {
+    REGISTERED.with_borrow_mut(|registered| registered.script_modules.push((table, None)));
+}
+
+unsafe extern "C" fn register_script_module_name(
+    table: *mut SCRIPT_MODULE_TABLE,
+    name: *const u16,
+) {
+    let name = unsafe { load_wide(name) };
+    REGISTERED.with_borrow_mut(|registered| registered.script_modules.push((table, name)));
+}
+
+unsafe extern "C" fn register_project_handler(_: unsafe extern "C" fn(*mut PROJECT_FILE)) {}
+
+unsafe extern "C" fn register_edit_section_handler(_: unsafe extern "C" fn(*mut EDIT_SECTION)) {}
+
+unsafe extern "C" fn register_config_menu(
+    _name: *const u16,
+    _func_config: unsafe extern "C" fn(HWND, HINSTANCE),
+) {
+}
+
+unsafe extern "C" fn register_event_listener(
+    _event_type: EVENT_TYPE,
+    _param: *mut std::ffi::c_void,
+    _func: unsafe extern "C" fn(*mut std::ffi::c_void),
+) {
+}
+
+/// 汎用プラグインを呼び出すシミュレーター。
+///
+/// `RegisterPlugin`で登録された入力・出力・フィルタプラグインを、それぞれのシミュレーターで呼び出せます。
+pub struct GenericPluginSim<'a> {
+    input_plugins: Vec<&'a INPUT_PLUGIN_TABLE>,
+    output_plugins: Vec<&'a OUTPUT_PLUGIN_TABLE>,
+    filter_plugins: Vec<&'a FILTER_PLUGIN_TABLE>,
+    script_modules: Vec<(&'a SCRIPT_MODULE_TABLE, Option<String>)>,
+}
+
+impl std::fmt::Debug for GenericPluginSim<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GenericPluginSim")
+            .field("input_plugins", &self.input_plugins())
+            .field("output_plugins", &self.output_plugins())
+            .field("filter_plugins", &self.filter_plugins())
+            .field(
+                "script_modules",
+                &self
+                    .script_modules
+                    .iter()
+                    .map(|(_, name)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<'a> GenericPluginSim<'a> {
+    /// `RegisterPlugin`を呼び出し、登録されたプラグインを記録する。
+    ///
+    /// # Note
+    ///
+    /// - `RegisterPlugin`は1つのDLLにつき一度しか呼ばれない前提のため、同じDLLに対して複数回呼び出さないでください。
+    /// - プラグインとスクリプトモジュールの登録、プロジェクトとキャッシュのハンドラ、設定メニュー、
+    ///   イベントリスナー以外のホストの関数は提供していません。登録されたハンドラは呼び出されません。
+    pub fn register(dll: &'a PluginDll) -> anyhow::Result<Self> {
+        let register_plugin = unsafe {
+            dll.symbol::<unsafe extern "C" fn(*mut HOST_APP_TABLE)>(b"RegisterPlugin\0")?
+        };
+
+        // 上のNoteに挙げた登録関数だけを用意する
+        let mut host = Box::new(std::mem::MaybeUninit::<HOST_APP_TABLE>::zeroed());
+        let ptr = host.as_mut_ptr();
+        unsafe {
+            std::ptr::addr_of_mut!((*ptr).register_input_plugin).write(register_input_plugin);
+            std::ptr::addr_of_mut!((*ptr).register_output_plugin).write(register_output_plugin);
+            std::ptr::addr_of_mut!((*ptr).register_filter_plugin).write(register_filter_plugin);
+            std::ptr::addr_of_mut!((*ptr).register_script_module).write(register_script_module);
+            std::ptr::addr_of_mut!((*ptr).register_script_module_name)
+                .write(register_script_module_name);
+            std::ptr::addr_of_mut!((*ptr).register_project_load_handler)
+                .write(register_project_handler);
+            std::ptr::addr_of_mut!((*ptr).register_project_save_handler)
+                .write(register_project_handler);
+            std::ptr::addr_of_mut!((*ptr).register_clear_cache_handler)
+                .write(register_edit_section_handler);
+            std::ptr::addr_of_mut!((*ptr).register_change_scene_handler)
+                .write(register_edit_section_handler);
+            std::ptr::addr_of_mut!((*ptr).register_config_menu).write(register_config_menu);
+            std::ptr::addr_of_mut!((*ptr).register_event_listener).write(register_event_listener);
+        }
+
+        REGISTERED.take();
+        unsafe { register_plugin(ptr) };
+        let registered = REGISTERED.take();
+        Ok(Self {
+            input_plugins: to_refs(registered.input_plugins)?,
+            output_plugins: to_refs(registered.output_plugins)?,
+            filter_plugins: to_refs(registered.filter_plugins)?,
+            script_modules: registered
+                .script_modules
+                .into_iter()
+                .map(|(table, name)| Ok((to_ref(table)?, name)))
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+
+    /// 登録された入力プラグインを、登録された順に返す。
+    pub fn input_plugins(&self) -> Vec<InputPluginSim<'a>> {
+        self.input_plugins
+            .iter()
+            .map(|table| InputPluginSim::from_table(table))
+            .collect()
+    }
+
+    /// 登録された出力プラグインを、登録された順に返す。
+    pub fn output_plugins(&self) -> Vec<OutputPluginSim<'a>> {
+        self.output_plugins
+            .iter()
+            .map(|table| OutputPluginSim::from_table(table))
+            .collect()
+    }
+
+    /// 登録されたフィルタプラグインを、ホストに登録された順に返す。
+    pub fn filter_plugins(&self) -> Vec<FilterPluginSim<'a>> {
+        self.filter_plugins
+            .iter()
+            .map(|table| FilterPluginSim::from_table(table))
+            .collect()
+    }
+
+    /// 登録されたスクリプトモジュールと、指定されたモジュール名を返す。
+    pub fn script_modules(&self) -> &[(&'a SCRIPT_MODULE_TABLE, Option<String>)] {
+        &self.script_modules
+    }
+}
+
+fn to_ref<'a, T>(table: *mut T) -> anyhow::Result<&'a T> {
+    anyhow::ensure!(!table.is_null(), "RegisterPlugin registered a null table");
+    Ok(unsafe { &*table })
+}
+
+fn to_refs<'a, T>(tables: Vec<*mut T>) -> anyhow::Result<Vec<&'a T>> {
+    tables.into_iter().map(to_ref).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ImageSim, VideoFormatSim};
+
+    #[test]
+    fn test_palette_cache_plugin_shares_cache() {
+        let dll = crate::load_example("example-palette-cache-plugin", "rusty_palette_cache_plugin")
+            .unwrap();
+        let plugin = GenericPluginSim::register(dll).unwrap();
+        let inputs = plugin.input_plugins();
+        let filters = plugin.filter_plugins();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(filters.len(), 1);
+        assert!(plugin.output_plugins().is_empty());
+        assert!(plugin.script_modules().is_empty());
+        assert_eq!(inputs[0].name().as_deref(), Some("Rusty Palette Input"));
+        assert_eq!(filters[0].name().as_deref(), Some("Rusty Palette Quantize"));
+
+        let image = ImageSim {
+            width: 2,
+            height: 1,
+            pixels: vec![[200, 40, 30, 255], [100, 100, 100, 128]],
+        };
+        // パレットを読み込む前は何もしない
+        let (result, processed) = filters[0].proc_video(image.clone()).unwrap();
+        assert!(result);
+        assert_eq!(processed, image);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("三色.palette");
+        std::fs::write(&path, "; test\n#000000\n#FFFFFF\n#FF0000\n").unwrap();
+        let file = inputs[0].open(&path).unwrap();
+        assert_eq!(
+            file.video_format().unwrap(),
+            Some(VideoFormatSim {
+                rate: 30,
+                scale: 1,
+                num_frames: 1,
+                width: 96,
+                height: 32,
+                bit_count: 32,
+                compression: aviutl2_sys::common::BI_RGB,
+                size_image: 96 * 32 * 4,
+            })
+        );
+        let data = file.read_video(0).unwrap();
+        for (x, bgra) in [
+            (0, [0, 0, 0, 255]),
+            (40, [255, 255, 255, 255]),
+            (95, [0, 0, 255, 255]),
+        ] {
+            assert_eq!(data[x * 4..x * 4 + 4], bgra, "x = {x}");
+        }
+
+        // 入力プラグインが読み込んだパレットをフィルタが使う
+        let (result, processed) = filters[0].proc_video(image).unwrap();
+        assert!(result);
+        assert_eq!(processed.pixels, vec![[255, 0, 0, 255], [0, 0, 0, 128]]);
+    }
+}
@@ -0,0 +1,431 @@
+use crate::{PluginDll, load_file_filters, load_wide};
+use aviutl2_sys::input2::{INPUT_HANDLE, INPUT_INFO, INPUT_PLUGIN_TABLE};
+use std::os::windows::ffi::OsStrExt;
+
+/// 入力プラグインを呼び出すシミュレーター。
+pub struct InputPluginSim<'a> {
+    table: &'a INPUT_PLUGIN_TABLE,
+}
+
+/// 開いているファイル。破棄時に`func_close`を呼び出します。
+pub struct InputFileSim<'a> {
+    table: &'a INPUT_PLUGIN_TABLE,
+    handle: INPUT_HANDLE,
+}
+
+/// `func_info_get`で取得した動画の情報。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoFormatSim {
+    pub rate: i32,
+    pub scale: i32,
+    pub num_frames: i32,
+    pub width: i32,
+    pub height: i32,
+    pub bit_count: u16,
+    /// `biCompression`（`BI_RGB`やFourCC）。
+    pub compression: u32,
+    pub size_image: u32,
+}
+
+/// `func_info_get`で取得した音声の情報。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioFormatSim {
+    pub num_samples: i32,
+    /// `wFormatTag`（`WAVE_FORMAT_PCM`や`WAVE_FORMAT_IEEE_FLOAT`）。
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub block_align: u16,
+    pub bits_per_sample: u16,
+}
+
+impl std::fmt::Debug for InputPluginSim<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputPluginSim")
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> InputPluginSim<'a> {
+    /// `GetInputPluginTable`を呼び出す。
+    pub fn new(dll: &'a PluginDll) -> anyhow::Result<Self> {
+        let table = unsafe { dll.plugin_table::<INPUT_PLUGIN_TABLE>(b"GetInputPluginTable\0")? };
+        Ok(Self {
+            table: unsafe { &*table },
+        })
+    }
+
+    /// 汎用プラグインが登録したプラグインの情報から作成する。
+    ///
+    /// # See Also
+    ///
+    /// - [`crate::GenericPluginSim`]
+    pub fn from_table(table: &'a INPUT_PLUGIN_TABLE) -> Self {
+        Self { table }
+    }
+
+    /// プラグインの情報を返す。
+    pub fn table(&self) -> &'a INPUT_PLUGIN_TABLE {
+        self.table
+    }
+
+    /// プラグインの名前を返す。
+    pub fn name(&self) -> Option<String> {
+        unsafe { load_wide(self.table.name) }
+    }
+
+    /// ファイルフィルタの名前とパターンの組を返す。
+    pub fn file_filters(&self) -> Vec<(String, String)> {
+        unsafe { load_file_filters(self.table.filefilter) }
+    }
+
+    /// `func_open`でファイルを開く。
+    pub fn open(&self, path: &std::path::Path) -> anyhow::Result<InputFileSim<'a>> {
+        let func_open = self
+            .table
+            .func_open
+            .ok_or_else(|| anyhow::anyhow!("func_open is null"))?;
+        let path = path
+            .as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect::<Vec<u16>>();
+        let handle = unsafe { func_open(path.as_ptr()) };
+        anyhow::ensure!(!handle.is_null(), "func_open returned null");
+        Ok(InputFileSim {
+            table: self.table,
+            handle,
+        })
+    }
+}
+
+impl std::fmt::Debug for InputFileSim<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputFileSim")
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl InputFileSim<'_> {
+    /// `func_open`が返した入力ハンドルを返す。
+    pub fn handle(&self) -> INPUT_HANDLE {
+        self.handle
+    }
+
+    /// `func_close`でファイルを閉じる。
+    pub fn close(self) -> anyhow::Result<()> {
+        let this = std::mem::ManuallyDrop::new(self);
+        let func_close = this
+            .table
+            .func_close
+            .ok_or_else(|| anyhow::anyhow!("func_close is null"))?;
+        anyhow::ensure!(func_close(this.handle), "func_close returned false");
+        Ok(())
+    }
+
+    fn info(&self) -> anyhow::Result<INPUT_INFO> {
+        let func_info_get = self
+            .table
+            .func_info_get
+            .ok_or_else(|| anyhow::anyhow!("func_info_get is null"))?;
+        let mut info = std::mem::MaybeUninit::<INPUT_INFO>::zeroed();
+        anyhow::ensure!(
+            func_info_get(self.handle, info.as_mut_ptr()),
+            "func_info_get returned false"
+        );
+        Ok(unsafe { info.assume_init() })
+    }
+
+    /// `func_info_get`で動画の情報を取得する。動画が無い場合は`None`を返す。
+    pub fn video_format(&self) -> anyhow::Result<Option<VideoFormatSim>> {
+        let info = self.info()?;
+        if info.flag & INPUT_INFO::FLAG_VIDEO == 0 {
+            return Ok(None);
+        }
+        anyhow::ensure!(!info.format.is_null(), "video format is null");
+        let format = unsafe { &*info.format };
+        Ok(Some(VideoFormatSim {
+            rate: info.rate,
+            scale: info.scale,
+            num_frames: info.n,
+            width: format.biWidth,
+            height: format.biHeight,
+            bit_count: format.biBitCount,
+            compression: format.biCompression,
+            size_image: format.biSizeImage,
+        }))
+    }
+
+    /// `func_read_video`で動画のフレームを読み込む。
+    ///
+    /// `biSizeImage`の大きさのバッファを渡し、書き込まれた分だけを返します。
+    pub fn read_video(&self, frame: i32) -> anyhow::Result<Vec<u8>> {
+        let format = self
+            .video_format()?
+            .ok_or_else(|| anyhow::anyhow!("the file has no video"))?;
+        let func_read_video = self
+            .table
+            .func_read_video
+            .ok_or_else(|| anyhow::anyhow!("func_read_video is null"))?;
+        let mut buffer = vec![0u8; format.size_image as usize];
+        let written = func_read_video(self.handle, frame, buffer.as_mut_ptr().cast());
+        anyhow::ensure!(
+            (0..=buffer.len() as i32).contains(&written),
+            "func_read_video returned {written}, but the buffer size is {}",
+            buffer.len()
+        );
+        buffer.truncate(written as usize);
+        Ok(buffer)
+    }
+}
+
+impl InputFileSim<'_> {
+    /// `func_info_get`で音声の情報を取得する。音声が無い場合は`None`を返す。
+    pub fn audio_format(&self) -> anyhow::Result<Option<AudioFormatSim>> {
+        let info = self.info()?;
+        if info.flag & INPUT_INFO::FLAG_AUDIO == 0 {
+            return Ok(None);
+        }
+        anyhow::ensure!(!info.audio_format.is_null(), "audio format is null");
+        let format = unsafe { info.audio_format.read_unaligned() };
+        Ok(Some(AudioFormatSim {
+            num_samples: info.audio_n,
+            format_tag: format.wFormatTag,
+            channels: format.nChannels,
+            sample_rate: format.nSamplesPerSec,
+            block_align: format.nBlockAlign,
+            bits_per_sample: format.wBitsPerSample,
+        }))
+    }
+
+    /// `func_read_audio`で`start`サンプル目から`length`サンプル分の音声を読み込む。
+    ///
+    /// `length * nBlockAlign`の大きさのバッファを渡し、書き込まれたサンプル数の分だけを返します。
+    pub fn read_audio(&self, start: i32, length: i32) -> anyhow::Result<Vec<u8>> {
+        let format = self
+            .audio_format()?
+            .ok_or_else(|| anyhow::anyhow!("the file has no audio"))?;
+        let func_read_audio = self
+            .table
+            .func_read_audio
+            .ok_or_else(|| anyhow::anyhow!("func_read_audio is null"))?;
+        let block_align = format.block_align as usize;
+        let mut buffer = vec![0u8; length.max(0) as usize * block_align];
+        let written = func_read_audio(self.handle, start, length, buffer.as_mut_ptr().cast());
+        anyhow::ensure!(
+            (0..=length).contains(&written),
+            "func_read_audio returned {written}, but {length} samples were requested"
+        );
+        buffer.truncate(written as usize * block_align);
+        Ok(buffer)
+    }
+}
+
+impl Drop for InputFileSim<'_> {
+    fn drop(&mut self) {
+        if let Some(func_close) = self.table.func_close {
+            func_close(self.handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel_format_test_input() -> &'static PluginDll {
+        crate::load_example(
+            "example-pixel-format-test-input",
+            "rusty_pixel_format_test_input",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pixel_format_test_input_table() {
+        let plugin = InputPluginSim::new(pixel_format_test_input()).unwrap();
+        assert_eq!(
+            plugin.name().as_deref(),
+            Some("Rusty Pixel Format Tester Input")
+        );
+        assert_ne!(plugin.table().flag & INPUT_PLUGIN_TABLE::FLAG_VIDEO, 0);
+        assert_ne!(plugin.table().flag & INPUT_PLUGIN_TABLE::FLAG_AUDIO, 0);
+        assert_eq!(
+            plugin.file_filters(),
+            [
+                (
+                    "Pixel Formats (.bgra, .bgra_manual, .bgr, .yuy2, .pa64, .hf64, .yc48)"
+                        .to_string(),
+                    "*.bgra;*.bgra_manual;*.bgr;*.yuy2;*.pa64;*.hf64;*.yc48".to_string()
+                ),
+                (
+                    "Audio Test Signals (.sine, .sweep, .impulse)".to_string(),
+                    "*.sine;*.sweep;*.impulse".to_string()
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pixel_format_test_input_read_bgra() {
+        let plugin = InputPluginSim::new(pixel_format_test_input()).unwrap();
+        // パスがUTF-16のまま渡るかを確認するため、日本語を含める
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("テスト画像.bgra");
+        std::fs::write(&path, []).unwrap();
+
+        let file = plugin.open(&path).unwrap();
+        let format = file.video_format().unwrap().unwrap();
+        assert_eq!(
+            format,
+            VideoFormatSim {
+                rate: 30,
+                scale: 1,
+                num_frames: 300,
+                width: 256,
+                height: 256,
+                bit_count: 32,
+                compression: aviutl2_sys::common::BI_RGB,
+                size_image: 256 * 256 * 4,
+            }
+        );
+        assert_eq!(file.audio_format().unwrap(), None);
+
+        let gradient = |x: usize, y: usize| {
+            [
+                x as u8,
+                y as u8,
+                ((x + y) as f64 / 512.0 * 255.0) as u8,
+                255,
+            ]
+        };
+        let data = file.read_video(0).unwrap();
+        assert_eq!(data.len(), 256 * 256 * 4);
+        // フレームカウンターの外側はグラデーション
+        for (x, y) in [(200, 0), (10, 100), (255, 255), (128, 60)] {
+            assert_eq!(bgra_at(&data, x, y), gradient(x, y), "({x}, {y})");
+        }
+        // 左上のフレームカウンターは「0000」。1桁目の上のセグメントは点灯し、中央は消灯している
+        assert_eq!(bgra_at(&data, 18, 9), [255, 255, 255, 255]);
+        assert_eq!(bgra_at(&data, 18, 26), [0, 0, 0, 255]);
+
+        // グラデーションは1フレームあたり2ピクセル横に動く
+        let data = file.read_video(10).unwrap();
+        for (x, y) in [(200, 0), (10, 100), (250, 255)] {
+            assert_eq!(
+                bgra_at(&data, x, y),
+                gradient((x + 20) % 256, y),
+                "({x}, {y})"
+            );
+        }
+        // 「0010」の4桁目（0）は上のセグメントが点灯し、1フレーム目の「0001」では消灯している
+        assert_eq!(bgra_at(&data, 96, 9), [255, 255, 255, 255]);
+        let data = file.read_video(1).unwrap();
+        assert_eq!(bgra_at(&data, 96, 9), [0, 0, 0, 255]);
+        assert_eq!(bgra_at(&data, 104, 17), [255, 255, 255, 255]);
+    }
+
+    /// 下から上に並ぶBGRAの`(x, y)`の画素を返す。`y`は上から数える。
+    fn bgra_at(data: &[u8], x: usize, y: usize) -> [u8; 4] {
+        let i = ((255 - y) * 256 + x) * 4;
+        data[i..i + 4].try_into().unwrap()
+    }
+
+    fn read_f32_stereo(data: &[u8]) -> Vec<(f32, f32)> {
+        data.chunks_exact(8)
+            .map(|frame| {
+                (
+                    f32::from_le_bytes(frame[0..4].try_into().unwrap()),
+                    f32::from_le_bytes(frame[4..8].try_into().unwrap()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pixel_format_test_input_audio_signals() {
+        let plugin = InputPluginSim::new(pixel_format_test_input()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        for extension in ["sine", "sweep", "impulse"] {
+            let path = dir.path().join(format!("テスト信号.{extension}"));
+            std::fs::write(&path, []).unwrap();
+            let file = plugin.open(&path).unwrap();
+            assert_eq!(file.video_format().unwrap(), None, "{extension}");
+            assert_eq!(
+                file.audio_format().unwrap(),
+                Some(AudioFormatSim {
+                    num_samples: 48000 * 10,
+                    format_tag: aviutl2_sys::common::WAVE_FORMAT_IEEE_FLOAT as u16,
+                    channels: 2,
+                    sample_rate: 48000,
+                    block_align: 8,
+                    bits_per_sample: 32,
+                }),
+                "{extension}"
+            );
+        }
+
+        let open = |extension: &str| {
+            let path = dir.path().join(format!("signal.{extension}"));
+            std::fs::write(&path, []).unwrap();
+            plugin.open(&path).unwrap()
+        };
+
+        // 1kHzの正弦波は48サンプル周期で、左右が同じ
+        let sine = read_f32_stereo(&open("sine").read_audio(0, 49).unwrap());
+        assert_eq!(sine.len(), 49);
+        assert_eq!(sine[0], (0.0, 0.0));
+        assert!((sine[12].0 - 0.5).abs() < 1e-6);
+        assert!((sine[36].0 + 0.5).abs() < 1e-6);
+        assert!(sine.iter().all(|(l, r)| l == r));
+
+        // インパルスは左が0秒、右が0.5秒から1秒ごと。途中から読み込んでも位置は変わらない
+        let impulse = open("impulse");
+        let samples = read_f32_stereo(&impulse.read_audio(0, 48000).unwrap());
+        assert_eq!(samples[0], (1.0, 0.0));
+        assert_eq!(samples[24000], (0.0, 1.0));
+        assert_eq!(
+            samples
+                .iter()
+                .filter(|(l, r)| *l != 0.0 || *r != 0.0)
+                .count(),
+            2
+        );
+        let samples = read_f32_stereo(&impulse.read_audio(47999, 2).unwrap());
+        assert_eq!(samples, [(0.0, 0.0), (1.0, 0.0)]);
+
+        // スイープは振幅-6dBFSで、範囲外は無音
+        let sweep = open("sweep");
+        let samples = read_f32_stereo(&sweep.read_audio(0, 48000).unwrap());
+        assert!(samples.iter().all(|(l, _)| l.abs() <= 0.5 + 1e-6));
+        assert!(samples.iter().any(|(l, _)| l.abs() > 0.49));
+        let samples = read_f32_stereo(&sweep.read_audio(48000 * 10, 16).unwrap());
+        assert!(samples.iter().all(|&sample| sample == (0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_pixel_format_test_input_rejects_double_close() {
+        let plugin = InputPluginSim::new(pixel_format_test_input()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("double_close.bgra");
+        std::fs::write(&path, []).unwrap();
+
+        let file = plugin.open(&path).unwrap();
+        let handle = file.handle();
+        file.close().unwrap();
+        let func_close = plugin.table().func_close.unwrap();
+        assert!(!func_close(handle));
+        assert!(!func_close(std::ptr::null_mut()));
+    }
+
+    #[test]
+    fn test_pixel_format_test_input_rejects_unknown_extension() {
+        let plugin = InputPluginSim::new(pixel_format_test_input()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("unknown.txt");
+        std::fs::write(&path, []).unwrap();
+        assert!(plugin.open(&path).is_err());
+    }
+}
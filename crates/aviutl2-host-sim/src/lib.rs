@@ -0,0 +1,78 @@
+//! # aviutl2-host-sim
+//!
+//! ビルドしたプラグインのDLLを読み込み、AviUtl2と同じ手順で呼び出すためのテスト用クレート。
+//!
+//! 構造体のレイアウトや呼び出し規約、文字列のエンコーディングなど、
+//! 単体テストでは確認できないDLLの境界部分の不具合を検出するために使います。
+//!
+//! - [`PluginDll`]：DLLの読み込みと初期化。
+//! - [`InputPluginSim`]：入力プラグインの呼び出し。
+//! - [`OutputPluginSim`]：出力プラグインの呼び出し。
+//! - [`FilterPluginSim`]：フィルタプラグインの呼び出し。
+//! - [`GenericPluginSim`]：汎用プラグインの登録と、登録されたプラグインの呼び出し。
+//! - [`load_example`]：ワークスペースのサンプルプラグインのビルドと読み込み。
+//!
+//! ## Note
+//!
+//! ホストの関数のうち、各シミュレーターが使うもの以外は提供していません。
+
+mod example;
+mod filter;
+mod generic;
+mod input;
+mod output;
+mod plugin;
+
+pub use example::*;
+pub use filter::*;
+pub use generic::*;
+pub use input::*;
+pub use output::*;
+pub use plugin::*;
+
+/// 文字列をnull終端のUTF-16に変換する。
+pub fn to_wide(value: &str) -> Vec<u16> {
+    value.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// null終端のUTF-16の文字列を読み込む。nullポインタの場合は`None`を返す。
+///
+/// # Safety
+///
+/// `ptr`はnullか、有効なnull終端のUTF-16文字列を指している必要があります。
+pub unsafe fn load_wide(ptr: *const u16) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let mut len = 0;
+    while unsafe { *ptr.add(len) } != 0 {
+        len += 1;
+    }
+    let slice = unsafe { std::slice::from_raw_parts(ptr, len) };
+    Some(String::from_utf16_lossy(slice))
+}
+
+/// `filefilter`の形式（`名前\0パターン\0...\0\0`）の文字列を読み込み、名前とパターンの組を返す。
+///
+/// # Safety
+///
+/// `ptr`はnullか、空文字列で終わるnull区切りのUTF-16文字列を指している必要があります。
+pub unsafe fn load_file_filters(ptr: *const u16) -> Vec<(String, String)> {
+    if ptr.is_null() {
+        return Vec::new();
+    }
+    let mut entries = Vec::new();
+    let mut current = ptr;
+    loop {
+        let entry = unsafe { load_wide(current) }.unwrap_or_default();
+        if entry.is_empty() {
+            break;
+        }
+        current = unsafe { current.add(entry.encode_utf16().count() + 1) };
+        entries.push(entry);
+    }
+    entries
+        .chunks(2)
+        .map(|pair| (pair[0].clone(), pair.get(1).cloned().unwrap_or_default()))
+        .collect()
+}
@@ -0,0 +1,268 @@
+use crate::{PluginDll, load_file_filters, load_wide};
+use aviutl2_sys::output2::{OUTPUT_INFO, OUTPUT_PLUGIN_TABLE};
+use std::os::windows::ffi::OsStrExt;
+
+/// 出力プラグインに渡す動画。
+pub struct VideoSourceSim {
+    pub width: i32,
+    pub height: i32,
+    pub rate: i32,
+    pub scale: i32,
+    pub num_frames: i32,
+    /// フレーム番号と要求されたフォーマット（`BI_RGB`やFourCC）を受け取り、フレームのデータを返す関数。
+    ///
+    /// 対応していないフォーマットの場合は`None`を返します。
+    pub frame: Box<dyn Fn(i32, u32) -> Option<Vec<u8>> + Send>,
+}
+
+impl std::fmt::Debug for VideoSourceSim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VideoSourceSim")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("rate", &self.rate)
+            .field("scale", &self.scale)
+            .field("num_frames", &self.num_frames)
+            .finish_non_exhaustive()
+    }
+}
+
+impl VideoSourceSim {
+    /// ピクセルごとの色（RGBA、0.0〜1.0）からHF64形式のフレームを作る動画を作成する。
+    ///
+    /// HF64以外のフォーマットは要求されても`None`を返します。
+    pub fn hf64(
+        width: i32,
+        height: i32,
+        num_frames: i32,
+        pixel: impl Fn(i32, i32, i32) -> [f32; 4] + Send + 'static,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            rate: 30,
+            scale: 1,
+            num_frames,
+            frame: Box::new(move |frame, format| {
+                if format != aviutl2_sys::common::BI_HF64 {
+                    return None;
+                }
+                let mut data = Vec::with_capacity((width * height * 8) as usize);
+                for y in 0..height {
+                    for x in 0..width {
+                        for value in pixel(frame, x, y) {
+                            data.extend_from_slice(&half::f16::from_f32(value).to_le_bytes());
+                        }
+                    }
+                }
+                Some(data)
+            }),
+        }
+    }
+}
+
+/// 出力中の状態。ホストの関数にはユーザーデータを渡せないため、グローバルに持つ。
+struct OutputState {
+    source: VideoSourceSim,
+    /// `func_get_video`が最後に返したバッファ。次の呼び出しまで有効。
+    buffer: Vec<u8>,
+    /// `func_get_video`で要求されたフレーム番号とフォーマット。
+    requests: Vec<(i32, u32)>,
+}
+
+static OUTPUT_STATE: std::sync::Mutex<Option<OutputState>> = std::sync::Mutex::new(None);
+/// 出力は同時に1つしか行えないため、出力中はこれをロックする。
+static OUTPUT_SESSION: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+extern "C" fn get_video(frame: i32, format: u32) -> *mut std::ffi::c_void {
+    let mut state = OUTPUT_STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(state) = state.as_mut() else {
+        return std::ptr::null_mut();
+    };
+    state.requests.push((frame, format));
+    if !(0..state.source.num_frames).contains(&frame) {
+        return std::ptr::null_mut();
+    }
+    match (state.source.frame)(frame, format) {
+        Some(data) => {
+            state.buffer = data;
+            state.buffer.as_mut_ptr().cast()
+        }
+        None => std::ptr::null_mut(),
+    }
+}
+
+extern "C" fn is_abort() -> bool {
+    false
+}
+
+extern "C" fn rest_time_disp(_now: i32, _total: i32) {}
+
+extern "C" fn set_buffer_size(_video_size: i32, _audio_size: i32) {}
+
+/// 出力の結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputResultSim {
+    /// `func_output`の戻り値。
+    pub success: bool,
+    /// `func_get_video`で要求されたフレーム番号とフォーマット。
+    pub video_requests: Vec<(i32, u32)>,
+}
+
+/// 出力プラグインを呼び出すシミュレーター。
+pub struct OutputPluginSim<'a> {
+    table: &'a OUTPUT_PLUGIN_TABLE,
+}
+
+impl std::fmt::Debug for OutputPluginSim<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputPluginSim")
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> OutputPluginSim<'a> {
+    /// `GetOutputPluginTable`を呼び出す。
+    pub fn new(dll: &'a PluginDll) -> anyhow::Result<Self> {
+        let table = unsafe { dll.plugin_table::<OUTPUT_PLUGIN_TABLE>(b"GetOutputPluginTable\0")? };
+        Ok(Self {
+            table: unsafe { &*table },
+        })
+    }
+
+    /// 汎用プラグインが登録したプラグインの情報から作成する。
+    ///
+    /// # See Also
+    ///
+    /// - [`crate::GenericPluginSim`]
+    pub fn from_table(table: &'a OUTPUT_PLUGIN_TABLE) -> Self {
+        Self { table }
+    }
+
+    /// プラグインの情報を返す。
+    pub fn table(&self) -> &'a OUTPUT_PLUGIN_TABLE {
+        self.table
+    }
+
+    /// プラグインの名前を返す。
+    pub fn name(&self) -> Option<String> {
+        unsafe { load_wide(self.table.name) }
+    }
+
+    /// ファイルフィルタの名前とパターンの組を返す。
+    pub fn file_filters(&self) -> Vec<(String, String)> {
+        unsafe { load_file_filters(self.table.filefilter) }
+    }
+
+    /// `func_output`で動画を出力する。
+    ///
+    /// 音声は渡しません。
+    pub fn output_video(
+        &self,
+        path: &std::path::Path,
+        source: VideoSourceSim,
+    ) -> anyhow::Result<OutputResultSim> {
+        let func_output = self
+            .table
+            .func_output
+            .ok_or_else(|| anyhow::anyhow!("func_output is null"))?;
+        let _session = OUTPUT_SESSION.lock().unwrap_or_else(|e| e.into_inner());
+        let savefile = path
+            .as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect::<Vec<u16>>();
+        let mut info = OUTPUT_INFO {
+            flag: OUTPUT_INFO::FLAG_VIDEO,
+            w: source.width,
+            h: source.height,
+            rate: source.rate,
+            scale: source.scale,
+            n: source.num_frames,
+            audio_rate: 0,
+            audio_ch: 0,
+            audio_n: 0,
+            savefile: savefile.as_ptr(),
+            func_get_video: Some(get_video),
+            func_get_audio: None,
+            func_is_abort: Some(is_abort),
+            func_rest_time_disp: Some(rest_time_disp),
+            func_set_buffer_size: Some(set_buffer_size),
+        };
+        *OUTPUT_STATE.lock().unwrap_or_else(|e| e.into_inner()) = Some(OutputState {
+            source,
+            buffer: Vec::new(),
+            requests: Vec::new(),
+        });
+        let success = func_output(&mut info);
+        let state = OUTPUT_STATE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .expect("output state was removed during func_output");
+        Ok(OutputResultSim {
+            success,
+            video_requests: state.requests,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image_rs_single_output() -> &'static PluginDll {
+        crate::load_example(
+            "example-image-rs-single-output",
+            "rusty_image_rs_single_output",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_image_rs_single_output_table() {
+        let plugin = OutputPluginSim::new(image_rs_single_output()).unwrap();
+        assert_eq!(plugin.name().as_deref(), Some("Rusty Single Image Output"));
+        assert_ne!(plugin.table().flag & OUTPUT_PLUGIN_TABLE::FLAG_IMAGE, 0);
+        let filters = plugin.file_filters();
+        assert_eq!(
+            filters[1],
+            ("PNG Image (.png)".to_string(), "*.png".to_string())
+        );
+    }
+
+    #[test]
+    fn test_image_rs_single_output_writes_png() {
+        let plugin = OutputPluginSim::new(image_rs_single_output()).unwrap();
+        // パスがUTF-16のまま渡るかを確認するため、日本語を含める
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("出力.png");
+        let result = plugin
+            .output_video(
+                &path,
+                VideoSourceSim::hf64(4, 2, 1, |_frame, x, y| [x as f32 / 3.0, y as f32, 0.5, 1.0]),
+            )
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.video_requests, [(0, aviutl2_sys::common::BI_HF64)]);
+
+        let image = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (4, 2));
+        for (x, y) in [(0, 0), (3, 0), (1, 1), (2, 1)] {
+            let pixel = image.get_pixel(x, y).0;
+            let expected = [
+                (x as f32 / 3.0 * 255.0).round() as u8,
+                (y as f32 * 255.0) as u8,
+                128,
+                255,
+            ];
+            for (actual, expected) in pixel.iter().zip(expected) {
+                assert!(
+                    actual.abs_diff(expected) <= 1,
+                    "({x}, {y}): {pixel:?} != {expected:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+/// 読み込んだプラグインのDLL。
+///
+/// 破棄時に`UninitializePlugin`を呼び出します。
+pub struct PluginDll {
+    library: libloading::Library,
+    path: PathBuf,
+    version: u32,
+}
+
+impl std::fmt::Debug for PluginDll {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginDll")
+            .field("path", &self.path)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+impl PluginDll {
+    /// DLLを読み込み、初期化する。
+    ///
+    /// `RequiredVersion`が返すバージョンのAviUtl2として振る舞います。
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::load_impl(path.as_ref(), None)
+    }
+
+    /// 指定したバージョンのAviUtl2として、DLLを読み込んで初期化する。
+    pub fn load_with_version(path: impl AsRef<Path>, version: u32) -> anyhow::Result<Self> {
+        Self::load_impl(path.as_ref(), Some(version))
+    }
+
+    fn load_impl(path: &Path, version: Option<u32>) -> anyhow::Result<Self> {
+        let library = unsafe { libloading::Library::new(path) }
+            .with_context(|| format!("Failed to load {}", path.display()))?;
+        let mut dll = Self {
+            library,
+            path: path.to_path_buf(),
+            version: 0,
+        };
+        let required_version =
+            match unsafe { dll.symbol::<unsafe extern "C" fn() -> u32>(b"RequiredVersion\0") } {
+                Ok(required_version) => Some(unsafe { required_version() }),
+                Err(_) => None,
+            };
+        dll.version = version.or(required_version).unwrap_or(0);
+        if let Some(required_version) = required_version {
+            anyhow::ensure!(
+                dll.version >= required_version,
+                "{} requires AviUtl2 {required_version}, but the simulated version is {}",
+                path.display(),
+                dll.version
+            );
+        }
+
+        let initialize_plugin = unsafe {
+            dll.symbol::<unsafe extern "C" fn(version: u32) -> bool>(b"InitializePlugin\0")?
+        };
+        anyhow::ensure!(
+            unsafe { initialize_plugin(dll.version) },
+            "InitializePlugin of {} returned false",
+            path.display()
+        );
+        Ok(dll)
+    }
+
+    /// DLLのパスを返す。
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// `InitializePlugin`に渡したバージョンを返す。
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// エクスポートされた関数を取得する。
+    ///
+    /// # Safety
+    ///
+    /// `T`はエクスポートされた関数のシグネチャと一致している必要があります。
+    pub unsafe fn symbol<T: Copy>(&self, name: &[u8]) -> anyhow::Result<T> {
+        let symbol = unsafe { self.library.get::<T>(name) }.with_context(|| {
+            format!(
+                "{} does not export {}",
+                self.path.display(),
+                String::from_utf8_lossy(name.strip_suffix(b"\0").unwrap_or(name))
+            )
+        })?;
+        Ok(*symbol)
+    }
+
+    /// `Get*PluginTable`を呼び出し、プラグインの情報を取得する。
+    ///
+    /// # Safety
+    ///
+    /// `T`は`name`の関数が返す構造体と一致している必要があります。
+    pub unsafe fn plugin_table<T>(&self, name: &[u8]) -> anyhow::Result<*mut T> {
+        let get_table = unsafe { self.symbol::<unsafe extern "C" fn() -> *mut T>(name)? };
+        let table = unsafe { get_table() };
+        anyhow::ensure!(
+            !table.is_null(),
+            "{} returned null",
+            String::from_utf8_lossy(name.strip_suffix(b"\0").unwrap_or(name))
+        );
+        Ok(table)
+    }
+}
+
+impl Drop for PluginDll {
+    fn drop(&mut self) {
+        if let Ok(uninitialize_plugin) =
+            unsafe { self.symbol::<unsafe extern "C" fn()>(b"UninitializePlugin\0") }
+        {
+            unsafe { uninitialize_plugin() };
+        }
+    }
+}
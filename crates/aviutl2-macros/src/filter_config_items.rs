@@ -6,7 +6,20 @@ use syn::{parse::Parse, spanned::Spanned};
 pub fn filter_config_items(
     item: proc_macro2::TokenStream,
 ) -> Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
-    let mut item: syn::ItemStruct = syn::parse2(item).map_err(|e| e.to_compile_error())?;
+    let item: syn::Item = syn::parse2(item).map_err(|e| e.to_compile_error())?;
+    match item {
+        syn::Item::Struct(item) => filter_config_items_struct(item),
+        syn::Item::Enum(item) => filter_config_items_enum(item),
+        other => Err(
+            syn::Error::new_spanned(other, "only structs and enums are supported")
+                .to_compile_error(),
+        ),
+    }
+}
+
+fn filter_config_items_struct(
+    mut item: syn::ItemStruct,
+) -> Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
     item.fields = expand_groups_in_fields(&item.fields)?;
 
     let name = &item.ident;
@@ -16,7 +29,7 @@ pub fn filter_config_items(
         .map(filter_config_field)
         .collect::<crate::utils::CombinedVecResults<_>>()
         .into_result()?;
-    validate_filter_config(&item, &fields)?;
+    validate_filter_config(&item.fields.iter().collect::<Vec<_>>(), &fields)?;
     item.fields = clean_fields(&item.fields);
     let to_config_items = impl_to_config_items(&fields);
     let from_config_items = impl_from_filter_config(&fields);
@@ -43,6 +56,274 @@ pub fn filter_config_items(
     Ok(expanded)
 }
 
+/// enumのVariantの情報。
+struct ConfigVariant {
+    ident: syn::Ident,
+    name: String,
+    /// Variantが持つフィールドの、和集合の中でのインデックス。
+    field_indices: Vec<usize>,
+    is_unit: bool,
+}
+
+/// enumの場合：Variantを選ぶセレクトボックスと、全Variantのフィールドの和集合を設定項目にする。
+fn filter_config_items_enum(
+    mut item: syn::ItemEnum,
+) -> Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
+    let select_name = take_enum_select_name(&mut item).map_err(|e| e.to_compile_error())?;
+
+    // 同じ名前のフィールドは同じ設定項目として扱う
+    let mut union_fields: Vec<syn::Field> = vec![];
+    let mut variants: Vec<ConfigVariant> = vec![];
+    let mut default_variant = None;
+    let mut errors = vec![];
+    for variant in &mut item.variants {
+        let (name, is_default) = match take_variant_attr(variant) {
+            Ok(attr) => attr,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        if is_default {
+            if default_variant.is_some() {
+                errors.push(syn::Error::new_spanned(
+                    &variant.ident,
+                    "only one variant can be marked as default",
+                ));
+            }
+            default_variant = Some(variants.len());
+        }
+        let fields = match &variant.fields {
+            syn::Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            syn::Fields::Unit => vec![],
+            syn::Fields::Unnamed(_) => {
+                errors.push(syn::Error::new_spanned(
+                    &variant.fields,
+                    "only named fields or unit variants are supported",
+                ));
+                continue;
+            }
+        };
+        let mut field_indices = vec![];
+        for field in fields {
+            if field.attrs.iter().any(|attr| attr.path().is_ident("group")) {
+                errors.push(syn::Error::new_spanned(
+                    field,
+                    "`#[group]` is not supported in enum variants",
+                ));
+                continue;
+            }
+            if let Some(index) = union_fields.iter().position(|f| f.ident == field.ident) {
+                if union_fields[index].to_token_stream().to_string()
+                    != field.to_token_stream().to_string()
+                {
+                    errors.push(syn::Error::new_spanned(
+                        field,
+                        "fields with the same name in different variants must have the same attributes and type",
+                    ));
+                }
+                field_indices.push(index);
+            } else {
+                field_indices.push(union_fields.len());
+                union_fields.push(field.clone());
+            }
+        }
+        variants.push(ConfigVariant {
+            ident: variant.ident.clone(),
+            name,
+            field_indices,
+            is_unit: matches!(variant.fields, syn::Fields::Unit),
+        });
+    }
+    if !errors.is_empty() {
+        return Err(errors.into_iter().map(|e| e.to_compile_error()).collect());
+    }
+    if variants.is_empty() {
+        return Err(
+            syn::Error::new_spanned(&item.ident, "enum must have at least one variant")
+                .to_compile_error(),
+        );
+    }
+    let default_variant = default_variant.unwrap_or(0);
+
+    let union_config_fields = union_fields
+        .iter()
+        .map(filter_config_field)
+        .collect::<crate::utils::CombinedVecResults<_>>()
+        .into_result()?;
+    // 先頭にVariantを選ぶセレクトボックスを置く
+    let mut fields = vec![FilterConfigField::Select {
+        id: "__variant".to_string(),
+        name: select_name.unwrap_or_else(|| item.ident.to_string()),
+        default: either::Either::Left(default_variant as i32),
+        items: either::Either::Left(variants.iter().map(|v| v.name.clone()).collect()),
+        tooltip: None,
+    }];
+    fields.extend(union_config_fields);
+    let mut sources = vec![item.ident.to_token_stream()];
+    sources.extend(union_fields.iter().map(|f| f.to_token_stream()));
+    validate_filter_config(&sources, &fields)?;
+
+    for variant in &mut item.variants {
+        variant.fields = clean_fields(&variant.fields);
+    }
+
+    let name = &item.ident;
+    let to_config_items = impl_to_config_items(&fields);
+    let assignments = field_assignments(&fields);
+    let defaults = field_defaults(&fields);
+    let item_indices = item_indices(&fields);
+    let item_count = fields
+        .iter()
+        .map(FilterConfigField::item_count)
+        .sum::<usize>();
+
+    let construct_variant =
+        |variant: &ConfigVariant, values: &[Option<proc_macro2::TokenStream>]| {
+            let ident = &variant.ident;
+            if variant.is_unit {
+                return quote::quote! { Self::#ident };
+            }
+            let values = variant
+                .field_indices
+                .iter()
+                .filter_map(|&index| values[index + 1].as_ref());
+            quote::quote! { Self::#ident { #(#values),* } }
+        };
+    let from_arms = variants.iter().enumerate().map(|(i, variant)| {
+        let i = i as i32;
+        let construct = construct_variant(variant, &assignments);
+        quote::quote! { #i => #construct }
+    });
+    let default = construct_variant(&variants[default_variant], &defaults);
+
+    // トラックバーグループは、グループ内のトラックバーのどれかが有効なら有効にする
+    let mut track_groups = Vec::<(&str, Vec<usize>)>::new();
+    for (i, f) in fields.iter().enumerate() {
+        if let FilterConfigField::Track {
+            group: Some(group), ..
+        } = f
+        {
+            if let Some((_, members)) = track_groups.iter_mut().find(|(name, _)| name == group) {
+                members.push(i);
+            } else {
+                track_groups.push((group, vec![i]));
+            }
+        }
+    }
+    let active_arms = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        let active_fields = variant
+            .field_indices
+            .iter()
+            .map(|&index| index + 1)
+            .collect::<Vec<_>>();
+        let mut indices = vec![0usize];
+        for &field_index in &active_fields {
+            let start = item_indices[field_index];
+            indices.extend(start..start + fields[field_index].item_count());
+        }
+        for (group_index, (_, members)) in track_groups.iter().enumerate() {
+            if members.iter().any(|m| active_fields.contains(m)) {
+                indices.push(item_count + group_index);
+            }
+        }
+        let pattern = if variant.is_unit {
+            quote::quote! { Self::#ident }
+        } else {
+            quote::quote! { Self::#ident { .. } }
+        };
+        quote::quote! { #pattern => vec![#(#indices),*] }
+    });
+
+    let expanded = quote::quote! {
+        #item
+
+        #[automatically_derived]
+        impl ::aviutl2::filter::FilterConfigItems for #name {
+            #to_config_items
+
+            fn from_config_items(items: &[::aviutl2::filter::FilterConfigItem]) -> Self {
+                match items[0] {
+                    ::aviutl2::filter::FilterConfigItem::Select(ref select) => match select.value {
+                        #(#from_arms,)*
+                        value => panic!("unexpected variant index {}", value),
+                    },
+                    _ => panic!("expected Select at index {}", 0),
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl ::std::default::Default for #name {
+            fn default() -> Self {
+                #default
+            }
+        }
+
+        #[automatically_derived]
+        impl #name {
+            pub fn active_item_indices(&self) -> ::std::vec::Vec<usize> {
+                match self {
+                    #(#active_arms,)*
+                }
+            }
+        }
+    };
+
+    Ok(expanded)
+}
+
+/// enumに付いた`#[select(name = "...")]`を取り除き、名前を返す。
+fn take_enum_select_name(item: &mut syn::ItemEnum) -> Result<Option<String>, syn::Error> {
+    let Some(position) = item
+        .attrs
+        .iter()
+        .position(|attr| attr.path().is_ident("select"))
+    else {
+        return Ok(None);
+    };
+    let attr = item.attrs.remove(position);
+    let mut name = None;
+    attr.parse_nested_meta(|m| {
+        if m.path.is_ident("name") {
+            name = Some(m.value()?.parse::<syn::LitStr>()?.value());
+            Ok(())
+        } else {
+            Err(m.error("expected `name`"))
+        }
+    })?;
+    Ok(name)
+}
+
+/// Variantに付いた`#[variant(...)]`を取り除き、名前とデフォルトかどうかを返す。
+fn take_variant_attr(variant: &mut syn::Variant) -> Result<(String, bool), syn::Error> {
+    let mut name = None;
+    let mut is_default = false;
+    if let Some(position) = variant
+        .attrs
+        .iter()
+        .position(|attr| attr.path().is_ident("variant"))
+    {
+        let attr = variant.attrs.remove(position);
+        attr.parse_nested_meta(|m| {
+            if m.path.is_ident("name") {
+                name = Some(m.value()?.parse::<syn::LitStr>()?.value());
+                Ok(())
+            } else if m.path.is_ident("default") {
+                is_default = true;
+                Ok(())
+            } else {
+                Err(m.error("expected `name` or `default`"))
+            }
+        })?;
+    }
+    Ok((
+        name.unwrap_or_else(|| variant.ident.to_string()),
+        is_default,
+    ))
+}
+
 fn expand_groups_in_fields(fields: &syn::Fields) -> Result<syn::Fields, proc_macro2::TokenStream> {
     let syn::Fields::Named(fields) = fields else {
         return Err(syn::Error::new(
@@ -183,34 +464,41 @@ enum FilterConfigField {
         group: Option<String>,
         zero_display: Option<String>,
         slider_ratio: f64,
+        tooltip: Option<String>,
     },
     Check {
         id: String,
         name: String,
         default: bool,
+        tooltip: Option<String>,
     },
     CheckSection {
         id: String,
         name: String,
         default: bool,
         multi_section: bool,
+        tooltip: Option<String>,
     },
     Color {
         id: String,
         name: String,
         default: u32,
+        alpha_track: Option<String>,
+        tooltip: Option<String>,
     },
     Select {
         id: String,
         name: String,
         default: either::Either<i32, syn::ExprPath>,
         items: either::Either<Vec<String>, syn::TypePath>,
+        tooltip: Option<String>,
     },
     File {
         id: String,
         name: String,
         filters: Vec<FileFilterEntry>,
         default: Option<syn::Expr>,
+        tooltip: Option<String>,
     },
     String {
         id: String,
@@ -250,6 +538,19 @@ enum FilterConfigField {
     },
 }
 
+impl FilterConfigField {
+    /// `to_config_items`でこのフィールドから生成される設定項目の数。
+    fn item_count(&self) -> usize {
+        match self {
+            FilterConfigField::Color {
+                alpha_track: Some(_),
+                ..
+            } => 2,
+            _ => 1,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct TrackStep {
     value: decimal_rs::Decimal,
@@ -358,16 +659,18 @@ fn impl_to_config_items(fields: &[FilterConfigField]) -> proc_macro2::TokenStrea
                 group,
                 zero_display,
                 slider_ratio,
+                tooltip,
             } => {
-                let track = quote_filter_config_track(
+                let track = quote_filter_config_track(&TrackSpec {
                     name,
-                    *default,
-                    *min,
-                    *max,
-                    *step,
-                    zero_display.as_deref(),
-                    *slider_ratio,
-                );
+                    default: *default,
+                    min: *min,
+                    max: *max,
+                    step: *step,
+                    zero_display: zero_display.as_deref(),
+                    slider_ratio: *slider_ratio,
+                    tooltip: tooltip.as_deref(),
+                });
                 if let Some(group) = group {
                     if let Some((_, tracks)) =
                         track_groups.iter_mut().find(|(name, _)| name == group)
@@ -387,11 +690,14 @@ fn impl_to_config_items(fields: &[FilterConfigField]) -> proc_macro2::TokenStrea
                 id: _,
                 name,
                 default,
+                tooltip,
             } => {
+                let tooltip = quote_tooltip(tooltip.as_deref());
                 quote::quote! {
                     ::aviutl2::filter::FilterConfigItem::Checkbox(
                         ::aviutl2::filter::FilterConfigCheckbox {
                             name: #name.to_string(),
+                            tooltip: #tooltip,
                             value: #default,
                         }
                     )
@@ -402,11 +708,14 @@ fn impl_to_config_items(fields: &[FilterConfigField]) -> proc_macro2::TokenStrea
                 name,
                 default,
                 multi_section,
+                tooltip,
             } => {
+                let tooltip = quote_tooltip(tooltip.as_deref());
                 quote::quote! {
                     ::aviutl2::filter::FilterConfigItem::CheckSection(
                         ::aviutl2::filter::FilterConfigCheckSection {
                             name: #name.to_string(),
+                            tooltip: #tooltip,
                             value: #default,
                             multi_section: #multi_section,
                         }
@@ -417,14 +726,38 @@ fn impl_to_config_items(fields: &[FilterConfigField]) -> proc_macro2::TokenStrea
                 id: _,
                 name,
                 default,
+                alpha_track,
+                tooltip,
             } => {
-                quote::quote! {
+                let tooltip = quote_tooltip(tooltip.as_deref());
+                let color = quote::quote! {
                     ::aviutl2::filter::FilterConfigItem::Color(
                         ::aviutl2::filter::FilterConfigColor {
                             name: #name.to_string(),
+                            tooltip: #tooltip,
                             value: #default.into(),
                         }
                     )
+                };
+                if let Some(alpha_track) = alpha_track {
+                    let track = quote_filter_config_track(&TrackSpec {
+                        name: alpha_track,
+                        default: 100.0,
+                        min: 0.0,
+                        max: 100.0,
+                        step: 1.0,
+                        zero_display: None,
+                        slider_ratio: 1.0,
+                        tooltip: None,
+                    });
+                    quote::quote! {
+                        #color,
+                        ::aviutl2::filter::FilterConfigItem::Track(
+                            #track
+                        )
+                    }
+                } else {
+                    color
                 }
             }
             FilterConfigField::Select {
@@ -432,7 +765,9 @@ fn impl_to_config_items(fields: &[FilterConfigField]) -> proc_macro2::TokenStrea
                 name,
                 default,
                 items,
+                tooltip,
             } => {
+                let tooltip = quote_tooltip(tooltip.as_deref());
                 let items = match items {
                     either::Either::Left(items) => {
                         let items = items.iter().enumerate().map(|(i, item)| {
@@ -457,6 +792,7 @@ fn impl_to_config_items(fields: &[FilterConfigField]) -> proc_macro2::TokenStrea
                     ::aviutl2::filter::FilterConfigItem::Select(
                         ::aviutl2::filter::FilterConfigSelect {
                             name: #name.to_string(),
+                            tooltip: #tooltip,
                             value: #default,
                             items: #items,
                         }
@@ -468,7 +804,9 @@ fn impl_to_config_items(fields: &[FilterConfigField]) -> proc_macro2::TokenStrea
                 name,
                 filters,
                 default,
+                tooltip,
             } => {
+                let tooltip = quote_tooltip(tooltip.as_deref());
                 let filter_entries = filters.iter().map(|entry| {
                     let n = &entry.name;
                     let exts = &entry.exts;
@@ -488,6 +826,7 @@ fn impl_to_config_items(fields: &[FilterConfigField]) -> proc_macro2::TokenStrea
                     ::aviutl2::filter::FilterConfigItem::File(
                         ::aviutl2::filter::FilterConfigFile {
                             name: #name.to_string(),
+                            tooltip: #tooltip,
                             value: #value,
                             filters: vec![#(#filter_entries),*],
                         }
@@ -696,15 +1035,30 @@ fn impl_to_config_items(fields: &[FilterConfigField]) -> proc_macro2::TokenStrea
     }
 }
 
-fn quote_filter_config_track(
-    name: &str,
+/// `FilterConfigTrack`を生成するための値。
+struct TrackSpec<'a> {
+    name: &'a str,
     default: f64,
     min: f64,
     max: f64,
     step: f64,
-    zero_display: Option<&str>,
+    zero_display: Option<&'a str>,
     slider_ratio: f64,
-) -> proc_macro2::TokenStream {
+    tooltip: Option<&'a str>,
+}
+
+fn quote_filter_config_track(spec: &TrackSpec<'_>) -> proc_macro2::TokenStream {
+    let TrackSpec {
+        name,
+        default,
+        min,
+        max,
+        step,
+        zero_display,
+        slider_ratio,
+        tooltip,
+    } = *spec;
+    let tooltip = quote_tooltip(tooltip);
     let zero_display = zero_display.map_or_else(
         || quote::quote! { ::std::option::Option::None },
         |zero_display| quote::quote! { ::std::option::Option::Some(#zero_display.to_string()) },
@@ -712,6 +1066,7 @@ fn quote_filter_config_track(
     quote::quote! {
         ::aviutl2::filter::FilterConfigTrack {
             name: #name.to_string(),
+            tooltip: #tooltip,
             value: #default,
             range: #min..=#max,
             step: #step,
@@ -721,11 +1076,51 @@ fn quote_filter_config_track(
     }
 }
 
+fn quote_tooltip(tooltip: Option<&str>) -> proc_macro2::TokenStream {
+    tooltip.map_or_else(
+        || quote::quote! { ::std::option::Option::None },
+        |tooltip| quote::quote! { ::std::option::Option::Some(#tooltip.to_string()) },
+    )
+}
+
+/// 各フィールドに対応する最初の設定項目のインデックスを返す。
+///
+/// 1つのフィールドから複数の設定項目が生成されることがあるので、設定項目のインデックスを別に数える。
+fn item_indices(config_fields: &[FilterConfigField]) -> Vec<usize> {
+    config_fields
+        .iter()
+        .scan(0usize, |next_index, f| {
+            let index = *next_index;
+            *next_index += f.item_count();
+            Some(index)
+        })
+        .collect()
+}
+
 fn impl_from_filter_config(config_fields: &[FilterConfigField]) -> proc_macro2::TokenStream {
-    let field_assign = config_fields
+    let field_assign = field_assignments(config_fields)
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    quote::quote! {
+        fn from_config_items(items: &[::aviutl2::filter::FilterConfigItem]) -> Self {
+            Self {
+                #(
+                    #field_assign
+                ),*
+            }
+        }
+    }
+}
+
+/// 各フィールドについて、設定項目から値を取り出す`field: value`の形のトークンを返す。
+///
+/// 値を持たないフィールドは`None`になります。
+fn field_assignments(config_fields: &[FilterConfigField]) -> Vec<Option<proc_macro2::TokenStream>> {
+    config_fields
         .iter()
-        .enumerate()
-        .filter_map(|(i, f)| match f {
+        .zip(item_indices(config_fields))
+        .map(|(f, i)| match f {
             FilterConfigField::Track { id, step, .. } => {
                 let id_ident = syn::Ident::new(id, proc_macro2::Span::call_site());
                 let to_value = if *step == 1.0 {
@@ -763,6 +1158,26 @@ fn impl_from_filter_config(config_fields: &[FilterConfigField]) -> proc_macro2::
                     }
                 })
             }
+            FilterConfigField::Color {
+                id,
+                alpha_track: Some(_),
+                ..
+            } => {
+                let id_ident = syn::Ident::new(id, proc_macro2::Span::call_site());
+                let alpha_index = i + 1;
+                Some(quote::quote! {
+                    #id_ident: match (&items[#i], &items[#alpha_index]) {
+                        (
+                            ::aviutl2::filter::FilterConfigItem::Color(color),
+                            ::aviutl2::filter::FilterConfigItem::Track(alpha),
+                        ) => color
+                            .value
+                            .to_rgba(::aviutl2::filter::__opacity_to_alpha(alpha.value))
+                            .into(),
+                        _ => panic!("expected Color and Track at index {}", #i),
+                    }
+                })
+            }
             FilterConfigField::Color { id, .. } => {
                 let id_ident = syn::Ident::new(id, proc_macro2::Span::call_site());
                 Some(quote::quote! {
@@ -865,20 +1280,23 @@ fn impl_from_filter_config(config_fields: &[FilterConfigField]) -> proc_macro2::
                 None
             }
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
+
+fn impl_default(fields: &[FilterConfigField]) -> proc_macro2::TokenStream {
+    let field_inits = field_defaults(fields).into_iter().flatten();
     quote::quote! {
-        fn from_config_items(items: &[::aviutl2::filter::FilterConfigItem]) -> Self {
-            Self {
-                #(
-                    #field_assign
-                ),*
-            }
+        Self {
+            #(#field_inits),*
         }
     }
 }
 
-fn impl_default(fields: &[FilterConfigField]) -> proc_macro2::TokenStream {
-    let field_inits = fields.iter().filter_map(|f| match f {
+/// 各フィールドについて、初期値を表す`field: value`の形のトークンを返す。
+///
+/// 値を持たないフィールドは`None`になります。
+fn field_defaults(fields: &[FilterConfigField]) -> Vec<Option<proc_macro2::TokenStream>> {
+    fields.iter().map(|f| match f {
         FilterConfigField::Track { id, default, .. } => {
             let id_ident = syn::Ident::new(id, proc_macro2::Span::call_site());
             Some(quote::quote! {
@@ -897,10 +1315,21 @@ fn impl_default(fields: &[FilterConfigField]) -> proc_macro2::TokenStream {
                 #id_ident: #default
             })
         }
-        FilterConfigField::Color { id, default, .. } => {
+        FilterConfigField::Color {
+            id,
+            default,
+            alpha_track,
+            ..
+        } => {
             let id_ident = syn::Ident::new(id, proc_macro2::Span::call_site());
-            Some(quote::quote! {
-                #id_ident: #default.into()
+            Some(if alpha_track.is_some() {
+                quote::quote! {
+                    #id_ident: ::aviutl2::filter::FilterConfigColorValue(#default).to_rgba(255).into()
+                }
+            } else {
+                quote::quote! {
+                    #id_ident: #default.into()
+                }
             })
         }
         FilterConfigField::Select { id, default, .. } => {
@@ -973,19 +1402,15 @@ fn impl_default(fields: &[FilterConfigField]) -> proc_macro2::TokenStream {
         | FilterConfigField::GroupEnd
         | FilterConfigField::Separator { .. }
         | FilterConfigField::Button { .. } => None,
-    });
-    quote::quote! {
-        Self {
-            #(#field_inits),*
-        }
-    }
+    }).collect()
 }
 
-fn validate_filter_config(
-    item: &syn::ItemStruct,
+/// 設定項目の名前が重複していないか確認する。`sources`はエラーの位置に使うトークンで、`fields`と同じ順番に並べる。
+fn validate_filter_config<T: ToTokens>(
+    sources: &[T],
     fields: &[FilterConfigField],
 ) -> Result<(), proc_macro2::TokenStream> {
-    assert!(item.fields.len() == fields.len());
+    assert!(sources.len() == fields.len());
     let field_names = fields
         .iter()
         .map(|f| match f {
@@ -1015,7 +1440,7 @@ fn validate_filter_config(
 
     let errors = counts
         .into_iter()
-        .zip(item.fields.iter())
+        .zip(sources.iter())
         .filter(|((_, count), _)| *count > 1)
         .map(|((name, count), field)| {
             syn::Error::new_spanned(
@@ -1152,6 +1577,7 @@ fn filter_config_field_track(
 ) -> Result<FilterConfigField, syn::Error> {
     let mut name = None;
     let mut salt = None;
+    let mut tooltip = None;
     let mut default = None;
     let mut min = None;
     let mut max = None;
@@ -1165,6 +1591,8 @@ fn filter_config_field_track(
             name = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("salt") {
             salt = Some(m.value()?.parse::<syn::LitStr>()?.value());
+        } else if m.path.is_ident("tooltip") {
+            tooltip = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("group") {
             group = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("zero_display") {
@@ -1200,7 +1628,7 @@ fn filter_config_field_track(
                     ));
                 }
                 if let Some(ref from) = expr_range.start {
-                    min = Some(parse_int_or_float(from)?);
+                    min = Some((parse_int_or_float(from)?, (**from).clone()));
                 } else {
                     return Err(syn::Error::new_spanned(
                         expr_range,
@@ -1208,7 +1636,7 @@ fn filter_config_field_track(
                     ));
                 }
                 if let Some(to) = expr_range.end {
-                    max = Some(parse_int_or_float(&to)?);
+                    max = Some((parse_int_or_float(&to)?, *to));
                 } else {
                     return Err(syn::Error::new_spanned(
                         expr_range,
@@ -1222,52 +1650,75 @@ fn filter_config_field_track(
                 ));
             }
         } else if m.path.is_ident("default") {
-            let value_token = m.value()?;
-            default = Some(parse_int_or_float(&value_token.parse()?)?);
+            let expr = m.value()?.parse::<syn::Expr>()?;
+            default = Some((parse_int_or_float(&expr)?, expr));
         } else {
-            return Err(m.error("Unknown attribute for track"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "track",
+                &[
+                    "name",
+                    "salt",
+                    "tooltip",
+                    "group",
+                    "zero_display",
+                    "slider_ratio",
+                    "step",
+                    "range",
+                    "default",
+                ],
+            ));
         }
         Ok(())
     })?;
 
     let Some(step) = step else {
-        return Err(syn::Error::new_spanned(recognized_attr, "step is required"));
+        return Err(syn::Error::new_spanned(
+            recognized_attr,
+            "missing `step` for track (e.g., step = 0.01)",
+        ));
     };
 
     let name = with_salt(name, salt, field.ident.as_ref().unwrap());
-    let (Some(default), Some(min), Some(max)) = (default, min, max) else {
+    let Some((default, default_expr)) = default else {
         return Err(syn::Error::new_spanned(
             recognized_attr,
-            "default, min, and max are required",
+            "missing `default` for track (e.g., default = 0.5)",
         ));
     };
-    if !(min <= default && default <= max) {
+    let (Some((min, min_expr)), Some((max, max_expr))) = (min, max) else {
         return Err(syn::Error::new_spanned(
             recognized_attr,
-            "default must be between min and max",
+            "missing `range` for track (e.g., range = 0.0..=1.0)",
+        ));
+    };
+    if min > max {
+        return Err(syn::Error::new_spanned(
+            &max_expr,
+            format!(
+                "range end ({}) must not be less than range start ({})",
+                max.normalize(),
+                min.normalize()
+            ),
         ));
     }
-    let step_value = decimal_rs::Decimal::from(step.clone());
-    match (min % step_value, max % step_value, default % step_value) {
-        (d, _, _) if d != decimal_rs::Decimal::ZERO => {
-            return Err(syn::Error::new_spanned(
-                recognized_attr,
-                "min must be a multiple of step",
-            ));
-        }
-        (_, d, _) if d != decimal_rs::Decimal::ZERO => {
-            return Err(syn::Error::new_spanned(
-                recognized_attr,
-                "max must be a multiple of step",
-            ));
-        }
-        (_, _, d) if d != decimal_rs::Decimal::ZERO => {
-            return Err(syn::Error::new_spanned(
-                recognized_attr,
-                "default must be a multiple of step",
-            ));
-        }
-        _ => {}
+    if !(min <= default && default <= max) {
+        return Err(syn::Error::new_spanned(
+            &default_expr,
+            format!(
+                "default ({}) must be within range {}..={}",
+                default.normalize(),
+                min.normalize(),
+                max.normalize()
+            ),
+        ));
+    }
+    for (label, value, expr) in [
+        ("range start", min, &min_expr),
+        ("range end", max, &max_expr),
+        ("default", default, &default_expr),
+    ] {
+        validate_track_step_multiple(label, value, &step, expr)?;
     }
     Ok(FilterConfigField::Track {
         id: field.ident.as_ref().unwrap().to_string(),
@@ -1279,6 +1730,7 @@ fn filter_config_field_track(
         group,
         zero_display,
         slider_ratio: slider_ratio.unwrap_or(decimal_rs::Decimal::ONE).into(),
+        tooltip,
     })
 }
 
@@ -1288,6 +1740,7 @@ fn filter_config_field_check(
 ) -> Result<FilterConfigField, syn::Error> {
     let mut name = None;
     let mut salt = None;
+    let mut tooltip = None;
     let mut default = None;
 
     recognized_attr.parse_nested_meta(|m| {
@@ -1295,10 +1748,16 @@ fn filter_config_field_check(
             name = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("salt") {
             salt = Some(m.value()?.parse::<syn::LitStr>()?.value());
+        } else if m.path.is_ident("tooltip") {
+            tooltip = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("default") {
             default = Some(m.value()?.parse::<syn::LitBool>()?.value);
         } else {
-            return Err(m.error("Unknown attribute for check"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "check",
+                &["name", "salt", "tooltip", "default"],
+            ));
         }
         Ok(())
     })?;
@@ -1314,6 +1773,7 @@ fn filter_config_field_check(
         id: field.ident.as_ref().unwrap().to_string(),
         name,
         default,
+        tooltip,
     })
 }
 
@@ -1323,6 +1783,7 @@ fn filter_config_field_check_section(
 ) -> Result<FilterConfigField, syn::Error> {
     let mut name = None;
     let mut salt = None;
+    let mut tooltip = None;
     let mut default = None;
     let mut multi_section = true;
 
@@ -1331,12 +1792,18 @@ fn filter_config_field_check_section(
             name = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("salt") {
             salt = Some(m.value()?.parse::<syn::LitStr>()?.value());
+        } else if m.path.is_ident("tooltip") {
+            tooltip = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("default") {
             default = Some(m.value()?.parse::<syn::LitBool>()?.value);
         } else if m.path.is_ident("multi_section") {
             multi_section = m.value()?.parse::<syn::LitBool>()?.value;
         } else {
-            return Err(m.error("Unknown attribute for checksection"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "checksection",
+                &["name", "salt", "tooltip", "default", "multi_section"],
+            ));
         }
         Ok(())
     })?;
@@ -1353,6 +1820,7 @@ fn filter_config_field_check_section(
         name,
         default,
         multi_section,
+        tooltip,
     })
 }
 
@@ -1362,13 +1830,19 @@ fn filter_config_field_color(
 ) -> Result<FilterConfigField, syn::Error> {
     let mut name = None;
     let mut salt = None;
+    let mut tooltip = None;
     let mut default = None;
+    let mut alpha_track = None;
 
     recognized_attr.parse_nested_meta(|m| {
         if m.path.is_ident("name") {
             name = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("salt") {
             salt = Some(m.value()?.parse::<syn::LitStr>()?.value());
+        } else if m.path.is_ident("tooltip") {
+            tooltip = Some(m.value()?.parse::<syn::LitStr>()?.value());
+        } else if m.path.is_ident("with_alpha_track") {
+            alpha_track = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("default") {
             let lit = m.value()?;
             default = Some(
@@ -1387,7 +1861,11 @@ fn filter_config_field_color(
                     })?,
                 );
         } else {
-            return Err(m.error("Unknown attribute for color"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "color",
+                &["name", "salt", "tooltip", "with_alpha_track", "default"],
+            ));
         }
 
         Ok(())
@@ -1405,6 +1883,8 @@ fn filter_config_field_color(
         id: field.ident.as_ref().unwrap().to_string(),
         name,
         default,
+        alpha_track,
+        tooltip,
     });
 
     fn parse_color_lit(lit: &syn::Lit) -> Result<u32, syn::Error> {
@@ -1474,6 +1954,7 @@ fn filter_config_field_select(
 ) -> Result<FilterConfigField, syn::Error> {
     let mut name = None;
     let mut salt = None;
+    let mut tooltip = None;
     let mut default = None;
     let mut items = None;
 
@@ -1482,6 +1963,8 @@ fn filter_config_field_select(
             name = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("salt") {
             salt = Some(m.value()?.parse::<syn::LitStr>()?.value());
+        } else if m.path.is_ident("tooltip") {
+            tooltip = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("default") {
             let value = m.value()?;
             let lookahead = value.lookahead1();
@@ -1529,7 +2012,11 @@ fn filter_config_field_select(
                 return Err(lookahead.error());
             }
         } else {
-            return Err(m.error("Unknown attribute for select"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "select",
+                &["name", "salt", "tooltip", "default", "items"],
+            ));
         }
         Ok(())
     })?;
@@ -1556,6 +2043,7 @@ fn filter_config_field_select(
         name,
         default,
         items,
+        tooltip,
     })
 }
 
@@ -1565,6 +2053,7 @@ fn filter_config_field_file(
 ) -> Result<FilterConfigField, syn::Error> {
     let mut name = None;
     let mut salt = None;
+    let mut tooltip = None;
     let mut filters = None;
     let mut default = None;
 
@@ -1573,6 +2062,8 @@ fn filter_config_field_file(
             name = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("salt") {
             salt = Some(m.value()?.parse::<syn::LitStr>()?.value());
+        } else if m.path.is_ident("tooltip") {
+            tooltip = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else if m.path.is_ident("filters") {
             let content;
             syn::braced!(content in &m.value()?);
@@ -1585,7 +2076,11 @@ fn filter_config_field_file(
         } else if m.path.is_ident("default") {
             default = Some(m.value()?.parse::<syn::Expr>()?);
         } else {
-            return Err(m.error("Unknown attribute for file"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "file",
+                &["name", "salt", "tooltip", "filters", "default"],
+            ));
         }
         Ok(())
     })?;
@@ -1602,6 +2097,7 @@ fn filter_config_field_file(
         name,
         filters,
         default,
+        tooltip,
     })
 }
 
@@ -1622,7 +2118,11 @@ fn filter_config_field_data(
         } else if m.path.is_ident("default") {
             default = Some(m.value()?.parse::<syn::Expr>()?);
         } else {
-            return Err(m.error("Unknown attribute for data"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "data",
+                &["name", "salt", "default"],
+            ));
         }
         Ok(())
     });
@@ -1652,7 +2152,11 @@ fn filter_config_field_string(
         } else if m.path.is_ident("default") {
             default = Some(m.value()?.parse::<syn::Expr>()?);
         } else {
-            return Err(m.error("Unknown attribute for string"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "string",
+                &["name", "salt", "default"],
+            ));
         }
         Ok(())
     });
@@ -1681,7 +2185,11 @@ fn filter_config_field_text(
         } else if m.path.is_ident("default") {
             default = Some(m.value()?.parse::<syn::Expr>()?);
         } else {
-            return Err(m.error("Unknown attribute for text"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "text",
+                &["name", "salt", "default"],
+            ));
         }
         Ok(())
     });
@@ -1710,7 +2218,11 @@ fn filter_config_field_folder(
         } else if m.path.is_ident("default") {
             default = Some(m.value()?.parse::<syn::Expr>()?);
         } else {
-            return Err(m.error("Unknown attribute for folder"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "folder",
+                &["name", "salt", "default"],
+            ));
         }
         Ok(())
     });
@@ -1740,7 +2252,11 @@ fn filter_config_field_group_start(
             opened = Some(m.value()?.parse::<syn::LitBool>()?.value);
         } else {
             // NOTE: ユーザーには`#[group(...)]`として見えるので、`group`と表示する
-            return Err(m.error("Unknown attribute for group"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "group",
+                &["name", "salt", "opened"],
+            ));
         }
         Ok(())
     })?;
@@ -1768,7 +2284,11 @@ fn filter_config_field_separator(
         } else if m.path.is_ident("salt") {
             salt = Some(m.value()?.parse::<syn::LitStr>()?.value());
         } else {
-            return Err(m.error("Unknown attribute for separator"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "separator",
+                &["name", "salt"],
+            ));
         }
         Ok(())
     })?;
@@ -1808,7 +2328,11 @@ fn filter_config_field_button(
                 unwind = m.value()?.parse::<syn::LitBool>()?.value;
             }
         } else {
-            return Err(m.error("Unknown attribute for button"));
+            return Err(crate::utils::unknown_attribute_error(
+                &m.path,
+                "button",
+                &["name", "salt", "error", "unwind"],
+            ));
         }
         Ok(())
     })?;
@@ -1908,6 +2432,31 @@ fn validate_track_step(
     }
 }
 
+/// `value`が`step`の倍数であることを確認する。倍数でない場合は、前後の有効な値をエラーに含める。
+fn validate_track_step_multiple(
+    label: &str,
+    value: decimal_rs::Decimal,
+    step: &TrackStep,
+    expr: &syn::Expr,
+) -> Result<(), syn::Error> {
+    let step_value = decimal_rs::Decimal::from(step.clone());
+    if value % step_value == decimal_rs::Decimal::ZERO {
+        return Ok(());
+    }
+    let lower = (value / step_value).floor() * step_value;
+    let upper = lower + step_value;
+    Err(syn::Error::new_spanned(
+        expr,
+        format!(
+            "{label} ({}) must be a multiple of step {}; the nearest valid values are {} and {}",
+            value.normalize(),
+            step.repr,
+            lower.normalize(),
+            upper.normalize()
+        ),
+    ))
+}
+
 fn is_negative_power_of_ten_decimal(value: decimal_rs::Decimal) -> bool {
     let mut current = value;
     for _ in 0..30 {
@@ -1959,6 +2508,18 @@ mod tests {
         insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
     }
 
+    #[test]
+    fn test_check_with_tooltip() {
+        let input: proc_macro2::TokenStream = quote::quote! {
+            struct Config {
+                #[check(name = "Enable", default = true, tooltip = "Apply the effect")]
+                enable: bool,
+            }
+        };
+        let output = filter_config_items(input).unwrap();
+        insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
+    }
+
     #[test]
     fn test_checksection() {
         let input: proc_macro2::TokenStream = quote::quote! {
@@ -1987,6 +2548,20 @@ mod tests {
         insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
     }
 
+    #[test]
+    fn test_color_with_alpha_track() {
+        let input: proc_macro2::TokenStream = quote::quote! {
+            struct Config {
+                #[color(name = "Fill", default = "#FF8000", with_alpha_track = "不透明度")]
+                fill: aviutl2::filter::RgbaColorValue,
+                #[track(name = "Size", range = 0.0..=100.0, step = 1.0, default = 10.0)]
+                size: f64,
+            }
+        };
+        let output = filter_config_items(input).unwrap();
+        insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
+    }
+
     #[test]
     fn test_select() {
         let input: proc_macro2::TokenStream = quote::quote! {
@@ -2056,6 +2631,48 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_track_error_messages() {
+        let error_of = |input: proc_macro2::TokenStream| match filter_config_items(input) {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.to_string(),
+        };
+
+        let out_of_range = error_of(quote::quote! {
+            struct Config {
+                #[track(name = "Volume", range = 0..=100, step = 1.0, default = 150)]
+                volume: f64,
+            }
+        });
+        assert!(out_of_range.contains("default (150) must be within range 0..=100"));
+
+        let not_multiple = error_of(quote::quote! {
+            struct Config {
+                #[track(name = "Volume", range = 0.0..=1.0, step = 0.1, default = 0.15)]
+                volume: f64,
+            }
+        });
+        assert!(not_multiple.contains(
+            "default (0.15) must be a multiple of step 0.1; the nearest valid values are 0.1 and 0.2"
+        ));
+
+        let typo = error_of(quote::quote! {
+            struct Config {
+                #[track(name = "Volume", rangee = 0.0..=1.0, step = 0.1, default = 0.5)]
+                volume: f64,
+            }
+        });
+        assert!(typo.contains("Unknown attribute `rangee` for track; did you mean `range`?"));
+
+        let missing = error_of(quote::quote! {
+            struct Config {
+                #[track(name = "Volume", range = 0.0..=1.0, default = 0.5)]
+                volume: f64,
+            }
+        });
+        assert!(missing.contains("missing `step` for track"));
+    }
+
     #[test]
     fn test_group() {
         let input: proc_macro2::TokenStream = quote::quote! {
@@ -2215,4 +2832,162 @@ mod tests {
         let output = filter_config_items(input).unwrap();
         insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
     }
+
+    #[test]
+    fn test_enum() {
+        let input: proc_macro2::TokenStream = quote::quote! {
+            #[select(name = "Mode")]
+            enum Config {
+                #[variant(name = "MIDI")]
+                Midi {
+                    #[track(name = "Note", range = 0..=127, step = 1.0, default = 60)]
+                    note: f64,
+                },
+                #[variant(name = "Hz", default)]
+                Hz {
+                    #[track(name = "Frequency", range = 20.0..=20000.0, step = 1.0, default = 440.0)]
+                    frequency: f64,
+                },
+            }
+        };
+        let output = filter_config_items(input).unwrap();
+        insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
+    }
+
+    #[test]
+    fn test_enum_behavior() {
+        use aviutl2::filter::{FilterConfigItem, FilterConfigItems};
+
+        #[aviutl2::filter::filter_config_items]
+        #[derive(Debug, PartialEq)]
+        #[select(name = "Mode")]
+        enum Config {
+            #[variant(name = "MIDI")]
+            Midi {
+                #[track(name = "Volume", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+                volume: f64,
+                #[track(name = "Note", range = 0..=127, step = 1.0, default = 60, group = "Pitch")]
+                note: i32,
+            },
+            #[variant(name = "Hz")]
+            Hz {
+                #[track(name = "Volume", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+                volume: f64,
+                #[check(name = "Fine", default = false)]
+                fine: bool,
+                #[track(name = "Frequency", range = 20.0..=20000.0, step = 0.1, default = 440.0)]
+                frequency: f64,
+            },
+            Off,
+        }
+
+        fn set_variant(items: &mut [FilterConfigItem], value: i32) {
+            let FilterConfigItem::Select(select) = &mut items[0] else {
+                panic!("expected Select");
+            };
+            select.value = value;
+        }
+        fn set_track(items: &mut [FilterConfigItem], index: usize, value: f64) {
+            let FilterConfigItem::Track(track) = &mut items[index] else {
+                panic!("expected Track");
+            };
+            track.value = value;
+        }
+
+        let mut items = Config::to_config_items();
+        // セレクトボックス、Volume、Note、Fine、Frequency、Pitchグループ
+        assert_eq!(items.len(), 6);
+        assert_eq!(
+            Config::from_config_items(&items),
+            Config::Midi {
+                volume: 0.5,
+                note: 60
+            }
+        );
+        assert_eq!(Config::default(), Config::from_config_items(&items));
+
+        set_track(&mut items, 1, 0.25);
+        set_track(&mut items, 2, 64.0);
+        set_track(&mut items, 4, 880.0);
+        assert_eq!(
+            Config::from_config_items(&items),
+            Config::Midi {
+                volume: 0.25,
+                note: 64
+            }
+        );
+
+        set_variant(&mut items, 1);
+        let FilterConfigItem::Checkbox(fine) = &mut items[3] else {
+            panic!("expected Checkbox");
+        };
+        fine.value = true;
+        let hz = Config::from_config_items(&items);
+        assert_eq!(
+            hz,
+            Config::Hz {
+                volume: 0.25,
+                fine: true,
+                frequency: 880.0
+            }
+        );
+
+        set_variant(&mut items, 2);
+        let off = Config::from_config_items(&items);
+        assert_eq!(off, Config::Off);
+
+        assert_eq!(Config::default().active_item_indices(), vec![0, 1, 2, 5]);
+        assert_eq!(hz.active_item_indices(), vec![0, 1, 3, 4]);
+        assert_eq!(off.active_item_indices(), vec![0]);
+    }
+
+    #[test]
+    fn test_enum_conflicting_shared_field() {
+        let input: proc_macro2::TokenStream = quote::quote! {
+            enum Config {
+                A {
+                    #[track(name = "Volume", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+                    volume: f64,
+                },
+                B {
+                    #[track(name = "Volume", range = 0.0..=1.0, step = 0.01, default = 1.0)]
+                    volume: f64,
+                },
+            }
+        };
+        assert!(filter_config_items(input).is_err());
+    }
+
+    #[test]
+    fn test_enum_unsupported_variants() {
+        let tuple: proc_macro2::TokenStream = quote::quote! {
+            enum Config {
+                A(f64),
+            }
+        };
+        assert!(filter_config_items(tuple).is_err());
+
+        let group: proc_macro2::TokenStream = quote::quote! {
+            enum Config {
+                A {
+                    #[group(name = "Group")]
+                    group: group! {
+                        #[track(name = "Volume", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+                        volume: f64,
+                    },
+                },
+            }
+        };
+        assert!(filter_config_items(group).is_err());
+
+        let multiple_default: proc_macro2::TokenStream = quote::quote! {
+            enum Config {
+                #[variant(default)]
+                A,
+                #[variant(default)]
+                B,
+            }
+        };
+        assert!(filter_config_items(multiple_default).is_err());
+    }
 }
@@ -46,27 +46,25 @@ pub fn filter_config_select_items(
 
 fn parse_enum_variant(variant: &syn::Variant) -> Result<BaseEnumVariant, syn::Error> {
     let ident = variant.ident.clone();
-    let name = variant
+    let mut name = None;
+    if let Some(attr) = variant
         .attrs
         .iter()
         .find(|attr| attr.path().is_ident("item"))
-        .and_then(|attr| {
-            attr.parse_args_with(|input: syn::parse::ParseStream| {
-                let lookahead = input.lookahead1();
-                if lookahead.peek(syn::Ident) && input.peek2(syn::Token![=]) {
-                    let ident: syn::Ident = input.parse()?;
-                    if ident != "name" {
-                        return Err(syn::Error::new_spanned(ident, "Expected `name`"));
-                    }
-                    let _eq_token: syn::Token![=] = input.parse()?;
-                    let name_lit: syn::LitStr = input.parse()?;
-                    Ok(name_lit.value())
-                } else {
-                    Err(lookahead.error())
-                }
-            })
-            .ok()
-        });
+    {
+        attr.parse_nested_meta(|m| {
+            if m.path.is_ident("name") {
+                name = Some(m.value()?.parse::<syn::LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(crate::utils::unknown_attribute_error(
+                    &m.path,
+                    "item",
+                    &["name"],
+                ))
+            }
+        })?;
+    }
 
     let discriminant = variant.discriminant.as_ref().map(|(_, expr)| expr.clone());
     if !variant.fields.is_empty() {
@@ -61,19 +61,47 @@ struct Entry {
     has_self: bool,
     self_is_mut: bool,
     error_mode: ErrorMode,
+    enabled_if: Option<syn::Ident>,
+}
+
+struct MenuAttr {
+    name: String,
+    error_mode: ErrorMode,
+    enabled_if: Option<syn::Ident>,
 }
 
 fn parse_menu_attr(
     attr: syn::Attribute,
+    entry_type: EntryType,
     default_name: &str,
-) -> Result<(String, ErrorMode), proc_macro2::TokenStream> {
+) -> Result<MenuAttr, proc_macro2::TokenStream> {
     let mut name: Option<String> = None;
+    let mut path: Option<Vec<String>> = None;
     let mut error_mode = ErrorMode::Log;
+    let mut enabled_if: Option<syn::Ident> = None;
     attr.parse_nested_meta(|m| {
         if m.path.is_ident("name") {
             let value: syn::LitStr = m.value()?.parse()?;
             name = Some(value.value());
             Ok(())
+        } else if m.path.is_ident("path") {
+            let value: syn::LitStr = m.value()?.parse()?;
+            if entry_type != EntryType::Edit {
+                return Err(m.error("`path` is only supported for #[edit]"));
+            }
+            let segments: Vec<String> = value.value().split('/').map(str::to_string).collect();
+            if segments.iter().any(|segment| segment.is_empty()) {
+                return Err(syn::Error::new_spanned(
+                    &value,
+                    "`path` must not contain empty segments",
+                ));
+            }
+            path = Some(segments);
+            Ok(())
+        } else if m.path.is_ident("enabled_if") {
+            let value: syn::LitStr = m.value()?.parse()?;
+            enabled_if = Some(value.parse()?);
+            Ok(())
         } else if m.path.is_ident("error") {
             let value: syn::LitStr = m.value()?.parse()?;
             match value.value().as_str() {
@@ -84,11 +112,24 @@ fn parse_menu_attr(
             }
             Ok(())
         } else {
-            Err(m.error("expected `name` or `error`"))
+            Err(m.error("expected `name`, `path`, `enabled_if`, or `error`"))
         }
     })
     .map_err(|e| e.to_compile_error())?;
-    Ok((name.unwrap_or_else(|| default_name.to_string()), error_mode))
+    let name = name.unwrap_or_else(|| default_name.to_string());
+    // ホスト側は名前の`\`区切りで階層を作る
+    let name = match path {
+        Some(mut segments) => {
+            segments.push(name);
+            segments.join("\\")
+        }
+        None => name,
+    };
+    Ok(MenuAttr {
+        name,
+        error_mode,
+        enabled_if,
+    })
 }
 
 fn analyze_receiver(sig: &syn::Signature) -> Result<(bool, bool), proc_macro2::TokenStream> {
@@ -142,6 +183,7 @@ pub fn generic_menus(
     let impl_token = item.self_ty.to_token_stream();
 
     let mut entries: Vec<Entry> = Vec::new();
+    let mut unattributed_methods: Vec<syn::Ident> = Vec::new();
     for it in item.items.iter_mut() {
         let syn::ImplItem::Fn(method) = it else {
             continue;
@@ -150,19 +192,20 @@ pub fn generic_menus(
         let method_ident = method.sig.ident.clone();
         let menu_attrs = find_menu_attrs(&method.attrs);
         if menu_attrs.is_empty() {
-            return Err(syn::Error::new_spanned(
-                &method.sig.ident,
-                format!("method must have one of {}", all_entry_types_display()),
-            )
-            .to_compile_error());
+            // `enabled_if`で参照されるメソッドかどうかは全てのメソッドを見てから判定する
+            unattributed_methods.push(method_ident);
+            continue;
         }
 
         // Analyze receiver
         let (has_self, self_is_mut) = analyze_receiver(&method.sig)?;
         let has_multiple_attrs = menu_attrs.len() > 1;
         for (menu_attr_index, (attr_idx, entry_type)) in menu_attrs.into_iter().enumerate() {
-            let (menu_name, error_mode) =
-                parse_menu_attr(method.attrs[attr_idx].clone(), &method_ident.to_string())?;
+            let menu_attr = parse_menu_attr(
+                method.attrs[attr_idx].clone(),
+                entry_type,
+                &method_ident.to_string(),
+            )?;
             let wrapper_name = if has_multiple_attrs {
                 format!("bridge_{}_{}", method_ident, menu_attr_index)
             } else {
@@ -171,17 +214,33 @@ pub fn generic_menus(
 
             entries.push(Entry {
                 entry_type,
-                menu_name,
+                menu_name: menu_attr.name,
                 method_ident: method_ident.clone(),
                 wrapper_ident: syn::Ident::new(&wrapper_name, method_ident.span()),
                 has_self,
                 self_is_mut,
-                error_mode,
+                error_mode: menu_attr.error_mode,
+                enabled_if: menu_attr.enabled_if,
             });
         }
         method.attrs.retain(|attr| menu_attr_type(attr).is_none());
     }
 
+    if let Some(method_ident) = unattributed_methods.iter().find(|method_ident| {
+        !entries
+            .iter()
+            .any(|e| e.enabled_if.as_ref() == Some(*method_ident))
+    }) {
+        return Err(syn::Error::new_spanned(
+            method_ident,
+            format!(
+                "method must have one of {}, or be referenced by `enabled_if`",
+                all_entry_types_display()
+            ),
+        )
+        .to_compile_error());
+    }
+
     // Build registration lines and wrapper fn bodies
     let mut register_lines: Vec<proc_macro2::TokenStream> = Vec::new();
     let mut wrappers: Vec<proc_macro2::TokenStream> = Vec::new();
@@ -218,6 +277,18 @@ pub fn generic_menus(
         };
         register_lines.push(reg);
 
+        // ホストにはメニューを無効化するAPIがないので、呼び出し時に判定して何もしないようにする
+        let enabled_guard = e.enabled_if.as_ref().map(|enabled_if| {
+            quote::quote! {
+                if !<#impl_token as ::aviutl2::generic::GenericPlugin>::with_instance(|__self| {
+                    <#impl_token>::#enabled_if(__self)
+                }) {
+                    ::aviutl2::tracing::debug!("Menu {} is disabled", #name_str);
+                    return;
+                }
+            }
+        });
+
         let call_on_error = match e.error_mode {
             ErrorMode::Ignore => quote::quote! { let _ = ret; },
             ErrorMode::LogOnly => quote::quote! { ::aviutl2::common::__output_log_if_error(ret); },
@@ -285,6 +356,10 @@ pub fn generic_menus(
                 #call_on_error
             }
         };
+        let wrapper_body = quote::quote! {
+            #enabled_guard
+            #wrapper_body
+        };
         let wrapper = if unwind {
             let method_name_str = method_ident.to_string();
             if e.entry_type == EntryType::Config {
@@ -544,6 +619,59 @@ mod tests {
         insta::assert_snapshot!(format_tokens(output));
     }
 
+    #[test]
+    fn test_edit_with_path_and_enabled_if() {
+        let input = quote::quote! {
+            impl MyPlugin {
+                #[edit(name = "左揃え", path = "Rustyツール/整列", enabled_if = "has_selection")]
+                fn align_left(&mut self) -> Result<(), ()> {
+                    Ok(())
+                }
+
+                #[object(name = "オブジェクト", enabled_if = "has_selection", error = "ignore")]
+                fn object_menu() -> Result<(), ()> {
+                    Ok(())
+                }
+
+                fn has_selection(&self) -> bool {
+                    true
+                }
+            }
+        };
+        let output = generic_menus(proc_macro2::TokenStream::new(), input).unwrap();
+        insta::assert_snapshot!(format_tokens(output));
+    }
+
+    #[test]
+    fn test_path_is_only_for_edit() {
+        let input = quote::quote! {
+            impl MyPlugin {
+                #[export(name = "Export", path = "Rustyツール")]
+                fn export_menu() -> Result<(), ()> {
+                    Ok(())
+                }
+            }
+        };
+        assert!(generic_menus(proc_macro2::TokenStream::new(), input).is_err());
+    }
+
+    #[test]
+    fn test_method_without_menu_attr() {
+        let input = quote::quote! {
+            impl MyPlugin {
+                #[edit(name = "Edit", enabled_if = "has_selection")]
+                fn edit_menu() -> Result<(), ()> {
+                    Ok(())
+                }
+
+                fn helper(&self) -> bool {
+                    true
+                }
+            }
+        };
+        assert!(generic_menus(proc_macro2::TokenStream::new(), input).is_err());
+    }
+
     fn format_tokens(tokens: proc_macro2::TokenStream) -> String {
         let replaced = tokens
             .to_string()
@@ -44,6 +44,7 @@ mod utils;
 /// ```
 ///
 /// - `name`: トラックバーの名前。省略した場合、フィールド名が使用されます。
+/// - `tooltip`: 設定項目の説明。AviUtl2には説明を表示する欄がないため、プラグインの情報に追記されます。省略した場合、説明はありません。
 /// - `range`: トラックバーの範囲。閉区間で指定します（例: `0.0..=1.0`）。
 /// - `default`: トラックバーの初期値。
 /// - `step`: トラックバーのステップ値。`1.0`, `0.1`, `0.01`, `0.001` のいずれかを指定します。
@@ -60,12 +61,17 @@ mod utils;
 /// ```rust
 /// # #[aviutl2_macros::filter_config_items]
 /// # struct S {
-/// #[check(name = "サンプルチェックボックス", default = true)]
+/// #[check(
+///     name = "サンプルチェックボックス",
+///     default = true,
+///     tooltip = "有効にすると効果を適用します。"
+/// )]
 /// bool_field: bool,
 /// # }
 /// ```
 ///
 /// - `name`: チェックボックスの名前。省略した場合、フィールド名が使用されます。
+/// - `tooltip`: 設定項目の説明。AviUtl2には説明を表示する欄がないため、プラグインの情報に追記されます。省略した場合、説明はありません。
 /// - `default`: チェックボックスの初期値。
 ///
 /// - 値の型は`bool`である必要があります。
@@ -81,6 +87,7 @@ mod utils;
 /// ```
 ///
 /// - `name`: セクションごとのチェックボックスの名前。省略した場合、フィールド名が使用されます。
+/// - `tooltip`: 設定項目の説明。AviUtl2には説明を表示する欄がないため、プラグインの情報に追記されます。省略した場合、説明はありません。
 /// - `default`: セクションごとのチェックボックスの初期値。
 ///
 /// - 値の型は`bool`である必要があります。
@@ -96,13 +103,19 @@ mod utils;
 /// color_field2: aviutl2::filter::FilterConfigColorValue,
 /// #[color(name = "サンプルカラー3", default = (72, 176, 213))]
 /// color_field3: aviutl2::filter::FilterConfigColorValue,
+/// #[color(name = "サンプルカラー4", default = "#48b0d5", with_alpha_track = "不透明度")]
+/// color_field4: aviutl2::filter::RgbaColorValue,
 /// # }
 /// ```
 ///
 /// - `name`: 色選択の名前。省略した場合、フィールド名が使用されます。
+/// - `tooltip`: 設定項目の説明。AviUtl2には説明を表示する欄がないため、プラグインの情報に追記されます。省略した場合、説明はありません。
 /// - `default`: 色の初期値。`0xRRGGBB`形式の整数、`"#RRGGBB"`形式の文字列、または`(R, G, B)`形式のタプルで指定します。
+/// - `with_alpha_track`: 指定した場合、色選択の直後にこの名前の0〜100のトラックバーを追加し、不透明度として扱います。
+///   初期値は100（不透明）です。
 ///
 /// - 値の型は`From<aviutl2::filter::FilterConfigColorValue>`を実装している必要があります。
+///   `with_alpha_track`を指定した場合は`From<aviutl2::filter::RgbaColorValue>`を実装している必要があります。
 ///
 /// ## `select`
 ///
@@ -139,6 +152,7 @@ mod utils;
 /// ```
 ///
 /// - `name`: セレクトボックスの名前。省略した場合、フィールド名が使用されます。
+/// - `tooltip`: 設定項目の説明。AviUtl2には説明を表示する欄がないため、プラグインの情報に追記されます。省略した場合、説明はありません。
 /// - `items`: セレクトボックスの項目のリスト、または`aviutl2::filter::FilterConfigSelectItems`を実装したenumの名前。
 /// - `default`: セレクトボックスの初期値。`items`のインデックス、またはenumのVariantを指定します。
 ///
@@ -193,6 +207,7 @@ mod utils;
 /// ```
 ///
 /// - `name`: ファイル選択の名前。省略した場合、フィールド名が使用されます。
+/// - `tooltip`: 設定項目の説明。AviUtl2には説明を表示する欄がないため、プラグインの情報に追記されます。省略した場合、説明はありません。
 /// - `filters`: ファイルフィルタのリスト。キーがフィルタ名、値が拡張子のリストです。
 /// - `default`: ファイルの初期値。省略した場合、空文字列または`None`になります。
 ///
@@ -331,6 +346,54 @@ mod utils;
 /// }
 /// ```
 ///
+/// # enum
+///
+/// enumに適用すると、Variantを選ぶセレクトボックスと、全Variantのフィールドを合わせた設定項目が生成されます。
+/// `from_config_items`はセレクトボックスで選ばれたVariantを作成し、他のVariantのフィールドは無視します。
+///
+/// ```rust
+/// use aviutl2::filter::FilterConfigItems;
+///
+/// #[aviutl2_macros::filter_config_items]
+/// #[derive(Debug, PartialEq)]
+/// #[select(name = "周波数モード")]
+/// enum FrequencyConfig {
+///     #[variant(name = "MIDIノート")]
+///     Midi {
+///         #[track(name = "音量", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+///         volume: f64,
+///         #[track(name = "MIDIノート", range = 0..=127, step = 1.0, default = 60)]
+///         note: f64,
+///     },
+///     #[variant(name = "周波数（Hz）", default)]
+///     Hz {
+///         #[track(name = "音量", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+///         volume: f64,
+///         #[track(name = "周波数（Hz）", range = 20.0..=20000.0, step = 1.0, default = 440.0)]
+///         frequency: f64,
+///     },
+/// }
+///
+/// // 周波数モード、音量、MIDIノート、周波数（Hz）の順に並ぶ
+/// assert_eq!(FrequencyConfig::to_config_items().len(), 4);
+/// assert_eq!(
+///     FrequencyConfig::default(),
+///     FrequencyConfig::Hz { volume: 0.5, frequency: 440.0 }
+/// );
+/// assert_eq!(FrequencyConfig::default().active_item_indices(), vec![0, 1, 3]);
+/// ```
+///
+/// - enumの`select`属性の`name`: Variantを選ぶセレクトボックスの名前。省略した場合、enumの名前が使用されます。
+/// - `variant`属性の`name`: セレクトボックスの項目の名前。省略した場合、Variantの名前が使用されます。
+/// - `variant`属性の`default`: 初期値にするVariant。省略した場合、最初のVariantが初期値になります。
+///
+/// - Variantは名前付きフィールドを持つか、フィールドを持たない必要があります。
+/// - 複数のVariantに同じ名前のフィールドがある場合、1つの設定項目として扱われます。
+///   この場合、属性と型が同じである必要があります。
+/// - Variantの中では`group`は使えません。
+/// - `active_item_indices`メソッドが生成されます。現在のVariantで使われる設定項目の、
+///   `to_config_items`の戻り値の中でのインデックスを返します。
+///
 /// # See Also
 ///
 /// - [`FilterConfigSelectItems`]
@@ -480,6 +543,64 @@ pub fn into_script_module_return_value(item: proc_macro::TokenStream) -> proc_ma
 /// }
 /// ```
 ///
+/// # Return Values
+///
+/// 戻り値の型は`IntoScriptModuleReturnValue`を実装している必要があります。
+///
+/// - `Result<T, E>`（`E: Display`）を返すと、`Ok`の場合は`T`を返し、
+///   `Err`の場合はエラーを`Display`で文字列にしてスクリプトのエラーとして設定します（値は返しません）。
+///   `anyhow::Result<T>`や`aviutl2::AnyResult<T>`も使えます。
+/// - タプルを返すと、複数の値を返します。
+///   全ての値を変換・確認してから返すため、途中の値で失敗した場合も一部の値だけが返ることはありません。
+/// - `Option<T>`は`None`の場合は何も返しません。
+///   `nil`を返すことはできないため、タプルの中では最後に置く必要があります（途中に置くとコンパイルエラーになります）。
+///
+/// ```rust
+/// # #[aviutl2::plugin(ScriptModule)]
+/// # struct MyModule;
+/// # impl aviutl2::module::ScriptModule for MyModule {
+/// #     fn new(info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+/// #         let _ = info;
+/// #         unimplemented!()
+/// #     }
+/// #     fn plugin_info(&self) -> aviutl2::module::ScriptModuleTable {
+/// #         unimplemented!()
+/// #     }
+/// # }
+/// struct UnknownColor(String);
+///
+/// impl std::fmt::Display for UnknownColor {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "unknown color: {}", self.0)
+///     }
+/// }
+///
+/// #[aviutl2::module::functions]
+/// impl MyModule {
+///     // `Err`の場合は「unknown color: ...」というエラーになる
+///     fn color_code(name: String) -> Result<i32, UnknownColor> {
+///         match name.as_str() {
+///             "red" => Ok(0xff0000),
+///             "green" => Ok(0x00ff00),
+///             _ => Err(UnknownColor(name)),
+///         }
+///     }
+///
+///     // 区切りが無い場合は1つの値だけを返す
+///     fn split_once(text: String) -> (String, Option<String>) {
+///         match text.split_once(',') {
+///             Some((head, tail)) => (head.to_string(), Some(tail.to_string())),
+///             None => (text, None),
+///         }
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```lua
+/// local head, tail = my_module.split_once("a,b")
+/// ```
+///
 /// # Attributes
 ///
 /// ### `direct`
@@ -567,10 +688,93 @@ pub fn into_script_module_return_value(item: proc_macro::TokenStream) -> proc_ma
 /// # fn main() {}
 /// ```
 ///
+/// ### `constants`
+///
+/// スクリプトに公開する定数の一覧を返す関数として登録します。
+/// この関数はスクリプトの関数としては登録されず、`ScriptModuleFunctions::constants`から呼び出されます。
+/// 引数を取らず、`Vec<(String, aviutl2::module::ScriptModuleValue)>`を返す必要があります。
+///
+/// `ScriptModuleTable::constants`に渡すと、定数名から値を返す`get_constant`関数がモジュールに追加されます。
+///
+/// ```rust
+/// # #[aviutl2::plugin(ScriptModule)]
+/// # struct MyModule;
+/// # impl aviutl2::module::ScriptModule for MyModule {
+/// #     fn new(info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+/// #         let _ = info;
+/// #         unimplemented!()
+/// #     }
+/// #     fn plugin_info(&self) -> aviutl2::module::ScriptModuleTable {
+/// #         unimplemented!()
+/// #     }
+/// # }
+/// #[aviutl2::module::functions]
+/// impl MyModule {
+///     #[constants]
+///     fn constants() -> Vec<(String, aviutl2::module::ScriptModuleValue)> {
+///         vec![
+///             ("MODE_FAST".to_string(), 0.into()),
+///             ("MODE_SLOW".to_string(), 1.into()),
+///         ]
+///     }
+/// }
+/// ```
+///
+/// ```lua
+/// local mode = my_module.get_constant("MODE_FAST")
+/// ```
+///
 /// ### `unwind`
 ///
 /// 関数呼び出し時のpanicを捕捉するかどうかを指定します。
 /// デフォルトは`true`です。
+///
+/// # Introspection
+///
+/// 関数名・引数・戻り値の型・ドキュメントコメントから`ScriptModuleFunctions::function_metadata`が生成されます。
+/// `ScriptModuleTable::include_introspection`が`true`の場合、これらの情報をJSON文字列として返す
+/// `__functions`関数がモジュールに追加されます。
+///
+/// ```lua
+/// local functions = my_module.__functions()
+/// -- [{"name":"sum","params":[{"name":"a","type":"i32"},{"name":"b","type":"i32"}],"returns":"i32","doc":""}]
+/// ```
+///
+/// `#[direct]`な関数の場合、`params`は空になり、`returns`は`null`になります。
+///
+/// # Call Context
+///
+/// 関数の中では`aviutl2::module::current_call()`で呼び出しごとの情報を取得できます。
+/// `ScriptModuleTable::default_time_budget`を設定すると、その時間を超えた後に`CallContext::check`がエラーを返すようになります。
+/// 呼び出し元のオブジェクトの情報は`aviutl2::module::current_object_context()`で取得できます（現在のAviUtl2では常に`None`です）。
+///
+/// ```rust
+/// # #[aviutl2::plugin(ScriptModule)]
+/// # struct MyModule;
+/// # impl aviutl2::module::ScriptModule for MyModule {
+/// #     fn new(info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+/// #         let _ = info;
+/// #         unimplemented!()
+/// #     }
+/// #     fn plugin_info(&self) -> aviutl2::module::ScriptModuleTable {
+/// #         unimplemented!()
+/// #     }
+/// # }
+/// #[aviutl2::module::functions]
+/// impl MyModule {
+///     fn count_up(limit: i32) -> aviutl2::AnyResult<i32> {
+///         let call = aviutl2::module::current_call();
+///         let mut count = 0;
+///         while count < limit {
+///             // 制限時間を超えていたら`time budget exceeded`というエラーになる
+///             call.check()?;
+///             count += 1;
+///         }
+///         Ok(count)
+///     }
+/// }
+/// # fn main() {}
+/// ```
 #[proc_macro_attribute]
 pub fn module_functions(
     attr: proc_macro::TokenStream,
@@ -628,6 +832,21 @@ pub fn script_module_direct_callback(input: proc_macro::TokenStream) -> proc_mac
 /// # Attributes
 ///
 /// - 引数には`InputPlugin`、`OutputPlugin`、`FilterPlugin`、`ScriptModule`、`GenericPlugin`のいずれかを指定します。
+/// - `FilterPlugin`の場合、`lazy`を指定すると、`new()`の呼び出しを最初の描画処理まで遅延させます。
+///   このとき、構造体は[`Default`]を実装している必要があり、`Default`で作成したインスタンスが
+///   プラグイン情報の取得に使われます。
+/// - `FilterPlugin`の場合、`label = "..."`と`order = ...`を指定すると、`plugin_info()`が
+///   `FilterPluginTable::label`を`None`、`FilterPluginTable::order`を`0`のままにしたときに、その値で埋めます。
+///   汎用プラグインから複数のフィルタを登録する場合、同じ`label`でグループにまとめ、`order`の小さい順に並べられます。
+/// - `FilterPlugin`の場合、`name = "..."`と`config_items = 型`を指定すると、`new()`が失敗したときも
+///   そのプラグイン名と、[`FilterConfigItems`](aviutl2::filter::FilterConfigItems)を実装した型の設定項目で登録します。
+///   指定しない場合は型名で登録されるため、プロジェクトに保存された設定が失われます。
+///   `plugin_info()`と同じ値を指定してください。
+/// - `GenericPlugin`の場合、`config`を指定すると、プラグイン名で設定メニューを登録し、
+///   選択されたときに`GenericPlugin::config`を呼び出します。
+///   Rustではトレイトの関数を実装したかどうかを判定できないため、`config`を実装した場合は明示的に指定してください。
+///
+/// `new()`が失敗した場合もプラグインは登録され、最初に処理が呼ばれたときにエラー内容がログに出力されます。
 ///
 /// # Example
 ///
@@ -655,6 +874,105 @@ pub fn script_module_direct_callback(input: proc_macro::TokenStream) -> proc_mac
 ///
 /// # fn main() {}
 /// ```
+///
+/// ```rust
+/// #[aviutl2::plugin(FilterPlugin, lazy)]
+/// #[derive(Default)]
+/// struct MyFilterPlugin {
+///     // 初期化に時間がかかるもの
+///     device: Option<String>,
+/// }
+///
+/// impl aviutl2::filter::FilterPlugin for MyFilterPlugin {
+///     // ...
+/// #   fn new(info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+/// #       let _ = info;
+/// #       todo!()
+/// #   }
+/// #
+/// #   fn plugin_info(&self) -> aviutl2::filter::FilterPluginTable {
+/// #       todo!()
+/// #   }
+/// }
+///
+/// aviutl2::register_filter_plugin!(MyFilterPlugin);
+///
+/// # fn main() {}
+/// ```
+///
+/// ```rust
+/// #[aviutl2::plugin(FilterPlugin, label = "Rustyツール", order = 10)]
+/// struct MyToolFilter;
+///
+/// impl aviutl2::filter::FilterPlugin for MyToolFilter {
+///     // ...
+/// #   fn new(info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+/// #       let _ = info;
+/// #       todo!()
+/// #   }
+/// #
+/// #   fn plugin_info(&self) -> aviutl2::filter::FilterPluginTable {
+/// #       todo!()
+/// #   }
+/// }
+///
+/// # fn main() {}
+/// ```
+///
+/// ```rust
+/// #[aviutl2::filter::filter_config_items]
+/// struct MyFilterConfig {
+///     #[track(name = "強さ", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+///     strength: f64,
+/// }
+///
+/// #[aviutl2::plugin(FilterPlugin, name = "Rusty Filter", config_items = MyFilterConfig)]
+/// struct MyDeclaredFilter;
+///
+/// impl aviutl2::filter::FilterPlugin for MyDeclaredFilter {
+///     // ...
+/// #   fn new(info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+/// #       let _ = info;
+/// #       todo!()
+/// #   }
+/// #
+/// #   fn plugin_info(&self) -> aviutl2::filter::FilterPluginTable {
+/// #       todo!()
+/// #   }
+/// }
+///
+/// # fn main() {}
+/// ```
+///
+/// ```rust
+/// #[aviutl2::plugin(GenericPlugin, config)]
+/// struct MyGenericPlugin;
+///
+/// impl aviutl2::generic::GenericPlugin for MyGenericPlugin {
+///     // ...
+/// #   fn new(info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+/// #       let _ = info;
+/// #       todo!()
+/// #   }
+/// #
+/// #   fn plugin_info(&self) -> aviutl2::generic::GenericPluginTable {
+/// #       todo!()
+/// #   }
+/// #
+/// #   fn register(&mut self, registry: &mut aviutl2::generic::HostAppHandle) {
+/// #       let _ = registry;
+/// #   }
+///     fn config(&mut self, hwnd: aviutl2::Win32WindowHandle) -> aviutl2::AnyResult<()> {
+///         // 設定ダイアログを表示する
+/// #       let _ = hwnd;
+///         Ok(())
+///     }
+/// }
+///
+/// aviutl2::register_generic_plugin!(MyGenericPlugin);
+///
+/// # fn main() {}
+/// ```
 #[proc_macro_attribute]
 pub fn plugin(
     attr: proc_macro::TokenStream,
@@ -682,6 +1000,10 @@ pub fn plugin(
 ///   - `"log"`: エラー発生時にログにエラーメッセージを出力し、警告音を鳴らします。
 ///   - `"log_only"`: エラー発生時にログにエラーメッセージを出力します。
 ///   - `"ignore"`: エラー発生時に何も行いません。
+/// - `enabled_if`: メニューが有効かどうかを返すメソッドの名前を指定します。
+///   メソッドは`fn(&self) -> bool`のシグネチャを持つ必要があり、同じ`impl`ブロック内に属性なしで定義できます。
+///   ホストにはメニューを無効化（グレーアウト）する仕組みがないため、メニューは常に表示され、
+///   選択時に`false`を返した場合は関数を呼び出さずに何もしません。
 ///
 /// 関数は以下のシグネチャのうちいずれかを持つ必要があります：
 /// ```rust
@@ -821,6 +1143,9 @@ pub fn plugin(
 /// 編集メニューとして登録します。
 /// パラメーター、シグネチャは`import`属性と同様です。
 ///
+/// - `path`: サブメニューの階層を`/`区切りで指定します。
+///   例えば`#[edit(name = "左揃え", path = "Rustyツール/整列")]`は「Rustyツール > 整列 > 左揃え」として登録されます。
+///
 /// ### `config`
 ///
 /// 設定メニューとして登録します。
@@ -933,6 +1258,17 @@ pub fn plugin(
 /// #       Ok(())
 ///     }
 ///
+///     #[edit(name = "左揃え", path = "Rustyツール/整列", enabled_if = "has_selection")]
+///     fn align_left(&self) -> aviutl2::AnyResult<()> {
+///         // ...
+/// #       Ok(())
+///     }
+///
+///     fn has_selection(&self) -> bool {
+///         // ...
+/// #       true
+///     }
+///
 ///     #[config(name = "オブジェクトを削除")]
 ///     fn show_config(hwnd: aviutl2::Win32WindowHandle) -> aviutl2::AnyResult<()> {
 ///         // ...
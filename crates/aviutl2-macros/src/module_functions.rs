@@ -12,16 +12,34 @@ pub fn module_functions(
     let mut item = parse_inherent_impl(item, "module_functions")?;
     let impl_token = item.self_ty.to_token_stream();
 
-    let (function_tables, function_impls): (
-        Vec<proc_macro2::TokenStream>,
-        Vec<proc_macro2::TokenStream>,
-    ) = item
-        .items
-        .iter_mut()
-        .map(|item| create_bridge(&impl_token, item, unwind))
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .unzip();
+    let mut function_tables = Vec::new();
+    let mut function_impls = Vec::new();
+    let mut function_metadata = Vec::new();
+    let mut constants_fn: Option<syn::Ident> = None;
+    for item in item.items.iter_mut() {
+        if let Some(ident) = take_constants_fn(item)? {
+            if constants_fn.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &ident,
+                    "only one `#[constants]` function is allowed",
+                )
+                .to_compile_error());
+            }
+            constants_fn = Some(ident);
+            continue;
+        }
+        let (func_table, func_impl, metadata) = create_bridge(&impl_token, item, unwind)?;
+        function_tables.push(func_table);
+        function_impls.push(func_impl);
+        function_metadata.push(metadata);
+    }
+    let constants_impl = constants_fn.map(|ident| {
+        quote::quote! {
+            fn constants() -> Vec<(String, ::aviutl2::module::ScriptModuleValue)> {
+                <#impl_token>::#ident()
+            }
+        }
+    });
 
     Ok(quote::quote! {
         #item
@@ -35,18 +53,58 @@ pub fn module_functions(
 
                     #(#function_impls)*
                 }
+
+                fn function_metadata() -> Vec<::aviutl2::module::ModuleFunctionMetadata> {
+                    vec![#(#function_metadata),*]
+                }
+
+                #constants_impl
             }
         }
     })
 }
 
+/// `#[constants]`が付いた関数なら、属性を取り除いて関数名を返す。
+fn take_constants_fn(
+    item: &mut syn::ImplItem,
+) -> Result<Option<syn::Ident>, proc_macro2::TokenStream> {
+    let syn::ImplItem::Fn(method) = item else {
+        return Ok(None);
+    };
+    let Some(index) = method
+        .attrs
+        .iter()
+        .position(|attr| attr.path().is_ident("constants"))
+    else {
+        return Ok(None);
+    };
+    method.attrs.remove(index);
+    if !method.sig.inputs.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &method.sig.inputs,
+            "`#[constants]` function must not take any arguments",
+        )
+        .to_compile_error());
+    }
+    Ok(Some(method.sig.ident.clone()))
+}
+
 fn create_bridge(
     impl_token: &proc_macro2::TokenStream,
     item: &mut syn::ImplItem,
     unwind: bool,
-) -> Result<(proc_macro2::TokenStream, proc_macro2::TokenStream), proc_macro2::TokenStream> {
+) -> Result<
+    (
+        proc_macro2::TokenStream,
+        proc_macro2::TokenStream,
+        proc_macro2::TokenStream,
+    ),
+    proc_macro2::TokenStream,
+> {
     match item {
         syn::ImplItem::Fn(method) => {
+            // `#[direct]`は`create_method_bridge`で取り除かれるので、先にメタデータを作る
+            let metadata = create_metadata(method);
             let bridge =
                 create_method_bridge(impl_token, method, ReceiverKind::ScriptModuleSingleton)?;
             let method_name_str = &bridge.method_name_str;
@@ -57,15 +115,16 @@ fn create_bridge(
                     func: #internal_method_name,
                 });
             };
-            let func_impl = wrap_with_unwind(
-                internal_method_name,
-                method_name_str,
-                &bridge.body,
-                false,
-                unwind,
-            );
-
-            Ok((func_table, func_impl))
+            let body = &bridge.body;
+            let body = quote::quote! {
+                ::aviutl2::module::__with_call_context::<#impl_token, _>(|| {
+                    #body
+                });
+            };
+            let func_impl =
+                wrap_with_unwind(internal_method_name, method_name_str, &body, false, unwind);
+
+            Ok((func_table, func_impl, metadata))
         }
         _ => Err(syn::Error::new_spanned(
             item,
@@ -75,6 +134,96 @@ fn create_bridge(
     }
 }
 
+fn create_metadata(method: &syn::ImplItemFn) -> proc_macro2::TokenStream {
+    let name = method.sig.ident.to_string();
+    let doc = method
+        .attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }),
+                ..
+            }) if path.is_ident("doc") => Some(lit.value()),
+            _ => None,
+        })
+        .map(|line| {
+            line.strip_prefix(' ')
+                .unwrap_or(&line)
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string();
+    let is_direct = method
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("direct"));
+
+    let (params, returns) = if is_direct {
+        (Vec::new(), quote::quote! { ::std::option::Option::None })
+    } else {
+        let params = method
+            .sig
+            .inputs
+            .iter()
+            .filter_map(|param| match param {
+                syn::FnArg::Typed(pat_type) => Some(pat_type),
+                syn::FnArg::Receiver(_) => None,
+            })
+            .map(|pat_type| {
+                let param_name = match &*pat_type.pat {
+                    syn::Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
+                    pat => pat.to_token_stream().to_string(),
+                };
+                let param_ty = type_to_string(&pat_type.ty);
+                quote::quote! {
+                    ::aviutl2::module::ModuleFunctionParamMetadata {
+                        name: #param_name.to_string(),
+                        ty: #param_ty.to_string(),
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        let returns = match &method.sig.output {
+            syn::ReturnType::Default => "()".to_string(),
+            syn::ReturnType::Type(_, ty) => type_to_string(ty),
+        };
+        (
+            params,
+            quote::quote! { ::std::option::Option::Some(#returns.to_string()) },
+        )
+    };
+
+    quote::quote! {
+        ::aviutl2::module::ModuleFunctionMetadata {
+            name: #name.to_string(),
+            params: vec![#(#params),*],
+            returns: #returns,
+            doc: #doc.to_string(),
+        }
+    }
+}
+
+/// 型をLua側に見せるための文字列にする。
+fn type_to_string(ty: &syn::Type) -> String {
+    ty.to_token_stream()
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(":: ", "::")
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace("& ", "&")
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -149,6 +298,109 @@ mod tests {
         insta::assert_snapshot!(format_tokens(output));
     }
 
+    #[test]
+    fn test_username_module_metadata() {
+        let input: proc_macro2::TokenStream = quote::quote! {
+            impl UsernameModule {
+                /// 現在のユーザー名を返す。
+                fn get_username(&self) -> aviutl2::AnyResult<String> {
+                    Ok(whoami::username()?)
+                }
+            }
+        };
+        let output = module_functions(proc_macro2::TokenStream::new(), input).unwrap();
+        insta::assert_snapshot!(format_tokens(output));
+    }
+
+    #[test]
+    fn test_result_with_display_error() {
+        let input: proc_macro2::TokenStream = quote::quote! {
+            impl MyModule {
+                fn parse_number(text: String) -> Result<i32, ParseError> {
+                    text.parse().map_err(|_| ParseError(text))
+                }
+            }
+        };
+        let output = module_functions(proc_macro2::TokenStream::new(), input).unwrap();
+        insta::assert_snapshot!(format_tokens(output));
+    }
+
+    #[test]
+    fn test_result_tuple_with_trailing_option() {
+        let input: proc_macro2::TokenStream = quote::quote! {
+            impl MyModule {
+                fn split_once(text: String) -> anyhow::Result<(String, Option<String>)> {
+                    match text.split_once(',') {
+                        Some((head, tail)) => Ok((head.to_string(), Some(tail.to_string()))),
+                        None => Ok((text, None)),
+                    }
+                }
+            }
+        };
+        let output = module_functions(proc_macro2::TokenStream::new(), input).unwrap();
+        insta::assert_snapshot!(format_tokens(output));
+    }
+
+    #[test]
+    fn test_option_in_middle_of_tuple() {
+        for return_type in [
+            quote::quote! { (Option<i32>, i32) },
+            quote::quote! { aviutl2::AnyResult<(String, Option<i32>, f64)> },
+        ] {
+            let input: proc_macro2::TokenStream = quote::quote! {
+                impl MyModule {
+                    fn my_function() -> #return_type {
+                        todo!()
+                    }
+                }
+            };
+            assert!(module_functions(proc_macro2::TokenStream::new(), input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_constants() {
+        let input: proc_macro2::TokenStream = quote::quote! {
+            impl MyModule {
+                #[constants]
+                fn constants() -> Vec<(String, aviutl2::module::ScriptModuleValue)> {
+                    vec![("VERSION".to_string(), "1.0.0".into())]
+                }
+            }
+        };
+        let output = module_functions(proc_macro2::TokenStream::new(), input).unwrap();
+        insta::assert_snapshot!(format_tokens(output));
+    }
+
+    #[test]
+    fn test_constants_with_args() {
+        let input: proc_macro2::TokenStream = quote::quote! {
+            impl MyModule {
+                #[constants]
+                fn constants(&self) -> Vec<(String, aviutl2::module::ScriptModuleValue)> {
+                    Vec::new()
+                }
+            }
+        };
+        assert!(module_functions(proc_macro2::TokenStream::new(), input).is_err());
+    }
+
+    #[test]
+    fn test_type_to_string() {
+        let cases: [(syn::Type, &str); 4] = [
+            (syn::parse_quote!(i32), "i32"),
+            (syn::parse_quote!(&str), "&str"),
+            (
+                syn::parse_quote!(aviutl2::AnyResult<Option<Vec<f64>>>),
+                "aviutl2::AnyResult<Option<Vec<f64>>>",
+            ),
+            (syn::parse_quote!((i32, String)), "(i32, String)"),
+        ];
+        for (ty, expected) in cases {
+            assert_eq!(type_to_string(&ty), expected);
+        }
+    }
+
     fn format_tokens(tokens: proc_macro2::TokenStream) -> String {
         let replaced = tokens
             .to_string()
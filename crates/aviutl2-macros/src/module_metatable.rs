@@ -142,11 +142,12 @@ fn lua_meta_method_name(
         .to_compile_error());
     }
     if !LUA_5_1_META_METHODS.contains(&method_name) {
-        return Err(syn::Error::new_spanned(
-            method,
-            format!("`module_metatable` method `{method_name}` is not a Lua 5.1 metatable method"),
-        )
-        .to_compile_error());
+        let mut message =
+            format!("`module_metatable` method `{method_name}` is not a Lua 5.1 metatable method");
+        if let Some(candidate) = crate::utils::suggest_similar(method_name, LUA_5_1_META_METHODS) {
+            message.push_str(&format!("; did you mean `{candidate}`?"));
+        }
+        return Err(syn::Error::new_spanned(method, message).to_compile_error());
     }
 
     Ok(format!("__{method_name}"))
@@ -234,6 +235,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_suggest_metatable_method() {
+        let input: proc_macro2::TokenStream = quote::quote! {
+            impl UserData {
+                fn tostirng(&self) -> String {
+                    self.name.clone()
+                }
+            }
+        };
+        let error = module_metatable(proc_macro2::TokenStream::new(), input).unwrap_err();
+        assert!(error.to_string().contains("did you mean `tostring`?"));
+    }
+
     fn format_tokens(tokens: proc_macro2::TokenStream) -> String {
         let replaced = tokens
             .to_string()
@@ -1,10 +1,151 @@
+use syn::punctuated::Punctuated;
+
+struct PluginAttr {
+    kind: syn::Ident,
+    lazy: bool,
+    config: bool,
+    label: Option<syn::Expr>,
+    order: Option<syn::Expr>,
+    name: Option<syn::Expr>,
+    config_items: Option<syn::Expr>,
+}
+
+impl syn::parse::Parse for PluginAttr {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let args = Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated(input)?;
+        let mut args = args.into_iter();
+        let Some(kind) = args.next() else {
+            return Err(input.error("expected plugin kind"));
+        };
+        let kind = kind.require_path_only()?.require_ident()?.clone();
+        let mut lazy = false;
+        let mut config = false;
+        let mut label = None;
+        let mut order = None;
+        let mut name = None;
+        let mut config_items = None;
+        for arg in args {
+            let arg = match arg {
+                syn::Meta::Path(path) => path.require_ident()?.clone(),
+                syn::Meta::NameValue(name_value) => {
+                    let (value, option) = if name_value.path.is_ident("label") {
+                        (&mut label, "label")
+                    } else if name_value.path.is_ident("order") {
+                        (&mut order, "order")
+                    } else if name_value.path.is_ident("name") {
+                        (&mut name, "name")
+                    } else if name_value.path.is_ident("config_items") {
+                        (&mut config_items, "config_items")
+                    } else {
+                        return Err(syn::Error::new_spanned(name_value.path, "unknown option"));
+                    };
+                    if value.is_some() {
+                        return Err(syn::Error::new_spanned(name_value.path, "duplicate option"));
+                    }
+                    if kind != "FilterPlugin" {
+                        return Err(syn::Error::new_spanned(
+                            name_value.path,
+                            format!("`{option}` is only supported for FilterPlugin"),
+                        ));
+                    }
+                    *value = Some(name_value.value);
+                    continue;
+                }
+                syn::Meta::List(list) => {
+                    return Err(syn::Error::new_spanned(list, "unknown option"));
+                }
+            };
+            let (flag, supported_kind) = if arg == "lazy" {
+                (&mut lazy, "FilterPlugin")
+            } else if arg == "config" {
+                (&mut config, "GenericPlugin")
+            } else {
+                return Err(syn::Error::new_spanned(arg, "unknown option"));
+            };
+            if *flag {
+                return Err(syn::Error::new_spanned(arg, "duplicate option"));
+            }
+            if kind != supported_kind {
+                return Err(syn::Error::new_spanned(
+                    &arg,
+                    format!("`{arg}` is only supported for {supported_kind}"),
+                ));
+            }
+            *flag = true;
+        }
+        Ok(Self {
+            kind,
+            lazy,
+            config,
+            label,
+            order,
+            name,
+            config_items,
+        })
+    }
+}
+
 pub fn plugin(
     attr: proc_macro2::TokenStream,
     item: proc_macro2::TokenStream,
 ) -> Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
-    let attr: syn::Ident = syn::parse2(attr).map_err(|e| e.to_compile_error())?;
+    let PluginAttr {
+        kind: attr,
+        lazy,
+        config,
+        label,
+        order,
+        name,
+        config_items,
+    } = syn::parse2(attr).map_err(|e| e.to_compile_error())?;
     let ast: syn::ItemStruct = syn::parse2(item.clone()).map_err(|e| e.to_compile_error())?;
     let struct_name = &ast.ident;
+    let lazy_default = lazy.then(|| {
+        quote::quote! {
+            fn __lazy_default() -> ::std::option::Option<Self> {
+                ::std::option::Option::Some(<Self as ::std::default::Default>::default())
+            }
+        }
+    });
+    let has_config = config.then(|| {
+        quote::quote! {
+            fn __has_config() -> bool {
+                true
+            }
+        }
+    });
+    let default_label = label.map(|label| {
+        quote::quote! {
+            fn __default_label() -> ::std::option::Option<&'static str> {
+                ::std::option::Option::Some(#label)
+            }
+        }
+    });
+    let default_order = order.map(|order| {
+        quote::quote! {
+            fn __default_order() -> i32 {
+                #order
+            }
+        }
+    });
+    let declared_name = name.map(|name| {
+        quote::quote! {
+            fn __declared_name() -> ::std::option::Option<&'static str> {
+                ::std::option::Option::Some(#name)
+            }
+        }
+    });
+    let declared_config_items = config_items.map(|config_items| {
+        quote::quote! {
+            fn __declared_config_items()
+                -> ::std::option::Option<::std::vec::Vec<::aviutl2::filter::FilterConfigItem>>
+            {
+                ::std::option::Option::Some(
+                    <#config_items as ::aviutl2::filter::FilterConfigItems>::to_config_items(),
+                )
+            }
+        }
+    });
     Ok(quote::quote! {
         #item
 
@@ -16,6 +157,141 @@ pub fn plugin(
                     ::std::sync::RwLock::new(None);
                 &PLUGIN
             }
+
+            #lazy_default
+            #has_config
+            #default_label
+            #default_order
+            #declared_name
+            #declared_config_items
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin() {
+        let output = plugin(
+            quote::quote! { FilterPlugin },
+            quote::quote! {
+                struct MyFilter;
+            },
+        )
+        .unwrap();
+        insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
+    }
+
+    #[test]
+    fn test_plugin_lazy() {
+        let output = plugin(
+            quote::quote! { FilterPlugin, lazy },
+            quote::quote! {
+                #[derive(Default)]
+                struct MyFilter {
+                    device: Option<String>,
+                }
+            },
+        )
+        .unwrap();
+        insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
+    }
+
+    #[test]
+    fn test_plugin_config() {
+        let output = plugin(
+            quote::quote! { GenericPlugin, config },
+            quote::quote! {
+                struct MyGeneric;
+            },
+        )
+        .unwrap();
+        insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
+    }
+
+    #[test]
+    fn test_plugin_label_order() {
+        let output = plugin(
+            quote::quote! { FilterPlugin, label = "Rustyツール", order = -10 },
+            quote::quote! {
+                struct MyFilter;
+            },
+        )
+        .unwrap();
+        insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
+    }
+
+    #[test]
+    fn test_plugin_declared_info() {
+        let output = plugin(
+            quote::quote! { FilterPlugin, name = "Rusty Filter", config_items = FilterConfig },
+            quote::quote! {
+                struct MyFilter;
+            },
+        )
+        .unwrap();
+        insta::assert_snapshot!(rustfmt_wrapper::rustfmt(output).unwrap());
+    }
+
+    #[test]
+    fn test_plugin_label_unsupported() {
+        let output = plugin(
+            quote::quote! { OutputPlugin, label = "Rustyツール" },
+            quote::quote! {
+                struct MyOutput;
+            },
+        )
+        .unwrap_err();
+        assert!(
+            output
+                .to_string()
+                .contains("`label` is only supported for FilterPlugin")
+        );
+    }
+
+    #[test]
+    fn test_plugin_duplicate_order() {
+        let output = plugin(
+            quote::quote! { FilterPlugin, order = 1, order = 2 },
+            quote::quote! {
+                struct MyFilter;
+            },
+        )
+        .unwrap_err();
+        assert!(output.to_string().contains("duplicate option"));
+    }
+
+    #[test]
+    fn test_plugin_config_unsupported() {
+        let output = plugin(
+            quote::quote! { FilterPlugin, config },
+            quote::quote! {
+                struct MyFilter;
+            },
+        )
+        .unwrap_err();
+        assert!(
+            output
+                .to_string()
+                .contains("`config` is only supported for GenericPlugin")
+        );
+    }
+
+    #[test]
+    fn test_plugin_lazy_unsupported() {
+        let output = plugin(
+            quote::quote! { OutputPlugin, lazy },
+            quote::quote! {
+                struct MyOutput;
+            },
+        )
+        .unwrap_err();
+        assert!(
+            output
+                .to_string()
+                .contains("`lazy` is only supported for FilterPlugin")
+        );
+    }
+}
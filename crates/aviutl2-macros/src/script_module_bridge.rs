@@ -16,7 +16,11 @@ pub fn parse_unwind_attr(attr: proc_macro2::TokenStream) -> Result<bool, proc_ma
             unwind = value.value;
             Ok(())
         } else {
-            Err(meta.error("expected `unwind`"))
+            Err(crate::utils::unknown_attribute_error(
+                &meta.path,
+                "module macros",
+                &["unwind"],
+            ))
         }
     });
     parser.parse2(attr).map_err(|e| e.to_compile_error())?;
@@ -174,6 +178,7 @@ fn create_converted_body(
     receiver_kind: &ReceiverKind,
 ) -> Result<proc_macro2::TokenStream, proc_macro2::TokenStream> {
     let method_name = &method.sig.ident;
+    check_return_type(&method.sig.output)?;
     let params = &method.sig.inputs;
     let mut param_bridges = Vec::new();
     let mut param_names = Vec::new();
@@ -258,6 +263,66 @@ fn create_converted_body(
     })
 }
 
+/// 戻り値のタプルの途中に`Option`が無いか確認する。
+///
+/// `nil`を返すことはできず、`None`の後ろの値が前に詰められてしまうため。
+/// `Result<(..), E>`の場合は`Ok`の型を確認する。
+fn check_return_type(output: &syn::ReturnType) -> Result<(), proc_macro2::TokenStream> {
+    let syn::ReturnType::Type(_, ty) = output else {
+        return Ok(());
+    };
+    let mut ty = &**ty;
+    loop {
+        ty = match ty {
+            syn::Type::Paren(paren) => &paren.elem,
+            syn::Type::Group(group) => &group.elem,
+            syn::Type::Path(_) if last_segment_ident(ty).is_some_and(|i| i.ends_with("Result")) => {
+                match first_generic_type(ty) {
+                    Some(ok_ty) => ok_ty,
+                    None => return Ok(()),
+                }
+            }
+            _ => break,
+        };
+    }
+    let syn::Type::Tuple(tuple) = ty else {
+        return Ok(());
+    };
+    let mut seen_option = false;
+    for elem in &tuple.elems {
+        if last_segment_ident(elem).is_some_and(|i| i == "Option") {
+            seen_option = true;
+        } else if seen_option {
+            return Err(syn::Error::new_spanned(
+                elem,
+                "values after `Option` are not allowed in a returned tuple; script module functions cannot return `nil`, so move `Option` to the end",
+            )
+            .to_compile_error());
+        }
+    }
+    Ok(())
+}
+
+fn last_segment_ident(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+fn first_generic_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let syn::PathArguments::AngleBracketed(args) = &path.path.segments.last()?.arguments else {
+        return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
 fn create_userdata_call_body(
     impl_token: &proc_macro2::TokenStream,
     _method_name: &syn::Ident,
@@ -29,3 +29,70 @@ impl<T> std::iter::FromIterator<Result<T, syn::Error>> for CombinedVecResults<T>
         Self::Ok(fields)
     }
 }
+
+/// 未知の属性名に対するエラーを作る。
+///
+/// `known`の中に綴りの近い属性名があれば、`did you mean`として候補を表示する。
+pub fn unknown_attribute_error(path: &syn::Path, kind: &str, known: &[&str]) -> syn::Error {
+    let name = path
+        .get_ident()
+        .map(ToString::to_string)
+        .unwrap_or_else(|| quote::ToTokens::to_token_stream(path).to_string());
+    let message = match suggest_similar(&name, known) {
+        Some(candidate) => {
+            format!("Unknown attribute `{name}` for {kind}; did you mean `{candidate}`?")
+        }
+        None => format!(
+            "Unknown attribute `{name}` for {kind}; expected one of {}",
+            known
+                .iter()
+                .map(|key| format!("`{key}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    syn::Error::new_spanned(path, message)
+}
+
+/// `candidates`の中から`name`に最も綴りの近いものを返す。
+///
+/// 編集距離が2以下（短い名前の場合は1以下）のものだけを候補とする。
+pub fn suggest_similar<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let threshold = if name.chars().count() <= 3 { 1 } else { 2 };
+    candidates
+        .iter()
+        .map(|candidate| (strsim::levenshtein(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_similar() {
+        let known = ["name", "range", "step", "default", "tooltip"];
+        assert_eq!(suggest_similar("rangee", &known), Some("range"));
+        assert_eq!(suggest_similar("defualt", &known), Some("default"));
+        assert_eq!(suggest_similar("tooltips", &known), Some("tooltip"));
+        assert_eq!(suggest_similar("color", &known), None);
+        assert_eq!(suggest_similar("nm", &known), None);
+    }
+
+    #[test]
+    fn test_unknown_attribute_error_message() {
+        let known = ["name", "range", "step", "default"];
+        let path: syn::Path = syn::parse_quote!(rangee);
+        assert_eq!(
+            unknown_attribute_error(&path, "track", &known).to_string(),
+            "Unknown attribute `rangee` for track; did you mean `range`?"
+        );
+        let path: syn::Path = syn::parse_quote!(color);
+        assert_eq!(
+            unknown_attribute_error(&path, "track", &known).to_string(),
+            "Unknown attribute `color` for track; expected one of `name`, `range`, `step`, `default`"
+        );
+    }
+}
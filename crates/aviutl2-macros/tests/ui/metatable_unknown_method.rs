@@ -0,0 +1,12 @@
+struct UserData {
+    name: String,
+}
+
+#[aviutl2::module::metatable]
+impl UserData {
+    fn tostirng(&self) -> String {
+        self.name.clone()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[derive(aviutl2::filter::FilterConfigSelectItems)]
+enum Mode {
+    #[item(nmae = "Normal")]
+    Normal,
+    #[item(name = "Fast")]
+    Fast,
+}
+
+fn main() {}
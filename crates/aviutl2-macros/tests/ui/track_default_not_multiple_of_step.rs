@@ -0,0 +1,7 @@
+#[aviutl2::filter::filter_config_items]
+struct Config {
+    #[track(name = "Mix", range = 0.0..=1.0, step = 0.1, default = 0.15)]
+    mix: f64,
+}
+
+fn main() {}
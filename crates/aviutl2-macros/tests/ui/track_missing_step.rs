@@ -0,0 +1,7 @@
+#[aviutl2::filter::filter_config_items]
+struct Config {
+    #[track(name = "Volume", range = 0.0..=1.0, default = 0.5)]
+    volume: f64,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[aviutl2::filter::filter_config_items]
+struct Config {
+    #[track(name = "Volume", rangee = 0.0..=1.0, step = 0.01, defualt = 0.5)]
+    volume: f64,
+}
+
+fn main() {}
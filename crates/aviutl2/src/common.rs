@@ -11,6 +11,13 @@ pub struct AviUtl2Info {
     /// AviUtl2のバージョン。
     pub version: AviUtl2Version,
 }
+impl AviUtl2Info {
+    pub(crate) fn new(version: u32) -> Self {
+        Self {
+            version: version.into(),
+        }
+    }
+}
 
 /// 対応する最小のAviUtl2バージョン。
 pub const MINIMUM_AVIUTL2_VERSION: AviUtl2Version = AviUtl2Version(2010100);
@@ -148,15 +155,95 @@ pub struct FileFilter {
     pub extensions: Vec<String>,
 }
 
+impl FileFilter {
+    /// 拡張子のリストを正規化し、修正した内容を返す。
+    ///
+    /// - 先頭の`*.`や`.`を取り除きます。
+    /// - 空の拡張子を取り除きます。
+    /// - 大文字小文字を区別せずに重複している拡張子を取り除きます。
+    /// - `*`や`*.*`が含まれている場合は、すべてのファイルを対象にします（`extensions`を空にします）。
+    ///
+    /// 別のフィルタとの間で拡張子が重複するのは問題ないため（「対応するすべてのファイル」など）、
+    /// 同じフィルタ内の重複のみを取り除きます。
+    ///
+    /// プラグインの登録時に自動で呼ばれ、修正した内容は警告としてログに出力されます。
+    pub fn validate(&mut self) -> Vec<FileFilterWarning> {
+        let mut warnings = Vec::new();
+        let mut extensions: Vec<String> = Vec::with_capacity(self.extensions.len());
+        for original in std::mem::take(&mut self.extensions) {
+            let trimmed = original.trim();
+            if trimmed == "*" || trimmed == "*.*" {
+                warnings.push(FileFilterWarning::Wildcard {
+                    filter: self.name.clone(),
+                });
+                return warnings;
+            }
+            let normalized = trimmed
+                .strip_prefix("*.")
+                .or_else(|| trimmed.strip_prefix('.'))
+                .unwrap_or(trimmed);
+            if normalized.is_empty() {
+                warnings.push(FileFilterWarning::EmptyExtension {
+                    filter: self.name.clone(),
+                });
+                continue;
+            }
+            if normalized != original {
+                warnings.push(FileFilterWarning::Normalized {
+                    filter: self.name.clone(),
+                    from: original.clone(),
+                    to: normalized.to_string(),
+                });
+            }
+            if extensions
+                .iter()
+                .any(|ext| ext.eq_ignore_ascii_case(normalized))
+            {
+                warnings.push(FileFilterWarning::Duplicated {
+                    filter: self.name.clone(),
+                    extension: normalized.to_string(),
+                });
+                continue;
+            }
+            extensions.push(normalized.to_string());
+        }
+        self.extensions = extensions;
+        warnings
+    }
+}
+
+/// [`FileFilter::validate`]で修正された内容。
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum FileFilterWarning {
+    #[error("file filter {filter:?}: extension {from:?} was normalized to {to:?}")]
+    Normalized {
+        filter: String,
+        from: String,
+        to: String,
+    },
+    #[error("file filter {filter:?}: empty extension was removed")]
+    EmptyExtension { filter: String },
+    #[error("file filter {filter:?}: duplicated extension {extension:?} was removed")]
+    Duplicated { filter: String, extension: String },
+    #[error("file filter {filter:?}: wildcard extension was found, matching all files")]
+    Wildcard { filter: String },
+}
+
 /// [`Vec<FileFilter>`]を簡単に作成するためのマクロ。
 ///
+/// フィルタの名前には文字列リテラル以外の式も使えます。
+///
 /// # Example
 ///
 /// ```rust
+/// use aviutl2::config::translate as tr;
+///
+/// # fn plugin_info() {
 /// let filters = aviutl2::file_filters! {
-///     "Image Files" => ["png", "jpg"],
+///     tr("Image Files") => ["png", "jpg"],
 ///     "All Files" => []
 /// };
+/// # }
 /// ```
 #[macro_export]
 macro_rules! file_filters {
@@ -191,71 +278,44 @@ pub struct Yc48 {
 }
 impl Yc48 {
     /// YUV 4:2:2（YUY2）からYC48に変換します。
+    ///
+    /// YUY2は[`Colorimetry::HOST`][crate::pixel::Colorimetry::HOST]として扱います。
     pub fn from_yuy2(yuy2: (u8, u8, u8, u8)) -> (Self, Self) {
-        let (y1, u, y2, v) = yuy2;
-        let ny1 = ((y1 as u16 * 1197) >> 6) - 299;
-        let ny2 = ((y2 as u16 * 1197) >> 6) - 299;
-        let ncb = ((u as u16 - 128) * 4681 + 164) >> 8;
-        let ncr = ((v as u16 - 128) * 4681 + 164) >> 8;
-        (
-            Self {
-                y: ny1 as i16,
-                cb: ncb as i16,
-                cr: ncr as i16,
-            },
-            Self {
-                y: ((ny1 + ny2) >> 1) as i16,
-                cb: ncb as i16,
-                cr: ncr as i16,
-            },
-        )
+        let [first, second] =
+            crate::pixel::yuy2_to_yc48(yuy2, crate::pixel::Colorimetry::HOST.range);
+        (first, second)
     }
 
     /// YC48からYUV 4:2:2（YUY2）に変換します。
+    ///
+    /// 色差は2ピクセルの平均になります。
+    /// YUY2は[`Colorimetry::HOST`][crate::pixel::Colorimetry::HOST]として扱います。
     pub fn to_yuy2(self, other: Yc48) -> (u8, u8, u8, u8) {
-        let y1 = ((self.y * 219 + 383) >> 12) + 16;
-        let y2 = ((other.y * 219 + 383) >> 12) + 16;
-        let u = (((self.cb + 2048) * 7 + 66) >> 7) + 16;
-        let v = (((self.cr + 2048) * 7 + 66) >> 7) + 16;
-        let y1 = y1.min(255) as u8;
-        let y2 = y2.min(255) as u8;
-        let u = u.min(255) as u8;
-        let v = v.min(255) as u8;
-        (y1, u, y2, v)
+        crate::pixel::yc48_to_yuy2([self, other], crate::pixel::Colorimetry::HOST.range)
     }
 
     /// RGBからYC48に変換します。
-    pub fn from_rgb(self, rgb: (u8, u8, u8)) -> Self {
-        let (r, g, b) = rgb;
-        let r = i16::from(r);
-        let g = i16::from(g);
-        let b = i16::from(b);
-        let y = ((4918 * r + 354) >> 10) + ((9655 * g + 585) >> 10) + ((1875 * b + 523) >> 10);
-        let cb = ((-2775 * r + 240) >> 10) + ((-5449 * g + 515) >> 10) + ((8224 * b + 256) >> 10);
-        let cr = ((8224 * r + 256) >> 10) + ((-6887 * g + 110) >> 10) + ((-1337 * b + 646) >> 10);
-
-        Yc48 { y, cb, cr }
+    ///
+    /// 変換にはBT.601を使います。
+    pub fn from_rgb(rgb: (u8, u8, u8)) -> Self {
+        crate::pixel::rgb_to_yc48(rgb, crate::pixel::YuvMatrix::Bt601)
     }
 
     /// YC48からRGBに変換します。
+    ///
+    /// 変換にはBT.601を使います。
     pub fn to_rgb(self) -> (u8, u8, u8) {
-        let y = self.y as i32;
-        let cr = self.cr as i32;
-        let cb = self.cr as i32;
-        let r = (255 * y + ((((22881 * cr) >> 16) + 3) << 10)) >> 12;
-        let g = (255 * y + ((((-5616 * cb) >> 16) + ((-11655 * cr) >> 16) + 3) << 10)) >> 12;
-        let b = (255 * y + ((((28919 * cb) >> 16) + 3) << 10)) >> 12;
-
-        let r = r.min(255) as u8;
-        let g = g.min(255) as u8;
-        let b = b.min(255) as u8;
-        (r, g, b)
+        crate::pixel::yc48_to_rgb(self, crate::pixel::YuvMatrix::Bt601)
     }
 }
 
 pub(crate) fn format_file_filters(file_filters: &[FileFilter]) -> String {
     let mut file_filter = String::new();
     for filter in file_filters {
+        let mut filter = filter.clone();
+        for warning in filter.validate() {
+            tracing::warn!("{}", warning);
+        }
         let display = format!(
             "{} ({})",
             filter.name,
@@ -371,9 +431,7 @@ impl LeakManager {
 
     pub fn leak_as_wide_string(&self, s: &str) -> *const u16 {
         tracing::debug!("Leaking wide string: {}", s);
-        let mut wide: Vec<u16> = s.encode_utf16().collect();
-        wide.push(0); // Null-terminate the string
-        let boxed = wide.into_boxed_slice();
+        let boxed = crate::utils::encoding::to_wide(s).into_boxed_slice();
         let ptr = Box::into_raw(boxed) as *mut u16 as usize;
         let mut ptrs = self.ptrs.lock().unwrap();
         ptrs.push((LeakType::WideString, ptr));
@@ -526,7 +584,7 @@ pub(crate) unsafe fn load_wide_string(ptr: *const u16) -> String {
         len += 1;
     }
 
-    unsafe { String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len)) }
+    crate::utils::encoding::from_wide(unsafe { std::slice::from_raw_parts(ptr, len) })
 }
 
 #[doc(hidden)]
@@ -602,14 +660,14 @@ impl CWString {
     ///
     /// `string`にヌルバイトが含まれている場合、`NullByteError`を返します。
     pub fn new(string: &str) -> Result<Self, NullByteError> {
-        let mut wide: Vec<u16> = string.encode_utf16().collect();
-        if let Some((pos, _)) = wide.iter().enumerate().find(|&(_, &c)| c == 0) {
+        let mut wide = crate::utils::encoding::to_wide(string);
+        if let Some(pos) = wide[..wide.len() - 1].iter().position(|&c| c == 0) {
+            wide.pop();
             return Err(NullByteError {
                 position: pos,
                 u16_seq: wide,
             });
         }
-        wide.push(0); // Null-terminate the string
         Ok(Self(wide))
     }
 
@@ -625,11 +683,16 @@ impl CWString {
     pub fn as_ptr(&self) -> *const u16 {
         self.0.as_ptr()
     }
+
+    /// 終端のヌル文字を含むUTF-16の列を取得します。
+    #[cfg_attr(not(feature = "generic"), allow(dead_code))]
+    pub fn as_slice_with_nul(&self) -> &[u16] {
+        &self.0
+    }
 }
 impl std::fmt::Display for CWString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = String::from_utf16_lossy(&self.0[..self.0.len() - 1]);
-        write!(f, "{}", s)
+        write!(f, "{}", crate::utils::encoding::from_wide(&self.0))
     }
 }
 
@@ -654,6 +717,20 @@ mod tests {
         assert_eq!(formatted, expected);
     }
 
+    #[test]
+    fn test_yc48_yuy2() {
+        // 色差が128未満でも変換できる
+        let (first, second) = Yc48::from_yuy2((16, 16, 235, 240));
+        assert_eq!(first.y, 0);
+        assert_eq!(second.y, 4096);
+        assert_eq!(first.cb, second.cb);
+        assert!(first.cb < 0 && first.cr > 0);
+        assert_eq!(first.to_yuy2(second), (16, 16, 235, 240));
+
+        let white = Yc48::from_rgb((255, 255, 255));
+        assert_eq!(white.to_rgb(), (255, 255, 255));
+    }
+
     #[test]
     fn test_file_filters_macro() {
         let filters = file_filters! {
@@ -670,6 +747,92 @@ mod tests {
         assert_eq!(filters[1].extensions, Vec::<String>::new());
     }
 
+    #[test]
+    fn test_format_file_filters_normalizes() {
+        let filters = file_filters! {
+            "Image Files" => ["*.png", ".JPG", "jpg", ""],
+        };
+        let formatted = format_file_filters(&filters);
+        assert_eq!(formatted, "Image Files (.png, .JPG)\x00*.png;*.JPG\x00");
+    }
+
+    #[test]
+    fn test_file_filter_validate() {
+        let mut filter = FileFilter {
+            name: "Image Files".to_string(),
+            extensions: vec![
+                "png".to_string(),
+                "*.jpg".to_string(),
+                ".PNG".to_string(),
+                " ".to_string(),
+                "webp".to_string(),
+            ],
+        };
+        let warnings = filter.validate();
+        assert_eq!(filter.extensions, ["png", "jpg", "webp"]);
+        assert_eq!(
+            warnings,
+            [
+                FileFilterWarning::Normalized {
+                    filter: "Image Files".to_string(),
+                    from: "*.jpg".to_string(),
+                    to: "jpg".to_string(),
+                },
+                FileFilterWarning::Normalized {
+                    filter: "Image Files".to_string(),
+                    from: ".PNG".to_string(),
+                    to: "PNG".to_string(),
+                },
+                FileFilterWarning::Duplicated {
+                    filter: "Image Files".to_string(),
+                    extension: "PNG".to_string(),
+                },
+                FileFilterWarning::EmptyExtension {
+                    filter: "Image Files".to_string(),
+                },
+            ]
+        );
+
+        let mut valid = FileFilter {
+            name: "Text".to_string(),
+            extensions: vec!["txt".to_string()],
+        };
+        assert!(valid.validate().is_empty());
+        assert_eq!(valid.extensions, ["txt"]);
+    }
+
+    #[test]
+    fn test_file_filter_validate_wildcard() {
+        let mut filter = FileFilter {
+            name: "All Files".to_string(),
+            extensions: vec!["png".to_string(), "*.*".to_string()],
+        };
+        let warnings = filter.validate();
+        assert!(filter.extensions.is_empty());
+        assert_eq!(
+            warnings,
+            [FileFilterWarning::Wildcard {
+                filter: "All Files".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_file_filters_macro_with_expression_names() {
+        fn tr(text: &str) -> String {
+            format!("translated: {text}")
+        }
+        let name = String::from("Owned");
+        let filters = file_filters! {
+            tr("Image Files") => ["png"],
+            name => ["txt"],
+            &format!("{} Files", "Audio") => ["wav"],
+        };
+        assert_eq!(filters[0].name, "translated: Image Files");
+        assert_eq!(filters[1].name, "Owned");
+        assert_eq!(filters[2].name, "Audio Files");
+    }
+
     #[test]
     fn test_aviutl2_version() {
         let version = AviUtl2Version::new(2, 0, 15, 0);
@@ -52,6 +52,19 @@ pub fn app_data_path() -> std::path::PathBuf {
     std::path::PathBuf::from(path)
 }
 
+/// アプリケーションデータフォルダへのパスを取得する。
+///
+/// [`app_data_path`]と異なり、設定ハンドルが初期化されていない場合は`None`を返す。
+pub(crate) fn try_app_data_path() -> Option<std::path::PathBuf> {
+    let handle = CONFIG_HANDLE
+        .get()?
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let raw = unsafe { handle.raw.as_ref() }?;
+    let path = unsafe { load_wide_string(raw.app_data_path) };
+    Some(std::path::PathBuf::from(path))
+}
+
 /// 現在の言語設定で定義されているテキストを取得する。
 ///
 /// 参照する言語設定のセクションはビルドしたプラグインのファイル名になります。
@@ -132,7 +145,7 @@ pub fn get_language_text_strict(section: &str, text: &str) -> Result<String, Nul
 ///
 /// - `key`: 設定ファイル(style.conf)の`[Font]`のキー名
 pub fn get_font_info(key: &str) -> Result<FontInfo, std::ffi::NulError> {
-    let c_key = std::ffi::CString::new(key)?;
+    let c_key = crate::utils::encoding::to_host_utf8(key)?;
     let font_info = unsafe {
         let handle = CONFIG_HANDLE
             .get()
@@ -176,7 +189,7 @@ pub fn get_color_code(key: &str) -> Result<Option<(u8, u8, u8)>, std::ffi::NulEr
 ///
 /// - [`get_color_code`]
 pub fn get_all_color_codes(key: &str) -> Result<Vec<(u8, u8, u8)>, std::ffi::NulError> {
-    let c_key = std::ffi::CString::new(key)?;
+    let c_key = crate::utils::encoding::to_host_utf8(key)?;
     let color_codes = unsafe {
         let handle = CONFIG_HANDLE
             .get()
@@ -215,7 +228,7 @@ pub fn get_all_color_codes(key: &str) -> Result<Vec<(u8, u8, u8)>, std::ffi::Nul
 ///
 /// - `key`: 設定ファイル(style.conf)の`[Layout]`のキー名
 pub fn get_layout_size(key: &str) -> Result<i32, std::ffi::NulError> {
-    let c_key = std::ffi::CString::new(key)?;
+    let c_key = crate::utils::encoding::to_host_utf8(key)?;
     let layout_size = unsafe {
         let handle = CONFIG_HANDLE
             .get()
@@ -63,6 +63,12 @@ pub struct FilterProcAudio {
 unsafe impl Send for FilterProcAudio {}
 unsafe impl Sync for FilterProcAudio {}
 
+/// 音声のチャンネル。
+///
+/// # See Also
+///
+/// - [`FilterProcAudio::channel_count`]
+/// - [`FilterProcAudio::get_sample_data_ch`]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AudioChannel {
     Left,
@@ -78,6 +84,17 @@ impl From<i32> for AudioChannel {
         }
     }
 }
+impl TryFrom<AudioChannel> for u16 {
+    type Error = FilterProcError;
+
+    fn try_from(value: AudioChannel) -> Result<Self, Self::Error> {
+        match value {
+            AudioChannel::Left => Ok(0),
+            AudioChannel::Right => Ok(1),
+            AudioChannel::Any(v) => u16::try_from(v).map_err(|_| FilterProcError::ValueOutOfRange),
+        }
+    }
+}
 impl From<AudioChannel> for i32 {
     fn from(value: AudioChannel) -> Self {
         match value {
@@ -89,39 +106,103 @@ impl From<AudioChannel> for i32 {
 }
 
 impl FilterProcAudio {
+    /// 現在の音声のチャンネル数を返す。
+    ///
+    /// 通常は2（ステレオ）ですが、モノラルのシーンなどでは1になります。
+    pub fn channel_count(&self) -> u16 {
+        u16::try_from(self.audio_object.channel_num).unwrap_or(u16::MAX)
+    }
+
+    fn check_channel(&self, channel: u16) -> FilterProcResult<()> {
+        let channel_count = self.channel_count();
+        if channel < channel_count {
+            Ok(())
+        } else {
+            Err(FilterProcError::ChannelOutOfRange {
+                channel,
+                channel_count,
+            })
+        }
+    }
+
     /// 現在の音声のデータを取得する。
-    /// `channel` は 0 が左チャンネル、1 が右チャンネルです。
+    ///
+    /// # Errors
+    ///
+    /// `channel` が存在しないチャンネルの場合、[`FilterProcError::ChannelOutOfRange`] を返します。
+    ///
+    /// # Panics
+    ///
+    /// `buffer` の長さが `sample_num` と一致しない場合、パニックします。
+    pub fn get_sample_data(
+        &mut self,
+        channel: AudioChannel,
+        buffer: &mut [f32],
+    ) -> FilterProcResult<usize> {
+        self.get_sample_data_ch(channel.try_into()?, buffer)
+    }
+
+    /// 現在の音声のデータを設定する。
+    ///
+    /// # Errors
+    ///
+    /// `channel` が存在しないチャンネルの場合、[`FilterProcError::ChannelOutOfRange`] を返します。
+    ///
+    /// # Panics
+    ///
+    /// `data` の長さが `sample_num` と一致しない場合、パニックします。
+    pub fn set_sample_data(&mut self, channel: AudioChannel, data: &[f32]) -> FilterProcResult<()> {
+        self.set_sample_data_ch(channel.try_into()?, data)
+    }
+
+    /// 指定したチャンネルの現在の音声のデータを取得する。
+    /// `channel` は0から[`FilterProcAudio::channel_count`]未満の値です。
+    ///
+    /// # Errors
+    ///
+    /// `channel` が存在しないチャンネルの場合、[`FilterProcError::ChannelOutOfRange`] を返します。
     ///
     /// # Panics
     ///
     /// `buffer` の長さが `sample_num` と一致しない場合、パニックします。
-    pub fn get_sample_data(&mut self, channel: AudioChannel, buffer: &mut [f32]) -> usize {
+    pub fn get_sample_data_ch(
+        &mut self,
+        channel: u16,
+        buffer: &mut [f32],
+    ) -> FilterProcResult<usize> {
         let sample_num = self.audio_object.sample_num as usize;
         assert_eq!(
             buffer.len(),
             sample_num,
             "buffer length does not match sample_num"
         );
+        self.check_channel(channel)?;
         let inner = unsafe { &*self.inner };
         unsafe { (inner.get_sample_data)(buffer.as_mut_ptr(), channel.into()) };
-        sample_num
+        Ok(sample_num)
     }
 
-    /// 現在の音声のデータを設定する。
-    /// `channel` は 0 が左チャンネル、1 が右チャンネルです。
+    /// 指定したチャンネルの現在の音声のデータを設定する。
+    /// `channel` は0から[`FilterProcAudio::channel_count`]未満の値です。
+    ///
+    /// # Errors
+    ///
+    /// `channel` が存在しないチャンネルの場合、[`FilterProcError::ChannelOutOfRange`] を返します。
     ///
     /// # Panics
     ///
     /// `data` の長さが `sample_num` と一致しない場合、パニックします。
-    pub fn set_sample_data(&mut self, channel: AudioChannel, data: &[f32]) {
+    pub fn set_sample_data_ch(&mut self, channel: u16, data: &[f32]) -> FilterProcResult<()> {
         let sample_num = self.audio_object.sample_num as usize;
         assert_eq!(
             data.len(),
             sample_num,
             "data length does not match sample_num"
         );
+        self.check_channel(channel)?;
         let inner = unsafe { &*self.inner };
         unsafe { (inner.set_sample_data)(data.as_ptr(), channel.into()) };
+        Ok(())
     }
 
     /// 読み取り専用の編集セクション。
@@ -175,3 +256,171 @@ impl FilterProcAudio {
         inner.vol_r = self.param.vol_r;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    const SAMPLE_NUM: usize = 4;
+
+    thread_local! {
+        static CHANNELS: RefCell<Vec<Vec<f32>>> = const { RefCell::new(Vec::new()) };
+    }
+
+    unsafe extern "C" fn mock_get_sample_data(buffer: *mut f32, channel: i32) {
+        CHANNELS.with_borrow(|channels| {
+            let data = &channels[channel as usize];
+            unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), buffer, data.len()) };
+        });
+    }
+
+    unsafe extern "C" fn mock_set_sample_data(buffer: *const f32, channel: i32) {
+        CHANNELS.with_borrow_mut(|channels| {
+            let data = &mut channels[channel as usize];
+            unsafe { std::ptr::copy_nonoverlapping(buffer, data.as_mut_ptr(), data.len()) };
+        });
+    }
+
+    unsafe extern "C" fn mock_get_output_audio_param(
+        _object: aviutl2_sys::filter2::OBJECT_HANDLE,
+        _offset: f64,
+        _param: *mut aviutl2_sys::filter2::OBJECT_AUDIO_PARAM,
+        _param_size: i32,
+    ) -> bool {
+        false
+    }
+
+    unsafe extern "C" fn mock_get_audio_object(
+        _layer: i32,
+        _offset: f64,
+    ) -> aviutl2_sys::filter2::OBJECT_HANDLE {
+        std::ptr::null_mut()
+    }
+
+    fn with_mock_audio(channel_count: u16, f: impl FnOnce(&mut FilterProcAudio)) {
+        CHANNELS.with_borrow_mut(|channels| {
+            *channels = (0..channel_count)
+                .map(|ch| vec![ch as f32; SAMPLE_NUM])
+                .collect();
+        });
+        let mut param = aviutl2_sys::filter2::OBJECT_AUDIO_PARAM {
+            vol_l: 1.0,
+            vol_r: 1.0,
+        };
+        let raw = aviutl2_sys::filter2::FILTER_PROC_AUDIO {
+            scene: std::ptr::null(),
+            object: std::ptr::null(),
+            get_sample_data: mock_get_sample_data,
+            set_sample_data: mock_set_sample_data,
+            edit: std::ptr::null_mut(),
+            param: &mut param,
+            get_output_audio_param: mock_get_output_audio_param,
+            get_audio_object: mock_get_audio_object,
+        };
+        let mut audio = FilterProcAudio {
+            scene: SceneInfo {
+                width: 1920,
+                height: 1080,
+                frame_rate: crate::common::Rational32::new(60, 1),
+                sample_rate: 48000,
+            },
+            object: ObjectInfo {
+                id: 0,
+                effect_id: 0,
+                layer: 0,
+                frame: 0,
+                frame_total: 1,
+                time: 0.0,
+                time_total: 0.0,
+                is_filter_object: false,
+                frame_s: 0,
+                frame_e: 0,
+            },
+            audio_object: AudioObjectInfo {
+                sample_index: 0,
+                sample_total: SAMPLE_NUM as u64,
+                sample_num: SAMPLE_NUM as u32,
+                channel_num: channel_count as u32,
+            },
+            param: param.into(),
+            read_section: crate::generic::ReadSection {
+                internal: std::ptr::null_mut(),
+            },
+            inner: &raw,
+        };
+        f(&mut audio);
+    }
+
+    fn assert_channels(audio: &mut FilterProcAudio, channel_count: u16) {
+        assert_eq!(audio.channel_count(), channel_count);
+        let mut buffer = [0.0; SAMPLE_NUM];
+        for ch in 0..channel_count {
+            assert_eq!(
+                audio.get_sample_data_ch(ch, &mut buffer).unwrap(),
+                SAMPLE_NUM
+            );
+            assert_eq!(buffer, [ch as f32; SAMPLE_NUM]);
+            audio.set_sample_data_ch(ch, &[-1.0; SAMPLE_NUM]).unwrap();
+            audio.get_sample_data_ch(ch, &mut buffer).unwrap();
+            assert_eq!(buffer, [-1.0; SAMPLE_NUM]);
+        }
+        assert!(matches!(
+            audio.get_sample_data_ch(channel_count, &mut buffer),
+            Err(FilterProcError::ChannelOutOfRange { channel, channel_count: count })
+                if channel == channel_count && count == channel_count
+        ));
+        assert!(matches!(
+            audio.set_sample_data_ch(channel_count, &buffer),
+            Err(FilterProcError::ChannelOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn mono() {
+        with_mock_audio(1, |audio| {
+            assert_channels(audio, 1);
+            let mut buffer = [0.0; SAMPLE_NUM];
+            assert!(
+                audio
+                    .get_sample_data(AudioChannel::Left, &mut buffer)
+                    .is_ok()
+            );
+            assert!(matches!(
+                audio.get_sample_data(AudioChannel::Right, &mut buffer),
+                Err(FilterProcError::ChannelOutOfRange { .. })
+            ));
+            assert!(audio.set_sample_data(AudioChannel::Right, &buffer).is_err());
+        });
+    }
+
+    #[test]
+    fn stereo() {
+        with_mock_audio(2, |audio| {
+            assert_channels(audio, 2);
+            let mut buffer = [0.0; SAMPLE_NUM];
+            audio
+                .set_sample_data(AudioChannel::Right, &[0.5; SAMPLE_NUM])
+                .unwrap();
+            audio.get_sample_data_ch(1, &mut buffer).unwrap();
+            assert_eq!(buffer, [0.5; SAMPLE_NUM]);
+        });
+    }
+
+    #[test]
+    fn surround() {
+        with_mock_audio(6, |audio| {
+            assert_channels(audio, 6);
+            let mut buffer = [0.0; SAMPLE_NUM];
+            assert!(
+                audio
+                    .get_sample_data(AudioChannel::Any(5), &mut buffer)
+                    .is_ok()
+            );
+            assert!(matches!(
+                audio.get_sample_data(AudioChannel::Any(-1), &mut buffer),
+                Err(FilterProcError::ValueOutOfRange)
+            ));
+        });
+    }
+}
@@ -0,0 +1,74 @@
+use super::{FilterProcAudio, FilterProcVideo, ObjectInfo, SceneInfo};
+use crate::generic::EditState;
+
+/// フィルタ処理が呼ばれた状況。
+///
+/// [`FilterProcVideo::context`] や [`FilterProcAudio::context`] で取得できます。
+///
+/// # Note
+///
+/// AviUtl2のフィルタプラグインのAPIには、プレビュー再生中やファイル出力中かどうかを知る手段がありません。
+/// そのため、[`Self::is_exporting`] と [`Self::is_fast_preview`] は常に`false`になります。
+/// 同じDLLで汎用プラグインを登録している場合は、[`crate::generic::EditHandle::get_edit_state`]
+/// で取得した編集状態を [`Self::with_edit_state`] に渡すことで埋められます。
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct ProcContext {
+    /// オブジェクト基準の現在のフレーム番号。
+    pub object_frame: u32,
+    /// オブジェクトの総フレーム数。
+    pub object_total_frames: u32,
+    /// シーン基準の現在の時間（秒）。
+    pub scene_time: f64,
+    /// ファイル出力中かどうか。
+    pub is_exporting: bool,
+    /// プレビュー再生中かどうか。
+    ///
+    /// 再生中は処理が間に合わないとコマ落ちするため、品質を落として高速に処理するべきです。
+    pub is_fast_preview: bool,
+}
+
+impl ProcContext {
+    pub(crate) fn new(scene: &SceneInfo, object: &ObjectInfo) -> Self {
+        let scene_frame = object.frame_s as u64 + object.frame as u64;
+        let frame_rate = scene.frame_rate;
+        Self {
+            object_frame: object.frame,
+            object_total_frames: object.frame_total,
+            scene_time: if *frame_rate.numer() == 0 {
+                0.0
+            } else {
+                scene_frame as f64 * *frame_rate.denom() as f64 / *frame_rate.numer() as f64
+            },
+            is_exporting: false,
+            is_fast_preview: false,
+        }
+    }
+
+    /// 編集状態から [`Self::is_exporting`] と [`Self::is_fast_preview`] を設定する。
+    pub fn with_edit_state(self, state: EditState) -> Self {
+        Self {
+            is_exporting: state == EditState::Save,
+            is_fast_preview: state == EditState::Preview,
+            ..self
+        }
+    }
+}
+
+impl FilterProcVideo {
+    /// フィルタ処理が呼ばれた状況を取得する。
+    ///
+    /// 取得できない情報については [`ProcContext`] を参照してください。
+    pub fn context(&self) -> ProcContext {
+        ProcContext::new(&self.scene, &self.object)
+    }
+}
+
+impl FilterProcAudio {
+    /// フィルタ処理が呼ばれた状況を取得する。
+    ///
+    /// 取得できない情報については [`ProcContext`] を参照してください。
+    pub fn context(&self) -> ProcContext {
+        ProcContext::new(&self.scene, &self.object)
+    }
+}
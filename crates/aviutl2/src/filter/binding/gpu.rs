@@ -0,0 +1,148 @@
+use super::FilterProcVideo;
+use windows::{
+    Win32::{
+        Foundation::HANDLE,
+        Graphics::{
+            Direct3D11::{D3D11_TEXTURE2D_DESC, ID3D11Device, ID3D11Texture2D},
+            Dxgi::{Common::DXGI_FORMAT, IDXGIResource},
+        },
+    },
+    core::Interface,
+};
+
+/// AviUtl2が管理しているD3D11のテクスチャ。
+///
+/// [`FilterProcVideo::d3d11_texture`]・[`FilterProcVideo::d3d11_framebuffer_texture`]で取得できます。
+///
+/// CPUを経由せずにGPU上で画像を処理するためのものです。
+/// 取得元の[`FilterProcVideo`]を借用するため、[`FilterProcVideo::set_image_data`]などで
+/// テクスチャが無効になる操作は、このテクスチャを破棄するまで呼び出せません。
+///
+/// <div class="warning">
+///
+/// テクスチャはAviUtl2のD3D11デバイスで作成されています。
+/// 別のデバイス（wgpuなど）から使う場合は、[`Self::shared_handle`]で共有ハンドルを取得してください。
+/// 共有ハンドルを取得できない場合は、[`FilterProcVideo::get_image_data`]・[`FilterProcVideo::set_image_data`]
+/// を使ったCPU経由の処理にフォールバックしてください。
+///
+/// </div>
+#[derive(Debug)]
+pub struct D3D11Texture<'a> {
+    texture: ID3D11Texture2D,
+    _video: std::marker::PhantomData<&'a mut FilterProcVideo>,
+}
+
+/// D3D11のテクスチャの共有ハンドル。
+///
+/// `IDXGIResource::GetSharedHandle`で取得した、従来の（NTハンドルではない）共有ハンドルです。
+/// 所有権はテクスチャが持っているため、このハンドルを閉じる必要はありません。
+///
+/// D3D11のデバイスでは`ID3D11Device::OpenSharedResource`で開けます。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedTextureHandle {
+    handle: HANDLE,
+    width: u32,
+    height: u32,
+    format: DXGI_FORMAT,
+}
+
+impl SharedTextureHandle {
+    /// ハンドルを返す。
+    pub fn as_raw(&self) -> HANDLE {
+        self.handle
+    }
+
+    /// テクスチャのサイズを返す。
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// テクスチャのフォーマットを返す。
+    pub fn format(&self) -> DXGI_FORMAT {
+        self.format
+    }
+}
+
+impl<'a> D3D11Texture<'a> {
+    /// # Safety
+    ///
+    /// `ptr`はnullか、有効な`ID3D11Texture2D`へのポインタである必要があります。
+    pub(crate) unsafe fn from_raw(ptr: *mut std::ffi::c_void) -> Option<Self> {
+        // SAFETY: 呼び出し元が保証する。借用したポインタから参照カウントを増やして保持する
+        let texture = unsafe { ID3D11Texture2D::from_raw_borrowed(&ptr) }?.clone();
+        Some(Self {
+            texture,
+            _video: std::marker::PhantomData,
+        })
+    }
+
+    /// `ID3D11Texture2D`を返す。
+    pub fn as_raw(&self) -> &ID3D11Texture2D {
+        &self.texture
+    }
+
+    /// テクスチャの情報を返す。
+    pub fn desc(&self) -> D3D11_TEXTURE2D_DESC {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { self.texture.GetDesc(&mut desc) };
+        desc
+    }
+
+    /// テクスチャのサイズを返す。
+    pub fn size(&self) -> (u32, u32) {
+        let desc = self.desc();
+        (desc.Width, desc.Height)
+    }
+
+    /// テクスチャを作成したデバイスを返す。
+    pub fn device(&self) -> crate::AnyResult<ID3D11Device> {
+        Ok(unsafe { self.texture.GetDevice() }?)
+    }
+
+    /// 共有ハンドルを取得する。
+    ///
+    /// テクスチャが共有可能なリソースとして作成されていない場合は`None`を返します。
+    pub fn shared_handle(&self) -> Option<SharedTextureHandle> {
+        let resource: IDXGIResource = self.texture.cast().ok()?;
+        let handle = unsafe { resource.GetSharedHandle() }.ok()?;
+        if handle.is_invalid() {
+            return None;
+        }
+        let desc = self.desc();
+        Some(SharedTextureHandle {
+            handle,
+            width: desc.Width,
+            height: desc.Height,
+            format: desc.Format,
+        })
+    }
+}
+
+impl FilterProcVideo {
+    /// 現在のオブジェクトの画像を、D3D11のテクスチャとして取得する。
+    ///
+    /// AviUtl2がテクスチャを返さなかった場合は`None`を返します。
+    /// その場合は[`Self::get_image_data`]などを使ったCPU経由の処理にフォールバックしてください。
+    pub fn d3d11_texture(&mut self) -> Option<D3D11Texture<'_>> {
+        let ptr = self.get_image_texture2d();
+        unsafe { D3D11Texture::from_raw(ptr) }
+    }
+
+    /// 現在のフレームバッファを、D3D11のテクスチャとして取得する。
+    ///
+    /// AviUtl2がテクスチャを返さなかった場合は`None`を返します。
+    pub fn d3d11_framebuffer_texture(&mut self) -> Option<D3D11Texture<'_>> {
+        let ptr = self.get_framebuffer_texture2d();
+        unsafe { D3D11Texture::from_raw(ptr) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn null_texture_is_none() {
+        assert!(unsafe { D3D11Texture::from_raw(std::ptr::null_mut()) }.is_none());
+    }
+}
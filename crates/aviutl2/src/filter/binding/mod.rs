@@ -1,7 +1,16 @@
 mod audio;
+mod context;
+#[cfg(feature = "gpu-interop")]
+mod gpu;
+mod object_state;
 mod video;
 
 pub use audio::*;
+pub use context::*;
+#[cfg(feature = "gpu-interop")]
+pub use gpu::*;
+pub(crate) use object_state::{DestroyedObject, ObjectTracker, evict_object_states};
+pub use object_state::{OBJECT_EXPIRE_DURATION, ObjectStateMap, ObjectStateRefMut};
 pub use video::*;
 
 use super::config;
@@ -14,7 +23,13 @@ pub struct FilterPluginTable {
     pub name: String,
     /// ラベルの初期値。
     /// Noneの場合、デフォルトのラベルになります
+    ///
+    /// 同じDLLから複数のフィルタを登録する場合、同じラベルを指定すると一つのグループにまとまります。
     pub label: Option<String>,
+    /// 登録順のヒント。
+    /// 汎用プラグインから複数のフィルタを登録する場合、小さい順にホストへ登録されます。
+    /// 同じ値のフィルタは登録した順のままになります。
+    pub order: i32,
     /// プラグインの情報。
     /// 「プラグイン情報」ダイアログで表示されます。
     pub information: String,
@@ -66,6 +81,11 @@ pub trait FilterPlugin: Send + Sync + Sized {
     /// # Note
     ///
     /// フィルタオブジェクトの場合、画像サイズは変更できません。
+    ///
+    /// `config`のトラックバーの値は、再生中かシーク中かに関わらず、
+    /// AviUtl2側で描画するフレームの時点の値に補間（移動方法を適用）されてから渡されます。
+    /// ただし、シーク中は同じフレームに対して複数回呼ばれたり、フレームが前後して呼ばれたりするため、
+    /// 前回の呼び出しからフレームが進んでいることを前提にしないでください。
     fn proc_video(
         &self,
         _config: &[crate::filter::FilterConfigItem],
@@ -83,12 +103,39 @@ pub trait FilterPlugin: Send + Sync + Sized {
         anyhow::bail!("proc_audio is not implemented");
     }
 
+    /// オブジェクトが初めて処理されたときに呼ばれる。
+    ///
+    /// # Note
+    ///
+    /// AviUtl2にはオブジェクトの生成を通知する仕組みがないため、
+    /// 初めて[`Self::proc_video`]または[`Self::proc_audio`]が呼ばれる直前に呼ばれます。
+    /// 削除されたとみなされたオブジェクトが再び処理された場合も、再度呼ばれます。
+    fn on_object_created(&self, object_id: i64) {
+        let _ = object_id;
+    }
+
+    /// オブジェクトが削除されたときに呼ばれる。
+    ///
+    /// # Note
+    ///
+    /// AviUtl2にはオブジェクトの削除を通知する仕組みがないため、
+    /// [`crate::filter::OBJECT_EXPIRE_DURATION`]の間処理されなかったオブジェクトや、
+    /// プラグインの終了時に残っているオブジェクトが削除されたものとして扱われます。
+    /// このプラグインが処理したエフェクトの[`crate::filter::ObjectStateMap`]の状態は、このメソッドが呼ばれる前に自動で削除されます。
+    fn on_object_destroyed(&self, object_id: i64) {
+        let _ = object_id;
+    }
+
     /// シングルトンインスタンスを参照するためのヘルパーメソッド。
     ///
+    /// # Errors
+    ///
+    /// プラグインがまだ初期化されていない場合や、`new()`が失敗している場合にエラーを返します。
+    ///
     /// # Panics
     ///
-    /// プラグインが初期化されていない場合や、二重に呼び出された場合にパニックします。
-    fn with_instance<R>(f: impl FnOnce(&Self) -> R) -> R
+    /// 二重に呼び出された場合にパニックします。
+    fn with_instance<R>(f: impl FnOnce(&Self) -> R) -> crate::common::AnyResult<R>
     where
         Self: crate::filter::__bridge::FilterSingleton,
     {
@@ -97,10 +144,14 @@ pub trait FilterPlugin: Send + Sync + Sized {
 
     /// シングルトンインスタンスを可変参照するためのヘルパーメソッド。
     ///
+    /// # Errors
+    ///
+    /// プラグインがまだ初期化されていない場合や、`new()`が失敗している場合にエラーを返します。
+    ///
     /// # Panics
     ///
-    /// プラグインが初期化されていない場合や、二重に呼び出された場合にパニックします。
-    fn with_instance_mut<R>(f: impl FnOnce(&mut Self) -> R) -> R
+    /// 二重に呼び出された場合にパニックします。
+    fn with_instance_mut<R>(f: impl FnOnce(&mut Self) -> R) -> crate::common::AnyResult<R>
     where
         Self: crate::filter::__bridge::FilterSingleton,
     {
@@ -108,6 +159,9 @@ pub trait FilterPlugin: Send + Sync + Sized {
     }
 }
 
+// NOTE:
+// シーンの背景色はSCENE_INFOに含まれていないため、SceneInfoでも提供していない。
+
 /// シーン情報。
 #[derive(Debug, Clone, Copy)]
 pub struct SceneInfo {
@@ -164,6 +218,8 @@ pub enum FilterProcError {
     InputCwstrContainsNull(#[from] crate::common::NullByteError),
     #[error("value is out of range")]
     ValueOutOfRange,
+    #[error("channel {channel} does not exist (channel count: {channel_count})")]
+    ChannelOutOfRange { channel: u16, channel_count: u16 },
 }
 
 pub type FilterProcResult<T> = Result<T, FilterProcError>;
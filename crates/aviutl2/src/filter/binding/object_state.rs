@@ -0,0 +1,366 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+use super::ObjectInfo;
+
+/// この時間の間処理されなかったオブジェクトは、削除されたものとして扱われます。
+///
+/// AviUtl2にはオブジェクトの削除を通知する仕組みがないため、
+/// 一定時間処理されなかったオブジェクトを削除されたものとみなします。
+pub const OBJECT_EXPIRE_DURATION: Duration = Duration::from_secs(60);
+
+/// 削除されたオブジェクトの確認を行う間隔。
+const OBJECT_SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// オブジェクトごとの状態を保持するためのマップ。
+///
+/// キーは`(オブジェクトのID, エフェクトのID)`です。
+/// 同じオブジェクトに同じフィルタが複数かけられている場合も、それぞれ別の状態になります。
+///
+/// オブジェクトが削除された（[`FilterPlugin::on_object_destroyed`][crate::filter::FilterPlugin::on_object_destroyed]が呼ばれた）とき、
+/// そのフィルタプラグインが処理したエフェクトの状態は自動的に削除されます。
+/// 他のフィルタプラグインのエフェクトの状態は、同じオブジェクトのものでも削除されません。
+///
+/// # Example
+///
+/// ```rust
+/// # struct State;
+/// # fn process(map: &aviutl2::filter::ObjectStateMap<State>, audio: &aviutl2::filter::FilterProcAudio) {
+/// let _state = map.get_or_insert_with(&audio.object, || State);
+/// # }
+/// ```
+pub struct ObjectStateMap<T> {
+    inner: Arc<dashmap::DashMap<(i64, i64), T>>,
+}
+
+/// [`ObjectStateMap`]から取り出した状態への参照。
+pub type ObjectStateRefMut<'a, T> = dashmap::mapref::one::RefMut<'a, (i64, i64), T>;
+
+impl<T: Send + Sync + 'static> ObjectStateMap<T> {
+    /// 新しいマップを作成する。
+    pub fn new() -> Self {
+        let inner = Arc::new(dashmap::DashMap::new());
+        let evictor: Weak<dyn ObjectStateEvictor> =
+            Arc::downgrade(&inner) as Weak<dyn ObjectStateEvictor>;
+        OBJECT_STATE_MAPS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(evictor);
+        Self { inner }
+    }
+}
+
+impl<T: Send + Sync + 'static> Default for ObjectStateMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ObjectStateMap<T> {
+    fn key(object: &ObjectInfo) -> (i64, i64) {
+        (object.id, object.effect_id)
+    }
+
+    /// オブジェクトの状態を取得する。
+    pub fn get(&self, object: &ObjectInfo) -> Option<ObjectStateRefMut<'_, T>> {
+        self.inner.get_mut(&Self::key(object))
+    }
+
+    /// オブジェクトの状態を取得する。存在しない場合は`f`で作成します。
+    pub fn get_or_insert_with(
+        &self,
+        object: &ObjectInfo,
+        f: impl FnOnce() -> T,
+    ) -> ObjectStateRefMut<'_, T> {
+        self.inner.entry(Self::key(object)).or_insert_with(f)
+    }
+
+    /// オブジェクトの状態を取得する。存在しない場合は`f`で作成します。
+    pub fn get_or_try_insert_with<E>(
+        &self,
+        object: &ObjectInfo,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<ObjectStateRefMut<'_, T>, E> {
+        self.inner.entry(Self::key(object)).or_try_insert_with(f)
+    }
+
+    /// オブジェクトの状態を設定する。
+    pub fn insert(&self, object: &ObjectInfo, value: T) -> Option<T> {
+        self.inner.insert(Self::key(object), value)
+    }
+
+    /// オブジェクトの状態を削除する。
+    pub fn remove(&self, object: &ObjectInfo) -> Option<T> {
+        self.inner
+            .remove(&Self::key(object))
+            .map(|(_, value)| value)
+    }
+
+    /// オブジェクトに紐づくすべての状態を削除する。
+    pub fn remove_object(&self, object_id: i64) {
+        self.inner.retain(|(id, _), _| *id != object_id);
+    }
+
+    /// すべての状態を削除する。
+    pub fn clear(&self) {
+        self.inner.clear();
+    }
+
+    /// 保持している状態の数を返す。
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// 状態を1つも保持していないかどうかを返す。
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<T> std::fmt::Debug for ObjectStateMap<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObjectStateMap")
+            .field("len", &self.inner.len())
+            .finish()
+    }
+}
+
+trait ObjectStateEvictor: Send + Sync {
+    fn remove_effects(&self, object: &DestroyedObject);
+}
+
+impl<T: Send + Sync> ObjectStateEvictor for dashmap::DashMap<(i64, i64), T> {
+    fn remove_effects(&self, object: &DestroyedObject) {
+        self.retain(|(id, effect_id), _| {
+            *id != object.id || !object.effect_ids.contains(effect_id)
+        });
+    }
+}
+
+static OBJECT_STATE_MAPS: Mutex<Vec<Weak<dyn ObjectStateEvictor>>> = Mutex::new(Vec::new());
+
+/// 生存している[`ObjectStateMap`]から、削除されたオブジェクトの状態を削除する。
+///
+/// キーにエフェクトのIDを含むので、削除を検出したフィルタプラグインが処理したエフェクトの状態だけが削除される。
+pub(crate) fn evict_object_states(object: &DestroyedObject) {
+    let mut maps = OBJECT_STATE_MAPS.lock().unwrap_or_else(|e| e.into_inner());
+    maps.retain(|map| match map.upgrade() {
+        Some(map) => {
+            map.remove_effects(object);
+            true
+        }
+        None => false,
+    });
+}
+
+/// 削除されたとみなしたオブジェクト。
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct DestroyedObject {
+    pub id: i64,
+    /// このオブジェクトで処理したエフェクトのID。
+    pub effect_ids: HashSet<i64>,
+}
+
+/// オブジェクトの生成・削除の検出結果。
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ObjectLifecycleEvents {
+    pub created: Option<i64>,
+    pub destroyed: Vec<DestroyedObject>,
+}
+
+#[derive(Debug)]
+struct TrackedObject {
+    last_seen: Instant,
+    effect_ids: HashSet<i64>,
+}
+
+/// 処理されたオブジェクトを記録し、オブジェクトの生成・削除を検出する。
+///
+/// フィルタプラグインごとに作成し、そのプラグインが処理したエフェクトだけを記録する。
+#[derive(Debug)]
+pub(crate) struct ObjectTracker {
+    objects: Mutex<HashMap<i64, TrackedObject>>,
+    last_sweep: Mutex<Instant>,
+}
+
+impl ObjectTracker {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            objects: Mutex::new(HashMap::new()),
+            last_sweep: Mutex::new(now),
+        }
+    }
+
+    /// オブジェクトのエフェクトが処理されたことを記録する。
+    pub fn observe(&self, object: &ObjectInfo, now: Instant) -> ObjectLifecycleEvents {
+        let mut objects = self.objects.lock().unwrap_or_else(|e| e.into_inner());
+        let mut created = None;
+        let tracked = objects.entry(object.id).or_insert_with(|| {
+            created = Some(object.id);
+            TrackedObject {
+                last_seen: now,
+                effect_ids: HashSet::new(),
+            }
+        });
+        tracked.last_seen = now;
+        tracked.effect_ids.insert(object.effect_id);
+
+        let mut destroyed = Vec::new();
+        let mut last_sweep = self.last_sweep.lock().unwrap_or_else(|e| e.into_inner());
+        if now.saturating_duration_since(*last_sweep) >= OBJECT_SWEEP_INTERVAL {
+            *last_sweep = now;
+            objects.retain(|id, tracked| {
+                let alive =
+                    now.saturating_duration_since(tracked.last_seen) < OBJECT_EXPIRE_DURATION;
+                if !alive {
+                    destroyed.push(DestroyedObject {
+                        id: *id,
+                        effect_ids: std::mem::take(&mut tracked.effect_ids),
+                    });
+                }
+                alive
+            });
+        }
+
+        ObjectLifecycleEvents { created, destroyed }
+    }
+
+    /// 記録しているすべてのオブジェクトを削除されたものとして返す。
+    pub fn drain(&self) -> Vec<DestroyedObject> {
+        let mut objects = self.objects.lock().unwrap_or_else(|e| e.into_inner());
+        objects
+            .drain()
+            .map(|(id, tracked)| DestroyedObject {
+                id,
+                effect_ids: tracked.effect_ids,
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.objects.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(id: i64, effect_id: i64) -> ObjectInfo {
+        ObjectInfo {
+            id,
+            effect_id,
+            layer: 0,
+            frame: 0,
+            frame_total: 1,
+            time: 0.0,
+            time_total: 0.0,
+            is_filter_object: false,
+            frame_s: 0,
+            frame_e: 0,
+        }
+    }
+
+    #[test]
+    fn tracker_reports_new_objects_once() {
+        let now = Instant::now();
+        let tracker = ObjectTracker::new(now);
+        assert_eq!(tracker.observe(&object(1, 10), now).created, Some(1));
+        assert_eq!(tracker.observe(&object(1, 10), now).created, None);
+        // 同じオブジェクトの別のエフェクト
+        assert_eq!(tracker.observe(&object(1, 11), now).created, None);
+        assert_eq!(tracker.observe(&object(2, 20), now).created, Some(2));
+    }
+
+    #[test]
+    fn tracker_reports_expired_objects() {
+        let now = Instant::now();
+        let tracker = ObjectTracker::new(now);
+        tracker.observe(&object(1, 10), now);
+        tracker.observe(&object(1, 11), now);
+        tracker.observe(&object(2, 20), now + OBJECT_EXPIRE_DURATION / 2);
+
+        let events = tracker.observe(&object(3, 30), now + OBJECT_EXPIRE_DURATION);
+        assert_eq!(
+            events.destroyed,
+            vec![DestroyedObject {
+                id: 1,
+                effect_ids: HashSet::from([10, 11]),
+            }]
+        );
+        assert_eq!(tracker.len(), 2);
+    }
+
+    #[test]
+    fn state_map_keeps_effects_separate_and_removes_by_object() {
+        let map = ObjectStateMap::<u32>::new();
+        *map.get_or_insert_with(&object(1, 10), || 0) += 1;
+        *map.get_or_insert_with(&object(1, 11), || 0) += 2;
+        *map.get_or_insert_with(&object(2, 20), || 0) += 3;
+        assert_eq!(*map.get(&object(1, 10)).unwrap(), 1);
+        assert_eq!(*map.get(&object(1, 11)).unwrap(), 2);
+
+        evict_object_states(&DestroyedObject {
+            id: 1,
+            effect_ids: HashSet::from([10, 11]),
+        });
+        assert_eq!(map.len(), 1);
+        assert!(map.get(&object(1, 10)).is_none());
+        assert_eq!(*map.get(&object(2, 20)).unwrap(), 3);
+    }
+
+    #[test]
+    fn eviction_keeps_states_of_other_filters() {
+        // 2つのフィルタプラグインが、同じオブジェクトのそれぞれのエフェクトを処理している
+        let map_a = ObjectStateMap::<u32>::new();
+        let map_b = ObjectStateMap::<u32>::new();
+        let start = Instant::now();
+        let tracker_a = ObjectTracker::new(start);
+        let tracker_b = ObjectTracker::new(start);
+        tracker_a.observe(&object(1, 10), start);
+        map_a.insert(&object(1, 10), 1);
+        tracker_b.observe(&object(1, 20), start);
+        map_b.insert(&object(1, 20), 2);
+
+        // フィルタAだけ処理されなくなった
+        let later = start + OBJECT_EXPIRE_DURATION;
+        tracker_b.observe(&object(1, 20), later);
+        let events = tracker_a.observe(&object(2, 11), later);
+        assert_eq!(events.destroyed.len(), 1);
+        for destroyed in &events.destroyed {
+            evict_object_states(destroyed);
+        }
+        assert!(map_a.get(&object(1, 10)).is_none());
+        assert_eq!(*map_b.get(&object(1, 20)).unwrap(), 2);
+    }
+
+    #[test]
+    fn soak_adding_and_deleting_objects_does_not_grow_unbounded() {
+        let map = ObjectStateMap::<Vec<f32>>::new();
+        let start = Instant::now();
+        let tracker = ObjectTracker::new(start);
+        let mut next_id = 0;
+        for round in 0..20u32 {
+            let now = start + OBJECT_EXPIRE_DURATION * round;
+            for _ in 0..1000 {
+                let info = object(next_id, next_id);
+                next_id += 1;
+                let events = tracker.observe(&info, now);
+                for destroyed in &events.destroyed {
+                    evict_object_states(destroyed);
+                }
+                map.get_or_insert_with(&info, || vec![0.0; 16]);
+            }
+            assert!(map.len() <= 2000, "round {round}: {} states", map.len());
+            assert!(
+                tracker.len() <= 2000,
+                "round {round}: {} objects",
+                tracker.len()
+            );
+        }
+    }
+}
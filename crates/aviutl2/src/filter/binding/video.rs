@@ -76,6 +76,123 @@ impl From<&aviutl2_sys::filter2::OBJECT_IMAGE_PARAM> for ObjectImageParam {
     }
 }
 
+/// オブジェクトの変形情報。
+///
+/// [`FilterProcVideo::object_transform`]で取得します。
+/// フィルタ効果はオブジェクトの画像に対して適用され、その後この変形で画面に配置されます。
+/// 座標と中心座標はピクセル、角度は度（360.0で1回転）、拡大率は1.0で等倍です。
+///
+/// # Note
+///
+/// [`Self::image_to_scene`]などの座標変換は2Dのみを扱います。
+/// X軸・Y軸回転、Z座標、カメラ制御はSDKからカメラの情報を取得できないため考慮しません。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObjectTransform {
+    /// 基準座標X。シーンの中央が0で、右が正。
+    pub x: f64,
+    /// 基準座標Y。シーンの中央が0で、下が正。
+    pub y: f64,
+    /// 基準座標Z。
+    pub z: f64,
+    /// X軸回転（度）。
+    pub rotation_x: f64,
+    /// Y軸回転（度）。
+    pub rotation_y: f64,
+    /// Z軸回転（度）。画面上で時計回りが正。
+    pub rotation_z: f64,
+    /// 拡大率X。
+    pub scale_x: f64,
+    /// 拡大率Y。
+    pub scale_y: f64,
+    /// 拡大率Z。
+    pub scale_z: f64,
+    /// 中心座標X。画像の中央からの相対位置で、基準座標に重なる点。
+    pub center_x: f64,
+    /// 中心座標Y。画像の中央からの相対位置で、基準座標に重なる点。
+    pub center_y: f64,
+    /// 中心座標Z。
+    pub center_z: f64,
+    /// 不透明度。（0.0〜1.0）
+    pub opacity: f64,
+    /// オブジェクトの現在の画像サイズの幅。
+    pub image_width: u32,
+    /// オブジェクトの現在の画像サイズの高さ。
+    pub image_height: u32,
+    /// シーンの解像度（幅）。
+    pub scene_width: u32,
+    /// シーンの解像度（高さ）。
+    pub scene_height: u32,
+}
+
+impl ObjectTransform {
+    /// シーン情報・画像情報・画像パラメータから変形情報を作成する。
+    pub fn new(
+        scene: &SceneInfo,
+        video_object: &VideoObjectInfo,
+        param: &ObjectImageParam,
+    ) -> Self {
+        Self {
+            x: param.x as f64,
+            y: param.y as f64,
+            z: param.z as f64,
+            rotation_x: param.rx as f64,
+            rotation_y: param.ry as f64,
+            rotation_z: param.rz as f64,
+            scale_x: param.sx as f64,
+            scale_y: param.sy as f64,
+            scale_z: param.sz as f64,
+            center_x: param.cx as f64,
+            center_y: param.cy as f64,
+            center_z: param.cz as f64,
+            opacity: param.alpha as f64,
+            image_width: video_object.width,
+            image_height: video_object.height,
+            scene_width: scene.width,
+            scene_height: scene.height,
+        }
+    }
+
+    /// 基準座標をシーンの左上を原点としたピクセル座標で返す。
+    pub fn scene_position(&self) -> (f64, f64) {
+        (
+            self.scene_width as f64 / 2.0 + self.x,
+            self.scene_height as f64 / 2.0 + self.y,
+        )
+    }
+
+    /// オブジェクトの画像上の座標（左上が原点）を、シーン上の座標（左上が原点）に変換する。
+    ///
+    /// ピクセルの中心を変換する場合は、`x + 0.5`のように0.5を足してください。
+    pub fn image_to_scene(&self, x: f64, y: f64) -> (f64, f64) {
+        let local_x = (x - self.image_width as f64 / 2.0 - self.center_x) * self.scale_x;
+        let local_y = (y - self.image_height as f64 / 2.0 - self.center_y) * self.scale_y;
+        let (sin, cos) = self.rotation_z.to_radians().sin_cos();
+        let (base_x, base_y) = self.scene_position();
+        (
+            base_x + local_x * cos - local_y * sin,
+            base_y + local_x * sin + local_y * cos,
+        )
+    }
+
+    /// シーン上の座標（左上が原点）を、オブジェクトの画像上の座標（左上が原点）に変換する。
+    ///
+    /// 拡大率が0の場合は`None`を返します。
+    pub fn scene_to_image(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        if self.scale_x == 0.0 || self.scale_y == 0.0 {
+            return None;
+        }
+        let (base_x, base_y) = self.scene_position();
+        let (dx, dy) = (x - base_x, y - base_y);
+        let (sin, cos) = self.rotation_z.to_radians().sin_cos();
+        let local_x = dx * cos + dy * sin;
+        let local_y = -dx * sin + dy * cos;
+        Some((
+            local_x / self.scale_x + self.center_x + self.image_width as f64 / 2.0,
+            local_y / self.scale_y + self.center_y + self.image_height as f64 / 2.0,
+        ))
+    }
+}
+
 /// RGBAのピクセル。
 #[derive(
     Debug, Default, Clone, Copy, PartialEq, Eq, IntoBytes, FromBytes, Immutable, KnownLayout,
@@ -178,6 +295,7 @@ pub struct FilterProcVideo {
 
     pub(crate) prevent_post_effect: bool,
 
+    pub(crate) frame_pool: crate::filter::FramePool,
     pub(crate) read_section: crate::generic::ReadSection,
     pub(crate) inner: *const aviutl2_sys::filter2::FILTER_PROC_VIDEO,
 }
@@ -801,9 +919,26 @@ fn image_data_byte_len(pitch: u32, height: u32) -> FilterProcResult<usize> {
 }
 
 impl FilterProcVideo {
+    /// オブジェクトの変形情報を取得する。
+    ///
+    /// [`Self::param`]の現在の値から作成します。
+    /// 画像のサイズは、この時点での[`Self::video_object`]のサイズです。
+    pub fn object_transform(&self) -> ObjectTransform {
+        ObjectTransform::new(&self.scene, &self.video_object, &self.param)
+    }
+
     /// 現在の画像のデータを取得する。
     /// RGBA32bit で取得されます。
     ///
+    /// 現在の画像は、このフィルタより前のフィルタ効果を適用した後の画像です。
+    /// AviUtl2にはフィルタ効果を適用する前の画像を取得する手段がないため、
+    /// 元の画像が必要な場合は[`Self::set_image_data`]を呼び出す前に取得しておいてください。
+    ///
+    /// # Note
+    ///
+    /// AviUtl2の画像フィルタはVRAM上のRGBAの画像を処理するため、YC48やYUY2で画像を取得・設定する手段はありません。
+    /// 輝度や色差だけを処理する場合も、[`crate::pixel::rgb_to_yc48`]・[`crate::pixel::yc48_to_rgb`]で変換してください。
+    ///
     /// # Panics
     ///
     /// `buffer` をバイト列に変換した際の長さが `width * height * 4` と一致しない場合、パニックします。
@@ -838,6 +973,32 @@ impl FilterProcVideo {
         width * height * 4
     }
 
+    /// 現在の画像のサイズを `(幅, 高さ)` で取得する。
+    ///
+    /// [`Self::set_image_data`] で画像を変更した場合でも、フィルタ処理開始時のサイズを返します。
+    pub fn image_size(&self) -> (u32, u32) {
+        (self.video_object.width, self.video_object.height)
+    }
+
+    /// 中間バッファのプールを取得する。
+    ///
+    /// プラグインごとに1つのプールが共有され、借りたバッファは`proc_video`の終了時までにプールに戻ります。
+    /// 詳しくは[`crate::filter::FramePool`]を参照してください。
+    pub fn frame_pool(&self) -> crate::filter::FramePool {
+        self.frame_pool.clone()
+    }
+
+    /// 現在の画像のデータをバイト列として取得する。
+    /// RGBA32bit で取得されます。
+    ///
+    /// `buffer` は `width * height * 4` バイトにリサイズされ、確保済みの領域は再利用されます。
+    /// 毎フレーム同じ `Vec` を渡すことで、[`RgbaPixel`] を経由したコピーや再確保を避けられます。
+    pub fn get_image_bytes(&mut self, buffer: &mut Vec<u8>) -> usize {
+        let (width, height) = self.image_size();
+        buffer.resize(width as usize * height as usize * 4, 0);
+        self.get_image_data(buffer.as_mut_slice())
+    }
+
     /// 現在の画像のデータを設定する。
     ///
     /// # Panics
@@ -867,6 +1028,8 @@ impl FilterProcVideo {
 
     /// 現在のオブジェクトの画像データのポインタをID3D11Texture2Dのポインタとして取得する。
     ///
+    /// `gpu-interop`フィーチャーを有効にすると、型付きの`FilterProcVideo::d3d11_texture`を使えます。
+    ///
     /// # Warning
     ///
     /// [`Self::set_image_data`] によって現在の画像が変更されるかフィルタ処理の終了まで有効です。
@@ -1600,6 +1763,10 @@ mod tests {
         ]]);
         let constant = [0u8; 16];
 
+        let mut bytes = Vec::new();
+        let (width, height) = video.image_size();
+        video.get_image_bytes(&mut bytes);
+        video.set_image_data(&bytes, width, height);
         video.set_culling_state(true);
         let _ = video.get_image_resource_texture2d(&readable)?;
         video.copy_image_resource(&readable, &writable)?;
@@ -2,7 +2,8 @@ use crate::{
     common::{AnyResult, LeakManager},
     filter::{
         AudioObjectInfo, FilterConfigItem, FilterPlugin, FilterPluginTable, FilterProcAudio,
-        FilterProcVideo, ObjectInfo, SceneInfo, VideoObjectInfo,
+        FilterProcVideo, FramePool, ObjectInfo, ObjectTracker, SceneInfo, VideoObjectInfo,
+        evict_object_states,
     },
     utils::catch_unwind_with_panic_info,
 };
@@ -21,7 +22,10 @@ impl FilterProcAudio {
     }
 }
 impl FilterProcVideo {
-    unsafe fn from_raw(raw_ptr: *const aviutl2_sys::filter2::FILTER_PROC_VIDEO) -> FilterProcVideo {
+    unsafe fn from_raw(
+        raw_ptr: *const aviutl2_sys::filter2::FILTER_PROC_VIDEO,
+        frame_pool: FramePool,
+    ) -> FilterProcVideo {
         let raw = unsafe { &*raw_ptr };
         FilterProcVideo {
             scene: unsafe { SceneInfo::from_raw(raw.scene) },
@@ -30,6 +34,7 @@ impl FilterProcVideo {
             param: unsafe { (&*raw.param).into() },
             read_section: unsafe { crate::generic::ReadSection::from_raw(raw.edit) },
             prevent_post_effect: false,
+            frame_pool,
             inner: raw_ptr,
         }
     }
@@ -97,15 +102,64 @@ pub struct InternalFilterPluginState<T: Send + Sync + FilterPlugin> {
     leak_manager: LeakManager,
     config_pointers: Vec<*const aviutl2_sys::filter2::FILTER_ITEM>,
     config_items: Vec<FilterConfigItem>,
+    object_tracker: ObjectTracker,
+    frame_pool: FramePool,
+    init: PluginInit,
+    /// 遅延している`new()`を1つのスレッドだけが呼び出すためのロック。
+    /// `new()`の間は状態のロックを取らないので、別に持つ。
+    init_lock: std::sync::Arc<std::sync::Mutex<()>>,
+    init_error_reported: std::sync::OnceLock<()>,
 
-    instance: T,
+    instance: Option<T>,
 }
 unsafe impl<T: Send + Sync + FilterPlugin> Send for InternalFilterPluginState<T> {}
 unsafe impl<T: Send + Sync + FilterPlugin> Sync for InternalFilterPluginState<T> {}
 
+/// プラグインの初期化状態。
+#[derive(Debug)]
+enum PluginInit {
+    /// `new()`が成功している。
+    Ready,
+    /// `new()`の呼び出しを最初の描画処理まで遅延している。
+    Lazy(crate::common::AviUtl2Info),
+    /// `new()`が失敗した。
+    Failed(String),
+}
+
 impl<T: Send + Sync + FilterPlugin> InternalFilterPluginState<T> {
     pub fn new(instance: T) -> Self {
         let plugin_info = instance.plugin_info();
+        Self::with_init(plugin_info, PluginInit::Ready, Some(instance))
+    }
+
+    /// `new()`の呼び出しを遅延する。
+    /// `placeholder`は`new()`が呼ばれるまでの間、プラグイン情報の取得などに使われます。
+    fn new_lazy(placeholder: T, info: crate::common::AviUtl2Info) -> Self {
+        let plugin_info = placeholder.plugin_info();
+        Self::with_init(plugin_info, PluginInit::Lazy(info), Some(placeholder))
+    }
+
+    /// `new()`が失敗したプラグインを作成する。
+    /// エラー内容を表示するため、プラグイン自体は登録されます。
+    ///
+    /// プロジェクトに保存された設定を失わないよう、`name`と`config_items`には
+    /// `plugin_info()`と同じプラグイン名と設定項目を渡す必要があります。
+    fn failed(error: String, name: String, config_items: Vec<FilterConfigItem>) -> Self {
+        let plugin_info = FilterPluginTable {
+            name,
+            label: None,
+            order: 0,
+            information: format!("プラグイン初期化に失敗しました: {error}"),
+            flags: crate::bitflag!(crate::filter::FilterPluginFlags {
+                video: true,
+                audio: true,
+            }),
+            config_items,
+        };
+        Self::with_init(plugin_info, PluginInit::Failed(error), None)
+    }
+
+    fn with_init(plugin_info: FilterPluginTable, init: PluginInit, instance: Option<T>) -> Self {
         let config_items = plugin_info.config_items.clone();
         Self {
             plugin_info,
@@ -113,11 +167,72 @@ impl<T: Send + Sync + FilterPlugin> InternalFilterPluginState<T> {
             leak_manager: LeakManager::new(),
             config_pointers: Vec::new(),
             config_items,
+            object_tracker: ObjectTracker::new(std::time::Instant::now()),
+            frame_pool: FramePool::new(),
+            init,
+            init_lock: std::sync::Arc::new(std::sync::Mutex::new(())),
+            init_error_reported: std::sync::OnceLock::new(),
 
             instance,
         }
     }
 
+    fn instance(&self) -> &T {
+        self.instance.as_ref().expect("Plugin not initialized")
+    }
+
+    /// 初期化済みのインスタンスを返す。
+    ///
+    /// `new()`を遅延している間（プラグイン情報用のインスタンスしかない間）や、
+    /// `new()`が失敗している場合はエラーを返す。
+    fn try_instance_mut(&mut self) -> AnyResult<&mut T> {
+        match &self.init {
+            PluginInit::Ready => self
+                .instance
+                .as_mut()
+                .ok_or_else(|| anyhow::anyhow!("Plugin not initialized")),
+            PluginInit::Lazy(_) => anyhow::bail!("Plugin not initialized"),
+            PluginInit::Failed(error) => anyhow::bail!("Plugin initialization failed: {error}"),
+        }
+    }
+
+    /// 初期化済みのインスタンスを返す。
+    ///
+    /// `new()`を遅延している間（プラグイン情報用のインスタンスしかない間）や、
+    /// `new()`が失敗している場合はエラーを返す。
+    fn try_instance(&self) -> AnyResult<&T> {
+        match &self.init {
+            PluginInit::Ready => self
+                .instance
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Plugin not initialized")),
+            PluginInit::Lazy(_) => anyhow::bail!("Plugin not initialized"),
+            PluginInit::Failed(error) => anyhow::bail!("Plugin initialization failed: {error}"),
+        }
+    }
+
+    /// 初期化の失敗を一度だけ報告する。
+    fn report_init_error(&self, error: &str) {
+        self.init_error_reported.get_or_init(|| {
+            tracing::error!("Plugin initialization failed: {}", error);
+            let _ =
+                crate::logger::write_error_log(&format!("プラグイン初期化に失敗しました: {error}"));
+        });
+    }
+
+    fn observe_object(&self, object: &ObjectInfo) {
+        let events = self
+            .object_tracker
+            .observe(object, std::time::Instant::now());
+        for destroyed in events.destroyed {
+            evict_object_states(&destroyed);
+            self.instance().on_object_destroyed(destroyed.id);
+        }
+        if let Some(created) = events.created {
+            self.instance().on_object_created(created);
+        }
+    }
+
     pub fn should_apply_configs(&self) -> bool {
         for (item, raw) in self.config_items.iter().zip(self.config_pointers.iter()) {
             if unsafe { item.should_apply_from_raw(*raw) } {
@@ -138,6 +253,65 @@ impl<T: Send + Sync + FilterPlugin> InternalFilterPluginState<T> {
     }
 }
 
+/// 遅延している`new()`を呼び出し、プラグインが処理可能かどうかを返す。
+///
+/// 初期化に失敗している場合、エラー内容を一度だけ報告して`false`を返します。
+/// `new()`は状態のロックを取らずに呼び出すので、その間も他の処理はブロックされません。
+fn ensure_initialized<T: Send + Sync + FilterPlugin>(
+    plugin_state: &std::sync::RwLock<Option<InternalFilterPluginState<T>>>,
+) -> bool {
+    let init_lock = {
+        let plugin_lock = plugin_state.read().unwrap();
+        let Some(plugin) = plugin_lock.as_ref() else {
+            return false;
+        };
+        match &plugin.init {
+            PluginInit::Ready => return true,
+            PluginInit::Failed(error) => {
+                plugin.report_init_error(error);
+                return false;
+            }
+            PluginInit::Lazy(_) => std::sync::Arc::clone(&plugin.init_lock),
+        }
+    };
+
+    let _init_guard = init_lock.lock().unwrap_or_else(|e| e.into_inner());
+    // ロックを取るまでの間に、他のスレッドで初期化されている可能性がある
+    let info = {
+        let plugin_lock = plugin_state.read().unwrap();
+        let Some(plugin) = plugin_lock.as_ref() else {
+            return false;
+        };
+        match &plugin.init {
+            PluginInit::Ready => return true,
+            PluginInit::Failed(error) => {
+                plugin.report_init_error(error);
+                return false;
+            }
+            PluginInit::Lazy(info) => info.clone(),
+        }
+    };
+    let result = T::new(info);
+
+    let mut plugin_lock = plugin_state.write().unwrap();
+    let Some(plugin) = plugin_lock.as_mut() else {
+        return false;
+    };
+    match result {
+        Ok(instance) => {
+            plugin.instance = Some(instance);
+            plugin.init = PluginInit::Ready;
+            true
+        }
+        Err(e) => {
+            let error = format!("{e}");
+            plugin.report_init_error(&error);
+            plugin.init = PluginInit::Failed(error);
+            false
+        }
+    }
+}
+
 fn update_configs<T: Send + Sync + FilterPlugin>(
     plugin_state: &std::sync::RwLock<Option<InternalFilterPluginState<T>>>,
 ) {
@@ -163,17 +337,41 @@ where
 {
     fn __get_singleton_state()
     -> &'static std::sync::RwLock<Option<crate::filter::__bridge::InternalFilterPluginState<Self>>>;
-    fn with_instance<R>(f: impl FnOnce(&Self) -> R) -> R {
+    /// `#[aviutl2::plugin(FilterPlugin, lazy)]`の場合、`new()`を遅延している間に使うインスタンスを返す。
+    fn __lazy_default() -> Option<Self> {
+        None
+    }
+    /// `#[aviutl2::plugin(FilterPlugin, label = ...)]`で指定されたラベル。
+    fn __default_label() -> Option<&'static str> {
+        None
+    }
+    /// `#[aviutl2::plugin(FilterPlugin, order = ...)]`で指定された登録順のヒント。
+    fn __default_order() -> i32 {
+        0
+    }
+    /// `#[aviutl2::plugin(FilterPlugin, name = ...)]`で指定されたプラグイン名。
+    fn __declared_name() -> Option<&'static str> {
+        None
+    }
+    /// `#[aviutl2::plugin(FilterPlugin, config_items = ...)]`で指定された型の設定項目。
+    fn __declared_config_items() -> Option<Vec<FilterConfigItem>> {
+        None
+    }
+    fn with_instance<R>(f: impl FnOnce(&Self) -> R) -> AnyResult<R> {
         let lock = Self::__get_singleton_state();
         let guard = lock.read().unwrap();
-        let state = guard.as_ref().expect("Plugin not initialized");
-        f(&state.instance)
+        let state = guard
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Plugin not initialized"))?;
+        Ok(f(state.try_instance()?))
     }
-    fn with_instance_mut<R>(f: impl FnOnce(&mut Self) -> R) -> R {
+    fn with_instance_mut<R>(f: impl FnOnce(&mut Self) -> R) -> AnyResult<R> {
         let lock = Self::__get_singleton_state();
         let mut guard = lock.write().unwrap();
-        let state = guard.as_mut().expect("Plugin not initialized");
-        f(&mut state.instance)
+        let state = guard
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Plugin not initialized"))?;
+        Ok(f(state.try_instance_mut()?))
     }
 }
 
@@ -205,18 +403,95 @@ pub unsafe fn initialize_plugin_c_unwind<T: FilterSingleton>(version: u32) -> bo
 pub(crate) fn initialize_plugin<T: FilterSingleton>(version: u32) -> AnyResult<()> {
     crate::common::ensure_minimum_aviutl2_version(version.into())?;
     let plugin_state = T::__get_singleton_state();
-    let info = crate::common::AviUtl2Info {
-        version: version.into(),
+    let info = crate::common::AviUtl2Info::new(version);
+    let mut plugin = match T::__lazy_default() {
+        Some(placeholder) => InternalFilterPluginState::new_lazy(placeholder, info),
+        None => match T::new(info) {
+            Ok(internal) => InternalFilterPluginState::new(internal),
+            Err(e) => {
+                // プラグインが一覧から消えると原因が分からないため、エラーを保持して登録は続ける
+                tracing::error!("Failed to initialize plugin: {}", e);
+                InternalFilterPluginState::failed(
+                    format!("{e}"),
+                    failed_plugin_name::<T>(),
+                    T::__declared_config_items().unwrap_or_default(),
+                )
+            }
+        },
     };
-    let internal = T::new(info)?;
-    let plugin = InternalFilterPluginState::new(internal);
+    apply_attribute_defaults::<T>(&mut plugin.plugin_info);
+    *plugin_state.write().unwrap() = Some(plugin);
+
+    Ok(())
+}
+
+/// 作成済みのインスタンスでプラグインを初期化する。
+///
+/// `lazy`の指定に関わらず、渡したインスタンスをそのまま使う。
+pub(crate) fn initialize_plugin_with<T: FilterSingleton>(
+    version: u32,
+    instance: T,
+) -> AnyResult<()> {
+    crate::common::ensure_minimum_aviutl2_version(version.into())?;
+    let plugin_state = T::__get_singleton_state();
+    let mut plugin = InternalFilterPluginState::new(instance);
+    apply_attribute_defaults::<T>(&mut plugin.plugin_info);
     *plugin_state.write().unwrap() = Some(plugin);
 
     Ok(())
 }
+
+/// `new()`が失敗したときに登録するプラグイン名を返す。
+///
+/// `#[aviutl2::plugin(FilterPlugin, name = ...)]`で指定されていない場合は型名を使う。
+fn failed_plugin_name<T: FilterSingleton>() -> String {
+    T::__declared_name().map_or_else(
+        || {
+            let type_name = std::any::type_name::<T>();
+            tracing::warn!(
+                "Plugin name is not declared, registering {} by its type name; specify `name` in #[aviutl2::plugin] to keep project settings",
+                type_name
+            );
+            type_name
+                .rsplit("::")
+                .next()
+                .unwrap_or(type_name)
+                .to_string()
+        },
+        str::to_string,
+    )
+}
+
+/// `plugin_info()`が既定値のままにしたラベルと登録順を、属性マクロで指定された値で埋める。
+fn apply_attribute_defaults<T: FilterSingleton>(plugin_info: &mut FilterPluginTable) {
+    if plugin_info.label.is_none() {
+        plugin_info.label = T::__default_label().map(str::to_string);
+    }
+    if plugin_info.order == 0 {
+        plugin_info.order = T::__default_order();
+    }
+}
+
+/// 登録順のヒント（[`FilterPluginTable::order`]）を返す。
+pub(crate) fn registration_order<T: FilterSingleton>() -> i32 {
+    let plugin_state = T::__get_singleton_state();
+    let plugin_state = plugin_state.read().unwrap();
+    plugin_state
+        .as_ref()
+        .map_or(0, |state| state.plugin_info.order)
+}
+
 pub unsafe fn uninitialize_plugin<T: FilterSingleton>() {
     let plugin_state = T::__get_singleton_state();
     let mut plugin_state = plugin_state.write().unwrap();
+    if let Some(state) = plugin_state.as_ref()
+        && let Some(instance) = state.instance.as_ref()
+    {
+        for destroyed in state.object_tracker.drain() {
+            evict_object_states(&destroyed);
+            instance.on_object_destroyed(destroyed.id);
+        }
+    }
     *plugin_state = None;
 }
 
@@ -241,7 +516,10 @@ fn create_table_impl<T: FilterSingleton>(
     let plugin_info = &plugin_state.plugin_info;
 
     let name = plugin_info.name.clone();
-    let information = plugin_info.information.clone();
+    let information = crate::filter::config::information_with_tooltips(
+        &plugin_info.information,
+        &plugin_info.config_items,
+    );
 
     let config_items = plugin_info
         .config_items
@@ -315,38 +593,48 @@ fn proc_video_impl<T: FilterSingleton>(
 ) -> AnyResult<bool> {
     let plugin_lock = T::__get_singleton_state();
     anyhow::ensure!(!plugin_lock.is_poisoned(), "Plugin state lock is poisoned");
+    if !ensure_initialized::<T>(plugin_lock) {
+        return Ok(false);
+    }
     update_configs::<T>(plugin_lock);
     let plugin_state = plugin_lock.read().unwrap();
     let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
 
     plugin_state.leak_manager.free_leaked_memory();
-    let plugin = &plugin_state.instance;
-    let mut video = unsafe { FilterProcVideo::from_raw(video) };
-    plugin.proc_video(&plugin_state.config_items, &mut video)?;
+    let plugin = plugin_state.instance();
+    let mut video = unsafe { FilterProcVideo::from_raw(video, plugin_state.frame_pool.clone()) };
+    plugin_state.observe_object(&video.object);
+    let result = plugin.proc_video(&plugin_state.config_items, &mut video);
+    plugin_state.frame_pool.end_call();
+    result?;
     video.apply_param();
-    Ok(video.prevent_post_effect)
+    Ok(!video.prevent_post_effect)
 }
 
 fn proc_audio_impl<T: FilterSingleton>(
     audio: *mut aviutl2_sys::filter2::FILTER_PROC_AUDIO,
-) -> AnyResult<()> {
+) -> AnyResult<bool> {
     let plugin_lock = T::__get_singleton_state();
+    if !ensure_initialized::<T>(plugin_lock) {
+        return Ok(false);
+    }
     update_configs::<T>(plugin_lock);
     let plugin_state = plugin_lock.read().unwrap();
     let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
     plugin_state.leak_manager.free_leaked_memory();
-    let plugin = &plugin_state.instance;
+    let plugin = plugin_state.instance();
     let mut audio = unsafe { FilterProcAudio::from_raw(audio) };
+    plugin_state.observe_object(&audio.object);
     plugin.proc_audio(&plugin_state.config_items, &mut audio)?;
     audio.apply_param();
-    Ok(())
+    Ok(true)
 }
 
 extern "C" fn func_proc_video<T: FilterSingleton>(
     video: *mut aviutl2_sys::filter2::FILTER_PROC_VIDEO,
 ) -> bool {
     match proc_video_impl::<T>(video) {
-        Ok(prevent_post_effect) => !prevent_post_effect,
+        Ok(result) => result,
         Err(e) => {
             tracing::error!("Error in proc_video: {}", e);
             false
@@ -357,7 +645,7 @@ extern "C" fn func_proc_video_unwind<T: FilterSingleton>(
     video: *mut aviutl2_sys::filter2::FILTER_PROC_VIDEO,
 ) -> bool {
     match catch_unwind_with_panic_info(|| proc_video_impl::<T>(video)) {
-        Ok(Ok(prevent_post_effect)) => !prevent_post_effect,
+        Ok(Ok(result)) => result,
         Ok(Err(e)) => {
             tracing::error!("Error in proc_video: {}", e);
             false
@@ -372,7 +660,7 @@ extern "C" fn func_proc_audio<T: FilterSingleton>(
     audio: *mut aviutl2_sys::filter2::FILTER_PROC_AUDIO,
 ) -> bool {
     match proc_audio_impl::<T>(audio) {
-        Ok(()) => true,
+        Ok(result) => result,
         Err(e) => {
             tracing::error!("Error in proc_audio: {}", e);
             false
@@ -383,7 +671,7 @@ extern "C" fn func_proc_audio_unwind<T: FilterSingleton>(
     audio: *mut aviutl2_sys::filter2::FILTER_PROC_AUDIO,
 ) -> bool {
     match catch_unwind_with_panic_info(|| proc_audio_impl::<T>(audio)) {
-        Ok(Ok(())) => true,
+        Ok(Ok(result)) => result,
         Ok(Err(e)) => {
             tracing::error!("Error in proc_audio: {}", e);
             false
@@ -492,3 +780,387 @@ macro_rules! register_filter_plugin {
         $crate::register_filter_plugin!($struct, );
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{FilterSingleton, InternalFilterPluginState, PluginInit, ensure_initialized};
+    use crate::filter::{FilterPlugin, FilterPluginTable};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    macro_rules! test_plugin {
+        ($name:ident, $new_calls:ident, $new:expr, $lazy:expr) => {
+            test_plugin!($name, $new_calls, $new, $lazy, None, None);
+        };
+        (
+            $name:ident,
+            $new_calls:ident,
+            $new:expr,
+            $lazy:expr,
+            $declared_name:expr,
+            $declared_config_items:expr
+        ) => {
+            static $new_calls: AtomicUsize = AtomicUsize::new(0);
+
+            #[derive(Default)]
+            struct $name;
+
+            impl FilterPlugin for $name {
+                fn new(_info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self> {
+                    $new_calls.fetch_add(1, Ordering::SeqCst);
+                    $new
+                }
+
+                fn plugin_info(&self) -> FilterPluginTable {
+                    FilterPluginTable {
+                        name: stringify!($name).to_string(),
+                        label: None,
+                        order: 0,
+                        information: String::new(),
+                        flags: crate::bitflag!(crate::filter::FilterPluginFlags { video: true }),
+                        config_items: vec![],
+                    }
+                }
+            }
+
+            impl FilterSingleton for $name {
+                fn __get_singleton_state()
+                -> &'static std::sync::RwLock<Option<InternalFilterPluginState<Self>>> {
+                    static PLUGIN: std::sync::RwLock<Option<InternalFilterPluginState<$name>>> =
+                        std::sync::RwLock::new(None);
+                    &PLUGIN
+                }
+
+                fn __lazy_default() -> Option<Self> {
+                    $lazy.then(Self::default)
+                }
+
+                fn __declared_name() -> Option<&'static str> {
+                    $declared_name
+                }
+
+                fn __declared_config_items() -> Option<Vec<crate::filter::FilterConfigItem>> {
+                    $declared_config_items
+                }
+            }
+        };
+    }
+
+    test_plugin!(
+        FailingPlugin,
+        FAILING_NEW_CALLS,
+        Err(anyhow::anyhow!("adapter not found")),
+        false,
+        Some("Failing Plugin"),
+        Some(vec![crate::filter::FilterConfigItem::Track(
+            crate::filter::FilterConfigTrack {
+                name: "強さ".to_string(),
+                tooltip: None,
+                value: 50.0,
+                range: 0.0..=100.0,
+                step: 1.0,
+                zero_display: None,
+                slider_ratio: 1.0,
+            }
+        )])
+    );
+    test_plugin!(
+        LockCheckingPlugin,
+        LOCK_CHECKING_NEW_CALLS,
+        if LockCheckingPlugin::__get_singleton_state()
+            .try_write()
+            .is_ok()
+        {
+            Ok(Self)
+        } else {
+            Err(anyhow::anyhow!("state is locked during new()"))
+        },
+        true
+    );
+    test_plugin!(
+        UndeclaredFailingPlugin,
+        UNDECLARED_FAILING_NEW_CALLS,
+        Err(anyhow::anyhow!("adapter not found")),
+        false
+    );
+    test_plugin!(LazyPlugin, LAZY_NEW_CALLS, Ok(Self), true);
+    test_plugin!(
+        LazyFailingPlugin,
+        LAZY_FAILING_NEW_CALLS,
+        Err(anyhow::anyhow!("adapter not found")),
+        true
+    );
+
+    fn initialize<T: FilterSingleton>() {
+        super::initialize_plugin::<T>(crate::common::MINIMUM_AVIUTL2_VERSION.into()).unwrap();
+    }
+
+    fn is_error_reported<T: FilterSingleton>() -> bool {
+        let state = T::__get_singleton_state().read().unwrap();
+        state.as_ref().unwrap().init_error_reported.get().is_some()
+    }
+
+    #[test]
+    fn failing_new_still_registers_plugin() {
+        initialize::<FailingPlugin>();
+        assert_eq!(FAILING_NEW_CALLS.load(Ordering::SeqCst), 1);
+
+        let table = unsafe { &*super::create_table::<FailingPlugin>() };
+        assert_eq!(
+            unsafe { crate::common::load_wide_string(table.name) },
+            "Failing Plugin"
+        );
+        // 設定項目が残っていないと、プロジェクトに保存された値が失われる
+        {
+            let state = FailingPlugin::__get_singleton_state().read().unwrap();
+            let state = state.as_ref().unwrap();
+            assert_eq!(state.plugin_info.config_items.len(), 1);
+            // null終端を含む
+            assert_eq!(state.config_pointers.len(), 2);
+        }
+        let error = <FailingPlugin as FilterSingleton>::with_instance(|_| ()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Plugin initialization failed: adapter not found"
+        );
+        assert!(<FailingPlugin as FilterSingleton>::with_instance_mut(|_| ()).is_err());
+        assert_eq!(
+            unsafe { crate::common::load_wide_string(table.information) },
+            "プラグイン初期化に失敗しました: adapter not found"
+        );
+
+        assert!(!is_error_reported::<FailingPlugin>());
+        assert!(!ensure_initialized(FailingPlugin::__get_singleton_state()));
+        assert!(is_error_reported::<FailingPlugin>());
+        assert!(!ensure_initialized(FailingPlugin::__get_singleton_state()));
+        assert_eq!(FAILING_NEW_CALLS.load(Ordering::SeqCst), 1);
+
+        unsafe { super::uninitialize_plugin::<FailingPlugin>() };
+    }
+
+    #[test]
+    fn lazy_plugin_defers_new_until_first_proc() {
+        initialize::<LazyPlugin>();
+        assert_eq!(LAZY_NEW_CALLS.load(Ordering::SeqCst), 0);
+
+        let table = unsafe { &*super::create_table::<LazyPlugin>() };
+        assert_eq!(
+            unsafe { crate::common::load_wide_string(table.name) },
+            "LazyPlugin"
+        );
+        assert_eq!(LAZY_NEW_CALLS.load(Ordering::SeqCst), 0);
+
+        assert!(ensure_initialized(LazyPlugin::__get_singleton_state()));
+        assert!(ensure_initialized(LazyPlugin::__get_singleton_state()));
+        assert_eq!(LAZY_NEW_CALLS.load(Ordering::SeqCst), 1);
+
+        unsafe { super::uninitialize_plugin::<LazyPlugin>() };
+    }
+
+    #[test]
+    fn lazy_plugin_reports_failing_new() {
+        initialize::<LazyFailingPlugin>();
+        assert!(!ensure_initialized(
+            LazyFailingPlugin::__get_singleton_state()
+        ));
+        assert!(!ensure_initialized(
+            LazyFailingPlugin::__get_singleton_state()
+        ));
+        assert_eq!(LAZY_FAILING_NEW_CALLS.load(Ordering::SeqCst), 1);
+        assert!(is_error_reported::<LazyFailingPlugin>());
+
+        let state = LazyFailingPlugin::__get_singleton_state().read().unwrap();
+        assert!(matches!(
+            state.as_ref().unwrap().init,
+            PluginInit::Failed(ref e) if e == "adapter not found"
+        ));
+    }
+
+    #[test]
+    fn lazy_plugin_constructs_outside_state_lock() {
+        initialize::<LockCheckingPlugin>();
+        assert!(<LockCheckingPlugin as FilterSingleton>::with_instance(|_| ()).is_err());
+        assert!(ensure_initialized(
+            LockCheckingPlugin::__get_singleton_state()
+        ));
+        assert_eq!(LOCK_CHECKING_NEW_CALLS.load(Ordering::SeqCst), 1);
+        assert!(<LockCheckingPlugin as FilterSingleton>::with_instance(|_| ()).is_ok());
+
+        unsafe { super::uninitialize_plugin::<LockCheckingPlugin>() };
+    }
+
+    #[test]
+    fn failing_new_falls_back_to_type_name() {
+        initialize::<UndeclaredFailingPlugin>();
+        let table = unsafe { &*super::create_table::<UndeclaredFailingPlugin>() };
+        assert_eq!(
+            unsafe { crate::common::load_wide_string(table.name) },
+            "UndeclaredFailingPlugin"
+        );
+        assert_eq!(UNDECLARED_FAILING_NEW_CALLS.load(Ordering::SeqCst), 1);
+
+        unsafe { super::uninitialize_plugin::<UndeclaredFailingPlugin>() };
+    }
+
+    fn raw_object_info(frame: i32, frame_s: i32, flag: i32) -> aviutl2_sys::filter2::OBJECT_INFO {
+        aviutl2_sys::filter2::OBJECT_INFO {
+            id: 1,
+            frame,
+            frame_total: 60,
+            time: frame as f64 / 30.0,
+            time_total: 2.0,
+            width: 0,
+            height: 0,
+            sample_index: 0,
+            sample_total: 0,
+            sample_num: 0,
+            channel_num: 0,
+            effect_id: 2,
+            flag,
+            layer: 0,
+            index: 0,
+            num: 1,
+            frame_s,
+            frame_e: frame_s + 59,
+        }
+    }
+
+    #[test]
+    fn proc_context_is_built_from_raw_info() {
+        let scene = aviutl2_sys::filter2::SCENE_INFO {
+            width: 1920,
+            height: 1080,
+            rate: 30000,
+            scale: 1001,
+            sample_rate: 48000,
+        };
+        let scene = unsafe { crate::filter::SceneInfo::from_raw(&scene) };
+        let object = raw_object_info(
+            15,
+            285,
+            aviutl2_sys::filter2::OBJECT_INFO::FLAG_FILTER_OBJECT,
+        );
+        let object = unsafe { crate::filter::ObjectInfo::from_raw(&object) };
+        assert!(object.is_filter_object);
+
+        let context = crate::filter::ProcContext::new(&scene, &object);
+        assert_eq!(context.object_frame, 15);
+        assert_eq!(context.object_total_frames, 60);
+        assert!((context.scene_time - 300.0 * 1001.0 / 30000.0).abs() < 1e-9);
+        assert!(!context.is_exporting);
+        assert!(!context.is_fast_preview);
+    }
+
+    fn raw_image_param() -> aviutl2_sys::filter2::OBJECT_IMAGE_PARAM {
+        aviutl2_sys::filter2::OBJECT_IMAGE_PARAM {
+            x: 100.0,
+            y: -50.0,
+            z: 0.0,
+            rx: 0.0,
+            ry: 0.0,
+            rz: 0.0,
+            sx: 1.0,
+            sy: 1.0,
+            sz: 1.0,
+            cx: 0.0,
+            cy: 0.0,
+            cz: 0.0,
+            alpha: 0.5,
+        }
+    }
+
+    fn object_transform(
+        param: aviutl2_sys::filter2::OBJECT_IMAGE_PARAM,
+    ) -> crate::filter::ObjectTransform {
+        let scene = aviutl2_sys::filter2::SCENE_INFO {
+            width: 1920,
+            height: 1080,
+            rate: 60,
+            scale: 1,
+            sample_rate: 48000,
+        };
+        let mut object = raw_object_info(0, 0, 0);
+        object.width = 200;
+        object.height = 100;
+        let scene = unsafe { crate::filter::SceneInfo::from_raw(&scene) };
+        let video_object = unsafe { crate::filter::VideoObjectInfo::from_raw(&object) };
+        crate::filter::ObjectTransform::new(&scene, &video_object, &param.into())
+    }
+
+    fn assert_point_eq(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-6 && (actual.1 - expected.1).abs() < 1e-6,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn object_transform_is_built_from_raw_info() {
+        let transform = object_transform(raw_image_param());
+        assert_eq!(transform.x, 100.0);
+        assert_eq!(transform.y, -50.0);
+        assert_eq!(transform.opacity, 0.5);
+        assert_eq!((transform.image_width, transform.image_height), (200, 100));
+        assert_eq!(
+            (transform.scene_width, transform.scene_height),
+            (1920, 1080)
+        );
+        assert_eq!(transform.scene_position(), (1060.0, 490.0));
+
+        // 画像の中央が基準座標に、左上が幅・高さの半分だけ左上に来る
+        assert_point_eq(transform.image_to_scene(100.0, 50.0), (1060.0, 490.0));
+        assert_point_eq(transform.image_to_scene(0.0, 0.0), (960.0, 440.0));
+    }
+
+    #[test]
+    fn object_transform_applies_center_scale_and_rotation() {
+        let transform = object_transform(aviutl2_sys::filter2::OBJECT_IMAGE_PARAM {
+            x: 0.0,
+            y: 0.0,
+            rz: 90.0,
+            sx: 2.0,
+            sy: 2.0,
+            cx: 50.0,
+            ..raw_image_param()
+        });
+        // 中心座標の点が基準座標に重なる
+        assert_point_eq(transform.image_to_scene(150.0, 50.0), (960.0, 540.0));
+        // 右に10px（拡大して20px）の点は、時計回りに90度回転して下に来る
+        assert_point_eq(transform.image_to_scene(160.0, 50.0), (960.0, 560.0));
+
+        let (x, y) = transform.image_to_scene(12.0, 34.0);
+        assert_point_eq(transform.scene_to_image(x, y).unwrap(), (12.0, 34.0));
+
+        let collapsed = object_transform(aviutl2_sys::filter2::OBJECT_IMAGE_PARAM {
+            sx: 0.0,
+            ..raw_image_param()
+        });
+        assert_eq!(collapsed.scene_to_image(0.0, 0.0), None);
+    }
+
+    #[test]
+    fn proc_context_maps_edit_state() {
+        use aviutl2_sys::plugin2::EDIT_HANDLE;
+
+        let object = unsafe { crate::filter::ObjectInfo::from_raw(&raw_object_info(0, 0, 0)) };
+        assert!(!object.is_filter_object);
+        let scene = crate::filter::SceneInfo {
+            width: 1920,
+            height: 1080,
+            frame_rate: crate::common::Rational32::new(30, 1),
+            sample_rate: 48000,
+        };
+        let context = crate::filter::ProcContext::new(&scene, &object);
+
+        let cases = [
+            (EDIT_HANDLE::EDIT_STATE_EDIT, false, false),
+            (EDIT_HANDLE::EDIT_STATE_PLAY, false, true),
+            (EDIT_HANDLE::EDIT_STATE_SAVE, true, false),
+        ];
+        for (raw, is_exporting, is_fast_preview) in cases {
+            let state = crate::generic::EditState::try_from(raw).unwrap();
+            let context = context.with_edit_state(state);
+            assert_eq!(context.is_exporting, is_exporting, "state: {raw}");
+            assert_eq!(context.is_fast_preview, is_fast_preview, "state: {raw}");
+        }
+    }
+}
@@ -44,6 +44,95 @@ impl FilterConfigItemSliceExt for &[FilterConfigItem] {
     }
 }
 
+/// オブジェクトごとに前回の設定を覚えておき、設定が変わったかどうかを判定する。
+///
+/// [`crate::filter::FilterPlugin::proc_video`]などには毎回すべての設定が渡されるため、
+/// 設定から作るバッファや係数などを、設定が変わったときだけ作り直すために使います。
+/// 前回の設定は[`crate::filter::ObjectStateMap`]と同じく`(オブジェクトのID, エフェクトのID)`ごとに保持され、
+/// オブジェクトが削除されたときに自動で削除されます。
+///
+/// # Note
+///
+/// AviUtl2には設定の変更を通知する仕組みがないため、毎回前回の設定と比較します。
+///
+/// # Example
+///
+/// ```rust
+/// # #[aviutl2::filter::filter_config_items]
+/// # #[derive(Debug, Clone, PartialEq)]
+/// # struct Config {
+/// #     #[track(name = "強さ", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+/// #     strength: f64,
+/// # }
+/// # fn process(
+/// #     tracker: &aviutl2::filter::ConfigChangeTracker<Config>,
+/// #     config: &[aviutl2::filter::FilterConfigItem],
+/// #     video: &aviutl2::filter::FilterProcVideo,
+/// # ) {
+/// let (config, changed) = tracker.changed(&video.object, config);
+/// if changed {
+///     // 設定から作るものを作り直す
+/// }
+/// # }
+/// ```
+pub struct ConfigChangeTracker<T> {
+    last: crate::filter::ObjectStateMap<T>,
+}
+
+impl<T: FilterConfigItems + PartialEq + Clone + Send + Sync + 'static> ConfigChangeTracker<T> {
+    /// 新しいトラッカーを作成する。
+    pub fn new() -> Self {
+        Self {
+            last: crate::filter::ObjectStateMap::new(),
+        }
+    }
+
+    /// 設定を構造体に変換し、前回から変わったかどうかと一緒に返す。
+    ///
+    /// そのオブジェクトで初めて呼ばれた場合は、変わったものとして扱います。
+    pub fn changed(
+        &self,
+        object: &crate::filter::ObjectInfo,
+        items: &[FilterConfigItem],
+    ) -> (T, bool) {
+        let config = T::from_config_items(items);
+        let mut last = match self.last.get(object) {
+            Some(last) => last,
+            None => {
+                self.last.insert(object, config.clone());
+                return (config, true);
+            }
+        };
+        if *last == config {
+            (config, false)
+        } else {
+            *last = config.clone();
+            (config, true)
+        }
+    }
+
+    /// オブジェクトの前回の設定を忘れる。次の[`Self::changed`]は変わったものとして扱われます。
+    pub fn forget(&self, object: &crate::filter::ObjectInfo) {
+        self.last.remove(object);
+    }
+}
+
+impl<T: FilterConfigItems + PartialEq + Clone + Send + Sync + 'static> Default
+    for ConfigChangeTracker<T>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> std::fmt::Debug for ConfigChangeTracker<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigChangeTracker")
+            .field("objects", &self.last.len())
+            .finish()
+    }
+}
+
 /// フィルタの設定。
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
@@ -123,6 +212,21 @@ impl FilterConfigItem {
         }
     }
 
+    /// 設定項目の説明を取得します。
+    ///
+    /// 説明を持たない種類の設定項目の場合は`None`を返します。
+    pub fn tooltip(&self) -> Option<&str> {
+        match self {
+            FilterConfigItem::Track(item) => item.tooltip.as_deref(),
+            FilterConfigItem::Checkbox(item) => item.tooltip.as_deref(),
+            FilterConfigItem::CheckSection(item) => item.tooltip.as_deref(),
+            FilterConfigItem::Color(item) => item.tooltip.as_deref(),
+            FilterConfigItem::Select(item) => item.tooltip.as_deref(),
+            FilterConfigItem::File(item) => item.tooltip.as_deref(),
+            _ => None,
+        }
+    }
+
     pub(crate) fn to_raw(&self, leak_manager: &LeakManager) -> aviutl2_sys::filter2::FILTER_ITEM {
         match self {
             FilterConfigItem::Track(item) => aviutl2_sys::filter2::FILTER_ITEM {
@@ -455,12 +559,32 @@ impl FilterConfigItem {
     }
 }
 
+/// 設定項目の説明をプラグインの情報に追記する。
+///
+/// AviUtl2には設定項目の説明を表示する欄がないため、「プラグイン情報」ダイアログで確認できるようにする。
+pub(crate) fn information_with_tooltips(information: &str, items: &[FilterConfigItem]) -> String {
+    let tooltips = items
+        .iter()
+        .filter_map(|item| Some((item.name(), item.tooltip()?)))
+        .map(|(name, tooltip)| format!("・{name}：{tooltip}"))
+        .collect::<Vec<_>>();
+    if tooltips.is_empty() {
+        return information.to_string();
+    }
+    format!("{information}\n\n■ 設定項目の説明\n{}", tooltips.join("\n"))
+}
+
 /// トラックバー。
 #[derive(Debug, Clone)]
 pub struct FilterConfigTrack {
     /// 設定名。
     pub name: String,
 
+    /// 設定項目の説明。
+    ///
+    /// AviUtl2には説明を表示する欄がないため、登録時にプラグインの情報に追記されます。
+    pub tooltip: Option<String>,
+
     /// 設定値。
     pub value: f64,
 
@@ -483,6 +607,11 @@ pub struct FilterConfigCheckbox {
     /// 設定名。
     pub name: String,
 
+    /// 設定項目の説明。
+    ///
+    /// AviUtl2には説明を表示する欄がないため、登録時にプラグインの情報に追記されます。
+    pub tooltip: Option<String>,
+
     /// 設定値。
     pub value: bool,
 }
@@ -493,6 +622,11 @@ pub struct FilterConfigCheckSection {
     /// 設定名。
     pub name: String,
 
+    /// 設定項目の説明。
+    ///
+    /// AviUtl2には説明を表示する欄がないため、登録時にプラグインの情報に追記されます。
+    pub tooltip: Option<String>,
+
     /// 設定値。
     pub value: bool,
 
@@ -505,6 +639,11 @@ pub struct FilterConfigCheckSection {
 pub struct FilterConfigColor {
     /// 設定名。
     pub name: String,
+
+    /// 設定項目の説明。
+    ///
+    /// AviUtl2には説明を表示する欄がないため、登録時にプラグインの情報に追記されます。
+    pub tooltip: Option<String>,
     /// 設定値。
     pub value: FilterConfigColorValue,
 }
@@ -526,6 +665,22 @@ impl FilterConfigColorValue {
         let value = (r as u32) << 16 | (g as u32) << 8 | (b as u32);
         FilterConfigColorValue(value)
     }
+
+    /// 不透明度を付けて [`RgbaColorValue`] に変換します。
+    pub fn to_rgba(&self, alpha: u8) -> RgbaColorValue {
+        let (r, g, b) = self.to_rgb();
+        RgbaColorValue { r, g, b, a: alpha }
+    }
+}
+impl From<(u8, u8, u8)> for FilterConfigColorValue {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        FilterConfigColorValue::from_rgb(r, g, b)
+    }
+}
+impl From<FilterConfigColorValue> for (u8, u8, u8) {
+    fn from(value: FilterConfigColorValue) -> Self {
+        value.to_rgb()
+    }
 }
 impl From<u32> for FilterConfigColorValue {
     fn from(value: u32) -> Self {
@@ -563,12 +718,114 @@ impl std::fmt::UpperHex for FilterConfigColorValue {
         write!(f, "{:06X}", self.0 & 0xFFFFFF)
     }
 }
+#[cfg(feature = "egui")]
+impl From<FilterConfigColorValue> for egui::Color32 {
+    fn from(value: FilterConfigColorValue) -> Self {
+        let (r, g, b) = value.to_rgb();
+        egui::Color32::from_rgb(r, g, b)
+    }
+}
+#[cfg(feature = "image")]
+impl From<FilterConfigColorValue> for image::Rgb<u8> {
+    fn from(value: FilterConfigColorValue) -> Self {
+        let (r, g, b) = value.to_rgb();
+        image::Rgb([r, g, b])
+    }
+}
+
+/// 不透明度付きの色。
+///
+/// `#[color(with_alpha_track = "...")]`を指定した設定項目の値として使われます。
+/// 各成分はストレートα（乗算されていない値）です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct RgbaColorValue {
+    /// 赤。
+    pub r: u8,
+    /// 緑。
+    pub g: u8,
+    /// 青。
+    pub b: u8,
+    /// 不透明度。
+    pub a: u8,
+}
+impl RgbaColorValue {
+    /// 不透明度を除いた色を取得します。
+    pub fn color(&self) -> FilterConfigColorValue {
+        FilterConfigColorValue::from_rgb(self.r, self.g, self.b)
+    }
+}
+impl From<(u8, u8, u8, u8)> for RgbaColorValue {
+    fn from((r, g, b, a): (u8, u8, u8, u8)) -> Self {
+        RgbaColorValue { r, g, b, a }
+    }
+}
+impl From<RgbaColorValue> for (u8, u8, u8, u8) {
+    fn from(value: RgbaColorValue) -> Self {
+        (value.r, value.g, value.b, value.a)
+    }
+}
+impl From<RgbaColorValue> for crate::filter::RgbaPixel {
+    fn from(value: RgbaColorValue) -> Self {
+        crate::filter::RgbaPixel {
+            r: value.r,
+            g: value.g,
+            b: value.b,
+            a: value.a,
+        }
+    }
+}
+impl std::fmt::Display for RgbaColorValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#{:02X}{:02X}{:02X}{:02X}",
+            self.r, self.g, self.b, self.a
+        )
+    }
+}
+#[cfg(feature = "egui")]
+impl From<RgbaColorValue> for egui::Color32 {
+    fn from(value: RgbaColorValue) -> Self {
+        egui::Color32::from_rgba_unmultiplied(value.r, value.g, value.b, value.a)
+    }
+}
+#[cfg(feature = "egui")]
+impl From<egui::Color32> for RgbaColorValue {
+    fn from(value: egui::Color32) -> Self {
+        let [r, g, b, a] = value.to_srgba_unmultiplied();
+        RgbaColorValue { r, g, b, a }
+    }
+}
+#[cfg(feature = "image")]
+impl From<RgbaColorValue> for image::Rgba<u8> {
+    fn from(value: RgbaColorValue) -> Self {
+        image::Rgba([value.r, value.g, value.b, value.a])
+    }
+}
+#[cfg(feature = "image")]
+impl From<image::Rgba<u8>> for RgbaColorValue {
+    fn from(value: image::Rgba<u8>) -> Self {
+        let [r, g, b, a] = value.0;
+        RgbaColorValue { r, g, b, a }
+    }
+}
+
+/// 0〜100の不透明度を0〜255のアルファ値に変換する。
+#[doc(hidden)]
+pub fn __opacity_to_alpha(opacity: f64) -> u8 {
+    (opacity.clamp(0.0, 100.0) * 255.0 / 100.0).round() as u8
+}
 
 /// 選択リスト。
 #[derive(Debug, Clone)]
 pub struct FilterConfigSelect {
     /// 設定名。
     pub name: String,
+
+    /// 設定項目の説明。
+    ///
+    /// AviUtl2には説明を表示する欄がないため、登録時にプラグインの情報に追記されます。
+    pub tooltip: Option<String>,
     /// 設定値。
     pub value: i32,
     /// 選択肢リスト。
@@ -622,6 +879,11 @@ pub use aviutl2_macros::FilterConfigSelectItems;
 pub struct FilterConfigFile {
     /// 設定名。
     pub name: String,
+
+    /// 設定項目の説明。
+    ///
+    /// AviUtl2には説明を表示する欄がないため、登録時にプラグインの情報に追記されます。
+    pub tooltip: Option<String>,
     /// 設定値。
     pub value: String,
     /// ファイルフィルタ。
@@ -1120,6 +1382,183 @@ impl<T: Copy> std::ops::DerefMut for FilterConfigDataWriteGuard<'_, T> {
 mod tests {
     use super::*;
 
+    #[derive(Debug, Clone, PartialEq)]
+    struct StrengthConfig {
+        strength: f64,
+    }
+
+    impl FilterConfigItems for StrengthConfig {
+        fn to_config_items() -> Vec<FilterConfigItem> {
+            vec![strength_item(0.5)]
+        }
+
+        fn from_config_items(items: &[FilterConfigItem]) -> Self {
+            match &items[0] {
+                FilterConfigItem::Track(track) => Self {
+                    strength: track.value,
+                },
+                _ => panic!("unexpected item"),
+            }
+        }
+    }
+
+    fn strength_item(value: f64) -> FilterConfigItem {
+        FilterConfigItem::Track(FilterConfigTrack {
+            name: "強さ".to_string(),
+            tooltip: None,
+            value,
+            range: 0.0..=1.0,
+            step: 0.01,
+            zero_display: None,
+            slider_ratio: 1.0,
+        })
+    }
+
+    #[test]
+    fn test_information_with_tooltips() {
+        let mut strength = strength_item(0.5);
+        assert_eq!(strength.tooltip(), None);
+        assert_eq!(
+            information_with_tooltips("Sample Filter", std::slice::from_ref(&strength)),
+            "Sample Filter"
+        );
+
+        if let FilterConfigItem::Track(track) = &mut strength {
+            track.tooltip = Some("効果の強さ".to_string());
+        }
+        let items = [
+            strength,
+            FilterConfigItem::Checkbox(FilterConfigCheckbox {
+                name: "反転".to_string(),
+                tooltip: Some("明暗を反転します".to_string()),
+                value: false,
+            }),
+        ];
+        assert_eq!(items[0].tooltip(), Some("効果の強さ"));
+        assert_eq!(
+            information_with_tooltips("Sample Filter", &items),
+            "Sample Filter\n\n■ 設定項目の説明\n・強さ：効果の強さ\n・反転：明暗を反転します"
+        );
+    }
+
+    fn object(id: i64, effect_id: i64) -> crate::filter::ObjectInfo {
+        crate::filter::ObjectInfo {
+            id,
+            effect_id,
+            layer: 0,
+            frame: 0,
+            frame_total: 1,
+            time: 0.0,
+            time_total: 0.0,
+            is_filter_object: false,
+            frame_s: 0,
+            frame_e: 0,
+        }
+    }
+
+    #[test]
+    fn config_change_tracker_keeps_objects_separate() {
+        let tracker = ConfigChangeTracker::<StrengthConfig>::new();
+        let (a, b, b2) = (object(1, 10), object(2, 20), object(2, 21));
+        let changed = |object, value| tracker.changed(object, &[strength_item(value)]).1;
+
+        // 初めて処理されたオブジェクトは変わったものとして扱う
+        assert!(changed(&a, 0.5));
+        assert!(changed(&b, 0.5));
+        assert!(!changed(&a, 0.5));
+        assert!(!changed(&b, 0.5));
+
+        // 他のオブジェクトの設定が変わっても影響しない
+        assert!(changed(&b, 0.7));
+        assert!(!changed(&a, 0.5));
+        assert!(!changed(&b, 0.7));
+        assert!(changed(&a, 0.7));
+        assert!(!changed(&b, 0.7));
+
+        // 同じオブジェクトの別のエフェクトも別に扱う
+        assert!(changed(&b2, 0.7));
+        assert!(changed(&b2, 0.1));
+        assert!(!changed(&b, 0.7));
+
+        let (config, changed_now) = tracker.changed(&a, &[strength_item(0.25)]);
+        assert_eq!(config, StrengthConfig { strength: 0.25 });
+        assert!(changed_now);
+
+        tracker.forget(&a);
+        assert!(changed(&a, 0.25));
+        assert!(!changed(&a, 0.25));
+    }
+
+    #[test]
+    fn color_value_converts_from_and_to_tuple() {
+        let color = FilterConfigColorValue::from((0x12, 0x34, 0x56));
+        assert_eq!(color, FilterConfigColorValue(0x123456));
+        assert_eq!(<(u8, u8, u8)>::from(color), (0x12, 0x34, 0x56));
+        assert_eq!(u32::from(color), 0x123456);
+    }
+
+    #[test]
+    fn color_value_to_rgba_keeps_components() {
+        let rgba = FilterConfigColorValue(0xFF8000).to_rgba(0x40);
+        assert_eq!(
+            rgba,
+            RgbaColorValue {
+                r: 0xFF,
+                g: 0x80,
+                b: 0x00,
+                a: 0x40,
+            }
+        );
+        assert_eq!(rgba.color(), FilterConfigColorValue(0xFF8000));
+        assert_eq!(rgba.to_string(), "#FF800040");
+        assert_eq!(<(u8, u8, u8, u8)>::from(rgba), (0xFF, 0x80, 0x00, 0x40));
+    }
+
+    #[test]
+    fn opacity_to_alpha_maps_percent_to_byte() {
+        assert_eq!(__opacity_to_alpha(0.0), 0);
+        assert_eq!(__opacity_to_alpha(50.0), 128);
+        assert_eq!(__opacity_to_alpha(100.0), 255);
+        assert_eq!(__opacity_to_alpha(150.0), 255);
+        assert_eq!(__opacity_to_alpha(-10.0), 0);
+    }
+
+    #[cfg(feature = "egui")]
+    #[test]
+    fn rgba_color_value_round_trips_through_egui() {
+        let rgba = RgbaColorValue {
+            r: 0x20,
+            g: 0x40,
+            b: 0x60,
+            a: 0xFF,
+        };
+        let color: egui::Color32 = rgba.into();
+        assert_eq!(color, egui::Color32::from_rgb(0x20, 0x40, 0x60));
+        assert_eq!(RgbaColorValue::from(color), rgba);
+        assert_eq!(
+            egui::Color32::from(FilterConfigColorValue(0x204060)),
+            egui::Color32::from_rgb(0x20, 0x40, 0x60)
+        );
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn rgba_color_value_round_trips_through_image() {
+        let rgba = RgbaColorValue {
+            r: 1,
+            g: 2,
+            b: 3,
+            a: 4,
+        };
+        let pixel: image::Rgba<u8> = rgba.into();
+        assert_eq!(pixel, image::Rgba([1, 2, 3, 4]));
+        assert_eq!(RgbaColorValue::from(pixel), rgba);
+        assert_eq!(
+            image::Rgb::<u8>::from(FilterConfigColorValue(0x010203)),
+            image::Rgb([1, 2, 3])
+        );
+    }
+
     #[test]
     fn filter_config_data_handle_reads_initial_value() {
         let handle = FilterConfigDataHandle::<u32>::__new_owned(42);
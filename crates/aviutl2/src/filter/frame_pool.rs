@@ -0,0 +1,422 @@
+//! フィルタ処理中に使う中間バッファのプール。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+use crate::filter::RgbaPixel;
+
+/// 使われなくなった中間バッファを解放するまでの`proc_video`の呼び出し回数。
+const TRIM_AFTER_CALLS: u64 = 16;
+
+/// バッファの先頭と長さの単位。キャッシュラインの大きさに合わせる。
+const BUFFER_ALIGN: usize = 64;
+
+/// [`FramePool`]が扱うピクセルの形式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameFormat {
+    /// [`RgbaPixel`]。
+    Rgba8,
+    /// `[f32; 4]`。[`crate::filter::ops`]で使う、乗算済みアルファのRGBA。
+    RgbaF32,
+    /// `f32`。マスクなどの1チャンネルの画像。
+    F32,
+}
+
+impl FrameFormat {
+    /// 1ピクセルあたりのバイト数。
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            FrameFormat::Rgba8 => 4,
+            FrameFormat::RgbaF32 => 16,
+            FrameFormat::F32 => 4,
+        }
+    }
+}
+
+/// [`FramePool`]から借りられるピクセルの型。
+pub trait FramePixel:
+    Copy + FromBytes + IntoBytes + Immutable + KnownLayout + sealed::Sealed
+{
+    /// ピクセルの形式。
+    const FORMAT: FrameFormat;
+}
+
+impl FramePixel for RgbaPixel {
+    const FORMAT: FrameFormat = FrameFormat::Rgba8;
+}
+impl FramePixel for [f32; 4] {
+    const FORMAT: FrameFormat = FrameFormat::RgbaF32;
+}
+impl FramePixel for f32 {
+    const FORMAT: FrameFormat = FrameFormat::F32;
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::RgbaPixel {}
+    impl Sealed for [f32; 4] {}
+    impl Sealed for f32 {}
+}
+
+#[derive(Clone, Copy, FromBytes, IntoBytes, Immutable, KnownLayout)]
+#[repr(C, align(64))]
+struct CacheLine([u8; BUFFER_ALIGN]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FrameKey {
+    width: u32,
+    height: u32,
+    format: FrameFormat,
+}
+
+impl FrameKey {
+    fn bytes(&self) -> usize {
+        self.width as usize * self.height as usize * self.format.bytes_per_pixel()
+    }
+}
+
+struct FreeBuffer {
+    storage: Box<[CacheLine]>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct PoolState {
+    free: HashMap<FrameKey, Vec<FreeBuffer>>,
+    calls: u64,
+    stats: FramePoolStats,
+}
+
+impl PoolState {
+    fn release(&mut self, key: FrameKey, storage: Box<[CacheLine]>) {
+        self.stats.buffers_in_use -= 1;
+        let last_used = self.calls;
+        self.free
+            .entry(key)
+            .or_default()
+            .push(FreeBuffer { storage, last_used });
+    }
+
+    fn free_buffer(&mut self, storage: &[CacheLine]) {
+        self.stats.buffers -= 1;
+        self.stats.bytes -= std::mem::size_of_val(storage);
+    }
+}
+
+/// [`FramePool`]の統計情報。
+///
+/// バイト数は実際に確保した大きさ（64バイト単位に切り上げたもの）です。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FramePoolStats {
+    /// 確保しているバッファの数。貸し出し中のものも含む。
+    pub buffers: usize,
+    /// 貸し出し中のバッファの数。
+    pub buffers_in_use: usize,
+    /// 確保しているバッファの合計のバイト数。
+    pub bytes: usize,
+    /// [`Self::buffers`]の最大値。
+    pub peak_buffers: usize,
+    /// [`Self::bytes`]の最大値。
+    pub peak_bytes: usize,
+    /// 新しくバッファを確保した回数。
+    pub allocations: u64,
+    /// 確保済みのバッファを使い回した回数。
+    pub reuses: u64,
+}
+
+/// フィルタ処理の中間バッファを使い回すためのプール。
+///
+/// 縮小→ぼかし→合成のように何段階かに分けて処理するフィルタで、
+/// 中間の画像を毎フレーム確保し直さずに済むようにします。
+/// [`crate::filter::FilterProcVideo::frame_pool`]から取得できます。
+///
+/// バッファは幅・高さ・形式ごとに管理され、[`PooledFrame`]がドロップされるとプールに戻ります。
+/// [`PooledFrame`]はプールを借用するので、`proc_video`の終了時には必ずプールに戻ります。
+/// しばらく使われなかった大きさのバッファは自動的に解放されます。
+///
+/// `clone`しても同じプールを指します。
+///
+/// # Example
+///
+/// ```rust
+/// # use aviutl2::filter::{FilterProcVideo, RgbaPixel, ops};
+/// # fn f(video: &mut FilterProcVideo) {
+/// let (width, height) = video.image_size();
+/// let pool = video.frame_pool();
+/// let mut image = pool.acquire::<RgbaPixel>(width, height);
+/// video.get_image_data(&mut image);
+///
+/// let mut work = pool.acquire::<[f32; 4]>(width, height);
+/// let mut scratch = pool.acquire::<[f32; 4]>(width, height);
+/// ops::rgba8_to_premultiplied(&image, &mut work);
+/// ops::box_blur(&mut work, &mut scratch, width, height, 8);
+/// ops::premultiplied_to_rgba8(&work, &mut image);
+///
+/// video.set_image_data(&image, width, height);
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct FramePool {
+    state: Arc<Mutex<PoolState>>,
+}
+
+impl std::fmt::Debug for FramePool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FramePool")
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl FramePool {
+    /// 空のプールを作成する。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `width * height`ピクセルのバッファを借りる。
+    ///
+    /// 使い回したバッファの場合、中身は前回使った時のままです。
+    /// 全て上書きしない場合は[`Self::acquire_zeroed`]を使ってください。
+    pub fn acquire<T: FramePixel>(&self, width: u32, height: u32) -> PooledFrame<'_, T> {
+        let key = FrameKey {
+            width,
+            height,
+            format: T::FORMAT,
+        };
+        let mut state = self.state.lock().unwrap();
+        let storage = match state.free.get_mut(&key).and_then(Vec::pop) {
+            Some(buffer) => {
+                state.stats.reuses += 1;
+                buffer.storage
+            }
+            None => {
+                let storage =
+                    vec![CacheLine([0; BUFFER_ALIGN]); key.bytes().div_ceil(BUFFER_ALIGN)]
+                        .into_boxed_slice();
+                let stats = &mut state.stats;
+                stats.allocations += 1;
+                stats.buffers += 1;
+                stats.bytes += std::mem::size_of_val(&*storage);
+                stats.peak_buffers = stats.peak_buffers.max(stats.buffers);
+                stats.peak_bytes = stats.peak_bytes.max(stats.bytes);
+                storage
+            }
+        };
+        state.stats.buffers_in_use += 1;
+        PooledFrame {
+            pool: self,
+            key,
+            storage: Some(storage),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// 中身を0で埋めたバッファを借りる。
+    pub fn acquire_zeroed<T: FramePixel>(&self, width: u32, height: u32) -> PooledFrame<'_, T> {
+        let mut frame = self.acquire(width, height);
+        frame.as_mut_bytes().fill(0);
+        frame
+    }
+
+    /// 統計情報を取得する。
+    pub fn stats(&self) -> FramePoolStats {
+        self.state.lock().unwrap().stats
+    }
+
+    /// 最大値の統計情報を現在の値に戻す。
+    pub fn reset_peak(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.stats.peak_buffers = state.stats.buffers;
+        state.stats.peak_bytes = state.stats.bytes;
+    }
+
+    /// 貸し出し中でないバッファを全て解放する。
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        let free = std::mem::take(&mut state.free);
+        for buffer in free.into_values().flatten() {
+            state.free_buffer(&buffer.storage);
+        }
+    }
+
+    /// `proc_video`の呼び出しの終わりに呼ばれ、しばらく使われていないバッファを解放する。
+    pub(crate) fn end_call(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.calls += 1;
+        let Some(threshold) = state.calls.checked_sub(TRIM_AFTER_CALLS) else {
+            return;
+        };
+        let mut expired = Vec::new();
+        state.free.retain(|_, buffers| {
+            buffers.retain_mut(|buffer| {
+                if buffer.last_used < threshold {
+                    expired.push(std::mem::take(&mut buffer.storage));
+                    false
+                } else {
+                    true
+                }
+            });
+            !buffers.is_empty()
+        });
+        for storage in expired {
+            state.free_buffer(&storage);
+        }
+    }
+}
+
+/// [`FramePool`]から借りたバッファ。
+///
+/// `[T]`として読み書きでき、ドロップされるとプールに戻ります。
+/// 先頭は64バイト境界に揃っています。
+pub struct PooledFrame<'a, T: FramePixel> {
+    pool: &'a FramePool,
+    key: FrameKey,
+    storage: Option<Box<[CacheLine]>>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T: FramePixel> PooledFrame<'_, T> {
+    /// 幅。
+    pub fn width(&self) -> u32 {
+        self.key.width
+    }
+
+    /// 高さ。
+    pub fn height(&self) -> u32 {
+        self.key.height
+    }
+
+    /// バイト列として取得する。
+    pub fn as_bytes(&self) -> &[u8] {
+        let storage = self.storage.as_deref().unwrap();
+        &storage.as_bytes()[..self.key.bytes()]
+    }
+
+    /// バイト列として取得する。
+    pub fn as_mut_bytes(&mut self) -> &mut [u8] {
+        let bytes = self.key.bytes();
+        let storage = self.storage.as_deref_mut().unwrap();
+        &mut storage.as_mut_bytes()[..bytes]
+    }
+}
+
+impl<T: FramePixel> std::ops::Deref for PooledFrame<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        <[T]>::ref_from_bytes(self.as_bytes()).expect("pool buffers are always aligned")
+    }
+}
+
+impl<T: FramePixel> std::ops::DerefMut for PooledFrame<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        <[T]>::mut_from_bytes(self.as_mut_bytes()).expect("pool buffers are always aligned")
+    }
+}
+
+impl<T: FramePixel> Drop for PooledFrame<'_, T> {
+    fn drop(&mut self) {
+        if let Some(storage) = self.storage.take()
+            && let Ok(mut state) = self.pool.state.lock()
+        {
+            state.release(self.key, storage);
+        }
+    }
+}
+
+impl<T: FramePixel + std::fmt::Debug> std::fmt::Debug for PooledFrame<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PooledFrame")
+            .field("width", &self.key.width)
+            .field("height", &self.key.height)
+            .field("format", &self.key.format)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuses_buffers_by_key() {
+        let pool = FramePool::new();
+        {
+            let mut frame = pool.acquire::<[f32; 4]>(3, 2);
+            assert_eq!(frame.len(), 6);
+            frame.fill([1.0; 4]);
+            let other = pool.acquire::<RgbaPixel>(3, 2);
+            assert_eq!(other.len(), 6);
+            assert_eq!(pool.stats().buffers_in_use, 2);
+        }
+        let stats = pool.stats();
+        assert_eq!(stats.buffers_in_use, 0);
+        assert_eq!(stats.buffers, 2);
+        assert_eq!(stats.allocations, 2);
+
+        // 同じ大きさ・形式なら使い回す
+        let frame = pool.acquire::<[f32; 4]>(3, 2);
+        assert_eq!(frame[0], [1.0; 4]);
+        drop(frame);
+        let frame = pool.acquire_zeroed::<[f32; 4]>(3, 2);
+        assert!(frame.iter().all(|pixel| *pixel == [0.0; 4]));
+        drop(frame);
+        // 形式が違えば別のバッファになる
+        drop(pool.acquire::<f32>(3, 2));
+
+        let stats = pool.stats();
+        assert_eq!(stats.allocations, 3);
+        assert_eq!(stats.reuses, 2);
+        assert_eq!(stats.buffers, 3);
+    }
+
+    #[test]
+    fn test_alignment_and_stats() {
+        let pool = FramePool::new();
+        let a = pool.acquire::<RgbaPixel>(5, 3);
+        let b = pool.acquire::<f32>(1, 1);
+        assert_eq!(a.as_ptr() as usize % BUFFER_ALIGN, 0);
+        assert_eq!(b.as_ptr() as usize % BUFFER_ALIGN, 0);
+        assert_eq!(a.as_bytes().len(), 60);
+        let stats = pool.stats();
+        assert_eq!(stats.bytes, 128);
+        assert_eq!(stats.peak_buffers, 2);
+        drop((a, b));
+
+        pool.clear();
+        let stats = pool.stats();
+        assert_eq!(stats.buffers, 0);
+        assert_eq!(stats.bytes, 0);
+        assert_eq!(stats.peak_bytes, 128);
+        pool.reset_peak();
+        assert_eq!(pool.stats().peak_bytes, 0);
+    }
+
+    #[test]
+    fn test_trims_unused_sizes() {
+        let pool = FramePool::new();
+        drop(pool.acquire::<[f32; 4]>(64, 64));
+        for _ in 0..TRIM_AFTER_CALLS {
+            drop(pool.acquire::<[f32; 4]>(32, 32));
+            pool.end_call();
+        }
+        assert_eq!(pool.stats().buffers, 2);
+
+        drop(pool.acquire::<[f32; 4]>(32, 32));
+        pool.end_call();
+        let stats = pool.stats();
+        assert_eq!(stats.buffers, 1);
+        assert_eq!(stats.bytes, 32 * 32 * 16);
+    }
+
+    #[test]
+    fn test_empty_frame() {
+        let pool = FramePool::new();
+        let mut frame = pool.acquire_zeroed::<RgbaPixel>(0, 10);
+        assert!(frame.is_empty());
+        assert!(frame.as_mut_bytes().is_empty());
+    }
+}
@@ -0,0 +1,360 @@
+//! 音声フィルタと画像フィルタの間でメディアのデータを受け渡すためのキャッシュ。
+//!
+//! AviUtl2の画像フィルタには、オブジェクトの音声を取得する方法がありません。
+//! 画像フィルタと音声フィルタの両方に対応したフィルタ（[`FilterPluginFlags`][crate::filter::FilterPluginFlags]の`video`と`audio`）を登録し、
+//! 音声フィルタで受け取った音声を[`AudioTap`]に記録することで、同じオブジェクトの画像フィルタから参照できます。
+//!
+//! 音声と画像は別々のスレッドから、別々のタイミングで処理されます。
+//! 再生中は音声が先に処理されるため、通常は画像フィルタの時点ではそのフレームの音声が記録されていますが、
+//! シーク直後などは記録されていないことがあります。
+//!
+//! # Example
+//!
+//! ```rust
+//! # struct MyFilter {
+//! #     tap: aviutl2::filter::media_cache::AudioTap,
+//! # }
+//! # impl MyFilter {
+//! fn proc_audio(&self, audio: &mut aviutl2::filter::FilterProcAudio) -> aviutl2::AnyResult<()> {
+//!     // 音声はそのままにして、記録だけする
+//!     self.tap.record(audio)?;
+//!     Ok(())
+//! }
+//!
+//! fn proc_video(&self, video: &mut aviutl2::filter::FilterProcVideo) -> aviutl2::AnyResult<()> {
+//!     // フレームの時点を中心にした0.1秒分の音声
+//!     if let Some(window) = self.tap.window(video, std::time::Duration::from_millis(100)) {
+//!         let _peak = window.samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+//!     }
+//!     Ok(())
+//! }
+//! # }
+//! ```
+
+use std::{collections::VecDeque, time::Duration};
+
+use super::{FilterProcAudio, FilterProcResult, FilterProcVideo, ObjectInfo, ObjectStateMap};
+use crate::common::Rational32;
+
+/// [`AudioTap`]が保持する音声の長さの既定値。
+pub const DEFAULT_AUDIO_TAP_DURATION: Duration = Duration::from_secs(10);
+
+/// 音声フィルタで受け取った音声を、オブジェクトごとに記録するキャッシュ。
+///
+/// 音声は全チャンネルの平均（モノラル）として、オブジェクトの開始からのサンプル位置と一緒に記録されます。
+/// キーは[`ObjectStateMap`]と同じく`(オブジェクトのID, エフェクトのID)`で、
+/// オブジェクトが削除されたとき（[`crate::filter::FilterPlugin::on_object_destroyed`]）に自動で削除されます。
+///
+/// 詳しくは[モジュールのドキュメント][self]を参照してください。
+#[derive(Debug)]
+pub struct AudioTap {
+    buffers: ObjectStateMap<TapBuffer>,
+    max_duration: Duration,
+}
+
+/// [`AudioTap`]から取り出した音声。
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioWindow {
+    /// `samples`の最初のサンプルの、オブジェクトの開始からのサンプル位置。
+    /// オブジェクトの開始より前の場合は負の値になります。
+    pub start: i64,
+    /// 音声のサンプル。記録されていない部分は`0.0`になります。
+    pub samples: Vec<f32>,
+    /// `samples`のうち、記録されていたサンプルの数。
+    pub recorded: usize,
+    /// サンプリングレート。
+    pub sample_rate: u32,
+}
+
+#[derive(Debug)]
+struct TapBuffer {
+    sample_rate: u32,
+    start: u64,
+    samples: VecDeque<f32>,
+}
+
+impl AudioTap {
+    /// 新しいキャッシュを作成する。
+    ///
+    /// オブジェクトごとに、最後に記録した位置から`max_duration`前までの音声を保持します。
+    pub fn new(max_duration: Duration) -> Self {
+        Self {
+            buffers: ObjectStateMap::new(),
+            max_duration,
+        }
+    }
+
+    /// 音声フィルタで受け取った音声を記録する。
+    ///
+    /// 音声のデータは変更しません。
+    pub fn record(&self, audio: &mut FilterProcAudio) -> FilterProcResult<()> {
+        let sample_num = audio.audio_object.sample_num as usize;
+        let channel_count = audio.channel_count();
+        let mut mixed = vec![0.0; sample_num];
+        let mut buffer = vec![0.0; sample_num];
+        for channel in 0..channel_count {
+            audio.get_sample_data_ch(channel, &mut buffer)?;
+            for (mixed, sample) in mixed.iter_mut().zip(&buffer) {
+                *mixed += sample;
+            }
+        }
+        if channel_count > 1 {
+            for sample in &mut mixed {
+                *sample /= channel_count as f32;
+            }
+        }
+        self.record_samples(
+            &audio.object,
+            audio.scene.sample_rate,
+            audio.audio_object.sample_index,
+            &mixed,
+        );
+        Ok(())
+    }
+
+    /// モノラルの音声を、オブジェクトの開始からのサンプル位置`sample_index`に記録する。
+    ///
+    /// すでに記録されている範囲と重なる場合は上書きします。
+    /// 記録されている範囲から離れた位置（シーク後など）の場合は、それまでの記録を破棄します。
+    pub fn record_samples(
+        &self,
+        object: &ObjectInfo,
+        sample_rate: u32,
+        sample_index: u64,
+        samples: &[f32],
+    ) {
+        let max_len =
+            (self.max_duration.as_nanos() * sample_rate as u128).div_ceil(1_000_000_000) as usize;
+        let mut buffer = self.buffers.get_or_insert_with(object, || TapBuffer {
+            sample_rate,
+            start: sample_index,
+            samples: VecDeque::new(),
+        });
+        buffer.write(sample_rate, sample_index, samples, max_len);
+    }
+
+    /// 画像フィルタで処理中のフレームの時点を中心にした、`duration`の長さの音声を取得する。
+    ///
+    /// このオブジェクトの音声が一度も記録されていない場合は`None`を返します。
+    pub fn window(&self, video: &FilterProcVideo, duration: Duration) -> Option<AudioWindow> {
+        let sample_rate = video.scene.sample_rate;
+        let center = frame_to_sample_index(video.object.frame, video.scene.frame_rate, sample_rate);
+        let len = (duration.as_secs_f64() * sample_rate as f64).round() as usize;
+        self.window_at(&video.object, center, len)
+    }
+
+    /// オブジェクトの開始からのサンプル位置`center`を中心にした、`len`サンプルの音声を取得する。
+    ///
+    /// このオブジェクトの音声が一度も記録されていない場合は`None`を返します。
+    pub fn window_at(&self, object: &ObjectInfo, center: u64, len: usize) -> Option<AudioWindow> {
+        let buffer = self.buffers.get(object)?;
+        let start = center as i64 - (len / 2) as i64;
+        let mut samples = vec![0.0; len];
+        let mut recorded = 0;
+        for (i, sample) in samples.iter_mut().enumerate() {
+            let Some(index) = u64::try_from(start + i as i64)
+                .ok()
+                .and_then(|index| index.checked_sub(buffer.start))
+            else {
+                continue;
+            };
+            if let Some(&value) = buffer.samples.get(index as usize) {
+                *sample = value;
+                recorded += 1;
+            }
+        }
+        Some(AudioWindow {
+            start,
+            samples,
+            recorded,
+            sample_rate: buffer.sample_rate,
+        })
+    }
+
+    /// オブジェクトの記録を削除する。
+    pub fn remove(&self, object: &ObjectInfo) {
+        self.buffers.remove(object);
+    }
+
+    /// すべての記録を削除する。
+    pub fn clear(&self) {
+        self.buffers.clear();
+    }
+}
+
+impl Default for AudioTap {
+    fn default() -> Self {
+        Self::new(DEFAULT_AUDIO_TAP_DURATION)
+    }
+}
+
+impl TapBuffer {
+    fn write(&mut self, sample_rate: u32, sample_index: u64, samples: &[f32], max_len: usize) {
+        let end = self.start + self.samples.len() as u64;
+        if sample_rate != self.sample_rate || sample_index < self.start || sample_index > end {
+            // 記録と繋がらないので、作り直す
+            self.sample_rate = sample_rate;
+            self.start = sample_index;
+            self.samples.clear();
+        }
+        let offset = (sample_index - self.start) as usize;
+        let overlap = samples.len().min(self.samples.len() - offset);
+        for (destination, sample) in self
+            .samples
+            .range_mut(offset..offset + overlap)
+            .zip(samples)
+        {
+            *destination = *sample;
+        }
+        self.samples.extend(&samples[overlap..]);
+
+        if self.samples.len() > max_len {
+            let excess = self.samples.len() - max_len;
+            self.samples.drain(..excess);
+            self.start += excess as u64;
+        }
+    }
+}
+
+/// オブジェクトの開始からのフレーム番号を、同じ時点の音声のサンプル位置に変換する。
+///
+/// フレームの開始時刻（`frame / frame_rate`秒）のサンプル位置を、切り捨てで返します。
+pub fn frame_to_sample_index(frame: u32, frame_rate: Rational32, sample_rate: u32) -> u64 {
+    let numer = *frame_rate.numer();
+    let denom = *frame_rate.denom();
+    if numer <= 0 || denom <= 0 {
+        return 0;
+    }
+    (frame as u128 * sample_rate as u128 * denom as u128 / numer as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(id: i64) -> ObjectInfo {
+        // 状態の削除は全てのマップに対して行われるので、他のテストと重ならないIDにする
+        let id = 1_632_000 + id;
+        ObjectInfo {
+            id,
+            effect_id: id,
+            layer: 0,
+            frame: 0,
+            frame_total: 1,
+            time: 0.0,
+            time_total: 0.0,
+            is_filter_object: true,
+            frame_s: 0,
+            frame_e: 0,
+        }
+    }
+
+    #[test]
+    fn frame_to_sample_index_matches_frame_start_time() {
+        let fps30 = Rational32::new(30, 1);
+        assert_eq!(frame_to_sample_index(0, fps30, 48000), 0);
+        assert_eq!(frame_to_sample_index(1, fps30, 48000), 1600);
+        assert_eq!(frame_to_sample_index(90, fps30, 48000), 144000);
+
+        // 29.97fpsでは1フレームが1601.6サンプルなので、切り捨てる
+        let ntsc = Rational32::new(30000, 1001);
+        assert_eq!(frame_to_sample_index(1, ntsc, 48000), 1601);
+        assert_eq!(frame_to_sample_index(5, ntsc, 48000), 8008);
+        // 約1時間のオブジェクトでも、浮動小数点の誤差が出ない
+        assert_eq!(frame_to_sample_index(107892, ntsc, 48000), 172799827);
+
+        assert_eq!(
+            frame_to_sample_index(10, Rational32::new(24, 1), 44100),
+            18375
+        );
+        assert_eq!(frame_to_sample_index(10, Rational32::new(0, 1), 44100), 0);
+    }
+
+    #[test]
+    fn window_is_centered_on_frame_time() {
+        let tap = AudioTap::default();
+        let info = object(1);
+        // 音声フィルタは1600サンプルずつ処理される
+        for chunk in 0..4u64 {
+            let samples: Vec<f32> = (0..1600).map(|i| (chunk * 1600 + i) as f32).collect();
+            tap.record_samples(&info, 48000, chunk * 1600, &samples);
+        }
+
+        let center = frame_to_sample_index(2, Rational32::new(30, 1), 48000);
+        let window = tap.window_at(&info, center, 8).unwrap();
+        assert_eq!(window.start, 3196);
+        assert_eq!(
+            window.samples,
+            vec![
+                3196.0, 3197.0, 3198.0, 3199.0, 3200.0, 3201.0, 3202.0, 3203.0
+            ]
+        );
+        assert_eq!(window.recorded, 8);
+        assert_eq!(window.sample_rate, 48000);
+    }
+
+    #[test]
+    fn window_fills_unrecorded_samples_with_silence() {
+        let tap = AudioTap::default();
+        let info = object(1);
+        tap.record_samples(&info, 48000, 0, &[1.0, 2.0, 3.0]);
+
+        let window = tap.window_at(&info, 0, 4).unwrap();
+        assert_eq!(window.start, -2);
+        assert_eq!(window.samples, vec![0.0, 0.0, 1.0, 2.0]);
+        assert_eq!(window.recorded, 2);
+
+        let window = tap.window_at(&info, 3, 4).unwrap();
+        assert_eq!(window.samples, vec![2.0, 3.0, 0.0, 0.0]);
+
+        assert!(tap.window_at(&object(2), 0, 4).is_none());
+    }
+
+    #[test]
+    fn record_overwrites_overlap_and_resets_after_seek() {
+        let tap = AudioTap::default();
+        let info = object(1);
+        tap.record_samples(&info, 48000, 100, &[1.0, 1.0, 1.0, 1.0]);
+        // 同じ位置がもう一度処理された場合は、後の記録を残したまま上書きする
+        tap.record_samples(&info, 48000, 101, &[2.0, 2.0]);
+        let window = tap.window_at(&info, 102, 4).unwrap();
+        assert_eq!(window.samples, vec![1.0, 2.0, 2.0, 1.0]);
+
+        // 離れた位置に飛んだ場合は作り直す
+        tap.record_samples(&info, 48000, 1000, &[3.0]);
+        let window = tap.window_at(&info, 102, 4).unwrap();
+        assert_eq!(window.recorded, 0);
+        assert_eq!(
+            tap.window_at(&info, 1001, 2).unwrap().samples,
+            vec![3.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn record_keeps_only_max_duration() {
+        let tap = AudioTap::new(Duration::from_millis(1));
+        let info = object(1);
+        let samples: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        tap.record_samples(&info, 48000, 0, &samples);
+
+        // 48kHzで1msは48サンプルなので、古い52サンプルは破棄される
+        let window = tap.window_at(&info, 50, 100).unwrap();
+        assert_eq!(window.recorded, 48);
+        assert_eq!(window.samples[52], 52.0);
+        assert_eq!(window.samples[51], 0.0);
+    }
+
+    #[test]
+    fn objects_are_evicted_with_object_states() {
+        let tap = AudioTap::default();
+        tap.record_samples(&object(1), 48000, 0, &[1.0]);
+        tap.record_samples(&object(2), 48000, 0, &[2.0]);
+
+        crate::filter::evict_object_states(&crate::filter::DestroyedObject {
+            id: object(1).id,
+            effect_ids: std::collections::HashSet::from([object(1).effect_id]),
+        });
+        assert!(tap.window_at(&object(1), 0, 1).is_none());
+        assert_eq!(tap.window_at(&object(2), 0, 1).unwrap().samples, vec![2.0]);
+    }
+}
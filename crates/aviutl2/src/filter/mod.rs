@@ -10,10 +10,18 @@
 
 mod binding;
 mod config;
+mod frame_pool;
+pub mod media_cache;
+pub mod ops;
+mod rng;
 
 pub use super::common::*;
+#[cfg(feature = "module")]
+pub use crate::interop::shared_store;
 pub use binding::*;
 pub use config::*;
+pub use frame_pool::*;
+pub use rng::*;
 
 #[doc(hidden)]
 #[path = "bridge.rs"]
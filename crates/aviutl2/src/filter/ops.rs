@@ -0,0 +1,456 @@
+//! 画像フィルタでよく使う処理。
+//!
+//! 画像は乗算済みアルファの`[f32; 4]`（RGBA、0.0〜1.0）で扱います。
+//! [`rgba8_to_premultiplied`]・[`premultiplied_to_rgba8`]で[`RgbaPixel`]と相互に変換できます。
+//! 中間のバッファは[`crate::filter::FramePool`]から借りると、毎フレーム確保し直さずに済みます。
+//!
+//! どの処理も1ピクセルの4チャンネルをまとめて計算するように書いてあり、
+//! コンパイラの自動ベクトル化でSIMD命令になります。
+
+use crate::filter::RgbaPixel;
+
+type Pixel = [f32; 4];
+
+#[inline(always)]
+fn add(a: Pixel, b: Pixel) -> Pixel {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]]
+}
+
+#[inline(always)]
+fn sub(a: Pixel, b: Pixel) -> Pixel {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]]
+}
+
+#[inline(always)]
+fn scale(a: Pixel, s: f32) -> Pixel {
+    [a[0] * s, a[1] * s, a[2] * s, a[3] * s]
+}
+
+#[inline(always)]
+fn lerp(a: Pixel, b: Pixel, t: f32) -> Pixel {
+    add(a, scale(sub(b, a), t))
+}
+
+fn assert_size(len: usize, width: u32, height: u32, name: &str) {
+    assert_eq!(
+        len,
+        width as usize * height as usize,
+        "{name} length does not match width * height"
+    );
+}
+
+/// [`RgbaPixel`]を乗算済みアルファの`[f32; 4]`に変換する。
+///
+/// # Panics
+///
+/// `src`と`dst`の長さが違う場合はパニックします。
+pub fn rgba8_to_premultiplied(src: &[RgbaPixel], dst: &mut [[f32; 4]]) {
+    assert_eq!(
+        src.len(),
+        dst.len(),
+        "src and dst must have the same length"
+    );
+    for (src, dst) in src.iter().zip(dst.iter_mut()) {
+        let alpha = src.a as f32 / 255.0;
+        let factor = alpha / 255.0;
+        *dst = [
+            src.r as f32 * factor,
+            src.g as f32 * factor,
+            src.b as f32 * factor,
+            alpha,
+        ];
+    }
+}
+
+/// 乗算済みアルファの`[f32; 4]`を[`RgbaPixel`]に変換する。
+///
+/// 範囲外の値は0.0〜1.0に丸められます。
+///
+/// # Panics
+///
+/// `src`と`dst`の長さが違う場合はパニックします。
+pub fn premultiplied_to_rgba8(src: &[[f32; 4]], dst: &mut [RgbaPixel]) {
+    assert_eq!(
+        src.len(),
+        dst.len(),
+        "src and dst must have the same length"
+    );
+    let to_u8 = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    for (src, dst) in src.iter().zip(dst.iter_mut()) {
+        let alpha = src[3].clamp(0.0, 1.0);
+        *dst = if alpha > 0.0 {
+            RgbaPixel {
+                r: to_u8(src[0] / alpha),
+                g: to_u8(src[1] / alpha),
+                b: to_u8(src[2] / alpha),
+                a: to_u8(alpha),
+            }
+        } else {
+            RgbaPixel::default()
+        };
+    }
+}
+
+/// ボックスブラーをかける。
+///
+/// 横方向と縦方向に分けて、`2 * radius + 1`ピクセルの平均を取ります。
+/// 移動和を使うので、`radius`によらず1ピクセルあたりの計算量は一定です。
+/// 画像の外は透明として扱うため、端は外側に向かって薄くなります。
+///
+/// 3回程度繰り返すとガウスぼかしに近くなります。
+///
+/// `scratch`は作業用のバッファで、`image`と同じ長さが必要です。中身は上書きされます。
+///
+/// # Panics
+///
+/// `image`か`scratch`の長さが`width * height`と一致しない場合はパニックします。
+pub fn box_blur(
+    image: &mut [[f32; 4]],
+    scratch: &mut [[f32; 4]],
+    width: u32,
+    height: u32,
+    radius: u32,
+) {
+    assert_size(image.len(), width, height, "image");
+    assert_size(scratch.len(), width, height, "scratch");
+    if radius == 0 || image.is_empty() {
+        return;
+    }
+    let (width, height, radius) = (width as usize, height as usize, radius as usize);
+    let inv = 1.0 / (2 * radius + 1) as f32;
+
+    // 横方向：1行ずつ移動和を取る
+    for (src, dst) in image
+        .chunks_exact(width)
+        .zip(scratch.chunks_exact_mut(width))
+    {
+        let mut sum = src[..(radius + 1).min(width)]
+            .iter()
+            .fold([0.0; 4], |sum, &pixel| add(sum, pixel));
+        for x in 0..width {
+            dst[x] = scale(sum, inv);
+            if x + radius + 1 < width {
+                sum = add(sum, src[x + radius + 1]);
+            }
+            if x >= radius {
+                sum = sub(sum, src[x - radius]);
+            }
+        }
+    }
+
+    // 縦方向：行単位で移動和を取る。内側のループが連続したメモリを読むのでベクトル化しやすい
+    let mut sums = vec![[0.0f32; 4]; width];
+    for row in scratch.chunks_exact(width).take(radius + 1) {
+        for (sum, &pixel) in sums.iter_mut().zip(row) {
+            *sum = add(*sum, pixel);
+        }
+    }
+    for y in 0..height {
+        let dst = &mut image[y * width..(y + 1) * width];
+        for (dst, &sum) in dst.iter_mut().zip(&sums) {
+            *dst = scale(sum, inv);
+        }
+        if y + radius + 1 < height {
+            let row = &scratch[(y + radius + 1) * width..(y + radius + 2) * width];
+            for (sum, &pixel) in sums.iter_mut().zip(row) {
+                *sum = add(*sum, pixel);
+            }
+        }
+        if y >= radius {
+            let row = &scratch[(y - radius) * width..(y - radius + 1) * width];
+            for (sum, &pixel) in sums.iter_mut().zip(row) {
+                *sum = sub(*sum, pixel);
+            }
+        }
+    }
+}
+
+/// 画像をバイリニア補間で拡大・縮小する。
+///
+/// ピクセルの中心を合わせて補間し、画像の外は端のピクセルで埋めます。
+/// 半分より小さく縮小すると読み飛ばすピクセルが出るため、
+/// ぼかしの前処理などでは何回かに分けて縮小するか、縮小後にぼかしてください。
+///
+/// # Panics
+///
+/// `src`の長さが`src_width * src_height`、`dst`の長さが`dst_width * dst_height`と一致しない場合はパニックします。
+pub fn resize_bilinear(
+    src: &[[f32; 4]],
+    src_width: u32,
+    src_height: u32,
+    dst: &mut [[f32; 4]],
+    dst_width: u32,
+    dst_height: u32,
+) {
+    assert_size(src.len(), src_width, src_height, "src");
+    assert_size(dst.len(), dst_width, dst_height, "dst");
+    if dst.is_empty() {
+        return;
+    }
+    if src.is_empty() {
+        dst.fill([0.0; 4]);
+        return;
+    }
+    let src_width = src_width as usize;
+
+    // 列ごとの参照位置と重みは全ての行で同じなので、先に計算しておく
+    let columns = (0..dst_width)
+        .map(|x| sample_position(x, dst_width, src_width as u32))
+        .collect::<Vec<_>>();
+    for (y, dst) in dst.chunks_exact_mut(dst_width as usize).enumerate() {
+        let (y0, y1, ty) = sample_position(y as u32, dst_height, src_height);
+        let row0 = &src[y0 * src_width..(y0 + 1) * src_width];
+        let row1 = &src[y1 * src_width..(y1 + 1) * src_width];
+        for (dst, &(x0, x1, tx)) in dst.iter_mut().zip(&columns) {
+            let top = lerp(row0[x0], row0[x1], tx);
+            let bottom = lerp(row1[x0], row1[x1], tx);
+            *dst = lerp(top, bottom, ty);
+        }
+    }
+}
+
+/// 出力の`index`番目のピクセルの中心に対応する、入力の2つのピクセルと重みを返す。
+fn sample_position(index: u32, dst_len: u32, src_len: u32) -> (usize, usize, f32) {
+    let position = ((index as f64 + 0.5) * src_len as f64 / dst_len as f64 - 0.5)
+        .clamp(0.0, (src_len - 1) as f64);
+    let lower = position.floor() as usize;
+    let upper = (lower + 1).min(src_len as usize - 1);
+    (lower, upper, (position - lower as f64) as f32)
+}
+
+/// 乗算済みアルファの画像を重ねる（Porter-Duffのover）。
+///
+/// `src`を`dst`の上に重ねた結果を`dst`に書き込みます。
+///
+/// # Panics
+///
+/// `src`と`dst`の長さが違う場合はパニックします。
+pub fn over(dst: &mut [[f32; 4]], src: &[[f32; 4]]) {
+    assert_eq!(
+        src.len(),
+        dst.len(),
+        "src and dst must have the same length"
+    );
+    for (dst, &src) in dst.iter_mut().zip(src) {
+        *dst = add(src, scale(*dst, 1.0 - src[3]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[[f32; 4]], expected: &[[f32; 4]]) {
+        assert_eq!(actual.len(), expected.len());
+        for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+            for c in 0..4 {
+                assert!((a[c] - e[c]).abs() < 1e-5, "pixel {i}: {a:?} != {e:?}");
+            }
+        }
+    }
+
+    fn gray(value: f32) -> [f32; 4] {
+        [value; 4]
+    }
+
+    fn test_image(width: u32, height: u32) -> Vec<[f32; 4]> {
+        let mut state = 0x2545_f491_u32;
+        (0..width * height)
+            .map(|_| {
+                let mut next = || {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    (state >> 8) as f32 / (1 << 24) as f32
+                };
+                let alpha = next();
+                [next() * alpha, next() * alpha, next() * alpha, alpha]
+            })
+            .collect()
+    }
+
+    /// 定義どおりに窓の中を足し合わせるボックスブラー。
+    fn naive_box_blur(
+        image: &[[f32; 4]],
+        width: usize,
+        height: usize,
+        radius: usize,
+    ) -> Vec<[f32; 4]> {
+        let count = ((2 * radius + 1) * (2 * radius + 1)) as f64;
+        let mut result = vec![[0.0; 4]; image.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0.0f64; 4];
+                for sy in y.saturating_sub(radius)..(y + radius + 1).min(height) {
+                    for sx in x.saturating_sub(radius)..(x + radius + 1).min(width) {
+                        for c in 0..4 {
+                            sum[c] += image[sy * width + sx][c] as f64;
+                        }
+                    }
+                }
+                result[y * width + x] = sum.map(|value| (value / count) as f32);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_box_blur_reference() {
+        // 1ピクセルだけ白い5x3の画像を半径1でぼかすと、3x3の範囲に1/9ずつ広がる
+        let mut image = vec![gray(0.0); 15];
+        image[7] = gray(1.0);
+        let mut scratch = vec![gray(0.0); 15];
+        box_blur(&mut image, &mut scratch, 5, 3, 1);
+        let n = 1.0 / 9.0;
+        #[rustfmt::skip]
+        let expected = [
+            0.0, n, n, n, 0.0,
+            0.0, n, n, n, 0.0,
+            0.0, n, n, n, 0.0,
+        ];
+        assert_close(&image, &expected.map(gray));
+
+        // 画像の外は透明として扱う
+        let mut image = vec![gray(1.0); 12];
+        let mut scratch = vec![gray(0.0); 12];
+        box_blur(&mut image, &mut scratch, 4, 3, 1);
+        let (a, b, c) = (4.0 / 9.0, 2.0 / 3.0, 1.0);
+        #[rustfmt::skip]
+        let expected = [
+            a, b, b, a,
+            b, c, c, b,
+            a, b, b, a,
+        ];
+        assert_close(&image, &expected.map(gray));
+    }
+
+    #[test]
+    fn test_box_blur_matches_naive() {
+        for (width, height, radius) in [(17, 9, 1), (17, 9, 3), (8, 31, 5), (3, 3, 10), (1, 6, 2)] {
+            let original = test_image(width, height);
+            let mut image = original.clone();
+            let mut scratch = vec![[0.0; 4]; image.len()];
+            box_blur(&mut image, &mut scratch, width, height, radius);
+            assert_close(
+                &image,
+                &naive_box_blur(&original, width as usize, height as usize, radius as usize),
+            );
+        }
+    }
+
+    #[test]
+    fn test_box_blur_radius_zero() {
+        let original = test_image(4, 4);
+        let mut image = original.clone();
+        let mut scratch = vec![[0.0; 4]; 16];
+        box_blur(&mut image, &mut scratch, 4, 4, 0);
+        assert_eq!(image, original);
+    }
+
+    #[test]
+    fn test_resize_reference() {
+        // 2x1 -> 4x1：ピクセルの中心で補間し、外側は端の値になる
+        let src = [gray(0.0), gray(1.0)];
+        let mut dst = vec![gray(0.0); 4];
+        resize_bilinear(&src, 2, 1, &mut dst, 4, 1);
+        assert_close(&dst, &[0.0, 0.25, 0.75, 1.0].map(gray));
+
+        // 2x2 -> 1x1：4ピクセルの平均
+        let src = [gray(0.0), gray(0.2), gray(0.4), gray(1.0)];
+        let mut dst = vec![gray(0.0); 1];
+        resize_bilinear(&src, 2, 2, &mut dst, 1, 1);
+        assert_close(&dst, &[gray(0.4)]);
+
+        // 同じ大きさならそのまま
+        let src = test_image(7, 5);
+        let mut dst = vec![gray(0.0); 35];
+        resize_bilinear(&src, 7, 5, &mut dst, 7, 5);
+        assert_close(&dst, &src);
+    }
+
+    #[test]
+    fn test_resize_matches_naive() {
+        let src = test_image(13, 7);
+        let (dst_width, dst_height) = (5usize, 11usize);
+        let mut dst = vec![gray(0.0); dst_width * dst_height];
+        resize_bilinear(&src, 13, 7, &mut dst, dst_width as u32, dst_height as u32);
+
+        let sample = |x: f64, y: f64| -> [f32; 4] {
+            let x = x.clamp(0.0, 12.0);
+            let y = y.clamp(0.0, 6.0);
+            let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+            let (x1, y1) = ((x0 + 1).min(12), (y0 + 1).min(6));
+            let (tx, ty) = (x - x0 as f64, y - y0 as f64);
+            std::array::from_fn(|c| {
+                let p = |x: usize, y: usize| src[y * 13 + x][c] as f64;
+                let top = p(x0, y0) * (1.0 - tx) + p(x1, y0) * tx;
+                let bottom = p(x0, y1) * (1.0 - tx) + p(x1, y1) * tx;
+                (top * (1.0 - ty) + bottom * ty) as f32
+            })
+        };
+        let expected = (0..dst_width * dst_height)
+            .map(|i| {
+                let (x, y) = ((i % dst_width) as f64, (i / dst_width) as f64);
+                sample(
+                    (x + 0.5) * 13.0 / dst_width as f64 - 0.5,
+                    (y + 0.5) * 7.0 / dst_height as f64 - 0.5,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_close(&dst, &expected);
+    }
+
+    #[test]
+    fn test_over_reference() {
+        let mut dst = [
+            [0.0, 0.0, 1.0, 1.0],
+            [0.2, 0.2, 0.2, 0.4],
+            [0.3, 0.0, 0.0, 0.5],
+        ];
+        let src = [[0.5, 0.0, 0.0, 0.5], [0.0; 4], [0.0, 0.0, 0.8, 1.0]];
+        over(&mut dst, &src);
+        assert_close(
+            &dst,
+            &[
+                [0.5, 0.0, 0.5, 1.0],
+                [0.2, 0.2, 0.2, 0.4],
+                [0.0, 0.0, 0.8, 1.0],
+            ],
+        );
+    }
+
+    #[test]
+    fn test_rgba8_round_trip() {
+        let pixels = [
+            RgbaPixel {
+                r: 255,
+                g: 128,
+                b: 0,
+                a: 255,
+            },
+            RgbaPixel {
+                r: 200,
+                g: 100,
+                b: 50,
+                a: 128,
+            },
+            RgbaPixel {
+                r: 10,
+                g: 20,
+                b: 30,
+                a: 0,
+            },
+        ];
+        let mut premultiplied = [[0.0; 4]; 3];
+        rgba8_to_premultiplied(&pixels, &mut premultiplied);
+        assert_close(&premultiplied[..1], &[[1.0, 128.0 / 255.0, 0.0, 1.0]]);
+        assert!((premultiplied[1][0] - 200.0 / 255.0 * 128.0 / 255.0).abs() < 1e-6);
+        assert_eq!(premultiplied[2], [0.0; 4]);
+
+        let mut round_trip = [RgbaPixel::default(); 3];
+        premultiplied_to_rgba8(&premultiplied, &mut round_trip);
+        assert_eq!(round_trip[..2], pixels[..2]);
+        // 完全に透明なピクセルは色を持たない
+        assert_eq!(round_trip[2], RgbaPixel::default());
+    }
+}
@@ -0,0 +1,309 @@
+//! オブジェクトとフレームから決まる乱数。
+
+/// オブジェクトとフレームごとに決まった値を返す乱数生成器。
+///
+/// `rand::rng()`などを使うと、プレビューで同じフレームを描画するたびに結果が変わってしまいます。
+/// `FrameRng`は同じ引数からは常に同じ列を返すので、シークしても同じ結果になります。
+///
+/// アルゴリズムは[PCG32](https://www.pcg-random.org/)（PCG-XSH-RR 64/32）です。
+/// 引数はSplitMix64で混ぜてから状態とストリームにするため、
+/// 隣り合ったフレーム番号やサンプル位置からでも相関の少ない列になります。
+/// 暗号用途には使えません。
+///
+/// # Note
+///
+/// [`ObjectInfo::id`][crate::filter::ObjectInfo::id]はアプリの起動ごとに変わります。
+/// 起動をまたいで同じ結果にしたい場合は、`object_id`の代わりにレイヤー番号や設定項目の値などを使ってください。
+///
+/// # Example
+///
+/// ```rust
+/// # fn f(video: &aviutl2::filter::FilterProcVideo) {
+/// let mut rng = aviutl2::filter::FrameRng::new(video.object.id, video.object.frame as u64, 0);
+/// let _x = rng.range(0.0..video.video_object.width as f64);
+/// let _angle = rng.range(0..360);
+/// let mut order = [0, 1, 2, 3];
+/// rng.shuffle(&mut order);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameRng {
+    state: u64,
+    increment: u64,
+}
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+
+impl FrameRng {
+    /// オブジェクトのID、フレーム番号（音声の場合はサンプル位置など）、任意のシードから作成する。
+    pub fn new(object_id: i64, frame_index: u64, user_seed: u64) -> Self {
+        let seed = splitmix64(splitmix64(splitmix64(object_id as u64) ^ frame_index) ^ user_seed);
+        Self::from_seed_and_stream(seed, splitmix64(seed))
+    }
+
+    /// PCG32の参照実装の`pcg32_srandom_r`と同じ初期化を行う。
+    fn from_seed_and_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// 32ビットの乱数を返す。
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old
+            .wrapping_mul(PCG_MULTIPLIER)
+            .wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// 64ビットの乱数を返す。
+    pub fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    /// `0.0`以上`1.0`未満の乱数を返す。
+    pub fn f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// `0.0`以上`1.0`未満の乱数を返す。
+    pub fn f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+
+    /// `true`か`false`を同じ確率で返す。
+    pub fn bool(&mut self) -> bool {
+        self.next_u32() >> 31 == 1
+    }
+
+    /// 範囲内の乱数を返す。
+    ///
+    /// 整数の場合は`a..b`と`a..=b`、浮動小数点数の場合は`a..b`を指定できます。
+    ///
+    /// # Panics
+    ///
+    /// 範囲が空の場合はパニックします。
+    pub fn range<R: FrameRngRange>(&mut self, range: R) -> R::Output {
+        range.sample(self)
+    }
+
+    /// スライスの要素をシャッフルする。
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            slice.swap(i, j);
+        }
+    }
+
+    /// `0`以上`bound`未満の一様な乱数を返す。`bound`が`0`の場合は全ての値を返しうる。
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return self.next_u64();
+        }
+        // 偏りが出ないよう、2^64を`bound`で割った余りの分に当たった場合はやり直す
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let value = self.next_u64();
+            if value >= threshold {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// [`FrameRng::range`]に渡せる範囲。
+pub trait FrameRngRange {
+    type Output;
+
+    /// 範囲内の乱数を返す。
+    fn sample(self, rng: &mut FrameRng) -> Self::Output;
+}
+
+macro_rules! impl_frame_rng_range_int {
+    ($($ty:ty => $unsigned:ty),* $(,)?) => {
+        $(
+            impl FrameRngRange for std::ops::Range<$ty> {
+                type Output = $ty;
+
+                fn sample(self, rng: &mut FrameRng) -> $ty {
+                    assert!(self.start < self.end, "cannot sample from an empty range");
+                    let span = self.end.wrapping_sub(self.start) as $unsigned as u64;
+                    self.start.wrapping_add(rng.below(span) as $ty)
+                }
+            }
+
+            impl FrameRngRange for std::ops::RangeInclusive<$ty> {
+                type Output = $ty;
+
+                fn sample(self, rng: &mut FrameRng) -> $ty {
+                    let (start, end) = self.into_inner();
+                    assert!(start <= end, "cannot sample from an empty range");
+                    // 型の全範囲の場合は0になり、`below`が全ての値を返す
+                    let span = (end.wrapping_sub(start) as $unsigned as u64).wrapping_add(1)
+                        & (<$unsigned>::MAX as u64);
+                    start.wrapping_add(rng.below(span) as $ty)
+                }
+            }
+        )*
+    };
+}
+
+impl_frame_rng_range_int!(
+    u8 => u8,
+    u16 => u16,
+    u32 => u32,
+    u64 => u64,
+    usize => usize,
+    i8 => u8,
+    i16 => u16,
+    i32 => u32,
+    i64 => u64,
+    isize => usize,
+);
+
+macro_rules! impl_frame_rng_range_float {
+    ($($ty:ident),* $(,)?) => {
+        $(
+            impl FrameRngRange for std::ops::Range<$ty> {
+                type Output = $ty;
+
+                fn sample(self, rng: &mut FrameRng) -> $ty {
+                    assert!(self.start < self.end, "cannot sample from an empty range");
+                    let value = self.start + (self.end - self.start) * rng.$ty();
+                    // 丸め誤差で`end`になった場合は範囲内に戻す
+                    if value < self.end {
+                        value
+                    } else {
+                        self.end.next_down()
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_frame_rng_range_float!(f32, f64);
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pcg32_reference() {
+        // pcg32-demo.cの`pcg32_srandom_r(&rng, 42, 54)`の出力
+        let mut rng = FrameRng::from_seed_and_stream(42, 54);
+        let values = (0..6).map(|_| rng.next_u32()).collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [
+                0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let sequence = |object_id, frame_index, user_seed| {
+            let mut rng = FrameRng::new(object_id, frame_index, user_seed);
+            (0..16).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(sequence(1, 2, 3), sequence(1, 2, 3));
+        assert_ne!(sequence(1, 2, 3), sequence(1, 3, 3));
+        assert_ne!(sequence(1, 2, 3), sequence(2, 2, 3));
+        assert_ne!(sequence(1, 2, 3), sequence(1, 2, 4));
+        // 引数を入れ替えても別の列になる
+        assert_ne!(sequence(1, 2, 3), sequence(2, 1, 3));
+
+        let mut a = FrameRng::new(7, 100, 0);
+        let mut b = a.clone();
+        let mut items_a = (0..32).collect::<Vec<_>>();
+        let mut items_b = items_a.clone();
+        a.shuffle(&mut items_a);
+        b.shuffle(&mut items_b);
+        assert_eq!(items_a, items_b);
+    }
+
+    #[test]
+    fn test_f64_distribution() {
+        let mut rng = FrameRng::new(0, 0, 0);
+        let values = (0..100_000).map(|_| rng.f64()).collect::<Vec<_>>();
+        assert!(values.iter().all(|v| (0.0..1.0).contains(v)));
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        assert!((mean - 0.5).abs() < 0.01, "mean = {mean}");
+        assert!(
+            (variance - 1.0 / 12.0).abs() < 0.005,
+            "variance = {variance}"
+        );
+    }
+
+    #[test]
+    fn test_adjacent_frames_are_uncorrelated() {
+        // サンプル位置ごとに作り直して使う場合（ノイズなど）でも偏らないこと
+        let values = (0..100_000u64)
+            .map(|i| FrameRng::new(1, i, 0).f64() * 2.0 - 1.0)
+            .collect::<Vec<_>>();
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let lag1 = values.windows(2).map(|w| w[0] * w[1]).sum::<f64>() / values.len() as f64;
+        assert!(mean.abs() < 0.02, "mean = {mean}");
+        assert!(lag1.abs() < 0.01, "lag1 = {lag1}");
+    }
+
+    #[test]
+    fn test_range() {
+        let mut rng = FrameRng::new(3, 4, 5);
+        let mut counts = [0u32; 6];
+        for _ in 0..60_000 {
+            let value = rng.range(1..=6);
+            assert!((1..=6).contains(&value));
+            counts[value as usize - 1] += 1;
+        }
+        assert!(
+            counts.iter().all(|&count| (9_000..11_000).contains(&count)),
+            "{counts:?}"
+        );
+
+        for _ in 0..1000 {
+            assert!((-5..5).contains(&rng.range(-5i32..5)));
+            assert!((-1.0..1.0).contains(&rng.range(-1.0..1.0)));
+            assert!((10.0f32..10.5).contains(&rng.range(10.0f32..10.5)));
+        }
+        assert_eq!(rng.range(7u8..8), 7);
+        // 型の全範囲でもパニックしない
+        rng.range(i8::MIN..=i8::MAX);
+        rng.range(u64::MIN..=u64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty range")]
+    fn test_empty_range_panics() {
+        FrameRng::new(0, 0, 0).range(3..3);
+    }
+
+    #[test]
+    fn test_shuffle_is_permutation() {
+        let mut rng = FrameRng::new(9, 9, 9);
+        let mut items = (0..100).collect::<Vec<_>>();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..100).collect::<Vec<_>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..100).collect::<Vec<_>>());
+
+        let mut empty: [u8; 0] = [];
+        rng.shuffle(&mut empty);
+    }
+}
@@ -38,6 +38,30 @@ pub enum EditHandleError {
     ValueOutOfRange,
 }
 
+/// 実行中の編集セクション・参照セクションの呼び出しの数。
+static SECTION_CALLS_IN_PROGRESS: std::sync::atomic::AtomicUsize =
+    std::sync::atomic::AtomicUsize::new(0);
+
+/// 編集セクション・参照セクションの呼び出しが実行中かどうかを返す。
+pub(crate) fn is_section_call_in_progress() -> bool {
+    SECTION_CALLS_IN_PROGRESS.load(std::sync::atomic::Ordering::Acquire) > 0
+}
+
+struct SectionCallGuard;
+
+impl SectionCallGuard {
+    fn new() -> Self {
+        SECTION_CALLS_IN_PROGRESS.fetch_add(1, std::sync::atomic::Ordering::AcqRel);
+        Self
+    }
+}
+
+impl Drop for SectionCallGuard {
+    fn drop(&mut self) {
+        SECTION_CALLS_IN_PROGRESS.fetch_sub(1, std::sync::atomic::Ordering::AcqRel);
+    }
+}
+
 /// シーンの映像レンダリング結果。
 #[derive(Debug, Clone, Copy)]
 pub struct RenderingSceneVideo<'a> {
@@ -115,6 +139,7 @@ impl EditHandle {
             self.is_ready(),
             "call_edit_section cannot be called before register_plugin is done"
         );
+        let _call_guard = SectionCallGuard::new();
 
         type CallbackParam<'a, F, T> = (ChildKillablePointer<Option<F>>, &'a mut Option<T>);
 
@@ -161,6 +186,38 @@ impl EditHandle {
         }
     }
 
+    /// プロジェクトデータの編集を開始し、変更を1つのUndoとしてまとめる。
+    ///
+    /// 1回の操作で複数の編集を行う場合、[`Self::call_edit_section`]を複数回呼ぶとUndoが分かれてしまうため、
+    /// この関数でまとめて行ってください。
+    /// `f`が失敗した場合は変更を元に戻します。詳しくは[`EditSection::undo_group`]を参照してください。
+    ///
+    /// # Note
+    ///
+    /// SDKにはUndo・Redoを実行するAPIがないため、プラグインからUndo・Redoを実行することはできません。
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn f(handle: &aviutl2::generic::EditHandle, object: aviutl2::generic::ObjectHandle) -> aviutl2::AnyResult<()> {
+    /// handle.undo_group("置き換え", |tx| {
+    ///     let frame = tx.get_object_layer_frame(object)?;
+    ///     tx.delete_object(object)?;
+    ///     // 作成に失敗した場合、削除したオブジェクトは作り直される
+    ///     tx.try_create_object_from_alias("...", frame.layer, frame.start, frame.end - frame.start + 1)?;
+    ///     Ok(())
+    /// })
+    /// # }
+    /// ```
+    pub fn undo_group<R, F>(&self, name: &str, f: F) -> crate::common::AnyResult<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut crate::generic::EditTransactionGuard<'_>) -> crate::common::AnyResult<R>
+            + Send,
+    {
+        self.call_edit_section(|section| section.undo_group(name, f))?
+    }
+
     /// プロジェクトデータの参照を開始する。
     ///
     /// # Note
@@ -175,6 +232,7 @@ impl EditHandle {
             self.is_ready(),
             "call_read_section cannot be called before register_plugin is done"
         );
+        let _call_guard = SectionCallGuard::new();
 
         type CallbackParam<'a, F, T> = (ChildKillablePointer<Option<F>>, &'a mut Option<T>);
 
@@ -239,6 +297,12 @@ impl EditHandle {
     }
 
     /// ホストアプリケーションを再起動する。
+    ///
+    /// # Note
+    ///
+    /// プロジェクトの保存や、再起動後のプロジェクトの再読み込みは行いません。
+    /// AviUtl2のSDKにはプロジェクトを保存・読み込みする関数や、再起動時の引数を指定する手段がないため、
+    /// このクレートでもこれらの機能は提供していません。
     pub fn restart_host_app(&self) {
         assert!(
             self.is_ready(),
@@ -473,6 +537,48 @@ impl EditHandle {
         EditState::try_from(state).map_err(|_| EditHandleError::UnknownEditState(state))
     }
 
+    /// プレビュー再生中かどうかを返す。
+    ///
+    /// どのスレッドからでも呼び出せます。
+    /// 初期化前の場合は警告をログに出力し、`false`を返します。
+    pub fn is_playing(&self) -> bool {
+        if !self.is_ready() {
+            tracing::warn!("is_playing was called before register_plugin is done");
+            return false;
+        }
+        matches!(self.get_edit_state(), Ok(EditState::Preview))
+    }
+
+    /// 現在のカーソルのフレーム番号（0始まり）を返す。
+    ///
+    /// どのスレッドからでも呼び出せます。
+    /// 初期化前の場合は警告をログに出力し、`0`を返します。
+    pub fn current_frame(&self) -> u32 {
+        if !self.is_ready() {
+            tracing::warn!("current_frame was called before register_plugin is done");
+            return 0;
+        }
+        self.get_edit_info().frame.try_into().unwrap_or(u32::MAX)
+    }
+
+    /// カーソルを指定したフレーム（0始まり）に移動する。レイヤーは現在の位置のままです。
+    ///
+    /// どのスレッドからでも呼び出せます。
+    /// 初期化前の場合は警告をログに出力し、何もしません。
+    ///
+    /// # Note
+    ///
+    /// 出力中など編集ができない状態では[`EditHandleError::ApiCallFailed`]を返します。
+    /// フレームは設定出来る範囲に調整されます。
+    pub fn seek_frame(&self, frame: u32) -> Result<(), EditHandleError> {
+        if !self.is_ready() {
+            tracing::warn!("seek_frame was called before register_plugin is done");
+            return Ok(());
+        }
+        self.call_edit_section(|edit| edit.set_cursor_layer_frame(edit.info.layer, frame as usize))?
+            .map_err(|_| EditHandleError::ValueOutOfRange)
+    }
+
     /// 現在のシーンの映像レンダリングを要求する。
     ///
     /// この関数はレンダリングタスクの追加のみを行います。レンダリング完了時に
@@ -1032,6 +1138,11 @@ impl GlobalEditHandle {
             .get()
             .is_some_and(|handle| handle.is_ready())
     }
+
+    /// 初期化されている場合、編集ハンドルを返す。
+    pub(crate) fn get(&self) -> Option<&crate::generic::EditHandle> {
+        self.edit_handle.get()
+    }
 }
 
 impl Default for GlobalEditHandle {
@@ -131,6 +131,32 @@ impl EditInfo {
     }
 }
 
+/// シーンの情報。
+///
+/// # Note
+///
+/// AviUtl2のSDKは現在のシーン以外の情報を提供していないため、取得できるのは現在のシーンの情報のみです。
+/// シーンの一覧の取得・切り替え・作成もできません。
+/// シーンの切り替えを検知するには、[`crate::generic::GenericPlugin::event_change_scene_info`]を使ってください。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SceneInfo {
+    /// シーンのID。
+    pub id: i32,
+    /// シーン名。
+    pub name: String,
+    /// シーンの解像度（幅、高さ）。
+    pub resolution: (usize, usize),
+    /// フレームレート。
+    pub fps: Rational32,
+    /// サンプルレート。
+    pub sample_rate: usize,
+    /// シーンの長さ（フレーム数）。
+    ///
+    /// オブジェクトが存在する最大のフレーム番号に1を足した値です。
+    pub length: usize,
+}
+
 /// オブジェクトのレイヤーとフレーム情報。
 #[derive(Debug, Clone, Copy)]
 pub struct ObjectLayerFrame {
@@ -151,6 +177,77 @@ impl ObjectLayerFrame {
     }
 }
 
+/// [`ReadSection::find_objects`]の検索条件。
+///
+/// 条件を指定しなかった項目は絞り込みに使われません。
+///
+/// ```rust
+/// # use aviutl2::generic::ObjectQuery;
+/// // レイヤー1〜3（0始まり）の、フレーム100から200までに掛かっているテキストオブジェクト
+/// let query = ObjectQuery::new()
+///     .layers(0..3)
+///     .frames(100..200)
+///     .has_effect("テキスト");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectQuery {
+    layer_start: usize,
+    layer_end: Option<usize>,
+    frame_start: usize,
+    frame_end: Option<usize>,
+    effects: Vec<String>,
+}
+
+impl ObjectQuery {
+    /// すべてのオブジェクトに一致する検索条件を作成する。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 指定の範囲のレイヤー（0始まり）にあるオブジェクトに絞り込む。
+    pub fn layers(mut self, layers: impl std::ops::RangeBounds<usize>) -> Self {
+        (self.layer_start, self.layer_end) = range_bounds_to_range(layers);
+        self
+    }
+
+    /// 指定の範囲のフレームに一部でも掛かっているオブジェクトに絞り込む。
+    pub fn frames(mut self, frames: impl std::ops::RangeBounds<usize>) -> Self {
+        (self.frame_start, self.frame_end) = range_bounds_to_range(frames);
+        self
+    }
+
+    /// 指定のフレームに存在するオブジェクトに絞り込む。
+    pub fn at_frame(self, frame: usize) -> Self {
+        self.frames(frame..=frame)
+    }
+
+    /// 指定のエフェクトを持つオブジェクトに絞り込む。
+    ///
+    /// 複数回呼び出した場合は、すべてのエフェクトを持つオブジェクトに絞り込みます。
+    ///
+    /// # Arguments
+    ///
+    /// - `effect`：エフェクト名。エイリアスファイルの`effect.name`の値を指定します。
+    pub fn has_effect(mut self, effect: impl Into<String>) -> Self {
+        self.effects.push(effect.into());
+        self
+    }
+}
+
+fn range_bounds_to_range(range: impl std::ops::RangeBounds<usize>) -> (usize, Option<usize>) {
+    let start = match range.start_bound() {
+        std::ops::Bound::Included(&start) => start,
+        std::ops::Bound::Excluded(&start) => start.saturating_add(1),
+        std::ops::Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        std::ops::Bound::Included(&end) => Some(end.saturating_add(1)),
+        std::ops::Bound::Excluded(&end) => Some(end),
+        std::ops::Bound::Unbounded => None,
+    };
+    (start, end)
+}
+
 /// レイヤーとフレーム情報。
 #[derive(Debug, Clone, Copy)]
 pub struct LayerFrameData {
@@ -305,6 +402,22 @@ pub enum EditSectionError {
     ParseFailed(#[from] aviutl2_alias::TableParseError),
 }
 
+/// [`EditSection::try_create_object_from_alias`] のエラー。
+#[derive(thiserror::Error, Debug)]
+pub enum CreateObjectError {
+    /// 作成するオブジェクトが既存のオブジェクトと重なる。
+    #[error("object would overlap an existing object (free until frame {available_until})")]
+    Overlap {
+        /// 重なる既存のオブジェクト。
+        blocking_object: ObjectHandle,
+        /// 作成位置から空いている範囲の終端（このフレームは含まない）。
+        /// 作成位置がすでに埋まっている場合は、作成位置以下の値になります。
+        available_until: usize,
+    },
+    #[error(transparent)]
+    EditSectionError(#[from] EditSectionError),
+}
+
 /// [`ReadSection::get_object_effect_item_parsed`] などのエラー。
 #[derive(thiserror::Error, Debug)]
 pub enum EditSectionParsedError<E: std::error::Error + Send + Sync + 'static> {
@@ -460,6 +573,29 @@ impl ReadSection {
         Ok(value)
     }
 
+    /// メディアファイルを参照するオブジェクトのファイルパスを取得する。
+    ///
+    /// 動画ファイル・画像ファイル・音声ファイルの順に探し、最初に見つかったファイルのパスを返します。
+    ///
+    /// # Returns
+    ///
+    /// メディアファイルを参照していない場合、またはファイルが未指定の場合は`None`を返します。
+    pub fn get_object_source_path(
+        &self,
+        object: ObjectHandle,
+    ) -> EditSectionResult<Option<std::path::PathBuf>> {
+        for effect_name in MEDIA_FILE_EFFECT_NAMES {
+            if self.count_object_effect(object, effect_name)? == 0 {
+                continue;
+            }
+            let path = self.get_object_effect_item(object, effect_name, 0, "ファイル")?;
+            if !path.is_empty() {
+                return Ok(Some(std::path::PathBuf::from(path)));
+            }
+        }
+        Ok(None)
+    }
+
     /// オブジェクトの設定項目の値を取得し、パースする。
     #[cfg(feature = "aviutl2-alias")]
     pub fn get_object_effect_item_parsed<T: aviutl2_alias::FromTableValue>(
@@ -569,6 +705,29 @@ impl ReadSection {
         Ok(unsafe { crate::common::load_wide_string(name_ptr) })
     }
 
+    /// 現在のシーンのIDを取得する。
+    pub fn current_scene_id(&self) -> i32 {
+        unsafe { (*(*self.internal).info).scene_id }
+    }
+
+    /// 現在のシーンの情報を取得する。
+    ///
+    /// 参照のみのため、[`ReadSection`]（`call_read_section`）でも呼び出せます。
+    /// シーンの設定を変更する [`EditSection::set_scene_name`] などは [`EditSection`]
+    /// （`call_edit_section`）でのみ呼び出せます。
+    pub fn current_scene(&self) -> EditSectionResult<SceneInfo> {
+        let name = self.get_scene_name()?;
+        let info = unsafe { EditInfo::from_raw((*self.internal).info) };
+        Ok(SceneInfo {
+            id: info.scene_id,
+            name,
+            resolution: (info.width, info.height),
+            fps: info.fps,
+            sample_rate: info.sample_rate,
+            length: info.frame_max + 1,
+        })
+    }
+
     /// レイヤーの表示・非表示を取得する。
     pub fn get_layer_enable(&self, layer: usize) -> EditSectionResult<bool> {
         let visible = unsafe { ((*self.internal).get_layer_enable)(layer.try_into()?) };
@@ -1149,6 +1308,81 @@ impl ReadSection {
         EditSectionLayerObjectsIterator::new(self, layer)
     }
 
+    /// 条件に一致するオブジェクトを検索する。
+    ///
+    /// レイヤーとフレームの範囲で候補を絞り込んでから、エフェクトの有無を調べます。
+    /// すべてのオブジェクトを走査して絞り込むよりも、AviUtl2の関数を呼び出す回数が少なくなります。
+    ///
+    /// 結果はレイヤー順、開始フレームの昇順に並びます。
+    pub fn find_objects(&self, query: ObjectQuery) -> EditSectionResult<Vec<ObjectHandle>> {
+        let effects = query
+            .effects
+            .iter()
+            .map(|effect| crate::common::CWString::new(effect))
+            .collect::<Result<Vec<_>, _>>()?;
+        let layer_end = query
+            .layer_end
+            .unwrap_or(usize::MAX)
+            .min(self.layer_max()?.saturating_add(1));
+        let frame_end = query.frame_end.unwrap_or(usize::MAX);
+        if query.frame_start >= frame_end {
+            return Ok(Vec::new());
+        }
+
+        // 先にレイヤーとフレームで候補を集める
+        let mut candidates = Vec::new();
+        for layer in query.layer_start..layer_end {
+            let layer = i32::try_from(layer)?;
+            let mut frame = query.frame_start;
+            while frame < frame_end {
+                let handle = unsafe { ((*self.internal).find_object)(layer, frame.try_into()?) };
+                if handle.is_null() {
+                    break;
+                }
+                // find_objectで見つかったハンドルは存在が保証されているので、ensure_object_existsは呼ばない
+                let lf = unsafe { ((*self.internal).get_object_layer_frame)(handle) };
+                if lf.layer == -1 {
+                    return Err(EditSectionError::ObjectDoesNotExist);
+                }
+                let (start, end): (usize, usize) = (lf.start.try_into()?, lf.end.try_into()?);
+                if start >= frame_end {
+                    break;
+                }
+                candidates.push(ObjectHandle { internal: handle });
+                frame = end.saturating_add(1);
+            }
+        }
+
+        // エフェクトの有無はまとめて調べる
+        let mut objects = Vec::with_capacity(candidates.len());
+        for object in candidates {
+            let mut matched = true;
+            for effect in &effects {
+                let count = unsafe {
+                    ((*self.internal).count_object_effect)(object.internal, effect.as_ptr())
+                };
+                if count <= 0 {
+                    matched = false;
+                    break;
+                }
+            }
+            if matched {
+                objects.push(object);
+            }
+        }
+        Ok(objects)
+    }
+
+    /// 指定のフレームに存在するオブジェクトを、レイヤー順で取得する。
+    pub fn objects_at_frame(&self, frame: usize) -> EditSectionResult<Vec<ObjectHandle>> {
+        self.find_objects(ObjectQuery::new().at_frame(frame))
+    }
+
+    pub(crate) fn layer_max(&self) -> EditSectionResult<usize> {
+        let layer_max = unsafe { (*(*self.internal).info).layer_max };
+        Ok(layer_max.try_into()?)
+    }
+
     /// [EditSectionLayerCaller] を作成する。
     pub fn layer<'a>(&'a self, layer: usize) -> EditSectionLayerCaller<'a, ReadSection> {
         EditSectionLayerCaller::new(self, layer)
@@ -1180,6 +1414,27 @@ impl EditSection {
         }
     }
 
+    /// スクリーン座標にあるレイヤー番号とフレーム番号（どちらも0始まり）を返す。
+    ///
+    /// ドラッグ＆ドロップなどで、ドロップした位置にオブジェクトを作成するときに使えます。
+    /// レイヤー編集の位置やスクロール・拡大率はAviUtl2側で考慮されます。
+    ///
+    /// # Returns
+    ///
+    /// 座標がレイヤー編集エリアの外の場合は`None`を返します。
+    ///
+    /// # See Also
+    ///
+    /// - [`Self::pos_to_layer_frame`]
+    /// - [`Self::get_mouse_layer_frame`]：マウスカーソルの位置を使う場合
+    pub fn frame_at_point(
+        &self,
+        screen_pos: (i32, i32),
+    ) -> EditSectionResult<Option<(usize, usize)>> {
+        let position = self.pos_to_layer_frame(screen_pos.0, screen_pos.1)?;
+        Ok(position.map(|position| (position.layer, position.frame)))
+    }
+
     /// オブジェクトエイリアスから指定の位置にオブジェクトを作成する。
     ///
     /// # Arguments
@@ -1202,7 +1457,7 @@ impl EditSection {
         frame: usize,
         length: usize,
     ) -> EditSectionResult<ObjectHandle> {
-        let c_alias = std::ffi::CString::new(alias)?;
+        let c_alias = crate::utils::encoding::to_host_utf8(alias)?;
         let object_handle = unsafe {
             ((*self.internal).create_object_from_alias)(
                 c_alias.as_ptr(),
@@ -1219,6 +1474,74 @@ impl EditSection {
         })
     }
 
+    /// 既存のオブジェクトと重ならないか確認してから、オブジェクトエイリアスからオブジェクトを作成する。
+    ///
+    /// 引数は[`Self::create_object_from_alias`]と同じです。
+    /// 重なる場合は何も作成せずに[`CreateObjectError::Overlap`]を返します。
+    ///
+    /// # Note
+    ///
+    /// 重なりの確認には`length`を使います。
+    /// エイリアスデータにフレーム情報が含まれている場合は、`length`をそれに合わせてください。
+    pub fn try_create_object_from_alias(
+        &self,
+        alias: &str,
+        layer: usize,
+        frame: usize,
+        length: usize,
+    ) -> Result<ObjectHandle, CreateObjectError> {
+        if let Some(blocking_object) = self.find_object_after(layer, frame)? {
+            let available_until = self.get_object_layer_frame(blocking_object)?.start;
+            if available_until < frame.saturating_add(length) {
+                return Err(CreateObjectError::Overlap {
+                    blocking_object,
+                    available_until,
+                });
+            }
+        }
+        Ok(self.create_object_from_alias(alias, layer, frame, length)?)
+    }
+
+    /// 作成・削除したオブジェクトを記録し、確定しなかった場合に元に戻すガードを作成する。
+    ///
+    /// 詳しくは[`EditTransactionGuard`][crate::generic::EditTransactionGuard]を参照してください。
+    pub fn transaction(&self) -> crate::generic::EditTransactionGuard<'_> {
+        crate::generic::EditTransactionGuard::new(self)
+    }
+
+    /// `f`の中で行った変更を、成功した場合のみ確定する。
+    ///
+    /// `f`には[`transaction`][Self::transaction]で作成したガードが渡されます。
+    /// `f`が`Ok`を返した場合は変更を確定し、`Err`を返した場合やパニックした場合は、
+    /// ガードを通して作成・削除したオブジェクトを元に戻します。
+    ///
+    /// # Note
+    ///
+    /// AviUtl2は、1回の[`EditHandle::call_edit_section`][crate::generic::EditHandle::call_edit_section]の中で
+    /// 行った変更をまとめて1つのUndoとして登録します。
+    /// SDKにはUndoの区切りを指定したり、Undoに名前を付けたりするAPIはないため、
+    /// `name`はAviUtl2のUndo履歴には表示されず、ログにのみ使われます。
+    ///
+    /// 失敗した場合も、元に戻す処理を含めた変更が1つのUndoとして登録されます。
+    pub fn undo_group<R>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&mut crate::generic::EditTransactionGuard<'_>) -> crate::common::AnyResult<R>,
+    ) -> crate::common::AnyResult<R> {
+        let mut tx = self.transaction();
+        match f(&mut tx) {
+            Ok(value) => {
+                tx.commit();
+                Ok(value)
+            }
+            Err(e) => {
+                tracing::debug!("Rolling back undo group {name:?}: {e}");
+                drop(tx);
+                Err(e)
+            }
+        }
+    }
+
     /// オブジェクト名を設定する。
     ///
     /// # Note
@@ -1265,7 +1588,7 @@ impl EditSection {
         self.read_section.ensure_object_exists(object)?;
         let c_effect_name = crate::common::CWString::new(&effect_key(effect_name, effect_index))?;
         let c_item = crate::common::CWString::new(item)?;
-        let c_value = std::ffi::CString::new(value)?;
+        let c_value = crate::utils::encoding::to_host_utf8(value)?;
         let success = unsafe {
             ((*self.internal).set_object_item_value)(
                 object.internal,
@@ -1602,7 +1925,7 @@ impl EditSection {
     ) -> EditSectionResult<()> {
         self.read_section.ensure_effect_exists(effect)?;
         let c_item = crate::common::CWString::new(item)?;
-        let c_value = std::ffi::CString::new(value)?;
+        let c_value = crate::utils::encoding::to_host_utf8(value)?;
         let success = unsafe {
             ((*self.internal).set_effect_item_value)(
                 effect.internal,
@@ -1785,6 +2108,15 @@ where
         self.read_section().object_exists(self.handle)
     }
 
+    /// オブジェクトが参照しているメディアファイルのパスを取得する。
+    ///
+    /// # See Also
+    ///
+    /// [`ReadSection::get_object_source_path`]
+    pub fn get_source_path(&self) -> EditSectionResult<Option<std::path::PathBuf>> {
+        self.read_section().get_object_source_path(self.handle)
+    }
+
     /// オブジェクトの名前を取得する。
     ///
     /// # Returns
@@ -2249,3 +2581,218 @@ where
 fn effect_key(effect_name: &str, effect_index: usize) -> String {
     format!("{effect_name}:{effect_index}")
 }
+
+/// メディアファイルを参照するエフェクトの名前。
+const MEDIA_FILE_EFFECT_NAMES: [&str; 3] = ["動画ファイル", "画像ファイル", "音声ファイル"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic::binding::test_section::{self, MockObject, count_host_calls};
+    use aviutl2_sys::plugin2::{EDIT_INFO, EDIT_SECTION};
+
+    const LAYERS: usize = 100;
+    const OBJECTS_PER_LAYER: usize = 10;
+    const OBJECT_LENGTH: usize = 10;
+    const MARKER: &str = "Marker";
+
+    /// `LAYERS`レイヤーに`OBJECTS_PER_LAYER`個ずつ、隙間なく並んだオブジェクトを持つ編集セクションを作る。
+    /// 7個に1個のオブジェクトが`MARKER`エフェクトを持つ。
+    fn with_mock_section<R>(f: impl FnOnce(&ReadSection) -> R) -> R {
+        let objects = (0..LAYERS * OBJECTS_PER_LAYER)
+            .map(|index| {
+                let object = MockObject::new(
+                    index / OBJECTS_PER_LAYER,
+                    (index % OBJECTS_PER_LAYER) * OBJECT_LENGTH,
+                    (index % OBJECTS_PER_LAYER + 1) * OBJECT_LENGTH - 1,
+                );
+                if index % 7 == 0 {
+                    object.with_effect(MARKER)
+                } else {
+                    object
+                }
+            })
+            .collect();
+        test_section::with_mock_section(
+            objects,
+            |info| info.layer_max = LAYERS as i32 - 1,
+            |ptr| f(&unsafe { ReadSection::from_raw(ptr) }),
+        )
+    }
+
+    /// 全レイヤーの全オブジェクトを走査して絞り込む。
+    fn naive_scan(
+        section: &ReadSection,
+        filter: impl Fn(&ObjectLayerFrame) -> bool,
+    ) -> Vec<ObjectHandle> {
+        (0..LAYERS)
+            .flat_map(|layer| section.objects_in_layer(layer))
+            .filter(|(lf, handle)| {
+                filter(lf) && section.object(*handle).count_effect(MARKER).unwrap() > 0
+            })
+            .map(|(_, handle)| handle)
+            .collect()
+    }
+
+    fn layer_frames(section: &ReadSection, objects: &[ObjectHandle]) -> Vec<(usize, usize)> {
+        objects
+            .iter()
+            .map(|&object| {
+                let lf = section.get_object_layer_frame(object).unwrap();
+                (lf.layer, lf.start)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_objects_with_effect() {
+        with_mock_section(|section| {
+            let (naive, naive_calls) = count_host_calls(|| naive_scan(section, |_| true));
+            let (found, calls) =
+                count_host_calls(|| section.find_objects(ObjectQuery::new().has_effect(MARKER)));
+            let found = found.unwrap();
+
+            assert_eq!(found, naive);
+            assert_eq!(found.len(), (LAYERS * OBJECTS_PER_LAYER).div_ceil(7));
+            // 存在確認を省くので、オブジェクトごとに3回（検索・位置・エフェクト）で済む
+            assert_eq!(calls, LAYERS * (OBJECTS_PER_LAYER * 3 + 1));
+            assert!(calls < naive_calls);
+        });
+    }
+
+    #[test]
+    fn test_find_objects_in_range() {
+        with_mock_section(|section| {
+            let (naive, naive_calls) = count_host_calls(|| {
+                naive_scan(section, |lf| {
+                    (10..20).contains(&lf.layer) && lf.start < 40 && lf.end >= 20
+                })
+            });
+            let (found, calls) = count_host_calls(|| {
+                section.find_objects(
+                    ObjectQuery::new()
+                        .layers(10..20)
+                        .frames(20..40)
+                        .has_effect(MARKER),
+                )
+            });
+            let found = found.unwrap();
+
+            assert_eq!(found, naive);
+            assert_eq!(
+                layer_frames(section, &found),
+                vec![(11, 20), (13, 30), (18, 20)]
+            );
+            assert!(
+                calls * 10 <= naive_calls,
+                "find_objects: {calls}, naive: {naive_calls}"
+            );
+        });
+    }
+
+    #[test]
+    fn test_objects_at_frame() {
+        with_mock_section(|section| {
+            let (found, calls) = count_host_calls(|| section.objects_at_frame(25));
+            let found = found.unwrap();
+
+            assert_eq!(
+                layer_frames(section, &found),
+                (0..LAYERS).map(|layer| (layer, 20)).collect::<Vec<_>>()
+            );
+            assert_eq!(calls, LAYERS * 2);
+        });
+    }
+
+    /// 左上が(100, 200)、1レイヤーが20px、1フレームが5pxのレイヤー編集エリア。
+    /// 表示範囲はレイヤー3・フレーム40から。
+    unsafe extern "C" fn pos_to_layer_frame(
+        x: i32,
+        y: i32,
+        layer: *mut i32,
+        frame: *mut i32,
+    ) -> bool {
+        if !(100..600).contains(&x) || !(200..400).contains(&y) {
+            return false;
+        }
+        unsafe {
+            *layer = 3 + (y - 200) / 20;
+            *frame = 40 + (x - 100) / 5;
+        }
+        true
+    }
+
+    #[test]
+    fn test_frame_at_point() {
+        let mut info: EDIT_INFO = unsafe { std::mem::zeroed() };
+        let mut section = std::mem::MaybeUninit::<EDIT_SECTION>::zeroed();
+        let ptr = section.as_mut_ptr();
+        unsafe {
+            std::ptr::addr_of_mut!((*ptr).info).write(&mut info);
+            std::ptr::addr_of_mut!((*ptr).pos_to_layer_frame).write(pos_to_layer_frame);
+        }
+        let section = unsafe { EditSection::from_raw(ptr) };
+
+        assert_eq!(section.frame_at_point((100, 200)).unwrap(), Some((3, 40)));
+        assert_eq!(section.frame_at_point((112, 259)).unwrap(), Some((5, 42)));
+        assert_eq!(section.frame_at_point((599, 399)).unwrap(), Some((12, 139)));
+        assert_eq!(section.frame_at_point((99, 300)).unwrap(), None);
+        assert_eq!(section.frame_at_point((300, 400)).unwrap(), None);
+    }
+
+    unsafe extern "C" fn get_scene_name() -> *const u16 {
+        static NAME: std::sync::LazyLock<Vec<u16>> =
+            std::sync::LazyLock::new(|| "字幕".encode_utf16().chain(std::iter::once(0)).collect());
+        NAME.as_ptr()
+    }
+
+    #[test]
+    fn test_current_scene() {
+        let mut info: EDIT_INFO = unsafe { std::mem::zeroed() };
+        info.width = 1920;
+        info.height = 1080;
+        info.rate = 30000;
+        info.scale = 1001;
+        info.sample_rate = 48000;
+        info.frame_max = 299;
+        info.scene_id = 2;
+        let mut section = std::mem::MaybeUninit::<EDIT_SECTION>::zeroed();
+        let ptr = section.as_mut_ptr();
+        unsafe {
+            std::ptr::addr_of_mut!((*ptr).info).write(&mut info);
+            std::ptr::addr_of_mut!((*ptr).get_scene_name).write(get_scene_name);
+        }
+        let section = unsafe { ReadSection::from_raw(ptr) };
+
+        assert_eq!(section.current_scene_id(), 2);
+        assert_eq!(
+            section.current_scene().unwrap(),
+            SceneInfo {
+                id: 2,
+                name: "字幕".to_string(),
+                resolution: (1920, 1080),
+                fps: Rational32::new(30000, 1001),
+                sample_rate: 48000,
+                length: 300,
+            }
+        );
+    }
+
+    #[test]
+    fn test_object_query_ranges() {
+        assert_eq!(
+            ObjectQuery::new().layers(2..=4).frames(10..),
+            ObjectQuery {
+                layer_start: 2,
+                layer_end: Some(5),
+                frame_start: 10,
+                frame_end: None,
+                effects: Vec::new(),
+            }
+        );
+        assert_eq!(
+            ObjectQuery::new().at_frame(3),
+            ObjectQuery::new().frames(3..4)
+        );
+    }
+}
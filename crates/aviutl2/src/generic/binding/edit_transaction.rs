@@ -0,0 +1,285 @@
+use crate::generic::{
+    CreateObjectError, EditSection, EditSectionResult, ObjectHandle, ObjectLayerFrame,
+};
+
+/// 削除したオブジェクトを復元するための情報。
+#[derive(Debug)]
+struct DeletedObject {
+    alias: String,
+    layer_frame: ObjectLayerFrame,
+}
+
+/// 編集セクション内での変更を記録し、確定しなかった場合に元に戻すガード。
+///
+/// [`EditSection::transaction`]で作成します。
+/// このガードを通して作成・削除したオブジェクトは、[`Self::commit`]を呼ばずにドロップされた場合、
+/// 次の順に元に戻されます。
+///
+/// 1. 作成したオブジェクトを新しいものから順に削除する
+/// 2. 削除したオブジェクトを、後に削除したものから順にエイリアスから作り直す
+///
+/// # Note
+///
+/// 作り直したオブジェクトは元のオブジェクトとは別のハンドルになります。
+/// また、ガードを通さずに行った変更は元に戻りません。
+///
+/// # Example
+///
+/// ```rust
+/// # fn f(section: &aviutl2::generic::EditSection, alias: &str) -> aviutl2::AnyResult<()> {
+/// let mut tx = section.transaction();
+/// tx.try_create_object_from_alias(alias, 0, 0, 30)?;
+/// tx.try_create_object_from_alias(alias, 0, 30, 30)?;
+/// // ここまでに失敗した場合、作成したオブジェクトは削除される
+/// tx.commit();
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "dropping the guard without calling commit rolls back all changes"]
+#[derive(Debug)]
+pub struct EditTransactionGuard<'a> {
+    section: &'a EditSection,
+    created: Vec<ObjectHandle>,
+    deleted: Vec<DeletedObject>,
+    committed: bool,
+}
+
+impl<'a> EditTransactionGuard<'a> {
+    pub(crate) fn new(section: &'a EditSection) -> Self {
+        Self {
+            section,
+            created: Vec::new(),
+            deleted: Vec::new(),
+            committed: false,
+        }
+    }
+
+    /// 既存のオブジェクトと重ならないか確認してから、オブジェクトエイリアスからオブジェクトを作成する。
+    ///
+    /// [`EditSection::try_create_object_from_alias`]と同じですが、作成したオブジェクトを記録します。
+    pub fn try_create_object_from_alias(
+        &mut self,
+        alias: &str,
+        layer: usize,
+        frame: usize,
+        length: usize,
+    ) -> Result<ObjectHandle, CreateObjectError> {
+        let object = self
+            .section
+            .try_create_object_from_alias(alias, layer, frame, length)?;
+        self.created.push(object);
+        Ok(object)
+    }
+
+    /// オブジェクトを削除する。
+    ///
+    /// 削除する前にエイリアスと位置を記録し、ロールバック時に作り直します。
+    pub fn delete_object(&mut self, object: ObjectHandle) -> EditSectionResult<()> {
+        let alias = self.section.get_object_alias(object)?;
+        let layer_frame = self.section.get_object_layer_frame(object)?;
+        self.section.delete_object(object)?;
+        if let Some(index) = self.created.iter().position(|&created| created == object) {
+            // このガードで作成したオブジェクトは、作り直す必要がない
+            self.created.remove(index);
+        } else {
+            self.deleted.push(DeletedObject { alias, layer_frame });
+        }
+        Ok(())
+    }
+
+    /// このガードを通して作成したオブジェクト。作成した順に並びます。
+    pub fn created_objects(&self) -> &[ObjectHandle] {
+        &self.created
+    }
+
+    /// 変更を確定する。
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+
+    fn rollback(&mut self) {
+        for object in self.created.drain(..).rev() {
+            if let Err(e) = self.section.delete_object(object) {
+                tracing::warn!("Failed to delete {object:?} during rollback: {e}");
+            }
+        }
+        for deleted in self.deleted.drain(..).rev() {
+            let ObjectLayerFrame { layer, start, end } = deleted.layer_frame;
+            if let Err(e) =
+                self.section
+                    .create_object_from_alias(&deleted.alias, layer, start, end + 1 - start)
+            {
+                tracing::warn!("Failed to restore object at layer {layer}, frame {start}: {e}");
+            }
+        }
+    }
+}
+
+impl std::ops::Deref for EditTransactionGuard<'_> {
+    type Target = EditSection;
+
+    fn deref(&self) -> &Self::Target {
+        self.section
+    }
+}
+
+impl Drop for EditTransactionGuard<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.rollback();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic::binding::test_section::{self, MockObject, handle, log};
+
+    /// レイヤー0のフレーム0〜9に`original`、フレーム100〜109に`next`がある編集セクションを作る。
+    fn with_mock_section<R>(f: impl FnOnce(&EditSection) -> R) -> R {
+        let objects = vec![
+            MockObject::new(0, 0, 9).with_alias("original"),
+            MockObject::new(0, 100, 109).with_alias("next"),
+        ];
+        test_section::with_mock_section(
+            objects,
+            |info| {
+                info.rate = 30;
+                info.scale = 1;
+            },
+            |ptr| f(&unsafe { EditSection::from_raw(ptr) }),
+        )
+    }
+
+    #[test]
+    fn test_overlap_is_checked_without_side_effects() {
+        with_mock_section(|section| {
+            let result = section.try_create_object_from_alias("sub", 0, 95, 10);
+            let Err(CreateObjectError::Overlap {
+                blocking_object,
+                available_until,
+            }) = result
+            else {
+                panic!("expected overlap, got {result:?}");
+            };
+            assert_eq!(blocking_object, handle(1));
+            assert_eq!(available_until, 100);
+            assert!(log().is_empty());
+
+            let created = section.try_create_object_from_alias("sub", 0, 90, 10);
+            assert_eq!(created.unwrap(), handle(2));
+        });
+    }
+
+    #[test]
+    fn test_rollback_deletes_newest_first_and_restores() {
+        with_mock_section(|section| {
+            let result: Result<(), CreateObjectError> = (|| {
+                let mut tx = section.transaction();
+                tx.delete_object(handle(0))?;
+                tx.try_create_object_from_alias("a", 0, 0, 30)?;
+                tx.try_create_object_from_alias("b", 0, 30, 30)?;
+                tx.try_create_object_from_alias("c", 0, 60, 60)?;
+                tx.commit();
+                Ok(())
+            })();
+            assert!(matches!(result, Err(CreateObjectError::Overlap { .. })));
+            assert_eq!(
+                log(),
+                [
+                    "delete original 0:0",
+                    "create a 0:0",
+                    "create b 0:30",
+                    "delete b 0:30",
+                    "delete a 0:0",
+                    "create original 0:0",
+                ]
+            );
+            let restored = section.find_object_after(0, 0).unwrap().unwrap();
+            assert_eq!(section.get_object_alias(restored).unwrap(), "original");
+            assert_eq!(section.get_object_layer_frame(restored).unwrap().end, 9);
+        });
+    }
+
+    #[test]
+    fn test_undo_group_commits_on_success() {
+        with_mock_section(|section| {
+            let created = section
+                .undo_group("replace", |tx| {
+                    tx.delete_object(handle(0))?;
+                    Ok(tx.try_create_object_from_alias("a", 0, 0, 30)?)
+                })
+                .unwrap();
+            assert_eq!(log(), ["delete original 0:0", "create a 0:0"]);
+            assert!(section.object_exists(created));
+        });
+    }
+
+    #[test]
+    fn test_undo_group_rolls_back_on_error() {
+        with_mock_section(|section| {
+            let result = section.undo_group("replace", |tx| {
+                tx.delete_object(handle(0))?;
+                tx.try_create_object_from_alias("a", 0, 0, 30)?;
+                anyhow::bail!("failed after editing");
+            });
+            assert_eq!(result.unwrap_err().to_string(), "failed after editing");
+            assert_eq!(
+                log(),
+                [
+                    "delete original 0:0",
+                    "create a 0:0",
+                    "delete a 0:0",
+                    "create original 0:0",
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn test_undo_group_rolls_back_on_panic() {
+        with_mock_section(|section| {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                section.undo_group("replace", |tx| -> crate::common::AnyResult<()> {
+                    tx.try_create_object_from_alias("a", 0, 20, 30)?;
+                    panic!("panicked after editing");
+                })
+            }));
+            assert!(result.is_err());
+            assert_eq!(log(), ["create a 0:20", "delete a 0:20"]);
+        });
+    }
+
+    #[test]
+    fn test_nested_undo_group_rolls_back_only_inner() {
+        with_mock_section(|section| {
+            section
+                .undo_group("outer", |tx| {
+                    tx.try_create_object_from_alias("a", 0, 20, 30)?;
+                    let inner = tx.undo_group("inner", |inner| {
+                        inner.try_create_object_from_alias("b", 0, 50, 10)?;
+                        inner.try_create_object_from_alias("c", 0, 95, 10)?;
+                        Ok(())
+                    });
+                    assert!(inner.is_err());
+                    Ok(())
+                })
+                .unwrap();
+            assert_eq!(log(), ["create a 0:20", "create b 0:50", "delete b 0:50"]);
+        });
+    }
+
+    #[test]
+    fn test_commit_keeps_changes() {
+        with_mock_section(|section| {
+            let mut tx = section.transaction();
+            tx.delete_object(handle(0)).unwrap();
+            let created = tx.try_create_object_from_alias("a", 0, 0, 30).unwrap();
+            assert_eq!(tx.created_objects(), [created]);
+            tx.commit();
+            assert_eq!(log(), ["delete original 0:0", "create a 0:0"]);
+            assert!(section.object_exists(created));
+        });
+    }
+}
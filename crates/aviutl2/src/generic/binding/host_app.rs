@@ -14,6 +14,9 @@ pub struct HostAppHandle<'a> {
     is_register_plugin_done: std::sync::Arc<std::sync::atomic::AtomicBool>,
     plugin_registry: &'a mut crate::generic::PluginRegistry,
     is_edit_handle_available: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    /// 登録順のヒントで並べ替えてから登録するため、`register()`が終わるまで保持しているフィルタプラグイン。
+    #[cfg(feature = "filter")]
+    pending_filter_plugins: Vec<(i32, *mut aviutl2_sys::filter2::FILTER_PLUGIN_TABLE)>,
 }
 
 /// プラグインの初期化状態を管理するためのハンドル。
@@ -44,6 +47,8 @@ impl<'plugin> HostAppHandle<'plugin> {
             is_register_plugin_done,
             plugin_registry,
             is_edit_handle_available,
+            #[cfg(feature = "filter")]
+            pending_filter_plugins: Vec::new(),
         }
     }
 
@@ -332,9 +337,35 @@ impl<'plugin> HostAppHandle<'plugin> {
                 hwnd.get() as *mut std::ffi::c_void,
             );
         }
+        self.plugin_registry.window_clients.insert(name, hwnd);
         Ok(())
     }
 
+    /// 登録したウィンドウクライアントを操作するためのハンドルを取得します。
+    ///
+    /// `name`には[`Self::register_window_client`]に渡した名前を指定します。
+    /// 登録されていない場合は`None`を返します。
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use aviutl2::common::raw_window_handle::HasWindowHandle;
+    /// # fn f(registry: &mut aviutl2::generic::HostAppHandle, window: &impl HasWindowHandle) {
+    /// registry.register_window_client("My Window", window).unwrap();
+    /// let client = registry.window_client("My Window").unwrap();
+    /// registry.register_edit_menu("My Window を表示", move || {
+    ///     client.toggle();
+    /// });
+    /// # }
+    /// ```
+    pub fn window_client(&self, name: &str) -> Option<crate::generic::WindowClientHandle> {
+        self.assert_not_killed();
+        self.plugin_registry
+            .window_clients
+            .get(name)
+            .map(crate::generic::WindowClientHandle::new)
+    }
+
     /// メニューを一括登録します。
     ///
     /// # See Also
@@ -669,6 +700,7 @@ pub(crate) struct PluginRegistry {
     filter_plugins: Vec<std::sync::Arc<InternalReferenceHandle>>,
     #[cfg(feature = "module")]
     script_modules: Vec<std::sync::Arc<InternalReferenceHandle>>,
+    window_clients: crate::generic::binding::window_client::WindowClientRegistry,
 }
 impl PluginRegistry {
     pub(crate) fn new() -> Self {
@@ -682,7 +714,6 @@ macro_rules! impl_plugin_registry {
         $feature:literal,
         $module:ident,
         $name:ident,
-        $register_method:ident,
         $PluginTrait:path,
         $SingletonTrait:path,
         $TableType:ty
@@ -696,16 +727,45 @@ macro_rules! impl_plugin_registry {
                     T: $PluginTrait + $SingletonTrait + 'static
                 {
                     crate::$module::__bridge::initialize_plugin::<T>(info.version.into())?;
-                    let internal = std::sync::Arc::new(InternalReferenceHandle {
+                    Ok(Self {
+                        plugin: std::marker::PhantomData,
+                        internal: Self::[<$name _reference_handle>](),
+                    })
+                }
+
+                #[cfg(feature = $feature)]
+                #[doc = concat!("作成済みのインスタンスから", $description, "を作成します。")]
+                ///
+                /// 汎用プラグインが持つキャッシュなどの状態を共有する場合に使います。
+                /// `new`は呼び出されません。
+                pub fn [<new_ $name _with>](
+                    info: &AviUtl2Info,
+                    instance: T,
+                ) -> crate::AnyResult<Self>
+                where
+                    T: $PluginTrait + $SingletonTrait + 'static
+                {
+                    crate::$module::__bridge::initialize_plugin_with::<T>(
+                        info.version.into(),
+                        instance,
+                    )?;
+                    Ok(Self {
+                        plugin: std::marker::PhantomData,
+                        internal: Self::[<$name _reference_handle>](),
+                    })
+                }
+
+                #[cfg(feature = $feature)]
+                fn [<$name _reference_handle>]() -> std::sync::Arc<InternalReferenceHandle>
+                where
+                    T: $PluginTrait + $SingletonTrait + 'static
+                {
+                    std::sync::Arc::new(InternalReferenceHandle {
                         uninitialize_fn: || {
                             unsafe {
                                 crate::$module::__bridge::uninitialize_plugin::<T>();
                             }
                         },
-                    });
-                    Ok(Self {
-                        plugin: std::marker::PhantomData,
-                        internal,
                     })
                 }
             }
@@ -717,7 +777,7 @@ macro_rules! impl_plugin_registry {
                     handle: &SubPlugin<T>,
                 ) {
                     self.assert_not_killed();
-                    unsafe { ((*self.internal).$register_method)(crate::$module::__bridge::create_table_unwind::<T>()) };
+                    self.[<submit_ $name>]::<T>(unsafe { crate::$module::__bridge::create_table_unwind::<T>() });
                     self.plugin_registry
                         .[<$name s>]
                         .push(std::sync::Arc::clone(&handle.internal));
@@ -728,7 +788,7 @@ macro_rules! impl_plugin_registry {
                     handle: &SubPlugin<T>,
                 ) {
                     self.assert_not_killed();
-                    unsafe { ((*self.internal).$register_method)(crate::$module::__bridge::create_table::<T>()) };
+                    self.[<submit_ $name>]::<T>(unsafe { crate::$module::__bridge::create_table::<T>() });
                     self.plugin_registry
                         .[<$name s>]
                         .push(std::sync::Arc::clone(&handle.internal));
@@ -743,7 +803,6 @@ impl_plugin_registry!(
     "input",
     input,
     input_plugin,
-    register_input_plugin,
     crate::input::InputPlugin,
     crate::input::__bridge::InputSingleton,
     aviutl2_sys::input2::INPUT_PLUGIN_TABLE
@@ -753,7 +812,6 @@ impl_plugin_registry!(
     "output",
     output,
     output_plugin,
-    register_output_plugin,
     crate::output::OutputPlugin,
     crate::output::__bridge::OutputSingleton,
     aviutl2_sys::output2::OUTPUT_PLUGIN_TABLE
@@ -763,11 +821,44 @@ impl_plugin_registry!(
     "filter",
     filter,
     filter_plugin,
-    register_filter_plugin,
     crate::filter::FilterPlugin,
     crate::filter::__bridge::FilterSingleton,
     aviutl2_sys::filter2::FILTER_PLUGIN_TABLE
 );
+#[cfg(feature = "input")]
+impl<'a> HostAppHandle<'a> {
+    fn submit_input_plugin<T>(&mut self, table: *mut aviutl2_sys::input2::INPUT_PLUGIN_TABLE) {
+        unsafe { ((*self.internal).register_input_plugin)(table) };
+    }
+}
+#[cfg(feature = "output")]
+impl<'a> HostAppHandle<'a> {
+    fn submit_output_plugin<T>(&mut self, table: *mut aviutl2_sys::output2::OUTPUT_PLUGIN_TABLE) {
+        unsafe { ((*self.internal).register_output_plugin)(table) };
+    }
+}
+#[cfg(feature = "filter")]
+impl<'a> HostAppHandle<'a> {
+    /// フィルタプラグインは`register()`が終わるまで登録せずに保持する。
+    fn submit_filter_plugin<T: crate::filter::__bridge::FilterSingleton>(
+        &mut self,
+        table: *mut aviutl2_sys::filter2::FILTER_PLUGIN_TABLE,
+    ) {
+        let order = crate::filter::__bridge::registration_order::<T>();
+        self.pending_filter_plugins.push((order, table));
+    }
+
+    /// 保持しているフィルタプラグインを、登録順のヒントが小さい順にホストへ登録する。
+    /// 登録順のヒントが同じ場合は、登録した順のままになる。
+    pub(crate) fn flush_filter_plugins(&mut self) {
+        let mut pending = std::mem::take(&mut self.pending_filter_plugins);
+        pending.sort_by_key(|(order, _)| *order);
+        for (_, table) in pending {
+            unsafe { ((*self.internal).register_filter_plugin)(table) };
+        }
+    }
+}
+
 #[cfg(feature = "module")]
 impl<T> SubPlugin<T> {
     /// スクリプトモジュールの新しいインスタンスを作成します。
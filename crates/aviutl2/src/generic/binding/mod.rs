@@ -37,6 +37,33 @@ pub trait GenericPlugin: Send + Sync + Sized {
         let _ = edit_section;
     }
 
+    /// 設定メニューが選択されたときに呼ばれる。
+    ///
+    /// `#[aviutl2::plugin(GenericPlugin, config)]`のように`config`を指定した場合のみ、
+    /// [`GenericPluginTable::name`]の名前で設定メニューが登録されます。
+    /// 設定メニューは[`Self::register`]より前に登録されるため、ウィンドウクライアントを登録した場合は
+    /// そのシステムメニューにも「設定」が追加されます。
+    ///
+    /// # Note
+    ///
+    /// 呼び出している間はプラグインのインスタンスが書き込みロックされるため、
+    /// ダイアログを表示している間はイベントなどの他のコールバックが待たされます。
+    fn config(&mut self, hwnd: crate::common::Win32WindowHandle) -> crate::common::AnyResult<()> {
+        let _ = hwnd;
+        anyhow::bail!("config is not implemented");
+    }
+
+    /// 設定の概要を返す。
+    ///
+    /// 空でない場合、「プラグイン情報」ダイアログで[`GenericPluginTable::information`]の後ろに表示されます。
+    ///
+    /// # Note
+    ///
+    /// プラグイン情報はAviUtl2の起動時に一度だけ取得されるため、起動後に設定を変更しても表示は更新されません。
+    fn settings_summary(&self) -> String {
+        String::new()
+    }
+
     // NOTE:
     // register_change_scene_handlerのコールバックはAviUtl2内でシーンを編集したときに呼ばれるが、これは同期的に呼ばれてしまう。
     // それにより、
@@ -54,6 +81,28 @@ pub trait GenericPlugin: Send + Sync + Sized {
     //     let _ = edit_section;
     // }
 
+    /// プロジェクトが変更されたときに呼ばれる。
+    ///
+    /// プロジェクトから作ったキャッシュを破棄するのに使えます。
+    /// 次のタイミングで呼ばれます：
+    /// - [`Self::on_project_load`]の直後（[`crate::generic::ProjectChangeKind::Loaded`]）
+    /// - [`Self::on_project_save`]の直後（[`crate::generic::ProjectChangeKind::Saved`]）
+    /// - [`Self::event_update_object_info`]の直後（[`crate::generic::ProjectChangeKind::ObjectUpdated`]）
+    /// - [`Self::event_change_scene_info`]の直後（[`crate::generic::ProjectChangeKind::SceneChanged`]）
+    ///
+    /// # Note
+    ///
+    /// オブジェクトとシーンの変更はイベント用スレッドから呼び出されます。
+    /// [`crate::generic::EditHandle::call_edit_section`]は利用できません。
+    fn on_project_changed(&mut self, change: crate::generic::ProjectChange) {
+        let _ = change;
+    }
+
+    // NOTE:
+    // プロジェクトを「変更あり」にする（保存を促す）関数はSDKに無いため、
+    // EditHandle::mark_project_dirtyのようなものは提供していない。
+    // 空のcall_edit_sectionで代用できるかはホストの実装次第なので、ここでは当てにしない。
+
     /// オブジェクト情報が更新されたときに呼ばれる。
     ///
     /// # Note
@@ -109,9 +158,23 @@ pub trait GenericPlugin: Send + Sync + Sized {
 
 mod project;
 pub use project::*;
+mod project_blob;
+pub use project_blob::*;
+mod project_change;
+pub use project_change::*;
 mod edit_section;
 pub use edit_section::*;
+mod edit_transaction;
+pub use edit_transaction::*;
 mod host_app;
 pub use host_app::*;
 mod edit_handle;
 pub use edit_handle::*;
+mod poller;
+pub use poller::*;
+mod transport;
+pub use transport::*;
+pub(crate) mod window_client;
+pub use window_client::WindowClientHandle;
+#[cfg(test)]
+mod test_section;
@@ -0,0 +1,236 @@
+use std::sync::{
+    Arc, Condvar, Mutex, OnceLock,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::{Duration, Instant};
+
+use crate::generic::{EditSection, GlobalEditHandle};
+
+/// [`GlobalEditHandle::spawn_poller`]で登録した定期処理のガード。
+///
+/// このガードがドロップされると、定期処理が停止します。
+#[derive(Debug)]
+#[must_use = "PollerGuardをドロップすると定期処理が停止します"]
+pub struct PollerGuard {
+    task: Arc<PollerTask>,
+}
+
+impl PollerGuard {
+    /// 定期処理を停止する。ドロップと同じです。
+    pub fn stop(self) {}
+}
+
+impl Drop for PollerGuard {
+    fn drop(&mut self) {
+        self.task.stopped.store(true, Ordering::Release);
+        scheduler().wake.notify_all();
+    }
+}
+
+struct PollerTask {
+    interval: Duration,
+    stopped: AtomicBool,
+    next_due: Mutex<Instant>,
+    tick: Mutex<Box<dyn FnMut() + Send>>,
+}
+
+impl std::fmt::Debug for PollerTask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PollerTask")
+            .field("interval", &self.interval)
+            .field("stopped", &self.stopped)
+            .finish()
+    }
+}
+
+/// すべての定期処理を1つのスレッドで実行するスケジューラ。
+struct PollerScheduler {
+    tasks: Mutex<Vec<Arc<PollerTask>>>,
+    wake: Condvar,
+}
+
+fn scheduler() -> &'static PollerScheduler {
+    static SCHEDULER: OnceLock<PollerScheduler> = OnceLock::new();
+    SCHEDULER.get_or_init(|| {
+        std::thread::Builder::new()
+            .name("aviutl2-rs poller".to_string())
+            .spawn(run_scheduler)
+            .expect("Failed to spawn poller thread");
+        PollerScheduler {
+            tasks: Mutex::new(Vec::new()),
+            wake: Condvar::new(),
+        }
+    })
+}
+
+fn run_scheduler() {
+    let scheduler = scheduler();
+    let mut tasks = scheduler.tasks.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        tasks.retain(|task| !task.stopped.load(Ordering::Acquire));
+        let now = Instant::now();
+        let mut due = Vec::new();
+        let mut next_wake = None::<Instant>;
+        for task in tasks.iter() {
+            let next_due = *task.next_due.lock().unwrap_or_else(|e| e.into_inner());
+            if next_due <= now {
+                due.push(Arc::clone(task));
+            } else {
+                next_wake = Some(next_wake.map_or(next_due, |wake| wake.min(next_due)));
+            }
+        }
+
+        if due.is_empty() {
+            tasks = match next_wake {
+                Some(wake) => {
+                    scheduler
+                        .wake
+                        .wait_timeout(tasks, wake.saturating_duration_since(now))
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => scheduler
+                    .wake
+                    .wait(tasks)
+                    .unwrap_or_else(|e| e.into_inner()),
+            };
+            continue;
+        }
+
+        // 処理中に新しい定期処理を登録できるように、ロックを外してから実行する
+        drop(tasks);
+        for task in due {
+            if task.stopped.load(Ordering::Acquire) {
+                continue;
+            }
+            (task.tick.lock().unwrap_or_else(|e| e.into_inner()))();
+            // 処理に時間がかかった場合も、溜まった分をまとめて実行しないように終了時刻から数える
+            *task.next_due.lock().unwrap_or_else(|e| e.into_inner()) =
+                Instant::now() + task.interval;
+        }
+        tasks = scheduler.tasks.lock().unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+/// 定期処理をスケジューラに登録する。
+pub(crate) fn spawn_timer_task(
+    interval: Duration,
+    tick: impl FnMut() + Send + 'static,
+) -> PollerGuard {
+    let task = Arc::new(PollerTask {
+        interval,
+        stopped: AtomicBool::new(false),
+        next_due: Mutex::new(Instant::now() + interval),
+        tick: Mutex::new(Box::new(tick)),
+    });
+    let scheduler = scheduler();
+    scheduler
+        .tasks
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Arc::clone(&task));
+    scheduler.wake.notify_all();
+    PollerGuard { task }
+}
+
+impl GlobalEditHandle {
+    /// 編集セクションを定期的に参照する。
+    ///
+    /// `interval`ごとに`f`を編集セクション内で呼び出し、`Some`が返された場合はその値で`sink`を呼び出します。
+    /// 定期処理はすべてのポーラーで共有されるスレッドで実行されます。
+    ///
+    /// - 他の編集セクションの呼び出しが実行中の場合、その回の処理はスキップされます。
+    /// - ハンドルが初期化されるまでの間、`f`は呼び出されません。
+    /// - 返された[`PollerGuard`]がドロップされると、定期処理は停止します。
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// static EDIT_HANDLE: aviutl2::generic::GlobalEditHandle =
+    ///     aviutl2::generic::GlobalEditHandle::new();
+    ///
+    /// let _guard = EDIT_HANDLE.spawn_poller(
+    ///     std::time::Duration::from_millis(100),
+    ///     |edit| Some(edit.info.frame),
+    ///     |frame| println!("frame: {frame}"),
+    /// );
+    /// ```
+    pub fn spawn_poller<T, F, S>(
+        &'static self,
+        interval: Duration,
+        mut f: F,
+        sink: S,
+    ) -> PollerGuard
+    where
+        T: Send + 'static,
+        F: Fn(&EditSection) -> Option<T> + Send + 'static,
+        S: Fn(T) + Send + 'static,
+    {
+        spawn_timer_task(interval, move || {
+            let Some(handle) = self.get() else {
+                return;
+            };
+            if !handle.is_ready() || super::edit_handle::is_section_call_in_progress() {
+                return;
+            }
+            let f = &mut f;
+            match handle.call_edit_section(move |edit| f(&*edit)) {
+                Ok(Some(value)) => sink(value),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to poll edit section: {}", e),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    const INTERVAL: Duration = Duration::from_millis(20);
+
+    #[test]
+    fn dropping_guard_stops_callbacks_within_one_interval() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let guard = spawn_timer_task(INTERVAL, {
+            let count = Arc::clone(&count);
+            move || {
+                count.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let started = Instant::now();
+        while count.load(Ordering::SeqCst) < 3 {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "poller did not run"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        drop(guard);
+        let stopped_at = count.load(Ordering::SeqCst);
+        std::thread::sleep(INTERVAL * 5);
+        // ドロップの時点で実行中だった1回分は許容する
+        assert!(count.load(Ordering::SeqCst) <= stopped_at + 1);
+    }
+
+    #[test]
+    fn uninitialized_handle_never_calls_f() {
+        static EDIT_HANDLE: GlobalEditHandle = GlobalEditHandle::new();
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        let _guard = EDIT_HANDLE.spawn_poller(
+            INTERVAL,
+            |_| {
+                CALLS.fetch_add(1, Ordering::SeqCst);
+                Some(())
+            },
+            |_| {
+                CALLS.fetch_add(1, Ordering::SeqCst);
+            },
+        );
+        std::thread::sleep(INTERVAL * 5);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 0);
+    }
+}
@@ -39,15 +39,16 @@ impl<'a> ProjectFile<'a> {
     /// - `key`にヌル文字が含まれている場合、失敗します。
     /// - 文字列が見つからなかった場合は失敗します。
     pub fn get_param_string(&self, key: &str) -> Result<String, ProjectFileError> {
-        let c_key = std::ffi::CString::new(key).map_err(ProjectFileError::KeyContainsNull)?;
+        let c_key =
+            crate::utils::encoding::to_host_utf8(key).map_err(ProjectFileError::KeyContainsNull)?;
         unsafe {
             let raw_str = ((*self.internal).get_param_string)(c_key.as_ptr() as _);
             if raw_str.is_null() {
                 return Err(ProjectFileError::RetrievalFailed(key.to_string()));
             }
-            Ok(std::ffi::CStr::from_ptr(raw_str)
-                .to_string_lossy()
-                .into_owned())
+            Ok(crate::utils::encoding::from_host_utf8(
+                std::ffi::CStr::from_ptr(raw_str),
+            ))
         }
     }
 
@@ -60,7 +61,8 @@ impl<'a> ProjectFile<'a> {
     /// - 指定されたキーに対応するデータが存在しない場合、失敗します。
     pub fn get_param_binary(&self, key: &str, data: &mut [u8]) -> Result<(), ProjectFileError> {
         let success = unsafe {
-            let key = std::ffi::CString::new(key).map_err(ProjectFileError::KeyContainsNull)?;
+            let key = crate::utils::encoding::to_host_utf8(key)
+                .map_err(ProjectFileError::KeyContainsNull)?;
             ((*self.internal).get_param_binary)(
                 key.as_ptr() as _,
                 data.as_mut_ptr() as _,
@@ -79,9 +81,10 @@ impl<'a> ProjectFile<'a> {
     ///
     /// key、valueにヌル文字が含まれている場合、失敗します。
     pub fn set_param_string(&mut self, key: &str, value: &str) -> Result<(), ProjectFileError> {
-        let key_cstr = std::ffi::CString::new(key).map_err(ProjectFileError::KeyContainsNull)?;
-        let value_cstr =
-            std::ffi::CString::new(value).map_err(ProjectFileError::ValueContainsNull)?;
+        let key_cstr =
+            crate::utils::encoding::to_host_utf8(key).map_err(ProjectFileError::KeyContainsNull)?;
+        let value_cstr = crate::utils::encoding::to_host_utf8(value)
+            .map_err(ProjectFileError::ValueContainsNull)?;
         unsafe {
             ((*self.internal).set_param_string)(key_cstr.as_ptr() as _, value_cstr.as_ptr() as _);
         }
@@ -99,7 +102,8 @@ impl<'a> ProjectFile<'a> {
             return Err(ProjectFileError::DataTooLarge(data.len()));
         }
         unsafe {
-            let key = std::ffi::CString::new(key).map_err(ProjectFileError::KeyContainsNull)?;
+            let key = crate::utils::encoding::to_host_utf8(key)
+                .map_err(ProjectFileError::KeyContainsNull)?;
             ((*self.internal).set_param_binary)(
                 key.as_ptr() as _,
                 data.as_ptr() as _,
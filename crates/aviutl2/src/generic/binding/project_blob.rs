@@ -0,0 +1,436 @@
+use super::{ProjectFile, ProjectFileError};
+
+/// [`ProjectFile::write_blob`]で保存できるデータの最大サイズ（16MiB）。
+///
+/// データは4096バイトごとに分割して保存されるため、大きなデータを保存すると
+/// プロジェクトファイルの読み込み・保存が遅くなります。
+/// 実用上は数百KB程度までにすることを推奨します。
+pub const PROJECT_BLOB_SIZE_LIMIT: usize = 16 * 1024 * 1024;
+
+static BLOB_NAMESPACE: &str = "--aviutl2-rs:blob";
+static BLOB_KEYS_KEY: &str = "--aviutl2-rs:blob-keys";
+const BLOB_CHUNK_SIZE: usize = 4096;
+
+/// [`ProjectFile::write_blob_with`]で使用する圧縮方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlobCompression {
+    /// 圧縮しない。
+    #[default]
+    None,
+    /// zstdで圧縮する。
+    ///
+    /// 圧縮後のサイズが元のサイズ以上になる場合は、圧縮せずに保存されます。
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+/// プロジェクトファイルへのバイナリデータの保存・取得に関するエラー。
+#[derive(thiserror::Error, Debug)]
+pub enum ProjectBlobError {
+    #[error("project file error: {0}")]
+    ProjectFile(#[from] ProjectFileError),
+    #[error("blob key contains newline")]
+    KeyContainsNewline,
+    #[error("blob length exceeds {PROJECT_BLOB_SIZE_LIMIT} bytes, got {0} bytes")]
+    DataTooLarge(usize),
+    #[error("invalid blob header: {0}")]
+    InvalidHeader(String),
+    #[error("incomplete blob data for key {0}")]
+    IncompleteData(String),
+    #[error("unsupported blob compression: {0}")]
+    UnsupportedCompression(String),
+    #[error("blob decompression error: {0}")]
+    Decompression(std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlobCodec {
+    Raw,
+    Zstd,
+}
+
+impl BlobCodec {
+    fn as_str(self) -> &'static str {
+        match self {
+            BlobCodec::Raw => "raw",
+            BlobCodec::Zstd => "zstd",
+        }
+    }
+}
+
+/// `v1:{圧縮方式}:{保存したサイズ}:{元のサイズ}`の形式のヘッダー。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BlobHeader {
+    codec: BlobCodec,
+    stored_len: usize,
+    raw_len: usize,
+}
+
+impl BlobHeader {
+    fn parse(header: &str) -> Result<Self, ProjectBlobError> {
+        let invalid = || ProjectBlobError::InvalidHeader(header.to_string());
+        let mut parts = header.split(':');
+        if parts.next() != Some("v1") {
+            return Err(invalid());
+        }
+        let codec = match parts.next().ok_or_else(invalid)? {
+            "raw" => BlobCodec::Raw,
+            "zstd" => BlobCodec::Zstd,
+            codec => return Err(ProjectBlobError::UnsupportedCompression(codec.to_string())),
+        };
+        let stored_len = parts
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(invalid)?;
+        let raw_len = parts
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(invalid)?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            codec,
+            stored_len,
+            raw_len,
+        })
+    }
+
+    fn chunk_count(&self) -> usize {
+        self.stored_len.div_ceil(BLOB_CHUNK_SIZE)
+    }
+}
+
+impl std::fmt::Display for BlobHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "v1:{}:{}:{}",
+            self.codec.as_str(),
+            self.stored_len,
+            self.raw_len
+        )
+    }
+}
+
+fn header_key(key: &str) -> String {
+    format!("{BLOB_NAMESPACE}:header:{key}")
+}
+
+fn chunk_key(key: &str, index: usize) -> String {
+    format!("{BLOB_NAMESPACE}:chunk:{key}:{index}")
+}
+
+impl<'a> ProjectFile<'a> {
+    /// プロジェクトにバイナリデータをそのまま保存します。
+    ///
+    /// [`ProjectFile::set_param_binary`]と異なり、4096バイトを超えるデータも保存できます。
+    /// データはJSONやbase64を経由せずに保存されます。
+    ///
+    /// # Errors
+    ///
+    /// - `key`にヌル文字や改行が含まれている場合、失敗します。
+    /// - `data`の長さが[`PROJECT_BLOB_SIZE_LIMIT`]を超える場合、失敗します。
+    pub fn write_blob(&mut self, key: &str, data: &[u8]) -> Result<(), ProjectBlobError> {
+        self.write_blob_with(key, data, BlobCompression::None)
+    }
+
+    /// 圧縮方式を指定して、プロジェクトにバイナリデータを保存します。
+    ///
+    /// # Errors
+    ///
+    /// - `key`にヌル文字や改行が含まれている場合、失敗します。
+    /// - `data`の長さが[`PROJECT_BLOB_SIZE_LIMIT`]を超える場合、失敗します。
+    pub fn write_blob_with(
+        &mut self,
+        key: &str,
+        data: &[u8],
+        compression: BlobCompression,
+    ) -> Result<(), ProjectBlobError> {
+        if key.contains('\n') {
+            return Err(ProjectBlobError::KeyContainsNewline);
+        }
+        if data.len() > PROJECT_BLOB_SIZE_LIMIT {
+            return Err(ProjectBlobError::DataTooLarge(data.len()));
+        }
+        let (codec, stored): (BlobCodec, std::borrow::Cow<'_, [u8]>) = match compression {
+            BlobCompression::None => (BlobCodec::Raw, data.into()),
+            #[cfg(feature = "zstd")]
+            BlobCompression::Zstd => {
+                let compressed = ruzstd::encoding::compress_to_vec(
+                    data,
+                    ruzstd::encoding::CompressionLevel::Fastest,
+                );
+                if compressed.len() < data.len() {
+                    (BlobCodec::Zstd, compressed.into())
+                } else {
+                    (BlobCodec::Raw, data.into())
+                }
+            }
+        };
+
+        let old_chunk_count = self
+            .read_blob_header(key)
+            .ok()
+            .flatten()
+            .map_or(0, |header| header.chunk_count());
+        let header = BlobHeader {
+            codec,
+            stored_len: stored.len(),
+            raw_len: data.len(),
+        };
+        for (i, chunk) in stored.chunks(BLOB_CHUNK_SIZE).enumerate() {
+            self.set_param_binary(&chunk_key(key, i), chunk)?;
+        }
+        for i in header.chunk_count()..old_chunk_count {
+            self.set_param_binary(&chunk_key(key, i), &[])?;
+        }
+        self.set_param_string(&header_key(key), &header.to_string())?;
+
+        let mut keys = self.blob_keys();
+        if !keys.iter().any(|k| k == key) {
+            keys.push(key.to_string());
+            self.set_param_string(BLOB_KEYS_KEY, &keys.join("\n"))?;
+        }
+        Ok(())
+    }
+
+    /// [`ProjectFile::write_blob`]で保存したバイナリデータを取得します。
+    ///
+    /// データが保存されていない場合は`Ok(None)`を返します。
+    ///
+    /// # Errors
+    ///
+    /// - `key`にヌル文字が含まれている場合、失敗します。
+    /// - 保存されているデータが壊れている場合、失敗します。
+    /// - `zstd`フィーチャーが無効な状態で、zstdで圧縮されたデータを読み込もうとした場合、失敗します。
+    pub fn read_blob(&self, key: &str) -> Result<Option<Vec<u8>>, ProjectBlobError> {
+        let Some(header) = self.read_blob_header(key)? else {
+            return Ok(None);
+        };
+        let mut stored = vec![0u8; header.stored_len];
+        for (i, chunk) in stored.chunks_mut(BLOB_CHUNK_SIZE).enumerate() {
+            self.get_param_binary(&chunk_key(key, i), chunk)
+                .map_err(|_| ProjectBlobError::IncompleteData(key.to_string()))?;
+        }
+        let data = match header.codec {
+            BlobCodec::Raw => stored,
+            #[cfg(feature = "zstd")]
+            BlobCodec::Zstd => {
+                use std::io::Read;
+                let mut decoder = ruzstd::decoding::StreamingDecoder::new(&stored[..])
+                    .map_err(|e| ProjectBlobError::Decompression(std::io::Error::other(e)))?;
+                let mut data = Vec::with_capacity(header.raw_len);
+                decoder
+                    .read_to_end(&mut data)
+                    .map_err(ProjectBlobError::Decompression)?;
+                data
+            }
+            #[cfg(not(feature = "zstd"))]
+            BlobCodec::Zstd => {
+                return Err(ProjectBlobError::UnsupportedCompression(
+                    BlobCodec::Zstd.as_str().to_string(),
+                ));
+            }
+        };
+        if data.len() != header.raw_len {
+            return Err(ProjectBlobError::IncompleteData(key.to_string()));
+        }
+        Ok(Some(data))
+    }
+
+    /// [`ProjectFile::write_blob`]で保存したバイナリデータを削除します。
+    ///
+    /// データが保存されていた場合は`true`を返します。
+    pub fn remove_blob(&mut self, key: &str) -> Result<bool, ProjectBlobError> {
+        let Some(header) = self.read_blob_header(key).ok().flatten() else {
+            return Ok(false);
+        };
+        for i in 0..header.chunk_count() {
+            self.set_param_binary(&chunk_key(key, i), &[])?;
+        }
+        self.set_param_string(&header_key(key), "")?;
+        let keys = self
+            .blob_keys()
+            .into_iter()
+            .filter(|k| k != key)
+            .collect::<Vec<_>>();
+        self.set_param_string(BLOB_KEYS_KEY, &keys.join("\n"))?;
+        Ok(true)
+    }
+
+    /// [`ProjectFile::write_blob`]で保存したバイナリデータのキーを列挙します。
+    ///
+    /// 保存時に使われなくなったデータを[`ProjectFile::remove_blob`]で削除するのに使えます。
+    pub fn blob_keys(&self) -> Vec<String> {
+        self.get_param_string(BLOB_KEYS_KEY)
+            .map(|keys| {
+                keys.split('\n')
+                    .filter(|key| !key.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn read_blob_header(&self, key: &str) -> Result<Option<BlobHeader>, ProjectBlobError> {
+        let header = match self.get_param_string(&header_key(key)) {
+            Ok(header) => header,
+            Err(ProjectFileError::RetrievalFailed(_)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if header.is_empty() {
+            return Ok(None);
+        }
+        BlobHeader::parse(&header).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aviutl2_sys::plugin2::{LPCSTR, PROJECT_FILE};
+    use std::{cell::RefCell, collections::HashMap, ffi::CString};
+
+    thread_local! {
+        static STRINGS: RefCell<HashMap<CString, CString>> = RefCell::new(HashMap::new());
+        static BINARIES: RefCell<HashMap<CString, Vec<u8>>> = RefCell::new(HashMap::new());
+    }
+
+    unsafe fn key_of(key: LPCSTR) -> CString {
+        unsafe { std::ffi::CStr::from_ptr(key) }.to_owned()
+    }
+
+    unsafe extern "C" fn get_param_string(key: LPCSTR) -> LPCSTR {
+        let key = unsafe { key_of(key) };
+        STRINGS.with_borrow(|strings| strings.get(&key).map_or(std::ptr::null(), |v| v.as_ptr()))
+    }
+
+    unsafe extern "C" fn set_param_string(key: LPCSTR, value: LPCSTR) {
+        let (key, value) = unsafe { (key_of(key), key_of(value)) };
+        STRINGS.with_borrow_mut(|strings| strings.insert(key, value));
+    }
+
+    unsafe extern "C" fn get_param_binary(
+        key: LPCSTR,
+        data: *mut std::ffi::c_void,
+        size: i32,
+    ) -> bool {
+        let key = unsafe { key_of(key) };
+        BINARIES.with_borrow(|binaries| match binaries.get(&key) {
+            Some(value) if value.len() == size as usize => {
+                unsafe {
+                    std::ptr::copy_nonoverlapping(value.as_ptr(), data.cast(), value.len());
+                }
+                true
+            }
+            _ => false,
+        })
+    }
+
+    unsafe extern "C" fn set_param_binary(key: LPCSTR, data: *mut std::ffi::c_void, size: i32) {
+        let key = unsafe { key_of(key) };
+        let value = unsafe { std::slice::from_raw_parts(data.cast::<u8>(), size as usize) };
+        BINARIES.with_borrow_mut(|binaries| binaries.insert(key, value.to_vec()));
+    }
+
+    unsafe extern "C" fn clear_params() {
+        STRINGS.with_borrow_mut(HashMap::clear);
+        BINARIES.with_borrow_mut(HashMap::clear);
+    }
+
+    unsafe extern "C" fn get_project_file_path() -> aviutl2_sys::common::LPCWSTR {
+        std::ptr::null()
+    }
+
+    fn with_mock_project(f: impl FnOnce(&mut ProjectFile)) {
+        let mut raw = PROJECT_FILE {
+            get_param_string,
+            set_param_string,
+            get_param_binary,
+            set_param_binary,
+            clear_params,
+            get_project_file_path,
+        };
+        let mut project = unsafe { ProjectFile::from_raw(&mut raw) };
+        project.clear_params();
+        f(&mut project);
+    }
+
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_blob_round_trip() {
+        with_mock_project(|project| {
+            for len in [0, 1, BLOB_CHUNK_SIZE, BLOB_CHUNK_SIZE * 3 + 17] {
+                let data = pattern(len);
+                project.write_blob("preview", &data).unwrap();
+                assert_eq!(project.read_blob("preview").unwrap(), Some(data));
+            }
+            assert_eq!(project.read_blob("missing").unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn test_blob_overwrite_clears_old_chunks() {
+        with_mock_project(|project| {
+            project
+                .write_blob("preview", &pattern(BLOB_CHUNK_SIZE * 4))
+                .unwrap();
+            project.write_blob("preview", &pattern(10)).unwrap();
+            assert_eq!(project.read_blob("preview").unwrap(), Some(pattern(10)));
+            BINARIES.with_borrow(|binaries| {
+                let stale = CString::new(chunk_key("preview", 3)).unwrap();
+                assert_eq!(binaries.get(&stale).map(Vec::len), Some(0));
+            });
+        });
+    }
+
+    #[test]
+    fn test_blob_keys_and_remove() {
+        with_mock_project(|project| {
+            project.write_blob("a", b"first").unwrap();
+            project.write_blob("b", b"second").unwrap();
+            project.write_blob("a", b"again").unwrap();
+            assert_eq!(project.blob_keys(), vec!["a", "b"]);
+
+            assert!(project.remove_blob("a").unwrap());
+            assert!(!project.remove_blob("a").unwrap());
+            assert_eq!(project.blob_keys(), vec!["b"]);
+            assert_eq!(project.read_blob("a").unwrap(), None);
+            assert_eq!(project.read_blob("b").unwrap(), Some(b"second".to_vec()));
+        });
+    }
+
+    #[test]
+    fn test_blob_rejects_invalid_input() {
+        with_mock_project(|project| {
+            assert!(matches!(
+                project.write_blob("a\nb", b"data"),
+                Err(ProjectBlobError::KeyContainsNewline)
+            ));
+            assert!(matches!(
+                project.write_blob("large", &vec![0; PROJECT_BLOB_SIZE_LIMIT + 1]),
+                Err(ProjectBlobError::DataTooLarge(_))
+            ));
+            assert!(project.blob_keys().is_empty());
+        });
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_blob_zstd_round_trip() {
+        with_mock_project(|project| {
+            let data = vec![42u8; BLOB_CHUNK_SIZE * 8];
+            project
+                .write_blob_with("compressed", &data, BlobCompression::Zstd)
+                .unwrap();
+            let header = project.read_blob_header("compressed").unwrap().unwrap();
+            assert_eq!(header.codec, BlobCodec::Zstd);
+            assert!(header.stored_len < data.len());
+            assert_eq!(project.read_blob("compressed").unwrap(), Some(data));
+        });
+    }
+}
@@ -0,0 +1,36 @@
+/// プロジェクトの変更の種類。
+///
+/// ホストから通知されるイベントの粒度に合わせた大まかな分類です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ProjectChangeKind {
+    /// プロジェクトが読み込まれた（新規作成を含む）。
+    Loaded,
+    /// プロジェクトが保存された。
+    Saved,
+    /// オブジェクトが追加・削除・編集された。Undo・Redoによる変更も含みます。
+    ObjectUpdated,
+    /// シーンを移動した、またはシーン情報が変更された。
+    SceneChanged,
+}
+
+/// プロジェクトの変更の通知。
+///
+/// [`crate::generic::GenericPlugin::on_project_changed`]に渡されます。
+///
+/// # Note
+///
+/// 現在のSDKのイベントには変更されたオブジェクトやレイヤーの情報が含まれないため、種類のみを持ちます。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ProjectChange {
+    /// 変更の種類。
+    pub kind: ProjectChangeKind,
+}
+
+impl ProjectChange {
+    /// 変更の通知を作成する。
+    pub fn new(kind: ProjectChangeKind) -> Self {
+        Self { kind }
+    }
+}
@@ -0,0 +1,187 @@
+//! テスト用の、オブジェクトの一覧だけを持つ編集セクションのモック。
+
+use crate::generic::ObjectHandle;
+use aviutl2_sys::plugin2::{EDIT_INFO, EDIT_SECTION, OBJECT_HANDLE, OBJECT_LAYER_FRAME};
+use std::cell::{Cell, RefCell};
+
+/// モックの編集セクションに置くオブジェクト。
+pub(crate) struct MockObject {
+    layer: usize,
+    start: usize,
+    end: usize,
+    alias: std::ffi::CString,
+    /// オブジェクトが持つエフェクトの名前。
+    effects: Vec<&'static str>,
+}
+
+impl MockObject {
+    pub(crate) fn new(layer: usize, start: usize, end: usize) -> Self {
+        Self {
+            layer,
+            start,
+            end,
+            alias: std::ffi::CString::default(),
+            effects: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_alias(mut self, alias: &str) -> Self {
+        self.alias = std::ffi::CString::new(alias).unwrap();
+        self
+    }
+
+    pub(crate) fn with_effect(mut self, effect: &'static str) -> Self {
+        self.effects.push(effect);
+        self
+    }
+}
+
+thread_local! {
+    /// ハンドルは`index + 1`。削除されたオブジェクトは`None`になる。
+    static OBJECTS: RefCell<Vec<Option<MockObject>>> = const { RefCell::new(Vec::new()) };
+    /// オブジェクトの作成・削除の記録。
+    static LOG: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static HOST_CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// `index`番目に置いたオブジェクトのハンドル。
+pub(crate) fn handle(index: usize) -> ObjectHandle {
+    ObjectHandle::from((index + 1) as OBJECT_HANDLE)
+}
+
+/// オブジェクトの作成・削除の記録を返す。
+pub(crate) fn log() -> Vec<String> {
+    LOG.with_borrow(|log| log.clone())
+}
+
+/// `f`の中でホストの関数が呼ばれた回数を数える。
+pub(crate) fn count_host_calls<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    HOST_CALLS.set(0);
+    let result = f();
+    (result, HOST_CALLS.get())
+}
+
+fn with_object<R>(object: OBJECT_HANDLE, f: impl FnOnce(Option<&MockObject>) -> R) -> R {
+    HOST_CALLS.set(HOST_CALLS.get() + 1);
+    OBJECTS.with_borrow(|objects| f(objects[object as usize - 1].as_ref()))
+}
+
+unsafe extern "C" fn find_object(layer: i32, frame: i32) -> OBJECT_HANDLE {
+    HOST_CALLS.set(HOST_CALLS.get() + 1);
+    OBJECTS.with_borrow(|objects| {
+        objects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, object)| Some((index, object.as_ref()?)))
+            .filter(|(_, object)| object.layer == layer as usize && object.end >= frame as usize)
+            .min_by_key(|(_, object)| object.start)
+            .map_or(std::ptr::null_mut(), |(index, _)| {
+                (index + 1) as OBJECT_HANDLE
+            })
+    })
+}
+
+unsafe extern "C" fn get_object_layer_frame(object: OBJECT_HANDLE) -> OBJECT_LAYER_FRAME {
+    with_object(object, |object| match object {
+        Some(object) => OBJECT_LAYER_FRAME {
+            layer: object.layer as i32,
+            start: object.start as i32,
+            end: object.end as i32,
+        },
+        None => OBJECT_LAYER_FRAME {
+            layer: -1,
+            start: -1,
+            end: -1,
+        },
+    })
+}
+
+unsafe extern "C" fn get_object_alias(object: OBJECT_HANDLE) -> *const std::ffi::c_char {
+    // `CString`の中身は`OBJECTS`が持っているので、借用を抜けてもポインタは有効
+    with_object(object, |object| {
+        object.map_or(std::ptr::null(), |object| object.alias.as_ptr())
+    })
+}
+
+unsafe extern "C" fn count_object_effect(object: OBJECT_HANDLE, effect: *const u16) -> i32 {
+    let effect = unsafe { crate::common::load_wide_string(effect) };
+    with_object(object, |object| {
+        object.map_or(0, |object| {
+            object
+                .effects
+                .iter()
+                .filter(|name| **name == effect)
+                .count() as i32
+        })
+    })
+}
+
+unsafe extern "C" fn create_object_from_alias(
+    alias: *const std::ffi::c_char,
+    layer: i32,
+    frame: i32,
+    length: i32,
+) -> OBJECT_HANDLE {
+    HOST_CALLS.set(HOST_CALLS.get() + 1);
+    let alias = unsafe { std::ffi::CStr::from_ptr(alias) }.to_owned();
+    LOG.with_borrow_mut(|log| {
+        log.push(format!(
+            "create {} {layer}:{frame}",
+            alias.to_string_lossy()
+        ))
+    });
+    OBJECTS.with_borrow_mut(|objects| {
+        objects.push(Some(MockObject {
+            alias,
+            ..MockObject::new(
+                layer as usize,
+                frame as usize,
+                (frame + length - 1) as usize,
+            )
+        }));
+        objects.len() as OBJECT_HANDLE
+    })
+}
+
+unsafe extern "C" fn delete_object(object: OBJECT_HANDLE) {
+    HOST_CALLS.set(HOST_CALLS.get() + 1);
+    OBJECTS.with_borrow_mut(|objects| {
+        let removed = objects[object as usize - 1].take().unwrap();
+        LOG.with_borrow_mut(|log| {
+            log.push(format!(
+                "delete {} {}:{}",
+                removed.alias.to_string_lossy(),
+                removed.layer,
+                removed.start
+            ))
+        });
+    });
+}
+
+/// `objects`を持つ編集セクションを作り、そのポインタを`f`に渡す。
+///
+/// オブジェクトの検索・位置・エイリアス・エフェクト数の取得と、エイリアスからの作成・削除だけを提供します。
+/// `info`で[`EDIT_INFO`]のシーンの情報を設定できます。
+pub(crate) fn with_mock_section<R>(
+    objects: Vec<MockObject>,
+    info: impl FnOnce(&mut EDIT_INFO),
+    f: impl FnOnce(*mut EDIT_SECTION) -> R,
+) -> R {
+    OBJECTS.with_borrow_mut(|slot| *slot = objects.into_iter().map(Some).collect());
+    LOG.with_borrow_mut(|log| log.clear());
+    let mut edit_info: EDIT_INFO = unsafe { std::mem::zeroed() };
+    info(&mut edit_info);
+    // 関数ポインタのフィールドはnullにできないので、提供しない関数は未初期化のまま触れない
+    let mut section = std::mem::MaybeUninit::<EDIT_SECTION>::zeroed();
+    let ptr = section.as_mut_ptr();
+    unsafe {
+        std::ptr::addr_of_mut!((*ptr).info).write(&mut edit_info);
+        std::ptr::addr_of_mut!((*ptr).find_object).write(find_object);
+        std::ptr::addr_of_mut!((*ptr).get_object_layer_frame).write(get_object_layer_frame);
+        std::ptr::addr_of_mut!((*ptr).get_object_alias).write(get_object_alias);
+        std::ptr::addr_of_mut!((*ptr).count_object_effect).write(count_object_effect);
+        std::ptr::addr_of_mut!((*ptr).create_object_from_alias).write(create_object_from_alias);
+        std::ptr::addr_of_mut!((*ptr).delete_object).write(delete_object);
+    }
+    f(ptr)
+}
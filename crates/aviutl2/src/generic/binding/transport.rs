@@ -0,0 +1,80 @@
+use crate::generic::{EditHandle, EditHandleError, GlobalEditHandle};
+
+/// タイムラインの再生位置を扱うためのトレイト。
+///
+/// [`EditHandle`]と[`GlobalEditHandle`]が実装しています。
+/// 再生状態に応じた処理をこのトレイト越しに書くと、ホストなしでテストできます。
+///
+/// # Note
+///
+/// 現在のSDKには再生・停止を行う関数が無いため、再生の開始・停止は提供していません。
+pub trait Transport {
+    /// プレビュー再生中かどうかを返す。
+    fn is_playing(&self) -> bool;
+
+    /// 現在のカーソルのフレーム番号（0始まり）を返す。
+    fn current_frame(&self) -> u32;
+
+    /// カーソルを指定したフレーム（0始まり）に移動する。
+    fn seek_frame(&self, frame: u32) -> Result<(), EditHandleError>;
+}
+
+impl Transport for EditHandle {
+    fn is_playing(&self) -> bool {
+        EditHandle::is_playing(self)
+    }
+
+    fn current_frame(&self) -> u32 {
+        EditHandle::current_frame(self)
+    }
+
+    fn seek_frame(&self, frame: u32) -> Result<(), EditHandleError> {
+        EditHandle::seek_frame(self, frame)
+    }
+}
+
+impl Transport for GlobalEditHandle {
+    fn is_playing(&self) -> bool {
+        match self.get() {
+            Some(handle) => handle.is_playing(),
+            None => {
+                tracing::warn!("is_playing was called before GlobalEditHandle is initialized");
+                false
+            }
+        }
+    }
+
+    fn current_frame(&self) -> u32 {
+        match self.get() {
+            Some(handle) => handle.current_frame(),
+            None => {
+                tracing::warn!("current_frame was called before GlobalEditHandle is initialized");
+                0
+            }
+        }
+    }
+
+    fn seek_frame(&self, frame: u32) -> Result<(), EditHandleError> {
+        match self.get() {
+            Some(handle) => handle.seek_frame(frame),
+            None => {
+                tracing::warn!("seek_frame was called before GlobalEditHandle is initialized");
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for &T {
+    fn is_playing(&self) -> bool {
+        (**self).is_playing()
+    }
+
+    fn current_frame(&self) -> u32 {
+        (**self).current_frame()
+    }
+
+    fn seek_frame(&self, frame: u32) -> Result<(), EditHandleError> {
+        (**self).seek_frame(frame)
+    }
+}
@@ -0,0 +1,256 @@
+use std::num::NonZeroIsize;
+
+/// ウィンドウの操作。テストでは差し替える。
+pub(crate) trait WindowBackend: Send + Sync {
+    fn is_visible(&self, hwnd: NonZeroIsize) -> bool;
+    fn set_visible(&self, hwnd: NonZeroIsize, visible: bool);
+    fn bring_to_front(&self, hwnd: NonZeroIsize);
+}
+
+/// Win32 APIでウィンドウを操作する。
+struct Win32Backend;
+
+impl Win32Backend {
+    fn hwnd(hwnd: NonZeroIsize) -> windows::Win32::Foundation::HWND {
+        windows::Win32::Foundation::HWND(hwnd.get() as *mut std::ffi::c_void)
+    }
+
+    /// 表示・非表示を切り替えるウィンドウを返す。
+    ///
+    /// ウィンドウクライアントはホストのコンテナウィンドウの子になる。
+    /// フローティング状態ではコンテナがオーナー付きのトップレベルウィンドウになるので、コンテナごと切り替える。
+    /// ドッキングされている場合は、メインウィンドウを隠さないようにクライアント自身を切り替える。
+    fn target(hwnd: NonZeroIsize) -> windows::Win32::Foundation::HWND {
+        use windows::Win32::UI::WindowsAndMessaging::{GA_ROOT, GW_OWNER, GetAncestor, GetWindow};
+        let hwnd = Self::hwnd(hwnd);
+        let root = unsafe { GetAncestor(hwnd, GA_ROOT) };
+        if !root.is_invalid() && root != hwnd && unsafe { GetWindow(root, GW_OWNER) }.is_ok() {
+            root
+        } else {
+            hwnd
+        }
+    }
+}
+
+impl WindowBackend for Win32Backend {
+    fn is_visible(&self, hwnd: NonZeroIsize) -> bool {
+        unsafe { windows::Win32::UI::WindowsAndMessaging::IsWindowVisible(Self::hwnd(hwnd)) }
+            .as_bool()
+    }
+
+    fn set_visible(&self, hwnd: NonZeroIsize, visible: bool) {
+        use windows::Win32::UI::WindowsAndMessaging::{SW_HIDE, SW_SHOW, ShowWindow};
+        let target = Self::target(hwnd);
+        unsafe {
+            if visible && target != Self::hwnd(hwnd) {
+                // コンテナだけを表示しても、クライアントが隠れていると中身が表示されない
+                let _ = ShowWindow(Self::hwnd(hwnd), SW_SHOW);
+            }
+            let _ = ShowWindow(target, if visible { SW_SHOW } else { SW_HIDE });
+        }
+    }
+
+    fn bring_to_front(&self, hwnd: NonZeroIsize) {
+        use windows::Win32::UI::WindowsAndMessaging::{
+            BringWindowToTop, GA_ROOT, GetAncestor, SetForegroundWindow,
+        };
+        let root = unsafe { GetAncestor(Self::hwnd(hwnd), GA_ROOT) };
+        unsafe {
+            if let Err(e) = BringWindowToTop(root) {
+                tracing::warn!("BringWindowToTop failed: {e}");
+            }
+            let _ = SetForegroundWindow(root);
+        }
+    }
+}
+
+static WIN32_BACKEND: Win32Backend = Win32Backend;
+
+/// 登録したウィンドウクライアントを操作するためのハンドル。
+///
+/// [`crate::generic::HostAppHandle::window_client`]で取得します。
+///
+/// # Note
+///
+/// SDKにはウィンドウクライアントの表示状態を操作する関数がないため、Win32 APIで直接操作します。
+/// フローティング状態のウィンドウはホストのコンテナウィンドウごと表示・非表示を切り替えますが、
+/// ドッキングされている場合はクライアントのウィンドウのみを切り替えるため、配置された領域は残ります。
+/// また、ホストの「表示」メニューのチェック状態とは連動しません。
+#[derive(Clone, Copy)]
+pub struct WindowClientHandle {
+    hwnd: NonZeroIsize,
+    backend: &'static dyn WindowBackend,
+}
+
+impl std::fmt::Debug for WindowClientHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WindowClientHandle")
+            .field("hwnd", &self.hwnd)
+            .finish()
+    }
+}
+
+impl WindowClientHandle {
+    pub(crate) fn new(hwnd: NonZeroIsize) -> Self {
+        Self::with_backend(hwnd, &WIN32_BACKEND)
+    }
+
+    pub(crate) fn with_backend(hwnd: NonZeroIsize, backend: &'static dyn WindowBackend) -> Self {
+        Self { hwnd, backend }
+    }
+
+    /// ウィンドウクライアントのウィンドウハンドル。
+    pub fn hwnd(&self) -> NonZeroIsize {
+        self.hwnd
+    }
+
+    /// ウィンドウを表示する。
+    pub fn show(&self) {
+        self.backend.set_visible(self.hwnd, true);
+    }
+
+    /// ウィンドウを非表示にする。
+    pub fn hide(&self) {
+        self.backend.set_visible(self.hwnd, false);
+    }
+
+    /// ウィンドウの表示・非表示を切り替える。
+    ///
+    /// 表示した場合は前面に移動します。
+    /// 切り替えた後に表示されている場合は`true`を返します。
+    pub fn toggle(&self) -> bool {
+        if self.is_visible() {
+            self.hide();
+            false
+        } else {
+            self.show();
+            self.bring_to_front();
+            true
+        }
+    }
+
+    /// ウィンドウが表示されているかどうか。
+    ///
+    /// コンテナウィンドウが非表示の場合も`false`を返します。
+    pub fn is_visible(&self) -> bool {
+        self.backend.is_visible(self.hwnd)
+    }
+
+    /// ウィンドウを含むトップレベルウィンドウを前面に移動する。
+    pub fn bring_to_front(&self) {
+        self.backend.bring_to_front(self.hwnd);
+    }
+}
+
+/// 登録したウィンドウクライアントの一覧。
+#[derive(Debug, Default)]
+pub(crate) struct WindowClientRegistry {
+    clients: Vec<(String, NonZeroIsize)>,
+}
+
+impl WindowClientRegistry {
+    pub(crate) fn insert(&mut self, name: &str, hwnd: NonZeroIsize) {
+        if let Some((_, existing)) = self.clients.iter_mut().find(|(n, _)| n == name) {
+            tracing::warn!("Window client {name:?} is registered twice, using the latest one");
+            *existing = hwnd;
+        } else {
+            self.clients.push((name.to_string(), hwnd));
+        }
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<NonZeroIsize> {
+        self.clients
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|&(_, hwnd)| hwnd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockBackend {
+        visible: Mutex<Vec<(NonZeroIsize, bool)>>,
+        log: Mutex<Vec<String>>,
+    }
+
+    impl MockBackend {
+        fn leak() -> &'static Self {
+            Box::leak(Box::default())
+        }
+
+        fn log(&self) -> Vec<String> {
+            self.log.lock().unwrap().clone()
+        }
+    }
+
+    impl WindowBackend for MockBackend {
+        fn is_visible(&self, hwnd: NonZeroIsize) -> bool {
+            self.visible
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|&(h, visible)| h == hwnd && visible)
+        }
+
+        fn set_visible(&self, hwnd: NonZeroIsize, visible: bool) {
+            let mut states = self.visible.lock().unwrap();
+            states.retain(|&(h, _)| h != hwnd);
+            states.push((hwnd, visible));
+            self.log.lock().unwrap().push(format!(
+                "{} {:x}",
+                if visible { "show" } else { "hide" },
+                hwnd
+            ));
+        }
+
+        fn bring_to_front(&self, hwnd: NonZeroIsize) {
+            self.log.lock().unwrap().push(format!("front {hwnd:x}"));
+        }
+    }
+
+    fn hwnd(value: isize) -> NonZeroIsize {
+        NonZeroIsize::new(value).unwrap()
+    }
+
+    #[test]
+    fn test_registry_lookup_by_name() {
+        let mut registry = WindowClientRegistry::default();
+        registry.insert("Search", hwnd(0x10));
+        registry.insert("Preview", hwnd(0x20));
+        assert_eq!(registry.get("Search"), Some(hwnd(0x10)));
+        assert_eq!(registry.get("Preview"), Some(hwnd(0x20)));
+        assert_eq!(registry.get("Missing"), None);
+
+        registry.insert("Search", hwnd(0x30));
+        assert_eq!(registry.get("Search"), Some(hwnd(0x30)));
+    }
+
+    #[test]
+    fn test_toggle_shows_and_brings_to_front() {
+        let backend = MockBackend::leak();
+        let handle = WindowClientHandle::with_backend(hwnd(0x10), backend);
+        assert!(!handle.is_visible());
+
+        assert!(handle.toggle());
+        assert!(handle.is_visible());
+        assert!(!handle.toggle());
+        assert!(!handle.is_visible());
+        assert_eq!(backend.log(), ["show 10", "front 10", "hide 10"]);
+    }
+
+    #[test]
+    fn test_handles_are_independent() {
+        let backend = MockBackend::leak();
+        let a = WindowClientHandle::with_backend(hwnd(0x10), backend);
+        let b = WindowClientHandle::with_backend(hwnd(0x20), backend);
+        a.show();
+        assert!(a.is_visible());
+        assert!(!b.is_visible());
+        b.bring_to_front();
+        assert_eq!(backend.log(), ["show 10", "front 20"]);
+    }
+}
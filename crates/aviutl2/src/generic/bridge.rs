@@ -1,7 +1,9 @@
+use std::num::NonZeroIsize;
+
 use crate::{
     common::{AnyResult, LeakManager},
     generic::{
-        GenericPlugin, ProjectFile,
+        GenericPlugin, ProjectChange, ProjectChangeKind, ProjectFile,
         binding::{HostAppHandle, PluginRegistry},
     },
 };
@@ -36,6 +38,10 @@ where
     fn __get_singleton_state() -> &'static std::sync::RwLock<
         Option<crate::generic::__bridge::InternalGenericPluginState<Self>>,
     >;
+    /// `#[aviutl2::plugin(GenericPlugin, config)]`の場合に`true`を返す。
+    fn __has_config() -> bool {
+        false
+    }
     fn with_instance<R>(f: impl FnOnce(&Self) -> R) -> R {
         let lock = Self::__get_singleton_state();
         let guard = lock.read().unwrap();
@@ -60,13 +66,19 @@ pub unsafe fn create_table<T: GenericSingleton>() -> *mut aviutl2_sys::plugin2::
         std::any::type_name::<T>()
     );
     let info = plugin_state.instance.plugin_info();
+    let summary = plugin_state.instance.settings_summary();
+    let information = if summary.is_empty() {
+        info.information
+    } else {
+        format!("{} / {}", info.information, summary)
+    };
     let table = Box::new(aviutl2_sys::plugin2::COMMON_PLUGIN_TABLE {
         name: plugin_state
             .global_leak_manager
             .leak_as_wide_string(&info.name),
         information: plugin_state
             .global_leak_manager
-            .leak_as_wide_string(&info.information),
+            .leak_as_wide_string(&information),
     });
     Box::into_raw(table)
 }
@@ -113,9 +125,7 @@ pub unsafe fn initialize_plugin_c_unwind<T: GenericSingleton>(version: u32) -> b
 pub(crate) fn initialize_plugin<T: GenericSingleton>(version: u32) -> AnyResult<()> {
     crate::common::ensure_minimum_aviutl2_version(version.into())?;
     let plugin_state = T::__get_singleton_state();
-    let info = crate::common::AviUtl2Info {
-        version: version.into(),
-    };
+    let info = crate::common::AviUtl2Info::new(version);
     let internal = T::new(info)?;
     let plugin = InternalGenericPluginState::new(internal);
     *plugin_state.write().unwrap() = Some(plugin);
@@ -139,6 +149,18 @@ fn register_plugin_impl<T: GenericSingleton>(
             plugin_state.is_edit_handle_ready.clone(),
         )
     };
+    // ウィンドウクライアントのシステムメニューに「設定」を出すため、register()より前に登録する
+    if T::__has_config() {
+        let name = plugin_state.instance.plugin_info().name;
+        handle.register_config_menu(
+            &name,
+            if unwind {
+                func_config_unwind::<T>
+            } else {
+                func_config::<T>
+            },
+        );
+    }
     if unwind {
         let result =
             crate::utils::catch_unwind_with_panic_info(std::panic::AssertUnwindSafe(|| {
@@ -155,25 +177,35 @@ fn register_plugin_impl<T: GenericSingleton>(
             ));
             return;
         }
+        #[cfg(feature = "filter")]
+        handle.flush_filter_plugins();
         handle.register_project_load_handler(on_project_load_impl::<T>);
         handle.register_project_save_handler(on_project_save_impl::<T>);
         handle.register_clear_cache_handler(on_clear_cache_impl::<T>);
         // handle.register_change_scene_handler(on_change_scene_impl::<T>);
     } else {
         T::register(&mut plugin_state.instance, &mut handle);
+        #[cfg(feature = "filter")]
+        handle.flush_filter_plugins();
         handle.register_project_load_handler(on_project_load_impl::<T>);
         handle.register_project_save_handler(on_project_save_impl::<T>);
         handle.register_clear_cache_handler(on_clear_cache_impl::<T>);
         // handle.register_change_scene_handler(on_change_scene_impl::<T>);
     }
     handle.register_event_listener(crate::generic::EventType::UpdateObject, || {
-        <T as GenericSingleton>::with_instance_mut(|instance| instance.event_update_object_info())
+        <T as GenericSingleton>::with_instance_mut(|instance| {
+            instance.event_update_object_info();
+            instance.on_project_changed(ProjectChange::new(ProjectChangeKind::ObjectUpdated));
+        })
     });
     handle.register_event_listener(crate::generic::EventType::ChangeEditFrame, || {
         <T as GenericSingleton>::with_instance_mut(|instance| instance.event_change_edit_frame())
     });
     handle.register_event_listener(crate::generic::EventType::ChangeEditScene, || {
-        <T as GenericSingleton>::with_instance_mut(|instance| instance.event_change_scene_info())
+        <T as GenericSingleton>::with_instance_mut(|instance| {
+            instance.event_change_scene_info();
+            instance.on_project_changed(ProjectChange::new(ProjectChangeKind::SceneChanged));
+        })
     });
     handle.register_event_listener(crate::generic::EventType::ChangeFocusObject, || {
         <T as GenericSingleton>::with_instance_mut(|instance| instance.event_change_focus_object())
@@ -181,41 +213,67 @@ fn register_plugin_impl<T: GenericSingleton>(
     plugin_state
         .register_plugin_done
         .store(true, std::sync::atomic::Ordering::SeqCst);
+}
 
-    fn on_project_load_impl<T: GenericSingleton>(project: &mut ProjectFile) {
-        {
-            // on_project_loadはプロジェクトの初期化時に呼ばれるので、RegisterPluginが終わった合図として使う。
-            let state = T::__get_singleton_state();
-            let guard = state.read().unwrap();
-            let plugin_state = guard.as_ref().expect("Plugin not initialized");
-            plugin_state
-                .is_edit_handle_ready
-                .store(true, std::sync::atomic::Ordering::SeqCst);
-        }
-        <T as GenericSingleton>::with_instance_mut(|instance| {
-            instance.on_project_load(project);
-        });
+fn on_project_load_impl<T: GenericSingleton>(project: &mut ProjectFile) {
+    {
+        // on_project_loadはプロジェクトの初期化時に呼ばれるので、RegisterPluginが終わった合図として使う。
+        let state = T::__get_singleton_state();
+        let guard = state.read().unwrap();
+        let plugin_state = guard.as_ref().expect("Plugin not initialized");
+        plugin_state
+            .is_edit_handle_ready
+            .store(true, std::sync::atomic::Ordering::SeqCst);
     }
+    <T as GenericSingleton>::with_instance_mut(|instance| {
+        instance.on_project_load(project);
+        instance.on_project_changed(ProjectChange::new(ProjectChangeKind::Loaded));
+    });
+}
 
-    fn on_project_save_impl<T: GenericSingleton>(project: &mut ProjectFile) {
-        <T as GenericSingleton>::with_instance_mut(|instance| {
-            instance.on_project_save(project);
-        });
-    }
+fn on_project_save_impl<T: GenericSingleton>(project: &mut ProjectFile) {
+    <T as GenericSingleton>::with_instance_mut(|instance| {
+        instance.on_project_save(project);
+        instance.on_project_changed(ProjectChange::new(ProjectChangeKind::Saved));
+    });
+}
 
-    fn on_clear_cache_impl<T: GenericSingleton>(edit_section: &crate::generic::EditSection) {
-        <T as GenericSingleton>::with_instance_mut(|instance| {
-            instance.on_clear_cache(edit_section);
-        });
-    }
+fn on_clear_cache_impl<T: GenericSingleton>(edit_section: &crate::generic::EditSection) {
+    <T as GenericSingleton>::with_instance_mut(|instance| {
+        instance.on_clear_cache(edit_section);
+    });
+}
+
+// ref: [GenericPlugin::on_change_scene]
+//
+// fn on_change_scene_impl<T: GenericSingleton>(edit_section: &crate::generic::EditSection) {
+//     <T as GenericSingleton>::with_instance_mut(|instance| {
+//         instance.on_change_scene(edit_section);
+//     });
+// }
 
-    // ref: [GenericPlugin::on_change_scene]
-    //
-    // fn on_change_scene_impl<T: GenericSingleton>(edit_section: &crate::generic::EditSection) {
-    //     <T as GenericSingleton>::with_instance_mut(|instance| {
-    //         instance.on_change_scene(edit_section);
-    //     });
-    // }
+extern "C" fn func_config<T: GenericSingleton>(
+    hwnd: aviutl2_sys::plugin2::HWND,
+    dll_hinst: aviutl2_sys::plugin2::HINSTANCE,
+) {
+    let mut handle =
+        raw_window_handle::Win32WindowHandle::new(NonZeroIsize::new(hwnd as isize).unwrap());
+    handle.hinstance = NonZeroIsize::new(dll_hinst as isize);
+    if let Err(e) = <T as GenericSingleton>::with_instance_mut(|instance| instance.config(handle)) {
+        tracing::error!("Error during func_config: {}", e);
+        let _ = crate::logger::write_error_log(&format!("{e}"));
+    }
+}
+extern "C" fn func_config_unwind<T: GenericSingleton>(
+    hwnd: aviutl2_sys::plugin2::HWND,
+    dll_hinst: aviutl2_sys::plugin2::HINSTANCE,
+) {
+    if let Err(panic_info) =
+        crate::utils::catch_unwind_with_panic_info(|| func_config::<T>(hwnd, dll_hinst))
+    {
+        tracing::error!("Panic occurred during func_config: {}", panic_info);
+        let _ = crate::logger::write_error_log(&panic_info);
+    }
 }
 
 pub unsafe fn register_plugin<T: GenericSingleton>(
@@ -360,3 +418,362 @@ macro_rules! register_generic_plugin {
         $crate::register_generic_plugin!($struct, );
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{GenericSingleton, InternalGenericPluginState};
+    use crate::generic::{
+        GenericPlugin, GenericPluginTable, HostAppHandle, ProjectChange, ProjectChangeKind,
+        ProjectFile,
+    };
+    use aviutl2_sys::plugin2::{
+        EDIT_SECTION, EVENT_TYPE, HINSTANCE, HOST_APP_TABLE, HWND, PROJECT_FILE,
+    };
+    use std::cell::RefCell;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type ConfigFn = unsafe extern "C" fn(HWND, HINSTANCE);
+    type EventListener = (
+        EVENT_TYPE,
+        *mut std::ffi::c_void,
+        unsafe extern "C" fn(*mut std::ffi::c_void),
+    );
+
+    thread_local! {
+        static CONFIG_MENUS: RefCell<Vec<(String, ConfigFn)>> = const { RefCell::new(Vec::new()) };
+        static EVENT_LISTENERS: RefCell<Vec<EventListener>> = const { RefCell::new(Vec::new()) };
+    }
+
+    macro_rules! test_plugin {
+        ($name:ident, $config_calls:ident, $has_config:expr, $summary:expr) => {
+            static $config_calls: AtomicUsize = AtomicUsize::new(0);
+
+            struct $name;
+
+            impl GenericPlugin for $name {
+                fn new(_info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self> {
+                    Ok(Self)
+                }
+
+                fn plugin_info(&self) -> GenericPluginTable {
+                    GenericPluginTable {
+                        name: stringify!($name).to_string(),
+                        information: "information".to_string(),
+                    }
+                }
+
+                fn register(&mut self, _registry: &mut HostAppHandle) {}
+
+                fn config(
+                    &mut self,
+                    _hwnd: crate::common::Win32WindowHandle,
+                ) -> crate::common::AnyResult<()> {
+                    $config_calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+
+                fn settings_summary(&self) -> String {
+                    $summary.to_string()
+                }
+            }
+
+            impl GenericSingleton for $name {
+                fn __get_singleton_state()
+                -> &'static std::sync::RwLock<Option<InternalGenericPluginState<Self>>> {
+                    static PLUGIN: std::sync::RwLock<Option<InternalGenericPluginState<$name>>> =
+                        std::sync::RwLock::new(None);
+                    &PLUGIN
+                }
+
+                fn __has_config() -> bool {
+                    $has_config
+                }
+            }
+        };
+    }
+
+    test_plugin!(ConfigPlugin, CONFIG_PLUGIN_CALLS, true, "");
+    test_plugin!(NoConfigPlugin, NO_CONFIG_PLUGIN_CALLS, false, "");
+    test_plugin!(SummaryPlugin, SUMMARY_PLUGIN_CALLS, false, "mode: fast");
+    test_plugin!(PlainPlugin, PLAIN_PLUGIN_CALLS, false, "");
+
+    unsafe extern "C" fn register_project_handler(_: unsafe extern "C" fn(*mut PROJECT_FILE)) {}
+    unsafe extern "C" fn register_edit_section_handler(_: unsafe extern "C" fn(*mut EDIT_SECTION)) {
+    }
+    unsafe extern "C" fn register_event_listener(
+        event_type: EVENT_TYPE,
+        param: *mut std::ffi::c_void,
+        func: unsafe extern "C" fn(*mut std::ffi::c_void),
+    ) {
+        EVENT_LISTENERS.with(|listeners| listeners.borrow_mut().push((event_type, param, func)));
+    }
+
+    /// 登録されたイベントリスナーを呼び出す。
+    fn fire_event(event_type: EVENT_TYPE) {
+        let listeners = EVENT_LISTENERS.with(|listeners| listeners.borrow().clone());
+        for (registered_type, param, func) in listeners {
+            if registered_type == event_type {
+                unsafe { func(param) };
+            }
+        }
+    }
+
+    static PROJECT_CHANGES: std::sync::Mutex<Vec<ProjectChangeKind>> =
+        std::sync::Mutex::new(Vec::new());
+
+    struct ChangePlugin;
+
+    impl GenericPlugin for ChangePlugin {
+        fn new(_info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self> {
+            Ok(Self)
+        }
+
+        fn plugin_info(&self) -> GenericPluginTable {
+            GenericPluginTable {
+                name: "ChangePlugin".to_string(),
+                information: "information".to_string(),
+            }
+        }
+
+        fn register(&mut self, _registry: &mut HostAppHandle) {}
+
+        fn on_project_changed(&mut self, change: ProjectChange) {
+            PROJECT_CHANGES.lock().unwrap().push(change.kind);
+        }
+    }
+
+    impl GenericSingleton for ChangePlugin {
+        fn __get_singleton_state()
+        -> &'static std::sync::RwLock<Option<InternalGenericPluginState<Self>>> {
+            static PLUGIN: std::sync::RwLock<Option<InternalGenericPluginState<ChangePlugin>>> =
+                std::sync::RwLock::new(None);
+            &PLUGIN
+        }
+    }
+    unsafe extern "C" fn register_config_menu(name: *const u16, func_config: ConfigFn) {
+        let name = unsafe { crate::common::load_wide_string(name) };
+        CONFIG_MENUS.with(|menus| menus.borrow_mut().push((name, func_config)));
+    }
+
+    #[cfg(feature = "filter")]
+    thread_local! {
+        static FILTER_PLUGINS: RefCell<Vec<(String, Option<String>)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    #[cfg(feature = "filter")]
+    unsafe extern "C" fn register_filter_plugin(
+        table: *mut aviutl2_sys::filter2::FILTER_PLUGIN_TABLE,
+    ) {
+        let table = unsafe { &*table };
+        let name = unsafe { crate::common::load_wide_string(table.name) };
+        let label = (!table.label.is_null())
+            .then(|| unsafe { crate::common::load_wide_string(table.label) });
+        FILTER_PLUGINS.with(|plugins| plugins.borrow_mut().push((name, label)));
+    }
+
+    #[cfg(feature = "filter")]
+    macro_rules! order_filter {
+        ($name:ident, $order:expr, $default_label:expr, $default_order:expr) => {
+            struct $name;
+
+            impl crate::filter::FilterPlugin for $name {
+                fn new(_info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self> {
+                    Ok(Self)
+                }
+
+                fn plugin_info(&self) -> crate::filter::FilterPluginTable {
+                    crate::filter::FilterPluginTable {
+                        name: stringify!($name).to_string(),
+                        label: None,
+                        order: $order,
+                        information: String::new(),
+                        flags: crate::bitflag!(crate::filter::FilterPluginFlags { video: true }),
+                        config_items: vec![],
+                    }
+                }
+            }
+
+            impl crate::filter::__bridge::FilterSingleton for $name {
+                fn __get_singleton_state() -> &'static std::sync::RwLock<
+                    Option<crate::filter::__bridge::InternalFilterPluginState<Self>>,
+                > {
+                    static PLUGIN: std::sync::RwLock<
+                        Option<crate::filter::__bridge::InternalFilterPluginState<$name>>,
+                    > = std::sync::RwLock::new(None);
+                    &PLUGIN
+                }
+
+                fn __default_label() -> Option<&'static str> {
+                    $default_label
+                }
+
+                fn __default_order() -> i32 {
+                    $default_order
+                }
+            }
+        };
+    }
+
+    #[cfg(feature = "filter")]
+    order_filter!(LateFilter, 10, None, 0);
+    // ラベルと登録順は属性マクロの既定値で埋められる
+    #[cfg(feature = "filter")]
+    order_filter!(EarlyFilter, 0, Some("Rustyツール"), -5);
+    // plugin_info()で指定した登録順が優先される
+    #[cfg(feature = "filter")]
+    order_filter!(MiddleFilter, 3, None, 100);
+    #[cfg(feature = "filter")]
+    order_filter!(TieFilter, 3, None, 0);
+
+    #[cfg(feature = "filter")]
+    struct FilterOrderPlugin {
+        late: crate::generic::SubPlugin<LateFilter>,
+        tie: crate::generic::SubPlugin<TieFilter>,
+        early: crate::generic::SubPlugin<EarlyFilter>,
+        middle: crate::generic::SubPlugin<MiddleFilter>,
+    }
+
+    #[cfg(feature = "filter")]
+    impl GenericPlugin for FilterOrderPlugin {
+        fn new(info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self> {
+            Ok(Self {
+                late: crate::generic::SubPlugin::new_filter_plugin(&info)?,
+                tie: crate::generic::SubPlugin::new_filter_plugin(&info)?,
+                early: crate::generic::SubPlugin::new_filter_plugin(&info)?,
+                middle: crate::generic::SubPlugin::new_filter_plugin(&info)?,
+            })
+        }
+
+        fn plugin_info(&self) -> GenericPluginTable {
+            GenericPluginTable {
+                name: "FilterOrderPlugin".to_string(),
+                information: "information".to_string(),
+            }
+        }
+
+        fn register(&mut self, registry: &mut HostAppHandle) {
+            registry.register_filter_plugin_nounwind(&self.late);
+            registry.register_filter_plugin_nounwind(&self.tie);
+            registry.register_filter_plugin_nounwind(&self.early);
+            registry.register_filter_plugin_nounwind(&self.middle);
+        }
+    }
+
+    #[cfg(feature = "filter")]
+    impl GenericSingleton for FilterOrderPlugin {
+        fn __get_singleton_state()
+        -> &'static std::sync::RwLock<Option<InternalGenericPluginState<Self>>> {
+            static PLUGIN: std::sync::RwLock<
+                Option<InternalGenericPluginState<FilterOrderPlugin>>,
+            > = std::sync::RwLock::new(None);
+            &PLUGIN
+        }
+    }
+
+    /// プラグインを初期化して登録し、登録された設定メニューを返す。
+    fn register<T: GenericSingleton>() -> Vec<(String, ConfigFn)> {
+        super::initialize_plugin::<T>(crate::common::MINIMUM_AVIUTL2_VERSION.into()).unwrap();
+        CONFIG_MENUS.with(|menus| menus.borrow_mut().clear());
+        EVENT_LISTENERS.with(|listeners| listeners.borrow_mut().clear());
+        // `register_plugin_impl`が呼ぶ登録関数だけを用意する
+        let mut host = std::mem::MaybeUninit::<HOST_APP_TABLE>::zeroed();
+        let ptr = host.as_mut_ptr();
+        unsafe {
+            std::ptr::addr_of_mut!((*ptr).register_project_load_handler)
+                .write(register_project_handler);
+            std::ptr::addr_of_mut!((*ptr).register_project_save_handler)
+                .write(register_project_handler);
+            std::ptr::addr_of_mut!((*ptr).register_clear_cache_handler)
+                .write(register_edit_section_handler);
+            std::ptr::addr_of_mut!((*ptr).register_event_listener).write(register_event_listener);
+            std::ptr::addr_of_mut!((*ptr).register_config_menu).write(register_config_menu);
+            #[cfg(feature = "filter")]
+            std::ptr::addr_of_mut!((*ptr).register_filter_plugin).write(register_filter_plugin);
+        }
+        super::register_plugin_impl::<T>(ptr, false);
+        CONFIG_MENUS.with(|menus| menus.borrow().clone())
+    }
+
+    #[test]
+    fn test_config_menu_registered_when_config_is_enabled() {
+        let menus = register::<ConfigPlugin>();
+        assert_eq!(menus.len(), 1);
+        assert_eq!(menus[0].0, "ConfigPlugin");
+
+        unsafe { (menus[0].1)(1 as HWND, 2 as HINSTANCE) };
+        assert_eq!(CONFIG_PLUGIN_CALLS.load(Ordering::SeqCst), 1);
+        unsafe { super::uninitialize_plugin::<ConfigPlugin>() };
+    }
+
+    #[test]
+    fn test_config_menu_not_registered_by_default() {
+        let menus = register::<NoConfigPlugin>();
+        assert!(menus.is_empty());
+        assert_eq!(NO_CONFIG_PLUGIN_CALLS.load(Ordering::SeqCst), 0);
+        unsafe { super::uninitialize_plugin::<NoConfigPlugin>() };
+    }
+
+    #[test]
+    fn test_settings_summary_is_appended_to_information() {
+        super::initialize_plugin::<SummaryPlugin>(crate::common::MINIMUM_AVIUTL2_VERSION.into())
+            .unwrap();
+        let table = unsafe { Box::from_raw(super::create_table::<SummaryPlugin>()) };
+        let information = unsafe { crate::common::load_wide_string(table.information) };
+        assert_eq!(information, "information / mode: fast");
+        assert_eq!(SUMMARY_PLUGIN_CALLS.load(Ordering::SeqCst), 0);
+        unsafe { super::uninitialize_plugin::<SummaryPlugin>() };
+
+        super::initialize_plugin::<PlainPlugin>(crate::common::MINIMUM_AVIUTL2_VERSION.into())
+            .unwrap();
+        let table = unsafe { Box::from_raw(super::create_table::<PlainPlugin>()) };
+        let information = unsafe { crate::common::load_wide_string(table.information) };
+        assert_eq!(information, "information");
+        unsafe { super::uninitialize_plugin::<PlainPlugin>() };
+    }
+
+    #[cfg(feature = "filter")]
+    #[test]
+    fn test_filter_plugins_are_registered_in_order() {
+        FILTER_PLUGINS.with(|plugins| plugins.borrow_mut().clear());
+        register::<FilterOrderPlugin>();
+        let plugins = FILTER_PLUGINS.with(|plugins| plugins.borrow().clone());
+        assert_eq!(
+            plugins,
+            vec![
+                ("EarlyFilter".to_string(), Some("Rustyツール".to_string())),
+                ("TieFilter".to_string(), None),
+                ("MiddleFilter".to_string(), None),
+                ("LateFilter".to_string(), None),
+            ]
+        );
+        unsafe { super::uninitialize_plugin::<FilterOrderPlugin>() };
+    }
+
+    #[test]
+    fn test_project_changes_are_dispatched() {
+        register::<ChangePlugin>();
+        for event_type in [
+            EVENT_TYPE::UPDATE_OBJECT,
+            EVENT_TYPE::CHANGE_EDIT_FRAME,
+            EVENT_TYPE::CHANGE_EDIT_SCENE,
+            EVENT_TYPE::CHANGE_FOCUS_OBJECT,
+        ] {
+            fire_event(event_type);
+        }
+        // プロジェクトファイルには触れないので、nullでも問題ない
+        let mut project = unsafe { ProjectFile::from_raw(std::ptr::null_mut()) };
+        super::on_project_load_impl::<ChangePlugin>(&mut project);
+        super::on_project_save_impl::<ChangePlugin>(&mut project);
+
+        assert_eq!(
+            *PROJECT_CHANGES.lock().unwrap(),
+            vec![
+                ProjectChangeKind::ObjectUpdated,
+                ProjectChangeKind::SceneChanged,
+                ProjectChangeKind::Loaded,
+                ProjectChangeKind::Saved,
+            ]
+        );
+        unsafe { super::uninitialize_plugin::<ChangePlugin>() };
+    }
+}
@@ -0,0 +1,390 @@
+//! クリップボードでAviUtl2とオブジェクトのエイリアスをやり取りするためのモジュール。
+//!
+//! [`set_object_alias`]でエイリアスをクリップボードに置くと、ユーザーはCtrl+Vでタイムラインに貼り付けられます。
+//!
+//! AviUtl2がオブジェクトのコピー・貼り付けに使うクリップボード形式の名前は公開されていないため、
+//! クリップボードに置かれている登録済みの形式から、名前に`AviUtl`を含むものを探して使います。
+//! 形式は[`set_object_alias`]・[`get_object_alias`]・[`object_alias_format_name`]を呼んだときに探され、
+//! 一度見つかった形式はプロセス内で記憶されます。
+//! 形式が見つからない場合は、プレーンテキスト（`CF_UNICODETEXT`）として読み書きします。
+//!
+//! # Note
+//!
+//! AviUtl2の形式が見つかる前に[`set_object_alias`]を呼んだ場合は、プレーンテキストのみが書き込まれます。
+//! AviUtl2でオブジェクトを一度コピーしてから呼び出してください。
+
+use std::sync::Mutex;
+use std::time::Duration;
+use windows::Win32::{
+    Foundation::{HANDLE, HGLOBAL},
+    System::{
+        DataExchange::{
+            CloseClipboard, EmptyClipboard, EnumClipboardFormats, GetClipboardData,
+            GetClipboardFormatNameW, IsClipboardFormatAvailable, OpenClipboard, SetClipboardData,
+        },
+        Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock},
+    },
+};
+
+/// `CF_UNICODETEXT`。
+const CF_UNICODETEXT: u32 = 13;
+/// `RegisterClipboardFormatW`で登録された形式の最小値。
+const FIRST_REGISTERED_FORMAT: u32 = 0xC000;
+/// 他のアプリケーションがクリップボードを開いている場合に、開き直す回数。
+const OPEN_RETRIES: usize = 10;
+const OPEN_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// 見つかったAviUtl2のクリップボード形式。
+static OBJECT_ALIAS_FORMAT: Mutex<Option<ClipboardFormat>> = Mutex::new(None);
+
+/// クリップボード関連のエラー。
+#[derive(thiserror::Error, Debug)]
+pub enum ClipboardError {
+    #[error("failed to open clipboard: {0}")]
+    OpenFailed(windows::core::Error),
+    #[error("api call failed: {0}")]
+    ApiCallFailed(#[from] windows::core::Error),
+    #[error("input utf-16 string contains null byte")]
+    InputCwstrContainsNull(#[from] crate::common::NullByteError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ClipboardFormat {
+    id: u32,
+    name: String,
+}
+
+/// エイリアスをクリップボードに置く。
+///
+/// AviUtl2のクリップボード形式が見つかっている場合はその形式とプレーンテキストの両方に、
+/// 見つかっていない場合はプレーンテキストのみに書き込みます。
+pub fn set_object_alias(alias: &str) -> Result<(), ClipboardError> {
+    let text = crate::common::CWString::new(alias)?;
+    let clipboard = Clipboard::open()?;
+    // 消す前に、今のクリップボードの内容からAviUtl2の形式を探しておく
+    let format = clipboard
+        .find_object_alias_format()
+        .or_else(|| OBJECT_ALIAS_FORMAT.lock().unwrap().clone());
+    clipboard.empty()?;
+    if let Some(format) = format {
+        let mut data = alias.as_bytes().to_vec();
+        data.push(0);
+        clipboard.set_data(format.id, &data)?;
+    }
+    clipboard.set_data(CF_UNICODETEXT, &wide_to_bytes(text.as_slice_with_nul()))?;
+    Ok(())
+}
+
+/// クリップボードからエイリアスを取得する。
+///
+/// AviUtl2のクリップボード形式のデータがあればそれを、なければエイリアスとして読めるプレーンテキストを返します。
+/// エイリアスがない場合や、クリップボードを開けなかった場合は`None`を返します。
+pub fn get_object_alias() -> Option<String> {
+    read_object_alias().unwrap_or_else(|e| {
+        tracing::warn!("Failed to read object alias from clipboard: {e}");
+        None
+    })
+}
+
+fn read_object_alias() -> Result<Option<String>, ClipboardError> {
+    let clipboard = Clipboard::open()?;
+    if let Some(format) = clipboard.find_object_alias_format()
+        && let Some(data) = clipboard.get_data(format.id)?
+    {
+        return Ok(Some(decode_text(&data)));
+    }
+    Ok(clipboard
+        .get_text()?
+        .filter(|text| looks_like_object_alias(text)))
+}
+
+/// 見つかっているAviUtl2のクリップボード形式の名前を返す。
+///
+/// まだ見つかっていない場合は、現在のクリップボードの内容から探します。
+pub fn object_alias_format_name() -> Option<String> {
+    if let Some(format) = OBJECT_ALIAS_FORMAT.lock().unwrap().as_ref() {
+        return Some(format.name.clone());
+    }
+    let clipboard = Clipboard::open().ok()?;
+    clipboard
+        .find_object_alias_format()
+        .map(|format| format.name)
+}
+
+/// クリップボードにプレーンテキストを置く。
+pub fn set_text(text: &str) -> Result<(), ClipboardError> {
+    let text = crate::common::CWString::new(text)?;
+    let clipboard = Clipboard::open()?;
+    clipboard.empty()?;
+    clipboard.set_data(CF_UNICODETEXT, &wide_to_bytes(text.as_slice_with_nul()))?;
+    Ok(())
+}
+
+/// クリップボードからプレーンテキストを取得する。
+///
+/// テキストがない場合や、クリップボードを開けなかった場合は`None`を返します。
+pub fn get_text() -> Option<String> {
+    Clipboard::open()
+        .and_then(|clipboard| clipboard.get_text())
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to read text from clipboard: {e}");
+            None
+        })
+}
+
+/// 開いているクリップボード。ドロップすると閉じます。
+struct Clipboard;
+
+impl Clipboard {
+    fn open() -> Result<Self, ClipboardError> {
+        let mut last_error = None;
+        for _ in 0..OPEN_RETRIES {
+            match unsafe { OpenClipboard(None) } {
+                Ok(()) => return Ok(Self),
+                Err(e) => {
+                    last_error = Some(e);
+                    std::thread::sleep(OPEN_RETRY_INTERVAL);
+                }
+            }
+        }
+        Err(ClipboardError::OpenFailed(
+            last_error.expect("OPEN_RETRIES should be greater than 0"),
+        ))
+    }
+
+    fn empty(&self) -> Result<(), ClipboardError> {
+        unsafe { EmptyClipboard() }?;
+        Ok(())
+    }
+
+    fn formats(&self) -> Vec<u32> {
+        let mut formats = Vec::new();
+        let mut format = 0;
+        loop {
+            format = unsafe { EnumClipboardFormats(format) };
+            if format == 0 {
+                break;
+            }
+            formats.push(format);
+        }
+        formats
+    }
+
+    /// クリップボードにあるAviUtl2の形式を探し、見つかった場合は記憶する。
+    fn find_object_alias_format(&self) -> Option<ClipboardFormat> {
+        let mut cached = OBJECT_ALIAS_FORMAT.lock().unwrap();
+        if let Some(format) = cached.as_ref() {
+            return self.has_format(format.id).then(|| format.clone());
+        }
+        let format = self.formats().into_iter().find_map(|id| {
+            let name = format_name(id)?;
+            is_object_alias_format_name(&name).then_some(ClipboardFormat { id, name })
+        })?;
+        tracing::debug!(
+            "Found object alias clipboard format: {} ({})",
+            format.name,
+            format.id
+        );
+        *cached = Some(format.clone());
+        Some(format)
+    }
+
+    fn has_format(&self, format: u32) -> bool {
+        unsafe { IsClipboardFormatAvailable(format) }.is_ok()
+    }
+
+    fn get_data(&self, format: u32) -> Result<Option<Vec<u8>>, ClipboardError> {
+        if !self.has_format(format) {
+            return Ok(None);
+        }
+        let handle = unsafe { GetClipboardData(format) }?;
+        let memory = HGLOBAL(handle.0);
+        let size = unsafe { GlobalSize(memory) };
+        let ptr = unsafe { GlobalLock(memory) };
+        if ptr.is_null() {
+            return Err(windows::core::Error::from_thread().into());
+        }
+        // SAFETY: GlobalLockで取得したポインタは、GlobalSizeのバイト数だけ読める
+        let data = unsafe { std::slice::from_raw_parts(ptr as *const u8, size) }.to_vec();
+        let _ = unsafe { GlobalUnlock(memory) };
+        Ok(Some(data))
+    }
+
+    fn set_data(&self, format: u32, data: &[u8]) -> Result<(), ClipboardError> {
+        let memory = unsafe { GlobalAlloc(GMEM_MOVEABLE, data.len()) }?;
+        let ptr = unsafe { GlobalLock(memory) };
+        if ptr.is_null() {
+            let error = windows::core::Error::from_thread();
+            let _ = unsafe { GlobalFree(Some(memory)) };
+            return Err(error.into());
+        }
+        // SAFETY: GlobalAllocでdata.len()バイト確保している
+        unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), ptr as *mut u8, data.len()) };
+        let _ = unsafe { GlobalUnlock(memory) };
+        if let Err(e) = unsafe { SetClipboardData(format, Some(HANDLE(memory.0))) } {
+            // 失敗した場合、メモリの所有権はシステムに移らない
+            let _ = unsafe { GlobalFree(Some(memory)) };
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    fn get_text(&self) -> Result<Option<String>, ClipboardError> {
+        Ok(self
+            .get_data(CF_UNICODETEXT)?
+            .map(|data| decode_utf16(&data)))
+    }
+}
+
+impl Drop for Clipboard {
+    fn drop(&mut self) {
+        let _ = unsafe { CloseClipboard() };
+    }
+}
+
+fn format_name(format: u32) -> Option<String> {
+    if format < FIRST_REGISTERED_FORMAT {
+        return None;
+    }
+    let mut buffer = [0u16; 256];
+    let len = unsafe { GetClipboardFormatNameW(format, &mut buffer) };
+    (len > 0).then(|| String::from_utf16_lossy(&buffer[..len as usize]))
+}
+
+fn is_object_alias_format_name(name: &str) -> bool {
+    name.to_ascii_lowercase().contains("aviutl")
+}
+
+fn wide_to_bytes(wide: &[u16]) -> Vec<u8> {
+    wide.iter().flat_map(|c| c.to_le_bytes()).collect()
+}
+
+/// 終端のヌル文字までのUTF-16LEをデコードする。
+fn decode_utf16(data: &[u8]) -> String {
+    let wide = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&c| c != 0)
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&wide)
+}
+
+/// AviUtl2の形式のデータをデコードする。
+///
+/// 2バイト目がヌル文字の場合はUTF-16LE、それ以外はUTF-8とみなします。
+/// `GlobalSize`は確保したサイズより大きくなることがあるため、終端のヌル文字以降は無視します。
+fn decode_text(data: &[u8]) -> String {
+    if data.len() >= 2 && data[0] != 0 && data[1] == 0 {
+        return decode_utf16(data);
+    }
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+/// プレーンテキストがエイリアスとして読めるかどうかを返す。
+///
+/// 空行を除いた最初の行が`[...]`のセクションになっているものをエイリアスとみなします。
+fn looks_like_object_alias(text: &str) -> bool {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| line.starts_with('[') && line.ends_with(']'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// クリップボードはプロセス間で共有されるため、テストを直列に実行する。
+    static CLIPBOARD_LOCK: Mutex<()> = Mutex::new(());
+
+    const ALIAS: &str = "[Object]\r\nframe=0,99\r\n[Object.0]\r\neffect.name=図形\r\n";
+
+    fn lock() -> std::sync::MutexGuard<'static, ()> {
+        CLIPBOARD_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn clear() {
+        Clipboard::open().unwrap().empty().unwrap();
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(decode_text(b"[Object]\0garbage"), "[Object]");
+        assert_eq!(decode_text("図形".as_bytes()), "図形");
+        let utf16 = wide_to_bytes(&"[Object]\0".encode_utf16().collect::<Vec<_>>());
+        assert_eq!(decode_text(&utf16), "[Object]");
+    }
+
+    #[test]
+    fn test_looks_like_object_alias() {
+        assert!(looks_like_object_alias(ALIAS));
+        assert!(looks_like_object_alias("\r\n  [Object]  \r\n"));
+        assert!(!looks_like_object_alias("hello"));
+        assert!(!looks_like_object_alias(""));
+    }
+
+    #[test]
+    fn test_empty_clipboard() {
+        let _lock = lock();
+        clear();
+        assert_eq!(get_object_alias(), None);
+        assert_eq!(get_text(), None);
+    }
+
+    #[test]
+    fn test_foreign_format() {
+        let _lock = lock();
+        let format = unsafe {
+            windows::Win32::System::DataExchange::RegisterClipboardFormatW(windows::core::w!(
+                "clipboard test foreign format"
+            ))
+        };
+        assert_ne!(format, 0);
+        {
+            let clipboard = Clipboard::open().unwrap();
+            clipboard.empty().unwrap();
+            clipboard.set_data(format, b"[Object]\0").unwrap();
+        }
+        assert_eq!(get_object_alias(), None);
+        assert_eq!(get_text(), None);
+    }
+
+    #[test]
+    fn test_plain_text() {
+        let _lock = lock();
+        set_text("hello").unwrap();
+        assert_eq!(get_text().as_deref(), Some("hello"));
+        assert_eq!(get_object_alias(), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let _lock = lock();
+        set_object_alias(ALIAS).unwrap();
+        assert_eq!(get_object_alias().as_deref(), Some(ALIAS));
+        assert_eq!(get_text().as_deref(), Some(ALIAS));
+    }
+
+    #[test]
+    fn test_large_payload() {
+        let _lock = lock();
+        let mut alias = String::from("[Object]\r\n");
+        while alias.len() < 8 * 1024 * 1024 {
+            alias.push_str("effect.name=テキスト\r\ntext=あいうえお\r\n");
+        }
+        set_object_alias(&alias).unwrap();
+        assert_eq!(get_object_alias().as_deref(), Some(alias.as_str()));
+    }
+
+    #[test]
+    fn test_null_byte() {
+        let _lock = lock();
+        assert!(matches!(
+            set_object_alias("[Object]\0"),
+            Err(ClipboardError::InputCwstrContainsNull(_))
+        ));
+    }
+}
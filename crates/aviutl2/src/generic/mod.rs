@@ -13,6 +13,7 @@
 //! これは公式SDKの`plugin2.h`に相当します。が、わかりづらいので`generic`と命名しています。
 
 mod binding;
+pub mod clipboard;
 
 pub use super::common::*;
 pub use binding::*;
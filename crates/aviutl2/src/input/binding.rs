@@ -14,23 +14,120 @@ pub struct InputPluginTable {
 
     /// 入力の種類。
     pub input_type: InputType,
-    /// 音声・動画の同時取得が可能かどうか。
+    /// 入力ハンドルへの同時アクセスの方針。
     ///
     /// <div class="warning">
     ///
-    /// このフラグによって、呼ばれるトレイトのメソッドが変わります。
-    /// `true` の場合は [`InputPlugin::read_video`] と [`InputPlugin::read_audio`] が呼ばれ、
-    /// `false` の場合は [`InputPlugin::read_video_mut`] と [`InputPlugin::read_audio_mut`] が呼ばれます。
+    /// この値によって、呼ばれるトレイトのメソッドが変わります。
+    /// [`ConcurrencyMode::Full`] の場合は [`InputPlugin::read_video`] と [`InputPlugin::read_audio`] が呼ばれ、
+    /// それ以外の場合は [`InputPlugin::read_video_mut`] と [`InputPlugin::read_audio_mut`] が呼ばれます。
     ///
     /// </div>
-    pub concurrent: bool,
+    pub concurrency: ConcurrencyMode,
+    /// 同じファイルに対する入力ハンドルを共有するかどうか。
+    ///
+    /// AviUtl2は同じファイルを参照するオブジェクトごとに [`InputPlugin::open`] を呼び出します。
+    /// `true` の場合、正規化したパスが同じファイルに対しては最初に開いた入力ハンドルを使い回し、
+    /// 全てのオブジェクトが閉じられた時点で [`InputPlugin::close`] が呼ばれます。
+    /// 共有された入力ハンドルへのアクセスは [`Self::concurrency`] に従って直列化されます。
+    ///
+    /// <div class="warning">
+    ///
+    /// 共有された入力ハンドルには、複数のオブジェクトからの呼び出しが任意の順序で届きます。
+    /// 例えば、あるオブジェクトがフレーム10を読み込んだ直後に、別のオブジェクトがフレーム0を要求することがあります。
+    /// 入力ハンドルの状態（デコーダーの読み込み位置など）に関わらず、要求されたフレームを正しく返せる必要があります。
+    ///
+    /// </div>
+    pub share_handles: bool,
+    /// ファイルの情報（トラック数と [`InputInfo`]）をディスクにキャッシュするかどうか。
+    ///
+    /// `true` の場合、ファイルの情報を正規化したパス・サイズ・更新日時をキーにして
+    /// `app_data_path()/input_info_cache/<プラグイン名>.bin` に保存します。
+    /// 次回以降のプロジェクトの読み込みでは、キャッシュが有効な間はファイルを開かずに情報を返し、
+    /// 画像や音声が実際に必要になった時点で [`InputPlugin::open`] を呼び出します。
+    ///
+    /// ファイル以外の設定で情報が変わる場合は、[`InputPlugin::cache_generation`] を変えてください。
+    pub cache_input_info: bool,
     /// プラグインがサポートするファイルフィルタのリスト。
     pub file_filters: Vec<FileFilter>,
+    /// 動画の読み込みに失敗したときに、AviUtl2にエラーを返すかどうか。
+    ///
+    /// `false` の場合、[`InputPlugin::read_video`] がエラーを返すと、エラーを入力ハンドルごとに1回だけログに出力し、
+    /// 代わりに [`ImageReturner::write_error_placeholder`] の画像を返します。
+    /// 1つのフレームが壊れていても、オブジェクト全体が表示されなくなることはありません。
+    ///
+    /// `true` の場合、エラーをそのままAviUtl2に返します。
+    pub fail_on_read_error: bool,
 
     /// プラグインが設定可能かどうか。
     pub can_config: bool,
 }
 
+/// 入力ハンドルへの同時アクセスの方針を表す列挙型。
+///
+/// 同期はブリッジ側で行われるため、入力ハンドルは`Send`であれば十分です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ConcurrencyMode {
+    /// 同時アクセスを行わない。
+    ///
+    /// すべての入力ハンドルへの呼び出しが、プラグイン全体で1つずつ行われます。
+    #[default]
+    None,
+    /// 入力ハンドルごとに同時アクセスを行わない。
+    ///
+    /// 異なる入力ハンドルへの呼び出しは並行して行われますが、
+    /// 同じ入力ハンドルへの呼び出しは1つずつ行われます。
+    PerHandle,
+    /// 同じ入力ハンドルに対しても同時アクセスを行う。
+    ///
+    /// [`InputPlugin::read_video`] と [`InputPlugin::read_audio`] が並行して呼ばれるため、
+    /// 入力ハンドルが`Sync`である必要があります。
+    /// [`SyncHandleMarker::new`] または [`ConcurrencyMode::full`] で作成してください。
+    Full(SyncHandleMarker),
+}
+
+impl ConcurrencyMode {
+    /// 入力ハンドルの型`H`に対する [`ConcurrencyMode::Full`] を作成する。
+    pub fn full<H: std::any::Any + Send + Sync>() -> Self {
+        ConcurrencyMode::Full(SyncHandleMarker::new::<H>())
+    }
+
+    /// ホストに並行呼び出しを許可するかどうか。
+    pub(crate) fn is_concurrent(&self) -> bool {
+        !matches!(self, ConcurrencyMode::None)
+    }
+}
+
+/// 入力ハンドルの型が`Sync`であることを表すマーカー。
+///
+/// [`ConcurrencyMode::Full`] で使用されます。
+/// プラグインの初期化時に [`InputPlugin::InputHandle`] と型が一致するかどうかが検証されます。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SyncHandleMarker {
+    type_id: std::any::TypeId,
+    type_name: &'static str,
+}
+
+impl SyncHandleMarker {
+    /// 入力ハンドルの型`H`に対するマーカーを作成する。
+    pub fn new<H: std::any::Any + Send + Sync>() -> Self {
+        Self {
+            type_id: std::any::TypeId::of::<H>(),
+            type_name: std::any::type_name::<H>(),
+        }
+    }
+
+    /// マーカーが型`H`に対するものかどうかを返す。
+    pub(crate) fn is_for<H: std::any::Any>(&self) -> bool {
+        self.type_id == std::any::TypeId::of::<H>()
+    }
+
+    /// マーカーが作成された型の名前を返す。
+    pub(crate) fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
 /// 動画・画像の入力情報を表す構造体。
 #[derive(Debug, Clone)]
 pub struct VideoInputInfo {
@@ -54,6 +151,28 @@ pub struct VideoInputInfo {
 
     /// 画像のフォーマット。
     pub format: InputPixelFormat,
+
+    /// 画像データのαの形式。
+    ///
+    /// AviUtl2が受け付けるαの形式はフォーマットごとに決まっているため、
+    /// [`InputPixelFormat::native_alpha_mode`]と異なる場合はaviutl2-rs側で変換します。
+    /// αチャンネルを持たないフォーマットの場合、この値は無視されます。
+    pub alpha: AlphaMode,
+
+    /// YUV形式の画像データの色空間。
+    ///
+    /// [`Colorimetry::HOST`][crate::pixel::Colorimetry::HOST]と異なる場合はaviutl2-rs側で変換します。
+    /// [`InputPixelFormat::Yuy2`]以外のフォーマットの場合、この値は無視されます。
+    pub colorimetry: crate::pixel::Colorimetry,
+}
+
+/// 画像データのαの形式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlphaMode {
+    /// ストレートα。
+    Straight,
+    /// 乗算済みα。
+    Premultiplied,
 }
 
 /// 画像のフォーマット。
@@ -100,6 +219,18 @@ pub enum InputPixelFormat {
     Hf64,
 }
 
+impl InputPixelFormat {
+    /// AviUtl2がこのフォーマットで受け付けるαの形式を返す。
+    /// αチャンネルを持たないフォーマットの場合は`None`を返します。
+    pub fn native_alpha_mode(self) -> Option<AlphaMode> {
+        match self {
+            InputPixelFormat::Bgra => Some(AlphaMode::Straight),
+            InputPixelFormat::Pa64 | InputPixelFormat::Hf64 => Some(AlphaMode::Premultiplied),
+            InputPixelFormat::Bgr | InputPixelFormat::Yuy2 | InputPixelFormat::Yc48 => None,
+        }
+    }
+}
+
 /// 音声の入力情報を表す構造体。
 #[derive(Debug, Clone)]
 pub struct AudioInputInfo {
@@ -153,9 +284,216 @@ impl InputType {
     }
 }
 
+/// トラックの種類を表す列挙型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackKind {
+    /// 動画のトラック。
+    Video,
+    /// 音声のトラック。
+    Audio,
+}
+
+/// トラックの情報を表す構造体。
+///
+/// # See Also
+/// [`InputPlugin::track_info`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackInfo {
+    /// トラックの名前。
+    pub name: String,
+    /// トラックの言語（`jpn`、`en`など）。
+    pub language: Option<String>,
+    /// デフォルトのトラックかどうか。
+    pub default: bool,
+}
+
+impl TrackInfo {
+    /// 番号だけの名前を持つトラックの情報を作成する。
+    pub fn numbered(kind: TrackKind, index: u32) -> Self {
+        let name = match kind {
+            TrackKind::Video => format!("映像トラック{index}"),
+            TrackKind::Audio => format!("音声トラック{index}"),
+        };
+        Self {
+            name,
+            language: None,
+            default: index == 0,
+        }
+    }
+
+    /// トラック番号・名前・言語をまとめた1行のラベルを返す。
+    ///
+    /// `2: 日本語 [jpn] (default)`のような形式になります。
+    pub fn label(&self, index: u32) -> String {
+        let mut label = format!("{index}: {}", self.name);
+        if let Some(language) = &self.language {
+            label.push_str(&format!(" [{language}]"));
+        }
+        if self.default {
+            label.push_str(" (default)");
+        }
+        label
+    }
+}
+
+#[cfg(test)]
+mod track_info_tests {
+    use super::{TrackInfo, TrackKind};
+
+    #[test]
+    fn numbered_track_info() {
+        assert_eq!(
+            TrackInfo::numbered(TrackKind::Audio, 0),
+            TrackInfo {
+                name: "音声トラック0".to_string(),
+                language: None,
+                default: true,
+            }
+        );
+        assert!(!TrackInfo::numbered(TrackKind::Video, 1).default);
+    }
+
+    #[test]
+    fn label_includes_language_and_default() {
+        let info = TrackInfo {
+            name: "日本語".to_string(),
+            language: Some("jpn".to_string()),
+            default: true,
+        };
+        assert_eq!(info.label(2), "2: 日本語 [jpn] (default)");
+
+        let info = TrackInfo {
+            name: "Commentary".to_string(),
+            language: None,
+            default: false,
+        };
+        assert_eq!(info.label(1), "1: Commentary");
+    }
+}
+
 /// 画像のバッファを表す構造体。
-#[derive(Debug, Clone)]
-pub struct ImageBuffer(pub Vec<u8>);
+///
+/// 中身は`Arc<[u8]>`で共有されるため、`clone()`はデータをコピーしません。
+/// 書き換えるときは[`ImageBuffer::make_mut`]を使ってください（共有されている場合のみコピーされます）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageBuffer(std::sync::Arc<[u8]>);
+
+impl ImageBuffer {
+    /// バイト列から`ImageBuffer`を作成します。
+    pub fn new(data: Vec<u8>) -> Self {
+        Self(data.into())
+    }
+
+    /// バッファのバイト数を返します。
+    ///
+    /// キャッシュのメモリ使用量の計算に使えます。
+    ///
+    /// # See Also
+    /// [`crate::input::CacheBudget`]
+    pub fn byte_len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// 2つの`ImageBuffer`が同じデータを共有しているかどうかを返します。
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// 書き換え可能なバイト列を返します。
+    ///
+    /// 他の`ImageBuffer`とデータを共有している場合は、先にコピーします。
+    pub fn make_mut(&mut self) -> &mut [u8] {
+        if std::sync::Arc::get_mut(&mut self.0).is_none() {
+            self.0 = std::sync::Arc::from(&self.0[..]);
+        }
+        std::sync::Arc::get_mut(&mut self.0).expect("ImageBuffer is not shared")
+    }
+
+    /// `Vec<u8>`に変換します。
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    /// 長辺が`max_dim`以下になるように縮小した画像を、幅・高さと一緒に返します。
+    ///
+    /// プレビュー用の軽い画像を作るのに使えます。
+    /// 既に長辺が`max_dim`以下の場合は、データをコピーせずにそのまま返します。
+    ///
+    /// 1チャンネルが整数の形式（[`InputPixelFormat::Bgr`]・[`InputPixelFormat::Bgra`]・
+    /// [`InputPixelFormat::Pa64`]・[`InputPixelFormat::Yc48`]）は平均画素法で、
+    /// それ以外の形式は最近傍法で縮小します。
+    /// [`InputPixelFormat::Yuy2`]の場合、幅は2の倍数に切り上げられます。
+    ///
+    /// <div class="warning">
+    ///
+    /// AviUtl2の入力プラグインAPIはプレビューの表示倍率を渡さないため、
+    /// 縮小した画像を使うかどうかはプラグイン側で判断する必要があります。
+    ///
+    /// </div>
+    ///
+    /// # Panics
+    ///
+    /// バッファの長さが`width`・`height`・`format`と一致しない場合、または`max_dim`が0の場合はPanicします。
+    pub fn downscaled(
+        &self,
+        width: u32,
+        height: u32,
+        format: InputPixelFormat,
+        max_dim: u32,
+    ) -> (ImageBuffer, u32, u32) {
+        assert!(max_dim > 0, "max_dim must be positive");
+        let (bytes_per_pixel, channel) = match format {
+            InputPixelFormat::Bgr => (3, ChannelKind::U8),
+            InputPixelFormat::Bgra => (4, ChannelKind::U8),
+            InputPixelFormat::Pa64 => (8, ChannelKind::U16),
+            InputPixelFormat::Yc48 => (6, ChannelKind::I16),
+            InputPixelFormat::Hf64 => (8, ChannelKind::Opaque),
+            // 2ピクセルで4バイトなので、2ピクセルを1つとして扱う
+            InputPixelFormat::Yuy2 => (4, ChannelKind::Opaque),
+        };
+        let unit_width = if format == InputPixelFormat::Yuy2 {
+            width.div_ceil(2)
+        } else {
+            width
+        };
+        assert_eq!(
+            self.0.len(),
+            unit_width as usize * height as usize * bytes_per_pixel,
+            "buffer length does not match the image size"
+        );
+        let long_side = width.max(height);
+        if long_side <= max_dim {
+            return (self.clone(), width, height);
+        }
+        let scale =
+            |size: u32| ((size as u64 * max_dim as u64).div_ceil(long_side as u64) as u32).max(1);
+        let (new_width, new_height) = (scale(width), scale(height));
+        let new_unit_width = if format == InputPixelFormat::Yuy2 {
+            new_width.div_ceil(2)
+        } else {
+            new_width
+        };
+        let out = downscale_box(
+            &self.0,
+            (unit_width as usize, height as usize),
+            (new_unit_width as usize, new_height as usize),
+            bytes_per_pixel,
+            channel,
+        );
+        let new_width = if format == InputPixelFormat::Yuy2 {
+            new_unit_width * 2
+        } else {
+            new_width
+        };
+        (ImageBuffer::new(out), new_width, new_height)
+    }
+}
+
+impl From<Vec<u8>> for ImageBuffer {
+    fn from(data: Vec<u8>) -> Self {
+        Self::new(data)
+    }
+}
 
 impl std::ops::Deref for ImageBuffer {
     type Target = [u8];
@@ -164,6 +502,79 @@ impl std::ops::Deref for ImageBuffer {
     }
 }
 
+#[derive(Clone, Copy)]
+enum ChannelKind {
+    U8,
+    U16,
+    I16,
+    /// 平均を取れない形式。最近傍法で縮小する。
+    Opaque,
+}
+
+fn downscale_box(
+    src: &[u8],
+    (src_width, src_height): (usize, usize),
+    (dst_width, dst_height): (usize, usize),
+    bytes_per_pixel: usize,
+    channel: ChannelKind,
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(dst_width * dst_height * bytes_per_pixel);
+    let range = |i: usize, src_len: usize, dst_len: usize| {
+        let start = i * src_len / dst_len;
+        let end = ((i + 1) * src_len / dst_len).max(start + 1);
+        start..end
+    };
+    for y in 0..dst_height {
+        let ys = range(y, src_height, dst_height);
+        for x in 0..dst_width {
+            let xs = range(x, src_width, dst_width);
+            let pixel = |sx: usize, sy: usize| {
+                let offset = (sy * src_width + sx) * bytes_per_pixel;
+                &src[offset..offset + bytes_per_pixel]
+            };
+            match channel {
+                ChannelKind::Opaque => {
+                    out.extend_from_slice(pixel(xs.start, ys.start));
+                }
+                ChannelKind::U8 => {
+                    let count = (xs.len() * ys.len()) as u64;
+                    for c in 0..bytes_per_pixel {
+                        let sum: u64 = ys
+                            .clone()
+                            .flat_map(|sy| xs.clone().map(move |sx| (sx, sy)))
+                            .map(|(sx, sy)| pixel(sx, sy)[c] as u64)
+                            .sum();
+                        out.push(((sum + count / 2) / count) as u8);
+                    }
+                }
+                ChannelKind::U16 | ChannelKind::I16 => {
+                    let count = (xs.len() * ys.len()) as i64;
+                    for c in (0..bytes_per_pixel).step_by(2) {
+                        let sum: i64 = ys
+                            .clone()
+                            .flat_map(|sy| xs.clone().map(move |sx| (sx, sy)))
+                            .map(|(sx, sy)| {
+                                let bytes = [pixel(sx, sy)[c], pixel(sx, sy)[c + 1]];
+                                match channel {
+                                    ChannelKind::U16 => u16::from_le_bytes(bytes) as i64,
+                                    _ => i16::from_le_bytes(bytes) as i64,
+                                }
+                            })
+                            .sum();
+                        let average = (sum + count / 2).div_euclid(count);
+                        let bytes = match channel {
+                            ChannelKind::U16 => (average as u16).to_le_bytes(),
+                            _ => (average as i16).to_le_bytes(),
+                        };
+                        out.extend_from_slice(&bytes);
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
 /// 画像データを `ImageBuffer` に変換するトレイト。
 pub trait IntoImage {
     fn into_image(self) -> crate::input::ImageBuffer;
@@ -171,7 +582,7 @@ pub trait IntoImage {
 
 impl<T: AsImage> IntoImage for T {
     fn into_image(self) -> ImageBuffer {
-        ImageBuffer(self.as_image().into_owned())
+        ImageBuffer::new(self.as_image().into_owned())
     }
 }
 
@@ -209,14 +620,16 @@ impl AsImage for Cow<'_, [u8]> {
 
 duplicate::duplicate! {
     [
-        Name            Trait     method;
-        [ImageReturner] [AsImage] [as_image];
-        [AudioReturner] [AsAudio] [as_audio];
+        Name            Trait     method      Format;
+        [ImageReturner] [AsImage] [as_image]  [Option<(InputPixelFormat, crate::pixel::Colorimetry)>];
+        [AudioReturner] [AsAudio] [as_audio]  [Option<AudioRequest>];
     ]
     /// AviUtl2側にバイト列を返すためのstruct。
     pub struct Name {
         ptr: *mut u8,
         capacity: usize,
+        track: u32,
+        format: Format,
         pub(crate) written: usize,
     }
 
@@ -229,10 +642,25 @@ duplicate::duplicate! {
             Self {
                 ptr,
                 capacity,
+                track: 0,
+                format: Default::default(),
                 written: 0,
             }
         }
 
+        pub(crate) fn with_track(mut self, track: u32) -> Self {
+            self.track = track;
+            self
+        }
+
+        /// 読み込み対象のトラック番号。
+        ///
+        /// [`InputPluginTable::share_handles`]が有効な場合、1つの入力ハンドルが別々のトラックを選択した
+        /// 複数のオブジェクトから使われるため、ハンドルに保存したトラック番号ではなくこちらを使ってください。
+        pub fn track(&self) -> u32 {
+            self.track
+        }
+
         fn assert_writable(&self, len: usize) {
             let remaining = self.capacity - self.written;
             assert!(
@@ -270,9 +698,233 @@ duplicate::duplicate! {
     }
 }
 
+impl ImageReturner {
+    pub(crate) fn with_format(
+        mut self,
+        format: InputPixelFormat,
+        colorimetry: crate::pixel::Colorimetry,
+    ) -> Self {
+        self.format = Some((format, colorimetry));
+        self
+    }
+
+    /// 読み込みに失敗したことを表す画像を書き込む。
+    ///
+    /// マゼンタの背景に`message`を白い文字で描いた、`width`x`height`の画像を、
+    /// 入力ハンドルのピクセルフォーマットで書き込みます。
+    /// 文字は5x7ピクセルのASCIIフォントで描かれ、ASCII以外の文字は`?`になります。
+    ///
+    /// 1つのフレームが壊れていても、他のフレームを読み込めるようにする場合に使います。
+    /// [`InputPluginTable::fail_on_read_error`]が`false`の場合、
+    /// [`InputPlugin::read_video`]がエラーを返すとブリッジがこれを書き込みます。
+    ///
+    /// # Panics
+    ///
+    /// `width`x`height`の画像が出力バッファに収まらない場合にパニックします。
+    pub fn write_error_placeholder(&mut self, width: u32, height: u32, message: &str) {
+        let (format, colorimetry) = self
+            .format
+            .unwrap_or((InputPixelFormat::Bgra, crate::pixel::Colorimetry::HOST));
+        self.write(&super::placeholder::render(
+            format,
+            colorimetry,
+            width,
+            height,
+            message,
+        ));
+    }
+}
+
+/// [`AudioReturner`]に書き込む音声の情報。
+pub(crate) struct AudioRequest {
+    info: AudioInputInfo,
+    /// 読み込みの開始位置（サンプル単位）。
+    start: i64,
+    /// 入力ハンドルごとのサンプルレートの変換の状態。
+    resampler: std::sync::Arc<std::sync::Mutex<super::resample::Resampler>>,
+}
+
+impl AudioReturner {
+    pub(crate) fn with_request(
+        mut self,
+        info: AudioInputInfo,
+        start: i64,
+        resampler: std::sync::Arc<std::sync::Mutex<super::resample::Resampler>>,
+    ) -> Self {
+        self.format = Some(AudioRequest {
+            info,
+            start,
+            resampler,
+        });
+        self
+    }
+
+    fn request(&self) -> &AudioRequest {
+        self.format
+            .as_ref()
+            .expect("Audio format is not available for this returner")
+    }
+
+    fn block_align(&self) -> usize {
+        let info = &self.request().info;
+        let bytes_per_sample = match info.format {
+            AudioFormat::Pcm16 => 2,
+            AudioFormat::IeeeFloat32 => 4,
+        };
+        info.channels as usize * bytes_per_sample
+    }
+
+    /// 入力の情報で指定したサンプルレート。
+    pub fn sample_rate(&self) -> u32 {
+        self.request().info.sample_rate
+    }
+
+    /// 入力の情報で指定したチャンネル数。
+    pub fn channels(&self) -> u16 {
+        self.request().info.channels
+    }
+
+    /// 次に書き込むサンプルの位置。
+    fn position(&self) -> i64 {
+        self.request().start + (self.written / self.block_align()) as i64
+    }
+
+    /// 出力バッファに書き込めるサンプル数。
+    pub fn remaining_samples(&self) -> usize {
+        (self.capacity - self.written) / self.block_align()
+    }
+
+    /// チャンネルごとに分かれた音声を書き込む。
+    ///
+    /// `-1.0`から`1.0`の範囲のサンプルを、入力の情報で指定したフォーマットに変換して書き込みます。
+    ///
+    /// # Panics
+    ///
+    /// - `planes`の数が入力の情報で指定したチャンネル数と異なる場合
+    /// - チャンネルごとのサンプル数が揃っていない場合
+    /// - 出力バッファに収まらない場合
+    pub fn write_planar(&mut self, planes: &[Vec<f32>]) {
+        let planes = planes.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        self.write_planes(&planes);
+    }
+
+    fn write_planes(&mut self, planes: &[&[f32]]) {
+        assert_eq!(
+            planes.len(),
+            self.channels() as usize,
+            "Number of planes must match the number of channels"
+        );
+        let len = planes.first().map_or(0, |plane| plane.len());
+        assert!(
+            planes.iter().all(|plane| plane.len() == len),
+            "All planes must have the same length"
+        );
+        let format = self.request().info.format.clone();
+        let block_align = self.block_align();
+        let _ = self.write_with(len * block_align, |buffer| {
+            let samples = (0..len).flat_map(|i| planes.iter().map(move |plane| plane[i]));
+            match format {
+                AudioFormat::Pcm16 => {
+                    for (chunk, sample) in buffer.chunks_exact_mut(2).zip(samples) {
+                        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+                        chunk.copy_from_slice(&value.to_le_bytes());
+                    }
+                }
+                AudioFormat::IeeeFloat32 => {
+                    for (chunk, sample) in buffer.chunks_exact_mut(4).zip(samples) {
+                        chunk.copy_from_slice(&sample.to_le_bytes());
+                    }
+                }
+            }
+            Ok::<(), std::convert::Infallible>(())
+        });
+    }
+
+    /// [`Self::write_resampled`]に渡す必要のある、`source_rate`での入力サンプルの範囲を返す。
+    ///
+    /// 範囲は0より前や音声の長さより後ろを含むことがあります。その部分は無音を渡してください。
+    ///
+    /// 同じ入力ハンドルで前回の続きを読み込む場合は、前回渡した入力サンプルの続きだけを返します。
+    pub fn resample_source_range(&self, source_rate: u32) -> std::ops::Range<i64> {
+        let request = self.request();
+        request
+            .resampler
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .source_range(
+                source_rate,
+                request.info.sample_rate,
+                2,
+                self.position(),
+                self.remaining_samples(),
+            )
+    }
+
+    /// `source_rate`のステレオの音声を、入力の情報で指定したサンプルレートに変換して、
+    /// 出力バッファの残りを埋めるように書き込む。
+    ///
+    /// `samples`は[`Self::resample_source_range`]が返した範囲の入力サンプルである必要があります。
+    /// 変換にはカイザー窓をかけたsinc関数を使い、1kHzの正弦波を44100Hzと48000Hzの間で変換した場合の
+    /// THD+Nは-80dB未満です。
+    /// 変換の状態は入力ハンドルごとに保持されるため、続けて読み込まれた場合も継ぎ目は生じません。
+    ///
+    /// チャンネル数が1の場合は、左右の平均を書き込みます。
+    ///
+    /// # Note
+    ///
+    /// 入力プラグインのAPIからはシーンのサンプルレートを取得できないため、
+    /// 変換先は[`AudioInputInfo::sample_rate`]で指定したサンプルレートになります。
+    ///
+    /// # Panics
+    ///
+    /// - `samples`の長さが[`Self::resample_source_range`]の範囲と異なる場合
+    /// - 入力の情報で指定したチャンネル数が1か2でない場合
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn f(returner: &mut aviutl2::input::AudioReturner, synthesize: impl Fn(i64) -> (f32, f32)) {
+    /// // 44100Hzで合成した音声を、宣言したサンプルレートに変換して返す
+    /// let range = returner.resample_source_range(44100);
+    /// let samples = range.map(synthesize).collect::<Vec<_>>();
+    /// returner.write_resampled(&samples, 44100);
+    /// # }
+    /// ```
+    pub fn write_resampled(&mut self, samples: &[(f32, f32)], source_rate: u32) {
+        let channels = self.channels();
+        assert!(
+            matches!(channels, 1 | 2),
+            "write_resampled only supports mono or stereo output, but the input has {channels} channels"
+        );
+        let (left, right): (Vec<f32>, Vec<f32>) = samples.iter().copied().unzip();
+        let request = self.request();
+        let output = request
+            .resampler
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .process(
+                source_rate,
+                request.info.sample_rate,
+                self.position(),
+                self.remaining_samples(),
+                &[&left, &right],
+            );
+        if channels == 1 {
+            let mono = output[0]
+                .iter()
+                .zip(&output[1])
+                .map(|(l, r)| (l + r) / 2.0)
+                .collect::<Vec<_>>();
+            self.write_planes(&[&mono]);
+        } else {
+            self.write_planes(&[&output[0], &output[1]]);
+        }
+    }
+}
+
 #[cfg(test)]
 mod returner_tests {
-    use super::ImageReturner;
+    use super::{AudioFormat, AudioInputInfo, AudioReturner, ImageReturner};
 
     #[test]
     fn write_with_writes_directly_and_updates_length() {
@@ -290,6 +942,34 @@ mod returner_tests {
         assert_eq!(output, [1, 2, 3, 4]);
     }
 
+    #[test]
+    fn write_error_placeholder_uses_handle_format() {
+        let mut output = [0u8; 4 * 4 * 2];
+        let mut returner = unsafe { ImageReturner::new(output.as_mut_ptr(), output.len()) }
+            .with_format(
+                crate::input::InputPixelFormat::Yuy2,
+                crate::pixel::Colorimetry::HOST,
+            );
+
+        returner.write_error_placeholder(4, 4, "");
+
+        assert_eq!(returner.written, output.len());
+        let magenta = crate::pixel::rgb_to_yuy2(
+            [(255, 0, 255), (255, 0, 255)],
+            crate::pixel::Colorimetry::HOST,
+        );
+        assert_eq!(output[..4], [magenta.0, magenta.1, magenta.2, magenta.3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Output buffer overflow")]
+    fn write_error_placeholder_rejects_larger_image() {
+        let mut output = [0u8; 4];
+        let mut returner = unsafe { ImageReturner::new(output.as_mut_ptr(), output.len()) };
+
+        returner.write_error_placeholder(2, 1, "");
+    }
+
     #[test]
     #[should_panic(expected = "Output buffer overflow")]
     fn write_rejects_data_larger_than_remaining_capacity() {
@@ -309,6 +989,60 @@ mod returner_tests {
             .write_with(output.len() + 1, |_| Ok::<(), ()>(()))
             .unwrap();
     }
+
+    fn audio_returner(output: &mut [u8], channels: u16, format: AudioFormat) -> AudioReturner {
+        unsafe { AudioReturner::new(output.as_mut_ptr(), output.len()) }.with_request(
+            AudioInputInfo {
+                sample_rate: 48000,
+                num_samples: 48000,
+                channels,
+                format,
+            },
+            0,
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn write_planar_interleaves_and_converts() {
+        let mut output = [0u8; 2 * 2 * 2];
+        let mut returner = audio_returner(&mut output, 2, AudioFormat::Pcm16);
+        returner.write_planar(&[vec![1.0, -2.0], vec![0.5, 0.0]]);
+        assert_eq!(returner.remaining_samples(), 0);
+
+        let samples = output
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(samples, [i16::MAX, 16384, -i16::MAX, 0]);
+    }
+
+    #[test]
+    fn write_resampled_fills_remaining_samples() {
+        let mut output = [0u8; 480 * 4];
+        let mut returner = audio_returner(&mut output, 1, AudioFormat::IeeeFloat32);
+        returner.write_planar(&[vec![0.0; 80]]);
+
+        let range = returner.resample_source_range(44100);
+        let samples = range.map(|_| (0.25, 0.75)).collect::<Vec<_>>();
+        returner.write_resampled(&samples, 44100);
+        assert_eq!(returner.remaining_samples(), 0);
+
+        // 直流成分はそのまま残り、左右の平均になる
+        let samples = output
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert!(samples[80..].iter().all(|v| (v - 0.5).abs() < 1e-3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Number of planes")]
+    fn write_planar_rejects_wrong_channel_count() {
+        let mut output = [0u8; 16];
+        let mut returner = audio_returner(&mut output, 2, AudioFormat::IeeeFloat32);
+        returner.write_planar(&[vec![0.0; 2]]);
+    }
 }
 
 #[duplicate::duplicate_item(
@@ -449,7 +1183,12 @@ into_audio_impl_for_tuple!((f32, f32), l, r);
 /// このトレイトを実装し、[`crate::register_input_plugin!`] マクロを使用してプラグインを登録します。
 pub trait InputPlugin: Send + Sync + Sized {
     /// 入力ハンドルの型。
-    type InputHandle: std::any::Any + Send + Sync;
+    ///
+    /// 同期はブリッジ側で行われるため、`Sync`である必要はありません。
+    ///
+    /// # See Also
+    /// [`ConcurrencyMode`]
+    type InputHandle: std::any::Any + Send;
 
     /// プラグインを初期化する。
     fn new(info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self>;
@@ -473,6 +1212,22 @@ pub trait InputPlugin: Send + Sync + Sized {
         Ok((video_tracks, audio_tracks))
     }
 
+    /// トラックの名前などの情報を取得する。
+    ///
+    /// AviUtl2にはトラックの名前を渡す仕組みがないため、トラックが選択されたときに
+    /// [`TrackInfo::label`]の形式でログに出力されます。
+    ///
+    /// デフォルトでは、番号だけの名前を返します。
+    fn track_info(
+        &self,
+        handle: &mut Self::InputHandle,
+        kind: TrackKind,
+        index: u32,
+    ) -> crate::common::AnyResult<TrackInfo> {
+        let _ = handle;
+        Ok(TrackInfo::numbered(kind, index))
+    }
+
     /// 入力の情報を取得する。
     fn get_input_info(
         &self,
@@ -485,8 +1240,8 @@ pub trait InputPlugin: Send + Sync + Sized {
     ///
     /// <div class="warning">
     ///
-    /// [`InputPluginTable::concurrent`] が `true` の場合に呼ばれます。
-    /// `false` の場合は [`Self::read_video_mut`] が呼ばれます。
+    /// [`InputPluginTable::concurrency`] が [`ConcurrencyMode::Full`] の場合に呼ばれます。
+    /// それ以外の場合は [`Self::read_video_mut`] が呼ばれます。
     ///
     /// </div>
     fn read_video(
@@ -505,8 +1260,8 @@ pub trait InputPlugin: Send + Sync + Sized {
     ///
     /// <div class="warning">
     ///
-    /// [`InputPluginTable::concurrent`] が `false` の場合に呼ばれます。
-    /// `true` の場合は [`Self::read_video`] が呼ばれます。
+    /// [`InputPluginTable::concurrency`] が [`ConcurrencyMode::Full`] 以外の場合に呼ばれます。
+    /// [`ConcurrencyMode::Full`] の場合は [`Self::read_video`] が呼ばれます。
     ///
     /// </div>
     fn read_video_mut(
@@ -534,20 +1289,25 @@ pub trait InputPlugin: Send + Sync + Sized {
 
     // TODO: これが他の関数と同時に呼ばれるかどうかは未検証なので、検証する（handleが `&mut` でいいかどうかに影響するため）
     /// 現在の時刻からフレーム数を取得する。
-    /// [`VideoInputInfo::manual_frame_index`] が `true` の場合に使用されます。
+    /// [`VideoInputInfo::manual_frame_index`] が `true` の場合にのみ呼ばれます。
+    ///
+    /// 可変フレームレートの場合は、[`FrameTimeline::frame_for`][crate::input::FrameTimeline::frame_for] を使って実装できます。
+    ///
+    /// デフォルトでは、固定フレームレートとして時刻をフレーム番号に変換します。
+    /// 負の時刻は最初のフレーム、動画の長さ以降の時刻は最後のフレームになります。
     fn time_to_frame(
         &self,
         handle: &mut Self::InputHandle,
         track: u32,
         time: f64,
     ) -> crate::common::AnyResult<u32> {
-        const RESOLUTION: i32 = 1000; // ミリ秒単位での解像度
         let info = self.get_input_info(handle, track, 0)?;
         if let Some(video_info) = &info.video {
-            Ok(
-                (video_info.fps * Rational32::new((time * RESOLUTION as f64) as i32, RESOLUTION))
-                    .to_integer() as u32,
-            )
+            Ok(super::timeline::frame_at_constant_rate(
+                video_info.fps,
+                video_info.num_frames,
+                time,
+            ))
         } else {
             Err(anyhow::anyhow!("No video information available"))
         }
@@ -557,8 +1317,8 @@ pub trait InputPlugin: Send + Sync + Sized {
     ///
     /// <div class="warning">
     ///
-    /// [`InputPluginTable::concurrent`] が `true` の場合に呼ばれます。
-    /// `false` の場合は [`Self::read_audio_mut`] が呼ばれます。
+    /// [`InputPluginTable::concurrency`] が [`ConcurrencyMode::Full`] の場合に呼ばれます。
+    /// それ以外の場合は [`Self::read_audio_mut`] が呼ばれます。
     ///
     /// </div>
     fn read_audio(
@@ -578,8 +1338,8 @@ pub trait InputPlugin: Send + Sync + Sized {
     ///
     /// <div class="warning">
     ///
-    /// [`InputPluginTable::concurrent`] が `false` の場合に呼ばれます。
-    /// `true` の場合は [`Self::read_audio`] が呼ばれます。
+    /// [`InputPluginTable::concurrency`] が [`ConcurrencyMode::Full`] 以外の場合に呼ばれます。
+    /// [`ConcurrencyMode::Full`] の場合は [`Self::read_audio`] が呼ばれます。
     ///
     /// </div>
     fn read_audio_mut(
@@ -606,12 +1366,26 @@ pub trait InputPlugin: Send + Sync + Sized {
         Ok(track)
     }
 
+    /// [`InputPluginTable::cache_input_info`] のキャッシュの世代を返す。
+    ///
+    /// この値が変わると、それまでのキャッシュは使われなくなります。
+    /// 例えば、MIDIの読み込みに使うサウンドフォントを設定で変えた場合など、
+    /// ファイルが同じでも [`InputInfo`] が変わる設定に合わせて値を変えてください。
+    fn cache_generation(&self) -> u64 {
+        0
+    }
+
     /// 設定ダイアログを表示する。
     fn config(&self, hwnd: crate::common::Win32WindowHandle) -> crate::common::AnyResult<()> {
         let _ = hwnd;
         Ok(())
     }
 
+    /// プラグインが終了する直前に呼ばれる。キャッシュの書き出しなどに使えます。
+    ///
+    /// AviUtl2が閉じなかった入力ハンドルは、この関数の前に [`Self::close`] で閉じられます。
+    fn on_unload(&self) {}
+
     /// シングルトンインスタンスを参照するためのヘルパーメソッド。
     ///
     /// # Panics
@@ -636,3 +1410,88 @@ pub trait InputPlugin: Send + Sync + Sized {
         <Self as crate::input::__bridge::InputSingleton>::with_instance_mut(f)
     }
 }
+
+#[cfg(test)]
+mod image_buffer_tests {
+    use super::{ImageBuffer, InputPixelFormat};
+
+    #[test]
+    fn clone_shares_data_and_make_mut_copies() {
+        let mut buffer = ImageBuffer::new(vec![1, 2, 3, 4]);
+        let shared = buffer.clone();
+        assert!(buffer.ptr_eq(&shared));
+        assert_eq!(buffer.byte_len(), 4);
+
+        buffer.make_mut()[0] = 9;
+        assert!(!buffer.ptr_eq(&shared));
+        assert_eq!(&*buffer, &[9, 2, 3, 4]);
+        assert_eq!(&*shared, &[1, 2, 3, 4]);
+
+        // 共有されていなければコピーしない
+        let ptr = buffer.as_ptr();
+        buffer.make_mut()[1] = 8;
+        assert_eq!(buffer.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn downscaled_returns_same_buffer_when_small_enough() {
+        let buffer = ImageBuffer::new(vec![0; 4 * 4 * 4]);
+        let (scaled, width, height) = buffer.downscaled(4, 4, InputPixelFormat::Bgra, 4);
+        assert!(scaled.ptr_eq(&buffer));
+        assert_eq!((width, height), (4, 4));
+    }
+
+    #[test]
+    fn downscaled_averages_bgra() {
+        #[rustfmt::skip]
+        let buffer = ImageBuffer::new(vec![
+            0, 0, 0, 255,     100, 0, 0, 255,   10, 10, 10, 10,  10, 10, 10, 10,
+            0, 100, 0, 255,   0, 0, 100, 255,   10, 10, 10, 10,  10, 10, 10, 10,
+        ]);
+        let (scaled, width, height) = buffer.downscaled(4, 2, InputPixelFormat::Bgra, 2);
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(&*scaled, &[25, 25, 25, 255, 10, 10, 10, 10]);
+    }
+
+    #[test]
+    fn downscaled_averages_pa64_and_yc48_channels() {
+        let pa64: Vec<u8> = [0u16, 1000, 65535, 65535, 2000, 3000, 65535, 65535]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let (scaled, width, height) =
+            ImageBuffer::new(pa64).downscaled(2, 1, InputPixelFormat::Pa64, 1);
+        assert_eq!((width, height), (1, 1));
+        let channels: Vec<u16> = scaled
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(channels, [1000, 2000, 65535, 65535]);
+
+        let yc48: Vec<u8> = [100i16, -200, 300, 200, -400, 500]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let (scaled, _, _) = ImageBuffer::new(yc48).downscaled(2, 1, InputPixelFormat::Yc48, 1);
+        let channels: Vec<i16> = scaled
+            .chunks_exact(2)
+            .map(|c| i16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(channels, [150, -300, 400]);
+    }
+
+    #[test]
+    fn downscaled_keeps_yuy2_pairs() {
+        // 8x1のYUY2（4ペア）
+        let buffer = ImageBuffer::new((0..16).collect());
+        let (scaled, width, height) = buffer.downscaled(8, 1, InputPixelFormat::Yuy2, 4);
+        assert_eq!((width, height), (4, 1));
+        assert_eq!(&*scaled, &[0, 1, 2, 3, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    #[should_panic(expected = "buffer length does not match the image size")]
+    fn downscaled_rejects_mismatched_size() {
+        ImageBuffer::new(vec![0; 3]).downscaled(1, 1, InputPixelFormat::Bgra, 1);
+    }
+}
@@ -3,13 +3,14 @@ use std::num::NonZeroIsize;
 use crate::{
     common::{AnyResult, LeakManager, format_file_filters, load_wide_string},
     input::{
-        AudioFormat, AudioInputInfo, AudioReturner, ImageReturner, InputInfo, InputPixelFormat,
-        InputPlugin, InputPluginTable, VideoInputInfo,
+        AlphaMode, AudioFormat, AudioInputInfo, AudioReturner, ConcurrencyMode, ImageReturner,
+        InputInfo, InputPixelFormat, InputPlugin, InputPluginTable, TrackKind, VideoInputInfo,
+        info_cache::{InputInfoCache, InputInfoCacheKey},
     },
 };
 
 impl InputPixelFormat {
-    fn bytes_count_per_pixel(&self) -> usize {
+    pub(crate) fn bytes_count_per_pixel(&self) -> usize {
         match self {
             InputPixelFormat::Bgr => 3,  // RGB format
             InputPixelFormat::Bgra => 4, // RGBA format
@@ -21,6 +22,59 @@ impl InputPixelFormat {
     }
 }
 
+/// プラグインが返した画像データを、AviUtl2が受け付けるαの形式に変換する。
+fn convert_alpha_to_native(format: InputPixelFormat, alpha: AlphaMode, buffer: &mut [u8]) {
+    if format
+        .native_alpha_mode()
+        .is_none_or(|native| native == alpha)
+    {
+        return;
+    }
+    match (format, alpha) {
+        (InputPixelFormat::Bgra, AlphaMode::Premultiplied) => {
+            crate::utils::unpremultiply_alpha_bytes(buffer)
+        }
+        (InputPixelFormat::Pa64, AlphaMode::Straight) => {
+            with_typed_buffer(buffer, crate::utils::premultiply_alpha_u16)
+        }
+        (InputPixelFormat::Hf64, AlphaMode::Straight) => {
+            with_typed_buffer(buffer, crate::utils::premultiply_alpha_f16)
+        }
+        _ => unreachable!("Unexpected alpha conversion: {format:?} / {alpha:?}"),
+    }
+}
+
+/// プラグインが返した画像データを、AviUtl2が受け付ける色空間に変換する。
+fn convert_colorimetry_to_native(
+    format: InputPixelFormat,
+    colorimetry: crate::pixel::Colorimetry,
+    buffer: &mut [u8],
+) {
+    if format == InputPixelFormat::Yuy2 {
+        crate::pixel::convert_yuy2_bytes(buffer, colorimetry, crate::pixel::Colorimetry::HOST);
+    }
+}
+
+/// バイト列を`T`の配列として扱って処理する。
+fn with_typed_buffer<T>(buffer: &mut [u8], f: impl FnOnce(&mut [T]))
+where
+    T: zerocopy::FromBytes + zerocopy::IntoBytes + zerocopy::Immutable + zerocopy::KnownLayout,
+{
+    use zerocopy::{FromBytes, IntoBytes};
+    match <[T]>::mut_from_bytes(buffer) {
+        Ok(data) => f(data),
+        Err(_) => {
+            // アラインメントが合わない場合はコピーしてから処理する
+            let mut data = buffer
+                .chunks_exact(std::mem::size_of::<T>())
+                .map(|chunk| T::read_from_bytes(chunk).expect("Chunk size mismatch"))
+                .collect::<Vec<T>>();
+            f(&mut data);
+            buffer.copy_from_slice(data.as_bytes());
+        }
+    }
+}
+
 impl AudioFormat {
     fn bytes_per_sample(&self) -> usize {
         match self {
@@ -95,10 +149,29 @@ pub struct InternalInputPluginState<T: Send + Sync + InputPlugin> {
     plugin_info: InputPluginTable,
     global_leak_manager: LeakManager,
     leak_manager: LeakManager,
+    /// [`ConcurrencyMode::None`] の場合に、すべての入力ハンドルへの呼び出しを直列化するためのロック。
+    global_handle_lock: std::sync::Mutex<()>,
+    /// [`InputPluginTable::share_handles`] が有効な場合に、開いている入力ハンドルを正規化したパスごとに保持する。
+    shared_handles: std::sync::Mutex<
+        std::collections::HashMap<
+            std::path::PathBuf,
+            std::sync::Weak<SharedInputHandle<T::InputHandle>>,
+        >,
+    >,
+    /// `func_open`で返し、まだ`func_close`に渡されていない入力ハンドルのアドレス。
+    live_handles: std::sync::Mutex<std::collections::HashMap<usize, LiveHandle>>,
+    /// [`InputPluginTable::cache_input_info`] が有効な場合の、入力情報のキャッシュ。最初に使うときに読み込む。
+    info_cache: std::sync::OnceLock<InputInfoCache>,
 
     instance: T,
 }
 
+/// 開いている入力ハンドルの情報。リークの検出に使う。
+struct LiveHandle {
+    path: std::path::PathBuf,
+    opened_at: std::time::Instant,
+}
+
 impl<T: Send + Sync + InputPlugin> InternalInputPluginState<T> {
     pub fn new(instance: T) -> Self {
         let plugin_info = instance.plugin_info();
@@ -106,18 +179,370 @@ impl<T: Send + Sync + InputPlugin> InternalInputPluginState<T> {
             plugin_info,
             global_leak_manager: LeakManager::new(),
             leak_manager: LeakManager::new(),
+            global_handle_lock: std::sync::Mutex::new(()),
+            shared_handles: std::sync::Mutex::new(std::collections::HashMap::new()),
+            live_handles: std::sync::Mutex::new(std::collections::HashMap::new()),
+            info_cache: std::sync::OnceLock::new(),
             instance,
         }
     }
+
+    fn validate_concurrency(&self) -> AnyResult<()> {
+        if let ConcurrencyMode::Full(marker) = &self.plugin_info.concurrency {
+            anyhow::ensure!(
+                marker.is_for::<T::InputHandle>(),
+                "ConcurrencyMode::Full was created for `{}`, but the input handle type is `{}`",
+                marker.type_name(),
+                std::any::type_name::<T::InputHandle>()
+            );
+        }
+        Ok(())
+    }
+
+    /// [`ConcurrencyMode::None`] の場合に、プラグイン全体のロックを取得する。
+    fn lock_global(&self) -> Option<std::sync::MutexGuard<'_, ()>> {
+        (self.plugin_info.concurrency == ConcurrencyMode::None).then(|| {
+            self.global_handle_lock
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+        })
+    }
+
+    /// 入力ハンドルを排他的にロックする。
+    ///
+    /// [`ConcurrencyMode::None`] の場合は、プラグイン全体のロックも取得します。
+    fn lock_handle<'a>(
+        &'a self,
+        handle: &'a InternalInputHandle<T::InputHandle>,
+    ) -> AnyResult<InputHandleGuard<'a, T::InputHandle>> {
+        let opened = self.ensure_opened(handle)?;
+        let global = self.lock_global();
+        let handle = opened
+            .shared
+            .handle
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        Ok(InputHandleGuard {
+            handle,
+            _global: global,
+        })
+    }
+
+    /// 入力ハンドルを返す。キャッシュを使ってまだ開いていない場合は、ここで開く。
+    fn ensure_opened<'a>(
+        &self,
+        handle: &'a InternalInputHandle<T::InputHandle>,
+    ) -> AnyResult<&'a OpenedInputHandle<T::InputHandle>> {
+        if let Some(opened) = handle.opened.get() {
+            return Ok(opened);
+        }
+        let _open_lock = handle.open_lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(opened) = handle.opened.get() {
+            return Ok(opened);
+        }
+        tracing::debug!("Opening cached input {}", handle.path.display());
+        let (shared_key, shared) = self.open_handle(handle.path.clone())?;
+        Ok(handle
+            .opened
+            .get_or_init(|| OpenedInputHandle { shared_key, shared }))
+    }
+
+    /// 入力情報のキャッシュを返す。[`InputPluginTable::cache_input_info`] が無効な場合は`None`を返す。
+    fn info_cache(&self) -> Option<&InputInfoCache> {
+        if !self.plugin_info.cache_input_info {
+            return None;
+        }
+        Some(self.info_cache.get_or_init(|| {
+            match crate::config::try_app_data_path() {
+                Some(app_data_path) => InputInfoCache::load(
+                    app_data_path
+                        .join("input_info_cache")
+                        .join(super::info_cache::cache_file_name(&self.plugin_info.name)),
+                ),
+                None => {
+                    tracing::warn!(
+                        "App data path is not available, input info cache will not be saved"
+                    );
+                    InputInfoCache::in_memory()
+                }
+            }
+        }))
+    }
+
+    /// 入力ハンドルのキーに一致するキャッシュを返す。
+    fn cached_input(
+        &self,
+        handle: &InternalInputHandle<T::InputHandle>,
+    ) -> Option<super::info_cache::CachedInput> {
+        self.info_cache()?.get(handle.cache_key.as_ref()?)
+    }
+
+    /// プラグインから取得した情報をキャッシュに記録する。
+    fn record_input(
+        &self,
+        handle: &InternalInputHandle<T::InputHandle>,
+        f: impl FnOnce(&mut super::info_cache::CachedInput),
+    ) {
+        if let (Some(cache), Some(key)) = (self.info_cache(), handle.cache_key.as_ref()) {
+            cache.update(key, f);
+        }
+    }
+
+    /// 変更があれば入力情報のキャッシュを書き出す。
+    fn save_info_cache(&self) {
+        let Some(cache) = self.info_cache.get() else {
+            return;
+        };
+        if let Err(e) = cache.save() {
+            tracing::warn!("Failed to save input info cache: {}", e);
+        }
+    }
+
+    /// 入力ハンドルを開く。
+    ///
+    /// [`InputPluginTable::share_handles`] が有効で、同じファイルの入力ハンドルが既に開かれている場合はそれを返します。
+    fn open_handle(
+        &self,
+        path: std::path::PathBuf,
+    ) -> AnyResult<(
+        Option<std::path::PathBuf>,
+        std::sync::Arc<SharedInputHandle<T::InputHandle>>,
+    )> {
+        if !self.plugin_info.share_handles {
+            let handle = self.instance.open(path)?;
+            return Ok((None, std::sync::Arc::new(SharedInputHandle::new(handle))));
+        }
+
+        let key = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        // NOTE: 同じファイルが同時に開かれても1回だけ`open`するため、`open`の間もロックを保持する
+        let mut shared_handles = self
+            .shared_handles
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(shared) = shared_handles.get(&key).and_then(std::sync::Weak::upgrade) {
+            tracing::debug!("Reusing input handle for {}", key.display());
+            return Ok((Some(key), shared));
+        }
+        let handle = self.instance.open(path)?;
+        let shared = std::sync::Arc::new(SharedInputHandle::new(handle));
+        shared_handles.insert(key.clone(), std::sync::Arc::downgrade(&shared));
+        Ok((Some(key), shared))
+    }
+
+    /// 入力ハンドルへの参照を1つ手放す。
+    ///
+    /// 最後の参照だった場合は、[`InputPlugin::close`] に渡すための入力ハンドルを返します。
+    fn release_handle(
+        &self,
+        key: Option<&std::path::Path>,
+        shared: std::sync::Arc<SharedInputHandle<T::InputHandle>>,
+    ) -> Option<T::InputHandle> {
+        let shared = match key {
+            Some(key) => {
+                let mut shared_handles = self
+                    .shared_handles
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                let shared = std::sync::Arc::into_inner(shared)?;
+                shared_handles.remove(key);
+                shared
+            }
+            None => std::sync::Arc::into_inner(shared)?,
+        };
+        Some(
+            shared
+                .handle
+                .into_inner()
+                .unwrap_or_else(|e| e.into_inner()),
+        )
+    }
+
+    /// 入力ハンドルを開いたことを記録し、AviUtl2に渡すポインタを返す。
+    fn register_handle(
+        &self,
+        path: std::path::PathBuf,
+        handle: Box<InternalInputHandle<T::InputHandle>>,
+    ) -> aviutl2_sys::input2::INPUT_HANDLE {
+        let ih = Box::into_raw(handle) as aviutl2_sys::input2::INPUT_HANDLE;
+        self.live_handles
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                ih as usize,
+                LiveHandle {
+                    path,
+                    opened_at: std::time::Instant::now(),
+                },
+            );
+        ih
+    }
+
+    /// 入力ハンドルの記録を消し、所有権を取り戻す。
+    ///
+    /// `func_open`で返していないポインタや、既に閉じたポインタの場合はエラーを返します。
+    fn unregister_handle(
+        &self,
+        ih: aviutl2_sys::input2::INPUT_HANDLE,
+    ) -> AnyResult<Box<InternalInputHandle<T::InputHandle>>> {
+        let removed = self
+            .live_handles
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&(ih as usize));
+        anyhow::ensure!(
+            removed.is_some(),
+            "Input handle {ih:p} is unknown or already closed"
+        );
+        // SAFETY: `live_handles`に残っていたポインタは`register_handle`で作成され、まだ解放されていない
+        Ok(unsafe { Box::from_raw(ih as *mut InternalInputHandle<T::InputHandle>) })
+    }
+
+    /// 入力ハンドルを閉じる。
+    ///
+    /// 他の入力ハンドルと共有している場合は、最後の1つが閉じられたときに [`InputPlugin::close`] を呼び出します。
+    fn close_handle(&self, handle: Box<InternalInputHandle<T::InputHandle>>) -> AnyResult<()> {
+        let Some(OpenedInputHandle { shared_key, shared }) = handle.opened.into_inner() else {
+            // キャッシュを使ったため、プラグインの入力ハンドルは開いていない
+            return Ok(());
+        };
+        let Some(handle) = self.release_handle(shared_key.as_deref(), shared) else {
+            // 他のオブジェクトがまだ入力ハンドルを使っている
+            return Ok(());
+        };
+        let _global = self.lock_global();
+        self.instance.close(handle)
+    }
+
+    /// AviUtl2が閉じなかった入力ハンドルを警告として出力し、閉じる。
+    fn close_leaked_handles(&self) {
+        let leaked =
+            std::mem::take(&mut *self.live_handles.lock().unwrap_or_else(|e| e.into_inner()));
+        if leaked.is_empty() {
+            return;
+        }
+        let now = std::time::Instant::now();
+        let mut leaked = leaked.into_iter().collect::<Vec<_>>();
+        leaked.sort_by_key(|(_, live)| live.opened_at);
+        tracing::warn!(
+            "{} input handle(s) were not closed by AviUtl2: {}",
+            leaked.len(),
+            leaked
+                .iter()
+                .map(|(_, live)| format!(
+                    "{} (opened {:.1}s ago)",
+                    live.path.display(),
+                    now.duration_since(live.opened_at).as_secs_f64()
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        for (ih, _) in leaked {
+            // SAFETY: `live_handles`に残っていたポインタは`register_handle`で作成され、まだ解放されていない
+            let handle = unsafe { Box::from_raw(ih as *mut InternalInputHandle<T::InputHandle>) };
+            if let Err(e) = self.close_handle(handle) {
+                tracing::error!("Error while closing leaked input handle: {}", e);
+            }
+        }
+    }
 }
 
-struct InternalInputHandle<T: Send + Sync> {
-    input_info: Option<InputInfo>,
+struct InputHandleGuard<'a, H> {
+    // NOTE: 入力ハンドルのロックを先に解放するため、`_global`より前に置く
+    handle: std::sync::RwLockWriteGuard<'a, H>,
+    _global: Option<std::sync::MutexGuard<'a, ()>>,
+}
+
+impl<H> std::ops::Deref for InputHandleGuard<'_, H> {
+    type Target = H;
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+impl<H> std::ops::DerefMut for InputHandleGuard<'_, H> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.handle
+    }
+}
+
+struct InternalInputHandle<T: Send> {
+    input_info: std::sync::Mutex<Option<InputInfo>>,
     num_tracks: std::sync::Mutex<Option<AnyResult<(u32, u32)>>>,
     current_video_track: std::sync::OnceLock<u32>,
     current_audio_track: std::sync::OnceLock<u32>,
+    /// 動画の読み込みのエラーをログに出力したかどうか。
+    read_video_error_logged: std::sync::atomic::AtomicBool,
+    /// [`AudioReturner::write_resampled`]の変換の状態。
+    resampler: std::sync::Arc<std::sync::Mutex<super::resample::Resampler>>,
+
+    /// `func_open`に渡されたパス。
+    path: std::path::PathBuf,
+    /// [`InputPluginTable::cache_input_info`] が有効な場合の、キャッシュのキー。
+    cache_key: Option<InputInfoCacheKey>,
+    /// プラグインの入力ハンドル。キャッシュを使った場合は、必要になるまで開かない。
+    opened: std::sync::OnceLock<OpenedInputHandle<T>>,
+    /// 同じ入力ハンドルを2回開かないためのロック。
+    open_lock: std::sync::Mutex<()>,
+}
+
+struct OpenedInputHandle<T> {
+    /// [`InputPluginTable::share_handles`] が有効な場合の、共有に使う正規化したパス。
+    shared_key: Option<std::path::PathBuf>,
+    shared: std::sync::Arc<SharedInputHandle<T>>,
+}
 
-    handle: T,
+/// プラグインの入力ハンドル。
+///
+/// [`InputPluginTable::share_handles`] が有効な場合は、複数の [`InternalInputHandle`] から共有されます。
+struct SharedInputHandle<T> {
+    // NOTE:
+    // 共有ロック（`read`）は`ConcurrencyMode::Full`の場合のみ使用する。
+    // `ConcurrencyMode::Full`は`SyncHandleMarker`によって`T: Sync`であることが保証されているため、
+    // それ以外の場合は`T: Send`のみで健全になる。
+    handle: std::sync::RwLock<T>,
+}
+
+impl<T> SharedInputHandle<T> {
+    fn new(handle: T) -> Self {
+        Self {
+            handle: std::sync::RwLock::new(handle),
+        }
+    }
+}
+
+// SAFETY: 上記のNOTEの通り、`T: Sync`でない場合は排他ロック（`write`）経由でしかアクセスしない
+unsafe impl<T: Send> Send for SharedInputHandle<T> {}
+unsafe impl<T: Send> Sync for SharedInputHandle<T> {}
+
+impl<T: Send> InternalInputHandle<T> {
+    /// # Safety
+    ///
+    /// `ih`は`func_open`で作成され、まだ`func_close`に渡されていない入力ハンドルである必要があります。
+    unsafe fn from_raw<'a>(ih: aviutl2_sys::input2::INPUT_HANDLE) -> &'a Self {
+        unsafe { &*(ih as *const Self) }
+    }
+
+    fn video_info(&self) -> VideoInputInfo {
+        self.input_info
+            .lock()
+            .unwrap()
+            .as_ref()
+            .expect("Unreachable: Input info not set")
+            .video
+            .clone()
+            .expect("Unreachable: Video format not set")
+    }
+
+    fn audio_info(&self) -> AudioInputInfo {
+        self.input_info
+            .lock()
+            .unwrap()
+            .as_ref()
+            .expect("Unreachable: Input info not set")
+            .audio
+            .clone()
+            .expect("Unreachable: Audio format not set")
+    }
 }
 
 pub unsafe fn initialize_plugin_c<T: InputSingleton>(version: u32) -> bool {
@@ -148,13 +573,20 @@ pub unsafe fn initialize_plugin_c_unwind<T: InputSingleton>(version: u32) -> boo
 }
 
 pub(crate) fn initialize_plugin<T: InputSingleton>(version: u32) -> AnyResult<()> {
+    crate::common::ensure_minimum_aviutl2_version(version.into())?;
+    let info = crate::common::AviUtl2Info::new(version);
+    initialize_plugin_with::<T>(version, T::new(info)?)
+}
+
+/// 作成済みのインスタンスでプラグインを初期化する。
+pub(crate) fn initialize_plugin_with<T: InputSingleton>(
+    version: u32,
+    instance: T,
+) -> AnyResult<()> {
     crate::common::ensure_minimum_aviutl2_version(version.into())?;
     let plugin_state = T::__get_singleton_state();
-    let info = crate::common::AviUtl2Info {
-        version: version.into(),
-    };
-    let internal = T::new(info)?;
-    let plugin = InternalInputPluginState::new(internal);
+    let plugin = InternalInputPluginState::new(instance);
+    plugin.validate_concurrency()?;
     *plugin_state.write().unwrap() = Some(plugin);
 
     Ok(())
@@ -163,6 +595,11 @@ pub(crate) fn initialize_plugin<T: InputSingleton>(version: u32) -> AnyResult<()
 pub unsafe fn uninitialize_plugin<T: InputSingleton>() {
     let plugin_state = T::__get_singleton_state();
     let mut plugin_state = plugin_state.write().unwrap();
+    if let Some(plugin_state) = plugin_state.as_ref() {
+        plugin_state.close_leaked_handles();
+        plugin_state.save_info_cache();
+        plugin_state.instance.on_unload();
+    }
     *plugin_state = None;
 }
 
@@ -192,7 +629,7 @@ fn create_table_impl<T: InputSingleton>(
     let information = plugin_info.information.clone();
 
     let mut flag = plugin_info.input_type.to_bits();
-    if plugin_info.concurrent {
+    if plugin_info.concurrency.is_concurrent() {
         flag |= aviutl2_sys::input2::INPUT_PLUGIN_TABLE::FLAG_CONCURRENT;
     }
     flag |= aviutl2_sys::input2::INPUT_PLUGIN_TABLE::FLAG_MULTI_TRACK;
@@ -284,26 +721,44 @@ extern "C" fn func_open<T: InputSingleton>(
     let plugin_state = plugin_state.read().unwrap();
     let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
     plugin_state.leak_manager.free_leaked_memory();
-    let path = unsafe { load_wide_string(file) };
-    tracing::info!("func_open called with path: {}", path);
-    let plugin = &plugin_state.instance;
-    match plugin.open(std::path::PathBuf::from(path)) {
-        Ok(handle) => {
-            let boxed_handle: Box<InternalInputHandle<T::InputHandle>> =
-                Box::new(InternalInputHandle {
-                    input_info: None,
-                    num_tracks: std::sync::Mutex::new(None),
-                    current_video_track: std::sync::OnceLock::new(),
-                    current_audio_track: std::sync::OnceLock::new(),
-                    handle,
-                });
-            Box::into_raw(boxed_handle) as aviutl2_sys::input2::INPUT_HANDLE
-        }
-        Err(e) => {
-            tracing::error!("Error during func_open: {}", e);
-            std::ptr::null_mut()
-        }
+    let path = std::path::PathBuf::from(unsafe { load_wide_string(file) });
+    tracing::info!("func_open called with path: {}", path.display());
+    let cache_key = if plugin_state.plugin_info.cache_input_info {
+        InputInfoCacheKey::for_file(&path, plugin_state.instance.cache_generation())
+            .map_err(|e| {
+                tracing::debug!(
+                    "Input info cache is not available for {}: {}",
+                    path.display(),
+                    e
+                );
+            })
+            .ok()
+    } else {
+        None
+    };
+    let boxed_handle: Box<InternalInputHandle<T::InputHandle>> = Box::new(InternalInputHandle {
+        input_info: std::sync::Mutex::new(None),
+        num_tracks: std::sync::Mutex::new(None),
+        current_video_track: std::sync::OnceLock::new(),
+        current_audio_track: std::sync::OnceLock::new(),
+        read_video_error_logged: std::sync::atomic::AtomicBool::new(false),
+        resampler: Default::default(),
+        path: path.clone(),
+        cache_key,
+        opened: std::sync::OnceLock::new(),
+        open_lock: std::sync::Mutex::new(()),
+    });
+    let cached = plugin_state
+        .cached_input(&boxed_handle)
+        .is_some_and(|cached| cached.track_counts.is_some());
+    if cached {
+        tracing::debug!("Using cached input info for {}", path.display());
+    } else if let Err(e) = plugin_state.ensure_opened(&boxed_handle) {
+        // キャッシュが無い場合は、開けないファイルをここでエラーにする
+        tracing::error!("Error during func_open: {}", e);
+        return std::ptr::null_mut();
     }
+    plugin_state.register_handle(path, boxed_handle)
 }
 extern "C" fn func_open_unwind<T: InputSingleton>(
     file: aviutl2_sys::common::LPCWSTR,
@@ -322,9 +777,10 @@ extern "C" fn func_close<T: InputSingleton>(ih: aviutl2_sys::input2::INPUT_HANDL
     let plugin_state = plugin_state.read().unwrap();
     let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
     plugin_state.leak_manager.free_leaked_memory();
-    let handle = unsafe { Box::from_raw(ih as *mut InternalInputHandle<T::InputHandle>) };
-    let plugin = &plugin_state.instance;
-    match plugin.close(handle.handle) {
+    let close_result = plugin_state
+        .unregister_handle(ih)
+        .and_then(|handle| plugin_state.close_handle(handle));
+    match close_result {
         Ok(()) => true,
         Err(e) => {
             tracing::error!("Error during func_close: {}", e);
@@ -350,7 +806,7 @@ extern "C" fn func_info_get<T: InputSingleton>(
     let plugin_state = plugin_state.read().unwrap();
     let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
     plugin_state.leak_manager.free_leaked_memory();
-    let handle = unsafe { &mut *(ih as *mut InternalInputHandle<T::InputHandle>) };
+    let handle = unsafe { InternalInputHandle::<T::InputHandle>::from_raw(ih) };
     let video_track = {
         *handle
             .current_video_track
@@ -365,9 +821,23 @@ extern "C" fn func_info_get<T: InputSingleton>(
     };
     let plugin = &plugin_state.instance;
 
-    match T::get_input_info(plugin, &mut handle.handle, video_track, audio_track) {
+    let cached = plugin_state
+        .cached_input(handle)
+        .and_then(|cached| cached.info((video_track, audio_track)).cloned());
+    let input_info = match cached {
+        Some(info) => Ok(info),
+        None => plugin_state
+            .lock_handle(handle)
+            .and_then(|mut guard| T::get_input_info(plugin, &mut guard, video_track, audio_track))
+            .inspect(|info| {
+                plugin_state.record_input(handle, |cached| {
+                    cached.set_info((video_track, audio_track), info.clone())
+                });
+            }),
+    };
+    match input_info {
         Ok(info) => {
-            handle.input_info = Some(info.clone());
+            *handle.input_info.lock().unwrap() = Some(info.clone());
             if let Some(video_info) = info.video {
                 let fps = video_info.fps;
                 let num_frames = video_info.num_frames;
@@ -433,27 +903,54 @@ extern "C" fn func_read_video<T: InputSingleton>(
     let plugin_state = plugin_state.read().unwrap();
     let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
     plugin_state.leak_manager.free_leaked_memory();
-    let handle = unsafe { &mut *(ih as *mut InternalInputHandle<T::InputHandle>) };
+    let handle = unsafe { InternalInputHandle::<T::InputHandle>::from_raw(ih) };
     let plugin = &plugin_state.instance;
     let frame = frame as u32;
-    let output_size = {
-        let video_format = handle
-            .input_info
-            .as_ref()
-            .expect("Unreachable: Input info not set")
-            .video
-            .as_ref()
-            .expect("Unreachable: Video format not set");
-        (video_format.width as usize)
-            .checked_mul(video_format.height as usize)
-            .and_then(|size| size.checked_mul(video_format.format.bytes_count_per_pixel()))
-            .expect("Video output buffer size overflow")
-    };
-    let mut returner = unsafe { ImageReturner::new(buf as *mut u8, output_size) };
-    let read_result = if plugin_state.plugin_info.concurrent {
-        T::read_video(plugin, &handle.handle, frame, &mut returner)
+    let video_format = handle.video_info();
+    let output_size = (video_format.width as usize)
+        .checked_mul(video_format.height as usize)
+        .and_then(|size| size.checked_mul(video_format.format.bytes_count_per_pixel()))
+        .expect("Video output buffer size overflow");
+    let track = handle.current_video_track.get().copied().unwrap_or(0);
+    let mut returner = unsafe { ImageReturner::new(buf as *mut u8, output_size) }
+        .with_track(track)
+        .with_format(video_format.format, video_format.colorimetry);
+    let read_result = if let ConcurrencyMode::Full(_) = plugin_state.plugin_info.concurrency {
+        plugin_state.ensure_opened(handle).and_then(|opened| {
+            let guard = opened
+                .shared
+                .handle
+                .read()
+                .unwrap_or_else(|e| e.into_inner());
+            T::read_video(plugin, &guard, frame, &mut returner)
+        })
     } else {
-        T::read_video_mut(plugin, &mut handle.handle, frame, &mut returner)
+        plugin_state
+            .lock_handle(handle)
+            .and_then(|mut guard| T::read_video_mut(plugin, &mut guard, frame, &mut returner))
+    };
+    let read_result = match read_result {
+        Err(e) if !plugin_state.plugin_info.fail_on_read_error => {
+            // 1つのフレームの失敗でオブジェクト全体が表示されなくならないよう、代わりの画像を返す
+            if !handle
+                .read_video_error_logged
+                .swap(true, std::sync::atomic::Ordering::Relaxed)
+            {
+                tracing::error!(
+                    "Error during func_read_video (frame {frame}), returning placeholder: {e}"
+                );
+            } else {
+                tracing::debug!("Error during func_read_video (frame {frame}): {e}");
+            }
+            returner.written = 0;
+            returner.write_error_placeholder(
+                video_format.width,
+                video_format.height,
+                &e.to_string(),
+            );
+            Ok(())
+        }
+        read_result => read_result,
     };
     match read_result {
         Ok(()) => {
@@ -464,6 +961,10 @@ extern "C" fn func_read_video<T: InputSingleton>(
                     "Image data size does not match expected size"
                 );
             }
+            let buffer =
+                unsafe { std::slice::from_raw_parts_mut(buf as *mut u8, returner.written) };
+            convert_alpha_to_native(video_format.format, video_format.alpha, buffer);
+            convert_colorimetry_to_native(video_format.format, video_format.colorimetry, buffer);
             returner.written as i32
         }
         Err(e) => {
@@ -498,16 +999,11 @@ extern "C" fn func_read_audio<T: InputSingleton>(
     let plugin_state = plugin_state.read().unwrap();
     let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
     plugin_state.leak_manager.free_leaked_memory();
-    let handle = unsafe { &mut *(ih as *mut InternalInputHandle<T::InputHandle>) };
+    let handle = unsafe { InternalInputHandle::<T::InputHandle>::from_raw(ih) };
     let plugin = &plugin_state.instance;
+    let audio_info = handle.audio_info();
     let (output_size, block_align) = {
-        let audio_format = handle
-            .input_info
-            .as_ref()
-            .expect("Unreachable: Input info not set")
-            .audio
-            .as_ref()
-            .expect("Unreachable: Audio format not set");
+        let audio_format = &audio_info;
         let block_align = (audio_format.channels as usize)
             .checked_mul(audio_format.format.bytes_per_sample())
             .expect("Audio block alignment overflow");
@@ -518,11 +1014,23 @@ extern "C" fn func_read_audio<T: InputSingleton>(
             .expect("Audio output buffer size overflow");
         (output_size, block_align)
     };
-    let mut returner = unsafe { AudioReturner::new(buf as *mut u8, output_size) };
-    let read_result = if plugin_state.plugin_info.concurrent {
-        T::read_audio(plugin, &handle.handle, start, length, &mut returner)
+    let track = handle.current_audio_track.get().copied().unwrap_or(0);
+    let mut returner = unsafe { AudioReturner::new(buf as *mut u8, output_size) }
+        .with_track(track)
+        .with_request(audio_info, start as i64, handle.resampler.clone());
+    let read_result = if let ConcurrencyMode::Full(_) = plugin_state.plugin_info.concurrency {
+        plugin_state.ensure_opened(handle).and_then(|opened| {
+            let guard = opened
+                .shared
+                .handle
+                .read()
+                .unwrap_or_else(|e| e.into_inner());
+            T::read_audio(plugin, &guard, start, length, &mut returner)
+        })
     } else {
-        T::read_audio_mut(plugin, &mut handle.handle, start, length, &mut returner)
+        plugin_state.lock_handle(handle).and_then(|mut guard| {
+            T::read_audio_mut(plugin, &mut guard, start, length, &mut returner)
+        })
     };
     match read_result {
         Ok(()) => audio_sample_count(returner.written, block_align),
@@ -594,15 +1102,28 @@ extern "C" fn func_set_track<T: InputSingleton>(
     let plugin_state = plugin_state.read().unwrap();
     let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
     plugin_state.leak_manager.free_leaked_memory();
-    let handle = unsafe { &mut *(ih as *mut InternalInputHandle<T::InputHandle>) };
+    let handle = unsafe { InternalInputHandle::<T::InputHandle>::from_raw(ih) };
     let plugin = &plugin_state.instance;
     if track == -1 {
         // track == -1：トラック数取得
         if handle.num_tracks.lock().unwrap().is_none() {
-            let num_tracks = plugin.get_track_count(&mut handle.handle).map_err(|e| {
-                tracing::error!("Failed to get track count: {}", e);
-                e
-            });
+            let cached = plugin_state
+                .cached_input(handle)
+                .and_then(|cached| cached.track_counts);
+            let num_tracks = match cached {
+                Some(num_tracks) => Ok(num_tracks),
+                None => plugin_state
+                    .lock_handle(handle)
+                    .and_then(|mut guard| plugin.get_track_count(&mut guard))
+                    .inspect(|&num_tracks| {
+                        plugin_state
+                            .record_input(handle, |cached| cached.track_counts = Some(num_tracks));
+                    })
+                    .map_err(|e| {
+                        tracing::error!("Failed to get track count: {}", e);
+                        e
+                    }),
+            };
 
             if matches!(num_tracks, Ok((0, _))) {
                 handle
@@ -641,41 +1162,104 @@ extern "C" fn func_set_track<T: InputSingleton>(
         // track != -1：トラック設定
         match track_type {
             aviutl2_sys::input2::INPUT_PLUGIN_TABLE::TRACK_TYPE_VIDEO => {
-                let new_track = plugin
-                    .can_set_video_track(&mut handle.handle, track as u32)
-                    .map_or_else(
-                        |e| {
-                            tracing::debug!("Failed to set video track: {}", e);
-                            -1
-                        },
-                        |t| t as i32,
-                    );
-                handle
-                    .current_video_track
-                    .set(new_track as u32)
-                    .expect("unreachable: func_set_track should only be called once per handle");
+                let cached = plugin_state
+                    .cached_input(handle)
+                    .and_then(|cached| cached.track_result(true, track as u32));
+                let new_track = cached.unwrap_or_else(|| {
+                    let mut guard = match plugin_state.lock_handle(handle) {
+                        Ok(guard) => guard,
+                        Err(e) => {
+                            // ファイルを開けなかった場合は、結果をキャッシュしない
+                            tracing::error!("Error during func_set_track: {}", e);
+                            return -1;
+                        }
+                    };
+                    let new_track = plugin
+                        .can_set_video_track(&mut guard, track as u32)
+                        .map_or_else(
+                            |e| {
+                                tracing::debug!("Failed to set video track: {}", e);
+                                -1
+                            },
+                            |t| t as i32,
+                        );
+                    plugin_state.record_input(handle, |cached| {
+                        cached.set_track_result(true, track as u32, new_track)
+                    });
+                    new_track
+                });
+                if new_track >= 0 {
+                    // 失敗した場合は-1をトラック番号として保存せず、別のトラックで再度設定できるようにする
+                    handle
+                        .current_video_track
+                        .set(new_track as u32)
+                        .expect("unreachable: func_set_track should only succeed once per handle");
+                    // キャッシュを使った場合は、ログのためだけにファイルを開かない
+                    if cached.is_none() {
+                        log_track_info(plugin_state, handle, TrackKind::Video, new_track as u32);
+                    }
+                }
                 new_track
             }
             aviutl2_sys::input2::INPUT_PLUGIN_TABLE::TRACK_TYPE_AUDIO => {
-                let new_track = plugin
-                    .can_set_audio_track(&mut handle.handle, track as u32)
-                    .map_or_else(
-                        |e| {
-                            tracing::debug!("Failed to set audio track: {}", e);
-                            -1
-                        },
-                        |t| t as i32,
-                    );
-                handle
-                    .current_audio_track
-                    .set(new_track as u32)
-                    .expect("unreachable: func_set_track should only be called once per handle");
+                let cached = plugin_state
+                    .cached_input(handle)
+                    .and_then(|cached| cached.track_result(false, track as u32));
+                let new_track = cached.unwrap_or_else(|| {
+                    let mut guard = match plugin_state.lock_handle(handle) {
+                        Ok(guard) => guard,
+                        Err(e) => {
+                            // ファイルを開けなかった場合は、結果をキャッシュしない
+                            tracing::error!("Error during func_set_track: {}", e);
+                            return -1;
+                        }
+                    };
+                    let new_track = plugin
+                        .can_set_audio_track(&mut guard, track as u32)
+                        .map_or_else(
+                            |e| {
+                                tracing::debug!("Failed to set audio track: {}", e);
+                                -1
+                            },
+                            |t| t as i32,
+                        );
+                    plugin_state.record_input(handle, |cached| {
+                        cached.set_track_result(false, track as u32, new_track)
+                    });
+                    new_track
+                });
+                if new_track >= 0 {
+                    // 失敗した場合は-1をトラック番号として保存せず、別のトラックで再度設定できるようにする
+                    handle
+                        .current_audio_track
+                        .set(new_track as u32)
+                        .expect("unreachable: func_set_track should only succeed once per handle");
+                    // キャッシュを使った場合は、ログのためだけにファイルを開かない
+                    if cached.is_none() {
+                        log_track_info(plugin_state, handle, TrackKind::Audio, new_track as u32);
+                    }
+                }
                 new_track
             }
             _ => -1, // Invalid track type
         }
     }
 }
+/// ホストにはトラックの名前を渡せないため、選択されたトラックの情報をログに出力する。
+fn log_track_info<T: Send + Sync + InputPlugin>(
+    plugin_state: &InternalInputPluginState<T>,
+    handle: &InternalInputHandle<T::InputHandle>,
+    kind: TrackKind,
+    track: u32,
+) {
+    let info = plugin_state
+        .lock_handle(handle)
+        .and_then(|mut guard| plugin_state.instance.track_info(&mut guard, kind, track));
+    match info {
+        Ok(info) => tracing::info!("Selected {:?} track: {}", kind, info.label(track)),
+        Err(e) => tracing::debug!("Failed to get {:?} track info: {}", kind, e),
+    }
+}
 extern "C" fn func_set_track_unwind<T: InputSingleton>(
     ih: aviutl2_sys::input2::INPUT_HANDLE,
     track_type: i32,
@@ -699,15 +1283,31 @@ extern "C" fn func_time_to_frame<T: InputSingleton>(
     let plugin_state = plugin_state.read().unwrap();
     let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
     plugin_state.leak_manager.free_leaked_memory();
-    let handle = unsafe { &mut *(ih as *mut InternalInputHandle<T::InputHandle>) };
+    let handle = unsafe { InternalInputHandle::<T::InputHandle>::from_raw(ih) };
     let video_track = {
         *handle
             .current_video_track
             .get()
             .expect("unreachable: func_set_track should have been called before func_time_to_frame")
     };
+    // `manual_frame_index`が`false`の場合、AviUtl2はこの関数を呼ばないはずだが、
+    // 念のためプラグインには渡さずに固定フレームレートとして計算する
+    let constant_rate = handle
+        .input_info
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|info| info.video.as_ref())
+        .filter(|video| !video.manual_frame_index)
+        .map(|video| (video.fps, video.num_frames));
+    if let Some((fps, num_frames)) = constant_rate {
+        return super::timeline::frame_at_constant_rate(fps, num_frames, time) as i32;
+    }
     let plugin = &plugin_state.instance;
-    match T::time_to_frame(plugin, &mut handle.handle, video_track, time) {
+    let frame = plugin_state
+        .lock_handle(handle)
+        .and_then(|mut guard| T::time_to_frame(plugin, &mut guard, video_track, time));
+    match frame {
         Ok(frame) => frame as i32,
         Err(e) => {
             tracing::error!("Error during func_time_to_frame: {}", e);
@@ -875,7 +1475,898 @@ macro_rules! register_input_plugin {
 
 #[cfg(test)]
 mod tests {
-    use super::audio_sample_count;
+    use super::{audio_sample_count, convert_alpha_to_native, convert_colorimetry_to_native};
+    use crate::input::{
+        AlphaMode, ConcurrencyMode, FrameTimeline, ImageReturner, InputInfo, InputPixelFormat,
+        InputPlugin, InputPluginTable, InputType, TimeToFrame, VideoInputInfo,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct StressHandle {
+        active: AtomicUsize,
+        max_active: AtomicUsize,
+        reads: AtomicUsize,
+        waited_for_peer: std::sync::atomic::AtomicBool,
+    }
+
+    impl StressHandle {
+        /// 最初の読み込みが、他のスレッドの読み込みを待つ最大時間。
+        const PEER_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+
+        fn read(&self, returner: &mut ImageReturner) {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(active, Ordering::SeqCst);
+            // 同時に読み込めるなら必ず重なるよう、最初の読み込みは他の読み込みが来るまで待つ
+            if !self.waited_for_peer.swap(true, Ordering::SeqCst) {
+                let started = std::time::Instant::now();
+                while self.max_active.load(Ordering::SeqCst) < 2
+                    && started.elapsed() < Self::PEER_TIMEOUT
+                {
+                    std::thread::sleep(std::time::Duration::from_micros(50));
+                }
+            }
+            std::thread::sleep(std::time::Duration::from_micros(50));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            returner.write(&vec![0u8; 4]);
+        }
+    }
+
+    static MAX_ACTIVE_ON_CLOSE: AtomicUsize = AtomicUsize::new(0);
+
+    /// すべての入力ハンドルが閉じられていることを確認してから、プラグインを終了する。
+    fn uninitialize<T: super::InputSingleton>() {
+        {
+            let plugin_state = T::__get_singleton_state().read().unwrap();
+            let plugin_state = plugin_state.as_ref().unwrap();
+            debug_assert!(
+                plugin_state.live_handles.lock().unwrap().is_empty(),
+                "input handles were leaked"
+            );
+        }
+        unsafe { super::uninitialize_plugin::<T>() };
+    }
+
+    macro_rules! stress_plugin {
+        ($name:ident, $mode:expr) => {
+            struct $name;
+
+            impl InputPlugin for $name {
+                type InputHandle = StressHandle;
+
+                fn new(_info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self> {
+                    Ok(Self)
+                }
+
+                fn plugin_info(&self) -> InputPluginTable {
+                    InputPluginTable {
+                        name: stringify!($name).to_string(),
+                        information: String::new(),
+                        input_type: InputType::Video,
+                        concurrency: $mode,
+                        share_handles: false,
+                        cache_input_info: false,
+                        file_filters: vec![],
+                        fail_on_read_error: false,
+                        can_config: false,
+                    }
+                }
+
+                fn open(
+                    &self,
+                    _file: std::path::PathBuf,
+                ) -> crate::common::AnyResult<Self::InputHandle> {
+                    Ok(StressHandle::default())
+                }
+
+                fn close(&self, handle: Self::InputHandle) -> crate::common::AnyResult<()> {
+                    assert_eq!(handle.reads.load(Ordering::SeqCst), 4 * 64);
+                    MAX_ACTIVE_ON_CLOSE
+                        .store(handle.max_active.load(Ordering::SeqCst), Ordering::SeqCst);
+                    Ok(())
+                }
+
+                fn get_input_info(
+                    &self,
+                    _handle: &mut Self::InputHandle,
+                    _video_track: u32,
+                    _audio_track: u32,
+                ) -> crate::common::AnyResult<InputInfo> {
+                    Ok(InputInfo {
+                        video: Some(VideoInputInfo {
+                            fps: crate::common::Rational32::new(30, 1),
+                            num_frames: 1,
+                            manual_frame_index: false,
+                            width: 1,
+                            height: 1,
+                            format: InputPixelFormat::Bgra,
+                            alpha: AlphaMode::Straight,
+                            colorimetry: crate::pixel::Colorimetry::HOST,
+                        }),
+                        audio: None,
+                    })
+                }
+
+                fn read_video(
+                    &self,
+                    handle: &Self::InputHandle,
+                    _frame: u32,
+                    returner: &mut ImageReturner,
+                ) -> crate::common::AnyResult<()> {
+                    handle.read(returner);
+                    Ok(())
+                }
+
+                fn read_video_mut(
+                    &self,
+                    handle: &mut Self::InputHandle,
+                    _frame: u32,
+                    returner: &mut ImageReturner,
+                ) -> crate::common::AnyResult<()> {
+                    handle.read(returner);
+                    Ok(())
+                }
+            }
+
+            impl super::InputSingleton for $name {
+                fn __get_singleton_state()
+                -> &'static std::sync::RwLock<Option<super::InternalInputPluginState<Self>>> {
+                    static PLUGIN: std::sync::RwLock<
+                        Option<super::InternalInputPluginState<$name>>,
+                    > = std::sync::RwLock::new(None);
+                    &PLUGIN
+                }
+            }
+        };
+    }
+
+    stress_plugin!(NonePlugin, ConcurrencyMode::None);
+    stress_plugin!(PerHandlePlugin, ConcurrencyMode::PerHandle);
+    stress_plugin!(FullPlugin, ConcurrencyMode::full::<StressHandle>());
+    stress_plugin!(MismatchedFullPlugin, ConcurrencyMode::full::<()>());
+
+    static STRESS_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// 1つのハンドルを開き、4スレッドから同時に読み込んだときの最大同時アクセス数を返す。
+    fn hammer_single_handle<T: super::InputSingleton<InputHandle = StressHandle>>() -> usize {
+        let _lock = STRESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        super::initialize_plugin::<T>(crate::common::MINIMUM_AVIUTL2_VERSION.into()).unwrap();
+
+        let path: Vec<u16> = "stress\0".encode_utf16().collect();
+        let ih = super::func_open::<T>(path.as_ptr());
+        assert!(!ih.is_null());
+        let ih_addr = ih as usize;
+        assert_eq!(
+            super::func_set_track::<T>(
+                ih,
+                aviutl2_sys::input2::INPUT_PLUGIN_TABLE::TRACK_TYPE_VIDEO,
+                -1
+            ),
+            1
+        );
+        super::func_set_track::<T>(
+            ih,
+            aviutl2_sys::input2::INPUT_PLUGIN_TABLE::TRACK_TYPE_VIDEO,
+            0,
+        );
+        let mut info: aviutl2_sys::input2::INPUT_INFO = unsafe { std::mem::zeroed() };
+        assert!(super::func_info_get::<T>(ih, &mut info));
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(move || {
+                    let mut buffer = [0u8; 4];
+                    for frame in 0..64 {
+                        let written = super::func_read_video::<T>(
+                            ih_addr as aviutl2_sys::input2::INPUT_HANDLE,
+                            frame,
+                            buffer.as_mut_ptr() as *mut std::ffi::c_void,
+                        );
+                        assert_eq!(written, 4);
+                    }
+                });
+            }
+        });
+
+        assert!(super::func_close::<T>(ih));
+        uninitialize::<T>();
+        MAX_ACTIVE_ON_CLOSE.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn concurrency_none_serializes_reads() {
+        assert_eq!(hammer_single_handle::<NonePlugin>(), 1);
+    }
+
+    #[test]
+    fn concurrency_per_handle_serializes_reads_on_same_handle() {
+        assert_eq!(hammer_single_handle::<PerHandlePlugin>(), 1);
+    }
+
+    #[test]
+    fn concurrency_full_allows_parallel_reads_on_same_handle() {
+        assert!(hammer_single_handle::<FullPlugin>() >= 2);
+    }
+
+    #[test]
+    fn concurrency_full_rejects_marker_for_other_handle_type() {
+        let result = super::initialize_plugin::<MismatchedFullPlugin>(
+            crate::common::MINIMUM_AVIUTL2_VERSION.into(),
+        );
+        assert!(result.is_err());
+    }
+
+    /// 先頭から順にデコードするデコーダーを模した入力ハンドル。
+    struct SequentialHandle {
+        next_frame: u32,
+    }
+
+    static SHARED_OPENS: AtomicUsize = AtomicUsize::new(0);
+    static SHARED_CLOSES: AtomicUsize = AtomicUsize::new(0);
+    static SHARED_UNLOADS: AtomicUsize = AtomicUsize::new(0);
+
+    macro_rules! sequential_plugin {
+        ($name:ident, $share_handles:expr, $cache_input_info:expr) => {
+            struct $name;
+
+            impl InputPlugin for $name {
+                type InputHandle = SequentialHandle;
+
+                fn new(_info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self> {
+                    Ok(Self)
+                }
+
+                fn plugin_info(&self) -> InputPluginTable {
+                    InputPluginTable {
+                        name: stringify!($name).to_string(),
+                        information: String::new(),
+                        input_type: InputType::Video,
+                        concurrency: ConcurrencyMode::PerHandle,
+                        share_handles: $share_handles,
+                        cache_input_info: $cache_input_info,
+                        file_filters: vec![],
+                        fail_on_read_error: false,
+                        can_config: false,
+                    }
+                }
+
+                fn open(
+                    &self,
+                    _file: std::path::PathBuf,
+                ) -> crate::common::AnyResult<Self::InputHandle> {
+                    SHARED_OPENS.fetch_add(1, Ordering::SeqCst);
+                    Ok(SequentialHandle { next_frame: 0 })
+                }
+
+                fn close(&self, _handle: Self::InputHandle) -> crate::common::AnyResult<()> {
+                    SHARED_CLOSES.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+
+                fn on_unload(&self) {
+                    SHARED_UNLOADS.fetch_add(1, Ordering::SeqCst);
+                }
+
+                fn get_input_info(
+                    &self,
+                    _handle: &mut Self::InputHandle,
+                    _video_track: u32,
+                    _audio_track: u32,
+                ) -> crate::common::AnyResult<InputInfo> {
+                    Ok(InputInfo {
+                        video: Some(VideoInputInfo {
+                            fps: crate::common::Rational32::new(30, 1),
+                            num_frames: 16,
+                            manual_frame_index: false,
+                            width: 1,
+                            height: 1,
+                            format: InputPixelFormat::Bgra,
+                            alpha: AlphaMode::Straight,
+                            colorimetry: crate::pixel::Colorimetry::HOST,
+                        }),
+                        audio: None,
+                    })
+                }
+
+                fn read_video_mut(
+                    &self,
+                    handle: &mut Self::InputHandle,
+                    frame: u32,
+                    returner: &mut ImageReturner,
+                ) -> crate::common::AnyResult<()> {
+                    // 巻き戻す場合は先頭からデコードし直す
+                    if frame < handle.next_frame {
+                        handle.next_frame = 0;
+                    }
+                    while handle.next_frame < frame {
+                        handle.next_frame += 1;
+                    }
+                    let decoded = handle.next_frame;
+                    handle.next_frame += 1;
+                    returner.write(&vec![decoded as u8; 4]);
+                    Ok(())
+                }
+            }
+
+            impl super::InputSingleton for $name {
+                fn __get_singleton_state()
+                -> &'static std::sync::RwLock<Option<super::InternalInputPluginState<Self>>> {
+                    static PLUGIN: std::sync::RwLock<
+                        Option<super::InternalInputPluginState<$name>>,
+                    > = std::sync::RwLock::new(None);
+                    &PLUGIN
+                }
+            }
+        };
+    }
+
+    sequential_plugin!(SharedPlugin, true, false);
+    sequential_plugin!(UnsharedPlugin, false, false);
+    sequential_plugin!(CachedPlugin, false, true);
+
+    fn open_video<T: super::InputSingleton>(path: &str) -> aviutl2_sys::input2::INPUT_HANDLE {
+        let path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+        let ih = super::func_open::<T>(path.as_ptr());
+        assert!(!ih.is_null());
+        super::func_set_track::<T>(
+            ih,
+            aviutl2_sys::input2::INPUT_PLUGIN_TABLE::TRACK_TYPE_VIDEO,
+            -1,
+        );
+        super::func_set_track::<T>(
+            ih,
+            aviutl2_sys::input2::INPUT_PLUGIN_TABLE::TRACK_TYPE_VIDEO,
+            0,
+        );
+        let mut info: aviutl2_sys::input2::INPUT_INFO = unsafe { std::mem::zeroed() };
+        assert!(super::func_info_get::<T>(ih, &mut info));
+        ih
+    }
+
+    fn read_frame<T: super::InputSingleton>(
+        ih: aviutl2_sys::input2::INPUT_HANDLE,
+        frame: i32,
+    ) -> u8 {
+        let mut buffer = [0u8; 4];
+        let written =
+            super::func_read_video::<T>(ih, frame, buffer.as_mut_ptr() as *mut std::ffi::c_void);
+        assert_eq!(written, 4);
+        buffer[0]
+    }
+
+    /// 2つのオブジェクトが交互にフレームを要求したときの`open`・`close`の回数を返す。
+    fn ping_pong<T: super::InputSingleton>() -> (usize, usize) {
+        let _lock = STRESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        SHARED_OPENS.store(0, Ordering::SeqCst);
+        SHARED_CLOSES.store(0, Ordering::SeqCst);
+        super::initialize_plugin::<T>(crate::common::MINIMUM_AVIUTL2_VERSION.into()).unwrap();
+
+        let first = open_video::<T>("shared.webp");
+        let second = open_video::<T>("shared.webp");
+        assert_ne!(first, second);
+        for (a, b) in [(0, 15), (1, 14), (7, 7), (8, 3), (2, 12)] {
+            assert_eq!(read_frame::<T>(first, a), a as u8);
+            assert_eq!(read_frame::<T>(second, b), b as u8);
+        }
+
+        assert!(super::func_close::<T>(first));
+        let closes_after_first = SHARED_CLOSES.load(Ordering::SeqCst);
+        // 残った方のハンドルは引き続き使える
+        assert_eq!(read_frame::<T>(second, 5), 5);
+        assert!(super::func_close::<T>(second));
+        let result = (SHARED_OPENS.load(Ordering::SeqCst), closes_after_first);
+        assert_eq!(SHARED_CLOSES.load(Ordering::SeqCst), result.0);
+        uninitialize::<T>();
+        result
+    }
+
+    #[test]
+    fn share_handles_reuses_handle_for_same_file() {
+        assert_eq!(ping_pong::<SharedPlugin>(), (1, 0));
+    }
+
+    #[test]
+    fn share_handles_disabled_opens_each_time() {
+        assert_eq!(ping_pong::<UnsharedPlugin>(), (2, 1));
+    }
+
+    #[test]
+    fn share_handles_reopens_after_all_handles_are_closed() {
+        let _lock = STRESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        SHARED_OPENS.store(0, Ordering::SeqCst);
+        super::initialize_plugin::<SharedPlugin>(crate::common::MINIMUM_AVIUTL2_VERSION.into())
+            .unwrap();
+
+        let first = open_video::<SharedPlugin>("reopen.webp");
+        assert!(super::func_close::<SharedPlugin>(first));
+        let second = open_video::<SharedPlugin>("reopen.webp");
+        assert_eq!(read_frame::<SharedPlugin>(second, 3), 3);
+        assert!(super::func_close::<SharedPlugin>(second));
+
+        assert_eq!(SHARED_OPENS.load(Ordering::SeqCst), 2);
+        uninitialize::<SharedPlugin>();
+    }
+
+    #[test]
+    fn cache_input_info_defers_open_until_read() {
+        let _lock = STRESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        SHARED_OPENS.store(0, Ordering::SeqCst);
+        SHARED_CLOSES.store(0, Ordering::SeqCst);
+        super::initialize_plugin::<CachedPlugin>(crate::common::MINIMUM_AVIUTL2_VERSION.into())
+            .unwrap();
+        let dir = std::env::temp_dir().join(format!(
+            "aviutl2-rs-input-info-cache-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("cached.webp");
+        std::fs::write(&file, b"cached").unwrap();
+        let path = file.to_str().unwrap();
+
+        // 初回はキャッシュが無いので開く
+        let first = open_video::<CachedPlugin>(path);
+        assert_eq!(SHARED_OPENS.load(Ordering::SeqCst), 1);
+        assert!(super::func_close::<CachedPlugin>(first));
+
+        // 2回目以降は、フレームを読み込むまで開かない
+        let unused = open_video::<CachedPlugin>(path);
+        let second = open_video::<CachedPlugin>(path);
+        assert_eq!(SHARED_OPENS.load(Ordering::SeqCst), 1);
+        assert_eq!(read_frame::<CachedPlugin>(second, 3), 3);
+        assert_eq!(SHARED_OPENS.load(Ordering::SeqCst), 2);
+        assert!(super::func_close::<CachedPlugin>(unused));
+        assert!(super::func_close::<CachedPlugin>(second));
+        assert_eq!(SHARED_CLOSES.load(Ordering::SeqCst), 2);
+
+        // ファイルが変わったらキャッシュは使わない
+        std::fs::write(&file, b"modified file").unwrap();
+        let third = open_video::<CachedPlugin>(path);
+        assert_eq!(SHARED_OPENS.load(Ordering::SeqCst), 3);
+        assert!(super::func_close::<CachedPlugin>(third));
+
+        uninitialize::<CachedPlugin>();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn close_rejects_unknown_and_already_closed_handles() {
+        let _lock = STRESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        SHARED_CLOSES.store(0, Ordering::SeqCst);
+        super::initialize_plugin::<UnsharedPlugin>(crate::common::MINIMUM_AVIUTL2_VERSION.into())
+            .unwrap();
+
+        let ih = open_video::<UnsharedPlugin>("double_close.webp");
+        assert!(super::func_close::<UnsharedPlugin>(ih));
+        assert!(!super::func_close::<UnsharedPlugin>(ih));
+        let mut unknown = 0u8;
+        assert!(!super::func_close::<UnsharedPlugin>(
+            (&raw mut unknown).cast()
+        ));
+        assert_eq!(SHARED_CLOSES.load(Ordering::SeqCst), 1);
+        uninitialize::<UnsharedPlugin>();
+    }
+
+    #[test]
+    fn uninitialize_closes_leaked_handles_before_unload() {
+        let _lock = STRESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        SHARED_OPENS.store(0, Ordering::SeqCst);
+        SHARED_CLOSES.store(0, Ordering::SeqCst);
+        SHARED_UNLOADS.store(0, Ordering::SeqCst);
+        super::initialize_plugin::<SharedPlugin>(crate::common::MINIMUM_AVIUTL2_VERSION.into())
+            .unwrap();
+
+        let _first = open_video::<SharedPlugin>("leaked.webp");
+        let _second = open_video::<SharedPlugin>("leaked.webp");
+        let _other = open_video::<SharedPlugin>("leaked_other.webp");
+        assert_eq!(SHARED_CLOSES.load(Ordering::SeqCst), 0);
+
+        unsafe { super::uninitialize_plugin::<SharedPlugin>() };
+        assert_eq!(SHARED_CLOSES.load(Ordering::SeqCst), 2);
+        assert_eq!(SHARED_UNLOADS.load(Ordering::SeqCst), 1);
+    }
+
+    struct MultiTrackPlugin;
+
+    impl InputPlugin for MultiTrackPlugin {
+        type InputHandle = ();
+
+        fn new(_info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self> {
+            Ok(Self)
+        }
+
+        fn plugin_info(&self) -> InputPluginTable {
+            InputPluginTable {
+                name: "MultiTrackPlugin".to_string(),
+                information: String::new(),
+                input_type: InputType::Video,
+                concurrency: ConcurrencyMode::PerHandle,
+                share_handles: true,
+                cache_input_info: false,
+                file_filters: vec![],
+                fail_on_read_error: false,
+                can_config: false,
+            }
+        }
+
+        fn open(&self, _file: std::path::PathBuf) -> crate::common::AnyResult<Self::InputHandle> {
+            Ok(())
+        }
+
+        fn close(&self, _handle: Self::InputHandle) -> crate::common::AnyResult<()> {
+            Ok(())
+        }
+
+        fn get_track_count(
+            &self,
+            _handle: &mut Self::InputHandle,
+        ) -> crate::common::AnyResult<(u32, u32)> {
+            Ok((3, 0))
+        }
+
+        fn can_set_video_track(
+            &self,
+            _handle: &mut Self::InputHandle,
+            track: u32,
+        ) -> crate::common::AnyResult<u32> {
+            anyhow::ensure!(track < 3, "Track {track} does not exist");
+            Ok(track)
+        }
+
+        fn get_input_info(
+            &self,
+            _handle: &mut Self::InputHandle,
+            video_track: u32,
+            _audio_track: u32,
+        ) -> crate::common::AnyResult<InputInfo> {
+            Ok(InputInfo {
+                video: Some(VideoInputInfo {
+                    fps: crate::common::Rational32::new(30, 1),
+                    num_frames: 1,
+                    manual_frame_index: false,
+                    width: 1,
+                    height: video_track + 1,
+                    format: InputPixelFormat::Bgra,
+                    alpha: AlphaMode::Straight,
+                    colorimetry: crate::pixel::Colorimetry::HOST,
+                }),
+                audio: None,
+            })
+        }
+
+        fn read_video_mut(
+            &self,
+            _handle: &mut Self::InputHandle,
+            _frame: u32,
+            returner: &mut ImageReturner,
+        ) -> crate::common::AnyResult<()> {
+            let track = returner.track();
+            returner.write(&vec![track as u8; 4 * (track as usize + 1)]);
+            Ok(())
+        }
+    }
+
+    impl super::InputSingleton for MultiTrackPlugin {
+        fn __get_singleton_state()
+        -> &'static std::sync::RwLock<Option<super::InternalInputPluginState<Self>>> {
+            static PLUGIN: std::sync::RwLock<
+                Option<super::InternalInputPluginState<MultiTrackPlugin>>,
+            > = std::sync::RwLock::new(None);
+            &PLUGIN
+        }
+    }
+
+    #[test]
+    fn shared_handle_reads_each_selected_video_track() {
+        type T = MultiTrackPlugin;
+        let _lock = STRESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        super::initialize_plugin::<T>(crate::common::MINIMUM_AVIUTL2_VERSION.into()).unwrap();
+
+        let path: Vec<u16> = "layers.psd\0".encode_utf16().collect();
+        let open_track = |track: i32| {
+            let ih = super::func_open::<T>(path.as_ptr());
+            assert!(!ih.is_null());
+            assert_eq!(
+                super::func_set_track::<T>(
+                    ih,
+                    aviutl2_sys::input2::INPUT_PLUGIN_TABLE::TRACK_TYPE_VIDEO,
+                    -1
+                ),
+                3
+            );
+            // 存在しないトラックの選択に失敗しても、別のトラックを選択し直せる
+            assert_eq!(
+                super::func_set_track::<T>(
+                    ih,
+                    aviutl2_sys::input2::INPUT_PLUGIN_TABLE::TRACK_TYPE_VIDEO,
+                    5
+                ),
+                -1
+            );
+            assert_eq!(
+                super::func_set_track::<T>(
+                    ih,
+                    aviutl2_sys::input2::INPUT_PLUGIN_TABLE::TRACK_TYPE_VIDEO,
+                    track
+                ),
+                track
+            );
+            let mut info: aviutl2_sys::input2::INPUT_INFO = unsafe { std::mem::zeroed() };
+            assert!(super::func_info_get::<T>(ih, &mut info));
+            ih
+        };
+        let read = |ih, track: usize| {
+            let mut buffer = vec![0xFFu8; 4 * (track + 1)];
+            let written =
+                super::func_read_video::<T>(ih, 0, buffer.as_mut_ptr() as *mut std::ffi::c_void);
+            assert_eq!(written as usize, buffer.len());
+            buffer
+        };
+
+        let first = open_track(1);
+        let second = open_track(2);
+        for _ in 0..2 {
+            assert!(read(first, 1).iter().all(|&b| b == 1));
+            assert!(read(second, 2).iter().all(|&b| b == 2));
+        }
+        assert!(super::func_close::<T>(first));
+        assert!(super::func_close::<T>(second));
+        uninitialize::<T>();
+    }
+
+    macro_rules! corrupt_frame_plugin {
+        ($name:ident, $fail_on_read_error:expr) => {
+            /// 奇数フレームの読み込みに失敗する、2x2のBGRAの動画。
+            struct $name;
+
+            impl InputPlugin for $name {
+                type InputHandle = ();
+
+                fn new(_info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self> {
+                    Ok(Self)
+                }
+
+                fn plugin_info(&self) -> InputPluginTable {
+                    InputPluginTable {
+                        name: stringify!($name).to_string(),
+                        information: String::new(),
+                        input_type: InputType::Video,
+                        concurrency: ConcurrencyMode::None,
+                        share_handles: false,
+                        cache_input_info: false,
+                        file_filters: vec![],
+                        fail_on_read_error: $fail_on_read_error,
+                        can_config: false,
+                    }
+                }
+
+                fn open(
+                    &self,
+                    _file: std::path::PathBuf,
+                ) -> crate::common::AnyResult<Self::InputHandle> {
+                    Ok(())
+                }
+
+                fn close(&self, _handle: Self::InputHandle) -> crate::common::AnyResult<()> {
+                    Ok(())
+                }
+
+                fn get_input_info(
+                    &self,
+                    _handle: &mut Self::InputHandle,
+                    _video_track: u32,
+                    _audio_track: u32,
+                ) -> crate::common::AnyResult<InputInfo> {
+                    Ok(InputInfo {
+                        video: Some(VideoInputInfo {
+                            fps: crate::common::Rational32::new(30, 1),
+                            num_frames: 4,
+                            manual_frame_index: false,
+                            width: 2,
+                            height: 2,
+                            format: InputPixelFormat::Bgra,
+                            alpha: AlphaMode::Straight,
+                            colorimetry: crate::pixel::Colorimetry::HOST,
+                        }),
+                        audio: None,
+                    })
+                }
+
+                fn read_video_mut(
+                    &self,
+                    _handle: &mut Self::InputHandle,
+                    frame: u32,
+                    returner: &mut ImageReturner,
+                ) -> crate::common::AnyResult<()> {
+                    // 途中まで書き込んでから失敗する
+                    returner.write(&vec![frame as u8; 4]);
+                    anyhow::ensure!(frame % 2 == 0, "Frame {frame} is corrupt");
+                    returner.write(&vec![frame as u8; 12]);
+                    Ok(())
+                }
+            }
+
+            impl super::InputSingleton for $name {
+                fn __get_singleton_state()
+                -> &'static std::sync::RwLock<Option<super::InternalInputPluginState<Self>>> {
+                    static PLUGIN: std::sync::RwLock<
+                        Option<super::InternalInputPluginState<$name>>,
+                    > = std::sync::RwLock::new(None);
+                    &PLUGIN
+                }
+            }
+        };
+    }
+
+    corrupt_frame_plugin!(RecoveringPlugin, false);
+    corrupt_frame_plugin!(StrictPlugin, true);
+
+    /// 0から3フレーム目を読み込み、書き込まれたサイズと内容を返す。
+    fn read_corrupt_frames<T: super::InputSingleton>() -> Vec<(i32, [u8; 16])> {
+        let _lock = STRESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        super::initialize_plugin::<T>(crate::common::MINIMUM_AVIUTL2_VERSION.into()).unwrap();
+
+        let path: Vec<u16> = "corrupt.gif\0".encode_utf16().collect();
+        let ih = super::func_open::<T>(path.as_ptr());
+        assert!(!ih.is_null());
+        super::func_set_track::<T>(
+            ih,
+            aviutl2_sys::input2::INPUT_PLUGIN_TABLE::TRACK_TYPE_VIDEO,
+            0,
+        );
+        let mut info: aviutl2_sys::input2::INPUT_INFO = unsafe { std::mem::zeroed() };
+        assert!(super::func_info_get::<T>(ih, &mut info));
+        let frames = (0..4)
+            .map(|frame| {
+                let mut buffer = [0u8; 16];
+                let written = super::func_read_video::<T>(
+                    ih,
+                    frame,
+                    buffer.as_mut_ptr() as *mut std::ffi::c_void,
+                );
+                (written, buffer)
+            })
+            .collect();
+        assert!(super::func_close::<T>(ih));
+        uninitialize::<T>();
+        frames
+    }
+
+    #[test]
+    fn read_video_error_returns_placeholder_and_keeps_handle_usable() {
+        let frames = read_corrupt_frames::<RecoveringPlugin>();
+        assert_eq!(frames[0], (16, [0; 16]));
+        assert_eq!(frames[2], (16, [2; 16]));
+        for (written, buffer) in [frames[1], frames[3]] {
+            assert_eq!(written, 16);
+            // 2x2ではすべてのピクセルが背景のマゼンタになる
+            assert_eq!(buffer.as_slice(), [255, 0, 255, 255].repeat(4));
+        }
+    }
+
+    #[test]
+    fn fail_on_read_error_returns_error_to_host() {
+        let frames = read_corrupt_frames::<StrictPlugin>();
+        assert_eq!(frames[0].0, 16);
+        assert_eq!(frames[1].0, 0);
+        assert_eq!(frames[2].0, 16);
+    }
+
+    static TIME_TO_FRAME_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    macro_rules! time_to_frame_plugin {
+        ($name:ident, $manual_frame_index:expr) => {
+            struct $name;
+
+            impl InputPlugin for $name {
+                type InputHandle = ();
+
+                fn new(_info: crate::common::AviUtl2Info) -> crate::common::AnyResult<Self> {
+                    Ok(Self)
+                }
+
+                fn plugin_info(&self) -> InputPluginTable {
+                    InputPluginTable {
+                        name: stringify!($name).to_string(),
+                        information: String::new(),
+                        input_type: InputType::Video,
+                        concurrency: ConcurrencyMode::None,
+                        share_handles: false,
+                        cache_input_info: false,
+                        file_filters: vec![],
+                        fail_on_read_error: false,
+                        can_config: false,
+                    }
+                }
+
+                fn open(
+                    &self,
+                    _file: std::path::PathBuf,
+                ) -> crate::common::AnyResult<Self::InputHandle> {
+                    Ok(())
+                }
+
+                fn close(&self, _handle: Self::InputHandle) -> crate::common::AnyResult<()> {
+                    Ok(())
+                }
+
+                fn get_input_info(
+                    &self,
+                    _handle: &mut Self::InputHandle,
+                    _video_track: u32,
+                    _audio_track: u32,
+                ) -> crate::common::AnyResult<InputInfo> {
+                    Ok(InputInfo {
+                        video: Some(VideoInputInfo {
+                            fps: crate::common::Rational32::new(10, 1),
+                            num_frames: 3,
+                            manual_frame_index: $manual_frame_index,
+                            width: 1,
+                            height: 1,
+                            format: InputPixelFormat::Bgra,
+                            alpha: AlphaMode::Straight,
+                            colorimetry: crate::pixel::Colorimetry::HOST,
+                        }),
+                        audio: None,
+                    })
+                }
+
+                fn time_to_frame(
+                    &self,
+                    _handle: &mut Self::InputHandle,
+                    _track: u32,
+                    time: f64,
+                ) -> crate::common::AnyResult<u32> {
+                    TIME_TO_FRAME_CALLS.fetch_add(1, Ordering::SeqCst);
+                    let timeline = FrameTimeline::from_durations([0.1, 0.2])?;
+                    Ok(timeline.frame_for(time, TimeToFrame::Loop))
+                }
+            }
+
+            impl super::InputSingleton for $name {
+                fn __get_singleton_state()
+                -> &'static std::sync::RwLock<Option<super::InternalInputPluginState<Self>>> {
+                    static PLUGIN: std::sync::RwLock<
+                        Option<super::InternalInputPluginState<$name>>,
+                    > = std::sync::RwLock::new(None);
+                    &PLUGIN
+                }
+            }
+        };
+    }
+
+    time_to_frame_plugin!(ConstantRatePlugin, false);
+    time_to_frame_plugin!(ManualFramePlugin, true);
+
+    /// `time_to_frame`を呼び出し、（フレーム番号, プラグインの`time_to_frame`が呼ばれた回数）を返す。
+    fn time_to_frame<T: super::InputSingleton>(times: &[f64]) -> (Vec<i32>, usize) {
+        let _lock = STRESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        TIME_TO_FRAME_CALLS.store(0, Ordering::SeqCst);
+        super::initialize_plugin::<T>(crate::common::MINIMUM_AVIUTL2_VERSION.into()).unwrap();
+
+        let ih = open_video::<T>("timeline.gif");
+        let frames = times
+            .iter()
+            .map(|&time| super::func_time_to_frame::<T>(ih, time))
+            .collect();
+        assert!(super::func_close::<T>(ih));
+        uninitialize::<T>();
+        (frames, TIME_TO_FRAME_CALLS.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn time_to_frame_skips_plugin_without_manual_frame_index() {
+        let (frames, calls) = time_to_frame::<ConstantRatePlugin>(&[-1.0, 0.0, 0.15, 10.0]);
+        assert_eq!(frames, [0, 0, 1, 2]);
+        assert_eq!(calls, 0);
+    }
+
+    #[test]
+    fn time_to_frame_calls_plugin_with_manual_frame_index() {
+        let (frames, calls) = time_to_frame::<ManualFramePlugin>(&[-0.05, 0.0, 0.15, 0.35]);
+        assert_eq!(frames, [1, 0, 1, 0]);
+        assert_eq!(calls, 4);
+    }
 
     #[test]
     fn audio_sample_count_converts_bytes_to_sample_frames() {
@@ -892,4 +2383,58 @@ mod tests {
     fn audio_sample_count_rejects_incomplete_sample_frames() {
         audio_sample_count(7, 8);
     }
+
+    #[test]
+    fn convert_alpha_keeps_native_mode() {
+        let mut buffer = [10, 20, 30, 128];
+        convert_alpha_to_native(InputPixelFormat::Bgra, AlphaMode::Straight, &mut buffer);
+        assert_eq!(buffer, [10, 20, 30, 128]);
+    }
+
+    #[test]
+    fn convert_alpha_unpremultiplies_bgra() {
+        let mut buffer = [64, 0, 128, 128, 5, 5, 5, 0];
+        convert_alpha_to_native(
+            InputPixelFormat::Bgra,
+            AlphaMode::Premultiplied,
+            &mut buffer,
+        );
+        assert_eq!(buffer, [128, 0, 255, 128, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn convert_alpha_premultiplies_unaligned_pa64() {
+        use zerocopy::IntoBytes;
+
+        let pixel: [u16; 4] = [65535, 32768, 0, 32768];
+        // 先頭を1バイトずらして、アラインされていないバッファにする
+        let mut storage = vec![0u8; 9];
+        storage[1..].copy_from_slice(pixel.as_bytes());
+        convert_alpha_to_native(
+            InputPixelFormat::Pa64,
+            AlphaMode::Straight,
+            &mut storage[1..],
+        );
+
+        let expected: [u16; 4] = [32768, 16384, 0, 32768];
+        assert_eq!(&storage[1..], expected.as_bytes());
+    }
+
+    #[test]
+    fn convert_colorimetry_converts_yuy2_only() {
+        use crate::pixel::Colorimetry;
+
+        // フルレンジの白黒はリミテッドレンジの16・235になる
+        let mut buffer = [0, 128, 255, 128];
+        convert_colorimetry_to_native(InputPixelFormat::Yuy2, Colorimetry::BT601_FULL, &mut buffer);
+        assert_eq!(buffer, [16, 128, 235, 128]);
+
+        let mut buffer = [0, 128, 255, 128];
+        convert_colorimetry_to_native(InputPixelFormat::Yuy2, Colorimetry::HOST, &mut buffer);
+        assert_eq!(buffer, [0, 128, 255, 128]);
+
+        let mut buffer = [0, 128, 255, 128];
+        convert_colorimetry_to_native(InputPixelFormat::Bgra, Colorimetry::BT709_FULL, &mut buffer);
+        assert_eq!(buffer, [0, 128, 255, 128]);
+    }
 }
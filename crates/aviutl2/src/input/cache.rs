@@ -0,0 +1,304 @@
+use crate::input::ImageBuffer;
+
+/// デコード済みの画像のキャッシュに使うメモリの上限を管理する構造体。
+///
+/// [`CacheBudget::insert`]で登録した画像は、合計サイズが上限を超えると最後に使われた時刻が古い順に破棄されます。
+/// 破棄された画像は[`CachedImage::get`]が`None`を返すようになるので、その場合はデコードし直してください。
+///
+/// 通常は[`CacheBudget::global`]で取得できる、DLLごとに1つのインスタンスを使います。
+///
+/// # Example
+///
+/// ```rust
+/// use aviutl2::input::{CacheBudget, ImageBuffer};
+///
+/// static BUDGET: CacheBudget = CacheBudget::new();
+/// BUDGET.set_limit(Some(8));
+///
+/// let first = BUDGET.insert(ImageBuffer::new(vec![0; 4]));
+/// let second = BUDGET.insert(ImageBuffer::new(vec![0; 4]));
+/// assert!(first.get().is_some());
+///
+/// // secondの方が古いので、secondが破棄される
+/// let third = BUDGET.insert(ImageBuffer::new(vec![0; 4]));
+/// assert!(second.get().is_none());
+/// assert!(first.get().is_some());
+/// assert!(third.get().is_some());
+/// assert_eq!(BUDGET.usage(), 8);
+/// ```
+#[derive(Debug)]
+pub struct CacheBudget {
+    inner: std::sync::Mutex<CacheBudgetInner>,
+}
+
+#[derive(Debug)]
+struct CacheBudgetInner {
+    limit: Option<usize>,
+    usage: usize,
+    clock: u64,
+    next_id: u64,
+    entries: Vec<CacheSlot>,
+}
+
+#[derive(Debug)]
+struct CacheSlot {
+    id: u64,
+    buffer: ImageBuffer,
+    last_used: u64,
+}
+
+static GLOBAL_CACHE_BUDGET: CacheBudget = CacheBudget::new();
+
+/// DLL全体で共有するキャッシュの上限を設定します。
+///
+/// [`CacheBudget::global`]の[`CacheBudget::set_limit`]と同じです。
+pub fn cache_budget(bytes: usize) {
+    CacheBudget::global().set_limit(Some(bytes));
+}
+
+impl Default for CacheBudget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheBudget {
+    /// 上限なしの`CacheBudget`を作成します。
+    pub const fn new() -> Self {
+        Self {
+            inner: std::sync::Mutex::new(CacheBudgetInner {
+                limit: None,
+                usage: 0,
+                clock: 0,
+                next_id: 0,
+                entries: Vec::new(),
+            }),
+        }
+    }
+
+    /// DLL全体で共有する`CacheBudget`を返します。
+    pub fn global() -> &'static CacheBudget {
+        &GLOBAL_CACHE_BUDGET
+    }
+
+    /// キャッシュの上限をバイト数で設定します。`None`の場合は上限なしになります。
+    ///
+    /// 現在の使用量が上限を超えている場合は、すぐに古い画像から破棄されます。
+    pub fn set_limit(&self, limit: Option<usize>) {
+        let mut inner = self.lock();
+        inner.limit = limit;
+        inner.evict_until_fits(0);
+    }
+
+    /// キャッシュの上限を返します。
+    pub fn limit(&self) -> Option<usize> {
+        self.lock().limit
+    }
+
+    /// 現在キャッシュされている画像の合計バイト数を返します。
+    pub fn usage(&self) -> usize {
+        self.lock().usage
+    }
+
+    /// 現在キャッシュされている画像の数を返します。
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// キャッシュされている画像がないかどうかを返します。
+    pub fn is_empty(&self) -> bool {
+        self.lock().entries.is_empty()
+    }
+
+    /// 画像をキャッシュに登録します。
+    ///
+    /// 上限に収まるように、最後に使われた時刻が古い画像から破棄します。
+    /// 画像が1枚だけで上限を超える場合は、キャッシュせずに破棄済みの[`CachedImage`]を返します。
+    pub fn insert(&'static self, buffer: ImageBuffer) -> CachedImage {
+        let mut inner = self.lock();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        let size = buffer.byte_len();
+        if inner.limit.is_none_or(|limit| size <= limit) {
+            inner.evict_until_fits(size);
+            let last_used = inner.tick();
+            inner.usage += size;
+            inner.entries.push(CacheSlot {
+                id,
+                buffer,
+                last_used,
+            });
+        }
+        CachedImage { budget: self, id }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheBudgetInner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl CacheBudgetInner {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// `additional`バイトを追加しても上限に収まるまで、古い画像を破棄する。
+    fn evict_until_fits(&mut self, additional: usize) {
+        let Some(limit) = self.limit else {
+            return;
+        };
+        while self.usage + additional > limit {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, slot)| slot.last_used)
+                .map(|(index, _)| index)
+            else {
+                break;
+            };
+            let slot = self.entries.swap_remove(oldest);
+            self.usage -= slot.buffer.byte_len();
+        }
+    }
+
+    fn remove(&mut self, id: u64) {
+        if let Some(index) = self.entries.iter().position(|slot| slot.id == id) {
+            let slot = self.entries.swap_remove(index);
+            self.usage -= slot.buffer.byte_len();
+        }
+    }
+}
+
+/// [`CacheBudget`]に登録された画像。
+///
+/// Dropするとキャッシュから取り除かれます。
+#[derive(Debug)]
+pub struct CachedImage {
+    budget: &'static CacheBudget,
+    id: u64,
+}
+
+impl CachedImage {
+    /// キャッシュされている画像を返します。破棄されている場合は`None`を返します。
+    ///
+    /// 画像は最後に使われたものとして扱われます。データはコピーされません。
+    pub fn get(&self) -> Option<ImageBuffer> {
+        let mut inner = self.budget.lock();
+        let now = inner.tick();
+        let slot = inner.entries.iter_mut().find(|slot| slot.id == self.id)?;
+        slot.last_used = now;
+        Some(slot.buffer.clone())
+    }
+
+    /// 画像が破棄されていないかどうかを返します。最後に使われた時刻は更新しません。
+    pub fn is_cached(&self) -> bool {
+        self.budget
+            .lock()
+            .entries
+            .iter()
+            .any(|slot| slot.id == self.id)
+    }
+}
+
+impl Drop for CachedImage {
+    fn drop(&mut self) {
+        self.budget.lock().remove(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(limit: Option<usize>) -> &'static CacheBudget {
+        let budget = Box::leak(Box::new(CacheBudget::new()));
+        budget.set_limit(limit);
+        budget
+    }
+
+    fn buffer(len: usize) -> ImageBuffer {
+        ImageBuffer::new(vec![0; len])
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let budget = budget(Some(300));
+        let a = budget.insert(buffer(100));
+        let b = budget.insert(buffer(100));
+        let c = budget.insert(buffer(100));
+        assert_eq!(budget.usage(), 300);
+
+        // aを使ったので、一番古いのはbになる
+        assert!(a.get().is_some());
+        let d = budget.insert(buffer(100));
+        assert!(a.is_cached());
+        assert!(!b.is_cached());
+        assert!(c.is_cached());
+        assert!(d.is_cached());
+        assert_eq!(budget.usage(), 300);
+        assert!(b.get().is_none());
+    }
+
+    #[test]
+    fn test_usage_never_exceeds_limit() {
+        let budget = budget(Some(1000));
+        let mut images = Vec::new();
+        for i in 0..50 {
+            images.push(budget.insert(buffer(60 + (i * 37) % 200)));
+            assert!(budget.usage() <= 1000);
+        }
+        let cached: usize = images
+            .iter()
+            .filter_map(|image| image.get())
+            .map(|buffer| buffer.byte_len())
+            .sum();
+        assert_eq!(cached, budget.usage());
+    }
+
+    #[test]
+    fn test_too_large_image_is_not_cached() {
+        let budget = budget(Some(100));
+        let small = budget.insert(buffer(50));
+        let large = budget.insert(buffer(101));
+        assert!(large.get().is_none());
+        assert!(small.is_cached());
+        assert_eq!(budget.usage(), 50);
+    }
+
+    #[test]
+    fn test_drop_releases_usage() {
+        let budget = budget(None);
+        let a = budget.insert(buffer(100));
+        let b = budget.insert(buffer(200));
+        assert_eq!(budget.usage(), 300);
+        drop(a);
+        assert_eq!(budget.usage(), 200);
+        assert_eq!(budget.len(), 1);
+        drop(b);
+        assert!(budget.is_empty());
+        assert_eq!(budget.usage(), 0);
+    }
+
+    #[test]
+    fn test_lowering_limit_evicts() {
+        let budget = budget(None);
+        let a = budget.insert(buffer(100));
+        let b = budget.insert(buffer(100));
+        budget.set_limit(Some(150));
+        assert!(!a.is_cached());
+        assert!(b.is_cached());
+        assert_eq!(budget.usage(), 100);
+    }
+
+    #[test]
+    fn test_get_does_not_copy() {
+        let budget = budget(None);
+        let source = buffer(16);
+        let cached = budget.insert(source.clone());
+        assert!(cached.get().unwrap().ptr_eq(&source));
+    }
+}
@@ -0,0 +1,634 @@
+//! [`InputPluginTable::cache_input_info`][crate::input::InputPluginTable::cache_input_info]で使う、入力情報の永続キャッシュ。
+
+use crate::common::Rational32;
+use crate::input::{
+    AlphaMode, AudioFormat, AudioInputInfo, InputInfo, InputPixelFormat, VideoInputInfo,
+};
+use crate::pixel::{Colorimetry, YuvMatrix, YuvRange};
+
+const MAGIC: &[u8; 8] = b"AU2INFOC";
+/// キャッシュファイルの形式のバージョン。形式を変えたら上げる。
+const FORMAT_VERSION: u32 = 1;
+
+/// キャッシュのキー。ファイルが変更されていないかを、サイズと更新日時で判定する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InputInfoCacheKey {
+    /// 正規化したパス。
+    path: std::path::PathBuf,
+    size: u64,
+    /// UNIXエポックからのナノ秒。
+    modified: u64,
+    /// [`InputPlugin::cache_generation`][crate::input::InputPlugin::cache_generation]の値。
+    generation: u64,
+}
+
+impl InputInfoCacheKey {
+    /// ファイルのメタデータからキーを作成する。
+    pub(crate) fn for_file(path: &std::path::Path, generation: u64) -> std::io::Result<Self> {
+        let path = std::fs::canonicalize(path)?;
+        let metadata = std::fs::metadata(&path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos() as u64);
+        Ok(Self {
+            path,
+            size: metadata.len(),
+            modified,
+            generation,
+        })
+    }
+
+    fn matches(&self, entry: &CacheEntry) -> bool {
+        self.size == entry.size
+            && self.modified == entry.modified
+            && self.generation == entry.generation
+    }
+}
+
+/// 1つのファイルについてキャッシュした、プラグインの呼び出し結果。
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CachedInput {
+    /// [`InputPlugin::get_track_count`][crate::input::InputPlugin::get_track_count]の結果。
+    pub track_counts: Option<(u32, u32)>,
+    /// 要求された動画のトラック番号と、`func_set_track`で返した値。
+    pub video_tracks: Vec<(u32, i32)>,
+    /// 要求された音声のトラック番号と、`func_set_track`で返した値。
+    pub audio_tracks: Vec<(u32, i32)>,
+    /// 動画・音声のトラック番号ごとの[`InputInfo`]。
+    pub infos: Vec<((u32, u32), InputInfo)>,
+}
+
+impl CachedInput {
+    pub(crate) fn track_result(&self, video: bool, track: u32) -> Option<i32> {
+        let tracks = if video {
+            &self.video_tracks
+        } else {
+            &self.audio_tracks
+        };
+        tracks
+            .iter()
+            .find(|(requested, _)| *requested == track)
+            .map(|(_, result)| *result)
+    }
+
+    pub(crate) fn set_track_result(&mut self, video: bool, track: u32, result: i32) {
+        let tracks = if video {
+            &mut self.video_tracks
+        } else {
+            &mut self.audio_tracks
+        };
+        tracks.retain(|(requested, _)| *requested != track);
+        tracks.push((track, result));
+    }
+
+    pub(crate) fn info(&self, tracks: (u32, u32)) -> Option<&InputInfo> {
+        self.infos
+            .iter()
+            .find(|(key, _)| *key == tracks)
+            .map(|(_, info)| info)
+    }
+
+    pub(crate) fn set_info(&mut self, tracks: (u32, u32), info: InputInfo) {
+        self.infos.retain(|(key, _)| *key != tracks);
+        self.infos.push((tracks, info));
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    size: u64,
+    modified: u64,
+    generation: u64,
+    input: CachedInput,
+}
+
+/// 入力情報のキャッシュ。
+///
+/// 正規化したパスごとに1つのエントリを持ち、サイズ・更新日時・世代のどれかが違う場合は破棄する。
+#[derive(Debug)]
+pub(crate) struct InputInfoCache {
+    /// 保存先。`None`の場合はメモリ上にのみ保持する。
+    file: Option<std::path::PathBuf>,
+    entries: std::sync::Mutex<std::collections::HashMap<std::path::PathBuf, CacheEntry>>,
+    dirty: std::sync::atomic::AtomicBool,
+}
+
+impl InputInfoCache {
+    /// メモリ上にのみ保持するキャッシュを作成する。
+    pub(crate) fn in_memory() -> Self {
+        Self {
+            file: None,
+            entries: Default::default(),
+            dirty: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    /// キャッシュファイルを読み込む。
+    ///
+    /// ファイルが無い場合や壊れている場合は、空のキャッシュとして扱い、次の保存で作り直す。
+    pub(crate) fn load(file: std::path::PathBuf) -> Self {
+        let entries = match std::fs::read(&file) {
+            Ok(bytes) => decode(&bytes).unwrap_or_else(|| {
+                tracing::warn!(
+                    "Input info cache {} is corrupted, rebuilding",
+                    file.display()
+                );
+                Default::default()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Default::default(),
+            Err(e) => {
+                tracing::warn!("Failed to read input info cache {}: {}", file.display(), e);
+                Default::default()
+            }
+        };
+        Self {
+            file: Some(file),
+            entries: std::sync::Mutex::new(entries),
+            dirty: std::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    /// キーに一致するキャッシュを返す。
+    pub(crate) fn get(&self, key: &InputInfoCacheKey) -> Option<CachedInput> {
+        let entries = self.lock();
+        entries
+            .get(&key.path)
+            .filter(|entry| key.matches(entry))
+            .map(|entry| entry.input.clone())
+    }
+
+    /// キャッシュを更新する。キーに一致しない古いエントリは破棄される。
+    pub(crate) fn update(&self, key: &InputInfoCacheKey, f: impl FnOnce(&mut CachedInput)) {
+        let mut entries = self.lock();
+        let entry = entries
+            .entry(key.path.clone())
+            .or_insert_with(|| CacheEntry {
+                size: key.size,
+                modified: key.modified,
+                generation: key.generation,
+                input: CachedInput::default(),
+            });
+        if !key.matches(entry) {
+            *entry = CacheEntry {
+                size: key.size,
+                modified: key.modified,
+                generation: key.generation,
+                input: CachedInput::default(),
+            };
+        }
+        f(&mut entry.input);
+        self.dirty.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// 変更があればキャッシュファイルに書き出す。
+    pub(crate) fn save(&self) -> std::io::Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if !self.dirty.swap(false, std::sync::atomic::Ordering::Relaxed) {
+            return Ok(());
+        }
+        let bytes = encode(&self.lock());
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // 書き込み中に終了しても壊れたファイルが残らないよう、一時ファイルに書いてから置き換える
+        let temp = file.with_extension("tmp");
+        std::fs::write(&temp, bytes)?;
+        std::fs::rename(&temp, file)
+    }
+
+    fn lock(
+        &self,
+    ) -> std::sync::MutexGuard<'_, std::collections::HashMap<std::path::PathBuf, CacheEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// プラグイン名からキャッシュファイルの名前を作る。
+pub(crate) fn cache_file_name(plugin_name: &str) -> String {
+    let name = plugin_name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    format!("{}.bin", name.trim())
+}
+
+fn encode(entries: &std::collections::HashMap<std::path::PathBuf, CacheEntry>) -> Vec<u8> {
+    let mut body = Writer::default();
+    let entries = entries
+        .iter()
+        .filter_map(|(path, entry)| Some((path.to_str()?, entry)))
+        .collect::<Vec<_>>();
+    body.u32(entries.len() as u32);
+    for (path, entry) in entries {
+        body.bytes(path.as_bytes());
+        body.u64(entry.size);
+        body.u64(entry.modified);
+        body.u64(entry.generation);
+        let input = &entry.input;
+        match input.track_counts {
+            Some((video, audio)) => {
+                body.u8(1);
+                body.u32(video);
+                body.u32(audio);
+            }
+            None => body.u8(0),
+        }
+        for tracks in [&input.video_tracks, &input.audio_tracks] {
+            body.u32(tracks.len() as u32);
+            for &(requested, result) in tracks {
+                body.u32(requested);
+                body.u32(result as u32);
+            }
+        }
+        body.u32(input.infos.len() as u32);
+        for ((video_track, audio_track), info) in &input.infos {
+            body.u32(*video_track);
+            body.u32(*audio_track);
+            encode_info(&mut body, info);
+        }
+    }
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&xxhash_rust::xxh3::xxh3_64(&body.0).to_le_bytes());
+    bytes.extend_from_slice(&body.0);
+    bytes
+}
+
+fn encode_info(body: &mut Writer, info: &InputInfo) {
+    match &info.video {
+        Some(video) => {
+            body.u8(1);
+            body.u32(*video.fps.numer() as u32);
+            body.u32(*video.fps.denom() as u32);
+            body.u32(video.num_frames);
+            body.u8(video.manual_frame_index as u8);
+            body.u32(video.width);
+            body.u32(video.height);
+            body.u8(match video.format {
+                InputPixelFormat::Bgr => 0,
+                InputPixelFormat::Bgra => 1,
+                InputPixelFormat::Yuy2 => 2,
+                InputPixelFormat::Pa64 => 3,
+                InputPixelFormat::Yc48 => 4,
+                InputPixelFormat::Hf64 => 5,
+            });
+            body.u8(match video.alpha {
+                AlphaMode::Straight => 0,
+                AlphaMode::Premultiplied => 1,
+            });
+            body.u8(match video.colorimetry.matrix {
+                YuvMatrix::Bt601 => 0,
+                YuvMatrix::Bt709 => 1,
+            });
+            body.u8(match video.colorimetry.range {
+                YuvRange::Limited => 0,
+                YuvRange::Full => 1,
+            });
+        }
+        None => body.u8(0),
+    }
+    match &info.audio {
+        Some(audio) => {
+            body.u8(1);
+            body.u32(audio.sample_rate);
+            body.u32(audio.num_samples);
+            body.u16(audio.channels);
+            body.u8(match audio.format {
+                AudioFormat::Pcm16 => 0,
+                AudioFormat::IeeeFloat32 => 1,
+            });
+        }
+        None => body.u8(0),
+    }
+}
+
+/// キャッシュファイルを読み込む。壊れている場合は`None`を返す。
+fn decode(bytes: &[u8]) -> Option<std::collections::HashMap<std::path::PathBuf, CacheEntry>> {
+    let mut header = Reader(bytes);
+    if header.take(MAGIC.len())? != MAGIC || header.u32()? != FORMAT_VERSION {
+        return None;
+    }
+    let checksum = header.u64()?;
+    if xxhash_rust::xxh3::xxh3_64(header.0) != checksum {
+        return None;
+    }
+
+    let mut body = header;
+    let mut entries = std::collections::HashMap::new();
+    for _ in 0..body.u32()? {
+        let path = std::str::from_utf8(body.bytes()?).ok()?;
+        let size = body.u64()?;
+        let modified = body.u64()?;
+        let generation = body.u64()?;
+        let track_counts = match body.u8()? {
+            0 => None,
+            1 => Some((body.u32()?, body.u32()?)),
+            _ => return None,
+        };
+        let mut track_results = [Vec::new(), Vec::new()];
+        for tracks in &mut track_results {
+            for _ in 0..body.u32()? {
+                tracks.push((body.u32()?, body.u32()? as i32));
+            }
+        }
+        let [video_tracks, audio_tracks] = track_results;
+        let mut infos = Vec::new();
+        for _ in 0..body.u32()? {
+            let tracks = (body.u32()?, body.u32()?);
+            infos.push((tracks, decode_info(&mut body)?));
+        }
+        entries.insert(
+            std::path::PathBuf::from(path),
+            CacheEntry {
+                size,
+                modified,
+                generation,
+                input: CachedInput {
+                    track_counts,
+                    video_tracks,
+                    audio_tracks,
+                    infos,
+                },
+            },
+        );
+    }
+    body.0.is_empty().then_some(entries)
+}
+
+fn decode_info(body: &mut Reader) -> Option<InputInfo> {
+    let video = match body.u8()? {
+        0 => None,
+        1 => {
+            let numer = body.u32()? as i32;
+            let denom = body.u32()? as i32;
+            if denom == 0 {
+                return None;
+            }
+            Some(VideoInputInfo {
+                fps: Rational32::new(numer, denom),
+                num_frames: body.u32()?,
+                manual_frame_index: match body.u8()? {
+                    0 => false,
+                    1 => true,
+                    _ => return None,
+                },
+                width: body.u32()?,
+                height: body.u32()?,
+                format: match body.u8()? {
+                    0 => InputPixelFormat::Bgr,
+                    1 => InputPixelFormat::Bgra,
+                    2 => InputPixelFormat::Yuy2,
+                    3 => InputPixelFormat::Pa64,
+                    4 => InputPixelFormat::Yc48,
+                    5 => InputPixelFormat::Hf64,
+                    _ => return None,
+                },
+                alpha: match body.u8()? {
+                    0 => AlphaMode::Straight,
+                    1 => AlphaMode::Premultiplied,
+                    _ => return None,
+                },
+                colorimetry: Colorimetry {
+                    matrix: match body.u8()? {
+                        0 => YuvMatrix::Bt601,
+                        1 => YuvMatrix::Bt709,
+                        _ => return None,
+                    },
+                    range: match body.u8()? {
+                        0 => YuvRange::Limited,
+                        1 => YuvRange::Full,
+                        _ => return None,
+                    },
+                },
+            })
+        }
+        _ => return None,
+    };
+    let audio = match body.u8()? {
+        0 => None,
+        1 => Some(AudioInputInfo {
+            sample_rate: body.u32()?,
+            num_samples: body.u32()?,
+            channels: body.u16()?,
+            format: match body.u8()? {
+                0 => AudioFormat::Pcm16,
+                1 => AudioFormat::IeeeFloat32,
+                _ => return None,
+            },
+        }),
+        _ => return None,
+    };
+    Some(InputInfo { video, audio })
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+    fn bytes(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value);
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (head, rest) = self.0.split_at_checked(len)?;
+        self.0 = rest;
+        Some(head)
+    }
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+    fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "aviutl2-rs-info-cache-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn info() -> InputInfo {
+        InputInfo {
+            video: Some(VideoInputInfo {
+                fps: Rational32::new(30000, 1001),
+                num_frames: 120,
+                manual_frame_index: true,
+                width: 1920,
+                height: 1080,
+                format: InputPixelFormat::Yuy2,
+                alpha: AlphaMode::Premultiplied,
+                colorimetry: Colorimetry::BT709_FULL,
+            }),
+            audio: Some(AudioInputInfo {
+                sample_rate: 48000,
+                num_samples: 96000,
+                channels: 2,
+                format: AudioFormat::IeeeFloat32,
+            }),
+        }
+    }
+
+    fn key(dir: &std::path::Path, generation: u64) -> InputInfoCacheKey {
+        let file = dir.join("image.png");
+        if !file.exists() {
+            std::fs::write(&file, b"image").unwrap();
+        }
+        InputInfoCacheKey::for_file(&file, generation).unwrap()
+    }
+
+    fn fill(cache: &InputInfoCache, key: &InputInfoCacheKey) {
+        cache.update(key, |input| {
+            input.track_counts = Some((1, 2));
+            input.set_track_result(true, 0, 0);
+            input.set_track_result(false, 3, -1);
+            input.set_info((0, 1), info());
+        });
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let dir = temp_dir("roundtrip");
+        let key = key(&dir, 0);
+        let file = dir.join("cache").join(cache_file_name("Rusty Image Input"));
+        let cache = InputInfoCache::load(file.clone());
+        assert!(cache.get(&key).is_none());
+        fill(&cache, &key);
+        cache.save().unwrap();
+
+        let loaded = InputInfoCache::load(file);
+        let input = loaded.get(&key).unwrap();
+        assert_eq!(input.track_counts, Some((1, 2)));
+        assert_eq!(input.track_result(true, 0), Some(0));
+        assert_eq!(input.track_result(false, 3), Some(-1));
+        assert_eq!(input.track_result(false, 0), None);
+        assert_eq!(
+            format!("{:?}", input.info((0, 1)).unwrap()),
+            format!("{:?}", info())
+        );
+        assert!(input.info((1, 1)).is_none());
+    }
+
+    #[test]
+    fn test_invalidated_by_size_mtime_and_generation() {
+        let dir = temp_dir("invalidate");
+        let cache = InputInfoCache::in_memory();
+        let original = key(&dir, 0);
+        fill(&cache, &original);
+        assert!(cache.get(&original).is_some());
+
+        // 世代が違う場合は使わない
+        assert!(cache.get(&key(&dir, 1)).is_none());
+
+        let modified = InputInfoCacheKey {
+            modified: original.modified + 1,
+            ..original.clone()
+        };
+        assert!(cache.get(&modified).is_none());
+
+        std::fs::write(dir.join("image.png"), b"larger image").unwrap();
+        let resized = key(&dir, 0);
+        assert_ne!(resized.size, original.size);
+        assert!(cache.get(&resized).is_none());
+
+        // 一致しないキーで更新すると、古い内容は破棄される
+        cache.update(&resized, |input| input.track_counts = Some((1, 0)));
+        let input = cache.get(&resized).unwrap();
+        assert_eq!(input.track_counts, Some((1, 0)));
+        assert!(input.infos.is_empty());
+        assert!(cache.get(&original).is_none());
+    }
+
+    #[test]
+    fn test_key_uses_canonical_path() {
+        let dir = temp_dir("canonical");
+        let direct = key(&dir, 0);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let indirect =
+            InputInfoCacheKey::for_file(&dir.join("sub").join("..").join("image.png"), 0).unwrap();
+        assert_eq!(direct, indirect);
+    }
+
+    #[test]
+    fn test_corrupted_file_is_ignored() {
+        let dir = temp_dir("corrupted");
+        let key = key(&dir, 0);
+        let file = dir.join("cache.bin");
+        let cache = InputInfoCache::load(file.clone());
+        fill(&cache, &key);
+        cache.save().unwrap();
+        let bytes = std::fs::read(&file).unwrap();
+
+        let mut flipped = bytes.clone();
+        *flipped.last_mut().unwrap() ^= 0xff;
+        for corrupted in [
+            flipped,
+            bytes[..bytes.len() - 1].to_vec(),
+            b"not a cache".to_vec(),
+            Vec::new(),
+        ] {
+            std::fs::write(&file, corrupted).unwrap();
+            let cache = InputInfoCache::load(file.clone());
+            assert!(cache.get(&key).is_none());
+
+            // 作り直したキャッシュは保存できる
+            fill(&cache, &key);
+            cache.save().unwrap();
+            assert!(InputInfoCache::load(file.clone()).get(&key).is_some());
+        }
+    }
+
+    #[test]
+    fn test_save_without_changes_does_not_write() {
+        let dir = temp_dir("clean");
+        let file = dir.join("cache.bin");
+        InputInfoCache::load(file.clone()).save().unwrap();
+        assert!(!file.exists());
+    }
+}
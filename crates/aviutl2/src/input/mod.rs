@@ -9,9 +9,20 @@
 //! サンプルは<https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/image-rs-input>を参照してください。
 
 mod binding;
+mod cache;
+mod info_cache;
+mod placeholder;
+mod resample;
+#[cfg(feature = "text")]
+mod text;
+mod timeline;
 
 pub use super::common::*;
 pub use binding::*;
+pub use cache::*;
+#[cfg(feature = "text")]
+pub use text::*;
+pub use timeline::*;
 
 #[doc(hidden)]
 #[path = "bridge.rs"]
@@ -0,0 +1,251 @@
+//! 読み込みに失敗したフレームの代わりに表示する画像を生成する。
+
+use crate::{
+    common::f16,
+    input::InputPixelFormat,
+    pixel::{Colorimetry, rgb_to_yc48, rgb_to_yuy2},
+};
+
+/// 背景色（マゼンタ）。
+const BACKGROUND: (u8, u8, u8) = (255, 0, 255);
+/// 文字色。
+const FOREGROUND: (u8, u8, u8) = (255, 255, 255);
+
+/// 1文字の幅（ピクセル、拡大前）。
+const GLYPH_WIDTH: u32 = 5;
+/// 1文字の高さ（ピクセル、拡大前）。
+const GLYPH_HEIGHT: u32 = 7;
+/// 文字の間隔を含めた1文字の幅（ピクセル、拡大前）。
+const CELL_WIDTH: u32 = GLYPH_WIDTH + 1;
+/// 行間を含めた1行の高さ（ピクセル、拡大前）。
+const CELL_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+/// 画像の端からの余白（ピクセル、拡大前）。
+const MARGIN: u32 = 4;
+
+/// ASCIIの`' '`から`'~'`までの5x7ピクセルのフォント。
+///
+/// 1文字あたり5列で、各列の下位ビットが上の行に対応します。
+#[rustfmt::skip]
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5F, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00], // ' ' ! "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], [0x24, 0x2A, 0x7F, 0x2A, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62], // # $ %
+    [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], [0x00, 0x1C, 0x22, 0x41, 0x00], // & ' (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], [0x08, 0x2A, 0x1C, 0x2A, 0x08], [0x08, 0x08, 0x3E, 0x08, 0x08], // ) * +
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00], // , - .
+    [0x20, 0x10, 0x08, 0x04, 0x02], [0x3E, 0x51, 0x49, 0x45, 0x3E], [0x00, 0x42, 0x7F, 0x40, 0x00], // / 0 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4B, 0x31], [0x18, 0x14, 0x12, 0x7F, 0x10], // 2 3 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], [0x3C, 0x4A, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03], // 5 6 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1E], [0x00, 0x36, 0x36, 0x00, 0x00], // 8 9 :
+    [0x00, 0x56, 0x36, 0x00, 0x00], [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14], // ; < =
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], [0x32, 0x49, 0x79, 0x41, 0x3E], // > ? @
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], [0x7F, 0x49, 0x49, 0x49, 0x36], [0x3E, 0x41, 0x41, 0x41, 0x22], // A B C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], [0x7F, 0x49, 0x49, 0x49, 0x41], [0x7F, 0x09, 0x09, 0x01, 0x01], // D E F
+    [0x3E, 0x41, 0x41, 0x51, 0x32], [0x7F, 0x08, 0x08, 0x08, 0x7F], [0x00, 0x41, 0x7F, 0x41, 0x00], // G H I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], [0x7F, 0x08, 0x14, 0x22, 0x41], [0x7F, 0x40, 0x40, 0x40, 0x40], // J K L
+    [0x7F, 0x02, 0x04, 0x02, 0x7F], [0x7F, 0x04, 0x08, 0x10, 0x7F], [0x3E, 0x41, 0x41, 0x41, 0x3E], // M N O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], [0x3E, 0x41, 0x51, 0x21, 0x5E], [0x7F, 0x09, 0x19, 0x29, 0x46], // P Q R
+    [0x46, 0x49, 0x49, 0x49, 0x31], [0x01, 0x01, 0x7F, 0x01, 0x01], [0x3F, 0x40, 0x40, 0x40, 0x3F], // S T U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], [0x7F, 0x20, 0x18, 0x20, 0x7F], [0x63, 0x14, 0x08, 0x14, 0x63], // V W X
+    [0x03, 0x04, 0x78, 0x04, 0x03], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7F, 0x41, 0x41, 0x00], // Y Z [
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7F, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04], // \ ] ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78], // _ ` a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], [0x38, 0x44, 0x44, 0x48, 0x7F], // b c d
+    [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7E, 0x09, 0x01, 0x02], [0x08, 0x14, 0x54, 0x54, 0x3C], // e f g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7D, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3D, 0x00], // h i j
+    [0x00, 0x7F, 0x10, 0x28, 0x44], [0x00, 0x41, 0x7F, 0x40, 0x00], [0x7C, 0x04, 0x18, 0x04, 0x78], // k l m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], [0x7C, 0x14, 0x14, 0x14, 0x08], // n o p
+    [0x08, 0x14, 0x14, 0x18, 0x7C], [0x7C, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20], // q r s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], [0x3C, 0x40, 0x40, 0x20, 0x7C], [0x1C, 0x20, 0x40, 0x20, 0x1C], // t u v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], [0x44, 0x28, 0x10, 0x28, 0x44], [0x0C, 0x50, 0x50, 0x50, 0x3C], // w x y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], [0x00, 0x00, 0x7F, 0x00, 0x00], // z { |
+    [0x00, 0x41, 0x36, 0x08, 0x00], [0x08, 0x04, 0x08, 0x10, 0x08],                                 // } ~
+];
+
+/// `c`の`(x, y)`のピクセルが点灯しているかを返す。ASCII以外の文字は`?`として扱います。
+fn glyph_pixel(c: char, x: u32, y: u32) -> bool {
+    let index = match c {
+        ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+    FONT[index][x as usize] & (1 << y) != 0
+}
+
+/// `width`x`height`の画像に`message`を描いたときの文字の拡大率を返す。
+fn text_scale(width: u32, height: u32) -> u32 {
+    (width.min(height) / 160).max(1)
+}
+
+/// `message`を、1行あたり`columns`文字以内になるように折り返す。
+fn wrap_lines(message: &str, columns: usize) -> Vec<Vec<char>> {
+    let columns = columns.max(1);
+    let mut lines = vec![];
+    for line in message.lines() {
+        let chars = line.chars().collect::<Vec<_>>();
+        if chars.is_empty() {
+            lines.push(vec![]);
+        }
+        lines.extend(chars.chunks(columns).map(<[char]>::to_vec));
+    }
+    lines
+}
+
+/// 読み込みに失敗したことを表す画像を、上の行から順にRGBで返す。
+///
+/// マゼンタの背景に、`message`を白い文字で左上から描きます。
+/// 画像に収まらない部分は切り捨てられます。
+pub(crate) fn render_rgb(width: u32, height: u32, message: &str) -> Vec<(u8, u8, u8)> {
+    let mut pixels = vec![BACKGROUND; width as usize * height as usize];
+    let scale = text_scale(width, height);
+    let margin = MARGIN * scale;
+    let columns = (width.saturating_sub(margin * 2) / (CELL_WIDTH * scale)) as usize;
+    for (row, line) in wrap_lines(message, columns).iter().enumerate() {
+        let top = margin + row as u32 * CELL_HEIGHT * scale;
+        if top + GLYPH_HEIGHT * scale > height {
+            break;
+        }
+        for (column, &c) in line.iter().enumerate() {
+            let left = margin + column as u32 * CELL_WIDTH * scale;
+            for y in 0..GLYPH_HEIGHT * scale {
+                for x in 0..GLYPH_WIDTH * scale {
+                    if glyph_pixel(c, x / scale, y / scale) {
+                        pixels[((top + y) * width + left + x) as usize] = FOREGROUND;
+                    }
+                }
+            }
+        }
+    }
+    pixels
+}
+
+/// 読み込みに失敗したことを表す画像を、`format`のバイト列で返す。
+///
+/// BGR・BGRAは下の行から、それ以外は上の行から並びます。
+/// YUY2は`colorimetry`で変換します。
+pub(crate) fn render(
+    format: InputPixelFormat,
+    colorimetry: Colorimetry,
+    width: u32,
+    height: u32,
+    message: &str,
+) -> Vec<u8> {
+    let pixels = render_rgb(width, height, message);
+    let rows = pixels.chunks_exact(width.max(1) as usize);
+    let mut out = Vec::with_capacity(pixels.len() * format.bytes_count_per_pixel());
+    match format {
+        InputPixelFormat::Bgr => {
+            for (r, g, b) in rows.rev().flatten().copied() {
+                out.extend_from_slice(&[b, g, r]);
+            }
+        }
+        InputPixelFormat::Bgra => {
+            for (r, g, b) in rows.rev().flatten().copied() {
+                out.extend_from_slice(&[b, g, r, 255]);
+            }
+        }
+        InputPixelFormat::Yuy2 => {
+            for row in rows {
+                for pair in row.chunks(2) {
+                    let (y0, cb, y1, cr) =
+                        rgb_to_yuy2([pair[0], *pair.last().unwrap()], colorimetry);
+                    out.extend_from_slice(&[y0, cb, y1, cr][..pair.len() * 2]);
+                }
+            }
+        }
+        InputPixelFormat::Pa64 => {
+            for (r, g, b) in pixels.iter().copied() {
+                for value in [r, g, b, 255] {
+                    out.extend_from_slice(&(value as u16 * 257).to_le_bytes());
+                }
+            }
+        }
+        InputPixelFormat::Hf64 => {
+            for (r, g, b) in pixels.iter().copied() {
+                for value in [r, g, b, 255] {
+                    out.extend_from_slice(&f16::from_f32(value as f32 / 255.0).to_le_bytes());
+                }
+            }
+        }
+        InputPixelFormat::Yc48 => {
+            for rgb in pixels.iter().copied() {
+                let yc48 = rgb_to_yc48(rgb, Colorimetry::HOST.matrix);
+                for value in [yc48.y, yc48.cb, yc48.cr] {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_sizes() {
+        for format in [
+            InputPixelFormat::Bgr,
+            InputPixelFormat::Bgra,
+            InputPixelFormat::Yuy2,
+            InputPixelFormat::Pa64,
+            InputPixelFormat::Hf64,
+            InputPixelFormat::Yc48,
+        ] {
+            for (width, height) in [(64, 48), (7, 5), (1, 1), (0, 0)] {
+                assert_eq!(
+                    render(format, Colorimetry::HOST, width, height, "Error").len(),
+                    (width * height) as usize * format.bytes_count_per_pixel(),
+                    "{format:?} {width}x{height}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_text() {
+        let pixels = render_rgb(64, 32, "I");
+        // 「I」の縦棒は3列目
+        let lit = |x: u32, y: u32| pixels[(y * 64 + x) as usize] == FOREGROUND;
+        assert!((0..GLYPH_HEIGHT).all(|y| lit(MARGIN + 2, MARGIN + y)));
+        assert!(!lit(MARGIN + 2, MARGIN + GLYPH_HEIGHT));
+        assert!(!lit(0, 0));
+        assert_eq!(
+            pixels.iter().filter(|&&pixel| pixel == FOREGROUND).count(),
+            7 + 2 * 2
+        );
+    }
+
+    #[test]
+    fn test_wrap_lines() {
+        assert_eq!(
+            wrap_lines("abcde\n\nfg", 2),
+            vec![
+                vec!['a', 'b'],
+                vec!['c', 'd'],
+                vec!['e'],
+                vec![],
+                vec!['f', 'g']
+            ]
+        );
+        // 収まらない行は描かれない
+        assert_eq!(
+            render_rgb(20, 12, "abcdefgh\nijkl"),
+            render_rgb(20, 12, "ab")
+        );
+    }
+
+    #[test]
+    fn test_render_bgr_is_bottom_up() {
+        let bgr = render(InputPixelFormat::Bgr, Colorimetry::HOST, 64, 32, "I");
+        let row_bytes = 64 * 3;
+        // 上からMARGIN行目は、下からは32 - 1 - MARGIN行目
+        let offset = (32 - 1 - MARGIN as usize) * row_bytes + (MARGIN as usize + 2) * 3;
+        assert_eq!(&bgr[offset..offset + 3], &[255, 255, 255]);
+        assert_eq!(&bgr[..3], &[255, 0, 255]);
+    }
+
+    #[test]
+    fn test_non_ascii_is_question_mark() {
+        assert_eq!(render_rgb(64, 32, "あ"), render_rgb(64, 32, "?"));
+    }
+}
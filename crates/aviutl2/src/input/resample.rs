@@ -0,0 +1,398 @@
+//! 音声のサンプルレートの変換。
+//!
+//! カイザー窓をかけたsinc関数で補間します。
+//! 出力の各サンプルは、その位置の前後の入力サンプルだけから計算するため、
+//! 分割して変換しても一度に変換しても同じ結果になります。
+
+/// 低い方のサンプルレートのナイキスト周波数に対する、通過させる帯域の割合。
+const CUTOFF: f64 = 0.95;
+/// 補間に使う片側のsinc関数のゼロ点の数。
+const KERNEL_ZEROS: f64 = 16.0;
+/// カイザー窓のβ。阻止域の減衰量は約90dBになる。
+const KAISER_BETA: f64 = 9.0;
+/// 入力サンプル1つあたりのフィルタ係数の数。係数の間は線形補間する。
+const PHASES: usize = 512;
+
+/// 補間に使うフィルタ。
+#[derive(Debug, Clone)]
+struct Kernel {
+    /// 出力位置の手前（その位置を含む）のうち、使う入力サンプルの数から1を引いたもの。
+    before: i64,
+    /// 出力位置より後ろの、使う入力サンプルの数。
+    after: i64,
+    /// `0`から`after`までの距離に対するフィルタ係数。空の場合はサンプルレートが同じ。
+    table: Vec<f64>,
+}
+
+impl Kernel {
+    fn new(source_rate: u32, target_rate: u32) -> Self {
+        if source_rate == target_rate {
+            return Self {
+                before: 0,
+                after: 0,
+                table: Vec::new(),
+            };
+        }
+        // ダウンサンプリングの場合は、折り返しを防ぐために出力側のナイキスト周波数で切る
+        let cutoff = CUTOFF * (target_rate as f64 / source_rate as f64).min(1.0);
+        let half_width = (KERNEL_ZEROS / cutoff).ceil();
+        let norm = bessel_i0(KAISER_BETA);
+        let table = (0..=half_width as usize * PHASES + 1)
+            .map(|i| {
+                let distance = i as f64 / PHASES as f64;
+                let ratio = distance / half_width;
+                if ratio >= 1.0 {
+                    return 0.0;
+                }
+                let window = bessel_i0(KAISER_BETA * (1.0 - ratio * ratio).sqrt()) / norm;
+                cutoff * sinc(cutoff * distance) * window
+            })
+            .collect();
+        Self {
+            before: half_width as i64 - 1,
+            after: half_width as i64,
+            table,
+        }
+    }
+
+    fn is_identity(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// 出力位置と入力サンプルの距離に対するフィルタ係数。
+    fn weight(&self, distance: f64) -> f64 {
+        let position = distance.abs() * PHASES as f64;
+        let index = position as usize;
+        let fraction = position - index as f64;
+        self.table[index] * (1.0 - fraction) + self.table[index + 1] * fraction
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let x = x * std::f64::consts::PI;
+        x.sin() / x
+    }
+}
+
+/// 第1種変形ベッセル関数（0次）。
+fn bessel_i0(x: f64) -> f64 {
+    let half = x / 2.0;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for k in 1..64 {
+        term *= half / k as f64;
+        let squared = term * term;
+        sum += squared;
+        if squared < sum * 1e-17 {
+            break;
+        }
+    }
+    sum
+}
+
+/// サンプルレートの変換の状態。
+///
+/// 前回の呼び出しで受け取った入力サンプルのうち、続きの出力に必要な分を保持します。
+/// 続きの位置が読み込まれた場合は、保持している分を除いた入力サンプルだけを要求します。
+#[derive(Debug, Default)]
+pub(crate) struct Resampler {
+    /// `(入力のサンプルレート, 出力のサンプルレート, チャンネル数)`と、それに対するフィルタ。
+    kernel: Option<((u32, u32, usize), Kernel)>,
+    /// `cache`の最初のサンプルの、入力の位置。
+    cache_start: i64,
+    /// 保持している入力サンプル。チャンネルごとに分かれている。
+    cache: Vec<Vec<f32>>,
+}
+
+impl Resampler {
+    /// `start`から`len`サンプルを出力するために、次に渡す必要のある入力サンプルの範囲を返す。
+    pub(crate) fn source_range(
+        &self,
+        source_rate: u32,
+        target_rate: u32,
+        channels: usize,
+        start: i64,
+        len: usize,
+    ) -> std::ops::Range<i64> {
+        let needed = match &self.kernel {
+            Some((key, kernel)) if *key == (source_rate, target_rate, channels) => {
+                needed_range(kernel, source_rate, target_rate, start, len)
+            }
+            _ => {
+                let kernel = Kernel::new(source_rate, target_rate);
+                return needed_range(&kernel, source_rate, target_rate, start, len);
+            }
+        };
+        match self.cached_end(&needed) {
+            Some(cache_end) => cache_end..needed.end.max(cache_end),
+            None => needed,
+        }
+    }
+
+    /// 保持している入力サンプルが`needed`の先頭を含む場合、保持している範囲の終わりを返す。
+    fn cached_end(&self, needed: &std::ops::Range<i64>) -> Option<i64> {
+        let cache_end = self.cache_start + self.cache.first().map_or(0, Vec::len) as i64;
+        (self.cache_start <= needed.start && needed.start <= cache_end).then_some(cache_end)
+    }
+
+    /// `start`から`len`サンプルを出力する。
+    ///
+    /// `source`は[`Self::source_range`]が返した範囲の入力サンプルで、チャンネルごとに分かれている必要があります。
+    pub(crate) fn process(
+        &mut self,
+        source_rate: u32,
+        target_rate: u32,
+        start: i64,
+        len: usize,
+        source: &[&[f32]],
+    ) -> Vec<Vec<f32>> {
+        assert!(
+            source_rate > 0 && target_rate > 0,
+            "Sample rate must not be zero"
+        );
+        let channels = source.len();
+        let expected = self.source_range(source_rate, target_rate, channels, start, len);
+        let expected_len = (expected.end - expected.start) as usize;
+        for samples in source {
+            assert_eq!(
+                samples.len(),
+                expected_len,
+                "Expected {expected_len} source samples ({expected:?}) per channel"
+            );
+        }
+
+        let key = (source_rate, target_rate, channels);
+        if self.kernel.as_ref().is_none_or(|(k, _)| *k != key) {
+            self.kernel = Some((key, Kernel::new(source_rate, target_rate)));
+            self.cache = vec![Vec::new(); channels];
+        }
+        let (_, kernel) = self.kernel.as_ref().expect("Unreachable: kernel not set");
+        let needed = needed_range(kernel, source_rate, target_rate, start, len);
+        if self.cached_end(&needed).is_none() {
+            self.cache_start = expected.start;
+            self.cache.iter_mut().for_each(Vec::clear);
+        }
+        for (cache, samples) in self.cache.iter_mut().zip(source) {
+            cache.extend_from_slice(samples);
+        }
+
+        let output = self
+            .cache
+            .iter()
+            .map(|cache| {
+                (0..len as i64)
+                    .map(|i| {
+                        let (center, fraction) =
+                            source_position(start + i, source_rate, target_rate);
+                        if kernel.is_identity() {
+                            return cache[(center - self.cache_start) as usize];
+                        }
+                        let mut sum = 0.0;
+                        for k in center - kernel.before..=center + kernel.after {
+                            let distance = (center - k) as f64 + fraction;
+                            sum += cache[(k - self.cache_start) as usize] as f64
+                                * kernel.weight(distance);
+                        }
+                        sum as f32
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // 続きの出力に使わない入力サンプルを捨てる
+        let next_start =
+            source_position(start + len as i64, source_rate, target_rate).0 - kernel.before;
+        let drop =
+            (next_start - self.cache_start).clamp(0, self.cache.first().map_or(0, Vec::len) as i64);
+        for cache in &mut self.cache {
+            cache.drain(..drop as usize);
+        }
+        self.cache_start += drop;
+        output
+    }
+}
+
+/// 出力の位置に対応する入力の位置を、整数部分と小数部分に分けて返す。
+fn source_position(index: i64, source_rate: u32, target_rate: u32) -> (i64, f64) {
+    let scaled = index as i128 * source_rate as i128;
+    let target_rate = target_rate as i128;
+    (
+        scaled.div_euclid(target_rate) as i64,
+        scaled.rem_euclid(target_rate) as f64 / target_rate as f64,
+    )
+}
+
+/// `start`から`len`サンプルを出力するのに必要な入力サンプルの範囲。
+fn needed_range(
+    kernel: &Kernel,
+    source_rate: u32,
+    target_rate: u32,
+    start: i64,
+    len: usize,
+) -> std::ops::Range<i64> {
+    let first = source_position(start, source_rate, target_rate).0 - kernel.before;
+    if len == 0 {
+        return first..first;
+    }
+    let last = source_position(start + len as i64 - 1, source_rate, target_rate).0 + kernel.after;
+    first..last + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 入力の位置`range`の、周波数`freq`の正弦波。
+    fn sine(range: std::ops::Range<i64>, rate: u32, freq: f64) -> Vec<f32> {
+        range
+            .map(|k| {
+                let t = k as f64 / rate as f64;
+                (0.5 * (2.0 * std::f64::consts::PI * freq * t).sin()) as f32
+            })
+            .collect()
+    }
+
+    fn resample_chunks(
+        source_rate: u32,
+        target_rate: u32,
+        freq: f64,
+        start: i64,
+        chunks: &[usize],
+    ) -> Vec<f32> {
+        let mut resampler = Resampler::default();
+        let mut output = Vec::new();
+        let mut position = start;
+        for &len in chunks {
+            let range = resampler.source_range(source_rate, target_rate, 1, position, len);
+            let source = sine(range, source_rate, freq);
+            let chunk = resampler.process(source_rate, target_rate, position, len, &[&source]);
+            output.extend_from_slice(&chunk[0]);
+            position += len as i64;
+        }
+        output
+    }
+
+    /// 出力から`freq`の正弦波成分を最小二乗法で取り除いた残りの、正弦波成分に対する比（THD+N）と、
+    /// 正弦波成分の振幅を返す。
+    fn thd_plus_noise(samples: &[f32], rate: u32, freq: f64, start: i64) -> (f64, f64) {
+        let omega = 2.0 * std::f64::consts::PI * freq / rate as f64;
+        let basis = |i: usize| {
+            let phase = omega * (start + i as i64) as f64;
+            (phase.sin(), phase.cos())
+        };
+        let (mut ss, mut sc, mut cc, mut ys, mut yc) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for (i, &y) in samples.iter().enumerate() {
+            let (s, c) = basis(i);
+            ss += s * s;
+            sc += s * c;
+            cc += c * c;
+            ys += y as f64 * s;
+            yc += y as f64 * c;
+        }
+        let det = ss * cc - sc * sc;
+        let a = (ys * cc - yc * sc) / det;
+        let b = (yc * ss - ys * sc) / det;
+        let mut signal = 0.0;
+        let mut residual = 0.0;
+        for (i, &y) in samples.iter().enumerate() {
+            let (s, c) = basis(i);
+            let fitted = a * s + b * c;
+            signal += fitted * fitted;
+            residual += (y as f64 - fitted).powi(2);
+        }
+        ((residual / signal).sqrt(), a.hypot(b))
+    }
+
+    /// 変換後の正弦波のTHD+Nが-80dB（0.01%）未満であること。
+    const MAX_THD: f64 = 1e-4;
+
+    #[test]
+    fn test_sine_thd() {
+        for (source_rate, target_rate, freq) in [
+            (44100, 48000, 1000.0),
+            (48000, 44100, 1000.0),
+            (22050, 48000, 5000.0),
+            (96000, 44100, 15000.0),
+        ] {
+            // 出力の途中から読み込んでも、振幅と位相が合っていること
+            let start = 12345;
+            let output = resample_chunks(source_rate, target_rate, freq, start, &[4800]);
+            let (thd, amplitude) = thd_plus_noise(&output, target_rate, freq, start);
+            assert!(
+                thd < MAX_THD,
+                "{source_rate} -> {target_rate} ({freq} Hz): THD+N = {thd}"
+            );
+            assert!(
+                (amplitude - 0.5).abs() < 0.005,
+                "{source_rate} -> {target_rate} ({freq} Hz): amplitude = {amplitude}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_downsampling_rejects_aliases() {
+        // 出力側のナイキスト周波数（11025Hz）を超える成分は、折り返さずに消えること
+        let output = resample_chunks(48000, 22050, 15000.0, 0, &[4096]);
+        let rms = (output[64..]
+            .iter()
+            .map(|v| (*v as f64).powi(2))
+            .sum::<f64>()
+            / (output.len() - 64) as f64)
+            .sqrt();
+        assert!(rms < 0.5 * 1e-3, "rms = {rms}");
+    }
+
+    #[test]
+    fn test_chunks_are_seamless() {
+        let chunks = [1, 7, 480, 1024, 3, 2000, 735, 1];
+        let total = chunks.iter().sum();
+        for (source_rate, target_rate) in [(44100, 48000), (48000, 44100), (48000, 48000)] {
+            let whole = resample_chunks(source_rate, target_rate, 440.0, 100, &[total]);
+            let chunked = resample_chunks(source_rate, target_rate, 440.0, 100, &chunks);
+            assert_eq!(whole, chunked, "{source_rate} -> {target_rate}");
+
+            // 継ぎ目で値が跳ばないこと
+            let max_step = 0.5 * 2.0 * std::f64::consts::PI * 440.0 / target_rate as f64;
+            let mut boundary = 0;
+            for &len in &chunks[..chunks.len() - 1] {
+                boundary += len;
+                let step = (chunked[boundary] - chunked[boundary - 1]).abs() as f64;
+                assert!(step <= max_step * 1.01, "step at {boundary} = {step}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_only_requests_new_samples_when_continuing() {
+        let mut resampler = Resampler::default();
+        let first = resampler.source_range(44100, 48000, 2, 0, 480);
+        assert!(first.start < 0);
+        let source = vec![0.0; (first.end - first.start) as usize];
+        resampler.process(44100, 48000, 0, 480, &[&source, &source]);
+        // 続きの位置は、前回の続きの入力サンプルだけを要求する
+        let next = resampler.source_range(44100, 48000, 2, 480, 480);
+        assert_eq!(next.start, first.end);
+        assert_eq!(next.end - next.start, 441);
+        // 離れた位置は、必要な入力サンプルを全て要求する
+        let seek = resampler.source_range(44100, 48000, 2, 48000, 480);
+        assert_eq!(
+            seek,
+            Resampler::default().source_range(44100, 48000, 2, 48000, 480)
+        );
+        assert!(seek.end - seek.start > 441);
+        // サンプルレートが同じ場合はそのまま
+        assert_eq!(
+            Resampler::default().source_range(48000, 48000, 2, 100, 480),
+            100..580
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "source samples")]
+    fn test_wrong_source_length_panics() {
+        Resampler::default().process(44100, 48000, 0, 480, &[&[0.0; 10]]);
+    }
+}
@@ -0,0 +1,603 @@
+use crate::common::AnyResult;
+use ab_glyph::{Font, ScaleFont};
+use std::sync::Arc;
+
+/// テキストの配置。
+///
+/// ASS形式の`\an`タグと同じく、テンキーの配置に対応しています。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TextAlignment {
+    /// 左下。
+    BottomLeft,
+    /// 中央下。
+    #[default]
+    BottomCenter,
+    /// 右下。
+    BottomRight,
+    /// 左中央。
+    MiddleLeft,
+    /// 中央。
+    MiddleCenter,
+    /// 右中央。
+    MiddleRight,
+    /// 左上。
+    TopLeft,
+    /// 中央上。
+    TopCenter,
+    /// 右上。
+    TopRight,
+}
+
+impl TextAlignment {
+    /// テンキーの番号（1〜9）から作成する。
+    pub fn from_numpad(value: u8) -> Option<Self> {
+        Some(match value {
+            1 => Self::BottomLeft,
+            2 => Self::BottomCenter,
+            3 => Self::BottomRight,
+            4 => Self::MiddleLeft,
+            5 => Self::MiddleCenter,
+            6 => Self::MiddleRight,
+            7 => Self::TopLeft,
+            8 => Self::TopCenter,
+            9 => Self::TopRight,
+            _ => return None,
+        })
+    }
+
+    /// 横方向の位置を、左端を0.0、右端を1.0とした割合で返す。
+    fn horizontal(self) -> f32 {
+        match self {
+            Self::BottomLeft | Self::MiddleLeft | Self::TopLeft => 0.0,
+            Self::BottomCenter | Self::MiddleCenter | Self::TopCenter => 0.5,
+            Self::BottomRight | Self::MiddleRight | Self::TopRight => 1.0,
+        }
+    }
+
+    /// 縦方向の位置を、上端を0.0、下端を1.0とした割合で返す。
+    fn vertical(self) -> f32 {
+        match self {
+            Self::TopLeft | Self::TopCenter | Self::TopRight => 0.0,
+            Self::MiddleLeft | Self::MiddleCenter | Self::MiddleRight => 0.5,
+            Self::BottomLeft | Self::BottomCenter | Self::BottomRight => 1.0,
+        }
+    }
+}
+
+/// テキストのスタイル。
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    /// 文字の大きさ（ピクセル）。
+    pub font_size: f32,
+    /// 文字の色（RGBA）。
+    pub color: [u8; 4],
+    /// 縁取りの色（RGBA）。
+    pub outline_color: [u8; 4],
+    /// 縁取りの太さ（ピクセル）。0の場合は縁取りしません。
+    pub outline_width: u32,
+    /// 配置。複数行のテキストの、行ごとの揃え方にも使われます。
+    pub alignment: TextAlignment,
+    /// 画面端からの余白（ピクセル、横・縦）。[`Self::position`]が`None`の場合に使われます。
+    pub margin: (u32, u32),
+    /// 配置の基準点の座標（ピクセル）。
+    ///
+    /// `Some`の場合、テキストは[`Self::alignment`]に従ってこの点に揃えられます（ASS形式の`\pos`タグ相当）。
+    pub position: Option<(f32, f32)>,
+    /// 行間の倍率。
+    pub line_spacing: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 48.0,
+            color: [255, 255, 255, 255],
+            outline_color: [0, 0, 0, 255],
+            outline_width: 2,
+            alignment: TextAlignment::BottomCenter,
+            margin: (32, 32),
+            position: None,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+/// ラスタライズの結果に影響するスタイルの値。キャッシュのキーに使う。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RasterKey {
+    text: String,
+    font_size: u32,
+    color: [u8; 4],
+    outline_color: [u8; 4],
+    outline_width: u32,
+    horizontal: u32,
+    line_spacing: u32,
+}
+
+impl RasterKey {
+    fn new(text: &str, style: &TextStyle) -> Self {
+        Self {
+            text: text.to_string(),
+            font_size: style.font_size.to_bits(),
+            color: style.color,
+            outline_color: style.outline_color,
+            outline_width: style.outline_width,
+            horizontal: style.alignment.horizontal().to_bits(),
+            line_spacing: style.line_spacing.to_bits(),
+        }
+    }
+}
+
+/// ラスタライズしたテキスト。
+///
+/// ピクセルはストレートαのRGBAで、左上から右下に向かって並びます。
+/// 縁取りの分だけ、四辺に余白があります。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextBitmap {
+    /// 幅。
+    pub width: u32,
+    /// 高さ。
+    pub height: u32,
+    /// ピクセル。
+    pub pixels: Vec<[u8; 4]>,
+}
+
+/// テキストを描画するフレーム。
+///
+/// ピクセルはストレートαのRGBAで、左上から右下に向かって並びます。
+/// [`Self::to_bgra`]で[`InputPixelFormat::Bgra`][crate::input::InputPixelFormat::Bgra]の画像データに変換できます。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextCanvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<[u8; 4]>,
+}
+
+impl TextCanvas {
+    /// 透明なフレームを作成する。
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0, 0, 0, 0]; (width as usize) * (height as usize)],
+        }
+    }
+
+    /// 幅を返す。
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// 高さを返す。
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// 指定した座標のピクセル（RGBA）を返す。
+    ///
+    /// # Panics
+    ///
+    /// 座標が範囲外の場合にパニックします。
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        assert!(
+            x < self.width && y < self.height,
+            "({x}, {y}) is out of range"
+        );
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// ピクセル（RGBA）を返す。
+    pub fn pixels(&self) -> &[[u8; 4]] {
+        &self.pixels
+    }
+
+    /// 全体を透明にする。
+    pub fn clear(&mut self) {
+        self.pixels.fill([0, 0, 0, 0]);
+    }
+
+    /// ラスタライズしたテキストを、スタイルの配置に従って重ねる。
+    pub fn draw_bitmap(&mut self, bitmap: &TextBitmap, style: &TextStyle) {
+        let (anchor_x, anchor_y) = style.position.unwrap_or_else(|| {
+            let horizontal = style.alignment.horizontal();
+            let vertical = style.alignment.vertical();
+            let (margin_x, margin_y) = (style.margin.0 as f32, style.margin.1 as f32);
+            (
+                margin_x + (self.width as f32 - margin_x * 2.0) * horizontal,
+                margin_y + (self.height as f32 - margin_y * 2.0) * vertical,
+            )
+        });
+        let left = (anchor_x - bitmap.width as f32 * style.alignment.horizontal()).round() as i64;
+        let top = (anchor_y - bitmap.height as f32 * style.alignment.vertical()).round() as i64;
+
+        for by in 0..bitmap.height as i64 {
+            let y = top + by;
+            if !(0..self.height as i64).contains(&y) {
+                continue;
+            }
+            for bx in 0..bitmap.width as i64 {
+                let x = left + bx;
+                if !(0..self.width as i64).contains(&x) {
+                    continue;
+                }
+                let src = bitmap.pixels[(by * bitmap.width as i64 + bx) as usize];
+                let dst = &mut self.pixels[(y * self.width as i64 + x) as usize];
+                *dst = blend_over(src, *dst);
+            }
+        }
+    }
+
+    /// [`InputPixelFormat::Bgra`][crate::input::InputPixelFormat::Bgra]の画像データ（左下から右上）に変換する。
+    pub fn to_bgra(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.pixels.len() * 4);
+        for row in self.pixels.chunks_exact(self.width.max(1) as usize).rev() {
+            for &[r, g, b, a] in row {
+                data.extend_from_slice(&[b, g, r, a]);
+            }
+        }
+        data
+    }
+}
+
+/// ストレートαのRGBAを重ねる。
+fn blend_over(src: [u8; 4], dst: [u8; 4]) -> [u8; 4] {
+    let src_a = src[3] as f32 / 255.0;
+    let dst_a = dst[3] as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+    if out_a <= 0.0 {
+        return [0, 0, 0, 0];
+    }
+    let channel = |src: u8, dst: u8| {
+        let value = (src as f32 * src_a + dst as f32 * dst_a * (1.0 - src_a)) / out_a;
+        value.round().clamp(0.0, 255.0) as u8
+    };
+    [
+        channel(src[0], dst[0]),
+        channel(src[1], dst[1]),
+        channel(src[2], dst[2]),
+        (out_a * 255.0).round() as u8,
+    ]
+}
+
+/// テキストを縁取り付きでラスタライズする構造体。
+///
+/// ラスタライズした結果はテキストとスタイルごとにキャッシュされるため、
+/// シークなどで同じフレームを何度も描画してもラスタライズし直しません。
+/// キャッシュが[`Self::with_cache_capacity`]で指定した数を超えた場合は、キャッシュを全て破棄します。
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use aviutl2::input::{TextCanvas, TextRenderer, TextStyle};
+///
+/// let renderer = TextRenderer::from_bytes(std::fs::read("font.ttf")?)?;
+/// let mut canvas = TextCanvas::new(1920, 1080);
+/// renderer.draw(&mut canvas, "こんにちは", &TextStyle::default());
+/// let bgra = canvas.to_bgra();
+/// # Ok::<(), aviutl2::anyhow::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct TextRenderer {
+    font: ab_glyph::FontArc,
+    cache: parking_lot::Mutex<std::collections::HashMap<RasterKey, Arc<TextBitmap>>>,
+    cache_capacity: usize,
+}
+
+impl TextRenderer {
+    /// フォントから作成する。
+    pub fn new(font: ab_glyph::FontArc) -> Self {
+        Self {
+            font,
+            cache: parking_lot::Mutex::new(std::collections::HashMap::new()),
+            cache_capacity: 256,
+        }
+    }
+
+    /// フォントファイル（TrueType・OpenType）のデータから作成する。
+    ///
+    /// フォントコレクション（`.ttc`）の場合は最初のフォントを使います。
+    pub fn from_bytes(data: Vec<u8>) -> AnyResult<Self> {
+        Self::from_bytes_with_index(data, 0)
+    }
+
+    /// フォントファイルのデータと、フォントコレクション内の番号から作成する。
+    pub fn from_bytes_with_index(data: Vec<u8>, index: u32) -> AnyResult<Self> {
+        let font = ab_glyph::FontVec::try_from_vec_and_index(data, index)
+            .map_err(|e| anyhow::anyhow!("Failed to load font: {e}"))?;
+        Ok(Self::new(ab_glyph::FontArc::new(font)))
+    }
+
+    /// キャッシュするテキストの数の上限を設定する。
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    /// キャッシュしているテキストの数を返す。
+    pub fn cache_len(&self) -> usize {
+        self.cache.lock().len()
+    }
+
+    /// キャッシュを破棄する。
+    pub fn clear_cache(&self) {
+        self.cache.lock().clear();
+    }
+
+    /// テキストをラスタライズする。`\n`で改行します。
+    pub fn rasterize(&self, text: &str, style: &TextStyle) -> Arc<TextBitmap> {
+        let key = RasterKey::new(text, style);
+        if let Some(bitmap) = self.cache.lock().get(&key) {
+            return Arc::clone(bitmap);
+        }
+        let bitmap = Arc::new(self.rasterize_uncached(text, style));
+        let mut cache = self.cache.lock();
+        if cache.len() >= self.cache_capacity {
+            cache.clear();
+        }
+        cache.insert(key, Arc::clone(&bitmap));
+        bitmap
+    }
+
+    /// テキストをラスタライズし、フレームに重ねる。
+    pub fn draw(&self, canvas: &mut TextCanvas, text: &str, style: &TextStyle) {
+        let bitmap = self.rasterize(text, style);
+        canvas.draw_bitmap(&bitmap, style);
+    }
+
+    fn rasterize_uncached(&self, text: &str, style: &TextStyle) -> TextBitmap {
+        let scale = ab_glyph::PxScale::from(style.font_size.max(0.0));
+        let font = self.font.as_scaled(scale);
+        let line_height = (font.ascent() - font.descent() + font.line_gap()) * style.line_spacing;
+
+        let mut lines = Vec::new();
+        for line in text.split('\n') {
+            let mut glyphs = Vec::new();
+            let mut caret = 0.0f32;
+            let mut previous = None;
+            for c in line.chars() {
+                let id = font.glyph_id(c);
+                if let Some(previous) = previous {
+                    caret += font.kern(previous, id);
+                }
+                glyphs.push(id.with_scale_and_position(scale, ab_glyph::point(caret, 0.0)));
+                caret += font.h_advance(id);
+                previous = Some(id);
+            }
+            lines.push((glyphs, caret));
+        }
+        let text_width = lines.iter().map(|(_, width)| *width).fold(0.0, f32::max);
+        let padding = style.outline_width as f32 + 1.0;
+        let width = (text_width + padding * 2.0).ceil() as u32;
+        let height = (line_height * lines.len() as f32 + padding * 2.0).ceil() as u32;
+
+        let mut fill = vec![0.0f32; (width as usize) * (height as usize)];
+        let horizontal = style.alignment.horizontal();
+        for (index, (glyphs, line_width)) in lines.into_iter().enumerate() {
+            let offset_x = padding + (text_width - line_width) * horizontal;
+            let baseline = padding + line_height * index as f32 + font.ascent();
+            for mut glyph in glyphs {
+                glyph.position.x += offset_x;
+                glyph.position.y = baseline;
+                let Some(outlined) = self.font.outline_glyph(glyph) else {
+                    continue;
+                };
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    let x = bounds.min.x as i64 + gx as i64;
+                    let y = bounds.min.y as i64 + gy as i64;
+                    if (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+                        let value = &mut fill[(y * width as i64 + x) as usize];
+                        *value = (*value + coverage).min(1.0);
+                    }
+                });
+            }
+        }
+
+        let outline = dilate(&fill, width, height, style.outline_width);
+        let pixels = fill
+            .iter()
+            .zip(&outline)
+            .map(|(&fill, &outline)| {
+                let outline_pixel = with_coverage(style.outline_color, outline);
+                let fill_pixel = with_coverage(style.color, fill);
+                blend_over(fill_pixel, outline_pixel)
+            })
+            .collect();
+        TextBitmap {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// 色のαにカバレッジを掛ける。
+fn with_coverage(color: [u8; 4], coverage: f32) -> [u8; 4] {
+    let [r, g, b, a] = color;
+    [r, g, b, (a as f32 * coverage).round() as u8]
+}
+
+/// 各ピクセルについて、半径`radius`の円の中の最大のカバレッジを求める。
+fn dilate(coverage: &[f32], width: u32, height: u32, radius: u32) -> Vec<f32> {
+    if radius == 0 {
+        return vec![0.0; coverage.len()];
+    }
+    let radius = radius as i64;
+    let offsets = (-radius..=radius)
+        .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+        .filter(|(dx, dy)| dx * dx + dy * dy <= radius * radius)
+        .collect::<Vec<_>>();
+    let (width, height) = (width as i64, height as i64);
+    let mut result = vec![0.0f32; coverage.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let mut max = 0.0f32;
+            for &(dx, dy) in &offsets {
+                let (sx, sy) = (x + dx, y + dy);
+                if (0..width).contains(&sx) && (0..height).contains(&sy) {
+                    max = max.max(coverage[(sy * width + sx) as usize]);
+                }
+            }
+            result[(y * width + x) as usize] = max;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renderer() -> TextRenderer {
+        TextRenderer::from_bytes(epaint_default_fonts::UBUNTU_LIGHT.to_vec()).unwrap()
+    }
+
+    /// 不透明度が閾値以上のピクセルを含む範囲を（左, 上, 右, 下）で返す。
+    fn opaque_bounds(canvas: &TextCanvas) -> Option<(u32, u32, u32, u32)> {
+        let mut bounds: Option<(u32, u32, u32, u32)> = None;
+        for y in 0..canvas.height() {
+            for x in 0..canvas.width() {
+                if canvas.pixel(x, y)[3] >= 128 {
+                    bounds = Some(match bounds {
+                        None => (x, y, x, y),
+                        Some((l, t, r, b)) => (l.min(x), t.min(y), r.max(x), b.max(y)),
+                    });
+                }
+            }
+        }
+        bounds
+    }
+
+    #[test]
+    fn test_rasterize_fill_and_outline() {
+        let style = TextStyle {
+            font_size: 96.0,
+            outline_width: 3,
+            ..Default::default()
+        };
+        let bitmap = renderer().rasterize("I", &style);
+        assert!(bitmap.width > 0 && bitmap.height > 0);
+        assert_eq!(bitmap.pixels.len(), (bitmap.width * bitmap.height) as usize);
+        // 縦棒の中央は文字の色、その左右は縁取りの色になる
+        let row = bitmap.height / 2;
+        let pixels = &bitmap.pixels[(row * bitmap.width) as usize..][..bitmap.width as usize];
+        assert!(pixels.contains(&[255, 255, 255, 255]));
+        assert!(pixels.contains(&[0, 0, 0, 255]));
+        // 余白は透明
+        assert_eq!(pixels[0][3], 0);
+        assert_eq!(pixels[bitmap.width as usize - 1][3], 0);
+    }
+
+    #[test]
+    fn test_rasterize_is_cached() {
+        let renderer = renderer();
+        let style = TextStyle::default();
+        let first = renderer.rasterize("Hello", &style);
+        let second = renderer.rasterize("Hello", &style);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(renderer.cache_len(), 1);
+
+        // 位置はラスタライズ結果に影響しないので、同じキャッシュを使う
+        let moved = TextStyle {
+            position: Some((10.0, 10.0)),
+            ..style.clone()
+        };
+        assert!(Arc::ptr_eq(&first, &renderer.rasterize("Hello", &moved)));
+
+        let larger = TextStyle {
+            font_size: 96.0,
+            ..style
+        };
+        assert!(!Arc::ptr_eq(&first, &renderer.rasterize("Hello", &larger)));
+        assert_eq!(renderer.cache_len(), 2);
+
+        let renderer = renderer.with_cache_capacity(1);
+        renderer.rasterize("a", &TextStyle::default());
+        renderer.rasterize("b", &TextStyle::default());
+        assert_eq!(renderer.cache_len(), 1);
+    }
+
+    #[test]
+    fn test_draw_bottom_center() {
+        let renderer = renderer();
+        let mut canvas = TextCanvas::new(640, 360);
+        let style = TextStyle {
+            margin: (0, 20),
+            ..Default::default()
+        };
+        renderer.draw(&mut canvas, "Hello", &style);
+        let (left, top, right, bottom) = opaque_bounds(&canvas).unwrap();
+        // 横方向は中央、縦方向は下端から余白の分だけ上
+        let center = (left + right) as i64 / 2;
+        assert!((center - 320).abs() <= 4, "center: {center}");
+        assert!(
+            bottom < 360 - 20 && bottom > 360 - 20 - 48,
+            "bottom: {bottom}"
+        );
+        assert!(top > 360 / 2, "top: {top}");
+    }
+
+    #[test]
+    fn test_draw_position_and_lines() {
+        let renderer = renderer();
+        let mut canvas = TextCanvas::new(640, 360);
+        let style = TextStyle {
+            alignment: TextAlignment::TopLeft,
+            position: Some((100.0, 50.0)),
+            ..Default::default()
+        };
+        renderer.draw(&mut canvas, "A\nB", &style);
+        let (left, top, _, bottom) = opaque_bounds(&canvas).unwrap();
+        assert!((100..110).contains(&left), "left: {left}");
+        assert!((50..70).contains(&top), "top: {top}");
+        // 2行分の高さがある
+        assert!(bottom - top > 48, "height: {}", bottom - top);
+    }
+
+    #[test]
+    fn test_to_bgra_is_bottom_up() {
+        let mut canvas = TextCanvas::new(2, 2);
+        canvas.draw_bitmap(
+            &TextBitmap {
+                width: 1,
+                height: 1,
+                pixels: vec![[10, 20, 30, 255]],
+            },
+            &TextStyle {
+                alignment: TextAlignment::TopLeft,
+                position: Some((0.0, 0.0)),
+                ..Default::default()
+            },
+        );
+        assert_eq!(canvas.pixel(0, 0), [10, 20, 30, 255]);
+        // 左上のピクセルは、BGRAでは最後の行の先頭になる
+        assert_eq!(
+            canvas.to_bgra(),
+            [0, 0, 0, 0, 0, 0, 0, 0, 30, 20, 10, 255, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_blend_over() {
+        assert_eq!(
+            blend_over([255, 0, 0, 255], [0, 0, 255, 255]),
+            [255, 0, 0, 255]
+        );
+        assert_eq!(blend_over([0, 0, 0, 0], [0, 0, 255, 255]), [0, 0, 255, 255]);
+        assert_eq!(
+            blend_over([255, 255, 255, 128], [0, 0, 0, 0]),
+            [255, 255, 255, 128]
+        );
+    }
+
+    #[test]
+    fn test_alignment_from_numpad() {
+        assert_eq!(
+            TextAlignment::from_numpad(2),
+            Some(TextAlignment::BottomCenter)
+        );
+        assert_eq!(TextAlignment::from_numpad(7), Some(TextAlignment::TopLeft));
+        assert_eq!(TextAlignment::from_numpad(0), None);
+        assert_eq!(TextAlignment::from_numpad(10), None);
+    }
+}
@@ -0,0 +1,455 @@
+use crate::common::{AnyResult, Rational32};
+
+/// 時刻がフレームの範囲外になったときの扱い方。
+///
+/// # See Also
+/// [`FrameTimeline::frame_for`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeToFrame {
+    /// 範囲外の時刻を端に寄せる。
+    ///
+    /// 負の時刻は最初のフレーム、長さ以降の時刻は最後のフレームになります。
+    Clamp,
+    /// 長さごとに繰り返す。
+    ///
+    /// 負の時刻は末尾から遡ってループします（`-0.1`秒は長さ - 0.1秒と同じフレームになります）。
+    Loop,
+    /// 再生開始前は最初のフレームを表示し、それ以降は長さごとに繰り返す。
+    ///
+    /// 負の時刻は[`TimeToFrame::Clamp`]、0以上の時刻は[`TimeToFrame::Loop`]と同じフレームになります。
+    Hold,
+}
+
+/// 可変フレームレートの動画・アニメーション画像の、各フレームの開始時刻を保持する構造体。
+///
+/// [`VideoInputInfo::manual_frame_index`][crate::input::VideoInputInfo::manual_frame_index]を`true`にした場合の、
+/// [`InputPlugin::time_to_frame`][crate::input::InputPlugin::time_to_frame]の実装に使えます。
+///
+/// # Example
+///
+/// ```rust
+/// use aviutl2::input::{FrameTimeline, TimeToFrame};
+///
+/// // 0.1秒、0.2秒、0.1秒のフレームからなるアニメーション
+/// let timeline = FrameTimeline::from_durations([0.1, 0.2, 0.1]).unwrap();
+/// assert_eq!(timeline.frame_for(0.15, TimeToFrame::Loop), 1);
+/// assert_eq!(timeline.frame_for(0.45, TimeToFrame::Loop), 0);
+/// assert_eq!(timeline.frame_for(0.45, TimeToFrame::Clamp), 2);
+/// assert_eq!(timeline.frame_for(-0.05, TimeToFrame::Loop), 2);
+/// assert_eq!(timeline.frame_for(-0.05, TimeToFrame::Hold), 0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameTimeline {
+    /// （開始時刻, フレーム番号）。開始時刻の昇順に並んでいる。
+    timings: Vec<(f64, u32)>,
+    duration: f64,
+}
+
+impl FrameTimeline {
+    /// 各フレームの開始時刻（秒）とフレーム番号の組、および全体の長さ（秒）から作成する。
+    ///
+    /// `BTreeMap<開始時刻, フレーム番号>`のようなフレームタイミングの表をそのまま渡せます。
+    /// 同じ開始時刻が複数ある場合は、後に渡したものが優先されます。
+    ///
+    /// # Errors
+    ///
+    /// - フレームが1つもない場合
+    /// - 開始時刻・長さが有限の0以上の値でない場合
+    pub fn new(timings: impl IntoIterator<Item = (f64, u32)>, duration: f64) -> AnyResult<Self> {
+        anyhow::ensure!(
+            duration.is_finite() && duration >= 0.0,
+            "Invalid duration: {duration}"
+        );
+        let mut sorted: Vec<(f64, u32)> = Vec::new();
+        for (start, frame) in timings {
+            anyhow::ensure!(
+                start.is_finite() && start >= 0.0,
+                "Invalid start time for frame {frame}: {start}"
+            );
+            sorted.push((start, frame));
+        }
+        anyhow::ensure!(
+            !sorted.is_empty(),
+            "Frame timeline must have at least one frame"
+        );
+        // 安定ソートなので、同じ開始時刻の中では渡した順が保たれる
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut timings: Vec<(f64, u32)> = Vec::with_capacity(sorted.len());
+        for (start, frame) in sorted {
+            match timings.last_mut() {
+                Some(last) if last.0 == start => last.1 = frame,
+                _ => timings.push((start, frame)),
+            }
+        }
+        Ok(Self { timings, duration })
+    }
+
+    /// 各フレームの表示時間（秒）から作成する。
+    ///
+    /// フレーム番号は0から順に割り当てられ、全体の長さは表示時間の合計になります。
+    /// 表示時間が0のフレームは、次のフレームに上書きされるため選ばれません。
+    ///
+    /// # Errors
+    ///
+    /// - フレームが1つもない場合
+    /// - 表示時間が有限の0以上の値でない場合
+    pub fn from_durations(durations: impl IntoIterator<Item = f64>) -> AnyResult<Self> {
+        let mut timings = Vec::new();
+        let mut total = 0.0;
+        for (frame, duration) in durations.into_iter().enumerate() {
+            anyhow::ensure!(
+                duration.is_finite() && duration >= 0.0,
+                "Invalid duration for frame {frame}: {duration}"
+            );
+            timings.push((total, frame as u32));
+            total += duration;
+        }
+        Self::new(timings, total)
+    }
+
+    /// 全体の長さ（秒）を返す。
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// 保持しているフレームの数を返す。
+    pub fn len(&self) -> usize {
+        self.timings.len()
+    }
+
+    /// フレームを保持していないかどうかを返す。
+    ///
+    /// [`FrameTimeline`]は常に1つ以上のフレームを保持するため、常に`false`を返します。
+    pub fn is_empty(&self) -> bool {
+        self.timings.is_empty()
+    }
+
+    /// 指定した時刻（秒）に表示するフレーム番号を返す。
+    ///
+    /// 時刻が範囲外の場合は`strategy`に従います。
+    /// 時刻がNaNの場合や、長さが0の場合は最初のフレームを返します。
+    pub fn frame_for(&self, time: f64, strategy: TimeToFrame) -> u32 {
+        if time.is_nan() || self.duration == 0.0 {
+            return self.first_frame();
+        }
+        match strategy {
+            TimeToFrame::Clamp => self.clamped(time),
+            TimeToFrame::Loop => self.clamped(time.rem_euclid(self.duration)),
+            TimeToFrame::Hold if time < 0.0 => self.first_frame(),
+            TimeToFrame::Hold => self.clamped(time.rem_euclid(self.duration)),
+        }
+    }
+
+    fn first_frame(&self) -> u32 {
+        self.timings[0].1
+    }
+
+    fn clamped(&self, time: f64) -> u32 {
+        let index = if time >= self.duration {
+            // 長さちょうどに開始するフレームは表示されないので、その手前のフレームを使う
+            self.timings
+                .partition_point(|&(start, _)| start < self.duration)
+        } else {
+            self.timings.partition_point(|&(start, _)| start <= time)
+        };
+        self.timings[index.saturating_sub(1)].1
+    }
+}
+
+/// 字幕のように、表示する要素（キュー）が時刻によって切り替わる入力のタイムライン。
+///
+/// キューの開始・終了時刻で区切った区間を1フレームとして扱います。
+/// 同じ区間では表示するキューが変わらないため、字幕を画像として読み込む入力プラグインで
+/// [`VideoInputInfo::manual_frame_index`][crate::input::VideoInputInfo::manual_frame_index]を`true`にして使うことで、
+/// 同じ画像を何度も描画せずに済みます。
+///
+/// # Example
+///
+/// ```rust
+/// use aviutl2::input::CueTimeline;
+///
+/// // 0.5〜1.5秒と1.0〜2.0秒に表示するキュー
+/// let timeline = CueTimeline::new([(0.5, 1.5), (1.0, 2.0)]).unwrap();
+/// // 0〜0.5、0.5〜1.0、1.0〜1.5、1.5〜2.0の4区間
+/// assert_eq!(timeline.num_frames(), 4);
+/// assert_eq!(timeline.frame_for(1.2), 2);
+/// assert_eq!(timeline.cues_at(2), [0, 1]);
+/// assert!(timeline.cues_at(0).is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CueTimeline {
+    timeline: FrameTimeline,
+    /// 各区間で表示するキューの番号。
+    segments: Vec<Vec<usize>>,
+}
+
+impl CueTimeline {
+    /// キューの（開始時刻, 終了時刻）（秒）から作成する。
+    ///
+    /// キューの番号は渡した順に0から割り当てられます。
+    /// 全体の長さは最後に終了するキューの終了時刻になり、0秒から最初のキューまでは何も表示しない区間になります。
+    ///
+    /// # Errors
+    ///
+    /// - 時刻が有限の0以上の値でない場合
+    /// - 終了時刻が開始時刻より前の場合
+    pub fn new(cues: impl IntoIterator<Item = (f64, f64)>) -> AnyResult<Self> {
+        let cues = cues.into_iter().collect::<Vec<_>>();
+        let mut boundaries = vec![0.0];
+        for (index, &(start, end)) in cues.iter().enumerate() {
+            anyhow::ensure!(
+                start.is_finite() && start >= 0.0 && end.is_finite() && end >= 0.0,
+                "Invalid time for cue {index}: {start}..{end}"
+            );
+            anyhow::ensure!(
+                start <= end,
+                "Cue {index} ends before it starts: {start}..{end}"
+            );
+            boundaries.push(start);
+            boundaries.push(end);
+        }
+        boundaries.sort_by(f64::total_cmp);
+        boundaries.dedup();
+        let duration = *boundaries.last().unwrap_or(&0.0);
+
+        let mut segments = Vec::new();
+        let mut timings = Vec::new();
+        // 最後の境界は終了時刻なので区間を作らない。ただし、キューが無い場合は空の区間を1つ作る。
+        let starts = &boundaries[..boundaries.len().saturating_sub(1).max(1)];
+        for (frame, &start) in starts.iter().enumerate() {
+            let visible = cues
+                .iter()
+                .enumerate()
+                .filter(|(_, (cue_start, cue_end))| *cue_start <= start && start < *cue_end)
+                .map(|(index, _)| index)
+                .collect();
+            segments.push(visible);
+            timings.push((start, frame as u32));
+        }
+        Ok(Self {
+            timeline: FrameTimeline::new(timings, duration)?,
+            segments,
+        })
+    }
+
+    /// 区間を表す [`FrameTimeline`] を返す。
+    pub fn timeline(&self) -> &FrameTimeline {
+        &self.timeline
+    }
+
+    /// 区間（フレーム）の数を返す。
+    pub fn num_frames(&self) -> u32 {
+        self.segments.len() as u32
+    }
+
+    /// 全体の長さ（秒）を返す。
+    pub fn duration(&self) -> f64 {
+        self.timeline.duration()
+    }
+
+    /// 指定した時刻（秒）のフレーム番号を返す。範囲外の時刻は端に寄せます。
+    pub fn frame_for(&self, time: f64) -> u32 {
+        self.timeline.frame_for(time, TimeToFrame::Clamp)
+    }
+
+    /// 指定したフレームで表示するキューの番号を返す。範囲外のフレームの場合は空を返します。
+    pub fn cues_at(&self, frame: u32) -> &[usize] {
+        self.segments
+            .get(frame as usize)
+            .map_or(&[], |segment| segment.as_slice())
+    }
+}
+
+/// 固定フレームレートの場合の、時刻（秒）に対応するフレーム番号を返す。
+///
+/// 負の時刻は最初のフレーム、動画の長さ以降の時刻は最後のフレームになります。
+pub(crate) fn frame_at_constant_rate(fps: Rational32, num_frames: u32, time: f64) -> u32 {
+    if time.is_nan() {
+        return 0;
+    }
+    let frame = (time * *fps.numer() as f64 / *fps.denom() as f64).floor();
+    // 負の値は`as`で0になる
+    (frame as u32).min(num_frames.saturating_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// ミリ秒単位の表示時間からなるタイムラインを生成する。
+    fn timeline_strategy() -> impl Strategy<Value = FrameTimeline> {
+        prop::collection::vec(0u32..500, 1..32).prop_map(|durations| {
+            FrameTimeline::from_durations(durations.into_iter().map(|ms| ms as f64 / 1000.0))
+                .unwrap()
+        })
+    }
+
+    const STRATEGIES: [TimeToFrame; 3] = [TimeToFrame::Clamp, TimeToFrame::Loop, TimeToFrame::Hold];
+
+    proptest! {
+        #[test]
+        fn strategies_agree_inside_duration(
+            timeline in timeline_strategy(),
+            ratio in 0.0f64..1.0,
+        ) {
+            let time = timeline.duration() * ratio;
+            prop_assume!(time < timeline.duration());
+            let clamp = timeline.frame_for(time, TimeToFrame::Clamp);
+            prop_assert_eq!(timeline.frame_for(time, TimeToFrame::Loop), clamp);
+            prop_assert_eq!(timeline.frame_for(time, TimeToFrame::Hold), clamp);
+        }
+
+        #[test]
+        fn hold_matches_clamp_before_start_and_loop_after(
+            timeline in timeline_strategy(),
+            time in -100.0f64..100.0,
+        ) {
+            let hold = timeline.frame_for(time, TimeToFrame::Hold);
+            if time < 0.0 {
+                prop_assert_eq!(hold, timeline.frame_for(time, TimeToFrame::Clamp));
+            } else {
+                prop_assert_eq!(hold, timeline.frame_for(time, TimeToFrame::Loop));
+            }
+        }
+
+        #[test]
+        fn clamp_saturates_at_both_ends(
+            timeline in timeline_strategy(),
+            over in 0.0f64..100.0,
+        ) {
+            let first = timeline.frame_for(0.0, TimeToFrame::Clamp);
+            prop_assert_eq!(timeline.frame_for(-over, TimeToFrame::Clamp), first);
+            let last = timeline.frame_for(timeline.duration() + over, TimeToFrame::Clamp);
+            prop_assert_eq!(timeline.frame_for(timeline.duration(), TimeToFrame::Clamp), last);
+        }
+
+        #[test]
+        fn loop_matches_clamp_of_wrapped_time(
+            timeline in timeline_strategy(),
+            time in -100.0f64..100.0,
+        ) {
+            let wrapped = if timeline.duration() == 0.0 {
+                0.0
+            } else {
+                time.rem_euclid(timeline.duration())
+            };
+            prop_assert_eq!(
+                timeline.frame_for(time, TimeToFrame::Loop),
+                timeline.frame_for(wrapped, TimeToFrame::Clamp)
+            );
+        }
+
+        #[test]
+        fn frames_are_monotonic_within_one_loop(
+            timeline in timeline_strategy(),
+            mut ratios in prop::collection::vec(0.0f64..1.0, 2..16),
+        ) {
+            ratios.sort_by(f64::total_cmp);
+            // 丸め誤差で長さちょうどになった時刻はループの先頭に戻るので除く
+            ratios.retain(|ratio| timeline.duration() * ratio < timeline.duration());
+            for strategy in STRATEGIES {
+                let frames: Vec<u32> = ratios
+                    .iter()
+                    .map(|ratio| timeline.frame_for(timeline.duration() * ratio, strategy))
+                    .collect();
+                prop_assert!(frames.windows(2).all(|pair| pair[0] <= pair[1]));
+            }
+        }
+
+        #[test]
+        fn frame_is_always_in_range(
+            durations in prop::collection::vec(0.0f64..1.0, 1..32),
+            time in prop::num::f64::ANY,
+        ) {
+            let num_frames = durations.len();
+            let timeline = FrameTimeline::from_durations(durations).unwrap();
+            for strategy in STRATEGIES {
+                prop_assert!((timeline.frame_for(time, strategy) as usize) < num_frames);
+            }
+        }
+
+        #[test]
+        fn constant_rate_is_clamped(
+            fps in 1i32..240,
+            num_frames in 1u32..1000,
+            time in prop::num::f64::ANY,
+        ) {
+            let frame = frame_at_constant_rate(Rational32::new(fps, 1), num_frames, time);
+            prop_assert!(frame < num_frames);
+            if time <= 0.0 || time.is_nan() {
+                prop_assert_eq!(frame, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_for_boundaries() {
+        let timeline = FrameTimeline::from_durations([0.5, 0.5]).unwrap();
+        assert_eq!(timeline.frame_for(0.0, TimeToFrame::Clamp), 0);
+        assert_eq!(timeline.frame_for(0.5, TimeToFrame::Clamp), 1);
+        assert_eq!(timeline.frame_for(1.0, TimeToFrame::Clamp), 1);
+        assert_eq!(timeline.frame_for(1.0, TimeToFrame::Loop), 0);
+        assert_eq!(timeline.frame_for(-0.25, TimeToFrame::Loop), 1);
+        assert_eq!(timeline.frame_for(-0.25, TimeToFrame::Hold), 0);
+        assert_eq!(timeline.frame_for(1.25, TimeToFrame::Hold), 0);
+        assert_eq!(timeline.frame_for(f64::NAN, TimeToFrame::Loop), 0);
+    }
+
+    #[test]
+    fn test_new_ignores_frame_starting_at_end() {
+        // 最後のフレームの終了時刻にも番号が振られている表
+        let timeline = FrameTimeline::new([(0.0, 0), (0.5, 1), (1.0, 2)], 1.0).unwrap();
+        assert_eq!(timeline.frame_for(1.0, TimeToFrame::Clamp), 1);
+        assert_eq!(timeline.frame_for(0.99, TimeToFrame::Loop), 1);
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_timings() {
+        assert!(FrameTimeline::new(std::iter::empty(), 1.0).is_err());
+        assert!(FrameTimeline::new([(-1.0, 0)], 1.0).is_err());
+        assert!(FrameTimeline::new([(0.0, 0)], f64::INFINITY).is_err());
+        assert!(FrameTimeline::from_durations([0.1, f64::NAN]).is_err());
+    }
+
+    #[test]
+    fn test_cue_timeline_segments() {
+        // 重なるキューと、間が空いたキュー
+        let timeline = CueTimeline::new([(1.0, 3.0), (2.0, 4.0), (5.0, 6.0)]).unwrap();
+        assert_eq!(timeline.num_frames(), 6);
+        assert_eq!(timeline.duration(), 6.0);
+        let expected: [&[usize]; 6] = [&[], &[0], &[0, 1], &[1], &[], &[2]];
+        for (frame, cues) in expected.into_iter().enumerate() {
+            assert_eq!(timeline.cues_at(frame as u32), cues, "frame {frame}");
+        }
+        assert_eq!(timeline.frame_for(0.5), 0);
+        assert_eq!(timeline.frame_for(2.0), 2);
+        assert_eq!(timeline.frame_for(4.5), 4);
+        assert_eq!(timeline.frame_for(100.0), 5);
+        assert!(timeline.cues_at(6).is_empty());
+    }
+
+    #[test]
+    fn test_cue_timeline_edge_cases() {
+        let empty = CueTimeline::new(std::iter::empty()).unwrap();
+        assert_eq!(empty.num_frames(), 1);
+        assert!(empty.cues_at(0).is_empty());
+
+        // 0秒から始まるキューは、空の区間を作らない
+        let timeline = CueTimeline::new([(0.0, 1.0)]).unwrap();
+        assert_eq!(timeline.num_frames(), 1);
+        assert_eq!(timeline.cues_at(0), [0]);
+
+        assert!(CueTimeline::new([(2.0, 1.0)]).is_err());
+        assert!(CueTimeline::new([(-1.0, 1.0)]).is_err());
+        assert!(CueTimeline::new([(0.0, f64::NAN)]).is_err());
+    }
+
+    #[test]
+    fn test_constant_rate() {
+        let fps = Rational32::new(30000, 1001);
+        assert_eq!(frame_at_constant_rate(fps, 100, -1.0), 0);
+        assert_eq!(frame_at_constant_rate(fps, 100, 1.0), 29);
+        assert_eq!(frame_at_constant_rate(fps, 100, 1000.0), 99);
+    }
+}
@@ -0,0 +1,239 @@
+//! # aviutl2-rs / interop
+//!
+//! スクリプトモジュールとフィルタプラグインの間で値を受け渡すためのモジュール。
+//!
+//! [`SharedStore`]に書き込んだ値は、Luaスクリプトとフィルタの両方から読み書きできます。
+//! - Luaスクリプトからは、[`ScriptModuleTable::enable_shared_store`][crate::module::ScriptModuleTable::enable_shared_store]を
+//!   有効にしたモジュールの`set`・`get`関数を使います。
+//! - Rustのフィルタからは、[`crate::filter::shared_store`]を使います。
+//!
+//! ```lua
+//! -- テキストオブジェクトのスクリプト
+//! mymod.set("speed", 2.0, obj.frame)
+//! ```
+//!
+//! ```rust
+//! fn proc_video(video: &mut aviutl2::filter::FilterProcVideo) {
+//!     let speed = aviutl2::filter::shared_store()
+//!         .get_at_frame("speed", video.object.frame)
+//!         .and_then(|value| value.as_f64())
+//!         .unwrap_or(1.0);
+//! }
+//! ```
+//!
+//! # Note
+//!
+//! - ストアはDLLごとに1つです。スクリプトモジュールとフィルタプラグインが別々のDLLの場合は値を共有できないため、
+//!   汎用プラグインで[`SubPlugin`][crate::generic::SubPlugin]を使い、1つのDLLに両方を登録してください。
+//! - AviUtl2 SDKではスクリプトモジュールの関数呼び出しにフレーム番号が渡されないため、
+//!   フレームと対応付ける場合はスクリプト側からフレーム番号を渡してください。
+//!   フレーム番号の基準（オブジェクト基準かシーン基準か）は、読み書きする側で揃える必要があります。
+
+use crate::module::ScriptModuleValue;
+use std::collections::{BTreeMap, HashMap};
+
+/// [`shared_store`]がキーごとに保持するフレーム数。
+pub const DEFAULT_HISTORY_FRAMES: usize = 120;
+
+/// フレームごとの値を保持する、スクリプトとフィルタの間で共有するキーバリューストア。
+///
+/// スクリプトの評価とフィルタの処理は別々のスレッドから、順不同で行われます。
+/// そのため、値はフレーム番号と一緒にキーごとの履歴として記録され、
+/// フィルタからは[`SharedStore::get_at_frame`]でそのフレームで設定された値を取得できます。
+///
+/// 履歴はキーごとに`history_frames`フレームまで保持され、それを超えた場合は
+/// 最後に書き込んだフレームから最も遠いフレームの値が破棄されます。
+///
+/// 詳しくは[モジュールのドキュメント][self]を参照してください。
+#[derive(Debug)]
+pub struct SharedStore {
+    history_frames: usize,
+    entries: parking_lot::RwLock<HashMap<String, StoreEntry>>,
+}
+
+#[derive(Debug)]
+struct StoreEntry {
+    /// 最後に書き込まれた値。
+    latest: ScriptModuleValue,
+    /// フレーム番号ごとの値。
+    frames: BTreeMap<u32, ScriptModuleValue>,
+}
+
+impl SharedStore {
+    /// キーごとに`history_frames`フレームまで保持するストアを作成する。
+    ///
+    /// # Panics
+    ///
+    /// `history_frames`が0の場合にパニックします。
+    pub fn new(history_frames: usize) -> Self {
+        assert!(history_frames > 0, "history_frames must be greater than 0");
+        Self {
+            history_frames,
+            entries: parking_lot::RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// キーごとに保持するフレーム数を返す。
+    pub fn history_frames(&self) -> usize {
+        self.history_frames
+    }
+
+    /// フレームに関係なく値を設定する。
+    ///
+    /// キーのフレームごとの履歴は削除され、[`SharedStore::get_at_frame`]はどのフレームでもこの値を返すようになります。
+    pub fn set(&self, key: &str, value: impl Into<ScriptModuleValue>) {
+        self.entries.write().insert(
+            key.to_string(),
+            StoreEntry {
+                latest: value.into(),
+                frames: BTreeMap::new(),
+            },
+        );
+    }
+
+    /// `frame`での値を設定する。
+    pub fn set_at_frame(&self, key: &str, frame: u32, value: impl Into<ScriptModuleValue>) {
+        let value = value.into();
+        let mut entries = self.entries.write();
+        let entry = entries
+            .entry(key.to_string())
+            .or_insert_with(|| StoreEntry {
+                latest: value.clone(),
+                frames: BTreeMap::new(),
+            });
+        entry.latest = value.clone();
+        entry.frames.insert(frame, value);
+        while entry.frames.len() > self.history_frames {
+            // 書き込んだフレームから最も遠いフレームを破棄する
+            let (&first, _) = entry.frames.first_key_value().expect("frames is not empty");
+            let (&last, _) = entry.frames.last_key_value().expect("frames is not empty");
+            let farthest = if frame.abs_diff(first) >= frame.abs_diff(last) {
+                first
+            } else {
+                last
+            };
+            entry.frames.remove(&farthest);
+        }
+    }
+
+    /// 最後に書き込まれた値を取得する。
+    pub fn get(&self, key: &str) -> Option<ScriptModuleValue> {
+        self.entries
+            .read()
+            .get(key)
+            .map(|entry| entry.latest.clone())
+    }
+
+    /// `frame`での値を取得する。
+    ///
+    /// `frame`の値が記録されていない場合は、それより前で最も近いフレームの値を返します。
+    /// [`SharedStore::set`]で設定された値は、どのフレームでも返されます。
+    /// キーがない場合や、`frame`より前の値が記録されていない場合は`None`を返します。
+    pub fn get_at_frame(&self, key: &str, frame: u32) -> Option<ScriptModuleValue> {
+        let entries = self.entries.read();
+        let entry = entries.get(key)?;
+        if entry.frames.is_empty() {
+            return Some(entry.latest.clone());
+        }
+        entry
+            .frames
+            .range(..=frame)
+            .next_back()
+            .map(|(_, value)| value.clone())
+    }
+
+    /// キーを削除する。
+    pub fn remove(&self, key: &str) -> Option<ScriptModuleValue> {
+        self.entries.write().remove(key).map(|entry| entry.latest)
+    }
+
+    /// すべてのキーを削除する。
+    pub fn clear(&self) {
+        self.entries.write().clear();
+    }
+}
+
+impl Default for SharedStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_FRAMES)
+    }
+}
+
+static SHARED_STORE: std::sync::LazyLock<SharedStore> =
+    std::sync::LazyLock::new(SharedStore::default);
+
+/// DLL内で共有される[`SharedStore`]を返す。
+///
+/// スクリプトモジュールの`set`・`get`関数はこのストアを読み書きします。
+pub fn shared_store() -> &'static SharedStore {
+    &SHARED_STORE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_at_frame_sees_value_for_that_frame() {
+        let store = SharedStore::new(16);
+        for frame in 0..10 {
+            store.set_at_frame("speed", frame, frame as f64 * 0.5);
+        }
+        // フィルタが順不同で処理しても、そのフレームの値が返る
+        for frame in [7, 2, 9, 0] {
+            assert_eq!(
+                store.get_at_frame("speed", frame),
+                Some(ScriptModuleValue::Float(frame as f64 * 0.5))
+            );
+        }
+        assert_eq!(store.get("speed"), Some(ScriptModuleValue::Float(4.5)));
+        assert_eq!(store.get_at_frame("unknown", 0), None);
+    }
+
+    #[test]
+    fn test_get_at_frame_falls_back_to_previous_frame() {
+        let store = SharedStore::new(16);
+        store.set_at_frame("mode", 10, "slow");
+        store.set_at_frame("mode", 20, "fast");
+        assert_eq!(store.get_at_frame("mode", 5), None);
+        assert_eq!(store.get_at_frame("mode", 15), Some("slow".into()));
+        assert_eq!(store.get_at_frame("mode", 25), Some("fast".into()));
+    }
+
+    #[test]
+    fn test_set_without_frame_applies_to_all_frames() {
+        let store = SharedStore::new(16);
+        store.set_at_frame("enabled", 3, false);
+        store.set("enabled", true);
+        assert_eq!(store.get_at_frame("enabled", 0), Some(true.into()));
+        assert_eq!(store.get_at_frame("enabled", 3), Some(true.into()));
+    }
+
+    #[test]
+    fn test_evicts_farthest_frames() {
+        let store = SharedStore::new(3);
+        for frame in [10, 11, 12, 13] {
+            store.set_at_frame("x", frame, frame as i32);
+        }
+        // 13を書き込んだ時点で、最も遠い10が破棄される
+        assert_eq!(store.get_at_frame("x", 10), None);
+        assert_eq!(store.get_at_frame("x", 11), Some(11.into()));
+
+        // 巻き戻して書き込んだ場合は、後ろのフレームが破棄される
+        store.set_at_frame("x", 0, 0);
+        assert_eq!(store.get_at_frame("x", 13), Some(12.into()));
+        assert_eq!(store.get_at_frame("x", 12), Some(12.into()));
+        assert_eq!(store.get_at_frame("x", 0), Some(0.into()));
+    }
+
+    #[test]
+    fn test_remove_and_clear() {
+        let store = SharedStore::default();
+        store.set("a", 1);
+        store.set_at_frame("b", 0, 2);
+        assert_eq!(store.remove("a"), Some(1.into()));
+        assert_eq!(store.get("a"), None);
+        store.clear();
+        assert_eq!(store.get_at_frame("b", 0), None);
+    }
+}
@@ -10,10 +10,12 @@
 //! - [`filter`][]：AviUtl2のフィルタプラグインを実装するためのモジュール。
 //! - [`module`][]：AviUtl2のスクリプトモジュールプラグインを実装するためのモジュール。
 //! - [`generic`][]：AviUtl2の汎用プラグインを実装するためのモジュール。
+//! - [`interop`][]：スクリプトモジュールとフィルタプラグインの間で値を受け渡すためのモジュール。
 //! - [`logger`][]：AviUtl2のロガーへのインターフェースを提供するモジュール。
 //! - [`config`][]：AviUtl2の設定関連機能へのインターフェースを提供するモジュール。
 //! - [`cache`][]：AviUtl2のキャッシュ関連機能へのインターフェースを提供するモジュール。
 //! - [`common`][]：共通の型や関数を提供するモジュール。（トップレベルに再エクスポートされています）
+//! - [`pixel`][]：YUV・RGB・YC48の色の変換を提供するモジュール。
 //! - [`utils`][]：ユーティリティ関数を提供するモジュール。
 //!
 //! 詳細は各モジュールのドキュメントを参照してください。
@@ -29,6 +31,10 @@
 //! - `aviutl2-alias`：（デフォルト）`aviutl2_alias`クレートを依存関係として追加し、いくつかの関数を追加します。
 //! - `image`：`image`クレートを使用して画像の読み書きをサポートします。
 //! - `serde`：`serde`を使用してプロジェクトファイルへのデータの保存と読み込みをサポートします。
+//! - `zstd`：プロジェクトファイルに保存するバイナリデータのzstd圧縮をサポートします。
+//! - `gpu-interop`：フィルタプラグインで、AviUtl2のD3D11テクスチャを直接扱えるようにします。
+//! - `text`：入力プラグインで、テキストを縁取り付きで画像に描画する[`input::TextRenderer`]を使えるようにします。
+//! - `frame-cache`：出力プラグインで、取得した動画フレームをディスクにキャッシュする[`output::OutputInfo::with_frame_cache`]を使えるようにします。
 //!
 //! ## Note
 //!
@@ -75,6 +81,9 @@ pub mod module;
 #[cfg(feature = "generic")]
 pub mod generic;
 
+#[cfg(feature = "module")]
+pub mod interop;
+
 #[doc(hidden)]
 #[path = "internal_base.rs"]
 pub mod __internal_base;
@@ -83,6 +92,7 @@ pub mod cache;
 pub mod common;
 pub mod config;
 pub mod logger;
+pub mod pixel;
 pub mod utils;
 pub use common::*;
 
@@ -36,6 +36,39 @@
 //!
 //! tracing::info!("This is an info log message using tracing.");
 //! ```
+//!
+//! スパンの所要時間も出力したい場合は、[`AviUtl2Layer`]を使います。
+//!
+//! ```rust
+//! use aviutl2::tracing_subscriber::prelude::*;
+//!
+//! aviutl2::tracing_subscriber::registry()
+//!     .with(aviutl2::tracing_subscriber::filter::LevelFilter::DEBUG)
+//!     .with(aviutl2::logger::AviUtl2Layer::new().with_threshold(std::time::Duration::from_millis(1)))
+//!     .init();
+//! ```
+//!
+//! [`AviUtl2LogWriter`]を通したログは、ターゲットごとに流量が制限されます。
+//! また、[`set_min_host_level`]でAviUtl2のログに出力する最低レベルを変更できます。
+//!
+//! ```rust
+//! aviutl2::logger::set_min_host_level(tracing::Level::INFO);
+//! aviutl2::logger::set_host_rate_limit(Some(aviutl2::logger::HostLogRateLimit {
+//!     burst: 100,
+//!     per_second: 20,
+//! }));
+//! ```
+//!
+//! 書き込まれたログは、直近の[`DEFAULT_RECENT_CAPACITY`]件がメモリにも記録され、[`recent`]で取得できます。
+//! [`set_min_host_level`]や流量制限でAviUtl2のログに出力されなかったログも記録されます。
+//! プラグインのUIにログを表示したい場合は、`aviutl2_eframe::widgets::LogConsole`を使うと便利です。
+//!
+//! ```rust
+//! aviutl2::logger::write_info_log("This is an info log message.").unwrap();
+//! for record in aviutl2::logger::recent(10) {
+//!     println!("[{}] {}", record.level, record.message);
+//! }
+//! ```
 
 use crate::common::{CWString, NullByteError};
 use tracing_log::NormalizeEvent;
@@ -71,9 +104,271 @@ where
     }
 }
 
+/// スパンの開始・終了を記録してAviUtl2のログに出力する[`tracing_subscriber::Layer`]。
+///
+/// [`AviUtl2Formatter`]と同じ形式でイベントを出力しつつ、スパンの深さに応じてインデントします。
+/// スパンが閉じられたとき、生存期間が[`AviUtl2Layer::with_threshold`]以上であれば
+/// `span_name{fields} took 12.3ms`の形式で所要時間を出力します。
+///
+/// スパンのフィールドはスパンの作成時に文字列化されるため、
+/// イベントやスパンの終了時にフィールドを再びフォーマットすることはありません。
+///
+/// ```rust
+/// use aviutl2::tracing_subscriber::prelude::*;
+///
+/// aviutl2::tracing_subscriber::registry()
+///     .with(
+///         aviutl2::logger::AviUtl2Layer::new()
+///             .with_threshold(std::time::Duration::from_millis(5)),
+///     )
+///     .init();
+///
+/// #[aviutl2::logger::instrument(skip_all, fields(frame = 3))]
+/// fn proc_video() {
+///     tracing::info!("processing");
+/// }
+/// proc_video();
+/// // [target]   processing
+/// // のようにスパンの深さに応じてインデントされ、5ms以上かかった場合は
+/// // [target] proc_video{frame=3} took 12.3ms
+/// // が続けて出力されます。
+/// ```
+#[derive(Debug, Clone)]
+pub struct AviUtl2Layer<W = AviUtl2LogWriter> {
+    make_writer: W,
+    threshold: std::time::Duration,
+    indent: usize,
+    now: fn() -> std::time::Instant,
+}
+
+impl AviUtl2Layer {
+    /// [`AviUtl2LogWriter`]に出力するLayerを作成します。
+    ///
+    /// デフォルトでは、すべてのスパンの所要時間を出力します。
+    pub fn new() -> Self {
+        Self {
+            make_writer: AviUtl2LogWriter,
+            threshold: std::time::Duration::ZERO,
+            indent: 2,
+            now: std::time::Instant::now,
+        }
+    }
+}
+
+impl Default for AviUtl2Layer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W> AviUtl2Layer<W> {
+    /// 出力先を変更します。
+    pub fn with_writer<W2>(self, make_writer: W2) -> AviUtl2Layer<W2>
+    where
+        W2: for<'a> tracing_subscriber::fmt::MakeWriter<'a> + 'static,
+    {
+        AviUtl2Layer {
+            make_writer,
+            threshold: self.threshold,
+            indent: self.indent,
+            now: self.now,
+        }
+    }
+
+    /// スパンの所要時間を出力する最小の時間を設定します。
+    ///
+    /// これより短い時間で閉じられたスパンの所要時間は出力されません。
+    pub fn with_threshold(mut self, threshold: std::time::Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// スパンの深さ1つあたりのインデントの幅を設定します。デフォルトは2です。
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    fn write_line(
+        &self,
+        meta: &tracing::Metadata<'_>,
+        depth: usize,
+        f: impl FnOnce(&mut String) -> std::fmt::Result,
+    ) where
+        W: for<'a> tracing_subscriber::fmt::MakeWriter<'a>,
+    {
+        thread_local! {
+            static LINE_BUFFER: std::cell::RefCell<String> = const { std::cell::RefCell::new(String::new()) };
+        }
+        LINE_BUFFER.with(|buffer| {
+            // 再入された場合（Writerの中でログを出力した場合など）は新しいバッファを使う
+            let mut fallback = String::new();
+            let mut borrowed = buffer.try_borrow_mut();
+            let line = match borrowed.as_deref_mut() {
+                Ok(line) => line,
+                Err(_) => &mut fallback,
+            };
+            line.clear();
+            use std::fmt::Write as _;
+            let result = write!(
+                line,
+                "[{}] {:width$}",
+                meta.target(),
+                "",
+                width = depth * self.indent
+            )
+            .and_then(|_| f(line));
+            if result.is_err() {
+                return;
+            }
+            line.push('\n');
+            let mut writer = self.make_writer.make_writer_for(meta);
+            let _ = std::io::Write::write_all(&mut writer, line.as_bytes());
+        });
+    }
+}
+
+/// スパンの作成時に記録する情報。
+struct SpanTiming {
+    start: std::time::Instant,
+    depth: usize,
+    fields: String,
+}
+
+/// フィールドを`key=value`の形式で文字列に書き込む。
+struct FieldWriter<'a> {
+    buffer: &'a mut String,
+}
+
+impl tracing::field::Visit for FieldWriter<'_> {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        use std::fmt::Write as _;
+        if field.name().starts_with("log.") {
+            return;
+        }
+        if !self.buffer.is_empty() {
+            self.buffer.push(' ');
+        }
+        let _ = if field.name() == "message" {
+            write!(self.buffer, "{value:?}")
+        } else {
+            write!(self.buffer, "{}={value:?}", field.name())
+        };
+    }
+}
+
+impl<S, W> tracing_subscriber::Layer<S> for AviUtl2Layer<W>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    W: for<'a> tracing_subscriber::fmt::MakeWriter<'a> + 'static,
+{
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        id: &tracing::span::Id,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let depth = span.scope().skip(1).count();
+        let mut fields = String::new();
+        attrs.record(&mut FieldWriter {
+            buffer: &mut fields,
+        });
+        span.extensions_mut().insert(SpanTiming {
+            start: (self.now)(),
+            depth,
+            fields,
+        });
+    }
+
+    fn on_record(
+        &self,
+        id: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(timing) = extensions.get_mut::<SpanTiming>() {
+            values.record(&mut FieldWriter {
+                buffer: &mut timing.fields,
+            });
+        }
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let meta = event.normalized_metadata();
+        let meta = meta.as_ref().unwrap_or_else(|| event.metadata());
+        let depth = ctx.event_scope(event).map_or(0, |scope| scope.count());
+        self.write_line(meta, depth, |line| {
+            event.record(&mut FieldWriter { buffer: line });
+            Ok(())
+        });
+    }
+
+    fn on_close(&self, id: tracing::span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let extensions = span.extensions();
+        let Some(timing) = extensions.get::<SpanTiming>() else {
+            return;
+        };
+        let elapsed = (self.now)().saturating_duration_since(timing.start);
+        if elapsed < self.threshold {
+            return;
+        }
+        self.write_line(span.metadata(), timing.depth, |line| {
+            use std::fmt::Write as _;
+            line.push_str(span.name());
+            push_fields(line, &timing.fields);
+            write!(line, " took {:.1}ms", elapsed.as_secs_f64() * 1000.0)
+        });
+    }
+}
+
+fn push_fields(line: &mut String, fields: &str) {
+    if !fields.is_empty() {
+        line.push('{');
+        line.push_str(fields);
+        line.push('}');
+    }
+}
+
+/// [`tracing::instrument`]の再エクスポート。
+///
+/// [`AviUtl2Layer`]と組み合わせると、関数の所要時間をAviUtl2のログに出力できます。
+/// `proc_video`などの引数はDebugを実装していないことが多いので、`skip_all`を指定してください。
+///
+/// # Note
+///
+/// 展開されたコードは`tracing`クレートを参照するため、`tracing`を依存関係に追加する必要があります。
+///
+/// ```rust
+/// #[aviutl2::logger::instrument(level = "debug", skip_all)]
+/// fn proc_video() {}
+/// ```
+pub use tracing::instrument;
+
 /// [`tracing_subscriber::fmt::MakeWriter`]を実装する構造体。
 ///
 /// AviUtl2のログに書き込みます。
+/// レベルはAviUtl2のログのレベル（色分け）に対応付けられます：
+///
+/// - `ERROR`：errorレベル
+/// - `WARN`：warnレベル
+/// - `INFO`：infoレベル
+/// - `DEBUG`、`TRACE`：verboseレベル
+///
+/// # Note
+///
+/// [`set_min_host_level`]より低いレベルのログは出力されません。
+/// また、[`set_host_rate_limit`]で設定された流量を超えたログは抑制され、
+/// 次に出力されるログの前に抑制された件数が出力されます。
 #[derive(Debug, Clone, Default)]
 pub struct AviUtl2LogWriter;
 
@@ -85,97 +380,242 @@ impl tracing_subscriber::fmt::MakeWriter<'_> for AviUtl2LogWriter {
     }
 
     fn make_writer_for(&'_ self, meta: &tracing::Metadata<'_>) -> Self::Writer {
-        match *meta.level() {
+        let level = *meta.level();
+        let log_level = LogLevel::from_tracing(level);
+        if !is_host_level_enabled(level) {
+            return LockedInternalWriter::sink(log_level);
+        }
+        let decision = HOST_RATE_LIMITER
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .check(meta.target(), std::time::Instant::now());
+        let writer = match level {
             tracing::Level::ERROR => LockedInternalWriter::error(),
             tracing::Level::WARN => LockedInternalWriter::warn(),
             tracing::Level::INFO => LockedInternalWriter::info(),
             tracing::Level::DEBUG | tracing::Level::TRACE => LockedInternalWriter::verbose(),
+        };
+        match decision {
+            RateLimitDecision::Allow { suppressed: 0 } => writer,
+            RateLimitDecision::Allow { suppressed } => {
+                let _ = write_warn_log(&format!(
+                    "[{}] suppressed {suppressed} log messages",
+                    meta.target()
+                ));
+                writer
+            }
+            RateLimitDecision::Suppress => LockedInternalWriter::sink(log_level),
         }
     }
 }
 
-static INTERNAL_WRITER_MUTEX_PLUGIN: std::sync::LazyLock<std::sync::Mutex<InternalWriter>> =
+static MIN_HOST_LEVEL: std::sync::atomic::AtomicU8 =
+    std::sync::atomic::AtomicU8::new(level_to_u8(tracing::Level::TRACE));
+
+const fn level_to_u8(level: tracing::Level) -> u8 {
+    match level {
+        tracing::Level::ERROR => 1,
+        tracing::Level::WARN => 2,
+        tracing::Level::INFO => 3,
+        tracing::Level::DEBUG => 4,
+        tracing::Level::TRACE => 5,
+    }
+}
+
+fn is_host_level_enabled(level: tracing::Level) -> bool {
+    level_to_u8(level) <= MIN_HOST_LEVEL.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// [`AviUtl2LogWriter`]がAviUtl2のログに出力する最低レベルを設定します。
+///
+/// `tracing_subscriber`のフィルタとは独立しているため、
+/// 他のWriter（ファイルなど）には詳細なログを出力しつつ、AviUtl2のログには重要なログのみを出力する、といったことができます。
+/// デフォルトは`TRACE`（すべて出力）です。
+pub fn set_min_host_level(level: tracing::Level) {
+    MIN_HOST_LEVEL.store(level_to_u8(level), std::sync::atomic::Ordering::Relaxed);
+}
+
+/// [`AviUtl2LogWriter`]の流量制限の設定。
+///
+/// ターゲットごとにトークンバケットで制限されます。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostLogRateLimit {
+    /// 連続して出力できるログの数。
+    pub burst: u32,
+    /// 1秒あたりに出力できるログの数。
+    pub per_second: u32,
+}
+
+impl Default for HostLogRateLimit {
+    fn default() -> Self {
+        Self {
+            burst: 50,
+            per_second: 50,
+        }
+    }
+}
+
+/// [`AviUtl2LogWriter`]の流量制限を設定します。
+///
+/// `None`を指定すると流量制限を無効にします。
+/// デフォルトは[`HostLogRateLimit::default`]です。
+pub fn set_host_rate_limit(limit: Option<HostLogRateLimit>) {
+    let mut limiter = HOST_RATE_LIMITER.lock().unwrap_or_else(|e| e.into_inner());
+    *limiter = RateLimiter::new(limit);
+}
+
+static HOST_RATE_LIMITER: std::sync::LazyLock<std::sync::Mutex<RateLimiter>> =
     std::sync::LazyLock::new(|| {
-        std::sync::Mutex::new(InternalWriter::new(InternalWriterLevel::Plugin))
+        std::sync::Mutex::new(RateLimiter::new(Some(HostLogRateLimit::default())))
     });
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RateLimitDecision {
+    /// 出力する。`suppressed`は直前に抑制されたログの数。
+    Allow { suppressed: u64 },
+    /// 抑制する。
+    Suppress,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: std::time::Instant,
+    suppressed: u64,
+}
+
+#[derive(Debug)]
+struct RateLimiter {
+    limit: Option<HostLogRateLimit>,
+    buckets: std::collections::HashMap<String, TokenBucket>,
+}
+
+impl RateLimiter {
+    fn new(limit: Option<HostLogRateLimit>) -> Self {
+        Self {
+            limit,
+            buckets: std::collections::HashMap::new(),
+        }
+    }
+
+    fn check(&mut self, target: &str, now: std::time::Instant) -> RateLimitDecision {
+        let Some(limit) = self.limit else {
+            return RateLimitDecision::Allow { suppressed: 0 };
+        };
+        let burst = limit.burst.max(1) as f64;
+        let bucket = self
+            .buckets
+            .entry(target.to_string())
+            .or_insert_with(|| TokenBucket {
+                tokens: burst,
+                last_refill: now,
+                suppressed: 0,
+            });
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens =
+            (bucket.tokens + elapsed.as_secs_f64() * limit.per_second as f64).min(burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            RateLimitDecision::Allow {
+                suppressed: std::mem::take(&mut bucket.suppressed),
+            }
+        } else {
+            bucket.suppressed += 1;
+            RateLimitDecision::Suppress
+        }
+    }
+}
+
+static INTERNAL_WRITER_MUTEX_PLUGIN: std::sync::LazyLock<std::sync::Mutex<InternalWriter>> =
+    std::sync::LazyLock::new(|| std::sync::Mutex::new(InternalWriter::new(LogLevel::Plugin)));
 static INTERNAL_WRITER_MUTEX_INFO: std::sync::LazyLock<std::sync::Mutex<InternalWriter>> =
-    std::sync::LazyLock::new(|| {
-        std::sync::Mutex::new(InternalWriter::new(InternalWriterLevel::Info))
-    });
+    std::sync::LazyLock::new(|| std::sync::Mutex::new(InternalWriter::new(LogLevel::Info)));
 static INTERNAL_WRITER_MUTEX_WARN: std::sync::LazyLock<std::sync::Mutex<InternalWriter>> =
-    std::sync::LazyLock::new(|| {
-        std::sync::Mutex::new(InternalWriter::new(InternalWriterLevel::Warn))
-    });
+    std::sync::LazyLock::new(|| std::sync::Mutex::new(InternalWriter::new(LogLevel::Warn)));
 static INTERNAL_WRITER_MUTEX_ERROR: std::sync::LazyLock<std::sync::Mutex<InternalWriter>> =
-    std::sync::LazyLock::new(|| {
-        std::sync::Mutex::new(InternalWriter::new(InternalWriterLevel::Error))
-    });
+    std::sync::LazyLock::new(|| std::sync::Mutex::new(InternalWriter::new(LogLevel::Error)));
 static INTERNAL_WRITER_MUTEX_VERBOSE: std::sync::LazyLock<std::sync::Mutex<InternalWriter>> =
-    std::sync::LazyLock::new(|| {
-        std::sync::Mutex::new(InternalWriter::new(InternalWriterLevel::Verbose))
-    });
+    std::sync::LazyLock::new(|| std::sync::Mutex::new(InternalWriter::new(LogLevel::Verbose)));
 
 pub struct LockedInternalWriter {
-    mutex: &'static std::sync::Mutex<InternalWriter>,
+    mutex: Option<&'static std::sync::Mutex<InternalWriter>>,
+    /// `mutex`が`None`のときに記録するレベル。
+    sink_level: LogLevel,
 }
 
 impl LockedInternalWriter {
     pub fn plugin() -> Self {
         Self {
-            mutex: &INTERNAL_WRITER_MUTEX_PLUGIN,
+            mutex: Some(&INTERNAL_WRITER_MUTEX_PLUGIN),
+            sink_level: LogLevel::Plugin,
         }
     }
 
     pub fn info() -> Self {
         Self {
-            mutex: &INTERNAL_WRITER_MUTEX_INFO,
+            mutex: Some(&INTERNAL_WRITER_MUTEX_INFO),
+            sink_level: LogLevel::Info,
         }
     }
 
     pub fn warn() -> Self {
         Self {
-            mutex: &INTERNAL_WRITER_MUTEX_WARN,
+            mutex: Some(&INTERNAL_WRITER_MUTEX_WARN),
+            sink_level: LogLevel::Warn,
         }
     }
 
     pub fn error() -> Self {
         Self {
-            mutex: &INTERNAL_WRITER_MUTEX_ERROR,
+            mutex: Some(&INTERNAL_WRITER_MUTEX_ERROR),
+            sink_level: LogLevel::Error,
         }
     }
 
     pub fn verbose() -> Self {
         Self {
-            mutex: &INTERNAL_WRITER_MUTEX_VERBOSE,
+            mutex: Some(&INTERNAL_WRITER_MUTEX_VERBOSE),
+            sink_level: LogLevel::Verbose,
+        }
+    }
+
+    /// AviUtl2のログには出力せず、[`recent`]用に記録だけするWriter。
+    fn sink(level: LogLevel) -> Self {
+        Self {
+            mutex: None,
+            sink_level: level,
         }
     }
 }
 impl std::io::Write for LockedInternalWriter {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let mut writer = self.mutex.lock().unwrap();
+        let Some(mutex) = self.mutex else {
+            for line in String::from_utf8_lossy(buf).lines() {
+                record_line(self.sink_level, line);
+            }
+            return Ok(buf.len());
+        };
+        let mut writer = mutex.lock().unwrap();
         writer.write(buf)
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
-        let mut writer = self.mutex.lock().unwrap();
+        let Some(mutex) = self.mutex else {
+            return Ok(());
+        };
+        let mut writer = mutex.lock().unwrap();
         writer.flush()
     }
 }
 
-enum InternalWriterLevel {
-    Plugin,
-    Info,
-    Warn,
-    Error,
-    Verbose,
-}
-
 struct InternalWriter {
-    level: InternalWriterLevel,
+    level: LogLevel,
     buffer: Vec<u8>,
 }
 impl InternalWriter {
-    fn new(level: InternalWriterLevel) -> Self {
+    fn new(level: LogLevel) -> Self {
         Self {
             level,
             buffer: Vec::new(),
@@ -195,23 +635,8 @@ impl std::io::Write for InternalWriter {
             let line = self.buffer.drain(..=pos).collect::<Vec<u8>>();
             let line = String::from_utf8_lossy(&line);
             let line = line.trim_end_matches('\n');
-            match self.level {
-                InternalWriterLevel::Plugin => {
-                    let _ = write_plugin_log(line);
-                }
-                InternalWriterLevel::Info => {
-                    let _ = write_info_log(line);
-                }
-                InternalWriterLevel::Warn => {
-                    let _ = write_warn_log(line);
-                }
-                InternalWriterLevel::Error => {
-                    let _ = write_error_log(line);
-                }
-                InternalWriterLevel::Verbose => {
-                    let _ = write_verbose_log(line);
-                }
-            }
+            record_line(self.level, line);
+            let _ = write_host_log(self.level, line);
         }
         Ok(())
     }
@@ -323,22 +748,15 @@ fn split_into_chunks(message: &str, kind_length: usize) -> Vec<String> {
 /// - [`ldbg!`]
 /// - [`lprintln!`]
 pub fn write_plugin_log(message: &str) -> Result<(), NullByteError> {
-    with_logger_handle(|handle| unsafe {
-        for chunk in split_into_chunks(message, "PLUGIN".len()) {
-            let wide_message = CWString::new(&chunk)?;
-            ((*handle).log)(handle, wide_message.as_ptr());
-        }
-        Ok(())
-    })
-    .unwrap_or(Ok(()))
+    write_log(LogLevel::Plugin, message)
 }
 
 #[duplicate::duplicate_item(
-    level       function_name       log_method;
-    ["ERROR"]   [write_error_log]   [error];
-    ["WARN"]    [write_warn_log]    [warn];
-    ["INFO"]    [write_info_log]    [info];
-    ["VERBOSE"] [write_verbose_log] [verbose];
+    level       function_name       log_level;
+    ["ERROR"]   [write_error_log]   [LogLevel::Error];
+    ["WARN"]    [write_warn_log]    [LogLevel::Warn];
+    ["INFO"]    [write_info_log]    [LogLevel::Info];
+    ["VERBOSE"] [write_verbose_log] [LogLevel::Verbose];
 )]
 #[doc = concat!("ログに", level, "レベルのメッセージを書き込みます。")]
 ///
@@ -351,16 +769,294 @@ pub fn write_plugin_log(message: &str) -> Result<(), NullByteError> {
 /// - [`ldbg!`]
 /// - [`lprintln!`]
 pub fn function_name(message: &str) -> Result<(), NullByteError> {
+    write_log(log_level, message)
+}
+
+/// [`recent`]用に記録してから、AviUtl2のログに書き込む。
+fn write_log(level: LogLevel, message: &str) -> Result<(), NullByteError> {
+    push_record(level, "", message);
+    write_host_log(level, message)
+}
+
+fn write_host_log(level: LogLevel, message: &str) -> Result<(), NullByteError> {
     with_logger_handle(|handle| unsafe {
-        for chunk in split_into_chunks(message, level.len()) {
+        let log = match level {
+            LogLevel::Plugin => (*handle).log,
+            LogLevel::Error => (*handle).error,
+            LogLevel::Warn => (*handle).warn,
+            LogLevel::Info => (*handle).info,
+            LogLevel::Verbose => (*handle).verbose,
+        };
+        for chunk in split_into_chunks(message, level.as_str().len()) {
             let wide_message = CWString::new(&chunk)?;
-            ((*handle).log_method)(handle, wide_message.as_ptr());
+            log(handle, wide_message.as_ptr());
         }
         Ok(())
     })
     .unwrap_or(Ok(()))
 }
 
+/// ログのレベル。
+///
+/// AviUtl2のログのレベルに対応します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogLevel {
+    /// プラグイン用のログ。
+    Plugin,
+    /// errorレベル。
+    Error,
+    /// warnレベル。
+    Warn,
+    /// infoレベル。
+    Info,
+    /// verboseレベル。
+    Verbose,
+}
+
+impl LogLevel {
+    /// 全てのレベル。
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Plugin,
+        LogLevel::Verbose,
+    ];
+
+    /// AviUtl2のログでの表記（`PLUGIN`、`ERROR`など）を返します。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Plugin => "PLUGIN",
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Verbose => "VERBOSE",
+        }
+    }
+
+    fn from_tracing(level: tracing::Level) -> Self {
+        match level {
+            tracing::Level::ERROR => LogLevel::Error,
+            tracing::Level::WARN => LogLevel::Warn,
+            tracing::Level::INFO => LogLevel::Info,
+            tracing::Level::DEBUG | tracing::Level::TRACE => LogLevel::Verbose,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        match self {
+            LogLevel::Plugin => 1 << 0,
+            LogLevel::Error => 1 << 1,
+            LogLevel::Warn => 1 << 2,
+            LogLevel::Info => 1 << 3,
+            LogLevel::Verbose => 1 << 4,
+        }
+    }
+}
+
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// [`recent`]で取得できる、記録されたログ。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    /// 記録された順に振られる通し番号。
+    pub seq: u64,
+    /// 記録された時刻。
+    pub time: std::time::SystemTime,
+    /// レベル。
+    pub level: LogLevel,
+    /// ターゲット。
+    ///
+    /// [`AviUtl2LogWriter`]を通したログでは`[target] message`の`target`の部分になります。
+    /// [`write_info_log`]などで直接書き込んだログでは空文字列です。
+    pub target: String,
+    /// メッセージ。
+    pub message: String,
+}
+
+/// [`LogRecord`]の絞り込み条件。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    hidden_levels: u8,
+    /// 検索する文字列。
+    ///
+    /// ターゲットとメッセージから、大文字小文字を区別せずに探します。空の場合は絞り込みません。
+    pub query: String,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogFilter {
+    /// 全てのログに一致する条件を作成します。
+    pub fn new() -> Self {
+        Self {
+            hidden_levels: 0,
+            query: String::new(),
+        }
+    }
+
+    /// `level`のログを表示するかどうかを返します。
+    pub fn is_level_shown(&self, level: LogLevel) -> bool {
+        self.hidden_levels & level.bit() == 0
+    }
+
+    /// `level`のログを表示するかどうかを設定します。
+    pub fn set_level_shown(&mut self, level: LogLevel, shown: bool) {
+        if shown {
+            self.hidden_levels &= !level.bit();
+        } else {
+            self.hidden_levels |= level.bit();
+        }
+    }
+
+    /// `record`が条件に一致するかどうかを返します。
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        if !self.is_level_shown(record.level) {
+            return false;
+        }
+        if self.query.is_empty() {
+            return true;
+        }
+        let query = self.query.to_lowercase();
+        record.target.to_lowercase().contains(&query)
+            || record.message.to_lowercase().contains(&query)
+    }
+}
+
+/// [`recent`]で保持するログの数のデフォルト。
+pub const DEFAULT_RECENT_CAPACITY: usize = 2000;
+
+/// 直近のログを保持するリングバッファ。
+#[derive(Debug)]
+struct RecordRing {
+    records: std::collections::VecDeque<LogRecord>,
+    capacity: usize,
+    next_seq: u64,
+}
+
+impl RecordRing {
+    const fn new(capacity: usize) -> Self {
+        Self {
+            records: std::collections::VecDeque::new(),
+            capacity,
+            next_seq: 0,
+        }
+    }
+
+    fn push(&mut self, time: std::time::SystemTime, level: LogLevel, target: &str, message: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.records.len() >= self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(LogRecord {
+            seq: self.next_seq,
+            time,
+            level,
+            target: target.to_string(),
+            message: message.to_string(),
+        });
+        self.next_seq += 1;
+    }
+
+    fn recent(&self, n: usize) -> Vec<LogRecord> {
+        let skip = self.records.len().saturating_sub(n);
+        self.records.iter().skip(skip).cloned().collect()
+    }
+
+    fn since(&self, seq: u64) -> Vec<LogRecord> {
+        // 通し番号は連続しているので、先頭からの位置で探せる
+        let skip = match self.records.front() {
+            Some(front) => seq.saturating_sub(front.seq) as usize,
+            None => 0,
+        };
+        self.records.iter().skip(skip).cloned().collect()
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.records.len() > capacity {
+            self.records.pop_front();
+        }
+    }
+}
+
+static RECENT_RECORDS: parking_lot::Mutex<RecordRing> =
+    parking_lot::const_mutex(RecordRing::new(DEFAULT_RECENT_CAPACITY));
+static DROPPED_RECORDS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// ログを記録する。
+///
+/// ログを書き込むスレッドを止めないよう、ロックを取れなかった場合は記録せずに捨てた数だけ数える。
+fn push_record(level: LogLevel, target: &str, message: &str) {
+    let time = std::time::SystemTime::now();
+    match RECENT_RECORDS.try_lock_for(std::time::Duration::from_millis(1)) {
+        Some(mut ring) => ring.push(time, level, target, message),
+        None => {
+            DROPPED_RECORDS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+}
+
+/// [`AviUtl2LogWriter`]に書き込まれた1行を記録する。
+fn record_line(level: LogLevel, line: &str) {
+    let (target, message) = split_target(line);
+    push_record(level, target, message);
+}
+
+/// `[target] message`の形式の行を`target`と`message`に分ける。
+///
+/// スパンの深さによるインデントも取り除く。
+fn split_target(line: &str) -> (&str, &str) {
+    if let Some(rest) = line.strip_prefix('[') {
+        if let Some((target, message)) = rest.split_once("] ") {
+            return (target, message.trim_start());
+        }
+        if let Some(target) = rest.strip_suffix(']') {
+            return (target, "");
+        }
+    }
+    ("", line)
+}
+
+/// 直近に記録されたログを、古いものから順に最大`n`件返します。
+///
+/// # Note
+///
+/// 記録されるのは、[`write_info_log`]などや[`AviUtl2LogWriter`]を通して書き込まれたログです。
+/// ロックが取れなかったなどの理由で記録できなかったログの数は[`dropped_record_count`]で取得できます。
+pub fn recent(n: usize) -> Vec<LogRecord> {
+    RECENT_RECORDS.lock().recent(n)
+}
+
+/// 通し番号が`seq`以降のログを、古いものから順に返します。
+///
+/// 前回取得した最後のログの[`LogRecord::seq`]に1を足して渡すと、新しいログだけを取得できます。
+pub fn recent_since(seq: u64) -> Vec<LogRecord> {
+    RECENT_RECORDS.lock().since(seq)
+}
+
+/// 記録しておくログの数を設定します。デフォルトは[`DEFAULT_RECENT_CAPACITY`]です。
+///
+/// `0`を指定すると記録しなくなります。
+pub fn set_recent_capacity(capacity: usize) {
+    RECENT_RECORDS.lock().set_capacity(capacity);
+}
+
+/// 記録できずに捨てられたログの数を返します。
+pub fn dropped_record_count() -> u64 {
+    DROPPED_RECORDS.load(std::sync::atomic::Ordering::Relaxed)
+}
+
 struct InternalLoggerHandle(*mut aviutl2_sys::logger2::LOG_HANDLE);
 unsafe impl Send for InternalLoggerHandle {}
 
@@ -405,6 +1101,302 @@ where
 
 #[cfg(test)]
 mod tests {
+    use super::{HostLogRateLimit, RateLimitDecision, RateLimiter};
+    use std::time::{Duration, Instant};
+
+    fn limiter(burst: u32, per_second: u32) -> RateLimiter {
+        RateLimiter::new(Some(HostLogRateLimit { burst, per_second }))
+    }
+
+    #[test]
+    fn test_rate_limiter_allows_burst_then_suppresses() {
+        let mut limiter = limiter(3, 1);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(
+                limiter.check("a", now),
+                RateLimitDecision::Allow { suppressed: 0 }
+            );
+        }
+        assert_eq!(limiter.check("a", now), RateLimitDecision::Suppress);
+        assert_eq!(
+            limiter.check("b", now),
+            RateLimitDecision::Allow { suppressed: 0 }
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter_steady_state() {
+        let mut limiter = limiter(1, 10);
+        let start = Instant::now();
+        let mut allowed = 0;
+        for i in 0..1000 {
+            let now = start + Duration::from_millis(i);
+            if matches!(limiter.check("a", now), RateLimitDecision::Allow { .. }) {
+                allowed += 1;
+            }
+        }
+        // 1秒間で初期の1件 + 10件/秒
+        assert!((10..=11).contains(&allowed), "allowed = {allowed}");
+    }
+
+    #[test]
+    fn test_rate_limiter_reports_suppressed_count() {
+        let mut limiter = limiter(1, 1);
+        let now = Instant::now();
+        assert_eq!(
+            limiter.check("a", now),
+            RateLimitDecision::Allow { suppressed: 0 }
+        );
+        for _ in 0..5 {
+            assert_eq!(limiter.check("a", now), RateLimitDecision::Suppress);
+        }
+        assert_eq!(
+            limiter.check("a", now + Duration::from_secs(1)),
+            RateLimitDecision::Allow { suppressed: 5 }
+        );
+        assert_eq!(
+            limiter.check("a", now + Duration::from_secs(2)),
+            RateLimitDecision::Allow { suppressed: 0 }
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter_disabled() {
+        let mut limiter = RateLimiter::new(None);
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert_eq!(
+                limiter.check("a", now),
+                RateLimitDecision::Allow { suppressed: 0 }
+            );
+        }
+    }
+
+    mod layer {
+        use super::super::AviUtl2Layer;
+        use std::time::{Duration, Instant};
+        use tracing_subscriber::prelude::*;
+
+        thread_local! {
+            static NOW: std::cell::Cell<Option<Instant>> = const { std::cell::Cell::new(None) };
+        }
+
+        fn now() -> Instant {
+            NOW.with(|now| *now.get().get_or_insert_with(Instant::now))
+        }
+
+        fn advance(duration: Duration) {
+            NOW.with(|now| now.set(Some(self::now() + duration)));
+        }
+
+        #[derive(Clone, Default)]
+        struct Captured(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+        impl Captured {
+            fn lines(&self) -> Vec<String> {
+                String::from_utf8(self.0.lock().unwrap().clone())
+                    .unwrap()
+                    .lines()
+                    .map(str::to_string)
+                    .collect()
+            }
+        }
+
+        impl std::io::Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        fn with_layer(threshold: Duration, f: impl FnOnce()) -> Vec<String> {
+            let captured = Captured::default();
+            let writer = captured.clone();
+            let mut layer = AviUtl2Layer::new()
+                .with_writer(move || writer.clone())
+                .with_threshold(threshold);
+            layer.now = now;
+            let subscriber = tracing_subscriber::registry().with(layer);
+            tracing::subscriber::with_default(subscriber, f);
+            captured.lines()
+        }
+
+        #[test]
+        fn test_layer_nesting() {
+            let lines = with_layer(Duration::ZERO, || {
+                let outer = tracing::info_span!(target: "test", "outer", frame = 3);
+                let _outer = outer.enter();
+                tracing::info!(target: "test", "start");
+                {
+                    let inner = tracing::info_span!(target: "test", "inner", name = "a");
+                    let _inner = inner.enter();
+                    advance(Duration::from_micros(1500));
+                    tracing::warn!(target: "test", count = 2, "nested");
+                }
+                advance(Duration::from_millis(10));
+            });
+            assert_eq!(
+                lines,
+                [
+                    "[test]   start",
+                    "[test]     nested count=2",
+                    "[test]   inner{name=\"a\"} took 1.5ms",
+                    "[test] outer{frame=3} took 11.5ms",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_layer_threshold() {
+            let lines = with_layer(Duration::from_millis(5), || {
+                for (i, ms) in [1, 5, 4, 20].into_iter().enumerate() {
+                    let span = tracing::info_span!(target: "test", "proc", i);
+                    let _span = span.enter();
+                    advance(Duration::from_millis(ms));
+                }
+            });
+            assert_eq!(
+                lines,
+                [
+                    "[test] proc{i=1} took 5.0ms",
+                    "[test] proc{i=3} took 20.0ms"
+                ]
+            );
+        }
+
+        #[test]
+        fn test_layer_records_late_fields() {
+            let lines = with_layer(Duration::ZERO, || {
+                let span = tracing::info_span!(
+                    target: "test",
+                    "load",
+                    path = "a.wav",
+                    samples = tracing::field::Empty
+                );
+                span.record("samples", 48000);
+            });
+            assert_eq!(
+                lines,
+                ["[test] load{path=\"a.wav\" samples=48000} took 0.0ms"]
+            );
+        }
+    }
+
+    mod recent {
+        use super::super::{
+            LogFilter, LogLevel, LogRecord, RecordRing, recent, split_target, write_info_log,
+        };
+
+        fn push(ring: &mut RecordRing, message: &str) {
+            ring.push(std::time::SystemTime::now(), LogLevel::Info, "", message);
+        }
+
+        fn messages(records: &[LogRecord]) -> Vec<&str> {
+            records.iter().map(|r| r.message.as_str()).collect()
+        }
+
+        #[test]
+        fn test_ring_keeps_latest_records() {
+            let mut ring = RecordRing::new(3);
+            for message in ["a", "b", "c", "d", "e"] {
+                push(&mut ring, message);
+            }
+            assert_eq!(messages(&ring.recent(10)), ["c", "d", "e"]);
+            assert_eq!(messages(&ring.recent(2)), ["d", "e"]);
+            assert!(ring.recent(0).is_empty());
+            assert_eq!(
+                ring.recent(10).iter().map(|r| r.seq).collect::<Vec<_>>(),
+                [2, 3, 4]
+            );
+        }
+
+        #[test]
+        fn test_ring_since() {
+            let mut ring = RecordRing::new(3);
+            for message in ["a", "b", "c", "d"] {
+                push(&mut ring, message);
+            }
+            assert_eq!(messages(&ring.since(3)), ["d"]);
+            assert!(ring.since(4).is_empty());
+            // 既に捨てられた番号を指定した場合は、残っているものを全て返す
+            assert_eq!(messages(&ring.since(0)), ["b", "c", "d"]);
+        }
+
+        #[test]
+        fn test_ring_set_capacity() {
+            let mut ring = RecordRing::new(4);
+            for message in ["a", "b", "c", "d"] {
+                push(&mut ring, message);
+            }
+            ring.set_capacity(2);
+            assert_eq!(messages(&ring.recent(10)), ["c", "d"]);
+            push(&mut ring, "e");
+            assert_eq!(messages(&ring.recent(10)), ["d", "e"]);
+
+            ring.set_capacity(0);
+            push(&mut ring, "f");
+            assert!(ring.recent(10).is_empty());
+        }
+
+        #[test]
+        fn test_split_target() {
+            assert_eq!(split_target("[my_plugin] loaded"), ("my_plugin", "loaded"));
+            assert_eq!(
+                split_target("[my_plugin]     nested count=2"),
+                ("my_plugin", "nested count=2")
+            );
+            assert_eq!(split_target("[my_plugin]"), ("my_plugin", ""));
+            assert_eq!(split_target("no target"), ("", "no target"));
+            assert_eq!(split_target("[unclosed"), ("", "[unclosed"));
+        }
+
+        #[test]
+        fn test_filter() {
+            let record = |level, target: &str, message: &str| LogRecord {
+                seq: 0,
+                time: std::time::SystemTime::UNIX_EPOCH,
+                level,
+                target: target.to_string(),
+                message: message.to_string(),
+            };
+            let error = record(LogLevel::Error, "decoder", "Failed to open File");
+            let info = record(LogLevel::Info, "Loader", "loaded 3 files");
+
+            let mut filter = LogFilter::new();
+            assert!(filter.matches(&error) && filter.matches(&info));
+
+            filter.set_level_shown(LogLevel::Info, false);
+            assert!(filter.matches(&error));
+            assert!(!filter.matches(&info));
+            assert!(!filter.is_level_shown(LogLevel::Info));
+            filter.set_level_shown(LogLevel::Info, true);
+            assert!(filter.matches(&info));
+
+            // 大文字小文字を区別せず、ターゲットとメッセージの両方から探す
+            filter.query = "file".to_string();
+            assert!(filter.matches(&error) && filter.matches(&info));
+            filter.query = "LOADER".to_string();
+            assert!(!filter.matches(&error));
+            assert!(filter.matches(&info));
+        }
+
+        #[test]
+        fn test_write_log_is_recorded() {
+            write_info_log("recorded by test_write_log_is_recorded").unwrap();
+            let record = recent(usize::MAX)
+                .into_iter()
+                .find(|r| r.message == "recorded by test_write_log_is_recorded")
+                .unwrap();
+            assert_eq!(record.level, LogLevel::Info);
+            assert_eq!(record.target, "");
+        }
+    }
+
     #[test]
     fn test_can_compile_ldbg() {
         let x = 42;
@@ -5,8 +5,118 @@ pub struct ScriptModuleTable {
     /// 「プラグイン情報」ダイアログで表示されます。
     pub information: String,
 
+    /// 関数名の前に付ける名前空間。
+    ///
+    /// `Some("color".to_string())`の場合、`blend`関数は`color_blend`としてモジュールに登録されます
+    /// （例：`my_module.color_blend()`）。`None`の場合は関数名をそのまま使います。
+    /// [`crate::register_script_modules!`]で複数のモジュールを1つのDLLにまとめるときに使います。
+    ///
+    /// # Note
+    ///
+    /// モジュールの名前はDLLのファイル名で決まり、SDKには入れ子のテーブルを登録する方法がないため、
+    /// `my_module.color.blend()`のような形にはできません。
+    pub name: Option<String>,
+
     /// プラグインが提供する関数。
     pub functions: Vec<ModuleFunction>,
+
+    /// 関数の一覧を返す`__functions`関数を追加するかどうか。
+    ///
+    /// `true`の場合、[`ScriptModuleFunctions::function_metadata`]の内容をJSON文字列として返す
+    /// `__functions`関数がモジュールに追加されます。
+    pub include_introspection: bool,
+
+    /// 関数呼び出し1回あたりの制限時間。
+    ///
+    /// [`CallContext::deadline`][crate::module::CallContext::deadline]はこの値から計算されます。
+    /// `None`の場合は制限時間を設けません。
+    pub default_time_budget: Option<std::time::Duration>,
+
+    /// スクリプトに公開する定数の一覧。
+    ///
+    /// 空でない場合、定数名を受け取って値を返す`get_constant`関数がモジュールに追加されます。
+    /// （例：`module.get_constant("VERSION")`）
+    pub constants: Vec<(String, ScriptModuleValue)>,
+
+    /// [`crate::interop::shared_store`]を読み書きする`set`・`get`関数を追加するかどうか。
+    ///
+    /// `true`の場合、以下の関数がモジュールに追加されます。
+    /// - `set(key, value[, frame])`：値を設定する。`frame`を省略した場合はすべてのフレームの値になり、
+    ///   `value`が`nil`の場合はキーを削除します。
+    /// - `get(key[, frame])`：値を取得する。`frame`を省略した場合は最後に設定された値を返します。
+    ///
+    /// 詳しくは[`crate::interop`]を参照してください。
+    pub enable_shared_store: bool,
+}
+
+/// スクリプトモジュールの定数の値を表す列挙型。
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptModuleValue {
+    /// 整数。
+    Int(i32),
+    /// 浮動小数点数。
+    Float(f64),
+    /// 文字列。
+    String(String),
+    /// 真偽値。
+    Boolean(bool),
+}
+
+impl ScriptModuleValue {
+    /// 数値の場合は`f64`として返す。
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(value) => Some(*value as f64),
+            Self::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// 文字列の場合は返す。
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// 真偽値の場合は返す。
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl From<i32> for ScriptModuleValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f64> for ScriptModuleValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&str> for ScriptModuleValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for ScriptModuleValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<bool> for ScriptModuleValue {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
 }
 
 /// スクリプトモジュールプラグインの関数を表す構造体。
@@ -18,6 +128,31 @@ pub struct ModuleFunction {
     pub func: extern "C" fn(*mut crate::sys::module2::SCRIPT_MODULE_PARAM),
 }
 
+/// スクリプトモジュールの関数の情報を表す構造体。
+///
+/// `__functions`関数で返されます。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleFunctionMetadata {
+    /// 関数名。
+    pub name: String,
+    /// 引数の一覧。
+    pub params: Vec<ModuleFunctionParamMetadata>,
+    /// 戻り値の型。
+    /// `#[direct]`な関数など、型が分からない場合は`None`になります。
+    pub returns: Option<String>,
+    /// 関数のドキュメント。
+    pub doc: String,
+}
+
+/// スクリプトモジュールの関数の引数の情報を表す構造体。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleFunctionParamMetadata {
+    /// 引数名。
+    pub name: String,
+    /// 引数の型。
+    pub ty: String,
+}
+
 pub use aviutl2_macros::{
     module_functions as functions, module_metatable as metatable, script_module_callback,
     script_module_direct_callback,
@@ -28,6 +163,20 @@ pub use aviutl2_macros::{
 pub trait ScriptModuleFunctions: Sized + Send + Sync + 'static {
     /// プラグインが提供する関数の一覧を返す。
     fn functions() -> Vec<crate::module::ModuleFunction>;
+
+    /// プラグインが提供する関数の情報を返す。
+    ///
+    /// [`macro@functions`]マクロでは、関数のシグネチャとドキュメントコメントから生成されます。
+    fn function_metadata() -> Vec<crate::module::ModuleFunctionMetadata> {
+        Vec::new()
+    }
+
+    /// プラグインが提供する定数の一覧を返す。
+    ///
+    /// [`macro@functions`]マクロでは、`#[constants]`を付けた関数の戻り値になります。
+    fn constants() -> Vec<(String, crate::module::ScriptModuleValue)> {
+        Vec::new()
+    }
 }
 
 /// スクリプトモジュールプラグインのトレイト。
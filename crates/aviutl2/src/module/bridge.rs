@@ -1,11 +1,11 @@
 use crate::{
     common::{AnyResult, LeakManager},
-    module::{ScriptModule, ScriptModuleTable},
+    module::{ModuleFunctionMetadata, ScriptModule, ScriptModuleTable, ScriptModuleValue},
 };
 
 #[doc(hidden)]
 pub struct InternalScriptModuleState<T: Send + Sync + ScriptModule> {
-    plugin_info: ScriptModuleTable,
+    pub(crate) plugin_info: ScriptModuleTable,
     global_leak_manager: LeakManager,
 
     pub instance: T,
@@ -74,9 +74,7 @@ pub unsafe fn initialize_plugin_c_unwind<T: ScriptModuleSingleton>(version: u32)
 pub(crate) fn initialize_plugin<T: ScriptModuleSingleton>(version: u32) -> AnyResult<()> {
     crate::common::ensure_minimum_aviutl2_version(version.into())?;
     let plugin_state = T::__get_singleton_state();
-    let info = crate::common::AviUtl2Info {
-        version: version.into(),
-    };
+    let info = crate::common::AviUtl2Info::new(version);
     let internal = T::new(info)?;
     let plugin = InternalScriptModuleState::new(internal);
     *plugin_state.write().unwrap() = Some(plugin);
@@ -106,34 +104,111 @@ pub unsafe fn create_table<T: ScriptModuleSingleton>()
     let plugin_state_lock = T::__get_singleton_state();
     let plugin_state = plugin_state_lock.read().unwrap();
     let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
-    let plugin_info = &plugin_state.plugin_info;
-    let information = plugin_info.information.clone();
+    let entries = table_entries_from_info::<T>(&plugin_state.plugin_info);
+    leak_table(vec![entries], &plugin_state.global_leak_manager)
+}
 
-    let module_functions: Vec<aviutl2_sys::module2::SCRIPT_MODULE_FUNCTION> = plugin_info
-        .functions
+/// 複数のモジュールの関数をまとめたテーブルを作成する。
+///
+/// プラグインの情報には最初のモジュールの情報を使う。
+pub unsafe fn create_combined_table(
+    entries: &[fn() -> ModuleTableEntries],
+) -> *mut aviutl2_sys::module2::SCRIPT_MODULE_TABLE {
+    static LEAK_MANAGER: std::sync::LazyLock<LeakManager> =
+        std::sync::LazyLock::new(LeakManager::new);
+    leak_table(
+        entries.iter().map(|entries| entries()).collect(),
+        &LEAK_MANAGER,
+    )
+}
+
+pub unsafe fn create_combined_table_unwind(
+    entries: &[fn() -> ModuleTableEntries],
+) -> *mut aviutl2_sys::module2::SCRIPT_MODULE_TABLE {
+    match crate::utils::catch_unwind_with_panic_info(|| unsafe { create_combined_table(entries) }) {
+        Ok(table) => table,
+        Err(panic_info) => {
+            tracing::error!("Panic occurred during create_table: {}", panic_info);
+            let _ = crate::logger::write_error_log(&panic_info);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// テーブルに登録するモジュールの情報と関数。
+#[doc(hidden)]
+pub struct ModuleTableEntries {
+    information: String,
+    functions: Vec<(String, ModuleFunctionPointer)>,
+}
+
+/// モジュールの情報と、名前空間を付けた関数の一覧を返す。
+pub fn table_entries<T: ScriptModuleSingleton>() -> ModuleTableEntries {
+    let plugin_state = T::__get_singleton_state().read().unwrap();
+    let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
+    table_entries_from_info::<T>(&plugin_state.plugin_info)
+}
+
+fn table_entries_from_info<T: ScriptModuleSingleton>(
+    plugin_info: &ScriptModuleTable,
+) -> ModuleTableEntries {
+    ModuleTableEntries {
+        information: plugin_info.information.clone(),
+        functions: registered_functions::<T>(plugin_info)
+            .into_iter()
+            .map(|(name, func)| match &plugin_info.name {
+                Some(namespace) => (format!("{namespace}_{name}"), func),
+                None => (name.to_string(), func),
+            })
+            .collect(),
+    }
+}
+
+/// 関数名が重複している場合は、後から登録した関数を無視する。
+fn merge_functions(entries: &[ModuleTableEntries]) -> Vec<(&str, ModuleFunctionPointer)> {
+    let mut seen = std::collections::HashSet::new();
+    entries
         .iter()
-        .map(|f| aviutl2_sys::module2::SCRIPT_MODULE_FUNCTION {
-            name: plugin_state
-                .global_leak_manager
-                .leak_as_wide_string(&f.name),
-            func: f.func,
+        .flat_map(|entries| &entries.functions)
+        .filter(|(name, _)| {
+            let is_new = seen.insert(name.as_str());
+            if !is_new {
+                tracing::error!("Duplicate script module function name: {}", name);
+            }
+            is_new
         })
-        .chain(std::iter::once(
-            aviutl2_sys::module2::SCRIPT_MODULE_FUNCTION {
-                name: std::ptr::null(),
-                func: unreachable_function,
-            },
-        ))
-        .collect();
-    let functions_ptr = plugin_state
-        .global_leak_manager
-        .leak_value_vec(module_functions);
+        .map(|(name, func)| (name.as_str(), *func))
+        .collect()
+}
+
+fn leak_table(
+    entries: Vec<ModuleTableEntries>,
+    leak_manager: &LeakManager,
+) -> *mut aviutl2_sys::module2::SCRIPT_MODULE_TABLE {
+    let module_functions: Vec<aviutl2_sys::module2::SCRIPT_MODULE_FUNCTION> =
+        merge_functions(&entries)
+            .into_iter()
+            .map(
+                |(name, func)| aviutl2_sys::module2::SCRIPT_MODULE_FUNCTION {
+                    name: leak_manager.leak_as_wide_string(name),
+                    func,
+                },
+            )
+            .chain(std::iter::once(
+                aviutl2_sys::module2::SCRIPT_MODULE_FUNCTION {
+                    name: std::ptr::null(),
+                    func: unreachable_function,
+                },
+            ))
+            .collect();
+    let functions_ptr = leak_manager.leak_value_vec(module_functions);
+    let information = entries
+        .first()
+        .map_or(String::new(), |entries| entries.information.clone());
 
     // NOTE: プラグイン名などの文字列はAviUtlが終了するまで解放しない
     let table = aviutl2_sys::module2::SCRIPT_MODULE_TABLE {
-        information: plugin_state
-            .global_leak_manager
-            .leak_as_wide_string(&information),
+        information: leak_manager.leak_as_wide_string(&information),
         functions: functions_ptr,
     };
     let table = Box::new(table);
@@ -152,6 +227,290 @@ pub unsafe fn create_table_unwind<T: ScriptModuleSingleton>()
     }
 }
 
+type ModuleFunctionPointer = extern "C" fn(*mut aviutl2_sys::module2::SCRIPT_MODULE_PARAM);
+
+/// モジュールに登録する関数の一覧を返す。
+///
+/// ユーザー定義の関数の後に、`__functions`関数・`get_constant`関数・`set`関数・`get`関数が必要に応じて追加される。
+fn registered_functions<T: ScriptModuleSingleton>(
+    plugin_info: &ScriptModuleTable,
+) -> Vec<(&str, ModuleFunctionPointer)> {
+    plugin_info
+        .functions
+        .iter()
+        .map(|f| (f.name.as_str(), f.func))
+        .chain(plugin_info.include_introspection.then_some((
+            INTROSPECTION_FUNCTION_NAME,
+            introspection_function::<T> as ModuleFunctionPointer,
+        )))
+        .chain((!plugin_info.constants.is_empty()).then_some((
+            CONSTANT_FUNCTION_NAME,
+            constant_function::<T> as ModuleFunctionPointer,
+        )))
+        .chain(
+            plugin_info
+                .enable_shared_store
+                .then_some([
+                    (
+                        SHARED_STORE_SET_FUNCTION_NAME,
+                        shared_store_set_function as ModuleFunctionPointer,
+                    ),
+                    (
+                        SHARED_STORE_GET_FUNCTION_NAME,
+                        shared_store_get_function as ModuleFunctionPointer,
+                    ),
+                ])
+                .into_iter()
+                .flatten(),
+        )
+        .collect()
+}
+
+const INTROSPECTION_FUNCTION_NAME: &str = "__functions";
+const CONSTANT_FUNCTION_NAME: &str = "get_constant";
+const SHARED_STORE_SET_FUNCTION_NAME: &str = "set";
+const SHARED_STORE_GET_FUNCTION_NAME: &str = "get";
+
+/// 値を関数の戻り値として返す。
+fn push_value(
+    handle: &mut crate::module::ScriptModuleCallHandle,
+    value: ScriptModuleValue,
+) -> crate::module::ScriptModuleCallHandleResult<()> {
+    match value {
+        ScriptModuleValue::Int(value) => handle.push_result_int(value),
+        ScriptModuleValue::Float(value) => handle.push_result_float(value),
+        ScriptModuleValue::String(value) => return handle.push_result_str(&value),
+        ScriptModuleValue::Boolean(value) => handle.push_result_boolean(value),
+    }
+    Ok(())
+}
+
+// NOTE: module2のSCRIPT_MODULE_TABLEには関数しか登録できないため、定数は取得用の関数経由で公開する
+extern "C" fn constant_function<T: ScriptModuleSingleton>(
+    smp: *mut aviutl2_sys::module2::SCRIPT_MODULE_PARAM,
+) {
+    if let Err(panic_info) = crate::utils::catch_unwind_with_panic_info(|| {
+        let mut handle = unsafe { crate::module::ScriptModuleCallHandle::from_raw(smp) };
+        let name = match handle.get_param_str(0) {
+            Ok(name) => name,
+            Err(e) => {
+                let _ = handle.set_error(&format!("invalid constant name: {e}"));
+                return;
+            }
+        };
+        let value = {
+            let plugin_state = T::__get_singleton_state().read().unwrap();
+            let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
+            plugin_state
+                .plugin_info
+                .constants
+                .iter()
+                .find(|(constant_name, _)| *constant_name == name)
+                .map(|(_, value)| value.clone())
+        };
+        let result = match value {
+            Some(value) => push_value(&mut handle, value),
+            None => handle.set_error(&format!("unknown constant: {name}")),
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to push constant {:?}: {}", name, e);
+        }
+    }) {
+        tracing::error!(
+            "Panic occurred during {}: {}",
+            CONSTANT_FUNCTION_NAME,
+            panic_info
+        );
+        let _ = crate::logger::write_error_log(&panic_info);
+    }
+}
+
+/// `index`番目の引数をフレーム番号として取得する。
+///
+/// 省略された場合は、呼び出し元のオブジェクトの情報があればそのフレーム番号を使う。
+fn optional_frame_param(
+    handle: &crate::module::ScriptModuleCallHandle,
+    index: usize,
+) -> Result<Option<u32>, String> {
+    match handle.get_param_type(index) {
+        None | Some(crate::module::ParamType::Nil) => {
+            Ok(crate::module::current_object_context().map(|object| object.frame))
+        }
+        Some(_) => {
+            let frame = handle
+                .get_param_int(index)
+                .map_err(|e| format!("invalid frame: {e}"))?;
+            u32::try_from(frame)
+                .map(Some)
+                .map_err(|_| format!("frame must not be negative: {frame}"))
+        }
+    }
+}
+
+fn shared_store_set(handle: &crate::module::ScriptModuleCallHandle) -> Result<(), String> {
+    use crate::module::ParamType;
+
+    let key = handle
+        .get_param_str(0)
+        .map_err(|e| format!("invalid key: {e}"))?;
+    let value = match handle.get_param_type(1) {
+        None | Some(ParamType::Nil) => {
+            crate::interop::shared_store().remove(&key);
+            return Ok(());
+        }
+        Some(ParamType::Number) => ScriptModuleValue::Float(
+            handle
+                .get_param_float(1)
+                .map_err(|e| format!("invalid value: {e}"))?,
+        ),
+        Some(ParamType::String) => ScriptModuleValue::String(
+            handle
+                .get_param_str(1)
+                .map_err(|e| format!("invalid value: {e}"))?,
+        ),
+        Some(ParamType::Boolean) => ScriptModuleValue::Boolean(handle.get_param_boolean(1)),
+        Some(other) => return Err(format!("unsupported value type: {other:?}")),
+    };
+    match optional_frame_param(handle, 2)? {
+        Some(frame) => crate::interop::shared_store().set_at_frame(&key, frame, value),
+        None => crate::interop::shared_store().set(&key, value),
+    }
+    Ok(())
+}
+
+extern "C" fn shared_store_set_function(smp: *mut aviutl2_sys::module2::SCRIPT_MODULE_PARAM) {
+    if let Err(panic_info) = crate::utils::catch_unwind_with_panic_info(|| {
+        let mut handle = unsafe { crate::module::ScriptModuleCallHandle::from_raw(smp) };
+        if let Err(message) = shared_store_set(&handle) {
+            let _ = handle.set_error(&message);
+        }
+    }) {
+        tracing::error!(
+            "Panic occurred during {}: {}",
+            SHARED_STORE_SET_FUNCTION_NAME,
+            panic_info
+        );
+        let _ = crate::logger::write_error_log(&panic_info);
+    }
+}
+
+extern "C" fn shared_store_get_function(smp: *mut aviutl2_sys::module2::SCRIPT_MODULE_PARAM) {
+    if let Err(panic_info) = crate::utils::catch_unwind_with_panic_info(|| {
+        let mut handle = unsafe { crate::module::ScriptModuleCallHandle::from_raw(smp) };
+        let key = match handle.get_param_str(0) {
+            Ok(key) => key,
+            Err(e) => {
+                let _ = handle.set_error(&format!("invalid key: {e}"));
+                return;
+            }
+        };
+        let value = match optional_frame_param(&handle, 1) {
+            Ok(Some(frame)) => crate::interop::shared_store().get_at_frame(&key, frame),
+            Ok(None) => crate::interop::shared_store().get(&key),
+            Err(message) => {
+                let _ = handle.set_error(&message);
+                return;
+            }
+        };
+        // 値がない場合は何も返さず、スクリプト側では`nil`になる
+        if let Some(value) = value
+            && let Err(e) = push_value(&mut handle, value)
+        {
+            tracing::error!("Failed to push shared store value {:?}: {}", key, e);
+        }
+    }) {
+        tracing::error!(
+            "Panic occurred during {}: {}",
+            SHARED_STORE_GET_FUNCTION_NAME,
+            panic_info
+        );
+        let _ = crate::logger::write_error_log(&panic_info);
+    }
+}
+
+extern "C" fn introspection_function<T: ScriptModuleSingleton>(
+    smp: *mut aviutl2_sys::module2::SCRIPT_MODULE_PARAM,
+) {
+    if let Err(panic_info) = crate::utils::catch_unwind_with_panic_info(|| {
+        let mut handle = unsafe { crate::module::ScriptModuleCallHandle::from_raw(smp) };
+        let registered: Vec<String> = {
+            let plugin_state = T::__get_singleton_state().read().unwrap();
+            let plugin_state = plugin_state.as_ref().expect("Plugin not initialized");
+            plugin_state
+                .plugin_info
+                .functions
+                .iter()
+                .map(|f| f.name.clone())
+                .collect()
+        };
+        let metadata: Vec<ModuleFunctionMetadata> = T::function_metadata()
+            .into_iter()
+            .filter(|m| registered.contains(&m.name))
+            .collect();
+        if let Err(e) = handle.push_result_str(&function_metadata_to_json(&metadata)) {
+            tracing::error!("Failed to push function metadata: {}", e);
+        }
+    }) {
+        tracing::error!(
+            "Panic occurred during {}: {}",
+            INTROSPECTION_FUNCTION_NAME,
+            panic_info
+        );
+        let _ = crate::logger::write_error_log(&panic_info);
+    }
+}
+
+/// 関数の情報をJSON文字列に変換する。
+///
+/// スクリプトモジュールからは入れ子のテーブルを返せないため、JSON文字列として返す。
+fn function_metadata_to_json(metadata: &[ModuleFunctionMetadata]) -> String {
+    fn push_json_str(out: &mut String, s: &str) {
+        out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+
+    let mut out = String::from("[");
+    for (i, function) in metadata.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"name\":");
+        push_json_str(&mut out, &function.name);
+        out.push_str(",\"params\":[");
+        for (j, param) in function.params.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            push_json_str(&mut out, &param.name);
+            out.push_str(",\"type\":");
+            push_json_str(&mut out, &param.ty);
+            out.push('}');
+        }
+        out.push_str("],\"returns\":");
+        match &function.returns {
+            Some(returns) => push_json_str(&mut out, returns),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"doc\":");
+        push_json_str(&mut out, &function.doc);
+        out.push('}');
+    }
+    out.push(']');
+    out
+}
+
 extern "C" fn unreachable_function(_: *mut aviutl2_sys::module2::SCRIPT_MODULE_PARAM) {
     unreachable!("This function should never be called");
 }
@@ -253,3 +612,713 @@ macro_rules! register_script_module {
         $crate::register_script_module!($struct, );
     };
 }
+
+/// 複数のスクリプトモジュールを1つのDLLに登録するマクロ。
+///
+/// すべてのモジュールの関数が1つのモジュールにまとめて登録されます。
+/// 関数名が重ならないように、それぞれのモジュールの[`ScriptModuleTable::name`][crate::module::ScriptModuleTable::name]に
+/// 名前空間を指定してください。関数名が重なった場合は、先に指定したモジュールの関数が使われます。
+/// 「プラグイン情報」ダイアログには最初のモジュールの情報が表示されます。
+///
+/// # Arguments
+///
+/// - `unwind`: panic時にunwindするかどうか。デフォルトは`true`。
+///
+/// # Example
+///
+/// ```rust,ignore
+/// // my_tools.mod2
+/// aviutl2::register_script_modules!(ColorModule, MathModule);
+/// // unwindを無効にする場合
+/// aviutl2::register_script_modules!(ColorModule, MathModule; unwind = false);
+/// ```
+///
+/// ```lua
+/// -- ColorModuleのnameが"color"、MathModuleのnameが"math"の場合
+/// local c = my_tools.color_blend(0xff0000, 0x0000ff, 0.5)
+/// local n = my_tools.math_clamp(10, 0, 5)
+/// ```
+#[macro_export]
+macro_rules! register_script_modules {
+    ($($struct:ident),+ ; $($key:ident = $value:expr),* $(,)?) => {
+        $crate::__internal_module! {
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn RequiredVersion() -> u32 {
+                $crate::MINIMUM_AVIUTL2_VERSION.into()
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn InitializeLogger(logger: *mut $crate::sys::logger2::LOG_HANDLE) {
+                $crate::comptime_if::comptime_if! {
+                    if unwind where (unwind = true, $( $key = $value ),* ) {
+                        $crate::logger::__initialize_logger_unwind(logger)
+                    } else {
+                        $crate::logger::__initialize_logger(logger)
+                    }
+                }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn InitializeConfig(
+                config: *mut $crate::sys::config2::CONFIG_HANDLE
+            ) {
+                $crate::comptime_if::comptime_if! {
+                    if unwind where (unwind = true, $( $key = $value ),* ) {
+                        $crate::config::__initialize_config_handle_unwind(config)
+                    } else {
+                        $crate::config::__initialize_config_handle(config)
+                    }
+                }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn InitializeCache(
+                cache: *mut $crate::sys::cache2::CACHE_HANDLE
+            ) {
+                $crate::comptime_if::comptime_if! {
+                    if unwind where (unwind = true, $( $key = $value ),* ) {
+                        $crate::cache::__initialize_cache_unwind(cache)
+                    } else {
+                        $crate::cache::__initialize_cache(cache)
+                    }
+                }
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn InitializePlugin(version: u32) -> bool {
+                // 1つでも初期化に失敗した場合は、すべてのモジュールを破棄する
+                let initialized = [$(
+                    unsafe {
+                        $crate::comptime_if::comptime_if! {
+                            if unwind where (unwind = true, $( $key = $value ),* ) {
+                                $crate::module::__bridge::initialize_plugin_c_unwind::<$struct>(version)
+                            } else {
+                                $crate::module::__bridge::initialize_plugin_c::<$struct>(version)
+                            }
+                        }
+                    }
+                ),+];
+                if initialized.iter().all(|&initialized| initialized) {
+                    return true;
+                }
+                unsafe { UninitializePlugin() };
+                false
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn UninitializePlugin() {
+                $(
+                    unsafe {
+                        $crate::comptime_if::comptime_if! {
+                            if unwind where (unwind = true, $( $key = $value ),* ) {
+                                $crate::module::__bridge::uninitialize_plugin_c_unwind::<$struct>()
+                            } else {
+                                $crate::module::__bridge::uninitialize_plugin::<$struct>()
+                            }
+                        }
+                    }
+                )+
+            }
+
+            #[unsafe(no_mangle)]
+            unsafe extern "C" fn GetScriptModuleTable()
+            -> *mut $crate::sys::module2::SCRIPT_MODULE_TABLE {
+                let entries: &[fn() -> $crate::module::__bridge::ModuleTableEntries] = &[
+                    $( $crate::module::__bridge::table_entries::<$struct> ),+
+                ];
+                $crate::comptime_if::comptime_if! {
+                    if unwind where (unwind = true, $( $key = $value ),* ) {
+                        unsafe { $crate::module::__bridge::create_combined_table_unwind(entries) }
+                    } else {
+                        unsafe { $crate::module::__bridge::create_combined_table(entries) }
+                    }
+                }
+            }
+        }
+    };
+    ($($struct:ident),+ ; $($key:ident),* $(,)?) => {
+        $crate::register_script_modules!($($struct),+ ; $( $key = true ),* );
+    };
+    ($($struct:ident),+ $(,)?) => {
+        $crate::register_script_modules!($($struct),+ ; );
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::{ModuleFunctionParamMetadata, ScriptModuleCallHandle, TimeBudgetExceeded};
+    use aviutl2_sys::module2::{META_METHOD_FUNCTION, PARAM_TYPE, SCRIPT_MODULE_PARAM};
+    use std::cell::RefCell;
+    use std::ffi::{c_char, c_double, c_int, c_void};
+
+    #[test]
+    fn test_function_metadata_to_json() {
+        let metadata = vec![
+            ModuleFunctionMetadata {
+                name: "add".to_string(),
+                params: vec![
+                    ModuleFunctionParamMetadata {
+                        name: "a".to_string(),
+                        ty: "i32".to_string(),
+                    },
+                    ModuleFunctionParamMetadata {
+                        name: "b".to_string(),
+                        ty: "i32".to_string(),
+                    },
+                ],
+                returns: Some("i32".to_string()),
+                doc: "2つの\"数\"を足す。\n結果を返す。".to_string(),
+            },
+            ModuleFunctionMetadata {
+                name: "raw".to_string(),
+                params: vec![],
+                returns: None,
+                doc: String::new(),
+            },
+        ];
+        assert_eq!(
+            function_metadata_to_json(&metadata),
+            concat!(
+                r#"[{"name":"add","params":[{"name":"a","type":"i32"},{"name":"b","type":"i32"}],"#,
+                r#""returns":"i32","doc":"2つの\"数\"を足す。\n結果を返す。"},"#,
+                r#"{"name":"raw","params":[],"returns":null,"doc":""}]"#
+            )
+        );
+        assert_eq!(function_metadata_to_json(&[]), "[]");
+    }
+
+    thread_local! {
+        static ERRORS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    macro_rules! stub {
+        ($($name:ident($($arg:ty),*) $(-> $ret:ty = $value:expr)?;)*) => {
+            $(
+                unsafe extern "C" fn $name($(_: $arg),*) $(-> $ret)? {
+                    $($value)?
+                }
+            )*
+        };
+    }
+
+    stub! {
+        get_param_num() -> c_int = 0;
+        get_param_int(c_int) -> c_int = 0;
+        get_param_double(c_int) -> c_double = 0.0;
+        get_param_string(c_int) -> *const c_char = std::ptr::null();
+        get_param_data(c_int) -> *mut c_void = std::ptr::null_mut();
+        get_param_table_int(c_int, *const c_char) -> c_int = 0;
+        get_param_table_double(c_int, *const c_char) -> c_double = 0.0;
+        get_param_table_string(c_int, *const c_char) -> *const c_char = std::ptr::null();
+        get_param_array_num(c_int) -> c_int = 0;
+        get_param_array_int(c_int, c_int) -> c_int = 0;
+        get_param_array_double(c_int, c_int) -> c_double = 0.0;
+        get_param_array_string(c_int, c_int) -> *const c_char = std::ptr::null();
+        push_result_int(c_int);
+        push_result_double(c_double);
+        push_result_string(*const c_char);
+        push_result_data(*const c_void);
+        push_result_table_int(*const *const c_char, *const c_int, c_int);
+        push_result_table_double(*const *const c_char, *const c_double, c_int);
+        push_result_table_string(*const *const c_char, *const *const c_char, c_int);
+        push_result_array_int(*const c_int, c_int);
+        push_result_array_double(*const c_double, c_int);
+        push_result_array_string(*const *const c_char, c_int);
+        get_param_boolean(c_int) -> bool = false;
+        push_result_boolean(bool);
+        get_param_table_boolean(c_int, *const c_char) -> bool = false;
+        push_result_array_boolean(*const bool, c_int);
+        push_result_table_boolean(*const *const c_char, *const bool, c_int);
+        push_result_function(unsafe extern "C" fn(*mut SCRIPT_MODULE_PARAM), *mut c_void);
+        deprecated_push_result_meta_table(
+            unsafe extern "C" fn(*mut SCRIPT_MODULE_PARAM),
+            unsafe extern "C" fn(*mut SCRIPT_MODULE_PARAM),
+            *mut c_void
+        );
+        push_result_meta_table(*const META_METHOD_FUNCTION, *mut c_void);
+        get_param_meta_table(c_int, *mut META_METHOD_FUNCTION) -> *mut c_void = std::ptr::null_mut();
+        get_param_type(c_int) -> PARAM_TYPE = PARAM_TYPE::NONE;
+    }
+
+    unsafe extern "C" fn set_error(message: *const c_char) {
+        let message = unsafe { std::ffi::CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned();
+        ERRORS.with(|errors| errors.borrow_mut().push(message));
+    }
+
+    fn mock_param() -> SCRIPT_MODULE_PARAM {
+        SCRIPT_MODULE_PARAM {
+            get_param_num,
+            get_param_int,
+            get_param_double,
+            get_param_string,
+            get_param_data,
+            get_param_table_int,
+            get_param_table_double,
+            get_param_table_string,
+            get_param_array_num,
+            get_param_array_int,
+            get_param_array_double,
+            get_param_array_string,
+            push_result_int,
+            push_result_double,
+            push_result_string,
+            push_result_data,
+            push_result_table_int,
+            push_result_table_double,
+            push_result_table_string,
+            push_result_array_int,
+            push_result_array_double,
+            push_result_array_string,
+            set_error,
+            get_param_boolean,
+            push_result_boolean,
+            get_param_table_boolean,
+            push_result_array_boolean,
+            push_result_table_boolean,
+            edit: std::ptr::null_mut(),
+            push_result_function,
+            deprecated_push_result_meta_table,
+            userdata: std::ptr::null_mut(),
+            push_result_meta_table,
+            get_param_meta_table,
+            get_param_type,
+        }
+    }
+
+    struct BudgetModule;
+
+    impl crate::module::ScriptModuleFunctions for BudgetModule {
+        fn functions() -> Vec<crate::module::ModuleFunction> {
+            Vec::new()
+        }
+    }
+
+    impl ScriptModule for BudgetModule {
+        fn new(_info: crate::common::AviUtl2Info) -> AnyResult<Self> {
+            Ok(BudgetModule)
+        }
+
+        fn plugin_info(&self) -> ScriptModuleTable {
+            ScriptModuleTable {
+                information: "Budget".to_string(),
+                name: None,
+                functions: Vec::new(),
+                include_introspection: false,
+                default_time_budget: Some(std::time::Duration::from_millis(10)),
+                constants: Vec::new(),
+                enable_shared_store: false,
+            }
+        }
+    }
+
+    impl ScriptModuleSingleton for BudgetModule {
+        fn __get_singleton_state()
+        -> &'static std::sync::RwLock<Option<InternalScriptModuleState<Self>>> {
+            static STATE: std::sync::RwLock<Option<InternalScriptModuleState<BudgetModule>>> =
+                std::sync::RwLock::new(None);
+            &STATE
+        }
+    }
+
+    extern "C" fn bridge_busy_loop(smp: *mut SCRIPT_MODULE_PARAM) {
+        crate::module::__with_call_context::<BudgetModule, _>(|| {
+            let mut handle = unsafe { ScriptModuleCallHandle::from_raw(smp) };
+            let result = (|| -> Result<i32, TimeBudgetExceeded> {
+                let call = crate::module::current_call();
+                assert!(call.deadline().is_some());
+                loop {
+                    call.check()?;
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                }
+            })();
+            crate::module::__push_return_value(&mut handle, result);
+        });
+    }
+
+    #[test]
+    fn test_call_context_time_budget_exceeded() {
+        *BudgetModule::__get_singleton_state().write().unwrap() =
+            Some(InternalScriptModuleState::new(BudgetModule));
+
+        let mut param = mock_param();
+        bridge_busy_loop(&mut param);
+
+        assert_eq!(
+            ERRORS.with(|errors| errors.take()),
+            vec!["time budget exceeded".to_string()]
+        );
+        assert_eq!(crate::module::current_call().deadline(), None);
+    }
+
+    struct ConstantModule;
+
+    impl crate::module::ScriptModuleFunctions for ConstantModule {
+        fn functions() -> Vec<crate::module::ModuleFunction> {
+            vec![crate::module::ModuleFunction {
+                name: "noop".to_string(),
+                func: noop_function,
+            }]
+        }
+
+        fn constants() -> Vec<(String, ScriptModuleValue)> {
+            vec![
+                ("VERSION".to_string(), "1.2.3".into()),
+                ("MODE_FAST".to_string(), 2.into()),
+            ]
+        }
+    }
+
+    impl ScriptModule for ConstantModule {
+        fn new(_info: crate::common::AviUtl2Info) -> AnyResult<Self> {
+            Ok(ConstantModule)
+        }
+
+        fn plugin_info(&self) -> ScriptModuleTable {
+            ScriptModuleTable {
+                information: "Constant".to_string(),
+                name: None,
+                functions: Self::functions(),
+                include_introspection: true,
+                default_time_budget: None,
+                constants: Self::constants(),
+                enable_shared_store: false,
+            }
+        }
+    }
+
+    impl ScriptModuleSingleton for ConstantModule {
+        fn __get_singleton_state()
+        -> &'static std::sync::RwLock<Option<InternalScriptModuleState<Self>>> {
+            static STATE: std::sync::RwLock<Option<InternalScriptModuleState<ConstantModule>>> =
+                std::sync::RwLock::new(None);
+            &STATE
+        }
+    }
+
+    extern "C" fn noop_function(_: *mut SCRIPT_MODULE_PARAM) {}
+
+    thread_local! {
+        static CONSTANT_NAME: RefCell<std::ffi::CString> = RefCell::new(std::ffi::CString::default());
+        static RESULTS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    unsafe extern "C" fn one_param() -> c_int {
+        1
+    }
+
+    unsafe extern "C" fn string_param_type(index: c_int) -> PARAM_TYPE {
+        if index == 0 {
+            PARAM_TYPE::STRING
+        } else {
+            PARAM_TYPE::NONE
+        }
+    }
+
+    unsafe extern "C" fn constant_name_param(_: c_int) -> *const c_char {
+        CONSTANT_NAME.with(|name| name.borrow().as_ptr())
+    }
+
+    unsafe extern "C" fn record_int(value: c_int) {
+        RESULTS.with(|results| results.borrow_mut().push(format!("int:{value}")));
+    }
+
+    unsafe extern "C" fn record_string(value: *const c_char) {
+        let value = unsafe { std::ffi::CStr::from_ptr(value) }.to_string_lossy();
+        RESULTS.with(|results| results.borrow_mut().push(format!("string:{value}")));
+    }
+
+    fn call_get_constant(name: &str) -> (Vec<String>, Vec<String>) {
+        CONSTANT_NAME.with(|n| *n.borrow_mut() = std::ffi::CString::new(name).unwrap());
+        let mut param = SCRIPT_MODULE_PARAM {
+            get_param_num: one_param,
+            get_param_type: string_param_type,
+            get_param_string: constant_name_param,
+            push_result_int: record_int,
+            push_result_string: record_string,
+            ..mock_param()
+        };
+        constant_function::<ConstantModule>(&mut param);
+        (
+            RESULTS.with(|results| results.take()),
+            ERRORS.with(|errors| errors.take()),
+        )
+    }
+
+    #[test]
+    fn test_registered_functions_with_constants() {
+        let state = InternalScriptModuleState::new(ConstantModule);
+        let names: Vec<&str> = registered_functions::<ConstantModule>(&state.plugin_info)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["noop", "__functions", "get_constant"]);
+
+        let budget = InternalScriptModuleState::new(BudgetModule);
+        assert!(registered_functions::<BudgetModule>(&budget.plugin_info).is_empty());
+    }
+
+    #[test]
+    fn test_registered_functions_with_shared_store() {
+        let mut plugin_info = InternalScriptModuleState::new(ConstantModule).plugin_info;
+        plugin_info.enable_shared_store = true;
+        let names: Vec<&str> = registered_functions::<ConstantModule>(&plugin_info)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(
+            names,
+            vec!["noop", "__functions", "get_constant", "set", "get"]
+        );
+    }
+
+    #[test]
+    fn test_namespaced_functions() {
+        let mut plugin_info = InternalScriptModuleState::new(ConstantModule).plugin_info;
+        plugin_info.name = Some("color".to_string());
+        let names: Vec<String> = table_entries_from_info::<ConstantModule>(&plugin_info)
+            .functions
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(
+            names,
+            vec!["color_noop", "color___functions", "color_get_constant"]
+        );
+    }
+
+    extern "C" fn other_function(_: *mut SCRIPT_MODULE_PARAM) {}
+
+    #[test]
+    fn test_merge_functions_skips_duplicates() {
+        let entries = vec![
+            ModuleTableEntries {
+                information: "A".to_string(),
+                functions: vec![("a".to_string(), noop_function as ModuleFunctionPointer)],
+            },
+            ModuleTableEntries {
+                information: "B".to_string(),
+                functions: vec![
+                    ("a".to_string(), other_function as ModuleFunctionPointer),
+                    ("b".to_string(), other_function as ModuleFunctionPointer),
+                ],
+            },
+        ];
+        let merged = merge_functions(&entries);
+        assert_eq!(
+            merged.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+        assert!(std::ptr::fn_addr_eq(
+            merged[0].1,
+            noop_function as ModuleFunctionPointer
+        ));
+    }
+
+    struct ColorModule;
+    struct MathModule;
+
+    extern "C" fn push_color(smp: *mut SCRIPT_MODULE_PARAM) {
+        let mut handle = unsafe { ScriptModuleCallHandle::from_raw(smp) };
+        handle.push_result_str("color").unwrap();
+    }
+
+    extern "C" fn push_math(smp: *mut SCRIPT_MODULE_PARAM) {
+        let mut handle = unsafe { ScriptModuleCallHandle::from_raw(smp) };
+        handle.push_result_str("math").unwrap();
+    }
+
+    macro_rules! namespaced_module {
+        ($module:ident, $name:literal, $func:ident, $constants:expr) => {
+            impl crate::module::ScriptModuleFunctions for $module {
+                fn functions() -> Vec<crate::module::ModuleFunction> {
+                    vec![crate::module::ModuleFunction {
+                        name: "blend".to_string(),
+                        func: $func,
+                    }]
+                }
+            }
+
+            impl ScriptModule for $module {
+                fn new(_info: crate::common::AviUtl2Info) -> AnyResult<Self> {
+                    Ok($module)
+                }
+
+                fn plugin_info(&self) -> ScriptModuleTable {
+                    ScriptModuleTable {
+                        information: $name.to_string(),
+                        name: Some($name.to_string()),
+                        functions: Self::functions(),
+                        include_introspection: false,
+                        default_time_budget: None,
+                        constants: $constants,
+                        enable_shared_store: false,
+                    }
+                }
+            }
+
+            impl ScriptModuleSingleton for $module {
+                fn __get_singleton_state()
+                -> &'static std::sync::RwLock<Option<InternalScriptModuleState<Self>>> {
+                    static STATE: std::sync::RwLock<Option<InternalScriptModuleState<$module>>> =
+                        std::sync::RwLock::new(None);
+                    &STATE
+                }
+            }
+        };
+    }
+
+    namespaced_module!(
+        ColorModule,
+        "color",
+        push_color,
+        vec![("VERSION".to_string(), 1.into())]
+    );
+    namespaced_module!(MathModule, "math", push_math, Vec::new());
+
+    crate::register_script_modules!(ColorModule, MathModule);
+
+    unsafe extern "C" {
+        fn InitializePlugin(version: u32) -> bool;
+        fn UninitializePlugin();
+        fn GetScriptModuleTable() -> *mut aviutl2_sys::module2::SCRIPT_MODULE_TABLE;
+    }
+
+    #[test]
+    fn test_register_script_modules() {
+        assert!(unsafe { InitializePlugin(crate::MINIMUM_AVIUTL2_VERSION.into()) });
+        let table = unsafe { &*GetScriptModuleTable() };
+        assert_eq!(
+            unsafe { crate::common::load_wide_string(table.information) },
+            "color"
+        );
+
+        let mut functions = Vec::new();
+        let mut ptr = table.functions;
+        while !unsafe { (*ptr).name }.is_null() {
+            let function = unsafe { &*ptr };
+            functions.push((
+                unsafe { crate::common::load_wide_string(function.name) },
+                function.func,
+            ));
+            ptr = unsafe { ptr.add(1) };
+        }
+        assert_eq!(
+            functions
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["color_blend", "color_get_constant", "math_blend"]
+        );
+
+        // 名前空間ごとに、それぞれのモジュールの関数が呼ばれる
+        for (name, func) in &functions {
+            if name.ends_with("_blend") {
+                let mut param = SCRIPT_MODULE_PARAM {
+                    push_result_string: record_string,
+                    ..mock_param()
+                };
+                func(&mut param);
+            }
+        }
+        assert_eq!(
+            RESULTS.with(|results| results.take()),
+            vec!["string:color".to_string(), "string:math".to_string()]
+        );
+
+        unsafe { UninitializePlugin() };
+        assert!(
+            ColorModule::__get_singleton_state()
+                .read()
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            MathModule::__get_singleton_state()
+                .read()
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    /// `Display`だけを実装したエラー。
+    struct ParseError(String);
+
+    impl std::fmt::Display for ParseError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "invalid number: {}", self.0)
+        }
+    }
+
+    thread_local! {
+        static PARSE_INPUT: RefCell<String> = const { RefCell::new(String::new()) };
+    }
+
+    /// `#[aviutl2::module::functions]`で`Result<(i32, String), ParseError>`を返す関数を定義した場合と同じ処理。
+    /// `数値:ラベル`を受け取り、数値とラベルを返す。
+    extern "C" fn bridge_parse_number(smp: *mut SCRIPT_MODULE_PARAM) {
+        let mut handle = unsafe { ScriptModuleCallHandle::from_raw(smp) };
+        let text = PARSE_INPUT.with(|input| input.borrow().clone());
+        let (number, label) = text.split_once(':').unwrap_or((&text, ""));
+        let fn_result = number
+            .parse::<i32>()
+            .map(|number| (number, label.to_string()))
+            .map_err(|_| ParseError(number.to_string()));
+        crate::module::__push_return_value(&mut handle, fn_result);
+    }
+
+    fn call_parse_number(text: &str) -> (Vec<String>, Vec<String>) {
+        PARSE_INPUT.with(|input| *input.borrow_mut() = text.to_string());
+        let mut param = SCRIPT_MODULE_PARAM {
+            push_result_int: record_int,
+            push_result_string: record_string,
+            ..mock_param()
+        };
+        bridge_parse_number(&mut param);
+        (
+            RESULTS.with(|results| results.take()),
+            ERRORS.with(|errors| errors.take()),
+        )
+    }
+
+    #[test]
+    fn test_push_result_value() {
+        assert_eq!(
+            call_parse_number("42:answer"),
+            (
+                vec!["int:42".to_string(), "string:answer".to_string()],
+                vec![]
+            )
+        );
+        assert_eq!(
+            call_parse_number("abc:answer"),
+            (vec![], vec!["invalid number: abc".to_string()])
+        );
+        // 2つ目の値が返せない場合、1つ目の値も返さない
+        assert_eq!(
+            call_parse_number("1:a\0b"),
+            (vec![], vec!["value contains null byte".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_get_constant() {
+        *ConstantModule::__get_singleton_state().write().unwrap() =
+            Some(InternalScriptModuleState::new(ConstantModule));
+
+        assert_eq!(
+            call_get_constant("VERSION"),
+            (vec!["string:1.2.3".to_string()], vec![])
+        );
+        assert_eq!(
+            call_get_constant("MODE_FAST"),
+            (vec!["int:2".to_string()], vec![])
+        );
+        assert_eq!(
+            call_get_constant("MODE_SLOW"),
+            (vec![], vec!["unknown constant: MODE_SLOW".to_string()])
+        );
+    }
+}
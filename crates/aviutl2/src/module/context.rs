@@ -0,0 +1,280 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// スクリプトモジュールの関数呼び出しごとの情報を表す構造体。
+///
+/// [`current_call`]で取得できます。
+///
+/// AviUtl2はスクリプトの評価中に関数を同期的に呼び出すため、時間のかかる処理を行うと描画が止まってしまいます。
+/// ループする処理では定期的に[`CallContext::check`]を呼び出し、制限時間を超えたら処理を中断してください。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallContext {
+    started_at: Instant,
+    deadline: Option<Instant>,
+    object: Option<ObjectContext>,
+}
+
+/// 関数を呼び出したオブジェクトの情報を表す構造体。
+///
+/// [`current_object_context`]で取得できます。
+///
+/// # Note
+///
+/// 現在のAviUtl2 SDKでは、スクリプトモジュールの関数呼び出しにオブジェクトの情報が渡されないため、
+/// AviUtl2から呼び出された場合は常に取得できません。
+/// SDKが対応した場合に、ブリッジ側で設定されるようになります。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ObjectContext {
+    /// 描画中のオブジェクト。
+    pub object_id: crate::generic::ObjectHandle,
+    /// オブジェクトの開始からのフレーム番号。
+    pub frame: u32,
+    /// オブジェクトの長さ（フレーム数）。
+    pub total_frames: u32,
+    /// シーンの解像度（幅、高さ）。
+    pub scene_resolution: (u32, u32),
+}
+
+/// 制限時間を超えたことを表すエラー。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("time budget exceeded")]
+pub struct TimeBudgetExceeded;
+
+thread_local! {
+    static CURRENT_CALL: Cell<Option<CallContext>> = const { Cell::new(None) };
+}
+
+impl CallContext {
+    fn new(time_budget: Option<Duration>) -> Self {
+        let started_at = Instant::now();
+        Self {
+            started_at,
+            deadline: time_budget.and_then(|budget| started_at.checked_add(budget)),
+            object: None,
+        }
+    }
+
+    /// 関数の呼び出しが始まった時刻を返す。
+    pub fn started_at(&self) -> Instant {
+        self.started_at
+    }
+
+    /// 処理を打ち切るべき時刻を返す。
+    ///
+    /// [`ScriptModuleTable::default_time_budget`][crate::module::ScriptModuleTable::default_time_budget]が
+    /// `None`の場合は`None`を返します。
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// 残り時間を返す。
+    ///
+    /// 制限時間がない場合は`None`を返します。
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// 関数を呼び出したオブジェクトの情報を返す。
+    ///
+    /// 詳しくは[`current_object_context`]を参照してください。
+    pub fn object(&self) -> Option<ObjectContext> {
+        self.object
+    }
+
+    /// 処理を中断するべきかどうかを返す。
+    ///
+    /// AviUtl2側から呼び出しを中断する手段はないため、制限時間を超えたかどうかを返します。
+    pub fn is_cancelled(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// 処理を中断するべき場合にエラーを返す。
+    ///
+    /// 関数の戻り値を`Result`にして`?`で伝播させると、`time budget exceeded`というエラーがスクリプト側に返されます。
+    pub fn check(&self) -> Result<(), TimeBudgetExceeded> {
+        if self.is_cancelled() {
+            Err(TimeBudgetExceeded)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 現在の関数呼び出しの情報を返す。
+///
+/// [`macro@crate::module::functions`]で登録した関数の中から呼び出してください。
+/// 関数呼び出しの外から呼び出した場合は、制限時間のない情報を返します。
+pub fn current_call() -> CallContext {
+    CURRENT_CALL
+        .with(|current| current.get())
+        .unwrap_or_else(|| CallContext::new(None))
+}
+
+impl ObjectContext {
+    /// オブジェクトの情報を作成する。
+    pub fn new(
+        object_id: crate::generic::ObjectHandle,
+        frame: u32,
+        total_frames: u32,
+        scene_resolution: (u32, u32),
+    ) -> Self {
+        Self {
+            object_id,
+            frame,
+            total_frames,
+            scene_resolution,
+        }
+    }
+
+    /// オブジェクトの開始から終了までの進み具合を`0.0`〜`1.0`で返す。
+    pub fn progress(&self) -> f64 {
+        if self.total_frames <= 1 {
+            0.0
+        } else {
+            self.frame as f64 / (self.total_frames - 1) as f64
+        }
+    }
+}
+
+/// 現在の関数呼び出しを行ったオブジェクトの情報を返す。
+///
+/// 関数呼び出しの外から呼び出した場合や、呼び出し元のオブジェクトの情報がない場合は`None`を返します。
+/// 返された情報は、その関数呼び出しの間のみ有効です。
+///
+/// # Note
+///
+/// 現在のAviUtl2 SDKでは、スクリプトモジュールの関数呼び出しにオブジェクトの情報が渡されないため、
+/// AviUtl2から呼び出された場合は常に`None`を返します。
+pub fn current_object_context() -> Option<ObjectContext> {
+    CURRENT_CALL
+        .with(|current| current.get())
+        .and_then(|context| context.object)
+}
+
+struct CallContextGuard {
+    previous: Option<CallContext>,
+}
+
+impl Drop for CallContextGuard {
+    fn drop(&mut self) {
+        CURRENT_CALL.with(|current| current.set(self.previous));
+    }
+}
+
+#[doc(hidden)]
+pub fn __with_call_context<T, R>(f: impl FnOnce() -> R) -> R
+where
+    T: crate::module::__bridge::ScriptModuleSingleton,
+{
+    let time_budget = {
+        let plugin_state = T::__get_singleton_state().read().unwrap();
+        plugin_state
+            .as_ref()
+            .expect("Plugin not initialized")
+            .plugin_info
+            .default_time_budget
+    };
+    // SCRIPT_MODULE_PARAMにはオブジェクトの情報がないので、`object`は設定しない
+    let context = CallContext::new(time_budget);
+    let _guard = CallContextGuard {
+        previous: CURRENT_CALL.with(|current| current.replace(Some(context))),
+    };
+    f()
+}
+
+/// `f`の間、呼び出し元のオブジェクトの情報を`object`にする。
+///
+/// 関数呼び出しの外から呼び出した場合は、制限時間のない呼び出しとして扱います。
+#[doc(hidden)]
+pub fn __with_object_context<R>(object: ObjectContext, f: impl FnOnce() -> R) -> R {
+    let context = CallContext {
+        object: Some(object),
+        ..current_call()
+    };
+    let _guard = CallContextGuard {
+        previous: CURRENT_CALL.with(|current| current.replace(Some(context))),
+    };
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_without_budget_is_never_cancelled() {
+        let context = CallContext::new(None);
+        assert_eq!(context.deadline(), None);
+        assert_eq!(context.remaining(), None);
+        assert!(!context.is_cancelled());
+        assert_eq!(context.check(), Ok(()));
+    }
+
+    #[test]
+    fn test_context_with_zero_budget_is_cancelled() {
+        let context = CallContext::new(Some(Duration::ZERO));
+        assert_eq!(context.deadline(), Some(context.started_at()));
+        assert_eq!(context.remaining(), Some(Duration::ZERO));
+        assert!(context.is_cancelled());
+        assert_eq!(context.check(), Err(TimeBudgetExceeded));
+        assert_eq!(TimeBudgetExceeded.to_string(), "time budget exceeded");
+    }
+
+    #[test]
+    fn test_current_call_outside_of_call() {
+        assert_eq!(current_call().deadline(), None);
+        assert_eq!(current_call().object(), None);
+        assert_eq!(current_object_context(), None);
+    }
+
+    fn mock_object_context(frame: u32) -> ObjectContext {
+        ObjectContext::new(
+            crate::generic::ObjectHandle::from(std::ptr::null_mut()),
+            frame,
+            31,
+            (1920, 1080),
+        )
+    }
+
+    #[test]
+    fn test_object_context_is_only_valid_during_call() {
+        let object = mock_object_context(15);
+        let progress = __with_object_context(object, || {
+            let context = current_object_context().unwrap();
+            assert_eq!(context, object);
+            assert_eq!(current_call().object(), Some(object));
+            context.progress()
+        });
+        assert_eq!(progress, 0.5);
+        assert_eq!(current_object_context(), None);
+    }
+
+    #[test]
+    fn test_object_context_restores_outer_context() {
+        let outer = mock_object_context(0);
+        let inner = mock_object_context(30);
+        __with_object_context(outer, || {
+            __with_object_context(inner, || {
+                assert_eq!(current_object_context(), Some(inner));
+                assert_eq!(inner.progress(), 1.0);
+            });
+            assert_eq!(current_object_context(), Some(outer));
+        });
+    }
+
+    #[test]
+    fn test_object_context_keeps_time_budget() {
+        let context = CallContext::new(Some(Duration::ZERO));
+        let _guard = CallContextGuard {
+            previous: CURRENT_CALL.with(|current| current.replace(Some(context))),
+        };
+        __with_object_context(mock_object_context(0), || {
+            assert_eq!(current_call().deadline(), context.deadline());
+            assert!(current_call().is_cancelled());
+        });
+        assert_eq!(current_call(), context);
+    }
+}
@@ -6,14 +6,17 @@
 //!    [`#[aviutl2::plugin(ScriptModule)]`][crate::plugin]属性を付与した構造体を定義する
 //! 2. [`crate::module::functions`]で関数を定義する
 //! 3. [`crate::register_script_module!`]マクロを使用してプラグインを登録する
+//!    （複数のモジュールを1つのDLLにまとめる場合は[`crate::register_script_modules!`]マクロを使用する）
 //!
 //! サンプルは<https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/username-module>を参照してください。
 
 mod binding;
+mod context;
 mod param;
 
 pub use super::common::*;
 pub use binding::*;
+pub use context::*;
 pub use param::*;
 
 #[doc(hidden)]
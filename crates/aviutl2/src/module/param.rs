@@ -271,9 +271,9 @@ impl ScriptModuleCallHandle {
         unsafe {
             let c_str = ((*self.internal).get_param_string)(index as i32);
             assert!(!c_str.is_null(), "get_param_string returned null");
-            Ok(std::ffi::CStr::from_ptr(c_str)
-                .to_string_lossy()
-                .into_owned())
+            Ok(crate::utils::encoding::from_host_utf8(
+                std::ffi::CStr::from_ptr(c_str),
+            ))
         }
     }
 
@@ -308,7 +308,7 @@ impl ScriptModuleCallHandle {
         index: usize,
         key: &str,
     ) -> ScriptModuleCallHandleResult<i32> {
-        let c_key = std::ffi::CString::new(key)
+        let c_key = crate::utils::encoding::to_host_utf8(key)
             .map_err(ScriptModuleCallHandleError::KeyContainsNullByte)?;
         Ok(unsafe { ((*self.internal).get_param_table_int)(index as i32, c_key.as_ptr()) })
     }
@@ -323,7 +323,7 @@ impl ScriptModuleCallHandle {
         index: usize,
         key: &str,
     ) -> ScriptModuleCallHandleResult<f64> {
-        let c_key = std::ffi::CString::new(key)
+        let c_key = crate::utils::encoding::to_host_utf8(key)
             .map_err(ScriptModuleCallHandleError::KeyContainsNullByte)?;
         Ok(unsafe { ((*self.internal).get_param_table_double)(index as i32, c_key.as_ptr()) })
     }
@@ -334,18 +334,16 @@ impl ScriptModuleCallHandle {
         index: usize,
         key: &str,
     ) -> ScriptModuleCallHandleResult<Option<String>> {
-        let c_key = std::ffi::CString::new(key)
+        let c_key = crate::utils::encoding::to_host_utf8(key)
             .map_err(ScriptModuleCallHandleError::KeyContainsNullByte)?;
         unsafe {
             let c_str = ((*self.internal).get_param_table_string)(index as i32, c_key.as_ptr());
             Ok(if c_str.is_null() {
                 None
             } else {
-                Some(
-                    std::ffi::CStr::from_ptr(c_str)
-                        .to_string_lossy()
-                        .into_owned(),
-                )
+                Some(crate::utils::encoding::from_host_utf8(
+                    std::ffi::CStr::from_ptr(c_str),
+                ))
             })
         }
     }
@@ -360,7 +358,7 @@ impl ScriptModuleCallHandle {
         index: usize,
         key: &str,
     ) -> ScriptModuleCallHandleResult<bool> {
-        let c_key = std::ffi::CString::new(key)
+        let c_key = crate::utils::encoding::to_host_utf8(key)
             .map_err(ScriptModuleCallHandleError::KeyContainsNullByte)?;
         Ok(unsafe { ((*self.internal).get_param_table_boolean)(index as i32, c_key.as_ptr()) })
     }
@@ -387,18 +385,16 @@ impl ScriptModuleCallHandle {
             if c_str.is_null() {
                 None
             } else {
-                Some(
-                    std::ffi::CStr::from_ptr(c_str)
-                        .to_string_lossy()
-                        .into_owned(),
-                )
+                Some(crate::utils::encoding::from_host_utf8(
+                    std::ffi::CStr::from_ptr(c_str),
+                ))
             }
         }
     }
 
     /// 関数のエラーを設定する。
     pub fn set_error(&mut self, message: &str) -> ScriptModuleCallHandleResult<()> {
-        let c_message = std::ffi::CString::new(message)
+        let c_message = crate::utils::encoding::to_host_utf8(message)
             .map_err(ScriptModuleCallHandleError::ValueContainsNullByte)?;
         unsafe {
             ((*self.internal).set_error)(c_message.as_ptr());
@@ -430,7 +426,7 @@ impl ScriptModuleCallHandle {
 
     /// 関数の返り値に文字列を追加する。
     pub fn push_result_str(&mut self, value: &str) -> ScriptModuleCallHandleResult<()> {
-        let c_value = std::ffi::CString::new(value)
+        let c_value = crate::utils::encoding::to_host_utf8(value)
             .map_err(ScriptModuleCallHandleError::ValueContainsNullByte)?;
         unsafe {
             ((*self.internal).push_result_string)(c_value.as_ptr());
@@ -475,7 +471,7 @@ impl ScriptModuleCallHandle {
         let mut keys = Vec::new();
         let mut values = Vec::new();
         for (key, value) in table {
-            let c_key = std::ffi::CString::new(key)
+            let c_key = crate::utils::encoding::to_host_utf8(key)
                 .map_err(ScriptModuleCallHandleError::KeyContainsNullByte)?;
             keys.push(c_key);
             values.push(value);
@@ -499,7 +495,7 @@ impl ScriptModuleCallHandle {
         let mut keys = Vec::new();
         let mut values = Vec::new();
         for (key, value) in table {
-            let c_key = std::ffi::CString::new(key)
+            let c_key = crate::utils::encoding::to_host_utf8(key)
                 .map_err(ScriptModuleCallHandleError::KeyContainsNullByte)?;
             keys.push(c_key);
             values.push(value);
@@ -523,9 +519,9 @@ impl ScriptModuleCallHandle {
         let mut keys = Vec::new();
         let mut values = Vec::new();
         for (key, value) in table {
-            let c_key = std::ffi::CString::new(key)
+            let c_key = crate::utils::encoding::to_host_utf8(key)
                 .map_err(ScriptModuleCallHandleError::KeyContainsNullByte)?;
-            let c_value = std::ffi::CString::new(value)
+            let c_value = crate::utils::encoding::to_host_utf8(value)
                 .map_err(ScriptModuleCallHandleError::ValueContainsNullByte)?;
             keys.push(c_key);
             values.push(c_value);
@@ -554,7 +550,7 @@ impl ScriptModuleCallHandle {
         let mut keys = Vec::new();
         let mut values = Vec::new();
         for (key, value) in table {
-            let c_key = std::ffi::CString::new(key)
+            let c_key = crate::utils::encoding::to_host_utf8(key)
                 .map_err(ScriptModuleCallHandleError::KeyContainsNullByte)?;
             keys.push(c_key);
             values.push(value);
@@ -596,7 +592,7 @@ impl ScriptModuleCallHandle {
     pub fn push_result_array_str(&mut self, values: &[&str]) -> ScriptModuleCallHandleResult<()> {
         let c_values: Vec<std::ffi::CString> = values
             .iter()
-            .map(|s| std::ffi::CString::new(*s))
+            .map(|s| crate::utils::encoding::to_host_utf8(*s))
             .collect::<Result<_, _>>()
             .map_err(ScriptModuleCallHandleError::ValueContainsNullByte)?;
         if c_values.len() > i32::MAX as usize {
@@ -834,6 +830,12 @@ impl<'a> FromScriptModuleParam<'a> for () {
 }
 
 /// スクリプトモジュールの引数として渡される配列。
+///
+/// # Note
+///
+/// 現在のSDKには配列の要素の連想配列を読み取る関数が無いため、
+/// `{ {x=1,y=2}, {x=3,y=4} }`のような連想配列の配列は受け取れません。
+/// Lua側で`{ {1,2}, {3,4} }`を`{1,2,3,4}`のように平坦にするか、キーごとの配列に分けて渡してください。
 pub struct ScriptModuleParamArray<'a> {
     index: usize,
     ptr: *mut aviutl2_sys::module2::SCRIPT_MODULE_PARAM,
@@ -877,11 +879,9 @@ impl<'a> ScriptModuleParamArray<'a> {
             if c_str.is_null() {
                 None
             } else {
-                Some(
-                    std::ffi::CStr::from_ptr(c_str)
-                        .to_string_lossy()
-                        .into_owned(),
-                )
+                Some(crate::utils::encoding::from_host_utf8(
+                    std::ffi::CStr::from_ptr(c_str),
+                ))
             }
         }
     }
@@ -901,6 +901,10 @@ impl<'a> FromScriptModuleParam<'a> for ScriptModuleParamArray<'a> {
 }
 
 /// スクリプトモジュールの引数として渡される連想配列。
+///
+/// # Note
+///
+/// 現在のSDKには連想配列のキーを列挙する関数が無いため、キーが分かっている値のみ取得できます。
 #[derive(Debug)]
 pub struct ScriptModuleParamTable<'a> {
     index: usize,
@@ -911,36 +915,34 @@ pub struct ScriptModuleParamTable<'a> {
 impl<'a> ScriptModuleParamTable<'a> {
     /// 連想配列の要素を整数として取得する。
     pub fn get_int(&self, key: &str) -> i32 {
-        let c_key = std::ffi::CString::new(key).unwrap();
+        let c_key = crate::utils::encoding::to_host_utf8(key).unwrap();
         unsafe { ((*self.ptr).get_param_table_int)(self.index as i32, c_key.as_ptr()) }
     }
 
     /// 連想配列の要素を浮動小数点数として取得する。
     pub fn get_float(&self, key: &str) -> f64 {
-        let c_key = std::ffi::CString::new(key).unwrap();
+        let c_key = crate::utils::encoding::to_host_utf8(key).unwrap();
         unsafe { ((*self.ptr).get_param_table_double)(self.index as i32, c_key.as_ptr()) }
     }
 
     /// 連想配列の要素を文字列として取得する。
     pub fn get_str(&self, key: &str) -> Option<String> {
-        let c_key = std::ffi::CString::new(key).unwrap();
+        let c_key = crate::utils::encoding::to_host_utf8(key).unwrap();
         unsafe {
             let c_str = ((*self.ptr).get_param_table_string)(self.index as i32, c_key.as_ptr());
             if c_str.is_null() {
                 None
             } else {
-                Some(
-                    std::ffi::CStr::from_ptr(c_str)
-                        .to_string_lossy()
-                        .into_owned(),
-                )
+                Some(crate::utils::encoding::from_host_utf8(
+                    std::ffi::CStr::from_ptr(c_str),
+                ))
             }
         }
     }
 
     /// 連想配列の要素をブール値として取得する。
     pub fn get_boolean(&self, key: &str) -> bool {
-        let c_key = std::ffi::CString::new(key).unwrap();
+        let c_key = crate::utils::encoding::to_host_utf8(key).unwrap();
         unsafe { ((*self.ptr).get_param_table_boolean)(self.index as i32, c_key.as_ptr()) }
     }
 }
@@ -1120,6 +1122,57 @@ pub enum ScriptModuleReturnValue {
     MetaTable(ErasedScriptModuleUserData),
 }
 
+impl ScriptModuleReturnValue {
+    /// AviUtl2に渡せる値かどうかを確認する。
+    fn validate(&self) -> ScriptModuleCallHandleResult<()> {
+        fn check_value(value: &str) -> ScriptModuleCallHandleResult<()> {
+            crate::utils::encoding::to_host_utf8(value)
+                .map(drop)
+                .map_err(ScriptModuleCallHandleError::ValueContainsNullByte)
+        }
+        fn check_key(key: &str) -> ScriptModuleCallHandleResult<()> {
+            crate::utils::encoding::to_host_utf8(key)
+                .map(drop)
+                .map_err(ScriptModuleCallHandleError::KeyContainsNullByte)
+        }
+        fn check_len(len: usize) -> ScriptModuleCallHandleResult<()> {
+            if len > i32::MAX as usize {
+                return Err(ScriptModuleCallHandleError::TooManyElements);
+            }
+            Ok(())
+        }
+
+        match self {
+            ScriptModuleReturnValue::Int(_)
+            | ScriptModuleReturnValue::Float(_)
+            | ScriptModuleReturnValue::Boolean(_)
+            | ScriptModuleReturnValue::Data(_)
+            | ScriptModuleReturnValue::Function(_)
+            | ScriptModuleReturnValue::MetaTable(_) => Ok(()),
+            ScriptModuleReturnValue::String(v) => check_value(v),
+            ScriptModuleReturnValue::StringArray(v) => {
+                check_len(v.len())?;
+                v.iter().try_for_each(|v| check_value(v))
+            }
+            ScriptModuleReturnValue::IntArray(v) => check_len(v.len()),
+            ScriptModuleReturnValue::FloatArray(v) => check_len(v.len()),
+            ScriptModuleReturnValue::IntTable(v) => {
+                check_len(v.len())?;
+                v.keys().try_for_each(|k| check_key(k))
+            }
+            ScriptModuleReturnValue::FloatTable(v) => {
+                check_len(v.len())?;
+                v.keys().try_for_each(|k| check_key(k))
+            }
+            ScriptModuleReturnValue::StringTable(v) => {
+                check_len(v.len())?;
+                v.iter()
+                    .try_for_each(|(k, v)| check_key(k).and_then(|()| check_value(v)))
+            }
+        }
+    }
+}
+
 /// [`IntoScriptModuleReturnValue::push_into`]で使われるエラー。
 #[derive(thiserror::Error, Debug)]
 pub enum IntoScriptModuleReturnValueError<T> {
@@ -1146,10 +1199,14 @@ where
         self,
         param: &mut crate::module::ScriptModuleCallHandle,
     ) -> Result<(), crate::module::IntoScriptModuleReturnValueError<Self::Err>> {
-        for value in self
+        let values = self
             .into_return_values()
-            .map_err(IntoScriptModuleReturnValueError::ConversionFailed)?
-        {
+            .map_err(IntoScriptModuleReturnValueError::ConversionFailed)?;
+        // 途中の値で失敗して一部の値だけが返らないよう、全て確認してから追加する
+        for value in &values {
+            value.validate()?;
+        }
+        for value in values {
             match value {
                 ScriptModuleReturnValue::Int(v) => {
                     param.push_result_int(v);
@@ -1307,6 +1364,10 @@ impl IntoScriptModuleReturnValue for Vec<ScriptModuleReturnValue> {
     }
 }
 
+/// `None`の場合は何も返しません。
+///
+/// `nil`を返すことはできないため、タプルの途中に`None`があると、それ以降の値が前に詰められます。
+/// `Option`はタプルの最後に置いてください（`#[aviutl2::module::functions]`では途中に置くとコンパイルエラーになります）。
 impl<T: IntoScriptModuleReturnValue> IntoScriptModuleReturnValue for Option<T> {
     type Err = T::Err;
     fn into_return_values(self) -> Result<Vec<ScriptModuleReturnValue>, Self::Err> {
@@ -1328,9 +1389,10 @@ where
         vec.into_return_values()
     }
 }
+/// `Ok`の場合は値を返し、`Err`の場合は`Display`の内容をエラーとして設定します（値は返しません）。
 impl<T: IntoScriptModuleReturnValue, E> IntoScriptModuleReturnValue for Result<T, E>
 where
-    E: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
+    E: std::fmt::Display,
 {
     type Err = T::Err;
 
@@ -1356,11 +1418,7 @@ where
     > {
         match self {
             Ok(value) => value.push_into(param)?,
-            Err(err) => {
-                let e: Box<dyn std::error::Error + 'static> = err.into();
-                let e = e.to_string();
-                param.set_error(&e)?
-            }
+            Err(err) => param.set_error(&err.to_string())?,
         }
         Ok(())
     }
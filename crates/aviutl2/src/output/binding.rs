@@ -1,5 +1,5 @@
 use std::sync::{
-    Arc,
+    Arc, Mutex,
     atomic::{AtomicUsize, Ordering},
 };
 
@@ -64,9 +64,19 @@ pub struct OutputInfo {
     pub audio: Option<AudioOutputInfo>,
     /// 出力先のファイルパス。
     pub path: std::path::PathBuf,
+    /// プロジェクトファイルのパス。
+    ///
+    /// # Note
+    ///
+    /// 出力プラグインのAPIからはプロジェクトの情報を直接取得できないため、
+    /// 直前にプロジェクトの読み込み・保存で渡されたパスを使います。
+    /// [`OutputPluginTable::project_config`] が `false` の場合や、
+    /// プロジェクトが一度も保存されていない場合は`None`になります。
+    pub project_path: Option<std::path::PathBuf>,
 
     pub(crate) internal: *mut OUTPUT_INFO,
     pub(crate) last_frame_id: Arc<AtomicUsize>,
+    pub(crate) sidecars: Arc<Mutex<Vec<Sidecar>>>,
 }
 
 unsafe impl Send for OutputInfo {}
@@ -83,6 +93,10 @@ pub struct VideoOutputInfo {
     pub fps: Rational32,
     /// 動画のフレーム数。
     pub num_frames: u32,
+    /// YUY2形式で取得する動画フレームの色空間。
+    ///
+    /// AviUtl2からは常に[`Colorimetry::HOST`][crate::pixel::Colorimetry::HOST]で渡されます。
+    pub colorimetry: crate::pixel::Colorimetry,
 }
 
 /// 音声の出力情報を表す構造体。
@@ -96,6 +110,55 @@ pub struct AudioOutputInfo {
     pub num_channels: u32,
 }
 
+/// [`OutputPlugin::estimate`]に渡す、出力の見積もりに使う情報。
+///
+/// 動画・音声のデータは含まれません。プラグインの設定は、プラグイン自身が持っているものを使ってください。
+#[derive(Debug, Clone)]
+pub struct OutputEstimateInfo {
+    /// 動画出力情報。動画出力がない場合は`None`。
+    pub video: Option<VideoOutputInfo>,
+    /// 音声出力情報。音声出力がない場合は`None`。
+    pub audio: Option<AudioOutputInfo>,
+}
+
+impl OutputEstimateInfo {
+    /// 出力の長さ（秒）を返す。
+    ///
+    /// 動画がある場合は動画の長さ、ない場合は音声の長さを返します。
+    pub fn duration_secs(&self) -> f64 {
+        if let Some(video) = &self.video {
+            let fps = *video.fps.numer() as f64 / *video.fps.denom() as f64;
+            if fps > 0.0 {
+                return video.num_frames as f64 / fps;
+            }
+        }
+        match &self.audio {
+            Some(audio) if audio.sample_rate > 0 => {
+                audio.num_samples as f64 / audio.sample_rate as f64
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+impl From<&OutputInfo> for OutputEstimateInfo {
+    fn from(info: &OutputInfo) -> Self {
+        Self {
+            video: info.video.clone(),
+            audio: info.audio.clone(),
+        }
+    }
+}
+
+/// [`OutputPlugin::estimate`]が返す、出力の見積もり。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputEstimate {
+    /// 出力されるファイルの大きさ（バイト）。見積もれない場合は`None`。
+    pub bytes: Option<u64>,
+    /// 見積もりの根拠や幅などの補足。
+    pub notes: String,
+}
+
 /// 出力プラグインのトレイト。
 /// このトレイトを実装し、[`crate::register_output_plugin!`] マクロを使用してプラグインを登録します。
 pub trait OutputPlugin: Send + Sync + Sized {
@@ -124,6 +187,25 @@ pub trait OutputPlugin: Send + Sync + Sized {
         Ok(String::new())
     }
 
+    /// 出力するファイルの大きさを見積もる。
+    ///
+    /// デフォルトでは、`bytes`が`None`、`notes`が`"未対応"`の見積もりを返します。
+    ///
+    /// # Note
+    ///
+    /// AviUtl2には出力前に見積もりを行う仕組みがないため、AviUtl2からは呼び出されません。
+    /// 設定ダイアログなどから、[`last_output_estimate_info`]の値を使って呼び出してください。
+    fn estimate(
+        &self,
+        info: &crate::output::OutputEstimateInfo,
+    ) -> crate::common::AnyResult<crate::output::OutputEstimate> {
+        let _ = info;
+        Ok(crate::output::OutputEstimate {
+            bytes: None,
+            notes: "未対応".to_string(),
+        })
+    }
+
     /// プロジェクトファイルから出力設定を読み込む。
     ///
     /// # Note
@@ -199,6 +281,7 @@ impl OutputInfo {
                     height: raw.h as u32,
                     fps: Rational32::new(raw.rate, raw.scale),
                     num_frames: raw.n as u32,
+                    colorimetry: crate::pixel::Colorimetry::HOST,
                 })
             } else {
                 None
@@ -214,12 +297,27 @@ impl OutputInfo {
             },
 
             path: std::path::PathBuf::from(unsafe { load_wide_string(raw.savefile) }),
+            project_path: crate::output::__bridge::LAST_PROJECT_PATH
+                .lock()
+                .unwrap()
+                .clone(),
 
             internal: oip,
             last_frame_id: Arc::new(AtomicUsize::new(0)),
+            sidecars: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
+    /// ベンチマーク用に、`OUTPUT_INFO`から出力情報を作る。
+    ///
+    /// # Safety
+    ///
+    /// `oip`は有効な`OUTPUT_INFO`を指し、返した値を使い終わるまで生きている必要があります。
+    #[doc(hidden)]
+    pub unsafe fn __from_raw(oip: *mut aviutl2_sys::output2::OUTPUT_INFO) -> Self {
+        Self::from_raw(oip)
+    }
+
     /// 動画のフレームを取得する。
     pub fn get_video_frame<F: FromRawVideoFrame>(&self, frame: i32) -> Option<F> {
         if let Some(video) = &self.video {
@@ -258,9 +356,68 @@ impl OutputInfo {
         Some(frame)
     }
 
+    /// 複数の動画フレームをまとめて取得する。
+    ///
+    /// 返される`Vec`は`indices`と同じ順番になります。
+    /// AviUtl2は任意の順番でのフレーム取得を受け付けますが、先読みはフレーム番号の昇順に行われるため、
+    /// 内部ではフレーム番号の昇順に並べ替えてから取得します。
+    ///
+    /// # Errors
+    ///
+    /// - 動画がない場合
+    /// - [`FromRawVideoFrame::check`]が失敗した場合
+    /// - `indices`に動画のフレーム数の範囲外のフレーム番号が含まれている場合
+    /// - [`FromRawVideoFrame::IS_BORROWED`]が`true`の型で2フレーム以上を取得しようとした場合
+    /// - 取得中に出力が中断された場合
+    pub fn get_video_frames<F: FromRawVideoFrame>(
+        &self,
+        indices: &[u32],
+    ) -> crate::common::AnyResult<Vec<F>> {
+        let video = self
+            .video
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No video is available"))?;
+        F::check(video).map_err(|e| anyhow::anyhow!(e))?;
+        if F::IS_BORROWED && indices.len() > 1 {
+            anyhow::bail!("Borrowed video frames cannot be fetched in a batch");
+        }
+        if let Some(&index) = indices.iter().find(|&&index| index >= video.num_frames) {
+            anyhow::bail!(
+                "Frame {index} is out of range (num_frames: {})",
+                video.num_frames
+            );
+        }
+
+        let mut order: Vec<usize> = (0..indices.len()).collect();
+        order.sort_by_key(|&i| indices[i]);
+        let mut frames: Vec<Option<F>> = std::iter::repeat_with(|| None)
+            .take(indices.len())
+            .collect();
+        for i in order {
+            if self.is_aborted() {
+                anyhow::bail!("Output was aborted");
+            }
+            let frame = unsafe { self.get_video_frame_unchecked::<F>(indices[i] as i32) }
+                .ok_or_else(|| anyhow::anyhow!("Failed to get frame {}", indices[i]))?;
+            frames[i] = Some(frame);
+        }
+        Ok(frames.into_iter().map(Option::unwrap).collect())
+    }
+
     /// 動画のフレームをイテレータとして取得する。
     pub fn get_video_frames_iter<F: FromRawVideoFrame>(&self) -> VideoFramesIterator<'_, F> {
-        VideoFramesIterator::new(self)
+        VideoFramesIterator::new(self, 0)
+    }
+
+    /// 指定したフレームから動画のフレームをイテレータとして取得する。
+    ///
+    /// 中断された出力を途中から再開する場合などに使用します。
+    /// `start_frame`が動画のフレーム数以上の場合、イテレータは何も返しません。
+    pub fn get_video_frames_iter_from<F: FromRawVideoFrame>(
+        &self,
+        start_frame: u32,
+    ) -> VideoFramesIterator<'_, F> {
+        VideoFramesIterator::new(self, start_frame)
     }
 
     /// 指定した区間の音声サンプルとチャンネル数を取得する。
@@ -301,6 +458,10 @@ impl OutputInfo {
 
     /// モノラルの音声サンプルをイテレータとして取得する。
     ///
+    /// `length`サンプルずつ取得します（最後のチャンクは残りのサンプル数になります）。
+    /// AviUtl2が実際に読み込んだサンプル数は確認しないため、
+    /// チャンクの長さを保証する必要がある場合は[`Self::audio_samples_exact_iter`]を使ってください。
+    ///
     /// # Arguments
     /// - `length`: 一回のイテレーションで取得するサンプル数。
     pub fn get_mono_audio_samples_iter<F: FromRawAudioSamples>(
@@ -332,6 +493,10 @@ impl OutputInfo {
 
     /// ステレオの音声サンプルをイテレータとして取得する。
     ///
+    /// `length`サンプルずつ取得します（最後のチャンクは残りのサンプル数になります）。
+    /// AviUtl2が実際に読み込んだサンプル数は確認しないため、
+    /// チャンクの長さを保証する必要がある場合は[`Self::audio_samples_exact_iter`]を使ってください。
+    ///
     /// # Arguments
     /// - `length`: 一回のイテレーションで取得するサンプル数。
     pub fn get_stereo_audio_samples_iter<F: FromRawAudioSamples>(
@@ -341,6 +506,45 @@ impl OutputInfo {
         StereoAudioSamplesIterator::new(self, length)
     }
 
+    /// 音声サンプルを、最後以外は必ず`chunk`サンプルずつになるように区切ったイテレータとして取得する。
+    ///
+    /// アイテムは`(開始サンプル位置, サンプル)`の組で、サンプルはチャンネルごとにインターリーブされています。
+    /// AviUtl2から返されたサンプル数が要求より少なかった場合は、`chunk`サンプルになるまで続けて取得します。
+    /// 最後のチャンクは`chunk`サンプル以下になります。
+    ///
+    /// # Arguments
+    /// - `chunk`: 1チャンクあたりのサンプル数（チャンネルあたり）。
+    ///
+    /// # Panics
+    ///
+    /// `chunk`が0の場合にパニックします。
+    pub fn audio_samples_exact_iter<F: FromRawAudioSamples>(
+        &self,
+        chunk: usize,
+    ) -> ExactAudioSamplesIterator<'_, F> {
+        ExactAudioSamplesIterator::new(self, chunk)
+    }
+
+    /// 指定した区間の音声サンプルと、実際に読み込まれたサンプル数を取得する。
+    fn read_audio_samples<F: FromRawAudioSamples>(
+        &self,
+        start: i32,
+        length: i32,
+    ) -> Option<(Vec<F>, i32)> {
+        let audio = self.audio.as_ref()?;
+        let audio_ptr = unsafe { self.internal.as_mut().and_then(|oip| oip.func_get_audio) }?;
+        let mut readed = 0;
+        let audio_data_ptr = audio_ptr(start, length, &mut readed, F::FORMAT) as *mut u8;
+        if audio_data_ptr.is_null() {
+            return None;
+        }
+        let readed = readed.clamp(0, length);
+
+        let samples = unsafe { F::from_raw(readed, audio.num_channels, audio_data_ptr) };
+
+        Some((samples, readed))
+    }
+
     /// 出力が中断されたかどうかを確認する。
     pub fn is_aborted(&self) -> bool {
         let is_abort_func = unsafe { self.internal.as_mut().and_then(|oip| oip.func_is_abort) };
@@ -360,6 +564,10 @@ impl OutputInfo {
 
     /// データ取得のバッファ数（フレーム数）を設定する。
     /// バッファ数の半分のデータを先読みリクエストするようになります。
+    ///
+    /// # See Also
+    ///
+    /// [`Self::buffering`]
     pub fn set_buffer_size(&self, video_size: i32, audio_size: i32) {
         if let Some(func) = unsafe {
             self.internal
@@ -371,6 +579,206 @@ impl OutputInfo {
     }
 }
 
+impl OutputInfo {
+    /// データ取得のバッファ数を設定するビルダーを返す。
+    ///
+    /// 指定しなかった値は[`DEFAULT_OUTPUT_BUFFER_SIZE`]になります。
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn f(info: &aviutl2::output::OutputInfo) {
+    /// info.buffering().video_frames(8).audio_samples(0).apply();
+    /// # }
+    /// ```
+    pub fn buffering(&self) -> OutputBuffering<'_> {
+        OutputBuffering {
+            output_info: self,
+            video_frames: DEFAULT_OUTPUT_BUFFER_SIZE,
+            audio_samples: DEFAULT_OUTPUT_BUFFER_SIZE,
+        }
+    }
+}
+
+impl OutputInfo {
+    /// 出力先のファイルとは別に書き出したファイル（サイドカーファイル）を登録する。
+    ///
+    /// 登録したファイルは、出力が中断されたり失敗した場合に削除されます。
+    /// 出力が完了した場合は、ログに出力先と一緒に記録されます。
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn f(info: &aviutl2::output::OutputInfo) -> std::io::Result<()> {
+    /// let chapters = info.sidecar_path("chapters.srt");
+    /// std::fs::write(&chapters, "")?;
+    /// info.register_sidecar(&chapters, aviutl2::output::SidecarKind::Subtitle);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_sidecar(&self, path: &std::path::Path, kind: SidecarKind) {
+        let mut sidecars = self.sidecars.lock().unwrap();
+        if sidecars.iter().any(|sidecar| sidecar.path == path) {
+            return;
+        }
+        sidecars.push(Sidecar {
+            path: path.to_path_buf(),
+            kind,
+        });
+    }
+
+    /// 登録されたサイドカーファイルの一覧を取得する。
+    pub fn sidecars(&self) -> Vec<Sidecar> {
+        self.sidecars.lock().unwrap().clone()
+    }
+
+    /// 出力先のファイル名の拡張子を`extension`に置き換えたパスを返す。
+    ///
+    /// `extension`に`chapters.srt`のようにドットを含めると、`video.chapters.srt`のようになります。
+    pub fn sidecar_path(&self, extension: &str) -> std::path::PathBuf {
+        self.path.with_extension(extension)
+    }
+}
+
+/// サイドカーファイルの種類。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SidecarKind {
+    /// 字幕やチャプター。
+    Subtitle,
+    /// サムネイル画像。
+    Thumbnail,
+    /// 出力に関する情報（マニフェストや統計など）。
+    Metadata,
+    /// その他のファイル。
+    Other,
+}
+
+impl std::fmt::Display for SidecarKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SidecarKind::Subtitle => "字幕",
+            SidecarKind::Thumbnail => "サムネイル",
+            SidecarKind::Metadata => "メタデータ",
+            SidecarKind::Other => "その他",
+        };
+        f.write_str(name)
+    }
+}
+
+/// [`OutputInfo::register_sidecar`]で登録されたファイル。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sidecar {
+    /// ファイルのパス。
+    pub path: std::path::PathBuf,
+    /// ファイルの種類。
+    pub kind: SidecarKind,
+}
+
+static LAST_OUTPUT_SIDECARS: Mutex<Vec<Sidecar>> = Mutex::new(Vec::new());
+static LAST_OUTPUT_ESTIMATE_INFO: Mutex<Option<OutputEstimateInfo>> = Mutex::new(None);
+
+/// 最後に完了した出力で登録されたサイドカーファイルの一覧を取得する。
+///
+/// [`OutputPlugin::config_text`]などで、前回の出力結果を表示するのに使えます。
+/// 出力が中断されたり失敗した場合は更新されません。
+pub fn last_output_sidecars() -> Vec<Sidecar> {
+    LAST_OUTPUT_SIDECARS.lock().unwrap().clone()
+}
+
+/// 最後に開始した出力の解像度・長さなどを取得する。
+///
+/// 設定ダイアログで[`OutputPlugin::estimate`]を呼び出すのに使えます。
+/// 出力が中断されたり失敗した場合も更新されます。まだ出力していない場合は`None`を返します。
+pub fn last_output_estimate_info() -> Option<OutputEstimateInfo> {
+    LAST_OUTPUT_ESTIMATE_INFO.lock().unwrap().clone()
+}
+
+/// 出力処理を呼び出し、中断・失敗した場合はサイドカーファイルを削除する。
+pub(crate) fn run_output(
+    info: OutputInfo,
+    output: impl FnOnce(OutputInfo) -> crate::common::AnyResult<()>,
+) -> crate::common::AnyResult<()> {
+    let internal = info.internal;
+    let path = info.path.clone();
+    let sidecars = info.sidecars.clone();
+    *LAST_OUTPUT_ESTIMATE_INFO.lock().unwrap() = Some(OutputEstimateInfo::from(&info));
+    let result = output(info);
+    let aborted = unsafe { internal.as_mut().and_then(|oip| oip.func_is_abort) }
+        .is_some_and(|is_abort| is_abort());
+    let sidecars = std::mem::take(&mut *sidecars.lock().unwrap());
+
+    if result.is_err() || aborted {
+        for sidecar in &sidecars {
+            match std::fs::remove_file(&sidecar.path) {
+                Ok(()) => {
+                    tracing::debug!("Removed sidecar file: {}", sidecar.path.display());
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to remove sidecar file {}: {}",
+                        sidecar.path.display(),
+                        e
+                    );
+                }
+            }
+        }
+        return result;
+    }
+
+    if sidecars.is_empty() {
+        tracing::info!("Output completed: {}", path.display());
+    } else {
+        let files = sidecars
+            .iter()
+            .map(|sidecar| format!("{} ({})", sidecar.path.display(), sidecar.kind))
+            .collect::<Vec<_>>()
+            .join(", ");
+        tracing::info!("Output completed: {} (+ {})", path.display(), files);
+    }
+    *LAST_OUTPUT_SIDECARS.lock().unwrap() = sidecars;
+    result
+}
+
+/// AviUtl2のデータ取得のバッファ数の標準値。
+pub const DEFAULT_OUTPUT_BUFFER_SIZE: u32 = 4;
+
+/// データ取得のバッファ数を設定するビルダー。
+///
+/// # See Also
+///
+/// [`OutputInfo::buffering`]
+#[derive(Debug, Clone, Copy)]
+#[must_use = "OutputBuffering::applyを呼び出すまで設定は反映されません"]
+pub struct OutputBuffering<'a> {
+    output_info: &'a OutputInfo,
+    video_frames: u32,
+    audio_samples: u32,
+}
+
+impl OutputBuffering<'_> {
+    /// 画像データのバッファ数（フレーム数）を設定する。
+    pub fn video_frames(mut self, frames: u32) -> Self {
+        self.video_frames = frames;
+        self
+    }
+
+    /// 音声データのバッファ数を設定する。
+    pub fn audio_samples(mut self, samples: u32) -> Self {
+        self.audio_samples = samples;
+        self
+    }
+
+    /// 設定を反映する。
+    pub fn apply(self) {
+        self.output_info.set_buffer_size(
+            i32::try_from(self.video_frames).unwrap_or(i32::MAX),
+            i32::try_from(self.audio_samples).unwrap_or(i32::MAX),
+        );
+    }
+}
+
 impl Drop for OutputInfo {
     fn drop(&mut self) {
         self.last_frame_id.store(usize::MAX, Ordering::SeqCst);
@@ -380,7 +788,8 @@ impl Drop for OutputInfo {
 /// 動画フレームのイテレータ。
 ///
 /// # See Also
-/// [`OutputInfo::get_video_frames_iter`]
+/// - [`OutputInfo::get_video_frames_iter`]
+/// - [`OutputInfo::get_video_frames_iter_from`]
 #[derive(Debug, Clone)]
 pub struct VideoFramesIterator<'a, F: FromRawVideoFrame> {
     output_info: &'a OutputInfo,
@@ -392,14 +801,16 @@ pub struct VideoFramesIterator<'a, F: FromRawVideoFrame> {
 }
 
 impl<'a, F: FromRawVideoFrame> VideoFramesIterator<'a, F> {
-    pub(crate) fn new(output_info: &'a OutputInfo) -> Self {
+    pub(crate) fn new(output_info: &'a OutputInfo, start_frame: u32) -> Self {
         let total_frames = output_info
             .video
             .as_ref()
             .map_or(0, |v| v.num_frames as i32);
         Self {
             output_info,
-            current_frame: 0,
+            current_frame: i32::try_from(start_frame)
+                .unwrap_or(i32::MAX)
+                .min(total_frames),
             total_frames,
             last_updated_time: std::time::Instant::now(),
             check_result: output_info
@@ -500,3 +911,332 @@ duplicate::duplicate! {
         }
     }
 }
+
+/// チャンクの長さが揃った音声サンプルのイテレータ。
+///
+/// # See Also
+/// [`OutputInfo::audio_samples_exact_iter`]
+#[derive(Debug, Clone)]
+pub struct ExactAudioSamplesIterator<'a, F: FromRawAudioSamples> {
+    output_info: &'a OutputInfo,
+    chunk: usize,
+    total_length: usize,
+    readed: usize,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<'a, F: FromRawAudioSamples> ExactAudioSamplesIterator<'a, F> {
+    pub(crate) fn new(output_info: &'a OutputInfo, chunk: usize) -> Self {
+        assert!(chunk > 0, "chunk must be greater than 0");
+        Self {
+            output_info,
+            chunk,
+            total_length: output_info
+                .audio
+                .as_ref()
+                .map_or(0, |a| a.num_samples as usize),
+            readed: 0,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, F: FromRawAudioSamples> Iterator for ExactAudioSamplesIterator<'a, F> {
+    type Item = (usize, Vec<F>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.readed >= self.total_length {
+            return None;
+        }
+        if self.output_info.is_aborted() {
+            return None;
+        }
+
+        let start = self.readed;
+        let length = self.chunk.min(self.total_length - start);
+        let mut samples = Vec::new();
+        let mut chunk_readed = 0;
+        while chunk_readed < length {
+            let (Ok(position), Ok(rest)) = (
+                i32::try_from(start + chunk_readed),
+                i32::try_from(length - chunk_readed),
+            ) else {
+                break;
+            };
+            let Some((part, readed)) = self.output_info.read_audio_samples::<F>(position, rest)
+            else {
+                break;
+            };
+            if readed == 0 {
+                break;
+            }
+            samples.extend(part);
+            chunk_readed += readed as usize;
+        }
+
+        if chunk_readed < length {
+            // これ以上読み込めないので、このチャンクを最後にする
+            self.readed = self.total_length;
+        } else {
+            self.readed += chunk_readed;
+        }
+        if chunk_readed == 0 {
+            return None;
+        }
+        Some((start, samples))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::video_frame::{BorrowedRawBgrVideoFrame, RawBgrVideoFrame, RgbVideoFrame};
+    use std::cell::RefCell;
+
+    use crate::output::test_host::{self, fetched_frame_count, fetched_frames, mock_is_abort};
+
+    thread_local! {
+        static BUFFER_SIZE: RefCell<Option<(i32, i32)>> = const { RefCell::new(None) };
+        static AUDIO_MAX_READ: RefCell<i32> = const { RefCell::new(i32::MAX) };
+        static AUDIO_BUFFER: RefCell<Vec<f32>> = const { RefCell::new(Vec::new()) };
+        static ABORT_AFTER: RefCell<Option<usize>> = const { RefCell::new(None) };
+    }
+
+    /// `n`サンプル目の左チャンネルを`n`、右チャンネルを`-n`として返す。
+    /// 1回の呼び出しでは`AUDIO_MAX_READ`サンプルまでしか返さない。
+    extern "C" fn mock_get_audio(
+        start: i32,
+        length: i32,
+        readed: *mut i32,
+        _format: u32,
+    ) -> *mut std::ffi::c_void {
+        let length = length.min(AUDIO_MAX_READ.with_borrow(|max| *max));
+        unsafe { *readed = length };
+        AUDIO_BUFFER.with_borrow_mut(|buffer| {
+            buffer.clear();
+            buffer.extend((start..start + length).flat_map(|n| [n as f32, -n as f32]));
+            buffer.as_mut_ptr() as *mut std::ffi::c_void
+        })
+    }
+
+    /// `ABORT_AFTER`フレーム取得した後に中断されたことにする。
+    extern "C" fn mock_is_abort_after() -> bool {
+        let fetched = fetched_frame_count();
+        ABORT_AFTER.with_borrow(|after| after.is_some_and(|after| fetched >= after))
+    }
+
+    extern "C" fn mock_set_buffer_size(video_size: i32, audio_size: i32) {
+        BUFFER_SIZE.with_borrow_mut(|size| *size = Some((video_size, audio_size)));
+    }
+
+    fn mock_output_info(w: i32, h: i32, n: i32) -> (Box<OUTPUT_INFO>, OutputInfo) {
+        let mut raw = test_host::raw_video_output_info(w, h, n, test_host::fill_with_frame_number);
+        raw.func_set_buffer_size = Some(mock_set_buffer_size);
+        let info = OutputInfo::from_raw(&mut *raw);
+        (raw, info)
+    }
+
+    fn mock_audio_output_info(num_samples: i32, max_read: i32) -> (Box<OUTPUT_INFO>, OutputInfo) {
+        AUDIO_MAX_READ.with_borrow_mut(|max| *max = max_read);
+        let mut raw = Box::new(OUTPUT_INFO {
+            flag: OUTPUT_INFO::FLAG_AUDIO,
+            w: 0,
+            h: 0,
+            rate: 0,
+            scale: 0,
+            n: 0,
+            audio_rate: 44100,
+            audio_ch: 2,
+            audio_n: num_samples,
+            savefile: std::ptr::null(),
+            func_get_video: None,
+            func_get_audio: Some(mock_get_audio),
+            func_is_abort: Some(mock_is_abort),
+            func_rest_time_disp: None,
+            func_set_buffer_size: None,
+        });
+        let info = OutputInfo::from_raw(&mut *raw);
+        (raw, info)
+    }
+
+    fn expected_samples(range: std::ops::Range<i32>) -> Vec<f32> {
+        range.flat_map(|n| [n as f32, -n as f32]).collect()
+    }
+
+    #[test]
+    fn test_audio_samples_exact_iter_last_chunk() {
+        let (_raw, info) = mock_audio_output_info(1000, i32::MAX);
+        let chunks: Vec<(usize, Vec<f32>)> = info.audio_samples_exact_iter(300).collect();
+        let lengths: Vec<(usize, usize)> = chunks
+            .iter()
+            .map(|(start, samples)| (*start, samples.len() / 2))
+            .collect();
+        assert_eq!(lengths, [(0, 300), (300, 300), (600, 300), (900, 100)]);
+        assert_eq!(chunks[3].1, expected_samples(900..1000));
+    }
+
+    #[test]
+    fn test_audio_samples_exact_iter_multiple_of_chunk() {
+        let (_raw, info) = mock_audio_output_info(600, i32::MAX);
+        let lengths: Vec<usize> = info
+            .audio_samples_exact_iter::<f32>(300)
+            .map(|(_, samples)| samples.len() / 2)
+            .collect();
+        assert_eq!(lengths, [300, 300]);
+    }
+
+    #[test]
+    fn test_audio_samples_exact_iter_fills_short_reads() {
+        let (_raw, info) = mock_audio_output_info(1000, 128);
+        let chunks: Vec<(usize, Vec<f32>)> = info.audio_samples_exact_iter(300).collect();
+        let starts: Vec<usize> = chunks.iter().map(|(start, _)| *start).collect();
+        assert_eq!(starts, [0, 300, 600, 900]);
+        for (start, samples) in &chunks {
+            let start = *start as i32;
+            let end = (start + 300).min(1000);
+            assert_eq!(*samples, expected_samples(start..end));
+        }
+    }
+
+    #[test]
+    fn test_audio_samples_exact_iter_without_audio() {
+        let (_raw, info) = mock_output_info(4, 2, 10);
+        assert_eq!(info.audio_samples_exact_iter::<f32>(300).count(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_audio_samples_exact_iter_zero_chunk() {
+        let (_raw, info) = mock_audio_output_info(1000, i32::MAX);
+        let _ = info.audio_samples_exact_iter::<f32>(0);
+    }
+
+    #[test]
+    fn test_get_video_frames_keeps_requested_order() {
+        let (_raw, info) = mock_output_info(4, 2, 10);
+        let frames = info
+            .get_video_frames::<RawBgrVideoFrame>(&[7, 2, 5, 2])
+            .unwrap();
+        let firsts: Vec<u8> = frames.iter().map(|frame| frame.data[0]).collect();
+        assert_eq!(firsts, [7, 2, 5, 2]);
+        assert_eq!(fetched_frames(), [2, 2, 5, 7]);
+    }
+
+    #[test]
+    fn test_get_video_frames_errors() {
+        let (_raw, info) = mock_output_info(4, 2, 10);
+        assert!(info.get_video_frames::<RawBgrVideoFrame>(&[3, 10]).is_err());
+        assert!(
+            info.get_video_frames::<BorrowedRawBgrVideoFrame>(&[1, 2])
+                .is_err()
+        );
+        assert!(
+            info.get_video_frames::<BorrowedRawBgrVideoFrame>(&[1])
+                .is_ok()
+        );
+        assert_eq!(fetched_frames(), [1]);
+    }
+
+    #[test]
+    fn test_buffering() {
+        let (_raw, info) = mock_output_info(4, 2, 10);
+        info.buffering().video_frames(8).audio_samples(0).apply();
+        assert_eq!(BUFFER_SIZE.with_borrow(|size| *size), Some((8, 0)));
+        info.buffering().apply();
+        assert_eq!(BUFFER_SIZE.with_borrow(|size| *size), Some((4, 4)));
+    }
+
+    fn mock_export(info: &OutputInfo, dir: &std::path::Path) -> crate::common::AnyResult<()> {
+        let chapters = dir.join("video.chapters.srt");
+        std::fs::write(&chapters, "")?;
+        info.register_sidecar(&chapters, SidecarKind::Subtitle);
+        for (i, _frame) in info.get_video_frames_iter::<RgbVideoFrame>() {
+            if i == 1 {
+                let thumbnail = dir.join("video.png");
+                std::fs::write(&thumbnail, "")?;
+                info.register_sidecar(&thumbnail, SidecarKind::Thumbnail);
+            }
+        }
+        Ok(())
+    }
+
+    fn sidecar_test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "aviutl2-rs-sidecar-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_sidecar_path() {
+        let (_raw, mut info) = mock_output_info(4, 2, 10);
+        info.path = std::path::PathBuf::from("out/video.mp4");
+        assert_eq!(
+            info.sidecar_path("chapters.srt"),
+            std::path::Path::new("out/video.chapters.srt")
+        );
+        assert_eq!(
+            info.sidecar_path("png"),
+            std::path::Path::new("out/video.png")
+        );
+    }
+
+    #[test]
+    fn test_run_output_removes_sidecars_on_abort() {
+        let dir = sidecar_test_dir("abort");
+        let (mut raw, _info) = mock_output_info(4, 2, 10);
+        raw.func_is_abort = Some(mock_is_abort_after);
+        ABORT_AFTER.with_borrow_mut(|after| *after = Some(3));
+        let info = OutputInfo::from_raw(&mut *raw);
+
+        let result = run_output(info, |info| mock_export(&info, &dir));
+        ABORT_AFTER.with_borrow_mut(|after| *after = None);
+
+        // 中断されても出力自体は成功扱い
+        assert!(result.is_ok());
+        assert!(fetched_frame_count() < 10);
+        assert!(!dir.join("video.chapters.srt").exists());
+        assert!(!dir.join("video.png").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_run_output_keeps_sidecars_on_success() {
+        let dir = sidecar_test_dir("success");
+        let (_raw, info) = mock_output_info(4, 2, 10);
+
+        run_output(info, |info| mock_export(&info, &dir)).unwrap();
+
+        assert!(dir.join("video.chapters.srt").exists());
+        assert!(dir.join("video.png").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_run_output_removes_sidecars_on_error() {
+        let dir = sidecar_test_dir("error");
+        let (_raw, info) = mock_output_info(4, 2, 10);
+
+        let result = run_output(info, |info| {
+            mock_export(&info, &dir)?;
+            anyhow::bail!("encoder failed")
+        });
+
+        assert!(result.is_err());
+        assert!(!dir.join("video.chapters.srt").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_estimate_info_duration() {
+        let (_raw, info) = mock_output_info(4, 2, 90);
+        let estimate_info = OutputEstimateInfo::from(&info);
+        assert_eq!(estimate_info.duration_secs(), 3.0);
+
+        let (_raw, info) = mock_audio_output_info(88200, 1024);
+        assert_eq!(OutputEstimateInfo::from(&info).duration_secs(), 2.0);
+    }
+}
@@ -79,13 +79,19 @@ pub unsafe fn initialize_plugin_c_unwind<T: OutputSingleton>(version: u32) -> bo
 }
 
 pub(crate) fn initialize_plugin<T: OutputSingleton>(version: u32) -> AnyResult<()> {
+    crate::common::ensure_minimum_aviutl2_version(version.into())?;
+    let info = crate::common::AviUtl2Info::new(version);
+    initialize_plugin_with::<T>(version, T::new(info)?)
+}
+
+/// 作成済みのインスタンスでプラグインを初期化する。
+pub(crate) fn initialize_plugin_with<T: OutputSingleton>(
+    version: u32,
+    instance: T,
+) -> AnyResult<()> {
     crate::common::ensure_minimum_aviutl2_version(version.into())?;
     let plugin_state = T::__get_singleton_state();
-    let info = crate::common::AviUtl2Info {
-        version: version.into(),
-    };
-    let internal = T::new(info)?;
-    let plugin = InternalOutputPluginState::new(internal);
+    let plugin = InternalOutputPluginState::new(instance);
     *plugin_state.write().unwrap() = Some(plugin);
 
     Ok(())
@@ -204,7 +210,7 @@ extern "C" fn func_output<T: OutputSingleton>(oip: *mut aviutl2_sys::output2::OU
     let plugin = &plugin_state.instance;
     let oip = unsafe { &mut *oip };
     let output_info = OutputInfo::from_raw(oip);
-    match plugin.output(output_info) {
+    match crate::output::run_output(output_info, |info| plugin.output(info)) {
         Ok(()) => true,
         Err(e) => {
             tracing::error!("Error during func_output: {}", e);
@@ -289,6 +295,10 @@ extern "C" fn func_get_config_text_unwind<T: OutputSingleton>() -> *const u16 {
     }
 }
 
+/// 直前にプロジェクトの読み込み・保存で渡されたプロジェクトファイルのパス。
+pub(crate) static LAST_PROJECT_PATH: std::sync::Mutex<Option<std::path::PathBuf>> =
+    std::sync::Mutex::new(None);
+
 extern "C" fn func_load_project_config<T: OutputSingleton>(
     project: *mut aviutl2_sys::plugin2::PROJECT_FILE,
 ) -> bool {
@@ -298,6 +308,7 @@ extern "C" fn func_load_project_config<T: OutputSingleton>(
     plugin_state.leak_manager.free_leaked_memory();
     let plugin = &plugin_state.instance;
     let mut project = unsafe { crate::generic::ProjectFile::from_raw(project) };
+    *LAST_PROJECT_PATH.lock().unwrap() = project.get_path();
     match plugin.load_project_config(&mut project) {
         Ok(()) => true,
         Err(e) => {
@@ -317,6 +328,7 @@ extern "C" fn func_save_project_config<T: OutputSingleton>(
     plugin_state.leak_manager.free_leaked_memory();
     let plugin = &plugin_state.instance;
     let mut project = unsafe { crate::generic::ProjectFile::from_raw(project) };
+    *LAST_PROJECT_PATH.lock().unwrap() = project.get_path();
     match plugin.save_project_config(&mut project) {
         Ok(()) => true,
         Err(e) => {
@@ -0,0 +1,241 @@
+use crate::output::OutputInfo;
+
+/// テンプレートの展開に使う日時。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TemplateDateTime {
+    pub year: u16,
+    pub month: u16,
+    pub day: u16,
+    pub hour: u16,
+    pub minute: u16,
+    pub second: u16,
+}
+
+impl TemplateDateTime {
+    fn now() -> Self {
+        let time = unsafe { windows::Win32::System::SystemInformation::GetLocalTime() };
+        Self {
+            year: time.wYear,
+            month: time.wMonth,
+            day: time.wDay,
+            hour: time.wHour,
+            minute: time.wMinute,
+            second: time.wSecond,
+        }
+    }
+}
+
+/// 出力ファイル名のテンプレートを展開する。
+///
+/// 以下のプレースホルダーを置き換えます。未知のプレースホルダーはそのまま残ります。
+///
+/// - `{project}`：プロジェクトファイル名（拡張子なし）。取得できない場合は`untitled`
+/// - `{date}`：現在の日付（`YYYY-MM-DD`）
+/// - `{time}`：現在の時刻（`HH-MM-SS`）
+/// - `{fps}`：フレームレート（整数でない場合は小数点以下3桁まで）
+/// - `{width}`・`{height}`：動画の幅・高さ
+/// - `{frames}`：動画のフレーム数
+///
+/// 動画の情報は、動画出力がない場合は空文字列になります。
+/// 展開後の文字列は[`sanitize_filename`]でファイル名として使える形に整えられます。
+/// シーン名などはSDKから取得できないため、プレースホルダーとして使えません。
+///
+/// # Example
+///
+/// ```rust
+/// # fn f(info: &aviutl2::output::OutputInfo) {
+/// let file_name = aviutl2::output::expand_filename_template("{project}_{date}.mp4", info);
+/// let path = info.path.with_file_name(file_name);
+/// # }
+/// ```
+pub fn expand_filename_template(template: &str, info: &OutputInfo) -> String {
+    expand_with(template, info, TemplateDateTime::now())
+}
+
+pub(crate) fn expand_with(template: &str, info: &OutputInfo, now: TemplateDateTime) -> String {
+    let video = info.video.as_ref();
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let placeholder = &rest[start + 1..start + len];
+        let value = match placeholder {
+            "project" => Some(
+                info.project_path
+                    .as_deref()
+                    .and_then(|path| path.file_stem())
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "untitled".to_string()),
+            ),
+            "date" => Some(format!("{:04}-{:02}-{:02}", now.year, now.month, now.day)),
+            "time" => Some(format!(
+                "{:02}-{:02}-{:02}",
+                now.hour, now.minute, now.second
+            )),
+            "fps" => Some(video.map_or_else(String::new, |video| {
+                if video.fps.is_integer() {
+                    video.fps.to_integer().to_string()
+                } else {
+                    format!(
+                        "{:.3}",
+                        *video.fps.numer() as f64 / *video.fps.denom() as f64
+                    )
+                }
+            })),
+            "width" => Some(video.map_or_else(String::new, |video| video.width.to_string())),
+            "height" => Some(video.map_or_else(String::new, |video| video.height.to_string())),
+            "frames" => Some(video.map_or_else(String::new, |video| video.num_frames.to_string())),
+            _ => None,
+        };
+        match value {
+            Some(value) => result.push_str(&sanitize_component(&value)),
+            None => result.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    result.push_str(rest);
+    sanitize_filename(&result)
+}
+
+/// ファイル名に使えない文字を置き換える。
+fn sanitize_component(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Windowsのファイル名として使えるように文字列を整える。
+///
+/// - `<>:"/\|?*`と制御文字を`_`に置き換えます。
+/// - 末尾のピリオドと空白を取り除きます。
+/// - `CON`や`NUL`、`COM1`などの予約されたデバイス名（拡張子付きを含む）には先頭に`_`を付けます。
+/// - 結果が空になった場合は`_`を返します。
+pub fn sanitize_filename(name: &str) -> String {
+    let sanitized = sanitize_component(name);
+    let mut sanitized = sanitized.trim_end_matches(['.', ' ']).to_string();
+    if sanitized.is_empty() {
+        return "_".to_string();
+    }
+    let stem = sanitized
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(' ')
+        .to_ascii_uppercase();
+    let is_reserved = matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || ((stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.len() == 4
+            && matches!(stem.as_bytes()[3], b'1'..=b'9'));
+    if is_reserved {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{OutputInfo, VideoOutputInfo};
+    use std::sync::{Arc, atomic::AtomicUsize};
+
+    fn info(project_path: Option<&str>, fps: (i32, i32)) -> OutputInfo {
+        OutputInfo {
+            video: Some(VideoOutputInfo {
+                width: 1920,
+                height: 1080,
+                fps: crate::common::Rational32::new(fps.0, fps.1),
+                num_frames: 300,
+                colorimetry: crate::pixel::Colorimetry::HOST,
+            }),
+            audio: None,
+            path: std::path::PathBuf::from(r"C:\out\video.mp4"),
+            project_path: project_path.map(std::path::PathBuf::from),
+            internal: std::ptr::null_mut(),
+            last_frame_id: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    const NOW: TemplateDateTime = TemplateDateTime {
+        year: 2025,
+        month: 3,
+        day: 7,
+        hour: 9,
+        minute: 5,
+        second: 1,
+    };
+
+    #[test]
+    fn test_expand_placeholders() {
+        let info = info(Some("/projects/my movie.aup2"), (30, 1));
+        assert_eq!(
+            expand_with(
+                "{project}_{date}_{time}_{width}x{height}_{fps}fps_{frames}.mp4",
+                &info,
+                NOW
+            ),
+            "my movie_2025-03-07_09-05-01_1920x1080_30fps_300.mp4"
+        );
+    }
+
+    #[test]
+    fn test_expand_fractional_fps_and_missing_project() {
+        let info = info(None, (30000, 1001));
+        assert_eq!(
+            expand_with("{project}_{fps}.mp4", &info, NOW),
+            "untitled_29.970.mp4"
+        );
+    }
+
+    #[test]
+    fn test_expand_keeps_unknown_placeholders() {
+        let info = info(None, (60, 1));
+        assert_eq!(
+            expand_with("{scene}_{width}_{unclosed", &info, NOW),
+            "{scene}_1920_{unclosed"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_colons_and_separators() {
+        assert_eq!(sanitize_filename("12:34:56"), "12_34_56");
+        assert_eq!(
+            sanitize_filename("a/b\\c|d?e*f\"g<h>i"),
+            "a_b_c_d_e_f_g_h_i"
+        );
+        assert_eq!(sanitize_filename("tab\there"), "tab_here");
+    }
+
+    #[test]
+    fn test_sanitize_reserved_device_names() {
+        assert_eq!(sanitize_filename("CON"), "_CON");
+        assert_eq!(sanitize_filename("nul.mp4"), "_nul.mp4");
+        assert_eq!(sanitize_filename("Com1.txt"), "_Com1.txt");
+        assert_eq!(sanitize_filename("LPT9"), "_LPT9");
+        assert_eq!(sanitize_filename("COM0"), "COM0");
+        assert_eq!(sanitize_filename("CONSOLE.mp4"), "CONSOLE.mp4");
+    }
+
+    #[test]
+    fn test_sanitize_trailing_dots_and_spaces() {
+        assert_eq!(sanitize_filename("video. . "), "video");
+        assert_eq!(sanitize_filename("..."), "_");
+        assert_eq!(sanitize_filename("a.b"), "a.b");
+    }
+
+    #[test]
+    fn test_expanded_value_cannot_inject_separators() {
+        let info = info(Some("C:/projects/a.b.aup2"), (24, 1));
+        assert_eq!(expand_with("{project}.mp4", &info, NOW), "a.b.mp4");
+    }
+}
@@ -0,0 +1,951 @@
+//! 書き出しを繰り返すときに、AviUtl2から取得した動画フレームをディスクに保存して使い回すキャッシュ。
+
+use crate::output::{FromRawVideoFrame, OutputInfo, VideoOutputInfo};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicUsize, Ordering},
+};
+use zerocopy::IntoBytes;
+
+/// [`OutputInfo::with_frame_cache`]のキャッシュの最大サイズ（4GiB）。
+pub const DEFAULT_FRAME_CACHE_MAX_BYTES: u64 = 4 << 30;
+
+const BLOB_MAGIC: &[u8; 8] = b"AU2FRAME";
+const BLOB_EXTENSION: &str = "lz4";
+/// マジック・展開後のサイズ・チェックサム。
+const BLOB_HEADER_SIZE: usize = 8 + 8 + 8;
+
+/// 動画フレームのキャッシュ。
+///
+/// キャッシュキーとフレーム番号ごとに、フレームデータをlz4で圧縮して`dir`以下に保存します。
+/// 各フレームにはチェックサム（xxh3）を付け、壊れたフレームは読み込み時に削除します。
+/// 合計サイズが上限を超えた場合は、最後に使われたのが古いフレームから削除します。
+///
+/// 通常は[`OutputInfo::with_frame_cache`]を使ってください。
+#[derive(Debug)]
+pub struct FrameCache {
+    dir: std::path::PathBuf,
+    max_bytes: u64,
+    index: Mutex<FrameCacheIndex>,
+}
+
+#[derive(Debug, Default)]
+struct FrameCacheIndex {
+    /// `dir`からの相対パスごとの、ファイルサイズと最後に使われた時刻。
+    entries: std::collections::HashMap<std::path::PathBuf, (u64, std::time::SystemTime)>,
+    total_bytes: u64,
+}
+
+impl FrameCache {
+    /// キャッシュを開く。`dir`が無い場合は作成します。
+    pub fn open(dir: impl Into<std::path::PathBuf>, max_bytes: u64) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        let mut index = FrameCacheIndex::default();
+        for namespace in std::fs::read_dir(&dir)? {
+            let namespace = namespace?;
+            if !namespace.file_type()?.is_dir() {
+                continue;
+            }
+            for blob in std::fs::read_dir(namespace.path())? {
+                let blob = blob?;
+                let path = blob.path();
+                if path.extension().is_none_or(|ext| ext != BLOB_EXTENSION) {
+                    continue;
+                }
+                let metadata = blob.metadata()?;
+                let relative = path
+                    .strip_prefix(&dir)
+                    .expect("read_dir returns paths under dir")
+                    .to_path_buf();
+                let last_used = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+                index.total_bytes += metadata.len();
+                index.entries.insert(relative, (metadata.len(), last_used));
+            }
+        }
+        let cache = Self {
+            dir,
+            max_bytes,
+            index: Mutex::new(index),
+        };
+        cache.evict(None);
+        Ok(cache)
+    }
+
+    /// キャッシュの保存先を返す。
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+
+    /// 保存しているフレームの合計サイズ（バイト）を返す。
+    pub fn total_bytes(&self) -> u64 {
+        self.lock().total_bytes
+    }
+
+    /// フレームを読み込む。
+    ///
+    /// 無い場合や、壊れている場合は`None`を返します。壊れていたフレームは削除されます。
+    pub fn get(&self, key: u64, frame: u32) -> Option<Vec<u8>> {
+        let bytes = self.read_blob(key, frame)?;
+        let raw_len = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        // lz4の圧縮率は最大でも255倍程度なので、それを超えるサイズは壊れている
+        if raw_len > (bytes.len() as u64).saturating_mul(256) {
+            self.remove_corrupted(&blob_path(key, frame));
+            return None;
+        }
+        let mut data = vec![0; usize::try_from(raw_len).ok()?];
+        self.decode_blob(key, frame, &bytes, &mut data)
+            .then_some(data)
+    }
+
+    /// フレームを`out`に読み込む。
+    ///
+    /// 無い場合や、壊れている場合、サイズが`out`と違う場合は`false`を返します。
+    pub fn get_into(&self, key: u64, frame: u32, out: &mut [u8]) -> bool {
+        let Some(bytes) = self.read_blob(key, frame) else {
+            return false;
+        };
+        self.decode_blob(key, frame, &bytes, out)
+    }
+
+    /// フレームを保存する。
+    pub fn insert(&self, key: u64, frame: u32, data: &[u8]) -> std::io::Result<()> {
+        let compressed = lz4_flex::block::compress(data);
+        let mut blob = Vec::with_capacity(BLOB_HEADER_SIZE + compressed.len());
+        blob.extend_from_slice(BLOB_MAGIC);
+        blob.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        blob.extend_from_slice(&xxhash_rust::xxh3::xxh3_64(data).to_le_bytes());
+        blob.extend_from_slice(&compressed);
+
+        let relative = blob_path(key, frame);
+        let path = self.dir.join(&relative);
+        std::fs::create_dir_all(path.parent().expect("blob path has a parent"))?;
+        // 書き込み中に中断されても壊れたファイルが残らないよう、一時ファイルに書いてから置き換える
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, &blob)?;
+        std::fs::rename(&temp, &path)?;
+
+        {
+            let mut index = self.lock();
+            let size = blob.len() as u64;
+            if let Some((old_size, _)) = index
+                .entries
+                .insert(relative.clone(), (size, std::time::SystemTime::now()))
+            {
+                index.total_bytes -= old_size;
+            }
+            index.total_bytes += size;
+        }
+        self.evict(Some(&relative));
+        Ok(())
+    }
+
+    /// 保存しているフレームを全て削除する。
+    pub fn clear(&self) -> std::io::Result<()> {
+        let mut index = self.lock();
+        clear_frame_cache(&self.dir)?;
+        std::fs::create_dir_all(&self.dir)?;
+        *index = FrameCacheIndex::default();
+        Ok(())
+    }
+
+    fn read_blob(&self, key: u64, frame: u32) -> Option<Vec<u8>> {
+        let relative = blob_path(key, frame);
+        if !self.lock().entries.contains_key(&relative) {
+            return None;
+        }
+        match std::fs::read(self.dir.join(&relative)) {
+            Ok(bytes) if bytes.len() >= BLOB_HEADER_SIZE && bytes.starts_with(BLOB_MAGIC) => {
+                Some(bytes)
+            }
+            Ok(_) => {
+                self.remove_corrupted(&relative);
+                None
+            }
+            Err(e) => {
+                tracing::debug!("Failed to read cached frame {}: {}", relative.display(), e);
+                self.remove(&relative);
+                None
+            }
+        }
+    }
+
+    fn decode_blob(&self, key: u64, frame: u32, bytes: &[u8], out: &mut [u8]) -> bool {
+        let relative = blob_path(key, frame);
+        let raw_len = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        if raw_len != out.len() as u64 {
+            return false;
+        }
+        let checksum = u64::from_le_bytes(bytes[16..24].try_into().unwrap());
+        let decoded = lz4_flex::block::decompress_into(&bytes[BLOB_HEADER_SIZE..], out);
+        if !matches!(decoded, Ok(len) if len == out.len())
+            || xxhash_rust::xxh3::xxh3_64(out) != checksum
+        {
+            self.remove_corrupted(&relative);
+            return false;
+        }
+        self.touch(&relative);
+        true
+    }
+
+    /// 最後に使われた時刻を更新する。時刻はファイルの更新日時として保存する。
+    fn touch(&self, relative: &std::path::Path) {
+        let now = std::time::SystemTime::now();
+        if let Some(entry) = self.lock().entries.get_mut(relative) {
+            entry.1 = now;
+        }
+        let touched = std::fs::File::options()
+            .write(true)
+            .open(self.dir.join(relative))
+            .and_then(|file| file.set_modified(now));
+        if let Err(e) = touched {
+            tracing::debug!(
+                "Failed to update cached frame {}: {}",
+                relative.display(),
+                e
+            );
+        }
+    }
+
+    fn remove_corrupted(&self, relative: &std::path::Path) {
+        tracing::warn!("Cached frame {} is corrupted, removing", relative.display());
+        self.remove(relative);
+    }
+
+    fn remove(&self, relative: &std::path::Path) {
+        let mut index = self.lock();
+        if let Some((size, _)) = index.entries.remove(relative) {
+            index.total_bytes -= size;
+        }
+        let _ = std::fs::remove_file(self.dir.join(relative));
+    }
+
+    /// 合計サイズが上限を超えている間、最後に使われたのが古いフレームから削除する。
+    fn evict(&self, keep: Option<&std::path::Path>) {
+        let mut index = self.lock();
+        if index.total_bytes <= self.max_bytes {
+            return;
+        }
+        let mut entries = index
+            .entries
+            .iter()
+            .filter(|(path, _)| Some(path.as_path()) != keep)
+            .map(|(path, &(size, last_used))| (last_used, size, path.clone()))
+            .collect::<Vec<_>>();
+        entries.sort();
+        for (_, size, path) in entries {
+            if index.total_bytes <= self.max_bytes {
+                break;
+            }
+            if let Err(e) = std::fs::remove_file(self.dir.join(&path)) {
+                tracing::debug!("Failed to evict cached frame {}: {}", path.display(), e);
+            }
+            index.entries.remove(&path);
+            index.total_bytes -= size;
+            if let Some(parent) = path.parent() {
+                // 空になったキーのディレクトリを消す。空でない場合は失敗するので無視する
+                let _ = std::fs::remove_dir(self.dir.join(parent));
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FrameCacheIndex> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// `dir`に保存したフレームのキャッシュを全て削除する。
+///
+/// `dir`が無い場合は何もしません。
+pub fn clear_frame_cache(dir: &std::path::Path) -> std::io::Result<()> {
+    match std::fs::remove_dir_all(dir) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+fn blob_path(key: u64, frame: u32) -> std::path::PathBuf {
+    std::path::Path::new(&format!("{key:016x}")).join(format!("{frame:08}.{BLOB_EXTENSION}"))
+}
+
+/// フォーマットごとの1フレームのバイト数。キャッシュできないフォーマットの場合は`None`を返す。
+fn raw_frame_size(format: u32, video: &VideoOutputInfo) -> Option<usize> {
+    let bytes_per_pixel = match format {
+        aviutl2_sys::common::BI_RGB => 3,
+        aviutl2_sys::common::BI_YUY2 => 2,
+        aviutl2_sys::common::BI_YC48 => 6,
+        aviutl2_sys::common::BI_PA64 | aviutl2_sys::common::BI_HF64 => 8,
+        _ => return None,
+    };
+    (video.width as usize)
+        .checked_mul(video.height as usize)?
+        .checked_mul(bytes_per_pixel)
+}
+
+/// 編集中のプロジェクトの内容から計算した値。
+///
+/// [`OutputInfo::with_frame_cache`]のキャッシュキーに使います。
+/// 出力プラグインのAPIからは編集中のプロジェクトを参照できないため、
+/// 保存済みのプロジェクトファイルではなく、書き出しの直前に汎用プラグインの[`crate::generic::EditHandle`]から計算した値を渡してください。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProjectFingerprint(u64);
+
+/// 他のシーンを参照するオブジェクトのエフェクト名。
+#[cfg(feature = "generic")]
+const SCENE_EFFECT_NAMES: [&str; 2] = ["シーン", "シーン(音声)"];
+
+impl ProjectFingerprint {
+    /// 呼び出し側で計算した値から作成する。
+    ///
+    /// 値は、出力される映像に影響する全ての編集内容から計算する必要があります。
+    pub fn from_raw(value: u64) -> Self {
+        Self(value)
+    }
+
+    /// 計算した値を返す。
+    pub fn as_raw(self) -> u64 {
+        self.0
+    }
+
+    /// 現在のシーンの編集内容から計算する。
+    ///
+    /// 以下から計算します。
+    /// - シーンの解像度・フレームレート・サンプルレート・長さ
+    /// - 各レイヤーの表示状態
+    /// - 各オブジェクトの位置とエイリアス（全ての設定項目を含みます）
+    /// - メディアファイルを参照するオブジェクトの、ファイルのサイズと更新日時
+    ///
+    /// # Returns
+    ///
+    /// 他のシーンを参照するオブジェクトがある場合は、参照先の変更を検知できないため`None`を返します。
+    ///
+    /// # Note
+    ///
+    /// スクリプトやフォントなど、メディアファイル以外の外部ファイルの変更は検知できません。
+    #[cfg(feature = "generic")]
+    pub fn from_read_section(
+        section: &crate::generic::ReadSection,
+    ) -> crate::generic::EditSectionResult<Option<Self>> {
+        let scene = section.current_scene()?;
+        let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+        for value in [
+            scene.resolution.0 as u64,
+            scene.resolution.1 as u64,
+            *scene.fps.numer() as u64,
+            *scene.fps.denom() as u64,
+            scene.sample_rate as u64,
+            scene.length as u64,
+        ] {
+            hasher.update(&value.to_le_bytes());
+        }
+        for layer in 0..=section.layer_max()? {
+            hasher.update(&(layer as u64).to_le_bytes());
+            hasher.update(&[section.get_layer_enable(layer)? as u8]);
+            // `objects_in_layer`はエラーで止まるので、取りこぼさないよう直接検索する
+            let mut next_frame = 0;
+            while let Some(object) = section.find_object_after(layer, next_frame)? {
+                for effect_name in SCENE_EFFECT_NAMES {
+                    if section.count_object_effect(object, effect_name)? > 0 {
+                        return Ok(None);
+                    }
+                }
+                let layer_frame = section.get_object_layer_frame(object)?;
+                next_frame = layer_frame.end.saturating_add(1);
+                hasher.update(&(layer_frame.start as u64).to_le_bytes());
+                hasher.update(&(layer_frame.end as u64).to_le_bytes());
+                let alias = section.get_object_alias(object)?;
+                hasher.update(&(alias.len() as u64).to_le_bytes());
+                hasher.update(alias.as_bytes());
+                if let Some(path) = section.get_object_source_path(object)? {
+                    // ファイルを読み込めない場合も、読み込めないことをキーに含める
+                    let metadata = std::fs::metadata(&path).ok();
+                    let modified = metadata
+                        .as_ref()
+                        .and_then(|metadata| metadata.modified().ok())
+                        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+                        .map_or(0, |modified| modified.as_nanos() as u64);
+                    hasher.update(
+                        &metadata
+                            .map_or(u64::MAX, |metadata| metadata.len())
+                            .to_le_bytes(),
+                    );
+                    hasher.update(&modified.to_le_bytes());
+                }
+            }
+        }
+        Ok(Some(Self(hasher.digest())))
+    }
+}
+
+/// [`OutputInfo::with_frame_cache`]で渡される、キャッシュを使って動画フレームを取得する出力情報。
+///
+/// キャッシュキーは以下から計算します。
+/// - 編集中のプロジェクトの内容（[`ProjectFingerprint`]）
+/// - 動画の解像度・フレームレート・フレーム数
+/// - 最初のフレームの内容（フォーマットごとに、取得を始めるときにAviUtl2から取得します）
+#[derive(Debug)]
+pub struct CachedOutputInfo<'a> {
+    info: &'a OutputInfo,
+    cache: Option<FrameCache>,
+    /// プロジェクトの内容と動画の設定から計算したキー。
+    project_key: u64,
+    /// フォーマットごとのキャッシュキー。最初のフレームを取得できなかった場合は`None`。
+    format_keys: Mutex<std::collections::HashMap<u32, Option<u64>>>,
+    /// キャッシュから読み込んだフレームのバッファ。借用する型のフレームはここを指す。
+    /// `u16`などとして読めるよう、`u64`の配列として確保する。
+    buffer: Mutex<Vec<u64>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+/// [`CachedOutputInfo`]のキャッシュの使用状況。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameCacheStats {
+    /// キャッシュから読み込んだフレーム数。
+    pub hits: usize,
+    /// AviUtl2から取得したフレーム数。
+    pub misses: usize,
+}
+
+impl OutputInfo {
+    /// 動画フレームのキャッシュを使って出力する。
+    ///
+    /// `f`に渡される[`CachedOutputInfo`]から取得した動画フレームは`dir`に保存され、
+    /// 次回以降の出力で`project`が同じであれば、AviUtl2に問い合わせずにディスクから読み込みます。
+    /// `project`は書き出しの直前に計算してください。
+    /// 計算できなかった場合（`None`）は、キャッシュを使わずにAviUtl2から取得します。
+    /// 中断した出力も、取得済みのフレームまではキャッシュから読み込めます。
+    ///
+    /// キャッシュの合計サイズは[`DEFAULT_FRAME_CACHE_MAX_BYTES`]までです。
+    /// 変更するには[`Self::with_frame_cache_limit`]を使ってください。
+    ///
+    /// # Note
+    ///
+    /// 出力プラグインのAPIからは編集中のプロジェクトを参照できないため、
+    /// 通常の出力プラグインでは`project`を計算できず、キャッシュは使われません。
+    /// [`ProjectFingerprint::from_read_section`]で計算するには汎用プラグインの[`crate::generic::EditHandle`]が必要ですが、
+    /// 書き出し中にそこから計算した値を出力プラグインに渡す方法はまだ用意されていません。
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # fn f(
+    /// #     info: &aviutl2::output::OutputInfo,
+    /// #     dir: &std::path::Path,
+    /// #     project: Option<aviutl2::output::ProjectFingerprint>,
+    /// # ) {
+    /// use aviutl2::output::video_frame::RawYuy2VideoFrame;
+    ///
+    /// info.with_frame_cache(dir, project, |info| {
+    ///     for (frame, data) in info.get_video_frames_iter::<RawYuy2VideoFrame>() {
+    ///         // ...
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn with_frame_cache<R>(
+        &self,
+        dir: &std::path::Path,
+        project: Option<ProjectFingerprint>,
+        f: impl FnOnce(&CachedOutputInfo<'_>) -> R,
+    ) -> R {
+        self.with_frame_cache_limit(dir, project, DEFAULT_FRAME_CACHE_MAX_BYTES, f)
+    }
+
+    /// キャッシュの最大サイズを指定して、動画フレームのキャッシュを使って出力する。
+    ///
+    /// # See Also
+    ///
+    /// [`Self::with_frame_cache`]
+    pub fn with_frame_cache_limit<R>(
+        &self,
+        dir: &std::path::Path,
+        project: Option<ProjectFingerprint>,
+        max_bytes: u64,
+        f: impl FnOnce(&CachedOutputInfo<'_>) -> R,
+    ) -> R {
+        let cached = CachedOutputInfo::new(self, dir, project, max_bytes);
+        f(&cached)
+    }
+}
+
+impl<'a> CachedOutputInfo<'a> {
+    fn new(
+        info: &'a OutputInfo,
+        dir: &std::path::Path,
+        project: Option<ProjectFingerprint>,
+        max_bytes: u64,
+    ) -> Self {
+        let project_key = info.video.as_ref().and_then(|video| {
+            let project = project?;
+            let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+            hasher.update(&project.0.to_le_bytes());
+            for value in [
+                video.width,
+                video.height,
+                *video.fps.numer() as u32,
+                *video.fps.denom() as u32,
+                video.num_frames,
+            ] {
+                hasher.update(&value.to_le_bytes());
+            }
+            Some(hasher.digest())
+        });
+        if info.video.is_some() && project.is_none() {
+            tracing::info!("Project fingerprint is unavailable, frame cache is disabled");
+        }
+        let cache = project_key.and_then(|_| {
+            FrameCache::open(dir, max_bytes)
+                .map_err(|e| {
+                    tracing::warn!(
+                        "Failed to open frame cache {}, frame cache is disabled: {}",
+                        dir.display(),
+                        e
+                    );
+                })
+                .ok()
+        });
+        Self {
+            info,
+            cache,
+            project_key: project_key.unwrap_or_default(),
+            format_keys: Mutex::new(std::collections::HashMap::new()),
+            buffer: Mutex::new(Vec::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// 元の出力情報を返す。
+    pub fn info(&self) -> &OutputInfo {
+        self.info
+    }
+
+    /// キャッシュを使っているかどうかを返す。
+    pub fn is_enabled(&self) -> bool {
+        self.cache.is_some()
+    }
+
+    /// キャッシュの使用状況を返す。
+    pub fn stats(&self) -> FrameCacheStats {
+        FrameCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 動画のフレームを取得する。
+    ///
+    /// # See Also
+    ///
+    /// [`OutputInfo::get_video_frame`]
+    pub fn get_video_frame<F: FromRawVideoFrame>(&self, frame: i32) -> Option<F> {
+        let video = self.info.video.as_ref()?;
+        if F::check(video).is_err() || frame < 0 || frame >= video.num_frames as i32 {
+            return None;
+        }
+        unsafe { self.get_video_frame_unchecked::<F>(frame) }
+    }
+
+    /// 動画のフレームを取得する。
+    /// [`Self::get_video_frame`]と違い、[`FromRawVideoFrame::check`]や境界のチェックを行いません。
+    ///
+    /// # Safety
+    ///
+    /// [`OutputInfo::get_video_frame_unchecked`]と同じです。
+    pub unsafe fn get_video_frame_unchecked<F: FromRawVideoFrame>(&self, frame: i32) -> Option<F> {
+        let video = self.info.video.as_ref()?;
+        let (Some(cache), Some(size), Some(key)) = (
+            &self.cache,
+            raw_frame_size(F::FORMAT, video),
+            self.format_key(F::FORMAT),
+        ) else {
+            return unsafe { self.info.get_video_frame_unchecked::<F>(frame) };
+        };
+
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        buffer.resize(size.div_ceil(8), 0);
+        let bytes = &mut buffer.as_mut_bytes()[..size];
+        if cache.get_into(key, frame as u32, bytes) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            let frame_id = self.next_frame_id();
+            return Some(unsafe {
+                F::from_raw(
+                    video,
+                    bytes.as_ptr(),
+                    Arc::clone(&self.info.last_frame_id),
+                    frame_id,
+                )
+            });
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let data_ptr = self.fetch_host_frame(frame, F::FORMAT)?;
+        let data = unsafe { std::slice::from_raw_parts(data_ptr, size) };
+        if let Err(e) = cache.insert(key, frame as u32, data) {
+            tracing::warn!("Failed to write frame {} to cache: {}", frame, e);
+        }
+        let frame_id = self.next_frame_id();
+        Some(unsafe {
+            F::from_raw(
+                video,
+                data_ptr,
+                Arc::clone(&self.info.last_frame_id),
+                frame_id,
+            )
+        })
+    }
+
+    /// 動画のフレームをイテレータとして取得する。
+    pub fn get_video_frames_iter<F: FromRawVideoFrame>(&self) -> CachedVideoFramesIterator<'_, F> {
+        CachedVideoFramesIterator::new(self, 0)
+    }
+
+    /// 指定したフレームから動画のフレームをイテレータとして取得する。
+    pub fn get_video_frames_iter_from<F: FromRawVideoFrame>(
+        &self,
+        start_frame: u32,
+    ) -> CachedVideoFramesIterator<'_, F> {
+        CachedVideoFramesIterator::new(self, start_frame)
+    }
+
+    /// フォーマットごとのキャッシュキーを返す。
+    ///
+    /// 最初に呼ばれたときに、AviUtl2から最初のフレームを取得してキーに含める。
+    fn format_key(&self, format: u32) -> Option<u64> {
+        let mut format_keys = self.format_keys.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(key) = format_keys.get(&format) {
+            return *key;
+        }
+        let key = self.compute_format_key(format);
+        format_keys.insert(format, key);
+        key
+    }
+
+    fn compute_format_key(&self, format: u32) -> Option<u64> {
+        let cache = self.cache.as_ref()?;
+        let size = raw_frame_size(format, self.info.video.as_ref()?)?;
+        let data_ptr = self.fetch_host_frame(0, format)?;
+        let data = unsafe { std::slice::from_raw_parts(data_ptr, size) };
+        let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+        hasher.update(&self.project_key.to_le_bytes());
+        hasher.update(&format.to_le_bytes());
+        hasher.update(&xxhash_rust::xxh3::xxh3_64(data).to_le_bytes());
+        let key = hasher.digest();
+        // 取得したフレームは捨てずに保存しておく
+        if let Err(e) = cache.insert(key, 0, data) {
+            tracing::warn!("Failed to write frame 0 to cache: {}", e);
+        }
+        Some(key)
+    }
+
+    /// AviUtl2からフレームを取得する。以前に取得したフレームは無効になる。
+    fn fetch_host_frame(&self, frame: i32, format: u32) -> Option<*const u8> {
+        let get_video = unsafe {
+            self.info
+                .internal
+                .as_mut()
+                .and_then(|oip| oip.func_get_video)
+        }?;
+        self.next_frame_id();
+        let data_ptr = get_video(frame, format) as *const u8;
+        (!data_ptr.is_null()).then_some(data_ptr)
+    }
+
+    fn next_frame_id(&self) -> usize {
+        self.info.last_frame_id.fetch_add(1, Ordering::SeqCst) + 1
+    }
+}
+
+impl Drop for CachedOutputInfo<'_> {
+    fn drop(&mut self) {
+        // `buffer`を指している借用のフレームを無効にする
+        self.next_frame_id();
+    }
+}
+
+/// キャッシュを使う動画フレームのイテレータ。
+///
+/// # See Also
+/// - [`CachedOutputInfo::get_video_frames_iter`]
+/// - [`CachedOutputInfo::get_video_frames_iter_from`]
+#[derive(Debug)]
+pub struct CachedVideoFramesIterator<'a, F: FromRawVideoFrame> {
+    cached: &'a CachedOutputInfo<'a>,
+    current_frame: i32,
+    total_frames: i32,
+    last_updated_time: std::time::Instant,
+    check_result: bool,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<'a, F: FromRawVideoFrame> CachedVideoFramesIterator<'a, F> {
+    fn new(cached: &'a CachedOutputInfo<'a>, start_frame: u32) -> Self {
+        let video = cached.info.video.as_ref();
+        let total_frames = video.map_or(0, |v| v.num_frames as i32);
+        Self {
+            cached,
+            current_frame: i32::try_from(start_frame)
+                .unwrap_or(i32::MAX)
+                .min(total_frames),
+            total_frames,
+            last_updated_time: std::time::Instant::now(),
+            check_result: video.is_some_and(|v| F::check(v).is_ok()),
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<F: FromRawVideoFrame> Iterator for CachedVideoFramesIterator<'_, F> {
+    type Item = (i32, F);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.check_result
+            || self.current_frame >= self.total_frames
+            || self.cached.info.is_aborted()
+        {
+            return None;
+        }
+        let frame = unsafe { self.cached.get_video_frame_unchecked(self.current_frame) }?;
+        let current_frame = self.current_frame;
+        self.current_frame += 1;
+        if self.last_updated_time.elapsed().as_secs_f32() > 0.1 {
+            self.cached
+                .info
+                .update_display(current_frame, self.total_frames);
+            self.last_updated_time = std::time::Instant::now();
+        }
+        Some((current_frame, frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::test_host::{self, fetched_frames};
+    use crate::output::video_frame::{BorrowedRawBgrVideoFrame, RawBgrVideoFrame};
+    use aviutl2_sys::output2::OUTPUT_INFO;
+    use std::cell::RefCell;
+
+    const WIDTH: i32 = 4;
+    const HEIGHT: i32 = 2;
+
+    thread_local! {
+        /// 全フレームに足す値。プロジェクトの変更を表す。
+        static FRAME_OFFSET: RefCell<u8> = const { RefCell::new(0) };
+    }
+
+    fn frame_content(frame: i32, index: usize) -> u8 {
+        let offset = FRAME_OFFSET.with_borrow(|offset| *offset);
+        (frame as u8 * 16 + index as u8) ^ offset
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "aviutl2-rs-frame-cache-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn mock_output_info(n: i32) -> (Box<OUTPUT_INFO>, OutputInfo) {
+        test_host::video_output_info(WIDTH, HEIGHT, n, frame_content)
+    }
+
+    const PROJECT: ProjectFingerprint = ProjectFingerprint(1);
+
+    fn export(
+        info: &OutputInfo,
+        cache_dir: &std::path::Path,
+        project: ProjectFingerprint,
+    ) -> (Vec<Vec<u8>>, FrameCacheStats) {
+        info.with_frame_cache(cache_dir, Some(project), |cached| {
+            assert!(cached.is_enabled());
+            let frames = cached
+                .get_video_frames_iter::<RawBgrVideoFrame>()
+                .map(|(_, frame)| frame.data)
+                .collect();
+            (frames, cached.stats())
+        })
+    }
+
+    #[test]
+    fn test_second_export_reads_from_cache() {
+        let dir = temp_dir("hit");
+        let cache_dir = dir.join("cache");
+        let (_raw, info) = mock_output_info(5);
+        let expected = (0..5)
+            .map(|frame| unsafe { info.get_video_frame_unchecked::<RawBgrVideoFrame>(frame) })
+            .map(|frame| frame.unwrap().data)
+            .collect::<Vec<_>>();
+        fetched_frames();
+
+        let (frames, stats) = export(&info, &cache_dir, PROJECT);
+        assert_eq!(frames, expected);
+        // 最初のフレームはキーの計算で取得したものを使う
+        assert_eq!(fetched_frames(), [0, 1, 2, 3, 4]);
+        assert_eq!(stats, FrameCacheStats { hits: 1, misses: 4 });
+
+        let (frames, stats) = export(&info, &cache_dir, PROJECT);
+        assert_eq!(frames, expected);
+        assert_eq!(fetched_frames(), [0]);
+        assert_eq!(stats, FrameCacheStats { hits: 5, misses: 0 });
+    }
+
+    #[test]
+    fn test_changed_project_misses() {
+        let dir = temp_dir("miss");
+        let cache_dir = dir.join("cache");
+        let (_raw, info) = mock_output_info(3);
+        export(&info, &cache_dir, PROJECT);
+
+        // 編集内容が変わった
+        let edited = ProjectFingerprint::from_raw(2);
+        let (_, stats) = export(&info, &cache_dir, edited);
+        assert_eq!(stats, FrameCacheStats { hits: 1, misses: 2 });
+        let (_, stats) = export(&info, &cache_dir, PROJECT);
+        assert_eq!(stats, FrameCacheStats { hits: 3, misses: 0 });
+
+        // 編集内容が同じでも、最初のフレームが変わった場合は使わない
+        FRAME_OFFSET.with_borrow_mut(|offset| *offset = 0xff);
+        let (frames, stats) = export(&info, &cache_dir, PROJECT);
+        FRAME_OFFSET.with_borrow_mut(|offset| *offset = 0);
+        assert_eq!(stats, FrameCacheStats { hits: 1, misses: 2 });
+        assert_eq!(frames[2][0], 32 ^ 0xff);
+    }
+
+    #[test]
+    fn test_borrowed_frames_from_cache() {
+        let dir = temp_dir("borrowed");
+        let cache_dir = dir.join("cache");
+        let (_raw, info) = mock_output_info(3);
+        let (expected, _) = export(&info, &cache_dir, PROJECT);
+
+        let last = info.with_frame_cache(&cache_dir, Some(PROJECT), |cached| {
+            let mut last = None;
+            for (frame, data) in cached.get_video_frames_iter::<BorrowedRawBgrVideoFrame>() {
+                assert_eq!(data.as_slice(), expected[frame as usize]);
+                last = Some(data);
+            }
+            assert_eq!(cached.stats().misses, 0);
+            last.unwrap()
+        });
+        assert!(!last.is_valid());
+    }
+
+    #[test]
+    fn test_without_fingerprint_passes_through() {
+        let dir = temp_dir("no-project");
+        let (_raw, info) = mock_output_info(2);
+        info.with_frame_cache(&dir.join("cache"), None, |cached| {
+            assert!(!cached.is_enabled());
+            assert_eq!(
+                cached.get_video_frames_iter::<RawBgrVideoFrame>().count(),
+                2
+            );
+        });
+        assert_eq!(fetched_frames(), [0, 1]);
+        assert!(!dir.join("cache").exists());
+    }
+
+    #[test]
+    fn test_corrupted_blob_is_refetched() {
+        let dir = temp_dir("corrupted");
+        let cache_dir = dir.join("cache");
+        let (_raw, info) = mock_output_info(3);
+        let (expected, _) = export(&info, &cache_dir, PROJECT);
+
+        let blobs = std::fs::read_dir(
+            cache_dir
+                .read_dir()
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap()
+                .path(),
+        )
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+        let frame_1 = blobs
+            .iter()
+            .find(|path| path.file_stem().unwrap() == "00000001")
+            .unwrap();
+        let mut bytes = std::fs::read(frame_1).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xff;
+        std::fs::write(frame_1, bytes).unwrap();
+        let frame_2 = blobs
+            .iter()
+            .find(|path| path.file_stem().unwrap() == "00000002")
+            .unwrap();
+        std::fs::write(frame_2, b"broken").unwrap();
+        fetched_frames();
+
+        let (frames, stats) = export(&info, &cache_dir, PROJECT);
+        assert_eq!(frames, expected);
+        assert_eq!(fetched_frames(), [0, 1, 2]);
+        assert_eq!(stats, FrameCacheStats { hits: 1, misses: 2 });
+
+        // 取得し直したフレームは保存し直される
+        let (_, stats) = export(&info, &cache_dir, PROJECT);
+        assert_eq!(stats, FrameCacheStats { hits: 3, misses: 0 });
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let dir = temp_dir("evict");
+        let data = (0..4096).map(|i| (i * 7 % 251) as u8).collect::<Vec<_>>();
+        let blob_size = {
+            let cache = FrameCache::open(dir.join("probe"), u64::MAX).unwrap();
+            cache.insert(0, 0, &data).unwrap();
+            cache.total_bytes()
+        };
+
+        let cache = FrameCache::open(dir.join("cache"), blob_size * 3).unwrap();
+        for frame in 0..3 {
+            cache.insert(1, frame, &data).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        // フレーム0を使ったので、フレーム1が一番古くなる
+        assert_eq!(cache.get(1, 0).unwrap(), data);
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        cache.insert(2, 0, &data).unwrap();
+
+        assert_eq!(cache.total_bytes(), blob_size * 3);
+        assert!(cache.get(1, 1).is_none());
+        assert!(cache.get(1, 0).is_some());
+        assert!(cache.get(1, 2).is_some());
+        assert!(cache.get(2, 0).is_some());
+
+        // 開き直しても、使われた時刻は引き継がれる
+        for (key, frame) in [(1, 0), (2, 0)] {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            cache.get(key, frame).unwrap();
+        }
+        drop(cache);
+        let cache = FrameCache::open(dir.join("cache"), blob_size * 2).unwrap();
+        assert_eq!(cache.total_bytes(), blob_size * 2);
+        assert!(cache.get(1, 2).is_none());
+        assert!(cache.get(2, 0).is_some());
+    }
+
+    #[test]
+    fn test_clear() {
+        let dir = temp_dir("clear");
+        let cache = FrameCache::open(dir.join("cache"), u64::MAX).unwrap();
+        cache.insert(1, 0, b"frame").unwrap();
+        assert_eq!(cache.get(1, 0).unwrap(), b"frame");
+        cache.clear().unwrap();
+        assert_eq!(cache.total_bytes(), 0);
+        assert!(cache.get(1, 0).is_none());
+        cache.insert(1, 0, b"frame").unwrap();
+        assert!(cache.get(1, 0).is_some());
+
+        clear_frame_cache(&dir.join("cache")).unwrap();
+        assert!(!dir.join("cache").exists());
+        clear_frame_cache(&dir.join("cache")).unwrap();
+    }
+}
@@ -9,10 +9,18 @@
 //! サンプルは<https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/image-rs-output>を参照してください。
 
 mod binding;
+mod filename_template;
+#[cfg(feature = "frame-cache")]
+mod frame_cache;
+#[cfg(test)]
+pub(crate) mod test_host;
 pub mod video_frame;
 
 pub use super::common::*;
 pub use binding::*;
+pub use filename_template::{expand_filename_template, sanitize_filename};
+#[cfg(feature = "frame-cache")]
+pub use frame_cache::*;
 
 #[doc(hidden)]
 #[path = "bridge.rs"]
@@ -0,0 +1,90 @@
+//! テスト用の、動画を返すだけのホストのモック。
+
+use super::OutputInfo;
+use aviutl2_sys::output2::OUTPUT_INFO;
+use std::cell::{Cell, RefCell};
+
+/// フレーム番号と、フレーム内のバイトの位置から、そのバイトの値を返す関数。
+pub(crate) type FrameContent = fn(frame: i32, index: usize) -> u8;
+
+thread_local! {
+    static FRAME_SIZE: Cell<(i32, i32)> = const { Cell::new((0, 0)) };
+    static FRAME_CONTENT: Cell<FrameContent> = const { Cell::new(fill_with_frame_number) };
+    static FRAME_BUFFER: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static FETCHED_FRAMES: RefCell<Vec<i32>> = const { RefCell::new(Vec::new()) };
+}
+
+/// 全てのバイトをフレーム番号で埋める。
+pub(crate) fn fill_with_frame_number(frame: i32, _index: usize) -> u8 {
+    frame as u8
+}
+
+extern "C" fn mock_get_video(frame: i32, _format: u32) -> *mut std::ffi::c_void {
+    FETCHED_FRAMES.with_borrow_mut(|fetched| fetched.push(frame));
+    let (w, h) = FRAME_SIZE.get();
+    let content = FRAME_CONTENT.get();
+    FRAME_BUFFER.with_borrow_mut(|buffer| {
+        buffer.clear();
+        buffer.extend((0..(w * h * 3) as usize).map(|i| content(frame, i)));
+        buffer.as_mut_ptr() as *mut std::ffi::c_void
+    })
+}
+
+pub(crate) extern "C" fn mock_is_abort() -> bool {
+    false
+}
+
+/// `width`x`height`の動画を`num_frames`フレーム返す`OUTPUT_INFO`を作る。
+///
+/// フレームはBGRで、各バイトは`content`で決める。取得したフレームの記録もリセットします。
+pub(crate) fn raw_video_output_info(
+    width: i32,
+    height: i32,
+    num_frames: i32,
+    content: FrameContent,
+) -> Box<OUTPUT_INFO> {
+    FRAME_SIZE.set((width, height));
+    FRAME_CONTENT.set(content);
+    FETCHED_FRAMES.with_borrow_mut(|fetched| fetched.clear());
+    Box::new(OUTPUT_INFO {
+        flag: OUTPUT_INFO::FLAG_VIDEO,
+        w: width,
+        h: height,
+        rate: 30,
+        scale: 1,
+        n: num_frames,
+        audio_rate: 0,
+        audio_ch: 0,
+        audio_n: 0,
+        savefile: std::ptr::null(),
+        func_get_video: Some(mock_get_video),
+        func_get_audio: None,
+        func_is_abort: Some(mock_is_abort),
+        func_rest_time_disp: None,
+        func_set_buffer_size: None,
+    })
+}
+
+/// [`raw_video_output_info`]で作った`OUTPUT_INFO`と、それを指す[`OutputInfo`]を返す。
+///
+/// `OutputInfo`は`OUTPUT_INFO`を指しているので、`Box`を先に捨てないでください。
+pub(crate) fn video_output_info(
+    width: i32,
+    height: i32,
+    num_frames: i32,
+    content: FrameContent,
+) -> (Box<OUTPUT_INFO>, OutputInfo) {
+    let mut raw = raw_video_output_info(width, height, num_frames, content);
+    let info = OutputInfo::from_raw(&mut *raw);
+    (raw, info)
+}
+
+/// 前回呼んでから取得されたフレームの番号を、取得した順に返す。
+pub(crate) fn fetched_frames() -> Vec<i32> {
+    FETCHED_FRAMES.with_borrow_mut(std::mem::take)
+}
+
+/// 取得されたフレームの数。
+pub(crate) fn fetched_frame_count() -> usize {
+    FETCHED_FRAMES.with_borrow(|fetched| fetched.len())
+}
@@ -20,6 +20,12 @@ pub trait FromRawVideoFrame {
     /// 4:2:2）を使用する場合は、出力情報の幅と高さが偶数であることを確認します。
     fn check(video: &crate::output::VideoOutputInfo) -> Result<(), String>;
 
+    /// フレームデータを所有せず、次のフレーム取得で無効になる型かどうか。
+    ///
+    /// `true`の場合、[`OutputInfo::get_video_frames`][`super::OutputInfo::get_video_frames`]で
+    /// 複数のフレームを一度に取得することはできません。
+    const IS_BORROWED: bool = false;
+
     /// 動画フレームを生のポインタから取得する。
     ///
     /// # Safety
@@ -212,6 +218,26 @@ impl FromRawVideoFrame for RgbVideoFrame {
         Self { data: frame_buffer }
     }
 }
+impl Yuy2VideoFrame {
+    /// RGBに変換する。
+    ///
+    /// `colorimetry`には通常[`VideoOutputInfo::colorimetry`]を指定します。
+    pub fn to_rgb(&self, colorimetry: crate::pixel::Colorimetry) -> Vec<(u8, u8, u8)> {
+        self.data
+            .iter()
+            .flat_map(|&yuy2| crate::pixel::yuy2_to_rgb(yuy2, colorimetry))
+            .collect()
+    }
+}
+impl Yc48VideoFrame {
+    /// RGBに変換する。
+    pub fn to_rgb(&self, matrix: crate::pixel::YuvMatrix) -> Vec<(u8, u8, u8)> {
+        self.data
+            .iter()
+            .map(|&yc48| crate::pixel::yc48_to_rgb(yc48, matrix))
+            .collect()
+    }
+}
 impl FromRawVideoFrame for Yuy2VideoFrame {
     const FORMAT: u32 = aviutl2_sys::common::BI_YUY2;
 
@@ -232,8 +258,8 @@ impl FromRawVideoFrame for Yuy2VideoFrame {
         let frame_data_writer = frame_buffer.spare_capacity_mut();
         for y in 0..video.height as usize {
             for x in 0..(video.width / 2) as usize {
-                let i = y * video.width as usize + x;
-                // Each pixel is represented by 4 bytes (YUY2)
+                let i = y * (video.width as usize / 2) + x;
+                // Each pair of pixels is represented by 4 bytes (YUY2)
                 let d_y1 = unsafe { *frame_data_ptr.add(i * 4) };
                 let d_u = unsafe { *frame_data_ptr.add(i * 4 + 1) };
                 let d_y2 = unsafe { *frame_data_ptr.add(i * 4 + 2) };
@@ -243,7 +269,7 @@ impl FromRawVideoFrame for Yuy2VideoFrame {
             }
         }
         unsafe {
-            frame_buffer.set_len((video.width * video.height * 2) as usize);
+            frame_buffer.set_len((video.width * video.height / 2) as usize);
         }
 
         Self { data: frame_buffer }
@@ -410,6 +436,7 @@ impl FromRawVideoFrame for Name {
 )]
 impl FromRawVideoFrame for Name {
     const FORMAT: u32 = FMT;
+    const IS_BORROWED: bool = true;
 
     fn check(video: &VideoOutputInfo) -> Result<(), String> {
         let _ = video;
@@ -458,6 +485,35 @@ impl FromRawVideoFrame for image::RgbImage {
     }
 }
 
+/// PA64形式で取得し、8bitに変換したRGBAの画像。
+///
+/// AviUtl2は8bitのRGBA形式を提供しないため、16bitの値を丸めて8bitにします。
+#[cfg(feature = "image")]
+impl FromRawVideoFrame for image::RgbaImage {
+    const FORMAT: u32 = aviutl2_sys::common::BI_PA64;
+
+    fn check(video: &VideoOutputInfo) -> Result<(), String> {
+        let _ = video;
+        Ok(())
+    }
+    unsafe fn from_raw(
+        video: &VideoOutputInfo,
+        frame_data_ptr: *const u8,
+        last_frame_id: Arc<AtomicUsize>,
+        frame_id: usize,
+    ) -> Self {
+        let _ = (last_frame_id, frame_id);
+        let frame_data_ptr = frame_data_ptr as *const u16;
+        let buffer = unsafe {
+            std::slice::from_raw_parts(frame_data_ptr, (video.width * video.height * 4) as usize)
+                .iter()
+                .map(|&v| ((v as u32 * 255 + 32767) / 65535) as u8)
+                .collect::<Vec<_>>()
+        };
+        image::ImageBuffer::from_raw(video.width, video.height, buffer).unwrap()
+    }
+}
+
 #[cfg(feature = "image")]
 impl FromRawVideoFrame for image::ImageBuffer<image::Rgba<u16>, Vec<u16>> {
     const FORMAT: u32 = aviutl2_sys::common::BI_PA64;
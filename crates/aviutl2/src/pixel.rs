@@ -0,0 +1,616 @@
+//! YUV・RGB・YC48の色の変換。
+//!
+//! 入力プラグインと出力プラグインで同じ計算を使うため、変換はすべてこのモジュールで行います。
+//! 計算は固定小数点数で行い、浮動小数点数で計算した値との差が1以内になるようにしています。
+//!
+//! RGBは常にフルレンジ（0〜255）として扱います。
+
+use crate::common::Yc48;
+
+/// YUVとRGBの変換に使う行列。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum YuvMatrix {
+    /// ITU-R BT.601（SD向け）。
+    #[default]
+    Bt601,
+    /// ITU-R BT.709（HD向け）。
+    Bt709,
+}
+
+/// YUVの値の範囲。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum YuvRange {
+    /// リミテッドレンジ。
+    /// Yは16〜235、Cb・Crは16〜240の範囲を使います。
+    #[default]
+    Limited,
+    /// フルレンジ。
+    /// Y・Cb・Crともに0〜255の範囲を使います。
+    Full,
+}
+
+/// YUV形式の画像データの色空間。
+///
+/// デフォルトは[`Colorimetry::HOST`]です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Colorimetry {
+    /// 変換行列。
+    pub matrix: YuvMatrix,
+    /// 値の範囲。
+    pub range: YuvRange,
+}
+
+impl Colorimetry {
+    /// BT.601・リミテッドレンジ。
+    pub const BT601_LIMITED: Self = Self {
+        matrix: YuvMatrix::Bt601,
+        range: YuvRange::Limited,
+    };
+    /// BT.601・フルレンジ。
+    pub const BT601_FULL: Self = Self {
+        matrix: YuvMatrix::Bt601,
+        range: YuvRange::Full,
+    };
+    /// BT.709・リミテッドレンジ。
+    pub const BT709_LIMITED: Self = Self {
+        matrix: YuvMatrix::Bt709,
+        range: YuvRange::Limited,
+    };
+    /// BT.709・フルレンジ。
+    pub const BT709_FULL: Self = Self {
+        matrix: YuvMatrix::Bt709,
+        range: YuvRange::Full,
+    };
+
+    /// AviUtl2がYUY2形式の画像データで使う色空間。
+    ///
+    /// AviUtlのYC48とYUY2の変換仕様に合わせ、BT.601・リミテッドレンジとして扱います。
+    ///
+    /// # See Also
+    /// <https://makiuchi-d.github.io/mksoft/doc/aviutlyc.html>
+    pub const HOST: Self = Self::BT601_LIMITED;
+}
+
+const SHIFT: u32 = 16;
+const ONE: i64 = 1 << SHIFT;
+const HALF: i64 = ONE / 2;
+
+/// 浮動小数点数を固定小数点数に変換する。
+const fn fixed(value: f64) -> i64 {
+    let scaled = value * ONE as f64;
+    if scaled < 0.0 {
+        (scaled - 0.5) as i64
+    } else {
+        (scaled + 0.5) as i64
+    }
+}
+
+/// 固定小数点数を丸めて整数にする。
+fn round(value: i64) -> i64 {
+    (value + HALF) >> SHIFT
+}
+
+fn round_u8(value: i64) -> u8 {
+    round(value).clamp(0, 255) as u8
+}
+
+fn round_i16(value: i64) -> i16 {
+    round(value).clamp(i16::MIN as i64, i16::MAX as i64) as i16
+}
+
+struct MatrixCoefficients {
+    kr: i64,
+    kg: i64,
+    kb: i64,
+    pb_from_b: i64,
+    pr_from_r: i64,
+    r_from_pr: i64,
+    g_from_pb: i64,
+    g_from_pr: i64,
+    b_from_pb: i64,
+}
+
+impl MatrixCoefficients {
+    const fn new(kr: f64, kb: f64) -> Self {
+        let kg = 1.0 - kr - kb;
+        Self {
+            kr: fixed(kr),
+            // 合計がちょうど1になるようにする
+            kg: ONE - fixed(kr) - fixed(kb),
+            kb: fixed(kb),
+            pb_from_b: fixed(0.5 / (1.0 - kb)),
+            pr_from_r: fixed(0.5 / (1.0 - kr)),
+            r_from_pr: fixed(2.0 * (1.0 - kr)),
+            g_from_pb: fixed(-2.0 * kb * (1.0 - kb) / kg),
+            g_from_pr: fixed(-2.0 * kr * (1.0 - kr) / kg),
+            b_from_pb: fixed(2.0 * (1.0 - kb)),
+        }
+    }
+}
+
+const BT601: MatrixCoefficients = MatrixCoefficients::new(0.299, 0.114);
+const BT709: MatrixCoefficients = MatrixCoefficients::new(0.2126, 0.0722);
+
+impl YuvMatrix {
+    fn coefficients(self) -> &'static MatrixCoefficients {
+        match self {
+            YuvMatrix::Bt601 => &BT601,
+            YuvMatrix::Bt709 => &BT709,
+        }
+    }
+}
+
+struct RangeCoefficients {
+    y_offset: i64,
+    y_scale: i64,
+    y_inverse: i64,
+    c_scale: i64,
+    c_inverse: i64,
+}
+
+const LIMITED: RangeCoefficients = RangeCoefficients {
+    y_offset: 16,
+    y_scale: fixed(219.0 / 255.0),
+    y_inverse: fixed(255.0 / 219.0),
+    c_scale: fixed(224.0 / 255.0),
+    c_inverse: fixed(255.0 / 224.0),
+};
+const FULL: RangeCoefficients = RangeCoefficients {
+    y_offset: 0,
+    y_scale: ONE,
+    y_inverse: ONE,
+    c_scale: ONE,
+    c_inverse: ONE,
+};
+
+impl YuvRange {
+    fn coefficients(self) -> &'static RangeCoefficients {
+        match self {
+            YuvRange::Limited => &LIMITED,
+            YuvRange::Full => &FULL,
+        }
+    }
+}
+
+/// YC48の値と8bitの値の比（255 / 4096）。
+const YC48_TO_8BIT: i64 = fixed(255.0 / 4096.0);
+
+/// 行列と範囲に依存しない中間表現。
+///
+/// Yは0〜255、Pb・Prは-127.5〜127.5の範囲の固定小数点数です。
+#[derive(Debug, Clone, Copy)]
+struct YPbPr {
+    y: i64,
+    pb: i64,
+    pr: i64,
+}
+
+impl YPbPr {
+    fn from_rgb(rgb: (u8, u8, u8), matrix: YuvMatrix) -> Self {
+        let m = matrix.coefficients();
+        let (r, g, b) = (rgb.0 as i64, rgb.1 as i64, rgb.2 as i64);
+        let y = m.kr * r + m.kg * g + m.kb * b;
+        Self {
+            y,
+            pb: ((b * ONE - y) * m.pb_from_b) >> SHIFT,
+            pr: ((r * ONE - y) * m.pr_from_r) >> SHIFT,
+        }
+    }
+
+    fn to_rgb(self, matrix: YuvMatrix) -> (u8, u8, u8) {
+        let m = matrix.coefficients();
+        let r = self.y + ((self.pr * m.r_from_pr) >> SHIFT);
+        let g = self.y + ((self.pb * m.g_from_pb + self.pr * m.g_from_pr) >> SHIFT);
+        let b = self.y + ((self.pb * m.b_from_pb) >> SHIFT);
+        (round_u8(r), round_u8(g), round_u8(b))
+    }
+
+    fn from_yuv(yuv: (u8, u8, u8), range: YuvRange) -> Self {
+        let c = range.coefficients();
+        Self {
+            y: (yuv.0 as i64 - c.y_offset) * c.y_inverse,
+            pb: (yuv.1 as i64 - 128) * c.c_inverse,
+            pr: (yuv.2 as i64 - 128) * c.c_inverse,
+        }
+    }
+
+    fn to_yuv(self, range: YuvRange) -> (u8, u8, u8) {
+        let c = range.coefficients();
+        (
+            round_u8(((self.y * c.y_scale) >> SHIFT) + c.y_offset * ONE),
+            round_u8(((self.pb * c.c_scale) >> SHIFT) + 128 * ONE),
+            round_u8(((self.pr * c.c_scale) >> SHIFT) + 128 * ONE),
+        )
+    }
+
+    fn from_yc48(yc48: Yc48) -> Self {
+        Self {
+            y: yc48.y as i64 * YC48_TO_8BIT,
+            pb: yc48.cb as i64 * YC48_TO_8BIT,
+            pr: yc48.cr as i64 * YC48_TO_8BIT,
+        }
+    }
+
+    fn to_yc48(self) -> Yc48 {
+        let scale = |value: i64| round_i16(value * 4096 / 255);
+        Yc48 {
+            y: scale(self.y),
+            cb: scale(self.pb),
+            cr: scale(self.pr),
+        }
+    }
+
+    /// 2つのピクセルの色差を平均する。
+    fn average_chroma(self, other: Self) -> (Self, Self) {
+        let pb = (self.pb + other.pb) / 2;
+        let pr = (self.pr + other.pr) / 2;
+        (Self { pb, pr, ..self }, Self { pb, pr, ..other })
+    }
+}
+
+/// RGBをYUV（Y, Cb, Cr）に変換する。
+pub fn rgb_to_yuv(rgb: (u8, u8, u8), colorimetry: Colorimetry) -> (u8, u8, u8) {
+    YPbPr::from_rgb(rgb, colorimetry.matrix).to_yuv(colorimetry.range)
+}
+
+/// YUV（Y, Cb, Cr）をRGBに変換する。
+pub fn yuv_to_rgb(yuv: (u8, u8, u8), colorimetry: Colorimetry) -> (u8, u8, u8) {
+    YPbPr::from_yuv(yuv, colorimetry.range).to_rgb(colorimetry.matrix)
+}
+
+/// YUV（Y, Cb, Cr）を別の色空間に変換する。
+pub fn convert_yuv(yuv: (u8, u8, u8), from: Colorimetry, to: Colorimetry) -> (u8, u8, u8) {
+    if from == to {
+        return yuv;
+    }
+    let ypbpr = YPbPr::from_yuv(yuv, from.range);
+    if from.matrix == to.matrix {
+        return ypbpr.to_yuv(to.range);
+    }
+    convert_matrix(ypbpr, from.matrix, to.matrix).to_yuv(to.range)
+}
+
+/// RGBを経由して行列を変換する。途中でRGBの範囲に丸めないようにする。
+fn convert_matrix(ypbpr: YPbPr, from: YuvMatrix, to: YuvMatrix) -> YPbPr {
+    let f = from.coefficients();
+    let t = to.coefficients();
+    let r = ypbpr.y + ((ypbpr.pr * f.r_from_pr) >> SHIFT);
+    let g = ypbpr.y + ((ypbpr.pb * f.g_from_pb + ypbpr.pr * f.g_from_pr) >> SHIFT);
+    let b = ypbpr.y + ((ypbpr.pb * f.b_from_pb) >> SHIFT);
+    let y = (t.kr * r + t.kg * g + t.kb * b) >> SHIFT;
+    YPbPr {
+        y,
+        pb: ((b - y) * t.pb_from_b) >> SHIFT,
+        pr: ((r - y) * t.pr_from_r) >> SHIFT,
+    }
+}
+
+/// YUY2（Y0, Cb, Y1, Cr）の2ピクセルをRGBに変換する。
+pub fn yuy2_to_rgb(yuy2: (u8, u8, u8, u8), colorimetry: Colorimetry) -> [(u8, u8, u8); 2] {
+    let (y0, cb, y1, cr) = yuy2;
+    [
+        yuv_to_rgb((y0, cb, cr), colorimetry),
+        yuv_to_rgb((y1, cb, cr), colorimetry),
+    ]
+}
+
+/// RGBの2ピクセルをYUY2（Y0, Cb, Y1, Cr）に変換する。
+/// 色差は2ピクセルの平均になります。
+pub fn rgb_to_yuy2(rgb: [(u8, u8, u8); 2], colorimetry: Colorimetry) -> (u8, u8, u8, u8) {
+    let (first, second) = YPbPr::from_rgb(rgb[0], colorimetry.matrix)
+        .average_chroma(YPbPr::from_rgb(rgb[1], colorimetry.matrix));
+    let (y0, cb, cr) = first.to_yuv(colorimetry.range);
+    let (y1, _, _) = second.to_yuv(colorimetry.range);
+    (y0, cb, y1, cr)
+}
+
+/// YUY2（Y0, Cb, Y1, Cr）の2ピクセルを別の色空間に変換する。
+pub fn convert_yuy2(
+    yuy2: (u8, u8, u8, u8),
+    from: Colorimetry,
+    to: Colorimetry,
+) -> (u8, u8, u8, u8) {
+    if from == to {
+        return yuy2;
+    }
+    let (y0, cb, y1, cr) = yuy2;
+    let convert = |y| {
+        let ypbpr = YPbPr::from_yuv((y, cb, cr), from.range);
+        if from.matrix == to.matrix {
+            ypbpr
+        } else {
+            convert_matrix(ypbpr, from.matrix, to.matrix)
+        }
+    };
+    let (first, second) = convert(y0).average_chroma(convert(y1));
+    let (y0, cb, cr) = first.to_yuv(to.range);
+    let (y1, _, _) = second.to_yuv(to.range);
+    (y0, cb, y1, cr)
+}
+
+/// YUY2形式のバイト列を別の色空間に変換する。
+///
+/// # Panics
+///
+/// `buffer.len()`が4の倍数でない場合にパニックします。
+pub fn convert_yuy2_bytes(buffer: &mut [u8], from: Colorimetry, to: Colorimetry) {
+    assert!(buffer.len().is_multiple_of(4));
+    if from == to {
+        return;
+    }
+    for pixel in buffer.chunks_exact_mut(4) {
+        let (y0, cb, y1, cr) = convert_yuy2((pixel[0], pixel[1], pixel[2], pixel[3]), from, to);
+        pixel.copy_from_slice(&[y0, cb, y1, cr]);
+    }
+}
+
+/// YC48をYUV（Y, Cb, Cr）に変換する。
+pub fn yc48_to_yuv(yc48: Yc48, range: YuvRange) -> (u8, u8, u8) {
+    YPbPr::from_yc48(yc48).to_yuv(range)
+}
+
+/// YUV（Y, Cb, Cr）をYC48に変換する。
+pub fn yuv_to_yc48(yuv: (u8, u8, u8), range: YuvRange) -> Yc48 {
+    YPbPr::from_yuv(yuv, range).to_yc48()
+}
+
+/// YC48の2ピクセルをYUY2（Y0, Cb, Y1, Cr）に変換する。
+/// 色差は2ピクセルの平均になります。
+pub fn yc48_to_yuy2(yc48: [Yc48; 2], range: YuvRange) -> (u8, u8, u8, u8) {
+    let (first, second) = YPbPr::from_yc48(yc48[0]).average_chroma(YPbPr::from_yc48(yc48[1]));
+    let (y0, cb, cr) = first.to_yuv(range);
+    let (y1, _, _) = second.to_yuv(range);
+    (y0, cb, y1, cr)
+}
+
+/// YUY2（Y0, Cb, Y1, Cr）の2ピクセルをYC48に変換する。
+pub fn yuy2_to_yc48(yuy2: (u8, u8, u8, u8), range: YuvRange) -> [Yc48; 2] {
+    let (y0, cb, y1, cr) = yuy2;
+    [
+        yuv_to_yc48((y0, cb, cr), range),
+        yuv_to_yc48((y1, cb, cr), range),
+    ]
+}
+
+/// YC48をRGBに変換する。
+pub fn yc48_to_rgb(yc48: Yc48, matrix: YuvMatrix) -> (u8, u8, u8) {
+    YPbPr::from_yc48(yc48).to_rgb(matrix)
+}
+
+/// RGBをYC48に変換する。
+pub fn rgb_to_yc48(rgb: (u8, u8, u8), matrix: YuvMatrix) -> Yc48 {
+    YPbPr::from_rgb(rgb, matrix).to_yc48()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Colorimetry; 4] = [
+        Colorimetry::BT601_LIMITED,
+        Colorimetry::BT601_FULL,
+        Colorimetry::BT709_LIMITED,
+        Colorimetry::BT709_FULL,
+    ];
+
+    fn grid() -> impl Iterator<Item = u8> + Clone {
+        (0..=255u8).step_by(3).chain([255])
+    }
+
+    fn reference_params(colorimetry: Colorimetry) -> (f64, f64, f64, f64, f64) {
+        let (kr, kb) = match colorimetry.matrix {
+            YuvMatrix::Bt601 => (0.299, 0.114),
+            YuvMatrix::Bt709 => (0.2126, 0.0722),
+        };
+        let (y_offset, y_scale, c_scale) = match colorimetry.range {
+            YuvRange::Limited => (16.0, 219.0 / 255.0, 224.0 / 255.0),
+            YuvRange::Full => (0.0, 1.0, 1.0),
+        };
+        (kr, kb, y_offset, y_scale, c_scale)
+    }
+
+    fn reference_yuv_to_rgb(yuv: (u8, u8, u8), colorimetry: Colorimetry) -> [f64; 3] {
+        reference_yuv_to_rgb_unclamped(yuv, colorimetry).map(|v| v.clamp(0.0, 255.0))
+    }
+
+    fn reference_yuv_to_rgb_unclamped(yuv: (u8, u8, u8), colorimetry: Colorimetry) -> [f64; 3] {
+        let (kr, kb, y_offset, y_scale, c_scale) = reference_params(colorimetry);
+        let kg = 1.0 - kr - kb;
+        let y = (yuv.0 as f64 - y_offset) / y_scale;
+        let pb = (yuv.1 as f64 - 128.0) / c_scale;
+        let pr = (yuv.2 as f64 - 128.0) / c_scale;
+        let r = y + 2.0 * (1.0 - kr) * pr;
+        let b = y + 2.0 * (1.0 - kb) * pb;
+        let g = (y - kr * r - kb * b) / kg;
+        [r, g, b]
+    }
+
+    fn reference_rgb_to_yuv(rgb: [f64; 3], colorimetry: Colorimetry) -> [f64; 3] {
+        let (kr, kb, y_offset, y_scale, c_scale) = reference_params(colorimetry);
+        let [r, g, b] = rgb;
+        let y = kr * r + (1.0 - kr - kb) * g + kb * b;
+        let pb = (b - y) / (2.0 * (1.0 - kb));
+        let pr = (r - y) / (2.0 * (1.0 - kr));
+        [
+            y_offset + y * y_scale,
+            128.0 + pb * c_scale,
+            128.0 + pr * c_scale,
+        ]
+        .map(|v| v.clamp(0.0, 255.0))
+    }
+
+    fn assert_close(actual: (u8, u8, u8), expected: [f64; 3], context: impl std::fmt::Debug) {
+        for (a, e) in [actual.0, actual.1, actual.2].into_iter().zip(expected) {
+            assert!(
+                (a as f64 - e).abs() <= 1.0,
+                "{context:?}: expected {expected:?}, got {actual:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_yuv_to_rgb_matches_reference() {
+        for colorimetry in ALL {
+            for y in grid() {
+                for cb in grid() {
+                    for cr in grid() {
+                        assert_close(
+                            yuv_to_rgb((y, cb, cr), colorimetry),
+                            reference_yuv_to_rgb((y, cb, cr), colorimetry),
+                            (colorimetry, y, cb, cr),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rgb_to_yuv_matches_reference() {
+        for colorimetry in ALL {
+            for r in grid() {
+                for g in grid() {
+                    for b in grid() {
+                        assert_close(
+                            rgb_to_yuv((r, g, b), colorimetry),
+                            reference_rgb_to_yuv([r, g, b].map(f64::from), colorimetry),
+                            (colorimetry, r, g, b),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_values() {
+        assert_eq!(
+            rgb_to_yuv((0, 0, 0), Colorimetry::BT601_LIMITED),
+            (16, 128, 128)
+        );
+        assert_eq!(
+            rgb_to_yuv((255, 255, 255), Colorimetry::BT601_LIMITED),
+            (235, 128, 128)
+        );
+        assert_eq!(
+            rgb_to_yuv((255, 255, 255), Colorimetry::BT709_FULL),
+            (255, 128, 128)
+        );
+        assert_eq!(
+            yuv_to_rgb((235, 128, 128), Colorimetry::BT709_LIMITED),
+            (255, 255, 255)
+        );
+        assert_eq!(
+            yuv_to_rgb((16, 128, 128), Colorimetry::BT709_LIMITED),
+            (0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_convert_yuv_matches_reference() {
+        for from in ALL {
+            for to in ALL {
+                for y in grid().step_by(2) {
+                    for cb in grid().step_by(2) {
+                        for cr in grid().step_by(2) {
+                            let rgb = reference_yuv_to_rgb_unclamped((y, cb, cr), from);
+                            // RGBの範囲外になる色は変換先の範囲に収まらないことがあるため、比べない
+                            if rgb.iter().any(|v| !(0.0..=255.0).contains(v)) {
+                                continue;
+                            }
+                            assert_close(
+                                convert_yuv((y, cb, cr), from, to),
+                                reference_rgb_to_yuv(rgb, to),
+                                (from, to, y, cb, cr),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_convert_yuv_identity() {
+        for colorimetry in ALL {
+            for y in grid() {
+                for c in grid() {
+                    assert_eq!(convert_yuv((y, c, c), colorimetry, colorimetry), (y, c, c));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_yc48_round_trip_is_exact() {
+        // YC48は8bitより精度が高いため、リミテッドレンジの値はすべてそのまま戻る
+        for y in 16..=235u8 {
+            for c in 16..=240u8 {
+                let yc48 = yuv_to_yc48((y, c, 255 - c), YuvRange::Limited);
+                assert_eq!(
+                    yc48_to_yuv(yc48, YuvRange::Limited),
+                    (y, c, 255 - c),
+                    "{yc48:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_yc48_matches_reference() {
+        for y in 0..=255u8 {
+            for c in grid() {
+                let yc48 = yuv_to_yc48((y, c, c), YuvRange::Limited);
+                let expected_y = (y as f64 - 16.0) * 4096.0 / 219.0;
+                let expected_c = (c as f64 - 128.0) * 4096.0 / 224.0;
+                assert!((yc48.y as f64 - expected_y).abs() <= 1.0, "{y}: {yc48:?}");
+                assert!((yc48.cb as f64 - expected_c).abs() <= 1.0, "{c}: {yc48:?}");
+                assert_eq!(yc48.cb, yc48.cr);
+            }
+        }
+        assert_eq!(
+            rgb_to_yc48((255, 255, 255), YuvMatrix::Bt601),
+            Yc48 {
+                y: 4096,
+                cb: 0,
+                cr: 0
+            }
+        );
+        assert_eq!(
+            yc48_to_rgb(
+                Yc48 {
+                    y: 4096,
+                    cb: 0,
+                    cr: 0
+                },
+                YuvMatrix::Bt601
+            ),
+            (255, 255, 255)
+        );
+    }
+
+    #[test]
+    fn test_yuy2() {
+        let yuy2 = rgb_to_yuy2([(255, 0, 0), (0, 0, 255)], Colorimetry::HOST);
+        // 色差は2ピクセルで共通になり、Yだけが異なる
+        assert_eq!(
+            yuy2_to_rgb(yuy2, Colorimetry::HOST),
+            [
+                yuv_to_rgb((yuy2.0, yuy2.1, yuy2.3), Colorimetry::HOST),
+                yuv_to_rgb((yuy2.2, yuy2.1, yuy2.3), Colorimetry::HOST),
+            ]
+        );
+
+        let mut bytes = [yuy2.0, yuy2.1, yuy2.2, yuy2.3];
+        convert_yuy2_bytes(&mut bytes, Colorimetry::HOST, Colorimetry::HOST);
+        assert_eq!(bytes, [yuy2.0, yuy2.1, yuy2.2, yuy2.3]);
+        convert_yuy2_bytes(&mut bytes, Colorimetry::HOST, Colorimetry::BT709_FULL);
+        let converted = convert_yuy2(yuy2, Colorimetry::HOST, Colorimetry::BT709_FULL);
+        assert_eq!(bytes, [converted.0, converted.1, converted.2, converted.3]);
+
+        assert_eq!(
+            yc48_to_yuy2(yuy2_to_yc48(yuy2, YuvRange::Limited), YuvRange::Limited),
+            yuy2
+        );
+    }
+}
@@ -1,3 +1,8 @@
+pub mod encoding;
+mod file_watcher;
+pub mod notify;
+pub use file_watcher::{DEFAULT_FILE_WATCH_DEBOUNCE, FileWatcher};
+
 /// `Vec<T>`を2次元配列として捉え、上下に反転させる関数。
 ///
 /// # Panics
@@ -113,6 +118,139 @@ macro_rules! bitflag {
     }
 }
 
+/// 4チャンネル8bitの画像データをストレートαから乗算済みαに変換する関数。
+/// αチャンネルは最後のチャンネルである必要があります（RGBA・BGRAなど）。
+///
+/// # Panics
+///
+/// `data.len()` が4の倍数でない場合にパニックします。
+pub fn premultiply_alpha_bytes(data: &mut [u8]) {
+    assert!(data.len().is_multiple_of(4));
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        for color in &mut pixel[..3] {
+            *color = ((*color as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+}
+
+/// 4チャンネル8bitの画像データを乗算済みαからストレートαに変換する関数。
+/// αチャンネルは最後のチャンネルである必要があります（RGBA・BGRAなど）。
+///
+/// αが0のピクセルは色も0になります。
+///
+/// # Panics
+///
+/// `data.len()` が4の倍数でない場合にパニックします。
+pub fn unpremultiply_alpha_bytes(data: &mut [u8]) {
+    assert!(data.len().is_multiple_of(4));
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        for color in &mut pixel[..3] {
+            *color = if alpha == 0 {
+                0
+            } else {
+                ((*color as u32 * 255 + alpha / 2) / alpha).min(255) as u8
+            };
+        }
+    }
+}
+
+/// 4チャンネル16bitの画像データをストレートαから乗算済みαに変換する関数。
+/// αチャンネルは最後のチャンネルである必要があります。
+///
+/// # Panics
+///
+/// `data.len()` が4の倍数でない場合にパニックします。
+pub fn premultiply_alpha_u16(data: &mut [u16]) {
+    assert!(data.len().is_multiple_of(4));
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u64;
+        for color in &mut pixel[..3] {
+            *color = ((*color as u64 * alpha + 32767) / 65535) as u16;
+        }
+    }
+}
+
+/// 4チャンネル16bitの画像データを乗算済みαからストレートαに変換する関数。
+/// αチャンネルは最後のチャンネルである必要があります。
+///
+/// αが0のピクセルは色も0になります。
+///
+/// # Panics
+///
+/// `data.len()` が4の倍数でない場合にパニックします。
+pub fn unpremultiply_alpha_u16(data: &mut [u16]) {
+    assert!(data.len().is_multiple_of(4));
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u64;
+        for color in &mut pixel[..3] {
+            *color = if alpha == 0 {
+                0
+            } else {
+                ((*color as u64 * 65535 + alpha / 2) / alpha).min(65535) as u16
+            };
+        }
+    }
+}
+
+/// 4チャンネル16bit浮動小数点数の画像データをストレートαから乗算済みαに変換する関数。
+/// αチャンネルは最後のチャンネルである必要があります。
+///
+/// αは0.0から1.0の範囲に丸めて扱います。
+///
+/// # Panics
+///
+/// `data.len()` が4の倍数でない場合にパニックします。
+pub fn premultiply_alpha_f16(data: &mut [crate::common::f16]) {
+    assert!(data.len().is_multiple_of(4));
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = clamp_alpha_f32(pixel[3].to_f32());
+        for color in &mut pixel[..3] {
+            *color = crate::common::f16::from_f32(color.to_f32() * alpha);
+        }
+    }
+}
+
+/// 4チャンネル16bit浮動小数点数の画像データを乗算済みαからストレートαに変換する関数。
+/// αチャンネルは最後のチャンネルである必要があります。
+///
+/// αが0以下のピクセルは色も0になります。
+///
+/// # Panics
+///
+/// `data.len()` が4の倍数でない場合にパニックします。
+pub fn unpremultiply_alpha_f16(data: &mut [crate::common::f16]) {
+    assert!(data.len().is_multiple_of(4));
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = clamp_alpha_f32(pixel[3].to_f32());
+        for color in &mut pixel[..3] {
+            *color = if alpha == 0.0 {
+                crate::common::f16::ZERO
+            } else {
+                crate::common::f16::from_f32(color.to_f32() / alpha)
+            };
+        }
+    }
+}
+
+fn clamp_alpha_f32(alpha: f32) -> f32 {
+    if alpha.is_nan() {
+        0.0
+    } else {
+        alpha.clamp(0.0, 1.0)
+    }
+}
+
+/// フレームのバイト列からハッシュ値を計算する関数。
+///
+/// XXH3（64bit）を使います。
+/// 書き出したファイルを読み直して、元のフレームと一致するかを確認するのに使えます。
+/// ピクセルフォーマットが違うと同じ画像でも別のハッシュ値になるので、比較するときは同じ形式に揃えてください。
+pub fn frame_hash(frame_bytes: &[u8]) -> u64 {
+    xxhash_rust::xxh3::xxh3_64(frame_bytes)
+}
+
 pub(crate) fn catch_unwind_with_panic_info<F, R>(f: F) -> Result<R, String>
 where
     F: FnOnce() -> R + std::panic::UnwindSafe,
@@ -134,6 +272,13 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[test]
+    fn test_frame_hash() {
+        assert_eq!(frame_hash(b""), 0x2D06_8005_38D3_94C2);
+        assert_eq!(frame_hash(&[1, 2, 3, 4]), frame_hash(&[1, 2, 3, 4]));
+        assert_ne!(frame_hash(&[1, 2, 3, 4]), frame_hash(&[1, 2, 3, 5]));
+    }
+
     #[test]
     fn test_flip_vertical() {
         let mut data = vec![
@@ -211,4 +356,82 @@ mod tests {
         bgra_to_rgba_bytes(&mut data);
         assert_eq!(data, vec![255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255]);
     }
+
+    #[test]
+    fn test_premultiply_alpha_bytes() {
+        let mut data = vec![255, 128, 1, 128, 255, 255, 255, 0, 10, 20, 30, 255];
+        premultiply_alpha_bytes(&mut data);
+        assert_eq!(data, vec![128, 64, 1, 128, 0, 0, 0, 0, 10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn test_unpremultiply_alpha_bytes() {
+        let mut data = vec![128, 64, 1, 128, 10, 20, 30, 255];
+        unpremultiply_alpha_bytes(&mut data);
+        assert_eq!(data, vec![255, 128, 2, 128, 10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn test_unpremultiply_alpha_bytes_zero_alpha() {
+        let mut data = vec![12, 34, 56, 0];
+        unpremultiply_alpha_bytes(&mut data);
+        assert_eq!(data, vec![0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_unpremultiply_alpha_bytes_clamps_invalid_color() {
+        // 乗算済みαとしては不正な値（色がαより大きい）
+        let mut data = vec![200, 100, 50, 100];
+        unpremultiply_alpha_bytes(&mut data);
+        assert_eq!(data, vec![255, 255, 128, 100]);
+    }
+
+    #[test]
+    fn test_premultiply_alpha_u16() {
+        let mut data = vec![65535, 32768, 1, 32768, 65535, 32768, 12345, 0];
+        premultiply_alpha_u16(&mut data);
+        assert_eq!(data, vec![32768, 16384, 1, 32768, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_unpremultiply_alpha_u16() {
+        let mut data = vec![32768, 16384, 0, 32768, 1, 2, 3, 0, 60000, 1, 0, 30000];
+        unpremultiply_alpha_u16(&mut data);
+        assert_eq!(
+            data,
+            vec![65535, 32768, 0, 32768, 0, 0, 0, 0, 65535, 2, 0, 30000]
+        );
+    }
+
+    #[test]
+    fn test_premultiply_and_unpremultiply_alpha_f16() {
+        use crate::common::f16;
+
+        let mut data = [1.0, 0.5, 0.25, 0.5, 1.0, 1.0, 1.0, 0.0, 0.5, 0.5, 0.5, 2.0]
+            .map(f16::from_f32)
+            .to_vec();
+        premultiply_alpha_f16(&mut data);
+        assert_eq!(
+            data,
+            [
+                0.5, 0.25, 0.125, 0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.5, 0.5, 2.0
+            ]
+            .map(f16::from_f32)
+        );
+
+        unpremultiply_alpha_f16(&mut data);
+        assert_eq!(
+            data,
+            [1.0, 0.5, 0.25, 0.5, 0.0, 0.0, 0.0, 0.0, 0.5, 0.5, 0.5, 2.0].map(f16::from_f32)
+        );
+    }
+
+    #[test]
+    fn test_unpremultiply_alpha_f16_nan_alpha() {
+        use crate::common::f16;
+
+        let mut data = [0.5, 0.5, 0.5, f32::NAN].map(f16::from_f32).to_vec();
+        unpremultiply_alpha_f16(&mut data);
+        assert_eq!(data[..3], [f16::ZERO; 3]);
+    }
 }
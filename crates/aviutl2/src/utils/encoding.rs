@@ -0,0 +1,260 @@
+//! ホストとの間で文字列をやり取りするための変換関数。
+//!
+//! AviUtl2のAPIごとの文字コードは以下の通りです。
+//!
+//! | 用途 | 型 | 文字コード | 関数 |
+//! | --- | --- | --- | --- |
+//! | プラグイン名・情報・ファイルフィルタ・ファイルパス・メニュー名・ウィンドウ名 | `LPCWSTR` | UTF-16 | [`to_wide`] / [`from_wide`] |
+//! | 編集セクションの文字列（オブジェクト名・エイリアス・レイヤー名など） | `LPCSTR` | UTF-8 | [`to_host_utf8`] / [`from_host_utf8`] |
+//! | プロジェクトファイルに保存する文字列・キー | `LPCSTR` | UTF-8 | [`to_host_utf8`] / [`from_host_utf8`] |
+//! | スクリプトモジュールの引数・返り値・エラーメッセージ | `LPCSTR` | UTF-8 | [`to_host_utf8`] / [`from_host_utf8`] |
+//! | 設定（`config2.h`）のキー | `LPCSTR` | UTF-8 | [`to_host_utf8`] |
+//!
+//! 現在のSDKにはShift_JIS（CP932）で文字列を受け渡すAPIはありません。
+//! [`to_host_ansi`] / [`from_host_ansi`] は、AviUtl（1.x）向けのファイルや外部ツールなど、
+//! CP932を前提とするデータを扱うためのものです。
+
+use windows::Win32::Globalization::{
+    MB_ERR_INVALID_CHARS, MultiByteToWideChar, WC_NO_BEST_FIT_CHARS, WideCharToMultiByte,
+};
+
+/// ホストのANSIコードページ（Shift_JIS）。
+pub const HOST_ANSI_CODE_PAGE: u32 = 932;
+
+/// [`to_host_ansi`]でCP932に存在しない文字をどう扱うか。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Policy {
+    /// 存在しない文字を`?`に置き換える。
+    #[default]
+    Replace,
+    /// 存在しない文字があればエラーを返す。
+    Error,
+    /// Windowsの最適マッピングで似た文字に置き換える。
+    ///
+    /// 例えば`〜`（U+301C、波ダッシュ）は`～`（0x8160）に、`é`は`e`に置き換えられます。
+    /// 似た文字がない場合は`?`に置き換えます。
+    BestFit,
+}
+
+/// 文字列の変換に失敗した際のエラー。
+#[derive(thiserror::Error, Debug)]
+pub enum EncodingError {
+    /// CP932に存在しない文字が含まれていた。
+    #[error("Character {ch:?} (U+{:04X}) at byte {index} cannot be represented in CP932", *ch as u32)]
+    Unmappable {
+        /// 変換できなかった文字。
+        ch: char,
+        /// 文字列中のバイト位置。
+        index: usize,
+    },
+    /// Win32 APIの呼び出しに失敗した。
+    #[error("Failed to convert string: {0}")]
+    Win32(#[from] windows::core::Error),
+}
+
+/// 文字列をヌル終端されたUTF-16の列に変換する。
+///
+/// サロゲートペアはそのまま2要素に分割されます。
+pub fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// UTF-16の列を文字列に変換する。
+///
+/// 最初のヌル文字までを変換し、不正なサロゲートは`U+FFFD`に置き換えます。
+pub fn from_wide(wide: &[u16]) -> String {
+    let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+    String::from_utf16_lossy(&wide[..len])
+}
+
+/// 文字列をヌル終端されたUTF-8の文字列に変換する。
+///
+/// # Errors
+///
+/// `s`にヌル文字が含まれている場合はエラーを返します。
+pub fn to_host_utf8(s: impl AsRef<str>) -> Result<std::ffi::CString, std::ffi::NulError> {
+    std::ffi::CString::new(s.as_ref())
+}
+
+/// ホストから受け取ったUTF-8の文字列を変換する。
+///
+/// 不正なバイト列は`U+FFFD`に置き換えます。
+pub fn from_host_utf8(s: &std::ffi::CStr) -> String {
+    s.to_string_lossy().into_owned()
+}
+
+/// 文字列をCP932のバイト列に変換する。ヌル終端は含みません。
+///
+/// # Errors
+///
+/// `policy`が[`Policy::Error`]でCP932に存在しない文字が含まれている場合、
+/// またはWin32 APIの呼び出しに失敗した場合はエラーを返します。
+pub fn to_host_ansi(s: &str, policy: Policy) -> Result<Vec<u8>, EncodingError> {
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+    let flags = match policy {
+        Policy::BestFit => 0,
+        Policy::Replace | Policy::Error => WC_NO_BEST_FIT_CHARS,
+    };
+    let (bytes, used_default) = wide_to_ansi(&s.encode_utf16().collect::<Vec<_>>(), flags)?;
+    if !used_default || policy != Policy::Error {
+        return Ok(bytes);
+    }
+
+    // どの文字が変換できなかったかを調べるため、1文字ずつ変換し直す
+    let mut buf = [0; 2];
+    for (index, ch) in s.char_indices() {
+        let (_, used_default) = wide_to_ansi(ch.encode_utf16(&mut buf), flags)?;
+        if used_default {
+            return Err(EncodingError::Unmappable { ch, index });
+        }
+    }
+    Ok(bytes)
+}
+
+/// CP932のバイト列を文字列に変換する。
+///
+/// 最初のヌル文字までを変換し、不正なバイト列は`U+FFFD`などに置き換えます。
+pub fn from_host_ansi(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let bytes = &bytes[..len];
+    if bytes.is_empty() {
+        return String::new();
+    }
+    let flags = Default::default();
+    let wide_len = unsafe { MultiByteToWideChar(HOST_ANSI_CODE_PAGE, flags, bytes, None) };
+    let mut wide = vec![0; wide_len.max(0) as usize];
+    let written =
+        unsafe { MultiByteToWideChar(HOST_ANSI_CODE_PAGE, flags, bytes, Some(&mut wide)) };
+    wide.truncate(written.max(0) as usize);
+    String::from_utf16_lossy(&wide)
+}
+
+/// CP932のバイト列が正しいかどうかを確認してから文字列に変換する。
+///
+/// # Errors
+///
+/// 不正なバイト列が含まれている場合はエラーを返します。
+pub fn from_host_ansi_strict(bytes: &[u8]) -> Result<String, EncodingError> {
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let bytes = &bytes[..len];
+    if bytes.is_empty() {
+        return Ok(String::new());
+    }
+    let wide_len =
+        unsafe { MultiByteToWideChar(HOST_ANSI_CODE_PAGE, MB_ERR_INVALID_CHARS, bytes, None) };
+    if wide_len == 0 {
+        return Err(windows::core::Error::from_thread().into());
+    }
+    let mut wide = vec![0; wide_len as usize];
+    unsafe {
+        MultiByteToWideChar(
+            HOST_ANSI_CODE_PAGE,
+            MB_ERR_INVALID_CHARS,
+            bytes,
+            Some(&mut wide),
+        )
+    };
+    Ok(String::from_utf16_lossy(&wide))
+}
+
+fn wide_to_ansi(wide: &[u16], flags: u32) -> Result<(Vec<u8>, bool), EncodingError> {
+    let mut used_default = windows::core::BOOL(0);
+    let len = unsafe {
+        WideCharToMultiByte(
+            HOST_ANSI_CODE_PAGE,
+            flags,
+            wide,
+            None,
+            windows::core::PCSTR::null(),
+            None,
+        )
+    };
+    if len == 0 {
+        return Err(windows::core::Error::from_thread().into());
+    }
+    let mut bytes = vec![0; len as usize];
+    let written = unsafe {
+        WideCharToMultiByte(
+            HOST_ANSI_CODE_PAGE,
+            flags,
+            wide,
+            Some(&mut bytes),
+            windows::core::PCSTR::null(),
+            Some(&raw mut used_default),
+        )
+    };
+    if written == 0 {
+        return Err(windows::core::Error::from_thread().into());
+    }
+    bytes.truncate(written as usize);
+    Ok((bytes, used_default.as_bool()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fullwidth_tilde_round_trip() {
+        // U+FF5E（全角チルダ）はCP932の0x8160に対応する
+        let bytes = to_host_ansi("～", Policy::Error).unwrap();
+        assert_eq!(bytes, [0x81, 0x60]);
+        assert_eq!(from_host_ansi(&bytes), "～");
+        assert_eq!(from_host_ansi_strict(&bytes).unwrap(), "～");
+    }
+
+    #[test]
+    fn test_wave_dash_policies() {
+        // U+301C（波ダッシュ）はCP932に存在しない
+        let err = to_host_ansi("a〜", Policy::Error).unwrap_err();
+        assert!(matches!(
+            err,
+            EncodingError::Unmappable {
+                ch: '〜', index: 1
+            }
+        ));
+        assert_eq!(to_host_ansi("a〜", Policy::Replace).unwrap(), b"a?");
+        assert_eq!(
+            to_host_ansi("a〜", Policy::BestFit).unwrap(),
+            [b'a', 0x81, 0x60]
+        );
+    }
+
+    #[test]
+    fn test_emoji_is_unmappable() {
+        let err = to_host_ansi("あ😀", Policy::Error).unwrap_err();
+        assert!(matches!(
+            err,
+            EncodingError::Unmappable {
+                ch: '😀', index: 3
+            }
+        ));
+        // サロゲートペアは1文字として置き換えられる
+        assert_eq!(to_host_ansi("😀", Policy::Replace).unwrap(), b"?");
+        assert_eq!(to_host_ansi("あ", Policy::Error).unwrap(), [0x82, 0xA0]);
+    }
+
+    #[test]
+    fn test_from_host_ansi_stops_at_nul() {
+        assert_eq!(from_host_ansi(&[0x82, 0xA0, 0, 0x82, 0xA2]), "あ");
+        assert_eq!(from_host_ansi(&[]), "");
+        assert!(from_host_ansi_strict(&[0x82]).is_err());
+    }
+
+    #[test]
+    fn test_wide_surrogate_pairs() {
+        assert_eq!(to_wide("😀"), [0xD83D, 0xDE00, 0]);
+        assert_eq!(from_wide(&[0xD83D, 0xDE00, 0, 0x41]), "😀");
+        // 対になっていないサロゲートは置き換えられる
+        assert_eq!(from_wide(&[0xD83D, 0x41]), "\u{FFFD}A");
+    }
+
+    #[test]
+    fn test_host_utf8() {
+        let c = to_host_utf8("波ダッシュ〜").unwrap();
+        assert_eq!(from_host_utf8(&c), "波ダッシュ〜");
+        assert_eq!(to_host_utf8("a\0b").unwrap_err().nul_position(), 1);
+    }
+}
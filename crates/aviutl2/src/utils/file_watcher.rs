@@ -0,0 +1,539 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use windows::Win32::{
+    Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0},
+    Storage::FileSystem::{
+        CreateFileW, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OVERLAPPED, FILE_LIST_DIRECTORY,
+        FILE_NOTIFY_CHANGE_FILE_NAME, FILE_NOTIFY_CHANGE_LAST_WRITE, FILE_NOTIFY_CHANGE_SIZE,
+        FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING, ReadDirectoryChangesW,
+    },
+    System::{
+        IO::{CancelIoEx, GetOverlappedResult, OVERLAPPED},
+        Threading::{CreateEventW, INFINITE, ResetEvent, SetEvent, WaitForMultipleObjects},
+    },
+};
+
+/// 変更を通知するまでの待ち時間のデフォルト値。
+pub const DEFAULT_FILE_WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// ファイルの変更を監視する。
+///
+/// `ReadDirectoryChangesW`を使用して、指定したファイルが置かれているディレクトリを監視します。
+/// 監視用のスレッドは[`FileWatcher`]ごとに1つ作成されます。
+///
+/// - 短時間に連続して発生した変更は、最後の変更から`debounce`だけ経ってから1回だけ通知されます。
+/// - 書き込み中のプログラムがファイルをロックしている間は通知を遅らせ、読み込めるようになってから通知します。
+/// - ファイルが削除された場合も通知されます。
+///
+/// # Note
+///
+/// AviUtl2にはフィルタの処理結果の再描画を要求するAPIがないため、
+/// ファイルの変更はプレビューが次に描画されたときに反映されます。
+///
+/// # Example
+///
+/// ```rust
+/// let watcher = aviutl2::utils::FileWatcher::new(
+///     aviutl2::utils::DEFAULT_FILE_WATCH_DEBOUNCE,
+///     |path| println!("changed: {}", path.display()),
+/// )?;
+/// watcher.watch("lut.cube")?;
+/// # std::io::Result::Ok(())
+/// ```
+pub struct FileWatcher {
+    shared: Arc<WatcherShared>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+struct SendHandle(HANDLE);
+unsafe impl Send for SendHandle {}
+unsafe impl Sync for SendHandle {}
+
+struct WatcherShared {
+    /// 監視中のファイル。キーは比較用に小文字にしたパス。
+    files: Mutex<HashMap<String, PathBuf>>,
+    wake: SendHandle,
+    stopped: AtomicBool,
+}
+
+impl WatcherShared {
+    fn wake(&self) {
+        unsafe {
+            let _ = SetEvent(self.wake.0);
+        }
+    }
+}
+
+fn normalize_path(path: &Path) -> std::io::Result<PathBuf> {
+    std::path::absolute(path)
+}
+
+fn path_key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+impl FileWatcher {
+    /// ファイルの監視を開始する。
+    ///
+    /// `on_change`は監視用のスレッドから呼び出されます。
+    pub fn new(
+        debounce: Duration,
+        on_change: impl Fn(&Path) + Send + 'static,
+    ) -> std::io::Result<Self> {
+        let wake = unsafe { CreateEventW(None, false, false, windows::core::PCWSTR::null()) }?;
+        let shared = Arc::new(WatcherShared {
+            files: Mutex::new(HashMap::new()),
+            wake: SendHandle(wake),
+            stopped: AtomicBool::new(false),
+        });
+        let thread = std::thread::Builder::new()
+            .name("aviutl2-rs file watcher".to_string())
+            .spawn({
+                let shared = Arc::clone(&shared);
+                move || run_watcher(&shared, debounce, on_change)
+            })?;
+        Ok(Self {
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// ファイルを監視対象に追加する。
+    ///
+    /// ファイルが存在している必要はありませんが、ファイルを置くディレクトリは存在している必要があります。
+    pub fn watch(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = normalize_path(path.as_ref())?;
+        let inserted = self
+            .shared
+            .files
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path_key(&path), path)
+            .is_none();
+        if inserted {
+            self.shared.wake();
+        }
+        Ok(())
+    }
+
+    /// ファイルを監視対象から外す。
+    pub fn unwatch(&self, path: impl AsRef<Path>) {
+        let Ok(path) = normalize_path(path.as_ref()) else {
+            return;
+        };
+        let removed = self
+            .shared
+            .files
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&path_key(&path))
+            .is_some();
+        if removed {
+            self.shared.wake();
+        }
+    }
+
+    /// 監視中のファイルを返す。
+    pub fn watched_files(&self) -> Vec<PathBuf> {
+        self.shared
+            .files
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        self.shared.wake();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        unsafe {
+            let _ = CloseHandle(self.shared.wake.0);
+        }
+    }
+}
+
+impl std::fmt::Debug for FileWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileWatcher")
+            .field("files", &self.watched_files())
+            .finish()
+    }
+}
+
+/// 1つのディレクトリに対する`ReadDirectoryChangesW`の呼び出し。
+struct DirectoryWatch {
+    directory: PathBuf,
+    handle: HANDLE,
+    event: HANDLE,
+    overlapped: Box<OVERLAPPED>,
+    // FILE_NOTIFY_INFORMATIONはDWORD境界に置く必要があるため、u32の配列にする
+    buffer: Box<[u32; 16 * 1024]>,
+}
+
+impl DirectoryWatch {
+    fn open(directory: &Path) -> std::io::Result<Self> {
+        let handle = unsafe {
+            CreateFileW(
+                &windows::core::HSTRING::from(directory),
+                FILE_LIST_DIRECTORY.0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                None,
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OVERLAPPED,
+                None,
+            )
+        }?;
+        let event = match unsafe { CreateEventW(None, true, false, windows::core::PCWSTR::null()) }
+        {
+            Ok(event) => event,
+            Err(e) => {
+                unsafe {
+                    let _ = CloseHandle(handle);
+                }
+                return Err(e.into());
+            }
+        };
+        let mut watch = Self {
+            directory: directory.to_path_buf(),
+            handle,
+            event,
+            overlapped: Box::new(OVERLAPPED {
+                hEvent: event,
+                ..Default::default()
+            }),
+            buffer: Box::new([0; 16 * 1024]),
+        };
+        watch.request()?;
+        Ok(watch)
+    }
+
+    fn request(&mut self) -> std::io::Result<()> {
+        unsafe {
+            ResetEvent(self.event)?;
+            ReadDirectoryChangesW(
+                self.handle,
+                self.buffer.as_mut_ptr().cast(),
+                std::mem::size_of_val(&*self.buffer) as u32,
+                false,
+                FILE_NOTIFY_CHANGE_FILE_NAME
+                    | FILE_NOTIFY_CHANGE_LAST_WRITE
+                    | FILE_NOTIFY_CHANGE_SIZE,
+                None,
+                Some(&mut *self.overlapped as *mut _),
+                None,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 完了した変更通知を読み取る。
+    ///
+    /// 通知が溢れた場合は`None`を返します。
+    fn take_changes(&mut self) -> std::io::Result<Option<Vec<PathBuf>>> {
+        let mut transferred = 0u32;
+        unsafe {
+            GetOverlappedResult(self.handle, &*self.overlapped, &mut transferred, false)?;
+        }
+        if transferred == 0 {
+            return Ok(None);
+        }
+        let bytes = unsafe {
+            std::slice::from_raw_parts(self.buffer.as_ptr().cast::<u8>(), transferred as usize)
+        };
+        Ok(Some(
+            parse_notify_information(bytes)
+                .into_iter()
+                .map(|name| self.directory.join(name))
+                .collect(),
+        ))
+    }
+}
+
+impl Drop for DirectoryWatch {
+    fn drop(&mut self) {
+        unsafe {
+            // 実行中の読み取りが終わるまで待ってからバッファを解放する
+            if CancelIoEx(self.handle, Some(&*self.overlapped as *const _)).is_ok() {
+                let mut transferred = 0u32;
+                let _ = GetOverlappedResult(self.handle, &*self.overlapped, &mut transferred, true);
+            }
+            let _ = CloseHandle(self.handle);
+            let _ = CloseHandle(self.event);
+        }
+    }
+}
+
+/// `FILE_NOTIFY_INFORMATION`の列からファイル名を取り出す。
+fn parse_notify_information(bytes: &[u8]) -> Vec<String> {
+    let read_u32 = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    };
+    let mut names = Vec::new();
+    let mut offset = 0;
+    loop {
+        let (Some(next), Some(name_len)) = (read_u32(offset), read_u32(offset + 8)) else {
+            break;
+        };
+        let name_start = offset + 12;
+        let Some(name) = bytes.get(name_start..name_start + name_len as usize) else {
+            break;
+        };
+        let name = name
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect::<Vec<_>>();
+        names.push(String::from_utf16_lossy(&name));
+        if next == 0 {
+            break;
+        }
+        offset += next as usize;
+    }
+    names
+}
+
+/// 変更を通知するタイミングを管理する。
+#[derive(Debug, Default)]
+struct Debouncer {
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    fn record(&mut self, path: PathBuf, now: Instant, debounce: Duration) {
+        self.pending.insert(path, now + debounce);
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().min().copied()
+    }
+
+    /// 通知するファイルを取り出す。ロックされているファイルは`debounce`後に再試行します。
+    fn take_ready(
+        &mut self,
+        now: Instant,
+        debounce: Duration,
+        is_locked: impl Fn(&Path) -> bool,
+    ) -> Vec<PathBuf> {
+        let due = self
+            .pending
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        let mut ready = Vec::new();
+        for path in due {
+            if is_locked(&path) {
+                self.pending.insert(path, now + debounce);
+            } else {
+                self.pending.remove(&path);
+                ready.push(path);
+            }
+        }
+        ready
+    }
+}
+
+fn is_locked(path: &Path) -> bool {
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    match std::fs::File::open(path) {
+        Ok(_) => false,
+        Err(e) => matches!(
+            e.raw_os_error(),
+            Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+        ),
+    }
+}
+
+fn run_watcher(shared: &WatcherShared, debounce: Duration, on_change: impl Fn(&Path)) {
+    let mut directories: Vec<DirectoryWatch> = Vec::new();
+    let mut debouncer = Debouncer::default();
+    while !shared.stopped.load(Ordering::Acquire) {
+        let files = shared
+            .files
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+
+        // 監視するディレクトリを監視中のファイルに合わせる
+        let wanted = files
+            .values()
+            .filter_map(|path| path.parent())
+            .map(Path::to_path_buf)
+            .collect::<HashSet<_>>();
+        directories.retain(|watch| wanted.contains(&watch.directory));
+        for directory in &wanted {
+            if directories
+                .iter()
+                .any(|watch| &watch.directory == directory)
+            {
+                continue;
+            }
+            match DirectoryWatch::open(directory) {
+                Ok(watch) => directories.push(watch),
+                Err(e) => tracing::warn!("Failed to watch directory {directory:?}: {e}"),
+            }
+        }
+
+        let mut handles = vec![shared.wake.0];
+        handles.extend(directories.iter().map(|watch| watch.event));
+        let timeout = debouncer.next_deadline().map_or(INFINITE, |deadline| {
+            deadline
+                .saturating_duration_since(Instant::now())
+                .as_millis()
+                .min(u128::from(INFINITE - 1)) as u32
+        });
+        let result = unsafe { WaitForMultipleObjects(&handles, false, timeout) };
+        let index = result.0.wrapping_sub(WAIT_OBJECT_0.0) as usize;
+        if index >= 1 && index < handles.len() {
+            let watch = &mut directories[index - 1];
+            let changed = match watch.take_changes() {
+                Ok(Some(changed)) => changed,
+                // 通知が溢れた場合は、ディレクトリ内のすべてのファイルが変更されたとみなす
+                Ok(None) => files
+                    .values()
+                    .filter(|path| path.parent() == Some(watch.directory.as_path()))
+                    .cloned()
+                    .collect(),
+                Err(e) => {
+                    tracing::warn!("Failed to read changes in {:?}: {e}", watch.directory);
+                    Vec::new()
+                }
+            };
+            let now = Instant::now();
+            for path in changed {
+                if let Some(path) = files.get(&path_key(&path)) {
+                    debouncer.record(path.clone(), now, debounce);
+                }
+            }
+            if let Err(e) = watch.request() {
+                tracing::warn!("Failed to watch directory {:?}: {e}", watch.directory);
+                let directory = watch.directory.clone();
+                directories.retain(|watch| watch.directory != directory);
+            }
+        }
+
+        for path in debouncer.take_ready(Instant::now(), debounce, is_locked) {
+            if files.contains_key(&path_key(&path)) {
+                on_change(&path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEBOUNCE: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn debouncer_merges_rapid_changes() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::default();
+        let path = PathBuf::from("a.cube");
+        for i in 0..10 {
+            debouncer.record(
+                path.clone(),
+                start + Duration::from_millis(i * 10),
+                DEBOUNCE,
+            );
+        }
+        assert!(
+            debouncer
+                .take_ready(start + DEBOUNCE, DEBOUNCE, |_| false)
+                .is_empty()
+        );
+        assert_eq!(
+            debouncer.take_ready(start + DEBOUNCE * 2, DEBOUNCE, |_| false),
+            vec![path]
+        );
+        assert_eq!(debouncer.next_deadline(), None);
+    }
+
+    #[test]
+    fn debouncer_retries_locked_files() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::default();
+        let path = PathBuf::from("a.cube");
+        debouncer.record(path.clone(), start, DEBOUNCE);
+        assert!(
+            debouncer
+                .take_ready(start + DEBOUNCE, DEBOUNCE, |_| true)
+                .is_empty()
+        );
+        assert_eq!(debouncer.next_deadline(), Some(start + DEBOUNCE * 2));
+        assert_eq!(
+            debouncer.take_ready(start + DEBOUNCE * 2, DEBOUNCE, |_| false),
+            vec![path]
+        );
+    }
+
+    #[test]
+    fn parse_notify_information_reads_all_entries() {
+        fn entry(name: &str, last: bool) -> Vec<u8> {
+            let name = name
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes)
+                .collect::<Vec<_>>();
+            let len = (12 + name.len()).next_multiple_of(4);
+            let mut bytes = Vec::new();
+            bytes.extend_from_slice(&(if last { 0 } else { len as u32 }).to_le_bytes());
+            bytes.extend_from_slice(&3u32.to_le_bytes());
+            bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&name);
+            bytes.resize(len, 0);
+            bytes
+        }
+        let mut bytes = entry("lut.cube", false);
+        bytes.extend(entry("ファイル.wav", true));
+        assert_eq!(
+            parse_notify_information(&bytes),
+            vec!["lut.cube".to_string(), "ファイル.wav".to_string()]
+        );
+    }
+
+    #[test]
+    fn watcher_reports_file_changes() {
+        let dir = std::env::temp_dir().join(format!("aviutl2-rs-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("watched.txt");
+        std::fs::write(&file, "before").unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = Mutex::new(sender);
+        let watcher = FileWatcher::new(DEBOUNCE, move |path| {
+            let _ = sender.lock().unwrap().send(path.to_path_buf());
+        })
+        .unwrap();
+        watcher.watch(&file).unwrap();
+        // 監視用スレッドがディレクトリを開くまで待つ
+        std::thread::sleep(DEBOUNCE);
+        for i in 0..5 {
+            std::fs::write(&file, format!("after {i}")).unwrap();
+        }
+
+        let changed = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(path_key(&changed), path_key(&file));
+        // 連続した書き込みは1回にまとめられる
+        assert!(receiver.recv_timeout(DEBOUNCE * 3).is_err());
+
+        drop(watcher);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,615 @@
+//! トースト通知と通知領域のアイコン。
+//!
+//! 時間のかかる処理が終わったことを、AviUtl2を前面に出していないユーザーに知らせるために使います。
+//! どちらもワーカースレッドから呼び出せます。
+
+use std::sync::{OnceLock, mpsc};
+
+use windows::{
+    Data::Xml::Dom::XmlDocument,
+    UI::Notifications::{ToastNotification, ToastNotificationManager},
+    Win32::{
+        Foundation::{
+            E_FAIL, ERROR_CLASS_ALREADY_EXISTS, GetLastError, HWND, LPARAM, LRESULT, POINT, WPARAM,
+        },
+        System::{
+            Com::CoTaskMemFree,
+            LibraryLoader::GetModuleHandleW,
+            Registry::{
+                HKEY, HKEY_CURRENT_USER, KEY_SET_VALUE, REG_OPTION_NON_VOLATILE, REG_SZ,
+                RegCloseKey, RegCreateKeyExW, RegSetValueExW,
+            },
+        },
+        UI::{
+            Shell::{
+                GetCurrentProcessExplicitAppUserModelID, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD,
+                NIM_DELETE, NIM_MODIFY, NOTIFYICONDATAW, Shell_NotifyIconW,
+            },
+            WindowsAndMessaging::{
+                AppendMenuW, CREATESTRUCTW, CreatePopupMenu, CreateWindowExW, DefWindowProcW,
+                DestroyMenu, DestroyWindow, DispatchMessageW, GWLP_USERDATA, GetCursorPos,
+                GetMessageW, GetWindowLongPtrW, HICON, IDI_APPLICATION, LoadIconW, MF_STRING, MSG,
+                PostMessageW, PostQuitMessage, RegisterClassW, RegisterWindowMessageW,
+                SetForegroundWindow, SetWindowLongPtrW, TPM_NONOTIFY, TPM_RETURNCMD,
+                TrackPopupMenu, TranslateMessage, UnregisterClassW, WINDOW_EX_STYLE, WINDOW_STYLE,
+                WM_APP, WM_CLOSE, WM_CONTEXTMENU, WM_DESTROY, WM_LBUTTONUP, WM_NCCREATE, WM_NULL,
+                WM_RBUTTONUP, WNDCLASSW,
+            },
+        },
+    },
+    core::{HSTRING, PCWSTR, w},
+};
+
+/// プロセスにAppUserModelIDが設定されていない場合に使うAppUserModelID。
+const FALLBACK_APP_ID: &str = "AviUtl2.RustPlugins";
+/// [`FALLBACK_APP_ID`]の通知に表示する名前。
+const FALLBACK_APP_NAME: &str = "AviUtl2";
+
+/// [`toast`]の結果。
+#[derive(Debug)]
+pub enum ToastDelivery {
+    /// トースト通知を表示した。
+    Shown,
+    /// トースト通知を表示できなかったため、プラグイン用ログに出力した。
+    Logged(windows::core::Error),
+}
+
+/// トースト通知を表示する。
+///
+/// AviUtl2はパッケージ化されていないアプリのため、プロセスにAppUserModelIDが設定されていない場合は
+/// `HKEY_CURRENT_USER\Software\Classes\AppUserModelId`に通知用のAppUserModelIDを登録してから表示します。
+/// 登録や表示に失敗した場合は、代わりに同じ内容をプラグイン用ログに出力し、[`ToastDelivery::Logged`]を返します。
+///
+/// # Note
+///
+/// 通知がWindowsの設定で無効にされている場合など、表示に成功しても通知が表示されないことがあります。
+///
+/// # Example
+///
+/// ```rust,no_run
+/// std::thread::spawn(|| {
+///     // 時間のかかる処理
+///     aviutl2::utils::notify::toast("解析が完了しました", "120 BPM");
+/// });
+/// ```
+pub fn toast(title: &str, body: &str) -> ToastDelivery {
+    deliver_toast(title, body, show_toast, |message| {
+        let _ = crate::logger::write_plugin_log(message);
+    })
+}
+
+fn deliver_toast(
+    title: &str,
+    body: &str,
+    show: impl FnOnce(&str, &str) -> windows::core::Result<()>,
+    log: impl FnOnce(&str),
+) -> ToastDelivery {
+    match show(title, body) {
+        Ok(()) => ToastDelivery::Shown,
+        Err(e) => {
+            tracing::warn!("Failed to show toast notification: {}", e);
+            log(&fallback_message(title, body));
+            ToastDelivery::Logged(e)
+        }
+    }
+}
+
+fn fallback_message(title: &str, body: &str) -> String {
+    if body.is_empty() {
+        format!("[通知] {title}")
+    } else {
+        format!("[通知] {title}: {body}")
+    }
+}
+
+fn show_toast(title: &str, body: &str) -> windows::core::Result<()> {
+    let document = XmlDocument::new()?;
+    document.LoadXml(&HSTRING::from(toast_xml(title, body)))?;
+    let notification = ToastNotification::CreateToastNotification(&document)?;
+    let notifier = ToastNotificationManager::CreateToastNotifierWithId(&app_id()?)?;
+    notifier.Show(&notification)
+}
+
+/// トースト通知に使うAppUserModelID。登録はプロセスごとに一度だけ行う。
+fn app_id() -> windows::core::Result<HSTRING> {
+    static APP_ID: OnceLock<windows::core::Result<HSTRING>> = OnceLock::new();
+    APP_ID
+        .get_or_init(|| {
+            if let Some(app_id) = process_app_id() {
+                return Ok(app_id);
+            }
+            register_fallback_app_id()?;
+            Ok(HSTRING::from(FALLBACK_APP_ID))
+        })
+        .clone()
+}
+
+fn process_app_id() -> Option<HSTRING> {
+    let app_id = unsafe { GetCurrentProcessExplicitAppUserModelID() }.ok()?;
+    let result = unsafe { app_id.to_hstring() };
+    unsafe { CoTaskMemFree(Some(app_id.0 as *const _)) };
+    (!result.is_empty()).then_some(result)
+}
+
+fn register_fallback_app_id() -> windows::core::Result<()> {
+    let subkey = HSTRING::from(format!(
+        r"Software\Classes\AppUserModelId\{FALLBACK_APP_ID}"
+    ));
+    let mut key = HKEY::default();
+    unsafe {
+        RegCreateKeyExW(
+            HKEY_CURRENT_USER,
+            &subkey,
+            None,
+            PCWSTR::null(),
+            REG_OPTION_NON_VOLATILE,
+            KEY_SET_VALUE,
+            None,
+            &mut key,
+            None,
+        )
+        .ok()?;
+    }
+    let name = crate::utils::encoding::to_wide(FALLBACK_APP_NAME);
+    let result = unsafe {
+        RegSetValueExW(
+            key,
+            w!("DisplayName"),
+            None,
+            REG_SZ,
+            Some(std::slice::from_raw_parts(
+                name.as_ptr() as *const u8,
+                name.len() * 2,
+            )),
+        )
+    };
+    unsafe {
+        let _ = RegCloseKey(key);
+    }
+    result.ok()
+}
+
+/// トースト通知のXML。
+fn toast_xml(title: &str, body: &str) -> String {
+    format!(
+        "<toast><visual><binding template=\"ToastGeneric\"><text>{}</text><text>{}</text></binding></visual></toast>",
+        escape_xml(title),
+        escape_xml(body)
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // XMLで使えない制御文字は捨てる
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+type Callback = Box<dyn Fn() + Send + 'static>;
+
+/// [`tray_icon`]で作成する通知領域のアイコンの設定。
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let icon = aviutl2::utils::notify::tray_icon(
+///     aviutl2::utils::notify::TrayIconBuilder::new("Rusty Plugin")
+///         .menu_item("開く", || println!("open"))
+///         .menu_item("中止", || println!("cancel"))
+///         .on_click(|| println!("clicked")),
+/// )?;
+/// icon.set_tooltip("Rusty Plugin：解析中")?;
+/// # windows::core::Result::Ok(())
+/// ```
+pub struct TrayIconBuilder {
+    tooltip: String,
+    menu: Vec<(String, Callback)>,
+    on_click: Option<Callback>,
+}
+
+impl std::fmt::Debug for TrayIconBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TrayIconBuilder")
+            .field("tooltip", &self.tooltip)
+            .field(
+                "menu",
+                &self.menu.iter().map(|(label, _)| label).collect::<Vec<_>>(),
+            )
+            .field("on_click", &self.on_click.is_some())
+            .finish()
+    }
+}
+
+impl TrayIconBuilder {
+    /// マウスを乗せた時に表示するテキストを指定して作成する。
+    ///
+    /// テキストは127文字（UTF-16）までで、それより長い部分は切り捨てられます。
+    pub fn new(tooltip: impl Into<String>) -> Self {
+        Self {
+            tooltip: tooltip.into(),
+            menu: Vec::new(),
+            on_click: None,
+        }
+    }
+
+    /// 右クリックで表示するメニューに項目を追加する。
+    ///
+    /// `callback`はアイコンのスレッドで呼ばれます。
+    pub fn menu_item(
+        mut self,
+        label: impl Into<String>,
+        callback: impl Fn() + Send + 'static,
+    ) -> Self {
+        self.menu.push((label.into(), Box::new(callback)));
+        self
+    }
+
+    /// 左クリックされた時に呼ばれる関数を設定する。
+    ///
+    /// `callback`はアイコンのスレッドで呼ばれます。
+    pub fn on_click(mut self, callback: impl Fn() + Send + 'static) -> Self {
+        self.on_click = Some(Box::new(callback));
+        self
+    }
+}
+
+/// 通知領域のアイコンを表示する。
+///
+/// アイコンごとにメッセージを処理するスレッドを作成し、メニューとクリックの関数はそのスレッドで呼びます。
+/// 返り値の[`TrayIcon`]を破棄するとアイコンは消え、スレッドも終了します。
+///
+/// # Note
+///
+/// DLLが解放された後にアイコンが残らないよう、[`TrayIcon`]はプラグインの構造体などに保持し、
+/// プラグインと一緒に破棄してください。
+pub fn tray_icon(builder: TrayIconBuilder) -> windows::core::Result<TrayIcon> {
+    let (tx, rx) = mpsc::channel();
+    let thread = std::thread::Builder::new()
+        .name("aviutl2-rs tray icon".to_string())
+        .spawn(move || run_tray_thread(builder, tx))
+        .map_err(|e| windows::core::Error::new(E_FAIL, e.to_string()))?;
+    match rx.recv() {
+        Ok(Ok(hwnd)) => Ok(TrayIcon {
+            hwnd,
+            thread: Some(thread),
+        }),
+        Ok(Err(e)) => {
+            let _ = thread.join();
+            Err(e)
+        }
+        Err(_) => {
+            let _ = thread.join();
+            Err(windows::core::Error::new(
+                E_FAIL,
+                "Tray icon thread exited unexpectedly",
+            ))
+        }
+    }
+}
+
+/// 通知領域のアイコン。破棄するとアイコンが消える。
+#[derive(Debug)]
+pub struct TrayIcon {
+    hwnd: SendHwnd,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SendHwnd(HWND);
+unsafe impl Send for SendHwnd {}
+unsafe impl Sync for SendHwnd {}
+
+impl TrayIcon {
+    /// マウスを乗せた時に表示するテキストを変更する。
+    pub fn set_tooltip(&self, tooltip: &str) -> windows::core::Result<()> {
+        let tooltip = Box::new(tooltip.to_string());
+        let ptr = Box::into_raw(tooltip);
+        if let Err(e) = unsafe {
+            PostMessageW(
+                Some(self.hwnd.0),
+                WM_SET_TOOLTIP,
+                WPARAM(0),
+                LPARAM(ptr as isize),
+            )
+        } {
+            drop(unsafe { Box::from_raw(ptr) });
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = PostMessageW(Some(self.hwnd.0), WM_CLOSE, WPARAM(0), LPARAM(0));
+        }
+        if let Some(thread) = self.thread.take()
+            && thread.thread().id() != std::thread::current().id()
+        {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// アイコンに対する操作を受け取るメッセージ。
+const WM_TRAY: u32 = WM_APP + 1;
+/// [`TrayIcon::set_tooltip`]のメッセージ。`lParam`は`Box<String>`のポインタ。
+const WM_SET_TOOLTIP: u32 = WM_APP + 2;
+const TRAY_ICON_ID: u32 = 1;
+const WINDOW_CLASS: PCWSTR = w!("aviutl2-rs-tray-icon");
+
+/// ウィンドウに結びつける状態。
+struct TrayState {
+    builder: TrayIconBuilder,
+    /// エクスプローラーが再起動した時に送られるメッセージ。
+    taskbar_created: u32,
+    icon: HICON,
+}
+
+fn run_tray_thread(builder: TrayIconBuilder, tx: mpsc::Sender<windows::core::Result<SendHwnd>>) {
+    let hwnd = match create_tray_window(builder) {
+        Ok(hwnd) => hwnd,
+        Err(e) => {
+            let _ = tx.send(Err(e));
+            return;
+        }
+    };
+    let _ = tx.send(Ok(SendHwnd(hwnd)));
+    let mut message = MSG::default();
+    unsafe {
+        while GetMessageW(&mut message, None, 0, 0).as_bool() {
+            let _ = TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+        let instance = GetModuleHandleW(None).unwrap_or_default();
+        // 他のアイコンがまだ使っている場合は失敗するが、最後のアイコンが消えた時に解除される
+        let _ = UnregisterClassW(WINDOW_CLASS, Some(instance.into()));
+    }
+}
+
+fn create_tray_window(builder: TrayIconBuilder) -> windows::core::Result<HWND> {
+    unsafe {
+        let instance = GetModuleHandleW(None)?;
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(tray_window_proc),
+            hInstance: instance.into(),
+            lpszClassName: WINDOW_CLASS,
+            ..Default::default()
+        };
+        if RegisterClassW(&class) == 0 && GetLastError() != ERROR_CLASS_ALREADY_EXISTS {
+            return Err(windows::core::Error::from_thread());
+        }
+        let state = Box::new(TrayState {
+            builder,
+            taskbar_created: RegisterWindowMessageW(w!("TaskbarCreated")),
+            icon: LoadIconW(None, IDI_APPLICATION)?,
+        });
+        let state = Box::into_raw(state);
+        // 表示しないトップレベルウィンドウ。メッセージ専用ウィンドウには`TaskbarCreated`が届かない
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            WINDOW_CLASS,
+            w!("aviutl2-rs tray icon"),
+            WINDOW_STYLE::default(),
+            0,
+            0,
+            0,
+            0,
+            None,
+            None,
+            Some(instance.into()),
+            Some(state as *const _),
+        ) {
+            Ok(hwnd) => hwnd,
+            Err(e) => {
+                drop(Box::from_raw(state));
+                return Err(e);
+            }
+        };
+        if !notify_icon(hwnd, NIM_ADD, &(*state).builder.tooltip, (*state).icon) {
+            let _ = DestroyWindow(hwnd);
+            return Err(windows::core::Error::new(
+                E_FAIL,
+                "Shell_NotifyIconW failed",
+            ));
+        }
+        Ok(hwnd)
+    }
+}
+
+fn notify_icon(
+    hwnd: HWND,
+    message: windows::Win32::UI::Shell::NOTIFY_ICON_MESSAGE,
+    tooltip: &str,
+    icon: HICON,
+) -> bool {
+    let mut data = NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        hWnd: hwnd,
+        uID: TRAY_ICON_ID,
+        uFlags: NIF_MESSAGE | NIF_ICON | NIF_TIP,
+        uCallbackMessage: WM_TRAY,
+        hIcon: icon,
+        ..Default::default()
+    };
+    copy_truncated(&mut data.szTip, tooltip);
+    unsafe { Shell_NotifyIconW(message, &data) }.as_bool()
+}
+
+/// `text`を終端のヌル文字を含めて`buffer`に収まるように切り詰めてコピーする。
+fn copy_truncated(buffer: &mut [u16], text: &str) {
+    let mut len = 0;
+    for unit in text.encode_utf16().take(buffer.len() - 1) {
+        buffer[len] = unit;
+        len += 1;
+    }
+    // サロゲートペアの片方だけが残らないようにする
+    if len > 0 && (0xD800..0xDC00).contains(&buffer[len - 1]) {
+        len -= 1;
+    }
+    buffer[len] = 0;
+}
+
+unsafe extern "system" fn tray_window_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    unsafe {
+        if message == WM_NCCREATE {
+            let create = &*(lparam.0 as *const CREATESTRUCTW);
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, create.lpCreateParams as isize);
+            return DefWindowProcW(hwnd, message, wparam, lparam);
+        }
+        let state = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut TrayState;
+        if state.is_null() {
+            return DefWindowProcW(hwnd, message, wparam, lparam);
+        }
+        match message {
+            WM_TRAY => {
+                match (lparam.0 & 0xFFFF) as u32 {
+                    WM_LBUTTONUP => {
+                        if let Some(on_click) = &(*state).builder.on_click {
+                            invoke_callback(on_click);
+                        }
+                    }
+                    WM_RBUTTONUP | WM_CONTEXTMENU => show_menu(hwnd, &*state),
+                    _ => {}
+                }
+                LRESULT(0)
+            }
+            WM_SET_TOOLTIP => {
+                let tooltip = Box::from_raw(lparam.0 as *mut String);
+                (*state).builder.tooltip = *tooltip;
+                notify_icon(hwnd, NIM_MODIFY, &(*state).builder.tooltip, (*state).icon);
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                notify_icon(hwnd, NIM_DELETE, "", (*state).icon);
+                let _ = DestroyWindow(hwnd);
+                LRESULT(0)
+            }
+            WM_DESTROY => {
+                SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0);
+                drop(Box::from_raw(state));
+                PostQuitMessage(0);
+                LRESULT(0)
+            }
+            message if message == (*state).taskbar_created => {
+                notify_icon(hwnd, NIM_ADD, &(*state).builder.tooltip, (*state).icon);
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, message, wparam, lparam),
+        }
+    }
+}
+
+/// 右クリックメニューを表示し、選ばれた項目の関数を呼ぶ。
+unsafe fn show_menu(hwnd: HWND, state: &TrayState) {
+    if state.builder.menu.is_empty() {
+        return;
+    }
+    unsafe {
+        let Ok(menu) = CreatePopupMenu() else {
+            return;
+        };
+        for (i, (label, _)) in state.builder.menu.iter().enumerate() {
+            let label = HSTRING::from(label.as_str());
+            let _ = AppendMenuW(menu, MF_STRING, i + 1, &label);
+        }
+        let mut cursor = POINT::default();
+        let _ = GetCursorPos(&mut cursor);
+        // メニューの外をクリックした時にメニューが閉じるようにする
+        let _ = SetForegroundWindow(hwnd);
+        let selected = TrackPopupMenu(
+            menu,
+            TPM_RETURNCMD | TPM_NONOTIFY,
+            cursor.x,
+            cursor.y,
+            None,
+            hwnd,
+            None,
+        )
+        .0;
+        let _ = PostMessageW(Some(hwnd), WM_NULL, WPARAM(0), LPARAM(0));
+        let _ = DestroyMenu(menu);
+        if let Some((_, callback)) = (selected as usize)
+            .checked_sub(1)
+            .and_then(|index| state.builder.menu.get(index))
+        {
+            invoke_callback(callback);
+        }
+    }
+}
+
+fn invoke_callback(callback: &Callback) {
+    if let Err(panic_info) =
+        crate::utils::catch_unwind_with_panic_info(std::panic::AssertUnwindSafe(callback))
+    {
+        tracing::error!("Panic occurred in tray icon callback: {}", panic_info);
+        let _ = crate::logger::write_error_log(&panic_info);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_falls_back_to_log_when_toast_fails() {
+        let mut logged = Vec::new();
+        let delivery = deliver_toast(
+            "解析が完了しました",
+            "120 BPM",
+            |_, _| Err(windows::core::Error::from_hresult(E_FAIL)),
+            |message| logged.push(message.to_string()),
+        );
+        assert!(matches!(delivery, ToastDelivery::Logged(_)));
+        assert_eq!(logged, ["[通知] 解析が完了しました: 120 BPM"]);
+
+        let mut logged = Vec::new();
+        let delivery = deliver_toast(
+            "title",
+            "body",
+            |_, _| Ok(()),
+            |message| logged.push(message.to_string()),
+        );
+        assert!(matches!(delivery, ToastDelivery::Shown));
+        assert!(logged.is_empty());
+    }
+
+    #[test]
+    fn test_toast_xml_escapes_text() {
+        assert_eq!(
+            toast_xml("<A & B>", "\"it's\"\u{1}\n"),
+            "<toast><visual><binding template=\"ToastGeneric\"><text>&lt;A &amp; B&gt;</text><text>&quot;it&apos;s&quot;\n</text></binding></visual></toast>"
+        );
+    }
+
+    #[test]
+    fn test_copy_truncated() {
+        let mut buffer = [0xFFFFu16; 4];
+        copy_truncated(&mut buffer, "ab");
+        assert_eq!(buffer[..3], [b'a' as u16, b'b' as u16, 0]);
+
+        copy_truncated(&mut buffer, "abcdef");
+        assert_eq!(buffer, [b'a' as u16, b'b' as u16, b'c' as u16, 0]);
+
+        // サロゲートペアの途中で切らない
+        copy_truncated(&mut buffer, "ab🎵");
+        assert_eq!(buffer[..3], [b'a' as u16, b'b' as u16, 0]);
+    }
+}
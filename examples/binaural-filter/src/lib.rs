@@ -63,19 +63,28 @@ fn linear_resample(input: &[f32], output: &mut [f32]) {
 //     output
 // }
 
-struct BinauralStates {
+pub struct BinauralStates {
     hrtf: hrtf::HrtfProcessor,
-    num_blocks: usize,
-    block_size: usize,
     audio_cache: ringbuffer::AllocRingBuffer<f32>,
     requested_sample_count: usize,
     tail_index: usize,
 
     prev_left_samples: Vec<f32>,
     prev_right_samples: Vec<f32>,
+
+    // 呼び出しごとに確保しないように使い回すバッファ
+    left_samples: Vec<f32>,
+    right_samples: Vec<f32>,
+    samples: Vec<f32>,
+    source: Vec<f32>,
+    output: Vec<(f32, f32)>,
+    left_44100: Vec<f32>,
+    right_44100: Vec<f32>,
+    left: Vec<f32>,
+    right: Vec<f32>,
 }
 impl BinauralStates {
-    fn new(frame_size: usize, sample_rate: f64) -> anyhow::Result<Self> {
+    pub fn new(frame_size: usize, sample_rate: f64) -> anyhow::Result<Self> {
         let frame_44100_size = resample_size(frame_size, sample_rate as usize, 44100);
         let mut num_blocks = 2_usize.pow(3);
         let mut block_size = next_pow2(frame_44100_size) / (num_blocks / 2);
@@ -105,8 +114,6 @@ impl BinauralStates {
 
         Ok(Self {
             hrtf,
-            num_blocks,
-            block_size,
             audio_cache,
             requested_sample_count: resample_size(
                 num_blocks * block_size,
@@ -116,16 +123,124 @@ impl BinauralStates {
             tail_index: 0,
             prev_left_samples: vec![],
             prev_right_samples: vec![],
+
+            left_samples: vec![],
+            right_samples: vec![],
+            samples: vec![],
+            source: vec![0.0; block_size * num_blocks],
+            output: vec![(0.0, 0.0); block_size * num_blocks],
+            left_44100: vec![0.0; block_size * num_blocks],
+            right_44100: vec![0.0; block_size * num_blocks],
+            left: vec![],
+            right: vec![],
         })
     }
 
-    fn process(
+    /// [`Self::process`]に渡すサンプル数。
+    pub fn requested_sample_count(&self) -> usize {
+        self.requested_sample_count
+    }
+
+    /// [`Self::warm_up`]に渡すと十分な、直前の音声のサンプル数。
+    #[cfg(test)]
+    fn warmup_samples(&self) -> usize {
+        self.requested_sample_count
+    }
+
+    /// 直前の音声で履歴を埋めて、`sample_index`から処理を始められるようにする。
+    ///
+    /// `preceding`が[`Self::warmup_samples`]個あれば、最初から続けて処理した場合と同じ結果になります。
+    /// 足りない分は無音として扱います。
+    /// フィルタには直前の音声が渡されないため、今はテストでのみ使っています。
+    #[cfg(test)]
+    fn warm_up(&mut self, sample_index: usize, preceding: &[f32]) {
+        self.reset_history(sample_index);
+        let len = preceding.len().min(self.audio_cache.len());
+        self.audio_cache
+            .extend(preceding[(preceding.len() - len)..].iter().copied());
+    }
+
+    fn reset_history(&mut self, sample_index: usize) {
+        let cache_length = self.audio_cache.len();
+        self.tail_index = sample_index;
+        self.audio_cache.clear();
+        self.audio_cache.extend((0..cache_length).map(|_| 0.0));
+    }
+
+    /// `sample_index`から始まるモノラルの音声を処理し、左右の音声を返す。
+    ///
+    /// 返す音声は`mono_samples`と同じ長さです。
+    /// 前回の続きでない場合は、履歴を無音にしてから処理します。
+    pub fn process_frame(
+        &mut self,
+        sample_index: usize,
+        mono_samples: &[f32],
+        gain: f32,
+        rotate_yaw: f64,
+        rotate_pitch: f64,
+    ) -> anyhow::Result<(&[f32], &[f32])> {
+        let num_samples = mono_samples.len();
+        let cache_start = (self.tail_index as i64) - (self.audio_cache.len() as i64);
+        let expected_start =
+            (sample_index as i64) + (num_samples as i64) - (self.requested_sample_count as i64);
+
+        if (sample_index as i64) <= cache_start
+            || (self.tail_index as i64) < expected_start
+            || (self.tail_index < sample_index)
+            || expected_start < cache_start
+        {
+            tracing::info!(
+                "Cache reset: sample_index={}, tail_index={}, cache_start={}, expected_start={}",
+                sample_index,
+                self.tail_index,
+                cache_start,
+                expected_start,
+            );
+            self.reset_history(sample_index);
+        }
+
+        let last_index = sample_index + num_samples;
+        let uncached_samples = last_index.saturating_sub(self.tail_index);
+        if uncached_samples > 0 {
+            self.audio_cache.extend(
+                mono_samples
+                    .iter()
+                    .skip(num_samples - uncached_samples)
+                    .take(uncached_samples)
+                    .copied(),
+            );
+            self.tail_index += uncached_samples;
+        }
+
+        let cache_start = (self.tail_index as i64) - (self.audio_cache.len() as i64);
+        let mut samples = std::mem::take(&mut self.samples);
+        samples.clear();
+        samples.extend(
+            self.audio_cache
+                .iter()
+                .skip((expected_start - cache_start) as usize)
+                .take(self.requested_sample_count)
+                .copied(),
+        );
+
+        let result = self
+            .process(&samples, gain, rotate_yaw, rotate_pitch)
+            .map(|_| ());
+        self.samples = samples;
+        result?;
+        Ok((
+            &self.left[(self.left.len() - num_samples)..],
+            &self.right[(self.right.len() - num_samples)..],
+        ))
+    }
+
+    pub fn process(
         &mut self,
         audio: &[f32],
         gain: f32,
         rotate_yaw: f64,
         rotate_pitch: f64,
-    ) -> anyhow::Result<(Vec<f32>, Vec<f32>)> {
+    ) -> anyhow::Result<(&[f32], &[f32])> {
         assert_eq!(audio.len(), self.requested_sample_count);
         // NOTE: 17.0はおまじない
         let radians_yaw = (rotate_yaw + 17.0).to_radians();
@@ -135,13 +250,13 @@ impl BinauralStates {
             (1.0 * radians_pitch.sin()) as f32,
             (1.0 * radians_pitch.cos() * radians_yaw.cos()) as f32,
         );
-        let mut source = vec![0.0f32; self.block_size * self.num_blocks];
-        linear_resample(audio, &mut source[..]);
-        let mut output = vec![(0.0, 0.0); self.block_size * self.num_blocks];
+        linear_resample(audio, &mut self.source);
+        // HrtfProcessorは出力に加算するので、毎回クリアする
+        self.output.fill((0.0, 0.0));
 
         let context = hrtf::HrtfContext {
-            source: &source,
-            output: &mut output,
+            source: &self.source,
+            output: &mut self.output,
             new_sample_vector: hrtf::Vec3 { x, y, z },
             prev_sample_vector: hrtf::Vec3 { x, y, z },
             prev_left_samples: &mut self.prev_left_samples,
@@ -152,18 +267,26 @@ impl BinauralStates {
 
         self.hrtf.process_samples(context);
 
-        let (left_44100, right_44100): (Vec<f32>, Vec<f32>) = output.into_iter().unzip();
-        let mut left = vec![0.0; audio.len()];
-        let mut right = vec![0.0; audio.len()];
-        linear_resample(&left_44100, &mut left);
-        linear_resample(&right_44100, &mut right);
-        Ok((left, right))
+        for ((left, right), &(l, r)) in self
+            .left_44100
+            .iter_mut()
+            .zip(self.right_44100.iter_mut())
+            .zip(&self.output)
+        {
+            *left = l;
+            *right = r;
+        }
+        self.left.resize(audio.len(), 0.0);
+        self.right.resize(audio.len(), 0.0);
+        linear_resample(&self.left_44100, &mut self.left);
+        linear_resample(&self.right_44100, &mut self.right);
+        Ok((&self.left, &self.right))
     }
 }
 
 #[aviutl2::plugin(FilterPlugin)]
 struct BinauralFilter {
-    states: dashmap::DashMap<i64, BinauralStates>,
+    states: aviutl2::filter::ObjectStateMap<BinauralStates>,
 }
 
 impl aviutl2::filter::FilterPlugin for BinauralFilter {
@@ -178,7 +301,7 @@ impl aviutl2::filter::FilterPlugin for BinauralFilter {
             .with_writer(aviutl2::logger::AviUtl2LogWriter)
             .init();
         Ok(Self {
-            states: dashmap::DashMap::new(),
+            states: aviutl2::filter::ObjectStateMap::new(),
         })
     }
 
@@ -186,6 +309,7 @@ impl aviutl2::filter::FilterPlugin for BinauralFilter {
         aviutl2::filter::FilterPluginTable {
             name: "Rusty Binaural Filter".to_string(),
             label: None,
+            order: 0,
             information: format!(
                 "Binaural filter, powered by hrtf crate, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/equalizer-filter",
                 version = env!("CARGO_PKG_VERSION")
@@ -201,14 +325,13 @@ impl aviutl2::filter::FilterPlugin for BinauralFilter {
         audio: &mut aviutl2::filter::FilterProcAudio,
     ) -> anyhow::Result<()> {
         let config: FilterConfig = config.to_struct();
-        let obj_id = audio.object.effect_id;
 
         let num_samples = audio.audio_object.sample_num as usize;
         if num_samples == 0 {
             tracing::warn!("num_samples is zero");
             return Ok(());
         }
-        let mut states = self.states.entry(obj_id).or_try_insert_with(|| {
+        let mut states = self.states.get_or_try_insert_with(&audio.object, || {
             BinauralStates::new(num_samples, audio.scene.sample_rate as f64)
         })?;
         if (((states.requested_sample_count as f32) * (3.0 / 4.0)) as usize) < num_samples {
@@ -219,72 +342,55 @@ impl aviutl2::filter::FilterPlugin for BinauralFilter {
             );
             *states = BinauralStates::new(num_samples, audio.scene.sample_rate as f64)?;
         }
-        let mut left_samples = vec![0.0f32; num_samples];
-        let mut right_samples = vec![0.0f32; num_samples];
-        audio.get_sample_data(aviutl2::filter::AudioChannel::Left, &mut left_samples);
-        audio.get_sample_data(aviutl2::filter::AudioChannel::Right, &mut right_samples);
-
-        let cache_start = (states.tail_index as i64) - (states.audio_cache.len() as i64);
-        let expected_start = (audio.audio_object.sample_index as i64) + (num_samples as i64)
-            - (states.requested_sample_count as i64);
-
-        if (audio.audio_object.sample_index as i64) <= cache_start
-            || (states.tail_index as i64) < expected_start
-            || (states.tail_index < audio.audio_object.sample_index as usize)
-            || expected_start < cache_start
-        {
-            tracing::info!(
-                "Cache reset: sample_index={}, tail_index={}, cache_start={}, expected_start={}",
-                audio.audio_object.sample_index,
-                states.tail_index,
-                cache_start,
-                expected_start,
-            );
-            let cache_length = states.audio_cache.len();
-            states.tail_index = audio.audio_object.sample_index as usize;
-            states.audio_cache.clear();
-            states.audio_cache.extend((0..cache_length).map(|_| 0.0));
+        let states = &mut *states;
+        states.left_samples.resize(num_samples, 0.0);
+        states.right_samples.resize(num_samples, 0.0);
+        let is_stereo = audio.channel_count() >= 2;
+        audio.get_sample_data(
+            aviutl2::filter::AudioChannel::Left,
+            &mut states.left_samples,
+        )?;
+        if is_stereo {
+            audio.get_sample_data(
+                aviutl2::filter::AudioChannel::Right,
+                &mut states.right_samples,
+            )?;
+        } else {
+            states.right_samples.copy_from_slice(&states.left_samples);
         }
 
-        let mono_samples: Vec<f32> = left_samples
-            .iter()
-            .zip(right_samples.iter())
-            .map(|(l, r)| 0.5 * (l + r))
-            .collect();
-        let last_index = (audio.audio_object.sample_index as usize) + num_samples;
-        let uncached_samples = last_index.saturating_sub(states.tail_index);
-        if uncached_samples > 0 {
-            states.audio_cache.extend(
-                mono_samples
-                    .iter()
-                    .skip(num_samples - uncached_samples)
-                    .take(uncached_samples)
-                    .copied(),
-            );
-            states.tail_index += uncached_samples;
+        // 左チャンネルのバッファをモノラルのサンプルとして使う
+        for (l, r) in states
+            .left_samples
+            .iter_mut()
+            .zip(states.right_samples.iter())
+        {
+            *l = 0.5 * (*l + r);
+        }
+        let mono_samples = std::mem::take(&mut states.left_samples);
+        let result = states
+            .process_frame(
+                audio.audio_object.sample_index as usize,
+                &mono_samples,
+                config.gain,
+                config.rotate_yaw,
+                config.rotate_pitch,
+            )
+            .map(|_| ());
+        states.left_samples = mono_samples;
+        result?;
+        let new_left = &states.left[(states.left.len() - num_samples)..];
+        let new_right = &states.right[(states.right.len() - num_samples)..];
+        if is_stereo {
+            audio.set_sample_data(aviutl2::filter::AudioChannel::Left, new_left)?;
+            audio.set_sample_data(aviutl2::filter::AudioChannel::Right, new_right)?;
+        } else {
+            let new_mono = &mut states.left_samples;
+            for (mono, (l, r)) in new_mono.iter_mut().zip(new_left.iter().zip(new_right)) {
+                *mono = 0.5 * (l + r);
+            }
+            audio.set_sample_data(aviutl2::filter::AudioChannel::Left, new_mono)?;
         }
-
-        let cache_start = (states.tail_index as i64) - (states.audio_cache.len() as i64);
-        let expected_start = (audio.audio_object.sample_index as i64) + (num_samples as i64)
-            - (states.requested_sample_count as i64);
-        let samples = states
-            .audio_cache
-            .iter()
-            .skip((expected_start - cache_start) as usize)
-            .take(states.requested_sample_count)
-            .copied()
-            .collect::<Vec<_>>();
-
-        let (new_left, new_right) = states.process(
-            &samples,
-            config.gain,
-            config.rotate_yaw,
-            config.rotate_pitch,
-        )?;
-        let new_left = &new_left[(new_left.len() - num_samples)..];
-        let new_right = &new_right[(new_right.len() - num_samples)..];
-        audio.set_sample_data(aviutl2::filter::AudioChannel::Left, new_left);
-        audio.set_sample_data(aviutl2::filter::AudioChannel::Right, new_right);
 
         Ok(())
     }
@@ -298,4 +404,80 @@ fn next_pow2(n: usize) -> usize {
     m
 }
 
+#[cfg(not(feature = "no-entrypoint"))]
 aviutl2::register_filter_plugin!(BinauralFilter);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f64 = 48000.0;
+    const FRAME_SIZE: usize = 800;
+
+    fn noise(len: usize) -> Vec<f32> {
+        let mut state = 0x1234_5678_u32;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 8) as f32 / (1 << 23) as f32 - 1.0
+            })
+            .collect()
+    }
+
+    /// `expected`に対する`actual`の誤差（dB）。
+    fn error_db(expected: &[f32], actual: &[f32]) -> f64 {
+        let peak = expected.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        let error = expected
+            .iter()
+            .zip(actual)
+            .fold(0.0f32, |max, (e, a)| max.max((e - a).abs()));
+        20.0 * (error as f64 / peak as f64).max(1e-12).log10()
+    }
+
+    /// `frames`の範囲のフレームを順に処理し、左右の音声を返す。
+    fn render(
+        states: &mut BinauralStates,
+        input: &[f32],
+        frames: std::ops::Range<usize>,
+    ) -> (Vec<f32>, Vec<f32>) {
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        for frame in frames {
+            let start = frame * FRAME_SIZE;
+            let (l, r) = states
+                .process_frame(start, &input[start..(start + FRAME_SIZE)], 2.0, 30.0, 10.0)
+                .unwrap();
+            left.extend_from_slice(l);
+            right.extend_from_slice(r);
+        }
+        (left, right)
+    }
+
+    #[test]
+    fn test_warm_up_matches_continuous() {
+        let input = noise(FRAME_SIZE * 1100);
+        let start = FRAME_SIZE * 1000;
+
+        // 0フレーム目から続けて処理する
+        let mut states = BinauralStates::new(FRAME_SIZE, SAMPLE_RATE).unwrap();
+        let (expected_left, expected_right) = render(&mut states, &input, 0..1100);
+        let (expected_left, expected_right) = (&expected_left[start..], &expected_right[start..]);
+
+        // 1000フレーム目から処理する
+        let mut states = BinauralStates::new(FRAME_SIZE, SAMPLE_RATE).unwrap();
+        let warmup_samples = states.warmup_samples();
+        states.warm_up(start, &input[(start - warmup_samples)..start]);
+        let (left, right) = render(&mut states, &input, 1000..1100);
+        for error in [
+            error_db(expected_left, &left),
+            error_db(expected_right, &right),
+        ] {
+            assert!(error < -60.0, "error with warm-up: {error}dB");
+        }
+
+        let mut states = BinauralStates::new(FRAME_SIZE, SAMPLE_RATE).unwrap();
+        let (left, _) = render(&mut states, &input, 1000..1100);
+        let error = error_db(expected_left, &left);
+        assert!(error > -60.0, "error without warm-up: {error}dB");
+    }
+}
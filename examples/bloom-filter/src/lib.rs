@@ -0,0 +1,272 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use aviutl2::{
+    AnyResult, AviUtl2Info,
+    filter::{
+        FilterConfigItemSliceExt, FilterConfigItems, FilterPlugin, FilterPluginFlags,
+        FilterPluginTable, FilterProcVideo, FramePool, RgbaPixel, ops,
+    },
+    tracing,
+};
+
+#[aviutl2::filter::filter_config_items]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterConfig {
+    #[track(name = "しきい値", range = 0.0..=1.0, step = 0.01, default = 0.6)]
+    pub threshold: f64,
+    #[track(name = "半径", range = 0.0..=200.0, step = 1.0, default = 24.0)]
+    pub radius: f64,
+    #[track(name = "強さ", range = 0.0..=4.0, step = 0.01, default = 1.0)]
+    pub intensity: f64,
+    #[track(name = "縮小回数", range = 0..=4, step = 1.0, default = 2)]
+    pub downscale: f64,
+}
+
+/// ぼかしを繰り返す回数。3回でほぼガウスぼかしになる。
+const BLUR_PASSES: u32 = 3;
+
+#[aviutl2::plugin(FilterPlugin)]
+struct BloomFilter {
+    /// ログに出力した中間バッファの最大使用量。
+    logged_peak_bytes: AtomicUsize,
+}
+
+impl FilterPlugin for BloomFilter {
+    fn new(_info: AviUtl2Info) -> AnyResult<Self> {
+        aviutl2::tracing_subscriber::fmt()
+            .with_max_level(if cfg!(debug_assertions) {
+                tracing::Level::DEBUG
+            } else {
+                tracing::Level::INFO
+            })
+            .event_format(aviutl2::logger::AviUtl2Formatter)
+            .with_writer(aviutl2::logger::AviUtl2LogWriter)
+            .init();
+        Ok(Self {
+            logged_peak_bytes: AtomicUsize::new(0),
+        })
+    }
+
+    fn plugin_info(&self) -> FilterPluginTable {
+        FilterPluginTable {
+            name: "Rusty Bloom Filter".to_string(),
+            label: None,
+            order: 0,
+            information: format!(
+                "Bloom filter, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/bloom-filter",
+                version = env!("CARGO_PKG_VERSION")
+            ),
+            flags: aviutl2::bitflag!(FilterPluginFlags {
+                video: true,
+                filter: true,
+            }),
+            config_items: FilterConfig::to_config_items(),
+        }
+    }
+
+    fn proc_video(
+        &self,
+        config: &[aviutl2::filter::FilterConfigItem],
+        video: &mut FilterProcVideo,
+    ) -> AnyResult<()> {
+        let config: FilterConfig = config.to_struct();
+        let (width, height) = video.image_size();
+        if width == 0 || height == 0 || config.intensity == 0.0 {
+            return Ok(());
+        }
+        let pool = video.frame_pool();
+        let mut image = pool.acquire::<RgbaPixel>(width, height);
+        video.get_image_data(&mut image);
+        apply_bloom(&config, &pool, &mut image, width, height);
+        video.set_image_data(&image, width, height);
+
+        if cfg!(debug_assertions) {
+            let stats = pool.stats();
+            if self
+                .logged_peak_bytes
+                .fetch_max(stats.peak_bytes, Ordering::Relaxed)
+                < stats.peak_bytes
+            {
+                tracing::debug!(
+                    "frame pool grew: {} buffers, {} bytes (peak {} buffers, {} bytes)",
+                    stats.buffers,
+                    stats.bytes,
+                    stats.peak_buffers,
+                    stats.peak_bytes
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 画像にブルームをかける。中間バッファは`pool`から借りる。
+pub fn apply_bloom(
+    config: &FilterConfig,
+    pool: &FramePool,
+    image: &mut [RgbaPixel],
+    width: u32,
+    height: u32,
+) {
+    let mut source = pool.acquire::<[f32; 4]>(width, height);
+    ops::rgba8_to_premultiplied(image, &mut source);
+
+    let mut bright = pool.acquire::<[f32; 4]>(width, height);
+    extract_bright(&source, &mut bright, config.threshold as f32);
+
+    // 1/2ずつ縮小する。バイリニア補間で一気に縮小すると読み飛ばすピクセルが出るため
+    let mut small = bright;
+    let (mut small_width, mut small_height) = (width, height);
+    for _ in 0..config.downscale as u32 {
+        if small_width == 1 && small_height == 1 {
+            break;
+        }
+        let (next_width, next_height) = (small_width.div_ceil(2), small_height.div_ceil(2));
+        let mut next = pool.acquire::<[f32; 4]>(next_width, next_height);
+        ops::resize_bilinear(
+            &small,
+            small_width,
+            small_height,
+            &mut next,
+            next_width,
+            next_height,
+        );
+        (small, small_width, small_height) = (next, next_width, next_height);
+    }
+
+    let scale = width as f64 / small_width as f64;
+    let pass_radius = (config.radius / scale / BLUR_PASSES as f64).round() as u32;
+    if pass_radius > 0 {
+        let mut scratch = pool.acquire::<[f32; 4]>(small_width, small_height);
+        for _ in 0..BLUR_PASSES {
+            ops::box_blur(
+                &mut small,
+                &mut scratch,
+                small_width,
+                small_height,
+                pass_radius,
+            );
+        }
+    }
+
+    let mut glow = pool.acquire::<[f32; 4]>(width, height);
+    ops::resize_bilinear(&small, small_width, small_height, &mut glow, width, height);
+    drop(small);
+    let intensity = config.intensity as f32;
+    for pixel in glow.iter_mut() {
+        let [r, g, b, a] = *pixel;
+        *pixel = [
+            r * intensity,
+            g * intensity,
+            b * intensity,
+            (a * intensity).min(1.0),
+        ];
+    }
+
+    ops::over(&mut source, &glow);
+    ops::premultiplied_to_rgba8(&source, image);
+}
+
+/// しきい値より明るい部分を取り出す。しきい値付近はなめらかに薄くする。
+fn extract_bright(source: &[[f32; 4]], bright: &mut [[f32; 4]], threshold: f32) {
+    let knee = (1.0 - threshold).max(1e-3);
+    for (src, dst) in source.iter().zip(bright.iter_mut()) {
+        let alpha = src[3];
+        let luminance = if alpha > 0.0 {
+            (0.2126 * src[0] + 0.7152 * src[1] + 0.0722 * src[2]) / alpha
+        } else {
+            0.0
+        };
+        let weight = ((luminance - threshold) / knee).clamp(0.0, 1.0);
+        *dst = src.map(|value| value * weight);
+    }
+}
+
+#[cfg(not(feature = "no-entrypoint"))]
+aviutl2::register_filter_plugin!(BloomFilter);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> FilterConfig {
+        FilterConfig {
+            threshold: 0.5,
+            radius: 6.0,
+            intensity: 1.0,
+            downscale: 1.0,
+        }
+    }
+
+    fn image_with_dot(width: u32, height: u32) -> Vec<RgbaPixel> {
+        let mut image = vec![
+            RgbaPixel {
+                r: 0,
+                g: 0,
+                b: 0,
+                a: 255
+            };
+            (width * height) as usize
+        ];
+        let center = (height / 2 * width + width / 2) as usize;
+        image[center] = RgbaPixel {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 255,
+        };
+        image
+    }
+
+    #[test]
+    fn test_dark_image_is_unchanged() {
+        let pool = FramePool::new();
+        let original = vec![
+            RgbaPixel {
+                r: 40,
+                g: 80,
+                b: 20,
+                a: 200
+            };
+            64
+        ];
+        let mut image = original.clone();
+        apply_bloom(&config(), &pool, &mut image, 8, 8);
+        assert_eq!(image, original);
+    }
+
+    #[test]
+    fn test_bright_pixels_spread() {
+        let pool = FramePool::new();
+        let (width, height) = (32, 32);
+        let mut image = image_with_dot(width, height);
+        let config = FilterConfig {
+            intensity: 4.0,
+            ..config()
+        };
+        apply_bloom(&config, &pool, &mut image, width, height);
+
+        let at = |x: u32, y: u32| image[(y * width + x) as usize];
+        assert_eq!(at(16, 16).r, 255);
+        // 近くは明るくなり、遠くはそのまま
+        assert!(at(18, 16).r > 0, "{:?}", at(18, 16));
+        assert!(at(16, 13).g > 0, "{:?}", at(16, 13));
+        assert!(at(17, 17).r >= at(19, 19).r);
+        assert_eq!(at(0, 0).r, 0);
+        assert!(image.iter().all(|pixel| pixel.a == 255));
+    }
+
+    #[test]
+    fn test_reuses_pool_buffers() {
+        let pool = FramePool::new();
+        let (width, height) = (40, 30);
+        for _ in 0..3 {
+            let mut image = image_with_dot(width, height);
+            apply_bloom(&config(), &pool, &mut image, width, height);
+        }
+        let stats = pool.stats();
+        assert_eq!(stats.buffers_in_use, 0);
+        assert_eq!(stats.allocations as usize, stats.buffers);
+        assert!(stats.reuses > 0);
+    }
+}
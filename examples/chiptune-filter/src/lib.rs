@@ -20,35 +20,62 @@ enum WaveType {
     Noise,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, aviutl2::filter::FilterConfigSelectItems)]
-enum FrequencyMode {
-    #[item(name = "MIDIノート")]
-    MidiNote,
-    #[item(name = "周波数（Hz）")]
-    FrequencyHz,
-}
-
 #[aviutl2::filter::filter_config_items]
 #[derive(Debug, Clone, PartialEq)]
-struct FilterConfig {
-    #[track(name = "音量", range = 0.0..=1.0, step = 0.01, default = 0.5)]
-    volume: f64,
-    #[select(
-        name = "音源",
-        items = WaveType,
-        default = WaveType::Square
-    )]
-    wave_type: WaveType,
-    #[select(
-        name = "周波数モード",
-        items = FrequencyMode,
-        default = FrequencyMode::MidiNote
-    )]
-    freq_mode: FrequencyMode,
-    #[track(name = "MIDIノート", range = 0..=127, step = 1.0, default = 60)]
-    midi_note: f64,
-    #[track(name = "周波数（Hz）", range = 20.0..=20000.0, step = 1.0, default = 440.0)]
-    frequency: f64,
+#[select(name = "周波数モード")]
+enum FilterConfig {
+    #[variant(name = "MIDIノート")]
+    MidiNote {
+        #[track(name = "音量", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+        volume: f64,
+        #[select(
+            name = "音源",
+            items = WaveType,
+            default = WaveType::Square
+        )]
+        wave_type: WaveType,
+        #[track(name = "MIDIノート", range = 0..=127, step = 1.0, default = 60)]
+        midi_note: f64,
+    },
+    #[variant(name = "周波数（Hz）")]
+    FrequencyHz {
+        #[track(name = "音量", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+        volume: f64,
+        #[select(
+            name = "音源",
+            items = WaveType,
+            default = WaveType::Square
+        )]
+        wave_type: WaveType,
+        #[track(name = "周波数（Hz）", range = 20.0..=20000.0, step = 1.0, default = 440.0)]
+        frequency: f64,
+    },
+}
+
+impl FilterConfig {
+    fn volume(&self) -> f64 {
+        match self {
+            FilterConfig::MidiNote { volume, .. } | FilterConfig::FrequencyHz { volume, .. } => {
+                *volume
+            }
+        }
+    }
+
+    fn wave_type(&self) -> &WaveType {
+        match self {
+            FilterConfig::MidiNote { wave_type, .. }
+            | FilterConfig::FrequencyHz { wave_type, .. } => wave_type,
+        }
+    }
+
+    fn frequency(&self) -> f64 {
+        match self {
+            FilterConfig::MidiNote { midi_note, .. } => {
+                440.0 * 2.0f64.powf((midi_note - 69.0) / 12.0)
+            }
+            FilterConfig::FrequencyHz { frequency, .. } => *frequency,
+        }
+    }
 }
 
 struct Synthesizer {
@@ -62,15 +89,13 @@ impl Synthesizer {
 
 #[aviutl2::plugin(FilterPlugin)]
 struct ChiptuneFilter {
-    synthesizers: std::sync::RwLock<
-        std::collections::HashMap<i64, std::sync::Arc<std::sync::Mutex<Synthesizer>>>,
-    >,
+    synthesizers: aviutl2::filter::ObjectStateMap<Synthesizer>,
 }
 
 impl FilterPlugin for ChiptuneFilter {
     fn new(_info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
         Ok(Self {
-            synthesizers: std::sync::RwLock::new(std::collections::HashMap::new()),
+            synthesizers: aviutl2::filter::ObjectStateMap::new(),
         })
     }
 
@@ -78,6 +103,7 @@ impl FilterPlugin for ChiptuneFilter {
         FilterPluginTable {
             name: "Rusty Chiptune Filter".to_string(),
             label: None,
+            order: 0,
             information: format!(
                 "Example chiptune synthesizer, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/chiptune-filter",
                 version = env!("CARGO_PKG_VERSION")
@@ -97,35 +123,20 @@ impl FilterPlugin for ChiptuneFilter {
     ) -> AnyResult<()> {
         let config: FilterConfig = config.to_struct();
 
-        let synthesizer = {
-            let synthesizers = self.synthesizers.read().unwrap();
-            synthesizers.get(&audio.object.id).cloned()
-        };
-        let synthesizer = if let Some(synthesizer) = synthesizer {
-            synthesizer
-        } else {
-            let new_synthesizer = std::sync::Arc::new(std::sync::Mutex::new(Synthesizer::new()));
-            let mut synthesizers = self.synthesizers.write().unwrap();
-            synthesizers.insert(audio.object.id, new_synthesizer.clone());
-            new_synthesizer
-        };
-
-        let mut synthesizer = synthesizer.lock().unwrap();
+        let mut synthesizer = self
+            .synthesizers
+            .get_or_insert_with(&audio.object, Synthesizer::new);
 
         let sample_rate = audio.scene.sample_rate as f64;
         let sample_num = audio.audio_object.sample_num as usize;
-        let frequency = if config.freq_mode == FrequencyMode::MidiNote {
-            440.0 * 2.0f64.powf((config.midi_note - 69.0) / 12.0)
-        } else {
-            config.frequency
-        };
+        let frequency = config.frequency();
+        let volume = config.volume();
 
-        let mut left = vec![0.0; sample_num];
-        let mut right = vec![0.0; sample_num];
+        let mut samples = vec![0.0; sample_num];
 
         let mut phase = synthesizer.phase;
         for i in 0..sample_num {
-            let value = match config.wave_type {
+            let value = match config.wave_type() {
                 WaveType::Square => {
                     if phase < 0.5 {
                         1.0
@@ -142,10 +153,15 @@ impl FilterPlugin for ChiptuneFilter {
                 }
                 WaveType::Sawtooth => phase * 2.0 - 1.0,
                 WaveType::Sine => (phase * 2.0 * std::f64::consts::PI).sin(),
-                WaveType::Noise => rand::random::<f64>() * 2.0 - 1.0,
+                // サンプル位置ごとに決まった値にして、シークしても同じ波形になるようにする
+                WaveType::Noise => aviutl2::filter::FrameRng::new(
+                    audio.object.id,
+                    audio.audio_object.sample_index + i as u64,
+                    0,
+                )
+                .range(-1.0..1.0),
             };
-            left[i] = (value * config.volume) as f32;
-            right[i] = (value * config.volume) as f32;
+            samples[i] = (value * volume) as f32;
 
             phase += frequency / sample_rate;
             if phase >= 1.0 {
@@ -155,8 +171,9 @@ impl FilterPlugin for ChiptuneFilter {
 
         synthesizer.phase = phase;
 
-        audio.set_sample_data(aviutl2::filter::AudioChannel::Left, &left);
-        audio.set_sample_data(aviutl2::filter::AudioChannel::Right, &right);
+        for channel in 0..audio.channel_count() {
+            audio.set_sample_data_ch(channel, &samples)?;
+        }
 
         Ok(())
     }
@@ -1,4 +1,15 @@
-use biquad::{Biquad, ToHertz};
+use rustfft::num_complex::Complex64;
+
+/// `chunks_exact`で処理するサンプル数。
+///
+/// 出力とフィルタの状態を合わせて、f32が16個（SSEのレジスタ4本分）になるようにしています。
+const CHUNK_SIZE: usize = 6;
+
+/// フィルタの数。低音・中音・高音・ローパス・ハイパスの順に並べる。
+const FILTER_COUNT: usize = 5;
+
+/// フィルタの状態の数。1つのフィルタにつき`ic1`・`ic2`の2つ。
+const STATE_COUNT: usize = FILTER_COUNT * 2;
 
 pub struct EqState {
     bass: PeakEq,
@@ -10,10 +21,13 @@ pub struct EqState {
     wet: f64,
     lopass_enable: bool,
     hipass_enable: bool,
+
+    cascade: Cascade,
+    states: [f32; STATE_COUNT],
 }
 impl EqState {
     pub fn new(sample_rate: f64, config: &crate::FilterConfig) -> Self {
-        Self {
+        let mut state = Self {
             bass: PeakEq::new(config.bass_freq, config.bass_gain, sample_rate),
             mid: PeakEq::new(config.mid_freq, config.mid_gain, sample_rate),
             treble: PeakEq::new(config.treble_freq, config.treble_gain, sample_rate),
@@ -23,7 +37,12 @@ impl EqState {
             wet: config.wet,
             lopass_enable: config.lopass_enable,
             hipass_enable: config.hipass_enable,
-        }
+
+            cascade: Cascade::new([None; FILTER_COUNT], 1.0),
+            states: [0.0; STATE_COUNT],
+        };
+        state.update_cascade();
+        state
     }
 
     pub fn update_params(&mut self, sample_rate: f64, config: &crate::FilterConfig) {
@@ -39,159 +58,600 @@ impl EqState {
         self.wet = config.wet;
         self.lopass_enable = config.lopass_enable;
         self.hipass_enable = config.hipass_enable;
+        self.update_cascade();
     }
 
-    pub fn process(&mut self, samples: &mut [f64]) {
-        for sample in samples.iter_mut() {
-            let mut s = *sample;
-            let orig = s;
-            if self.bass.gain != 0.0 {
-                s = self.bass.apply(s);
-            }
-            if self.mid.gain != 0.0 {
-                s = self.mid.apply(s);
-            }
-            if self.treble.gain != 0.0 {
-                s = self.treble.apply(s);
-            }
-            if self.lopass_enable {
-                s = self.lopass.apply(s);
-            }
-            if self.hipass_enable {
-                s = self.hipass.apply(s);
+    fn update_cascade(&mut self) {
+        let filters = [
+            (self.bass.gain != 0.0).then_some(&self.bass.prototype),
+            (self.mid.gain != 0.0).then_some(&self.mid.prototype),
+            (self.treble.gain != 0.0).then_some(&self.treble.prototype),
+            self.lopass_enable.then_some(&self.lopass.prototype),
+            self.hipass_enable.then_some(&self.hipass.prototype),
+        ]
+        .map(|prototype| prototype.map(SvfPrototype::coefficients));
+        self.cascade = Cascade::new(filters, self.wet);
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        if self.cascade.filters.iter().all(Option::is_none) {
+            return;
+        }
+        let mut chunks = samples.chunks_exact_mut(CHUNK_SIZE);
+        for chunk in &mut chunks {
+            let chunk: &mut [f32; CHUNK_SIZE] = chunk.try_into().unwrap();
+            *chunk = self.cascade.process_chunk(&mut self.states, chunk);
+        }
+        let remainder = chunks.into_remainder();
+        if !remainder.is_empty() {
+            let mut states = self.states.map(f64::from);
+            for sample in remainder {
+                *sample = self.cascade.process_sample(&mut states, *sample as f64) as f32;
             }
-            *sample = s * self.wet + orig * (1.0 - self.wet);
+            self.states = states.map(|state| state as f32);
         }
     }
 
     pub fn reset(&mut self) {
-        self.bass.filter.reset_state();
-        self.mid.filter.reset_state();
-        self.treble.filter.reset_state();
-        self.lopass.filter.reset_state();
-        self.hipass.filter.reset_state();
+        self.states = [0.0; STATE_COUNT];
+    }
+
+    /// [`Self::warm_up`]に渡すと十分な、直前の音声のサンプル数。
+    #[cfg(test)]
+    fn warmup_samples(sample_rate: f64) -> usize {
+        (sample_rate * WARMUP_SECONDS).ceil() as usize
+    }
+
+    /// 直前の音声を処理して、フィルタの状態を途中から処理を始める位置に合わせる。
+    ///
+    /// `preceding`が[`Self::warmup_samples`]個あれば、最初から続けて処理した場合との差は-60dB以下になります。
+    /// フィルタには直前の音声が渡されないため、今はテストでのみ使っています。
+    #[cfg(test)]
+    fn warm_up(&mut self, preceding: &[f32]) {
+        self.reset();
+        let mut scratch = preceding.to_vec();
+        self.process(&mut scratch);
     }
 }
+
+/// 状態の影響が-60dB以下に減衰するまでの時間。
+///
+/// 一番減衰が遅いのは20Hz・+15dBのピーキングで、およそ0.19秒です。
+#[cfg(test)]
+const WARMUP_SECONDS: f64 = 0.5;
+
 pub struct PeakEq {
     sample_rate: f64,
     freq: f64,
     gain: f64,
 
-    filter: biquad::DirectForm1<f64>,
+    prototype: SvfPrototype,
 }
 impl PeakEq {
     fn new(freq: f64, gain: f64, sample_rate: f64) -> Self {
-        let coeffs = biquad::Coefficients::<f64>::from_params(
-            biquad::Type::PeakingEQ(gain),
-            sample_rate.hz(),
-            freq.hz(),
-            Q,
-        )
-        .unwrap();
-        let filter = biquad::DirectForm1::<f64>::new(coeffs);
         Self {
             freq,
             gain,
             sample_rate,
-            filter,
+            prototype: SvfPrototype::peaking_eq(freq, gain, sample_rate),
         }
     }
 
     fn set_params(&mut self, freq: f64, gain: f64, sample_rate: f64) {
-        let coeffs = biquad::Coefficients::<f64>::from_params(
-            biquad::Type::PeakingEQ(gain),
-            sample_rate.hz(),
-            freq.hz(),
-            Q,
-        )
-        .unwrap();
-        self.filter.update_coefficients(coeffs);
+        self.prototype = SvfPrototype::peaking_eq(freq, gain, sample_rate);
         self.freq = freq;
         self.gain = gain;
         self.sample_rate = sample_rate;
     }
-
-    fn apply(&mut self, sample: f64) -> f64 {
-        self.filter.run(sample)
-    }
 }
 pub struct LowPass {
     sample_rate: f64,
     freq: f64,
-    filter: biquad::DirectForm1<f64>,
+    prototype: SvfPrototype,
 }
 impl LowPass {
     fn new(freq: f64, sample_rate: f64) -> Self {
-        let coeffs = biquad::Coefficients::<f64>::from_params(
-            biquad::Type::LowPass,
-            sample_rate.hz(),
-            freq.hz(),
-            Q,
-        )
-        .unwrap();
-        let filter = biquad::DirectForm1::<f64>::new(coeffs);
         Self {
             freq,
             sample_rate,
-            filter,
+            prototype: SvfPrototype::low_pass(freq, sample_rate),
         }
     }
 
     fn set_params(&mut self, freq: f64, sample_rate: f64) {
-        let coeffs = biquad::Coefficients::<f64>::from_params(
-            biquad::Type::LowPass,
-            sample_rate.hz(),
-            freq.hz(),
-            Q,
-        )
-        .unwrap();
-        self.filter.update_coefficients(coeffs);
+        self.prototype = SvfPrototype::low_pass(freq, sample_rate);
         self.freq = freq;
         self.sample_rate = sample_rate;
     }
-
-    fn apply(&mut self, sample: f64) -> f64 {
-        self.filter.run(sample)
-    }
 }
 pub struct HighPass {
     sample_rate: f64,
     freq: f64,
-    filter: biquad::DirectForm1<f64>,
+    prototype: SvfPrototype,
 }
 impl HighPass {
     fn new(freq: f64, sample_rate: f64) -> Self {
-        let coeffs = biquad::Coefficients::<f64>::from_params(
-            biquad::Type::HighPass,
-            sample_rate.hz(),
-            freq.hz(),
-            Q,
-        )
-        .unwrap();
-        let filter = biquad::DirectForm1::<f64>::new(coeffs);
         Self {
             freq,
             sample_rate,
-            filter,
+            prototype: SvfPrototype::high_pass(freq, sample_rate),
         }
     }
 
     fn set_params(&mut self, freq: f64, sample_rate: f64) {
-        let coeffs = biquad::Coefficients::<f64>::from_params(
-            biquad::Type::HighPass,
-            sample_rate.hz(),
-            freq.hz(),
-            Q,
-        )
-        .unwrap();
-        self.filter.update_coefficients(coeffs);
+        self.prototype = SvfPrototype::high_pass(freq, sample_rate);
         self.freq = freq;
         self.sample_rate = sample_rate;
     }
+}
+
+/// 双二次フィルタの係数。
+///
+/// RBJのAudio EQ Cookbookと同じ伝達関数を、台形積分の状態変数フィルタ（SVF）で表したものです。
+/// 極が`z = 1`に近い低い周波数でも、直接形と違ってf32で精度が落ちにくくなっています。
+#[derive(Debug, Clone, Copy)]
+struct SvfCoefficients {
+    a1: f64,
+    a2: f64,
+    a3: f64,
+    m: [f64; 3],
+}
+impl SvfCoefficients {
+    fn new(g: f64, k: f64, m: [f64; 3]) -> Self {
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        Self { a1, a2, a3, m }
+    }
+
+    /// 1サンプル処理する。`state`は`[ic1, ic2]`。
+    fn process_sample(&self, state: &mut [f64], v0: f64) -> f64 {
+        let Self { a1, a2, a3, m } = *self;
+        let [ic1, ic2] = [state[0], state[1]];
+        let v3 = v0 - ic2;
+        let v1 = a1 * ic1 + a2 * v3;
+        let v2 = ic2 + a2 * ic1 + a3 * v3;
+        state[0] = 2.0 * v1 - ic1;
+        state[1] = 2.0 * v2 - ic2;
+        m[0] * v0 + m[1] * v1 + m[2] * v2
+    }
+}
+
+/// 状態変数フィルタのパラメータ。
+///
+/// `g`はプリワープしたカットオフ周波数、`k`は減衰、`m`は入力・バンドパス・ローパスの出力の混合比です。
+#[derive(Debug, Clone, Copy)]
+struct SvfPrototype {
+    g: f64,
+    k: f64,
+    m: [f64; 3],
+}
+impl SvfPrototype {
+    fn peaking_eq(freq: f64, gain: f64, sample_rate: f64) -> Self {
+        let a = 10.0f64.powf(gain / 40.0);
+        let k = 1.0 / (Q * a);
+        Self {
+            g: prewarp(freq, sample_rate),
+            k,
+            m: [1.0, k * (a * a - 1.0), 0.0],
+        }
+    }
+
+    fn low_pass(freq: f64, sample_rate: f64) -> Self {
+        Self {
+            g: prewarp(freq, sample_rate),
+            k: 1.0 / Q,
+            m: [0.0, 0.0, 1.0],
+        }
+    }
+
+    fn high_pass(freq: f64, sample_rate: f64) -> Self {
+        let k = 1.0 / Q;
+        Self {
+            g: prewarp(freq, sample_rate),
+            k,
+            m: [1.0, -k, -1.0],
+        }
+    }
+
+    fn coefficients(&self) -> SvfCoefficients {
+        SvfCoefficients::new(self.g, self.k, self.m)
+    }
+
+    /// `freq`での周波数応答。
+    ///
+    /// 双一次変換で対応するアナログの伝達関数`m0 + (m1 * s + m2) / (s^2 + k * s + 1)`から計算します。
+    fn response(&self, freq: f64, sample_rate: f64) -> Complex64 {
+        let s = Complex64::new(0.0, prewarp(freq, sample_rate) / self.g);
+        let denominator = s * s + self.k * s + 1.0;
+        self.m[0] + (self.m[1] * s + self.m[2]) / denominator
+    }
+}
+
+/// 設定に対するイコライザー全体の、`freq`での周波数応答の大きさ（dB）を計算する。
+pub fn magnitude_response_db(config: &crate::FilterConfig, sample_rate: f64, freq: f64) -> f64 {
+    if config.bypass {
+        return 0.0;
+    }
+    let mut response = Complex64::new(1.0, 0.0);
+    for (eq_freq, gain) in [
+        (config.bass_freq, config.bass_gain),
+        (config.mid_freq, config.mid_gain),
+        (config.treble_freq, config.treble_gain),
+    ] {
+        if gain != 0.0 {
+            response *=
+                SvfPrototype::peaking_eq(eq_freq, gain, sample_rate).response(freq, sample_rate);
+        }
+    }
+    if config.lopass_enable {
+        response *=
+            SvfPrototype::low_pass(config.lopass_freq, sample_rate).response(freq, sample_rate);
+    }
+    if config.hipass_enable {
+        response *=
+            SvfPrototype::high_pass(config.hipass_freq, sample_rate).response(freq, sample_rate);
+    }
+    let response = response * config.wet + (1.0 - config.wet);
+    20.0 * response.norm().max(1e-12).log10()
+}
+
+fn prewarp(freq: f64, sample_rate: f64) -> f64 {
+    // ナイキスト周波数以上だとtanが発散するので、その手前で止める
+    let freq = freq.min(sample_rate * 0.499);
+    (std::f64::consts::PI * freq / sample_rate).tan()
+}
+
+/// [`Cascade`]の列の長さ。[`CHUNK_SIZE`]サンプル分の出力と、次の状態。
+const LANES: usize = CHUNK_SIZE + STATE_COUNT;
+
+/// 有効なフィルタを直列につなぎ、Wetで混ぜたもの。
+///
+/// 1サンプルずつの漸化式は前のサンプルの結果を待つ必要があるので、
+/// [`CHUNK_SIZE`]サンプル分を「入力と状態から、出力と次の状態を求める」1つの行列として計算し、
+/// 自動ベクトル化が効くようにしています。
+/// 行列はf64で1サンプルずつ処理した結果から求めてから、f32に変換します。
+struct Cascade {
+    /// 無効なフィルタは`None`で、状態はそのまま残す。
+    filters: [Option<SvfCoefficients>; FILTER_COUNT],
+    wet: f64,
+    /// `j`サンプル目の入力の、`[y0, y1, ..., 次の状態...]`に対する寄与。
+    input_columns: [[f32; LANES]; CHUNK_SIZE],
+    /// 各状態の、`[y0, y1, ..., 次の状態...]`に対する寄与。
+    state_columns: [[f32; LANES]; STATE_COUNT],
+}
+impl Cascade {
+    fn new(filters: [Option<SvfCoefficients>; FILTER_COUNT], wet: f64) -> Self {
+        let mut cascade = Self {
+            filters,
+            wet,
+            input_columns: [[0.0; LANES]; CHUNK_SIZE],
+            state_columns: [[0.0; LANES]; STATE_COUNT],
+        };
+        // 入力と状態のうち1つだけを1にして処理したときの結果が、その列になる
+        let input_columns = std::array::from_fn(|j| {
+            let mut input = [0.0; CHUNK_SIZE];
+            input[j] = 1.0;
+            cascade.process_chunk_f64([0.0; STATE_COUNT], input)
+        });
+        let state_columns = std::array::from_fn(|l| {
+            let mut states = [0.0; STATE_COUNT];
+            states[l] = 1.0;
+            cascade.process_chunk_f64(states, [0.0; CHUNK_SIZE])
+        });
+        cascade.input_columns = input_columns;
+        cascade.state_columns = state_columns;
+        cascade
+    }
 
-    fn apply(&mut self, sample: f64) -> f64 {
-        self.filter.run(sample)
+    fn process_sample(&self, states: &mut [f64; STATE_COUNT], input: f64) -> f64 {
+        let mut sample = input;
+        for (filter, state) in self.filters.iter().zip(states.chunks_exact_mut(2)) {
+            if let Some(filter) = filter {
+                sample = filter.process_sample(state, sample);
+            }
+        }
+        sample * self.wet + input * (1.0 - self.wet)
+    }
+
+    /// f64で[`CHUNK_SIZE`]サンプル処理して、出力と次の状態を返す。
+    fn process_chunk_f64(
+        &self,
+        mut states: [f64; STATE_COUNT],
+        input: [f64; CHUNK_SIZE],
+    ) -> [f32; LANES] {
+        let mut lanes = [0.0; LANES];
+        for (lane, input) in lanes.iter_mut().zip(input) {
+            *lane = self.process_sample(&mut states, input) as f32;
+        }
+        for (lane, state) in lanes[CHUNK_SIZE..].iter_mut().zip(states) {
+            *lane = state as f32;
+        }
+        lanes
+    }
+
+    fn process_chunk(
+        &self,
+        states: &mut [f32; STATE_COUNT],
+        input: &[f32; CHUNK_SIZE],
+    ) -> [f32; CHUNK_SIZE] {
+        // 順番に足すと足し算が1列に並んで前の結果を待つので、2つずつ足し合わせる。
+        // 状態の寄与は別に足し合わせておくと、前のチャンクを待つ間にも入力の寄与を計算できる
+        let inputs = sum_columns(std::array::from_fn::<_, CHUNK_SIZE, _>(|j| {
+            scale(&self.input_columns[j], input[j])
+        }));
+        let states_sum = sum_columns(std::array::from_fn::<_, STATE_COUNT, _>(|l| {
+            scale(&self.state_columns[l], states[l])
+        }));
+        let lanes: [f32; LANES] = std::array::from_fn(|i| inputs[i] + states_sum[i]);
+        *states = lanes[CHUNK_SIZE..].try_into().unwrap();
+        lanes[..CHUNK_SIZE].try_into().unwrap()
     }
 }
 
+fn scale(column: &[f32; LANES], x: f32) -> [f32; LANES] {
+    column.map(|c| c * x)
+}
+
+fn sum_columns<const N: usize>(mut columns: [[f32; LANES]; N]) -> [f32; LANES] {
+    let mut len = N;
+    while len > 1 {
+        let half = len.div_ceil(2);
+        for i in 0..len / 2 {
+            columns[i] = std::array::from_fn(|lane| columns[i][lane] + columns[i + half][lane]);
+        }
+        len = half;
+    }
+    columns[0]
+}
+
 pub const Q: f64 = std::f64::consts::FRAC_1_SQRT_2; // Quality factor for the filters
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use biquad::{Biquad, ToHertz};
+
+    /// 以前のf64の実装。
+    struct Reference {
+        filters: Vec<biquad::DirectForm1<f64>>,
+        wet: f64,
+    }
+    impl Reference {
+        fn new(sample_rate: f64, config: &crate::FilterConfig) -> Self {
+            let mut types = [
+                (config.bass_gain, config.bass_freq),
+                (config.mid_gain, config.mid_freq),
+                (config.treble_gain, config.treble_freq),
+            ]
+            .into_iter()
+            .filter(|(gain, _)| *gain != 0.0)
+            .map(|(gain, freq)| (biquad::Type::PeakingEQ(gain), freq))
+            .collect::<Vec<_>>();
+            if config.lopass_enable {
+                types.push((biquad::Type::LowPass, config.lopass_freq));
+            }
+            if config.hipass_enable {
+                types.push((biquad::Type::HighPass, config.hipass_freq));
+            }
+            let filters = types
+                .into_iter()
+                .map(|(ty, freq)| {
+                    let coeffs = biquad::Coefficients::<f64>::from_params(
+                        ty,
+                        sample_rate.hz(),
+                        freq.hz(),
+                        Q,
+                    )
+                    .unwrap();
+                    biquad::DirectForm1::<f64>::new(coeffs)
+                })
+                .collect();
+            Self {
+                filters,
+                wet: config.wet,
+            }
+        }
+
+        fn process(&mut self, samples: &mut [f32]) {
+            for sample in samples {
+                let orig = *sample as f64;
+                let mut s = orig;
+                for filter in &mut self.filters {
+                    s = filter.run(s);
+                }
+                *sample = (s * self.wet + orig * (1.0 - self.wet)) as f32;
+            }
+        }
+    }
+
+    fn noise(len: usize) -> Vec<f32> {
+        let mut state = 0x1234_5678_u32;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 8) as f32 / (1 << 23) as f32 - 1.0
+            })
+            .collect()
+    }
+
+    fn sine(len: usize, freq: f64, sample_rate: f64) -> Vec<f32> {
+        (0..len)
+            .map(|i| (0.9 * (std::f64::consts::TAU * freq * i as f64 / sample_rate).sin()) as f32)
+            .collect()
+    }
+
+    fn max_deviation(config: &crate::FilterConfig, sample_rate: f64, input: &[f32]) -> f32 {
+        let mut state = EqState::new(sample_rate, config);
+        let mut reference = Reference::new(sample_rate, config);
+        let mut actual = input.to_vec();
+        let mut expected = input.to_vec();
+        let mut offset = 0;
+        for chunk_size in [256, 512, 735, 1024, 2048, 4096].into_iter().cycle() {
+            if offset >= input.len() {
+                break;
+            }
+            let end = (offset + chunk_size).min(input.len());
+            state.process(&mut actual[offset..end]);
+            reference.process(&mut expected[offset..end]);
+            offset = end;
+        }
+        actual
+            .iter()
+            .zip(&expected)
+            .map(|(a, e)| (a - e).abs())
+            .fold(0.0, f32::max)
+    }
+
+    fn assert_close(config: &crate::FilterConfig) {
+        for sample_rate in [44100.0, 48000.0] {
+            for input in [noise(48000), sine(48000, 50.0, sample_rate)] {
+                let deviation = max_deviation(config, sample_rate, &input);
+                assert!(
+                    deviation < 1e-4,
+                    "deviation {deviation} at {sample_rate}Hz, config: {config:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_is_identity() {
+        let input = noise(4096);
+        let mut samples = input.clone();
+        EqState::new(48000.0, &crate::FilterConfig::default()).process(&mut samples);
+        assert_eq!(samples, input);
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        assert_close(&crate::FilterConfig {
+            bass_gain: 6.0,
+            mid_gain: -3.0,
+            treble_gain: 4.5,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_matches_reference_at_extremes() {
+        assert_close(&crate::FilterConfig {
+            bass_freq: 20.0,
+            bass_gain: 15.0,
+            mid_freq: 4000.0,
+            mid_gain: -15.0,
+            treble_freq: 20000.0,
+            treble_gain: 15.0,
+            hipass_enable: true,
+            hipass_freq: 20.0,
+            lopass_enable: true,
+            lopass_freq: 20000.0,
+            ..Default::default()
+        });
+    }
+
+    /// サイン波を処理したときの音量の変化（dB）を測る。
+    fn measured_gain_db(config: &crate::FilterConfig, freq: f64, sample_rate: f64) -> f64 {
+        let input = sine(48000, freq, sample_rate);
+        let mut output = input.clone();
+        EqState::new(sample_rate, config).process(&mut output);
+        // 過渡応答を避けるため後半だけを見る
+        let rms = |samples: &[f32]| {
+            (samples.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / samples.len() as f64).sqrt()
+        };
+        20.0 * (rms(&output[24000..]) / rms(&input[24000..])).log10()
+    }
+
+    #[test]
+    fn test_magnitude_response_matches_processing() {
+        let config = crate::FilterConfig {
+            wet: 0.8,
+            bass_gain: 6.0,
+            mid_gain: -9.0,
+            lopass_enable: true,
+            lopass_freq: 8000.0,
+            ..Default::default()
+        };
+        for freq in [100.0, 440.0, 1000.0, 3000.0, 9000.0] {
+            let expected = magnitude_response_db(&config, 48000.0, freq);
+            let actual = measured_gain_db(&config, freq, 48000.0);
+            assert!(
+                (expected - actual).abs() < 0.1,
+                "{freq}Hz: expected {expected}dB, got {actual}dB"
+            );
+        }
+    }
+
+    #[test]
+    fn test_magnitude_response_of_default_is_flat() {
+        let config = crate::FilterConfig::default();
+        for freq in [20.0, 1000.0, 20000.0] {
+            assert!(magnitude_response_db(&config, 48000.0, freq).abs() < 1e-9);
+        }
+    }
+
+    /// `expected`に対する`actual`の誤差（dB）。
+    fn error_db(expected: &[f32], actual: &[f32]) -> f64 {
+        let peak = expected.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        let error = expected
+            .iter()
+            .zip(actual)
+            .fold(0.0f32, |max, (e, a)| max.max((e - a).abs()));
+        20.0 * (error as f64 / peak as f64).max(1e-12).log10()
+    }
+
+    #[test]
+    fn test_warm_up_matches_continuous() {
+        const FRAME_SIZE: usize = 800;
+        let sample_rate = 48000.0;
+        let config = crate::FilterConfig {
+            bass_freq: 20.0,
+            bass_gain: 15.0,
+            mid_gain: -15.0,
+            hipass_enable: true,
+            hipass_freq: 20.0,
+            ..Default::default()
+        };
+        let input = noise(FRAME_SIZE * 1100);
+        let start = FRAME_SIZE * 1000;
+
+        // 0フレーム目から続けて処理する
+        let mut expected = input.clone();
+        let mut state = EqState::new(sample_rate, &config);
+        for frame in expected.chunks_mut(FRAME_SIZE) {
+            state.process(frame);
+        }
+        let expected = &expected[start..];
+
+        // 1000フレーム目から処理する
+        let process_from_start = |warm_up: bool| {
+            let mut actual = input[start..].to_vec();
+            let mut state = EqState::new(sample_rate, &config);
+            if warm_up {
+                state.warm_up(&input[start - EqState::warmup_samples(sample_rate)..start]);
+            }
+            for frame in actual.chunks_mut(FRAME_SIZE) {
+                state.process(frame);
+            }
+            actual
+        };
+
+        let error = error_db(expected, &process_from_start(true));
+        assert!(error < -60.0, "error with warm-up: {error}dB");
+        let error = error_db(expected, &process_from_start(false));
+        assert!(error > -60.0, "error without warm-up: {error}dB");
+    }
+
+    #[test]
+    fn test_matches_reference_with_wet() {
+        assert_close(&crate::FilterConfig {
+            wet: 0.4,
+            bass_gain: -9.0,
+            hipass_enable: true,
+            hipass_freq: 80.0,
+            lopass_enable: true,
+            lopass_freq: 8000.0,
+            ..Default::default()
+        });
+    }
+}
@@ -0,0 +1,184 @@
+use aviutl2::config::translate as tr;
+use aviutl2_eframe::{AviUtl2EframeHandle, eframe, egui};
+
+/// 画面の更新間隔。約30Hz。
+const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(33);
+
+const MIN_FREQ: f64 = 20.0;
+const MAX_FREQ: f64 = 20000.0;
+/// スペクトルの表示範囲（dBFS）。
+const SPECTRUM_DB_RANGE: std::ops::RangeInclusive<f32> = -96.0..=0.0;
+/// イコライザーのカーブの表示範囲（dB）。
+const CURVE_DB_RANGE: std::ops::RangeInclusive<f64> = -18.0..=18.0;
+/// イコライザーのカーブを計算する点の数。
+const CURVE_POINTS: usize = 256;
+
+pub(crate) struct SpectrumApp {
+    handle: AviUtl2EframeHandle,
+    analyzer: crate::spectrum::SpectrumAnalyzer,
+    selected: Option<i64>,
+    /// 表示中のスペクトルの元になった音声。
+    current: Option<std::sync::Arc<crate::handoff::AudioChunk>>,
+    spectrum: crate::spectrum::Spectrum,
+}
+
+impl SpectrumApp {
+    pub(crate) fn new(cc: &eframe::CreationContext<'_>, handle: AviUtl2EframeHandle) -> Self {
+        cc.egui_ctx.all_styles_mut(|style| {
+            style.visuals = aviutl2_eframe::aviutl2_visuals();
+        });
+        cc.egui_ctx.set_fonts(aviutl2_eframe::aviutl2_fonts());
+
+        Self {
+            handle,
+            analyzer: crate::spectrum::SpectrumAnalyzer::new(),
+            selected: None,
+            current: None,
+            spectrum: crate::spectrum::Spectrum::default(),
+        }
+    }
+
+    fn update_spectrum(&mut self) {
+        let object_ids = crate::SPECTRUM_RING.object_ids();
+        if self.selected.is_none_or(|id| !object_ids.contains(&id)) {
+            self.selected = object_ids.first().copied();
+        }
+        let Some(selected) = self.selected else {
+            self.current = None;
+            self.spectrum = Default::default();
+            return;
+        };
+        let Some(chunk) = crate::SPECTRUM_RING.latest(selected) else {
+            return;
+        };
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|current| current.sequence == chunk.sequence)
+        {
+            return;
+        }
+        self.spectrum = self.analyzer.analyze(&chunk.samples, chunk.sample_rate);
+        self.current = Some(chunk);
+    }
+}
+
+impl eframe::App for SpectrumApp {
+    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        self.update_spectrum();
+        self.render_toolbar(ui);
+        egui::CentralPanel::default().show(ui, |ui| {
+            let (rect, _) = ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
+            self.render_plot(ui, rect);
+        });
+        ui.request_repaint_after(REFRESH_INTERVAL);
+    }
+}
+
+impl SpectrumApp {
+    fn render_toolbar(&mut self, ui: &mut egui::Ui) {
+        egui::Panel::top("toolbar").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let clicked = ui
+                    .heading(tr("Rusty Equalizer"))
+                    .interact(egui::Sense::click());
+                if clicked.secondary_clicked() {
+                    let _ = self.handle.show_context_menu();
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let object_ids = crate::SPECTRUM_RING.object_ids();
+                    let selected_text = self.selected.map_or_else(|| tr("なし"), object_label);
+                    egui::ComboBox::from_id_salt("object")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for id in object_ids {
+                                ui.selectable_value(&mut self.selected, Some(id), object_label(id));
+                            }
+                        });
+                    ui.label(tr("オブジェクト"));
+                });
+            });
+        });
+    }
+
+    fn render_plot(&self, ui: &egui::Ui, rect: egui::Rect) {
+        let painter = ui.painter_at(rect);
+        let visuals = ui.visuals();
+        painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+
+        let max_freq = self
+            .current
+            .as_ref()
+            .map_or(MAX_FREQ, |chunk| (chunk.sample_rate / 2.0).min(MAX_FREQ));
+        let x_of = |freq: f64| {
+            let t = (freq / MIN_FREQ).ln() / (max_freq / MIN_FREQ).ln();
+            rect.left() + rect.width() * t as f32
+        };
+        let grid_stroke = egui::Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color);
+        let text_color = visuals.weak_text_color();
+        let font = egui::FontId::proportional(10.0);
+        for (freq, label) in [(100.0, "100"), (1000.0, "1k"), (10000.0, "10k")] {
+            if freq > max_freq {
+                continue;
+            }
+            let x = x_of(freq);
+            painter.vline(x, rect.y_range(), grid_stroke);
+            painter.text(
+                egui::pos2(x + 2.0, rect.bottom() - 2.0),
+                egui::Align2::LEFT_BOTTOM,
+                label,
+                font.clone(),
+                text_color,
+            );
+        }
+
+        let Some(chunk) = &self.current else {
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                tr("音声を再生するとスペクトルが表示されます"),
+                egui::FontId::proportional(14.0),
+                text_color,
+            );
+            return;
+        };
+
+        let spectrum_y = |db: f32| {
+            let t = (db - SPECTRUM_DB_RANGE.start())
+                / (SPECTRUM_DB_RANGE.end() - SPECTRUM_DB_RANGE.start());
+            rect.bottom() - rect.height() * t.clamp(0.0, 1.0)
+        };
+        let spectrum_points = self
+            .spectrum
+            .bins()
+            .filter(|(freq, _)| (MIN_FREQ..=max_freq).contains(freq))
+            .map(|(freq, db)| egui::pos2(x_of(freq), spectrum_y(db)))
+            .collect::<Vec<_>>();
+        painter.line(
+            spectrum_points,
+            egui::Stroke::new(1.0, visuals.widgets.inactive.fg_stroke.color),
+        );
+
+        let curve_y = |db: f64| {
+            let t = (db - CURVE_DB_RANGE.start()) / (CURVE_DB_RANGE.end() - CURVE_DB_RANGE.start());
+            rect.bottom() - rect.height() * t.clamp(0.0, 1.0) as f32
+        };
+        painter.hline(rect.x_range(), curve_y(0.0), grid_stroke);
+        let curve_points = (0..CURVE_POINTS)
+            .map(|i| {
+                let t = i as f64 / (CURVE_POINTS - 1) as f64;
+                let freq = MIN_FREQ * (max_freq / MIN_FREQ).powf(t);
+                let db = crate::eq::magnitude_response_db(&chunk.config, chunk.sample_rate, freq);
+                egui::pos2(x_of(freq), curve_y(db))
+            })
+            .collect::<Vec<_>>();
+        painter.line(
+            curve_points,
+            egui::Stroke::new(2.0, visuals.selection.bg_fill),
+        );
+    }
+}
+
+fn object_label(id: i64) -> String {
+    tr("エフェクト {id}").replace("{id}", &id.to_string())
+}
@@ -0,0 +1,170 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// `proc_audio`で処理した音声の一部。
+#[derive(Debug, Clone)]
+pub struct AudioChunk {
+    /// エフェクトのID。
+    pub object_id: i64,
+    /// 追加された順番。同じオブジェクトでも、新しいものほど大きくなる。
+    pub sequence: u64,
+    pub sample_rate: f64,
+    /// 処理後の音声（モノラル）。
+    pub samples: Vec<f32>,
+    /// 処理したときの設定。
+    pub config: crate::FilterConfig,
+}
+
+/// 音声処理のスレッドからGUIのスレッドに音声を渡すためのリングバッファ。
+///
+/// オブジェクトごとに最新の音声だけを保持し、`capacity`個を超えた場合は最も古いオブジェクトのものから捨てる。
+/// 音声処理側は[`ChunkRing::push`]でロックを待たないので、GUI側が読み込んでいる間に来た音声は捨てられる。
+pub struct ChunkRing {
+    capacity: usize,
+    chunks: Mutex<VecDeque<Arc<AudioChunk>>>,
+    next_sequence: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl ChunkRing {
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            chunks: Mutex::new(VecDeque::new()),
+            next_sequence: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// 音声を追加する。
+    ///
+    /// ロックが取れなかった場合は追加せずに`false`を返す。
+    pub fn push(
+        &self,
+        object_id: i64,
+        sample_rate: f64,
+        samples: Vec<f32>,
+        config: crate::FilterConfig,
+    ) -> bool {
+        let Ok(mut chunks) = self.chunks.try_lock() else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        };
+        let chunk = Arc::new(AudioChunk {
+            object_id,
+            sequence: self.next_sequence.fetch_add(1, Ordering::Relaxed),
+            sample_rate,
+            samples,
+            config,
+        });
+        chunks.retain(|chunk| chunk.object_id != object_id);
+        while chunks.len() >= self.capacity.max(1) {
+            chunks.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        chunks.push_back(chunk);
+        true
+    }
+
+    /// オブジェクトの最新の音声を取得する。
+    pub fn latest(&self, object_id: i64) -> Option<Arc<AudioChunk>> {
+        let chunks = self.chunks.lock().unwrap_or_else(|e| e.into_inner());
+        chunks
+            .iter()
+            .find(|chunk| chunk.object_id == object_id)
+            .cloned()
+    }
+
+    /// 音声が残っているオブジェクトのIDを、新しい順に取得する。
+    pub fn object_ids(&self) -> Vec<i64> {
+        let chunks = self.chunks.lock().unwrap_or_else(|e| e.into_inner());
+        chunks.iter().rev().map(|chunk| chunk.object_id).collect()
+    }
+
+    /// 捨てられた音声の数。
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn clear(&self) {
+        self.chunks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(ring: &ChunkRing, object_id: i64, value: f32) -> bool {
+        ring.push(
+            object_id,
+            48000.0,
+            vec![value; 4],
+            crate::FilterConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_keeps_latest_per_object() {
+        let ring = ChunkRing::new(4);
+        push(&ring, 1, 0.1);
+        push(&ring, 2, 0.2);
+        push(&ring, 1, 0.3);
+
+        assert_eq!(ring.latest(1).unwrap().samples[0], 0.3);
+        assert_eq!(ring.latest(2).unwrap().samples[0], 0.2);
+        assert!(ring.latest(3).is_none());
+        assert_eq!(ring.object_ids(), [1, 2]);
+        assert_eq!(ring.dropped(), 0);
+    }
+
+    #[test]
+    fn test_overflow_drops_oldest_object() {
+        let ring = ChunkRing::new(3);
+        for object_id in 0..5 {
+            assert!(push(&ring, object_id, object_id as f32));
+        }
+
+        assert_eq!(ring.object_ids(), [4, 3, 2]);
+        assert!(ring.latest(0).is_none());
+        assert!(ring.latest(1).is_none());
+        assert_eq!(ring.dropped(), 2);
+
+        // 追加し直したオブジェクトは新しい扱いになる
+        push(&ring, 2, 2.5);
+        push(&ring, 5, 5.0);
+        assert_eq!(ring.object_ids(), [5, 2, 4]);
+    }
+
+    #[test]
+    fn test_push_does_not_wait_for_reader() {
+        let ring = ChunkRing::new(3);
+        push(&ring, 1, 0.1);
+        {
+            let _reading = ring.chunks.lock().unwrap();
+            assert!(!push(&ring, 1, 0.2));
+        }
+
+        assert_eq!(ring.latest(1).unwrap().samples[0], 0.1);
+        assert_eq!(ring.dropped(), 1);
+        assert!(push(&ring, 1, 0.3));
+        assert_eq!(ring.latest(1).unwrap().samples[0], 0.3);
+    }
+
+    #[test]
+    fn test_sequence_increases() {
+        let ring = ChunkRing::new(2);
+        push(&ring, 1, 0.0);
+        let first = ring.latest(1).unwrap().sequence;
+        push(&ring, 1, 0.0);
+        assert!(ring.latest(1).unwrap().sequence > first);
+    }
+}
@@ -1,63 +1,70 @@
-mod eq;
-use aviutl2::{
-    filter::{FilterConfigItemSliceExt, FilterConfigItems},
-    tracing,
-};
+pub mod eq;
+mod gui;
+pub mod handoff;
+pub mod spectrum;
+use aviutl2::{filter::FilterConfigItems, tracing};
+
+/// プレビューウィンドウに渡す音声。
+pub static SPECTRUM_RING: handoff::ChunkRing = handoff::ChunkRing::new(16);
 
 #[aviutl2::filter::filter_config_items]
 #[derive(Debug, Clone, PartialEq)]
 pub struct FilterConfig {
     #[checksection(name = "Bypass", multi_section = false, default = false)]
-    bypass: bool,
+    pub bypass: bool,
 
     #[track(name = "Wet", range = 0.0..=1.0, step = 0.01, default = 1.0)]
-    wet: f64,
+    pub wet: f64,
     #[group(name = "Bass")]
-    bass: group! {
+    pub bass: group! {
         #[track(name = "Bass: Frequency", range = 20.0..=250.0, step = 1.0, default = 100.0)]
-        bass_freq: f64,
+        pub bass_freq: f64,
         #[track(name = "Bass: Gain", range = -15.0..=15.0, step = 0.1, default = 0.0)]
-        bass_gain: f64,
+        pub bass_gain: f64,
     },
     #[group(name = "Mid")]
-    mid: group! {
+    pub mid: group! {
         #[track(name = "Mid: Frequency", range = 250.0..=4000.0, step = 1.0, default = 1000.0)]
-        mid_freq: f64,
+        pub mid_freq: f64,
         #[track(name = "Mid: Gain", range = -15.0..=15.0, step = 0.1, default = 0.0)]
-        mid_gain: f64,
+        pub mid_gain: f64,
     },
     #[group(name = "Treble")]
-    treble: group! {
+    pub treble: group! {
         #[track(name = "Treble: Frequency", range = 4000.0..=20000.0, step = 1.0, default = 10000.0)]
-        treble_freq: f64,
+        pub treble_freq: f64,
         #[track(name = "Treble: Gain", range = -15.0..=15.0, step = 0.1, default = 0.0)]
-        treble_gain: f64,
+        pub treble_gain: f64,
     },
 
     #[group(name = "Hi-pass Filter")]
-    hi_pass: group! {
+    pub hi_pass: group! {
         #[check(name = "Hi-pass: Enable", default = false)]
-        hipass_enable: bool,
+        pub hipass_enable: bool,
         #[track(name = "Hi-pass: Frequency", range = 20.0..=20000.0, step = 1.0, default = 20.0)]
-        hipass_freq: f64,
+        pub hipass_freq: f64,
     },
     #[group(name = "Lo-pass Filter")]
-    lo_pass: group! {
+    pub lo_pass: group! {
         #[check(name = "Lo-pass: Enable", default = false)]
-        lopass_enable: bool,
+        pub lopass_enable: bool,
         #[track(name = "Lo-pass: Frequency", range = 20.0..=20000.0, step = 1.0, default = 20000.0)]
-        lopass_freq: f64,
+        pub lopass_freq: f64,
     },
 }
 
 const NUM_CACHES: usize = 2;
 struct EqStates {
+    sample_rate: f64,
     left: eq::EqState,
     right: eq::EqState,
 
     expected_next_index: u64,
     next_cache_index: usize,
     caches: Vec<EqCache>,
+
+    left_samples: Vec<f32>,
+    right_samples: Vec<f32>,
 }
 struct EqCache {
     sample_index: u64,
@@ -68,6 +75,7 @@ struct EqCache {
 impl EqStates {
     fn new(sample_rate: f64, config: &FilterConfig) -> Self {
         Self {
+            sample_rate,
             left: eq::EqState::new(sample_rate, config),
             right: eq::EqState::new(sample_rate, config),
             expected_next_index: 0,
@@ -80,15 +88,18 @@ impl EqStates {
                     right: Vec::new(),
                 })
                 .collect(),
+            left_samples: Vec::new(),
+            right_samples: Vec::new(),
         }
     }
     fn update_params(&mut self, sample_rate: f64, config: &FilterConfig) {
+        self.sample_rate = sample_rate;
         self.left.update_params(sample_rate, config);
         self.right.update_params(sample_rate, config);
     }
-    fn process(&mut self, left: &mut [f64], right: &mut [f64]) {
-        self.left.process(left);
-        self.right.process(right);
+    fn process(&mut self) {
+        self.left.process(&mut self.left_samples);
+        self.right.process(&mut self.right_samples);
     }
     fn reset(&mut self) {
         self.left.reset();
@@ -97,23 +108,16 @@ impl EqStates {
 }
 
 #[aviutl2::plugin(FilterPlugin)]
-struct EqualizerFilter {
-    q_states: dashmap::DashMap<i64, EqStates>,
+pub struct EqualizerFilter {
+    q_states: aviutl2::filter::ObjectStateMap<EqStates>,
+    configs: aviutl2::filter::ConfigChangeTracker<FilterConfig>,
 }
 
 impl aviutl2::filter::FilterPlugin for EqualizerFilter {
     fn new(_info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
-        aviutl2::tracing_subscriber::fmt()
-            .with_max_level(if cfg!(debug_assertions) {
-                tracing::Level::DEBUG
-            } else {
-                tracing::Level::INFO
-            })
-            .event_format(aviutl2::logger::AviUtl2Formatter)
-            .with_writer(aviutl2::logger::AviUtl2LogWriter)
-            .init();
         Ok(Self {
-            q_states: dashmap::DashMap::new(),
+            q_states: aviutl2::filter::ObjectStateMap::new(),
+            configs: aviutl2::filter::ConfigChangeTracker::new(),
         })
     }
 
@@ -121,6 +125,7 @@ impl aviutl2::filter::FilterPlugin for EqualizerFilter {
         aviutl2::filter::FilterPluginTable {
             name: "Rusty Equalizer Filter".to_string(),
             label: None,
+            order: 0,
             information: format!(
                 "Simple equalizer, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/equalizer-filter",
                 version = env!("CARGO_PKG_VERSION")
@@ -138,34 +143,50 @@ impl aviutl2::filter::FilterPlugin for EqualizerFilter {
         config: &[aviutl2::filter::FilterConfigItem],
         audio: &mut aviutl2::filter::FilterProcAudio,
     ) -> anyhow::Result<()> {
-        let config: FilterConfig = config.to_struct();
+        let (config, config_changed) = self.configs.changed(&audio.object, config);
 
-        let mut left_samples = vec![0.0f32; audio.audio_object.sample_num as usize];
-        let mut right_samples = vec![0.0f32; audio.audio_object.sample_num as usize];
-        audio.get_sample_data(aviutl2::filter::AudioChannel::Left, &mut left_samples);
-        audio.get_sample_data(aviutl2::filter::AudioChannel::Right, &mut right_samples);
+        let num_samples = audio.audio_object.sample_num as usize;
+        let is_stereo = audio.channel_count() >= 2;
         let sample_rate = audio.scene.sample_rate as f64;
         let obj_id = audio.object.effect_id;
 
-        let mut q_state = self.q_states.entry(obj_id).or_insert_with(|| {
+        let mut q_state = self.q_states.get_or_insert_with(&audio.object, || {
             tracing::info!("Creating new EQ state for object ID {}", obj_id);
 
             EqStates::new(sample_rate, &config)
         });
+        let q_state = &mut *q_state;
+        q_state.left_samples.resize(num_samples, 0.0);
+        q_state.right_samples.resize(num_samples, 0.0);
+        audio.get_sample_data(
+            aviutl2::filter::AudioChannel::Left,
+            &mut q_state.left_samples,
+        )?;
+        if is_stereo {
+            audio.get_sample_data(
+                aviutl2::filter::AudioChannel::Right,
+                &mut q_state.right_samples,
+            )?;
+        } else {
+            q_state.right_samples.copy_from_slice(&q_state.left_samples);
+        }
 
         for cache in &mut q_state.caches {
             if cache.sample_index == audio.audio_object.sample_index
                 && cache.config == config
-                && cache.left.len() == left_samples.len()
-                && cache.right.len() == right_samples.len()
+                && cache.left.len() == num_samples
+                && cache.right.len() == num_samples
             {
                 tracing::debug!(
                     "Using cached EQ result for object ID {} at sample_index {}",
                     obj_id,
                     audio.audio_object.sample_index
                 );
-                audio.set_sample_data(aviutl2::filter::AudioChannel::Left, &cache.left);
-                audio.set_sample_data(aviutl2::filter::AudioChannel::Right, &cache.right);
+                audio.set_sample_data(aviutl2::filter::AudioChannel::Left, &cache.left)?;
+                if is_stereo {
+                    audio.set_sample_data(aviutl2::filter::AudioChannel::Right, &cache.right)?;
+                }
+                publish(obj_id, sample_rate, &cache.left, &cache.right, &config);
                 return Ok(());
             }
         }
@@ -182,45 +203,111 @@ impl aviutl2::filter::FilterPlugin for EqualizerFilter {
             "Processing audio for object ID {}: sample_index {}, num_samples {}",
             obj_id,
             audio.audio_object.sample_index,
-            left_samples.len()
+            num_samples
         );
-        q_state.expected_next_index = audio.audio_object.sample_index + left_samples.len() as u64;
+        q_state.expected_next_index = audio.audio_object.sample_index + num_samples as u64;
 
-        q_state.update_params(sample_rate, &config);
+        // 係数の計算は設定かサンプリングレートが変わったときだけ行う
+        if config_changed || q_state.sample_rate != sample_rate {
+            q_state.update_params(sample_rate, &config);
+        }
         if config.bypass {
             tracing::debug!(
                 "Bypass enabled, skipping EQ processing for object ID {}",
                 obj_id
             );
+            publish(
+                obj_id,
+                sample_rate,
+                &q_state.left_samples,
+                &q_state.right_samples,
+                &config,
+            );
             return Ok(());
         }
 
-        let mut left_samples = left_samples
-            .into_iter()
-            .map(|s| s as f64)
-            .collect::<Vec<_>>();
-        let mut right_samples = right_samples
-            .into_iter()
-            .map(|s| s as f64)
-            .collect::<Vec<_>>();
-        q_state.process(&mut left_samples, &mut right_samples);
-        let next_cache_index = q_state.next_cache_index;
-        let left_samples = left_samples.iter().map(|&s| s as f32).collect::<Vec<_>>();
-        let right_samples = right_samples.iter().map(|&s| s as f32).collect::<Vec<_>>();
-        audio.set_sample_data(aviutl2::filter::AudioChannel::Left, &left_samples);
-        audio.set_sample_data(aviutl2::filter::AudioChannel::Right, &right_samples);
-
-        let cache = &mut q_state.caches[next_cache_index];
+        q_state.process();
+        audio.set_sample_data(aviutl2::filter::AudioChannel::Left, &q_state.left_samples)?;
+        if is_stereo {
+            audio.set_sample_data(aviutl2::filter::AudioChannel::Right, &q_state.right_samples)?;
+        }
+        publish(
+            obj_id,
+            sample_rate,
+            &q_state.left_samples,
+            &q_state.right_samples,
+            &config,
+        );
+
+        let cache = &mut q_state.caches[q_state.next_cache_index];
         cache.sample_index = audio.audio_object.sample_index;
         cache.config = config.clone();
         cache.left.clear();
-        cache.left.extend_from_slice(&left_samples);
+        cache.left.extend_from_slice(&q_state.left_samples);
         cache.right.clear();
-        cache.right.extend_from_slice(&right_samples);
+        cache.right.extend_from_slice(&q_state.right_samples);
         q_state.next_cache_index = (q_state.next_cache_index + 1) % NUM_CACHES;
 
         Ok(())
     }
 }
 
-aviutl2::register_filter_plugin!(EqualizerFilter);
+/// 処理後の音声をモノラルにしてプレビューウィンドウに渡す。
+fn publish(object_id: i64, sample_rate: f64, left: &[f32], right: &[f32], config: &FilterConfig) {
+    let samples = left.iter().zip(right).map(|(l, r)| (l + r) * 0.5).collect();
+    SPECTRUM_RING.push(object_id, sample_rate, samples, config.clone());
+}
+
+#[aviutl2::plugin(GenericPlugin)]
+pub struct EqualizerPlugin {
+    window: aviutl2_eframe::EframeWindow,
+    filter: aviutl2::generic::SubPlugin<EqualizerFilter>,
+}
+
+impl aviutl2::generic::GenericPlugin for EqualizerPlugin {
+    fn new(info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+        aviutl2::tracing_subscriber::fmt()
+            .with_max_level(if cfg!(debug_assertions) {
+                tracing::Level::DEBUG
+            } else {
+                tracing::Level::INFO
+            })
+            .event_format(aviutl2::logger::AviUtl2Formatter)
+            .with_writer(aviutl2::logger::AviUtl2LogWriter)
+            .init();
+        let window = aviutl2_eframe::EframeWindow::new("RustyEqualizer", move |cc, handle| {
+            Ok(Box::new(gui::SpectrumApp::new(cc, handle)))
+        })?;
+
+        Ok(Self {
+            window,
+            filter: aviutl2::generic::SubPlugin::new_filter_plugin(&info)?,
+        })
+    }
+
+    fn plugin_info(&self) -> aviutl2::generic::GenericPluginTable {
+        aviutl2::generic::GenericPluginTable {
+            name: "Rusty Equalizer".to_string(),
+            information: format!(
+                "Simple equalizer with spectrum preview, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/equalizer-filter",
+                version = env!("CARGO_PKG_VERSION")
+            ),
+        }
+    }
+
+    fn register(&mut self, registry: &mut aviutl2::generic::HostAppHandle) {
+        registry.register_filter_plugin(&self.filter);
+        if let Ok(handle) = self.window.handle() {
+            registry
+                .register_window_client("Rusty Equalizer", &handle)
+                .unwrap();
+        }
+    }
+
+    fn on_clear_cache(&mut self, _edit_section: &aviutl2::generic::EditSection) {
+        SPECTRUM_RING.clear();
+    }
+}
+
+#[cfg(not(feature = "no-entrypoint"))]
+aviutl2::register_generic_plugin!(EqualizerPlugin);
@@ -0,0 +1,132 @@
+use rustfft::{FftPlanner, num_complex::Complex32};
+
+/// FFTのサイズの最大値。
+const MAX_FFT_SIZE: usize = 4096;
+/// FFTのサイズの最小値。これより短い音声は解析しない。
+const MIN_FFT_SIZE: usize = 64;
+
+/// 音声のスペクトル。
+#[derive(Debug, Clone, Default)]
+pub struct Spectrum {
+    /// 1ビンあたりの周波数（Hz）。
+    pub bin_width: f64,
+    /// 各ビンの大きさ（dBFS）。
+    pub magnitudes_db: Vec<f32>,
+}
+
+impl Spectrum {
+    /// `(周波数, 大きさ)`のイテレータを返す。直流成分は含まない。
+    pub fn bins(&self) -> impl Iterator<Item = (f64, f32)> + '_ {
+        self.magnitudes_db
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, db)| (i as f64 * self.bin_width, *db))
+    }
+}
+
+pub struct SpectrumAnalyzer {
+    planner: FftPlanner<f32>,
+    buffer: Vec<Complex32>,
+}
+
+impl Default for SpectrumAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpectrumAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            planner: FftPlanner::new(),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// 音声の末尾をハン窓をかけてFFTし、スペクトルを計算する。
+    ///
+    /// FFTのサイズは音声の長さ以下の2の累乗（最大4096）になります。
+    pub fn analyze(&mut self, samples: &[f32], sample_rate: f64) -> Spectrum {
+        if samples.len() < MIN_FFT_SIZE {
+            return Spectrum::default();
+        }
+        let size = prev_power_of_two(samples.len()).min(MAX_FFT_SIZE);
+        let samples = &samples[samples.len() - size..];
+
+        let window = hann_window(size);
+        let window_sum = window.iter().sum::<f32>();
+        self.buffer.clear();
+        self.buffer.extend(
+            samples
+                .iter()
+                .zip(&window)
+                .map(|(sample, w)| Complex32::new(sample * w, 0.0)),
+        );
+        self.planner
+            .plan_fft_forward(size)
+            .process(&mut self.buffer);
+
+        // 振幅1のサイン波が0dBになるように正規化する
+        let magnitudes_db = self.buffer[..size / 2]
+            .iter()
+            .map(|c| 20.0 * (2.0 * c.norm() / window_sum).max(1e-9).log10())
+            .collect();
+        Spectrum {
+            bin_width: sample_rate / size as f64,
+            magnitudes_db,
+        }
+    }
+}
+
+fn prev_power_of_two(n: usize) -> usize {
+    1 << (usize::BITS - 1 - n.leading_zeros())
+}
+
+fn hann_window(size: usize) -> Vec<f32> {
+    (0..size)
+        .map(|i| {
+            let phase = std::f32::consts::TAU * i as f32 / size as f32;
+            0.5 - 0.5 * phase.cos()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sine_peak() {
+        let sample_rate = 48000.0;
+        // ビンの中心に来る周波数
+        let freq = 48000.0 / 4096.0 * 100.0;
+        let samples = (0..6000)
+            .map(|i| (std::f64::consts::TAU * freq * i as f64 / sample_rate).sin() as f32 * 0.5)
+            .collect::<Vec<_>>();
+        let spectrum = SpectrumAnalyzer::new().analyze(&samples, sample_rate);
+
+        assert_eq!(spectrum.magnitudes_db.len(), 2048);
+        let (peak_freq, peak_db) = spectrum.bins().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+        assert!((peak_freq - freq).abs() < 1e-6);
+        // 振幅0.5は約-6dB
+        assert!((peak_db + 6.02).abs() < 0.1, "{peak_db}");
+        // 離れたビンはハン窓で十分小さくなる
+        assert!(spectrum.magnitudes_db[400] < -60.0);
+    }
+
+    #[test]
+    fn test_short_input() {
+        let mut analyzer = SpectrumAnalyzer::new();
+        assert!(
+            analyzer
+                .analyze(&[0.0; 10], 48000.0)
+                .magnitudes_db
+                .is_empty()
+        );
+        assert_eq!(
+            analyzer.analyze(&[0.0; 1600], 48000.0).magnitudes_db.len(),
+            512
+        );
+    }
+}
@@ -1,7 +1,7 @@
-use crate::DEFAULT_ARGS;
+use crate::{DEFAULT_ARGS, OPTIONAL_ARGS, REQUIRED_ARGS};
 use anyhow::Context;
 
-const CONFIG_VERSION: u64 = 3;
+const CONFIG_VERSION: u64 = 6;
 const PROJECT_CONFIG_KEY: &str = "config";
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -26,11 +26,62 @@ pub struct FfmpegOutputConfigV3 {
     pub args: Vec<String>,
     pub pixel_format: PixelFormat,
 }
-impl Default for FfmpegOutputConfigV3 {
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FfmpegOutputConfigV4 {
+    pub args: Vec<String>,
+    pub pixel_format: PixelFormat,
+    #[serde(default)]
+    pub two_pass: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FfmpegOutputConfigV5 {
+    pub args: Vec<String>,
+    pub pixel_format: PixelFormat,
+    #[serde(default)]
+    pub two_pass: bool,
+    /// プロジェクトに音声がない場合に、無音の音声トラックを追加するかどうか。
+    #[serde(default)]
+    pub silent_audio: bool,
+    /// 音声のオフセット（ミリ秒）。正の値で音声が遅れます。
+    #[serde(default)]
+    pub audio_offset_ms: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FfmpegOutputConfigV6 {
+    pub args: Vec<String>,
+    pub pixel_format: PixelFormat,
+    #[serde(default)]
+    pub two_pass: bool,
+    /// プロジェクトに音声がない場合に、無音の音声トラックを追加するかどうか。
+    #[serde(default)]
+    pub silent_audio: bool,
+    /// 音声のオフセット（ミリ秒）。正の値で音声が遅れます。
+    #[serde(default)]
+    pub audio_offset_ms: i32,
+    /// `{video_filters}`に入るフィルタグラフ。
+    #[serde(default)]
+    pub video_filters: String,
+    /// `{threads}`に入るスレッド数。0で自動。
+    #[serde(default)]
+    pub threads: u32,
+    /// `{extra_output_args}`に入る追加の引数。空白区切り。
+    #[serde(default)]
+    pub extra_output_args: String,
+}
+impl Default for FfmpegOutputConfigV6 {
     fn default() -> Self {
         Self {
             args: DEFAULT_ARGS.iter().map(|s| s.to_string()).collect(),
             pixel_format: PixelFormat::Bgr24,
+            two_pass: false,
+            silent_audio: false,
+            audio_offset_ms: 0,
+            video_filters: String::new(),
+            threads: 0,
+            extra_output_args: String::new(),
         }
     }
 }
@@ -60,9 +111,19 @@ impl PixelFormat {
             PixelFormat::Hf64 => "rgbaf16le",
         }
     }
+
+    /// `width`x`height`の1フレームのバイト数。
+    pub fn frame_size(&self, width: u32, height: u32) -> usize {
+        let bytes_per_pixel = match self {
+            PixelFormat::Yuy2 => 2,
+            PixelFormat::Bgr24 => 3,
+            PixelFormat::Pa64 | PixelFormat::Hf64 => 8,
+        };
+        width as usize * height as usize * bytes_per_pixel
+    }
 }
 
-pub type FfmpegOutputConfig = FfmpegOutputConfigV3;
+pub type FfmpegOutputConfig = FfmpegOutputConfigV6;
 
 impl TryFrom<FfmpegOutputConfigContainer> for FfmpegOutputConfig {
     type Error = anyhow::Error;
@@ -75,6 +136,8 @@ impl TryFrom<FfmpegOutputConfigContainer> for FfmpegOutputConfig {
                 Ok(Self {
                     args: config.args,
                     pixel_format: PixelFormat::Bgr24,
+                    two_pass: false,
+                    ..Default::default()
                 })
             }
             2 => {
@@ -83,10 +146,44 @@ impl TryFrom<FfmpegOutputConfigContainer> for FfmpegOutputConfig {
                 Ok(Self {
                     args: config.args,
                     pixel_format: config.pixel_format,
+                    two_pass: false,
+                    ..Default::default()
+                })
+            }
+            3 => {
+                let config: FfmpegOutputConfigV3 = serde_json::from_value(container.value)
+                    .context("Failed to parse FFmpeg output plugin config v3")?;
+                Ok(Self {
+                    args: config.args,
+                    pixel_format: config.pixel_format,
+                    two_pass: false,
+                    ..Default::default()
+                })
+            }
+            4 => {
+                let config: FfmpegOutputConfigV4 = serde_json::from_value(container.value)
+                    .context("Failed to parse FFmpeg output plugin config v4")?;
+                Ok(Self {
+                    args: config.args,
+                    pixel_format: config.pixel_format,
+                    two_pass: config.two_pass,
+                    ..Default::default()
+                })
+            }
+            5 => {
+                let config: FfmpegOutputConfigV5 = serde_json::from_value(container.value)
+                    .context("Failed to parse FFmpeg output plugin config v5")?;
+                Ok(Self {
+                    args: config.args,
+                    pixel_format: config.pixel_format,
+                    two_pass: config.two_pass,
+                    silent_audio: config.silent_audio,
+                    audio_offset_ms: config.audio_offset_ms,
+                    ..Default::default()
                 })
             }
-            3 => serde_json::from_value(container.value)
-                .context("Failed to parse FFmpeg output plugin config v3"),
+            6 => serde_json::from_value(container.value)
+                .context("Failed to parse FFmpeg output plugin config v6"),
             version => Err(anyhow::anyhow!(
                 "Unsupported FFmpeg output plugin config version: {}",
                 version
@@ -95,13 +192,18 @@ impl TryFrom<FfmpegOutputConfigContainer> for FfmpegOutputConfig {
     }
 }
 
+/// プロジェクトに保存された設定を読み込む。保存されていない場合は`None`を返す。
 pub fn load_project_config(
     project: &aviutl2::generic::ProjectFile<'_>,
-) -> anyhow::Result<FfmpegOutputConfig> {
+) -> anyhow::Result<Option<FfmpegOutputConfig>> {
+    if project.get_param_string(PROJECT_CONFIG_KEY).is_err() {
+        return Ok(None);
+    }
     match project.deserialize::<FfmpegOutputConfigContainer>(PROJECT_CONFIG_KEY) {
-        Ok(container) => FfmpegOutputConfig::try_from(container),
+        Ok(container) => FfmpegOutputConfig::try_from(container).map(Some),
         Err(container_error) => project
             .deserialize::<FfmpegOutputConfig>(PROJECT_CONFIG_KEY)
+            .map(Some)
             .with_context(|| {
                 format!(
                     "Failed to load FFmpeg output plugin config from project file: {container_error}"
@@ -124,3 +226,984 @@ pub fn save_project_config(
         .context("Failed to save FFmpeg output plugin config to project file")?;
     Ok(())
 }
+
+/// 全体の設定をファイルから読み込む。ファイルが無い場合は`None`を返す。
+pub fn load_global_config(path: &std::path::Path) -> anyhow::Result<Option<FfmpegOutputConfig>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).context("Failed to read FFmpeg output plugin config file");
+        }
+    };
+    let container: FfmpegOutputConfigContainer = serde_json::from_str(&content)
+        .context("Failed to parse FFmpeg output plugin config file")?;
+    FfmpegOutputConfig::try_from(container).map(Some)
+}
+
+/// 全体の設定をファイルに保存する。
+pub fn save_global_config(
+    path: &std::path::Path,
+    config: &FfmpegOutputConfig,
+) -> anyhow::Result<()> {
+    let container = FfmpegOutputConfigContainer {
+        version: CONFIG_VERSION,
+        value: serde_json::to_value(config)
+            .context("Failed to serialize FFmpeg output plugin config")?,
+    };
+    std::fs::write(
+        path,
+        serde_json::to_string_pretty(&container)
+            .context("Failed to serialize FFmpeg output plugin config")?,
+    )
+    .context("Failed to write FFmpeg output plugin config file")?;
+    Ok(())
+}
+
+/// 設定の保存先。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigScope {
+    /// 全てのプロジェクトで使う設定。DLLの横のフォルダに保存する。
+    Global,
+    /// 今のプロジェクトだけで使う設定。プロジェクトファイルに保存する。
+    Project,
+}
+impl ConfigScope {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConfigScope::Global => "全体",
+            ConfigScope::Project => "プロジェクト",
+        }
+    }
+}
+
+/// 全体の設定と、今のプロジェクトに保存された設定。
+#[derive(Debug, Clone, Default)]
+pub struct ConfigStore {
+    pub global: FfmpegOutputConfig,
+    pub project: Option<FfmpegOutputConfig>,
+}
+impl ConfigStore {
+    /// 使う設定と、その保存先を返す。
+    ///
+    /// プロジェクトに設定が保存されている場合はそちらを優先し、無い場合は全体の設定を使う。
+    pub fn resolve(&self) -> (&FfmpegOutputConfig, ConfigScope) {
+        match &self.project {
+            Some(project) => (project, ConfigScope::Project),
+            None => (&self.global, ConfigScope::Global),
+        }
+    }
+
+    /// 使う設定を返す。
+    pub fn active(&self) -> &FfmpegOutputConfig {
+        self.resolve().0
+    }
+
+    /// 設定ダイアログで保存した設定を反映する。
+    ///
+    /// プロジェクトに保存する場合は全体の設定を変えず、全体に保存する場合はプロジェクトの設定を破棄する。
+    pub fn apply(&mut self, config: FfmpegOutputConfig, scope: ConfigScope) {
+        match scope {
+            ConfigScope::Global => {
+                self.global = config;
+                self.project = None;
+            }
+            ConfigScope::Project => {
+                self.project = Some(config);
+            }
+        }
+    }
+}
+
+/// 複数の引数に展開されるプレースホルダー。1行に単独で指定する必要がある。
+const EXPANDING_PLACEHOLDERS: &[&str] = &["{extra_output_args}"];
+
+/// 入力・出力を表すプレースホルダー。1回だけ含まれている必要がある。
+const UNIQUE_PLACEHOLDERS: &[&str] = &["{video_source}", "{audio_source}", "{output_path}"];
+
+/// 引数のテンプレートの問題。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgsLint {
+    /// 必須のプレースホルダーが含まれていない。
+    MissingPlaceholder(&'static str),
+    /// 入力・出力のプレースホルダーが複数回含まれている。
+    DuplicatePlaceholder {
+        placeholder: &'static str,
+        count: usize,
+    },
+    /// 不明なプレースホルダー。そのままFFmpegに渡される。
+    UnknownPlaceholder { line: usize, placeholder: String },
+    /// 引数が引用符で囲まれている。引用符もそのままFFmpegに渡される。
+    QuotedArg { line: usize },
+    /// 引数に空白が含まれている。1行が1つの引数として渡される。
+    ContainsSpace { line: usize },
+    /// 複数の引数に展開されるプレースホルダーが、他の文字と同じ行にある。
+    NotStandalone {
+        line: usize,
+        placeholder: &'static str,
+    },
+    /// フィルタグラフが不正。
+    InvalidVideoFilters(VideoFiltersError),
+    /// 設定した値を使うプレースホルダーが引数に含まれていない。
+    UnusedSetting(&'static str),
+}
+
+impl ArgsLint {
+    /// 保存できない問題かどうか。
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            ArgsLint::MissingPlaceholder(_)
+                | ArgsLint::DuplicatePlaceholder { .. }
+                | ArgsLint::UnknownPlaceholder { .. }
+                | ArgsLint::NotStandalone { .. }
+                | ArgsLint::InvalidVideoFilters(_)
+        )
+    }
+
+    pub fn message(&self) -> String {
+        use aviutl2::config::translate as tr;
+        match self {
+            ArgsLint::MissingPlaceholder(placeholder) => {
+                tr("{placeholder}が含まれていません。").replace("{placeholder}", placeholder)
+            }
+            ArgsLint::DuplicatePlaceholder { placeholder, count } => {
+                tr("{placeholder}が{count}回含まれています。1回だけ指定してください。")
+                    .replace("{placeholder}", placeholder)
+                    .replace("{count}", &count.to_string())
+            }
+            ArgsLint::UnknownPlaceholder { line, placeholder } => {
+                tr("{line}行目：不明なプレースホルダー{placeholder}があります。")
+                    .replace("{line}", &line.to_string())
+                    .replace("{placeholder}", placeholder)
+            }
+            ArgsLint::QuotedArg { line } => {
+                tr("{line}行目：引用符はそのままFFmpegに渡されます。引用符は不要です。")
+                    .replace("{line}", &line.to_string())
+            }
+            ArgsLint::ContainsSpace { line } => {
+                tr("{line}行目：空白が含まれています。1行が1つの引数として渡されます。")
+                    .replace("{line}", &line.to_string())
+            }
+            ArgsLint::NotStandalone { line, placeholder } => {
+                tr("{line}行目：{placeholder}は1行に単独で指定してください。")
+                    .replace("{line}", &line.to_string())
+                    .replace("{placeholder}", placeholder)
+            }
+            ArgsLint::InvalidVideoFilters(error) => {
+                tr("フィルタグラフ：{error}").replace("{error}", &error.message())
+            }
+            ArgsLint::UnusedSetting(placeholder) => {
+                tr("引数に{placeholder}が含まれていないため、この設定は使われません。")
+                    .replace("{placeholder}", placeholder)
+            }
+        }
+    }
+}
+
+/// 引数のテンプレート中の`{...}`の形のプレースホルダーを列挙する。
+fn placeholders(arg: &str) -> impl Iterator<Item = &str> {
+    let mut rest = arg;
+    std::iter::from_fn(move || {
+        loop {
+            let start = rest.find('{')?;
+            let after = &rest[start + 1..];
+            let Some(len) = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')) else {
+                rest = "";
+                return None;
+            };
+            let tail = &after[len..];
+            if len > 0 && tail.starts_with('}') {
+                let placeholder = &rest[start..start + len + 2];
+                rest = &tail[1..];
+                return Some(placeholder);
+            }
+            rest = tail;
+        }
+    })
+}
+
+/// 引数のテンプレートを検査する。
+///
+/// 行番号は1始まりで、`args`のインデックスに対応する。空の引数は無視する。
+pub fn lint_args(args: &[String]) -> Vec<ArgsLint> {
+    let mut lints = vec![];
+    for &required in REQUIRED_ARGS {
+        let count = args
+            .iter()
+            .map(|arg| arg.matches(required).count())
+            .sum::<usize>();
+        if count == 0 {
+            lints.push(ArgsLint::MissingPlaceholder(required));
+        } else if count > 1 && UNIQUE_PLACEHOLDERS.contains(&required) {
+            lints.push(ArgsLint::DuplicatePlaceholder {
+                placeholder: required,
+                count,
+            });
+        }
+    }
+    for (index, arg) in args.iter().enumerate() {
+        if arg.is_empty() {
+            continue;
+        }
+        let line = index + 1;
+        for placeholder in placeholders(arg) {
+            if !REQUIRED_ARGS.contains(&placeholder) && !OPTIONAL_ARGS.contains(&placeholder) {
+                lints.push(ArgsLint::UnknownPlaceholder {
+                    line,
+                    placeholder: placeholder.to_string(),
+                });
+            }
+            if let Some(&placeholder) = EXPANDING_PLACEHOLDERS
+                .iter()
+                .find(|&&expanding| expanding == placeholder)
+                && arg != placeholder
+            {
+                lints.push(ArgsLint::NotStandalone { line, placeholder });
+            }
+        }
+        let quoted = ['"', '\'']
+            .iter()
+            .any(|&quote| arg.len() >= 2 && arg.starts_with(quote) && arg.ends_with(quote));
+        if quoted {
+            lints.push(ArgsLint::QuotedArg { line });
+        } else if arg.contains(char::is_whitespace) {
+            lints.push(ArgsLint::ContainsSpace { line });
+        }
+    }
+    lints
+}
+
+/// 引数以外の設定を検査する。
+///
+/// フィルタグラフが不正な場合と、設定した値を使うプレースホルダーが`config.args`に含まれていない場合に報告します。
+pub fn lint_settings(config: &FfmpegOutputConfig) -> Vec<ArgsLint> {
+    let mut lints = vec![];
+    if let Err(error) = validate_video_filters(&config.video_filters) {
+        lints.push(ArgsLint::InvalidVideoFilters(error));
+    }
+    let contains = |placeholder: &str| config.args.iter().any(|arg| arg.contains(placeholder));
+    for (placeholder, is_set) in [
+        ("{video_filters}", !config.video_filters.trim().is_empty()),
+        ("{threads}", config.threads != 0),
+        (
+            "{extra_output_args}",
+            !config.extra_output_args.trim().is_empty(),
+        ),
+    ] {
+        if is_set && !contains(placeholder) {
+            lints.push(ArgsLint::UnusedSetting(placeholder));
+        }
+    }
+    lints
+}
+
+/// フィルタグラフの問題。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoFiltersError {
+    /// 改行などの制御文字が含まれている。
+    ControlCharacter,
+    /// 空のフィルタがある（`,`が連続している、または先頭・末尾にある）。
+    EmptyFilter,
+    /// 引用符`'`が閉じられていない。
+    UnclosedQuote,
+    /// `[`と`]`の対応が取れていない。
+    UnbalancedBracket,
+}
+
+impl VideoFiltersError {
+    pub fn message(&self) -> String {
+        use aviutl2::config::translate as tr;
+        match self {
+            VideoFiltersError::ControlCharacter => tr("改行などの制御文字は使えません。"),
+            VideoFiltersError::EmptyFilter => {
+                tr("空のフィルタがあります。「,」の位置を確認してください。")
+            }
+            VideoFiltersError::UnclosedQuote => tr("引用符「'」が閉じられていません。"),
+            VideoFiltersError::UnbalancedBracket => tr("「[」と「]」の対応が取れていません。"),
+        }
+    }
+}
+
+/// `{video_filters}`に入れるフィルタグラフを検査する。
+///
+/// FFmpegのフィルタグラフの構文のうち、`-vf`の1つの引数を壊す問題のみを検出します。
+/// `\`によるエスケープと`'`による引用（引用の中ではエスケープされない）を考慮します。空の文字列は有効です。
+pub fn validate_video_filters(filters: &str) -> Result<(), VideoFiltersError> {
+    if filters.chars().any(char::is_control) {
+        return Err(VideoFiltersError::ControlCharacter);
+    }
+    let filters = filters.trim();
+    if filters.is_empty() {
+        return Ok(());
+    }
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut depth = 0usize;
+    let mut segment_empty = true;
+    for c in filters.chars() {
+        if escaped {
+            escaped = false;
+            segment_empty = false;
+            continue;
+        }
+        match c {
+            '\'' => quoted = !quoted,
+            _ if quoted => {}
+            '\\' => escaped = true,
+            '[' => depth += 1,
+            ']' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or(VideoFiltersError::UnbalancedBracket)?
+            }
+            ',' | ';' => {
+                if segment_empty {
+                    return Err(VideoFiltersError::EmptyFilter);
+                }
+                segment_empty = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            _ => {}
+        }
+        segment_empty = false;
+    }
+    if quoted || escaped {
+        return Err(VideoFiltersError::UnclosedQuote);
+    }
+    if depth != 0 {
+        return Err(VideoFiltersError::UnbalancedBracket);
+    }
+    if segment_empty {
+        return Err(VideoFiltersError::EmptyFilter);
+    }
+    Ok(())
+}
+
+/// 引数の置き換えに使う、出力する映像・音声の情報。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgsInfo {
+    /// 動画の解像度。動画がない場合は`None`。
+    pub video_size: Option<(u32, u32)>,
+    /// 動画のフレームレート。動画がない場合は`None`。
+    pub video_fps: Option<String>,
+    /// 音声のサンプルレート。音声がない場合は`None`。
+    pub audio_sample_rate: Option<u32>,
+}
+
+impl ArgsInfo {
+    pub fn from_output_info(info: &aviutl2::output::OutputInfo) -> Self {
+        Self {
+            video_size: info.video.as_ref().map(|v| (v.width, v.height)),
+            video_fps: info.video.as_ref().map(|v| v.fps.to_string()),
+            audio_sample_rate: info.audio.as_ref().map(|a| a.sample_rate),
+        }
+    }
+
+    pub fn from_estimate_info(info: &aviutl2::output::OutputEstimateInfo) -> Self {
+        Self {
+            video_size: info.video.as_ref().map(|v| (v.width, v.height)),
+            video_fps: info.video.as_ref().map(|v| v.fps.to_string()),
+            audio_sample_rate: info.audio.as_ref().map(|a| a.sample_rate),
+        }
+    }
+}
+
+/// 引数の置き換えに使う、入力・出力のパス。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArgsPaths<'a> {
+    pub video_source: &'a str,
+    pub audio_source: &'a str,
+    pub output_path: &'a str,
+}
+
+/// 引数のテンプレートのプレースホルダーを置き換えて、FFmpegに渡す引数を作る。
+///
+/// - 置き換えは1回だけ行うため、置き換えた値に含まれる`{...}`はそのまま残ります。
+/// - `{maybe_vflip}`・`{video_filters}`を含む引数は、テンプレートの`,`で区切られたフィルタのうち、
+///   プレースホルダーを置き換えて空になったものを取り除いてつなぎ直します。すべて空になった場合は`null`になります。
+/// - `{extra_output_args}`だけの引数は、[`FfmpegOutputConfig::extra_output_args`]を空白で区切った
+///   0個以上の引数に展開されます。
+///
+/// `config.args`ではなく`templates`を使うので、[`crate::stream_args::rewrite_stream_args`]などで
+/// 書き換えたテンプレートを渡せます。
+pub fn build_args(
+    templates: &[String],
+    config: &FfmpegOutputConfig,
+    info: &ArgsInfo,
+    paths: &ArgsPaths<'_>,
+) -> Vec<String> {
+    let (width, height) = info.video_size.unwrap_or((0, 0));
+    let video_size = format!("{width}x{height}");
+    let video_fps = info.video_fps.as_deref().unwrap_or("30");
+    let audio_sample_rate = info.audio_sample_rate.unwrap_or(44100).to_string();
+    let threads = config.threads.to_string();
+    let maybe_vflip = if config.pixel_format == PixelFormat::Bgr24 {
+        "vflip"
+    } else {
+        ""
+    };
+    let values: [(&str, &str); 10] = [
+        ("{video_source}", paths.video_source),
+        ("{video_pixel_format}", config.pixel_format.as_ffmpeg_str()),
+        ("{video_size}", &video_size),
+        ("{video_fps}", video_fps),
+        ("{audio_source}", paths.audio_source),
+        ("{audio_sample_rate}", &audio_sample_rate),
+        ("{output_path}", paths.output_path),
+        ("{maybe_vflip}", maybe_vflip),
+        ("{video_filters}", config.video_filters.trim()),
+        ("{threads}", &threads),
+    ];
+
+    let mut args = Vec::with_capacity(templates.len());
+    for template in templates {
+        if template == "{extra_output_args}" {
+            args.extend(
+                config
+                    .extra_output_args
+                    .split_whitespace()
+                    .map(str::to_string),
+            );
+        } else if template.contains("{maybe_vflip}") || template.contains("{video_filters}") {
+            let filters = template
+                .split(',')
+                .filter_map(|filter| {
+                    let substituted = substitute(filter, &values);
+                    // プレースホルダーが空になったフィルタだけを取り除く
+                    (substituted == filter || !substituted.trim().is_empty()).then_some(substituted)
+                })
+                .collect::<Vec<_>>();
+            args.push(if filters.is_empty() {
+                "null".to_string()
+            } else {
+                filters.join(",")
+            });
+        } else {
+            args.push(substitute(template, &values));
+        }
+    }
+    args
+}
+
+/// `template`中のプレースホルダーを`values`の値で置き換える。`values`にないものはそのまま残す。
+fn substitute(template: &str, values: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    for placeholder in placeholders(template) {
+        let Some(&(_, value)) = values.iter().find(|(key, _)| *key == placeholder) else {
+            continue;
+        };
+        let start = rest.find(placeholder).expect("placeholders are in order");
+        result.push_str(&rest[..start]);
+        result.push_str(value);
+        rest = &rest[start + placeholder.len()..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_frame_size() {
+        assert_eq!(PixelFormat::Yuy2.frame_size(1920, 1080), 1920 * 1080 * 2);
+        assert_eq!(PixelFormat::Bgr24.frame_size(1921, 1081), 1921 * 1081 * 3);
+        assert_eq!(PixelFormat::Pa64.frame_size(4, 2), 64);
+        assert_eq!(PixelFormat::Hf64.frame_size(4, 2), 64);
+    }
+
+    #[test]
+    fn test_default_args_have_no_lints() {
+        assert_eq!(lint_args(&args(DEFAULT_ARGS)), vec![]);
+        for preset in crate::presets::PRESETS {
+            assert_eq!(lint_args(&args(preset.args)), vec![], "{}", preset.id);
+        }
+    }
+
+    #[test]
+    fn test_missing_and_duplicate_placeholders() {
+        let mut template = args(DEFAULT_ARGS);
+        template.retain(|arg| arg != "{output_path}");
+        template.push("{video_source}".to_string());
+        // 入力・出力以外は複数回使ってもよい
+        template.push("-r".to_string());
+        template.push("{video_fps}".to_string());
+        let lints = lint_args(&template);
+        assert_eq!(
+            lints,
+            vec![
+                ArgsLint::DuplicatePlaceholder {
+                    placeholder: "{video_source}",
+                    count: 2
+                },
+                ArgsLint::MissingPlaceholder("{output_path}"),
+            ]
+        );
+        assert!(lints.iter().all(ArgsLint::is_error));
+    }
+
+    #[test]
+    fn test_unknown_placeholder() {
+        let mut template = args(DEFAULT_ARGS);
+        template.insert(0, "{output}".to_string());
+        template.insert(1, "scale={video_width}:{video_height}".to_string());
+        let lints = lint_args(&template);
+        assert_eq!(
+            lints,
+            vec![
+                ArgsLint::UnknownPlaceholder {
+                    line: 1,
+                    placeholder: "{output}".to_string()
+                },
+                ArgsLint::UnknownPlaceholder {
+                    line: 2,
+                    placeholder: "{video_width}".to_string()
+                },
+                ArgsLint::UnknownPlaceholder {
+                    line: 2,
+                    placeholder: "{video_height}".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quotes_and_spaces_are_warnings() {
+        let mut template = args(DEFAULT_ARGS);
+        let last = template.len() - 1;
+        template[last] = "\"{output_path}\"".to_string();
+        template.insert(0, "-c:v libx264".to_string());
+        let lints = lint_args(&template);
+        assert_eq!(
+            lints,
+            vec![
+                ArgsLint::ContainsSpace { line: 1 },
+                ArgsLint::QuotedArg {
+                    line: template.len()
+                },
+            ]
+        );
+        assert!(!lints.iter().any(ArgsLint::is_error));
+    }
+
+    #[test]
+    fn test_optional_placeholders() {
+        let lints = lint_args(&args(&[
+            "-i",
+            "{video_source}",
+            "-vf",
+            "{maybe_vflip},{video_filters}",
+            "-threads",
+            "{threads}",
+            "-movflags",
+            "{extra_output_args}",
+            "-metadata",
+            "title={extra_output_args}",
+            "{video_pixel_format}{video_size}{video_fps}",
+            "{audio_source}",
+            "{audio_sample_rate}",
+            "{output_path}",
+        ]));
+        assert_eq!(
+            lints,
+            vec![ArgsLint::NotStandalone {
+                line: 10,
+                placeholder: "{extra_output_args}"
+            }]
+        );
+        assert!(lints[0].is_error());
+    }
+
+    #[test]
+    fn test_lint_settings() {
+        let config = FfmpegOutputConfig {
+            video_filters: "scale=1280:-2".to_string(),
+            threads: 4,
+            extra_output_args: "-movflags +faststart".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(lint_settings(&config), vec![]);
+
+        let config = FfmpegOutputConfig {
+            args: args(&[
+                "-i",
+                "{video_source}",
+                "-vf",
+                "{maybe_vflip}",
+                "{output_path}",
+            ]),
+            ..config
+        };
+        let lints = lint_settings(&config);
+        assert_eq!(
+            lints,
+            vec![
+                ArgsLint::UnusedSetting("{video_filters}"),
+                ArgsLint::UnusedSetting("{threads}"),
+                ArgsLint::UnusedSetting("{extra_output_args}"),
+            ]
+        );
+        assert!(!lints.iter().any(ArgsLint::is_error));
+
+        // 未設定なら使われなくてもよい
+        let config = FfmpegOutputConfig {
+            video_filters: " ".to_string(),
+            threads: 0,
+            extra_output_args: String::new(),
+            ..config
+        };
+        assert_eq!(lint_settings(&config), vec![]);
+
+        let config = FfmpegOutputConfig {
+            video_filters: "scale=1280:-2,".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            lint_settings(&config),
+            vec![ArgsLint::InvalidVideoFilters(
+                VideoFiltersError::EmptyFilter
+            )]
+        );
+    }
+
+    #[test]
+    fn test_validate_video_filters() {
+        for valid in [
+            "",
+            "  ",
+            "scale=1280:-2",
+            "scale=1280:-2, fps=30",
+            "split[a][b];[a]hflip[c];[b][c]hstack",
+            "drawtext=text='a,b;[c'",
+            "drawtext=text=a\\,b",
+            "drawtext=text='C:\\'",
+        ] {
+            assert_eq!(validate_video_filters(valid), Ok(()), "{valid}");
+        }
+        for (invalid, error) in [
+            ("scale=1280:-2\nfps=30", VideoFiltersError::ControlCharacter),
+            (",scale=1280:-2", VideoFiltersError::EmptyFilter),
+            ("scale=1280:-2,", VideoFiltersError::EmptyFilter),
+            ("scale=1280:-2,,fps=30", VideoFiltersError::EmptyFilter),
+            ("scale=1280:-2, ;fps=30", VideoFiltersError::EmptyFilter),
+            ("drawtext=text='a", VideoFiltersError::UnclosedQuote),
+            ("drawtext=text=a\\", VideoFiltersError::UnclosedQuote),
+            ("[a", VideoFiltersError::UnbalancedBracket),
+            ("a]hflip", VideoFiltersError::UnbalancedBracket),
+        ] {
+            assert_eq!(validate_video_filters(invalid), Err(error), "{invalid}");
+        }
+    }
+
+    fn info() -> ArgsInfo {
+        ArgsInfo {
+            video_size: Some((1920, 1080)),
+            video_fps: Some("60".to_string()),
+            audio_sample_rate: Some(48000),
+        }
+    }
+
+    const PATHS: ArgsPaths<'static> = ArgsPaths {
+        video_source: r"\\.\pipe\video",
+        audio_source: r"\\.\pipe\audio",
+        output_path: r"C:\out.mp4",
+    };
+
+    /// `-vf`の値だけを取り出す。
+    fn video_filter_arg(config: &FfmpegOutputConfig) -> String {
+        let built = build_args(&config.args, config, &info(), &PATHS);
+        let index = built.iter().position(|arg| arg == "-vf").unwrap();
+        built[index + 1].clone()
+    }
+
+    #[test]
+    fn test_build_default_args() {
+        let config = FfmpegOutputConfig::default();
+        assert_eq!(
+            build_args(&config.args, &config, &info(), &PATHS),
+            args(&[
+                "-y",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "bgr24",
+                "-video_size",
+                "1920x1080",
+                "-framerate",
+                "60",
+                "-i",
+                r"\\.\pipe\video",
+                "-f",
+                "f32le",
+                "-ar",
+                "48000",
+                "-ac",
+                "2",
+                "-i",
+                r"\\.\pipe\audio",
+                "-map",
+                "0:v:0",
+                "-map",
+                "1:a:0",
+                "-vf",
+                "vflip",
+                "-pix_fmt",
+                "yuv420p",
+                "-threads",
+                "0",
+                r"C:\out.mp4",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_build_video_filters() {
+        let bgr = FfmpegOutputConfig::default();
+        let yuy2 = FfmpegOutputConfig {
+            pixel_format: PixelFormat::Yuy2,
+            ..Default::default()
+        };
+        let with_filters = |config: &FfmpegOutputConfig, filters: &str| FfmpegOutputConfig {
+            video_filters: filters.to_string(),
+            ..config.clone()
+        };
+
+        assert_eq!(video_filter_arg(&bgr), "vflip");
+        assert_eq!(video_filter_arg(&yuy2), "null");
+        assert_eq!(
+            video_filter_arg(&with_filters(&bgr, "scale=1280:-2")),
+            "vflip,scale=1280:-2"
+        );
+        assert_eq!(
+            video_filter_arg(&with_filters(&yuy2, " scale=1280:-2,fps=30 ")),
+            "scale=1280:-2,fps=30"
+        );
+        // 空白だけのフィルタグラフは未設定と同じ
+        assert_eq!(video_filter_arg(&with_filters(&yuy2, "  ")), "null");
+    }
+
+    #[test]
+    fn test_build_video_filters_in_custom_template() {
+        let config =
+            |template: &str, pixel_format: PixelFormat, filters: &str| FfmpegOutputConfig {
+                args: args(&["-vf", template]),
+                pixel_format,
+                video_filters: filters.to_string(),
+                ..Default::default()
+            };
+        // 古い設定の`{maybe_vflip}`だけのテンプレートは今まで通り
+        assert_eq!(
+            video_filter_arg(&config("{maybe_vflip}", PixelFormat::Bgr24, "fps=30")),
+            "vflip"
+        );
+        assert_eq!(
+            video_filter_arg(&config("{maybe_vflip}", PixelFormat::Pa64, "fps=30")),
+            "null"
+        );
+        // テンプレート側のフィルタは残す
+        assert_eq!(
+            video_filter_arg(&config(
+                "{maybe_vflip},{video_filters},format=yuv420p",
+                PixelFormat::Pa64,
+                ""
+            )),
+            "format=yuv420p"
+        );
+        assert_eq!(
+            video_filter_arg(&config(
+                "{video_filters},{maybe_vflip}",
+                PixelFormat::Bgr24,
+                "scale={video_size}"
+            )),
+            // 置き換えた値の中のプレースホルダーは置き換えない
+            "scale={video_size},vflip"
+        );
+        assert_eq!(
+            video_filter_arg(&config(
+                "scale={video_size},{video_filters}",
+                PixelFormat::Yuy2,
+                ""
+            )),
+            "scale=1920x1080"
+        );
+    }
+
+    #[test]
+    fn test_build_threads_and_extra_output_args() {
+        let config = FfmpegOutputConfig {
+            args: args(&[
+                "-i",
+                "{video_source}",
+                "-threads",
+                "{threads}",
+                "{extra_output_args}",
+                "{output_path}",
+            ]),
+            threads: 8,
+            extra_output_args: "  -movflags +faststart\t-metadata title=a  ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            build_args(&config.args, &config, &info(), &PATHS),
+            args(&[
+                "-i",
+                r"\\.\pipe\video",
+                "-threads",
+                "8",
+                "-movflags",
+                "+faststart",
+                "-metadata",
+                "title=a",
+                r"C:\out.mp4",
+            ])
+        );
+
+        let config = FfmpegOutputConfig {
+            extra_output_args: " ".to_string(),
+            ..config
+        };
+        assert_eq!(
+            build_args(&config.args, &config, &info(), &PATHS),
+            args(&["-i", r"\\.\pipe\video", "-threads", "8", r"C:\out.mp4",])
+        );
+    }
+
+    #[test]
+    fn test_build_args_without_streams() {
+        let config = FfmpegOutputConfig {
+            args: args(&[
+                "{video_size}",
+                "{video_fps}",
+                "{audio_sample_rate}",
+                "{unknown}",
+                "{output_path}",
+            ]),
+            ..Default::default()
+        };
+        let info = ArgsInfo {
+            video_size: None,
+            video_fps: None,
+            audio_sample_rate: None,
+        };
+        assert_eq!(
+            build_args(&config.args, &config, &info, &PATHS),
+            args(&["0x0", "30", "44100", "{unknown}", r"C:\out.mp4"])
+        );
+    }
+
+    #[test]
+    fn test_migrate_v5() {
+        let config = FfmpegOutputConfig::try_from(FfmpegOutputConfigContainer {
+            version: 5,
+            value: serde_json::json!({
+                "args": ["-i", "{video_source}", "{output_path}"],
+                "pixel_format": "Pa64",
+                "two_pass": true,
+                "silent_audio": true,
+                "audio_offset_ms": 10,
+            }),
+        })
+        .unwrap();
+        assert_eq!(
+            config.args,
+            args(&["-i", "{video_source}", "{output_path}"])
+        );
+        assert_eq!(config.pixel_format, PixelFormat::Pa64);
+        assert!(config.two_pass);
+        assert_eq!(config.audio_offset_ms, 10);
+        assert_eq!(config.video_filters, "");
+        assert_eq!(config.threads, 0);
+        assert_eq!(config.extra_output_args, "");
+    }
+
+    #[test]
+    fn test_config_store_resolve() {
+        let global = FfmpegOutputConfig {
+            threads: 4,
+            ..Default::default()
+        };
+        let project = FfmpegOutputConfig {
+            threads: 8,
+            ..Default::default()
+        };
+
+        // プロジェクトに設定が無い場合は全体の設定を使う
+        let mut store = ConfigStore {
+            global: global.clone(),
+            project: None,
+        };
+        assert_eq!(store.resolve(), (&global, ConfigScope::Global));
+
+        // プロジェクトの設定を優先する
+        store.project = Some(project.clone());
+        assert_eq!(store.resolve(), (&project, ConfigScope::Project));
+        assert_eq!(store.active(), &project);
+        assert_eq!(store.global, global);
+
+        // 別のプロジェクトを開いて設定が無かった場合は、全体の設定に戻る
+        store.project = None;
+        assert_eq!(store.active(), &global);
+    }
+
+    #[test]
+    fn test_config_store_apply() {
+        let mut store = ConfigStore::default();
+        let project = FfmpegOutputConfig {
+            two_pass: true,
+            ..Default::default()
+        };
+        store.apply(project.clone(), ConfigScope::Project);
+        assert_eq!(store.resolve(), (&project, ConfigScope::Project));
+        // プロジェクトに保存しても全体の設定は変わらない
+        assert_eq!(store.global, FfmpegOutputConfig::default());
+
+        let global = FfmpegOutputConfig {
+            audio_offset_ms: 100,
+            ..Default::default()
+        };
+        store.apply(global.clone(), ConfigScope::Global);
+        // 全体に保存するとプロジェクトの設定は破棄される
+        assert_eq!(store.project, None);
+        assert_eq!(store.resolve(), (&global, ConfigScope::Global));
+    }
+
+    #[test]
+    fn test_global_config_file() {
+        let path = std::env::temp_dir().join(format!(
+            "rusty_ffmpeg_test_config_{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(load_global_config(&path).unwrap(), None);
+
+        let config = FfmpegOutputConfig {
+            pixel_format: PixelFormat::Hf64,
+            video_filters: "scale=1280:-2".to_string(),
+            ..Default::default()
+        };
+        save_global_config(&path, &config).unwrap();
+        assert_eq!(load_global_config(&path).unwrap(), Some(config));
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(load_global_config(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(
+            placeholders("a{b}{c_1}{}{d").collect::<Vec<_>>(),
+            vec!["{b}", "{c_1}"]
+        );
+        assert_eq!(placeholders("{{x}}").collect::<Vec<_>>(), vec!["{x}"]);
+        assert_eq!(placeholders("{x y}").count(), 0);
+    }
+}
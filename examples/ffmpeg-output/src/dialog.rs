@@ -1,13 +1,38 @@
-use crate::{DEFAULT_ARGS, REQUIRED_ARGS, config::FfmpegOutputConfig};
+use crate::{
+    DEFAULT_ARGS,
+    config::{ConfigScope, FfmpegOutputConfig, lint_args, lint_settings},
+    dry_run::DryRunResult,
+};
 use aviutl2::config::translate as tr;
 use dedent::dedent;
 use eframe::egui;
 use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
+use std::sync::{Arc, Mutex};
 
 pub struct FfmpegOutputConfigDialog {
     pub args_buffer: String,
     pub pixel_format: crate::config::PixelFormat,
-    pub result_sender: std::sync::mpsc::Sender<FfmpegOutputConfig>,
+    pub two_pass: bool,
+    pub silent_audio: bool,
+    pub audio_offset_ms: i32,
+    pub video_filters: String,
+    pub threads: u32,
+    pub extra_output_args: String,
+    /// 「このプロジェクトに保存」。
+    pub save_to_project: bool,
+    pub result_sender: std::sync::mpsc::Sender<(FfmpegOutputConfig, ConfigScope)>,
+    dry_run: Arc<Mutex<DryRunStatus>>,
+    /// 「見積もり」の結果と、前回の出力の情報を使ったかどうか。
+    estimate: Option<(aviutl2::output::OutputEstimate, bool)>,
+}
+
+/// 「検証」の状態。
+#[derive(Debug, Clone)]
+enum DryRunStatus {
+    Idle,
+    Running,
+    Done(DryRunResult),
+    Failed(String),
 }
 
 fn buffer_to_args(buffer: &str) -> Vec<String> {
@@ -21,12 +46,143 @@ fn buffer_to_args(buffer: &str) -> Vec<String> {
 impl FfmpegOutputConfigDialog {
     pub fn new(
         config: FfmpegOutputConfig,
-        sender: std::sync::mpsc::Sender<FfmpegOutputConfig>,
+        scope: ConfigScope,
+        sender: std::sync::mpsc::Sender<(FfmpegOutputConfig, ConfigScope)>,
     ) -> Self {
         Self {
             args_buffer: config.args.join("\n"),
             pixel_format: config.pixel_format,
+            two_pass: config.two_pass,
+            silent_audio: config.silent_audio,
+            audio_offset_ms: config.audio_offset_ms,
+            video_filters: config.video_filters,
+            threads: config.threads,
+            extra_output_args: config.extra_output_args,
+            save_to_project: scope == ConfigScope::Project,
             result_sender: sender,
+            dry_run: Arc::new(Mutex::new(DryRunStatus::Idle)),
+            estimate: None,
+        }
+    }
+
+    /// 行番号が編集欄と一致するように、空行も含めて引数を返す。
+    fn buffer_lines(&self) -> Vec<String> {
+        self.args_buffer
+            .lines()
+            .map(|line| line.trim().to_string())
+            .collect()
+    }
+
+    /// 現在の入力内容から設定を作る。
+    fn to_config(&self) -> FfmpegOutputConfig {
+        FfmpegOutputConfig {
+            args: buffer_to_args(&self.args_buffer),
+            pixel_format: self.pixel_format,
+            two_pass: self.two_pass,
+            silent_audio: self.silent_audio,
+            audio_offset_ms: self.audio_offset_ms,
+            video_filters: self.video_filters.clone(),
+            threads: self.threads,
+            extra_output_args: self.extra_output_args.clone(),
+        }
+    }
+
+    fn start_dry_run(&self, ctx: egui::Context) {
+        let status = Arc::clone(&self.dry_run);
+        let config = self.to_config();
+        if let Ok(mut status) = status.lock() {
+            *status = DryRunStatus::Running;
+        }
+        std::thread::spawn(move || {
+            let result = match crate::dry_run::run_dry_run(&config.args, &config) {
+                Ok(result) => DryRunStatus::Done(result),
+                Err(e) => DryRunStatus::Failed(e.to_string()),
+            };
+            if let Ok(mut status) = status.lock() {
+                *status = result;
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn render_validation(&mut self, ui: &mut egui::Ui) {
+        let lints = lint_args(&self.buffer_lines())
+            .into_iter()
+            .chain(lint_settings(&self.to_config()));
+        for lint in lints {
+            let color = if lint.is_error() {
+                ui.visuals().error_fg_color
+            } else {
+                ui.visuals().warn_fg_color
+            };
+            ui.colored_label(color, lint.message());
+        }
+
+        let status = self
+            .dry_run
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or(DryRunStatus::Idle);
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    !matches!(status, DryRunStatus::Running),
+                    egui::Button::new(tr("検証")),
+                )
+                .on_hover_text(tr(
+                    "テスト用の映像と音声で、出力先を除いた引数をFFmpegで実行します。",
+                ))
+                .clicked()
+            {
+                self.start_dry_run(ui.ctx().clone());
+            }
+            if matches!(status, DryRunStatus::Running) {
+                ui.spinner();
+            }
+            if ui
+                .button(tr("見積もり"))
+                .on_hover_text(tr("引数から出力するファイルの大きさを見積もります。"))
+                .clicked()
+            {
+                let last_info = aviutl2::output::last_output_estimate_info();
+                let from_last_output = last_info.is_some();
+                let info = last_info.unwrap_or_else(crate::estimate::fallback_estimate_info);
+                self.estimate = Some((
+                    crate::estimate::estimate(&self.to_config(), &info),
+                    from_last_output,
+                ));
+            }
+        });
+        if let Some((estimate, from_last_output)) = &self.estimate {
+            ui.label(if *from_last_output {
+                tr("前回の出力の解像度・長さで見積もります。")
+            } else {
+                tr("出力したことがないため、1920x1080・30fps・60秒として見積もります。")
+            });
+            ui.add(egui::Label::new(estimate.notes.as_str()).wrap());
+        }
+        match status {
+            DryRunStatus::Idle | DryRunStatus::Running => {}
+            DryRunStatus::Done(result) => {
+                let (color, label) = if result.success {
+                    (
+                        ui.visuals().widgets.noninteractive.fg_stroke.color,
+                        tr("成功"),
+                    )
+                } else {
+                    (ui.visuals().error_fg_color, tr("失敗"))
+                };
+                ui.colored_label(color, format!("{label}（{}）", result.status));
+                if !result.stderr_tail.is_empty() {
+                    ui.add(
+                        egui::Label::new(egui::RichText::new(result.stderr_tail).monospace())
+                            .wrap(),
+                    );
+                }
+            }
+            DryRunStatus::Failed(error) => {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
         }
     }
 }
@@ -66,7 +222,28 @@ impl eframe::App for FfmpegOutputConfigDialog {
                                             - `{{maybe_vflip}}`：Bgr24でのみ`vflip`、それ以外では`null`
                                             - `{{output_path}}`：出力ファイルのパス
 
-                                            上の引数はすべて含まれている必要があります。
+                                            上の引数はすべて含まれている必要があります。\
+                                            また、以下の引数は下の設定の値に置換されます（省略可能）：
+                                            - `{{video_filters}}`：フィルタグラフ
+                                            - `{{threads}}`：スレッド数（0で自動）
+                                            - `{{extra_output_args}}`：追加の出力引数。空白で区切られ、複数の引数になります。1行に単独で指定してください。
+
+                                            `{{maybe_vflip}}`・`{{video_filters}}`を含む引数では、空になったフィルタは取り除かれます。\
+                                            すべて空になった場合は`null`になります。
+
+                                            
+                                            `{{video_source}}`・`{{audio_source}}`・`{{output_path}}`は1回だけ指定してください。\
+                                            「検証」を押すと、テスト用の映像と音声で引数を試せます。\
+                                            「見積もり」を押すと、ビットレートやCRFから出力するファイルの大きさの目安を表示します。
+
+                                            2パスエンコードを有効にすると、`-pass`と`-passlogfile`を追加してFFmpegを2回実行します。\
+                                            ビットレートを指定する場合（`-b:v`など）に使用してください。\
+                                            2パス目のために、1パス目のフレームを一時フォルダに書き出すため、十分な空き容量が必要です。
+
+                                            プロジェクトに音声がない場合、音声の入力と`-map`は自動で削除されます。\
+                                            「無音トラックを追加」を有効にすると、代わりに無音の音声トラックを追加します。\
+                                            音声オフセットは`-itsoffset`として音声の入力に指定されます。正の値で音声が遅れます。
+
                                             FFmpegについて詳しくない場合は、この設定を手動で変更せず、\
                                             プリセットを使用することをお勧めします。
                                             "#
@@ -110,26 +287,76 @@ impl eframe::App for FfmpegOutputConfigDialog {
                                         });
                                 });
 
+                                ui.checkbox(&mut self.two_pass, tr("2パスエンコード"));
+                                ui.checkbox(&mut self.silent_audio, tr("無音トラックを追加"));
+
+                                ui.horizontal(|ui| {
+                                    ui.label(tr("音声オフセット:"));
+                                    ui.add(
+                                        egui::DragValue::new(&mut self.audio_offset_ms)
+                                            .range(-10000..=10000)
+                                            .suffix("ms"),
+                                    );
+                                });
+
+                                ui.horizontal(|ui| {
+                                    ui.label(tr("フィルタグラフ:"));
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut self.video_filters)
+                                            .hint_text("scale=1280:-2")
+                                            .font(egui::TextStyle::Monospace),
+                                    );
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label(tr("スレッド数:"));
+                                    ui.add(egui::DragValue::new(&mut self.threads).range(0..=256))
+                                        .on_hover_text(tr("0で自動"));
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label(tr("追加の出力引数:"));
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut self.extra_output_args)
+                                            .hint_text("-movflags +faststart")
+                                            .font(egui::TextStyle::Monospace),
+                                    );
+                                });
+
+                                self.render_validation(ui);
+
+                                ui.checkbox(&mut self.save_to_project, tr("このプロジェクトに保存"))
+                                    .on_hover_text(tr(
+                                        "オンにすると、このプロジェクトでは全体の設定の代わりにこの設定を使います。プロジェクトを保存したときにプロジェクトファイルに書き込まれます。",
+                                    ));
+
                                 ui.horizontal(|ui| {
-                                    let args = buffer_to_args(&self.args_buffer);
-                                    let can_save = REQUIRED_ARGS
-                                        .iter()
-                                        .all(|arg| args.iter().any(|a| a.contains(arg)));
+                                    let config = self.to_config();
+                                    let can_save = !lint_args(&config.args)
+                                        .into_iter()
+                                        .chain(lint_settings(&config))
+                                        .any(|lint| lint.is_error());
                                     if ui
                                         .add_enabled(can_save, egui::Button::new(tr("保存")))
                                         .clicked()
                                     {
+                                        let scope = if self.save_to_project {
+                                            ConfigScope::Project
+                                        } else {
+                                            ConfigScope::Global
+                                        };
                                         self.result_sender
-                                            .send(FfmpegOutputConfig {
-                                                args,
-                                                pixel_format: self.pixel_format,
-                                            })
+                                            .send((config, scope))
                                             .expect("Failed to send args");
                                         ui.send_viewport_cmd(egui::ViewportCommand::Close);
                                     }
                                     if ui.button(tr("リセット")).clicked() {
-                                        self.pixel_format =
-                                            FfmpegOutputConfig::default().pixel_format;
+                                        let default = FfmpegOutputConfig::default();
+                                        self.pixel_format = default.pixel_format;
+                                        self.two_pass = default.two_pass;
+                                        self.silent_audio = default.silent_audio;
+                                        self.audio_offset_ms = default.audio_offset_ms;
+                                        self.video_filters = default.video_filters;
+                                        self.threads = default.threads;
+                                        self.extra_output_args = default.extra_output_args;
                                         self.args_buffer = DEFAULT_ARGS.join("\n");
                                     }
                                     if ui.button(tr("キャンセル")).clicked() {
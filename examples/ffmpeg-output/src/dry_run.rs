@@ -0,0 +1,161 @@
+//! 設定ダイアログの「検証」で、テスト用の入力を使ってFFmpegを実行する処理。
+use crate::{
+    config::{ArgsInfo, ArgsPaths, FfmpegOutputConfig, build_args},
+    stream_args::replace_source_inputs,
+};
+use std::os::windows::process::CommandExt;
+
+const DRY_RUN_DURATION: &str = "0.1";
+const DRY_RUN_SIZE: (u32, u32) = (64, 64);
+const DRY_RUN_FPS: &str = "30";
+const DRY_RUN_SAMPLE_RATE: u32 = 48000;
+/// 結果に表示するstderrの行数。
+const STDERR_TAIL_LINES: usize = 12;
+
+/// 検証の結果。
+#[derive(Debug, Clone)]
+pub struct DryRunResult {
+    pub success: bool,
+    pub status: String,
+    /// stderrの末尾。
+    pub stderr_tail: String,
+}
+
+/// 引数のテンプレートを、テスト用の入力と`-f null -`への出力に書き換える。
+///
+/// 入力は`testsrc`・`anullsrc`に、出力のパスは`-f null -`に置き換え、
+/// それ以外のプレースホルダーはダミーの値と`config`の値で置き換えます。
+pub fn dry_run_args(args: &[String], config: &FfmpegOutputConfig) -> Vec<String> {
+    let (width, height) = DRY_RUN_SIZE;
+    let to_args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let args = replace_source_inputs(args, |placeholder| match placeholder {
+        "{video_source}" => Some(to_args(&[
+            "-f",
+            "lavfi",
+            "-i",
+            &format!(
+                "testsrc=duration={DRY_RUN_DURATION}:size={width}x{height}:rate={DRY_RUN_FPS},format={}",
+                config.pixel_format.as_ffmpeg_str()
+            ),
+        ])),
+        "{audio_source}" => Some(to_args(&[
+            "-f",
+            "lavfi",
+            "-t",
+            DRY_RUN_DURATION,
+            "-i",
+            &format!("anullsrc=channel_layout=stereo:sample_rate={DRY_RUN_SAMPLE_RATE}"),
+        ])),
+        _ => None,
+    });
+
+    // 出力の引数は`-f null -`の3つに展開されるので、置き換えの前に差し込む
+    let mut templates = Vec::with_capacity(args.len() + 2);
+    for arg in args {
+        if arg.contains("{output_path}") {
+            templates.extend(to_args(&["-f", "null", "-"]));
+        } else {
+            templates.push(arg);
+        }
+    }
+    build_args(
+        &templates,
+        config,
+        &ArgsInfo {
+            video_size: Some(DRY_RUN_SIZE),
+            video_fps: Some(DRY_RUN_FPS.to_string()),
+            audio_sample_rate: Some(DRY_RUN_SAMPLE_RATE),
+        },
+        &ArgsPaths {
+            video_source: "",
+            audio_source: "",
+            output_path: "",
+        },
+    )
+}
+
+/// テスト用の入力でFFmpegを実行する。FFmpegがない場合はダウンロードします。
+pub fn run_dry_run(args: &[String], config: &FfmpegOutputConfig) -> anyhow::Result<DryRunResult> {
+    let ffmpeg_path = crate::find_ffmpeg()?;
+    let output = std::process::Command::new(ffmpeg_path)
+        .args(dry_run_args(args, config))
+        .stdin(std::process::Stdio::null())
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to start FFmpeg process: {}", e))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines = stderr.lines().collect::<Vec<_>>();
+    Ok(DryRunResult {
+        success: output.status.success(),
+        status: output.status.to_string(),
+        stderr_tail: lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dry_run_args() {
+        let args = crate::DEFAULT_ARGS
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>();
+        let config = FfmpegOutputConfig {
+            video_filters: "scale=32:-2".to_string(),
+            extra_output_args: "-t 1".to_string(),
+            ..Default::default()
+        };
+        let dry_run = dry_run_args(&args, &config);
+        assert_eq!(
+            dry_run,
+            [
+                "-y",
+                "-f",
+                "lavfi",
+                "-i",
+                "testsrc=duration=0.1:size=64x64:rate=30,format=bgr24",
+                "-f",
+                "lavfi",
+                "-t",
+                "0.1",
+                "-i",
+                "anullsrc=channel_layout=stereo:sample_rate=48000",
+                "-map",
+                "0:v:0",
+                "-map",
+                "1:a:0",
+                "-vf",
+                "vflip,scale=32:-2",
+                "-pix_fmt",
+                "yuv420p",
+                "-threads",
+                "0",
+                "-t",
+                "1",
+                "-f",
+                "null",
+                "-",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dry_run_args_has_no_placeholders() {
+        for preset in crate::presets::PRESETS {
+            let args = preset
+                .args
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<_>>();
+            let config = FfmpegOutputConfig {
+                pixel_format: preset.pixel_format,
+                ..Default::default()
+            };
+            for arg in dry_run_args(&args, &config) {
+                assert!(!arg.contains('{'), "{}: {arg}", preset.id);
+            }
+        }
+    }
+}
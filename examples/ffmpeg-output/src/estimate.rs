@@ -0,0 +1,447 @@
+//! 設定ダイアログの「見積もり」で、引数から出力するファイルの大きさを見積もる処理。
+use crate::config::{ArgsInfo, ArgsPaths, FfmpegOutputConfig, build_args};
+use aviutl2::output::{AudioOutputInfo, OutputEstimate, OutputEstimateInfo, VideoOutputInfo};
+
+/// 一度も出力していない場合に見積もりに使う長さ（秒）。
+const FALLBACK_DURATION_SECS: u32 = 60;
+
+/// 一度も出力していない場合に見積もりに使う、1920x1080・30fps・48kHzの情報。
+pub fn fallback_estimate_info() -> OutputEstimateInfo {
+    OutputEstimateInfo {
+        video: Some(VideoOutputInfo {
+            width: 1920,
+            height: 1080,
+            fps: aviutl2::Rational32::new(30, 1),
+            num_frames: 30 * FALLBACK_DURATION_SECS,
+            colorimetry: aviutl2::pixel::Colorimetry::HOST,
+        }),
+        audio: Some(AudioOutputInfo {
+            sample_rate: 48000,
+            num_samples: 48000 * FALLBACK_DURATION_SECS,
+            num_channels: 2,
+        }),
+    }
+}
+
+/// 動画のビットレート（bps）の見積もり。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoBitrate {
+    /// `-b:v`で指定された値。
+    Exact(u64),
+    /// CRFやプロファイルから推測した範囲。
+    Range { low: u64, high: u64 },
+    /// 推測できない。
+    Unknown,
+}
+
+/// `192k`や`5M`のようなFFmpegのビットレートの指定を、bpsに変換する。
+pub fn parse_bitrate(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, multiplier) = match value.char_indices().last()? {
+        (i, 'k' | 'K') => (&value[..i], 1e3),
+        (i, 'm' | 'M') => (&value[..i], 1e6),
+        (i, 'g' | 'G') => (&value[..i], 1e9),
+        _ => (value, 1.0),
+    };
+    let number = number.parse::<f64>().ok()?;
+    (number.is_finite() && number >= 0.0).then(|| (number * multiplier).round() as u64)
+}
+
+/// `names`のいずれかの引数に指定された値のうち、最後のものを返す。
+fn arg_value<'a>(args: &'a [String], names: &[&str]) -> Option<&'a str> {
+    args.windows(2)
+        .rev()
+        .find(|pair| names.contains(&pair[0].as_str()))
+        .map(|pair| pair[1].as_str())
+}
+
+/// CRFで品質を指定するエンコーダーの種類。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CrfCodec {
+    /// 既定のCRF。
+    default_crf: f64,
+    /// 既定のCRFでの、1ピクセル・1フレームあたりのビット数の範囲。
+    bits_per_pixel: (f64, f64),
+}
+
+/// エンコーダーのCRFの目安を返す。`-c:v`が指定されていない場合はlibx264として扱います。
+fn crf_codec(codec: Option<&str>) -> Option<CrfCodec> {
+    let codec = codec.unwrap_or("libx264");
+    if codec == "libx264" || codec.starts_with("h264") {
+        Some(CrfCodec {
+            default_crf: 23.0,
+            bits_per_pixel: (0.03, 0.12),
+        })
+    } else if codec == "libx265" || codec.starts_with("hevc") {
+        Some(CrfCodec {
+            default_crf: 28.0,
+            bits_per_pixel: (0.015, 0.06),
+        })
+    } else if codec == "libvpx-vp9" || codec.starts_with("vp9") {
+        Some(CrfCodec {
+            default_crf: 31.0,
+            bits_per_pixel: (0.02, 0.08),
+        })
+    } else if ["libaom-av1", "libsvtav1"].contains(&codec) || codec.starts_with("av1") {
+        Some(CrfCodec {
+            default_crf: 35.0,
+            bits_per_pixel: (0.012, 0.05),
+        })
+    } else {
+        None
+    }
+}
+
+/// ProRes 422 HQなどのプロファイルごとの、1920x1080・29.97fps（30000/1001）での目安のビットレート（bps）。
+fn prores_bitrate(profile: &str) -> Option<u64> {
+    let mbps = match profile {
+        "0" | "proxy" => 45,
+        "1" | "lt" => 102,
+        "2" | "standard" => 147,
+        "3" | "hq" => 220,
+        "4" | "4444" => 330,
+        "5" | "4444xq" => 500,
+        _ => return None,
+    };
+    Some(mbps * 1_000_000)
+}
+
+/// 1秒あたりのピクセル数を返す。
+fn pixels_per_second(video: &VideoOutputInfo) -> f64 {
+    let fps = *video.fps.numer() as f64 / *video.fps.denom() as f64;
+    video.width as f64 * video.height as f64 * fps
+}
+
+/// 引数から動画のビットレートを見積もり、根拠と一緒に返す。
+///
+/// `args`は[`build_args`]で置き換えた後の引数です。
+pub fn video_bitrate(args: &[String], video: &VideoOutputInfo) -> (VideoBitrate, String) {
+    if let Some(bitrate) = arg_value(args, &["-b:v", "-b:v:0"]).and_then(parse_bitrate) {
+        return (VideoBitrate::Exact(bitrate), "-b:v".to_string());
+    }
+    let codec = arg_value(args, &["-c:v", "-c:v:0", "-vcodec"]);
+    if codec.is_some_and(|codec| codec.starts_with("prores")) {
+        let profile = arg_value(args, &["-profile:v", "-profile:v:0"]).unwrap_or_else(|| {
+            // `auto`の場合は、ピクセルフォーマットから4444かHQかが選ばれる
+            match arg_value(args, &["-pix_fmt"]) {
+                Some(pix_fmt) if pix_fmt.contains("444") => "4444",
+                _ => "hq",
+            }
+        });
+        let Some(nominal) = prores_bitrate(profile) else {
+            return (
+                VideoBitrate::Unknown,
+                format!("ProResのプロファイル「{profile}」"),
+            );
+        };
+        let nominal =
+            nominal as f64 * pixels_per_second(video) / (1920.0 * 1080.0 * 30000.0 / 1001.0);
+        return (
+            VideoBitrate::Range {
+                low: (nominal * 0.8).round() as u64,
+                high: nominal.round() as u64,
+            },
+            format!("ProResのプロファイル{profile}"),
+        );
+    }
+    let Some(crf_codec) = crf_codec(codec) else {
+        return (
+            VideoBitrate::Unknown,
+            format!("エンコーダー「{}」", codec.unwrap_or_default()),
+        );
+    };
+    let crf = arg_value(args, &["-crf"])
+        .and_then(|crf| crf.parse::<f64>().ok())
+        .unwrap_or(crf_codec.default_crf);
+    // CRFが6小さくなるごとに、ビットレートはおよそ2倍になる
+    let scale = 2f64.powf((crf_codec.default_crf - crf) / 6.0) * pixels_per_second(video);
+    let (low, high) = crf_codec.bits_per_pixel;
+    (
+        VideoBitrate::Range {
+            low: (low * scale).round() as u64,
+            high: (high * scale).round() as u64,
+        },
+        format!("CRF {crf}（{}）", codec.unwrap_or("libx264")),
+    )
+}
+
+/// 引数から音声のビットレート（bps）を見積もり、根拠と一緒に返す。推測できない場合は`None`。
+pub fn audio_bitrate(args: &[String], audio: &AudioOutputInfo) -> (Option<u64>, String) {
+    if args.iter().any(|arg| arg == "-an") {
+        return (Some(0), "-an".to_string());
+    }
+    if let Some(bitrate) = arg_value(args, &["-b:a", "-b:a:0"]).and_then(parse_bitrate) {
+        return (Some(bitrate), "-b:a".to_string());
+    }
+    let codec = arg_value(args, &["-c:a", "-c:a:0", "-acodec"]).unwrap_or("aac");
+    let sample_rate = arg_value(args, &["-ar"])
+        .and_then(|rate| rate.parse::<u64>().ok())
+        .unwrap_or(audio.sample_rate as u64);
+    let pcm = |bits: u64| sample_rate * audio.num_channels as u64 * bits;
+    let bitrate = match codec {
+        "aac" | "libmp3lame" => Some(128_000),
+        "libopus" => Some(96_000),
+        "pcm_s16le" | "pcm_s16be" => Some(pcm(16)),
+        "pcm_s24le" | "pcm_s24be" => Some(pcm(24)),
+        "pcm_s32le" | "pcm_f32le" => Some(pcm(32)),
+        // 可逆圧縮は内容によるが、おおよそ16bit PCMの6割になる
+        "flac" | "alac" => Some(pcm(16) * 6 / 10),
+        _ => None,
+    };
+    (bitrate, codec.to_string())
+}
+
+/// バイト数を`1.2 GB`のような形式にする。
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    if value < 1000.0 {
+        return format!("{bytes} B");
+    }
+    let mut unit = UNITS[0];
+    for next in UNITS {
+        value /= 1000.0;
+        unit = next;
+        if value < 1000.0 {
+            break;
+        }
+    }
+    format!("{value:.1} {unit}")
+}
+
+/// 設定と出力の情報から、出力するファイルの大きさを見積もる。
+pub fn estimate(config: &FfmpegOutputConfig, info: &OutputEstimateInfo) -> OutputEstimate {
+    let args = build_args(
+        &config.args,
+        config,
+        &ArgsInfo::from_estimate_info(info),
+        &ArgsPaths {
+            video_source: "",
+            audio_source: "",
+            output_path: "",
+        },
+    );
+    let duration = info.duration_secs();
+    let mut notes = Vec::new();
+    // 見積もりの下限と上限（bps）
+    let mut low = 0u64;
+    let mut high = 0u64;
+
+    if let Some(video) = &info.video {
+        let (bitrate, reason) = video_bitrate(&args, video);
+        match bitrate {
+            VideoBitrate::Exact(bitrate) => {
+                notes.push(format!("動画：{reason}の{} kbps", bitrate / 1000));
+                low += bitrate;
+                high += bitrate;
+            }
+            VideoBitrate::Range {
+                low: video_low,
+                high: video_high,
+            } => {
+                notes.push(format!(
+                    "動画：{reason}から推測して{}〜{} kbps",
+                    video_low / 1000,
+                    video_high / 1000
+                ));
+                low += video_low;
+                high += video_high;
+            }
+            VideoBitrate::Unknown => {
+                return OutputEstimate {
+                    bytes: None,
+                    notes: format!("{reason}のビットレートは見積もれません。"),
+                };
+            }
+        }
+    }
+    if let Some(audio) = &info.audio {
+        match audio_bitrate(&args, audio) {
+            (Some(bitrate), reason) => {
+                notes.push(format!("音声：{reason}の{} kbps", bitrate / 1000));
+                low += bitrate;
+                high += bitrate;
+            }
+            (None, codec) => {
+                notes.push(format!(
+                    "音声：{codec}のビットレートは見積もれないため含めません"
+                ));
+            }
+        }
+    }
+
+    let to_bytes = |bps: u64| (bps as f64 * duration / 8.0).round() as u64;
+    // 範囲の場合は、上限と下限の相乗平均を見積もりとする
+    let bytes = to_bytes(((low as f64) * (high as f64)).sqrt().round() as u64);
+    if low == high {
+        notes.push(format!("{:.1}秒で約{}", duration, format_bytes(bytes)));
+    } else {
+        notes.push(format!(
+            "{:.1}秒で約{}（{}〜{}、内容によって大きく変わります）",
+            duration,
+            format_bytes(bytes),
+            format_bytes(to_bytes(low)),
+            format_bytes(to_bytes(high))
+        ));
+    }
+    notes
+        .push("フィルタグラフでの解像度の変更やコンテナのオーバーヘッドは含みません。".to_string());
+    OutputEstimate {
+        bytes: Some(bytes),
+        notes: notes.join("\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn video_1080p30() -> VideoOutputInfo {
+        fallback_estimate_info().video.unwrap()
+    }
+
+    #[test]
+    fn test_parse_bitrate() {
+        assert_eq!(parse_bitrate("192k"), Some(192_000));
+        assert_eq!(parse_bitrate("1.5M"), Some(1_500_000));
+        assert_eq!(parse_bitrate("8000000"), Some(8_000_000));
+        assert_eq!(parse_bitrate("fast"), None);
+        assert_eq!(parse_bitrate(""), None);
+    }
+
+    #[test]
+    fn test_video_bitrate_exact() {
+        let (bitrate, _) = video_bitrate(
+            &args(&["-c:v", "libx264", "-b:v", "1M", "-b:v", "6M"]),
+            &video_1080p30(),
+        );
+        assert_eq!(bitrate, VideoBitrate::Exact(6_000_000));
+    }
+
+    #[test]
+    fn test_video_bitrate_crf_doubles_every_six() {
+        let video = video_1080p30();
+        let (at_23, _) = video_bitrate(&args(&["-c:v", "libx264"]), &video);
+        let (at_17, reason) = video_bitrate(&args(&["-c:v", "libx264", "-crf", "17"]), &video);
+        assert_eq!(reason, "CRF 17（libx264）");
+        let (
+            VideoBitrate::Range {
+                low: low_23,
+                high: high_23,
+            },
+            VideoBitrate::Range {
+                low: low_17,
+                high: high_17,
+            },
+        ) = (at_23, at_17)
+        else {
+            panic!("expected ranges: {at_23:?}, {at_17:?}");
+        };
+        // 1920x1080x30 = 62208000ピクセル/秒
+        assert_eq!(low_23, 1_866_240);
+        assert_eq!(high_23, 7_464_960);
+        assert_eq!(low_17, low_23 * 2);
+        assert_eq!(high_17, high_23 * 2);
+    }
+
+    #[test]
+    fn test_video_bitrate_prores() {
+        let video = VideoOutputInfo {
+            fps: aviutl2::Rational32::new(30000, 1001),
+            ..video_1080p30()
+        };
+        let (bitrate, _) = video_bitrate(&args(&["-c:v", "prores_ks", "-profile:v", "3"]), &video);
+        assert_eq!(
+            bitrate,
+            VideoBitrate::Range {
+                low: 176_000_000,
+                high: 220_000_000
+            }
+        );
+        // プロファイルの指定がない場合は、ピクセルフォーマットから選ぶ
+        let (bitrate, _) = video_bitrate(
+            &args(&["-c:v", "prores_ks", "-pix_fmt", "yuva444p10le"]),
+            &video,
+        );
+        assert!(matches!(
+            bitrate,
+            VideoBitrate::Range {
+                high: 330_000_000,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_video_bitrate_unknown_codec() {
+        let (bitrate, _) = video_bitrate(&args(&["-c:v", "ffv1"]), &video_1080p30());
+        assert_eq!(bitrate, VideoBitrate::Unknown);
+    }
+
+    #[test]
+    fn test_audio_bitrate() {
+        let audio = fallback_estimate_info().audio.unwrap();
+        assert_eq!(
+            audio_bitrate(&args(&["-c:a", "aac", "-b:a", "192k"]), &audio).0,
+            Some(192_000)
+        );
+        assert_eq!(
+            audio_bitrate(&args(&["-c:a", "pcm_s16le"]), &audio).0,
+            Some(1_536_000)
+        );
+        assert_eq!(
+            audio_bitrate(&args(&["-ar", "44100", "-c:a", "pcm_s16le"]), &audio).0,
+            Some(1_411_200)
+        );
+        assert_eq!(audio_bitrate(&args(&["-an"]), &audio).0, Some(0));
+        assert_eq!(audio_bitrate(&args(&["-c:a", "libvorbis"]), &audio).0, None);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(999), "999 B");
+        assert_eq!(format_bytes(1_500), "1.5 KB");
+        assert_eq!(format_bytes(1_234_000_000), "1.2 GB");
+    }
+
+    #[test]
+    fn test_estimate_with_exact_bitrates() {
+        let config = FfmpegOutputConfig {
+            args: args(&[
+                "-c:v",
+                "libx264",
+                "-b:v",
+                "8M",
+                "-b:a",
+                "192k",
+                "{output_path}",
+            ]),
+            ..Default::default()
+        };
+        let estimate = estimate(&config, &fallback_estimate_info());
+        // (8000 + 192) kbps x 60秒
+        assert_eq!(estimate.bytes, Some(61_440_000));
+    }
+
+    #[test]
+    fn test_estimate_presets() {
+        for preset in crate::presets::PRESETS {
+            let config = FfmpegOutputConfig {
+                args: args(preset.args),
+                pixel_format: preset.pixel_format,
+                ..Default::default()
+            };
+            let estimate = estimate(&config, &fallback_estimate_info());
+            assert!(
+                estimate.bytes.is_some_and(|bytes| bytes > 0),
+                "{}: {}",
+                preset.id,
+                estimate.notes
+            );
+        }
+    }
+}
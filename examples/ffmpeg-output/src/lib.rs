@@ -1,13 +1,22 @@
 mod config;
 mod dialog;
+mod dry_run;
+mod estimate;
 mod named_pipe;
 mod presets;
+mod stream_args;
+mod two_pass;
 
 use crate::{
-    config::{FfmpegOutputConfig, load_project_config, save_project_config},
+    config::{
+        ConfigScope, ConfigStore, FfmpegOutputConfig, load_global_config, load_project_config,
+        save_global_config, save_project_config,
+    },
     dialog::FfmpegOutputConfigDialog,
     named_pipe::{NamedPipe, PipeWriter},
     presets::PRESETS,
+    stream_args::{StreamLayout, rewrite_stream_args},
+    two_pass::{Pass, PassProgress, TempWorkDir, two_pass_args},
 };
 use anyhow::Context;
 use aviutl2::output::{
@@ -34,7 +43,7 @@ fn create_send_only_named_pipe(name: &str) -> anyhow::Result<(String, NamedPipe)
 
 #[aviutl2::plugin(OutputPlugin)]
 struct FfmpegOutputPlugin {
-    config: Mutex<FfmpegOutputConfig>,
+    config: Mutex<ConfigStore>,
 }
 
 pub static DEFAULT_ARGS: &[&str] = &[
@@ -62,9 +71,12 @@ pub static DEFAULT_ARGS: &[&str] = &[
     "-map",
     "1:a:0",
     "-vf",
-    "{maybe_vflip}",
+    "{maybe_vflip},{video_filters}",
     "-pix_fmt",
     "yuv420p",
+    "-threads",
+    "{threads}",
+    "{extra_output_args}",
     "{output_path}",
 ];
 pub static REQUIRED_ARGS: &[&str] = &[
@@ -77,6 +89,8 @@ pub static REQUIRED_ARGS: &[&str] = &[
     "{output_path}",
     "{maybe_vflip}",
 ];
+/// 設定から値が入る、省略できるプレースホルダー。
+pub static OPTIONAL_ARGS: &[&str] = &["{video_filters}", "{threads}", "{extra_output_args}"];
 
 fn pipe_for_callback<T: Fn(PipeWriter) -> anyhow::Result<()> + Send + 'static>(
     name: &str,
@@ -106,6 +120,10 @@ fn get_data_dir() -> anyhow::Result<std::path::PathBuf> {
     Ok(path)
 }
 
+fn get_global_config_path() -> anyhow::Result<std::path::PathBuf> {
+    Ok(get_data_dir()?.join("config.json"))
+}
+
 fn get_log_dir() -> anyhow::Result<std::path::PathBuf> {
     let data_dir = get_data_dir()?;
     let log_dir = data_dir.join("logs");
@@ -175,10 +193,35 @@ fn download_ffmpeg_if_missing() -> anyhow::Result<std::path::PathBuf> {
 
     Ok(ffmpeg_dir)
 }
+
+/// FFmpegの実行ファイルのパスを返す。FFmpegがない場合はダウンロードします。
+fn find_ffmpeg() -> anyhow::Result<std::path::PathBuf> {
+    let ffmpeg_dir = download_ffmpeg_if_missing()
+        .map_err(|e| anyhow::anyhow!("Failed to download FFmpeg: {}", e))?;
+    let ffmpeg_path = ffmpeg_dir.join("bin").join("ffmpeg.exe");
+    if !ffmpeg_path.exists() {
+        return Err(anyhow::anyhow!(
+            "FFmpeg executable not found at {:?}",
+            ffmpeg_path
+        ));
+    }
+    Ok(ffmpeg_path)
+}
+
 impl OutputPlugin for FfmpegOutputPlugin {
     fn new(_info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+        let global = match get_global_config_path().and_then(|path| load_global_config(&path)) {
+            Ok(global) => global.unwrap_or_default(),
+            Err(e) => {
+                aviutl2::tracing::warn!("Failed to load FFmpeg output plugin config: {:?}", e);
+                FfmpegOutputConfig::default()
+            }
+        };
         Ok(FfmpegOutputPlugin {
-            config: Mutex::new(FfmpegOutputConfig::default()),
+            config: Mutex::new(ConfigStore {
+                global,
+                project: None,
+            }),
         })
     }
 
@@ -200,14 +243,20 @@ impl OutputPlugin for FfmpegOutputPlugin {
         }
     }
 
-    fn output(&self, info: aviutl2::output::OutputInfo) -> aviutl2::AnyResult<()> {
-        let killed = Arc::new(std::sync::atomic::AtomicBool::new(false));
-        let mut threads: Vec<std::thread::JoinHandle<anyhow::Result<()>>> = Vec::new();
+    fn output(&self, mut info: aviutl2::output::OutputInfo) -> aviutl2::AnyResult<()> {
+        // ファイル名にプレースホルダーが含まれている場合は展開する
+        if let Some(file_name) = info.path.file_name().and_then(|name| name.to_str())
+            && file_name.contains('{')
+        {
+            let file_name = aviutl2::output::expand_filename_template(file_name, &info);
+            info.path = info.path.with_file_name(file_name);
+        }
         let info = Arc::new(info);
         let config = self
             .config
             .lock()
             .map_err(|e| anyhow::anyhow!("Failed to lock FFmpeg Output Plugin config: {}", e))?
+            .active()
             .clone();
 
         if info.video.as_ref().is_some_and(|v| {
@@ -221,161 +270,71 @@ impl OutputPlugin for FfmpegOutputPlugin {
             ));
         }
 
-        let (video_path, video_server_thread) = pipe_for_callback("aviutl2_ffmpeg_video_pipe", {
-            let info = Arc::clone(&info);
-            move |stream: PipeWriter| -> anyhow::Result<()> {
-                if info.video.is_none() {
-                    return Ok(());
-                }
-                let mut writer = std::io::BufWriter::new(stream);
-                match config.pixel_format {
-                    config::PixelFormat::Yuy2 => {
-                        for (_, frame) in info.get_video_frames_iter::<BorrowedRawYuy2VideoFrame>()
-                        {
-                            writer.write_all(frame.as_slice())?;
-                        }
-                    }
-                    config::PixelFormat::Bgr24 => {
-                        for (_, frame) in info.get_video_frames_iter::<BorrowedRawBgrVideoFrame>() {
-                            writer.write_all(frame.as_slice())?;
-                        }
-                    }
-                    config::PixelFormat::Pa64 => {
-                        for (_, frame) in info.get_video_frames_iter::<BorrowedRawPa64VideoFrame>()
-                        {
-                            writer.write_all(frame.as_slice().as_bytes())?;
-                        }
-                    }
-                    config::PixelFormat::Hf64 => {
-                        for (_, frame) in info.get_video_frames_iter::<BorrowedRawHf64VideoFrame>()
-                        {
-                            writer.write_all(frame.as_slice().as_bytes())?;
-                        }
-                    }
-                }
-                writer.flush()?;
-                Ok(())
-            }
-        })?;
-        threads.push(video_server_thread);
-
-        let (audio_path, audio_server_thread) = pipe_for_callback("aviutl2_ffmpeg_audio_pipe", {
-            let info = Arc::clone(&info);
-            move |stream: PipeWriter| -> anyhow::Result<()> {
-                if info.audio.is_none() {
-                    return Ok(());
-                }
-                let mut buf = [0u8; 8]; // 2 f32 values, each 4 bytes
-                let mut writer = std::io::BufWriter::new(stream);
-                for (_, samples) in info.get_stereo_audio_samples_iter::<f32>(
-                    (info.audio.as_ref().map_or(44100, |a| a.sample_rate) / 10) as i32,
-                ) {
-                    for sample in &samples {
-                        buf[0..4].copy_from_slice(&sample.0.to_le_bytes());
-                        buf[4..8].copy_from_slice(&sample.1.to_le_bytes());
-                        writer.write_all(&buf)?;
-                    }
-                    writer.flush()?;
-                }
-                writer.flush()?;
-                Ok(())
-            }
-        })?;
-        threads.push(audio_server_thread);
+        if let Err(error) = config::validate_video_filters(&config.video_filters) {
+            anyhow::bail!("フィルタグラフが不正です：{}", error.message());
+        }
 
         assert!(
             info.video.is_some() || info.audio.is_some(),
             "At least one of video_input or audio_input must be provided"
         );
 
-        let ffmpeg_dir = download_ffmpeg_if_missing()
-            .map_err(|e| anyhow::anyhow!("Failed to download FFmpeg: {}", e))?;
-        let ffmpeg_path = ffmpeg_dir.join("bin").join("ffmpeg.exe");
-        if !ffmpeg_path.exists() {
-            return Err(anyhow::anyhow!(
-                "FFmpeg executable not found at {:?}",
-                ffmpeg_path
-            ));
-        }
-        let mut args = vec![];
-        let config_args = self
-            .config
-            .lock()
-            .map_err(|e| anyhow::anyhow!("Failed to lock FFmpeg Output Plugin config: {}", e))?
-            .args
-            .clone();
-        for arg in config_args {
-            args.push(
-                arg.replace("{video_source}", &video_path)
-                    .replace("{video_pixel_format}", config.pixel_format.as_ffmpeg_str())
-                    .replace(
-                        "{video_size}",
-                        &format!(
-                            "{}x{}",
-                            info.video.as_ref().map_or(0, |v| v.width),
-                            info.video.as_ref().map_or(0, |v| v.height)
-                        ),
-                    )
-                    .replace(
-                        "{video_fps}",
-                        &info
-                            .video
-                            .as_ref()
-                            .map_or("30".to_string(), |v| v.fps.to_string()),
-                    )
-                    .replace("{audio_source}", &audio_path)
-                    .replace(
-                        "{audio_sample_rate}",
-                        &info
-                            .audio
-                            .as_ref()
-                            .map_or("44100".to_string(), |a| a.sample_rate.to_string()),
-                    )
-                    .replace(
-                        "{maybe_vflip}",
-                        if config.pixel_format == config::PixelFormat::Bgr24 {
-                            "vflip"
-                        } else {
-                            "null"
-                        },
-                    )
-                    .replace("{output_path}", info.path.to_string_lossy().as_ref()),
+        let ffmpeg_path = find_ffmpeg()?;
+
+        if !config.two_pass {
+            return run_ffmpeg(
+                &info,
+                &config,
+                &ffmpeg_path,
+                &config.args,
+                VideoSource::Host { spool: None },
+                true,
             );
         }
 
-        threads.push(
-            std::thread::Builder::new()
-                .name("aviutl2_ffmpeg_process".to_string())
-                .spawn({
-                    let killed = Arc::clone(&killed);
-                    move || ffmpeg_thread(ffmpeg_path, args, killed)
-                })?,
-        );
-
-        while let Some(thread) = threads.pop() {
-            if thread.is_finished() {
-                match thread.join() {
-                    Ok(Ok(())) => continue, // Thread completed successfully
-                    Ok(Err(e)) => {
-                        killed.store(true, std::sync::atomic::Ordering::Relaxed);
-                        return Err(e);
-                    }
-                    Err(e) => {
-                        killed.store(true, std::sync::atomic::Ordering::Relaxed);
-                        return Err(anyhow::anyhow!("Thread panicked: {:?}", e));
-                    }
-                }
-            } else {
-                threads.push(thread);
-            }
-
-            std::thread::yield_now(); // Yield to allow other threads to run
+        if info.video.is_none() {
+            anyhow::bail!("2パスエンコードには動画が必要です。");
         }
-
-        if killed.load(std::sync::atomic::Ordering::Relaxed) {
-            return Err(anyhow::anyhow!("Output was killed"));
+        // パスログと1パス目のフレームは、2パス目が失敗してもドロップ時に削除される
+        let work_dir = TempWorkDir::new()?;
+        let spool_path = work_dir.path().join("frames.raw");
+        let passlog = work_dir.path().join("passlog");
+
+        aviutl2::lprintln!(info, "パス 1/2");
+        run_ffmpeg(
+            &info,
+            &config,
+            &ffmpeg_path,
+            &two_pass_args(&config.args, Pass::First, &passlog),
+            VideoSource::Host {
+                spool: Some(spool_path.clone()),
+            },
+            false,
+        )?;
+        if info.is_aborted() {
+            return Ok(());
         }
-        Ok(())
+
+        aviutl2::lprintln!(info, "パス 2/2");
+        run_ffmpeg(
+            &info,
+            &config,
+            &ffmpeg_path,
+            &two_pass_args(&config.args, Pass::Second, &passlog),
+            VideoSource::Spool(spool_path),
+            true,
+        )
+    }
+
+    fn estimate(
+        &self,
+        info: &aviutl2::output::OutputEstimateInfo,
+    ) -> aviutl2::AnyResult<aviutl2::output::OutputEstimate> {
+        let config = self
+            .config
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock FFmpeg Output Plugin config: {}", e))?;
+        Ok(estimate::estimate(config.active(), info))
     }
 
     fn config(&self, _handle: aviutl2::output::Win32WindowHandle) -> anyhow::Result<()> {
@@ -386,40 +345,62 @@ impl OutputPlugin for FfmpegOutputPlugin {
             Default::default(),
             Box::new(|cc| {
                 cc.egui_ctx.set_fonts(aviutl2_eframe::aviutl2_fonts());
+                let store = self.config.lock().map_err(|e| {
+                    anyhow::anyhow!("Failed to lock FFmpeg Output Plugin config: {}", e)
+                })?;
+                let (config, scope) = store.resolve();
                 Ok(Box::new(FfmpegOutputConfigDialog::new(
-                    self.config
-                        .lock()
-                        .map_err(|e| {
-                            anyhow::anyhow!("Failed to lock FFmpeg Output Plugin config: {}", e)
-                        })?
-                        .clone(),
+                    config.clone(),
+                    scope,
                     result_sender,
                 )))
             }),
         )
         .map_err(|e| anyhow::anyhow!("Failed to run FFmpeg Output Plugin configuration: {}", e))?;
 
-        if let Ok(new_config) = result_receiver.try_recv() {
-            let mut config = self.config.lock().map_err(|e| {
+        if let Ok((new_config, scope)) = result_receiver.try_recv() {
+            let mut store = self.config.lock().map_err(|e| {
                 anyhow::anyhow!("Failed to lock FFmpeg Output Plugin config: {}", e)
             })?;
-            *config = new_config;
+            // プロジェクトの設定は、プロジェクトの保存時に書き込まれる
+            if scope == ConfigScope::Global {
+                save_global_config(&get_global_config_path()?, &new_config)?;
+            }
+            store.apply(new_config, scope);
         }
         Ok(())
     }
 
     fn config_text(&self) -> anyhow::Result<String> {
-        let config = self
+        let store = self
             .config
             .lock()
             .map_err(|e| anyhow::anyhow!("Failed to lock FFmpeg Output Plugin config: {}", e))?;
+        let (config, scope) = store.resolve();
+        let scope = scope.label();
         let args = PRESETS
             .iter()
             .find(|p| config.args == p.args)
             .map_or("カスタム", |preset| preset.name);
         let pixel_format = config.pixel_format.as_str();
+        let two_pass = if config.two_pass { " | 2パス" } else { "" };
+        let silent_audio = if config.silent_audio {
+            " | 無音トラック"
+        } else {
+            ""
+        };
+        let audio_offset = if config.audio_offset_ms != 0 {
+            format!(" | 音声オフセット：{}ms", config.audio_offset_ms)
+        } else {
+            String::new()
+        };
+        let video_filters = if config.video_filters.trim().is_empty() {
+            String::new()
+        } else {
+            format!(" | フィルタ：{}", config.video_filters.trim())
+        };
         Ok(format!(
-            "引数：{args} | ピクセルフォーマット：{pixel_format}"
+            "設定：{scope} | 引数：{args} | ピクセルフォーマット：{pixel_format}{two_pass}{silent_audio}{audio_offset}{video_filters}"
         ))
     }
 
@@ -427,15 +408,17 @@ impl OutputPlugin for FfmpegOutputPlugin {
         &self,
         project: &mut aviutl2::generic::ProjectFile,
     ) -> aviutl2::common::AnyResult<()> {
-        let mut config = self
+        let mut store = self
             .config
             .lock()
             .map_err(|e| anyhow::anyhow!("Failed to lock FFmpeg Output Plugin config: {}", e))?;
+        // 設定が無いプロジェクトや読み込めなかった場合は、前のプロジェクトの設定を引き継がずに全体の設定を使う
         match load_project_config(project) {
             Ok(loaded_config) => {
-                *config = loaded_config;
+                store.project = loaded_config;
             }
             Err(e) => {
+                store.project = None;
                 aviutl2::lprintln!(
                     info,
                     "Failed to load FFmpeg output plugin config from project file: {}",
@@ -450,15 +433,227 @@ impl OutputPlugin for FfmpegOutputPlugin {
         &self,
         project: &mut aviutl2::generic::ProjectFile,
     ) -> aviutl2::common::AnyResult<()> {
-        let config = self
+        let store = self
             .config
             .lock()
             .map_err(|e| anyhow::anyhow!("Failed to lock FFmpeg Output Plugin config: {}", e))?;
-        save_project_config(project, &config)?;
+        match &store.project {
+            Some(config) => save_project_config(project, config)?,
+            // 全体の設定を使う場合は、以前に保存したプロジェクトの設定を消す
+            None => project.clear_params(),
+        }
         Ok(())
     }
 }
 
+/// 動画の入力元。
+enum VideoSource {
+    /// AviUtl2からフレームを取得する。`spool`が指定されている場合、同じフレームをファイルにも書き出す。
+    Host { spool: Option<std::path::PathBuf> },
+    /// 1パス目で書き出したフレームを読み込む。
+    Spool(std::path::PathBuf),
+}
+
+/// AviUtl2から取得したフレームを`write_frame`に渡す。
+///
+/// `progress`が指定されている場合、進捗表示はそれで更新する。
+fn write_host_frames(
+    info: &aviutl2::output::OutputInfo,
+    pixel_format: config::PixelFormat,
+    progress: Option<PassProgress>,
+    write_frame: impl FnMut(&[u8]) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    match pixel_format {
+        config::PixelFormat::Yuy2 => write_host_frames_as::<BorrowedRawYuy2VideoFrame>(
+            info,
+            progress,
+            |frame| frame.as_slice(),
+            write_frame,
+        ),
+        config::PixelFormat::Bgr24 => write_host_frames_as::<BorrowedRawBgrVideoFrame>(
+            info,
+            progress,
+            |frame| frame.as_slice(),
+            write_frame,
+        ),
+        config::PixelFormat::Pa64 => write_host_frames_as::<BorrowedRawPa64VideoFrame>(
+            info,
+            progress,
+            |frame| frame.as_slice().as_bytes(),
+            write_frame,
+        ),
+        config::PixelFormat::Hf64 => write_host_frames_as::<BorrowedRawHf64VideoFrame>(
+            info,
+            progress,
+            |frame| frame.as_slice().as_bytes(),
+            write_frame,
+        ),
+    }
+}
+
+fn write_host_frames_as<F: aviutl2::output::video_frame::FromRawVideoFrame>(
+    info: &aviutl2::output::OutputInfo,
+    progress: Option<PassProgress>,
+    as_bytes: impl Fn(&F) -> &[u8],
+    mut write_frame: impl FnMut(&[u8]) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let Some(mut progress) = progress else {
+        for (_, frame) in info.get_video_frames_iter::<F>() {
+            write_frame(as_bytes(&frame))?;
+        }
+        return Ok(());
+    };
+    // イテレーターは1パス分の進捗を表示してしまうので、自分でフレームを取得する
+    let total_frames = info.video.as_ref().map_or(0, |v| v.num_frames);
+    for i in 0..total_frames {
+        if info.is_aborted() {
+            break;
+        }
+        let Some(frame) = info.get_video_frame::<F>(i as i32) else {
+            break;
+        };
+        write_frame(as_bytes(&frame))?;
+        progress.update(info, i);
+    }
+    Ok(())
+}
+
+/// FFmpegを1回実行し、終了するまで待つ。
+///
+/// 動画・音声がない場合や`with_audio`が`false`の場合は、[`rewrite_stream_args`]で入力を削除します。
+fn run_ffmpeg(
+    info: &Arc<aviutl2::output::OutputInfo>,
+    config: &FfmpegOutputConfig,
+    ffmpeg_path: &std::path::Path,
+    arg_templates: &[String],
+    video_source: VideoSource,
+    with_audio: bool,
+) -> anyhow::Result<()> {
+    let killed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let mut threads: Vec<std::thread::JoinHandle<anyhow::Result<()>>> = Vec::new();
+    let pixel_format = config.pixel_format;
+    let layout = StreamLayout {
+        has_video: info.video.is_some(),
+        has_audio: with_audio && info.audio.is_some(),
+        silent_audio: with_audio && config.silent_audio,
+        audio_offset_ms: config.audio_offset_ms,
+    };
+    let arg_templates = rewrite_stream_args(arg_templates, &layout);
+
+    // 入力を削除した場合、FFmpegがパイプに接続しないので、パイプを作らない
+    let mut video_path = String::new();
+    if layout.has_video {
+        let (path, video_server_thread) = pipe_for_callback("aviutl2_ffmpeg_video_pipe", {
+            let info = Arc::clone(info);
+            move |stream: PipeWriter| -> anyhow::Result<()> {
+                let mut writer = std::io::BufWriter::new(stream);
+                match &video_source {
+                    VideoSource::Host { spool: None } => {
+                        write_host_frames(&info, pixel_format, None, |frame| {
+                            writer.write_all(frame)
+                        })?;
+                    }
+                    VideoSource::Host { spool: Some(path) } => {
+                        let mut spool = std::io::BufWriter::new(
+                            std::fs::File::create(path)
+                                .context("Failed to create file for spooled frames")?,
+                        );
+                        let total_frames = info.video.as_ref().map_or(0, |v| v.num_frames);
+                        write_host_frames(
+                            &info,
+                            pixel_format,
+                            Some(PassProgress::new(Pass::First, total_frames)),
+                            |frame| {
+                                spool.write_all(frame)?;
+                                writer.write_all(frame)
+                            },
+                        )?;
+                        spool.flush()?;
+                    }
+                    VideoSource::Spool(path) => {
+                        two_pass::copy_spooled_frames(&info, pixel_format, path, &mut writer)?;
+                    }
+                }
+                writer.flush()?;
+                Ok(())
+            }
+        })?;
+        threads.push(video_server_thread);
+        video_path = path;
+    }
+
+    let mut audio_path = String::new();
+    if layout.has_audio {
+        let (path, audio_server_thread) = pipe_for_callback("aviutl2_ffmpeg_audio_pipe", {
+            let info = Arc::clone(info);
+            move |stream: PipeWriter| -> anyhow::Result<()> {
+                let mut buf = [0u8; 8]; // 2 f32 values, each 4 bytes
+                let mut writer = std::io::BufWriter::new(stream);
+                for (_, samples) in info.get_stereo_audio_samples_iter::<f32>(
+                    (info.audio.as_ref().map_or(44100, |a| a.sample_rate) / 10) as i32,
+                ) {
+                    for sample in &samples {
+                        buf[0..4].copy_from_slice(&sample.0.to_le_bytes());
+                        buf[4..8].copy_from_slice(&sample.1.to_le_bytes());
+                        writer.write_all(&buf)?;
+                    }
+                    writer.flush()?;
+                }
+                writer.flush()?;
+                Ok(())
+            }
+        })?;
+        threads.push(audio_server_thread);
+        audio_path = path;
+    }
+
+    let args = config::build_args(
+        &arg_templates,
+        config,
+        &config::ArgsInfo::from_output_info(info),
+        &config::ArgsPaths {
+            video_source: &video_path,
+            audio_source: &audio_path,
+            output_path: info.path.to_string_lossy().as_ref(),
+        },
+    );
+
+    threads.push(
+        std::thread::Builder::new()
+            .name("aviutl2_ffmpeg_process".to_string())
+            .spawn({
+                let killed = Arc::clone(&killed);
+                let ffmpeg_path = ffmpeg_path.to_path_buf();
+                move || ffmpeg_thread(ffmpeg_path, args, killed)
+            })?,
+    );
+
+    while let Some(thread) = threads.pop() {
+        if thread.is_finished() {
+            match thread.join() {
+                Ok(Ok(())) => continue, // Thread completed successfully
+                Ok(Err(e)) => {
+                    killed.store(true, std::sync::atomic::Ordering::Relaxed);
+                    return Err(e);
+                }
+                Err(e) => {
+                    killed.store(true, std::sync::atomic::Ordering::Relaxed);
+                    return Err(anyhow::anyhow!("Thread panicked: {:?}", e));
+                }
+            }
+        } else {
+            threads.push(thread);
+        }
+
+        std::thread::yield_now(); // Yield to allow other threads to run
+    }
+
+    if killed.load(std::sync::atomic::Ordering::Relaxed) {
+        return Err(anyhow::anyhow!("Output was killed"));
+    }
+    Ok(())
+}
+
 fn ffmpeg_thread(
     ffmpeg_path: std::path::PathBuf,
     args: Vec<String>,
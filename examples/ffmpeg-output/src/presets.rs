@@ -57,7 +57,7 @@ pub static FINAL_MP4_PRESET: FfmpegPreset = FfmpegPreset {
         "-map",
         "1:a:0",
         "-vf",
-        "{maybe_vflip}",
+        "{maybe_vflip},{video_filters}",
         "-c:v",
         "libx264",
         "-preset",
@@ -70,6 +70,9 @@ pub static FINAL_MP4_PRESET: FfmpegPreset = FfmpegPreset {
         "yuv420p",
         "-b:a",
         "192k",
+        "-threads",
+        "{threads}",
+        "{extra_output_args}",
         "{output_path}",
     ],
     pixel_format: PixelFormat::Yuy2,
@@ -104,7 +107,7 @@ pub static YOUTUBE_PRESET: FfmpegPreset = FfmpegPreset {
         "-map",
         "1:a:0",
         "-vf",
-        "{maybe_vflip}",
+        "{maybe_vflip},{video_filters}",
         "-c:v",
         "libx264",
         "-preset",
@@ -119,6 +122,9 @@ pub static YOUTUBE_PRESET: FfmpegPreset = FfmpegPreset {
         "aac",
         "-b:a",
         "192k",
+        "-threads",
+        "{threads}",
+        "{extra_output_args}",
         "{output_path}",
     ],
     pixel_format: PixelFormat::Yuy2,
@@ -153,7 +159,7 @@ pub static NICONICO_STANDARD_PRESET: FfmpegPreset = FfmpegPreset {
         "-map",
         "1:a:0",
         "-vf",
-        "{maybe_vflip}",
+        "{maybe_vflip},{video_filters}",
         "-c:v",
         "libx264",
         "-profile:v",
@@ -170,6 +176,9 @@ pub static NICONICO_STANDARD_PRESET: FfmpegPreset = FfmpegPreset {
         "yuv420p",
         "-ar",
         "{audio_sample_rate}",
+        "-threads",
+        "{threads}",
+        "{extra_output_args}",
         "{output_path}",
     ],
     pixel_format: PixelFormat::Yuy2,
@@ -204,7 +213,7 @@ pub static NICONICO_MAX_PRESET: FfmpegPreset = FfmpegPreset {
         "-map",
         "1:a:0",
         "-vf",
-        "{maybe_vflip}",
+        "{maybe_vflip},{video_filters}",
         "-c:v",
         "libx264",
         "-crf",
@@ -215,6 +224,9 @@ pub static NICONICO_MAX_PRESET: FfmpegPreset = FfmpegPreset {
         "veryslow",
         "-c:a",
         "flac",
+        "-threads",
+        "{threads}",
+        "{extra_output_args}",
         "{output_path}",
     ],
     pixel_format: PixelFormat::Yuy2,
@@ -249,13 +261,16 @@ pub static PRORES_PRESET: FfmpegPreset = FfmpegPreset {
         "-map",
         "1:a:0",
         "-vf",
-        "{maybe_vflip}",
+        "{maybe_vflip},{video_filters}",
         "-c:v",
         "prores_ks",
         "-profile:v",
         "3",
         "-c:a",
         "pcm_s16le",
+        "-threads",
+        "{threads}",
+        "{extra_output_args}",
         "{output_path}",
     ],
     pixel_format: PixelFormat::Pa64,
@@ -290,7 +305,7 @@ pub static TRANSPARENT_MOV_PRESET: FfmpegPreset = FfmpegPreset {
         "-map",
         "1:a:0",
         "-vf",
-        "{maybe_vflip}",
+        "{maybe_vflip},{video_filters}",
         "-c:v",
         "prores_ks",
         "-profile:v",
@@ -299,6 +314,9 @@ pub static TRANSPARENT_MOV_PRESET: FfmpegPreset = FfmpegPreset {
         "yuva444p10le", // preserve alpha
         "-c:a",
         "pcm_s16le",
+        "-threads",
+        "{threads}",
+        "{extra_output_args}",
         "{output_path}",
     ],
     pixel_format: PixelFormat::Pa64,
@@ -307,7 +325,10 @@ pub static TRANSPARENT_MOV_PRESET: FfmpegPreset = FfmpegPreset {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::REQUIRED_ARGS;
+    use crate::{
+        REQUIRED_ARGS,
+        config::{ArgsInfo, ArgsPaths, FfmpegOutputConfig, build_args},
+    };
     use std::io::Write;
 
     #[test]
@@ -317,7 +338,11 @@ mod tests {
             assert!(!preset.name.is_empty());
             assert!(!preset.description.is_empty());
             assert!(!preset.args.is_empty());
-            assert!(REQUIRED_ARGS.iter().all(|arg| preset.args.contains(arg)));
+            assert!(
+                REQUIRED_ARGS
+                    .iter()
+                    .all(|required| preset.args.iter().any(|arg| arg.contains(required)))
+            );
         }
     }
 
@@ -353,34 +378,32 @@ mod tests {
                 audio.write_all(&sample.to_le_bytes()).unwrap();
             }
         }
-        let video_size = format!("{width}x{height}");
-        let base_replacements = vec![
-            ("{video_pixel_format}", "bgr24"),
-            ("{video_size}", &video_size),
-            ("{video_fps}", "30"),
-            ("{video_source}", test_image_path.to_str().unwrap()),
-            ("{audio_source}", test_audio_path.to_str().unwrap()),
-            ("{audio_sample_rate}", "48000"),
-            ("{maybe_vflip}", "null"), // No vertical flip for this test
-        ];
+        let info = ArgsInfo {
+            video_size: Some((width as u32, height as u32)),
+            video_fps: Some("30".to_string()),
+            audio_sample_rate: Some(48000),
+        };
         for preset in PRESETS {
-            let mut replacements: Vec<(&str, &str)> = base_replacements.clone();
             let extension = match preset.id {
                 "prores" | "transparent_mov" => "mov",
                 "niconico_max" => "mkv",
                 _ => "mp4",
             };
             let output_path = root.join(format!("{}_output.{}", preset.id, extension));
-            replacements.push(("{output_path}", output_path.to_str().unwrap()));
-            let args: Vec<String> = preset
-                .args
-                .iter()
-                .map(|arg| {
-                    replacements
-                        .iter()
-                        .fold(arg.to_string(), |acc, (key, value)| acc.replace(key, value))
-                })
-                .collect();
+            let config = FfmpegOutputConfig {
+                args: preset.args.iter().map(|arg| arg.to_string()).collect(),
+                ..Default::default()
+            };
+            let args = build_args(
+                &config.args,
+                &config,
+                &info,
+                &ArgsPaths {
+                    video_source: test_image_path.to_str().unwrap(),
+                    audio_source: test_audio_path.to_str().unwrap(),
+                    output_path: output_path.to_str().unwrap(),
+                },
+            );
 
             let cmd = std::process::Command::new("ffmpeg")
                 .args(&args)
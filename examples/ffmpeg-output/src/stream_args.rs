@@ -0,0 +1,448 @@
+/// FFmpegに渡す入力ストリームの構成。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamLayout {
+    /// 動画を渡すかどうか。
+    pub has_video: bool,
+    /// 音声を渡すかどうか。
+    pub has_audio: bool,
+    /// 音声がない場合に、`anullsrc`で無音の音声を追加するかどうか。
+    pub silent_audio: bool,
+    /// 音声の入力に指定する`-itsoffset`（ミリ秒）。正の値で音声が遅れます。
+    pub audio_offset_ms: i32,
+}
+
+/// 最初の入力より前に置かれる、値を取らないグローバルオプション。
+const GLOBAL_FLAGS: &[&str] = &["-y", "-n", "-hide_banner", "-nostdin", "-stats", "-nostats"];
+/// 最初の入力より前に置かれる、値を取るグローバルオプション。
+const GLOBAL_OPTIONS: &[&str] = &["-loglevel", "-v"];
+
+/// 入力（`-i`とその前の入力オプション）の位置。
+#[derive(Debug, Clone, Copy)]
+struct Input {
+    /// 入力オプションの開始位置。
+    start: usize,
+    /// `-i`の位置。
+    flag: usize,
+}
+
+impl Input {
+    fn end(&self) -> usize {
+        self.flag + 2
+    }
+}
+
+fn find_inputs(args: &[String]) -> Vec<Input> {
+    let mut inputs = Vec::new();
+    let mut start = 0;
+    while start < args.len() {
+        let arg = args[start].as_str();
+        if GLOBAL_FLAGS.contains(&arg) {
+            start += 1;
+        } else if GLOBAL_OPTIONS.contains(&arg) {
+            start += 2;
+        } else {
+            break;
+        }
+    }
+    let mut i = start;
+    while i + 1 < args.len() {
+        if args[i] == "-i" {
+            inputs.push(Input { start, flag: i });
+            start = i + 2;
+            i = start;
+        } else {
+            i += 1;
+        }
+    }
+    inputs
+}
+
+/// `-map`の値の入力番号を置き換える。入力が削除された場合は`None`を返す。
+fn remap_stream_specifier(specifier: &str, new_indices: &[Option<usize>]) -> Option<String> {
+    let (index, rest) = match specifier.split_once(':') {
+        Some((index, rest)) => (index, Some(rest)),
+        None => (specifier, None),
+    };
+    // `-map -0:a`のような否定や、`[out]`のようなフィルターの出力はそのままにする
+    let Ok(index) = index.parse::<usize>() else {
+        return Some(specifier.to_string());
+    };
+    let Some(&new_index) = new_indices.get(index) else {
+        return Some(specifier.to_string());
+    };
+    let new_index = new_index?;
+    Some(match rest {
+        Some(rest) => format!("{new_index}:{rest}"),
+        None => new_index.to_string(),
+    })
+}
+
+/// 入力ストリームの構成に合わせて引数のテンプレートを書き換える。
+///
+/// - 動画・音声がない場合は、その入力と、入力を参照する`-map`・`-vf`・`-af`を削除し、出力に`-vn`・`-an`を追加します。
+/// - 音声がなく、`silent_audio`が`true`の場合は、音声の入力を`anullsrc`に置き換え、出力に`-shortest`を追加します。
+/// - 音声があり、`audio_offset_ms`が0以外の場合は、音声の入力の前に`-itsoffset`を追加します。
+///
+/// 入力は`{video_source}`・`{audio_source}`を含む`-i`の引数で判別します。
+/// 出力の引数は`{output_path}`を含む引数、なければ最後の引数とみなします。
+pub fn rewrite_stream_args(args: &[String], layout: &StreamLayout) -> Vec<String> {
+    let inputs = find_inputs(args);
+    let input_containing = |placeholder: &str| {
+        inputs
+            .iter()
+            .position(|input| args[input.flag + 1].contains(placeholder))
+    };
+    let video_input = input_containing("{video_source}");
+    let audio_input = input_containing("{audio_source}");
+
+    let remove_video = !layout.has_video && video_input.is_some();
+    let remove_audio = !layout.has_audio && !layout.silent_audio && audio_input.is_some();
+    let silent_audio = !layout.has_audio && layout.silent_audio && audio_input.is_some();
+    let removed = |index: usize| {
+        (remove_video && Some(index) == video_input) || (remove_audio && Some(index) == audio_input)
+    };
+    let mut new_indices = Vec::with_capacity(inputs.len());
+    let mut next_index = 0;
+    for index in 0..inputs.len() {
+        if removed(index) {
+            new_indices.push(None);
+        } else {
+            new_indices.push(Some(next_index));
+            next_index += 1;
+        }
+    }
+
+    let output_position = args
+        .iter()
+        .position(|arg| arg.contains("{output_path}"))
+        .unwrap_or(args.len().saturating_sub(1));
+
+    let mut result = Vec::with_capacity(args.len() + 8);
+    let mut i = 0;
+    while i < args.len() {
+        if let Some(index) = inputs.iter().position(|input| input.start == i) {
+            let input = inputs[index];
+            if removed(index) {
+                // 入力オプションごと削除する
+            } else if silent_audio && Some(index) == audio_input {
+                result.extend(
+                    [
+                        "-f",
+                        "lavfi",
+                        "-i",
+                        "anullsrc=channel_layout=stereo:sample_rate={audio_sample_rate}",
+                    ]
+                    .map(str::to_string),
+                );
+            } else {
+                if layout.has_audio && layout.audio_offset_ms != 0 && Some(index) == audio_input {
+                    result.push("-itsoffset".to_string());
+                    result.push(format!("{:.3}", layout.audio_offset_ms as f64 / 1000.0));
+                }
+                result.extend_from_slice(&args[input.start..input.end()]);
+            }
+            i = input.end().max(i + 1);
+            continue;
+        }
+
+        let arg = args[i].as_str();
+        if i == output_position {
+            if remove_video {
+                result.push("-vn".to_string());
+            }
+            if remove_audio {
+                result.push("-an".to_string());
+            }
+            if silent_audio {
+                result.push("-shortest".to_string());
+            }
+        }
+        if i + 1 < args.len() && i + 1 != output_position {
+            let value = args[i + 1].as_str();
+            let drop_option = match arg {
+                "-vf" | "-filter:v" => remove_video,
+                "-af" | "-filter:a" => remove_audio,
+                _ => false,
+            };
+            if drop_option {
+                i += 2;
+                continue;
+            }
+            if arg == "-map" {
+                if let Some(specifier) = remap_stream_specifier(value, &new_indices) {
+                    result.push(arg.to_string());
+                    result.push(specifier);
+                }
+                i += 2;
+                continue;
+            }
+        }
+        result.push(arg.to_string());
+        i += 1;
+    }
+    result
+}
+
+/// `{video_source}`・`{audio_source}`を含む入力を、入力オプションごと置き換える。
+///
+/// `replacement`にはプレースホルダーを渡し、置き換え後の引数（`-i`を含む）を返します。
+/// `None`を返した場合はそのままにします。
+pub fn replace_source_inputs(
+    args: &[String],
+    replacement: impl Fn(&str) -> Option<Vec<String>>,
+) -> Vec<String> {
+    let inputs = find_inputs(args);
+    let mut result = Vec::with_capacity(args.len());
+    let mut i = 0;
+    while i < args.len() {
+        if let Some(input) = inputs.iter().find(|input| input.start == i) {
+            let source = args[input.flag + 1].as_str();
+            let replaced = ["{video_source}", "{audio_source}"]
+                .into_iter()
+                .find(|placeholder| source.contains(placeholder))
+                .and_then(&replacement);
+            match replaced {
+                Some(replaced) => result.extend(replaced),
+                None => result.extend_from_slice(&args[input.start..input.end()]),
+            }
+            i = input.end().max(i + 1);
+            continue;
+        }
+        result.push(args[i].clone());
+        i += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_ARGS;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn layout(has_video: bool, has_audio: bool) -> StreamLayout {
+        StreamLayout {
+            has_video,
+            has_audio,
+            silent_audio: false,
+            audio_offset_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_default_args_with_video_and_audio() {
+        let default = args(DEFAULT_ARGS);
+        assert_eq!(rewrite_stream_args(&default, &layout(true, true)), default);
+    }
+
+    #[test]
+    fn test_default_args_without_audio() {
+        assert_eq!(
+            rewrite_stream_args(&args(DEFAULT_ARGS), &layout(true, false)),
+            args(&[
+                "-y",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "{video_pixel_format}",
+                "-video_size",
+                "{video_size}",
+                "-framerate",
+                "{video_fps}",
+                "-i",
+                "{video_source}",
+                "-map",
+                "0:v:0",
+                "-vf",
+                "{maybe_vflip},{video_filters}",
+                "-pix_fmt",
+                "yuv420p",
+                "-threads",
+                "{threads}",
+                "{extra_output_args}",
+                "-an",
+                "{output_path}",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_default_args_without_video() {
+        assert_eq!(
+            rewrite_stream_args(&args(DEFAULT_ARGS), &layout(false, true)),
+            args(&[
+                "-y",
+                "-f",
+                "f32le",
+                "-ar",
+                "{audio_sample_rate}",
+                "-ac",
+                "2",
+                "-i",
+                "{audio_source}",
+                "-map",
+                "0:a:0",
+                "-pix_fmt",
+                "yuv420p",
+                "-threads",
+                "{threads}",
+                "{extra_output_args}",
+                "-vn",
+                "{output_path}",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_default_args_with_silent_audio() {
+        let layout = StreamLayout {
+            silent_audio: true,
+            // 音声がない場合、オフセットは無視される
+            audio_offset_ms: 100,
+            ..layout(true, false)
+        };
+        assert_eq!(
+            rewrite_stream_args(&args(DEFAULT_ARGS), &layout),
+            args(&[
+                "-y",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "{video_pixel_format}",
+                "-video_size",
+                "{video_size}",
+                "-framerate",
+                "{video_fps}",
+                "-i",
+                "{video_source}",
+                "-f",
+                "lavfi",
+                "-i",
+                "anullsrc=channel_layout=stereo:sample_rate={audio_sample_rate}",
+                "-map",
+                "0:v:0",
+                "-map",
+                "1:a:0",
+                "-vf",
+                "{maybe_vflip},{video_filters}",
+                "-pix_fmt",
+                "yuv420p",
+                "-threads",
+                "{threads}",
+                "{extra_output_args}",
+                "-shortest",
+                "{output_path}",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_default_args_with_audio_offset() {
+        let layout = StreamLayout {
+            audio_offset_ms: -50,
+            ..layout(true, true)
+        };
+        let rewritten = rewrite_stream_args(&args(DEFAULT_ARGS), &layout);
+        let offset = rewritten
+            .iter()
+            .position(|arg| arg == "-itsoffset")
+            .unwrap();
+        assert_eq!(
+            rewritten[offset..offset + 4],
+            args(&["-itsoffset", "-0.050", "-f", "f32le"])
+        );
+        assert_eq!(rewritten.len(), DEFAULT_ARGS.len() + 2);
+    }
+
+    #[test]
+    fn test_remaps_inputs_after_removed_input() {
+        let template = args(&[
+            "-y",
+            "-f",
+            "f32le",
+            "-i",
+            "{audio_source}",
+            "-i",
+            "logo.png",
+            "-f",
+            "rawvideo",
+            "-i",
+            "{video_source}",
+            "-map",
+            "2:v",
+            "-map",
+            "0:a",
+            "-map",
+            "1",
+            "-af",
+            "volume=2",
+            "{output_path}",
+        ]);
+        assert_eq!(
+            rewrite_stream_args(&template, &layout(true, false)),
+            args(&[
+                "-y",
+                "-i",
+                "logo.png",
+                "-f",
+                "rawvideo",
+                "-i",
+                "{video_source}",
+                "-map",
+                "1:v",
+                "-map",
+                "0",
+                "-an",
+                "{output_path}",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_replace_source_inputs() {
+        let template = args(&[
+            "-y",
+            "-f",
+            "rawvideo",
+            "-video_size",
+            "{video_size}",
+            "-i",
+            "{video_source}",
+            "-i",
+            "logo.png",
+            "-f",
+            "f32le",
+            "-i",
+            "{audio_source}",
+            "-c:v",
+            "libx264",
+            "{output_path}",
+        ]);
+        let replaced = replace_source_inputs(&template, |placeholder| match placeholder {
+            "{video_source}" => Some(args(&["-f", "lavfi", "-i", "testsrc"])),
+            _ => None,
+        });
+        assert_eq!(
+            replaced,
+            args(&[
+                "-y",
+                "-f",
+                "lavfi",
+                "-i",
+                "testsrc",
+                "-i",
+                "logo.png",
+                "-f",
+                "f32le",
+                "-i",
+                "{audio_source}",
+                "-c:v",
+                "libx264",
+                "{output_path}",
+            ])
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use crate::config::PixelFormat;
+use anyhow::Context;
+use std::io::{Read, Write};
+
+/// 2パスエンコードのパス。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    First,
+    Second,
+}
+
+/// 2パスエンコード用のFFmpegの引数を作る。
+///
+/// `{output_path}`を含む引数の直前に`-pass`と`-passlogfile`を挿入します。
+/// 1パス目では、出力先を`NUL`に置き換え、音声を無効にします。
+pub fn two_pass_args(args: &[String], pass: Pass, passlog: &std::path::Path) -> Vec<String> {
+    let mut result = Vec::with_capacity(args.len() + 8);
+    for arg in args {
+        if !arg.contains("{output_path}") {
+            result.push(arg.clone());
+            continue;
+        }
+        let pass_number = match pass {
+            Pass::First => "1",
+            Pass::Second => "2",
+        };
+        result.extend([
+            "-pass".to_string(),
+            pass_number.to_string(),
+            "-passlogfile".to_string(),
+            passlog.to_string_lossy().into_owned(),
+        ]);
+        match pass {
+            Pass::First => result.extend(["-an", "-f", "null", "NUL"].map(str::to_string)),
+            Pass::Second => result.push(arg.clone()),
+        }
+    }
+    result
+}
+
+/// 2パスエンコードの間、フレームとパスログを置いておく一時ディレクトリ。
+///
+/// ドロップ時に中身ごと削除されます。
+#[derive(Debug)]
+pub struct TempWorkDir {
+    path: std::path::PathBuf,
+}
+
+impl TempWorkDir {
+    pub fn new() -> anyhow::Result<Self> {
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        let path = std::env::temp_dir().join(format!("rusty_ffmpeg_{nonce}"));
+        std::fs::create_dir_all(&path)
+            .with_context(|| format!("Failed to create temporary directory {path:?}"))?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+impl Drop for TempWorkDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.path) {
+            aviutl2::tracing::warn!("Failed to remove temporary directory {:?}: {e}", self.path);
+        }
+    }
+}
+
+/// 2パスエンコードの進捗表示。
+///
+/// AviUtl2の進捗表示はフレーム数しか受け取らないため、
+/// 1パス目を前半、2パス目を後半として、2パス分のフレーム数で表示します。
+pub struct PassProgress {
+    pass: Pass,
+    total_frames: u32,
+    last_updated_time: std::time::Instant,
+}
+
+impl PassProgress {
+    pub fn new(pass: Pass, total_frames: u32) -> Self {
+        Self {
+            pass,
+            total_frames,
+            last_updated_time: std::time::Instant::now(),
+        }
+    }
+
+    /// `frame`を処理しているときに表示する、現在のフレームと全体のフレーム数。
+    fn display(&self, frame: u32) -> (i32, i32) {
+        let offset = match self.pass {
+            Pass::First => 0,
+            Pass::Second => self.total_frames,
+        };
+        (
+            i32::try_from(offset.saturating_add(frame)).unwrap_or(i32::MAX),
+            i32::try_from(self.total_frames.saturating_mul(2)).unwrap_or(i32::MAX),
+        )
+    }
+
+    /// 進捗表示を更新する。頻繁に呼ばれても0.1秒に1回だけ更新します。
+    pub fn update(&mut self, info: &aviutl2::output::OutputInfo, frame: u32) {
+        if self.last_updated_time.elapsed().as_secs_f32() > 0.1 {
+            let (current, total) = self.display(frame);
+            info.update_display(current, total);
+            self.last_updated_time = std::time::Instant::now();
+        }
+    }
+}
+
+/// 1パス目で書き出したフレームを`writer`に書き込む。
+pub fn copy_spooled_frames(
+    info: &aviutl2::output::OutputInfo,
+    pixel_format: PixelFormat,
+    spool_path: &std::path::Path,
+    writer: &mut impl Write,
+) -> anyhow::Result<()> {
+    let video = info
+        .video
+        .as_ref()
+        .context("2パスエンコードには動画が必要です。")?;
+    let total_frames = video.num_frames;
+    let frame_size = pixel_format.frame_size(video.width, video.height);
+    let spool = std::fs::File::open(spool_path).context("Failed to open spooled frames")?;
+    let spool_len = spool
+        .metadata()
+        .context("Failed to get spooled frames size")?
+        .len();
+    let expected_len = frame_size as u64 * u64::from(total_frames);
+    anyhow::ensure!(
+        spool_len == expected_len,
+        "Spooled frames size mismatch: expected {expected_len} bytes, got {spool_len} bytes"
+    );
+    let mut spool = std::io::BufReader::new(spool);
+    let mut buf = vec![0u8; frame_size];
+    let mut progress = PassProgress::new(Pass::Second, total_frames);
+    for i in 0..total_frames {
+        if info.is_aborted() {
+            break;
+        }
+        spool
+            .read_exact(&mut buf)
+            .context("Failed to read spooled frames")?;
+        writer.write_all(&buf)?;
+        progress.update(info, i);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_two_pass_args() {
+        let base = args(&["-i", "{video_source}", "-b:v", "2M", "{output_path}"]);
+        let passlog = std::path::Path::new("passlog");
+        assert_eq!(
+            two_pass_args(&base, Pass::First, passlog),
+            args(&[
+                "-i",
+                "{video_source}",
+                "-b:v",
+                "2M",
+                "-pass",
+                "1",
+                "-passlogfile",
+                "passlog",
+                "-an",
+                "-f",
+                "null",
+                "NUL",
+            ])
+        );
+        assert_eq!(
+            two_pass_args(&base, Pass::Second, passlog),
+            args(&[
+                "-i",
+                "{video_source}",
+                "-b:v",
+                "2M",
+                "-pass",
+                "2",
+                "-passlogfile",
+                "passlog",
+                "{output_path}",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_pass_progress_spans_both_passes() {
+        let first = PassProgress::new(Pass::First, 100);
+        assert_eq!(first.display(0), (0, 200));
+        assert_eq!(first.display(99), (99, 200));
+        let second = PassProgress::new(Pass::Second, 100);
+        assert_eq!(second.display(0), (100, 200));
+        assert_eq!(second.display(99), (199, 200));
+    }
+
+    #[test]
+    fn test_temp_work_dir_is_removed_on_drop() {
+        let dir = TempWorkDir::new().unwrap();
+        let path = dir.path().to_path_buf();
+        std::fs::write(path.join("passlog-0.log"), b"stats").unwrap();
+        std::fs::write(path.join("passlog-0.log.mbtree"), b"mbtree").unwrap();
+        drop(dir);
+        assert!(!path.exists());
+    }
+}
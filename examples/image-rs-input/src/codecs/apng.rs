@@ -1,4 +1,5 @@
 use super::AnimationInfo;
+use super::canvas::{Blend, Canvas, Dispose, Region};
 use ordered_float::OrderedFloat;
 
 pub fn read_headers<R: std::io::BufRead + std::io::Seek>(
@@ -40,6 +41,108 @@ pub fn read_headers<R: std::io::BufRead + std::io::Seek>(
     })
 }
 
+/// APNGのフレームを順番にデコードし、キャンバスに合成する。
+pub struct Frames<R: std::io::BufRead + std::io::Seek> {
+    reader: png::Reader<R>,
+    canvas: Canvas,
+    /// デコードしたフレーム（キャンバスの大きさ分確保する）。
+    buffer: Vec<u8>,
+    /// 合成したフレームの数。
+    composed: u32,
+}
+
+impl<R: std::io::BufRead + std::io::Seek> Frames<R> {
+    pub fn new(mut reader: R) -> Result<Self, anyhow::Error> {
+        reader.seek(std::io::SeekFrom::Start(0))?;
+        let mut decoder = png::Decoder::new(reader);
+        // 16bitやパレットの画像も、8bitのグレースケール+αかRGBAにする
+        decoder.set_transformations(
+            png::Transformations::EXPAND
+                | png::Transformations::STRIP_16
+                | png::Transformations::ALPHA,
+        );
+        let mut reader = decoder.read_info()?;
+        if reader.info().animation_control().is_some() && reader.info().frame_control().is_none() {
+            // `fcTL`の無い`IDAT`はアニメーションに含まれないので読み飛ばす
+            reader.next_frame_info()?;
+        }
+        let buffer_size = reader
+            .output_buffer_size()
+            .ok_or_else(|| anyhow::anyhow!("Image is too large"))?;
+        let info = reader.info();
+        let canvas = Canvas::new(info.width, info.height);
+        Ok(Self {
+            reader,
+            canvas,
+            buffer: vec![0; buffer_size],
+            composed: 0,
+        })
+    }
+
+    /// 合成済みのキャンバス（RGBA）。
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    /// 次のフレームをデコードしてキャンバスに合成する。フレームが残っていない場合は`false`を返す。
+    pub fn advance(&mut self) -> Result<bool, anyhow::Error> {
+        let num_frames = self
+            .reader
+            .info()
+            .animation_control()
+            .map_or(1, |animation_control| animation_control.num_frames);
+        if self.composed >= num_frames {
+            return Ok(false);
+        }
+        let output = self.reader.next_frame(&mut self.buffer)?;
+        let (region, dispose, blend) = match self.reader.info().frame_control() {
+            Some(frame_control) => (
+                Region {
+                    x: frame_control.x_offset,
+                    y: frame_control.y_offset,
+                    width: frame_control.width,
+                    height: frame_control.height,
+                },
+                match frame_control.dispose_op {
+                    png::DisposeOp::None => Dispose::None,
+                    png::DisposeOp::Background => Dispose::Background,
+                    // 最初のフレームで`APNG_DISPOSE_OP_PREVIOUS`の場合は、`APNG_DISPOSE_OP_BACKGROUND`として扱う
+                    png::DisposeOp::Previous if self.composed == 0 => Dispose::Background,
+                    png::DisposeOp::Previous => Dispose::Previous,
+                },
+                match frame_control.blend_op {
+                    png::BlendOp::Source => Blend::Source,
+                    png::BlendOp::Over => Blend::Over,
+                },
+            ),
+            None => (
+                Region {
+                    x: 0,
+                    y: 0,
+                    width: output.width,
+                    height: output.height,
+                },
+                Dispose::None,
+                Blend::Source,
+            ),
+        };
+        let frame = &self.buffer[..output.line_size * output.height as usize];
+        let rgba = match output.color_type {
+            png::ColorType::Rgba => std::borrow::Cow::Borrowed(frame),
+            png::ColorType::GrayscaleAlpha => std::borrow::Cow::Owned(
+                frame
+                    .chunks_exact(2)
+                    .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                    .collect(),
+            ),
+            color_type => anyhow::bail!("Unexpected color type: {color_type:?}"),
+        };
+        self.canvas.draw(region, &rgba, blend, dispose)?;
+        self.composed += 1;
+        Ok(true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,4 +164,75 @@ mod tests {
 
         insta::assert_debug_snapshot!(animation_info);
     }
+
+    /// `frames`を最後まで合成し、各フレームの`(x, y)`の画素を返す。
+    fn compose_all(data: &[u8], x: u32, y: u32) -> Vec<[u8; 4]> {
+        let mut frames = Frames::new(std::io::Cursor::new(data)).unwrap();
+        let mut pixels = vec![];
+        while frames.advance().unwrap() {
+            let canvas = frames.canvas();
+            assert_eq!(canvas.pixels().len(), 4 * 4 * 4);
+            let offset = ((y * canvas.width() + x) * 4) as usize;
+            pixels.push(canvas.pixels()[offset..offset + 4].try_into().unwrap());
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_compose_dispose_previous() {
+        // 赤の全面 → 左上2x2に緑（PREVIOUS） → 右下2x2に青
+        let data = include_bytes!("../../test_data/apng_dispose_previous.png");
+        assert_eq!(
+            compose_all(data, 0, 0),
+            [[255, 0, 0, 255], [0, 255, 0, 255], [255, 0, 0, 255]]
+        );
+        assert_eq!(
+            compose_all(data, 3, 3),
+            [[255, 0, 0, 255], [255, 0, 0, 255], [0, 0, 255, 255]]
+        );
+    }
+
+    #[test]
+    fn test_compose_dispose_previous_first_frame() {
+        // 最初のフレームのPREVIOUSはBACKGROUNDとして扱うので、赤は消える
+        let data = include_bytes!("../../test_data/apng_dispose_previous_first.png");
+        assert_eq!(compose_all(data, 0, 0), [[255, 0, 0, 255], [0, 0, 0, 0]]);
+        assert_eq!(
+            compose_all(data, 1, 1),
+            [[255, 0, 0, 255], [0, 255, 0, 255]]
+        );
+    }
+
+    #[test]
+    fn test_compose_dispose_background_blend_over() {
+        // 赤の全面（BACKGROUND） → 中央2x2に半透明の青（OVER） → 全面に半透明の緑（OVER） → (1, 1)を透明に（SOURCE）
+        let data = include_bytes!("../../test_data/apng_dispose_background_blend_over.png");
+        assert_eq!(
+            compose_all(data, 0, 0),
+            [
+                [255, 0, 0, 255],
+                [0, 0, 0, 0],
+                [0, 255, 0, 128],
+                [0, 255, 0, 128]
+            ]
+        );
+        assert_eq!(
+            compose_all(data, 1, 1),
+            [
+                [255, 0, 0, 255],
+                [0, 0, 255, 128],
+                [0, 170, 85, 192],
+                [0, 0, 0, 0]
+            ]
+        );
+        assert_eq!(
+            compose_all(data, 2, 2),
+            [
+                [255, 0, 0, 255],
+                [0, 0, 255, 128],
+                [0, 170, 85, 192],
+                [0, 170, 85, 192]
+            ]
+        );
+    }
 }
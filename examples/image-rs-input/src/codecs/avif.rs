@@ -0,0 +1,65 @@
+// image-rsのAVIFデコーダーは主画像しかデコードしないため、ftypボックスを読んで画像シーケンスかどうかを判定する
+pub fn is_image_sequence<R: std::io::Read + std::io::Seek>(
+    reader: &mut R,
+) -> Result<bool, anyhow::Error> {
+    reader.seek(std::io::SeekFrom::Start(0))?;
+    let mut header = [0; 8];
+    reader.read_exact(&mut header)?;
+    anyhow::ensure!(&header[4..8] == b"ftyp", "Not a valid AVIF file");
+    let box_size = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+    anyhow::ensure!(
+        box_size >= 16 && (box_size - 8) % 4 == 0,
+        "Invalid ftyp box size: {box_size}"
+    );
+    let mut body = vec![0; box_size - 8];
+    reader.read_exact(&mut body)?;
+
+    // major_brand、minor_version、compatible_brands[]の順に並んでいる
+    let major_brand = &body[0..4];
+    let compatible_brands = body[8..].chunks_exact(4);
+    Ok(std::iter::once(major_brand)
+        .chain(compatible_brands)
+        .any(|brand| brand == b"avis"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ftyp(major_brand: &[u8; 4], compatible_brands: &[&[u8; 4]]) -> Vec<u8> {
+        let size = 16 + compatible_brands.len() * 4;
+        let mut data = Vec::with_capacity(size);
+        data.extend_from_slice(&(size as u32).to_be_bytes());
+        data.extend_from_slice(b"ftyp");
+        data.extend_from_slice(major_brand);
+        data.extend_from_slice(&0u32.to_be_bytes());
+        for brand in compatible_brands {
+            data.extend_from_slice(*brand);
+        }
+        data
+    }
+
+    #[test]
+    fn test_still_avif_is_not_sequence() {
+        let data = ftyp(b"avif", &[b"avif", b"mif1", b"miaf"]);
+        let mut cursor = std::io::Cursor::new(data);
+        assert!(!is_image_sequence(&mut cursor).unwrap());
+    }
+
+    #[test]
+    fn test_detect_image_sequence() {
+        let data = ftyp(b"avis", &[b"avis", b"msf1", b"miaf"]);
+        let mut cursor = std::io::Cursor::new(data);
+        assert!(is_image_sequence(&mut cursor).unwrap());
+
+        let data = ftyp(b"avif", &[b"avif", b"avis", b"mif1"]);
+        let mut cursor = std::io::Cursor::new(data);
+        assert!(is_image_sequence(&mut cursor).unwrap());
+    }
+
+    #[test]
+    fn test_reject_non_avif() {
+        let mut cursor = std::io::Cursor::new(b"RIFF\0\0\0\0WEBPVP8 ".to_vec());
+        assert!(is_image_sequence(&mut cursor).is_err());
+    }
+}
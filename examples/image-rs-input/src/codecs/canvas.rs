@@ -0,0 +1,290 @@
+//! アニメーションのフレームをキャンバスに合成する。
+//!
+//! APNG・GIFのフレームはキャンバスの一部の領域だけを持つことがあるので、
+//! 直前のフレームの破棄方法と、このフレームの合成方法に従ってキャンバスに重ねる。
+
+/// フレームを表示した後、次のフレームを描く前にその領域をどうするか。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dispose {
+    /// そのまま残す。
+    None,
+    /// 透明にする。
+    Background,
+    /// このフレームを描く前の状態に戻す。
+    Previous,
+}
+
+/// フレームの画素をキャンバスにどう重ねるか。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blend {
+    /// キャンバスの画素を置き換える。
+    Source,
+    /// ストレートαでキャンバスの上に重ねる。
+    Over,
+}
+
+/// キャンバス上のフレームの領域。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// RGBA（ストレートα）のキャンバス。
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    /// 直前のフレームの領域と破棄方法。次のフレームを描く前に適用する。
+    pending_dispose: Option<(Region, Dispose)>,
+    /// `Dispose::Previous`で戻すための、直前のフレームを描く前の領域の画素。
+    saved: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+            pending_dispose: None,
+            saved: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// 合成済みのキャンバスの画素。
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// 最初のフレームを描く前の、透明な状態に戻す。
+    pub fn reset(&mut self) {
+        self.pixels.fill(0);
+        self.pending_dispose = None;
+        self.saved.clear();
+    }
+
+    /// 直前のフレームを破棄してから、`pixels`（`region`の大きさのRGBA）を描く。
+    ///
+    /// キャンバスからはみ出した部分は捨てる。
+    pub fn draw(
+        &mut self,
+        region: Region,
+        pixels: &[u8],
+        blend: Blend,
+        dispose: Dispose,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            pixels.len() == region.width as usize * region.height as usize * 4,
+            "Frame data size mismatch: expected {}x{}, got {} bytes",
+            region.width,
+            region.height,
+            pixels.len()
+        );
+
+        match self.pending_dispose.take() {
+            Some((previous, Dispose::Background)) => {
+                for (canvas, _) in spans(self.width, self.height, previous) {
+                    self.pixels[canvas].fill(0);
+                }
+            }
+            Some((previous, Dispose::Previous)) => {
+                // `saved`は同じ領域から保存しているので、行の数も長さも一致する
+                let mut offset = 0;
+                for (canvas, _) in spans(self.width, self.height, previous) {
+                    let len = canvas.len();
+                    self.pixels[canvas].copy_from_slice(&self.saved[offset..offset + len]);
+                    offset += len;
+                }
+            }
+            Some((_, Dispose::None)) | None => {}
+        }
+
+        if dispose == Dispose::Previous {
+            self.saved.clear();
+            for (canvas, _) in spans(self.width, self.height, region) {
+                self.saved.extend_from_slice(&self.pixels[canvas]);
+            }
+        }
+
+        for (canvas, frame) in spans(self.width, self.height, region) {
+            let dst = &mut self.pixels[canvas];
+            let src = &pixels[frame];
+            match blend {
+                Blend::Source => dst.copy_from_slice(src),
+                Blend::Over => {
+                    for (dst, src) in dst.chunks_exact_mut(4).zip(src.chunks_exact(4)) {
+                        blend_over(dst, src);
+                    }
+                }
+            }
+        }
+
+        self.pending_dispose = Some((region, dispose));
+        Ok(())
+    }
+}
+
+/// `region`の各行について、キャンバスとフレームのバイト範囲を返す。
+fn spans(
+    canvas_width: u32,
+    canvas_height: u32,
+    region: Region,
+) -> impl Iterator<Item = (std::ops::Range<usize>, std::ops::Range<usize>)> {
+    let x_end = region.x.saturating_add(region.width).min(canvas_width) as usize;
+    let y_end = region.y.saturating_add(region.height).min(canvas_height) as usize;
+    let x = region.x as usize;
+    let y = region.y as usize;
+    let len = x_end.saturating_sub(x) * 4;
+    (y..y_end).filter(move |_| len > 0).map(move |row| {
+        let canvas = (row * canvas_width as usize + x) * 4;
+        let frame = (row - y) * region.width as usize * 4;
+        (canvas..canvas + len, frame..frame + len)
+    })
+}
+
+/// APNGの仕様にある、ストレートαでの`APNG_BLEND_OP_OVER`。
+fn blend_over(dst: &mut [u8], src: &[u8]) {
+    let src_alpha = src[3] as u32;
+    if src_alpha == 255 {
+        dst.copy_from_slice(src);
+        return;
+    }
+    if src_alpha == 0 {
+        return;
+    }
+    // 255倍した値で計算する
+    let dst_weight = dst[3] as u32 * (255 - src_alpha);
+    let out_alpha = src_alpha * 255 + dst_weight;
+    for i in 0..3 {
+        let color = src[i] as u32 * src_alpha * 255 + dst[i] as u32 * dst_weight;
+        dst[i] = ((color + out_alpha / 2) / out_alpha) as u8;
+    }
+    dst[3] = ((out_alpha + 127) / 255) as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(canvas: &Canvas, x: u32, y: u32) -> [u8; 4] {
+        let offset = ((y * canvas.width() + x) * 4) as usize;
+        canvas.pixels()[offset..offset + 4].try_into().unwrap()
+    }
+
+    fn fill(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+        color.repeat((width * height) as usize)
+    }
+
+    #[test]
+    fn test_blend_over() {
+        let mut dst = [255, 0, 0, 255];
+        blend_over(&mut dst, &[0, 0, 255, 128]);
+        assert_eq!(dst, [127, 0, 128, 255]);
+
+        // 透明な画素の上に重ねると、そのままになる
+        let mut dst = [0, 0, 0, 0];
+        blend_over(&mut dst, &[0, 0, 255, 128]);
+        assert_eq!(dst, [0, 0, 255, 128]);
+
+        let mut dst = [0, 255, 0, 128];
+        blend_over(&mut dst, &[0, 0, 0, 0]);
+        assert_eq!(dst, [0, 255, 0, 128]);
+    }
+
+    #[test]
+    fn test_dispose_previous_restores_region() {
+        let mut canvas = Canvas::new(4, 4);
+        let full = Region {
+            x: 0,
+            y: 0,
+            width: 4,
+            height: 4,
+        };
+        let corner = Region {
+            x: 1,
+            y: 1,
+            width: 2,
+            height: 2,
+        };
+        canvas
+            .draw(
+                full,
+                &fill(4, 4, [255, 0, 0, 255]),
+                Blend::Source,
+                Dispose::None,
+            )
+            .unwrap();
+        canvas
+            .draw(
+                corner,
+                &fill(2, 2, [0, 255, 0, 255]),
+                Blend::Source,
+                Dispose::Previous,
+            )
+            .unwrap();
+        assert_eq!(pixel(&canvas, 1, 1), [0, 255, 0, 255]);
+        canvas
+            .draw(
+                Region {
+                    x: 3,
+                    y: 3,
+                    width: 1,
+                    height: 1,
+                },
+                &[0, 0, 255, 255],
+                Blend::Source,
+                Dispose::None,
+            )
+            .unwrap();
+        assert_eq!(pixel(&canvas, 1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&canvas, 2, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(&canvas, 3, 3), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_draw_clips_to_canvas() {
+        let mut canvas = Canvas::new(4, 4);
+        let region = Region {
+            x: 3,
+            y: 3,
+            width: 2,
+            height: 2,
+        };
+        canvas
+            .draw(
+                region,
+                &fill(2, 2, [0, 255, 0, 255]),
+                Blend::Source,
+                Dispose::Previous,
+            )
+            .unwrap();
+        assert_eq!(pixel(&canvas, 3, 3), [0, 255, 0, 255]);
+        assert_eq!(pixel(&canvas, 2, 3), [0, 0, 0, 0]);
+        canvas
+            .draw(
+                region,
+                &fill(2, 2, [0, 0, 0, 0]),
+                Blend::Over,
+                Dispose::None,
+            )
+            .unwrap();
+        assert_eq!(pixel(&canvas, 3, 3), [0, 0, 0, 0]);
+
+        assert!(
+            canvas
+                .draw(region, &[0; 4], Blend::Source, Dispose::None)
+                .is_err()
+        );
+    }
+}
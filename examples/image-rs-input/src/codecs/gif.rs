@@ -1,4 +1,5 @@
 use super::AnimationInfo;
+use super::canvas::{Blend, Canvas, Dispose, Region};
 use ordered_float::OrderedFloat;
 
 pub fn read_headers<R: std::io::Read + std::io::Seek>(
@@ -15,8 +16,19 @@ pub fn read_headers<R: std::io::Read + std::io::Seek>(
     frame_timings.insert(OrderedFloat(0.0), 0);
     let mut total_duration = 0.0;
     let mut frame_index = 0;
-    while let Some(frame) = reader.read_next_frame()? {
-        let delay_seconds = (frame.delay as f32) / 100.0; // delay is in hundredths of a second
+    loop {
+        // 画像データが途中で切れているフレームも数えるため、フレームの情報だけを読む
+        let delay = match reader.next_frame_info() {
+            Ok(Some(frame)) => frame.delay,
+            Ok(None) => break,
+            Err(error) if frame_index > 0 => {
+                // 途中で切れているファイルは、読めたフレームまでを返し、読めないフレームはデコード時にエラーにする
+                aviutl2::tracing::warn!("GIF is truncated after frame {frame_index}: {error}");
+                break;
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let delay_seconds = (delay as f32) / 100.0; // delay is in hundredths of a second
         total_duration += delay_seconds;
         frame_index += 1;
         frame_timings.insert(OrderedFloat(total_duration), frame_index);
@@ -29,6 +41,53 @@ pub fn read_headers<R: std::io::Read + std::io::Seek>(
     })
 }
 
+/// GIFのフレームを順番にデコードし、キャンバスに合成する。
+pub struct Frames<R: std::io::Read> {
+    decoder: gif::Decoder<R>,
+    canvas: Canvas,
+}
+
+impl<R: std::io::Read + std::io::Seek> Frames<R> {
+    pub fn new(mut reader: R) -> Result<Self, anyhow::Error> {
+        reader.seek(std::io::SeekFrom::Start(0))?;
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let decoder = options.read_info(reader)?;
+        let canvas = Canvas::new(decoder.width() as u32, decoder.height() as u32);
+        Ok(Self { decoder, canvas })
+    }
+}
+
+impl<R: std::io::Read> Frames<R> {
+    /// 合成済みのキャンバス（RGBA）。
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    /// 次のフレームをデコードしてキャンバスに合成する。フレームが残っていない場合は`false`を返す。
+    pub fn advance(&mut self) -> Result<bool, anyhow::Error> {
+        let Some(frame) = self.decoder.read_next_frame()? else {
+            return Ok(false);
+        };
+        let region = Region {
+            x: frame.left as u32,
+            y: frame.top as u32,
+            width: frame.width as u32,
+            height: frame.height as u32,
+        };
+        let dispose = match frame.dispose {
+            gif::DisposalMethod::Any | gif::DisposalMethod::Keep => Dispose::None,
+            // 背景色ではなく透明にする（ブラウザと同じ挙動）
+            gif::DisposalMethod::Background => Dispose::Background,
+            gif::DisposalMethod::Previous => Dispose::Previous,
+        };
+        // 透明色の画素はα=0になっているので、OVERで重ねると下の画素が残る
+        self.canvas
+            .draw(region, &frame.buffer, Blend::Over, dispose)?;
+        Ok(true)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,6 +101,17 @@ mod tests {
         insta::assert_debug_snapshot!(animation_info);
     }
 
+    #[test]
+    fn test_read_truncated_gif_headers() {
+        // 3フレーム目の画像データの途中で切れている
+        let data = include_bytes!("../../test_data/truncated.gif");
+        let mut cursor = std::io::Cursor::new(data.as_ref());
+        let animation_info = read_headers(&mut cursor).unwrap();
+        assert_eq!((animation_info.width, animation_info.height), (8, 8));
+        assert_eq!(animation_info.frame_timings.len(), 4);
+        assert!((animation_info.length_in_seconds - 0.3).abs() < 1e-6);
+    }
+
     #[test]
     fn test_read_static_gif_headers() {
         let data = include_bytes!("../../test_data/static.gif");
@@ -49,4 +119,40 @@ mod tests {
         let animation_info = read_headers(&mut cursor).unwrap();
         insta::assert_debug_snapshot!(animation_info);
     }
+
+    /// `frames`を最後まで合成し、各フレームの`(x, y)`の画素を返す。
+    fn compose_all(data: &[u8], x: u32, y: u32) -> Vec<[u8; 4]> {
+        let mut frames = Frames::new(std::io::Cursor::new(data)).unwrap();
+        let mut pixels = vec![];
+        while frames.advance().unwrap() {
+            let canvas = frames.canvas();
+            assert_eq!(canvas.pixels().len(), 4 * 4 * 4);
+            let offset = ((y * canvas.width() + x) * 4) as usize;
+            pixels.push(canvas.pixels()[offset..offset + 4].try_into().unwrap());
+        }
+        pixels
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+    #[test]
+    fn test_compose_dispose_previous() {
+        // 赤の全面 → 左上2x2に緑、(1, 0)は透明色（Restore to previous） → 右下2x2に青
+        let data = include_bytes!("../../test_data/gif_dispose_previous.gif");
+        assert_eq!(compose_all(data, 0, 0), [RED, GREEN, RED]);
+        assert_eq!(compose_all(data, 1, 0), [RED, RED, RED]);
+        assert_eq!(compose_all(data, 3, 3), [RED, RED, BLUE]);
+    }
+
+    #[test]
+    fn test_compose_dispose_background() {
+        // 赤の全面（Restore to background） → 中央2x2に緑、右上と左下は透明色 → 左上1x1に青
+        let data = include_bytes!("../../test_data/gif_dispose_background.gif");
+        assert_eq!(compose_all(data, 0, 0), [RED, TRANSPARENT, BLUE]);
+        assert_eq!(compose_all(data, 1, 1), [RED, GREEN, GREEN]);
+        assert_eq!(compose_all(data, 2, 1), [RED, TRANSPARENT, TRANSPARENT]);
+    }
 }
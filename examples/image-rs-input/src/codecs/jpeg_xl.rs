@@ -172,10 +172,9 @@ fn decode_current_frame(
             let bytes_per_row = width * 4;
             let mut img = vec![0u8; bytes_per_row * height];
             process_frame_into_buffer(decoder, input, &mut img, height, bytes_per_row)?;
-            crate::alpha::straight_alpha_to_premultiplied_alpha(&mut img);
             aviutl2::utils::flip_vertical(&mut img, width * 4, height);
             aviutl2::utils::rgba_to_bgra_bytes(&mut img);
-            Ok(ImageBuffer(img))
+            Ok(ImageBuffer::new(img))
         }
         OutputFormat::Rgba16 => {
             let bytes_per_row = width * 4 * std::mem::size_of::<u16>();
@@ -189,7 +188,6 @@ fn decode_current_frame(
                 )
             };
             process_frame_into_buffer(decoder, input, img_bytes, height, bytes_per_row)?;
-            crate::alpha::straight_alpha_to_premultiplied_alpha_u16(&mut img);
             Ok(img.into_image())
         }
     }
@@ -1,8 +1,13 @@
 use ordered_float::OrderedFloat;
 
 pub mod apng;
+#[cfg(feature = "avif")]
+pub mod avif;
+pub mod canvas;
 pub mod gif;
 pub mod jpeg_xl;
+#[cfg(feature = "svg")]
+pub mod svg;
 pub mod webp;
 
 #[derive(Debug, Clone)]
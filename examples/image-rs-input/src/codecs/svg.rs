@@ -0,0 +1,179 @@
+use aviutl2::input::ImageBuffer;
+use resvg::{tiny_skia, usvg};
+
+/// SVGの1pxを何DPIとして扱うか。
+const CSS_PIXEL_DPI: f32 = 96.0;
+
+/// ラスタライズ時の設定。
+///
+/// SVGファイルと同じ場所にある`<ファイル名>.json`（例：`icon.svg.json`）から読み込む。
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// ラスタライズ時の解像度。96DPIのとき、SVGの1pxが1ピクセルになる。
+    pub dpi: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { dpi: CSS_PIXEL_DPI }
+    }
+}
+
+pub struct OpenedImage {
+    pub reader: Reader,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub struct Reader {
+    tree: usvg::Tree,
+    width: u32,
+    height: u32,
+}
+
+pub fn is_file(path: &std::path::Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"))
+}
+
+fn config_path(path: &std::path::Path) -> std::path::PathBuf {
+    let mut config_path = path.as_os_str().to_owned();
+    config_path.push(".json");
+    config_path.into()
+}
+
+fn parse_config(json: &str) -> anyhow::Result<Config> {
+    let config: Config = serde_json::from_str(json)?;
+    anyhow::ensure!(
+        config.dpi.is_finite() && config.dpi > 0.0,
+        "DPI must be a positive number: {}",
+        config.dpi
+    );
+    Ok(config)
+}
+
+pub fn load_config(path: &std::path::Path) -> anyhow::Result<Config> {
+    match std::fs::read_to_string(config_path(path)) {
+        Ok(json) => parse_config(&json),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn open(file: &std::path::Path) -> anyhow::Result<OpenedImage> {
+    let config = load_config(file)?;
+    let data = std::fs::read(file)?;
+    let mut options = usvg::Options {
+        resources_dir: file.parent().map(|dir| dir.to_path_buf()),
+        ..Default::default()
+    };
+    options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_data(&data, &options)?;
+
+    let scale = config.dpi / CSS_PIXEL_DPI;
+    let size = tree.size();
+    let width = (size.width() * scale).ceil().max(1.0) as u32;
+    let height = (size.height() * scale).ceil().max(1.0) as u32;
+
+    Ok(OpenedImage {
+        reader: Reader {
+            tree,
+            width,
+            height,
+        },
+        width,
+        height,
+    })
+}
+
+/// 透明な背景にラスタライズし、下から上に並んだ、乗算済みでないBGRAの画像を返す。
+pub fn rasterize(reader: &Reader) -> anyhow::Result<ImageBuffer> {
+    let mut pixmap = tiny_skia::Pixmap::new(reader.width, reader.height)
+        .ok_or_else(|| anyhow::anyhow!("Invalid image size"))?;
+    let size = reader.tree.size();
+    // viewBoxはusvg側で解決されているので、ここでは出力サイズに合わせて拡大するだけでよい
+    let transform = tiny_skia::Transform::from_scale(
+        reader.width as f32 / size.width(),
+        reader.height as f32 / size.height(),
+    );
+    resvg::render(&reader.tree, transform, &mut pixmap.as_mut());
+
+    let mut img: Vec<u8> = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let pixel = pixel.demultiply();
+            [pixel.blue(), pixel.green(), pixel.red(), pixel.alpha()]
+        })
+        .collect();
+    aviutl2::utils::flip_vertical(&mut img, reader.width as usize * 4, reader.height as _);
+    Ok(ImageBuffer::new(img))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn svg_fixture() -> std::path::PathBuf {
+        std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_data")
+            .join("simple.svg")
+    }
+
+    fn pixel(buffer: &ImageBuffer, width: u32, height: u32, x: u32, y: u32) -> [u8; 4] {
+        // 下から上に並んでいるので、上からの座標に直す
+        let index = (((height - 1 - y) * width + x) * 4) as usize;
+        buffer[index..index + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn rasterize_svg_at_default_dpi() {
+        let image = open(&svg_fixture()).unwrap();
+        assert_eq!((image.width, image.height), (20, 10));
+
+        let buffer = rasterize(&image.reader).unwrap();
+        assert_eq!(buffer.byte_len(), 20 * 10 * 4);
+        // 左上：不透明な赤
+        assert_eq!(pixel(&buffer, 20, 10, 2, 2), [0, 0, 255, 255]);
+        // 右上：半透明の青
+        let [b, g, r, a] = pixel(&buffer, 20, 10, 17, 2);
+        assert_eq!((g, r), (0, 0));
+        assert!(b >= 254, "blue: {b}");
+        assert!(a.abs_diff(128) <= 1, "alpha: {a}");
+        // 下半分：透明
+        assert_eq!(pixel(&buffer, 20, 10, 2, 7)[3], 0);
+        assert_eq!(pixel(&buffer, 20, 10, 17, 7)[3], 0);
+    }
+
+    #[test]
+    fn rasterize_svg_with_sidecar_dpi() {
+        let dir =
+            std::env::temp_dir().join(format!("rusty_image_rs_input_svg_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("simple.svg");
+        std::fs::copy(svg_fixture(), &path).unwrap();
+        std::fs::write(config_path(&path), r#"{ "dpi": 192 }"#).unwrap();
+
+        let image = open(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((image.width, image.height), (40, 20));
+
+        let buffer = rasterize(&image.reader).unwrap();
+        assert_eq!(pixel(&buffer, 40, 20, 4, 4), [0, 0, 255, 255]);
+        assert_eq!(pixel(&buffer, 40, 20, 4, 15)[3], 0);
+    }
+
+    #[test]
+    fn parse_sidecar_config() {
+        assert_eq!(parse_config("{}").unwrap(), Config::default());
+        assert_eq!(parse_config(r#"{ "dpi": 300 }"#).unwrap().dpi, 300.0);
+        assert!(parse_config(r#"{ "dpi": 0 }"#).is_err());
+        assert!(parse_config(r#"{ "dpi": "high" }"#).is_err());
+    }
+
+    #[test]
+    fn missing_sidecar_uses_default_dpi() {
+        assert_eq!(load_config(&svg_fixture()).unwrap(), Config::default());
+    }
+}
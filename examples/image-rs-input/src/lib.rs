@@ -1,22 +1,43 @@
-mod alpha;
 mod codecs;
-use aviutl2::input::{AnyResult, ImageBuffer, ImageReturner, InputPlugin, IntoImage, Rational32};
+use aviutl2::input::{
+    AnyResult, CacheBudget, CachedImage, FrameTimeline, ImageBuffer, ImageReturner, InputPlugin,
+    IntoImage, Rational32, TimeToFrame,
+};
 use image::{AnimationDecoder, GenericImageView};
 use ordered_float::OrderedFloat;
 use std::io::Seek;
 
+/// デコード済みの画像のキャッシュに使うメモリの上限。
+/// 8Kの16bit画像（約256MB）を数枚保持できる程度にする。
+const CACHE_BUDGET_BYTES: usize = 1024 * 1024 * 1024;
+
 #[aviutl2::plugin(InputPlugin)]
 struct ImageInputPlugin {}
 
 enum ImageReader {
-    Animated(OwnedFrames),
+    Animated(AnimatedFrames),
     Jxl(codecs::jpeg_xl::Reader),
+    #[cfg(feature = "svg")]
+    Svg(codecs::svg::Reader),
     Single(Box<dyn image::ImageDecoder>),
-    SingleCached(ImageBuffer),
+    /// デコード済みの静止画。キャッシュから破棄された場合は`StillSource`を元に開き直す。
+    SingleCached(CachedImage, StillSource),
+}
+
+/// 静止画の読み込み方。
+#[derive(Debug, Clone, Copy)]
+enum StillSource {
+    Jxl,
+    #[cfg(feature = "svg")]
+    Svg,
+    Image,
 }
 
+// SAFETY:
+// `image::Frames`や`Box<dyn image::ImageDecoder>`は`Send`ではないが、
+// それぞれ自身が所有するファイルのみを読み込むため、別スレッドへの移動は問題ない。
+// 同時アクセスはブリッジ側（`ConcurrencyMode::None`）で防がれるため、`Sync`は実装しない。
 unsafe impl Send for ImageReader {}
-unsafe impl Sync for ImageReader {}
 
 #[ouroboros::self_referencing]
 struct OwnedFrames {
@@ -36,20 +57,169 @@ impl OwnedFrames {
     }
 }
 
+type FileReader = std::io::BufReader<std::fs::File>;
+
+/// アニメーションのフレームの読み込み方。
+enum AnimatedFrames {
+    /// APNG・GIFは、破棄方法・合成方法に従って自前でキャンバスに合成する。
+    Apng(Box<codecs::apng::Frames<FileReader>>),
+    Gif(Box<codecs::gif::Frames<FileReader>>),
+    /// WebPなど、image-rsが合成したフレームをそのまま使う。
+    Image(OwnedFrames),
+}
+
+impl AnimatedFrames {
+    fn new(file: FileReader, format: image::ImageFormat) -> anyhow::Result<Self> {
+        Ok(match format {
+            image::ImageFormat::Png => Self::Apng(Box::new(codecs::apng::Frames::new(file)?)),
+            image::ImageFormat::Gif => Self::Gif(Box::new(codecs::gif::Frames::new(file)?)),
+            _ => Self::Image(into_frames(file, format)?),
+        })
+    }
+
+    /// 先頭のフレームに戻す。キャンバスも最初から合成し直す。
+    fn reset(self, path: &std::path::Path) -> anyhow::Result<Self> {
+        Ok(match self {
+            Self::Apng(_) => Self::Apng(Box::new(codecs::apng::Frames::new(open_file(path)?)?)),
+            Self::Gif(_) => Self::Gif(Box::new(codecs::gif::Frames::new(open_file(path)?)?)),
+            Self::Image(frames) => Self::Image(frames.reset()?),
+        })
+    }
+
+    /// 次のフレームを読み飛ばす。合成が必要な形式では、キャンバスへの合成は行う。
+    fn skip_frame(&mut self) -> anyhow::Result<()> {
+        match self {
+            Self::Apng(frames) => {
+                frames.advance()?;
+            }
+            Self::Gif(frames) => {
+                frames.advance()?;
+            }
+            Self::Image(frames) => {
+                frames.with_frames_mut(|frames| frames.next().transpose())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 次のフレームをキャンバス全体のRGBAとして返す。
+    fn next_frame(&mut self) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(match self {
+            Self::Apng(frames) => frames.advance()?.then(|| frames.canvas().pixels().to_vec()),
+            Self::Gif(frames) => frames.advance()?.then(|| frames.canvas().pixels().to_vec()),
+            Self::Image(frames) => frames
+                .with_frames_mut(|frames| frames.next().transpose())?
+                .map(|frame| frame.into_buffer().into_raw()),
+        })
+    }
+}
+
+fn open_file(path: &std::path::Path) -> anyhow::Result<FileReader> {
+    Ok(std::io::BufReader::new(std::fs::File::open(path)?))
+}
+
 struct ImageHandle {
+    path: std::path::PathBuf,
     reader: Option<ImageReader>,
     current_frame: usize,
+    /// 最後にデコードしたアニメーションのフレーム。
+    /// 入力ハンドルを共有しているため、複数のオブジェクトから同じフレームが続けて要求されることが多い。
+    last_frame: Option<(usize, CachedImage)>,
+    /// デコードに失敗したアニメーションのフレームとそのエラー。
+    /// 壊れたフレーム以降は順番にデコードできないので、デコードせずにエラーにする。
+    decode_error: Option<(usize, String)>,
     format: aviutl2::input::InputPixelFormat,
     width: u32,
     height: u32,
+    timeline: FrameTimeline,
     frame_timings: std::collections::BTreeMap<OrderedFloat<f32>, usize>,
     length_in_seconds: f32,
 }
 
+impl ImageHandle {
+    /// アニメーションの`frame`番目のフレームをデコードする。
+    /// デコードに失敗した場合でも、次のフレームを読めるように`frames`を返す。
+    fn decode_animated_frame(
+        &mut self,
+        mut frames: AnimatedFrames,
+        frame: usize,
+    ) -> (Option<AnimatedFrames>, AnyResult<ImageBuffer>) {
+        if let Some((bad_frame, message)) = self
+            .decode_error
+            .as_ref()
+            .filter(|(bad_frame, _)| frame >= *bad_frame)
+        {
+            return (
+                Some(frames),
+                Err(anyhow::anyhow!(
+                    "Failed to decode frame {bad_frame}: {message}"
+                )),
+            );
+        }
+        if frame < self.current_frame {
+            self.current_frame = 0;
+            frames = match frames.reset(&self.path) {
+                Ok(frames) => frames,
+                Err(error) => return (None, Err(error)),
+            };
+        }
+        match self.decode_next_frames(&mut frames, frame) {
+            Ok(buffer) => (Some(frames), Ok(buffer)),
+            Err(error) => {
+                let bad_frame = self.current_frame;
+                aviutl2::tracing::warn!(
+                    "Failed to decode frame {bad_frame} of {}: {error}",
+                    self.path.display()
+                );
+                self.decode_error = Some((bad_frame, error.to_string()));
+                // デコーダーの状態が壊れているので、先頭に戻しておく
+                self.current_frame = 0;
+                (frames.reset(&self.path).ok(), Err(error))
+            }
+        }
+    }
+
+    fn decode_next_frames(
+        &mut self,
+        frames: &mut AnimatedFrames,
+        frame: usize,
+    ) -> AnyResult<ImageBuffer> {
+        while self.current_frame < frame {
+            frames.skip_frame()?;
+            self.current_frame += 1;
+        }
+        let mut img = frames
+            .next_frame()?
+            .ok_or_else(|| anyhow::anyhow!("Failed to get frame {}", frame))?;
+        self.current_frame += 1;
+        anyhow::ensure!(
+            img.len() == self.width as usize * self.height as usize * 4,
+            "Frame {} has unexpected size",
+            frame
+        );
+        aviutl2::utils::flip_vertical(&mut img, self.width as usize * 4, self.height as _);
+        aviutl2::utils::rgba_to_bgra_bytes(&mut img);
+        Ok(ImageBuffer::new(img))
+    }
+}
+
+fn frame_timeline(
+    frame_timings: &std::collections::BTreeMap<OrderedFloat<f32>, usize>,
+    length_in_seconds: f32,
+) -> AnyResult<FrameTimeline> {
+    FrameTimeline::new(
+        frame_timings
+            .iter()
+            .map(|(start, &frame)| (start.0 as f64, frame as u32)),
+        length_in_seconds as f64,
+    )
+}
+
 impl InputPlugin for ImageInputPlugin {
     type InputHandle = ImageHandle;
 
     fn new(_info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
+        aviutl2::input::cache_budget(CACHE_BUDGET_BYTES);
         Ok(Self {})
     }
 
@@ -57,36 +227,52 @@ impl InputPlugin for ImageInputPlugin {
         aviutl2::input::InputPluginTable {
             name: "Rusty Image Input".to_string(),
             input_type: aviutl2::input::InputType::Video,
-            file_filters: aviutl2::file_filters! {
-                "Image Files" => [
-                    "webp",
-                    "png",
-                    "apng",
-                    "jpg",
-                    "jpeg",
-                    "bmp",
-                    "tiff",
-                    "gif",
-                    "hdr",
-                    "jxl",
-                ],
-            },
+            file_filters: image_file_filters(),
             information: format!(
                 "image-rs Input for AviUtl2, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/image-rs-input",
                 version = env!("CARGO_PKG_VERSION")
             ),
             can_config: false,
-            concurrent: false,
+            // 壊れたフレームは自前でエラー表示の画像にするので、ブリッジのものは使わない
+            fail_on_read_error: false,
+            concurrency: aviutl2::input::ConcurrencyMode::None,
+            // read_video_mutはフレームの巻き戻し・読み飛ばしに対応しているので、同じファイルのハンドルを共有できる
+            share_handles: true,
+            cache_input_info: true,
         }
     }
 
     fn open(&self, file: std::path::PathBuf) -> AnyResult<Self::InputHandle> {
+        #[cfg(feature = "svg")]
+        if codecs::svg::is_file(&file) {
+            let image = codecs::svg::open(&file)?;
+            let mut frame_timings = std::collections::BTreeMap::new();
+            frame_timings.insert(OrderedFloat(0.0), 0);
+            return Ok(ImageHandle {
+                path: file,
+                current_frame: 0,
+                last_frame: None,
+                decode_error: None,
+                reader: Some(ImageReader::Svg(image.reader)),
+                format: aviutl2::input::InputPixelFormat::Bgra,
+                timeline: frame_timeline(&frame_timings, 0.0)?,
+                frame_timings,
+                length_in_seconds: 0.0,
+                width: image.width,
+                height: image.height,
+            });
+        }
+
         if codecs::jpeg_xl::is_file(&file)? {
-            let image = codecs::jpeg_xl::open(file)?;
+            let image = codecs::jpeg_xl::open(file.clone())?;
             return Ok(ImageHandle {
+                path: file,
                 current_frame: 0,
+                last_frame: None,
+                decode_error: None,
                 reader: Some(ImageReader::Jxl(image.reader)),
                 format: image.format,
+                timeline: frame_timeline(&image.frame_timings, image.length_in_seconds)?,
                 frame_timings: image.frame_timings,
                 length_in_seconds: image.length_in_seconds,
                 width: image.width,
@@ -100,19 +286,26 @@ impl InputPlugin for ImageInputPlugin {
             .ok_or_else(|| anyhow::anyhow!("Failed to guess image format"))?;
         match format {
             image::ImageFormat::Png | image::ImageFormat::Gif | image::ImageFormat::WebP => {
-                let mut file = std::io::BufReader::new(std::fs::File::open(&file)?);
+                let mut reader = open_file(&file)?;
                 let animation_info = match format {
-                    image::ImageFormat::Png => codecs::apng::read_headers(&mut file)?,
-                    image::ImageFormat::Gif => codecs::gif::read_headers(&mut file)?,
-                    image::ImageFormat::WebP => codecs::webp::read_headers(&mut file)?,
+                    image::ImageFormat::Png => codecs::apng::read_headers(&mut reader)?,
+                    image::ImageFormat::Gif => codecs::gif::read_headers(&mut reader)?,
+                    image::ImageFormat::WebP => codecs::webp::read_headers(&mut reader)?,
                     _ => unreachable!(),
                 };
                 if animation_info.frame_timings.len() > 1 {
-                    let frames = into_frames(file, format)?;
+                    let frames = AnimatedFrames::new(reader, format)?;
                     return Ok(ImageHandle {
+                        path: file,
                         current_frame: 0,
+                        last_frame: None,
+                        decode_error: None,
                         reader: Some(ImageReader::Animated(frames)),
                         format: aviutl2::input::InputPixelFormat::Bgra,
+                        timeline: frame_timeline(
+                            &animation_info.frame_timings,
+                            animation_info.length_in_seconds,
+                        )?,
                         frame_timings: animation_info.frame_timings,
                         length_in_seconds: animation_info.length_in_seconds,
                         width: animation_info.width,
@@ -122,7 +315,7 @@ impl InputPlugin for ImageInputPlugin {
             }
             _ => {}
         }
-        let frames = into_frames(std::io::BufReader::new(std::fs::File::open(&file)?), format);
+        let frames = into_frames(open_file(&file)?, format);
         // 自分が実装をミスっている可能性もあるので、codecsモジュールの関数でパースできなくてもimage-rsの実装でパースできるか試す
         if let Ok(mut frames) = frames {
             let (width, height, total_duration, frame_timings) =
@@ -148,9 +341,15 @@ impl InputPlugin for ImageInputPlugin {
                 })?;
             if frame_timings.len() > 1 {
                 return Ok(ImageHandle {
+                    path: file,
                     current_frame: 0,
-                    reader: Some(ImageReader::Animated(frames.reset()?)),
+                    last_frame: None,
+                    decode_error: None,
+                    reader: Some(ImageReader::Animated(AnimatedFrames::Image(
+                        frames.reset()?,
+                    ))),
                     format: aviutl2::input::InputPixelFormat::Bgra,
+                    timeline: frame_timeline(&frame_timings, total_duration)?,
                     frame_timings,
                     length_in_seconds: total_duration,
                     width,
@@ -159,8 +358,20 @@ impl InputPlugin for ImageInputPlugin {
             }
         }
 
+        #[cfg(feature = "avif")]
+        if format == image::ImageFormat::Avif
+            && codecs::avif::is_image_sequence(&mut std::fs::File::open(&file)?)?
+        {
+            // image-rsはAVIFの画像シーケンスをデコードできないので、主画像のみを静止画として読み込む
+            aviutl2::tracing::warn!(
+                "Animated AVIF is not supported, only the primary image of {} will be shown",
+                file.display()
+            );
+        }
+
         let decoded = decoder.decode()?;
         let (width, height) = decoded.dimensions();
+        // 10bit・12bitのAVIFなど、8bitを超える画像はRgb16・Rgba16としてデコードされるので、Pa64で返す
         let format = match decoded {
             image::DynamicImage::ImageRgb8(_) | image::DynamicImage::ImageRgba8(_) => {
                 aviutl2::input::InputPixelFormat::Bgra
@@ -171,13 +382,17 @@ impl InputPlugin for ImageInputPlugin {
         frame_timings.insert(OrderedFloat(0.0), 0);
 
         Ok(ImageHandle {
-            current_frame: 0,
             reader: Some(ImageReader::Single(Box::new(
                 image::ImageReader::open(&file)?
                     .with_guessed_format()?
                     .into_decoder()?,
             ))),
+            path: file,
+            current_frame: 0,
+            last_frame: None,
+            decode_error: None,
             format,
+            timeline: frame_timeline(&frame_timings, 0.0)?,
             frame_timings,
             length_in_seconds: 0.0,
             width,
@@ -208,6 +423,9 @@ impl InputPlugin for ImageInputPlugin {
                 height: handle.height,
                 format: handle.format,
                 manual_frame_index: true,
+                // 乗算済みαへの変換はaviutl2-rs側に任せる
+                alpha: aviutl2::input::AlphaMode::Straight,
+                colorimetry: aviutl2::pixel::Colorimetry::HOST,
             }),
             audio: None, // No audio for image files
         })
@@ -230,39 +448,52 @@ impl InputPlugin for ImageInputPlugin {
         match reader {
             None => anyhow::bail!("Reader is used up"),
             Some(ImageReader::Animated(frames)) => {
-                let mut frames = if frame < handle.current_frame {
-                    handle.current_frame = 0;
-                    frames.reset()?
-                } else {
-                    frames
-                };
-                while handle.current_frame < frame {
-                    frames.with_frames_mut(|frames| frames.next().transpose())?;
-                    handle.current_frame += 1;
+                if let Some(buffer) = handle
+                    .last_frame
+                    .as_ref()
+                    .filter(|(last_frame, _)| *last_frame == frame)
+                    .and_then(|(_, cached)| cached.get())
+                {
+                    returner.write(&buffer);
+                    handle.reader = Some(ImageReader::Animated(frames));
+                    return Ok(());
+                }
+                let (frames, decoded) = handle.decode_animated_frame(frames, frame);
+                handle.reader = frames.map(ImageReader::Animated);
+                match decoded {
+                    Ok(buffer) => {
+                        returner.write(&buffer);
+                        handle.last_frame = Some((frame, CacheBudget::global().insert(buffer)));
+                    }
+                    // 途中で切れているGIFなどでも、読めるフレームは表示できるようにする
+                    Err(error) => returner.write_error_placeholder(
+                        handle.width,
+                        handle.height,
+                        &error.to_string(),
+                    ),
                 }
-                let frame = frames
-                    .with_frames_mut(|frames| frames.next().transpose())?
-                    .ok_or_else(|| anyhow::anyhow!("Failed to get frame {}", frame))?;
-                handle.current_frame += 1;
-                let mut img = frame.into_buffer().into_raw();
-                aviutl2::utils::flip_vertical(
-                    &mut img,
-                    handle.width as usize * 4,
-                    handle.height as _,
-                );
-                aviutl2::utils::rgba_to_bgra_bytes(&mut img);
-                returner.write(&img);
-                handle.reader = Some(ImageReader::Animated(frames));
             }
             Some(ImageReader::Jxl(reader)) => {
                 let buffer = codecs::jpeg_xl::decode_frame(&reader, frame)?;
                 returner.write(&buffer);
                 if handle.frame_timings.len() == 1 {
-                    handle.reader = Some(ImageReader::SingleCached(buffer));
+                    handle.reader = Some(ImageReader::SingleCached(
+                        CacheBudget::global().insert(buffer),
+                        StillSource::Jxl,
+                    ));
                 } else {
                     handle.reader = Some(ImageReader::Jxl(reader));
                 }
             }
+            #[cfg(feature = "svg")]
+            Some(ImageReader::Svg(reader)) => {
+                let buffer = codecs::svg::rasterize(&reader)?;
+                returner.write(&buffer);
+                handle.reader = Some(ImageReader::SingleCached(
+                    CacheBudget::global().insert(buffer),
+                    StillSource::Svg,
+                ));
+            }
             Some(ImageReader::Single(decoder)) => {
                 let img = image::DynamicImage::from_decoder(decoder)?;
                 match handle.format {
@@ -274,24 +505,36 @@ impl InputPlugin for ImageInputPlugin {
                             handle.height as _,
                         );
                         aviutl2::utils::rgba_to_bgra_bytes(&mut img);
-                        let buffer = aviutl2::input::ImageBuffer(img);
+                        let buffer = ImageBuffer::new(img);
                         returner.write(&buffer);
-                        handle.reader = Some(ImageReader::SingleCached(buffer));
+                        handle.reader = Some(ImageReader::SingleCached(
+                            CacheBudget::global().insert(buffer),
+                            StillSource::Image,
+                        ));
                     }
                     aviutl2::input::InputPixelFormat::Pa64 => {
-                        let mut img = img.to_rgba16().into_raw();
-                        crate::alpha::straight_alpha_to_premultiplied_alpha_u16(&mut img);
+                        let img = img.to_rgba16().into_raw();
                         let buffer = img.into_image();
                         returner.write(&buffer);
-                        handle.reader = Some(ImageReader::SingleCached(buffer));
+                        handle.reader = Some(ImageReader::SingleCached(
+                            CacheBudget::global().insert(buffer),
+                            StillSource::Image,
+                        ));
                     }
                     _ => unreachable!(),
                 }
             }
-            Some(ImageReader::SingleCached(img)) => {
-                returner.write(&img);
-                handle.reader = Some(ImageReader::SingleCached(img));
-            }
+            Some(ImageReader::SingleCached(cached, source)) => match cached.get() {
+                Some(img) => {
+                    returner.write(&img);
+                    handle.reader = Some(ImageReader::SingleCached(cached, source));
+                }
+                None => {
+                    // メモリの上限を超えてキャッシュから破棄されたので、開き直してデコードする
+                    handle.reader = Some(reopen_still(&handle.path, source)?);
+                    return self.read_video_mut(handle, frame as u32, returner);
+                }
+            },
         };
 
         Ok(())
@@ -303,21 +546,7 @@ impl InputPlugin for ImageInputPlugin {
         _track: u32,
         time: f64,
     ) -> AnyResult<u32> {
-        if handle.frame_timings.len() == 1 {
-            return Ok(0);
-        }
-        if handle.length_in_seconds == 0.0 {
-            return Ok(0);
-        }
-
-        let time = OrderedFloat((time % (handle.length_in_seconds as f64)) as f32);
-        let (&_, &frame) = handle
-            .frame_timings
-            .range(..=time)
-            .next_back()
-            .expect("unreachable: ensure at least one frame");
-
-        Ok(frame as u32)
+        Ok(handle.timeline.frame_for(time, TimeToFrame::Loop))
     }
 
     fn close(&self, handle: Self::InputHandle) -> AnyResult<()> {
@@ -326,6 +555,41 @@ impl InputPlugin for ImageInputPlugin {
     }
 }
 
+fn image_file_filters() -> Vec<aviutl2::FileFilter> {
+    let mut file_filters = aviutl2::file_filters! {
+        "Image Files" => [
+            "webp",
+            "png",
+            "apng",
+            "jpg",
+            "jpeg",
+            "bmp",
+            "tiff",
+            "gif",
+            "hdr",
+            "jxl",
+        ],
+    };
+    #[cfg(feature = "svg")]
+    file_filters[0].extensions.push("svg".to_string());
+    #[cfg(feature = "avif")]
+    file_filters[0].extensions.push("avif".to_string());
+    file_filters
+}
+
+fn reopen_still(path: &std::path::Path, source: StillSource) -> AnyResult<ImageReader> {
+    Ok(match source {
+        StillSource::Jxl => ImageReader::Jxl(codecs::jpeg_xl::open(path.to_path_buf())?.reader),
+        #[cfg(feature = "svg")]
+        StillSource::Svg => ImageReader::Svg(codecs::svg::open(path)?.reader),
+        StillSource::Image => ImageReader::Single(Box::new(
+            image::ImageReader::open(path)?
+                .with_guessed_format()?
+                .into_decoder()?,
+        )),
+    })
+}
+
 fn into_frames(
     mut file: std::io::BufReader<std::fs::File>,
     format: image::ImageFormat,
@@ -354,3 +618,72 @@ fn into_frames(
 }
 
 aviutl2::register_input_plugin!(ImageInputPlugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncated_gif_keeps_handle_usable() {
+        let plugin = ImageInputPlugin {};
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("test_data/truncated.gif");
+        let mut handle = plugin.open(path).unwrap();
+        assert_eq!(handle.frame_timings.len(), 4);
+
+        let (frames, decoded) = handle.decode_animated_frame(take_frames(&mut handle), 0);
+        assert_eq!(decoded.unwrap().len(), 8 * 8 * 4);
+        let (frames, decoded) = handle.decode_animated_frame(frames.unwrap(), 1);
+        assert!(decoded.is_ok());
+        let (frames, decoded) = handle.decode_animated_frame(frames.unwrap(), 2);
+        assert!(decoded.is_err());
+        assert_eq!(
+            handle.decode_error.as_ref().map(|(frame, _)| *frame),
+            Some(2)
+        );
+        let (frames, decoded) = handle.decode_animated_frame(frames.unwrap(), 3);
+        assert!(decoded.is_err());
+
+        // 壊れたフレームより前は、もう一度読める
+        let (frames, decoded) = handle.decode_animated_frame(frames.unwrap(), 0);
+        assert_eq!(decoded.unwrap().len(), 8 * 8 * 4);
+        assert!(frames.is_some());
+    }
+
+    #[test]
+    fn animated_frames_are_composed_after_seeking_back() {
+        let plugin = ImageInputPlugin {};
+        for name in ["apng_dispose_previous.png", "gif_dispose_previous.gif"] {
+            let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("test_data")
+                .join(name);
+            let mut handle = plugin.open(path).unwrap();
+            assert_eq!(handle.frame_timings.len(), 3);
+
+            let mut frames = take_frames(&mut handle);
+            let mut decoded = vec![];
+            for frame in [2, 1, 0, 2] {
+                let (next, buffer) = handle.decode_animated_frame(frames, frame);
+                frames = next.unwrap();
+                decoded.push(buffer.unwrap());
+            }
+            // キャンバスより小さいフレームも、キャンバス全体の大きさで返す
+            assert!(decoded.iter().all(|buffer| buffer.len() == 4 * 4 * 4));
+            // 巻き戻した後も、最初から合成し直した結果と一致する
+            assert_eq!(&decoded[0][..], &decoded[3][..]);
+
+            // BGRAで上下反転しているので、左上の画素は最後の行にある
+            let top_left = |buffer: &ImageBuffer| buffer[3 * 16..3 * 16 + 4].to_vec();
+            assert_eq!(top_left(&decoded[0]), [0, 0, 255, 255], "{name}");
+            assert_eq!(top_left(&decoded[1]), [0, 255, 0, 255], "{name}");
+            assert_eq!(top_left(&decoded[2]), [0, 0, 255, 255], "{name}");
+            assert_eq!(&decoded[0][12..16], [255, 0, 0, 255], "{name}");
+        }
+    }
+
+    fn take_frames(handle: &mut ImageHandle) -> AnimatedFrames {
+        match handle.reader.take() {
+            Some(ImageReader::Animated(frames)) => frames,
+            _ => panic!("Expected an animated reader"),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PngBitDepth {
+    Eight,
+    Sixteen,
+}
+impl PngBitDepth {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PngBitDepth::Eight => "8bit",
+            PngBitDepth::Sixteen => "16bit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// 透過をそのまま保存する。
+    Keep,
+    /// αチャンネルを取り除く。
+    Strip,
+    /// 背景色と合成してからαチャンネルを取り除く。
+    Composite,
+}
+impl AlphaMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AlphaMode::Keep => "透過を保持",
+            AlphaMode::Strip => "透過を削除",
+            AlphaMode::Composite => "背景色と合成",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRsOutputConfig {
+    /// 書き出し済みの連番ファイルを飛ばして、途中から出力を再開するかどうか。
+    pub skip_existing: bool,
+    /// 書き出した後にファイルを読み直し、元のフレームと一致するかを検証するかどうか。
+    pub verify: bool,
+    pub png_bit_depth: PngBitDepth,
+    pub webp_lossless: bool,
+    /// JPEGと非可逆WebPの品質（1〜100）。
+    pub quality: u8,
+    pub alpha_mode: AlphaMode,
+    /// [`AlphaMode::Composite`]で使う背景色（RGB）。
+    pub background: [u8; 3],
+}
+impl Default for ImageRsOutputConfig {
+    fn default() -> Self {
+        Self {
+            skip_existing: false,
+            verify: false,
+            png_bit_depth: PngBitDepth::Eight,
+            webp_lossless: true,
+            quality: 90,
+            alpha_mode: AlphaMode::Keep,
+            background: [255, 255, 255],
+        }
+    }
+}
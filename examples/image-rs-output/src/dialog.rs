@@ -0,0 +1,107 @@
+use crate::config::{AlphaMode, ImageRsOutputConfig, PngBitDepth};
+use aviutl2::config::translate as tr;
+use eframe::egui;
+
+pub struct ImageRsOutputConfigDialog {
+    pub config: ImageRsOutputConfig,
+    pub result_sender: std::sync::mpsc::Sender<ImageRsOutputConfig>,
+}
+
+impl ImageRsOutputConfigDialog {
+    pub fn new(
+        config: ImageRsOutputConfig,
+        sender: std::sync::mpsc::Sender<ImageRsOutputConfig>,
+    ) -> Self {
+        Self {
+            config,
+            result_sender: sender,
+        }
+    }
+}
+
+impl eframe::App for ImageRsOutputConfigDialog {
+    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ui, |ui| {
+            ui.heading(format!("Rusty Image Output v{}", env!("CARGO_PKG_VERSION")));
+            ui.add_space(8.0);
+
+            egui::Grid::new("config-grid")
+                .num_columns(2)
+                .spacing([16.0, 8.0])
+                .show(ui, |ui| {
+                    ui.label(tr("PNGのビット深度:"));
+                    egui::ComboBox::from_id_salt("png_bit_depth")
+                        .selected_text(self.config.png_bit_depth.as_str())
+                        .show_ui(ui, |ui| {
+                            for depth in [PngBitDepth::Eight, PngBitDepth::Sixteen] {
+                                ui.selectable_value(
+                                    &mut self.config.png_bit_depth,
+                                    depth,
+                                    depth.as_str(),
+                                );
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label(tr("WebP:"));
+                    ui.checkbox(&mut self.config.webp_lossless, tr("可逆圧縮"));
+                    ui.end_row();
+
+                    ui.label(tr("品質（JPEG・非可逆WebP）:"));
+                    ui.add(egui::Slider::new(&mut self.config.quality, 1..=100));
+                    ui.end_row();
+
+                    ui.label(tr("透過:"));
+                    egui::ComboBox::from_id_salt("alpha_mode")
+                        .selected_text(tr(self.config.alpha_mode.as_str()))
+                        .show_ui(ui, |ui| {
+                            for mode in [AlphaMode::Keep, AlphaMode::Strip, AlphaMode::Composite] {
+                                ui.selectable_value(
+                                    &mut self.config.alpha_mode,
+                                    mode,
+                                    tr(mode.as_str()),
+                                );
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label(tr("背景色:"));
+                    ui.add_enabled_ui(self.config.alpha_mode == AlphaMode::Composite, |ui| {
+                        ui.color_edit_button_srgb(&mut self.config.background);
+                    });
+                    ui.end_row();
+                });
+
+            ui.checkbox(
+                &mut self.config.skip_existing,
+                tr("既存ファイルをスキップ"),
+            )
+            .on_hover_text(tr(
+                "有効にすると、書き出し済みの連番ファイルを飛ばして、途中から出力を再開します。",
+            ));
+            ui.checkbox(&mut self.config.verify, tr("書き出し後に検証"))
+                .on_hover_text(tr(
+                    "有効にすると、書き出したファイルを読み直して元のフレームと比較し、結果をmanifest.jsonに保存します。PNGと可逆WebPのみ検証できます。",
+                ));
+            ui.label(tr(
+                "JPEGは透過に対応していないため、「透過を保持」の場合でも透過を削除します。",
+            ));
+
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button(tr("保存")).clicked() {
+                    self.result_sender
+                        .send(self.config.clone())
+                        .expect("Failed to send config");
+                    ui.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+                if ui.button(tr("リセット")).clicked() {
+                    self.config = ImageRsOutputConfig::default();
+                }
+                if ui.button(tr("キャンセル")).clicked() {
+                    ui.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+        });
+    }
+}
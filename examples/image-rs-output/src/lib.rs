@@ -1,38 +1,209 @@
+mod config;
+mod dialog;
+mod verify;
+
 use anyhow::Context;
-use aviutl2::output::{OutputPlugin, video_frame::Pa64VideoFrame};
+use aviutl2::output::OutputPlugin;
+use config::{AlphaMode, ImageRsOutputConfig, PngBitDepth};
+use dialog::ImageRsOutputConfigDialog;
+use image::{DynamicImage, ImageBuffer, ImageFormat, Rgb, Rgba};
+use std::{io::Write, sync::Mutex};
 
 #[aviutl2::plugin(OutputPlugin)]
-struct ImageRsOutputPlugin;
+struct ImageRsOutputPlugin {
+    config: Mutex<ImageRsOutputConfig>,
+}
+
+/// 出力を再開するフレームを求める。
+///
+/// 先頭から連続して書き出し済みのフレームを探し、最初に欠けているフレームの1つ前から再開します。
+/// 欠けている直前のファイルは書き込み途中で中断された可能性があるため、書き直します。
+fn find_resume_frame(num_frames: u32, is_written: impl Fn(u32) -> bool) -> u32 {
+    let first_gap = (0..num_frames)
+        .find(|&i| !is_written(i))
+        .unwrap_or(num_frames);
+    first_gap.saturating_sub(1)
+}
+
+/// 大きさの見積もりに使うフレーム（先頭・中央・末尾）を返す。
+fn sample_frames(num_frames: u32) -> Vec<u32> {
+    let mut frames = vec![0, num_frames / 2, num_frames.saturating_sub(1)];
+    frames.retain(|&i| i < num_frames);
+    frames.dedup();
+    frames
+}
+
+/// 見本のフレームの大きさの平均から、全フレームの大きさの合計を見積もる。
+fn extrapolate_bytes(sample_sizes: &[u64], num_frames: u32) -> Option<u64> {
+    if sample_sizes.is_empty() {
+        return None;
+    }
+    let total = sample_sizes.iter().sum::<u64>() as f64;
+    Some((total / sample_sizes.len() as f64 * num_frames as f64).round() as u64)
+}
+
+/// αチャンネルを取り除いた画像を返す。
+///
+/// AviUtl2から受け取る画像は乗算済みαなので、背景色との合成は`色 + 背景色 × (1 - α)`で行います。
+/// `background`が`None`の場合は、色をそのまま残してαチャンネルだけを取り除きます。
+fn flatten_alpha<T>(
+    image: &ImageBuffer<Rgba<T>, Vec<T>>,
+    background: Option<[u8; 3]>,
+) -> ImageBuffer<Rgb<T>, Vec<T>>
+where
+    T: image::Primitive + Into<u64> + TryFrom<u64>,
+{
+    let max: u64 = T::DEFAULT_MAX_VALUE.into();
+    let background = background.map(|bg| bg.map(|c| c as u64 * max / 255));
+    ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *image.get_pixel(x, y);
+        let Some(background) = background else {
+            return Rgb([r, g, b]);
+        };
+        let inverse_alpha = max - a.into();
+        let composite = |color: T, background: u64| {
+            let value = color.into() + (background * inverse_alpha + max / 2) / max;
+            T::try_from(value.min(max)).unwrap_or(T::DEFAULT_MAX_VALUE)
+        };
+        Rgb([
+            composite(r, background[0]),
+            composite(g, background[1]),
+            composite(b, background[2]),
+        ])
+    })
+}
+
+/// 設定に従って透過を処理する。
+fn apply_alpha_mode(
+    image: DynamicImage,
+    config: &ImageRsOutputConfig,
+    supports_alpha: bool,
+) -> DynamicImage {
+    let background = match config.alpha_mode {
+        AlphaMode::Keep if supports_alpha => return image,
+        AlphaMode::Keep | AlphaMode::Strip => None,
+        AlphaMode::Composite => Some(config.background),
+    };
+    match image {
+        DynamicImage::ImageRgba8(image) => {
+            DynamicImage::ImageRgb8(flatten_alpha(&image, background))
+        }
+        DynamicImage::ImageRgba16(image) => {
+            DynamicImage::ImageRgb16(flatten_alpha(&image, background))
+        }
+        image => image,
+    }
+}
+
+/// 16bitのまま書き出すかどうか。
+///
+/// 16bitで書き出せるのはPNGのみなので、それ以外の形式では8bitの動画フレームを取得します。
+fn is_sixteen_bit(config: &ImageRsOutputConfig, format: ImageFormat) -> bool {
+    format == ImageFormat::Png && config.png_bit_depth == PngBitDepth::Sixteen
+}
+
+/// 画像を設定に従ってエンコードする。
+fn encode(
+    config: &ImageRsOutputConfig,
+    format: ImageFormat,
+    image: DynamicImage,
+    writer: &mut (impl std::io::Write + std::io::Seek),
+) -> anyhow::Result<()> {
+    let image = apply_alpha_mode(image, config, format != ImageFormat::Jpeg);
+    let quality = config.quality.clamp(1, 100);
+    match format {
+        ImageFormat::Png => {
+            image.write_with_encoder(image::codecs::png::PngEncoder::new(writer))?;
+        }
+        ImageFormat::Jpeg => {
+            image.write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(
+                writer, quality,
+            ))?;
+        }
+        ImageFormat::WebP if config.webp_lossless => {
+            image.write_with_encoder(image::codecs::webp::WebPEncoder::new_lossless(writer))?;
+        }
+        ImageFormat::WebP => {
+            // image-rsは非可逆WebPのエンコードに対応していないため、libwebpを使う
+            let encoder = match &image {
+                DynamicImage::ImageRgba8(image) => {
+                    webp::Encoder::from_rgba(image.as_raw(), image.width(), image.height())
+                }
+                DynamicImage::ImageRgb8(image) => {
+                    webp::Encoder::from_rgb(image.as_raw(), image.width(), image.height())
+                }
+                _ => anyhow::bail!("Unsupported color type for WebP: {:?}", image.color()),
+            };
+            writer.write_all(&encoder.encode(quality as f32))?;
+        }
+        format => {
+            image.write_to(writer, format)?;
+        }
+    }
+    Ok(())
+}
 
 impl ImageRsOutputPlugin {
     fn write(
         &self,
-        info: &aviutl2::output::OutputInfo,
+        config: &ImageRsOutputConfig,
         path: &std::path::Path,
-        frame: &Pa64VideoFrame,
+        format: ImageFormat,
+        image: DynamicImage,
     ) -> anyhow::Result<()> {
-        let video_info = info.video.as_ref().context("Video format not available")?;
-        let mut rgba_data = Vec::with_capacity(frame.data.len() * 4);
-        for &pixel in &frame.data {
-            rgba_data.push((pixel.0 >> 8) as u8); // R
-            rgba_data.push((pixel.1 >> 8) as u8); // G
-            rgba_data.push((pixel.2 >> 8) as u8); // B
-            rgba_data.push((pixel.3 >> 8) as u8); // A
-        }
-
-        let image = image::RgbaImage::from_raw(video_info.width, video_info.height, rgba_data)
-            .context("Failed to create image from raw data")?;
-        image
-            .save(path)
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = std::io::BufWriter::new(file);
+        encode(config, format, image, &mut writer)
             .with_context(|| format!("Failed to save image to {}", path.display()))?;
+        writer.flush()?;
 
         Ok(())
     }
+
+    /// 見本のフレームをエンコードして、全フレームの大きさを見積もる。
+    fn estimate_bytes(
+        &self,
+        info: &aviutl2::output::OutputInfo,
+        config: &ImageRsOutputConfig,
+        format: ImageFormat,
+        num_frames: u32,
+    ) -> anyhow::Result<Option<u64>> {
+        let indices = sample_frames(num_frames);
+        let images = if is_sixteen_bit(config, format) {
+            info.get_video_frames::<ImageBuffer<Rgba<u16>, Vec<u16>>>(&indices)?
+                .into_iter()
+                .map(DynamicImage::ImageRgba16)
+                .collect::<Vec<_>>()
+        } else {
+            info.get_video_frames::<image::RgbaImage>(&indices)?
+                .into_iter()
+                .map(DynamicImage::ImageRgba8)
+                .collect()
+        };
+        let sizes = images
+            .into_iter()
+            .map(|image| {
+                let mut buffer = std::io::Cursor::new(Vec::new());
+                encode(config, format, image, &mut buffer)?;
+                anyhow::Ok(buffer.into_inner().len() as u64)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(extrapolate_bytes(&sizes, num_frames))
+    }
+
+    fn lock_config(&self) -> anyhow::Result<std::sync::MutexGuard<'_, ImageRsOutputConfig>> {
+        self.config
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock Image Output Plugin config: {}", e))
+    }
 }
 
 impl OutputPlugin for ImageRsOutputPlugin {
     fn new(_info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
-        Ok(ImageRsOutputPlugin)
+        Ok(ImageRsOutputPlugin {
+            config: Mutex::new(ImageRsOutputConfig::default()),
+        })
     }
 
     fn plugin_info(&self) -> aviutl2::output::OutputPluginTable {
@@ -50,15 +221,80 @@ impl OutputPlugin for ImageRsOutputPlugin {
                 "image-rs Output for AviUtl2, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/image-rs-output",
                 version = env!("CARGO_PKG_VERSION")
             ),
-            can_config: false,
+            can_config: true,
             project_config: false,
         }
     }
 
+    fn config(&self, _hwnd: aviutl2::Win32WindowHandle) -> aviutl2::AnyResult<()> {
+        let (result_sender, result_receiver) = std::sync::mpsc::channel();
+        let config = self.lock_config()?.clone();
+        eframe::run_native(
+            "Rusty Image Output",
+            eframe::NativeOptions {
+                viewport: eframe::egui::ViewportBuilder::default()
+                    .with_inner_size([400.0, 320.0])
+                    .with_resizable(false),
+                ..Default::default()
+            },
+            Box::new(|cc| {
+                cc.egui_ctx.set_fonts(aviutl2_eframe::aviutl2_fonts());
+                Ok(Box::new(ImageRsOutputConfigDialog::new(
+                    config,
+                    result_sender,
+                )))
+            }),
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to run Image Output Plugin configuration: {}", e))?;
+
+        if let Ok(new_config) = result_receiver.try_recv() {
+            *self.lock_config()? = new_config;
+        }
+        Ok(())
+    }
+
+    fn config_text(&self) -> aviutl2::AnyResult<String> {
+        let config = self.lock_config()?;
+        let webp = if config.webp_lossless {
+            "可逆".to_string()
+        } else {
+            format!("非可逆（品質：{}）", config.quality)
+        };
+        let skip_existing = if config.skip_existing {
+            " | 既存ファイルをスキップ"
+        } else {
+            ""
+        };
+        let verify = if config.verify {
+            " | 書き出し後に検証"
+        } else {
+            ""
+        };
+        Ok(format!(
+            "PNG：{} | WebP：{webp} | JPEG品質：{} | {}{skip_existing}{verify}",
+            config.png_bit_depth.as_str(),
+            config.quality,
+            config.alpha_mode.as_str(),
+        ))
+    }
+
+    fn estimate(
+        &self,
+        _info: &aviutl2::output::OutputEstimateInfo,
+    ) -> aviutl2::AnyResult<aviutl2::output::OutputEstimate> {
+        // 画像の大きさは内容によって大きく変わるため、フレームを取得できる出力の開始時に見積もる
+        Ok(aviutl2::output::OutputEstimate {
+            bytes: None,
+            notes: "出力の開始時に3フレームをエンコードして見積もり、ログに表示します。"
+                .to_string(),
+        })
+    }
+
     fn output(&self, info: aviutl2::output::OutputInfo) -> aviutl2::AnyResult<()> {
         let Some(video_info) = &info.video else {
             anyhow::bail!("動画情報がありません。");
         };
+        let config = self.lock_config()?.clone();
         let path = info.path.clone();
         let pattern = lazy_regex::regex!(r"#+");
         let filename = path
@@ -79,26 +315,286 @@ impl OutputPlugin for ImageRsOutputPlugin {
             anyhow::bail!("連続する「`#`」の数が足りません。最低でも{required_len}つ必要です。");
         }
 
-        for (i, frame) in info.get_video_frames_iter() {
-            let frame_str = format!("{:0width$}", i, width = replaces[0].as_str().len());
-            let new_filename = pattern.replace(&filename, frame_str.as_str()).to_string()
-                + "."
-                + info
-                    .path
-                    .extension()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("webp");
-            let new_path = path.with_file_name(new_filename);
-            self.write(&info, &new_path, &frame).with_context(|| {
+        let extension = info
+            .path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("webp");
+        let format = ImageFormat::from_extension(extension)
+            .ok_or_else(|| anyhow::anyhow!("対応していない画像形式です：{extension}"))?;
+        let width = replaces[0].as_str().len();
+        let frame_path = |i: u32| {
+            let frame_str = format!("{i:0width$}");
+            let new_filename =
+                pattern.replace(&filename, frame_str.as_str()).to_string() + "." + extension;
+            path.with_file_name(new_filename)
+        };
+
+        let start_frame = if config.skip_existing {
+            find_resume_frame(video_info.num_frames, |i| {
+                std::fs::metadata(frame_path(i)).is_ok_and(|m| m.is_file() && m.len() > 0)
+            })
+        } else {
+            0
+        };
+        if start_frame > 0 {
+            aviutl2::lprintln!(
+                info,
+                "{start_frame}フレームは書き出し済みのためスキップします。"
+            );
+        }
+
+        match self.estimate_bytes(&info, &config, format, video_info.num_frames - start_frame) {
+            Ok(Some(bytes)) => {
+                aviutl2::lprintln!(
+                    info,
+                    "出力するファイルの合計は約{:.1} MBの見込みです。",
+                    bytes as f64 / 1_000_000.0
+                );
+            }
+            Ok(None) => {}
+            Err(e) => {
+                aviutl2::lprintln!(info, "出力するファイルの大きさを見積もれませんでした：{e}");
+            }
+        }
+
+        let should_verify = config.verify && verify::is_verifiable(&config, format);
+        if config.verify && !should_verify {
+            aviutl2::lprintln!(
+                info,
+                "非可逆圧縮の形式のため、書き出し後の検証をスキップします。"
+            );
+        }
+
+        // 検証は別スレッドで行い、次のフレームの取得・書き出しと並行させる
+        let (pending_sender, pending_receiver) = std::sync::mpsc::channel::<verify::PendingFrame>();
+        std::thread::scope(|scope| {
+            let verifier = should_verify.then(|| {
+                scope.spawn(move || {
+                    let mut manifest = verify::Manifest::new(format);
+                    for pending in pending_receiver {
+                        manifest.verify(format, pending);
+                    }
+                    manifest
+                })
+            });
+
+            let save = |i: u32, image: DynamicImage| {
+                let new_path = frame_path(i);
+                let image = apply_alpha_mode(image, &config, format != ImageFormat::Jpeg);
+                let pending = should_verify.then(|| verify::PendingFrame {
+                    frame: i,
+                    path: new_path.clone(),
+                    color: image.color(),
+                    hash: verify::image_hash(&image),
+                });
+                self.write(&config, &new_path, format, image)
+                    .with_context(|| {
+                        format!(
+                            "{}フレーム目を{}に保存できませんでした。",
+                            i,
+                            new_path.display()
+                        )
+                    })?;
+                if let Some(pending) = pending {
+                    pending_sender.send(pending).ok();
+                }
+                anyhow::Ok(())
+            };
+            // 16bitで書き出す場合のみPA64のまま取得し、それ以外は8bitに変換した状態で受け取る
+            let result = (|| {
+                if is_sixteen_bit(&config, format) {
+                    for (i, frame) in info
+                        .get_video_frames_iter_from::<ImageBuffer<Rgba<u16>, Vec<u16>>>(start_frame)
+                    {
+                        save(u32::try_from(i)?, DynamicImage::ImageRgba16(frame))?;
+                    }
+                } else {
+                    for (i, frame) in
+                        info.get_video_frames_iter_from::<image::RgbaImage>(start_frame)
+                    {
+                        save(u32::try_from(i)?, DynamicImage::ImageRgba8(frame))?;
+                    }
+                }
+                anyhow::Ok(())
+            })();
+            drop(save);
+            drop(pending_sender);
+
+            let Some(verifier) = verifier else {
+                return result;
+            };
+            let manifest = verifier
+                .join()
+                .map_err(|_| anyhow::anyhow!("Verification thread panicked"))?;
+            let manifest_path = path.with_file_name("manifest.json");
+            manifest.write(&manifest_path).with_context(|| {
                 format!(
-                    "{}フレーム目を{}に保存できませんでした。",
-                    i,
-                    new_path.display()
+                    "{}に検証結果を保存できませんでした。",
+                    manifest_path.display()
                 )
             })?;
-        }
-        Ok(())
+            let mismatches = manifest.mismatches().collect::<Vec<_>>();
+            // 検証に失敗した場合は結果を確認できるように、中断・失敗時に削除されないようにする
+            if mismatches.is_empty() {
+                info.register_sidecar(&manifest_path, aviutl2::output::SidecarKind::Metadata);
+            }
+            result?;
+
+            for record in &mismatches {
+                aviutl2::lprintln!(
+                    info,
+                    "{}フレーム目の検証に失敗しました：{}",
+                    record.frame,
+                    record.file
+                );
+            }
+            if !mismatches.is_empty() {
+                anyhow::bail!(
+                    "{}フレームの検証に失敗しました。詳細は{}を確認してください。",
+                    mismatches.len(),
+                    manifest_path.display()
+                );
+            }
+            Ok(())
+        })
     }
 }
 
 aviutl2::register_output_plugin!(ImageRsOutputPlugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume_from_start_when_nothing_written() {
+        assert_eq!(find_resume_frame(10, |_| false), 0);
+    }
+
+    #[test]
+    fn resume_rewrites_last_file_before_gap() {
+        assert_eq!(find_resume_frame(10, |i| i < 4), 3);
+        // 途中のフレームが欠けている場合、それ以降のファイルは無視する
+        assert_eq!(find_resume_frame(10, |i| i != 2), 1);
+    }
+
+    #[test]
+    fn resume_rewrites_last_file_when_all_written() {
+        assert_eq!(find_resume_frame(10, |_| true), 9);
+    }
+
+    #[test]
+    fn sample_frames_picks_first_middle_last() {
+        assert_eq!(sample_frames(100), vec![0, 50, 99]);
+        assert_eq!(sample_frames(2), vec![0, 1]);
+        assert_eq!(sample_frames(1), vec![0]);
+        assert_eq!(sample_frames(0), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn extrapolate_uses_average_size() {
+        assert_eq!(extrapolate_bytes(&[100, 200, 300], 10), Some(2000));
+        assert_eq!(extrapolate_bytes(&[], 10), None);
+    }
+
+    fn encode_to_vec(
+        config: &ImageRsOutputConfig,
+        format: ImageFormat,
+        image: DynamicImage,
+    ) -> Vec<u8> {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        encode(config, format, image, &mut buffer).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn png16_round_trip_is_exact() {
+        let config = ImageRsOutputConfig {
+            png_bit_depth: PngBitDepth::Sixteen,
+            ..Default::default()
+        };
+        let gradient = ImageBuffer::from_fn(256, 4, |x, y| {
+            let value = (x * 256 + y * 64) as u16;
+            Rgba([value, 65535 - value, value / 2, 65535])
+        });
+        assert!(is_sixteen_bit(&config, ImageFormat::Png));
+
+        let encoded = encode_to_vec(
+            &config,
+            ImageFormat::Png,
+            DynamicImage::ImageRgba16(gradient.clone()),
+        );
+        let decoded = image::load_from_memory_with_format(&encoded, ImageFormat::Png)
+            .unwrap()
+            .into_rgba16();
+        assert_eq!(decoded, gradient);
+    }
+
+    #[test]
+    fn sixteen_bit_is_png_only() {
+        let config = ImageRsOutputConfig {
+            png_bit_depth: PngBitDepth::Sixteen,
+            ..Default::default()
+        };
+        assert!(!is_sixteen_bit(&config, ImageFormat::Jpeg));
+        assert!(!is_sixteen_bit(&config, ImageFormat::WebP));
+        assert!(!is_sixteen_bit(
+            &ImageRsOutputConfig::default(),
+            ImageFormat::Png
+        ));
+    }
+
+    #[test]
+    fn composite_onto_background() {
+        // 乗算済みαで半透明の赤
+        let image = ImageBuffer::from_pixel(1, 1, Rgba([128u8, 0, 0, 128]));
+        let flattened = flatten_alpha(&image, Some([0, 0, 255]));
+        assert_eq!(flattened.get_pixel(0, 0), &Rgb([128, 0, 127]));
+
+        let stripped = flatten_alpha(&image, None);
+        assert_eq!(stripped.get_pixel(0, 0), &Rgb([128, 0, 0]));
+    }
+
+    #[test]
+    fn jpeg_drops_alpha_and_respects_quality() {
+        let image = DynamicImage::ImageRgba8(ImageBuffer::from_fn(64, 64, |x, y| {
+            Rgba([(x * 4) as u8, (y * 4) as u8, ((x ^ y) * 4) as u8, 255])
+        }));
+        let low = encode_to_vec(
+            &ImageRsOutputConfig {
+                quality: 10,
+                ..Default::default()
+            },
+            ImageFormat::Jpeg,
+            image.clone(),
+        );
+        let high = encode_to_vec(
+            &ImageRsOutputConfig {
+                quality: 100,
+                ..Default::default()
+            },
+            ImageFormat::Jpeg,
+            image,
+        );
+        assert!(low.len() < high.len());
+        let decoded = image::load_from_memory_with_format(&low, ImageFormat::Jpeg).unwrap();
+        assert!(!decoded.color().has_alpha());
+    }
+
+    #[test]
+    fn webp_lossless_round_trip() {
+        let image = ImageBuffer::from_fn(16, 16, |x, y| {
+            Rgba([(x * 16) as u8, (y * 16) as u8, 0, 255 - (x * y) as u8])
+        });
+        let encoded = encode_to_vec(
+            &ImageRsOutputConfig::default(),
+            ImageFormat::WebP,
+            DynamicImage::ImageRgba8(image.clone()),
+        );
+        let decoded = image::load_from_memory_with_format(&encoded, ImageFormat::WebP)
+            .unwrap()
+            .into_rgba8();
+        assert_eq!(decoded, image);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::config::ImageRsOutputConfig;
+use image::{ColorType, DynamicImage, ImageFormat};
+
+/// 書き出し後に読み直して検証できる形式かどうか。
+///
+/// 可逆圧縮の形式（PNGと可逆WebP）のみ検証できます。
+pub fn is_verifiable(config: &ImageRsOutputConfig, format: ImageFormat) -> bool {
+    match format {
+        ImageFormat::Png => true,
+        ImageFormat::WebP => config.webp_lossless,
+        _ => false,
+    }
+}
+
+/// 画像のハッシュ値を計算する。
+pub fn image_hash(image: &DynamicImage) -> u64 {
+    aviutl2::utils::frame_hash(image.as_bytes())
+}
+
+/// 書き出したファイルを読み直し、ハッシュ値を計算する。
+///
+/// デコーダーによってはαチャンネルの有無などが変わるため、書き出した画像の色の形式に揃えてから計算します。
+pub fn hash_written_file(
+    path: &std::path::Path,
+    format: ImageFormat,
+    color: ColorType,
+) -> anyhow::Result<u64> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let decoded = image::load(reader, format)?;
+    let decoded = match color {
+        ColorType::Rgb8 => DynamicImage::ImageRgb8(decoded.into_rgb8()),
+        ColorType::Rgba8 => DynamicImage::ImageRgba8(decoded.into_rgba8()),
+        ColorType::Rgb16 => DynamicImage::ImageRgb16(decoded.into_rgb16()),
+        ColorType::Rgba16 => DynamicImage::ImageRgba16(decoded.into_rgba16()),
+        color => anyhow::bail!("Unsupported color type for verification: {color:?}"),
+    };
+    Ok(image_hash(&decoded))
+}
+
+/// 検証を待っているフレーム。
+#[derive(Debug)]
+pub struct PendingFrame {
+    pub frame: u32,
+    pub path: std::path::PathBuf,
+    pub color: ColorType,
+    pub hash: u64,
+}
+
+/// 1フレーム分の検証結果。
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct FrameRecord {
+    pub frame: u32,
+    /// 出力先のフォルダからの相対パス。
+    pub file: String,
+    /// 元のフレームのハッシュ値（XXH3、16進数）。
+    pub hash: String,
+    /// 読み直したファイルのハッシュ値。読み直せなかった場合は`None`。
+    pub written_hash: Option<String>,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 検証結果の一覧。`manifest.json`として書き出す。
+#[derive(Debug, Default, serde::Serialize)]
+pub struct Manifest {
+    pub format: String,
+    pub frames: Vec<FrameRecord>,
+}
+
+impl Manifest {
+    pub fn new(format: ImageFormat) -> Self {
+        Self {
+            format: format.extensions_str()[0].to_string(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// フレームを読み直して検証し、結果を記録する。
+    pub fn verify(&mut self, format: ImageFormat, pending: PendingFrame) -> &FrameRecord {
+        let written = hash_written_file(&pending.path, format, pending.color);
+        let file = pending
+            .path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let record = match written {
+            Ok(written) => FrameRecord {
+                frame: pending.frame,
+                file,
+                hash: format!("{:016x}", pending.hash),
+                written_hash: Some(format!("{written:016x}")),
+                passed: written == pending.hash,
+                error: None,
+            },
+            Err(e) => FrameRecord {
+                frame: pending.frame,
+                file,
+                hash: format!("{:016x}", pending.hash),
+                written_hash: None,
+                passed: false,
+                error: Some(format!("{e:#}")),
+            },
+        };
+        self.frames.push(record);
+        self.frames.last().unwrap()
+    }
+
+    /// 検証に失敗したフレーム。
+    pub fn mismatches(&self) -> impl Iterator<Item = &FrameRecord> {
+        self.frames.iter().filter(|record| !record.passed)
+    }
+
+    pub fn write(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgba};
+
+    fn write_png(path: &std::path::Path, image: &DynamicImage) {
+        image.save_with_format(path, ImageFormat::Png).unwrap();
+    }
+
+    #[test]
+    fn corrupted_file_is_flagged_in_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let frames = (0..3u16)
+            .map(|i| {
+                DynamicImage::ImageRgba16(ImageBuffer::from_fn(8, 8, |x, y| {
+                    let value = (x as u16 * 4096) ^ (y as u16 * 256) ^ (i * 17);
+                    Rgba([value, 65535 - value, i, 65535])
+                }))
+            })
+            .collect::<Vec<_>>();
+
+        let mut pending = Vec::new();
+        for (i, frame) in frames.iter().enumerate() {
+            let path = dir.path().join(format!("frame_{i}.png"));
+            write_png(&path, frame);
+            pending.push(PendingFrame {
+                frame: i as u32,
+                path,
+                color: frame.color(),
+                hash: image_hash(frame),
+            });
+        }
+        // 1フレーム目を別の画像で上書きし、2フレーム目は途中のバイトを壊す
+        write_png(&pending[1].path, &frames[0]);
+        let mut bytes = std::fs::read(&pending[2].path).unwrap();
+        let len = bytes.len();
+        bytes[len / 2] ^= 0xFF;
+        std::fs::write(&pending[2].path, bytes).unwrap();
+
+        let mut manifest = Manifest::new(ImageFormat::Png);
+        for pending in pending {
+            manifest.verify(ImageFormat::Png, pending);
+        }
+        let failed = manifest
+            .mismatches()
+            .map(|record| record.frame)
+            .collect::<Vec<_>>();
+        assert_eq!(failed, [1, 2]);
+        assert!(manifest.frames[0].passed);
+        assert!(manifest.frames[1].written_hash.is_some());
+
+        let manifest_path = dir.path().join("manifest.json");
+        manifest.write(&manifest_path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&manifest_path).unwrap()).unwrap();
+        assert_eq!(json["format"], "png");
+        assert_eq!(json["frames"][0]["passed"], true);
+        assert_eq!(json["frames"][1]["passed"], false);
+        assert_eq!(json["frames"][1]["file"], "frame_1.png");
+    }
+
+    #[test]
+    fn lossy_formats_are_not_verifiable() {
+        let lossless = ImageRsOutputConfig::default();
+        let lossy = ImageRsOutputConfig {
+            webp_lossless: false,
+            ..Default::default()
+        };
+        assert!(is_verifiable(&lossless, ImageFormat::Png));
+        assert!(is_verifiable(&lossless, ImageFormat::WebP));
+        assert!(!is_verifiable(&lossy, ImageFormat::WebP));
+        assert!(!is_verifiable(&lossless, ImageFormat::Jpeg));
+    }
+}
@@ -1,13 +1,19 @@
+use crate::library::{self, ImportEvent};
 use crate::{AliasEntry, AliasState};
 use aviutl2::config::translate as tr;
 use aviutl2_eframe::{AviUtl2EframeHandle, eframe, egui};
-use std::sync::{Arc, Mutex};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, mpsc};
 
 pub(crate) struct LocalAliasApp {
     state: Arc<Mutex<AliasState>>,
     show_info: bool,
     rename_dialog: Option<RenameDialog>,
     delete_dialog: Option<DeleteDialog>,
+    group_dialog: Option<GroupDialog>,
+    export_dialog: Option<ExportDialog>,
+    import_job: Option<ImportJob>,
+    status: Option<String>,
     version: String,
     handle: AviUtl2EframeHandle,
     header_collapsed: bool,
@@ -23,6 +29,43 @@ struct DeleteDialog {
     name: String,
 }
 
+struct GroupDialog {
+    index: usize,
+    buffer: String,
+}
+
+struct ExportDialog {
+    checked: Vec<bool>,
+}
+
+struct ImportJob {
+    receiver: mpsc::Receiver<ImportEvent>,
+    total: Option<usize>,
+    processed: usize,
+    imported: usize,
+    failed: usize,
+    finished: bool,
+    pending: VecDeque<AliasEntry>,
+    conflict: Option<ConflictDialog>,
+    /// 「以降も同じ操作を行う」が選ばれたときの操作。
+    remembered: Option<ConflictAction>,
+}
+
+/// 読み込んだエイリアスが既存のエイリアスと同じグループ・名前だったときのダイアログ。
+struct ConflictDialog {
+    entry: AliasEntry,
+    existing: usize,
+    buffer: String,
+    apply_to_all: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ConflictAction {
+    Rename,
+    Skip,
+    Overwrite,
+}
+
 impl LocalAliasApp {
     pub(crate) fn new(
         cc: &eframe::CreationContext<'_>,
@@ -47,6 +90,10 @@ impl LocalAliasApp {
             show_info: false,
             rename_dialog: None,
             delete_dialog: None,
+            group_dialog: None,
+            export_dialog: None,
+            import_job: None,
+            status: None,
             version: env!("CARGO_PKG_VERSION").to_string(),
             handle,
             header_collapsed,
@@ -78,6 +125,10 @@ impl LocalAliasApp {
     fn move_alias(&self, index: usize, dir: i32) {
         self.with_state(|state| state.move_alias(index, dir));
     }
+
+    fn set_alias_group(&self, index: usize, group: String) {
+        self.with_state(|state| state.set_alias_group(index, group));
+    }
 }
 
 impl eframe::App for LocalAliasApp {
@@ -92,6 +143,17 @@ impl eframe::App for LocalAliasApp {
         }
 
         egui::CentralPanel::default().show(ui, |ui| {
+            if let Some(status) = &self.status {
+                let mut dismiss = false;
+                ui.horizontal(|ui| {
+                    ui.label(status);
+                    dismiss = ui.small_button("x").clicked();
+                });
+                if dismiss {
+                    self.status = None;
+                }
+                ui.separator();
+            }
             if aliases.is_empty() {
                 ui.label(tr(
                     "エイリアスがありません。オブジェクトを選択して「ローカルエイリアスに追加」メニューで追加してください。",
@@ -99,59 +161,24 @@ impl eframe::App for LocalAliasApp {
                 return;
             }
 
-            for (index, alias) in aliases.iter().enumerate() {
-                let selected = selected_index == Some(index);
-                let frame = egui::Frame::group(ui.style())
-                    .fill(if selected {
-                        ui.visuals().selection.bg_fill
-                    } else {
-                        ui.visuals().faint_bg_color
-                    })
-                    .stroke(if selected {
-                        ui.visuals().selection.stroke
-                    } else {
-                        ui.visuals().widgets.noninteractive.bg_stroke
-                    });
-                frame.show(ui, |ui| {
-                    ui.horizontal(|ui| {
-                        let select_button =
-                            egui::Button::new(&alias.name).selected(selected).frame(false);
-                        if ui.add(select_button).clicked() {
-                            self.set_selected_index(Some(index));
-                        }
-
-                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                            if ui
-                                .add_enabled(
-                                    index + 1 < aliases.len(),
-                                    egui::Button::new(tr("下へ")),
-                                )
-                                .clicked()
-                            {
-                                self.move_alias(index, 1);
-                            }
-                            if ui
-                                .add_enabled(index > 0, egui::Button::new(tr("上へ")))
-                                .clicked()
-                            {
-                                self.move_alias(index, -1);
-                            }
-                            if ui.button(tr("削除")).clicked() {
-                                self.delete_dialog = Some(DeleteDialog {
-                                    index,
-                                    name: alias.name.clone(),
-                                });
-                            }
-                            if ui.button(tr("名前変更")).clicked() {
-                                self.rename_dialog = Some(RenameDialog {
-                                    index,
-                                    buffer: alias.name.clone(),
-                                });
-                            }
+            let groups = self.with_state(|state| state.groups());
+            for group in groups {
+                let indices: Vec<usize> = aliases
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, alias)| alias.group == group)
+                    .map(|(index, _)| index)
+                    .collect();
+                if group.is_empty() {
+                    self.render_aliases(ui, &aliases, &indices, selected_index);
+                } else {
+                    egui::CollapsingHeader::new(&group)
+                        .id_salt(("alias_group", &group))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            self.render_aliases(ui, &aliases, &indices, selected_index);
                         });
-                    });
-                });
-                ui.add_space(6.0);
+                }
             }
         });
 
@@ -273,6 +300,10 @@ impl eframe::App for LocalAliasApp {
             self.delete_alias(index);
         }
 
+        self.render_group_dialog(ui, &aliases);
+        self.render_export_dialog(ui, &aliases);
+        self.update_import(ui);
+
         ui.data_mut(|data| {
             data.insert_persisted(
                 egui::Id::new("header_collapsed_local_alias"),
@@ -286,6 +317,20 @@ impl eframe::App for LocalAliasApp {
     }
 }
 
+fn copy_alias(alias: &AliasEntry) {
+    match aviutl2::generic::clipboard::set_object_alias(&alias.alias) {
+        Ok(()) if aviutl2::generic::clipboard::object_alias_format_name().is_none() => {
+            // AviUtl2のクリップボード形式が分からないうちは、プレーンテキストとしてのみコピーされる
+            tracing::warn!(
+                "Copied alias {:?} to clipboard as plain text; copy an object in AviUtl2 once to enable pasting with Ctrl+V",
+                alias.name
+            )
+        }
+        Ok(()) => tracing::info!("Copied alias {:?} to clipboard", alias.name),
+        Err(e) => tracing::error!("Failed to copy alias {:?} to clipboard: {}", alias.name, e),
+    }
+}
+
 impl LocalAliasApp {
     fn render_collapsed_header(&mut self, ui: &mut egui::Ui) {
         let toolbar = egui::Panel::top("header")
@@ -343,8 +388,491 @@ impl LocalAliasApp {
                     if collapse.clicked() {
                         self.header_collapsed = true;
                     }
+                    if ui
+                        .add_enabled(self.import_job.is_none(), egui::Button::new(tr("読み込み")))
+                        .on_hover_text(tr("フォルダ内の.objectファイルを読み込みます。"))
+                        .clicked()
+                    {
+                        self.start_import(ui.ctx());
+                    }
+                    if ui
+                        .button(tr("書き出し"))
+                        .on_hover_text(tr("エイリアスを.objectファイルとして書き出します。"))
+                        .clicked()
+                    {
+                        let len = self.state.lock().unwrap().aliases.len();
+                        self.export_dialog = Some(ExportDialog {
+                            checked: vec![true; len],
+                        });
+                    }
                 });
             });
         });
     }
 }
+
+impl LocalAliasApp {
+    fn render_aliases(
+        &mut self,
+        ui: &mut egui::Ui,
+        aliases: &[AliasEntry],
+        indices: &[usize],
+        selected_index: Option<usize>,
+    ) {
+        for (position, &index) in indices.iter().enumerate() {
+            let alias = &aliases[index];
+            let selected = selected_index == Some(index);
+            let frame = egui::Frame::group(ui.style())
+                .fill(if selected {
+                    ui.visuals().selection.bg_fill
+                } else {
+                    ui.visuals().faint_bg_color
+                })
+                .stroke(if selected {
+                    ui.visuals().selection.stroke
+                } else {
+                    ui.visuals().widgets.noninteractive.bg_stroke
+                });
+            frame.show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let select_button = egui::Button::new(&alias.name)
+                        .selected(selected)
+                        .frame(false)
+                        .sense(egui::Sense::click_and_drag());
+                    let response = ui
+                        .add(select_button)
+                        .on_hover_text(tr("タイムラインへドラッグすると、その位置に配置します。"));
+                    if response.clicked() {
+                        self.set_selected_index(Some(index));
+                    }
+                    if response.dragged() {
+                        ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+                    }
+                    if response.drag_stopped() {
+                        self.drop_alias(alias);
+                    }
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui
+                            .add_enabled(
+                                position + 1 < indices.len(),
+                                egui::Button::new(tr("下へ")),
+                            )
+                            .clicked()
+                        {
+                            self.move_alias(index, 1);
+                        }
+                        if ui
+                            .add_enabled(position > 0, egui::Button::new(tr("上へ")))
+                            .clicked()
+                        {
+                            self.move_alias(index, -1);
+                        }
+                        if ui.button(tr("削除")).clicked() {
+                            self.delete_dialog = Some(DeleteDialog {
+                                index,
+                                name: alias.name.clone(),
+                            });
+                        }
+                        if ui.button(tr("グループ")).clicked() {
+                            self.group_dialog = Some(GroupDialog {
+                                index,
+                                buffer: alias.group.clone(),
+                            });
+                        }
+                        if ui.button(tr("名前変更")).clicked() {
+                            self.rename_dialog = Some(RenameDialog {
+                                index,
+                                buffer: alias.name.clone(),
+                            });
+                        }
+                        if ui
+                            .button(tr("クリップボードへコピー"))
+                            .on_hover_text(tr("タイムラインでCtrl+Vを押すと貼り付けられます。"))
+                            .clicked()
+                        {
+                            copy_alias(alias);
+                        }
+                    });
+                });
+            });
+            ui.add_space(6.0);
+        }
+    }
+
+    /// ドラッグを終えたときのマウスカーソルの位置に、エイリアスを配置する。
+    ///
+    /// カーソルがレイヤー編集の外にある場合は何もしない。
+    fn drop_alias(&mut self, alias: &AliasEntry) {
+        let result = crate::EDIT_HANDLE.call_edit_section(|edit_section| {
+            let Some(position) = edit_section.get_mouse_layer_frame()? else {
+                return Ok(false);
+            };
+            edit_section.create_object_from_alias(
+                &alias.alias,
+                position.layer,
+                position.frame,
+                1,
+            )?;
+            anyhow::Ok(true)
+        });
+        match result
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result)
+        {
+            Ok(true) => tracing::info!("Dropped alias {:?} onto the timeline", alias.name),
+            Ok(false) => {}
+            Err(e) => {
+                tracing::error!("Failed to drop alias {:?}: {:#}", alias.name, e);
+                self.status = Some(tr("配置に失敗しました：{}").replace("{}", &format!("{e:#}")));
+            }
+        }
+    }
+
+    fn render_group_dialog(&mut self, ui: &mut egui::Ui, aliases: &[AliasEntry]) {
+        let mut group_action = None;
+        if let Some(dialog) = self.group_dialog.as_mut() {
+            if dialog.index >= aliases.len() {
+                self.group_dialog = None;
+            } else {
+                let groups = self.state.lock().unwrap().groups();
+                let mut open = true;
+                let mut save = false;
+                let mut cancel = false;
+                egui::Window::new(tr("グループ"))
+                    .collapsible(false)
+                    .resizable(false)
+                    .open(&mut open)
+                    .show(ui, |ui| {
+                        ui.label(tr(
+                            "グループ名（「/」で区切ると、書き出し時に入れ子のフォルダになります）",
+                        ));
+                        let response = ui.text_edit_singleline(&mut dialog.buffer);
+                        let pressed_enter =
+                            response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        ui.horizontal_wrapped(|ui| {
+                            for group in &groups {
+                                let label = if group.is_empty() {
+                                    tr("（グループなし）")
+                                } else {
+                                    group.clone()
+                                };
+                                if ui
+                                    .selectable_label(&dialog.buffer == group, label)
+                                    .clicked()
+                                {
+                                    dialog.buffer = group.clone();
+                                }
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if ui.button(tr("保存")).clicked() || pressed_enter {
+                                save = true;
+                            }
+                            if ui.button(tr("キャンセル")).clicked() {
+                                cancel = true;
+                            }
+                        });
+                    });
+                if save {
+                    group_action = Some((dialog.index, dialog.buffer.clone()));
+                    open = false;
+                }
+                if cancel {
+                    open = false;
+                }
+                if !open {
+                    self.group_dialog = None;
+                }
+            }
+        }
+        if let Some((index, group)) = group_action {
+            self.set_alias_group(index, group);
+        }
+    }
+
+    fn render_export_dialog(&mut self, ui: &mut egui::Ui, aliases: &[AliasEntry]) {
+        let mut export_action = None;
+        if let Some(dialog) = self.export_dialog.as_mut() {
+            dialog.checked.resize(aliases.len(), true);
+            let mut open = true;
+            let mut export = false;
+            let mut cancel = false;
+            egui::Window::new(tr("書き出し"))
+                .collapsible(false)
+                .resizable(true)
+                .open(&mut open)
+                .show(ui, |ui| {
+                    ui.label(tr("書き出すエイリアスを選択してください。"));
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("すべて選択")).clicked() {
+                            dialog.checked.fill(true);
+                        }
+                        if ui.button(tr("すべて解除")).clicked() {
+                            dialog.checked.fill(false);
+                        }
+                    });
+                    egui::ScrollArea::vertical()
+                        .max_height(240.0)
+                        .show(ui, |ui| {
+                            for (alias, checked) in aliases.iter().zip(dialog.checked.iter_mut()) {
+                                ui.checkbox(checked, display_name(alias));
+                            }
+                        });
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(
+                                dialog.checked.contains(&true),
+                                egui::Button::new(tr("フォルダを選択して書き出し")),
+                            )
+                            .clicked()
+                        {
+                            export = true;
+                        }
+                        if ui.button(tr("キャンセル")).clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+            if export {
+                export_action = Some(
+                    aliases
+                        .iter()
+                        .zip(&dialog.checked)
+                        .filter(|(_, checked)| **checked)
+                        .map(|(alias, _)| alias.clone())
+                        .collect::<Vec<_>>(),
+                );
+                open = false;
+            }
+            if cancel {
+                open = false;
+            }
+            if !open {
+                self.export_dialog = None;
+            }
+        }
+        if let Some(aliases) = export_action {
+            self.export_aliases(&aliases);
+        }
+    }
+
+    fn export_aliases(&mut self, aliases: &[AliasEntry]) {
+        let dir = native_dialog::FileDialogBuilder::default()
+            .set_title(tr("書き出し先のフォルダを選択"))
+            .open_single_dir()
+            .show();
+        let dir = match dir {
+            Ok(Some(dir)) => dir,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!("Failed to open folder dialog: {}", e);
+                return;
+            }
+        };
+        match library::export_aliases(aliases, &dir) {
+            Ok(written) => {
+                tracing::info!("Exported {} aliases to {}", written.len(), dir.display());
+                self.status = Some(
+                    tr("{}件のエイリアスを書き出しました。")
+                        .replace("{}", &written.len().to_string()),
+                );
+            }
+            Err(e) => {
+                tracing::error!("Failed to export aliases: {:#}", e);
+                self.status =
+                    Some(tr("書き出しに失敗しました：{}").replace("{}", &format!("{e:#}")));
+            }
+        }
+    }
+
+    fn start_import(&mut self, ctx: &egui::Context) {
+        let dir = native_dialog::FileDialogBuilder::default()
+            .set_title(tr("読み込むフォルダを選択"))
+            .open_single_dir()
+            .show();
+        let dir = match dir {
+            Ok(Some(dir)) => dir,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!("Failed to open folder dialog: {}", e);
+                return;
+            }
+        };
+        let (sender, receiver) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            library::import_aliases(&dir, |event| {
+                let _ = sender.send(event);
+                ctx.request_repaint();
+            });
+        });
+        self.import_job = Some(ImportJob {
+            receiver,
+            total: None,
+            processed: 0,
+            imported: 0,
+            failed: 0,
+            finished: false,
+            pending: VecDeque::new(),
+            conflict: None,
+            remembered: None,
+        });
+    }
+
+    fn update_import(&mut self, ui: &mut egui::Ui) {
+        let Some(job) = self.import_job.as_mut() else {
+            return;
+        };
+        while let Ok(event) = job.receiver.try_recv() {
+            match event {
+                ImportEvent::Found(total) => job.total = Some(total),
+                ImportEvent::Imported(entry) => {
+                    job.processed += 1;
+                    job.pending.push_back(entry);
+                }
+                ImportEvent::Failed(path, e) => {
+                    job.processed += 1;
+                    job.failed += 1;
+                    tracing::warn!("Failed to import {}: {}", path.display(), e);
+                }
+                ImportEvent::Finished => job.finished = true,
+            }
+        }
+
+        while job.conflict.is_none() {
+            let Some(entry) = job.pending.pop_front() else {
+                break;
+            };
+            let mut state = self.state.lock().unwrap();
+            let Some(existing) = state.find_alias(&entry.group, &entry.name) else {
+                state.add_alias(entry);
+                job.imported += 1;
+                continue;
+            };
+            let buffer = state.unique_name(&entry.group, &entry.name);
+            let conflict = ConflictDialog {
+                entry,
+                existing,
+                buffer,
+                apply_to_all: false,
+            };
+            match job.remembered {
+                Some(action) => resolve_conflict(&mut state, job, conflict, action),
+                None => job.conflict = Some(conflict),
+            }
+        }
+
+        if let Some(conflict) = job.conflict.as_mut() {
+            let name = conflict.buffer.trim().to_string();
+            let available = !name.is_empty()
+                && self
+                    .state
+                    .lock()
+                    .unwrap()
+                    .find_alias(&conflict.entry.group, &name)
+                    .is_none();
+            let mut action = None;
+            egui::Window::new(tr("エイリアスの重複"))
+                .collapsible(false)
+                .resizable(false)
+                .show(ui, |ui| {
+                    let template = tr("エイリアス \"{}\" は既に存在します。");
+                    ui.label(template.replace("{}", &display_name(&conflict.entry)));
+                    ui.label(tr("新しいエイリアス名"));
+                    ui.text_edit_singleline(&mut conflict.buffer);
+                    ui.checkbox(&mut conflict.apply_to_all, tr("以降も同じ操作を行う"));
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(available, egui::Button::new(tr("名前を変更して追加")))
+                            .clicked()
+                        {
+                            action = Some(ConflictAction::Rename);
+                        }
+                        if ui.button(tr("上書き")).clicked() {
+                            action = Some(ConflictAction::Overwrite);
+                        }
+                        if ui.button(tr("スキップ")).clicked() {
+                            action = Some(ConflictAction::Skip);
+                        }
+                    });
+                });
+            if let Some(action) = action {
+                let mut conflict = job.conflict.take().unwrap();
+                conflict.buffer = name;
+                if conflict.apply_to_all {
+                    job.remembered = Some(action);
+                }
+                let mut state = self.state.lock().unwrap();
+                resolve_conflict(&mut state, job, conflict, action);
+                ui.ctx().request_repaint();
+            }
+        } else if !job.finished {
+            egui::Window::new(tr("読み込み中"))
+                .collapsible(false)
+                .resizable(false)
+                .show(ui, |ui| match job.total {
+                    Some(total) if total > 0 => {
+                        ui.add(
+                            egui::ProgressBar::new(job.processed as f32 / total as f32)
+                                .text(format!("{} / {}", job.processed, total)),
+                        );
+                    }
+                    _ => {
+                        ui.spinner();
+                    }
+                });
+        } else if job.pending.is_empty() {
+            tracing::info!("Imported {} aliases ({} failed)", job.imported, job.failed);
+            let mut status =
+                tr("{}件のエイリアスを読み込みました。").replace("{}", &job.imported.to_string());
+            if job.failed > 0 {
+                status.push(' ');
+                status.push_str(
+                    &tr("{}件のファイルは読み込めませんでした。")
+                        .replace("{}", &job.failed.to_string()),
+                );
+            }
+            self.status = Some(status);
+            self.import_job = None;
+        }
+    }
+}
+
+fn resolve_conflict(
+    state: &mut AliasState,
+    job: &mut ImportJob,
+    conflict: ConflictDialog,
+    action: ConflictAction,
+) {
+    let ConflictDialog {
+        mut entry,
+        existing,
+        buffer,
+        ..
+    } = conflict;
+    match action {
+        ConflictAction::Rename => {
+            entry.name = if state.find_alias(&entry.group, &buffer).is_none() {
+                buffer
+            } else {
+                state.unique_name(&entry.group, &entry.name)
+            };
+            state.add_alias(entry);
+            job.imported += 1;
+        }
+        ConflictAction::Overwrite => {
+            state.overwrite_alias(existing, entry.alias);
+            job.imported += 1;
+        }
+        ConflictAction::Skip => {}
+    }
+}
+
+fn display_name(alias: &AliasEntry) -> String {
+    if alias.group.is_empty() {
+        alias.name.clone()
+    } else {
+        format!("{}/{}", alias.group, alias.name)
+    }
+}
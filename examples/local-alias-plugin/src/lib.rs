@@ -2,11 +2,15 @@ use aviutl2::AnyResult;
 use std::sync::{Arc, Mutex};
 
 mod gui;
+mod library;
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct AliasEntry {
     name: String,
     alias: String,
+    /// エイリアスのグループ。`a/b`のように`/`で区切ると、書き出し時に入れ子のフォルダになる。
+    #[serde(default)]
+    group: String,
 }
 
 #[derive(Default)]
@@ -38,6 +42,48 @@ impl AliasState {
         }
     }
 
+    fn set_alias_group(&mut self, index: usize, group: String) {
+        if let Some(alias) = self.aliases.get_mut(index) {
+            alias.group = library::normalize_group(&group);
+            update_current_alias(self);
+        }
+    }
+
+    fn overwrite_alias(&mut self, index: usize, alias: String) {
+        if let Some(entry) = self.aliases.get_mut(index) {
+            entry.alias = alias;
+            update_current_alias(self);
+        }
+    }
+
+    fn find_alias(&self, group: &str, name: &str) -> Option<usize> {
+        self.aliases
+            .iter()
+            .position(|alias| alias.group == group && alias.name == name)
+    }
+
+    /// `group`の中で重複しない名前を、`name (2)`のように番号を付けて作る。
+    fn unique_name(&self, group: &str, name: &str) -> String {
+        if self.find_alias(group, name).is_none() {
+            return name.to_string();
+        }
+        (2..)
+            .map(|suffix| format!("{name} ({suffix})"))
+            .find(|candidate| self.find_alias(group, candidate).is_none())
+            .unwrap()
+    }
+
+    /// グループ名を、最初に出てきた順に返す。
+    fn groups(&self) -> Vec<String> {
+        let mut groups: Vec<String> = Vec::new();
+        for alias in &self.aliases {
+            if !groups.contains(&alias.group) {
+                groups.push(alias.group.clone());
+            }
+        }
+        groups
+    }
+
     fn delete_alias(&mut self, index: usize) {
         if index >= self.aliases.len() {
             return;
@@ -53,29 +99,29 @@ impl AliasState {
         update_current_alias(self);
     }
 
+    /// 同じグループの中で、エイリアスを前後のエイリアスと入れ替える。
     fn move_alias(&mut self, index: usize, dir: i32) {
-        if index >= self.aliases.len() {
+        let Some(new_index) = self.neighbor_in_group(index, dir) else {
             return;
-        }
-        let new_index = if dir < 0 {
-            match index.checked_sub(1) {
-                Some(idx) => idx,
-                None => return,
-            }
-        } else {
-            index + 1
         };
-        if new_index >= self.aliases.len() {
-            return;
-        }
-        let item = self.aliases.remove(index);
-        self.aliases.insert(new_index, item);
+        self.aliases.swap(index, new_index);
         if self.selected_index == Some(index) {
             self.selected_index = Some(new_index);
+        } else if self.selected_index == Some(new_index) {
+            self.selected_index = Some(index);
         }
         update_current_alias(self);
     }
 
+    fn neighbor_in_group(&self, index: usize, dir: i32) -> Option<usize> {
+        let group = &self.aliases.get(index)?.group;
+        if dir < 0 {
+            (0..index).rev().find(|&i| &self.aliases[i].group == group)
+        } else {
+            (index + 1..self.aliases.len()).find(|&i| &self.aliases[i].group == group)
+        }
+    }
+
     fn clamp_selection(&mut self) {
         if let Some(index) = self.selected_index
             && index >= self.aliases.len()
@@ -187,11 +233,30 @@ impl LocalAliasPlugin {
         self.state.lock().unwrap().add_alias(AliasEntry {
             name: "New Alias".to_string(),
             alias,
+            group: String::new(),
         });
         self.window.egui_ctx()?.request_repaint();
         Ok(())
     }
 
+    #[object(name = "ローカルエイリアスで置き換え")]
+    fn menu_replace_alias(&mut self) -> AnyResult<()> {
+        let Some(alias) = CURRENT_ALIAS.lock().unwrap().clone() else {
+            anyhow::bail!("エイリアスが選択されていません。")
+        };
+        // 削除と作成を1つのUndoにまとめ、作成に失敗した場合は削除したオブジェクトを作り直す
+        EDIT_HANDLE.undo_group("エイリアス置き換え", move |tx| {
+            let Some(object) = tx.get_focused_object()? else {
+                anyhow::bail!("オブジェクトが選択されていません。");
+            };
+            let aviutl2::generic::ObjectLayerFrame { layer, start, end } =
+                tx.get_object_layer_frame(object)?;
+            tx.delete_object(object)?;
+            tx.try_create_object_from_alias(&alias.alias, layer, start, end + 1 - start)?;
+            Ok(())
+        })
+    }
+
     #[layer(name = "ローカルエイリアスを配置")]
     fn menu_insert_alias(&mut self) -> AnyResult<()> {
         EDIT_HANDLE.call_edit_section(|edit_section| {
@@ -211,3 +276,64 @@ impl LocalAliasPlugin {
 }
 
 aviutl2::register_generic_plugin!(LocalAliasPlugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, group: &str) -> AliasEntry {
+        AliasEntry {
+            name: name.to_string(),
+            alias: String::new(),
+            group: group.to_string(),
+        }
+    }
+
+    fn names(state: &AliasState) -> Vec<&str> {
+        state
+            .aliases
+            .iter()
+            .map(|alias| alias.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let mut state = AliasState::default();
+        state.set_aliases(vec![entry("a", ""), entry("a (2)", ""), entry("b", "g")]);
+        assert_eq!(state.unique_name("", "a"), "a (3)");
+        assert_eq!(state.unique_name("g", "a"), "a");
+        assert_eq!(state.unique_name("", "b"), "b");
+    }
+
+    #[test]
+    fn test_move_alias_within_group() {
+        let mut state = AliasState::default();
+        state.set_aliases(vec![
+            entry("a", ""),
+            entry("x", "g"),
+            entry("b", ""),
+            entry("y", "g"),
+        ]);
+        state.set_selected_index(Some(2));
+        state.move_alias(2, -1);
+        assert_eq!(names(&state), ["b", "x", "a", "y"]);
+        assert_eq!(state.selected_index, Some(0));
+        state.move_alias(0, -1);
+        assert_eq!(names(&state), ["b", "x", "a", "y"]);
+        state.move_alias(3, 1);
+        assert_eq!(names(&state), ["b", "x", "a", "y"]);
+        state.move_alias(1, 1);
+        assert_eq!(names(&state), ["b", "y", "a", "x"]);
+    }
+
+    #[test]
+    fn test_groups_and_set_alias_group() {
+        let mut state = AliasState::default();
+        state.set_aliases(vec![entry("a", "g"), entry("b", ""), entry("c", "g")]);
+        assert_eq!(state.groups(), ["g", ""]);
+        state.set_alias_group(1, " h / i ".to_string());
+        assert_eq!(state.aliases[1].group, "h/i");
+        assert_eq!(state.groups(), ["g", "h/i"]);
+    }
+}
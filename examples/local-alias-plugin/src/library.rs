@@ -0,0 +1,286 @@
+//! エイリアスを`.object`ファイルとして書き出したり、フォルダから読み込んだりする処理。
+//!
+//! グループはフォルダに対応していて、`a/b`のようなグループ名は`a\b`のサブフォルダとして書き出される。
+use crate::AliasEntry;
+use anyhow::Context;
+use aviutl2_alias::Table;
+use std::path::{Path, PathBuf};
+
+/// エイリアスファイルの拡張子。
+pub(crate) const OBJECT_EXTENSION: &str = "object";
+
+/// フォルダの読み込み中に送られるイベント。
+#[derive(Debug)]
+pub(crate) enum ImportEvent {
+    /// 読み込む`.object`ファイルが見つかった。中身は見つかったファイルの数。
+    Found(usize),
+    Imported(AliasEntry),
+    Failed(PathBuf, String),
+    Finished,
+}
+
+/// エイリアスの文字列を`.object`ファイルの中身に変換する。
+pub(crate) fn to_object_file(alias: &str) -> anyhow::Result<String> {
+    Ok(parse_object_table(alias)?.to_string())
+}
+
+/// `.object`ファイルの中身をエイリアスの文字列に変換する。
+pub(crate) fn from_object_file(content: &str) -> anyhow::Result<String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    Ok(parse_object_table(content)?.to_string())
+}
+
+fn parse_object_table(source: &str) -> anyhow::Result<Table> {
+    let table: Table = source.parse()?;
+    anyhow::ensure!(
+        table.get_table("Object").is_some(),
+        "[Object]セクションがありません。"
+    );
+    Ok(table)
+}
+
+/// グループ名をフォルダ名の列に分解する。
+pub(crate) fn group_parts(group: &str) -> impl Iterator<Item = &str> {
+    group
+        .split(['/', '\\'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+}
+
+/// グループ名を`a/b`の形に正規化する。
+pub(crate) fn normalize_group(group: &str) -> String {
+    group_parts(group).collect::<Vec<_>>().join("/")
+}
+
+/// ファイル名に使えない文字を`_`に置き換える。
+fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let sanitized = sanitized.trim().trim_end_matches('.').to_string();
+    if sanitized.is_empty() {
+        "alias".to_string()
+    } else {
+        sanitized
+    }
+}
+
+/// エイリアスを`dir`以下に`.object`ファイルとして書き出し、書き出したファイルのパスを返す。
+///
+/// 同じフォルダに同じ名前のエイリアスがある場合は、`名前 (2).object`のように番号を付ける。
+pub(crate) fn export_aliases(aliases: &[AliasEntry], dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut written: Vec<PathBuf> = Vec::new();
+    for alias in aliases {
+        let content = to_object_file(&alias.alias)
+            .with_context(|| format!("エイリアス \"{}\" を変換できませんでした。", alias.name))?;
+        let mut folder = dir.to_path_buf();
+        for part in group_parts(&alias.group) {
+            folder.push(sanitize_file_name(part));
+        }
+        std::fs::create_dir_all(&folder)?;
+
+        let stem = sanitize_file_name(&alias.name);
+        let mut path = folder.join(format!("{stem}.{OBJECT_EXTENSION}"));
+        let mut suffix = 2;
+        while written.contains(&path) {
+            path = folder.join(format!("{stem} ({suffix}).{OBJECT_EXTENSION}"));
+            suffix += 1;
+        }
+        std::fs::write(&path, content)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// `dir`以下の`.object`ファイルを再帰的に探す。
+pub(crate) fn find_object_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(OBJECT_EXTENSION))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// `.object`ファイルを読み込む。`root`からの相対的なフォルダがグループになる。
+pub(crate) fn read_object_file(root: &Path, path: &Path) -> anyhow::Result<AliasEntry> {
+    let content = std::fs::read_to_string(path)?;
+    let alias = from_object_file(&content)?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let group = path
+        .parent()
+        .and_then(|parent| parent.strip_prefix(root).ok())
+        .map(|relative| {
+            relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .unwrap_or_default();
+    Ok(AliasEntry { name, alias, group })
+}
+
+/// `dir`以下の`.object`ファイルをすべて読み込み、進捗を`on_event`に送る。
+pub(crate) fn import_aliases(dir: &Path, mut on_event: impl FnMut(ImportEvent)) {
+    let files = match find_object_files(dir) {
+        Ok(files) => files,
+        Err(e) => {
+            on_event(ImportEvent::Failed(dir.to_path_buf(), e.to_string()));
+            on_event(ImportEvent::Finished);
+            return;
+        }
+    };
+    on_event(ImportEvent::Found(files.len()));
+    for path in files {
+        match read_object_file(dir, &path) {
+            Ok(entry) => on_event(ImportEvent::Imported(entry)),
+            Err(e) => on_event(ImportEvent::Failed(path, e.to_string())),
+        }
+    }
+    on_event(ImportEvent::Finished);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBJECT_FIXTURE: &str = include_str!("../test_assets/object.object");
+    const EFFECT_FIXTURE: &str = include_str!("../test_assets/effect.effect");
+
+    struct TempDir(PathBuf);
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("rusty-local-alias-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn entry(name: &str, group: &str) -> AliasEntry {
+        AliasEntry {
+            name: name.to_string(),
+            alias: OBJECT_FIXTURE.to_string(),
+            group: group.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_object_file_matches_fixture() {
+        assert_eq!(to_object_file(OBJECT_FIXTURE).unwrap(), OBJECT_FIXTURE);
+        assert_eq!(from_object_file(OBJECT_FIXTURE).unwrap(), OBJECT_FIXTURE);
+    }
+
+    #[test]
+    fn test_object_file_with_bom() {
+        let content = format!("\u{feff}{OBJECT_FIXTURE}");
+        assert_eq!(from_object_file(&content).unwrap(), OBJECT_FIXTURE);
+    }
+
+    #[test]
+    fn test_object_file_rejects_non_object() {
+        assert!(from_object_file(EFFECT_FIXTURE).is_err());
+        assert!(from_object_file("not an alias").is_err());
+    }
+
+    #[test]
+    fn test_normalize_group() {
+        assert_eq!(normalize_group(""), "");
+        assert_eq!(normalize_group(" a / b "), "a/b");
+        assert_eq!(normalize_group("a\\\\b/"), "a/b");
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("a/b:c?"), "a_b_c_");
+        assert_eq!(sanitize_file_name("name."), "name");
+        assert_eq!(sanitize_file_name("  "), "alias");
+    }
+
+    #[test]
+    fn test_export_and_import_round_trip() {
+        let dir = TempDir::new("round-trip");
+        let aliases = vec![
+            entry("円", ""),
+            entry("円", ""),
+            entry("Glow", "effects/light"),
+        ];
+        let written = export_aliases(&aliases, &dir.0).unwrap();
+        assert_eq!(
+            written,
+            vec![
+                dir.0.join("円.object"),
+                dir.0.join("円 (2).object"),
+                dir.0.join("effects").join("light").join("Glow.object"),
+            ]
+        );
+        for path in &written {
+            assert_eq!(std::fs::read_to_string(path).unwrap(), OBJECT_FIXTURE);
+        }
+
+        let mut events = Vec::new();
+        import_aliases(&dir.0, |event| events.push(event));
+        assert!(matches!(events.first(), Some(ImportEvent::Found(3))));
+        assert!(matches!(events.last(), Some(ImportEvent::Finished)));
+        let mut imported: Vec<(String, String)> = events
+            .into_iter()
+            .filter_map(|event| match event {
+                ImportEvent::Imported(entry) => {
+                    assert_eq!(entry.alias, OBJECT_FIXTURE);
+                    Some((entry.group, entry.name))
+                }
+                _ => None,
+            })
+            .collect();
+        imported.sort();
+        assert_eq!(
+            imported,
+            vec![
+                ("".to_string(), "円".to_string()),
+                ("".to_string(), "円 (2)".to_string()),
+                ("effects/light".to_string(), "Glow".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_reports_invalid_files() {
+        let dir = TempDir::new("invalid");
+        std::fs::write(dir.0.join("broken.object"), EFFECT_FIXTURE).unwrap();
+        std::fs::write(dir.0.join("ignored.txt"), OBJECT_FIXTURE).unwrap();
+
+        let mut events = Vec::new();
+        import_aliases(&dir.0, |event| events.push(event));
+        assert!(matches!(events[0], ImportEvent::Found(1)));
+        assert!(
+            matches!(&events[1], ImportEvent::Failed(path, _) if path.ends_with("broken.object"))
+        );
+        assert!(matches!(events[2], ImportEvent::Finished));
+    }
+}
@@ -0,0 +1,252 @@
+//! 音声からテンポを推定する。
+
+/// 解析に使う音声の長さ（秒）。
+pub(crate) const ANALYSIS_SECONDS: f64 = 30.0;
+
+/// オンセット強度を計算するときのホップ幅（サンプル数）。
+const HOP_SIZE: usize = 128;
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 200.0;
+
+/// 推定したテンポ。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct TempoEstimate {
+    /// BPM。
+    pub bpm: f64,
+    /// 音声の先頭から最初の小節の頭までの時間（秒）。
+    pub downbeat_offset: f64,
+}
+
+/// 自己相関を使ってテンポと小節の頭の位置を推定する。
+///
+/// `progress`には0.0から1.0までの進捗が渡されます。
+pub(crate) fn estimate_tempo(
+    samples: &[f32],
+    sample_rate: u32,
+    beats_per_bar: usize,
+    mut progress: impl FnMut(f32),
+) -> anyhow::Result<TempoEstimate> {
+    let onsets = onset_strength(samples);
+    progress(0.2);
+
+    let frame_rate = sample_rate as f64 / HOP_SIZE as f64;
+    let min_lag = (frame_rate * 60.0 / MAX_BPM).floor().max(1.0) as usize;
+    let max_lag = (frame_rate * 60.0 / MIN_BPM).ceil() as usize;
+    if onsets.len() < max_lag * 4 {
+        anyhow::bail!("Audio is too short to estimate tempo");
+    }
+
+    let mean = onsets.iter().sum::<f64>() / onsets.len() as f64;
+    let centered: Vec<f64> = onsets.iter().map(|&v| v - mean).collect();
+    // 2倍・3倍…の周期でも精度を上げるため、探索範囲より長いラグまで計算しておく
+    let max_acf_lag = (centered.len() / 3).min(max_lag * 8);
+    let mut acf = vec![0.0; max_acf_lag + 2];
+    for (lag, value) in acf.iter_mut().enumerate() {
+        *value = autocorrelation(&centered, lag);
+        if lag % 64 == 0 {
+            progress(0.2 + 0.7 * (lag as f32 / max_acf_lag as f32));
+        }
+    }
+
+    // クリックの位置がフレームの境界をまたぐとピークが隣のラグに分散するので、前後のラグも合わせて比べる
+    let smoothed = |lag: usize| acf[lag - 1] + acf[lag] + acf[lag + 1];
+    let peak_near = |lag: usize| {
+        (lag - 1..=lag + 1)
+            .max_by(|&a, &b| smoothed(a).total_cmp(&smoothed(b)))
+            .expect("range is not empty")
+    };
+    let mut coarse_lag = (min_lag..=max_lag)
+        .max_by(|&a, &b| smoothed(a).total_cmp(&smoothed(b)))
+        .expect("lag range is not empty");
+    // 倍の周期のピークが同じくらい強くなることがあるので、半分の周期にも十分なピークがあればそちらを選ぶ
+    while coarse_lag / 2 > min_lag {
+        let half = peak_near(coarse_lag / 2);
+        if smoothed(half) < smoothed(coarse_lag) * 0.5 {
+            break;
+        }
+        coarse_lag = half;
+    }
+    let coarse_lag = (coarse_lag - 1..=coarse_lag + 1)
+        .max_by(|&a, &b| acf[a].total_cmp(&acf[b]))
+        .expect("range is not empty");
+    if acf[coarse_lag] <= 0.0 {
+        anyhow::bail!("No periodic onsets were found");
+    }
+    let period = refine_period(&acf, coarse_lag);
+    progress(0.95);
+
+    let beat_phase = best_phase(&onsets, period);
+    let bar_phase = (0..beats_per_bar.max(1))
+        .map(|beat| beat_phase + beat as f64 * period)
+        .max_by(|&a, &b| {
+            comb_sum(&onsets, a, period * beats_per_bar as f64).total_cmp(&comb_sum(
+                &onsets,
+                b,
+                period * beats_per_bar as f64,
+            ))
+        })
+        .expect("beats_per_bar is not zero");
+    progress(1.0);
+
+    Ok(TempoEstimate {
+        bpm: 60.0 * frame_rate / period,
+        downbeat_offset: bar_phase / frame_rate,
+    })
+}
+
+/// フレームごとのRMSの増加量をオンセット強度として返す。
+fn onset_strength(samples: &[f32]) -> Vec<f64> {
+    let rms: Vec<f64> = samples
+        .chunks(HOP_SIZE)
+        .map(|chunk| {
+            (chunk.iter().map(|&v| (v as f64).powi(2)).sum::<f64>() / chunk.len() as f64).sqrt()
+        })
+        .collect();
+    std::iter::once(0.0)
+        .chain(rms.windows(2).map(|w| (w[1] - w[0]).max(0.0)))
+        .collect()
+}
+
+fn autocorrelation(signal: &[f64], lag: usize) -> f64 {
+    signal
+        .iter()
+        .zip(signal.iter().skip(lag))
+        .map(|(a, b)| a * b)
+        .sum()
+}
+
+/// 自己相関のピークを放物線補間する。
+fn interpolate_peak(acf: &[f64], lag: usize) -> f64 {
+    if lag == 0 || lag + 1 >= acf.len() {
+        return lag as f64;
+    }
+    let (prev, current, next) = (acf[lag - 1], acf[lag], acf[lag + 1]);
+    let denominator = prev - 2.0 * current + next;
+    if denominator.abs() < f64::EPSILON {
+        return lag as f64;
+    }
+    lag as f64 + 0.5 * (prev - next) / denominator
+}
+
+/// 周期の整数倍の位置にあるピークも使って、周期を精度良く求める。
+fn refine_period(acf: &[f64], coarse_lag: usize) -> f64 {
+    let mut period = interpolate_peak(acf, coarse_lag);
+    let mut weighted_sum = period;
+    let mut weight = 1.0;
+    for multiple in 2.. {
+        let expected = (period * multiple as f64).round() as usize;
+        if expected + 3 >= acf.len() {
+            break;
+        }
+        let peak = (expected - 2..=expected + 2)
+            .max_by(|&a, &b| acf[a].total_cmp(&acf[b]))
+            .expect("range is not empty");
+        // 最小二乗法で 位置 = 倍数 * 周期 に当てはめる
+        weighted_sum += interpolate_peak(acf, peak) * multiple as f64;
+        weight += (multiple * multiple) as f64;
+        period = weighted_sum / weight;
+    }
+    period
+}
+
+/// `phase + n * interval`の位置のオンセット強度の合計。
+fn comb_sum(onsets: &[f64], phase: f64, interval: f64) -> f64 {
+    let mut sum = 0.0;
+    let mut position = phase;
+    while position + 1.0 < onsets.len() as f64 {
+        let index = position.floor() as usize;
+        let fraction = position - index as f64;
+        sum += onsets[index] * (1.0 - fraction) + onsets[index + 1] * fraction;
+        position += interval;
+    }
+    sum
+}
+
+/// `[0, period)`の中で`comb_sum`が最大になる位相を、1/4フレーム単位で探す。
+fn best_phase(onsets: &[f64], period: f64) -> f64 {
+    let steps = (period * 4.0).ceil() as usize;
+    (0..steps)
+        .map(|step| step as f64 / 4.0)
+        .max_by(|&a, &b| comb_sum(onsets, a, period).total_cmp(&comb_sum(onsets, b, period)))
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// 1拍目を強くしたクリック音を合成する。
+    ///
+    /// `first_beat`は最初のクリックが小節の何拍目（0始まり）か。
+    fn click_track(bpm: f64, offset: f64, beats_per_bar: usize, first_beat: usize) -> Vec<f32> {
+        let length = (ANALYSIS_SECONDS * SAMPLE_RATE as f64) as usize;
+        let click_length = (0.02 * SAMPLE_RATE as f64) as usize;
+        let mut samples = vec![0.0f32; length];
+        let mut beat = 0;
+        loop {
+            let start = ((offset + beat as f64 * 60.0 / bpm) * SAMPLE_RATE as f64).round() as usize;
+            if start >= length {
+                break;
+            }
+            let amplitude = if (beat + first_beat) % beats_per_bar == 0 {
+                1.0
+            } else {
+                0.5
+            };
+            for (i, sample) in samples[start..].iter_mut().take(click_length).enumerate() {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let decay = 1.0 - i as f32 / click_length as f32;
+                *sample = amplitude * decay * (t * 1000.0 * std::f32::consts::TAU).sin();
+            }
+            beat += 1;
+        }
+        samples
+    }
+
+    #[test]
+    fn test_estimate_bpm_of_click_tracks() {
+        for bpm in [72.0, 90.0, 120.0, 128.0, 145.5, 174.0] {
+            let samples = click_track(bpm, 0.0, 4, 0);
+            let estimate = estimate_tempo(&samples, SAMPLE_RATE, 4, |_| {}).unwrap();
+            assert!(
+                (estimate.bpm - bpm).abs() <= 0.5,
+                "expected {bpm}, got {}",
+                estimate.bpm
+            );
+        }
+    }
+
+    #[test]
+    fn test_estimate_downbeat_offset() {
+        let bpm = 120.0;
+        // 2拍目から始まる音声：小節の頭は 0.25 + 0.5 * 3 秒
+        let samples = click_track(bpm, 0.25, 4, 1);
+        let estimate = estimate_tempo(&samples, SAMPLE_RATE, 4, |_| {}).unwrap();
+        assert!((estimate.bpm - bpm).abs() <= 0.5);
+        assert!(
+            (estimate.downbeat_offset - 1.75).abs() < 0.01,
+            "got {}",
+            estimate.downbeat_offset
+        );
+    }
+
+    #[test]
+    fn test_estimate_silence_fails() {
+        let samples = vec![0.0; SAMPLE_RATE as usize * 10];
+        assert!(estimate_tempo(&samples, SAMPLE_RATE, 4, |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_progress_reaches_one() {
+        let samples = click_track(100.0, 0.0, 4, 0);
+        let mut last = 0.0;
+        estimate_tempo(&samples, SAMPLE_RATE, 4, |p| {
+            assert!(p >= last);
+            last = p;
+        })
+        .unwrap();
+        assert_eq!(last, 1.0);
+    }
+}
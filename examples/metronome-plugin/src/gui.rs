@@ -1,13 +1,20 @@
 use aviutl2::config::translate as tr;
 use aviutl2_eframe::{AviUtl2EframeHandle, eframe, egui};
-use std::{collections::VecDeque, time::Instant};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 const MAX_TAP_INTERVAL_SECS: f64 = 3.0;
 const MAX_INTERVALS: usize = 8;
 
 static CURRENT_BPM: std::sync::Mutex<f64> = std::sync::Mutex::new(0.0);
 pub(crate) fn update_current_bpm() {
-    if let Some(bpm) = get_current_bpm_from_host()
+    set_current_bpm(get_current_bpm_from_host());
+}
+pub(crate) fn set_current_bpm(bpm: Option<f64>) {
+    if let Some(bpm) = bpm
         && let Ok(mut lock) = CURRENT_BPM.lock()
     {
         *lock = bpm;
@@ -22,6 +29,7 @@ pub(crate) struct MetronomeApp {
     bpm_text_input: String,
     header_collapsed: bool,
     state: State,
+    analysis: Arc<Mutex<AnalysisStatus>>,
 }
 
 enum State {
@@ -36,12 +44,37 @@ enum State {
     },
 }
 
+/// 音声からのBPM検出の状態。
+#[derive(Debug, Clone)]
+enum AnalysisStatus {
+    Idle,
+    Running { stage: &'static str, progress: f32 },
+    Done { bpm: f64 },
+    Failed(String),
+}
+
+/// BPM検出の対象になるオブジェクトの情報。
+#[derive(Debug, Clone)]
+struct AnalysisTarget {
+    path: std::path::PathBuf,
+    /// オブジェクトの開始位置（秒）。
+    start_time: f64,
+    /// 音声ファイルの再生位置（秒）。
+    playback_position: f64,
+    beats_per_bar: i32,
+}
+
 impl MetronomeApp {
     pub(crate) fn new(cc: &eframe::CreationContext<'_>, handle: AviUtl2EframeHandle) -> Self {
         let header_collapsed = cc
             .egui_ctx
             .data_mut(|data| data.get_persisted::<bool>(egui::Id::new("header_collapsed")))
             .unwrap_or(false);
+        crate::playback::set_sync_with_playback(
+            cc.egui_ctx
+                .data_mut(|data| data.get_persisted::<bool>(egui::Id::new("sync_with_playback")))
+                .unwrap_or(false),
+        );
         let fonts = aviutl2_eframe::aviutl2_fonts();
 
         cc.egui_ctx.all_styles_mut(|style| {
@@ -57,6 +90,7 @@ impl MetronomeApp {
             bpm_text_input: String::new(),
             state: State::Idle,
             header_collapsed,
+            analysis: Arc::new(Mutex::new(AnalysisStatus::Idle)),
         }
     }
 
@@ -83,6 +117,10 @@ impl eframe::App for MetronomeApp {
         self.render_info_window(ui);
         ui.data_mut(|data| {
             data.insert_persisted(egui::Id::new("header_collapsed"), self.header_collapsed);
+            data.insert_persisted(
+                egui::Id::new("sync_with_playback"),
+                crate::playback::sync_with_playback(),
+            );
         });
     }
 }
@@ -303,10 +341,119 @@ impl MetronomeApp {
                         self.add_bpm_at_current_position();
                     }
                 });
+                ui.add_space(8.0);
+                self.render_playback_sync(ui);
+                ui.add_space(8.0);
+                self.render_analysis(ui);
             });
         });
     }
 
+    fn render_playback_sync(&mut self, ui: &mut egui::Ui) {
+        let mut sync = crate::playback::sync_with_playback();
+        if ui
+            .checkbox(&mut sync, tr("再生に合わせてクリック"))
+            .on_hover_text(tr(concat!(
+                "プレビュー再生中のみクリック音を鳴らします。",
+                "出力時には鳴らしません。"
+            )))
+            .changed()
+        {
+            crate::playback::set_sync_with_playback(sync);
+        }
+        if !sync {
+            return;
+        }
+        let position = crate::metronome::get_timeline_snapshot().and_then(|timeline| {
+            crate::playback::current_beat(&crate::EDIT_HANDLE, timeline.fps, &timeline.bpm_grids)
+        });
+        match position {
+            Some(position) => {
+                ui.label(
+                    tr("{bar}小節 {beat}拍")
+                        .replace("{bar}", &position.bar.to_string())
+                        .replace("{beat}", &position.beat.to_string()),
+                );
+                ui.request_repaint_after(std::time::Duration::from_millis(50));
+            }
+            None => {
+                ui.label(tr("停止中"));
+            }
+        }
+    }
+
+    fn render_analysis(&mut self, ui: &mut egui::Ui) {
+        let status = self
+            .analysis
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or(AnalysisStatus::Idle);
+        ui.columns_const(|[ui]| {
+            if ui
+                .add_enabled(
+                    !matches!(status, AnalysisStatus::Running { .. }),
+                    egui::Button::new(tr("選択中の音声からBPMを検出")),
+                )
+                .on_hover_text(tr(concat!(
+                    "選択中のオブジェクトのWAVファイルの先頭30秒からBPMを検出し、",
+                    "オブジェクトの開始位置にテンポを追加します。"
+                )))
+                .clicked()
+            {
+                self.start_analysis(ui.ctx().clone());
+            }
+        });
+        match status {
+            AnalysisStatus::Idle => {}
+            AnalysisStatus::Running { stage, progress } => {
+                ui.add(egui::ProgressBar::new(progress).text(tr(stage)));
+            }
+            AnalysisStatus::Done { bpm } => {
+                ui.label(tr("検出したBPM: {bpm}").replace("{bpm}", &format!("{bpm:.2}")));
+            }
+            AnalysisStatus::Failed(error) => {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        }
+    }
+
+    fn start_analysis(&self, ctx: egui::Context) {
+        let status = Arc::clone(&self.analysis);
+        let set_status = move |new_status: AnalysisStatus| {
+            if let Ok(mut status) = status.lock() {
+                *status = new_status;
+            }
+            ctx.request_repaint();
+        };
+        set_status(AnalysisStatus::Running {
+            stage: "読み込み中",
+            progress: 0.0,
+        });
+        std::thread::spawn(move || {
+            let result = detect_and_apply_bpm(|stage, progress| {
+                set_status(AnalysisStatus::Running { stage, progress })
+            });
+            match result {
+                Ok(bpm) => {
+                    tracing::info!("Detected BPM: {}", bpm);
+                    aviutl2::utils::notify::toast(
+                        &tr("BPMの検出が完了しました"),
+                        &format!("{bpm:.2} BPM"),
+                    );
+                    set_status(AnalysisStatus::Done { bpm });
+                }
+                Err(error) => {
+                    tracing::error!("Failed to detect BPM: {:?}", error);
+                    aviutl2::utils::notify::toast(
+                        &tr("BPMの検出に失敗しました"),
+                        &error.to_string(),
+                    );
+                    set_status(AnalysisStatus::Failed(error.to_string()));
+                }
+            }
+        });
+    }
+
     fn render_info_window(&mut self, ctx: &egui::Context) {
         if !self.show_info {
             return;
@@ -495,6 +642,80 @@ impl MetronomeApp {
     }
 }
 
+fn read_analysis_target(edit: &aviutl2::generic::EditSection) -> anyhow::Result<AnalysisTarget> {
+    let object = edit
+        .get_focused_object()?
+        .ok_or_else(|| anyhow::anyhow!("No object is focused"))?;
+    let object = edit.object(object);
+    let path = object
+        .get_source_path()?
+        .ok_or_else(|| anyhow::anyhow!("The focused object does not refer to a media file"))?;
+    let fps = edit.info.fps;
+    let start_time =
+        object.get_layer_frame()?.start as f64 * *fps.denom() as f64 / *fps.numer() as f64;
+    let playback_position = object
+        .get_effect_item("音声ファイル", 0, "再生位置")
+        .ok()
+        .and_then(|value| value.parse::<f64>().ok())
+        .unwrap_or(0.0);
+    let mut bpm_infos = edit.get_grid_bpm_list()?;
+    bpm_infos.sort_by(|a, b| a.start.total_cmp(&b.start));
+    let index = bpm_infos.partition_point(|bpm| bpm.start <= start_time);
+    let beats_per_bar = index
+        .checked_sub(1)
+        .map_or(4, |index| bpm_infos[index].beat)
+        .max(1);
+    Ok(AnalysisTarget {
+        path,
+        start_time,
+        playback_position,
+        beats_per_bar,
+    })
+}
+
+/// 選択中のオブジェクトの音声からBPMを検出し、オブジェクトの開始位置にテンポを追加する。
+fn detect_and_apply_bpm(mut report: impl FnMut(&'static str, f32)) -> anyhow::Result<f64> {
+    let target = crate::EDIT_HANDLE.call_edit_section(|edit| read_analysis_target(edit))??;
+    if !target
+        .path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"))
+    {
+        anyhow::bail!("Only WAV files are supported: {}", target.path.display());
+    }
+    let (samples, sample_rate) =
+        crate::wav::load_wav_mono_excerpt(&target.path, crate::bpm_detect::ANALYSIS_SECONDS)?;
+    let estimate = crate::bpm_detect::estimate_tempo(
+        &samples,
+        sample_rate,
+        target.beats_per_bar as usize,
+        |progress| report("解析中", progress),
+    )?;
+
+    let bar_length = 60.0 / estimate.bpm * target.beats_per_bar as f64;
+    let offset = (estimate.downbeat_offset - target.playback_position).rem_euclid(bar_length);
+    crate::EDIT_HANDLE.call_edit_section(move |edit| {
+        let mut bpm_infos = edit.get_grid_bpm_list()?;
+        let new_bpm_info = aviutl2::generic::BpmInfo {
+            tempo: estimate.bpm as f32,
+            beat: target.beats_per_bar,
+            start: target.start_time,
+            offset: offset as f32,
+        };
+        if let Some(existing) = bpm_infos
+            .iter_mut()
+            .find(|bpm| (bpm.start - target.start_time).abs() < 1e-6)
+        {
+            *existing = new_bpm_info;
+        } else {
+            bpm_infos.push(new_bpm_info);
+        }
+        bpm_infos.sort_by(|a, b| a.start.total_cmp(&b.start));
+        edit.set_grid_bpm_list(&bpm_infos)
+    })??;
+    Ok(estimate.bpm)
+}
+
 fn get_current_bpm_from_host() -> Option<f64> {
     let info = crate::EDIT_HANDLE.get_edit_info();
     let current_time = info.frame as f64 * *info.fps.denom() as f64 / *info.fps.numer() as f64;
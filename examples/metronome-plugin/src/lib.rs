@@ -1,7 +1,9 @@
 use aviutl2::AnyResult;
 
+mod bpm_detect;
 mod gui;
 mod metronome;
+mod playback;
 mod wav;
 
 pub static EDIT_HANDLE: aviutl2::generic::GlobalEditHandle =
@@ -11,6 +13,7 @@ pub static EDIT_HANDLE: aviutl2::generic::GlobalEditHandle =
 pub struct MetronomePlugin {
     window: aviutl2_eframe::EframeWindow,
     metronome: aviutl2::generic::SubPlugin<crate::metronome::MetronomeFilter>,
+    timeline_poller: Option<aviutl2::generic::PollerGuard>,
 }
 
 impl aviutl2::generic::GenericPlugin for MetronomePlugin {
@@ -25,6 +28,7 @@ impl aviutl2::generic::GenericPlugin for MetronomePlugin {
         Ok(Self {
             window,
             metronome: aviutl2::generic::SubPlugin::new_filter_plugin(&info)?,
+            timeline_poller: None,
         })
     }
 
@@ -47,6 +51,19 @@ impl aviutl2::generic::GenericPlugin for MetronomePlugin {
         }
         let edit_handle = registry.create_edit_handle();
         EDIT_HANDLE.init(edit_handle);
+        self.timeline_poller = Some(EDIT_HANDLE.spawn_poller(
+            std::time::Duration::from_millis(100),
+            crate::metronome::poll_timeline,
+            crate::metronome::update_timeline,
+        ));
+    }
+
+    fn on_project_load(&mut self, project: &mut aviutl2::generic::ProjectFile) {
+        crate::wav::load_samples_from_project(project);
+    }
+
+    fn on_project_save(&mut self, project: &mut aviutl2::generic::ProjectFile) {
+        crate::wav::save_samples_to_project(project);
     }
 
     fn on_clear_cache(&mut self, _edit_section: &aviutl2::generic::EditSection) {
@@ -70,14 +87,18 @@ impl aviutl2::generic::GenericPlugin for MetronomePlugin {
 
 impl MetronomePlugin {
     fn init_logging() {
-        aviutl2::tracing_subscriber::fmt()
-            .with_max_level(if cfg!(debug_assertions) {
-                tracing::Level::DEBUG
+        use aviutl2::tracing_subscriber::prelude::*;
+        aviutl2::tracing_subscriber::registry()
+            .with(if cfg!(debug_assertions) {
+                aviutl2::tracing_subscriber::filter::LevelFilter::DEBUG
             } else {
-                tracing::Level::INFO
+                aviutl2::tracing_subscriber::filter::LevelFilter::INFO
             })
-            .event_format(aviutl2::logger::AviUtl2Formatter)
-            .with_writer(aviutl2::logger::AviUtl2LogWriter)
+            .with(
+                // 1ms以上かかった処理の所要時間を出力する
+                aviutl2::logger::AviUtl2Layer::new()
+                    .with_threshold(std::time::Duration::from_millis(1)),
+            )
             .init();
     }
 }
@@ -20,6 +20,60 @@ pub struct MetronomeFilterConfig {
     sample_b: Option<std::path::PathBuf>,
 }
 
+/// ホストから定期的に取得したタイムラインの情報。
+#[derive(Debug, Clone)]
+pub(crate) struct TimelineSnapshot {
+    /// フレームレート。
+    pub fps: aviutl2::Rational32,
+    /// 現在のカーソル位置（秒）。
+    pub time: f64,
+    /// 開始位置順に並べたBPMグリッド。
+    pub bpm_grids: Vec<aviutl2::generic::BpmInfo>,
+}
+
+static TIMELINE: std::sync::RwLock<Option<TimelineSnapshot>> = std::sync::RwLock::new(None);
+
+#[aviutl2::logger::instrument(level = "debug", skip_all)]
+pub(crate) fn poll_timeline(edit: &aviutl2::generic::EditSection) -> Option<TimelineSnapshot> {
+    let mut bpm_grids = edit.get_grid_bpm_list().ok()?;
+    bpm_grids.sort_by(|a, b| a.start.total_cmp(&b.start));
+    let fps = edit.info.fps;
+    Some(TimelineSnapshot {
+        fps,
+        time: edit.info.frame as f64 * *fps.denom() as f64 / *fps.numer() as f64,
+        bpm_grids,
+    })
+}
+
+pub(crate) fn update_timeline(snapshot: TimelineSnapshot) {
+    crate::gui::set_current_bpm(
+        get_bpm_grid_at(&snapshot.bpm_grids, snapshot.time).map(|grid| grid.tempo as f64),
+    );
+    if let Ok(mut timeline) = TIMELINE.write() {
+        *timeline = Some(snapshot);
+    }
+}
+
+/// 最後にポーリングしたタイムラインの情報を返す。
+pub(crate) fn get_timeline_snapshot() -> Option<TimelineSnapshot> {
+    TIMELINE.read().ok().and_then(|timeline| timeline.clone())
+}
+
+fn get_timeline() -> AnyResult<TimelineSnapshot> {
+    if let Some(snapshot) = get_timeline_snapshot() {
+        return Ok(snapshot);
+    }
+    // まだ一度もポーリングされていない場合は直接取得する
+    let mut bpm_grids = crate::EDIT_HANDLE.call_read_section(|read| read.get_grid_bpm_list())??;
+    bpm_grids.sort_by(|a, b| a.start.total_cmp(&b.start));
+    let info = crate::EDIT_HANDLE.get_edit_info();
+    Ok(TimelineSnapshot {
+        fps: info.fps,
+        time: info.frame as f64 * *info.fps.denom() as f64 / *info.fps.numer() as f64,
+        bpm_grids,
+    })
+}
+
 #[aviutl2::plugin(FilterPlugin)]
 pub struct MetronomeFilter;
 
@@ -32,6 +86,7 @@ impl aviutl2::filter::FilterPlugin for MetronomeFilter {
         aviutl2::filter::FilterPluginTable {
             name: "Rusty Metronome Filter".to_string(),
             label: None,
+            order: 0,
             information: format!(
                 "Metronome effect, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/metronome-plugin",
                 version = env!("CARGO_PKG_VERSION")
@@ -44,11 +99,18 @@ impl aviutl2::filter::FilterPlugin for MetronomeFilter {
         }
     }
 
+    #[aviutl2::logger::instrument(level = "debug", skip_all, fields(samples = audio.audio_object.sample_num))]
     fn proc_audio(
         &self,
         config: &[aviutl2::filter::FilterConfigItem],
         audio: &mut aviutl2::filter::FilterProcAudio,
     ) -> anyhow::Result<()> {
+        if !crate::playback::should_click(
+            &crate::EDIT_HANDLE,
+            crate::playback::sync_with_playback(),
+        ) {
+            return Ok(());
+        }
         let config: MetronomeFilterConfig = config.to_struct();
         let sample_rate = audio.scene.sample_rate;
         let sample_a = config
@@ -59,22 +121,19 @@ impl aviutl2::filter::FilterPlugin for MetronomeFilter {
             .sample_b
             .as_deref()
             .and_then(|path| crate::wav::get_wav_sample(path, sample_rate));
-        let mut bpm_grids =
-            crate::EDIT_HANDLE.call_read_section(|read| read.get_grid_bpm_list())??;
+        let TimelineSnapshot { fps, bpm_grids, .. } = get_timeline()?;
         if bpm_grids.is_empty() {
             return Ok(());
         }
-        bpm_grids.sort_by(|a, b| a.start.total_cmp(&b.start));
 
-        let info = crate::EDIT_HANDLE.get_edit_info();
         let object_start_time =
-            audio.object.frame_s as f64 * *info.fps.denom() as f64 / *info.fps.numer() as f64;
+            audio.object.frame_s as f64 * *fps.denom() as f64 / *fps.numer() as f64;
         let object_start_sample_index = (object_start_time * sample_rate as f64).round() as u64;
         tracing::debug!(
             "frame_s: {}, fps: {}/{} => time_s: {}, bpm_grid_count: {}",
             audio.object.frame_s,
-            info.fps.numer(),
-            info.fps.denom(),
+            fps.numer(),
+            fps.denom(),
             object_start_time,
             bpm_grids.len(),
         );
@@ -124,13 +183,22 @@ impl aviutl2::filter::FilterPlugin for MetronomeFilter {
             }
         }
 
-        audio.set_sample_data(aviutl2::filter::AudioChannel::Left, &lbuf);
-        audio.set_sample_data(aviutl2::filter::AudioChannel::Right, &rbuf);
+        if audio.channel_count() >= 2 {
+            audio.set_sample_data(aviutl2::filter::AudioChannel::Left, &lbuf)?;
+            audio.set_sample_data(aviutl2::filter::AudioChannel::Right, &rbuf)?;
+        } else {
+            let mono = lbuf
+                .iter()
+                .zip(rbuf.iter())
+                .map(|(l, r)| 0.5 * (l + r))
+                .collect::<Vec<_>>();
+            audio.set_sample_data(aviutl2::filter::AudioChannel::Left, &mono)?;
+        }
 
         Ok(())
     }
 }
-fn get_bpm_grid_at(
+pub(crate) fn get_bpm_grid_at(
     bpm_grids: &[aviutl2::generic::BpmInfo],
     time: f64,
 ) -> Option<aviutl2::generic::BpmInfo> {
@@ -0,0 +1,139 @@
+use aviutl2::generic::Transport;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SYNC_WITH_PLAYBACK: AtomicBool = AtomicBool::new(false);
+
+/// 「再生に合わせてクリック」が有効かどうか。
+pub(crate) fn sync_with_playback() -> bool {
+    SYNC_WITH_PLAYBACK.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_sync_with_playback(enabled: bool) {
+    SYNC_WITH_PLAYBACK.store(enabled, Ordering::Relaxed);
+}
+
+/// クリック音を鳴らすかどうか。
+///
+/// 再生に合わせる場合はプレビュー再生中のみ鳴らし、出力時やシーク時には鳴らさない。
+pub(crate) fn should_click(transport: &impl Transport, sync: bool) -> bool {
+    !sync || transport.is_playing()
+}
+
+/// 拍の位置。どちらも1始まり。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BeatPosition {
+    pub bar: i64,
+    pub beat: i64,
+}
+
+/// 再生中の場合、現在のフレームが何小節目の何拍目かを返す。
+pub(crate) fn current_beat(
+    transport: &impl Transport,
+    fps: aviutl2::Rational32,
+    bpm_grids: &[aviutl2::generic::BpmInfo],
+) -> Option<BeatPosition> {
+    if !transport.is_playing() {
+        return None;
+    }
+    let time = transport.current_frame() as f64 * *fps.denom() as f64 / *fps.numer() as f64;
+    let grid = crate::metronome::get_bpm_grid_at(bpm_grids, time)?;
+    if !(grid.tempo.is_finite() && grid.tempo > 0.0) || grid.beat <= 0 {
+        return None;
+    }
+    let seconds_per_beat = 60.0 / f64::from(grid.tempo);
+    let beat_count =
+        ((time - grid.start - f64::from(grid.offset)) / seconds_per_beat).floor() as i64;
+    let beats_per_bar = i64::from(grid.beat);
+    Some(BeatPosition {
+        bar: beat_count.div_euclid(beats_per_bar) + 1,
+        beat: beat_count.rem_euclid(beats_per_bar) + 1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[derive(Default)]
+    struct MockTransport {
+        playing: Cell<bool>,
+        frame: Cell<u32>,
+    }
+
+    impl Transport for MockTransport {
+        fn is_playing(&self) -> bool {
+            self.playing.get()
+        }
+
+        fn current_frame(&self) -> u32 {
+            self.frame.get()
+        }
+
+        fn seek_frame(&self, frame: u32) -> Result<(), aviutl2::generic::EditHandleError> {
+            self.frame.set(frame);
+            Ok(())
+        }
+    }
+
+    fn grids() -> Vec<aviutl2::generic::BpmInfo> {
+        vec![aviutl2::generic::BpmInfo {
+            tempo: 120.0,
+            beat: 4,
+            start: 0.0,
+            offset: 0.0,
+        }]
+    }
+
+    #[test]
+    fn clicks_only_while_playing_when_synced() {
+        let transport = MockTransport::default();
+        assert!(should_click(&transport, false));
+        assert!(!should_click(&transport, true));
+        transport.playing.set(true);
+        assert!(should_click(&transport, true));
+    }
+
+    #[test]
+    fn no_beat_while_stopped() {
+        let transport = MockTransport::default();
+        transport.seek_frame(30).unwrap();
+        assert_eq!(
+            current_beat(&transport, aviutl2::Rational32::new(30, 1), &grids()),
+            None
+        );
+    }
+
+    #[test]
+    fn follows_current_frame() {
+        let transport = MockTransport::default();
+        transport.playing.set(true);
+        let fps = aviutl2::Rational32::new(30, 1);
+
+        // 120BPMなので1拍は15フレーム
+        for (frame, bar, beat) in [(0, 1, 1), (14, 1, 1), (15, 1, 2), (45, 1, 4), (60, 2, 1)] {
+            transport.seek_frame(frame).unwrap();
+            assert_eq!(
+                current_beat(&transport, fps, &grids()),
+                Some(BeatPosition { bar, beat }),
+                "frame {frame}"
+            );
+        }
+    }
+
+    #[test]
+    fn beats_before_offset() {
+        let transport = MockTransport::default();
+        transport.playing.set(true);
+        let grids = vec![aviutl2::generic::BpmInfo {
+            tempo: 120.0,
+            beat: 4,
+            start: 0.0,
+            offset: 1.0,
+        }];
+        assert_eq!(
+            current_beat(&transport, aviutl2::Rational32::new(30, 1), &grids),
+            Some(BeatPosition { bar: 0, beat: 3 })
+        );
+    }
+}
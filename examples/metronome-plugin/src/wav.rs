@@ -28,6 +28,105 @@ pub(crate) fn clear_sample_cache() {
     SAMPLE_CACHE.clear();
 }
 
+static SAMPLE_BLOB_PREFIX: &str = "sample:";
+
+fn sample_blob_key(key: &SampleCacheKey) -> String {
+    format!(
+        "{SAMPLE_BLOB_PREFIX}{}:{}",
+        key.sample_rate,
+        key.path.to_string_lossy()
+    )
+}
+
+fn parse_sample_blob_key(blob_key: &str) -> Option<SampleCacheKey> {
+    let (sample_rate, path) = blob_key.strip_prefix(SAMPLE_BLOB_PREFIX)?.split_once(':')?;
+    Some(SampleCacheKey {
+        path: PathBuf::from(path),
+        sample_rate: sample_rate.parse().ok()?,
+    })
+}
+
+/// 読み込んだサンプルをプロジェクトに保存する。
+///
+/// WAVファイルが移動・削除されても、プロジェクトを開き直したときに同じ音で鳴らせるようにする。
+pub(crate) fn save_samples_to_project(project: &mut aviutl2::generic::ProjectFile) {
+    let mut saved = std::collections::HashSet::new();
+    for entry in SAMPLE_CACHE.iter() {
+        let Some(sample) = entry.value() else {
+            continue;
+        };
+        let blob_key = sample_blob_key(entry.key());
+        if let Err(err) = project.write_blob_with(
+            &blob_key,
+            &encode_sample(sample),
+            aviutl2::generic::BlobCompression::Zstd,
+        ) {
+            tracing::warn!(
+                "Failed to save sample {:?} to project: {}",
+                entry.key().path,
+                err
+            );
+            continue;
+        }
+        saved.insert(blob_key);
+    }
+    for blob_key in project.blob_keys() {
+        if blob_key.starts_with(SAMPLE_BLOB_PREFIX)
+            && !saved.contains(&blob_key)
+            && let Err(err) = project.remove_blob(&blob_key)
+        {
+            tracing::warn!("Failed to remove stale sample {blob_key:?}: {err}");
+        }
+    }
+}
+
+/// プロジェクトに保存されたサンプルを読み込む。
+pub(crate) fn load_samples_from_project(project: &aviutl2::generic::ProjectFile) {
+    for blob_key in project.blob_keys() {
+        let Some(key) = parse_sample_blob_key(&blob_key) else {
+            continue;
+        };
+        let sample = match project.read_blob(&blob_key) {
+            Ok(Some(bytes)) => decode_sample(&bytes),
+            Ok(None) => None,
+            Err(err) => {
+                tracing::warn!("Failed to load sample {blob_key:?} from project: {err}");
+                None
+            }
+        };
+        if let Some(sample) = sample {
+            SAMPLE_CACHE
+                .entry(key)
+                .or_insert_with(|| Some(Arc::new(sample)));
+        }
+    }
+}
+
+/// サンプル数（u32）、左チャンネル、右チャンネルの順にリトルエンディアンで並べる。
+fn encode_sample(sample: &SampleData) -> Vec<u8> {
+    let len = sample.len();
+    let mut bytes = Vec::with_capacity(4 + len * 8);
+    bytes.extend_from_slice(&(len as u32).to_le_bytes());
+    for value in sample.left[..len].iter().chain(&sample.right[..len]) {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+fn decode_sample(bytes: &[u8]) -> Option<SampleData> {
+    let (len, rest) = bytes.split_first_chunk::<4>()?;
+    let len = u32::from_le_bytes(*len) as usize;
+    if rest.len() != len * 8 {
+        return None;
+    }
+    let mut values = rest
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()));
+    let left = values.by_ref().take(len).collect();
+    let right = values.collect();
+    Some(SampleData { left, right })
+}
+
 pub(crate) fn get_wav_sample(path: &Path, target_rate: u32) -> Option<Arc<SampleData>> {
     let key = SampleCacheKey {
         path: path.to_path_buf(),
@@ -47,24 +146,44 @@ pub(crate) fn get_wav_sample(path: &Path, target_rate: u32) -> Option<Arc<Sample
 }
 
 fn load_wav_sample(path: &Path, target_rate: u32) -> anyhow::Result<SampleData> {
-    let (left, right, input_rate) = load_wav_channels(path)?;
+    let (left, right, input_rate) = load_wav_channels(path, None)?;
     let left = resample_channel(&left, input_rate, target_rate);
     let right = resample_channel(&right, input_rate, target_rate);
     Ok(SampleData { left, right })
 }
 
-fn load_wav_channels(path: &Path) -> anyhow::Result<(Vec<f32>, Vec<f32>, u32)> {
+/// WAVファイルの先頭`max_seconds`秒をモノラルで読み込む。
+pub(crate) fn load_wav_mono_excerpt(
+    path: &Path,
+    max_seconds: f64,
+) -> anyhow::Result<(Vec<f32>, u32)> {
+    let (left, right, sample_rate) = load_wav_channels(path, Some(max_seconds))?;
+    let mono = left
+        .iter()
+        .zip(right.iter())
+        .map(|(l, r)| (l + r) * 0.5)
+        .collect();
+    Ok((mono, sample_rate))
+}
+
+fn load_wav_channels(
+    path: &Path,
+    max_seconds: Option<f64>,
+) -> anyhow::Result<(Vec<f32>, Vec<f32>, u32)> {
     let mut reader = hound::WavReader::open(path)?;
     let spec = reader.spec();
     let channels = spec.channels as usize;
     if channels == 0 || channels > 2 {
         anyhow::bail!("Unsupported channel count: {}", channels);
     }
+    let max_samples = max_seconds.map_or(usize::MAX, |seconds| {
+        (seconds * spec.sample_rate as f64) as usize * channels
+    });
     let mut left = Vec::new();
     let mut right = Vec::new();
     match spec.sample_format {
         hound::SampleFormat::Float => {
-            for (index, sample) in reader.samples::<f32>().enumerate() {
+            for (index, sample) in reader.samples::<f32>().take(max_samples).enumerate() {
                 let value = sample?;
                 push_sample(&mut left, &mut right, channels, index, value);
             }
@@ -73,13 +192,13 @@ fn load_wav_channels(path: &Path) -> anyhow::Result<(Vec<f32>, Vec<f32>, u32)> {
             let bits = spec.bits_per_sample;
             if bits <= 16 {
                 let max = i16::MAX as f32;
-                for (index, sample) in reader.samples::<i16>().enumerate() {
+                for (index, sample) in reader.samples::<i16>().take(max_samples).enumerate() {
                     let value = sample? as f32 / max;
                     push_sample(&mut left, &mut right, channels, index, value);
                 }
             } else {
                 let max = ((1u64 << (bits - 1)) - 1) as f32;
-                for (index, sample) in reader.samples::<i32>().enumerate() {
+                for (index, sample) in reader.samples::<i32>().take(max_samples).enumerate() {
                     let value = sample? as f32 / max;
                     push_sample(&mut left, &mut right, channels, index, value);
                 }
@@ -147,3 +266,30 @@ fn linear_resample(input: &[f32], output: &mut [f32]) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_round_trip() {
+        let sample = SampleData {
+            left: vec![0.0, 0.5, -1.0],
+            right: vec![1.0, -0.25, 0.125],
+        };
+        let decoded = decode_sample(&encode_sample(&sample)).unwrap();
+        assert_eq!(decoded.left, sample.left);
+        assert_eq!(decoded.right, sample.right);
+        assert!(decode_sample(&encode_sample(&sample)[..10]).is_none());
+    }
+
+    #[test]
+    fn sample_blob_key_round_trip() {
+        let key = SampleCacheKey {
+            path: PathBuf::from(r"C:\sounds\click.wav"),
+            sample_rate: 48000,
+        };
+        assert_eq!(parse_sample_blob_key(&sample_blob_key(&key)), Some(key));
+        assert_eq!(parse_sample_blob_key("other:48000:click.wav"), None);
+    }
+}
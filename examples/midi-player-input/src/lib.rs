@@ -57,8 +57,11 @@ impl InputPlugin for MidiPlayerPlugin {
                 "Midi Piano Player for AviUtl2, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/midi-player-input",
                 version = env!("CARGO_PKG_VERSION")
             ),
-            concurrent: false,
+            concurrency: aviutl2::input::ConcurrencyMode::None,
+            share_handles: false,
+            cache_input_info: false,
             // TODO: sf2の設定を可能にする
+            fail_on_read_error: false,
             can_config: false,
         }
     }
@@ -80,6 +83,28 @@ impl InputPlugin for MidiPlayerPlugin {
         Ok((video_tracks, audio_tracks + 1))
     }
 
+    fn track_info(
+        &self,
+        handle: &mut Self::InputHandle,
+        kind: aviutl2::input::TrackKind,
+        index: u32,
+    ) -> anyhow::Result<aviutl2::input::TrackInfo> {
+        if kind != aviutl2::input::TrackKind::Audio {
+            return Ok(aviutl2::input::TrackInfo::numbered(kind, index));
+        }
+        let name = if index == 0 {
+            "すべてのトラック".to_string()
+        } else {
+            track::track_name(&handle.smf, index - 1)
+                .unwrap_or_else(|| format!("トラック{}", index - 1))
+        };
+        Ok(aviutl2::input::TrackInfo {
+            name,
+            language: None,
+            default: index == 0,
+        })
+    }
+
     fn get_input_info(
         &self,
         handle: &mut Self::InputHandle,
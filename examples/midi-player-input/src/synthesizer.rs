@@ -16,6 +16,7 @@ pub struct Synthesizer {
     pub synthesizer: rustysynth::Synthesizer,
     pub expected_next_sample: u64,
     pub event_index: usize,
+    pub channels: [ChannelState; 16],
 
     pub track: Arc<crate::track::Track>,
 }
@@ -31,12 +32,14 @@ impl Synthesizer {
             synthesizer,
             expected_next_sample: 0,
             event_index: 0,
+            channels: Default::default(),
             track,
         })
     }
 
     pub fn reset(&mut self) {
         self.event_index = 0;
+        self.channels = Default::default();
         self.synthesizer.reset();
     }
     pub fn render(&mut self, length: i32, start_sample: u64, end_sample: u64) -> Vec<(f32, f32)> {
@@ -59,33 +62,32 @@ impl Synthesizer {
         samples
     }
 
-    pub fn apply_notes(
-        &mut self,
-        note_activate_buffer: std::collections::HashMap<u8, Option<crate::track::Note>>,
-    ) {
-        for (&midi, note) in &note_activate_buffer {
+    pub fn apply_notes(&mut self, note_activate_buffer: NoteActivateBuffer) {
+        for ((channel, midi), note) in note_activate_buffer {
             match note {
                 Some(note) => {
                     self.synthesizer.note_on(
-                        0,
+                        channel as i32,
                         note.midi_note as i32,
                         note.velocity as i32, // Normalize velocity
                     );
                 }
                 None => {
-                    self.synthesizer.note_off(0, midi as i32);
+                    self.synthesizer.note_off(channel as i32, midi as i32);
                 }
             }
         }
     }
 
-    pub fn procced_to(
-        &mut self,
-        current_time: f64,
-    ) -> std::collections::HashMap<u8, Option<crate::track::Note>> {
-        let mut note_activate_buffer = std::collections::HashMap::new();
-        while self.event_index < self.track.events.len() {
-            let (event_time, event) = &self.track.events[self.event_index];
+    /// `current_time`までのイベントを処理し、鳴らす・止めるノートを返す。
+    ///
+    /// ピッチベンドやプログラムチェンジなどはその場でシンセサイザーに送る。
+    /// シーク後はまとめて処理されるため、同じサンプル内で鳴って止まったノートは鳴らさない。
+    pub fn procced_to(&mut self, current_time: f64) -> NoteActivateBuffer {
+        let track = Arc::clone(&self.track);
+        let mut note_activate_buffer = NoteActivateBuffer::new();
+        while self.event_index < track.events.len() {
+            let (event_time, event) = &track.events[self.event_index];
             if *event_time > current_time {
                 break; // No more events for this sample
             }
@@ -93,32 +95,218 @@ impl Synthesizer {
             // Process the event
             match event {
                 crate::track::NoteEvent::NoteOn(note) => {
-                    match note_activate_buffer.get(&note.midi_note) {
+                    let key = (note.channel, note.midi_note);
+                    self.channels[note.channel as usize]
+                        .held_notes
+                        .remove(&note.midi_note);
+                    match note_activate_buffer.get(&key) {
                         Some(Some(_)) => {
-                            note_activate_buffer.insert(note.midi_note, None);
+                            note_activate_buffer.insert(key, None);
                         }
                         Some(None) | None => {
-                            note_activate_buffer.insert(note.midi_note, Some(note));
+                            note_activate_buffer.insert(key, Some(note.clone()));
                         }
                     }
                 }
-                crate::track::NoteEvent::NoteOff(midi_note) => {
-                    match note_activate_buffer.get(midi_note) {
-                        Some(Some(_note)) => {
-                            note_activate_buffer.remove(midi_note);
-                        }
-                        None | Some(None) => {
-                            note_activate_buffer.insert(*midi_note, None);
+                crate::track::NoteEvent::NoteOff { channel, key } => {
+                    let state = &mut self.channels[*channel as usize];
+                    if state.sustain {
+                        // ペダルが上がるまでノートオフを保留する
+                        state.held_notes.insert(*key);
+                    } else {
+                        release_note(&mut note_activate_buffer, *channel, *key);
+                    }
+                }
+                crate::track::NoteEvent::Sustain { channel, on } => {
+                    let state = &mut self.channels[*channel as usize];
+                    state.sustain = *on;
+                    if !on {
+                        for key in std::mem::take(&mut state.held_notes) {
+                            release_note(&mut note_activate_buffer, *channel, key);
                         }
                     }
                 }
+                crate::track::NoteEvent::PitchBend { channel, value } => {
+                    // ベンド幅はRPN 0（未指定なら±2半音）に従って、鳴っている音の周波数に掛けられる
+                    self.synthesizer.process_midi_message(
+                        *channel as i32,
+                        0xE0,
+                        (value & 0x7F) as i32,
+                        (value >> 7) as i32,
+                    );
+                }
+                crate::track::NoteEvent::ProgramChange { channel, program } => {
+                    // SoundFontにないプリセットはバンク0の最初のプリセットで代替される
+                    self.synthesizer.process_midi_message(
+                        *channel as i32,
+                        0xC0,
+                        *program as i32,
+                        0,
+                    );
+                }
+                crate::track::NoteEvent::Controller {
+                    channel,
+                    controller,
+                    value,
+                } => {
+                    self.synthesizer.process_midi_message(
+                        *channel as i32,
+                        0xB0,
+                        *controller as i32,
+                        *value as i32,
+                    );
+                }
             }
 
             self.event_index += 1;
         }
         note_activate_buffer
-            .iter()
-            .map(|(&k, &v)| (k, v.cloned()))
-            .collect()
+    }
+}
+
+/// 鳴らす（`Some`）・止める（`None`）ノート。
+///
+/// レンダリング結果が毎回同じになるよう、チャンネルとノート番号の順に処理する。
+pub type NoteActivateBuffer = std::collections::BTreeMap<(u8, u8), Option<crate::track::Note>>;
+
+fn release_note(note_activate_buffer: &mut NoteActivateBuffer, channel: u8, key: u8) {
+    match note_activate_buffer.get(&(channel, key)) {
+        Some(Some(_note)) => {
+            note_activate_buffer.remove(&(channel, key));
+        }
+        None | Some(None) => {
+            note_activate_buffer.insert((channel, key), None);
+        }
+    }
+}
+
+/// チャンネルごとのサステインペダルの状態。
+#[derive(Debug, Clone, Default)]
+pub struct ChannelState {
+    pub sustain: bool,
+    /// ペダルでノートオフが保留されているノート。
+    pub held_notes: std::collections::BTreeSet<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::track::{Note, NoteEvent, Track};
+
+    fn note_on(time: f64, channel: u8, midi_note: u8) -> (f64, NoteEvent) {
+        (
+            time,
+            NoteEvent::NoteOn(Note {
+                channel,
+                midi_note,
+                velocity: 100,
+            }),
+        )
+    }
+
+    fn test_track() -> Arc<Track> {
+        Arc::new(Track {
+            events: vec![
+                (
+                    0.0,
+                    NoteEvent::ProgramChange {
+                        channel: 1,
+                        program: 40,
+                    },
+                ),
+                note_on(0.0, 0, 60),
+                note_on(0.01, 1, 64),
+                (
+                    0.02,
+                    NoteEvent::Sustain {
+                        channel: 0,
+                        on: true,
+                    },
+                ),
+                (
+                    0.05,
+                    NoteEvent::NoteOff {
+                        channel: 0,
+                        key: 60,
+                    },
+                ),
+                (
+                    0.06,
+                    NoteEvent::PitchBend {
+                        channel: 1,
+                        value: 0x3000,
+                    },
+                ),
+                (
+                    0.08,
+                    NoteEvent::Sustain {
+                        channel: 0,
+                        on: false,
+                    },
+                ),
+                (
+                    0.09,
+                    NoteEvent::NoteOff {
+                        channel: 1,
+                        key: 64,
+                    },
+                ),
+            ],
+        })
+    }
+
+    #[test]
+    fn test_sustain_holds_note_off() {
+        let mut synth = Synthesizer::new(test_track()).unwrap();
+        let buffer = synth.procced_to(0.02);
+        assert!(matches!(buffer.get(&(0, 60)), Some(Some(_))));
+        assert!(matches!(buffer.get(&(1, 64)), Some(Some(_))));
+        assert!(synth.channels[0].sustain);
+
+        // ペダルが踏まれている間はノートオフを送らない
+        let buffer = synth.procced_to(0.05);
+        assert!(buffer.is_empty());
+        assert!(synth.channels[0].held_notes.contains(&60));
+
+        // ペダルが上がったらまとめて止める
+        let buffer = synth.procced_to(0.08);
+        assert_eq!(buffer.get(&(0, 60)), Some(&None));
+        assert!(synth.channels[0].held_notes.is_empty());
+    }
+
+    #[test]
+    fn test_sustained_note_survives_seek() {
+        // シーク後にまとめて処理しても、ペダルで伸びているノートは鳴らす
+        let mut synth = Synthesizer::new(test_track()).unwrap();
+        let buffer = synth.procced_to(0.07);
+        assert!(matches!(buffer.get(&(0, 60)), Some(Some(_))));
+
+        synth.reset();
+        let buffer = synth.procced_to(0.1);
+        assert_eq!(buffer.get(&(0, 60)), None);
+        assert_eq!(buffer.get(&(1, 64)), None);
+    }
+
+    #[test]
+    fn test_render_is_independent_of_chunk_size() {
+        let start = 100u64;
+        let length = (SAMPLE_RATE / 10) as u64;
+
+        let mut synth = Synthesizer::new(test_track()).unwrap();
+        let expected = synth.render(length as i32, start, start + length);
+        assert!(expected.iter().any(|&(l, r)| l != 0.0 || r != 0.0));
+
+        let mut synth = Synthesizer::new(test_track()).unwrap();
+        let mut actual = Vec::with_capacity(length as usize);
+        let mut current = start;
+        for chunk in [1u64, 7, 64, 333, 1000].into_iter().cycle() {
+            if current >= start + length {
+                break;
+            }
+            let end = (current + chunk).min(start + length);
+            actual.extend(synth.render((end - current) as i32, current, end));
+            current = end;
+        }
+        assert_eq!(actual, expected);
     }
 }
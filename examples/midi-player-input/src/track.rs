@@ -32,16 +32,51 @@ mod owned_smf {
 
 pub use owned_smf::OwnedSmf;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Note {
+    pub channel: u8,
     pub midi_note: u8,
     pub velocity: u8, // MIDI velocity for amplitude
 }
 
-#[derive(Debug, Clone)]
+/// シンセサイザーにそのまま渡すコントロールチェンジ。
+///
+/// - 0, 32: バンクセレクト
+/// - 6, 38: データエントリー
+/// - 98, 99: NRPN
+/// - 100, 101: RPN（RPN 0でピッチベンドの幅を指定する）
+pub const FORWARDED_CONTROLLERS: [u8; 8] = [0, 6, 32, 38, 98, 99, 100, 101];
+
+/// サステインペダルのコントロールチェンジ番号。
+pub const SUSTAIN_CONTROLLER: u8 = 64;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum NoteEvent {
     NoteOn(Note),
-    NoteOff(u8),
+    NoteOff {
+        channel: u8,
+        key: u8,
+    },
+    /// サステインペダル（CC64）。64以上で踏んだ状態になる。
+    Sustain {
+        channel: u8,
+        on: bool,
+    },
+    /// ピッチベンド。0x2000が中央の14bitの値。
+    PitchBend {
+        channel: u8,
+        value: u16,
+    },
+    ProgramChange {
+        channel: u8,
+        program: u8,
+    },
+    /// [`FORWARDED_CONTROLLERS`]のいずれか。
+    Controller {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -127,6 +162,30 @@ impl TempoIndex {
     }
 }
 
+/// トラックの名前（Sequence/Track Nameメタイベント）を返す。
+pub fn track_name(smf: &OwnedSmf, track: u32) -> Option<String> {
+    let mid = smf.borrow_mid();
+    mid.tracks.get(track as usize)?.iter().find_map(|event| {
+        if let midly::TrackEventKind::Meta(midly::MetaMessage::TrackName(name)) = event.kind {
+            let name = decode_midi_text(name);
+            (!name.trim().is_empty()).then(|| name.trim().to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// MIDIのテキストをデコードする。
+///
+/// MIDIのテキストにはエンコーディングの指定がないため、UTF-8として読めない場合は
+/// 日本語のMIDIで一般的なShift_JIS（CP932）として読み込む。
+pub fn decode_midi_text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::SHIFT_JIS.decode(bytes).0.into_owned(),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Track {
     pub events: Vec<(f64, crate::track::NoteEvent)>,
@@ -149,32 +208,86 @@ impl Track {
             events.push((current_tick, event));
         }
         for (current_tick, event) in events {
-            if let midly::TrackEventKind::Midi { message, .. } = event.kind {
-                if let midly::MidiMessage::NoteOn { key, vel: velocity } = message {
-                    key_state
-                        .entry(key.as_int())
-                        .and_modify(|v| *v += 1)
-                        .or_insert(1);
-                    if key_state[&key.as_int()] == 1 {
-                        let event_time = tempo_index.ticks_to_time(current_tick);
+            let midly::TrackEventKind::Midi { channel, message } = event.kind else {
+                continue;
+            };
+            let channel = channel.as_int();
+            let event_time = tempo_index.ticks_to_time(current_tick);
+            match message {
+                midly::MidiMessage::NoteOn { key, vel } if vel.as_int() > 0 => {
+                    let count = key_state.entry((channel, key.as_int())).or_insert(0);
+                    *count += 1;
+                    if *count == 1 {
                         current_track_events.push((
                             event_time,
                             NoteEvent::NoteOn(Note {
+                                channel,
                                 midi_note: key.as_int(),
-                                velocity: velocity.as_int(),
+                                velocity: vel.as_int(),
                             }),
                         ));
                     }
-                } else if let midly::MidiMessage::NoteOff { key, .. } = message
-                    && *key_state
-                        .entry(key.as_int())
-                        .and_modify(|v| *v -= 1)
-                        .or_insert(0)
-                        == 0
+                }
+                // ベロシティ0のノートオンはノートオフとして扱う
+                midly::MidiMessage::NoteOn { key, .. }
+                | midly::MidiMessage::NoteOff { key, .. } => {
+                    let count = key_state.entry((channel, key.as_int())).or_insert(0);
+                    if *count == 0 {
+                        continue;
+                    }
+                    *count -= 1;
+                    if *count == 0 {
+                        current_track_events.push((
+                            event_time,
+                            NoteEvent::NoteOff {
+                                channel,
+                                key: key.as_int(),
+                            },
+                        ));
+                    }
+                }
+                midly::MidiMessage::Controller { controller, value }
+                    if controller.as_int() == SUSTAIN_CONTROLLER =>
+                {
+                    current_track_events.push((
+                        event_time,
+                        NoteEvent::Sustain {
+                            channel,
+                            on: value.as_int() >= 64,
+                        },
+                    ));
+                }
+                midly::MidiMessage::Controller { controller, value }
+                    if FORWARDED_CONTROLLERS.contains(&controller.as_int()) =>
                 {
-                    let event_time = tempo_index.ticks_to_time(current_tick);
-                    current_track_events.push((event_time, NoteEvent::NoteOff(key.as_int())));
+                    current_track_events.push((
+                        event_time,
+                        NoteEvent::Controller {
+                            channel,
+                            controller: controller.as_int(),
+                            value: value.as_int(),
+                        },
+                    ));
                 }
+                midly::MidiMessage::PitchBend { bend } => {
+                    current_track_events.push((
+                        event_time,
+                        NoteEvent::PitchBend {
+                            channel,
+                            value: bend.0.as_int(),
+                        },
+                    ));
+                }
+                midly::MidiMessage::ProgramChange { program } => {
+                    current_track_events.push((
+                        event_time,
+                        NoteEvent::ProgramChange {
+                            channel,
+                            program: program.as_int(),
+                        },
+                    ));
+                }
+                _ => {}
             }
         }
 
@@ -207,4 +320,156 @@ mod tests {
         let track = Track::new(&smf, 1, &tempo_index).unwrap();
         assert!(!track.events.is_empty());
     }
+
+    #[test]
+    fn test_extract_control_events() {
+        use midly::num::{u4, u7, u14, u28};
+        let midi = |delta: u32, message| midly::TrackEvent {
+            delta: u28::new(delta),
+            kind: midly::TrackEventKind::Midi {
+                channel: u4::new(2),
+                message,
+            },
+        };
+        let smf = midly::Smf {
+            header: midly::Header::new(
+                midly::Format::SingleTrack,
+                midly::Timing::Metrical(480.into()),
+            ),
+            tracks: vec![vec![
+                midi(
+                    0,
+                    midly::MidiMessage::ProgramChange {
+                        program: u7::new(24),
+                    },
+                ),
+                midi(
+                    0,
+                    midly::MidiMessage::Controller {
+                        controller: u7::new(64),
+                        value: u7::new(127),
+                    },
+                ),
+                midi(
+                    0,
+                    midly::MidiMessage::NoteOn {
+                        key: u7::new(60),
+                        vel: u7::new(100),
+                    },
+                ),
+                midi(
+                    240,
+                    midly::MidiMessage::PitchBend {
+                        bend: midly::PitchBend(u14::new(0x3000)),
+                    },
+                ),
+                // ベロシティ0のノートオンはノートオフ
+                midi(
+                    240,
+                    midly::MidiMessage::NoteOn {
+                        key: u7::new(60),
+                        vel: u7::new(0),
+                    },
+                ),
+                // 扱わないコントロールチェンジは無視する
+                midi(
+                    0,
+                    midly::MidiMessage::Controller {
+                        controller: u7::new(1),
+                        value: u7::new(10),
+                    },
+                ),
+                midi(
+                    0,
+                    midly::MidiMessage::Controller {
+                        controller: u7::new(64),
+                        value: u7::new(0),
+                    },
+                ),
+                midly::TrackEvent {
+                    delta: u28::new(0),
+                    kind: midly::TrackEventKind::Meta(midly::MetaMessage::EndOfTrack),
+                },
+            ]],
+        };
+        let mut content = Vec::new();
+        smf.write_std(&mut content).unwrap();
+
+        let smf = OwnedSmf::from_content(content).unwrap();
+        let tempo_index = TempoIndex::new(&smf, 480);
+        let track = Track::new(&smf, 0, &tempo_index).unwrap();
+        assert_eq!(
+            track.events,
+            [
+                (
+                    0.0,
+                    NoteEvent::ProgramChange {
+                        channel: 2,
+                        program: 24
+                    }
+                ),
+                (
+                    0.0,
+                    NoteEvent::Sustain {
+                        channel: 2,
+                        on: true
+                    }
+                ),
+                (
+                    0.0,
+                    NoteEvent::NoteOn(Note {
+                        channel: 2,
+                        midi_note: 60,
+                        velocity: 100
+                    })
+                ),
+                (
+                    0.25,
+                    NoteEvent::PitchBend {
+                        channel: 2,
+                        value: 0x3000
+                    }
+                ),
+                (
+                    0.5,
+                    NoteEvent::NoteOff {
+                        channel: 2,
+                        key: 60
+                    }
+                ),
+                (
+                    0.5,
+                    NoteEvent::Sustain {
+                        channel: 2,
+                        on: false
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_midi_text() {
+        assert_eq!(decode_midi_text(b"Piano"), "Piano");
+        assert_eq!(decode_midi_text("ピアノ".as_bytes()), "ピアノ");
+        // "ピアノ"のShift_JIS
+        assert_eq!(
+            decode_midi_text(&[0x83, 0x73, 0x83, 0x41, 0x83, 0x6d]),
+            "ピアノ"
+        );
+    }
+
+    #[test]
+    fn test_track_name() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_data")
+            .join("library_w4nderers.mid");
+
+        let content = std::fs::read(path).unwrap();
+        let smf = OwnedSmf::from_content(content).unwrap();
+        assert_eq!(track_name(&smf, 0).as_deref(), Some("piano"));
+        assert_eq!(track_name(&smf, 10).as_deref(), Some("Enhance (Vital)"));
+        let num_tracks = smf.borrow_mid().tracks.len() as u32;
+        assert_eq!(track_name(&smf, num_tracks), None);
+    }
 }
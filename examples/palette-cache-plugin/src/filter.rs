@@ -0,0 +1,69 @@
+use crate::palette::{PaletteCache, quantize};
+use aviutl2::{
+    AnyResult,
+    filter::{
+        FilterConfigItemSliceExt, FilterConfigItems, FilterPlugin, FilterPluginFlags,
+        FilterPluginTable, FilterProcVideo, RgbaPixel,
+    },
+};
+use std::sync::Arc;
+
+#[aviutl2::filter::filter_config_items]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterConfig {
+    #[track(name = "強さ", range = 0.0..=100.0, step = 0.1, default = 100.0)]
+    pub strength: f64,
+}
+
+/// 最後に読み込んだパレットの色に減色するフィルタ。パレットが無い場合は何もしない。
+#[aviutl2::plugin(FilterPlugin)]
+pub struct PaletteQuantizeFilter {
+    cache: Arc<PaletteCache>,
+}
+
+impl PaletteQuantizeFilter {
+    pub fn with_cache(cache: Arc<PaletteCache>) -> Self {
+        Self { cache }
+    }
+}
+
+impl FilterPlugin for PaletteQuantizeFilter {
+    /// 汎用プラグインからは[`PaletteQuantizeFilter::with_cache`]で作成するため、単体で作成した場合はキャッシュを共有しない。
+    fn new(_info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
+        Ok(Self::with_cache(Arc::default()))
+    }
+
+    fn plugin_info(&self) -> FilterPluginTable {
+        FilterPluginTable {
+            name: "Rusty Palette Quantize".to_string(),
+            label: None,
+            order: 0,
+            information: "Quantizes the object to the last loaded palette".to_string(),
+            flags: aviutl2::bitflag!(FilterPluginFlags {
+                video: true,
+                filter: true,
+            }),
+            config_items: FilterConfig::to_config_items(),
+        }
+    }
+
+    fn proc_video(
+        &self,
+        config: &[aviutl2::filter::FilterConfigItem],
+        video: &mut FilterProcVideo,
+    ) -> AnyResult<()> {
+        let Some(palette) = self.cache.latest() else {
+            return Ok(());
+        };
+        let config: FilterConfig = config.to_struct();
+        let strength = (config.strength / 100.0) as f32;
+        let (width, height) = (video.video_object.width, video.video_object.height);
+        let mut image = vec![RgbaPixel::default(); (width * height) as usize];
+        video.get_image_data(&mut image);
+        for pixel in &mut image {
+            [pixel.r, pixel.g, pixel.b] = quantize(&palette, [pixel.r, pixel.g, pixel.b], strength);
+        }
+        video.set_image_data(&image, width, height);
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use crate::palette::{Palette, PaletteCache, parse_palette};
+use aviutl2::input::{
+    AnyResult, ImageReturner, InputInfo, InputPixelFormat, InputPlugin, InputPluginTable,
+    VideoInputInfo,
+};
+use std::sync::Arc;
+
+/// 見本の1色あたりの大きさ。
+pub const SWATCH_SIZE: u32 = 32;
+
+/// `.palette`ファイルを色見本の画像として読み込み、読み込んだパレットをキャッシュに記録する入力プラグイン。
+#[aviutl2::plugin(InputPlugin)]
+pub struct PaletteInput {
+    cache: Arc<PaletteCache>,
+}
+
+impl PaletteInput {
+    pub fn with_cache(cache: Arc<PaletteCache>) -> Self {
+        Self { cache }
+    }
+}
+
+impl InputPlugin for PaletteInput {
+    type InputHandle = Arc<Palette>;
+
+    /// 汎用プラグインからは[`PaletteInput::with_cache`]で作成するため、単体で作成した場合はキャッシュを共有しない。
+    fn new(_info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
+        Ok(Self::with_cache(Arc::default()))
+    }
+
+    fn plugin_info(&self) -> InputPluginTable {
+        InputPluginTable {
+            name: "Rusty Palette Input".to_string(),
+            input_type: aviutl2::input::InputType::Video,
+            file_filters: aviutl2::file_filters! {
+                "Palette" => ["palette"],
+            },
+            information: "Loads .palette files as color swatches".to_string(),
+            fail_on_read_error: false,
+            can_config: false,
+            concurrency: aviutl2::input::ConcurrencyMode::None,
+            share_handles: false,
+            cache_input_info: false,
+        }
+    }
+
+    fn open(&self, file: std::path::PathBuf) -> AnyResult<Self::InputHandle> {
+        let palette = Arc::new(parse_palette(&std::fs::read_to_string(&file)?)?);
+        self.cache.store(Arc::clone(&palette));
+        Ok(palette)
+    }
+
+    fn close(&self, _handle: Self::InputHandle) -> AnyResult<()> {
+        Ok(())
+    }
+
+    fn get_input_info(
+        &self,
+        handle: &mut Self::InputHandle,
+        _video_track: u32,
+        _audio_track: u32,
+    ) -> AnyResult<InputInfo> {
+        Ok(InputInfo {
+            video: Some(VideoInputInfo {
+                fps: aviutl2::input::Rational32::new(30, 1),
+                num_frames: 1,
+                width: SWATCH_SIZE * handle.len() as u32,
+                height: SWATCH_SIZE,
+                format: InputPixelFormat::Bgra,
+                manual_frame_index: false,
+                alpha: aviutl2::input::AlphaMode::Straight,
+                colorimetry: aviutl2::pixel::Colorimetry::HOST,
+            }),
+            audio: None,
+        })
+    }
+
+    fn read_video(
+        &self,
+        handle: &Self::InputHandle,
+        _frame: u32,
+        returner: &mut ImageReturner,
+    ) -> AnyResult<()> {
+        // 全ての行が同じなので、上下の向きは気にしなくてよい
+        let row = handle
+            .iter()
+            .flat_map(|&[r, g, b]| std::iter::repeat_n((b, g, r, 255u8), SWATCH_SIZE as usize))
+            .collect::<Vec<_>>();
+        returner.write(&row.repeat(SWATCH_SIZE as usize));
+        Ok(())
+    }
+}
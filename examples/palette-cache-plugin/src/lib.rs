@@ -0,0 +1,53 @@
+use aviutl2::AnyResult;
+use aviutl2::generic::SubPlugin;
+use std::sync::Arc;
+
+mod filter;
+mod input;
+mod palette;
+
+/// 入力プラグインとフィルタを1つのDLLに登録し、読み込んだパレットを共有する。
+#[aviutl2::plugin(GenericPlugin)]
+pub struct PaletteCachePlugin {
+    cache: Arc<palette::PaletteCache>,
+    input: SubPlugin<input::PaletteInput>,
+    filter: SubPlugin<filter::PaletteQuantizeFilter>,
+}
+
+impl aviutl2::generic::GenericPlugin for PaletteCachePlugin {
+    fn new(info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
+        let cache = Arc::new(palette::PaletteCache::default());
+        Ok(Self {
+            input: SubPlugin::new_input_plugin_with(
+                &info,
+                input::PaletteInput::with_cache(Arc::clone(&cache)),
+            )?,
+            filter: SubPlugin::new_filter_plugin_with(
+                &info,
+                filter::PaletteQuantizeFilter::with_cache(Arc::clone(&cache)),
+            )?,
+            cache,
+        })
+    }
+
+    fn plugin_info(&self) -> aviutl2::generic::GenericPluginTable {
+        aviutl2::generic::GenericPluginTable {
+            name: "Rusty Palette Cache Plugin".to_string(),
+            information: format!(
+                "Palette cache example, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/palette-cache-plugin",
+                version = env!("CARGO_PKG_VERSION")
+            ),
+        }
+    }
+
+    fn register(&mut self, registry: &mut aviutl2::generic::HostAppHandle) {
+        registry.register_input_plugin(&self.input);
+        registry.register_filter_plugin(&self.filter);
+    }
+
+    fn on_clear_cache(&mut self, _edit_section: &aviutl2::generic::EditSection) {
+        self.cache.clear();
+    }
+}
+
+aviutl2::register_generic_plugin!(PaletteCachePlugin);
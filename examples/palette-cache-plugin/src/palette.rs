@@ -0,0 +1,110 @@
+/// RGBの色の一覧。
+pub type Palette = Vec<[u8; 3]>;
+
+/// `.palette`ファイルを読み込む。
+///
+/// 1行に1色ずつ`#RRGGBB`の形式で書きます。空行と`;`で始まる行は無視されます。
+pub fn parse_palette(source: &str) -> anyhow::Result<Palette> {
+    let mut palette = Palette::new();
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        let color = line
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| anyhow::anyhow!("Line {}: invalid color {line:?}", i + 1))?;
+        palette.push([(color >> 16) as u8, (color >> 8) as u8, color as u8]);
+    }
+    anyhow::ensure!(!palette.is_empty(), "The palette has no colors");
+    Ok(palette)
+}
+
+/// パレットの中で`color`に最も近い色を返す。
+pub fn nearest(palette: &[[u8; 3]], color: [u8; 3]) -> [u8; 3] {
+    palette
+        .iter()
+        .copied()
+        .min_by_key(|candidate| {
+            candidate
+                .iter()
+                .zip(&color)
+                .map(|(&a, &b)| (a as i32 - b as i32).pow(2))
+                .sum::<i32>()
+        })
+        .unwrap_or(color)
+}
+
+/// `color`をパレットの色に`strength`（0.0〜1.0）の割合で近づける。
+pub fn quantize(palette: &[[u8; 3]], color: [u8; 3], strength: f32) -> [u8; 3] {
+    let target = nearest(palette, color);
+    std::array::from_fn(|i| {
+        (color[i] as f32 + (target[i] as f32 - color[i] as f32) * strength).round() as u8
+    })
+}
+
+/// 入力プラグインが読み込んだパレットを、フィルタと共有するためのキャッシュ。
+#[derive(Debug, Default)]
+pub struct PaletteCache {
+    latest: std::sync::Mutex<Option<std::sync::Arc<Palette>>>,
+}
+
+impl PaletteCache {
+    /// 読み込んだパレットを記録する。
+    pub fn store(&self, palette: std::sync::Arc<Palette>) {
+        *self.latest.lock().unwrap() = Some(palette);
+    }
+
+    /// 最後に読み込んだパレットを返す。
+    pub fn latest(&self) -> Option<std::sync::Arc<Palette>> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    /// 記録したパレットを破棄する。
+    pub fn clear(&self) {
+        *self.latest.lock().unwrap() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_palette() {
+        let palette = parse_palette("; comment\n#FF0000\n\n  #00ff80  \n").unwrap();
+        assert_eq!(palette, vec![[255, 0, 0], [0, 255, 128]]);
+    }
+
+    #[test]
+    fn test_parse_palette_errors() {
+        assert!(parse_palette("").is_err());
+        assert!(parse_palette("; only comments").is_err());
+        let error = parse_palette("#000000\nFF0000").unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: invalid color \"FF0000\"");
+        assert!(parse_palette("#12345").is_err());
+        assert!(parse_palette("#GGGGGG").is_err());
+    }
+
+    #[test]
+    fn test_quantize() {
+        let palette = [[0, 0, 0], [255, 255, 255], [255, 0, 0]];
+        assert_eq!(nearest(&palette, [200, 40, 30]), [255, 0, 0]);
+        assert_eq!(nearest(&palette, [100, 100, 100]), [0, 0, 0]);
+        assert_eq!(quantize(&palette, [200, 40, 30], 1.0), [255, 0, 0]);
+        assert_eq!(quantize(&palette, [200, 40, 30], 0.0), [200, 40, 30]);
+        assert_eq!(quantize(&palette, [200, 40, 30], 0.5), [228, 20, 15]);
+    }
+
+    #[test]
+    fn test_cache() {
+        let cache = PaletteCache::default();
+        assert!(cache.latest().is_none());
+        cache.store(std::sync::Arc::new(vec![[1, 2, 3]]));
+        assert_eq!(cache.latest().as_deref(), Some(&vec![[1, 2, 3]]));
+        cache.clear();
+        assert!(cache.latest().is_none());
+    }
+}
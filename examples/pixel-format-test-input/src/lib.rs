@@ -1,16 +1,25 @@
+mod pattern;
+mod signal;
+
 use aviutl2::input::{
-    AnyResult, ImageReturner, InputInfo, InputPixelFormat, InputPlugin, InputPluginTable,
-    VideoInputInfo, f16,
+    AnyResult, AudioFormat, AudioInputInfo, AudioReturner, ImageReturner, InputInfo,
+    InputPixelFormat, InputPlugin, InputPluginTable, VideoInputInfo, f16,
 };
+use pattern::Sample;
 
 #[aviutl2::plugin(InputPlugin)]
 struct PixelFormatTestPlugin;
 
 #[derive(Clone)]
-struct Handle {
-    format: InputPixelFormat,
-    width: u32,
-    height: u32,
+enum Handle {
+    Video {
+        format: InputPixelFormat,
+        width: u32,
+        height: u32,
+        /// `manual_frame_index`を有効にする場合のタイムライン。
+        timeline: Option<aviutl2::input::FrameTimeline>,
+    },
+    Audio(signal::Signal),
 }
 
 impl InputPlugin for PixelFormatTestPlugin {
@@ -23,41 +32,54 @@ impl InputPlugin for PixelFormatTestPlugin {
     fn plugin_info(&self) -> InputPluginTable {
         InputPluginTable {
             name: "Rusty Pixel Format Tester Input".to_string(),
-            input_type: aviutl2::input::InputType::Video,
+            input_type: aviutl2::input::InputType::Both,
             file_filters: aviutl2::file_filters! {
                 "Pixel Formats" => [
                     "bgra".to_string(),
+                    "bgra_manual".to_string(),
                     "bgr".to_string(),
                     "yuy2".to_string(),
                     "pa64".to_string(),
                     "hf64".to_string(),
                     "yc48".to_string(),
                 ],
+                "Audio Test Signals" => [
+                    "sine".to_string(),
+                    "sweep".to_string(),
+                    "impulse".to_string(),
+                ],
             },
             information: format!(
                 "Pixel Format Test Plugin / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/pixel-format-test-input",
                 version = env!("CARGO_PKG_VERSION")
             ),
+            fail_on_read_error: false,
             can_config: false,
-            concurrent: false,
+            concurrency: aviutl2::input::ConcurrencyMode::None,
+            share_handles: false,
+            cache_input_info: false,
         }
     }
 
     fn open(&self, file: std::path::PathBuf) -> AnyResult<Self::InputHandle> {
         let format_str = file.extension().and_then(|s| s.to_str()).unwrap_or("bgra");
+        if let Some(signal) = signal::Signal::from_extension(format_str) {
+            return Ok(Handle::Audio(signal));
+        }
         let format = match format_str {
             "bgr" => InputPixelFormat::Bgr,
             "yuy2" => InputPixelFormat::Yuy2,
-            "bgra" => InputPixelFormat::Bgra,
+            "bgra" | "bgra_manual" => InputPixelFormat::Bgra,
             "pa64" => InputPixelFormat::Pa64,
             "hf64" => InputPixelFormat::Hf64,
             "yc48" => InputPixelFormat::Yc48,
             _ => return Err(anyhow::anyhow!("Unsupported pixel format: {}", format_str)),
         };
-        Ok(Handle {
+        Ok(Handle::Video {
             format,
             width: 256,
             height: 256,
+            timeline: (format_str == "bgra_manual").then(pattern::manual_timeline),
         })
     }
 
@@ -67,17 +89,53 @@ impl InputPlugin for PixelFormatTestPlugin {
         _video_track: u32,
         _audio_track: u32,
     ) -> AnyResult<InputInfo> {
-        Ok(InputInfo {
-            video: Some(VideoInputInfo {
-                fps: aviutl2::input::Rational32::new(30, 1),
-                num_frames: 1,
-                width: handle.width,
-                height: handle.height,
-                format: handle.format,
-                manual_frame_index: false,
+        match handle {
+            Handle::Video {
+                format,
+                width,
+                height,
+                timeline,
+            } => Ok(InputInfo {
+                video: Some(VideoInputInfo {
+                    fps: aviutl2::input::Rational32::new(30, 1),
+                    num_frames: pattern::NUM_FRAMES,
+                    width: *width,
+                    height: *height,
+                    format: *format,
+                    manual_frame_index: timeline.is_some(),
+                    alpha: format
+                        .native_alpha_mode()
+                        .unwrap_or(aviutl2::input::AlphaMode::Straight),
+                    colorimetry: aviutl2::pixel::Colorimetry::HOST,
+                }),
+                audio: None,
             }),
-            audio: None,
-        })
+            Handle::Audio(_) => Ok(InputInfo {
+                video: None,
+                audio: Some(AudioInputInfo {
+                    sample_rate: signal::SAMPLE_RATE,
+                    num_samples: signal::NUM_SAMPLES,
+                    channels: 2,
+                    format: AudioFormat::IeeeFloat32,
+                }),
+            }),
+        }
+    }
+
+    fn time_to_frame(
+        &self,
+        handle: &mut Self::InputHandle,
+        _track: u32,
+        time: f64,
+    ) -> AnyResult<u32> {
+        let Handle::Video {
+            timeline: Some(timeline),
+            ..
+        } = handle
+        else {
+            anyhow::bail!("time_to_frame called without manual_frame_index");
+        };
+        Ok(timeline.frame_for(time, aviutl2::input::TimeToFrame::Clamp))
     }
 
     fn read_video(
@@ -86,44 +144,78 @@ impl InputPlugin for PixelFormatTestPlugin {
         frame: u32,
         returner: &mut ImageReturner,
     ) -> AnyResult<()> {
-        anyhow::ensure!(frame == 0, "Only frame 0 is valid");
-        let (width, height) = (handle.width, handle.height);
-        match handle.format {
+        let Handle::Video {
+            format,
+            width,
+            height,
+            ..
+        } = *handle
+        else {
+            anyhow::bail!("Audio files have no video");
+        };
+        anyhow::ensure!(frame < pattern::NUM_FRAMES, "Frame {frame} is out of range");
+        // BGR・BGRAは下から上に並ぶ
+        let rows: Box<dyn Iterator<Item = u32>> = match format {
+            InputPixelFormat::Bgr | InputPixelFormat::Bgra => Box::new((0..height).rev()),
+            _ => Box::new(0..height),
+        };
+        let sample = |x: u32, y: u32| pattern::sample(frame, x, y, width);
+        match format {
             InputPixelFormat::Bgra => {
                 let mut buffer = Vec::with_capacity((width * height) as usize);
-                for y in 0..height {
+                for y in rows {
                     for x in 0..width {
-                        buffer.push((
-                            x as u8,                                                  // B
-                            y as u8,                                                  // G
-                            ((x + y) as f64 / (width + height) as f64 * 255.0) as u8, // R
-                            255, // A (fully opaque)
-                        ));
+                        buffer.push(match sample(x, y) {
+                            Sample::Gradient { x, y } => (
+                                x as u8,                                                  // B
+                                y as u8,                                                  // G
+                                ((x + y) as f64 / (width + height) as f64 * 255.0) as u8, // R
+                                255, // A (fully opaque)
+                            ),
+                            Sample::CounterOn => (255, 255, 255, 255),
+                            Sample::CounterOff => (0, 0, 0, 255),
+                        });
                     }
                 }
                 returner.write(&buffer);
             }
             InputPixelFormat::Bgr => {
                 let mut buffer = Vec::with_capacity((width * height * 3) as usize);
-                for y in 0..height {
+                for y in rows {
                     for x in 0..width {
-                        buffer.push((
-                            x as u8,                                                  // B
-                            y as u8,                                                  // G
-                            ((x + y) as f64 / (width + height) as f64 * 255.0) as u8, // R
-                        ));
+                        buffer.push(match sample(x, y) {
+                            Sample::Gradient { x, y } => (
+                                x as u8,                                                  // B
+                                y as u8,                                                  // G
+                                ((x + y) as f64 / (width + height) as f64 * 255.0) as u8, // R
+                            ),
+                            Sample::CounterOn => (255, 255, 255),
+                            Sample::CounterOff => (0, 0, 0),
+                        });
                     }
                 }
                 returner.write(&buffer);
             }
             InputPixelFormat::Yuy2 => {
                 let mut buffer = Vec::with_capacity((width * height / 2) as usize);
-                for y in 0..height {
+                let luma = |sample: Sample| match sample {
+                    Sample::Gradient { x, y } => {
+                        ((x + y) as f64 / (width + height) as f64 * 256.0) as u8
+                    }
+                    Sample::CounterOn => 235,
+                    Sample::CounterOff => 16,
+                };
+                for y in rows {
                     for x in (0..width).step_by(2) {
-                        let y0 = ((x + y) as f64 / (width + height) as f64 * 256.0) as u8; // Y0
-                        let y1 = ((x + y + 1) as f64 / (width + height) as f64 * 256.0) as u8; // Y1
-                        let u = ((x as f64 / width as f64) * 256.0) as u8; // U
-                        let v = ((y as f64 / height as f64) * 256.0) as u8; // V
+                        let (u, v) = match sample(x, y) {
+                            Sample::Gradient { x, y } => (
+                                ((x as f64 / width as f64) * 256.0) as u8,  // U
+                                ((y as f64 / height as f64) * 256.0) as u8, // V
+                            ),
+                            Sample::CounterOn | Sample::CounterOff => (128, 128),
+                        };
+                        let y0 = luma(sample(x, y)); // Y0
+                        let y1 = luma(sample(x + 1, y)); // Y1
                         buffer.push((y0, u, y1, v)); // Y0, U, Y1, V
                     }
                 }
@@ -131,25 +223,37 @@ impl InputPlugin for PixelFormatTestPlugin {
             }
             InputPixelFormat::Pa64 => {
                 let mut buffer = Vec::with_capacity((width * height) as usize);
-                for y in 0..height {
+                for y in rows {
                     for x in 0..width {
-                        let r = (x as f64 / width as f64) * 65535.0;
-                        let g = (y as f64 / height as f64) * 65535.0;
-                        let b = ((x + y) as f64 / (width + height) as f64) * 65535.0;
-                        buffer.push((
-                            r as u16, g as u16, b as u16, 65535, // A (fully opaque)
-                        ));
+                        buffer.push(match sample(x, y) {
+                            Sample::Gradient { x, y } => {
+                                let r = (x as f64 / width as f64) * 65535.0;
+                                let g = (y as f64 / height as f64) * 65535.0;
+                                let b = ((x + y) as f64 / (width + height) as f64) * 65535.0;
+                                (
+                                    r as u16, g as u16, b as u16, 65535, // A (fully opaque)
+                                )
+                            }
+                            Sample::CounterOn => (65535, 65535, 65535, 65535),
+                            Sample::CounterOff => (0, 0, 0, 65535),
+                        });
                     }
                 }
                 returner.write(&buffer);
             }
             InputPixelFormat::Hf64 => {
                 let mut buffer = Vec::with_capacity((width * height) as usize);
-                for y in 0..height {
+                for y in rows {
                     for x in 0..width {
-                        let r = x as f64 / width as f64;
-                        let g = y as f64 / height as f64;
-                        let b = (x + y) as f64 / (width + height) as f64;
+                        let (r, g, b) = match sample(x, y) {
+                            Sample::Gradient { x, y } => (
+                                x as f64 / width as f64,
+                                y as f64 / height as f64,
+                                (x + y) as f64 / (width + height) as f64,
+                            ),
+                            Sample::CounterOn => (1.0, 1.0, 1.0),
+                            Sample::CounterOff => (0.0, 0.0, 0.0),
+                        };
                         buffer.push((
                             f16::from_f64(r),   // R
                             f16::from_f64(g),   // G
@@ -162,12 +266,19 @@ impl InputPlugin for PixelFormatTestPlugin {
             }
             InputPixelFormat::Yc48 => {
                 let mut buffer = Vec::with_capacity((width * height) as usize);
-                for y in 0..height {
+                for y in rows {
                     for x in 0..width {
-                        let y_val = (((x + y) as f64 / (width + height) as f64) * 4096.0) as i16; // Y
-                        let cb = ((x as f64 / width as f64) * 4096.0 - 2048.0) as i16; // Cb
-                        let cr = ((y as f64 / height as f64) * 4096.0 - 2048.0) as i16; // Cr
-                        buffer.push((y_val, cb, cr)); // Y, Cb, Cr
+                        buffer.push(match sample(x, y) {
+                            Sample::Gradient { x, y } => {
+                                let y_val =
+                                    (((x + y) as f64 / (width + height) as f64) * 4096.0) as i16; // Y
+                                let cb = ((x as f64 / width as f64) * 4096.0 - 2048.0) as i16; // Cb
+                                let cr = ((y as f64 / height as f64) * 4096.0 - 2048.0) as i16; // Cr
+                                (y_val, cb, cr) // Y, Cb, Cr
+                            }
+                            Sample::CounterOn => (4096, 0, 0),
+                            Sample::CounterOff => (0, 0, 0),
+                        });
                     }
                 }
                 returner.write(&buffer);
@@ -177,6 +288,20 @@ impl InputPlugin for PixelFormatTestPlugin {
         Ok(())
     }
 
+    fn read_audio_mut(
+        &self,
+        handle: &mut Self::InputHandle,
+        start: i32,
+        length: i32,
+        returner: &mut AudioReturner,
+    ) -> AnyResult<()> {
+        let Handle::Audio(signal) = *handle else {
+            anyhow::bail!("Video files have no audio");
+        };
+        returner.write(&signal.render(start as i64, length.max(0) as usize));
+        Ok(())
+    }
+
     fn close(&self, _handle: Self::InputHandle) -> AnyResult<()> {
         Ok(())
     }
@@ -0,0 +1,232 @@
+//! テスト用の動画パターンを生成する。
+//!
+//! 座標はすべて画像の左上を原点とし、下方向を正とします。
+//! 下から上に並ぶフォーマットで書き込む際の上下反転は呼び出し側で行います。
+
+use aviutl2::input::FrameTimeline;
+
+/// 動画のフレーム数。
+pub const NUM_FRAMES: u32 = 300;
+/// 1フレームあたりにグラデーションが横に動くピクセル数。
+pub const SCROLL_SPEED: u32 = 2;
+
+/// フレームカウンターの桁数。
+const COUNTER_DIGITS: u32 = 4;
+/// フレームカウンターの画像の端からの距離。
+const COUNTER_MARGIN: u32 = 8;
+/// 1桁の幅。
+const DIGIT_WIDTH: u32 = 20;
+/// 1桁の高さ。
+const DIGIT_HEIGHT: u32 = 36;
+/// 桁同士の間隔。
+const DIGIT_SPACING: u32 = 6;
+/// セグメントの太さ。
+const SEGMENT_THICKNESS: u32 = 4;
+
+/// 7セグメント表示の各数字で点灯するセグメント。
+///
+/// 下位ビットから順にa（上）、b（右上）、c（右下）、d（下）、e（左下）、f（左上）、g（中央）。
+const DIGIT_SEGMENTS: [u8; 10] = [
+    0b011_1111, // 0
+    0b000_0110, // 1
+    0b101_1011, // 2
+    0b100_1111, // 3
+    0b110_0110, // 4
+    0b110_1101, // 5
+    0b111_1101, // 6
+    0b000_0111, // 7
+    0b111_1111, // 8
+    0b110_1111, // 9
+];
+
+/// パターンの1ピクセル。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sample {
+    /// グラデーション部分。`x`はスクロール後の座標。
+    Gradient { x: u32, y: u32 },
+    /// フレームカウンターの点灯しているセグメント。
+    CounterOn,
+    /// フレームカウンターの背景。
+    CounterOff,
+}
+
+/// `frame`フレーム目の`(x, y)`のピクセルを返す。
+pub fn sample(frame: u32, x: u32, y: u32, width: u32) -> Sample {
+    match counter_pixel(frame, x, y) {
+        Some(true) => Sample::CounterOn,
+        Some(false) => Sample::CounterOff,
+        None => Sample::Gradient {
+            x: (x + frame * SCROLL_SPEED) % width,
+            y,
+        },
+    }
+}
+
+/// フレームカウンターの領域内であれば、`value`を表示したときに`(x, y)`が点灯しているかを返す。
+///
+/// `value`は下4桁のみ表示されます。
+pub fn counter_pixel(value: u32, x: u32, y: u32) -> Option<bool> {
+    let x = x.checked_sub(COUNTER_MARGIN)?;
+    let y = y.checked_sub(COUNTER_MARGIN)?;
+    if y >= DIGIT_HEIGHT {
+        return None;
+    }
+    let position = x / (DIGIT_WIDTH + DIGIT_SPACING);
+    if position >= COUNTER_DIGITS {
+        return None;
+    }
+    let x = x % (DIGIT_WIDTH + DIGIT_SPACING);
+    if x >= DIGIT_WIDTH {
+        return Some(false);
+    }
+    let digit = value / 10u32.pow(COUNTER_DIGITS - 1 - position) % 10;
+    Some(segments_at(x, y) & DIGIT_SEGMENTS[digit as usize] != 0)
+}
+
+/// 1桁の中の`(x, y)`を含むセグメントのビットマスクを返す。
+fn segments_at(x: u32, y: u32) -> u8 {
+    let half = DIGIT_HEIGHT / 2;
+    let top_half = y < half + SEGMENT_THICKNESS / 2;
+    let bottom_half = y >= half - SEGMENT_THICKNESS / 2;
+    let left = x < SEGMENT_THICKNESS;
+    let right = x >= DIGIT_WIDTH - SEGMENT_THICKNESS;
+
+    let mut mask = 0;
+    if y < SEGMENT_THICKNESS {
+        mask |= 1 << 0;
+    }
+    if right && top_half {
+        mask |= 1 << 1;
+    }
+    if right && bottom_half {
+        mask |= 1 << 2;
+    }
+    if y >= DIGIT_HEIGHT - SEGMENT_THICKNESS {
+        mask |= 1 << 3;
+    }
+    if left && bottom_half {
+        mask |= 1 << 4;
+    }
+    if left && top_half {
+        mask |= 1 << 5;
+    }
+    if (half - SEGMENT_THICKNESS / 2..half + SEGMENT_THICKNESS / 2).contains(&y) {
+        mask |= 1 << 6;
+    }
+    mask
+}
+
+/// `manual_frame_index`を有効にした場合のタイムライン。
+///
+/// 1/30秒と2/30秒のフレームが交互に並びます。
+pub fn manual_timeline() -> FrameTimeline {
+    FrameTimeline::from_durations((0..NUM_FRAMES).map(|frame| (1 + frame % 2) as f64 / 30.0))
+        .expect("durations are finite and non-empty")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aviutl2::input::TimeToFrame;
+
+    /// 各セグメントの中央付近の座標（桁内）。
+    const PROBES: [(u32, u32); 7] = [
+        (10, 1),  // a
+        (18, 9),  // b
+        (18, 27), // c
+        (10, 34), // d
+        (1, 27),  // e
+        (1, 9),   // f
+        (10, 18), // g
+    ];
+
+    fn lit_segments(value: u32, position: u32) -> u8 {
+        let origin_x = COUNTER_MARGIN + position * (DIGIT_WIDTH + DIGIT_SPACING);
+        PROBES
+            .iter()
+            .enumerate()
+            .filter(|(_, (x, y))| counter_pixel(value, origin_x + x, COUNTER_MARGIN + y).unwrap())
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+
+    #[test]
+    fn test_counter_digits() {
+        for digit in 0..10 {
+            assert_eq!(
+                lit_segments(digit, COUNTER_DIGITS - 1),
+                DIGIT_SEGMENTS[digit as usize],
+                "digit {digit}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_counter_positions() {
+        assert_eq!(lit_segments(1234, 0), DIGIT_SEGMENTS[1]);
+        assert_eq!(lit_segments(1234, 1), DIGIT_SEGMENTS[2]);
+        assert_eq!(lit_segments(1234, 2), DIGIT_SEGMENTS[3]);
+        assert_eq!(lit_segments(1234, 3), DIGIT_SEGMENTS[4]);
+        // 上の桁は切り捨てられる
+        assert_eq!(lit_segments(12345, 0), DIGIT_SEGMENTS[2]);
+    }
+
+    #[test]
+    fn test_counter_bounds() {
+        assert_eq!(counter_pixel(0, 0, 0), None);
+        assert_eq!(counter_pixel(0, COUNTER_MARGIN, COUNTER_MARGIN), Some(true));
+        // 桁の間は背景
+        assert_eq!(
+            counter_pixel(8888, COUNTER_MARGIN + DIGIT_WIDTH, COUNTER_MARGIN),
+            Some(false)
+        );
+        // 中央の穴
+        assert_eq!(
+            counter_pixel(8888, COUNTER_MARGIN + 10, COUNTER_MARGIN + 9),
+            Some(false)
+        );
+        assert_eq!(
+            counter_pixel(0, COUNTER_MARGIN, COUNTER_MARGIN + DIGIT_HEIGHT),
+            None
+        );
+        assert_eq!(
+            counter_pixel(
+                0,
+                COUNTER_MARGIN + COUNTER_DIGITS * (DIGIT_WIDTH + DIGIT_SPACING),
+                COUNTER_MARGIN
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_gradient_scrolls() {
+        assert_eq!(
+            sample(0, 100, 200, 256),
+            Sample::Gradient { x: 100, y: 200 }
+        );
+        assert_eq!(
+            sample(10, 100, 200, 256),
+            Sample::Gradient { x: 120, y: 200 }
+        );
+        assert_eq!(
+            sample(100, 100, 200, 256),
+            Sample::Gradient { x: 44, y: 200 }
+        );
+    }
+
+    #[test]
+    fn test_manual_timeline() {
+        let timeline = manual_timeline();
+        assert_eq!(timeline.len(), NUM_FRAMES as usize);
+        assert!((timeline.duration() - 15.0).abs() < 1e-9);
+        let frame_at = |n: f64| timeline.frame_for((n + 0.5) / 30.0, TimeToFrame::Clamp);
+        assert_eq!(
+            [0.0, 1.0, 2.0, 3.0, 4.0, 5.0].map(frame_at),
+            [0, 1, 1, 2, 3, 3]
+        );
+        assert_eq!(
+            timeline.frame_for(100.0, TimeToFrame::Clamp),
+            NUM_FRAMES - 1
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! テスト用の音声信号を生成する。
+//!
+//! どの信号もサンプル番号だけから計算されるため、どこから何サンプルずつ読み込んでも同じ波形になります。
+
+/// サンプルレート。
+pub const SAMPLE_RATE: u32 = 48000;
+/// 音声のサンプル数（10秒）。
+pub const NUM_SAMPLES: u32 = SAMPLE_RATE * 10;
+
+/// 正弦波の周波数。
+const SINE_FREQUENCY: f64 = 1000.0;
+/// スイープの開始周波数。
+const SWEEP_START: f64 = 20.0;
+/// スイープの終了周波数。
+const SWEEP_END: f64 = 20000.0;
+/// 正弦波・スイープの振幅（-6dBFS）。
+const AMPLITUDE: f64 = 0.5;
+
+/// テスト信号の種類。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// 1kHzの正弦波。左右とも同じ。
+    Sine,
+    /// 10秒かけて20Hzから20kHzまで指数的に上がるスイープ。左右とも同じ。
+    Sweep,
+    /// 1秒ごとのインパルス。
+    ///
+    /// 左は0秒から、右は0.5秒から1秒ごとに1サンプルだけ1.0になります。
+    Impulse,
+}
+
+impl Signal {
+    /// 拡張子から信号の種類を返す。
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "sine" => Some(Signal::Sine),
+            "sweep" => Some(Signal::Sweep),
+            "impulse" => Some(Signal::Impulse),
+            _ => None,
+        }
+    }
+
+    /// `index`サンプル目の値を返す。範囲外は無音です。
+    pub fn sample(self, index: i64) -> (f32, f32) {
+        if !(0..NUM_SAMPLES as i64).contains(&index) {
+            return (0.0, 0.0);
+        }
+        let t = index as f64 / SAMPLE_RATE as f64;
+        match self {
+            Signal::Sine => {
+                let value = (AMPLITUDE * (std::f64::consts::TAU * SINE_FREQUENCY * t).sin()) as f32;
+                (value, value)
+            }
+            Signal::Sweep => {
+                // 瞬時周波数 f(t) = f0 * (f1 / f0)^(t / T) を積分した位相
+                let duration = NUM_SAMPLES as f64 / SAMPLE_RATE as f64;
+                let rate = (SWEEP_END / SWEEP_START).ln() / duration;
+                let phase = std::f64::consts::TAU * SWEEP_START * ((rate * t).exp() - 1.0) / rate;
+                let value = (AMPLITUDE * phase.sin()) as f32;
+                (value, value)
+            }
+            Signal::Impulse => {
+                let position = index % SAMPLE_RATE as i64;
+                (
+                    if position == 0 { 1.0 } else { 0.0 },
+                    if position == SAMPLE_RATE as i64 / 2 {
+                        1.0
+                    } else {
+                        0.0
+                    },
+                )
+            }
+        }
+    }
+
+    /// `start`サンプル目から`length`サンプル分を返す。
+    pub fn render(self, start: i64, length: usize) -> Vec<(f32, f32)> {
+        (0..length as i64)
+            .map(|offset| self.sample(start + offset))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_is_independent_of_chunking() {
+        for signal in [Signal::Sine, Signal::Sweep, Signal::Impulse] {
+            let whole = signal.render(-100, 50000);
+            let mut chunked = Vec::new();
+            let mut start = -100;
+            for length in [1, 999, 2048, 17, 46935] {
+                chunked.extend(signal.render(start, length));
+                start += length as i64;
+            }
+            assert_eq!(whole, chunked, "{signal:?}");
+        }
+    }
+
+    #[test]
+    fn test_sine_values() {
+        let samples = Signal::Sine.render(0, 49);
+        // 1kHzの周期は48サンプル
+        assert_eq!(samples[0], (0.0, 0.0));
+        assert!((samples[12].0 - 0.5).abs() < 1e-6);
+        assert!(samples[24].0.abs() < 1e-6);
+        assert!((samples[36].0 + 0.5).abs() < 1e-6);
+        assert!(samples[48].0.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_impulse_positions() {
+        let samples = Signal::Impulse.render(0, NUM_SAMPLES as usize);
+        let left = samples
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.0 != 0.0)
+            .map(|(i, _)| i as u32)
+            .collect::<Vec<_>>();
+        let right = samples
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.1 != 0.0)
+            .map(|(i, _)| i as u32)
+            .collect::<Vec<_>>();
+        assert_eq!(left, (0..10).map(|s| s * SAMPLE_RATE).collect::<Vec<_>>());
+        assert_eq!(
+            right,
+            (0..10)
+                .map(|s| s * SAMPLE_RATE + SAMPLE_RATE / 2)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_sweep_frequency_rises() {
+        // 0.1秒間のゼロ交差の数から、おおよその周波数を求める
+        let crossings_per_second = |start_seconds: f64| {
+            let start = (start_seconds * SAMPLE_RATE as f64) as i64;
+            let samples = Signal::Sweep.render(start, SAMPLE_RATE as usize / 10);
+            let crossings = samples
+                .windows(2)
+                .filter(|w| (w[0].0 < 0.0) != (w[1].0 < 0.0))
+                .count();
+            crossings as f64 * 10.0 / 2.0
+        };
+        // 5秒地点では 20 * 1000^0.5 ≒ 632Hz
+        let middle = crossings_per_second(4.95);
+        assert!((600.0..670.0).contains(&middle), "middle = {middle}");
+        assert!(crossings_per_second(0.0) < 30.0);
+        assert!(crossings_per_second(9.8) > 15000.0);
+    }
+
+    #[test]
+    fn test_out_of_range_is_silent() {
+        for signal in [Signal::Sine, Signal::Sweep, Signal::Impulse] {
+            assert_eq!(signal.sample(-1), (0.0, 0.0));
+            assert_eq!(signal.sample(NUM_SAMPLES as i64), (0.0, 0.0));
+        }
+        assert_eq!(Signal::from_extension("sweep"), Some(Signal::Sweep));
+        assert_eq!(Signal::from_extension("bgra"), None);
+    }
+}
@@ -1,3 +1,4 @@
+mod mask;
 mod sort;
 use aviutl2::{
     AnyResult, AviUtl2Info,
@@ -8,7 +9,7 @@ use aviutl2::{
     tracing,
 };
 
-pub use sort::pixelsort;
+pub use sort::{calc_luminances, pixelsort, pixelsort_with_mask};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, FilterConfigSelectItems)]
 pub enum ThresholdType {
@@ -28,13 +29,51 @@ pub enum SortDirection {
     Vertical,
     #[item(name = "上下（反転）")]
     VerticalInverted,
+    #[item(name = "ランダム方向")]
+    Random,
+}
+
+impl SortDirection {
+    /// ランダム方向の場合に選ぶ方向。
+    const CONCRETE: [SortDirection; 4] = [
+        SortDirection::Horizontal,
+        SortDirection::HorizontalInverted,
+        SortDirection::Vertical,
+        SortDirection::VerticalInverted,
+    ];
+
+    /// ランダム方向の場合は、オブジェクトとフレームごとに決まった方向を選ぶ。
+    /// それ以外の場合はそのまま返す。
+    pub fn resolve(&self, object_id: i64, frame: u32) -> SortDirection {
+        match self {
+            SortDirection::Random => {
+                let mut rng = aviutl2::filter::FrameRng::new(object_id, frame as u64, 0);
+                Self::CONCRETE[rng.range(0..Self::CONCRETE.len())].clone()
+            }
+            direction => direction.clone(),
+        }
+    }
 }
 
 #[aviutl2::filter::filter_config_items]
 #[derive(Debug, Clone, PartialEq)]
 pub struct FilterConfig {
-    #[track(name = "しきい値", range = 0.0..=1.0, step = 0.001, default = 0.5)]
+    #[track(
+        name = "しきい値",
+        range = 0.0..=1.0,
+        step = 0.001,
+        default = 0.5,
+        tooltip = "この明るさを境に、ソートするピクセルを選びます。"
+    )]
     pub threshold: f64,
+    #[track(
+        name = "マスクしきい値",
+        range = 0.0..=1.0,
+        step = 0.001,
+        default = 0.0,
+        tooltip = "元の明るさがこの値未満のピクセルは動かしません。0のときは無効です。"
+    )]
+    pub mask_threshold: f64,
     #[select(
         name = "ソート対象",
         items = ThresholdType,
@@ -47,10 +86,18 @@ pub struct FilterConfig {
         default = SortDirection::Horizontal
     )]
     pub direction: SortDirection,
+    #[file(
+        name = "マスク画像",
+        filters = { "画像ファイル" => ["png", "bmp", "jpg", "jpeg"] },
+        tooltip = "白い部分だけをソートします。画像は対象の大きさに引き伸ばされ、ファイルを上書きすると読み込み直します。"
+    )]
+    pub mask_image: Option<std::path::PathBuf>,
 }
 
 #[aviutl2::plugin(FilterPlugin)]
-struct PixelSortFilter;
+struct PixelSortFilter {
+    masks: mask::MaskCache,
+}
 
 impl FilterPlugin for PixelSortFilter {
     fn new(_info: AviUtl2Info) -> AnyResult<Self> {
@@ -63,13 +110,16 @@ impl FilterPlugin for PixelSortFilter {
             .event_format(aviutl2::logger::AviUtl2Formatter)
             .with_writer(aviutl2::logger::AviUtl2LogWriter)
             .init();
-        Ok(Self)
+        Ok(Self {
+            masks: mask::MaskCache::new(),
+        })
     }
 
     fn plugin_info(&self) -> FilterPluginTable {
         FilterPluginTable {
             name: "Rusty Pixel Sort Filter".to_string(),
             label: None,
+            order: 0,
             information: format!(
                 "Pixel sort filter, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/pixelsort-filter",
                 version = env!("CARGO_PKG_VERSION")
@@ -87,17 +137,53 @@ impl FilterPlugin for PixelSortFilter {
         config: &[aviutl2::filter::FilterConfigItem],
         video: &mut FilterProcVideo,
     ) -> AnyResult<()> {
-        let config: FilterConfig = config.to_struct();
+        let mut config: FilterConfig = config.to_struct();
+        config.direction = config
+            .direction
+            .resolve(video.object.id, video.object.frame);
         let (width, height) = (
             video.video_object.width as usize,
             video.video_object.height as usize,
         );
         let mut image: Vec<RgbaPixel> = vec![RgbaPixel::default(); width * height];
         video.get_image_data(&mut image);
-        sort::pixelsort(&config, &mut image, width, height);
+        let image_mask = config
+            .mask_image
+            .as_deref()
+            .and_then(|path| self.masks.get(path))
+            .map(|mask| mask::resize_mask(&mask, width, height));
+        sort::pixelsort_with_mask(&config, &mut image, width, height, image_mask.as_deref());
         video.set_image_data(&image, video.video_object.width, video.video_object.height);
         Ok(())
     }
 }
 
 aviutl2::register_filter_plugin!(PixelSortFilter);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_direction_is_stable_per_frame() {
+        let directions = (0..64)
+            .map(|frame| SortDirection::Random.resolve(1, frame))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            directions,
+            (0..64)
+                .map(|frame| SortDirection::Random.resolve(1, frame))
+                .collect::<Vec<_>>()
+        );
+        for direction in SortDirection::CONCRETE {
+            assert!(
+                directions.contains(&direction),
+                "{direction:?} never chosen"
+            );
+        }
+        assert_eq!(
+            SortDirection::Vertical.resolve(1, 0),
+            SortDirection::Vertical
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use aviutl2::tracing;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+type MaskMap = HashMap<PathBuf, Option<Arc<image::GrayImage>>>;
+
+/// マスク画像を読み込んで保持する。
+///
+/// 読み込んだ画像は[`aviutl2::utils::FileWatcher`]で監視し、変更されたら読み込み直す。
+pub(crate) struct MaskCache {
+    masks: Arc<Mutex<MaskMap>>,
+    watcher: Option<aviutl2::utils::FileWatcher>,
+}
+
+impl MaskCache {
+    pub(crate) fn new() -> Self {
+        let masks = Arc::new(Mutex::new(MaskMap::new()));
+        let watcher =
+            aviutl2::utils::FileWatcher::new(aviutl2::utils::DEFAULT_FILE_WATCH_DEBOUNCE, {
+                let masks = Arc::clone(&masks);
+                move |path| {
+                    tracing::info!("Mask image changed: {:?}", path);
+                    let path = path.to_string_lossy();
+                    masks
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .retain(|key, _| !key.to_string_lossy().eq_ignore_ascii_case(&path));
+                }
+            })
+            .inspect_err(|err| tracing::warn!("Failed to start mask image watcher: {}", err))
+            .ok();
+        Self { masks, watcher }
+    }
+
+    /// マスク画像を取得する。読み込めなかった場合は`None`を返す。
+    pub(crate) fn get(&self, path: &Path) -> Option<Arc<image::GrayImage>> {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut masks = self.masks.lock().unwrap_or_else(|e| e.into_inner());
+        masks
+            .entry(path)
+            .or_insert_with_key(|path| {
+                if let Some(watcher) = &self.watcher
+                    && let Err(err) = watcher.watch(path)
+                {
+                    tracing::warn!("Failed to watch mask image {:?}: {}", path, err);
+                }
+                match image::open(path) {
+                    Ok(image) => Some(Arc::new(image.to_luma8())),
+                    Err(err) => {
+                        tracing::error!("Failed to load mask image from {:?}: {}", path, err);
+                        None
+                    }
+                }
+            })
+            .clone()
+    }
+}
+
+/// マスク画像を`width`x`height`に引き伸ばし、ピクセルごとにソートするかどうかを返す。
+///
+/// マスク画像の輝度が半分以上の部分をソートする。
+pub(crate) fn resize_mask(mask: &image::GrayImage, width: usize, height: usize) -> Vec<bool> {
+    let (mask_width, mask_height) = (mask.width() as usize, mask.height() as usize);
+    if mask_width == 0 || mask_height == 0 {
+        return vec![true; width * height];
+    }
+    (0..height)
+        .flat_map(|y| {
+            let mask_y = y * mask_height / height;
+            (0..width).map(move |x| {
+                let mask_x = x * mask_width / width;
+                mask.get_pixel(mask_x as u32, mask_y as u32).0[0] >= 128
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_mask_scales_nearest() {
+        let mask = image::GrayImage::from_raw(2, 1, vec![255, 0]).unwrap();
+        assert_eq!(
+            resize_mask(&mask, 4, 2),
+            [true, true, false, false, true, true, false, false]
+        );
+    }
+}
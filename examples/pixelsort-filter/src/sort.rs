@@ -3,8 +3,60 @@ use aviutl2::filter::RgbaPixel;
 use ndarray::{ArrayViewMut2, Axis};
 use rayon::prelude::*;
 
+#[inline(always)]
+fn luminance(pixel: &RgbaPixel) -> u8 {
+    ((pixel.r as u16 * 77 + pixel.g as u16 * 151 + pixel.b as u16 * 28) >> 8) as u8
+}
+
+/// 画像の各ピクセルの輝度を計算する。
+pub fn calc_luminances(pixels: &[RgbaPixel]) -> Vec<u8> {
+    pixels.par_iter().map(luminance).collect()
+}
+
+/// 1列分の（輝度, ピクセル）を、`should_sort`が`true`の区間ごとにソートする。
+///
+/// `should_sort`には列内の位置と輝度が渡されます。
+/// `false`になる位置のピクセルは動かず、区間をまたいでピクセルが移動することもありません。
+#[inline(always)]
+fn sort_line(
+    line: &mut [(u8, RgbaPixel)],
+    should_sort: impl Fn(usize, u8) -> bool,
+    inverted: bool,
+) {
+    let mut start = 0;
+    for i in 0..=line.len() {
+        let is_end_of_line = i == line.len();
+        if is_end_of_line || !should_sort(i, line[i].0) {
+            let segment = &mut line[start..i];
+            segment.sort_unstable_by_key(|k| k.0);
+            if inverted {
+                segment.reverse();
+            }
+            start = i + 1;
+        }
+    }
+}
+
+/// ピクセルソートを行う。
+///
+/// `config.direction`が[`SortDirection::Random`][crate::SortDirection::Random]の場合は左右として扱うため、
+/// 先に[`SortDirection::resolve`][crate::SortDirection::resolve]で方向を決めてください。
 #[cfg_attr(not(test), inline(always))]
 pub fn pixelsort(config: &FilterConfig, pixels: &mut [RgbaPixel], width: usize, height: usize) {
+    pixelsort_with_mask(config, pixels, width, height, None);
+}
+
+/// マスク画像を指定してピクセルソートを行う。
+///
+/// `image_mask`が`false`の位置のピクセルは、マスクしきい値で外れた場合と同じく動きません。
+#[cfg_attr(not(test), inline(always))]
+pub fn pixelsort_with_mask(
+    config: &FilterConfig,
+    pixels: &mut [RgbaPixel],
+    width: usize,
+    height: usize,
+    image_mask: Option<&[bool]>,
+) {
     let vertical = config.direction == crate::SortDirection::Vertical
         || config.direction == crate::SortDirection::VerticalInverted;
 
@@ -14,6 +66,23 @@ pub fn pixelsort(config: &FilterConfig, pixels: &mut [RgbaPixel], width: usize,
     let sort_above = config.threshold_type == crate::ThresholdType::Above;
 
     let threshold = (config.threshold * 255.0) as u8;
+    let mask_threshold = (config.mask_threshold * 255.0) as u8;
+
+    // マスクはソート前の画像から計算する。
+    // しきい値が0のときは全体がマスクに含まれるので、計算を省略する。
+    let mask = (mask_threshold > 0).then(|| calc_luminances(pixels));
+    let is_masked = |index: usize| {
+        mask.as_ref()
+            .is_none_or(|mask| mask[index] >= mask_threshold)
+            && image_mask.is_none_or(|image_mask| image_mask[index])
+    };
+    let matches_threshold = |l: u8| {
+        if sort_above {
+            l >= threshold
+        } else {
+            l < threshold
+        }
+    };
 
     if vertical {
         let mut image_array = ArrayViewMut2::from_shape((height, width), pixels).unwrap();
@@ -21,35 +90,20 @@ pub fn pixelsort(config: &FilterConfig, pixels: &mut [RgbaPixel], width: usize,
         image_array
             .axis_iter_mut(Axis(1)) // iterate over columns
             .into_par_iter()
+            .enumerate()
             .for_each_init(
                 || Vec::with_capacity(height),
-                |col_data, mut col| {
+                |col_data, (x, mut col)| {
                     col_data.clear();
                     for pixel in col.iter() {
-                        let l = ((pixel.r as u16 * 77 + pixel.g as u16 * 151 + pixel.b as u16 * 28)
-                            >> 8) as u8;
-                        col_data.push((l, *pixel));
+                        col_data.push((luminance(pixel), *pixel));
                     }
 
-                    let mut start = 0;
-                    for i in 0..=height {
-                        let is_end_of_line = i == height;
-                        let should_sort_current = i < height
-                            && if sort_above {
-                                col_data[i].0 >= threshold
-                            } else {
-                                col_data[i].0 < threshold
-                            };
-
-                        if is_end_of_line || !should_sort_current {
-                            let segment = &mut col_data[start..i];
-                            segment.sort_unstable_by_key(|k| k.0);
-                            if inverted {
-                                segment.reverse();
-                            }
-                            start = i + 1;
-                        }
-                    }
+                    sort_line(
+                        col_data,
+                        |y, l| matches_threshold(l) && is_masked(y * width + x),
+                        inverted,
+                    );
 
                     for i in 0..height {
                         col[i] = col_data[i].1;
@@ -60,36 +114,21 @@ pub fn pixelsort(config: &FilterConfig, pixels: &mut [RgbaPixel], width: usize,
         // Horizontal sort can be done in-place by processing rows in parallel.
         pixels
             .par_chunks_mut(width) // Each thread gets a mutable slice of a row.
+            .enumerate()
             .for_each_init(
                 || Vec::with_capacity(width),
-                |row_with_luminance, row_pixels| {
+                |row_with_luminance, (y, row_pixels)| {
                     row_with_luminance.clear();
                     // Inline luminance calculation for better locality
                     for pixel in row_pixels.iter() {
-                        let l = ((pixel.r as u16 * 77 + pixel.g as u16 * 151 + pixel.b as u16 * 28)
-                            >> 8) as u8;
-                        row_with_luminance.push((l, *pixel));
+                        row_with_luminance.push((luminance(pixel), *pixel));
                     }
 
-                    let mut start = 0;
-                    for i in 0..=width {
-                        let is_end_of_line = i == width;
-                        let should_sort_current = i < width
-                            && if sort_above {
-                                row_with_luminance[i].0 >= threshold
-                            } else {
-                                row_with_luminance[i].0 < threshold
-                            };
-
-                        if is_end_of_line || !should_sort_current {
-                            let segment = &mut row_with_luminance[start..i];
-                            segment.sort_unstable_by_key(|k| k.0);
-                            if inverted {
-                                segment.reverse();
-                            }
-                            start = i + 1;
-                        }
-                    }
+                    sort_line(
+                        row_with_luminance,
+                        |x, l| matches_threshold(l) && is_masked(y * width + x),
+                        inverted,
+                    );
 
                     for i in 0..width {
                         row_pixels[i] = row_with_luminance[i].1;
@@ -98,3 +137,222 @@ pub fn pixelsort(config: &FilterConfig, pixels: &mut [RgbaPixel], width: usize,
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn pixel_strategy() -> impl Strategy<Value = RgbaPixel> {
+        any::<[u8; 4]>().prop_map(|[r, g, b, a]| RgbaPixel { r, g, b, a })
+    }
+
+    fn image_strategy() -> impl Strategy<Value = (usize, usize, Vec<RgbaPixel>)> {
+        (1usize..16, 1usize..16).prop_flat_map(|(width, height)| {
+            (
+                Just(width),
+                Just(height),
+                prop::collection::vec(pixel_strategy(), width * height),
+            )
+        })
+    }
+
+    fn direction_strategy() -> impl Strategy<Value = crate::SortDirection> {
+        prop_oneof![
+            Just(crate::SortDirection::Horizontal),
+            Just(crate::SortDirection::HorizontalInverted),
+            Just(crate::SortDirection::Vertical),
+            Just(crate::SortDirection::VerticalInverted),
+        ]
+    }
+
+    /// `mask`が`true`の連続した区間（マスクの境界で区切ったもの）を返す。
+    fn masked_runs(mask: &[bool]) -> Vec<std::ops::Range<usize>> {
+        let mut runs = Vec::new();
+        let mut start = None;
+        for (i, &masked) in mask.iter().chain(std::iter::once(&false)).enumerate() {
+            match (masked, start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    runs.push(s..i);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        runs
+    }
+
+    fn sorted_pixels(pixels: impl Iterator<Item = RgbaPixel>) -> Vec<[u8; 4]> {
+        let mut pixels: Vec<[u8; 4]> = pixels.map(|p| [p.r, p.g, p.b, p.a]).collect();
+        pixels.sort_unstable();
+        pixels
+    }
+
+    /// マスク外のピクセルが動いておらず、マスク内の各区間のピクセルがその区間内に留まっていることを確認する。
+    fn assert_within_mask(before: &[RgbaPixel], after: &[RgbaPixel], mask: &[bool]) {
+        for (i, &masked) in mask.iter().enumerate() {
+            if !masked {
+                assert_eq!(before[i], after[i], "unmasked pixel at {i} moved");
+            }
+        }
+        for run in masked_runs(mask) {
+            assert_eq!(
+                sorted_pixels(before[run.clone()].iter().copied()),
+                sorted_pixels(after[run.clone()].iter().copied()),
+                "pixels crossed the mask boundary around {run:?}"
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn sort_line_never_crosses_mask_boundaries(
+            line in prop::collection::vec((pixel_strategy(), any::<bool>()), 0..64),
+            threshold in any::<u8>(),
+            inverted in any::<bool>(),
+        ) {
+            let (pixels, mask): (Vec<RgbaPixel>, Vec<bool>) = line.into_iter().unzip();
+            let mut data: Vec<(u8, RgbaPixel)> =
+                pixels.iter().map(|p| (luminance(p), *p)).collect();
+            sort_line(&mut data, |i, l| l >= threshold && mask[i], inverted);
+
+            let sorted: Vec<RgbaPixel> = data.iter().map(|(_, p)| *p).collect();
+            assert_within_mask(&pixels, &sorted, &mask);
+            // 区間内は輝度順に並んでいる
+            for run in masked_runs(&mask) {
+                let keys: Vec<u8> = data[run].iter().map(|(l, _)| *l).collect();
+                for pair in keys.windows(2) {
+                    if pair[0] >= threshold && pair[1] >= threshold {
+                        prop_assert!(if inverted { pair[0] >= pair[1] } else { pair[0] <= pair[1] });
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn pixelsort_never_crosses_mask_boundaries(
+            (width, height, image) in image_strategy(),
+            threshold in 0.0f64..=1.0,
+            mask_threshold in 0.0f64..=1.0,
+            sort_above in any::<bool>(),
+            direction in direction_strategy(),
+        ) {
+            let config = FilterConfig {
+                threshold,
+                mask_threshold,
+                threshold_type: if sort_above {
+                    crate::ThresholdType::Above
+                } else {
+                    crate::ThresholdType::Below
+                },
+                direction: direction.clone(),
+            };
+            let mask_threshold = (mask_threshold * 255.0) as u8;
+            let mask: Vec<bool> = calc_luminances(&image)
+                .into_iter()
+                .map(|l| l >= mask_threshold)
+                .collect();
+
+            let mut sorted = image.clone();
+            pixelsort(&config, &mut sorted, width, height);
+
+            let vertical = matches!(
+                direction,
+                crate::SortDirection::Vertical | crate::SortDirection::VerticalInverted
+            );
+            // 列ごとに確認できるように、上下の場合は転置する
+            let lines = |pixels: &[RgbaPixel]| -> Vec<Vec<RgbaPixel>> {
+                if vertical {
+                    (0..width)
+                        .map(|x| (0..height).map(|y| pixels[y * width + x]).collect())
+                        .collect()
+                } else {
+                    pixels.chunks(width).map(<[RgbaPixel]>::to_vec).collect()
+                }
+            };
+            let mask_lines: Vec<Vec<bool>> = if vertical {
+                (0..width)
+                    .map(|x| (0..height).map(|y| mask[y * width + x]).collect())
+                    .collect()
+            } else {
+                mask.chunks(width).map(<[bool]>::to_vec).collect()
+            };
+            for ((before, after), mask) in lines(&image)
+                .iter()
+                .zip(lines(&sorted).iter())
+                .zip(mask_lines.iter())
+            {
+                assert_within_mask(before, after, mask);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_mask_threshold_sorts_everything() {
+        let mut pixels: Vec<RgbaPixel> = [200u8, 0, 100, 50]
+            .iter()
+            .map(|&v| RgbaPixel {
+                r: v,
+                g: v,
+                b: v,
+                a: 255,
+            })
+            .collect();
+        let config = FilterConfig {
+            threshold: 0.0,
+            ..Default::default()
+        };
+        pixelsort(&config, &mut pixels, 4, 1);
+        let values: Vec<u8> = pixels.iter().map(|p| p.r).collect();
+        assert_eq!(values, [0, 50, 100, 200]);
+    }
+
+    #[test]
+    fn mask_threshold_keeps_dark_pixels_in_place() {
+        let mut pixels: Vec<RgbaPixel> = [200u8, 150, 0, 250, 100]
+            .iter()
+            .map(|&v| RgbaPixel {
+                r: v,
+                g: v,
+                b: v,
+                a: 255,
+            })
+            .collect();
+        let config = FilterConfig {
+            threshold: 0.0,
+            mask_threshold: 0.5,
+            ..Default::default()
+        };
+        pixelsort(&config, &mut pixels, 5, 1);
+        let values: Vec<u8> = pixels.iter().map(|p| p.r).collect();
+        // 0と100はマスク外なので動かず、[200, 150]と[250]がそれぞれソートされる
+        assert_eq!(values, [150, 200, 0, 250, 100]);
+    }
+
+    #[test]
+    fn image_mask_keeps_pixels_in_place() {
+        let mut pixels: Vec<RgbaPixel> = [200u8, 150, 0, 250, 100]
+            .iter()
+            .map(|&v| RgbaPixel {
+                r: v,
+                g: v,
+                b: v,
+                a: 255,
+            })
+            .collect();
+        let config = FilterConfig {
+            threshold: 0.0,
+            ..Default::default()
+        };
+        pixelsort_with_mask(
+            &config,
+            &mut pixels,
+            5,
+            1,
+            Some(&[true, true, false, true, true]),
+        );
+        let values: Vec<u8> = pixels.iter().map(|p| p.r).collect();
+        assert_eq!(values, [150, 200, 0, 100, 250]);
+    }
+}
@@ -0,0 +1,609 @@
+/// レイヤーの合成方法。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    /// グループ専用。子レイヤーをグループの外の画像に直接合成する。
+    PassThrough,
+}
+
+impl BlendMode {
+    /// psdクレートの合成方法を変換する。対応していない合成方法は通常として扱う。
+    pub fn from_psd(mode: psd::BlendMode, name: &str) -> Self {
+        match mode {
+            psd::BlendMode::Normal => BlendMode::Normal,
+            psd::BlendMode::Multiply => BlendMode::Multiply,
+            psd::BlendMode::Screen => BlendMode::Screen,
+            psd::BlendMode::PassThrough => BlendMode::PassThrough,
+            mode => {
+                aviutl2::tracing::warn!(
+                    "Blend mode {mode:?} of layer {name:?} is not supported, using normal instead"
+                );
+                BlendMode::Normal
+            }
+        }
+    }
+
+    /// 背景色`backdrop`と合成する色`source`を合成する（どちらもストレート、0.0〜1.0）。
+    fn blend(self, backdrop: f32, source: f32) -> f32 {
+        match self {
+            BlendMode::Normal | BlendMode::PassThrough => source,
+            BlendMode::Multiply => backdrop * source,
+            BlendMode::Screen => backdrop + source - backdrop * source,
+        }
+    }
+}
+
+/// 画像を持つレイヤー。
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    /// 不透明度（0.0〜1.0）。
+    pub opacity: f32,
+    pub blend_mode: BlendMode,
+    /// ドキュメントと同じ大きさのRGBA（ストレートα）。レイヤーの範囲外は透明。
+    pub rgba: Vec<u8>,
+}
+
+/// レイヤーかグループ。
+#[derive(Debug, Clone)]
+pub enum Node {
+    Layer(Layer),
+    Group {
+        name: String,
+        visible: bool,
+        opacity: f32,
+        blend_mode: BlendMode,
+        /// 下から順に並んだ子要素。
+        children: Vec<Node>,
+    },
+}
+
+impl Node {
+    pub fn name(&self) -> &str {
+        match self {
+            Node::Layer(layer) => &layer.name,
+            Node::Group { name, .. } => name,
+        }
+    }
+
+    fn visible(&self) -> bool {
+        match self {
+            Node::Layer(layer) => layer.visible,
+            Node::Group { visible, .. } => *visible,
+        }
+    }
+}
+
+/// PSDファイルのレイヤー構造。
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub width: u32,
+    pub height: u32,
+    /// 一番上の階層の要素。下から順に並ぶ。
+    pub nodes: Vec<Node>,
+}
+
+impl Document {
+    pub fn from_psd(psd: &psd::Psd) -> Self {
+        let groups = psd
+            .groups()
+            .iter()
+            .map(|group| (group.id(), group))
+            .collect::<std::collections::HashMap<_, _>>();
+        // レイヤーの親のグループを、近い順に並べたもの
+        let ancestors = psd
+            .layers()
+            .iter()
+            .map(|layer| {
+                std::iter::successors(layer.parent_id(), |id| {
+                    groups.get(id).and_then(|group| group.parent_id())
+                })
+                .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            width: psd.width(),
+            height: psd.height(),
+            nodes: build_nodes(psd, &groups, &ancestors, None),
+        }
+    }
+
+    /// トラック数。トラック0は全体の合成、トラック1以降は一番上の階層の要素を上から順に並べたもの。
+    pub fn track_count(&self) -> u32 {
+        self.nodes.len() as u32 + 1
+    }
+
+    /// トラックに対応する要素。トラック0の場合は`None`。
+    pub fn track_node(&self, track: u32) -> Option<&Node> {
+        let index = (track as usize).checked_sub(1)?;
+        self.nodes.iter().rev().nth(index)
+    }
+
+    /// トラックの画像をRGBA（ストレートα）で描画する。
+    ///
+    /// トラック0は表示されているすべての要素を、それ以外はその要素だけを透明な画像に合成します。
+    /// 要素だけを描画する場合は、非表示になっていても描画します。
+    pub fn render(&self, track: u32) -> Option<Vec<u8>> {
+        let mut canvas = Canvas::new(self.width, self.height);
+        if track == 0 {
+            canvas.composite_all(&self.nodes);
+        } else {
+            canvas.composite_node(self.track_node(track)?);
+        }
+        Some(canvas.into_rgba())
+    }
+}
+
+fn build_nodes(
+    psd: &psd::Psd,
+    groups: &std::collections::HashMap<u32, &psd::PsdGroup>,
+    ancestors: &[Vec<u32>],
+    parent: Option<u32>,
+) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    let mut emitted_groups = std::collections::HashSet::new();
+    for (layer, ancestors) in psd.layers().iter().zip(ancestors) {
+        // `parent`の直下にある要素を探す
+        let depth = match parent {
+            None => ancestors.len(),
+            Some(parent) => match ancestors.iter().position(|&id| id == parent) {
+                Some(depth) => depth,
+                None => continue,
+            },
+        };
+        if depth == 0 {
+            let name = layer.name().to_string();
+            nodes.push(Node::Layer(Layer {
+                blend_mode: BlendMode::from_psd(layer.blend_mode(), &name),
+                visible: layer.visible(),
+                opacity: layer.opacity() as f32 / 255.0,
+                rgba: layer.rgba(),
+                name,
+            }));
+            continue;
+        }
+        let group_id = ancestors[depth - 1];
+        if !emitted_groups.insert(group_id) {
+            continue;
+        }
+        let Some(group) = groups.get(&group_id) else {
+            continue;
+        };
+        let name = group.name().to_string();
+        nodes.push(Node::Group {
+            blend_mode: BlendMode::from_psd(group.blend_mode(), &name),
+            visible: group.visible(),
+            opacity: group.opacity() as f32 / 255.0,
+            children: build_nodes(psd, groups, ancestors, Some(group_id)),
+            name,
+        });
+    }
+    nodes
+}
+
+/// 乗算済みαのRGBAで合成するための画像。
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f32; 4]>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0.0; 4]; width as usize * height as usize],
+        }
+    }
+
+    fn composite_all(&mut self, nodes: &[Node]) {
+        for node in nodes.iter().filter(|node| node.visible()) {
+            self.composite_node(node);
+        }
+    }
+
+    fn composite_node(&mut self, node: &Node) {
+        match node {
+            Node::Layer(layer) => {
+                let source = layer.rgba.chunks_exact(4).map(|pixel| {
+                    let alpha = pixel[3] as f32 / 255.0;
+                    [
+                        pixel[0] as f32 / 255.0 * alpha,
+                        pixel[1] as f32 / 255.0 * alpha,
+                        pixel[2] as f32 / 255.0 * alpha,
+                        alpha,
+                    ]
+                });
+                self.composite_pixels(source, layer.opacity, layer.blend_mode);
+            }
+            Node::Group {
+                opacity,
+                blend_mode: BlendMode::PassThrough,
+                children,
+                ..
+            } => {
+                // グループの外の画像にそのまま合成し、グループの不透明度で元の画像と混ぜる
+                let backdrop = self.pixels.clone();
+                self.composite_all(children);
+                for (pixel, backdrop) in self.pixels.iter_mut().zip(backdrop) {
+                    for (value, backdrop) in pixel.iter_mut().zip(backdrop) {
+                        *value = backdrop + (*value - backdrop) * opacity;
+                    }
+                }
+            }
+            Node::Group {
+                opacity,
+                blend_mode,
+                children,
+                ..
+            } => {
+                let mut group = Canvas::new(self.width, self.height);
+                group.composite_all(children);
+                self.composite_pixels(group.pixels.into_iter(), *opacity, *blend_mode);
+            }
+        }
+    }
+
+    /// 乗算済みαの画像を合成する。
+    fn composite_pixels(
+        &mut self,
+        source: impl Iterator<Item = [f32; 4]>,
+        opacity: f32,
+        blend_mode: BlendMode,
+    ) {
+        for (backdrop, source) in self.pixels.iter_mut().zip(source) {
+            let source_alpha = source[3] * opacity;
+            if source_alpha == 0.0 {
+                continue;
+            }
+            let backdrop_alpha = backdrop[3];
+            for i in 0..3 {
+                let source_color = source[i] / source[3];
+                let backdrop_color = if backdrop_alpha > 0.0 {
+                    backdrop[i] / backdrop_alpha
+                } else {
+                    0.0
+                };
+                // 背景が透明な部分では合成方法によらずそのままの色になる
+                let mixed = (1.0 - backdrop_alpha) * source_color
+                    + backdrop_alpha * blend_mode.blend(backdrop_color, source_color);
+                backdrop[i] = source_alpha * mixed + (1.0 - source_alpha) * backdrop[i];
+            }
+            backdrop[3] = source_alpha + backdrop_alpha * (1.0 - source_alpha);
+        }
+    }
+
+    fn into_rgba(self) -> Vec<u8> {
+        let to_byte = |value: f32| (value * 255.0).round().clamp(0.0, 255.0) as u8;
+        self.pixels
+            .into_iter()
+            .flat_map(|[r, g, b, a]| {
+                if a == 0.0 {
+                    [0; 4]
+                } else {
+                    [to_byte(r / a), to_byte(g / a), to_byte(b / a), to_byte(a)]
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用のPSDファイルを作る。
+    ///
+    /// RGB・8bit・無圧縮のみ。`layers`は下から順に並べ、グループは`FixtureLayer::GroupEnd`から
+    /// `FixtureLayer::Group`までの間に子レイヤーを並べる（PSDファイル内の並びと同じ）。
+    mod fixture {
+        pub enum FixtureLayer {
+            Image {
+                name: &'static str,
+                left: i32,
+                right: i32,
+                color: [u8; 4],
+                opacity: u8,
+                blend: &'static [u8; 4],
+            },
+            GroupEnd,
+            Group {
+                name: &'static str,
+                opacity: u8,
+                blend: &'static [u8; 4],
+            },
+        }
+
+        fn pascal_string(name: &str) -> Vec<u8> {
+            let mut data = vec![name.len() as u8];
+            data.extend_from_slice(name.as_bytes());
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+            data
+        }
+
+        pub fn build(width: u32, height: u32, layers: &[FixtureLayer]) -> Vec<u8> {
+            let mut records = Vec::new();
+            let mut channel_data = Vec::new();
+            for layer in layers {
+                let (name, rect, opacity, blend, section) = match *layer {
+                    FixtureLayer::Image {
+                        name,
+                        left,
+                        right,
+                        opacity,
+                        blend,
+                        ..
+                    } => (name, [0, left, height as i32, right], opacity, blend, None),
+                    FixtureLayer::GroupEnd => ("</Layer group>", [0; 4], 255, b"norm", Some(3u32)),
+                    FixtureLayer::Group {
+                        name,
+                        opacity,
+                        blend,
+                    } => (name, [0; 4], opacity, blend, Some(1)),
+                };
+                let pixels = ((rect[2] - rect[0]) * (rect[3] - rect[1])) as usize;
+
+                for value in rect {
+                    records.extend_from_slice(&value.to_be_bytes());
+                }
+                records.extend_from_slice(&4u16.to_be_bytes());
+                for channel in [-1i16, 0, 1, 2] {
+                    records.extend_from_slice(&channel.to_be_bytes());
+                    records.extend_from_slice(&(2 + pixels as u32).to_be_bytes());
+                }
+                records.extend_from_slice(b"8BIM");
+                records.extend_from_slice(blend);
+                records.extend_from_slice(&[opacity, 0, 0, 0]);
+
+                let mut extra = Vec::new();
+                extra.extend_from_slice(&0u32.to_be_bytes()); // レイヤーマスク
+                extra.extend_from_slice(&0u32.to_be_bytes()); // 合成範囲
+                extra.extend_from_slice(&pascal_string(name));
+                if let Some(section) = section {
+                    extra.extend_from_slice(b"8BIMlsct");
+                    extra.extend_from_slice(&12u32.to_be_bytes());
+                    extra.extend_from_slice(&section.to_be_bytes());
+                    extra.extend_from_slice(b"8BIM");
+                    extra.extend_from_slice(blend);
+                }
+                records.extend_from_slice(&(extra.len() as u32).to_be_bytes());
+                records.extend_from_slice(&extra);
+
+                let color = match *layer {
+                    FixtureLayer::Image { color, .. } => color,
+                    _ => [0; 4],
+                };
+                for channel in [3, 0, 1, 2] {
+                    channel_data.extend_from_slice(&0u16.to_be_bytes());
+                    channel_data.extend(std::iter::repeat_n(color[channel], pixels));
+                }
+            }
+
+            let mut layer_info = Vec::new();
+            layer_info.extend_from_slice(&(layers.len() as i16).to_be_bytes());
+            layer_info.extend_from_slice(&records);
+            layer_info.extend_from_slice(&channel_data);
+            if layer_info.len() % 2 != 0 {
+                layer_info.push(0);
+            }
+
+            let mut data = Vec::new();
+            data.extend_from_slice(b"8BPS");
+            data.extend_from_slice(&1u16.to_be_bytes());
+            data.extend_from_slice(&[0; 6]);
+            data.extend_from_slice(&4u16.to_be_bytes());
+            data.extend_from_slice(&height.to_be_bytes());
+            data.extend_from_slice(&width.to_be_bytes());
+            data.extend_from_slice(&8u16.to_be_bytes());
+            data.extend_from_slice(&3u16.to_be_bytes());
+            data.extend_from_slice(&0u32.to_be_bytes()); // カラーモードデータ
+            data.extend_from_slice(&0u32.to_be_bytes()); // 画像リソース
+            data.extend_from_slice(&(layer_info.len() as u32 + 8).to_be_bytes());
+            data.extend_from_slice(&(layer_info.len() as u32).to_be_bytes());
+            data.extend_from_slice(&layer_info);
+            data.extend_from_slice(&0u32.to_be_bytes()); // グローバルレイヤーマスク
+            // 統合画像（このテストでは使わないので透明）
+            data.extend_from_slice(&0u16.to_be_bytes());
+            data.extend(std::iter::repeat_n(0, width as usize * height as usize * 4));
+            data
+        }
+    }
+
+    use fixture::FixtureLayer;
+
+    fn fixture_document() -> Document {
+        let psd = fixture::build(
+            4,
+            1,
+            &[
+                FixtureLayer::Image {
+                    name: "Background",
+                    left: 0,
+                    right: 4,
+                    color: [255, 0, 0, 255],
+                    opacity: 255,
+                    blend: b"norm",
+                },
+                FixtureLayer::GroupEnd,
+                FixtureLayer::Image {
+                    name: "Multiply",
+                    left: 0,
+                    right: 2,
+                    color: [128, 128, 128, 255],
+                    opacity: 255,
+                    blend: b"mul ",
+                },
+                FixtureLayer::Image {
+                    name: "Screen",
+                    left: 1,
+                    right: 3,
+                    color: [0, 0, 255, 255],
+                    opacity: 255,
+                    blend: b"scrn",
+                },
+                FixtureLayer::Group {
+                    name: "PassThrough",
+                    opacity: 255,
+                    blend: b"pass",
+                },
+                FixtureLayer::Image {
+                    name: "Translucent",
+                    left: 3,
+                    right: 4,
+                    color: [255, 255, 255, 255],
+                    opacity: 128,
+                    blend: b"norm",
+                },
+            ],
+        );
+        Document::from_psd(&psd::Psd::from_bytes(&psd).unwrap())
+    }
+
+    fn pixels(rgba: &[u8]) -> Vec<[u8; 4]> {
+        rgba.chunks_exact(4)
+            .map(|pixel| pixel.try_into().unwrap())
+            .collect()
+    }
+
+    fn layer(name: &str, color: [u8; 4], width: usize, blend_mode: BlendMode) -> Node {
+        Node::Layer(Layer {
+            name: name.to_string(),
+            visible: true,
+            opacity: 1.0,
+            blend_mode,
+            rgba: color.repeat(width),
+        })
+    }
+
+    #[test]
+    fn test_fixture_layer_structure() {
+        let document = fixture_document();
+        assert_eq!((document.width, document.height), (4, 1));
+        assert_eq!(document.track_count(), 4);
+        let names = (1..document.track_count())
+            .map(|track| document.track_node(track).unwrap().name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Translucent", "PassThrough", "Background"]);
+        match document.track_node(2).unwrap() {
+            Node::Group {
+                blend_mode,
+                children,
+                ..
+            } => {
+                assert_eq!(*blend_mode, BlendMode::PassThrough);
+                let children = children.iter().map(Node::name).collect::<Vec<_>>();
+                assert_eq!(children, ["Multiply", "Screen"]);
+            }
+            node => panic!("unexpected node: {node:?}"),
+        }
+        assert!(document.track_node(0).is_none());
+        assert!(document.track_node(4).is_none());
+    }
+
+    #[test]
+    fn test_fixture_composite() {
+        let document = fixture_document();
+        assert_eq!(
+            pixels(&document.render(0).unwrap()),
+            [
+                [128, 0, 0, 255],
+                [128, 0, 255, 255],
+                [255, 0, 255, 255],
+                [255, 128, 128, 255],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixture_single_tracks() {
+        let document = fixture_document();
+        // グループだけを描画する場合は、透明な画像に合成する
+        assert_eq!(
+            pixels(&document.render(2).unwrap()),
+            [
+                [128, 128, 128, 255],
+                [128, 128, 255, 255],
+                [0, 0, 255, 255],
+                [0, 0, 0, 0],
+            ]
+        );
+        assert_eq!(
+            pixels(&document.render(1).unwrap()),
+            [
+                [0, 0, 0, 0],
+                [0, 0, 0, 0],
+                [0, 0, 0, 0],
+                [255, 255, 255, 128]
+            ]
+        );
+        assert!(document.render(4).is_none());
+    }
+
+    #[test]
+    fn test_normal_group_is_isolated() {
+        let document = Document {
+            width: 2,
+            height: 1,
+            nodes: vec![
+                layer("Background", [255, 0, 0, 255], 2, BlendMode::Normal),
+                Node::Group {
+                    name: "Group".to_string(),
+                    visible: true,
+                    opacity: 1.0,
+                    blend_mode: BlendMode::Normal,
+                    children: vec![layer(
+                        "Multiply",
+                        [128, 128, 128, 255],
+                        2,
+                        BlendMode::Multiply,
+                    )],
+                },
+            ],
+        };
+        // 通常のグループの中の乗算は、グループの外の画像とは合成されない
+        assert_eq!(
+            pixels(&document.render(0).unwrap()),
+            [[128, 128, 128, 255]; 2]
+        );
+
+        let document = Document {
+            nodes: vec![
+                document.nodes[0].clone(),
+                Node::Group {
+                    name: "Group".to_string(),
+                    visible: true,
+                    opacity: 1.0,
+                    blend_mode: BlendMode::Multiply,
+                    children: vec![layer("Gray", [128, 128, 128, 255], 2, BlendMode::Normal)],
+                },
+            ],
+            ..document
+        };
+        assert_eq!(pixels(&document.render(0).unwrap()), [[128, 0, 0, 255]; 2]);
+    }
+
+    #[test]
+    fn test_hidden_layer_is_only_rendered_on_its_track() {
+        let mut hidden = layer("Hidden", [0, 255, 0, 255], 1, BlendMode::Normal);
+        if let Node::Layer(layer) = &mut hidden {
+            layer.visible = false;
+        }
+        let document = Document {
+            width: 1,
+            height: 1,
+            nodes: vec![
+                layer("Background", [255, 0, 0, 255], 1, BlendMode::Normal),
+                hidden,
+            ],
+        };
+        assert_eq!(pixels(&document.render(0).unwrap()), [[255, 0, 0, 255]]);
+        assert_eq!(pixels(&document.render(1).unwrap()), [[0, 255, 0, 255]]);
+    }
+}
@@ -0,0 +1,148 @@
+mod document;
+
+use aviutl2::input::{
+    AnyResult, ImageReturner, InputInfo, InputPixelFormat, InputPlugin, InputPluginTable,
+    Rational32, TrackInfo, TrackKind, VideoInputInfo,
+};
+use std::sync::{Arc, Mutex};
+
+#[aviutl2::plugin(InputPlugin)]
+struct PsdInputPlugin {}
+
+struct PsdHandle {
+    document: document::Document,
+    /// 描画済みのトラックの画像（BGRA、下から上）。
+    rendered: Mutex<std::collections::HashMap<u32, Arc<Vec<u8>>>>,
+}
+
+impl PsdHandle {
+    fn bgra(&self, track: u32) -> AnyResult<Arc<Vec<u8>>> {
+        if let Some(rendered) = self.rendered.lock().unwrap().get(&track) {
+            return Ok(Arc::clone(rendered));
+        }
+        let mut image = self
+            .document
+            .render(track)
+            .ok_or_else(|| anyhow::anyhow!("Track {track} does not exist"))?;
+        aviutl2::utils::flip_vertical(
+            &mut image,
+            self.document.width as usize * 4,
+            self.document.height as usize,
+        );
+        aviutl2::utils::rgba_to_bgra_bytes(&mut image);
+        let image = Arc::new(image);
+        self.rendered
+            .lock()
+            .unwrap()
+            .insert(track, Arc::clone(&image));
+        Ok(image)
+    }
+}
+
+impl InputPlugin for PsdInputPlugin {
+    type InputHandle = PsdHandle;
+
+    fn new(_info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
+        Ok(Self {})
+    }
+
+    fn plugin_info(&self) -> InputPluginTable {
+        InputPluginTable {
+            name: "Rusty PSD Input".to_string(),
+            input_type: aviutl2::input::InputType::Video,
+            file_filters: aviutl2::file_filters! {
+                "Photoshop Files" => ["psd"],
+            },
+            information: format!(
+                "PSD Input with layer selection, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/psd-input",
+                version = env!("CARGO_PKG_VERSION")
+            ),
+            fail_on_read_error: false,
+            can_config: false,
+            concurrency: aviutl2::input::ConcurrencyMode::full::<PsdHandle>(),
+            // 描画するトラックはImageReturner::trackで受け取るので、別のトラックを選んだオブジェクト同士でも共有できる
+            share_handles: true,
+            cache_input_info: false,
+        }
+    }
+
+    fn open(&self, file: std::path::PathBuf) -> AnyResult<Self::InputHandle> {
+        let content = std::fs::read(&file)?;
+        let psd = psd::Psd::from_bytes(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse PSD file: {e}"))?;
+        Ok(PsdHandle {
+            document: document::Document::from_psd(&psd),
+            rendered: Mutex::new(std::collections::HashMap::new()),
+        })
+    }
+
+    fn close(&self, _handle: Self::InputHandle) -> AnyResult<()> {
+        Ok(())
+    }
+
+    fn get_track_count(&self, handle: &mut Self::InputHandle) -> AnyResult<(u32, u32)> {
+        Ok((handle.document.track_count(), 0))
+    }
+
+    fn track_info(
+        &self,
+        handle: &mut Self::InputHandle,
+        kind: TrackKind,
+        index: u32,
+    ) -> AnyResult<TrackInfo> {
+        if kind != TrackKind::Video {
+            return Ok(TrackInfo::numbered(kind, index));
+        }
+        let name = match handle.document.track_node(index) {
+            None => "合成".to_string(),
+            Some(node) => node.name().to_string(),
+        };
+        Ok(TrackInfo {
+            name,
+            language: None,
+            default: index == 0,
+        })
+    }
+
+    fn can_set_video_track(&self, handle: &mut Self::InputHandle, track: u32) -> AnyResult<u32> {
+        anyhow::ensure!(
+            track < handle.document.track_count(),
+            "Track {track} does not exist"
+        );
+        Ok(track)
+    }
+
+    fn get_input_info(
+        &self,
+        handle: &mut Self::InputHandle,
+        _video_track: u32,
+        _audio_track: u32,
+    ) -> AnyResult<InputInfo> {
+        Ok(InputInfo {
+            video: Some(VideoInputInfo {
+                fps: Rational32::new(1, 1),
+                num_frames: 1,
+                manual_frame_index: false,
+                width: handle.document.width,
+                height: handle.document.height,
+                format: InputPixelFormat::Bgra,
+                alpha: aviutl2::input::AlphaMode::Straight,
+                colorimetry: aviutl2::pixel::Colorimetry::HOST,
+            }),
+            audio: None,
+        })
+    }
+
+    fn read_video(
+        &self,
+        handle: &Self::InputHandle,
+        _frame: u32,
+        returner: &mut ImageReturner,
+    ) -> AnyResult<()> {
+        let image = handle.bgra(returner.track())?;
+        returner.write(&image.as_slice());
+        Ok(())
+    }
+}
+
+aviutl2::register_input_plugin!(PsdInputPlugin);
@@ -61,6 +61,7 @@ impl FilterPlugin for RandomColorFilter {
         FilterPluginTable {
             name: "Rusty Random Color Filter".to_string(),
             label: None,
+            order: 0,
             information: format!(
                 "Example render filter plugin, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/wgsl-filter",
                 version = env!("CARGO_PKG_VERSION")
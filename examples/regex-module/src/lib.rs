@@ -14,7 +14,12 @@ impl aviutl2::module::ScriptModule for RegexModule {
                 "Regex for AviUtl2, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/regex-module",
                 version = env!("CARGO_PKG_VERSION")
             ),
+            name: None,
             functions: Self::functions(),
+            include_introspection: true,
+            default_time_budget: None,
+            constants: Vec::new(),
+            enable_shared_store: false,
         }
     }
 }
@@ -2,9 +2,20 @@ use aviutl2::{anyhow, config::translate as tr, tracing};
 use aviutl2_eframe::{AviUtl2EframeHandle, eframe, egui};
 use itertools::Itertools;
 
+/// 次のフレームで検索ボックスにフォーカスを移すかどうか。
+static FOCUS_SEARCH_REQUESTED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+/// 次のフレームで検索ボックスにフォーカスを移す。
+pub(crate) fn request_search_focus() {
+    FOCUS_SEARCH_REQUESTED.store(true, std::sync::atomic::Ordering::Relaxed);
+}
+
 pub(crate) struct ScriptsSearchApp {
     show_info: bool,
     show_click_behavior_settings: bool,
+    show_log_console: bool,
+    log_console: aviutl2_eframe::widgets::LogConsole,
     version: String,
     handle: AviUtl2EframeHandle,
     header_collapsed: bool,
@@ -16,6 +27,18 @@ pub(crate) struct ScriptsSearchApp {
     matcher: nucleo_matcher::Matcher,
     needle: String,
     last_match: Option<(String, Vec<EffectMatchInfo>)>,
+    selection: crate::keyboard::ResultSelection,
+    /// 前のフレームでの検索結果のスクロール位置と、表示範囲の高さ。
+    results_viewport: (f32, f32),
+}
+
+/// このフレームで押された、検索結果を操作するキー。
+#[derive(Debug, Clone, Copy, Default)]
+struct ResultKeys {
+    /// 選択の移動量。
+    delta: isize,
+    /// Enterが押された場合は、Shiftが押されていたかどうか。
+    apply: Option<bool>,
 }
 
 fn play_beep() {
@@ -124,6 +147,8 @@ impl ScriptsSearchApp {
         Self {
             show_info: false,
             show_click_behavior_settings: false,
+            show_log_console: false,
+            log_console: aviutl2_eframe::widgets::LogConsole::new(),
             version: env!("CARGO_PKG_VERSION").to_string(),
             handle,
             header_collapsed,
@@ -134,12 +159,32 @@ impl ScriptsSearchApp {
             matcher: nucleo_matcher::Matcher::new(config),
             needle: String::new(),
             last_match: None,
+            selection: crate::keyboard::ResultSelection::default(),
+            results_viewport: (0.0, 0.0),
         }
     }
 }
 
 impl eframe::App for ScriptsSearchApp {
     fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        // NOTE: ポップアップが開いている場合は、Escでポップアップだけを閉じる
+        if !egui::Popup::is_any_open(ui.ctx())
+            && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape))
+        {
+            if self.show_info || self.show_click_behavior_settings || self.show_log_console {
+                self.show_info = false;
+                self.show_click_behavior_settings = false;
+                self.show_log_console = false;
+            } else {
+                crate::hide_window();
+            }
+        }
+        // NOTE: 不具合の調査用に、Ctrl+Shift+Lでログを表示する（ツールバーには出さない）
+        if ui.input_mut(|i| {
+            i.consume_key(egui::Modifiers::CTRL | egui::Modifiers::SHIFT, egui::Key::L)
+        }) {
+            self.show_log_console = !self.show_log_console;
+        }
         if self.header_collapsed {
             self.render_collapsed_header(ui);
         } else {
@@ -148,6 +193,7 @@ impl eframe::App for ScriptsSearchApp {
         self.render_main_panel(ui);
         self.render_info_window(ui);
         self.render_click_behavior_settings_window(ui);
+        self.render_log_console_window(ui);
         ui.data_mut(|data| {
             data.insert_persisted(
                 egui::Id::new("header_collapsed_scripts_search"),
@@ -253,7 +299,16 @@ impl ScriptsSearchApp {
     }
 
     fn render_main_panel(&mut self, ui: &mut egui::Ui) {
-        egui::CentralPanel::default().show(ui, |ui| match crate::EFFECTS.get() {
+        // NOTE: クリック時に履歴を書き込むため、描画中はロックを保持しない
+        let (effects, history, search_history) = {
+            let store = crate::STORE.read().unwrap();
+            (
+                store.effects.clone(),
+                store.history.clone(),
+                store.search_history.queries.clone(),
+            )
+        };
+        egui::CentralPanel::default().show(ui, |ui| match effects {
             None => {
                 ui.label(tr("エフェクト情報を読み込み中..."));
 
@@ -265,16 +320,53 @@ impl ScriptsSearchApp {
                 let count_label = tr("登録されているエフェクト数: {count}");
                 ui.label(count_label.replace("{count}", &effects.effects.len().to_string()));
                 ui.add_space(8.0);
+                let keys = self.consume_result_keys(ui);
                 ui.horizontal(|ui| {
                     let font_height = ui.text_style_height(&egui::TextStyle::Body);
                     let button_side = font_height + 2.0 * ui.spacing().button_padding.y;
-                    let te_width =
-                        (ui.available_width() - button_side - ui.spacing().item_spacing.x).max(0.0);
+                    let te_width = (ui.available_width()
+                        - 2.0 * (button_side + ui.spacing().item_spacing.x))
+                        .max(0.0);
                     let te = egui::TextEdit::singleline(&mut self.needle)
                         .desired_width(te_width)
                         .hint_text(tr("検索..."))
                         .show(ui);
+                    if te.response.changed() {
+                        self.selection.reset();
+                    }
+                    if FOCUS_SEARCH_REQUESTED.swap(false, std::sync::atomic::Ordering::Relaxed) {
+                        te.response.request_focus();
+                        // 開き直したときにすぐ入力し直せるように、全体を選択する
+                        let mut state = te.state.clone();
+                        state
+                            .cursor
+                            .set_char_range(Some(egui::text::CCursorRange::two(
+                                egui::text::CCursor::new(0),
+                                egui::text::CCursor::new(self.needle.chars().count()),
+                            )));
+                        state.store(ui.ctx(), te.response.id);
+                    }
                     let actual_height = te.response.rect.height();
+                    let history_button = ui
+                        .add_enabled_ui(!search_history.is_empty(), |ui| {
+                            ui.add_sized(
+                                egui::vec2(actual_height, actual_height),
+                                egui::Button::image(include_iconify!("mdi:history"))
+                                    .image_tint_follows_text_color(true),
+                            )
+                        })
+                        .inner
+                        .on_hover_cursor(egui::CursorIcon::PointingHand)
+                        .on_hover_text(tr("検索履歴"));
+                    egui::Popup::menu(&history_button).show(|ui| {
+                        for query in &search_history {
+                            if ui.button(query.as_str()).clicked() {
+                                self.needle = query.clone();
+                                self.selection.reset();
+                                request_search_focus();
+                            }
+                        }
+                    });
                     let clicked = ui
                         .add_enabled_ui(!self.needle.is_empty(), |ui| {
                             ui.add_sized(
@@ -289,28 +381,101 @@ impl ScriptsSearchApp {
                         .clicked();
                     if clicked {
                         self.needle.clear();
+                        self.selection.reset();
                     }
                 });
                 ui.add_space(8.0);
-                self.render_effects_list(ui, &effects.effects);
+                self.render_effects_list(ui, &effects.effects, &history, keys);
             }
         });
     }
 
-    fn render_effects_list(&mut self, ui: &mut egui::Ui, effects: &[crate::EffectData]) {
+    /// 検索結果を操作するキーを読み取る。
+    ///
+    /// 検索ボックスに入力されたり、検索ボックスのフォーカスが外れたりしないように、キーは消費します。
+    fn consume_result_keys(&self, ui: &egui::Ui) -> ResultKeys {
+        if self.show_info || self.show_click_behavior_settings || self.needle.is_empty() {
+            return ResultKeys::default();
+        }
+        ui.input_mut(|i| {
+            let mut keys = ResultKeys::default();
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+                keys.delta += 1;
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+                keys.delta -= 1;
+            }
+            // NOTE: consume_keyは余分なShiftを無視するので、Shift+Enterを先に判定する
+            if i.consume_key(egui::Modifiers::SHIFT, egui::Key::Enter) {
+                keys.apply = Some(true);
+            } else if i.consume_key(egui::Modifiers::NONE, egui::Key::Enter) {
+                keys.apply = Some(false);
+            }
+            keys
+        })
+    }
+
+    fn render_effects_list(
+        &mut self,
+        ui: &mut egui::Ui,
+        effects: &[crate::EffectData],
+        history: &crate::history::EffectHistory,
+        keys: ResultKeys,
+    ) {
         if self.needle.is_empty() {
-            self.render_all_effects(ui, effects);
+            self.render_all_effects(ui, effects, history);
         } else {
-            self.render_filtered_effects(ui, effects);
+            self.render_filtered_effects(ui, effects, history, keys);
         }
     }
 
-    fn render_all_effects(&self, ui: &mut egui::Ui, effects: &[crate::EffectData]) {
-        self.render_effect_cards_rows(ui, effects.len(), |ui, row| {
-            self.render_effect_card(ui, &effects[row], None);
+    fn render_all_effects(
+        &self,
+        ui: &mut egui::Ui,
+        effects: &[crate::EffectData],
+        history: &crate::history::EffectHistory,
+    ) {
+        let recent_effects = history
+            .recent
+            .iter()
+            .filter_map(|recent| effects.iter().find(|e| e.effect.name == recent.name))
+            .collect::<Vec<_>>();
+        let mut rows = Vec::with_capacity(effects.len() + recent_effects.len() + 2);
+        if !recent_effects.is_empty() {
+            rows.push(EffectRow::Header(tr("最近使った")));
+            rows.extend(recent_effects.into_iter().map(EffectRow::Effect));
+            rows.push(EffectRow::Header(tr("すべてのエフェクト")));
+        }
+        let mut all_effects = effects.iter().collect::<Vec<_>>();
+        all_effects.sort_by_key(|effect| !history.is_favorite(&effect.effect.name));
+        rows.extend(all_effects.into_iter().map(EffectRow::Effect));
+
+        self.render_effect_cards_rows(ui, rows.len(), None, |ui, row| match &rows[row] {
+            EffectRow::Header(label) => Self::render_section_header(ui, label),
+            EffectRow::Effect(effect) => {
+                self.render_effect_card(
+                    ui,
+                    effect,
+                    None,
+                    history.is_favorite(&effect.effect.name),
+                    false,
+                );
+            }
         });
     }
 
+    fn render_section_header(ui: &mut egui::Ui, label: &str) {
+        let available_width = ui.available_width();
+        ui.allocate_ui_with_layout(
+            egui::vec2(available_width, Self::effect_card_height(ui)),
+            egui::Layout::left_to_right(egui::Align::Max),
+            |ui| {
+                ui.set_min_size(egui::vec2(available_width, Self::effect_card_height(ui)));
+                ui.add(egui::Label::new(egui::RichText::new(label).strong()).selectable(false));
+            },
+        );
+    }
+
     fn filter_effects(&mut self, effects: &[crate::EffectData]) -> Vec<EffectMatchInfo> {
         if self
             .last_match
@@ -360,37 +525,90 @@ impl ScriptsSearchApp {
         sorted_effects
     }
 
-    fn render_filtered_effects(&mut self, ui: &mut egui::Ui, effects: &[crate::EffectData]) {
-        let sorted_effects = self.filter_effects(effects);
-        if sorted_effects.is_empty() {
+    fn render_filtered_effects(
+        &mut self,
+        ui: &mut egui::Ui,
+        effects: &[crate::EffectData],
+        history: &crate::history::EffectHistory,
+        keys: ResultKeys,
+    ) {
+        // NOTE: 履歴は頻繁に変わるので、並べ替えはキャッシュせずに毎回行う
+        let mut sorted_effects = self.filter_effects(effects);
+        history.rank(
+            &mut sorted_effects,
+            |m| m.effect.effect.name.as_str(),
+            |a, b| a.partial_cmp(b).unwrap(),
+        );
+        let visible_effects = sorted_effects.iter().take(100).collect::<Vec<_>>();
+        if keys.delta != 0 {
+            self.selection.move_by(keys.delta, visible_effects.len());
+        }
+        let selected = self.selection.index(visible_effects.len());
+        let mut applied = false;
+        if let (Some(shift), Some(index)) = (keys.apply, selected) {
+            applied = Self::apply_with_keyboard(&visible_effects[index].effect, shift);
+        }
+        if visible_effects.is_empty() {
             ui.label(tr("一致するエフェクトが見つかりませんでした。"));
         } else {
-            let visible_effects = sorted_effects.iter().take(100).collect::<Vec<_>>();
-            self.render_effect_cards_rows(ui, visible_effects.len(), |ui, row| {
-                let effect = visible_effects[row];
-                self.render_effect_card(ui, &effect.effect, Some(effect));
-            });
+            let scroll_to_row = selected.filter(|_| self.selection.take_scroll_request());
+            let output = self.render_effect_cards_rows(
+                ui,
+                visible_effects.len(),
+                scroll_to_row,
+                |ui, row| {
+                    let effect = visible_effects[row];
+                    applied |= self.render_effect_card(
+                        ui,
+                        &effect.effect,
+                        Some(effect),
+                        history.is_favorite(&effect.effect.effect.name),
+                        selected == Some(row),
+                    );
+                },
+            );
+            self.results_viewport = (output.state.offset.y, output.inner_rect.height());
+        }
+        if applied {
+            crate::record_search_query(&self.needle);
         }
     }
 
+    /// 行を並べて表示する。
+    ///
+    /// `scroll_to_row`を指定した場合は、その行が表示範囲に収まるようにスクロールします。
     fn render_effect_cards_rows(
         &self,
         ui: &mut egui::Ui,
         total_rows: usize,
+        scroll_to_row: Option<usize>,
         mut render_row: impl FnMut(&mut egui::Ui, usize),
-    ) {
+    ) -> egui::scroll_area::ScrollAreaOutput<()> {
         ui.scope(|ui| {
             ui.spacing_mut().item_spacing.y = 4.0;
             let row_height = Self::effect_card_height(ui);
-            egui::ScrollArea::vertical()
+            let mut scroll_area = egui::ScrollArea::vertical()
                 .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysVisible)
-                .auto_shrink([false, false])
-                .show_rows(ui, row_height, total_rows, |ui, row_range| {
-                    for row in row_range {
-                        render_row(ui, row);
-                    }
-                });
-        });
+                .auto_shrink([false, false]);
+            let (offset, viewport_height) = self.results_viewport;
+            if let Some(offset) = scroll_to_row.and_then(|row| {
+                crate::keyboard::scroll_offset_to_show(
+                    row,
+                    row_height,
+                    ui.spacing().item_spacing.y,
+                    offset,
+                    viewport_height,
+                )
+            }) {
+                scroll_area = scroll_area.vertical_scroll_offset(offset);
+            }
+            scroll_area.show_rows(ui, row_height, total_rows, |ui, row_range| {
+                for row in row_range {
+                    render_row(ui, row);
+                }
+            })
+        })
+        .inner
     }
 
     fn render_info_window(&mut self, ui: &mut egui::Ui) {
@@ -443,15 +661,38 @@ impl ScriptsSearchApp {
         }
     }
 
+    fn render_log_console_window(&mut self, ui: &mut egui::Ui) {
+        if !self.show_log_console {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new(tr("ログ"))
+            .open(&mut open)
+            .default_size(egui::vec2(480.0, 320.0))
+            .order(egui::Order::Foreground)
+            .show(ui, |ui| self.log_console.ui(ui));
+        if !open {
+            self.show_log_console = false;
+        }
+    }
+
+    /// エフェクトのカードを表示する。クリックしてエフェクトを追加した場合は`true`を返す。
     fn render_effect_card(
         &self,
         ui: &mut egui::Ui,
         effect: &crate::EffectData,
         match_info: Option<&EffectMatchInfo>,
-    ) {
+        is_favorite: bool,
+        selected: bool,
+    ) -> bool {
+        let stroke = if selected {
+            ui.visuals().selection.stroke
+        } else {
+            ui.visuals().widgets.noninteractive.bg_stroke
+        };
         let frame = egui::Frame::group(ui.style())
             .fill(ui.visuals().faint_bg_color)
-            .stroke(ui.visuals().widgets.noninteractive.bg_stroke)
+            .stroke(stroke)
             .inner_margin(egui::Margin::symmetric(8, 4));
         let available_width = ui.available_width();
         let response = ui.allocate_ui_with_layout(
@@ -463,6 +704,23 @@ impl ScriptsSearchApp {
                         ui.set_width(ui.available_width());
                         ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
                             ui.set_min_height(24.0);
+                            let star_icon = if is_favorite {
+                                include_iconify!("mdi:star")
+                            } else {
+                                include_iconify!("mdi:star-outline")
+                            };
+                            let star_tint = if is_favorite {
+                                ui.visuals().warn_fg_color
+                            } else {
+                                ui.visuals().weak_text_color()
+                            };
+                            let star_rect = ui
+                                .add(
+                                    egui::Image::new(star_icon)
+                                        .max_size(egui::vec2(16.0, 16.0))
+                                        .tint(star_tint),
+                                )
+                                .rect;
                             let (name, icon) = Self::effect_type_display(effect);
                             ui.add(
                                 egui::Image::new(icon)
@@ -499,25 +757,48 @@ impl ScriptsSearchApp {
                                     egui::Label::new(colored_label).selectable(false).truncate(),
                                 );
                             });
-                        });
+                            star_rect
+                        })
+                        .inner
                     })
-                    .response
+                    .inner
             },
         );
+        let star_rect = response.inner;
         let response = response
             .response
             .interact(egui::Sense::click())
             .on_hover_cursor(egui::CursorIcon::PointingHand);
+        let star_hovered = ui
+            .ctx()
+            .pointer_hover_pos()
+            .is_some_and(|pos| star_rect.contains(pos));
+        let response = if star_hovered {
+            response.on_hover_text(if is_favorite {
+                tr("お気に入りから削除")
+            } else {
+                tr("お気に入りに追加")
+            })
+        } else {
+            response
+        };
+        if response.clicked() && star_hovered {
+            crate::update_history(|history| {
+                history.toggle_favorite(&effect.effect.name);
+            });
+            return false;
+        }
 
         // フィルタ効果の場合、ホバー時にオーバーレイを表示
         if effect.effect.effect_type == aviutl2::generic::EffectType::Filter {
+            let mut applied = false;
             if !self.show_info && !self.show_click_behavior_settings {
                 let clip_rect = ui.clip_rect();
                 let hovered = ui.ctx().pointer_hover_pos().is_some_and(|pos| {
                     Self::is_filter_actions_hovered(ui, response.rect, clip_rect, effect, pos)
                 });
                 if hovered || response.hovered() {
-                    Self::render_filter_actions_overlay(
+                    applied |= Self::render_filter_actions_overlay(
                         ui,
                         response.id,
                         response.rect,
@@ -533,6 +814,8 @@ impl ScriptsSearchApp {
                 match res {
                     Ok(_) => {
                         tracing::debug!("Filter added successfully with behavior {:?}", behavior);
+                        crate::record_effect_use(&effect.effect.name);
+                        applied = true;
                     }
                     Err(e) => {
                         tracing::error!("Failed to add filter: {}", e);
@@ -540,14 +823,11 @@ impl ScriptsSearchApp {
                     }
                 }
             }
+            applied
         } else if effect.effect.effect_type == aviutl2::generic::EffectType::Output {
-            if response.clicked() {
-                Self::handle_output_click(effect);
-            }
+            response.clicked() && Self::handle_output_click(effect)
         } else {
-            if response.clicked() {
-                Self::handle_non_filter_click(effect);
-            }
+            response.clicked() && Self::handle_non_filter_click(effect)
         }
     }
 
@@ -619,13 +899,14 @@ impl ScriptsSearchApp {
         colored_label
     }
 
+    /// フィルタ効果の追加ボタンを表示する。ボタンでエフェクトを追加した場合は`true`を返す。
     fn render_filter_actions_overlay(
         ui: &mut egui::Ui,
         id: egui::Id,
         rect: egui::Rect,
         clip_rect: egui::Rect,
         effect: &crate::EffectData,
-    ) {
+    ) -> bool {
         let button_size = egui::vec2(20.0, 20.0);
         let button_margin = egui::vec2(12.0, 4.0);
         let gap = 4.0;
@@ -639,9 +920,10 @@ impl ScriptsSearchApp {
         let actions_rect =
             egui::Rect::from_min_size(top_left, egui::vec2(total_width, button_size.y));
         if !clip_rect.contains(actions_rect.min) || !clip_rect.contains(actions_rect.max) {
-            return;
+            return false;
         }
         let actions_id = id.with("filter_actions_overlay");
+        let mut applied = false;
 
         egui::Area::new(actions_id)
             .order(egui::Order::Middle)
@@ -669,6 +951,8 @@ impl ScriptsSearchApp {
                             match res {
                                 Ok(_) => {
                                     tracing::debug!("Action '{}' executed successfully", tooltip);
+                                    crate::record_effect_use(&effect.effect.name);
+                                    applied = true;
                                 }
                                 Err(e) => {
                                     tracing::error!(
@@ -701,6 +985,7 @@ impl ScriptsSearchApp {
                     );
                 });
             });
+        applied
     }
 
     fn is_filter_actions_hovered(
@@ -730,7 +1015,7 @@ impl ScriptsSearchApp {
             && actions_rect.contains(pos)
     }
 
-    fn handle_non_filter_click(effect: &crate::EffectData) {
+    fn handle_non_filter_click(effect: &crate::EffectData) -> bool {
         let res = crate::EDIT_HANDLE
             .call_edit_section(|e| {
                 let created =
@@ -744,12 +1029,15 @@ impl ScriptsSearchApp {
         if let Err(e) = res {
             play_beep();
             tracing::error!("Failed to add effect: {}", e);
+            false
         } else {
             tracing::debug!("Effect added: {:?}", res);
+            crate::record_effect_use(&effect.effect.name);
+            true
         }
     }
 
-    fn handle_output_click(effect: &crate::EffectData) {
+    fn handle_output_click(effect: &crate::EffectData) -> bool {
         let res = crate::EDIT_HANDLE
             .call_edit_section(|e| {
                 // メディア出力の設定は選択したオブジェクトを対象にして変更するという挙動にする
@@ -764,10 +1052,19 @@ impl ScriptsSearchApp {
                 let maybe_output_effect_name = output_table
                     .get_value("effect.name")
                     .map_or("", |v| v.as_str());
-                if !crate::EFFECTS.get().unwrap().effects.iter().any(|eff| {
-                    eff.effect.effect_type == aviutl2::generic::EffectType::Output
-                        && eff.name == maybe_output_effect_name
-                }) {
+                let is_output_effect =
+                    crate::STORE
+                        .read()
+                        .unwrap()
+                        .effects
+                        .as_ref()
+                        .is_some_and(|effects| {
+                            effects.effects.iter().any(|eff| {
+                                eff.effect.effect_type == aviutl2::generic::EffectType::Output
+                                    && eff.name == maybe_output_effect_name
+                            })
+                        });
+                if !is_output_effect {
                     anyhow::bail!("選択中のオブジェクトは出力効果を設定できません。");
                 }
 
@@ -808,10 +1105,54 @@ impl ScriptsSearchApp {
         match res {
             Ok(_) => {
                 tracing::debug!("Output effect added successfully");
+                crate::record_effect_use(&effect.effect.name);
+                true
             }
             Err(e) => {
                 play_beep();
                 tracing::error!("Failed to add output effect: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Enterキーで選択中の検索結果を追加する。成功した場合は`true`を返す。
+    fn apply_with_keyboard(effect: &crate::EffectData, shift: bool) -> bool {
+        let has_focused_object = crate::EDIT_HANDLE
+            .call_read_section(|section| {
+                section
+                    .get_focused_object()
+                    .is_ok_and(|object| object.is_some())
+            })
+            .unwrap_or(false);
+        let action =
+            crate::keyboard::apply_action(effect.effect.effect_type, shift, has_focused_object);
+        let res = match action {
+            crate::keyboard::ApplyAction::AddToFocusedObject => {
+                Self::add_filter_to_focused_object(effect)
+            }
+            crate::keyboard::ApplyAction::CreateObject
+                if effect.effect.effect_type == aviutl2::generic::EffectType::Filter =>
+            {
+                Self::add_filter_as_object(effect)
+            }
+            crate::keyboard::ApplyAction::CreateObject => {
+                return Self::handle_non_filter_click(effect);
+            }
+            crate::keyboard::ApplyAction::ReplaceOutput => {
+                return Self::handle_output_click(effect);
+            }
+        };
+        match res {
+            Ok(_) => {
+                tracing::debug!("Effect applied with keyboard: {:?}", action);
+                crate::record_effect_use(&effect.effect.name);
+                true
+            }
+            Err(e) => {
+                tracing::error!("Failed to apply effect: {}", e);
+                play_beep();
+                false
             }
         }
     }
@@ -1089,6 +1430,11 @@ impl ScriptsSearchApp {
     }
 }
 
+enum EffectRow<'a> {
+    Header(String),
+    Effect(&'a crate::EffectData),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 struct EffectMatchInfo {
     name_match: Option<(u32, Vec<u32>)>,
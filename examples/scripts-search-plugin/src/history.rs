@@ -0,0 +1,333 @@
+use std::collections::BTreeSet;
+
+/// 最近使ったエフェクトとして保持する最大数。
+pub const MAX_RECENT_EFFECTS: usize = 20;
+
+/// 検索履歴として保持する最大数。
+pub const MAX_SEARCH_HISTORY: usize = 50;
+
+/// 最近使ったエフェクト。
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RecentEffect {
+    /// エフェクトの名前（`aviutl2::generic::Effect::name`）。
+    pub name: String,
+    /// 最後に追加した時刻（UNIX時間、秒）。
+    pub used_at: u64,
+}
+
+/// エフェクトの使用履歴とお気に入り。
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EffectHistory {
+    /// 最近使ったエフェクト。新しいものが先頭。
+    #[serde(default)]
+    pub recent: Vec<RecentEffect>,
+    /// お気に入りのエフェクトの名前。
+    #[serde(default)]
+    pub favorites: BTreeSet<String>,
+}
+
+impl EffectHistory {
+    pub const fn new() -> Self {
+        Self {
+            recent: Vec::new(),
+            favorites: BTreeSet::new(),
+        }
+    }
+
+    /// エフェクトを追加したことを記録する。
+    pub fn record_use(&mut self, name: &str, used_at: u64) {
+        self.recent.retain(|recent| recent.name != name);
+        self.recent.insert(
+            0,
+            RecentEffect {
+                name: name.to_string(),
+                used_at,
+            },
+        );
+        self.recent.truncate(MAX_RECENT_EFFECTS);
+    }
+
+    /// 読み込んだ履歴を新しい順に並べ直し、上限を超えた分を削除する。
+    pub fn normalize(&mut self) {
+        self.recent.sort_by(|a, b| b.used_at.cmp(&a.used_at));
+        let mut seen = BTreeSet::new();
+        self.recent
+            .retain(|recent| seen.insert(recent.name.clone()));
+        self.recent.truncate(MAX_RECENT_EFFECTS);
+    }
+
+    pub fn is_favorite(&self, name: &str) -> bool {
+        self.favorites.contains(name)
+    }
+
+    /// お気に入りを切り替える。切り替え後にお気に入りかどうかを返す。
+    pub fn toggle_favorite(&mut self, name: &str) -> bool {
+        if self.favorites.remove(name) {
+            false
+        } else {
+            self.favorites.insert(name.to_string());
+            true
+        }
+    }
+
+    /// 最近使った順の順位を返す。使っていない場合は`None`。
+    pub fn recent_rank(&self, name: &str) -> Option<usize> {
+        self.recent.iter().position(|recent| recent.name == name)
+    }
+
+    /// 検索結果を並べ替える。
+    ///
+    /// お気に入り、`compare_score`（スコアの高いものが`Less`）、最近使った順の優先度で並べます。
+    pub fn rank<T>(
+        &self,
+        items: &mut [T],
+        name: impl Fn(&T) -> &str,
+        compare_score: impl Fn(&T, &T) -> std::cmp::Ordering,
+    ) {
+        items.sort_by(|a, b| {
+            let (a_name, b_name) = (name(a), name(b));
+            self.is_favorite(b_name)
+                .cmp(&self.is_favorite(a_name))
+                .then_with(|| compare_score(a, b))
+                .then_with(
+                    || match (self.recent_rank(a_name), self.recent_rank(b_name)) {
+                        (Some(a_rank), Some(b_rank)) => a_rank.cmp(&b_rank),
+                        (Some(_), None) => std::cmp::Ordering::Less,
+                        (None, Some(_)) => std::cmp::Ordering::Greater,
+                        (None, None) => std::cmp::Ordering::Equal,
+                    },
+                )
+        });
+    }
+}
+
+/// 検索した文字列の履歴。プロジェクトによらず共通で保存する。
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SearchHistory {
+    /// 検索した文字列。新しいものが先頭。
+    #[serde(default)]
+    pub queries: Vec<String>,
+}
+
+impl SearchHistory {
+    pub const fn new() -> Self {
+        Self {
+            queries: Vec::new(),
+        }
+    }
+
+    /// 検索した文字列を記録する。前後の空白は取り除き、空の場合は記録しない。
+    ///
+    /// 履歴が変わった場合は`true`を返す。
+    pub fn record(&mut self, query: &str) -> bool {
+        let query = query.trim();
+        if query.is_empty() || self.queries.first().is_some_and(|q| q == query) {
+            return false;
+        }
+        self.queries.retain(|q| q != query);
+        self.queries.insert(0, query.to_string());
+        self.queries.truncate(MAX_SEARCH_HISTORY);
+        true
+    }
+
+    /// 読み込んだ履歴から空の文字列と重複を削除し、上限を超えた分を削除する。
+    pub fn normalize(&mut self) {
+        let mut seen = BTreeSet::new();
+        self.queries = std::mem::take(&mut self.queries)
+            .into_iter()
+            .map(|q| q.trim().to_string())
+            .filter(|q| !q.is_empty() && seen.insert(q.clone()))
+            .collect();
+        self.queries.truncate(MAX_SEARCH_HISTORY);
+    }
+}
+
+/// 検索履歴のファイルのパス。
+pub fn search_history_path() -> std::path::PathBuf {
+    aviutl2::config::app_data_path().join("rusty_scripts_search_queries.json")
+}
+
+pub fn load_search_history() -> aviutl2::AnyResult<SearchHistory> {
+    let content = std::fs::read_to_string(search_history_path())?;
+    let mut history: SearchHistory = serde_json::from_str(&content)?;
+    history.normalize();
+    Ok(history)
+}
+
+pub fn save_search_history(history: &SearchHistory) -> aviutl2::AnyResult<()> {
+    std::fs::write(
+        search_history_path(),
+        serde_json::to_string_pretty(history)?,
+    )?;
+    Ok(())
+}
+
+/// プロジェクトが開かれていないときに使う履歴ファイルのパス。
+pub fn fallback_path() -> std::path::PathBuf {
+    aviutl2::config::app_data_path().join("rusty_scripts_search_history.json")
+}
+
+pub fn load_fallback() -> aviutl2::AnyResult<EffectHistory> {
+    let content = std::fs::read_to_string(fallback_path())?;
+    let mut history: EffectHistory = serde_json::from_str(&content)?;
+    history.normalize();
+    Ok(history)
+}
+
+pub fn save_fallback(history: &EffectHistory) -> aviutl2::AnyResult<()> {
+    std::fs::write(fallback_path(), serde_json::to_string_pretty(history)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rank(history: &EffectHistory, items: &[(&'static str, u32)]) -> Vec<&'static str> {
+        let mut items = items.to_vec();
+        history.rank(&mut items, |(name, _)| *name, |a, b| b.1.cmp(&a.1));
+        items.into_iter().map(|(name, _)| name).collect()
+    }
+
+    #[test]
+    fn test_record_use_moves_to_front_and_caps() {
+        let mut history = EffectHistory::new();
+        for i in 0..(MAX_RECENT_EFFECTS as u64 + 5) {
+            history.record_use(&format!("effect{i}"), i);
+        }
+        history.record_use("effect10", 100);
+        assert_eq!(history.recent.len(), MAX_RECENT_EFFECTS);
+        assert_eq!(history.recent[0].name, "effect10");
+        assert_eq!(history.recent[1].name, "effect24");
+        assert_eq!(
+            history
+                .recent
+                .iter()
+                .filter(|r| r.name == "effect10")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_rank_favorites_first() {
+        let mut history = EffectHistory::new();
+        history.toggle_favorite("low");
+        assert_eq!(
+            rank(&history, &[("high", 100), ("low", 1), ("mid", 50)]),
+            ["low", "high", "mid"]
+        );
+    }
+
+    #[test]
+    fn test_rank_score_before_recency() {
+        let mut history = EffectHistory::new();
+        history.record_use("low", 0);
+        assert_eq!(
+            rank(&history, &[("high", 100), ("low", 1)]),
+            ["high", "low"]
+        );
+    }
+
+    #[test]
+    fn test_rank_recency_breaks_ties() {
+        let mut history = EffectHistory::new();
+        history.record_use("a", 0);
+        history.record_use("b", 1);
+        assert_eq!(
+            rank(&history, &[("c", 10), ("a", 10), ("b", 10)]),
+            ["b", "a", "c"]
+        );
+    }
+
+    #[test]
+    fn test_toggle_favorite() {
+        let mut history = EffectHistory::new();
+        assert!(history.toggle_favorite("a"));
+        assert!(history.is_favorite("a"));
+        assert!(!history.toggle_favorite("a"));
+        assert!(!history.is_favorite("a"));
+    }
+
+    #[test]
+    fn test_normalize_sorts_and_dedups() {
+        let mut history = EffectHistory {
+            recent: vec![
+                RecentEffect {
+                    name: "a".to_string(),
+                    used_at: 1,
+                },
+                RecentEffect {
+                    name: "b".to_string(),
+                    used_at: 3,
+                },
+                RecentEffect {
+                    name: "a".to_string(),
+                    used_at: 2,
+                },
+            ],
+            favorites: BTreeSet::new(),
+        };
+        history.normalize();
+        assert_eq!(
+            history.recent,
+            [
+                RecentEffect {
+                    name: "b".to_string(),
+                    used_at: 3,
+                },
+                RecentEffect {
+                    name: "a".to_string(),
+                    used_at: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_history_dedups_and_moves_to_front() {
+        let mut history = SearchHistory::new();
+        assert!(history.record("blur"));
+        assert!(history.record("glow"));
+        assert!(history.record(" blur "));
+        assert_eq!(history.queries, ["blur", "glow"]);
+        // 先頭と同じ文字列は変更なし
+        assert!(!history.record("blur"));
+        assert!(!history.record("   "));
+        assert_eq!(history.queries, ["blur", "glow"]);
+    }
+
+    #[test]
+    fn test_search_history_caps() {
+        let mut history = SearchHistory::new();
+        for i in 0..(MAX_SEARCH_HISTORY + 5) {
+            history.record(&format!("query{i}"));
+        }
+        history.record("query10");
+        assert_eq!(history.queries.len(), MAX_SEARCH_HISTORY);
+        assert_eq!(history.queries[0], "query10");
+        assert_eq!(
+            history.queries[1],
+            format!("query{}", MAX_SEARCH_HISTORY + 4)
+        );
+        assert_eq!(
+            history.queries.iter().filter(|q| *q == "query10").count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_search_history_normalize() {
+        let mut history = SearchHistory {
+            queries: vec![
+                "a".to_string(),
+                " b".to_string(),
+                String::new(),
+                "a ".to_string(),
+                "b".to_string(),
+            ],
+        };
+        history.normalize();
+        assert_eq!(history.queries, ["a", "b"]);
+    }
+}
@@ -0,0 +1,166 @@
+use aviutl2::generic::EffectType;
+
+/// 検索結果のうち、キーボードで選択している項目。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResultSelection {
+    index: usize,
+    /// 選択位置が変わって、表示位置を合わせる必要があるかどうか。
+    scroll_pending: bool,
+}
+
+impl ResultSelection {
+    /// 選択している項目の位置を返す。検索結果が空の場合は`None`。
+    pub fn index(&self, len: usize) -> Option<usize> {
+        (len > 0).then(|| self.index.min(len - 1))
+    }
+
+    /// 選択を`delta`だけ移動する。検索結果の範囲外には移動しない。
+    pub fn move_by(&mut self, delta: isize, len: usize) {
+        let Some(current) = self.index(len) else {
+            self.index = 0;
+            return;
+        };
+        self.index = current.saturating_add_signed(delta).min(len - 1);
+        self.scroll_pending = true;
+    }
+
+    /// 選択を先頭に戻す。
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.scroll_pending = true;
+    }
+
+    /// 表示位置を合わせる必要があるかを返し、要求を消費する。
+    pub fn take_scroll_request(&mut self) -> bool {
+        std::mem::take(&mut self.scroll_pending)
+    }
+}
+
+/// Enterキーで検索結果を適用するときの動作。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyAction {
+    /// 選択中のオブジェクトにフィルタ効果を追加する。
+    AddToFocusedObject,
+    /// 新しいオブジェクトとして追加する。
+    CreateObject,
+    /// 選択中のオブジェクトの出力効果を置き換える。
+    ReplaceOutput,
+}
+
+/// Enterキーで検索結果を適用するときの動作を決める。
+///
+/// フィルタ効果はEnterで選択中のオブジェクトに追加し、Shift+Enterで新しいオブジェクトとして追加します。
+/// オブジェクトが選択されていない場合は、Enterでも新しいオブジェクトとして追加します。
+pub fn apply_action(effect_type: EffectType, shift: bool, has_focused_object: bool) -> ApplyAction {
+    match effect_type {
+        EffectType::Filter if !shift && has_focused_object => ApplyAction::AddToFocusedObject,
+        EffectType::Output => ApplyAction::ReplaceOutput,
+        _ => ApplyAction::CreateObject,
+    }
+}
+
+/// `row`番目の行が表示範囲に収まるスクロール位置を返す。すでに収まっている場合は`None`。
+///
+/// `row_height`は行の間隔を含まない高さ、`offset`と`viewport_height`は現在のスクロール位置と表示範囲の高さです。
+pub fn scroll_offset_to_show(
+    row: usize,
+    row_height: f32,
+    spacing: f32,
+    offset: f32,
+    viewport_height: f32,
+) -> Option<f32> {
+    let top = row as f32 * (row_height + spacing);
+    let bottom = top + row_height;
+    let target = if top < offset {
+        top
+    } else if bottom > offset + viewport_height {
+        // 表示範囲より高い行は上端に合わせる
+        (bottom - viewport_height).min(top)
+    } else {
+        return None;
+    };
+    (target != offset).then_some(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection_moves_within_bounds() {
+        let mut selection = ResultSelection::default();
+        assert_eq!(selection.index(0), None);
+        assert_eq!(selection.index(3), Some(0));
+
+        selection.move_by(-1, 3);
+        assert_eq!(selection.index(3), Some(0));
+        selection.move_by(1, 3);
+        selection.move_by(5, 3);
+        assert_eq!(selection.index(3), Some(2));
+        // 検索結果が減った場合は末尾を選択する
+        assert_eq!(selection.index(2), Some(1));
+
+        selection.reset();
+        assert_eq!(selection.index(3), Some(0));
+    }
+
+    #[test]
+    fn test_selection_scroll_request() {
+        let mut selection = ResultSelection::default();
+        assert!(!selection.take_scroll_request());
+        selection.move_by(1, 3);
+        assert!(selection.take_scroll_request());
+        assert!(!selection.take_scroll_request());
+        // 検索結果が空の場合は移動しない
+        selection.move_by(1, 0);
+        assert!(!selection.take_scroll_request());
+    }
+
+    #[test]
+    fn test_apply_action_filter() {
+        assert_eq!(
+            apply_action(EffectType::Filter, false, true),
+            ApplyAction::AddToFocusedObject
+        );
+        assert_eq!(
+            apply_action(EffectType::Filter, true, true),
+            ApplyAction::CreateObject
+        );
+        assert_eq!(
+            apply_action(EffectType::Filter, false, false),
+            ApplyAction::CreateObject
+        );
+    }
+
+    #[test]
+    fn test_apply_action_non_filter() {
+        for effect_type in [
+            EffectType::Input,
+            EffectType::SceneChange,
+            EffectType::Control,
+        ] {
+            for shift in [false, true] {
+                assert_eq!(
+                    apply_action(effect_type, shift, true),
+                    ApplyAction::CreateObject
+                );
+            }
+        }
+        assert_eq!(
+            apply_action(EffectType::Output, true, false),
+            ApplyAction::ReplaceOutput
+        );
+    }
+
+    #[test]
+    fn test_scroll_offset_to_show() {
+        // 行の高さ20、間隔4、表示範囲100
+        assert_eq!(scroll_offset_to_show(1, 20.0, 4.0, 0.0, 100.0), None);
+        // 下にはみ出す行は下端に合わせる
+        assert_eq!(scroll_offset_to_show(4, 20.0, 4.0, 0.0, 100.0), Some(16.0));
+        // 上にはみ出す行は上端に合わせる
+        assert_eq!(scroll_offset_to_show(2, 20.0, 4.0, 60.0, 100.0), Some(48.0));
+        assert_eq!(scroll_offset_to_show(3, 20.0, 4.0, 0.0, 10.0), Some(72.0));
+        assert_eq!(scroll_offset_to_show(0, 20.0, 4.0, 0.0, 10.0), None);
+    }
+}
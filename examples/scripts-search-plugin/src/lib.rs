@@ -1,6 +1,8 @@
 use aviutl2::{AnyResult, tracing};
 
 mod gui;
+mod history;
+mod keyboard;
 
 #[aviutl2::plugin(GenericPlugin)]
 pub struct ScriptsSearchPlugin {
@@ -17,7 +19,67 @@ pub struct EffectData {
 pub struct EffectDb {
     effects: Vec<EffectData>,
 }
-pub static EFFECTS: std::sync::OnceLock<EffectDb> = std::sync::OnceLock::new();
+pub struct EffectStore {
+    effects: Option<std::sync::Arc<EffectDb>>,
+    history: history::EffectHistory,
+    search_history: history::SearchHistory,
+    /// 履歴をプロジェクトに保存できるかどうか。`false`の場合はフォールバックのファイルに保存する。
+    has_project_path: bool,
+}
+pub static STORE: std::sync::RwLock<EffectStore> = std::sync::RwLock::new(EffectStore {
+    effects: None,
+    history: history::EffectHistory::new(),
+    search_history: history::SearchHistory::new(),
+    has_project_path: false,
+});
+
+const RECENT_EFFECTS_KEY: &str = "recent_effects";
+const FAVORITE_EFFECTS_KEY: &str = "favorite_effects";
+
+/// 履歴を更新する。プロジェクトが開かれていない場合はフォールバックのファイルに保存する。
+pub fn update_history(f: impl FnOnce(&mut history::EffectHistory)) {
+    let mut store = STORE.write().unwrap();
+    f(&mut store.history);
+    if !store.has_project_path
+        && let Err(e) = history::save_fallback(&store.history)
+    {
+        tracing::warn!("Failed to save effect history: {}", e);
+    }
+}
+
+fn unix_time_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// エフェクトを追加したことを記録する。
+pub fn record_effect_use(name: &str) {
+    let now = unix_time_now();
+    update_history(|history| history.record_use(name, now));
+}
+
+/// 検索した文字列を記録する。
+pub fn record_search_query(query: &str) {
+    let mut store = STORE.write().unwrap();
+    if store.search_history.record(query)
+        && let Err(e) = history::save_search_history(&store.search_history)
+    {
+        tracing::warn!("Failed to save search history: {}", e);
+    }
+}
+
+const WINDOW_NAME: &str = "Rusty Scripts Search Plugin";
+
+static WINDOW_CLIENT: std::sync::OnceLock<aviutl2::generic::WindowClientHandle> =
+    std::sync::OnceLock::new();
+
+/// 検索ウィンドウを非表示にする。
+pub fn hide_window() {
+    if let Some(client) = WINDOW_CLIENT.get() {
+        client.hide();
+    }
+}
 
 pub static EDIT_HANDLE: aviutl2::generic::GlobalEditHandle =
     aviutl2::generic::GlobalEditHandle::new();
@@ -47,14 +109,44 @@ impl aviutl2::generic::GenericPlugin for ScriptsSearchPlugin {
     fn register(&mut self, registry: &mut aviutl2::generic::HostAppHandle) {
         if let Ok(handle) = self.window.handle() {
             registry
-                .register_window_client("Rusty Scripts Search Plugin", &handle)
+                .register_window_client(WINDOW_NAME, &handle)
                 .unwrap();
         }
+        if let Some(client) = registry.window_client(WINDOW_NAME) {
+            let _ = WINDOW_CLIENT.set(client);
+            registry.register_edit_menu("スクリプト検索を表示/非表示", move || {
+                if !client.toggle() {
+                    return;
+                }
+                gui::request_search_focus();
+                if let Err(e) =
+                    <ScriptsSearchPlugin as aviutl2::generic::GenericPlugin>::with_instance(
+                        |plugin| plugin.window.focus(),
+                    )
+                {
+                    tracing::warn!("Failed to focus the search window: {}", e);
+                }
+            });
+        }
         let edit_handle = registry.create_edit_handle();
         EDIT_HANDLE.init(edit_handle);
     }
 
-    fn on_project_load(&mut self, _project: &mut aviutl2::generic::ProjectFile) {
+    fn on_project_load(&mut self, project: &mut aviutl2::generic::ProjectFile) {
+        let history = Self::load_history(project);
+        {
+            let mut store = STORE.write().unwrap();
+            store.history = history;
+            store.has_project_path = project.get_path().is_some();
+        }
+        if STORE.read().unwrap().effects.is_some() {
+            return;
+        }
+
+        match history::load_search_history() {
+            Ok(search_history) => STORE.write().unwrap().search_history = search_history,
+            Err(e) => tracing::debug!("Failed to load search history: {}", e),
+        }
         let maybe_config = ScriptsSearchPlugin::load_aviutl2_ini();
         let config = match maybe_config {
             Ok(cfg) => cfg,
@@ -68,7 +160,7 @@ impl aviutl2::generic::GenericPlugin for ScriptsSearchPlugin {
             tracing::warn!("Effect section not found in aviutl2.ini");
             &empty_table
         });
-        EFFECTS.get_or_init(|| {
+        let effect_db = {
             let effects = EDIT_HANDLE.get_effects();
             let mut has_missing_label = false;
             let effects = effects
@@ -108,7 +200,30 @@ impl aviutl2::generic::GenericPlugin for ScriptsSearchPlugin {
                 })
                 .collect();
             EffectDb { effects }
-        });
+        };
+        STORE.write().unwrap().effects = Some(std::sync::Arc::new(effect_db));
+    }
+
+    fn on_project_save(&mut self, project: &mut aviutl2::generic::ProjectFile) {
+        let mut store = STORE.write().unwrap();
+        if let Err(e) = project.serialize(RECENT_EFFECTS_KEY, &store.history.recent) {
+            tracing::error!("Failed to save recent effects: {}", e);
+        }
+        if let Err(e) = project.serialize(FAVORITE_EFFECTS_KEY, &store.history.favorites) {
+            tracing::error!("Failed to save favorite effects: {}", e);
+        }
+        store.has_project_path = true;
+    }
+
+    fn on_project_changed(&mut self, change: aviutl2::generic::ProjectChange) {
+        match change.kind {
+            // 履歴はプロジェクトごとに持つので、読み込み・保存のたびに表示を更新する
+            aviutl2::generic::ProjectChangeKind::Loaded
+            | aviutl2::generic::ProjectChangeKind::Saved => {
+                let _ = self.window.egui_ctx().map(|ctx| ctx.request_repaint());
+            }
+            _ => {}
+        }
     }
 }
 
@@ -125,6 +240,23 @@ impl ScriptsSearchPlugin {
             .init();
     }
 
+    fn load_history(project: &aviutl2::generic::ProjectFile) -> history::EffectHistory {
+        if project.get_path().is_none() {
+            return history::load_fallback().unwrap_or_else(|e| {
+                tracing::debug!("Failed to load effect history: {}", e);
+                history::EffectHistory::new()
+            });
+        }
+        let mut history = history::EffectHistory {
+            recent: project.deserialize(RECENT_EFFECTS_KEY).unwrap_or_default(),
+            favorites: project
+                .deserialize(FAVORITE_EFFECTS_KEY)
+                .unwrap_or_default(),
+        };
+        history.normalize();
+        history
+    }
+
     fn load_aviutl2_ini() -> AnyResult<aviutl2::alias::Table> {
         let main_config_path = aviutl2::config::app_data_path().join("aviutl2.ini");
         let content = std::fs::read_to_string(&main_config_path)?;
@@ -0,0 +1,57 @@
+use aviutl2::{
+    AnyResult,
+    filter::{
+        FilterConfigItemSliceExt, FilterConfigItems, FilterPlugin, FilterPluginFlags,
+        FilterPluginTable, FilterProcVideo,
+    },
+};
+
+/// スクリプトから読み取るキー。
+pub const SCALE_KEY: &str = "scale";
+
+#[aviutl2::filter::filter_config_items]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterConfig {
+    #[track(name = "既定の拡大率", range = 0.0..=10.0, step = 0.01, default = 1.0)]
+    pub default_scale: f64,
+}
+
+/// スクリプトが共有ストアに書き込んだ`scale`で、オブジェクトを拡大するフィルタ。
+#[aviutl2::plugin(FilterPlugin)]
+pub struct SharedStoreScaleFilter;
+
+impl FilterPlugin for SharedStoreScaleFilter {
+    fn new(_info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
+        Ok(Self)
+    }
+
+    fn plugin_info(&self) -> FilterPluginTable {
+        FilterPluginTable {
+            name: "Rusty Shared Store Scale".to_string(),
+            label: None,
+            order: 0,
+            information: "Scales the object by the value set from a script".to_string(),
+            flags: aviutl2::bitflag!(FilterPluginFlags {
+                video: true,
+                filter: true,
+            }),
+            config_items: FilterConfig::to_config_items(),
+        }
+    }
+
+    fn proc_video(
+        &self,
+        config: &[aviutl2::filter::FilterConfigItem],
+        video: &mut FilterProcVideo,
+    ) -> AnyResult<()> {
+        let config: FilterConfig = config.to_struct();
+        // スクリプトは`obj.frame`（オブジェクト基準のフレーム番号）で書き込む
+        let scale = aviutl2::filter::shared_store()
+            .get_at_frame(SCALE_KEY, video.object.frame)
+            .and_then(|value| value.as_f64())
+            .unwrap_or(config.default_scale) as f32;
+        video.param.sx *= scale;
+        video.param.sy *= scale;
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use aviutl2::AnyResult;
+
+mod filter;
+mod module;
+
+/// スクリプトモジュールとフィルタは同じDLLに登録しないと、共有ストアを共有できない。
+#[aviutl2::plugin(GenericPlugin)]
+pub struct SharedStorePlugin {
+    module: aviutl2::generic::SubPlugin<crate::module::SharedStoreModule>,
+    filter: aviutl2::generic::SubPlugin<crate::filter::SharedStoreScaleFilter>,
+}
+
+impl aviutl2::generic::GenericPlugin for SharedStorePlugin {
+    fn new(info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
+        Ok(Self {
+            module: aviutl2::generic::SubPlugin::new_script_module(&info)?,
+            filter: aviutl2::generic::SubPlugin::new_filter_plugin(&info)?,
+        })
+    }
+
+    fn plugin_info(&self) -> aviutl2::generic::GenericPluginTable {
+        aviutl2::generic::GenericPluginTable {
+            name: "Rusty Shared Store Plugin".to_string(),
+            information: format!(
+                "Shared store example, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/shared-store-plugin",
+                version = env!("CARGO_PKG_VERSION")
+            ),
+        }
+    }
+
+    fn register(&mut self, registry: &mut aviutl2::generic::HostAppHandle) {
+        registry.register_script_module(Some("rusty_shared_store"), &self.module);
+        registry.register_filter_plugin(&self.filter);
+    }
+}
+
+aviutl2::register_generic_plugin!(SharedStorePlugin);
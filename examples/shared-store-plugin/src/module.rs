@@ -0,0 +1,27 @@
+/// 共有ストアの`set`・`get`関数だけを提供するスクリプトモジュール。
+#[aviutl2::plugin(ScriptModule)]
+pub struct SharedStoreModule;
+
+impl aviutl2::module::ScriptModuleFunctions for SharedStoreModule {
+    fn functions() -> Vec<aviutl2::module::ModuleFunction> {
+        Vec::new()
+    }
+}
+
+impl aviutl2::module::ScriptModule for SharedStoreModule {
+    fn new(_info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+        Ok(Self)
+    }
+
+    fn plugin_info(&self) -> aviutl2::module::ScriptModuleTable {
+        aviutl2::module::ScriptModuleTable {
+            information: "Shared store module for Rusty Shared Store Plugin".to_string(),
+            name: None,
+            functions: Self::functions(),
+            include_introspection: false,
+            default_time_budget: None,
+            constants: Vec::new(),
+            enable_shared_store: true,
+        }
+    }
+}
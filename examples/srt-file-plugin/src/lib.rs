@@ -37,6 +37,11 @@ impl aviutl2::generic::GenericPlugin for SrtFilePlugin {
 #[aviutl2::generic::menus]
 impl SrtFilePlugin {
     #[import(name = "SRTファイル（*.srt）")]
+    #[edit(
+        name = "SRTファイルを読み込む",
+        path = "Rustyツール/SRT",
+        enabled_if = "has_focused_object"
+    )]
     fn import_menu(&mut self) -> AnyResult<()> {
         EDIT_HANDLE.call_edit_section(|edit_section| {
             let current_object = edit_section.get_focused_object()?;
@@ -63,7 +68,7 @@ impl SrtFilePlugin {
             let aviutl2::generic::ObjectLayerFrame {
                 layer,
                 start: existing_start_frame,
-                end: existing_end_frame,
+                end: _,
             } = obj.get_layer_frame()?;
             let layer = edit_section.layer(layer);
             let fps = edit_section.info.fps;
@@ -74,20 +79,6 @@ impl SrtFilePlugin {
             let Some(last_subtitle) = subtitles.last() else {
                 anyhow::bail!("SRTファイルに字幕が含まれていません。");
             };
-            let last_subtitle_ms = last_subtitle.end_time.to_milliseconds();
-            let total_frames = (last_subtitle_ms as f64 / 1000.0 * fps).ceil() as u32;
-            let next_object = layer.find_object_after(existing_end_frame + 1)?;
-            let existing_next_frame = if let Some(next_object) = next_object {
-                let next_obj = edit_section.object(next_object);
-                let next_layer_frame = next_obj.get_layer_frame()?;
-                next_layer_frame.start
-            } else {
-                usize::MAX
-            };
-            if existing_start_frame + total_frames as usize > existing_next_frame {
-                edit_section.set_focus_object(Some(obj.handle))?;
-                anyhow::bail!("字幕を追加すると既存のオブジェクトと重なってしまいます。");
-            }
 
             let alias = obj.get_alias()?;
             let mut alias = alias.lines().collect::<Vec<_>>();
@@ -95,7 +86,9 @@ impl SrtFilePlugin {
                 anyhow::bail!("オブジェクトの編集に失敗しました。");
             }
             let alias = alias.join("\n");
-            obj.delete_object()?;
+            // 途中で失敗した場合は、追加した字幕を削除して元のオブジェクトを作り直す
+            let mut tx = edit_section.transaction();
+            tx.delete_object(obj.handle)?;
             let mut next_frame = existing_start_frame;
             for subtitle in subtitles {
                 let start_ms = subtitle.start_time.to_milliseconds();
@@ -117,16 +110,23 @@ impl SrtFilePlugin {
                     start_frame,
                     end_frame
                 );
-                let new_obj = edit_section.create_object_from_alias(
+                let new_obj = match tx.try_create_object_from_alias(
                     &alias,
                     layer.index,
                     start_frame,
                     end_frame - start_frame + 1,
-                )?;
+                ) {
+                    Ok(new_obj) => new_obj,
+                    Err(aviutl2::generic::CreateObjectError::Overlap { .. }) => {
+                        anyhow::bail!("字幕を追加すると既存のオブジェクトと重なってしまいます。");
+                    }
+                    Err(e) => return Err(e.into()),
+                };
                 let new_obj = edit_section.object(new_obj);
                 new_obj.set_effect_item("テキスト", 0, "テキスト", &subtitle.text)?;
                 next_frame = end_frame + 1;
             }
+            tx.commit();
 
             Ok(())
         })??;
@@ -134,6 +134,11 @@ impl SrtFilePlugin {
     }
 
     #[export(name = "SRTファイル（*.srt）")]
+    #[edit(
+        name = "SRTファイルに書き出す",
+        path = "Rustyツール/SRT",
+        enabled_if = "has_focused_object"
+    )]
     fn export_menu() -> AnyResult<()> {
         EDIT_HANDLE.call_edit_section(|edit_section| {
             let focused_object = edit_section.get_focused_object()?;
@@ -188,6 +193,14 @@ impl SrtFilePlugin {
         })??;
         Ok(())
     }
+
+    fn has_focused_object(&self) -> bool {
+        EDIT_HANDLE
+            .call_read_section(|read_section| {
+                matches!(read_section.get_focused_object(), Ok(Some(_)))
+            })
+            .unwrap_or(false)
+    }
 }
 
 aviutl2::register_generic_plugin!(SrtFilePlugin);
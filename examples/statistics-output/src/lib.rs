@@ -1,6 +1,9 @@
 use aviutl2::output::OutputPlugin;
 use base64::{Engine, engine::general_purpose::STANDARD as base64};
 use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+
+mod live;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,11 +20,34 @@ struct RenderData {
 }
 
 #[aviutl2::plugin(OutputPlugin)]
-struct StatisticsPlugin {}
+struct StatisticsPlugin {
+    /// 出力中に処理速度を表示するかどうか。
+    live_enabled: Mutex<bool>,
+    /// 処理速度を表示するウィンドウ。最初に使う時に作成する。
+    live: OnceLock<Result<live::LiveMonitor, String>>,
+}
+
+impl StatisticsPlugin {
+    fn live_monitor(&self) -> Option<&live::LiveMonitor> {
+        let monitor = self
+            .live
+            .get_or_init(|| live::LiveMonitor::new().map_err(|e| e.to_string()));
+        match monitor {
+            Ok(monitor) => Some(monitor),
+            Err(e) => {
+                aviutl2::tracing::warn!("Live window is unavailable: {}", e);
+                None
+            }
+        }
+    }
+}
 
 impl OutputPlugin for StatisticsPlugin {
     fn new(_info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
-        Ok(StatisticsPlugin {})
+        Ok(StatisticsPlugin {
+            live_enabled: Mutex::new(false),
+            live: OnceLock::new(),
+        })
     }
 
     fn plugin_info(&self) -> aviutl2::output::OutputPluginTable {
@@ -36,29 +62,65 @@ impl OutputPlugin for StatisticsPlugin {
                 "Statistics Page" => ["html"],
                 "Raw Statistics Data" => ["json"],
             },
-            can_config: false,
+            can_config: true,
             project_config: false,
         }
     }
 
+    fn config(&self, _hwnd: aviutl2::Win32WindowHandle) -> aviutl2::AnyResult<()> {
+        let mut live_enabled = self.live_enabled.lock().unwrap();
+        let switch = native_dialog::MessageDialogBuilder::default()
+            .set_level(native_dialog::MessageLevel::Info)
+            .set_title("Rusty Statistics Output")
+            .set_text(format!(
+                "ライブ表示：{}\n\n出力中に処理速度を表示するウィンドウを{}にしますか？",
+                live_label(*live_enabled),
+                live_label(!*live_enabled)
+            ))
+            .confirm()
+            .show()?;
+        if switch {
+            *live_enabled = !*live_enabled;
+        }
+        Ok(())
+    }
+
+    fn config_text(&self) -> aviutl2::AnyResult<String> {
+        Ok(format!(
+            "ライブ表示：{}",
+            live_label(*self.live_enabled.lock().unwrap())
+        ))
+    }
+
     fn output(&self, info: aviutl2::output::OutputInfo) -> aviutl2::AnyResult<()> {
         let Some(video_info) = &info.video else {
             return Err(anyhow::anyhow!("No video information available"));
         };
         // バッファを0に設定
-        info.set_buffer_size(0, 0);
+        info.buffering().video_frames(0).audio_samples(0).apply();
         let start_time = chrono::Local::now();
+        let live = if *self.live_enabled.lock().unwrap() {
+            self.live_monitor()
+                .map(|monitor| monitor.begin(video_info.num_frames))
+        } else {
+            None
+        };
 
         let mut elapsed = Vec::with_capacity(video_info.num_frames as usize);
         let mut time_before = std::time::Instant::now();
 
-        for (_i, _frame) in
+        for (i, _frame) in
             info.get_video_frames_iter::<aviutl2::output::video_frame::BorrowedRawYuy2VideoFrame>()
         {
             let time_after = std::time::Instant::now();
-            elapsed.push(time_after.duration_since(time_before).as_secs_f64() * 1000.0);
+            let ms = time_after.duration_since(time_before).as_secs_f64() * 1000.0;
+            elapsed.push(ms);
+            if let Some(live) = &live {
+                live.push(i as u32, ms);
+            }
             time_before = time_after;
         }
+        let aborted = info.is_aborted();
         let end_time = chrono::Local::now();
 
         let total_ms = elapsed.iter().sum::<f64>();
@@ -98,12 +160,38 @@ impl OutputPlugin for StatisticsPlugin {
             std::fs::write(&info.path, page)
                 .map_err(|e| anyhow::anyhow!("Failed to write output file: {}", e))?;
 
-            open::that(&info.path)
-                .map_err(|e| anyhow::anyhow!("Failed to open output file: {}", e))?;
+            // 生のデータもページの横にJSONとして保存しておく
+            let data_path = info.sidecar_path("json");
+            std::fs::write(
+                &data_path,
+                serde_json::to_string_pretty(&render_data)
+                    .map_err(|e| anyhow::anyhow!("Failed to serialize render data: {}", e))?,
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to write statistics data: {}", e))?;
+            info.register_sidecar(&data_path, aviutl2::output::SidecarKind::Metadata);
+
+            // ライブ表示中は、ウィンドウのボタンから開く
+            if live.is_none() {
+                open::that(&info.path)
+                    .map_err(|e| anyhow::anyhow!("Failed to open output file: {}", e))?;
+            }
+        }
+
+        if let Some(live) = live {
+            let html = info
+                .path
+                .extension()
+                .is_none_or(|ext| ext != "json")
+                .then(|| info.path.clone());
+            live.finish(html, aborted);
         }
 
         Ok(())
     }
 }
 
+fn live_label(enabled: bool) -> &'static str {
+    if enabled { "有効" } else { "無効" }
+}
+
 aviutl2::register_output_plugin!(StatisticsPlugin);
@@ -0,0 +1,226 @@
+use super::{Sample, SessionStatus, stats::LiveStats};
+use aviutl2::config::translate as tr;
+use aviutl2_eframe::{eframe, egui};
+use std::sync::{Arc, Mutex, mpsc::Receiver};
+
+/// 出力中に再描画する間隔。
+const REPAINT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+/// スパークラインの高さ。
+const SPARKLINE_HEIGHT: f32 = 64.0;
+
+pub(super) struct LiveApp {
+    receiver: Receiver<Sample>,
+    status: Arc<Mutex<SessionStatus>>,
+    /// 最後に見た出力の状態。
+    current: SessionStatus,
+    /// `stats`が集計している出力。
+    session: Option<u64>,
+    stats: LiveStats,
+}
+
+impl LiveApp {
+    pub(super) fn new(
+        cc: &eframe::CreationContext<'_>,
+        receiver: Receiver<Sample>,
+        status: Arc<Mutex<SessionStatus>>,
+    ) -> Self {
+        cc.egui_ctx.all_styles_mut(|style| {
+            style.visuals = aviutl2_eframe::aviutl2_visuals();
+        });
+        cc.egui_ctx.set_fonts(aviutl2_eframe::aviutl2_fonts());
+        Self {
+            receiver,
+            status,
+            current: SessionStatus::Idle,
+            session: None,
+            stats: LiveStats::new(0),
+        }
+    }
+
+    /// 出力の状態を読み込み、溜まっているサンプルを集計する。
+    fn sync(&mut self) {
+        self.current = self
+            .status
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if let SessionStatus::Running {
+            session,
+            total_frames,
+        }
+        | SessionStatus::Finished {
+            session,
+            total_frames,
+            ..
+        } = self.current
+            && self.session != Some(session)
+        {
+            self.session = Some(session);
+            self.stats = LiveStats::new(total_frames);
+        }
+        // 状態より先に送られた次の出力のサンプルは、取りこぼしとして扱う
+        while let Ok(sample) = self.receiver.try_recv() {
+            if self.session == Some(sample.session) {
+                self.stats.push(sample.index, sample.ms);
+            }
+        }
+    }
+}
+
+impl eframe::App for LiveApp {
+    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        self.sync();
+        egui::CentralPanel::default().show(ui, |ui| match &self.current {
+            SessionStatus::Idle => {
+                ui.label(tr("出力していません"));
+            }
+            SessionStatus::Running { .. } => {
+                ui.heading(tr("出力中"));
+                ui.separator();
+                self.render_progress(ui);
+                ui.separator();
+                self.render_frames(ui);
+                ui.request_repaint_after(REPAINT_INTERVAL);
+            }
+            SessionStatus::Finished { summary, .. } => {
+                ui.heading(if summary.aborted {
+                    tr("中断")
+                } else {
+                    tr("完了")
+                });
+                ui.separator();
+                egui::Grid::new("summary").num_columns(2).show(ui, |ui| {
+                    ui.label(tr("フレーム"));
+                    ui.label(format!(
+                        "{} / {}",
+                        self.stats.done_frames(),
+                        self.stats.total_frames()
+                    ));
+                    ui.end_row();
+                    ui.label(tr("経過時間"));
+                    ui.label(format_duration(summary.elapsed));
+                    ui.end_row();
+                    ui.label(tr("平均fps"));
+                    let secs = summary.elapsed.as_secs_f64();
+                    ui.label(if secs > 0.0 {
+                        format!("{:.2}", self.stats.done_frames() as f64 / secs)
+                    } else {
+                        "-".to_string()
+                    });
+                    ui.end_row();
+                });
+                if let Some(html) = &summary.html
+                    && ui.button(tr("HTMLを開く")).clicked()
+                    && let Err(e) = open::that(html)
+                {
+                    aviutl2::tracing::error!("Failed to open {}: {}", html.display(), e);
+                }
+                ui.separator();
+                self.render_frames(ui);
+            }
+        });
+    }
+}
+
+impl LiveApp {
+    fn render_progress(&self, ui: &mut egui::Ui) {
+        let stats = &self.stats;
+        let total = stats.total_frames().max(1);
+        ui.add(
+            egui::ProgressBar::new(stats.done_frames() as f32 / total as f32).text(format!(
+                "{} / {}",
+                stats.done_frames(),
+                stats.total_frames()
+            )),
+        );
+        egui::Grid::new("progress").num_columns(2).show(ui, |ui| {
+            ui.label(
+                tr("fps（直近{frames}フレーム）")
+                    .replace("{frames}", &super::stats::ROLLING_FRAMES.to_string()),
+            );
+            ui.label(
+                stats
+                    .rolling_fps()
+                    .map_or_else(|| "-".to_string(), |fps| format!("{fps:.2}")),
+            );
+            ui.end_row();
+            ui.label(tr("残り時間（中央値から推定）"));
+            ui.label(stats.eta().map_or_else(|| "-".to_string(), format_duration));
+            ui.end_row();
+        });
+    }
+
+    /// スパークラインと遅いフレームの一覧を表示する。
+    fn render_frames(&self, ui: &mut egui::Ui) {
+        let stats = &self.stats;
+        self.render_sparkline(ui);
+        egui::Grid::new("counts").num_columns(2).show(ui, |ui| {
+            ui.label(tr("スパイク"));
+            ui.label(stats.spikes().to_string());
+            ui.end_row();
+            ui.label(tr("受け取ったサンプル"));
+            ui.label(format!("{} / {}", stats.received(), stats.done_frames()));
+            ui.end_row();
+            ui.label(tr("取りこぼしたサンプル"));
+            ui.label(stats.dropped().to_string());
+            ui.end_row();
+        });
+        ui.separator();
+        ui.label(tr("遅いフレーム"));
+        egui::Grid::new("slowest")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for frame in stats.slowest() {
+                    ui.label(tr("フレーム {index}").replace("{index}", &frame.index.to_string()));
+                    ui.label(format!("{:.2} ms", frame.ms));
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// 直近のフレームの描画時間を折れ線で描く。スパイクは点で強調する。
+    fn render_sparkline(&self, ui: &mut egui::Ui) {
+        let (rect, _) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), SPARKLINE_HEIGHT),
+            egui::Sense::hover(),
+        );
+        let painter = ui.painter_at(rect);
+        let visuals = ui.visuals();
+        painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+        let recent = self.stats.recent();
+        let count = recent.len();
+        if count < 2 {
+            return;
+        }
+        let max_ms = self
+            .stats
+            .recent()
+            .map(|frame| frame.ms)
+            .fold(f64::EPSILON, f64::max);
+        let step = rect.width() / (super::stats::ROLLING_FRAMES - 1) as f32;
+        let points = recent
+            .enumerate()
+            .map(|(i, frame)| {
+                let position = egui::pos2(
+                    rect.right() - (count - 1 - i) as f32 * step,
+                    rect.bottom() - (frame.ms / max_ms) as f32 * rect.height(),
+                );
+                (position, frame.spike)
+            })
+            .collect::<Vec<_>>();
+        painter.add(egui::Shape::line(
+            points.iter().map(|(position, _)| *position).collect(),
+            egui::Stroke::new(1.5, visuals.selection.bg_fill),
+        ));
+        for (position, _) in points.iter().filter(|(_, spike)| *spike) {
+            painter.circle_filled(*position, 3.0, visuals.error_fg_color);
+        }
+    }
+}
+
+fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
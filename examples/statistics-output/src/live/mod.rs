@@ -0,0 +1,157 @@
+//! 出力中の処理速度を表示するウィンドウ。
+//!
+//! 出力側はサンプルを容量付きのチャンネルに`try_send`するだけで、ウィンドウ側を待つことはない。
+//! チャンネルが一杯の場合はサンプルを捨て、ウィンドウ側でフレーム番号の飛びとして数える。
+
+mod app;
+mod stats;
+
+use aviutl2::AnyResult;
+use aviutl2_eframe::{EframeWindow, egui};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU64, Ordering},
+    mpsc::{Receiver, SyncSender, sync_channel},
+};
+
+/// 出力側からウィンドウに送るサンプルの最大数。
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// 1フレームの描画時間。
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    session: u64,
+    index: u32,
+    ms: f64,
+}
+
+/// 出力の状態。出力の開始時と終了時にだけ書き換える。
+#[derive(Debug, Clone)]
+enum SessionStatus {
+    Idle,
+    Running {
+        session: u64,
+        total_frames: u32,
+    },
+    Finished {
+        session: u64,
+        total_frames: u32,
+        summary: Summary,
+    },
+}
+
+/// 出力が終わった時の集計。
+#[derive(Debug, Clone)]
+struct Summary {
+    elapsed: std::time::Duration,
+    aborted: bool,
+    /// 出力したHTMLファイル。JSONで出力した場合は`None`。
+    html: Option<std::path::PathBuf>,
+}
+
+/// ウィンドウと、ウィンドウにサンプルを送るためのチャンネル。
+///
+/// イベントループは1つのDLLにつき一度しか作成できないため、最初に使う時に作成し、プラグインが破棄されるまで使い回す。
+pub struct LiveMonitor {
+    window: EframeWindow,
+    sender: SyncSender<Sample>,
+    status: Arc<Mutex<SessionStatus>>,
+    next_session: AtomicU64,
+}
+
+impl LiveMonitor {
+    pub fn new() -> AnyResult<Self> {
+        let (sender, receiver) = sync_channel(CHANNEL_CAPACITY);
+        let status = Arc::new(Mutex::new(SessionStatus::Idle));
+        let app_status = Arc::clone(&status);
+        let window = EframeWindow::new_standalone(
+            "RustyStatisticsLive",
+            egui::ViewportBuilder::default()
+                .with_title("Rusty Statistics Output")
+                .with_inner_size([420.0, 480.0])
+                .with_visible(false),
+            move |cc, _handle| Ok(Box::new(app::LiveApp::new(cc, receiver, app_status))),
+        )?;
+        Ok(Self {
+            window,
+            sender,
+            status,
+            next_session: AtomicU64::new(0),
+        })
+    }
+
+    /// 出力を開始し、ウィンドウを表示する。
+    pub fn begin(&self, total_frames: u32) -> LiveSession<'_> {
+        let session = self.next_session.fetch_add(1, Ordering::Relaxed);
+        self.set_status(SessionStatus::Running {
+            session,
+            total_frames,
+        });
+        if let Err(e) = self.window.set_visible(true) {
+            aviutl2::tracing::warn!("Failed to show live window: {}", e);
+        }
+        LiveSession {
+            monitor: self,
+            session,
+            total_frames,
+            started: std::time::Instant::now(),
+            finished: false,
+        }
+    }
+
+    fn set_status(&self, status: SessionStatus) {
+        // ウィンドウ側がパニックしていても出力は続けられるようにする
+        *self.status.lock().unwrap_or_else(|e| e.into_inner()) = status;
+        if let Ok(ctx) = self.window.egui_ctx() {
+            ctx.request_repaint();
+        }
+    }
+}
+
+/// 1回の出力。
+///
+/// [`Self::finish`]を呼ばずに破棄された場合（出力がエラーで終わった場合など）は、中断として表示する。
+pub struct LiveSession<'a> {
+    monitor: &'a LiveMonitor,
+    session: u64,
+    total_frames: u32,
+    started: std::time::Instant,
+    finished: bool,
+}
+
+impl LiveSession<'_> {
+    /// フレームの描画時間を送る。ウィンドウ側が追いついていない場合は捨てる。
+    pub fn push(&self, index: u32, ms: f64) {
+        let _ = self.monitor.sender.try_send(Sample {
+            session: self.session,
+            index,
+            ms,
+        });
+    }
+
+    /// 出力を終了し、ウィンドウに集計を表示する。
+    pub fn finish(mut self, html: Option<std::path::PathBuf>, aborted: bool) {
+        self.finish_with(html, aborted);
+    }
+
+    fn finish_with(&mut self, html: Option<std::path::PathBuf>, aborted: bool) {
+        self.finished = true;
+        self.monitor.set_status(SessionStatus::Finished {
+            session: self.session,
+            total_frames: self.total_frames,
+            summary: Summary {
+                elapsed: self.started.elapsed(),
+                aborted,
+                html,
+            },
+        });
+    }
+}
+
+impl Drop for LiveSession<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.finish_with(None, true);
+        }
+    }
+}
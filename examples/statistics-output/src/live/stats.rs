@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+
+/// fpsとETAの計算に使う直近のフレーム数。
+pub const ROLLING_FRAMES: usize = 120;
+/// 表示する遅いフレームの数。
+pub const SLOWEST_COUNT: usize = 10;
+/// 直近の中央値の何倍以上かかったフレームをスパイクとみなすか。
+pub const SPIKE_RATIO: f64 = 3.0;
+/// スパイクの判定に必要な最低限のフレーム数。これより少ない間は判定しない。
+const SPIKE_MIN_HISTORY: usize = 10;
+
+/// 1フレームの描画時間。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTime {
+    pub index: u32,
+    pub ms: f64,
+    /// 直近の中央値と比べて極端に遅かったかどうか。
+    pub spike: bool,
+}
+
+/// 出力中に受け取ったフレームの描画時間を集計する。
+///
+/// 出力側はサンプルを取りこぼすことがあるため、フレーム番号の飛びを取りこぼしとして数える。
+#[derive(Debug, Clone)]
+pub struct LiveStats {
+    total_frames: u32,
+    last_index: Option<u32>,
+    received: u32,
+    dropped: u32,
+    spikes: u32,
+    recent: VecDeque<FrameTime>,
+    slowest: Vec<FrameTime>,
+}
+
+impl LiveStats {
+    pub fn new(total_frames: u32) -> Self {
+        Self {
+            total_frames,
+            last_index: None,
+            received: 0,
+            dropped: 0,
+            spikes: 0,
+            recent: VecDeque::with_capacity(ROLLING_FRAMES),
+            slowest: Vec::with_capacity(SLOWEST_COUNT + 1),
+        }
+    }
+
+    /// フレームの描画時間を追加する。
+    pub fn push(&mut self, index: u32, ms: f64) {
+        let expected = self.last_index.map_or(0, |last| last + 1);
+        self.dropped += index.saturating_sub(expected);
+        self.last_index = Some(self.last_index.map_or(index, |last| last.max(index)));
+        self.received += 1;
+
+        let spike = self.recent.len() >= SPIKE_MIN_HISTORY
+            && self
+                .median_ms()
+                .is_some_and(|median| ms > median * SPIKE_RATIO);
+        if spike {
+            self.spikes += 1;
+        }
+        let frame = FrameTime { index, ms, spike };
+
+        if self.recent.len() == ROLLING_FRAMES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(frame);
+
+        let position = self.slowest.partition_point(|slow| slow.ms >= ms);
+        if position < SLOWEST_COUNT {
+            self.slowest.insert(position, frame);
+            self.slowest.truncate(SLOWEST_COUNT);
+        }
+    }
+
+    pub fn total_frames(&self) -> u32 {
+        self.total_frames
+    }
+
+    /// 描画が終わったフレーム数。取りこぼしたサンプルも含む。
+    pub fn done_frames(&self) -> u32 {
+        self.last_index.map_or(0, |last| last + 1)
+    }
+
+    /// 受け取れなかったサンプルの数。
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    pub fn spikes(&self) -> u32 {
+        self.spikes
+    }
+
+    /// 直近[`ROLLING_FRAMES`]フレームのfps。
+    pub fn rolling_fps(&self) -> Option<f64> {
+        let total_ms = self.recent.iter().map(|frame| frame.ms).sum::<f64>();
+        (total_ms > 0.0).then(|| self.recent.len() as f64 * 1000.0 / total_ms)
+    }
+
+    /// 直近[`ROLLING_FRAMES`]フレームの描画時間の中央値。
+    pub fn median_ms(&self) -> Option<f64> {
+        if self.recent.is_empty() {
+            return None;
+        }
+        let mut sorted = self.recent.iter().map(|frame| frame.ms).collect::<Vec<_>>();
+        sorted.sort_by(f64::total_cmp);
+        let middle = sorted.len() / 2;
+        Some(if sorted.len() % 2 == 0 {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        } else {
+            sorted[middle]
+        })
+    }
+
+    /// 残りのフレームを直近の中央値で描画した場合の残り時間。
+    ///
+    /// 一部のフレームだけ遅い場合に引きずられないよう、平均ではなく中央値を使う。
+    pub fn eta(&self) -> Option<std::time::Duration> {
+        let remaining = self.total_frames.saturating_sub(self.done_frames());
+        let median = self.median_ms()?;
+        Some(std::time::Duration::from_secs_f64(
+            remaining as f64 * median / 1000.0,
+        ))
+    }
+
+    /// 直近のフレームを古い順に返す。
+    pub fn recent(&self) -> impl ExactSizeIterator<Item = &FrameTime> {
+        self.recent.iter()
+    }
+
+    /// 遅いフレームを遅い順に最大[`SLOWEST_COUNT`]個返す。
+    pub fn slowest(&self) -> &[FrameTime] {
+        &self.slowest
+    }
+
+    /// 受け取ったサンプルの数。
+    pub fn received(&self) -> u32 {
+        self.received
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_window() {
+        let mut stats = LiveStats::new(1000);
+        for i in 0..ROLLING_FRAMES as u32 {
+            stats.push(i, 100.0);
+        }
+        for i in ROLLING_FRAMES as u32..ROLLING_FRAMES as u32 * 2 {
+            stats.push(i, 10.0);
+        }
+        assert_eq!(stats.recent().len(), ROLLING_FRAMES);
+        assert_eq!(stats.rolling_fps(), Some(100.0));
+        assert_eq!(stats.median_ms(), Some(10.0));
+        assert_eq!(stats.done_frames(), ROLLING_FRAMES as u32 * 2);
+        assert_eq!(
+            stats.eta(),
+            Some(std::time::Duration::from_millis(
+                (1000 - ROLLING_FRAMES as u64 * 2) * 10
+            ))
+        );
+    }
+
+    #[test]
+    fn test_spikes_and_slowest() {
+        let mut stats = LiveStats::new(100);
+        // 履歴が少ない間はスパイクとみなさない
+        stats.push(0, 500.0);
+        for i in 1..50 {
+            stats.push(i, 10.0 + (i % 5) as f64);
+        }
+        stats.push(50, 100.0);
+        stats.push(51, 20.0);
+        assert_eq!(stats.spikes(), 1);
+        assert!(stats.recent().any(|frame| frame.index == 50 && frame.spike));
+        assert!(
+            stats
+                .recent()
+                .all(|frame| frame.index == 50 || !frame.spike)
+        );
+
+        let slowest = stats.slowest();
+        assert_eq!(slowest.len(), SLOWEST_COUNT);
+        assert_eq!(
+            slowest[..3].iter().map(|f| f.index).collect::<Vec<_>>(),
+            [0, 50, 51]
+        );
+        assert!(slowest.windows(2).all(|w| w[0].ms >= w[1].ms));
+    }
+
+    #[test]
+    fn test_dropped_samples() {
+        let mut stats = LiveStats::new(10);
+        assert_eq!(stats.eta(), None);
+        stats.push(2, 10.0);
+        stats.push(3, 10.0);
+        stats.push(7, 10.0);
+        assert_eq!(stats.received(), 3);
+        assert_eq!(stats.dropped(), 5);
+        assert_eq!(stats.done_frames(), 8);
+        assert_eq!(stats.eta(), Some(std::time::Duration::from_millis(20)));
+    }
+}
@@ -0,0 +1,219 @@
+mod subtitle;
+
+use aviutl2::input::{
+    AnyResult, CueTimeline, ImageReturner, InputInfo, InputPixelFormat, InputPlugin,
+    InputPluginTable, Rational32, TextCanvas, TextRenderer, VideoInputInfo,
+};
+use subtitle::SubtitleDocument;
+
+#[aviutl2::plugin(InputPlugin)]
+struct SubtitleInputPlugin {
+    renderer: TextRenderer,
+}
+
+struct SubtitleHandle {
+    document: SubtitleDocument,
+    timeline: CueTimeline,
+}
+
+impl SubtitleHandle {
+    fn new(document: SubtitleDocument) -> AnyResult<Self> {
+        let timeline = CueTimeline::new(document.cues.iter().map(|cue| (cue.start, cue.end)))?;
+        Ok(Self { document, timeline })
+    }
+}
+
+/// システムのフォントから、字幕の描画に使うフォントを読み込む。
+fn load_system_font() -> AnyResult<TextRenderer> {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+    let id = db
+        .query(&fontdb::Query {
+            families: &[
+                fontdb::Family::Name("Yu Gothic UI"),
+                fontdb::Family::SansSerif,
+            ],
+            ..Default::default()
+        })
+        .ok_or_else(|| anyhow::anyhow!("No font is available"))?;
+    db.with_face_data(id, |data, index| {
+        TextRenderer::from_bytes_with_index(data.to_vec(), index)
+    })
+    .ok_or_else(|| anyhow::anyhow!("Failed to read font data"))?
+}
+
+/// 指定したフレームで表示するキューを描画する。
+fn render_frame(renderer: &TextRenderer, handle: &SubtitleHandle, frame: u32) -> TextCanvas {
+    let mut canvas = TextCanvas::new(handle.document.width, handle.document.height);
+    for &index in handle.timeline.cues_at(frame) {
+        let cue = &handle.document.cues[index];
+        renderer.draw(&mut canvas, &cue.text, &cue.style);
+    }
+    canvas
+}
+
+impl InputPlugin for SubtitleInputPlugin {
+    type InputHandle = SubtitleHandle;
+
+    fn new(_info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
+        Ok(SubtitleInputPlugin {
+            renderer: load_system_font()?,
+        })
+    }
+
+    fn plugin_info(&self) -> InputPluginTable {
+        InputPluginTable {
+            name: "Rusty Subtitle Input".to_string(),
+            input_type: aviutl2::input::InputType::Video,
+            file_filters: aviutl2::file_filters! {
+                "Subtitles" => ["srt", "ass", "ssa"],
+            },
+            information: format!(
+                "Subtitle Input, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/subtitle-input",
+                version = env!("CARGO_PKG_VERSION")
+            ),
+            fail_on_read_error: false,
+            can_config: false,
+            concurrency: aviutl2::input::ConcurrencyMode::full::<SubtitleHandle>(),
+            share_handles: true,
+            cache_input_info: false,
+        }
+    }
+
+    fn open(&self, file: std::path::PathBuf) -> AnyResult<Self::InputHandle> {
+        let source = std::fs::read_to_string(&file)?;
+        let extension = file
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_ascii_lowercase());
+        let document = match extension.as_deref() {
+            Some("srt") => subtitle::parse_srt(&source)?,
+            Some("ass" | "ssa") => subtitle::parse_ass(&source)?,
+            _ => anyhow::bail!("Unsupported subtitle format: {}", file.display()),
+        };
+        SubtitleHandle::new(document)
+    }
+
+    fn get_input_info(
+        &self,
+        handle: &mut Self::InputHandle,
+        _video_track: u32,
+        _audio_track: u32,
+    ) -> AnyResult<InputInfo> {
+        // 区間ごとに1フレームなので、全体の長さが合うようにフレームレートを決める
+        let duration = handle.timeline.duration();
+        let fps = if duration > 0.0 {
+            let fps = handle.timeline.num_frames() as f64 / duration;
+            Rational32::new((fps * 1000.0).round().max(1.0) as i32, 1000)
+        } else {
+            Rational32::new(1, 1)
+        };
+        Ok(InputInfo {
+            video: Some(VideoInputInfo {
+                fps,
+                num_frames: handle.timeline.num_frames(),
+                manual_frame_index: true,
+                width: handle.document.width,
+                height: handle.document.height,
+                format: InputPixelFormat::Bgra,
+                alpha: aviutl2::input::AlphaMode::Straight,
+                colorimetry: aviutl2::pixel::Colorimetry::HOST,
+            }),
+            audio: None,
+        })
+    }
+
+    fn read_video(
+        &self,
+        handle: &Self::InputHandle,
+        frame: u32,
+        returner: &mut ImageReturner,
+    ) -> AnyResult<()> {
+        anyhow::ensure!(
+            frame < handle.timeline.num_frames(),
+            "Frame index out of bounds: {frame} >= {}",
+            handle.timeline.num_frames()
+        );
+        returner.write(&render_frame(&self.renderer, handle, frame).to_bgra());
+        Ok(())
+    }
+
+    fn time_to_frame(
+        &self,
+        handle: &mut Self::InputHandle,
+        _track: u32,
+        time: f64,
+    ) -> AnyResult<u32> {
+        Ok(handle.timeline.frame_for(time))
+    }
+
+    fn close(&self, _handle: Self::InputHandle) -> AnyResult<()> {
+        Ok(())
+    }
+}
+
+aviutl2::register_input_plugin!(SubtitleInputPlugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renderer() -> TextRenderer {
+        TextRenderer::from_bytes(epaint_default_fonts::UBUNTU_LIGHT.to_vec()).unwrap()
+    }
+
+    const SRT: &str =
+        "1\n00:00:01,000 --> 00:00:02,000\nHello\n\n2\n00:00:03,000 --> 00:00:04,000\nWorld\n";
+
+    #[test]
+    fn test_cues_map_to_frames() {
+        let handle = SubtitleHandle::new(subtitle::parse_srt(SRT).unwrap()).unwrap();
+        // 0〜1秒（無し）、1〜2秒（Hello）、2〜3秒（無し）、3〜4秒（World）
+        assert_eq!(handle.timeline.num_frames(), 4);
+        assert_eq!(handle.timeline.frame_for(1.5), 1);
+        assert_eq!(handle.timeline.cues_at(1), [0]);
+        assert!(handle.timeline.cues_at(2).is_empty());
+        assert_eq!(handle.timeline.frame_for(3.0), 3);
+    }
+
+    #[test]
+    fn test_render_cue_at_known_frame() {
+        let renderer = renderer();
+        let handle = SubtitleHandle::new(subtitle::parse_srt(SRT).unwrap()).unwrap();
+
+        // キューの無いフレームは完全に透明
+        let empty = render_frame(&renderer, &handle, 0);
+        assert!(empty.pixels().iter().all(|pixel| pixel[3] == 0));
+
+        // 1.5秒のフレームには、下中央に白文字・黒縁の「Hello」が描画される
+        let frame = render_frame(&renderer, &handle, handle.timeline.frame_for(1.5));
+        assert_eq!((frame.width(), frame.height()), (1920, 1080));
+        let opaque = (0..frame.height())
+            .flat_map(|y| (0..frame.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| frame.pixel(x, y)[3] == 255)
+            .collect::<Vec<_>>();
+        assert!(!opaque.is_empty());
+        let left = opaque.iter().map(|&(x, _)| x).min().unwrap();
+        let right = opaque.iter().map(|&(x, _)| x).max().unwrap();
+        let top = opaque.iter().map(|&(_, y)| y).min().unwrap();
+        let bottom = opaque.iter().map(|&(_, y)| y).max().unwrap();
+        assert!(((left + right) as i64 / 2 - 960).abs() <= 4);
+        assert!(top > 1080 - 32 - 64 && bottom < 1080 - 32);
+        assert!(
+            opaque
+                .iter()
+                .any(|&(x, y)| frame.pixel(x, y) == [255, 255, 255, 255])
+        );
+        assert!(
+            opaque
+                .iter()
+                .any(|&(x, y)| frame.pixel(x, y) == [0, 0, 0, 255])
+        );
+
+        // 同じフレームをもう一度描画しても、ラスタライズし直さない
+        assert_eq!(renderer.cache_len(), 1);
+        let again = render_frame(&renderer, &handle, handle.timeline.frame_for(1.5));
+        assert_eq!(renderer.cache_len(), 1);
+        assert_eq!(again, frame);
+    }
+}
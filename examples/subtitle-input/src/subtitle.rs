@@ -0,0 +1,336 @@
+use aviutl2::{
+    AnyResult,
+    input::{TextAlignment, TextStyle},
+};
+
+/// 字幕の1つのキュー。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    /// 開始時刻（秒）。
+    pub start: f64,
+    /// 終了時刻（秒）。
+    pub end: f64,
+    /// 表示するテキスト。改行は`\n`。
+    pub text: String,
+    pub style: TextStyle,
+}
+
+/// 読み込んだ字幕ファイル。
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleDocument {
+    /// 描画する画像の幅。
+    pub width: u32,
+    /// 描画する画像の高さ。
+    pub height: u32,
+    pub cues: Vec<Cue>,
+}
+
+/// 解像度の指定が無い場合の大きさ。
+const DEFAULT_SIZE: (u32, u32) = (1920, 1080);
+
+/// SRT形式の字幕を読み込む。
+///
+/// `<i>`などのHTMLタグは取り除きます。
+pub fn parse_srt(source: &str) -> AnyResult<SubtitleDocument> {
+    let source = source.trim_start_matches('\u{feff}');
+    let subtitles = srtlib::Subtitles::parse_from_str(source.to_string())
+        .map_err(|e| anyhow::anyhow!("Failed to parse SRT: {e}"))?;
+    let cues = subtitles
+        .to_vec()
+        .into_iter()
+        .map(|subtitle| Cue {
+            start: srt_seconds(&subtitle.start_time),
+            end: srt_seconds(&subtitle.end_time),
+            text: strip_html_tags(&subtitle.text),
+            style: TextStyle::default(),
+        })
+        .collect();
+    Ok(SubtitleDocument {
+        width: DEFAULT_SIZE.0,
+        height: DEFAULT_SIZE.1,
+        cues,
+    })
+}
+
+fn srt_seconds(timestamp: &srtlib::Timestamp) -> f64 {
+    let (h, m, s, ms) = timestamp.get();
+    srtlib::Timestamp::convert_to_milliseconds(h, m, s, ms) as f64 / 1000.0
+}
+
+fn strip_html_tags(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => result.push(c),
+            _ => {}
+        }
+    }
+    result
+}
+
+/// ASS（SSA）形式の字幕を読み込む。
+///
+/// 対応しているのは、スタイルの文字の大きさ・色・縁取り・配置・余白と、
+/// 上書きタグの`\an`・`\pos`・`\c`（`\1c`）・`\3c`・`\bord`・`\fs`だけです。
+/// `PlayResX`・`PlayResY`が無い場合は1920x1080として扱います。
+pub fn parse_ass(source: &str) -> AnyResult<SubtitleDocument> {
+    let mut section = String::new();
+    let mut width = None;
+    let mut height = None;
+    let mut style_format = Vec::new();
+    let mut styles = std::collections::HashMap::new();
+    let mut event_format = Vec::new();
+    let mut cues = Vec::new();
+
+    for (line_number, line) in source.lines().enumerate() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line.to_ascii_lowercase();
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match (section.as_str(), key.trim()) {
+            ("[script info]", "PlayResX") => width = value.parse().ok(),
+            ("[script info]", "PlayResY") => height = value.parse().ok(),
+            ("[v4+ styles]" | "[v4 styles]", "Format") => style_format = split_format(value),
+            ("[v4+ styles]" | "[v4 styles]", "Style") => {
+                let fields = split_fields(value, &style_format);
+                let name = fields.get("name").copied().unwrap_or("Default");
+                styles.insert(name.to_string(), parse_style(&fields));
+            }
+            ("[events]", "Format") => event_format = split_format(value),
+            ("[events]", "Dialogue") => {
+                let fields = split_fields(value, &event_format);
+                let time = |name: &str| {
+                    fields
+                        .get(name)
+                        .and_then(|value| parse_ass_time(value))
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("Invalid {name} time at line {}", line_number + 1)
+                        })
+                };
+                let start = time("start")?;
+                let end = time("end")?;
+                let style = fields
+                    .get("style")
+                    .and_then(|name| styles.get(name.trim_start_matches('*')))
+                    .cloned()
+                    .unwrap_or_default();
+                let (text, style) = apply_overrides(fields.get("text").unwrap_or(&""), style);
+                cues.push(Cue {
+                    start,
+                    end,
+                    text,
+                    style,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(SubtitleDocument {
+        width: width.unwrap_or(DEFAULT_SIZE.0),
+        height: height.unwrap_or(DEFAULT_SIZE.1),
+        cues,
+    })
+}
+
+fn split_format(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect()
+}
+
+/// `Format`で指定された順にフィールドを分割する。最後のフィールド（`Text`）はカンマを含められる。
+fn split_fields<'a>(
+    value: &'a str,
+    format: &'a [String],
+) -> std::collections::HashMap<&'a str, &'a str> {
+    format
+        .iter()
+        .map(String::as_str)
+        .zip(value.splitn(format.len().max(1), ',').map(str::trim))
+        .collect()
+}
+
+fn parse_style(fields: &std::collections::HashMap<&str, &str>) -> TextStyle {
+    let mut style = TextStyle::default();
+    let number = |name: &str| fields.get(name).and_then(|value| value.parse::<f32>().ok());
+    if let Some(font_size) = number("fontsize") {
+        style.font_size = font_size;
+    }
+    if let Some(color) = fields.get("primarycolour").and_then(|v| parse_ass_color(v)) {
+        style.color = color;
+    }
+    if let Some(color) = fields.get("outlinecolour").and_then(|v| parse_ass_color(v)) {
+        style.outline_color = color;
+    }
+    if let Some(outline) = number("outline") {
+        style.outline_width = outline.max(0.0).round() as u32;
+    }
+    if let Some(alignment) = number("alignment").and_then(|v| TextAlignment::from_numpad(v as u8)) {
+        style.alignment = alignment;
+    }
+    let margin_x = number("marginl").map_or(style.margin.0, |v| v.max(0.0) as u32);
+    let margin_y = number("marginv").map_or(style.margin.1, |v| v.max(0.0) as u32);
+    style.margin = (margin_x, margin_y);
+    style
+}
+
+/// `H:MM:SS.cc`形式の時刻を秒に変換する。
+fn parse_ass_time(value: &str) -> Option<f64> {
+    let mut parts = value.trim().splitn(3, ':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// `&HAABBGGRR&`形式の色をRGBAに変換する。ASSのαは0が不透明。
+fn parse_ass_color(value: &str) -> Option<[u8; 4]> {
+    let hex = value
+        .trim()
+        .trim_start_matches('&')
+        .trim_start_matches(['H', 'h'])
+        .trim_end_matches('&');
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let [r, g, b, a] = value.to_le_bytes();
+    Some([r, g, b, 255 - a])
+}
+
+/// テキスト中の上書きタグ（`{\an8}`など）をスタイルに反映し、タグを取り除いたテキストを返す。
+fn apply_overrides(text: &str, mut style: TextStyle) -> (String, TextStyle) {
+    let mut plain = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        plain.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        for tag in rest[open + 1..open + close].split('\\') {
+            apply_tag(tag.trim(), &mut style);
+        }
+        rest = &rest[open + close + 1..];
+    }
+    plain.push_str(rest);
+    let plain = plain
+        .replace("\\N", "\n")
+        .replace("\\n", "\n")
+        .replace("\\h", "\u{a0}");
+    (plain, style)
+}
+
+fn apply_tag(tag: &str, style: &mut TextStyle) {
+    if let Some(value) = tag.strip_prefix("an") {
+        if let Some(alignment) = value.parse().ok().and_then(TextAlignment::from_numpad) {
+            style.alignment = alignment;
+        }
+    } else if let Some(value) = tag.strip_prefix("pos(") {
+        let mut coords = value.trim_end_matches(')').split(',');
+        if let (Some(Ok(x)), Some(Ok(y))) = (
+            coords.next().map(|v| v.trim().parse::<f32>()),
+            coords.next().map(|v| v.trim().parse::<f32>()),
+        ) {
+            style.position = Some((x, y));
+        }
+    } else if let Some(value) = tag.strip_prefix("3c") {
+        if let Some([r, g, b, _]) = parse_ass_color(value) {
+            style.outline_color = [r, g, b, style.outline_color[3]];
+        }
+    } else if let Some(value) = tag
+        .strip_prefix("1c")
+        .or_else(|| tag.strip_prefix('c').filter(|v| v.starts_with('&')))
+    {
+        if let Some([r, g, b, _]) = parse_ass_color(value) {
+            style.color = [r, g, b, style.color[3]];
+        }
+    } else if let Some(value) = tag.strip_prefix("bord") {
+        if let Ok(width) = value.parse::<f32>() {
+            style.outline_width = width.max(0.0).round() as u32;
+        }
+    } else if let Some(value) = tag.strip_prefix("fs") {
+        // \fscx・\fscy・\fspは数値で始まらないので、ここで除外される
+        if let Ok(size) = value.parse::<f32>() {
+            style.font_size = size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_srt() {
+        let document = parse_srt(
+            "\u{feff}1\n00:00:01,000 --> 00:00:02,500\n<i>Hello</i>\nWorld\n\n2\n00:00:03,000 --> 00:00:04,000\nBye\n",
+        )
+        .unwrap();
+        assert_eq!((document.width, document.height), DEFAULT_SIZE);
+        assert_eq!(document.cues.len(), 2);
+        assert_eq!(document.cues[0].start, 1.0);
+        assert_eq!(document.cues[0].end, 2.5);
+        assert_eq!(document.cues[0].text, "Hello\nWorld");
+        assert_eq!(document.cues[1].text, "Bye");
+    }
+
+    const ASS: &str = r"[Script Info]
+ScriptType: v4.00+
+PlayResX: 1280
+PlayResY: 720
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding
+Style: Default,Arial,40,&H00FFFFFF,&H000000FF,&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,3,0,2,10,10,24,1
+Style: Top,Arial,30,&H0000FFFF,&H000000FF,&H80FF0000,&H00000000,0,0,0,0,100,100,0,0,1,1,0,8,10,10,10,1
+
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0,0:00:01.00,0:00:03.50,Default,,0,0,0,,Hello, world!\NSecond line
+Dialogue: 0,0:00:02.00,0:00:04.00,Top,,0,0,0,,{\an7\pos(100,50)\c&H0000FF&\3c&H00FF00&\bord5\fs60}Tagged
+";
+
+    #[test]
+    fn test_parse_ass() {
+        let document = parse_ass(ASS).unwrap();
+        assert_eq!((document.width, document.height), (1280, 720));
+        assert_eq!(document.cues.len(), 2);
+
+        let first = &document.cues[0];
+        assert_eq!((first.start, first.end), (1.0, 3.5));
+        assert_eq!(first.text, "Hello, world!\nSecond line");
+        assert_eq!(first.style.font_size, 40.0);
+        assert_eq!(first.style.color, [255, 255, 255, 255]);
+        assert_eq!(first.style.outline_width, 3);
+        assert_eq!(first.style.alignment, TextAlignment::BottomCenter);
+        assert_eq!(first.style.margin, (10, 24));
+
+        let second = &document.cues[1];
+        assert_eq!((second.start, second.end), (2.0, 4.0));
+        assert_eq!(second.text, "Tagged");
+        assert_eq!(second.style.alignment, TextAlignment::TopLeft);
+        assert_eq!(second.style.position, Some((100.0, 50.0)));
+        // &H0000FF& は赤
+        assert_eq!(second.style.color, [255, 0, 0, 255]);
+        // 色だけを上書きし、αはスタイルのものを引き継ぐ
+        assert_eq!(second.style.outline_color, [0, 255, 0, 127]);
+        assert_eq!(second.style.outline_width, 5);
+        assert_eq!(second.style.font_size, 60.0);
+    }
+
+    #[test]
+    fn test_parse_ass_color() {
+        assert_eq!(parse_ass_color("&H00FFFFFF"), Some([255, 255, 255, 255]));
+        assert_eq!(parse_ass_color("&HFF0000&"), Some([0, 0, 255, 255]));
+        assert_eq!(parse_ass_color("&HFF000000"), Some([0, 0, 0, 0]));
+        assert_eq!(parse_ass_color("invalid"), None);
+    }
+}
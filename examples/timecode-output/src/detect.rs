@@ -0,0 +1,186 @@
+/// 差分を求めるときに縮小する格子の横の数。
+pub const GRID_WIDTH: usize = 32;
+/// 差分を求めるときに縮小する格子の縦の数。
+pub const GRID_HEIGHT: usize = 18;
+
+/// カットの検出に使うパラメーター。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectionParams {
+    /// 閾値を求めるのに使う、直前のフレームの数。
+    pub window: usize,
+    /// 中央絶対偏差に掛ける係数。
+    pub k: f32,
+    /// 中央値からの最小の差（0〜255の輝度）。
+    ///
+    /// 静止したシーンでは中央絶対偏差がほぼ0になるため、わずかな変化をカットと誤検出しないようにします。
+    pub min_jump: f32,
+    /// カットの間の最小のフレーム数。
+    pub min_gap: usize,
+}
+
+impl Default for DetectionParams {
+    fn default() -> Self {
+        Self {
+            window: 15,
+            k: 5.0,
+            min_jump: 12.0,
+            min_gap: 5,
+        }
+    }
+}
+
+/// BGR24のフレームを`GRID_WIDTH`x`GRID_HEIGHT`の格子に縮小し、格子ごとの輝度の平均を返す。
+pub fn downscale_bgr(data: &[u8], width: usize, height: usize) -> Vec<f32> {
+    let mut sums = vec![0.0f32; GRID_WIDTH * GRID_HEIGHT];
+    let mut counts = vec![0u32; GRID_WIDTH * GRID_HEIGHT];
+    for y in 0..height {
+        let cell_y = y * GRID_HEIGHT / height;
+        let row = &data[y * width * 3..(y + 1) * width * 3];
+        for (x, pixel) in row.chunks_exact(3).enumerate() {
+            let cell = cell_y * GRID_WIDTH + x * GRID_WIDTH / width;
+            let luma = 0.114 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.299 * pixel[2] as f32;
+            sums[cell] += luma;
+            counts[cell] += 1;
+        }
+    }
+    sums.iter()
+        .zip(&counts)
+        .map(|(&sum, &count)| if count == 0 { 0.0 } else { sum / count as f32 })
+        .collect()
+}
+
+/// 縮小した2つのフレームの差の絶対値の平均を返す。
+pub fn frame_difference(a: &[f32], b: &[f32]) -> f32 {
+    if a.is_empty() {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(a, b)| (a - b).abs()).sum::<f32>() / a.len() as f32
+}
+
+/// フレーム間の差の列からカットの位置を検出する。
+///
+/// `differences[i]`は`i - 1`フレーム目と`i`フレーム目の差で、`differences[0]`は使用しません。
+/// 戻り値はカットの直後のフレーム（新しいショットの最初のフレーム）の番号です。
+pub fn detect_cuts(differences: &[f32], params: &DetectionParams) -> Vec<usize> {
+    let mut cuts: Vec<usize> = Vec::new();
+    for i in 1..differences.len() {
+        let start = i.saturating_sub(params.window).max(1);
+        let history = &differences[start..i];
+        let (median, mad) = if history.is_empty() {
+            (0.0, 0.0)
+        } else {
+            median_and_mad(history)
+        };
+        let threshold = median + (params.k * mad).max(params.min_jump);
+        if differences[i] <= threshold {
+            continue;
+        }
+        if cuts.last().is_some_and(|&last| i - last < params.min_gap) {
+            continue;
+        }
+        cuts.push(i);
+    }
+    cuts
+}
+
+/// 中央値と中央絶対偏差を返す。
+fn median_and_mad(values: &[f32]) -> (f32, f32) {
+    let center = median(values.to_vec());
+    let deviations = values.iter().map(|v| (v - center).abs()).collect();
+    (center, median(deviations))
+}
+
+fn median(mut values: Vec<f32>) -> f32 {
+    values.sort_by(f32::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `shots`の各要素を`(輝度, フレーム数)`とした、単色のフレームの列を作る。
+    /// `noise`が`true`の場合は、フレームごとに少しずつ明るさを揺らす。
+    fn synthetic_frames(shots: &[(u8, usize)], noise: bool) -> Vec<Vec<u8>> {
+        let (width, height) = (64, 36);
+        let mut frames = Vec::new();
+        for &(luma, length) in shots {
+            for i in 0..length {
+                let jitter = if noise { (i % 3) as u8 * 2 } else { 0 };
+                let value = luma.saturating_add(jitter);
+                frames.push(vec![value; width * height * 3]);
+            }
+        }
+        frames
+    }
+
+    fn differences(frames: &[Vec<u8>]) -> Vec<f32> {
+        let grids: Vec<_> = frames.iter().map(|f| downscale_bgr(f, 64, 36)).collect();
+        std::iter::once(0.0)
+            .chain(grids.windows(2).map(|w| frame_difference(&w[0], &w[1])))
+            .collect()
+    }
+
+    #[test]
+    fn test_downscale_bgr() {
+        // 左半分が白、右半分が黒
+        let (width, height) = (64, 4);
+        let mut data = vec![0u8; width * height * 3];
+        for y in 0..height {
+            for x in 0..width / 2 {
+                data[(y * width + x) * 3..][..3].copy_from_slice(&[255, 255, 255]);
+            }
+        }
+        let grid = downscale_bgr(&data, width, height);
+        assert_eq!(grid.len(), GRID_WIDTH * GRID_HEIGHT);
+        assert!((grid[0] - 255.0).abs() < 0.01);
+        assert_eq!(grid[GRID_WIDTH - 1], 0.0);
+    }
+
+    #[test]
+    fn test_downscale_bgr_smaller_than_grid() {
+        let grid = downscale_bgr(&[10, 20, 30], 1, 1);
+        assert!((grid[0] - (0.114 * 10.0 + 0.587 * 20.0 + 0.299 * 30.0)).abs() < 0.01);
+        assert!(grid[1..].iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn test_detect_cuts_known_positions() {
+        let frames = synthetic_frames(&[(20, 30), (200, 25), (90, 40)], true);
+        let cuts = detect_cuts(&differences(&frames), &DetectionParams::default());
+        assert_eq!(cuts, vec![30, 55]);
+    }
+
+    #[test]
+    fn test_detect_cuts_ignores_gradual_change() {
+        // 1フレームずつ少しずつ明るくなるフェード
+        let frames: Vec<_> = (0..100u8).map(|i| vec![i * 2; 64 * 36 * 3]).collect();
+        assert!(detect_cuts(&differences(&frames), &DetectionParams::default()).is_empty());
+    }
+
+    #[test]
+    fn test_detect_cuts_adapts_to_busy_scene() {
+        // 動きの多いシーンでは、同じ大きさの変化をカットとみなさない
+        let mut differences = vec![0.0];
+        differences.extend([10.0, 20.0, 30.0, 15.0, 25.0].repeat(8));
+        differences.push(35.0);
+        differences.extend([1.0; 10]);
+        differences.push(35.0);
+        let cuts = detect_cuts(&differences, &DetectionParams::default());
+        assert_eq!(cuts, vec![52]);
+    }
+
+    #[test]
+    fn test_detect_cuts_min_gap() {
+        let mut differences = vec![0.0; 20];
+        differences[10] = 100.0;
+        differences[12] = 100.0;
+        let cuts = detect_cuts(&differences, &DetectionParams::default());
+        assert_eq!(cuts, vec![10]);
+    }
+}
@@ -0,0 +1,79 @@
+mod detect;
+mod list;
+mod timecode;
+
+use aviutl2::output::{OutputPlugin, video_frame::RawBgrVideoFrame};
+use std::io::Write;
+
+#[aviutl2::plugin(OutputPlugin)]
+struct TimecodeOutputPlugin {}
+
+impl OutputPlugin for TimecodeOutputPlugin {
+    fn new(_info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+        Ok(TimecodeOutputPlugin {})
+    }
+
+    fn plugin_info(&self) -> aviutl2::output::OutputPluginTable {
+        aviutl2::output::OutputPluginTable {
+            name: "Rusty Timecode Output".to_string(),
+            output_type: aviutl2::output::OutputType::Video,
+            file_filters: aviutl2::file_filters! {
+                "Edit Decision List" => ["edl"],
+                "CSV" => ["csv"],
+            },
+
+            information: format!(
+                "Scene cut list output for AviUtl2, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/timecode-output",
+                version = env!("CARGO_PKG_VERSION")
+            ),
+            can_config: false,
+            project_config: false,
+        }
+    }
+
+    fn output(&self, info: aviutl2::output::OutputInfo) -> aviutl2::AnyResult<()> {
+        let Some(video_info) = &info.video else {
+            anyhow::bail!("動画情報がありません。");
+        };
+        let (width, height) = (video_info.width as usize, video_info.height as usize);
+
+        let mut differences = Vec::with_capacity(video_info.num_frames as usize);
+        let mut previous: Option<Vec<f32>> = None;
+        for (_, frame) in info.get_video_frames_iter::<RawBgrVideoFrame>() {
+            let grid = detect::downscale_bgr(&frame.data, width, height);
+            differences.push(
+                previous
+                    .as_ref()
+                    .map_or(0.0, |previous| detect::frame_difference(previous, &grid)),
+            );
+            previous = Some(grid);
+        }
+        if info.is_aborted() {
+            return Ok(());
+        }
+        anyhow::ensure!(
+            differences.len() == video_info.num_frames as usize,
+            "Only {} of {} frames were read",
+            differences.len(),
+            video_info.num_frames
+        );
+
+        let cuts = detect::detect_cuts(&differences, &detect::DetectionParams::default());
+        let shots = list::shots_from_cuts(&cuts, video_info.num_frames as u64);
+        let base = timecode::TimecodeBase::from_fps(video_info.fps);
+        let title = info.path.file_stem().map_or_else(
+            || "Untitled".to_string(),
+            |s| s.to_string_lossy().into_owned(),
+        );
+
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(&info.path)?);
+        match list::ListFormat::from_path(&info.path) {
+            list::ListFormat::Edl => list::write_edl(&mut writer, &title, &shots, base)?,
+            list::ListFormat::Csv => list::write_csv(&mut writer, &shots, base)?,
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+aviutl2::register_output_plugin!(TimecodeOutputPlugin);
@@ -0,0 +1,175 @@
+use crate::timecode::TimecodeBase;
+
+/// 保存するファイルの形式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    /// CMX3600形式のEDL。
+    Edl,
+    /// CSV。
+    Csv,
+}
+
+impl ListFormat {
+    /// 拡張子から形式を決める。`.csv`以外はEDLとして扱う。
+    pub fn from_path(path: &std::path::Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => ListFormat::Csv,
+            _ => ListFormat::Edl,
+        }
+    }
+}
+
+/// カットで区切られたショット。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shot {
+    /// 最初のフレーム。
+    pub start: u64,
+    /// 最後のフレームの次のフレーム。
+    pub end: u64,
+}
+
+/// カットの位置から、`total_frames`フレームをショットに区切る。
+pub fn shots_from_cuts(cuts: &[usize], total_frames: u64) -> Vec<Shot> {
+    let mut boundaries = vec![0];
+    boundaries.extend(
+        cuts.iter()
+            .map(|&cut| cut as u64)
+            .filter(|&cut| cut > 0 && cut < total_frames),
+    );
+    boundaries.push(total_frames);
+    boundaries
+        .windows(2)
+        .filter(|w| w[0] < w[1])
+        .map(|w| Shot {
+            start: w[0],
+            end: w[1],
+        })
+        .collect()
+}
+
+/// ショットの一覧をCMX3600形式のEDLとして書き込む。
+///
+/// 素材とタイムラインが同じ位置になるように、ソースとレコードに同じタイムコードを使います。
+pub fn write_edl(
+    writer: &mut impl std::io::Write,
+    title: &str,
+    shots: &[Shot],
+    base: TimecodeBase,
+) -> std::io::Result<()> {
+    writeln!(writer, "TITLE: {title}")?;
+    writeln!(
+        writer,
+        "FCM: {}",
+        if base.drop_frame {
+            "DROP FRAME"
+        } else {
+            "NON-DROP FRAME"
+        }
+    )?;
+    writeln!(writer)?;
+    for (i, shot) in shots.iter().enumerate() {
+        let start = base.timecode(shot.start);
+        let end = base.timecode(shot.end);
+        writeln!(
+            writer,
+            "{:03}  AX       V     C        {start} {end} {start} {end}",
+            i + 1
+        )?;
+        writeln!(writer, "* FROM CLIP NAME: {title}")?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// ショットの一覧をCSVとして書き込む。
+pub fn write_csv(
+    writer: &mut impl std::io::Write,
+    shots: &[Shot],
+    base: TimecodeBase,
+) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "index,start_frame,end_frame,duration_frames,start_timecode,end_timecode"
+    )?;
+    for (i, shot) in shots.iter().enumerate() {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            i + 1,
+            shot.start,
+            shot.end,
+            shot.end - shot.start,
+            base.timecode(shot.start),
+            base.timecode(shot.end)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NDF_30: TimecodeBase = TimecodeBase {
+        nominal_fps: 30,
+        drop_frame: false,
+    };
+
+    #[test]
+    fn test_list_format_from_path() {
+        let format = |path: &str| ListFormat::from_path(std::path::Path::new(path));
+        assert_eq!(format("cuts.csv"), ListFormat::Csv);
+        assert_eq!(format("cuts.CSV"), ListFormat::Csv);
+        assert_eq!(format("cuts.edl"), ListFormat::Edl);
+        assert_eq!(format("cuts"), ListFormat::Edl);
+    }
+
+    #[test]
+    fn test_shots_from_cuts() {
+        assert_eq!(
+            shots_from_cuts(&[30, 55], 95),
+            vec![
+                Shot { start: 0, end: 30 },
+                Shot { start: 30, end: 55 },
+                Shot { start: 55, end: 95 },
+            ]
+        );
+        assert_eq!(shots_from_cuts(&[], 10), vec![Shot { start: 0, end: 10 }]);
+        assert!(shots_from_cuts(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn test_write_edl() {
+        let mut buffer = Vec::new();
+        let shots = shots_from_cuts(&[45], 90);
+        write_edl(&mut buffer, "Test", &shots, NDF_30).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "TITLE: Test\n\
+             FCM: NON-DROP FRAME\n\
+             \n\
+             001  AX       V     C        00:00:00:00 00:00:01:15 00:00:00:00 00:00:01:15\n\
+             * FROM CLIP NAME: Test\n\
+             \n\
+             002  AX       V     C        00:00:01:15 00:00:03:00 00:00:01:15 00:00:03:00\n\
+             * FROM CLIP NAME: Test\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn test_write_csv_drop_frame() {
+        let base = TimecodeBase {
+            nominal_fps: 30,
+            drop_frame: true,
+        };
+        let mut buffer = Vec::new();
+        write_csv(&mut buffer, &shots_from_cuts(&[1800], 1900), base).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "index,start_frame,end_frame,duration_frames,start_timecode,end_timecode\n\
+             1,0,1800,1800,00:00:00;00,00:01:00;02\n\
+             2,1800,1900,100,00:01:00;02,00:01:03;12\n"
+        );
+    }
+}
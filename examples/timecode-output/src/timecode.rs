@@ -0,0 +1,167 @@
+use aviutl2::Rational32;
+
+/// タイムコードの数え方。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimecodeBase {
+    /// 1秒あたりのフレーム数（29.97fpsの場合は30）。
+    pub nominal_fps: u32,
+    /// ドロップフレームかどうか。
+    pub drop_frame: bool,
+}
+
+impl TimecodeBase {
+    /// フレームレートからタイムコードの数え方を決める。
+    ///
+    /// 30000/1001（29.97fps）と60000/1001（59.94fps）はドロップフレーム、
+    /// それ以外は四捨五入したフレームレートのノンドロップフレームになります。
+    pub fn from_fps(fps: Rational32) -> Self {
+        let (numer, denom) = (*fps.numer() as i64, *fps.denom() as i64);
+        let nominal_fps = ((numer + denom / 2) / denom).max(1) as u32;
+        let drop_frame =
+            numer * 1001 == nominal_fps as i64 * 1000 * denom && matches!(nominal_fps, 30 | 60);
+        Self {
+            nominal_fps,
+            drop_frame,
+        }
+    }
+
+    /// 1分ごとに飛ばすフレーム番号の数。
+    fn dropped_per_minute(&self) -> u64 {
+        if self.drop_frame {
+            self.nominal_fps as u64 / 15
+        } else {
+            0
+        }
+    }
+
+    /// フレーム番号（0始まり）をタイムコードに変換する。
+    ///
+    /// 24時間を超える場合は0時に戻ります。
+    pub fn timecode(&self, frame: u64) -> Timecode {
+        let fps = self.nominal_fps as u64;
+        let drop = self.dropped_per_minute();
+        let mut count = frame;
+        if drop > 0 {
+            // 10分ごとの実際のフレーム数と、1分ごとの実際のフレーム数
+            let frames_per_10_minutes = fps * 600 - drop * 9;
+            let frames_per_minute = fps * 60 - drop;
+            let tens = frame / frames_per_10_minutes;
+            let rest = frame % frames_per_10_minutes;
+            count += drop * 9 * tens;
+            if rest > drop {
+                count += drop * ((rest - drop) / frames_per_minute);
+            }
+        }
+        Timecode {
+            hours: ((count / (fps * 3600)) % 24) as u8,
+            minutes: ((count / (fps * 60)) % 60) as u8,
+            seconds: ((count / fps) % 60) as u8,
+            frames: (count % fps) as u8,
+            drop_frame: self.drop_frame,
+        }
+    }
+}
+
+/// SMPTEタイムコード。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timecode {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u8,
+    pub drop_frame: bool,
+}
+
+impl std::fmt::Display for Timecode {
+    /// `HH:MM:SS:FF`の形式で表示する。ドロップフレームの場合は`HH:MM:SS;FF`になる。
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let separator = if self.drop_frame { ';' } else { ':' };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{separator}{:02}",
+            self.hours, self.minutes, self.seconds, self.frames
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DF_2997: TimecodeBase = TimecodeBase {
+        nominal_fps: 30,
+        drop_frame: true,
+    };
+
+    #[test]
+    fn test_from_fps() {
+        let cases = [
+            ((30000, 1001), 30, true),
+            ((60000, 1001), 60, true),
+            ((24000, 1001), 24, false),
+            ((30, 1), 30, false),
+            ((25, 1), 25, false),
+            ((60, 1), 60, false),
+        ];
+        for ((numer, denom), nominal_fps, drop_frame) in cases {
+            assert_eq!(
+                TimecodeBase::from_fps(Rational32::new(numer, denom)),
+                TimecodeBase {
+                    nominal_fps,
+                    drop_frame
+                },
+                "{numer}/{denom}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_drop_frame_2997() {
+        let cases = [
+            (0, "00:00:00;00"),
+            (29, "00:00:00;29"),
+            (1799, "00:00:59;29"),
+            (1800, "00:01:00;02"),
+            (1801, "00:01:00;03"),
+            (3597, "00:01:59;29"),
+            (3598, "00:02:00;02"),
+            (17981, "00:09:59;29"),
+            (17982, "00:10:00;00"),
+            (17983, "00:10:00;01"),
+            (19781, "00:10:59;29"),
+            (19782, "00:11:00;02"),
+            (107892, "01:00:00;00"),
+            (2589407, "23:59:59;29"),
+            (2589408, "00:00:00;00"),
+        ];
+        for (frame, expected) in cases {
+            assert_eq!(DF_2997.timecode(frame).to_string(), expected, "{frame}");
+        }
+    }
+
+    #[test]
+    fn test_drop_frame_5994() {
+        let base = TimecodeBase::from_fps(Rational32::new(60000, 1001));
+        let cases = [
+            (3599, "00:00:59;59"),
+            (3600, "00:01:00;04"),
+            (35964, "00:10:00;00"),
+            (215784, "01:00:00;00"),
+        ];
+        for (frame, expected) in cases {
+            assert_eq!(base.timecode(frame).to_string(), expected, "{frame}");
+        }
+    }
+
+    #[test]
+    fn test_non_drop_frame() {
+        let base = TimecodeBase::from_fps(Rational32::new(25, 1));
+        assert_eq!(base.timecode(0).to_string(), "00:00:00:00");
+        assert_eq!(base.timecode(1500).to_string(), "00:01:00:00");
+        assert_eq!(base.timecode(90061).to_string(), "01:00:02:11");
+
+        // 23.976fpsは24fpsとして数える
+        let base = TimecodeBase::from_fps(Rational32::new(24000, 1001));
+        assert_eq!(base.timecode(1440).to_string(), "00:01:00:00");
+    }
+}
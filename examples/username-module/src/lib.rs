@@ -1,8 +1,13 @@
 use aviutl2::{AnyResult, module::ScriptModuleFunctions};
 
+/// ユーザーの情報を扱う関数（`rusty_username.user_*`）。
 #[aviutl2::plugin(ScriptModule)]
 struct UsernameModule;
 
+/// ファイルを扱う関数（`rusty_username.files_*`）。
+#[aviutl2::plugin(ScriptModule)]
+struct FilesModule;
+
 impl aviutl2::module::ScriptModule for UsernameModule {
     fn new(_info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
         Ok(UsernameModule)
@@ -14,16 +19,77 @@ impl aviutl2::module::ScriptModule for UsernameModule {
                 "User name query for AviUtl2, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/username-module",
                 version = env!("CARGO_PKG_VERSION")
             ),
+            name: Some("user".to_string()),
             functions: Self::functions(),
+            include_introspection: true,
+            default_time_budget: None,
+            constants: Self::constants(),
+            enable_shared_store: false,
         }
     }
 }
 
 #[aviutl2::module::functions]
 impl UsernameModule {
+    /// スクリプトに公開する定数。
+    #[constants]
+    fn constants() -> Vec<(String, aviutl2::module::ScriptModuleValue)> {
+        vec![("VERSION".to_string(), env!("CARGO_PKG_VERSION").into())]
+    }
+
+    /// 現在のユーザー名を返す。
     fn get_username(&self) -> aviutl2::AnyResult<String> {
         Ok(whoami::username()?)
     }
+
+    /// 関数を呼び出したオブジェクトのフレーム番号と総フレーム数を返す。
+    ///
+    /// オブジェクトの情報が取得できない場合は`nil`を返す。
+    fn get_object_progress(&self) -> Option<(u32, u32)> {
+        aviutl2::module::current_object_context().map(|object| (object.frame, object.total_frames))
+    }
+}
+
+impl aviutl2::module::ScriptModule for FilesModule {
+    fn new(_info: aviutl2::AviUtl2Info) -> AnyResult<Self> {
+        Ok(FilesModule)
+    }
+
+    fn plugin_info(&self) -> aviutl2::module::ScriptModuleTable {
+        aviutl2::module::ScriptModuleTable {
+            information: String::new(),
+            name: Some("files".to_string()),
+            functions: Self::functions(),
+            include_introspection: true,
+            default_time_budget: Some(std::time::Duration::from_millis(100)),
+            constants: Vec::new(),
+            enable_shared_store: false,
+        }
+    }
+}
+
+#[aviutl2::module::functions]
+impl FilesModule {
+    /// 指定したディレクトリ以下にあるファイルの数を返す。
+    ///
+    /// 100ミリ秒以内に数え終わらなかった場合はエラーを返す。
+    fn count_files(path: String) -> aviutl2::AnyResult<usize> {
+        let call = aviutl2::module::current_call();
+        let mut count = 0;
+        let mut dirs = vec![std::path::PathBuf::from(path)];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(dir)? {
+                call.check()?;
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    dirs.push(entry.path());
+                } else {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
 }
 
-aviutl2::register_script_module!(UsernameModule);
+aviutl2::register_script_modules!(UsernameModule, FilesModule);
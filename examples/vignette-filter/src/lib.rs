@@ -0,0 +1,168 @@
+use aviutl2::{
+    AnyResult, AviUtl2Info,
+    filter::{
+        FilterConfigItemSliceExt, FilterConfigItems, FilterPlugin, FilterPluginFlags,
+        FilterPluginTable, FilterProcVideo, ObjectTransform, RgbaPixel,
+    },
+    tracing,
+};
+
+#[aviutl2::filter::filter_config_items]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterConfig {
+    #[track(name = "強さ", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+    pub strength: f64,
+    #[track(name = "半径", range = 0.0..=2.0, step = 0.01, default = 0.75)]
+    pub radius: f64,
+    #[track(name = "ぼかし", range = 0.0..=1.0, step = 0.01, default = 0.5)]
+    pub softness: f64,
+}
+
+#[aviutl2::plugin(FilterPlugin)]
+struct VignetteFilter;
+
+impl FilterPlugin for VignetteFilter {
+    fn new(_info: AviUtl2Info) -> AnyResult<Self> {
+        aviutl2::tracing_subscriber::fmt()
+            .with_max_level(if cfg!(debug_assertions) {
+                tracing::Level::DEBUG
+            } else {
+                tracing::Level::INFO
+            })
+            .event_format(aviutl2::logger::AviUtl2Formatter)
+            .with_writer(aviutl2::logger::AviUtl2LogWriter)
+            .init();
+        Ok(Self)
+    }
+
+    fn plugin_info(&self) -> FilterPluginTable {
+        FilterPluginTable {
+            name: "Rusty Vignette Filter".to_string(),
+            label: None,
+            order: 0,
+            information: format!(
+                "Vignette filter, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/vignette-filter",
+                version = env!("CARGO_PKG_VERSION")
+            ),
+            flags: aviutl2::bitflag!(FilterPluginFlags {
+                video: true,
+                filter: true,
+            }),
+            config_items: FilterConfig::to_config_items(),
+        }
+    }
+
+    fn proc_video(
+        &self,
+        config: &[aviutl2::filter::FilterConfigItem],
+        video: &mut FilterProcVideo,
+    ) -> AnyResult<()> {
+        let config: FilterConfig = config.to_struct();
+        let (width, height) = (video.video_object.width, video.video_object.height);
+        if width == 0 || height == 0 || config.strength == 0.0 {
+            return Ok(());
+        }
+        let mut image = vec![RgbaPixel::default(); width as usize * height as usize];
+        video.get_image_data(&mut image);
+        apply_vignette(&config, &video.object_transform(), &mut image);
+        video.set_image_data(&image, width, height);
+        Ok(())
+    }
+}
+
+/// シーン上の座標での明るさの倍率を計算する。
+///
+/// シーンの中央が0、上下左右の端が1になる楕円状の距離で、`radius`より外側を暗くする。
+pub fn brightness_at(config: &FilterConfig, transform: &ObjectTransform, x: f64, y: f64) -> f64 {
+    let half_width = transform.scene_width as f64 / 2.0;
+    let half_height = transform.scene_height as f64 / 2.0;
+    let distance = ((x - half_width) / half_width).hypot((y - half_height) / half_height);
+
+    let inner = config.radius * (1.0 - config.softness);
+    let outer = config.radius * (1.0 + config.softness);
+    let t = if outer <= inner {
+        if distance < config.radius { 0.0 } else { 1.0 }
+    } else {
+        ((distance - inner) / (outer - inner)).clamp(0.0, 1.0)
+    };
+    let smooth = t * t * (3.0 - 2.0 * t);
+    1.0 - config.strength * smooth
+}
+
+/// オブジェクトの画像にビネットをかける。
+pub fn apply_vignette(config: &FilterConfig, transform: &ObjectTransform, image: &mut [RgbaPixel]) {
+    let width = transform.image_width as usize;
+    for (i, pixel) in image.iter_mut().enumerate() {
+        let (x, y) = (i % width, i / width);
+        let (scene_x, scene_y) = transform.image_to_scene(x as f64 + 0.5, y as f64 + 0.5);
+        let brightness = brightness_at(config, transform, scene_x, scene_y);
+        pixel.r = (pixel.r as f64 * brightness).round() as u8;
+        pixel.g = (pixel.g as f64 * brightness).round() as u8;
+        pixel.b = (pixel.b as f64 * brightness).round() as u8;
+    }
+}
+
+aviutl2::register_filter_plugin!(VignetteFilter);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> FilterConfig {
+        FilterConfig {
+            strength: 1.0,
+            radius: 0.5,
+            softness: 0.0,
+        }
+    }
+
+    fn transform(x: f64, y: f64) -> ObjectTransform {
+        ObjectTransform {
+            x,
+            y,
+            z: 0.0,
+            rotation_x: 0.0,
+            rotation_y: 0.0,
+            rotation_z: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            scale_z: 1.0,
+            center_x: 0.0,
+            center_y: 0.0,
+            center_z: 0.0,
+            opacity: 1.0,
+            image_width: 2,
+            image_height: 1,
+            scene_width: 100,
+            scene_height: 100,
+        }
+    }
+
+    #[test]
+    fn test_darkens_toward_scene_edges() {
+        let transform = transform(0.0, 0.0);
+        assert_eq!(brightness_at(&config(), &transform, 50.0, 50.0), 1.0);
+        assert_eq!(brightness_at(&config(), &transform, 0.0, 0.0), 0.0);
+        assert_eq!(brightness_at(&config(), &transform, 100.0, 50.0), 0.0);
+    }
+
+    #[test]
+    fn test_uses_object_position_in_scene() {
+        let white = RgbaPixel {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 255,
+        };
+
+        // シーンの中央にあるオブジェクトは暗くならない
+        let mut image = vec![white; 2];
+        apply_vignette(&config(), &transform(0.0, 0.0), &mut image);
+        assert_eq!(image, [white; 2]);
+
+        // 同じ画像でも、シーンの端にあるオブジェクトは暗くなる
+        let mut image = vec![white; 2];
+        apply_vignette(&config(), &transform(49.0, 0.0), &mut image);
+        assert!(image.iter().all(|pixel| pixel.r == 0 && pixel.a == 255));
+    }
+}
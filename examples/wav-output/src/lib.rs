@@ -0,0 +1,265 @@
+use aviutl2::output::OutputPlugin;
+
+/// WAVファイルのサンプル形式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SampleFormat {
+    Int16,
+    Int24,
+    Float32,
+}
+
+impl SampleFormat {
+    fn next(self) -> Self {
+        match self {
+            SampleFormat::Int16 => SampleFormat::Int24,
+            SampleFormat::Int24 => SampleFormat::Float32,
+            SampleFormat::Float32 => SampleFormat::Int16,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SampleFormat::Int16 => "16bit整数",
+            SampleFormat::Int24 => "24bit整数",
+            SampleFormat::Float32 => "32bit浮動小数点",
+        }
+    }
+
+    fn spec(self, channels: u16, sample_rate: u32) -> hound::WavSpec {
+        let (bits_per_sample, sample_format) = match self {
+            SampleFormat::Int16 => (16, hound::SampleFormat::Int),
+            SampleFormat::Int24 => (24, hound::SampleFormat::Int),
+            SampleFormat::Float32 => (32, hound::SampleFormat::Float),
+        };
+        hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample,
+            sample_format,
+        }
+    }
+}
+
+/// -1.0〜1.0のサンプルを24bit整数に変換する。
+fn f32_to_i24(sample: f32) -> i32 {
+    const MAX: f32 = ((1 << 23) - 1) as f32;
+    (sample.clamp(-1.0, 1.0) * MAX).round() as i32
+}
+
+/// チャンクごとのサンプルを書き込み、書き込んだサンプル数（チャンネルあたり）を返す。
+fn write_chunks<F, S, W>(
+    chunks: impl Iterator<Item = (usize, Vec<F>)>,
+    writer: &mut hound::WavWriter<W>,
+    convert: impl Fn(F) -> S,
+    mut on_progress: impl FnMut(usize),
+) -> anyhow::Result<usize>
+where
+    S: hound::Sample,
+    W: std::io::Write + std::io::Seek,
+{
+    let channels = writer.spec().channels as usize;
+    let mut written = 0;
+    for (start, samples) in chunks {
+        anyhow::ensure!(
+            start == written,
+            "Audio chunk starts at {start}, expected {written}"
+        );
+        for sample in samples.iter().copied() {
+            writer.write_sample(convert(sample))?;
+        }
+        written += samples.len() / channels;
+        on_progress(written);
+    }
+    Ok(written)
+}
+
+#[aviutl2::plugin(OutputPlugin)]
+struct WavOutputPlugin {
+    format: std::sync::Mutex<SampleFormat>,
+}
+
+impl OutputPlugin for WavOutputPlugin {
+    fn new(_info: aviutl2::AviUtl2Info) -> aviutl2::AnyResult<Self> {
+        Ok(WavOutputPlugin {
+            format: std::sync::Mutex::new(SampleFormat::Int16),
+        })
+    }
+
+    fn plugin_info(&self) -> aviutl2::output::OutputPluginTable {
+        aviutl2::output::OutputPluginTable {
+            name: "Rusty WAV Output".to_string(),
+            output_type: aviutl2::output::OutputType::Audio,
+            file_filters: aviutl2::file_filters! {
+                "WAV Audio" => ["wav"],
+            },
+
+            information: format!(
+                "WAV Output for AviUtl2, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/wav-output",
+                version = env!("CARGO_PKG_VERSION")
+            ),
+            can_config: true,
+            project_config: false,
+        }
+    }
+
+    fn config(&self, _hwnd: aviutl2::Win32WindowHandle) -> aviutl2::AnyResult<()> {
+        let mut format = self.format.lock().unwrap();
+        let switch = native_dialog::MessageDialogBuilder::default()
+            .set_level(native_dialog::MessageLevel::Info)
+            .set_title("Rusty WAV Output")
+            .set_text(format!(
+                "現在の形式：{}\n\n{}に切り替えますか？",
+                format.label(),
+                format.next().label()
+            ))
+            .confirm()
+            .show()?;
+        if switch {
+            *format = format.next();
+        }
+        Ok(())
+    }
+
+    fn config_text(&self) -> aviutl2::AnyResult<String> {
+        Ok(format!("形式：{}", self.format.lock().unwrap().label()))
+    }
+
+    fn output(&self, info: aviutl2::output::OutputInfo) -> aviutl2::AnyResult<()> {
+        let Some(audio_info) = &info.audio else {
+            anyhow::bail!("音声情報がありません。");
+        };
+        let format = *self.format.lock().unwrap();
+        let spec = format.spec(audio_info.num_channels as u16, audio_info.sample_rate);
+        let mut writer = hound::WavWriter::create(&info.path, spec)?;
+
+        // 0.1秒ずつ取得する
+        let chunk = (audio_info.sample_rate as usize / 10).max(1);
+        let total = audio_info.num_samples as usize;
+        let on_progress = |written: usize| info.update_display(written as i32, total as i32);
+        let written = match format {
+            SampleFormat::Int16 => write_chunks(
+                info.audio_samples_exact_iter::<i16>(chunk),
+                &mut writer,
+                |sample| sample,
+                on_progress,
+            )?,
+            SampleFormat::Int24 => write_chunks(
+                info.audio_samples_exact_iter::<f32>(chunk),
+                &mut writer,
+                f32_to_i24,
+                on_progress,
+            )?,
+            SampleFormat::Float32 => write_chunks(
+                info.audio_samples_exact_iter::<f32>(chunk),
+                &mut writer,
+                |sample| sample,
+                on_progress,
+            )?,
+        };
+        writer.finalize()?;
+
+        anyhow::ensure!(
+            written == total || info.is_aborted(),
+            "Only {written} of {total} samples were written"
+        );
+        Ok(())
+    }
+}
+
+aviutl2::register_output_plugin!(WavOutputPlugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `chunk`サンプルずつに区切った、`n`サンプル目が`n`のステレオ音声。
+    fn chunks(total: usize, chunk: usize) -> impl Iterator<Item = (usize, Vec<f32>)> {
+        (0..total).step_by(chunk).map(move |start| {
+            let end = (start + chunk).min(total);
+            let samples = (start..end)
+                .flat_map(|n| [n as f32 / total as f32, -(n as f32) / total as f32])
+                .collect();
+            (start, samples)
+        })
+    }
+
+    fn write_to_memory(format: SampleFormat, total: usize, chunk: usize) -> (usize, Vec<u8>) {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut buffer, format.spec(2, 44100)).unwrap();
+        let mut progress = Vec::new();
+        let written = match format {
+            SampleFormat::Int16 => write_chunks(
+                chunks(total, chunk),
+                &mut writer,
+                |sample| (sample * i16::MAX as f32) as i16,
+                |written| progress.push(written),
+            ),
+            SampleFormat::Int24 => {
+                write_chunks(chunks(total, chunk), &mut writer, f32_to_i24, |written| {
+                    progress.push(written)
+                })
+            }
+            SampleFormat::Float32 => write_chunks(
+                chunks(total, chunk),
+                &mut writer,
+                |sample| sample,
+                |written| progress.push(written),
+            ),
+        }
+        .unwrap();
+        writer.finalize().unwrap();
+        assert_eq!(progress.last(), Some(&total));
+        (written, buffer.into_inner())
+    }
+
+    #[test]
+    fn test_write_chunks_not_multiple_of_chunk() {
+        for format in [
+            SampleFormat::Int16,
+            SampleFormat::Int24,
+            SampleFormat::Float32,
+        ] {
+            let (written, data) = write_to_memory(format, 1000, 300);
+            assert_eq!(written, 1000);
+
+            let reader = hound::WavReader::new(std::io::Cursor::new(data)).unwrap();
+            assert_eq!(reader.spec(), format.spec(2, 44100));
+            assert_eq!(reader.duration(), 1000);
+        }
+    }
+
+    #[test]
+    fn test_write_chunks_float_samples() {
+        let (_, data) = write_to_memory(SampleFormat::Float32, 10, 4);
+        let mut reader = hound::WavReader::new(std::io::Cursor::new(data)).unwrap();
+        let samples: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+        assert_eq!(samples.len(), 20);
+        assert_eq!(samples[18..], [0.9, -0.9]);
+    }
+
+    #[test]
+    fn test_write_chunks_rejects_gap() {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        let mut writer =
+            hound::WavWriter::new(&mut buffer, SampleFormat::Float32.spec(2, 44100)).unwrap();
+        let chunks = chunks(1000, 300).filter(|(start, _)| *start != 300);
+        assert!(write_chunks(chunks, &mut writer, |sample| sample, |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_f32_to_i24() {
+        assert_eq!(f32_to_i24(0.0), 0);
+        assert_eq!(f32_to_i24(1.0), 8_388_607);
+        assert_eq!(f32_to_i24(-1.0), -8_388_607);
+        assert_eq!(f32_to_i24(2.0), 8_388_607);
+        assert_eq!(f32_to_i24(0.5), 4_194_304);
+    }
+
+    #[test]
+    fn test_sample_format_cycle() {
+        let format = SampleFormat::Int16;
+        assert_eq!(format.next(), SampleFormat::Int24);
+        assert_eq!(format.next().next(), SampleFormat::Float32);
+        assert_eq!(format.next().next().next(), format);
+    }
+}
@@ -0,0 +1,366 @@
+use aviutl2::{
+    AnyResult, AviUtl2Info,
+    filter::{
+        FilterConfigColorValue, FilterConfigItemSliceExt, FilterConfigItems,
+        FilterConfigSelectItems, FilterPlugin, FilterPluginFlags, FilterPluginTable,
+        FilterProcAudio, FilterProcVideo, RgbaPixel, media_cache::AudioTap,
+    },
+    tracing,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FilterConfigSelectItems)]
+pub enum WaveformStyle {
+    #[item(name = "線")]
+    Line,
+    #[item(name = "塗りつぶし")]
+    Filled,
+    #[item(name = "バー")]
+    Bars,
+}
+
+#[aviutl2::filter::filter_config_items]
+#[derive(Debug, Clone)]
+pub struct FilterConfig {
+    #[select(name = "表示形式", items = WaveformStyle, default = WaveformStyle::Line)]
+    pub style: WaveformStyle,
+    #[color(name = "色", default = "#FFFFFF")]
+    pub color: FilterConfigColorValue,
+    #[track(name = "表示範囲", range = 10.0..=2000.0, step = 1.0, default = 100.0)]
+    pub window_ms: f64,
+    #[track(name = "高さ", range = 0.0..=100.0, step = 0.1, default = 50.0)]
+    pub height: f64,
+    #[track(name = "振幅", range = 0.0..=1000.0, step = 0.1, default = 100.0)]
+    pub amplitude: f64,
+    #[track(name = "太さ", range = 1..=50, step = 1.0, default = 2)]
+    pub thickness: u32,
+    #[track(name = "バーの数", range = 1..=512, step = 1.0, default = 32)]
+    pub bars: u32,
+}
+
+#[aviutl2::plugin(FilterPlugin)]
+struct WaveformFilter {
+    tap: AudioTap,
+}
+
+impl FilterPlugin for WaveformFilter {
+    fn new(_info: AviUtl2Info) -> AnyResult<Self> {
+        aviutl2::tracing_subscriber::fmt()
+            .with_max_level(if cfg!(debug_assertions) {
+                tracing::Level::DEBUG
+            } else {
+                tracing::Level::INFO
+            })
+            .event_format(aviutl2::logger::AviUtl2Formatter)
+            .with_writer(aviutl2::logger::AviUtl2LogWriter)
+            .init();
+        Ok(Self {
+            tap: AudioTap::default(),
+        })
+    }
+
+    fn plugin_info(&self) -> FilterPluginTable {
+        FilterPluginTable {
+            name: "Rusty Waveform Filter".to_string(),
+            label: None,
+            order: 0,
+            information: format!(
+                "Audio waveform filter, written in Rust / v{version} / https://github.com/sevenc-nanashi/aviutl2-rs/tree/main/examples/waveform-filter",
+                version = env!("CARGO_PKG_VERSION")
+            ),
+            // 音声フィルタで記録した音声を、同じフィルタオブジェクトの画像フィルタで描く
+            flags: aviutl2::bitflag!(FilterPluginFlags {
+                video: true,
+                audio: true,
+                filter: true,
+            }),
+            config_items: FilterConfig::to_config_items(),
+        }
+    }
+
+    fn proc_audio(
+        &self,
+        _config: &[aviutl2::filter::FilterConfigItem],
+        audio: &mut FilterProcAudio,
+    ) -> AnyResult<()> {
+        self.tap.record(audio)?;
+        Ok(())
+    }
+
+    fn proc_video(
+        &self,
+        config: &[aviutl2::filter::FilterConfigItem],
+        video: &mut FilterProcVideo,
+    ) -> AnyResult<()> {
+        let config: FilterConfig = config.to_struct();
+        let (width, height) = (video.video_object.width, video.video_object.height);
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        let duration = std::time::Duration::from_secs_f64(config.window_ms / 1000.0);
+        let Some(window) = self.tap.window(video, duration) else {
+            return Ok(());
+        };
+        let mut image = vec![RgbaPixel::default(); width as usize * height as usize];
+        video.get_image_data(&mut image);
+        draw_waveform(
+            &config,
+            &window.samples,
+            &mut image,
+            width as usize,
+            height as usize,
+        );
+        video.set_image_data(&image, width, height);
+        Ok(())
+    }
+}
+
+/// 音声のサンプルを`columns`個の列に分け、それぞれの列の最小値と最大値を返す。
+///
+/// 1列あたりのサンプルが1つ未満の場合は、前後のサンプルを線形補間する。
+pub fn resample_envelope(samples: &[f32], columns: usize) -> Vec<(f32, f32)> {
+    let len = samples.len();
+    if len == 0 {
+        return vec![(0.0, 0.0); columns];
+    }
+    (0..columns)
+        .map(|column| {
+            if len >= columns {
+                let start = column * len / columns;
+                let end = ((column + 1) * len / columns).max(start + 1);
+                samples[start..end]
+                    .iter()
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &sample| {
+                        (min.min(sample), max.max(sample))
+                    })
+            } else {
+                let position = ((column as f64 + 0.5) * len as f64 / columns as f64 - 0.5)
+                    .clamp(0.0, (len - 1) as f64);
+                let index = position.floor() as usize;
+                let next = (index + 1).min(len - 1);
+                let t = (position - index as f64) as f32;
+                let value = samples[index] + (samples[next] - samples[index]) * t;
+                (value, value)
+            }
+        })
+        .collect()
+}
+
+/// 画像に音声の波形を描く。
+///
+/// 波形は画像の幅いっぱいに、画像の上下中央を0として描く。
+pub fn draw_waveform(
+    config: &FilterConfig,
+    samples: &[f32],
+    image: &mut [RgbaPixel],
+    width: usize,
+    height: usize,
+) {
+    let center = height as f64 / 2.0;
+    let scale = height as f64 * config.height / 100.0 / 2.0 * config.amplitude / 100.0;
+    let to_y = |sample: f32| center - sample as f64 * scale;
+    let (r, g, b) = config.color.to_rgb();
+    let color = RgbaPixel { r, g, b, a: 255 };
+    let mut fill = |x: usize, top: f64, bottom: f64| {
+        let top = top.floor().max(0.0) as usize;
+        let bottom = (bottom.ceil().max(0.0) as usize).min(height);
+        for y in top..bottom {
+            image[y * width + x] = color;
+        }
+    };
+
+    match config.style {
+        WaveformStyle::Line => {
+            let envelope = resample_envelope(samples, width);
+            let half = config.thickness as f64 / 2.0;
+            let mut previous: Option<(f32, f32)> = None;
+            for (x, &(min, max)) in envelope.iter().enumerate() {
+                // 前の列と繋がるように、前の列の値まで伸ばす
+                let (min, max) = match previous {
+                    Some((previous_min, previous_max)) => {
+                        (min.min(previous_max), max.max(previous_min))
+                    }
+                    None => (min, max),
+                };
+                fill(x, to_y(max) - half, to_y(min) + half);
+                previous = envelope.get(x).copied();
+            }
+        }
+        WaveformStyle::Filled => {
+            for (x, &(min, max)) in resample_envelope(samples, width).iter().enumerate() {
+                fill(x, to_y(max.max(0.0)), to_y(min.min(0.0)).max(center + 1.0));
+            }
+        }
+        WaveformStyle::Bars => {
+            let bars = config.bars.max(1) as usize;
+            let peaks: Vec<f32> = resample_envelope(samples, bars)
+                .into_iter()
+                .map(|(min, max)| min.abs().max(max.abs()))
+                .collect();
+            let bar_width = width as f64 / bars as f64;
+            // バーの間に隙間を空ける
+            let gap = (bar_width * 0.2).min(config.thickness as f64);
+            for x in 0..width {
+                let bar = ((x as f64 / bar_width) as usize).min(bars - 1);
+                let offset = x as f64 - bar as f64 * bar_width;
+                if offset >= bar_width - gap {
+                    continue;
+                }
+                let extent = peaks[bar] as f64 * scale;
+                fill(x, center - extent, (center + extent).max(center + 1.0));
+            }
+        }
+    }
+}
+
+aviutl2::register_filter_plugin!(WaveformFilter);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aviutl2::filter::{ObjectInfo, media_cache::frame_to_sample_index};
+
+    fn config(style: WaveformStyle) -> FilterConfig {
+        FilterConfig {
+            style,
+            color: FilterConfigColorValue(0xFF0000),
+            window_ms: 100.0,
+            height: 100.0,
+            amplitude: 100.0,
+            thickness: 1,
+            bars: 2,
+        }
+    }
+
+    fn colored_rows(image: &[RgbaPixel], width: usize, x: usize) -> Vec<usize> {
+        image
+            .chunks(width)
+            .enumerate()
+            .filter(|(_, row)| row[x].a == 255)
+            .map(|(y, _)| y)
+            .collect()
+    }
+
+    #[test]
+    fn test_envelope_takes_min_and_max_of_each_column() {
+        let samples = [0.0, 1.0, -1.0, 0.5, 0.25, -0.5];
+        assert_eq!(
+            resample_envelope(&samples, 2),
+            vec![(-1.0, 1.0), (-0.5, 0.5)]
+        );
+        assert_eq!(resample_envelope(&[], 2), vec![(0.0, 0.0); 2]);
+    }
+
+    #[test]
+    fn test_envelope_interpolates_when_upsampling() {
+        let envelope = resample_envelope(&[0.0, 1.0], 4);
+        let values: Vec<f32> = envelope.iter().map(|(min, _)| *min).collect();
+        assert_eq!(values, vec![0.0, 0.25, 0.75, 1.0]);
+        assert!(envelope.iter().all(|(min, max)| min == max));
+    }
+
+    #[test]
+    fn test_silence_draws_center_line() {
+        let (width, height) = (4, 8);
+        let mut image = vec![RgbaPixel::default(); width * height];
+        draw_waveform(
+            &config(WaveformStyle::Line),
+            &[0.0; 16],
+            &mut image,
+            width,
+            height,
+        );
+        for x in 0..width {
+            assert_eq!(colored_rows(&image, width, x), vec![3, 4]);
+        }
+        assert_eq!(
+            image[3 * width],
+            RgbaPixel {
+                r: 255,
+                g: 0,
+                b: 0,
+                a: 255
+            }
+        );
+    }
+
+    #[test]
+    fn test_filled_reaches_peak() {
+        let (width, height) = (2, 8);
+        let mut image = vec![RgbaPixel::default(); width * height];
+        draw_waveform(
+            &config(WaveformStyle::Filled),
+            &[1.0, 1.0, -0.5, -0.5],
+            &mut image,
+            width,
+            height,
+        );
+        // 1.0は上端まで、-0.5は中央から下に1/4まで塗る
+        assert_eq!(colored_rows(&image, width, 0), vec![0, 1, 2, 3, 4]);
+        assert_eq!(colored_rows(&image, width, 1), vec![4, 5]);
+    }
+
+    #[test]
+    fn test_bars_use_peak_of_each_range() {
+        let (width, height) = (10, 8);
+        let mut image = vec![RgbaPixel::default(); width * height];
+        draw_waveform(
+            &config(WaveformStyle::Bars),
+            &[0.25, -0.25, 1.0, -1.0],
+            &mut image,
+            width,
+            height,
+        );
+        assert_eq!(colored_rows(&image, width, 0), vec![3, 4]);
+        assert_eq!(colored_rows(&image, width, 5), (0..8).collect::<Vec<_>>());
+        // バーの間の隙間
+        assert!(colored_rows(&image, width, 4).is_empty());
+    }
+
+    #[test]
+    fn test_video_frame_reads_audio_recorded_at_same_time() {
+        let tap = AudioTap::default();
+        let object = ObjectInfo {
+            id: 1_632_100,
+            effect_id: 1,
+            layer: 0,
+            frame: 0,
+            frame_total: 60,
+            time: 0.0,
+            time_total: 2.0,
+            is_filter_object: true,
+            frame_s: 0,
+            frame_e: 59,
+        };
+        // 1秒目だけ音が鳴っている音声を、音声フィルタと同じように少しずつ記録する
+        for chunk in 0..60u64 {
+            let samples: Vec<f32> = (0..1600)
+                .map(|i| {
+                    if (chunk * 1600 + i) / 48000 == 1 {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                })
+                .collect();
+            tap.record_samples(&object, 48000, chunk * 1600, &samples);
+        }
+
+        let frame_rate = aviutl2::Rational32::new(30, 1);
+        let peak = |frame| {
+            let center = frame_to_sample_index(frame, frame_rate, 48000);
+            let window = tap.window_at(&object, center, 480).unwrap();
+            window
+                .samples
+                .iter()
+                .fold(0.0f32, |peak, s| peak.max(s.abs()))
+        };
+        assert_eq!(peak(15), 0.0);
+        assert_eq!(peak(45), 1.0);
+        // 音が鳴り始めるフレームでは、窓の後半だけに音がある
+        let window = tap
+            .window_at(&object, frame_to_sample_index(30, frame_rate, 48000), 480)
+            .unwrap();
+        assert_eq!(window.samples[..240], [0.0; 240]);
+        assert_eq!(window.samples[240..], [1.0; 240]);
+    }
+}